@@ -0,0 +1,235 @@
+//! `#[pads(file = "...")]` geometry files, read and checked when the derive expands
+//!
+//! The tables mirror `copper_substrate::fixed_geometry`'s `PadSpec`, `TextSpec` and
+//! `LineSpec`, and the checks its `FixedGeometry::validate`, so a file that would fail
+//! there is a compile error instead. This crate can't use copper-substrate, which
+//! depends on it, hence the copy; keep the two in step.
+
+use std::path::Path;
+
+use proc_macro2::{Literal, TokenStream as TokenStream2};
+use quote::quote;
+use serde::Deserialize;
+use syn::{Error, LitStr, Result};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Shape {
+    Circle,
+    Rect,
+    Oval,
+    #[serde(rename = "roundrect")]
+    RoundRect,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum TextKind {
+    Reference,
+    Value,
+    User,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Pad {
+    number: String,
+    position: (f32, f32),
+    size: (f32, f32),
+    shape: Shape,
+    #[serde(default)]
+    drill: Option<f32>,
+    #[serde(default)]
+    roundrect_ratio: Option<f32>,
+    #[serde(default)]
+    layers: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Text {
+    kind: TextKind,
+    text: String,
+    position: (f32, f32),
+    layer: String,
+    #[serde(default)]
+    size: Option<f32>,
+    #[serde(default)]
+    thickness: Option<f32>,
+    #[serde(default)]
+    hidden: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Line {
+    start: (f32, f32),
+    end: (f32, f32),
+    layer: String,
+    #[serde(default)]
+    width: Option<f32>,
+}
+
+/// The pads, texts and lines of one file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct GeometryFile {
+    #[serde(default)]
+    pad: Vec<Pad>,
+    #[serde(default)]
+    text: Vec<Text>,
+    #[serde(default)]
+    line: Vec<Line>,
+}
+
+impl GeometryFile {
+    /// Read `file`, relative to the manifest of the crate being compiled, and check it
+    pub(crate) fn read(file: &LitStr) -> Result<Self> {
+        let error = |message: String| Error::new(file.span(), format!("{}: {}", file.value(), message));
+        let manifest = std::env::var("CARGO_MANIFEST_DIR").map_err(|_| error("CARGO_MANIFEST_DIR is not set".to_string()))?;
+        let text = std::fs::read_to_string(Path::new(&manifest).join(file.value())).map_err(|e| error(e.to_string()))?;
+        let geometry: GeometryFile = toml::from_str(&text).map_err(|e| error(e.to_string().trim_end().to_string()))?;
+        geometry.validate().map_err(error)?;
+        Ok(geometry)
+    }
+
+    fn validate(&self) -> std::result::Result<(), String> {
+        for pad in &self.pad {
+            let invalid = |reason: &str| format!("pad \"{}\" {}", pad.number, reason);
+            if pad.number.is_empty() && pad.drill.is_none() {
+                return Err(invalid("has no number"));
+            }
+            if pad.size.0 <= 0.0 || pad.size.1 <= 0.0 {
+                return Err(invalid("has no area"));
+            }
+            if pad.drill.is_some_and(|drill| drill <= 0.0) {
+                return Err(invalid("has no drill"));
+            }
+            // Unnumbered holes aren't plated and need no copper around them
+            if !pad.number.is_empty() && pad.drill.is_some_and(|drill| drill >= pad.size.0.min(pad.size.1)) {
+                return Err(invalid("has a drill that leaves no copper around it"));
+            }
+            if let Some(layer) = pad.layers.iter().flatten().find(|layer| !is_kicad_layer_name(layer)) {
+                return Err(format!("pad \"{}\" is on unknown layer \"{}\"", pad.number, layer));
+            }
+        }
+        if let Some(text) = self.text.iter().find(|text| !is_kicad_layer_name(&text.layer)) {
+            return Err(format!("text \"{}\" is on unknown layer \"{}\"", text.text, text.layer));
+        }
+        if let Some(line) = self.line.iter().find(|line| !is_single_layer(&line.layer)) {
+            return Err(format!("line is on unknown layer \"{}\"", line.layer));
+        }
+        Ok(())
+    }
+
+    /// `PadSpec`, `TextSpec` and `LineSpec` expressions of the file's items
+    pub(crate) fn items(&self) -> (Vec<TokenStream2>, Vec<TokenStream2>, Vec<TokenStream2>) {
+        let pads = self.pad.iter().map(|pad| {
+            let number = &pad.number;
+            let (position, size) = (pair(pad.position), pair(pad.size));
+            let shape = match pad.shape {
+                Shape::Circle => quote!(Circle),
+                Shape::Rect => quote!(Rect),
+                Shape::Oval => quote!(Oval),
+                Shape::RoundRect => quote!(RoundRect),
+            };
+            let (drill, roundrect_ratio) = (optional(pad.drill), optional(pad.roundrect_ratio));
+            let layers = match &pad.layers {
+                Some(layers) => quote!(::core::option::Option::Some(vec![#(#layers.into()),*])),
+                None => quote!(::core::option::Option::None),
+            };
+            quote! {
+                ::copper_substrate::fixed_geometry::PadSpec {
+                    number: #number.into(),
+                    position: #position,
+                    size: #size,
+                    shape: ::copper_substrate::fixed_geometry::FixedPadShape::#shape,
+                    drill: #drill,
+                    roundrect_ratio: #roundrect_ratio,
+                    layers: #layers,
+                }
+            }
+        });
+        let texts = self.text.iter().map(|text| {
+            let kind = match text.kind {
+                TextKind::Reference => quote!(Reference),
+                TextKind::Value => quote!(Value),
+                TextKind::User => quote!(User),
+            };
+            let (body, layer, hidden) = (&text.text, &text.layer, text.hidden);
+            let position = pair(text.position);
+            let (size, thickness) = (optional(text.size), optional(text.thickness));
+            quote! {
+                ::copper_substrate::fixed_geometry::TextSpec {
+                    kind: ::copper_substrate::fixed_geometry::FixedTextKind::#kind,
+                    text: #body.to_string(),
+                    position: #position,
+                    layer: #layer.into(),
+                    size: #size,
+                    thickness: #thickness,
+                    hidden: #hidden,
+                }
+            }
+        });
+        let lines = self.line.iter().map(|line| {
+            let (start, end, layer, width) = (pair(line.start), pair(line.end), &line.layer, optional(line.width));
+            quote! {
+                ::copper_substrate::fixed_geometry::LineSpec { start: #start, end: #end, layer: #layer.into(), width: #width }
+            }
+        });
+        (pads.collect(), texts.collect(), lines.collect())
+    }
+}
+
+fn pair((x, y): (f32, f32)) -> TokenStream2 {
+    let (x, y) = (Literal::f32_suffixed(x), Literal::f32_suffixed(y));
+    quote!((#x, #y))
+}
+
+fn optional(value: Option<f32>) -> TokenStream2 {
+    match value.map(Literal::f32_suffixed) {
+        Some(value) => quote!(::core::option::Option::Some(#value)),
+        None => quote!(::core::option::Option::None),
+    }
+}
+
+/// Canonical KiCad layer names that are not tied to a side
+const SIDELESS_LAYERS: &[&str] = &["Dwgs.User", "Cmts.User", "Eco1.User", "Eco2.User", "Edge.Cuts", "Margin"];
+
+/// Suffixes that exist on both outer sides
+const SIDED_SUFFIXES: &[&str] = &["Cu", "Adhes", "Paste", "SilkS", "Mask", "CrtYd", "Fab"];
+
+const MAX_INNER_COPPER: u8 = 30;
+const MAX_USER_LAYER: u8 = 45;
+
+/// `layer_type::is_kicad_layer_name`: any footprint layer, pad wildcards included
+fn is_kicad_layer_name(name: &str) -> bool {
+    if SIDELESS_LAYERS.contains(&name) {
+        return true;
+    }
+    let Some((prefix, suffix)) = name.split_once('.') else {
+        return false;
+    };
+    match prefix {
+        "F" | "B" | "*" => SIDED_SUFFIXES.contains(&suffix),
+        "F&B" => suffix == "Cu",
+        "User" => suffix.parse::<u8>().is_ok_and(|index| (1..=MAX_USER_LAYER).contains(&index)),
+        inner => suffix == "Cu" && inner_index(inner).is_some_and(|index| index <= MAX_INNER_COPPER),
+    }
+}
+
+/// `BoardLayer::from_kicad_str` succeeding: one concrete layer a line can be drawn on
+fn is_single_layer(name: &str) -> bool {
+    let Some((side, suffix)) = name.split_once('.') else {
+        return false;
+    };
+    let known = matches!(suffix, "SilkS" | "Silkscreen" | "CrtYd" | "Courtyard" | "Fab" | "Cu" | "Mask" | "Paste");
+    match side {
+        "F" | "B" => known,
+        inner => suffix == "Cu" && inner_index(inner).is_some(),
+    }
+}
+
+fn inner_index(prefix: &str) -> Option<u8> {
+    prefix.strip_prefix("In")?.parse::<u8>().ok().filter(|&index| index > 0)
+}
@@ -0,0 +1,370 @@
+//! `#[derive(BoardComponent)]` for footprints of fixed geometry
+//!
+//! Implements `BoardComposableObject` for a struct from attributes, so a part whose pads
+//! never change needs no hand-written trait methods:
+//!
+//! ```ignore
+//! #[derive(BoardComponent)]
+//! #[footprint(name = "R_0805_2012Metric", library = "Resistor_SMD", kind = "Resistor", smt, passive)]
+//! #[pads(file = "pads/r0805.toml")]
+//! struct Resistor0805 {
+//!     #[value]
+//!     resistance: String,
+//! }
+//! ```
+//!
+//! `#[footprint]` takes the footprint `name`, the `library` and the `FunctionalType`
+//! variant as `kind`, all required, and optionally:
+//!
+//! - `smt` for surface mount parts, through hole otherwise
+//! - `passive`, and `mechanical` for parts with no electrical function
+//! - `description`, `tags` and the 3D `model` path
+//! - `bounding_box = (min_x, min_y, max_x, max_y)`, computed from the geometry otherwise
+//!
+//! The field marked `#[value]` is the functional type's value, e.g. "10k".
+//!
+//! The geometry is a `copper_substrate::fixed_geometry::FixedGeometry`: each
+//! `#[pads(file = "...")]` names a TOML file of it, relative to the crate's manifest, and
+//! `#[pad(...)]`, `#[text(...)]` and `#[line(...)]` add items inline with the keys of the
+//! file's tables. Files are read and checked when the derive expands, so one that doesn't
+//! hold valid geometry is a compile error. Inline items are checked on first use, which
+//! panics if they aren't valid.
+
+mod geometry_file;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote, ToTokens};
+use syn::meta::ParseNestedMeta;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Expr, Fields, LitStr, Member, Result};
+
+use crate::geometry_file::GeometryFile;
+
+#[proc_macro_derive(BoardComponent, attributes(footprint, pads, pad, text, line, value))]
+pub fn derive_board_component(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+#[derive(Default)]
+struct Footprint {
+    name: Option<LitStr>,
+    library: Option<LitStr>,
+    kind: Option<LitStr>,
+    smt: bool,
+    passive: bool,
+    mechanical: bool,
+    description: Option<LitStr>,
+    tags: Option<LitStr>,
+    model: Option<LitStr>,
+    bounding_box: Option<Expr>,
+}
+
+impl Footprint {
+    fn parse(&mut self, meta: ParseNestedMeta<'_>) -> Result<()> {
+        let key = meta.path.get_ident().map(ToString::to_string).unwrap_or_default();
+        match key.as_str() {
+            "name" => self.name = Some(meta.value()?.parse()?),
+            "library" => self.library = Some(meta.value()?.parse()?),
+            "kind" => self.kind = Some(meta.value()?.parse()?),
+            "smt" => self.smt = true,
+            "passive" => self.passive = true,
+            "mechanical" => self.mechanical = true,
+            "description" => self.description = Some(meta.value()?.parse()?),
+            "tags" => self.tags = Some(meta.value()?.parse()?),
+            "model" => self.model = Some(meta.value()?.parse()?),
+            "bounding_box" => self.bounding_box = Some(meta.value()?.parse()?),
+            _ => {
+                return Err(meta.error(
+                    "unknown footprint key, expected name, library, kind, smt, passive, mechanical, description, tags, \
+                     model or bounding_box",
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    fn required<'a>(value: &'a Option<LitStr>, key: &str, span: Span) -> Result<&'a LitStr> {
+        value.as_ref().ok_or_else(|| Error::new(span, format!("#[footprint] needs `{}`", key)))
+    }
+}
+
+/// Keys of one inline `#[pad]`, `#[text]` or `#[line]`, as written
+struct Item {
+    keys: Vec<(String, Option<Expr>)>,
+    span: Span,
+}
+
+impl Item {
+    fn parse(attribute: &syn::Attribute, known: &[&str]) -> Result<Self> {
+        let mut keys = Vec::new();
+        attribute.parse_nested_meta(|meta| {
+            let key = meta.path.get_ident().map(ToString::to_string).unwrap_or_default();
+            if !known.contains(&key.as_str()) {
+                return Err(meta.error(format!("unknown key, expected one of {}", known.join(", "))));
+            }
+            let value = if meta.input.peek(syn::Token![=]) { Some(meta.value()?.parse()?) } else { None };
+            keys.push((key, value));
+            Ok(())
+        })?;
+        Ok(Self { keys, span: attribute.path().span() })
+    }
+
+    fn get(&self, key: &str) -> Option<&Expr> {
+        self.keys.iter().find(|(name, _)| name == key).and_then(|(_, value)| value.as_ref())
+    }
+
+    fn flag(&self, key: &str) -> bool {
+        self.keys.iter().any(|(name, value)| name == key && value.is_none())
+    }
+
+    fn required(&self, attribute: &str, key: &str) -> Result<&Expr> {
+        self.get(key).ok_or_else(|| Error::new(self.span, format!("#[{}] needs `{}`", attribute, key)))
+    }
+
+    fn optional(&self, key: &str) -> TokenStream2 {
+        match self.get(key) {
+            Some(value) => quote!(::core::option::Option::Some(#value)),
+            None => quote!(::core::option::Option::None),
+        }
+    }
+
+    /// A string key naming a variant, e.g. shape = "roundrect" for `FixedPadShape::RoundRect`
+    fn variant(&self, attribute: &str, key: &str, variants: &[(&str, &str)]) -> Result<TokenStream2> {
+        let value = self.required(attribute, key)?;
+        let text = string_literal(value)?;
+        let names: Vec<&str> = variants.iter().map(|(name, _)| *name).collect();
+        match variants.iter().find(|(name, _)| *name == text.value()) {
+            Some((_, variant)) => Ok(format_ident!("{}", variant, span = text.span()).into_token_stream()),
+            None => Err(Error::new(text.span(), format!("unknown {} \"{}\", expected {}", key, text.value(), names.join(", ")))),
+        }
+    }
+}
+
+fn string_literal(expr: &Expr) -> Result<&LitStr> {
+    match expr {
+        Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(text), .. }) => Ok(text),
+        other => Err(Error::new_spanned(other, "expected a string literal")),
+    }
+}
+
+fn pad(item: &Item) -> Result<TokenStream2> {
+    let number = string_literal(item.required("pad", "number")?)?;
+    let position = item.required("pad", "position")?;
+    let size = item.required("pad", "size")?;
+    let shape = item.variant("pad", "shape", &[("circle", "Circle"), ("rect", "Rect"), ("oval", "Oval"), ("roundrect", "RoundRect")])?;
+    let drill = item.optional("drill");
+    let roundrect_ratio = item.optional("roundrect_ratio");
+    let layers = match item.get("layers") {
+        Some(layers) => quote!(::core::option::Option::Some(#layers.into_iter().map(::core::convert::Into::into).collect())),
+        None => quote!(::core::option::Option::None),
+    };
+    Ok(quote! {
+        ::copper_substrate::fixed_geometry::PadSpec {
+            number: #number.into(),
+            position: #position,
+            size: #size,
+            shape: ::copper_substrate::fixed_geometry::FixedPadShape::#shape,
+            drill: #drill,
+            roundrect_ratio: #roundrect_ratio,
+            layers: #layers,
+        }
+    })
+}
+
+fn text(item: &Item) -> Result<TokenStream2> {
+    let kind = item.variant("text", "kind", &[("reference", "Reference"), ("value", "Value"), ("user", "User")])?;
+    let text = string_literal(item.required("text", "text")?)?;
+    let position = item.required("text", "position")?;
+    let layer = string_literal(item.required("text", "layer")?)?;
+    let size = item.optional("size");
+    let thickness = item.optional("thickness");
+    let hidden = item.flag("hidden");
+    Ok(quote! {
+        ::copper_substrate::fixed_geometry::TextSpec {
+            kind: ::copper_substrate::fixed_geometry::FixedTextKind::#kind,
+            text: #text.to_string(),
+            position: #position,
+            layer: #layer.into(),
+            size: #size,
+            thickness: #thickness,
+            hidden: #hidden,
+        }
+    })
+}
+
+fn line(item: &Item) -> Result<TokenStream2> {
+    let start = item.required("line", "start")?;
+    let end = item.required("line", "end")?;
+    let layer = string_literal(item.required("line", "layer")?)?;
+    let width = item.optional("width");
+    Ok(quote! {
+        ::copper_substrate::fixed_geometry::LineSpec { start: #start, end: #end, layer: #layer.into(), width: #width }
+    })
+}
+
+/// The field marked `#[value]`, if any
+fn value_field(input: &DeriveInput) -> Result<Option<Member>> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(&input.ident, "BoardComponent can only be derived for structs"));
+    };
+    let members: Vec<Member> = match &data.fields {
+        Fields::Named(fields) => fields
+            .named
+            .iter()
+            .filter(|field| field.attrs.iter().any(|attribute| attribute.path().is_ident("value")))
+            .filter_map(|field| field.ident.clone().map(Member::Named))
+            .collect(),
+        Fields::Unnamed(fields) => fields
+            .unnamed
+            .iter()
+            .enumerate()
+            .filter(|(_, field)| field.attrs.iter().any(|attribute| attribute.path().is_ident("value")))
+            .map(|(index, _)| Member::Unnamed(index.into()))
+            .collect(),
+        Fields::Unit => Vec::new(),
+    };
+    match members.as_slice() {
+        [] => Ok(None),
+        [member] => Ok(Some(member.clone())),
+        [_, second, ..] => Err(Error::new_spanned(second, "only one field can be the #[value]")),
+    }
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+    let value = value_field(input)?;
+    let span = input.ident.span();
+    let mut footprint = None::<Footprint>;
+    let (mut files, mut pads, mut texts, mut lines) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    for attribute in &input.attrs {
+        let path = attribute.path();
+        if path.is_ident("footprint") {
+            attribute.parse_nested_meta(|meta| footprint.get_or_insert_with(Footprint::default).parse(meta))?;
+        } else if path.is_ident("pads") {
+            attribute.parse_nested_meta(|meta| {
+                if !meta.path.is_ident("file") {
+                    return Err(meta.error("expected `file = \"...\"`"));
+                }
+                files.push(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            })?;
+        } else if path.is_ident("pad") {
+            pads.push(pad(&Item::parse(attribute, &["number", "position", "size", "shape", "drill", "roundrect_ratio", "layers"])?)?);
+        } else if path.is_ident("text") {
+            texts.push(text(&Item::parse(attribute, &["kind", "text", "position", "layer", "size", "thickness", "hidden"])?)?);
+        } else if path.is_ident("line") {
+            lines.push(line(&Item::parse(attribute, &["start", "end", "layer", "width"])?)?);
+        }
+    }
+    let footprint = footprint.ok_or_else(|| Error::new(span, "BoardComponent needs #[footprint(name = ..., library = ..., kind = ...)]"))?;
+    let name = Footprint::required(&footprint.name, "name", span)?;
+    let library = Footprint::required(&footprint.library, "library", span)?;
+    let kind = Footprint::required(&footprint.kind, "kind", span)?;
+    if files.is_empty() && pads.is_empty() {
+        return Err(Error::new(span, "BoardComponent needs #[pads(file = \"...\")] or at least one #[pad(...)]"));
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let kind = format_ident!("{}", kind.value(), span = kind.span());
+    let value = match value {
+        Some(member) => quote!(::std::string::ToString::to_string(&self.#member)),
+        None => quote!(::std::string::String::new()),
+    };
+    let (smt, passive, electrical) = (footprint.smt, footprint.passive, !footprint.mechanical);
+    let bounding_box = match &footprint.bounding_box {
+        Some(bounds) => quote! {
+            let (min_x, min_y, max_x, max_y) = #bounds;
+            ::copper_substrate::board_interface::Rectangle { min_x, min_y, max_x, max_y }
+        },
+        None => quote!(self.computed_bounding_box()),
+    };
+    let description = footprint.description.as_ref().map(|description| {
+        quote!(fn description(&self) -> ::core::option::Option<::std::string::String> { ::core::option::Option::Some(#description.to_string()) })
+    });
+    let tags = footprint.tags.as_ref().map(|tags| {
+        quote!(fn tags(&self) -> ::core::option::Option<::std::string::String> { ::core::option::Option::Some(#tags.to_string()) })
+    });
+    let model = match &footprint.model {
+        Some(path) => quote! {
+            ::core::option::Option::Some(::copper_substrate::board_interface::Model3D {
+                path: #path.to_string(),
+                offset: (0.0, 0.0, 0.0),
+                scale: (1.0, 1.0, 1.0),
+                rotation: (0.0, 0.0, 0.0),
+            })
+        },
+        None => quote!(::core::option::Option::None),
+    };
+    // Inline items first, then each file's in order; the files are also included so
+    // editing one rebuilds the crate
+    let mut tracked = Vec::new();
+    for file in &files {
+        let (file_pads, file_texts, file_lines) = GeometryFile::read(file)?.items();
+        pads.extend(file_pads);
+        texts.extend(file_texts);
+        lines.extend(file_lines);
+        let path = LitStr::new(&format!("/{}", file.value()), file.span());
+        tracked.push(quote!(const _: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), #path));));
+    }
+
+    Ok(quote! {
+        const _: () = {
+            use ::copper_substrate::board_interface::{BoardComposableObject, FpText, GraphicElement, Model3D, PadDescriptor, Rectangle};
+            use ::copper_substrate::fixed_geometry::FixedGeometry;
+            use ::copper_substrate::functional_types::FunctionalType;
+
+            #(#tracked)*
+
+            static GEOMETRY: ::std::sync::LazyLock<FixedGeometry> = ::std::sync::LazyLock::new(|| {
+                let geometry = FixedGeometry { pads: vec![#(#pads),*], texts: vec![#(#texts),*], lines: vec![#(#lines),*] };
+                geometry
+                    .validate()
+                    .map(|()| geometry)
+                    .unwrap_or_else(|error| panic!("invalid geometry for {}: {}", #name, error))
+            });
+
+            impl #impl_generics BoardComposableObject for #ident #type_generics #where_clause {
+                fn is_smt(&self) -> bool {
+                    #smt
+                }
+                fn is_electrical(&self) -> bool {
+                    #electrical
+                }
+                fn is_passive(&self) -> bool {
+                    #passive
+                }
+                fn terminal_count(&self) -> usize {
+                    GEOMETRY.terminal_count()
+                }
+                fn functional_type(&self) -> FunctionalType {
+                    FunctionalType::#kind(#value)
+                }
+                fn footprint_name(&self) -> ::std::string::String {
+                    #name.to_string()
+                }
+                fn library_name(&self) -> ::std::string::String {
+                    #library.to_string()
+                }
+                fn bounding_box(&self) -> Rectangle {
+                    #bounding_box
+                }
+                fn pad_descriptors(&self) -> ::std::vec::Vec<PadDescriptor> {
+                    GEOMETRY.pad_descriptors()
+                }
+                #description
+                #tags
+                fn fp_text_elements(&self) -> ::std::vec::Vec<FpText> {
+                    GEOMETRY.fp_text_elements()
+                }
+                fn graphic_elements(&self) -> ::std::vec::Vec<GraphicElement> {
+                    GEOMETRY.graphic_elements()
+                }
+                fn model_3d(&self) -> ::core::option::Option<Model3D> {
+                    #model
+                }
+            }
+        };
+    })
+}
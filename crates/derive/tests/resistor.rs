@@ -0,0 +1,157 @@
+//! A derived 0805 resistor must export exactly as the hand-written one in
+//! examples/resistor.rs, uuids aside
+
+use copper_exporters::kicad_pcb_export::to_kicad_footprint;
+use copper_substrate::prelude::*;
+use uuid::Uuid;
+
+#[derive(BoardComponent)]
+#[footprint(
+    name = "R_0805_2012Metric",
+    library = "Resistor_SMD",
+    kind = "Resistor",
+    smt,
+    passive,
+    description = "Resistor SMD 0805 (2012 Metric), square (rectangular) end terminal",
+    tags = "resistor 0805",
+    model = "${KICAD9_3DMODEL_DIR}/Resistor_SMD.3dshapes/R_0805_2012Metric.wrl",
+    bounding_box = (-1.0, -0.625, 1.0, 0.625)
+)]
+#[pads(file = "tests/pads/r0805.toml")]
+#[text(kind = "reference", text = "REF**", position = (0.0, -1.16), layer = "F.SilkS")]
+#[text(kind = "value", text = "R_0805_2012Metric", position = (0.0, 1.16), layer = "F.Fab")]
+#[text(kind = "user", text = "${REFERENCE}", position = (0.0, 0.0), layer = "F.Fab", size = 0.25, thickness = 0.04)]
+struct DerivedResistor0805 {
+    #[value]
+    value: String,
+}
+
+/// The same resistor with inline pads
+#[derive(BoardComponent)]
+#[footprint(name = "R_0805_2012Metric", library = "Resistor_SMD", kind = "Resistor", smt, passive)]
+#[pad(number = "1", position = (-0.95, 0.0), size = (1.0, 1.45), shape = "roundrect", roundrect_ratio = 0.25)]
+#[pad(number = "2", position = (0.95, 0.0), size = (1.0, 1.45), shape = "roundrect", layers = ["F.Cu", "F.Paste", "F.Mask"])]
+struct InlineResistor0805(#[value] &'static str);
+
+struct SMTResistor0805 {
+    value: String,
+}
+
+impl BoardComposableObject for SMTResistor0805 {
+    fn is_smt(&self) -> bool {
+        true
+    }
+    fn is_electrical(&self) -> bool {
+        true
+    }
+    fn is_passive(&self) -> bool {
+        true
+    }
+    fn terminal_count(&self) -> usize {
+        2
+    }
+    fn functional_type(&self) -> FunctionalType {
+        FunctionalType::Resistor(self.value.clone())
+    }
+    fn footprint_name(&self) -> String {
+        "R_0805_2012Metric".to_string()
+    }
+    fn library_name(&self) -> String {
+        "Resistor_SMD".to_string()
+    }
+    fn bounding_box(&self) -> Rectangle {
+        Rectangle { min_x: -1.0, min_y: -0.625, max_x: 1.0, max_y: 0.625 }
+    }
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        [("1", -0.95), ("2", 0.95)]
+            .into_iter()
+            .map(|(number, x)| PadDescriptor {
+                number: number.into(),
+                pin_function: None,
+                pad_type: PadType::SMD,
+                shape: PadShape::RoundRect,
+                position: (x, 0.0),
+                rotation: None,
+                size: (1.0, 1.45),
+                drill_size: None,
+                drill_slot: None,
+                layers: PadLayers::smd_front(),
+                roundrect_ratio: Some(0.25),
+                tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+                padstack: None,
+                fab_property: None,
+                solder_mask_margin: None,
+                routing_hint: None,
+                uuid: Uuid::new_v4(),
+            })
+            .collect()
+    }
+    fn description(&self) -> Option<String> {
+        Some("Resistor SMD 0805 (2012 Metric), square (rectangular) end terminal".to_string())
+    }
+    fn tags(&self) -> Option<String> {
+        Some("resistor 0805".to_string())
+    }
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        let text = |text_type, text: &str, position, layer: &str, size: f32, thickness| FpText {
+            text_type,
+            text: text.to_string(),
+            position,
+            rotation: None,
+            layer: layer.into(),
+            uuid: Uuid::new_v4(),
+            font: FontSettings { size: (size, size), thickness },
+            hidden: false,
+            justify: None,
+            role: None,
+        };
+        vec![
+            text(FpTextType::Reference, "REF**", (0.0, -1.16), "F.SilkS", 1.0, 0.15),
+            text(FpTextType::Value, "R_0805_2012Metric", (0.0, 1.16), "F.Fab", 1.0, 0.15),
+            text(FpTextType::User, "${REFERENCE}", (0.0, 0.0), "F.Fab", 0.25, 0.04),
+        ]
+    }
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        vec![]
+    }
+    fn model_3d(&self) -> Option<Model3D> {
+        Some(Model3D {
+            path: "${KICAD9_3DMODEL_DIR}/Resistor_SMD.3dshapes/R_0805_2012Metric.wrl".to_string(),
+            offset: (0.0, 0.0, 0.0),
+            scale: (1.0, 1.0, 1.0),
+            rotation: (0.0, 0.0, 0.0),
+        })
+    }
+}
+
+/// Number, position, size, layers and corner ratio of a pad
+type Pad = (PadNumber, (f32, f32), (f32, f32), Vec<LayerName>, Option<f32>);
+
+/// Footprint text with every uuid replaced, as each export draws fresh ones
+fn without_uuids(text: &str) -> String {
+    text.split('"').map(|part| if Uuid::parse_str(part).is_ok() { "UUID" } else { part }).collect::<Vec<_>>().join("\"")
+}
+
+#[test]
+fn derived_resistor_matches_hand_written() {
+    let derived = DerivedResistor0805 { value: "10k".to_string() };
+    let hand_written = SMTResistor0805 { value: "10k".to_string() };
+    assert_eq!(without_uuids(&to_kicad_footprint(&derived)), without_uuids(&to_kicad_footprint(&hand_written)));
+    assert_eq!(derived.terminal_count(), 2);
+    assert_eq!(derived.functional_type().value(), "10k");
+    assert_eq!(derived.bounding_box(), hand_written.bounding_box());
+    assert!(derived.is_smt() && derived.is_passive() && derived.is_electrical());
+}
+
+#[test]
+fn inline_pads_match_the_file() {
+    let inline = InlineResistor0805("4k7");
+    let derived = DerivedResistor0805 { value: "4k7".to_string() };
+    let pads = |footprint: &dyn BoardComposableObject| -> Vec<Pad> {
+        footprint.pad_descriptors().into_iter().map(|pad| (pad.number, pad.position, pad.size, pad.layers, pad.roundrect_ratio)).collect()
+    };
+    assert_eq!(pads(&inline), pads(&derived));
+    assert_eq!(inline.functional_type().value(), "4k7");
+    assert!(inline.model_3d().is_none() && inline.description().is_none());
+    assert_eq!(inline.bounding_box(), inline.computed_bounding_box());
+}
@@ -0,0 +1,17 @@
+//! Derives missing what they need, or with keys misspelled, must not compile
+
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn compile_errors() {
+    // trybuild compiles the cases as a crate of its own under the target directory, whose
+    // manifest directory the `#[pads(file)]` paths are then relative to
+    let project = Path::new(env!("CARGO_TARGET_TMPDIR")).join("../tests/trybuild/copper-derive/tests/ui/pads");
+    fs::create_dir_all(&project).unwrap();
+    for entry in fs::read_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/ui/pads")).unwrap() {
+        let path = entry.unwrap().path();
+        fs::copy(&path, project.join(path.file_name().unwrap())).unwrap();
+    }
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,11 @@
+use copper_substrate::prelude::*;
+
+#[derive(BoardComponent)]
+#[footprint(name = "R_0805_2012Metric", library = "Resistor_SMD", kind = "Resistor", smt)]
+#[pad(number = "1", position = (0.0, 0.0), size = (1.0, 1.0), shape = "rect")]
+enum Resistor {
+    Small,
+    Large,
+}
+
+fn main() {}
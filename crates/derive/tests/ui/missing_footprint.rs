@@ -0,0 +1,7 @@
+use copper_substrate::prelude::*;
+
+#[derive(BoardComponent)]
+#[pad(number = "1", position = (0.0, 0.0), size = (1.0, 1.0), shape = "rect")]
+struct NoFootprint;
+
+fn main() {}
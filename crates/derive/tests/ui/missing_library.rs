@@ -0,0 +1,8 @@
+use copper_substrate::prelude::*;
+
+#[derive(BoardComponent)]
+#[footprint(name = "R_0805_2012Metric", kind = "Resistor", smt)]
+#[pad(number = "1", position = (0.0, 0.0), size = (1.0, 1.0), shape = "rect")]
+struct NoLibrary;
+
+fn main() {}
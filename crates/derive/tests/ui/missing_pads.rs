@@ -0,0 +1,7 @@
+use copper_substrate::prelude::*;
+
+#[derive(BoardComponent)]
+#[footprint(name = "R_0805_2012Metric", library = "Resistor_SMD", kind = "Resistor", smt)]
+struct NoPads;
+
+fn main() {}
@@ -0,0 +1,8 @@
+use copper_substrate::prelude::*;
+
+#[derive(BoardComponent)]
+#[footprint(name = "TestPoint", library = "TestPoint", kind = "Connector")]
+#[pads(file = "tests/ui/pads/hole_too_big.toml")]
+struct HoleTooBig;
+
+fn main() {}
@@ -0,0 +1,62 @@
+//! Generating and exporting 10k chip footprints
+//!
+//! `cargo bench -p copper-exporters --bench chip_footprints` times building the pads,
+//! graphics, texts and courtyard of 10,000 chip footprints across every standard size,
+//! and writing them as .kicad_mod text, each as its own criterion group.
+
+use std::hint::black_box;
+
+use copper_exporters::kicad_pcb_export::to_kicad_footprint;
+use copper_substrate::chip::STANDARD_CHIP_SIZES;
+use copper_substrate::prelude::*;
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+const FOOTPRINTS: usize = 10_000;
+
+fn chips() -> Vec<ChipFootprint> {
+    let kinds = [
+        FunctionalType::Resistor("10k".to_string()),
+        FunctionalType::Capacitor("100nF".to_string()),
+        FunctionalType::LED("red".to_string()),
+    ];
+    (0..FOOTPRINTS)
+        .map(|index| {
+            let (code, ..) = STANDARD_CHIP_SIZES[index % STANDARD_CHIP_SIZES.len()];
+            ChipFootprint::standard(kinds[index % kinds.len()].clone(), code).expect("standard chip size")
+        })
+        .collect()
+}
+
+fn generate(c: &mut Criterion) {
+    let chips = chips();
+    let mut group = c.benchmark_group("generate");
+    group.sample_size(10).throughput(Throughput::Elements(FOOTPRINTS as u64));
+    group.bench_function("chip_10k", |b| {
+        b.iter(|| {
+            for chip in &chips {
+                black_box(chip.pad_descriptors());
+                black_box(chip.graphic_elements());
+                black_box(chip.fp_text_elements());
+                black_box(chip.generate_courtyards());
+            }
+        })
+    });
+    group.finish();
+}
+
+fn export(c: &mut Criterion) {
+    let chips = chips();
+    let mut group = c.benchmark_group("export");
+    group.sample_size(10).throughput(Throughput::Elements(FOOTPRINTS as u64));
+    group.bench_function("chip_10k", |b| {
+        b.iter(|| {
+            for chip in &chips {
+                black_box(to_kicad_footprint(chip));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, generate, export);
+criterion_main!(benches);
@@ -0,0 +1,254 @@
+//! Assembly drawing SVG
+//!
+//! A top view of a board for the assembly line. It draws the board outline, and each
+//! placed part as its turned body rectangle with the reference in the middle. Back side
+//! parts are dashed, as if seen through the board. Actuation envelopes, where a lever
+//! swings or a card goes in, are dashed in the colour of their layer and may reach past
+//! the board edge. The connector marks of
+//! `Board::connector_marks` are drawn in their own colours over the parts. A legend under
+//! the board explains each kind of mark the drawing uses. References and the legend are
+//! drawn with `stroke_font`, as the board's own text is plotted, so the drawing needs no
+//! fonts and is sized to the text exactly.
+//!
+//! Coordinates are board millimeters, Y down, as in `svg_export`.
+
+use std::fmt::Write;
+
+use copper_substrate::board::{Board, PlacedComponent};
+use copper_substrate::connector_marks::{ConnectorMark, CONNECTOR_ROLES};
+use copper_substrate::prelude::*;
+use copper_substrate::stroke_font::line_width;
+
+use crate::format::{point_pair, Decimal};
+use crate::svg_export::{layer_color, write_svg_envelope, write_svg_text_as, xml_escape};
+
+/// Margin around the drawing in the viewBox (mm)
+const MARGIN: f32 = 2.0;
+const OUTLINE_COLOR: &str = "#d0d2cd";
+const OUTLINE_WIDTH: f32 = 0.15;
+const BODY_WIDTH: f32 = 0.1;
+const MARK_WIDTH: f32 = 0.15;
+/// Height of a legend row and its text (mm)
+const LEGEND_ROW: f32 = 1.6;
+const LEGEND_TEXT: f32 = 1.0;
+/// Stroke thickness of text, as a fraction of its height
+const TEXT_THICKNESS: f32 = 0.15;
+
+fn mark_color(role: &ElementRole) -> &'static str {
+    match role {
+        ElementRole::MatingFace => "#e8591a",
+        ElementRole::KeyingMark => "#2f9e44",
+        _ => "#1c7ed6",
+    }
+}
+
+fn mark_class(role: &ElementRole) -> &'static str {
+    match role {
+        ElementRole::MatingFace => "mating-face",
+        ElementRole::KeyingMark => "keying-mark",
+        _ => "insertion-arrow",
+    }
+}
+
+fn legend_text(role: &ElementRole) -> &'static str {
+    match role {
+        ElementRole::MatingFace => "Mating face, the plug or cable leaves this way",
+        ElementRole::KeyingMark => "Key or polarization feature",
+        _ => "Insertion direction of the mating part",
+    }
+}
+
+/// Corners of a placed part's bounding box on the board
+fn body_corners(board: &Board, placed: &PlacedComponent) -> Vec<Point> {
+    let body = placed.component.bounding_box();
+    [(body.min_x, body.min_y), (body.max_x, body.min_y), (body.max_x, body.max_y), (body.min_x, body.max_y)]
+        .into_iter()
+        .map(|(x, y)| board.board_point(placed, Point::new(x, y)))
+        .collect()
+}
+
+fn write_mark(output: &mut String, mark: &ConnectorMark) {
+    let color = mark_color(&mark.role);
+    let class = mark_class(&mark.role);
+    let reference = xml_escape(&mark.reference);
+    if let [start, end] = mark.points[..] {
+        writeln!(output, "  <line class=\"mark {}\" data-reference=\"{}\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"{}\" stroke-linecap=\"round\"/>",
+                 class, reference, Decimal(start.x), Decimal(start.y), Decimal(end.x), Decimal(end.y), color, Decimal(MARK_WIDTH)).unwrap();
+    } else {
+        let points: Vec<String> = mark.points.iter().map(|p| point_pair(p.x, p.y)).collect();
+        let fill = if mark.filled { color } else { "none" };
+        writeln!(output, "  <polygon class=\"mark {}\" data-reference=\"{}\" points=\"{}\" fill=\"{}\" stroke=\"{}\" stroke-width=\"{}\"/>",
+                 class, reference, points.join(" "), fill, color, Decimal(MARK_WIDTH)).unwrap();
+    }
+}
+
+/// A small sample of a mark at `(x, y)`, its left middle
+fn write_legend_glyph(output: &mut String, role: &ElementRole, x: f32, y: f32) {
+    let color = mark_color(role);
+    let points = match role {
+        ElementRole::KeyingMark => vec![(x + 0.4, y - 0.4), (x + 0.8, y), (x + 0.4, y + 0.4), (x, y)],
+        _ => {
+            writeln!(output, "    <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"{}\"/>",
+                     Decimal(x), Decimal(y), Decimal(x + 1.2), Decimal(y), color, Decimal(MARK_WIDTH)).unwrap();
+            vec![(x + 1.8, y), (x + 1.2, y - 0.3), (x + 1.2, y + 0.3)]
+        }
+    };
+    let points: Vec<String> = points.into_iter().map(|(x, y)| point_pair(x, y)).collect();
+    writeln!(output, "    <polygon points=\"{}\" fill=\"{}\"/>", points.join(" "), color).unwrap();
+}
+
+/// The assembly drawing of `board`, see the module documentation
+pub fn to_assembly_svg(board: &Board) -> String {
+    let marks = board.connector_marks();
+    let bodies: Vec<(&PlacedComponent, Vec<Point>)> =
+        board.components().iter().map(|placed| (placed, body_corners(board, placed))).collect();
+    let legend: Vec<&ElementRole> = CONNECTOR_ROLES.iter().filter(|role| marks.iter().any(|mark| &mark.role == *role)).collect();
+
+    let envelopes: Vec<(&PlacedComponent, ActuationEnvelope, Vec<Point>)> = board
+        .components()
+        .iter()
+        .filter_map(|placed| {
+            let envelope = placed.component.actuation_envelope()?;
+            let points = envelope.points().into_iter().map(|point| board.board_point(placed, point)).collect();
+            Some((placed, envelope, points))
+        })
+        .collect();
+
+    let outline = board.outline().unwrap_or_default();
+    let points = outline
+        .iter()
+        .chain(bodies.iter().flat_map(|(_, corners)| corners))
+        .chain(envelopes.iter().flat_map(|(_, _, points)| points))
+        .chain(marks.iter().flat_map(|mark| &mark.points));
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (f32::INFINITY, f32::INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
+    for point in points {
+        (min_x, min_y, max_x, max_y) = (min_x.min(point.x), min_y.min(point.y), max_x.max(point.x), max_y.max(point.y));
+    }
+    if min_x > max_x {
+        (min_x, min_y, max_x, max_y) = (0.0, 0.0, 0.0, 0.0);
+    }
+    let legend_top = max_y + MARGIN;
+    if !legend.is_empty() {
+        let widest = legend.iter().map(|role| line_width(legend_text(role), LEGEND_TEXT)).fold(0.0, f32::max);
+        max_x = max_x.max(min_x + 2.5 + widest + LEGEND_TEXT * TEXT_THICKNESS / 2.0);
+        max_y = legend_top + legend.len() as f32 * LEGEND_ROW;
+    }
+    let (width, height) = (max_x - min_x + 2.0 * MARGIN, max_y - min_y + 2.0 * MARGIN);
+
+    let mut output = String::new();
+    writeln!(output, "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" width=\"{}mm\" height=\"{}mm\">",
+             Decimal(min_x - MARGIN), Decimal(min_y - MARGIN), Decimal(width), Decimal(height), Decimal(width), Decimal(height)).unwrap();
+    writeln!(output, "  <title>{} assembly</title>", xml_escape(&board.name)).unwrap();
+    if !outline.is_empty() {
+        let points: Vec<String> = outline.iter().map(|p| point_pair(p.x, p.y)).collect();
+        writeln!(output, "  <polygon class=\"outline\" points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/>",
+                 points.join(" "), OUTLINE_COLOR, Decimal(OUTLINE_WIDTH)).unwrap();
+    }
+
+    for (placed, corners) in &bodies {
+        let back = placed.side == Side::Back;
+        let color = layer_color(if back { "B.Fab" } else { "F.Fab" });
+        let dash = if back { " stroke-dasharray=\"0.4 0.3\"" } else { "" };
+        let points: Vec<String> = corners.iter().map(|p| point_pair(p.x, p.y)).collect();
+        let reference = xml_escape(&placed.reference);
+        writeln!(output, "  <polygon class=\"body\" data-reference=\"{}\" points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"{}/>",
+                 reference, points.join(" "), color, Decimal(BODY_WIDTH), dash).unwrap();
+        let bounds = placed.component.bounding_box();
+        let size = (bounds.width().min(bounds.height()) * 0.4).clamp(0.4, 1.0);
+        let center = board.board_point(placed, Point::new((bounds.min_x + bounds.max_x) / 2.0, (bounds.min_y + bounds.max_y) / 2.0));
+        let text = FpText::new(FpTextType::Reference, placed.reference.as_str(), (center.x, center.y), "F.Fab")
+            .with_font(FontSettings { size: (size, size), thickness: size * TEXT_THICKNESS });
+        write_svg_text_as(&mut output, &text, "reference", color);
+    }
+
+    for (placed, envelope, points) in &envelopes {
+        write_svg_envelope(&mut output, envelope, points, Some(&placed.reference));
+    }
+
+    for mark in &marks {
+        write_mark(&mut output, mark);
+    }
+
+    if !legend.is_empty() {
+        writeln!(output, "  <g class=\"legend\">").unwrap();
+        for (row, role) in legend.iter().enumerate() {
+            let y = legend_top + (row as f32 + 0.5) * LEGEND_ROW;
+            write_legend_glyph(&mut output, role, min_x, y);
+            let text = FpText {
+                justify: Some(Justify::new(HorizontalJustify::Left, VerticalJustify::Center)),
+                ..FpText::new(FpTextType::User, legend_text(role), (min_x + 2.5, y), "Cmts.User")
+                    .with_font(FontSettings { size: (LEGEND_TEXT, LEGEND_TEXT), thickness: LEGEND_TEXT * TEXT_THICKNESS })
+            };
+            write_svg_text_as(&mut output, &text, "legend-text", "#404040");
+        }
+        writeln!(output, "  </g>").unwrap();
+    }
+
+    writeln!(output, "</svg>").unwrap();
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use copper_substrate::presets;
+
+    use super::*;
+
+    /// A 30 x 20 mm board with an SMA on its top edge and one on its right edge, both
+    /// marking their mating face
+    fn board() -> Board {
+        let mut board = Board::new("rf").with_rectangular_outline(30.0, 20.0);
+        board.place("J1", EdgeMountSma::new(1.6).with_mating_face(), (15.0, 0.0), 0.0).unwrap();
+        board.place("J2", EdgeMountSma::new(1.6).with_mating_face().with_insertion_arrow(), (30.0, 10.0), 270.0).unwrap();
+        board
+    }
+
+    /// Tip of each arrow head in a role, by reference
+    fn tips(board: &Board, role: ElementRole) -> Vec<(String, Point)> {
+        let marks = board.connector_marks();
+        marks.into_iter().filter(|mark| mark.role == role && mark.filled).map(|mark| (mark.reference, mark.points[0])).collect()
+    }
+
+    #[test]
+    fn mating_face_arrows_point_off_the_board_edge() {
+        let board = board();
+        let tips = tips(&board, ElementRole::MatingFace);
+        assert_eq!(tips.len(), 2);
+        for (reference, tip) in tips {
+            match reference.as_str() {
+                "J1" => assert!(tip.y < 0.0 && (tip.x - 15.0).abs() < 1e-3, "{:?}", tip),
+                _ => assert!(tip.x > 30.0 && (tip.y - 10.0).abs() < 1e-3, "{:?}", tip),
+            }
+        }
+    }
+
+    #[test]
+    fn the_legend_names_the_marks_drawn() {
+        let svg = to_assembly_svg(&board());
+        assert_eq!(svg.matches("class=\"mark mating-face\"").count(), 4);
+        assert_eq!(svg.matches("class=\"mark insertion-arrow\"").count(), 2);
+        assert!(svg.contains(legend_text(&ElementRole::MatingFace)));
+        assert!(svg.contains(legend_text(&ElementRole::InsertionArrow)));
+        assert!(!svg.contains(legend_text(&ElementRole::KeyingMark)));
+        assert_eq!(svg.matches("class=\"body\"").count(), 2);
+        // Text is drawn as strokes, no fonts needed
+        assert!(svg.contains("class=\"reference\" data-text=\"J2\""), "{}", svg);
+        assert!(!svg.contains("<text"), "{}", svg);
+
+        let plain = to_assembly_svg(&Board::new("plain").with_rectangular_outline(10.0, 10.0));
+        assert!(!plain.contains("legend"));
+    }
+
+    #[test]
+    fn envelopes_are_dashed_and_fit_in_the_drawing() {
+        let mut board = Board::new("socket").with_rectangular_outline(20.0, 20.0);
+        let card = ActuationEnvelope::rect("card", &Rectangle { min_x: -1.0, min_y: -8.0, max_x: 1.0, max_y: -0.5 });
+        let socket = Footprint { actuation_envelope: Some(card), ..Footprint::capture(&presets::resistor_0603("10k")) };
+        board.place("J1", socket, (10.0, 2.0), 0.0).unwrap();
+        let svg = to_assembly_svg(&board);
+        let line = svg.lines().find(|line| line.contains("class=\"envelope\"")).unwrap();
+        assert!(line.contains("data-name=\"card\" data-reference=\"J1\"") && line.contains("stroke-dasharray"), "{}", line);
+        // The card sticks 6 mm out over the top edge, and the drawing reaches that far
+        assert!(line.contains("9,-6") && svg.contains("viewBox=\"-2 -8 "), "{}", svg);
+    }
+}
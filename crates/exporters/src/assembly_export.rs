@@ -0,0 +1,207 @@
+//! Multi-board assembly export
+//!
+//! An assembly is written as one `.kicad_pcb` per board plus `<assembly>.assembly.json`
+//! describing how they fit together: each board's file, outline bounds, thickness and
+//! placement, the interconnects between them and the assembly nets with the board net
+//! each one maps. Boards are written through `Assembly::exported_board`, so a shared net
+//! carries the assembly name in every board file.
+//!
+//! A board file only declares the nets its pads, tracks, vias and zones are on.
+//! `AssemblyExport::net_mismatches` reads the declarations back and lists every assembly
+//! net missing from a board it maps.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use copper_substrate::assembly::{Assembly, ConnectorEnd, InterconnectKind};
+use serde_json::{json, Value};
+
+use crate::exporter::{ExportError, ExportOptions};
+use crate::kicad_board_export::try_to_kicad_pcb_with_options;
+use crate::sexpr;
+
+/// An assembly net a board maps but whose written file doesn't declare it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetMismatch {
+    pub net: String,
+    pub board: String,
+}
+
+/// The written files of an assembly, see the module documentation
+#[derive(Debug, Clone)]
+pub struct AssemblyExport {
+    /// Board name, file name and `.kicad_pcb` text, in assembly order
+    pub boards: Vec<(String, String, String)>,
+    pub manifest: Value,
+    manifest_name: String,
+}
+
+fn board_file_name(board: &str) -> String {
+    format!("{}.kicad_pcb", board)
+}
+
+fn end_to_json(end: &ConnectorEnd) -> Value {
+    json!({ "board": end.board, "reference": end.reference })
+}
+
+pub fn assembly_to_json(assembly: &Assembly) -> Value {
+    let boards: Vec<Value> = assembly
+        .boards()
+        .iter()
+        .map(|entry| {
+            let board = &entry.board;
+            let placement = &entry.placement;
+            let (x, y, z) = placement.position;
+            json!({
+                "name": board.name,
+                "file": board_file_name(&board.name),
+                "outline": board.outline().map(|outline| {
+                    let (xs, ys) = (outline.iter().map(|point| point.x), outline.iter().map(|point| point.y));
+                    let min = [xs.clone().fold(f32::INFINITY, f32::min), ys.clone().fold(f32::INFINITY, f32::min)];
+                    json!({ "min": min, "max": [xs.fold(f32::NEG_INFINITY, f32::max), ys.fold(f32::NEG_INFINITY, f32::max)] })
+                }),
+                "thickness": board.stackup().map_or(1.6, |stackup| stackup.total_thickness()),
+                "placement": { "position": [x, y, z], "rotation": placement.rotation, "fold": placement.fold },
+            })
+        })
+        .collect();
+    let interconnects: Vec<Value> = assembly
+        .interconnects()
+        .iter()
+        .map(|link| {
+            let (kind, length) = match link.kind {
+                InterconnectKind::BoardToBoard => ("board_to_board", None),
+                InterconnectKind::Flex { length } => ("flex", Some(length)),
+            };
+            json!({ "kind": kind, "length": length, "ends": [end_to_json(&link.ends.0), end_to_json(&link.ends.1)] })
+        })
+        .collect();
+    let nets: Vec<Value> = assembly
+        .nets()
+        .map(|(net, members)| {
+            let members: Vec<Value> = members.iter().map(|(board, board_net)| json!({ "board": board, "net": board_net })).collect();
+            json!({ "name": net, "boards": members })
+        })
+        .collect();
+    json!({ "name": assembly.name, "boards": boards, "interconnects": interconnects, "nets": nets })
+}
+
+/// Names of the `(net ...)` declarations of a `.kicad_pcb`, the unnamed net 0 left out
+pub fn declared_nets(kicad_pcb: &str) -> Result<BTreeSet<String>, ExportError> {
+    let document = sexpr::parse(kicad_pcb).map_err(ExportError::OutputStyle)?;
+    Ok(document
+        .children()
+        .iter()
+        .filter(|item| item.head() == Some("net"))
+        .filter_map(|net| net.children().get(2)?.as_str())
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Every board of `assembly` as `.kicad_pcb` text with its mapped nets renamed, and the
+/// assembly description
+pub fn export_assembly(assembly: &Assembly, options: &ExportOptions) -> Result<AssemblyExport, ExportError> {
+    let mut boards = Vec::new();
+    for entry in assembly.boards() {
+        let name = &entry.board.name;
+        let board = assembly.exported_board(name).expect("the board is part of the assembly");
+        boards.push((name.clone(), board_file_name(name), try_to_kicad_pcb_with_options(&board, options)?));
+    }
+    Ok(AssemblyExport { boards, manifest: assembly_to_json(assembly), manifest_name: format!("{}.assembly.json", assembly.name) })
+}
+
+impl AssemblyExport {
+    /// Assembly nets of `assembly` missing from the file of a board they map, by net
+    pub fn net_mismatches(&self, assembly: &Assembly) -> Result<Vec<NetMismatch>, ExportError> {
+        let mut mismatches = Vec::new();
+        let mut declared = Vec::new();
+        for (board, _, text) in &self.boards {
+            declared.push((board, declared_nets(text)?));
+        }
+        for (net, members) in assembly.nets() {
+            for (board, _) in members {
+                let found = declared.iter().any(|(name, nets)| *name == board && nets.contains(net));
+                if !found {
+                    mismatches.push(NetMismatch { net: net.to_string(), board: board.clone() });
+                }
+            }
+        }
+        Ok(mismatches)
+    }
+
+    /// Write the board files and the assembly description into `dir`, returning the path
+    /// of the description
+    pub fn write_to(&self, dir: &Path) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        for (_, file, text) in &self.boards {
+            fs::write(dir.join(file), text)?;
+        }
+        let manifest = dir.join(&self.manifest_name);
+        let text = serde_json::to_string_pretty(&self.manifest).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(&manifest, text)?;
+        Ok(manifest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use copper_substrate::board::Board;
+    use copper_substrate::prelude::*;
+    use copper_substrate::routing::Track;
+
+    use super::*;
+
+    fn two_boards() -> Assembly {
+        let top = BoardLayer::front(LayerType::Copper);
+        let mut main = Board::new("main").with_rectangular_outline(40.0, 30.0);
+        main.place("J1", Dip::new(8).unwrap(), (30.0, 12.0), 0.0).unwrap();
+        main.connect_pad("J1", "1", "VBUS");
+        main.connect_pad("J1", "2", "GND");
+        main.add_track(Track::new("VBUS", top, Point::new(10.0, 12.0), Point::new(30.0, 12.0), 0.5));
+        let mut display = Board::new("display").with_rectangular_outline(40.0, 20.0);
+        display.place("J2", Dip::new(8).unwrap(), (5.0, 5.0), 0.0).unwrap();
+        display.connect_pad("J2", "1", "PWR_IN");
+        display.connect_pad("J2", "2", "GND");
+        display.add_track(Track::new("PWR_IN", top, Point::new(5.0, 5.0), Point::new(25.0, 5.0), 0.5));
+
+        let mut assembly = Assembly::new("handheld");
+        assembly.add_board(main, BoardPlacement::default()).unwrap();
+        assembly.add_board(display, BoardPlacement::new(0.0, 30.0, 4.0).with_fold(180.0)).unwrap();
+        assembly.connect(InterconnectKind::Flex { length: 15.0 }, ConnectorEnd::new("main", "J1"), ConnectorEnd::new("display", "J2")).unwrap();
+        assembly
+    }
+
+    #[test]
+    fn a_shared_net_has_one_name_in_both_board_files() {
+        let mut assembly = two_boards();
+        assembly.map_net("VBUS", "main", "VBUS").unwrap();
+        assembly.map_net("VBUS", "display", "PWR_IN").unwrap();
+        let export = export_assembly(&assembly, &ExportOptions::default()).unwrap();
+        for (_, _, text) in &export.boards {
+            assert_eq!(declared_nets(text).unwrap(), BTreeSet::from(["GND".to_string(), "VBUS".to_string()]));
+        }
+        assert_eq!(export.net_mismatches(&assembly).unwrap(), vec![]);
+
+        let manifest = &export.manifest;
+        assert_eq!(manifest["boards"][1]["file"], "display.kicad_pcb");
+        assert_eq!(manifest["boards"][1]["placement"]["fold"], 180.0);
+        assert_eq!(manifest["interconnects"][0]["kind"], "flex");
+        assert_eq!(manifest["nets"][0]["boards"][1], json!({ "board": "display", "net": "PWR_IN" }));
+    }
+
+    #[test]
+    fn a_net_only_on_pads_is_declared_and_a_dropped_one_reported() {
+        let mut assembly = two_boards();
+        assembly.map_net("GND", "main", "GND").unwrap();
+        assembly.map_net("GND", "display", "GND").unwrap();
+        let mut export = export_assembly(&assembly, &ExportOptions::default()).unwrap();
+        assert_eq!(export.net_mismatches(&assembly).unwrap(), vec![]);
+
+        // A file edited after export, GND renamed on the display board
+        export.boards[1].2 = export.boards[1].2.replace("\"GND\"", "\"DGND\"");
+        assert_eq!(export.net_mismatches(&assembly).unwrap(), vec![NetMismatch { net: "GND".to_string(), board: "display".to_string() }]);
+    }
+}
@@ -0,0 +1,212 @@
+//! Layout block files
+//!
+//! Blocks (see `copper_substrate::block`) are stored as JSON so a validated layout can be
+//! kept next to the boards that reuse it. Footprints are embedded as `.kicad_mod` text,
+//! once per distinct footprint definition, and read back through `KiCadFootprint`; a
+//! loaded block is therefore made of parsed footprints, not of the generators that drew
+//! the original. Layers use their KiCad names.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+use copper_substrate::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::kicad_footprint_parse::{KiCadFootprint, ParseOptions};
+use crate::kicad_pcb_export::to_kicad_footprint;
+
+/// Format version written to and accepted from block files
+const BLOCK_FILE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockFileError {
+    Io(String),
+    Parse(String),
+    UnsupportedVersion(u32),
+    /// An embedded footprint that doesn't parse
+    Footprint { index: usize, message: String },
+    /// A component naming a footprint index the file doesn't have
+    MissingFootprint { reference: String, index: usize },
+    UnknownLayer(String),
+    UnknownViaKind(String),
+}
+
+impl fmt::Display for BlockFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockFileError::Io(message) => write!(f, "cannot read block: {}", message),
+            BlockFileError::Parse(message) => write!(f, "invalid block: {}", message),
+            BlockFileError::UnsupportedVersion(version) => write!(f, "unsupported block file version {}", version),
+            BlockFileError::Footprint { index, message } => write!(f, "footprint {}: {}", index, message),
+            BlockFileError::MissingFootprint { reference, index } => write!(f, "{} uses footprint {}, which the block doesn't have", reference, index),
+            BlockFileError::UnknownLayer(layer) => write!(f, "unknown layer \"{}\"", layer),
+            BlockFileError::UnknownViaKind(kind) => write!(f, "unknown via kind \"{}\"", kind),
+        }
+    }
+}
+
+impl std::error::Error for BlockFileError {}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BlockFile {
+    version: u32,
+    name: String,
+    footprints: Vec<FootprintEntry>,
+    components: Vec<ComponentEntry>,
+    tracks: Vec<TrackEntry>,
+    vias: Vec<ViaEntry>,
+    /// "reference.number" to net
+    pad_nets: Vec<(String, String)>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FootprintEntry {
+    library: String,
+    kicad_mod: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ComponentEntry {
+    reference: String,
+    footprint: usize,
+    position: (f64, f64),
+    rotation: f32,
+    back: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TrackEntry {
+    net: String,
+    layer: String,
+    start: (f32, f32),
+    end: (f32, f32),
+    width: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ViaEntry {
+    net: String,
+    position: (f32, f32),
+    diameter: f32,
+    drill: f32,
+    kind: String,
+    layers: (String, String),
+}
+
+fn layer(name: &str) -> Result<BoardLayer, BlockFileError> {
+    BoardLayer::from_kicad_str(name).ok_or_else(|| BlockFileError::UnknownLayer(name.to_string()))
+}
+
+fn via_kind(name: &str) -> Result<ViaKind, BlockFileError> {
+    [ViaKind::Through, ViaKind::Blind, ViaKind::Buried, ViaKind::Micro]
+        .into_iter()
+        .find(|kind| kind.to_string() == name)
+        .ok_or_else(|| BlockFileError::UnknownViaKind(name.to_string()))
+}
+
+/// The block as block file JSON
+pub fn block_to_string(block: &Block) -> String {
+    let mut shared: Vec<&Rc<dyn BoardComposableObject>> = Vec::new();
+    let mut components = Vec::new();
+    for placed in block.components() {
+        let footprint = match shared.iter().position(|component| Rc::ptr_eq(component, &placed.component)) {
+            Some(index) => index,
+            None => {
+                shared.push(&placed.component);
+                shared.len() - 1
+            }
+        };
+        components.push(ComponentEntry {
+            reference: placed.reference.clone(),
+            footprint,
+            position: placed.position,
+            rotation: placed.rotation,
+            back: placed.side == Side::Back,
+        });
+    }
+    let file = BlockFile {
+        version: BLOCK_FILE_VERSION,
+        name: block.name.clone(),
+        footprints: shared
+            .iter()
+            .map(|component| FootprintEntry { library: component.library_name(), kicad_mod: to_kicad_footprint(component.as_ref()) })
+            .collect(),
+        components,
+        tracks: block
+            .tracks()
+            .iter()
+            .map(|track| TrackEntry {
+                net: track.net.clone(),
+                layer: track.layer.to_kicad_string(),
+                start: (track.start.x, track.start.y),
+                end: (track.end.x, track.end.y),
+                width: track.width,
+            })
+            .collect(),
+        vias: block
+            .vias()
+            .iter()
+            .map(|via| ViaEntry {
+                net: via.net.clone(),
+                position: (via.position.x, via.position.y),
+                diameter: via.diameter,
+                drill: via.drill,
+                kind: via.kind.to_string(),
+                layers: (via.layers.0.to_kicad_string(), via.layers.1.to_kicad_string()),
+            })
+            .collect(),
+        pad_nets: block.pad_nets().map(|(pad, net)| (pad.to_string(), net.to_string())).collect(),
+    };
+    serde_json::to_string_pretty(&file).expect("block files hold only strings and numbers")
+}
+
+/// Read block file JSON
+pub fn block_from_str(text: &str) -> Result<Block, BlockFileError> {
+    let file: BlockFile = serde_json::from_str(text).map_err(|e| BlockFileError::Parse(e.to_string()))?;
+    if file.version != BLOCK_FILE_VERSION {
+        return Err(BlockFileError::UnsupportedVersion(file.version));
+    }
+    let mut footprints: Vec<Rc<dyn BoardComposableObject>> = Vec::new();
+    for (index, entry) in file.footprints.iter().enumerate() {
+        let footprint = KiCadFootprint::parse(&entry.kicad_mod, ParseOptions::default())
+            .map_err(|e| BlockFileError::Footprint { index, message: e.to_string() })?;
+        footprints.push(Rc::new(footprint.with_library_name(&entry.library)));
+    }
+
+    let mut block = Block::new(&file.name);
+    for entry in file.components {
+        let component = footprints
+            .get(entry.footprint)
+            .ok_or_else(|| BlockFileError::MissingFootprint { reference: entry.reference.clone(), index: entry.footprint })?;
+        let side = if entry.back { Side::Back } else { Side::Front };
+        block.add_component(&entry.reference, component.clone(), entry.position, entry.rotation, side);
+    }
+    for entry in file.tracks {
+        block.add_track(Track::new(&entry.net, layer(&entry.layer)?, entry.start.into(), entry.end.into(), entry.width));
+    }
+    for entry in file.vias {
+        let via = Via::new(&entry.net, entry.position.into(), entry.diameter, entry.drill);
+        block.add_via(via.with_span(via_kind(&entry.kind)?, layer(&entry.layers.0)?, layer(&entry.layers.1)?));
+    }
+    for (pad, net) in file.pad_nets {
+        if let Some((reference, number)) = pad.rsplit_once('.') {
+            block.connect_pad(reference, number, &net);
+        }
+    }
+    Ok(block)
+}
+
+pub fn write_block(path: &Path, block: &Block) -> Result<(), BlockFileError> {
+    fs::write(path, block_to_string(block)).map_err(|e| BlockFileError::Io(e.to_string()))
+}
+
+pub fn read_block(path: &Path) -> Result<Block, BlockFileError> {
+    block_from_str(&fs::read_to_string(path).map_err(|e| BlockFileError::Io(e.to_string()))?)
+}
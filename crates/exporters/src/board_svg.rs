@@ -0,0 +1,493 @@
+//! Whole boards as SVG, one layer at a time or composited
+//!
+//! `BoardSvg::render_svg` draws a board for web previews and documentation. A layer view
+//! draws what one layer holds, in the layer's colour on a transparent background:
+//!
+//! - copper: zones, thieving, tracks, pads, vias and copper graphics, with the holes
+//!   through them. Zones are drawn as their outline (or hatch) at `Theme::zone_opacity`,
+//!   since fills aren't computed; pads carry `data-pad` and routing `data-net`.
+//! - mask: the openings, the pads on the mask layer as they are shaped there
+//! - silkscreen and fab: graphics and visible texts, plotted with `stroke_font`, with the
+//!   reference put in for the footprint's placeholder
+//! - edge: the board outline
+//!
+//! A side view composites one side the way the board looks: substrate, outer copper, the
+//! solder mask with its openings cut out at `Theme::mask_opacity`, silkscreen, holes and
+//! the edge, in that order, in the `Theme`'s colours. Every view of a board has the same
+//! viewBox, so layer files overlay exactly.
+//!
+//! Coordinates are board millimeters, Y down, as in KiCad and `svg_export`, so the front
+//! and every single layer are drawn as pcbnew shows them without a flip; back layers are
+//! seen through the board. The bottom view is mirrored left to right, as KiCad's flipped
+//! board view is, so its silkscreen reads.
+
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use copper_substrate::board::{Board, PlacedComponent};
+use copper_substrate::prelude::*;
+
+use crate::drill_export::{drill_holes, Plating};
+use crate::format::{point_pair, Decimal};
+use crate::gerber_export::{board_side, element_shapes, Shape};
+use crate::svg_export::{layer_color, xml_escape};
+
+/// Margin around the board in the viewBox (mm)
+const MARGIN: f32 = 1.0;
+/// Stroke width of the board outline (mm)
+const EDGE_WIDTH: f32 = 0.1;
+/// Text footprints hold in place of their reference, in KiCad's two spellings
+const REFERENCE_PLACEHOLDERS: [&str; 2] = ["REF**", "${REFERENCE}"];
+
+/// What `render_svg` draws
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SvgView {
+    /// One layer on its own: copper, mask, silkscreen or fab
+    Layer(BoardLayer),
+    /// The board outline
+    Edge,
+    /// The top or bottom of the board, composited
+    Side(Side),
+}
+
+impl SvgView {
+    /// KiCad name of the view's layer, or "Edge.Cuts", "top" or "bottom"
+    pub fn name(&self) -> String {
+        match self {
+            SvgView::Layer(layer) => layer.to_kicad_string(),
+            SvgView::Edge => "Edge.Cuts".to_string(),
+            SvgView::Side(Side::Back) => "bottom".to_string(),
+            SvgView::Side(_) => "top".to_string(),
+        }
+    }
+
+    /// `name` as the fab package names files, e.g. "F_Cu"
+    pub fn file_stem(&self) -> String {
+        self.name().replace('.', "_")
+    }
+}
+
+/// Colours of the composited views, and of layers that shouldn't take their
+/// `svg_export::layer_color`. The defaults are those of the 3D preview's standard stack,
+/// so both previews of a board look alike.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub background: String,
+    /// Board material between the copper
+    pub substrate: String,
+    /// Outer copper where the mask leaves it bare, or seen through the mask
+    pub copper: String,
+    pub mask: String,
+    /// Opacity of the solder mask over copper and substrate, 0 to 1
+    pub mask_opacity: f32,
+    pub silkscreen: String,
+    pub hole: String,
+    pub edge: String,
+    /// Opacity of zones, which are drawn unfilled, 0 to 1
+    pub zone_opacity: f32,
+    /// Colours of single layer views by KiCad layer name
+    pub layer_colors: Vec<(String, String)>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            background: "#001023".to_string(),
+            substrate: "#50504b".to_string(),
+            copper: "#ffb478".to_string(),
+            mask: "#007800".to_string(),
+            mask_opacity: 0.7,
+            silkscreen: "#f0f0f0".to_string(),
+            hole: "#000000".to_string(),
+            edge: "#d0d2cd".to_string(),
+            zone_opacity: 0.5,
+            layer_colors: Vec::new(),
+        }
+    }
+}
+
+impl Theme {
+    /// Draw `layer` ("F.Cu", "Edge.Cuts", ...) in `color` in its own view
+    pub fn with_layer_color(mut self, layer: &str, color: &str) -> Self {
+        self.layer_colors.retain(|(name, _)| name != layer);
+        self.layer_colors.push((layer.to_string(), color.to_string()));
+        self
+    }
+
+    pub fn with_mask(mut self, color: &str, opacity: f32) -> Self {
+        self.mask = color.to_string();
+        self.mask_opacity = opacity;
+        self
+    }
+
+    /// Colour of a single layer view: the one set for it, else `svg_export::layer_color`,
+    /// else the edge colour for Edge.Cuts
+    pub fn layer_color(&self, layer: &str) -> &str {
+        match self.layer_colors.iter().find(|(name, _)| name == layer) {
+            Some((_, color)) => color,
+            None if layer == "Edge.Cuts" => &self.edge,
+            None => layer_color(layer),
+        }
+    }
+}
+
+/// How `render_svg` draws
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoardSvgOptions {
+    pub theme: Theme,
+    /// Largest deviation of the segments drawing pad arcs and arc tracks (mm)
+    pub arc_tolerance: f32,
+}
+
+impl Default for BoardSvgOptions {
+    fn default() -> Self {
+        Self { theme: Theme::default(), arc_tolerance: 0.01 }
+    }
+}
+
+impl BoardSvgOptions {
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn with_arc_tolerance(mut self, arc_tolerance: f32) -> Self {
+        self.arc_tolerance = arc_tolerance;
+        self
+    }
+}
+
+/// Rendering a board to SVG, see the module documentation
+pub trait BoardSvg {
+    fn render_svg(&self, view: SvgView, options: &BoardSvgOptions) -> String;
+}
+
+impl BoardSvg for Board {
+    fn render_svg(&self, view: SvgView, options: &BoardSvgOptions) -> String {
+        to_board_svg(self, view, options)
+    }
+}
+
+/// Every view worth drawing of `board`: its copper layers front to back, mask,
+/// silkscreen and fab of either side, the edge, then the top and bottom
+pub fn board_svg_views(board: &Board) -> Vec<SvgView> {
+    let mut views: Vec<SvgView> = board.copper_layers().into_iter().map(SvgView::Layer).collect();
+    for layer in [LayerType::Mask, LayerType::SilkScreen, LayerType::Fabrication] {
+        views.push(SvgView::Layer(BoardLayer::front(layer)));
+        views.push(SvgView::Layer(BoardLayer::back(layer)));
+    }
+    views.extend([SvgView::Edge, SvgView::Side(Side::Front), SvgView::Side(Side::Back)]);
+    views
+}
+
+/// Write every view of `board_svg_views` into `dir` as "<board>-<view>.svg", returning
+/// their paths in list order
+pub fn write_board_svgs(board: &Board, dir: &Path, options: &BoardSvgOptions) -> std::io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    let mut written = Vec::new();
+    for view in board_svg_views(board) {
+        let path = dir.join(format!("{}-{}.svg", board.name, view.file_stem()));
+        fs::write(&path, to_board_svg(board, view, options))?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// `BoardSvg::render_svg`
+pub fn to_board_svg(board: &Board, view: SvgView, options: &BoardSvgOptions) -> String {
+    let extent = board_extent(board);
+    let (width, height) = (extent.width() + 2.0 * MARGIN, extent.height() + 2.0 * MARGIN);
+    let mut output = String::new();
+    writeln!(output, "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" width=\"{}mm\" height=\"{}mm\">",
+             Decimal(extent.min_x - MARGIN), Decimal(extent.min_y - MARGIN), Decimal(width), Decimal(height), Decimal(width), Decimal(height)).unwrap();
+    writeln!(output, "  <title>{} {}</title>", xml_escape(&board.name), view.name()).unwrap();
+    let theme = &options.theme;
+    match view {
+        SvgView::Layer(layer) => {
+            let name = layer.to_kicad_string();
+            let color = theme.layer_color(&name);
+            writeln!(output, "  <g class=\"layer\" data-layer=\"{}\">", name).unwrap();
+            match layer.layer {
+                LayerType::Copper => {
+                    write_copper(&mut output, board, layer, color, theme.zone_opacity, options.arc_tolerance);
+                    write_holes(&mut output, board, layer, &theme.hole);
+                }
+                LayerType::Mask => {
+                    for opening in board.mask_openings(layer.side) {
+                        writeln!(output, "    <polygon class=\"opening\" points=\"{}\" fill=\"{}\"/>", points(&opening), color).unwrap();
+                    }
+                    write_graphics(&mut output, board, layer, color);
+                }
+                _ => {
+                    write_graphics(&mut output, board, layer, color);
+                    write_texts(&mut output, board, layer, color);
+                }
+            }
+            writeln!(output, "  </g>").unwrap();
+        }
+        SvgView::Edge => write_edge(&mut output, board, theme.layer_color("Edge.Cuts"), "  "),
+        SvgView::Side(side) => {
+            writeln!(output, "  <rect class=\"background\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+                     Decimal(extent.min_x - MARGIN), Decimal(extent.min_y - MARGIN), Decimal(width), Decimal(height), theme.background).unwrap();
+            // Mirrored about the middle of the board, so the bottom is seen from below
+            match side {
+                Side::Back => writeln!(output, "  <g class=\"bottom\" transform=\"matrix(-1 0 0 1 {} 0)\">", Decimal(extent.min_x + extent.max_x)).unwrap(),
+                _ => writeln!(output, "  <g class=\"top\">").unwrap(),
+            }
+            write_side(&mut output, board, side, theme, options.arc_tolerance);
+            writeln!(output, "  </g>").unwrap();
+        }
+    }
+    writeln!(output, "</svg>").unwrap();
+    output
+}
+
+/// The board outline's bounds, or those of the copper on a board without one
+fn board_extent(board: &Board) -> Rectangle {
+    let points: Vec<Point> = match board.outline() {
+        Some(outline) => outline.to_vec(),
+        None => board.copper_layers().into_iter().flat_map(|layer| board.copper_outlines(layer)).flatten().collect(),
+    };
+    Rectangle::enclosing(&points).unwrap_or(Rectangle { min_x: 0.0, min_y: 0.0, max_x: 0.0, max_y: 0.0 })
+}
+
+fn points(outline: &[Point]) -> String {
+    outline.iter().map(|p| point_pair(p.x, p.y)).collect::<Vec<_>>().join(" ")
+}
+
+/// An outline as a closed path, to cut holes out of with `fill-rule` or a mask
+fn closed_path(outline: &[Point]) -> String {
+    let mut path: Vec<String> = outline.iter().enumerate().map(|(index, p)| format!("{}{} {}", if index == 0 { "M" } else { "L" }, Decimal(p.x), Decimal(p.y))).collect();
+    path.push("Z".to_string());
+    path.join(" ")
+}
+
+fn write_shape(output: &mut String, shape: &Shape, class: &str, color: &str) {
+    match shape {
+        Shape::Flash { center, diameter } => {
+            writeln!(output, "    <circle class=\"{}\" cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>",
+                     class, Decimal(center.x), Decimal(center.y), Decimal(diameter / 2.0), color).unwrap();
+        }
+        Shape::Region(outline) => {
+            writeln!(output, "    <polygon class=\"{}\" points=\"{}\" fill=\"{}\"/>", class, points(outline), color).unwrap();
+        }
+        Shape::Stroke { points: line, width } => {
+            writeln!(output, "    <polyline class=\"{}\" points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\" stroke-linecap=\"round\" stroke-linejoin=\"round\"/>",
+                     class, points(line), color, Decimal(*width)).unwrap();
+        }
+        Shape::Circle { center, radius, width } => {
+            writeln!(output, "    <circle class=\"{}\" cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/>",
+                     class, Decimal(center.x), Decimal(center.y), Decimal(*radius), color, Decimal(*width)).unwrap();
+        }
+    }
+}
+
+/// A round ended line, the shape of tracks and slots
+fn write_line(output: &mut String, class: &str, net: Option<&str>, (start, end): (Point, Point), width: f32, color: &str) {
+    let net = net.map(|net| format!(" data-net=\"{}\"", xml_escape(net))).unwrap_or_default();
+    writeln!(output, "    <line class=\"{}\"{} x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"{}\" stroke-linecap=\"round\"/>",
+             class, net, Decimal(start.x), Decimal(start.y), Decimal(end.x), Decimal(end.y), color, Decimal(width)).unwrap();
+}
+
+/// Footprint and board graphics drawn on `layer`
+fn write_graphics(output: &mut String, board: &Board, layer: BoardLayer, color: &str) {
+    for placed in board.components() {
+        let to_board = |point: (f32, f32)| board.board_point(placed, Point::from(point));
+        for element in placed.component.graphic_elements().iter().filter(|element| element.layer == layer.layer && board_side(placed, element) == layer.side) {
+            for shape in element_shapes(element, to_board) {
+                write_shape(output, &shape, "graphic", color);
+            }
+        }
+    }
+    for element in board.graphics().iter().filter(|element| element.layer == layer.layer && element.side == layer.side) {
+        for shape in element_shapes(element, Point::from) {
+            write_shape(output, &shape, "graphic", color);
+        }
+    }
+}
+
+/// Visible footprint texts landing on `layer`, plotted as strokes
+fn write_texts(output: &mut String, board: &Board, layer: BoardLayer, color: &str) {
+    for placed in board.components() {
+        // Back side placements are mirrored, so their F.* texts end up on B.*
+        let local_layer = format!("{}.{}", if placed.side == layer.side { "F" } else { "B" }, layer.layer.kicad_suffix());
+        for text in placed.component.fp_text_elements().into_iter().filter(|text| !text.hidden && text.layer == local_layer) {
+            write_text(output, board, placed, &text, color);
+        }
+    }
+}
+
+fn write_text(output: &mut String, board: &Board, placed: &PlacedComponent, text: &FpText, color: &str) {
+    let mut text = text.clone();
+    if text.text_type == FpTextType::Reference && REFERENCE_PLACEHOLDERS.contains(&text.text.as_str()) {
+        text.text = placed.reference.as_str().into();
+    }
+    let mut path = Vec::new();
+    for stroke in text.strokes() {
+        for (index, point) in stroke.into_iter().enumerate() {
+            let point = board.board_point(placed, point);
+            path.push(format!("{}{} {}", if index == 0 { "M" } else { "L" }, Decimal(point.x), Decimal(point.y)));
+        }
+    }
+    if path.is_empty() {
+        return;
+    }
+    writeln!(output, "    <path class=\"text\" data-text=\"{}\" d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\" stroke-linecap=\"round\" stroke-linejoin=\"round\"/>",
+             xml_escape(&text.text), path.join(" "), color, Decimal(text.font.thickness)).unwrap();
+}
+
+/// Everything on a copper layer, zones first so the rest is drawn over them
+fn write_copper(output: &mut String, board: &Board, layer: BoardLayer, color: &str, zone_opacity: f32, arc_tolerance: f32) {
+    for zone in board.zones().iter().filter(|zone| zone.layers.contains(&layer)) {
+        let net = xml_escape(&zone.net);
+        match zone.fill.hatch() {
+            Some((_, width, _)) => {
+                writeln!(output, "    <g class=\"zone\" data-net=\"{}\" opacity=\"{}\">", net, Decimal(zone_opacity)).unwrap();
+                writeln!(output, "    <polygon class=\"zone-outline\" points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/>", points(&zone.outline), color, Decimal(width)).unwrap();
+                for line in zone.hatch_lines() {
+                    write_line(output, "hatch", None, line, width, color);
+                }
+                writeln!(output, "    </g>").unwrap();
+            }
+            None => {
+                writeln!(output, "    <polygon class=\"zone\" data-net=\"{}\" points=\"{}\" fill=\"{}\" fill-opacity=\"{}\"/>", net, points(&zone.outline), color, Decimal(zone_opacity)).unwrap();
+            }
+        }
+    }
+    for copper in board.thieving().iter().filter(|copper| copper.layer == layer) {
+        writeln!(output, "    <polygon class=\"thieving\" points=\"{}\" fill=\"{}\"/>", points(&copper.shape.outline(arc_tolerance)), color).unwrap();
+    }
+    for track in board.tracks().iter().filter(|track| track.layer == layer) {
+        write_line(output, "track", Some(&track.net), (track.start, track.end), track.width, color);
+    }
+    for arc in board.arcs().iter().filter(|arc| arc.layer == layer) {
+        for chord in arc.chords(arc_tolerance) {
+            write_line(output, "track", Some(&arc.net), chord, arc.width, color);
+        }
+    }
+    for (pad, outline) in board.pad_outlines(layer, arc_tolerance) {
+        writeln!(output, "    <polygon class=\"pad\" data-pad=\"{}\" points=\"{}\" fill=\"{}\"/>", xml_escape(&pad), points(&outline), color).unwrap();
+    }
+    for via in board.vias().iter().filter(|via| via.spans(&layer)) {
+        writeln!(output, "    <circle class=\"via\" data-net=\"{}\" cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>",
+                 xml_escape(&via.net), Decimal(via.position.x), Decimal(via.position.y), Decimal(via.diameter / 2.0), color).unwrap();
+    }
+    write_graphics(output, board, layer, color);
+}
+
+/// Pad holes and the drills of the vias reaching `layer`, slots as their center line
+fn write_holes(output: &mut String, board: &Board, layer: BoardLayer, color: &str) {
+    for hole in [Plating::Plated, Plating::NonPlated].into_iter().flat_map(|plating| drill_holes(board, plating)).filter(|hole| hole.source != "via") {
+        match hole.slot {
+            Some(ends) => write_line(output, "hole", None, ends, hole.diameter, color),
+            None => {
+                writeln!(output, "    <circle class=\"hole\" cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>",
+                         Decimal(hole.position.x), Decimal(hole.position.y), Decimal(hole.diameter / 2.0), color).unwrap();
+            }
+        }
+    }
+    for via in board.vias().iter().filter(|via| via.spans(&layer)) {
+        writeln!(output, "    <circle class=\"hole\" cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>",
+                 Decimal(via.position.x), Decimal(via.position.y), Decimal(via.drill / 2.0), color).unwrap();
+    }
+}
+
+fn write_edge(output: &mut String, board: &Board, color: &str, indent: &str) {
+    if let Some(outline) = board.outline() {
+        writeln!(output, "{}<polygon class=\"edge\" points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/>", indent, points(outline), color, Decimal(EDGE_WIDTH)).unwrap();
+    }
+}
+
+/// One side composited bottom up: substrate, copper, mask, silkscreen, holes
+fn write_side(output: &mut String, board: &Board, side: Side, theme: &Theme, arc_tolerance: f32) {
+    let outline = board.outline().map(<[Point]>::to_vec).unwrap_or_default();
+    if !outline.is_empty() {
+        writeln!(output, "    <polygon class=\"substrate\" points=\"{}\" fill=\"{}\"/>", points(&outline), theme.substrate).unwrap();
+    }
+    let copper = BoardLayer::new(LayerType::Copper, side);
+    writeln!(output, "    <g class=\"copper\">").unwrap();
+    write_copper(output, board, copper, &theme.copper, theme.zone_opacity, arc_tolerance);
+    writeln!(output, "    </g>").unwrap();
+    // The mask covers the board but for its openings, and the copper shows through it
+    if !outline.is_empty() {
+        let id = format!("{}-mask-openings", if side == Side::Back { "bottom" } else { "top" });
+        writeln!(output, "    <mask id=\"{}\" maskUnits=\"userSpaceOnUse\">", id).unwrap();
+        writeln!(output, "    <path d=\"{}\" fill=\"white\"/>", closed_path(&outline)).unwrap();
+        for opening in board.mask_openings(side) {
+            writeln!(output, "    <polygon points=\"{}\" fill=\"black\"/>", points(&opening)).unwrap();
+        }
+        writeln!(output, "    </mask>").unwrap();
+        writeln!(output, "    <path class=\"mask\" d=\"{}\" fill=\"{}\" fill-opacity=\"{}\" mask=\"url(#{})\"/>",
+                 closed_path(&outline), theme.mask, Decimal(theme.mask_opacity), id).unwrap();
+    }
+    let silk = BoardLayer::new(LayerType::SilkScreen, side);
+    writeln!(output, "    <g class=\"silkscreen\">").unwrap();
+    write_graphics(output, board, silk, &theme.silkscreen);
+    write_texts(output, board, silk, &theme.silkscreen);
+    writeln!(output, "    </g>").unwrap();
+    write_holes(output, board, copper, &theme.hole);
+    write_edge(output, board, &theme.edge, "    ");
+}
+
+#[cfg(test)]
+mod tests {
+    use copper_substrate::presets;
+
+    use super::*;
+
+    fn board() -> Board {
+        let mut board = Board::new("svg").with_rectangular_outline(20.0, 10.0);
+        board.place("R1", presets::resistor_0603("10k"), (5.0, 3.0), 0.0).unwrap();
+        board
+    }
+
+    /// x of the first point of R1 pad 1 in a rendering
+    fn pad_x(svg: &str) -> f32 {
+        let line = svg.lines().find(|line| line.contains("data-pad=\"R1.1\"")).unwrap();
+        let points = line.split("points=\"").nth(1).unwrap();
+        points.split(',').next().unwrap().parse().unwrap()
+    }
+
+    #[test]
+    fn layer_views_share_the_viewbox_and_are_not_flipped() {
+        let board = board();
+        let options = BoardSvgOptions::default();
+        let copper = board.render_svg(SvgView::Layer(BoardLayer::front(LayerType::Copper)), &options);
+        let edge = board.render_svg(SvgView::Edge, &options);
+        let viewbox = |svg: &str| svg.lines().next().unwrap().to_string();
+        assert_eq!(viewbox(&copper), viewbox(&edge));
+        assert!(copper.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"-1 -1 22 12\""), "{}", copper);
+        // Board Y down is SVG Y down: R1 near the top edge stays there
+        let line = copper.lines().find(|line| line.contains("data-pad=\"R1.1\"")).unwrap();
+        assert!(line.contains(",2.") || line.contains(",3."), "{}", line);
+        assert!(pad_x(&copper) < 5.0);
+        assert!(copper.contains(&format!("fill=\"{}\"", layer_color("F.Cu"))));
+    }
+
+    #[test]
+    fn the_bottom_view_is_mirrored_and_the_mask_is_translucent() {
+        let board = board();
+        let options = BoardSvgOptions::default().with_theme(Theme::default().with_mask("#202080", 0.8).with_layer_color("F.Cu", "#ff0000"));
+        let top = board.render_svg(SvgView::Side(Side::Front), &options);
+        assert!(top.contains("<path class=\"mask\" d=\"M0 0 L20 0 L20 10 L0 10 Z\" fill=\"#202080\" fill-opacity=\"0.8\" mask=\"url(#top-mask-openings)\"/>"), "{}", top);
+        // The mask is drawn over the copper and under the silkscreen, which shows R1
+        let at = |needle: &str| top.find(needle).unwrap_or_else(|| panic!("{} in {}", needle, top));
+        assert!(at("class=\"pad\"") < at("class=\"mask\"") && at("class=\"mask\"") < at("data-text=\"R1\""));
+        assert!(!top.contains("REF**"));
+        // Composites take the theme's copper, not a layer colour
+        assert!(top.contains("fill=\"#ffb478\"") && !top.contains("#ff0000"));
+        assert!(board.render_svg(SvgView::Layer(BoardLayer::front(LayerType::Copper)), &options).contains("fill=\"#ff0000\""));
+
+        let bottom = board.render_svg(SvgView::Side(Side::Back), &options);
+        assert!(bottom.contains("<g class=\"bottom\" transform=\"matrix(-1 0 0 1 20 0)\">"), "{}", bottom);
+        assert!(!bottom.contains("data-pad"), "R1 has no pads on the back: {}", bottom);
+    }
+
+    #[test]
+    fn every_view_has_a_file_name() {
+        let board = board();
+        let stems: Vec<String> = board_svg_views(&board).iter().map(SvgView::file_stem).collect();
+        assert_eq!(stems, ["F_Cu", "B_Cu", "F_Mask", "B_Mask", "F_SilkS", "B_SilkS", "F_Fab", "B_Fab", "Edge_Cuts", "top", "bottom"]);
+    }
+}
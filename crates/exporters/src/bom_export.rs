@@ -0,0 +1,131 @@
+//! Bill of materials export
+//!
+//! One line per distinct part: the placed components grouped by value and footprint, with
+//! their references in natural order (R2 before R10) and how many there are. Footprints
+//! that are never assembled, like fiducials, are left out as in the placement list (see
+//! `cpl_export`). Lines are sorted by their first reference.
+//!
+//! The CSV follows KiCad's grouped BOM: Reference,Value,Footprint,Qty, references joined
+//! with spaces and footprints named "library:footprint", with a Process column after it
+//! naming the assembly processes the part takes (see `assembly_process`), space separated.
+
+use std::cmp::Ordering;
+use std::fmt::Write;
+
+use copper_substrate::assembly_process::ProcessSet;
+use copper_substrate::board::Board;
+
+use crate::element_order::natural_cmp;
+use crate::exporter::{BoardExporter, ExportError, ExportOptions};
+
+/// Components sharing a value and footprint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BomLine {
+    /// In natural order
+    pub references: Vec<String>,
+    pub value: String,
+    /// "library:footprint"
+    pub footprint: String,
+    /// Processes the part can be assembled with
+    pub process: ProcessSet,
+}
+
+impl BomLine {
+    pub fn quantity(&self) -> usize {
+        self.references.len()
+    }
+}
+
+/// Bill of materials lines of `board`, see the module documentation
+pub fn bom_lines(board: &Board) -> Vec<BomLine> {
+    let mut lines: Vec<BomLine> = Vec::new();
+    for placed in board.components().iter().filter(|placed| placed.component.is_assembled()) {
+        let value = placed.component.functional_type().value().to_string();
+        let footprint = format!("{}:{}", placed.component.library_name(), placed.component.footprint_name());
+        match lines.iter_mut().find(|line| line.value == value && line.footprint == footprint) {
+            Some(line) => line.references.push(placed.reference.clone()),
+            None => lines.push(BomLine { references: vec![placed.reference.clone()], value, footprint, process: placed.component.process() }),
+        }
+    }
+    for line in &mut lines {
+        line.references.sort_by(|a, b| natural_cmp(a, b));
+    }
+    lines.sort_by(|a, b| match (a.references.first(), b.references.first()) {
+        (Some(a), Some(b)) => natural_cmp(a, b),
+        _ => Ordering::Equal,
+    });
+    lines
+}
+
+/// Quote a CSV field when it contains a separator or quote
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn to_bom_csv(board: &Board) -> String {
+    let mut output = String::from("Reference,Value,Footprint,Qty,Process\n");
+    for line in bom_lines(board) {
+        writeln!(
+            output,
+            "{},{},{},{},{}",
+            csv_field(&line.references.join(" ")),
+            csv_field(&line.value),
+            csv_field(&line.footprint),
+            line.quantity(),
+            line.process
+        )
+        .unwrap();
+    }
+    output
+}
+
+/// Writes the grouped bill of materials as CSV
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BomBoardExporter;
+
+impl BoardExporter for BomBoardExporter {
+    fn name(&self) -> &str {
+        "bom"
+    }
+
+    fn file_extension(&self) -> &str {
+        "csv"
+    }
+
+    fn export(&self, board: &Board, _options: &ExportOptions) -> Result<Vec<u8>, ExportError> {
+        Ok(to_bom_csv(board).into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use copper_substrate::presets;
+
+    use super::*;
+
+    #[test]
+    fn parts_group_by_value_and_footprint() {
+        let mut board = Board::new("bom");
+        for (reference, value, x) in [("R10", "10k", 0.0), ("R2", "10k", 3.0), ("R1", "1k, 1%", 6.0), ("R3", "10k", 9.0)] {
+            board.place(reference, presets::resistor_0603(value), (x, 0.0), 0.0).unwrap();
+        }
+        board.place("R4", presets::resistor_0402("10k"), (12.0, 0.0), 0.0).unwrap();
+        let lines = bom_lines(&board);
+        assert_eq!(lines.len(), 3, "{:?}", lines);
+        assert_eq!(lines[0].references, ["R1"]);
+        assert_eq!(lines[1].references, ["R2", "R3", "R10"]);
+        assert_eq!((lines[1].value.as_str(), lines[1].quantity()), ("10k", 3));
+        assert_eq!(lines[2].references, ["R4"]);
+
+        let csv = to_bom_csv(&board);
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows[0], "Reference,Value,Footprint,Qty,Process");
+        assert!(rows[1].starts_with("R1,\"1k, 1%\","), "{}", csv);
+        assert!(rows[2].starts_with("R2 R3 R10,10k,") && rows[2].ends_with(",3,reflow wave hand_solder"), "{}", csv);
+        assert!(lines[1].footprint.contains(':') && rows[2].contains(&lines[1].footprint), "{}", csv);
+    }
+}
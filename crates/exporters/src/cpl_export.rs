@@ -0,0 +1,274 @@
+//! Component placement list (pick-and-place) export
+//!
+//! Assembly machines want the true part centroid, whatever the footprint origin is. The
+//! centroid is taken from the footprint geometry (body bounds or pad centroid, depending
+//! on what the assembly house expects) and transformed to board coordinates, so a header
+//! anchored on pin 1 reports the same midpoint as the same header rebased to its centroid.
+//!
+//! Coordinates are written Y up like KiCad's position files, relative to the board origin
+//! or the auxiliary origin, see `Board::fab_point`.
+//!
+//! A rotation map corrects footprints drawn in another zero orientation than the reels.
+//! It maps footprint names to degrees added to their rotations, or taken off on the back
+//! where the part is seen mirrored. `OrientationReport::suggested_offsets` gives one, and
+//! `rotation_map_csv` writes it out for houses that apply corrections themselves.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use copper_substrate::anchor::CentroidSource;
+use copper_substrate::board::{Board, FabOrigin};
+use copper_substrate::geometry::Point;
+use copper_substrate::layer_type::Side;
+
+use crate::exporter::{BoardExporter, ExportError, ExportOptions};
+use crate::format::{Angle, Decimal};
+
+/// Column layout and conventions of a particular assembly house
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CplPreset {
+    /// KiCad's CSV position file: Ref,Val,Package,PosX,PosY,Rot,Side
+    #[default]
+    KiCad,
+    /// JLCPCB: Designator,Mid X,Mid Y,Layer,Rotation with unit suffixes
+    Jlcpcb,
+}
+
+impl CplPreset {
+    /// Centroid convention the house expects unless told otherwise
+    pub fn centroid_source(&self) -> CentroidSource {
+        match self {
+            CplPreset::KiCad | CplPreset::Jlcpcb => CentroidSource::BodyBounds,
+        }
+    }
+
+    fn header(&self) -> &'static str {
+        match self {
+            CplPreset::KiCad => "Ref,Val,Package,PosX,PosY,Rot,Side",
+            CplPreset::Jlcpcb => "Designator,Mid X,Mid Y,Layer,Rotation",
+        }
+    }
+}
+
+/// One row of the placement list
+#[derive(Debug, Clone, PartialEq)]
+pub struct CplEntry {
+    pub reference: String,
+    pub value: String,
+    pub package: String,
+    /// Part centroid in board coordinates (Y down, as everywhere in the model)
+    pub midpoint: (f32, f32),
+    pub rotation: f32,
+    pub side: Side,
+}
+
+/// Placement list rows in board order, paste-only footprints left out
+pub fn cpl_entries(board: &Board, centroid: CentroidSource) -> Vec<CplEntry> {
+    board
+        .components()
+        .iter()
+        .filter(|placed| placed.component.is_assembled())
+        .map(|placed| {
+            let local = centroid.centroid(placed.component.as_ref());
+            let midpoint = board.board_point(placed, local);
+            let (_, rotation) = board.exported_placement(placed);
+            CplEntry {
+                reference: placed.reference.clone(),
+                value: placed.component.functional_type().value().to_string(),
+                package: placed.component.footprint_name(),
+                midpoint: (midpoint.x, midpoint.y),
+                rotation: Angle::ccw(rotation).normalized().ccw_degrees(),
+                side: placed.side,
+            }
+        })
+        .collect()
+}
+
+/// Quote a CSV field when it contains a separator or quote
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn to_cpl(board: &Board, preset: CplPreset, centroid: CentroidSource, origin: FabOrigin) -> String {
+    to_cpl_with_rotation_map(board, preset, centroid, origin, &BTreeMap::new())
+}
+
+/// `to_cpl` with rotations corrected by `rotations`, see the module documentation
+pub fn to_cpl_with_rotation_map(
+    board: &Board,
+    preset: CplPreset,
+    centroid: CentroidSource,
+    origin: FabOrigin,
+    rotations: &BTreeMap<String, f32>,
+) -> String {
+    let mut output = String::new();
+    writeln!(output, "{}", preset.header()).unwrap();
+    for mut entry in cpl_entries(board, centroid) {
+        if let Some(offset) = rotations.get(&entry.package) {
+            let offset = if entry.side == Side::Back { -offset } else { *offset };
+            entry.rotation = Angle::ccw(entry.rotation + offset).normalized().ccw_degrees();
+        }
+        let Point { x, y } = board.fab_point(Point::from(entry.midpoint), origin);
+        let back = entry.side == Side::Back;
+        match preset {
+            CplPreset::KiCad => writeln!(
+                output,
+                "{},{},{},{:.4},{:.4},{:.4},{}",
+                csv_field(&entry.reference),
+                csv_field(&entry.value),
+                csv_field(&entry.package),
+                x,
+                y,
+                entry.rotation,
+                if back { "bottom" } else { "top" }
+            )
+            .unwrap(),
+            CplPreset::Jlcpcb => writeln!(
+                output,
+                "{},{:.4}mm,{:.4}mm,{},{}",
+                csv_field(&entry.reference),
+                x,
+                y,
+                if back { "Bottom" } else { "Top" },
+                Decimal(entry.rotation)
+            )
+            .unwrap(),
+        }
+    }
+    output
+}
+
+/// A rotation map as CSV, one footprint name and correction in degrees per row
+pub fn rotation_map_csv(rotations: &BTreeMap<String, f32>) -> String {
+    let mut output = String::from("Footprint,Rotation\n");
+    for (footprint, offset) in rotations {
+        writeln!(output, "{},{}", csv_field(footprint), Decimal(*offset)).unwrap();
+    }
+    output
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CplBoardExporter {
+    pub preset: CplPreset,
+    /// Overrides the preset's centroid convention
+    pub centroid: Option<CentroidSource>,
+    /// Absolute board coordinates unless set to the auxiliary origin
+    pub origin: FabOrigin,
+    /// Rotation corrections by footprint name, see the module documentation
+    pub rotations: BTreeMap<String, f32>,
+}
+
+impl CplBoardExporter {
+    pub fn new(preset: CplPreset) -> Self {
+        Self { preset, centroid: None, origin: FabOrigin::Absolute, rotations: BTreeMap::new() }
+    }
+
+    pub fn with_rotation_map(mut self, rotations: BTreeMap<String, f32>) -> Self {
+        self.rotations = rotations;
+        self
+    }
+
+    pub fn with_origin(mut self, origin: FabOrigin) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    pub fn with_centroid(mut self, centroid: CentroidSource) -> Self {
+        self.centroid = Some(centroid);
+        self
+    }
+}
+
+impl BoardExporter for CplBoardExporter {
+    fn name(&self) -> &str {
+        "cpl"
+    }
+
+    fn file_extension(&self) -> &str {
+        "csv"
+    }
+
+    fn export(&self, board: &Board, _options: &ExportOptions) -> Result<Vec<u8>, ExportError> {
+        let centroid = self.centroid.unwrap_or_else(|| self.preset.centroid_source());
+        Ok(to_cpl_with_rotation_map(board, self.preset, centroid, self.origin, &self.rotations).into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use copper_substrate::analysis::OrientationReport;
+    use copper_substrate::prelude::*;
+
+    use super::*;
+    use crate::drill_export::{to_excellon, Plating};
+    use crate::gerber_export::{to_copper_gerber, GerberDialect};
+
+    #[derive(BoardComponent)]
+    #[footprint(name = "TestPoint_THTPad_D2.0mm_Drill1.0mm", library = "TestPoint", kind = "Connector", bounding_box = (-1.0, -1.0, 1.0, 1.0))]
+    #[pad(number = "1", position = (0.0, 0.0), size = (2.0, 2.0), shape = "circle", drill = 1.0)]
+    struct TestPoint(#[value] &'static str);
+
+    /// A single through hole pad at (12.5, 7.25), with the auxiliary origin at (10, 20)
+    fn board() -> Board {
+        let mut board = Board::new("origin").with_rectangular_outline(30.0, 30.0);
+        board.set_aux_origin(Point::new(10.0, 20.0));
+        board.place("TP1", TestPoint("TP"), (12.5, 7.25), 0.0).unwrap();
+        board
+    }
+
+    /// The one pad's position in each fab output: copper flash, drill hit and placement row
+    fn pad_in_outputs(board: &Board, origin: FabOrigin) -> (String, String, String) {
+        let copper = to_copper_gerber(board, BoardLayer::front(LayerType::Copper), 0.01, origin, GerberDialect::Rs274x);
+        let flash = copper.lines().find(|line| line.ends_with("D03*")).unwrap().trim_end_matches("D03*").to_string();
+        let drill = to_excellon(board, Plating::Plated, origin);
+        let hit = drill.lines().find(|line| line.starts_with('X')).unwrap().to_string();
+        let cpl = to_cpl(board, CplPreset::KiCad, CentroidSource::BodyBounds, origin);
+        let row = cpl.lines().nth(1).unwrap().split(',').skip(3).take(2).collect::<Vec<_>>().join(",");
+        (flash, hit, row)
+    }
+
+    #[test]
+    fn a_pad_has_the_same_fab_point_in_every_output() {
+        let board = board();
+        assert_eq!(
+            pad_in_outputs(&board, FabOrigin::Aux),
+            ("X2500000Y12750000".to_string(), "X2.5Y12.75".to_string(), "2.5000,12.7500".to_string())
+        );
+        assert_eq!(
+            pad_in_outputs(&board, FabOrigin::Absolute),
+            ("X12500000Y-7250000".to_string(), "X12.5Y-7.25".to_string(), "12.5000,-7.2500".to_string())
+        );
+    }
+
+    #[test]
+    fn jlcpcb_rows_use_the_same_fab_point() {
+        let cpl = to_cpl(&board(), CplPreset::Jlcpcb, CentroidSource::BodyBounds, FabOrigin::Aux);
+        assert_eq!(cpl.lines().nth(1), Some("TP1,2.5000mm,12.7500mm,Top,0"));
+    }
+
+    #[test]
+    fn audited_offsets_correct_the_placement_rotations() {
+        let mut board = board();
+        let soic = || Soic::new(8, 1.27, 4.9, 3.9);
+        board.place("U1", soic(), (5.0, 20.0), 90.0).unwrap();
+        board.place_shared("U2", std::rc::Rc::new(soic()), (20.0, 20.0), 0.0, Side::Back).unwrap();
+        assert!(OrientationReport::for_board(&board).suggested_offsets().is_empty());
+
+        // As if the SOIC had been drawn with pin 1 bottom left
+        let rotations = BTreeMap::from([(soic().footprint_name(), 90.0)]);
+        let rotations_of = |exporter: CplBoardExporter| -> Vec<f32> {
+            let cpl = String::from_utf8(exporter.export(&board, &ExportOptions::default()).unwrap()).unwrap();
+            cpl.lines().skip(1).map(|line| line.rsplit(',').next().unwrap().parse().unwrap()).collect()
+        };
+        let plain = rotations_of(CplBoardExporter::new(CplPreset::Jlcpcb));
+        let corrected = rotations_of(CplBoardExporter::new(CplPreset::Jlcpcb).with_rotation_map(rotations.clone()));
+        assert_eq!(plain[..2], [0.0, 90.0]);
+        // The back part is seen mirrored, so its correction turns the other way
+        assert_eq!(corrected, [0.0, 180.0, (plain[2] + 270.0) % 360.0]);
+        assert_eq!(rotation_map_csv(&rotations), "Footprint,Rotation\nSOIC-8_3.9x4.9mm_P1.27mm,90\n");
+    }
+}
@@ -0,0 +1,293 @@
+//! Native copper-substrate files (.csf)
+//!
+//! `.kicad_mod` and `.kicad_pcb` only keep what KiCad knows about, so a footprint or board
+//! passed between pipeline stages through them loses roles, routing hints, functional
+//! types and the like. A `.csf` file keeps the model itself: a `Footprint` or a
+//! `BoardRecord` (see `copper_substrate::footprint`) serialized with serde, inside an
+//! envelope naming the format, its version and what the file holds:
+//!
+//! ```json
+//! { "format": "csf", "version": 1, "kind": "footprint", "content": { "name": "R_0603", ... } }
+//! ```
+//!
+//! Files are JSON, to read and diff, or CBOR, smaller and faster to read; `from_csf` tells
+//! them apart by the first byte, JSON envelopes starting with `{`. Reading checks the
+//! envelope, brings a file written by an older version up to date one version at a time
+//! (`MIGRATIONS`) and only then reads the content into the model. Fields added without a
+//! version bump must be optional so older files still load; files from a newer version
+//! are refused. Loaded boards are made of `Footprint`s, not of the generators that drew
+//! the original, and loaded footprints keep the KiCad source of parsed ones.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use copper_substrate::board::Board;
+use copper_substrate::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// Format version written to files
+pub const CSF_VERSION: u32 = 1;
+
+/// File extension of native files
+pub const CSF_EXTENSION: &str = "csf";
+
+/// Steps bringing a document up to date, entry `n` turning version `n + 1` into `n + 2`.
+/// Each takes the whole envelope, version already updated, and edits its content.
+pub const MIGRATIONS: [fn(&mut Value); CSF_VERSION as usize - 1] = [];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CsfEncoding {
+    #[default]
+    Json,
+    Cbor,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CsfError {
+    Io(String),
+    Parse(String),
+    /// Readable, but not a csf envelope
+    NotCsf,
+    UnsupportedVersion(u32),
+    /// A board file where a footprint was expected or the other way round
+    WrongKind { expected: &'static str, found: String },
+    /// A component naming a footprint index the board file doesn't have
+    MissingFootprint { reference: String, index: usize },
+}
+
+impl fmt::Display for CsfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsfError::Io(message) => write!(f, "cannot access csf file: {}", message),
+            CsfError::Parse(message) => write!(f, "invalid csf file: {}", message),
+            CsfError::NotCsf => f.write_str("not a csf file"),
+            CsfError::UnsupportedVersion(version) => {
+                write!(f, "csf version {} is newer than the supported version {}", version, CSF_VERSION)
+            }
+            CsfError::WrongKind { expected, found } => write!(f, "expected a csf {}, found a {}", expected, found),
+            CsfError::MissingFootprint { reference, index } => {
+                write!(f, "{} uses footprint {}, which the file doesn't have", reference, index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CsfError {}
+
+/// What a csf file can hold
+pub trait CsfContent: Serialize + DeserializeOwned {
+    /// Envelope `kind`
+    const KIND: &'static str;
+}
+
+impl CsfContent for Footprint {
+    const KIND: &'static str = "footprint";
+}
+
+impl CsfContent for BoardRecord {
+    const KIND: &'static str = "board";
+}
+
+#[derive(Serialize)]
+struct Envelope<'a, T> {
+    format: &'static str,
+    version: u32,
+    kind: &'static str,
+    content: &'a T,
+}
+
+/// `content` in a csf envelope
+pub fn to_csf<T: CsfContent>(content: &T, encoding: CsfEncoding) -> Result<Vec<u8>, CsfError> {
+    let envelope = Envelope { format: "csf", version: CSF_VERSION, kind: T::KIND, content };
+    match encoding {
+        CsfEncoding::Json => {
+            let mut bytes = serde_json::to_vec_pretty(&envelope).map_err(|e| CsfError::Parse(e.to_string()))?;
+            bytes.push(b'\n');
+            Ok(bytes)
+        }
+        CsfEncoding::Cbor => {
+            let mut bytes = Vec::new();
+            ciborium::into_writer(&envelope, &mut bytes).map_err(|e| CsfError::Parse(e.to_string()))?;
+            Ok(bytes)
+        }
+    }
+}
+
+/// The encoding of a csf file's bytes
+pub fn csf_encoding(bytes: &[u8]) -> CsfEncoding {
+    match bytes.iter().find(|byte| !byte.is_ascii_whitespace()) {
+        Some(b'{') => CsfEncoding::Json,
+        _ => CsfEncoding::Cbor,
+    }
+}
+
+/// The content of a csf file, migrated to the current version
+pub fn from_csf<T: CsfContent>(bytes: &[u8]) -> Result<T, CsfError> {
+    let mut envelope: Value = match csf_encoding(bytes) {
+        CsfEncoding::Json => serde_json::from_slice(bytes).map_err(|e| CsfError::Parse(e.to_string()))?,
+        CsfEncoding::Cbor => ciborium::from_reader(bytes).map_err(|e| CsfError::Parse(e.to_string()))?,
+    };
+    if envelope.get("format").and_then(Value::as_str) != Some("csf") {
+        return Err(CsfError::NotCsf);
+    }
+    let version = envelope.get("version").and_then(Value::as_u64).ok_or(CsfError::NotCsf)?;
+    let version = u32::try_from(version).map_err(|_| CsfError::UnsupportedVersion(u32::MAX))?;
+    if version == 0 || version > CSF_VERSION {
+        return Err(CsfError::UnsupportedVersion(version));
+    }
+    let kind = envelope.get("kind").and_then(Value::as_str).ok_or(CsfError::NotCsf)?;
+    if kind != T::KIND {
+        return Err(CsfError::WrongKind { expected: T::KIND, found: kind.to_string() });
+    }
+    for (step, migrate) in MIGRATIONS.iter().enumerate().skip(version as usize - 1) {
+        envelope["version"] = json!(step + 2);
+        migrate(&mut envelope);
+    }
+    let content = envelope.get_mut("content").map(Value::take).ok_or(CsfError::NotCsf)?;
+    serde_json::from_value(content).map_err(|e| CsfError::Parse(e.to_string()))
+}
+
+/// Saving and loading in the native format, see the module documentation
+pub trait CsfFile: Sized {
+    fn to_csf(&self, encoding: CsfEncoding) -> Result<Vec<u8>, CsfError>;
+
+    fn from_csf(bytes: &[u8]) -> Result<Self, CsfError>;
+
+    fn save(&self, path: &Path, encoding: CsfEncoding) -> Result<(), CsfError> {
+        fs::write(path, self.to_csf(encoding)?).map_err(|e| CsfError::Io(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Either encoding
+    fn load(path: &Path) -> Result<Self, CsfError> {
+        let bytes = fs::read(path).map_err(|e| CsfError::Io(format!("{}: {}", path.display(), e)))?;
+        Self::from_csf(&bytes)
+    }
+}
+
+impl CsfFile for Footprint {
+    fn to_csf(&self, encoding: CsfEncoding) -> Result<Vec<u8>, CsfError> {
+        to_csf(self, encoding)
+    }
+
+    fn from_csf(bytes: &[u8]) -> Result<Self, CsfError> {
+        from_csf(bytes)
+    }
+}
+
+impl CsfFile for Board {
+    fn to_csf(&self, encoding: CsfEncoding) -> Result<Vec<u8>, CsfError> {
+        to_csf(&self.to_record(), encoding)
+    }
+
+    fn from_csf(bytes: &[u8]) -> Result<Self, CsfError> {
+        let record: BoardRecord = from_csf(bytes)?;
+        if let Some(component) = record.components.iter().find(|component| component.footprint >= record.footprints.len()) {
+            return Err(CsfError::MissingFootprint { reference: component.reference.clone(), index: component.footprint });
+        }
+        Ok(Board::from_record(record))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use copper_substrate::presets;
+
+    use crate::kicad_footprint_parse::{KiCadFootprint, ParseOptions};
+
+    fn hinted_resistor() -> Footprint {
+        let mut footprint = Footprint::capture(&presets::resistor_0603("4k7"));
+        footprint.pads[0].routing_hint = Some(RoutingHint { no_via_under_pad: true, ..RoutingHint::default() });
+        footprint.graphics[0].role = Some(ElementRole::UserDefined("keep".to_string()));
+        footprint
+    }
+
+    fn board() -> Board {
+        let copper = BoardLayer::front(LayerType::Copper);
+        let mut board = Board::new("csf").with_rectangular_outline(20.0, 10.0).with_stackup(Stackup::four_layer_default());
+        board.place("R1", hinted_resistor(), (5.0, 5.0), 90.0).unwrap();
+        board.connect_pad("R1", "1", "SIG");
+        board.add_track(Track::new("SIG", copper, Point::new(5.0, 4.2), Point::new(12.0, 4.2), 0.2));
+        board.add_via(Via::new("SIG", Point::new(12.0, 4.2), 0.6, 0.3).with_backdrill(Backdrill::new(Side::Back, copper, 0.6)));
+        board.add_net_class(NetClass::new("Power", 0.3, 0.5, 0.8, 0.4));
+        board.assign_net_class("SIG", "Power");
+        board.add_zone(Zone::new("GND", &[BoardLayer::back(LayerType::Copper)], vec![Point::new(0.0, 0.0), Point::new(20.0, 0.0), Point::new(20.0, 10.0)]));
+        board
+    }
+
+    #[test]
+    fn footprints_round_trip_in_both_encodings() {
+        let footprint = hinted_resistor();
+        for encoding in [CsfEncoding::Json, CsfEncoding::Cbor] {
+            let bytes = footprint.to_csf(encoding).unwrap();
+            assert_eq!(csf_encoding(&bytes), encoding);
+            assert_eq!(Footprint::from_csf(&bytes).unwrap(), footprint);
+        }
+    }
+
+    #[test]
+    fn parsed_footprints_keep_their_source() {
+        let source = crate::to_kicad_footprint(&presets::resistor_0402("1k"));
+        let parsed = KiCadFootprint::parse(&source, ParseOptions::default()).unwrap();
+        let footprint = Footprint::capture(&parsed);
+        let loaded = Footprint::from_csf(&footprint.to_csf(CsfEncoding::Cbor).unwrap()).unwrap();
+        assert_eq!(loaded.kicad_source(), parsed.kicad_source());
+        assert_eq!(crate::to_kicad_footprint(&loaded), crate::to_kicad_footprint(&parsed));
+    }
+
+    #[test]
+    fn boards_round_trip_in_both_encodings() {
+        let board = board();
+        for encoding in [CsfEncoding::Json, CsfEncoding::Cbor] {
+            let loaded = Board::from_csf(&board.to_csf(encoding).unwrap()).unwrap();
+            assert_eq!(loaded.to_record(), board.to_record());
+            assert_eq!(loaded.net_class("SIG").name, "Power");
+        }
+        // The same board is written the same way every time
+        assert_eq!(board.to_csf(CsfEncoding::Json).unwrap(), board.to_csf(CsfEncoding::Json).unwrap());
+    }
+
+    #[test]
+    fn files_are_saved_and_loaded() {
+        let dir = std::env::temp_dir().join(format!("csf_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("board.csf");
+        let board = board();
+        board.save(&path, CsfEncoding::Cbor).unwrap();
+        assert_eq!(Board::load(&path).unwrap().to_record(), board.to_record());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// A version 1 file written before routing hints and backdrills existed
+    #[test]
+    fn version_1_files_without_later_fields_load() {
+        let board = board();
+        let mut envelope: Value = serde_json::from_slice(&board.to_csf(CsfEncoding::Json).unwrap()).unwrap();
+        for footprint in envelope["content"]["footprints"].as_array_mut().unwrap() {
+            for pad in footprint["pads"].as_array_mut().unwrap() {
+                pad.as_object_mut().unwrap().remove("routing_hint");
+            }
+        }
+        for via in envelope["content"]["routing"]["vias"].as_array_mut().unwrap() {
+            via.as_object_mut().unwrap().remove("backdrill");
+        }
+        let loaded = Board::from_csf(&serde_json::to_vec(&envelope).unwrap()).unwrap();
+        assert_eq!(loaded.components()[0].component.pad_descriptors()[0].routing_hint, None);
+        assert_eq!(loaded.vias()[0].backdrill, None);
+        assert_eq!(loaded.tracks(), board.tracks());
+    }
+
+    #[test]
+    fn envelopes_are_checked() {
+        let footprint = hinted_resistor().to_csf(CsfEncoding::Json).unwrap();
+        assert_eq!(Board::from_csf(&footprint).unwrap_err(), CsfError::WrongKind { expected: "board", found: "footprint".to_string() });
+        let newer = String::from_utf8(footprint).unwrap().replacen("\"version\": 1", "\"version\": 2", 1);
+        assert_eq!(Footprint::from_csf(newer.as_bytes()).unwrap_err(), CsfError::UnsupportedVersion(2));
+        assert_eq!(Footprint::from_csf(b"{\"name\": \"R1\"}").unwrap_err(), CsfError::NotCsf);
+        assert_eq!(Footprint::from_csf(b"(footprint R1)").unwrap_err(), CsfError::NotCsf);
+        assert!(matches!(Footprint::from_csf(b"{\"format\": \"csf\""), Err(CsfError::Parse(_))));
+    }
+}
@@ -0,0 +1,360 @@
+//! Excellon drill files
+//!
+//! Plated and non-plated holes go in separate files, as fabs expect and KiCad writes them:
+//! through vias and plated pad holes in the PTH file, non-plated pad holes in the NPTH
+//! file. Blind, buried and micro-vias need a file per layer span and aren't written.
+//!
+//! Backdrilled vias (see `copper_substrate::backdrill`) get a non-plated file per drilled
+//! side, `to_backdrill_excellon`. Its tools are set by diameter and depth, each with a
+//! comment giving the depth from the surface and the layer the drill must not cut.
+//!
+//! Each file defines its tools by diameter, smallest first, with a comment counting the
+//! holes and slots of each, then lists the holes tool by tool. Round holes are drill hits.
+//! Slots are routed: the tool goes down at one end (`M15`), cuts along the slot (`G01`)
+//! and comes up at the other (`M16`), and `G05` returns to drilling. Coordinates are
+//! metric decimal `Board::fab_point`s, like the Gerber layers.
+
+use std::fmt::Write;
+
+use copper_substrate::board::{Board, FabOrigin};
+use copper_substrate::prelude::*;
+
+use crate::exporter::{BoardExporter, ExportError, ExportOptions};
+
+/// Holes closer than this in diameter (mm) share a tool
+const TOOL_TOLERANCE: f32 = 0.0005;
+
+/// Which of the two drill files a hole belongs in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Plating {
+    #[default]
+    Plated,
+    NonPlated,
+}
+
+impl Plating {
+    /// .FileFunction of the file, for a board with `copper_layers` layers
+    fn file_function(self, copper_layers: usize) -> String {
+        match self {
+            Plating::Plated => format!("Plated,1,{},PTH", copper_layers),
+            Plating::NonPlated => format!("NonPlated,1,{},NPTH", copper_layers),
+        }
+    }
+}
+
+/// One hole in board coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrillHole {
+    pub plating: Plating,
+    /// Tool diameter: the drill, or the width of a slot
+    pub diameter: f32,
+    pub position: Point,
+    /// Ends of a slot's center line, `None` for a round hole
+    pub slot: Option<(Point, Point)>,
+    /// "via" or the pad as "reference.number"
+    pub source: String,
+}
+
+/// A tool of a drill file and what it makes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DrillTool {
+    pub diameter: f32,
+    pub holes: usize,
+    pub slots: usize,
+}
+
+/// Every hole of the board with `plating`: pads in placement order, then through vias
+pub fn drill_holes(board: &Board, plating: Plating) -> Vec<DrillHole> {
+    let mut holes = Vec::new();
+    for placed in board.components() {
+        for pad in placed.component.pad_descriptors() {
+            let Some(diameter) = pad.drill_size else {
+                continue;
+            };
+            let pad_plating = if pad.is_npth() { Plating::NonPlated } else { Plating::Plated };
+            if pad_plating != plating {
+                continue;
+            }
+            holes.push(DrillHole {
+                plating,
+                diameter,
+                position: board.board_point(placed, Point::from(pad.position)),
+                slot: pad.slot_ends().map(|(start, end)| (board.board_point(placed, start), board.board_point(placed, end))),
+                source: format!("{}.{}", placed.reference, pad.number),
+            });
+        }
+    }
+    if plating == Plating::Plated {
+        for via in board.vias().iter().filter(|via| via.kind == ViaKind::Through) {
+            holes.push(DrillHole {
+                plating,
+                diameter: via.drill,
+                position: via.position,
+                slot: None,
+                source: "via".to_string(),
+            });
+        }
+    }
+    holes
+}
+
+/// Tools for `holes`, smallest first, with their hole and slot counts
+pub fn drill_tools(holes: &[DrillHole]) -> Vec<DrillTool> {
+    let mut tools: Vec<DrillTool> = Vec::new();
+    for hole in holes {
+        let index = match tools.iter().position(|tool| (tool.diameter - hole.diameter).abs() < TOOL_TOLERANCE) {
+            Some(index) => index,
+            None => {
+                tools.push(DrillTool { diameter: hole.diameter, holes: 0, slots: 0 });
+                tools.len() - 1
+            }
+        };
+        if hole.slot.is_some() {
+            tools[index].slots += 1;
+        } else {
+            tools[index].holes += 1;
+        }
+    }
+    tools.sort_by(|a, b| a.diameter.total_cmp(&b.diameter));
+    tools
+}
+
+/// A millimeter value to four decimals, trailing zeros dropped
+fn excellon_number(value: f32) -> String {
+    let text = format!("{:.4}", value + 0.0);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" { "0".to_string() } else { text.to_string() }
+}
+
+fn coordinate(point: Point) -> String {
+    format!("X{}Y{}", excellon_number(point.x), excellon_number(point.y))
+}
+
+/// Everything up to the tool table, for a file with the X2 `.FileFunction` `file_function`
+fn write_header(output: &mut String, file_function: &str) {
+    writeln!(output, "M48").unwrap();
+    writeln!(output, "; DRILL file {{copper-substrate {}}}", env!("CARGO_PKG_VERSION")).unwrap();
+    writeln!(output, "; FORMAT={{-:-/ absolute / metric / decimal}}").unwrap();
+    writeln!(output, "; #@! TF.GenerationSoftware,Atlantix-EDA,copper-substrate,{}", env!("CARGO_PKG_VERSION")).unwrap();
+    writeln!(output, "; #@! TF.FileFunction,{}", file_function).unwrap();
+    writeln!(output, "FMAT,2").unwrap();
+    writeln!(output, "METRIC").unwrap();
+}
+
+/// The drill file for the holes with `plating`
+pub fn to_excellon(board: &Board, plating: Plating, origin: FabOrigin) -> String {
+    let holes = drill_holes(board, plating);
+    let tools = drill_tools(&holes);
+    let tool_of = |hole: &DrillHole| tools.iter().position(|tool| (tool.diameter - hole.diameter).abs() < TOOL_TOLERANCE);
+
+    let mut output = String::new();
+    write_header(&mut output, &plating.file_function(board.copper_layers().len()));
+    for (index, tool) in tools.iter().enumerate() {
+        writeln!(output, "; T{} {} holes, {} slots", index + 1, tool.holes, tool.slots).unwrap();
+        writeln!(output, "T{}C{:.3}", index + 1, tool.diameter).unwrap();
+    }
+    writeln!(output, "%").unwrap();
+    writeln!(output, "G90").unwrap();
+    writeln!(output, "G05").unwrap();
+    for index in 0..tools.len() {
+        writeln!(output, "T{}", index + 1).unwrap();
+        for hole in holes.iter().filter(|hole| tool_of(hole) == Some(index)) {
+            match hole.slot {
+                None => writeln!(output, "{}", coordinate(board.fab_point(hole.position, origin))).unwrap(),
+                Some((start, end)) => {
+                    writeln!(output, "G00{}", coordinate(board.fab_point(start, origin))).unwrap();
+                    writeln!(output, "M15").unwrap();
+                    writeln!(output, "G01{}", coordinate(board.fab_point(end, origin))).unwrap();
+                    writeln!(output, "M16").unwrap();
+                    writeln!(output, "G05").unwrap();
+                }
+            }
+        }
+    }
+    writeln!(output, "M30").unwrap();
+    output
+}
+
+/// The backdrill file for the vias drilled from `side`, holes in via order. Fails when a
+/// backdrill from that side has issues in `Board::backdrill_report`, since a wrong depth
+/// cuts a connection.
+pub fn to_backdrill_excellon(board: &Board, side: Side, origin: FabOrigin) -> Result<String, ExportError> {
+    let report = board.backdrill_report();
+    if let Some(entry) = report.entries.iter().find(|entry| entry.backdrill.side == side && !entry.issues.is_empty()) {
+        return Err(ExportError::Other(format!("via {} ({}): {}", entry.via, entry.net, entry.issues[0])));
+    }
+    let holes: Vec<_> = report.drillable(side).collect();
+    // Tools by diameter, then depth
+    let mut tools: Vec<(f32, f32, String)> = Vec::new();
+    for (entry, span) in &holes {
+        let same = |tool: &&(f32, f32, String)| {
+            (tool.0 - entry.backdrill.diameter).abs() < TOOL_TOLERANCE && (tool.1 - span.depth).abs() < TOOL_TOLERANCE
+        };
+        if !tools.iter().any(|tool| same(&tool)) {
+            tools.push((entry.backdrill.diameter, span.depth, entry.backdrill.must_not_cut.to_kicad_string()));
+        }
+    }
+    tools.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    let tool_of = |diameter: f32, depth: f32| {
+        tools.iter().position(|tool| (tool.0 - diameter).abs() < TOOL_TOLERANCE && (tool.1 - depth).abs() < TOOL_TOLERANCE)
+    };
+
+    // Copper layers are numbered from the top; the file spans the drilled side to the
+    // deepest layer cut
+    let copper = board.copper_layers();
+    let number = |layer: &BoardLayer| copper.iter().position(|copper| copper == layer).map_or(0, |index| index + 1);
+    let surface = BoardLayer::new(LayerType::Copper, side);
+    let start = number(&surface);
+    let deepest = holes.iter().flat_map(|(_, span)| span.cut_layers.last()).map(number);
+    let (from, to) = match side {
+        Side::Back => (deepest.min().unwrap_or(start), start),
+        _ => (start, deepest.max().unwrap_or(start)),
+    };
+
+    let mut output = String::new();
+    write_header(&mut output, &format!("NonPlated,{},{},Blind", from, to));
+    for (index, (diameter, depth, must_not_cut)) in tools.iter().enumerate() {
+        let count = holes.iter().filter(|(entry, span)| tool_of(entry.backdrill.diameter, span.depth) == Some(index)).count();
+        writeln!(output, "; T{} {} holes, depth {:.3} mm from {}, must not cut {}", index + 1, count, depth, surface.to_kicad_string(), must_not_cut)
+            .unwrap();
+        writeln!(output, "T{}C{:.3}", index + 1, diameter).unwrap();
+    }
+    writeln!(output, "%").unwrap();
+    writeln!(output, "G90").unwrap();
+    writeln!(output, "G05").unwrap();
+    for index in 0..tools.len() {
+        writeln!(output, "T{}", index + 1).unwrap();
+        for (entry, _) in holes.iter().filter(|(entry, span)| tool_of(entry.backdrill.diameter, span.depth) == Some(index)) {
+            writeln!(output, "{}", coordinate(board.fab_point(entry.position, origin))).unwrap();
+        }
+    }
+    writeln!(output, "M30").unwrap();
+    Ok(output)
+}
+
+/// Writes the plated or the non-plated drill file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DrillBoardExporter {
+    pub plating: Plating,
+    /// Absolute board coordinates unless set to the auxiliary origin
+    pub origin: FabOrigin,
+}
+
+impl DrillBoardExporter {
+    pub fn new(plating: Plating) -> Self {
+        Self { plating, origin: FabOrigin::Absolute }
+    }
+
+    pub fn with_origin(mut self, origin: FabOrigin) -> Self {
+        self.origin = origin;
+        self
+    }
+}
+
+impl BoardExporter for DrillBoardExporter {
+    /// "drill" for the plated file, "npth_drill" for the non-plated one
+    fn name(&self) -> &str {
+        match self.plating {
+            Plating::Plated => "drill",
+            Plating::NonPlated => "npth_drill",
+        }
+    }
+
+    fn file_extension(&self) -> &str {
+        "drl"
+    }
+
+    fn export(&self, board: &Board, _options: &ExportOptions) -> Result<Vec<u8>, ExportError> {
+        Ok(to_excellon(board, self.plating, self.origin).into_bytes())
+    }
+}
+
+/// Writes the backdrill file of one side
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackdrillBoardExporter {
+    /// The side drilled from, `Side::Front` or `Side::Back`
+    pub side: Side,
+    /// Absolute board coordinates unless set to the auxiliary origin
+    pub origin: FabOrigin,
+}
+
+impl BackdrillBoardExporter {
+    pub fn new(side: Side) -> Self {
+        Self { side, origin: FabOrigin::Absolute }
+    }
+
+    pub fn with_origin(mut self, origin: FabOrigin) -> Self {
+        self.origin = origin;
+        self
+    }
+}
+
+impl BoardExporter for BackdrillBoardExporter {
+    /// "backdrill_front" or "backdrill_back"
+    fn name(&self) -> &str {
+        match self.side {
+            Side::Front => "backdrill_front",
+            _ => "backdrill_back",
+        }
+    }
+
+    fn file_extension(&self) -> &str {
+        "drl"
+    }
+
+    fn export(&self, board: &Board, _options: &ExportOptions) -> Result<Vec<u8>, ExportError> {
+        Ok(to_backdrill_excellon(board, self.side, self.origin)?.into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1.51 mm six layer board, 35 micron copper throughout
+    fn six_layer() -> Stackup {
+        let mut layers = vec![StackupLayer::copper("F.Cu", 0.035)];
+        for (index, thickness) in [0.2, 0.3, 0.3, 0.3, 0.2].into_iter().enumerate() {
+            layers.push(StackupLayer::dielectric(&format!("Dielectric {}", index + 1), thickness, 4.2, 0.01));
+            layers.push(StackupLayer::copper(if index == 4 { "B.Cu".to_string() } else { format!("In{}.Cu", index + 1) }.as_str(), 0.035));
+        }
+        Stackup::new(layers)
+    }
+
+    /// F.Cu to In2.Cu through a via backdrilled from the back
+    fn backdrilled_board(must_not_cut: BoardLayer) -> Board {
+        let mut board = Board::new("backdrill").with_stackup(six_layer());
+        let via = Point::new(10.0, 5.0);
+        board.add_track(Track::new("SIG", BoardLayer::front(LayerType::Copper), Point::new(0.0, 5.0), via, 0.1));
+        board.add_track(Track::new("SIG", BoardLayer::inner_copper(2), via, Point::new(20.0, 5.0), 0.1));
+        board.add_via(Via::new("SIG", via, 0.5, 0.25).with_backdrill(Backdrill::new(Side::Back, must_not_cut, 0.45)));
+        board.add_via(Via::new("GND", Point::new(12.0, 8.0), 0.5, 0.25));
+        board
+    }
+
+    #[test]
+    fn backdrill_file_lists_the_depth_from_the_stackup() {
+        let board = backdrilled_board(BoardLayer::inner_copper(2));
+        let file = to_backdrill_excellon(&board, Side::Back, FabOrigin::Absolute).unwrap();
+        // B.Cu up to the near face of In2.Cu, 0.905 mm, less the 0.15 mm margin
+        for expected in [
+            "; #@! TF.FileFunction,NonPlated,4,6,Blind",
+            "; T1 1 holes, depth 0.755 mm from B.Cu, must not cut In2.Cu\nT1C0.450\n",
+            // Fab coordinates have Y up
+            "T1\nX10Y-5\nM30",
+        ] {
+            assert!(file.contains(expected), "{} missing from\n{}", expected, file);
+        }
+        // Plain vias stay in the plated file only, and nothing is drilled from the front
+        assert!(!file.contains("X12Y-8"));
+        let front = to_backdrill_excellon(&board, Side::Front, FabOrigin::Absolute).unwrap();
+        assert!(front.contains("NonPlated,1,1,Blind") && !front.contains("T1C"), "{}", front);
+    }
+
+    #[test]
+    fn backdrill_cutting_a_connection_is_refused() {
+        let board = backdrilled_board(BoardLayer::inner_copper(1));
+        let error = to_backdrill_excellon(&board, Side::Back, FabOrigin::Absolute).unwrap_err();
+        assert_eq!(error.to_string(), "via 0 (SIG): backdrill cuts In2.Cu, where the via is connected");
+    }
+}
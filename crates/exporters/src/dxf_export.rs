@@ -0,0 +1,191 @@
+use std::fmt::Write;
+use copper_substrate::prelude::*;
+
+use crate::exporter::{ExportError, ExportOptions, FootprintExporter};
+use crate::format::{y_up, Angle, Decimal};
+use crate::layer_map::{expand_layer_name, LayerMap, LayerMapError};
+
+/// Arc tolerance used when writing pad outlines as polylines
+const DXF_ARC_TOLERANCE: f32 = 0.01;
+
+fn write_line(output: &mut String, layer: &str, start: (f32, f32), end: (f32, f32)) {
+    writeln!(output, "0\nLINE\n8\n{}", layer).unwrap();
+    writeln!(output, "10\n{}\n20\n{}", Decimal(start.0), Decimal(y_up(start.1))).unwrap();
+    writeln!(output, "11\n{}\n21\n{}", Decimal(end.0), Decimal(y_up(end.1))).unwrap();
+}
+
+fn write_circle(output: &mut String, layer: &str, center: (f32, f32), radius: f32) {
+    writeln!(output, "0\nCIRCLE\n8\n{}", layer).unwrap();
+    writeln!(output, "10\n{}\n20\n{}\n40\n{}", Decimal(center.0), Decimal(y_up(center.1)), Decimal(radius)).unwrap();
+}
+
+/// Single line TEXT entity anchored on `position` as `justify` says, for dimension text. Y up leaves the
+/// counter-clockwise angle as it is; mirrored text is written backwards (71 = 2) rather
+/// than with a negated angle.
+fn write_text(output: &mut String, layer: &str, position: Point, height: f32, angle: Angle, justify: Justify, text: &str) {
+    let (x, y) = (Decimal(position.x), Decimal(y_up(position.y)));
+    writeln!(output, "0\nTEXT\n8\n{}", layer).unwrap();
+    writeln!(output, "10\n{}\n20\n{}\n40\n{}\n1\n{}\n50\n{}", x, y, Decimal(height), text, angle.normalized()).unwrap();
+    if justify.mirror {
+        writeln!(output, "71\n2").unwrap();
+    }
+    let horizontal = match justify.horizontal {
+        HorizontalJustify::Left => 0,
+        HorizontalJustify::Center => 1,
+        HorizontalJustify::Right => 2,
+    };
+    let vertical = match justify.vertical {
+        VerticalJustify::Bottom => 1,
+        VerticalJustify::Center => 2,
+        VerticalJustify::Top => 3,
+    };
+    // Any alignment but left and baseline anchors on the second point
+    writeln!(output, "72\n{}\n73\n{}\n11\n{}\n21\n{}", horizontal, vertical, x, y).unwrap();
+}
+
+fn write_closed_polyline(output: &mut String, layer: &str, points: &[Point]) {
+    writeln!(output, "0\nPOLYLINE\n8\n{}\n66\n1\n70\n1", layer).unwrap();
+    for point in points {
+        writeln!(output, "0\nVERTEX\n8\n{}\n10\n{}\n20\n{}", layer, Decimal(point.x), Decimal(y_up(point.y))).unwrap();
+    }
+    writeln!(output, "0\nSEQEND").unwrap();
+}
+
+pub fn write_dxf_graphic_element(output: &mut String, element: &GraphicElement, layers: &LayerMap) -> Result<(), LayerMapError> {
+    let layer = layers.resolve(&element.board_layer())?;
+    match &element.element_type {
+        GraphicType::Line { start, end } => write_line(output, layer, *start, *end),
+        GraphicType::Rectangle { bounds } => {
+            let corners = [
+                Point::new(bounds.min_x, bounds.min_y),
+                Point::new(bounds.max_x, bounds.min_y),
+                Point::new(bounds.max_x, bounds.max_y),
+                Point::new(bounds.min_x, bounds.max_y),
+            ];
+            write_closed_polyline(output, layer, &corners);
+        }
+        GraphicType::Circle { center, radius } => write_circle(output, layer, *center, *radius),
+        // DXF hatches are left to CAD tools; hatch lines are drawn as lines
+        GraphicType::Polygon { points, .. } => {
+            write_closed_polyline(output, layer, &points.iter().map(|point| Point::from(*point)).collect::<Vec<_>>());
+            for line in expand_hatch(element).iter().skip(1) {
+                if let GraphicType::Line { start, end } = line.element_type {
+                    write_line(output, layer, start, end);
+                }
+            }
+        }
+        GraphicType::Dimension(dimension) => {
+            let geometry = dimension.geometry();
+            for (start, end) in &geometry.lines {
+                write_line(output, layer, (start.x, start.y), (end.x, end.y));
+            }
+            for (tip, left, right) in &geometry.arrowheads {
+                write_line(output, layer, (left.x, left.y), (tip.x, tip.y));
+                write_line(output, layer, (tip.x, tip.y), (right.x, right.y));
+            }
+            let angle = Angle::ccw(geometry.text_angle);
+            write_text(output, layer, geometry.text_position, dimension.text_height, angle, Justify::default(), &dimension.text());
+        }
+    }
+    Ok(())
+}
+
+/// Open POLYLINE drawn `width` wide, start and end width alike
+fn write_wide_polyline(output: &mut String, layer: &str, points: &[Point], width: f32) {
+    writeln!(output, "0\nPOLYLINE\n8\n{}\n66\n1\n70\n0\n40\n{}\n41\n{}", layer, Decimal(width), Decimal(width)).unwrap();
+    for point in points {
+        writeln!(output, "0\nVERTEX\n8\n{}\n10\n{}\n20\n{}", layer, Decimal(point.x), Decimal(y_up(point.y))).unwrap();
+    }
+    writeln!(output, "0\nSEQEND").unwrap();
+}
+
+/// Footprint text, as the strokes it is plotted with (see `stroke_font`): one polyline
+/// per stroke at the font thickness, so the drawing matches the board whatever fonts the
+/// CAD tool has. Text on user layers such as "Cmts.User" has no board layer to map and is
+/// left out, as graphics can't be drawn there.
+pub fn write_dxf_text(output: &mut String, fp_text: &FpText, layers: &LayerMap) -> Result<(), LayerMapError> {
+    let Some(board_layer) = BoardLayer::from_kicad_str(&fp_text.layer) else {
+        return Ok(());
+    };
+    let layer = layers.resolve(&board_layer)?;
+    for stroke in fp_text.strokes() {
+        write_wide_polyline(output, layer, &stroke, fp_text.font.thickness);
+    }
+    Ok(())
+}
+
+/// Write the pad outline once on every concrete layer the pad occupies, and its hole on
+/// the outer copper layers. A non-plated hole without copper around it is only its hole,
+/// which goes on both outer copper layers whatever the pad lists.
+pub fn write_dxf_pad(output: &mut String, pad: &PadDescriptor, layers: &LayerMap) -> Result<(), LayerMapError> {
+    let outline = pad.outline_polygon(DXF_ARC_TOLERANCE);
+    let bare_hole = pad.is_npth() && !pad.has_copper();
+    for name in &pad.layers {
+        for board_layer in expand_layer_name(name) {
+            if !(bare_hole && board_layer.layer == LayerType::Copper) {
+                write_closed_polyline(output, layers.resolve(&board_layer)?, &outline);
+            }
+        }
+    }
+    if let Some(drill) = pad.drill_size {
+        for board_layer in [BoardLayer::front(LayerType::Copper), BoardLayer::back(LayerType::Copper)] {
+            if !bare_hole && !pad.has_layer(&board_layer.to_kicad_string()) {
+                continue;
+            }
+            match pad.drill_outline(DXF_ARC_TOLERANCE).filter(|_| pad.drill_slot.is_some()) {
+                Some(slot) => write_closed_polyline(output, layers.resolve(&board_layer)?, &slot),
+                None => write_circle(output, layers.resolve(&board_layer)?, pad.position, drill / 2.0),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Generate an ASCII DXF (R12 entities) drawing of a footprint. Every layer is resolved
+/// through `layers`; an unmapped layer is an error rather than a guess.
+pub fn to_dxf<T: BoardComposableObject + ?Sized>(component: &T, layers: &LayerMap) -> Result<String, LayerMapError> {
+    let mut output = String::new();
+    writeln!(output, "0\nSECTION\n2\nENTITIES").unwrap();
+
+    let mut all_graphics = component.graphic_elements();
+    for courtyard in component.generate_courtyards() {
+        all_graphics.extend(courtyard.to_graphic_elements());
+    }
+    for element in &all_graphics {
+        write_dxf_graphic_element(&mut output, element, layers)?;
+    }
+
+    for pad in component.pad_descriptors() {
+        write_dxf_pad(&mut output, &pad, layers)?;
+    }
+    for fp_text in component.fp_text_elements().iter().filter(|text| !text.hidden) {
+        write_dxf_text(&mut output, fp_text, layers)?;
+    }
+
+    writeln!(output, "0\nENDSEC\n0\nEOF").unwrap();
+    Ok(output)
+}
+
+pub struct DxfFootprintExporter;
+
+impl FootprintExporter for DxfFootprintExporter {
+    fn name(&self) -> &str {
+        "dxf"
+    }
+
+    fn file_extension(&self) -> &str {
+        "dxf"
+    }
+
+    fn export(&self, component: &dyn BoardComposableObject, options: &ExportOptions) -> Result<Vec<u8>, ExportError> {
+        let default_map;
+        let layers = match &options.layer_map {
+            Some(map) => map,
+            None => {
+                default_map = LayerMap::dxf_default();
+                &default_map
+            }
+        };
+        Ok(to_dxf(component, layers)?.into_bytes())
+    }
+}
@@ -0,0 +1,353 @@
+//! Canonical order of footprint elements
+//!
+//! Generators and wrappers hold texts, graphics and pads in whatever order they were built
+//! in, so composing the same footprint another way would reorder its file. Before writing,
+//! the KiCad exporter puts them in one order instead (see `ExportOptions::canonical_order`):
+//!
+//! - texts: reference, value, then user texts, each by layer, text and position
+//! - graphics: by layer in KiCad's layer numbering (F.Cu, inner copper, B.Cu, adhesive,
+//!   paste, silkscreen, mask, user layers, edge cuts, courtyard, fab), then by shape and
+//!   coordinates
+//! - pads: by number in natural order, see `natural_cmp`, then by position
+//!
+//! The uuid breaks any remaining tie, so the order never depends on the order elements
+//! came in.
+
+use std::cmp::Ordering;
+
+use copper_substrate::layer_type::KICAD_MAX_INNER_COPPER;
+use copper_substrate::prelude::*;
+
+/// Sided layers in KiCad's numbering, back before front as KiCad numbers them; copper is
+/// handled separately
+const SIDED_LAYER_ORDER: &[&str] = &["B.Adhes", "F.Adhes", "B.Paste", "F.Paste", "B.SilkS", "F.SilkS", "B.Mask", "F.Mask"];
+
+const USER_LAYER_ORDER: &[&str] = &["Dwgs.User", "Cmts.User", "Eco1.User", "Eco2.User", "Edge.Cuts", "Margin"];
+
+const OUTLINE_LAYER_ORDER: &[&str] = &["B.CrtYd", "F.CrtYd", "B.Fab", "F.Fab"];
+
+/// Position of a KiCad layer name in KiCad's numbering; unknown names sort last, by name
+fn layer_rank(name: &str) -> (u16, &str) {
+    let copper = match name {
+        "F.Cu" => Some(0),
+        "B.Cu" => Some(u16::from(KICAD_MAX_INNER_COPPER) + 1),
+        _ => name.strip_prefix("In").and_then(|rest| rest.strip_suffix(".Cu")).and_then(|index| index.parse().ok()),
+    };
+    if let Some(copper) = copper {
+        return (copper, "");
+    }
+    let mut rank = u16::from(KICAD_MAX_INNER_COPPER) + 2;
+    for table in [SIDED_LAYER_ORDER, USER_LAYER_ORDER, OUTLINE_LAYER_ORDER] {
+        if let Some(index) = table.iter().position(|layer| *layer == name) {
+            return (rank + index as u16, "");
+        }
+        rank += table.len() as u16;
+    }
+    match name.strip_prefix("User.").and_then(|index| index.parse::<u16>().ok()) {
+        Some(index) => (rank + index, ""),
+        None => (u16::MAX, name),
+    }
+}
+
+/// Letters, digits and everything else, in the order `natural_cmp` puts them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum RunKind {
+    Digits,
+    Letters,
+    Other,
+}
+
+fn run_kind(c: char) -> RunKind {
+    if c.is_ascii_digit() {
+        RunKind::Digits
+    } else if c.is_alphabetic() {
+        RunKind::Letters
+    } else {
+        RunKind::Other
+    }
+}
+
+/// `text` split into runs of digits, letters and other characters
+fn runs(text: &str) -> Vec<(RunKind, &str)> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    let mut kind = None;
+    for (index, c) in text.char_indices() {
+        let this = run_kind(c);
+        if let Some(previous) = kind.filter(|&kind| kind != this) {
+            runs.push((previous, &text[start..index]));
+            start = index;
+        }
+        kind = Some(this);
+    }
+    if let Some(kind) = kind {
+        runs.push((kind, &text[start..]));
+    }
+    runs
+}
+
+/// Compare pad numbers the way a person counts them: digit runs by value, so "2" comes
+/// before "10" and "A2" before "A10", and letter runs by length first, so BGA row "Y"
+/// comes before "AA". Numbers come before letters, so "1" before "A1" before "EP". Names
+/// equal under these rules ("01" and "1", "a1" and "A1") still get a fixed order.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (a_runs, b_runs) = (runs(a), runs(b));
+    for (&(a_kind, a_run), &(b_kind, b_run)) in a_runs.iter().zip(&b_runs) {
+        let ordering = a_kind.cmp(&b_kind).then_with(|| match a_kind {
+            RunKind::Digits => {
+                let (a_value, b_value) = (a_run.trim_start_matches('0'), b_run.trim_start_matches('0'));
+                a_value.len().cmp(&b_value.len()).then_with(|| a_value.cmp(b_value))
+            }
+            RunKind::Letters => a_run
+                .chars()
+                .count()
+                .cmp(&b_run.chars().count())
+                .then_with(|| a_run.to_uppercase().cmp(&b_run.to_uppercase())),
+            RunKind::Other => a_run.cmp(b_run),
+        });
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a_runs.len().cmp(&b_runs.len()).then_with(|| a.cmp(b))
+}
+
+/// Compare two coordinate lists value by value
+fn cmp_values(a: &[f32], b: &[f32]) -> Ordering {
+    a.iter().zip(b).map(|(a, b)| a.total_cmp(b)).find(|ordering| ordering.is_ne()).unwrap_or_else(|| a.len().cmp(&b.len()))
+}
+
+fn text_type_rank(text_type: &FpTextType) -> u8 {
+    match text_type {
+        FpTextType::Reference => 0,
+        FpTextType::Value => 1,
+        FpTextType::User => 2,
+    }
+}
+
+/// Shape rank and the numbers describing it
+fn shape_key(element_type: &GraphicType) -> (u8, Vec<f32>) {
+    match element_type {
+        GraphicType::Line { start, end } => (0, vec![start.0, start.1, end.0, end.1]),
+        GraphicType::Rectangle { bounds } => (1, vec![bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y]),
+        GraphicType::Circle { center, radius } => (2, vec![center.0, center.1, *radius]),
+        GraphicType::Polygon { points, fill } => {
+            let mut values: Vec<f32> = match fill {
+                None => vec![0.0],
+                Some(FillStyle::Solid) => vec![1.0],
+                Some(FillStyle::Hatch { pitch, width, angle }) => vec![2.0, *pitch, *width, *angle],
+                Some(FillStyle::CrossHatch { pitch, width, angle }) => vec![3.0, *pitch, *width, *angle],
+            };
+            values.extend(points.iter().flat_map(|point| [point.0, point.1]));
+            (3, values)
+        }
+        GraphicType::Dimension(dimension) => (
+            4,
+            vec![dimension.start.0, dimension.start.1, dimension.end.0, dimension.end.1, dimension.height, dimension.text_height],
+        ),
+    }
+}
+
+pub fn sort_texts(texts: &mut [FpText]) {
+    texts.sort_by(|a, b| {
+        text_type_rank(&a.text_type)
+            .cmp(&text_type_rank(&b.text_type))
+            .then_with(|| layer_rank(&a.layer).cmp(&layer_rank(&b.layer)))
+            .then_with(|| a.text.cmp(&b.text))
+            .then_with(|| cmp_values(&[a.position.0, a.position.1], &[b.position.0, b.position.1]))
+            .then_with(|| a.uuid.cmp(&b.uuid))
+    });
+}
+
+pub fn sort_graphics(graphics: &mut [GraphicElement]) {
+    graphics.sort_by(|a, b| {
+        let (a_layer, b_layer) = (a.kicad_layer(), b.kicad_layer());
+        let ((a_shape, a_values), (b_shape, b_values)) = (shape_key(&a.element_type), shape_key(&b.element_type));
+        layer_rank(&a_layer)
+            .cmp(&layer_rank(&b_layer))
+            .then_with(|| a_shape.cmp(&b_shape))
+            .then_with(|| cmp_values(&a_values, &b_values))
+            .then_with(|| a.stroke.width.total_cmp(&b.stroke.width))
+            .then_with(|| a.uuid.cmp(&b.uuid))
+    });
+}
+
+pub fn sort_pads(pads: &mut [PadDescriptor]) {
+    pads.sort_by(|a, b| {
+        natural_cmp(&a.number, &b.number)
+            .then_with(|| cmp_values(&[a.position.0, a.position.1], &[b.position.0, b.position.1]))
+            .then_with(|| a.uuid.cmp(&b.uuid))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::exporter::ExportOptions;
+    use crate::kicad_pcb_export::to_kicad_footprint_with_options;
+
+    /// Natural order of the SOIC-16's pads followed by BGA style and exposed pads
+    const NATURAL: [&str; 23] =
+        ["1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11", "12", "13", "14", "15", "16", "A1", "A2", "A10", "B1", "Y3", "AA1", "EP"];
+
+    /// Fisher-Yates with a fixed LCG, so failures reproduce
+    fn shuffle<T>(items: &mut [T], seed: &mut u64) {
+        for i in (1..items.len()).rev() {
+            *seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            items.swap(i, ((*seed >> 33) as usize) % (i + 1));
+        }
+    }
+
+    fn soic_with_grid_pads() -> (Vec<FpText>, Vec<GraphicElement>, Vec<PadDescriptor>) {
+        let soic = Soic::from_jedec("MS-012-AC", 16).unwrap();
+        let mut pads = soic.pad_descriptors();
+        let template = pads[0].clone();
+        for (index, number) in NATURAL[16..].iter().enumerate() {
+            pads.push(PadDescriptor { number: (*number).into(), position: (index as f32, 8.0), uuid: Uuid::new_v4(), ..template.clone() });
+        }
+        let mut texts = soic.fp_text_elements();
+        texts.push(FpText { text: "rev B".to_string(), uuid: Uuid::new_v4(), ..texts[2].clone() });
+        (texts, soic.graphic_elements(), pads)
+    }
+
+    /// A footprint's elements captured once, so their uuids stay fixed between exports
+    #[derive(Clone)]
+    struct Captured {
+        source: Soic,
+        texts: Vec<FpText>,
+        graphics: Vec<GraphicElement>,
+        pads: Vec<PadDescriptor>,
+    }
+
+    impl BoardComposableObject for Captured {
+        fn is_smt(&self) -> bool {
+            true
+        }
+        fn is_electrical(&self) -> bool {
+            true
+        }
+        fn terminal_count(&self) -> usize {
+            self.pads.len()
+        }
+        fn functional_type(&self) -> FunctionalType {
+            self.source.functional_type()
+        }
+        fn footprint_name(&self) -> String {
+            self.source.footprint_name()
+        }
+        fn library_name(&self) -> String {
+            self.source.library_name()
+        }
+        fn bounding_box(&self) -> Rectangle {
+            self.source.bounding_box()
+        }
+        fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+            self.pads.clone()
+        }
+        fn fp_text_elements(&self) -> Vec<FpText> {
+            self.texts.clone()
+        }
+        fn graphic_elements(&self) -> Vec<GraphicElement> {
+            self.graphics.clone()
+        }
+        fn model_3d(&self) -> Option<Model3D> {
+            None
+        }
+    }
+
+    /// The export without the uuid and tstamp lines, which are new for the courtyard on each export
+    fn export(footprint: &Captured, options: &ExportOptions) -> String {
+        to_kicad_footprint_with_options(footprint, options)
+            .lines()
+            .filter(|line| !line.contains("(uuid ") && !line.contains("(tstamp "))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn pad_numbers(text: &str) -> Vec<&str> {
+        text.lines().filter_map(|line| line.trim().strip_prefix("(pad \"")?.split('"').next()).collect()
+    }
+
+    #[test]
+    fn natural_cmp_counts_like_a_person() {
+        for (a, b) in [("2", "10"), ("A2", "A10"), ("Y", "AA"), ("Y3", "AA1"), ("16", "A1"), ("A1", "EP"), ("01", "1")] {
+            assert_eq!(natural_cmp(a, b), Ordering::Less, "{} < {}", a, b);
+            assert_eq!(natural_cmp(b, a), Ordering::Greater, "{} > {}", b, a);
+        }
+        assert_eq!(natural_cmp("A10", "A10"), Ordering::Equal);
+        // Equal under the rules but still in a fixed order
+        assert_ne!(natural_cmp("a1", "A1"), Ordering::Equal);
+    }
+
+    #[test]
+    fn shuffled_pads_sort_into_natural_order() {
+        let (_, _, pads) = soic_with_grid_pads();
+        let mut seed = 7;
+        for _ in 0..5 {
+            let mut shuffled = pads.clone();
+            shuffle(&mut shuffled, &mut seed);
+            sort_pads(&mut shuffled);
+            let numbers: Vec<&str> = shuffled.iter().map(|pad| pad.number.as_str()).collect();
+            assert_eq!(numbers, NATURAL);
+        }
+    }
+
+    #[test]
+    fn shuffles_of_texts_and_graphics_sort_the_same() {
+        let (mut texts, mut graphics, _) = soic_with_grid_pads();
+        sort_texts(&mut texts);
+        sort_graphics(&mut graphics);
+        let text_ids: Vec<Uuid> = texts.iter().map(|text| text.uuid).collect();
+        let graphic_ids: Vec<Uuid> = graphics.iter().map(|graphic| graphic.uuid).collect();
+        assert!(matches!(texts[0].text_type, FpTextType::Reference) && matches!(texts[1].text_type, FpTextType::Value));
+        let mut seed = 11;
+        for _ in 0..5 {
+            shuffle(&mut texts, &mut seed);
+            shuffle(&mut graphics, &mut seed);
+            sort_texts(&mut texts);
+            sort_graphics(&mut graphics);
+            assert_eq!(texts.iter().map(|text| text.uuid).collect::<Vec<_>>(), text_ids);
+            assert_eq!(graphics.iter().map(|graphic| graphic.uuid).collect::<Vec<_>>(), graphic_ids);
+        }
+    }
+
+    #[test]
+    fn graphics_follow_kicad_layer_numbering() {
+        let (_, mut graphics, _) = soic_with_grid_pads();
+        sort_graphics(&mut graphics);
+        let layers: Vec<String> = graphics.iter().map(|graphic| graphic.kicad_layer()).collect();
+        let ranks: Vec<_> = layers.iter().map(|layer| layer_rank(layer).0).collect();
+        assert!(ranks.windows(2).all(|pair| pair[0] <= pair[1]), "{:?}", ranks);
+    }
+
+    #[test]
+    fn every_shuffle_exports_the_same_text() {
+        let (texts, graphics, pads) = soic_with_grid_pads();
+        let footprint = Captured { source: Soic::from_jedec("MS-012-AC", 16).unwrap(), texts, graphics, pads };
+        let options = ExportOptions::default();
+        let expected = export(&footprint, &options);
+        assert_eq!(pad_numbers(&expected), NATURAL);
+        let mut seed = 13;
+        for _ in 0..5 {
+            let mut shuffled = footprint.clone();
+            shuffle(&mut shuffled.texts, &mut seed);
+            shuffle(&mut shuffled.graphics, &mut seed);
+            shuffle(&mut shuffled.pads, &mut seed);
+            assert_eq!(export(&shuffled, &options), expected);
+        }
+    }
+
+    #[test]
+    fn without_the_canonical_order_the_footprint_order_is_written() {
+        let (texts, graphics, mut pads) = soic_with_grid_pads();
+        pads.reverse();
+        let footprint = Captured { source: Soic::from_jedec("MS-012-AC", 16).unwrap(), texts, graphics, pads };
+        let literal = ExportOptions { canonical_order: false, ..ExportOptions::default() };
+        let written = export(&footprint, &literal);
+        let numbers = pad_numbers(&written);
+        assert_eq!(numbers.first(), Some(&"EP"));
+        assert_eq!(numbers.last(), Some(&"1"));
+    }
+}
@@ -0,0 +1,268 @@
+//! Exporter plug-in interface
+//!
+//! Every output format implements `FootprintExporter` and/or `BoardExporter`. The built-in
+//! KiCad, SVG, DXF, JSON, CPL, BOM, stencil, silkscreen and copper exporters are registered by default, and
+//! third parties can register their own formats without forking the crate. Both traits are
+//! object safe, so exporters are stored and dispatched by name as `Box<dyn ...>`.
+
+use std::fmt;
+use std::io;
+
+use copper_substrate::board::Board;
+use copper_substrate::prelude::*;
+
+use crate::drill_export::{BackdrillBoardExporter, DrillBoardExporter, Plating};
+use crate::format::Resolution;
+use crate::kicad_format::{Indent, LineEnding, OutputStyle};
+use crate::kicad_pcb_export::KiCadVersion;
+use crate::layer_map::{LayerMap, LayerMapError};
+use crate::sexpr::SExprError;
+use crate::uuids::{UuidMode, UuidNamespace};
+
+/// Settings shared by all exporters; each exporter uses the fields relevant to it
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    /// Maximum deviation when arcs and round pads are written as line segments (mm)
+    pub arc_tolerance: f32,
+    /// Layer mapping override for non-KiCad formats, `None` uses the exporter default
+    pub layer_map: Option<LayerMap>,
+    /// Fold text on placed footprints into the readable angle range as KiCad does; turn off
+    /// to write the literal footprint plus text rotation
+    pub normalize_text_rotation: bool,
+    /// KiCad release footprint files are written for; check `version_warnings` for what
+    /// older releases lose
+    pub kicad_version: KiCadVersion,
+    /// Write footprint texts, graphics and pads in the canonical order of `element_order`;
+    /// turn off to write them in the order the footprint gives them
+    pub canonical_order: bool,
+    /// Layout of KiCad footprint and board files, see `kicad_format`
+    pub output_style: OutputStyle,
+    pub indent: Indent,
+    pub line_ending: LineEnding,
+    /// Grid every number of generated KiCad footprint and board files is rounded to, see
+    /// `format::quantize_numbers`
+    pub resolution: Resolution,
+    /// Element UUIDs of footprint files as given, or derived so output is reproducible,
+    /// see `uuids`
+    pub uuids: UuidMode,
+    /// Organization and library version deterministic UUIDs are derived in
+    pub uuid_namespace: UuidNamespace,
+    /// Solder mask expansion per side for pads without their own, for exports that draw
+    /// mask openings
+    pub mask_margin: f32,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            arc_tolerance: 0.01,
+            layer_map: None,
+            normalize_text_rotation: true,
+            kicad_version: KiCadVersion::default(),
+            canonical_order: true,
+            output_style: OutputStyle::default(),
+            indent: Indent::default(),
+            line_ending: LineEnding::default(),
+            resolution: Resolution::default(),
+            uuids: UuidMode::default(),
+            uuid_namespace: UuidNamespace::default(),
+            mask_margin: 0.0,
+        }
+    }
+}
+
+/// Largest board coordinate in millimeters f32 holds to a quarter micron: below 4096 mm
+/// its steps are at most 2^-12 mm. Placements are f64 and exempt, see
+/// `board::IntoBoardPosition`.
+pub const F32_SAFE_COORDINATE: f32 = 4096.0;
+
+/// Something an export had to approximate; the file is still written
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportWarning {
+    /// The target release has no padstacks, the pad is written with its front geometry on
+    /// every layer
+    PadStackCollapsed { pad: String, version: KiCadVersion },
+    /// A board coordinate kept in f32 lies beyond `F32_SAFE_COORDINATE`, where f32 can't
+    /// hold it to a quarter micron
+    CoordinateRange { item: String, value: f32 },
+    /// A coordinate's text doesn't read back as the value it was written from, or the
+    /// value isn't a finite number
+    PrecisionLoss { item: String, value: f64, written: String },
+}
+
+impl fmt::Display for ExportWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportWarning::PadStackCollapsed { pad, version } => {
+                write!(f, "pad {} has per-layer geometry, which {} can't read; written with its front copper on every layer", pad, version)
+            }
+            ExportWarning::CoordinateRange { item, value } => write!(
+                f,
+                "{} at {} mm is beyond ±{} mm, where f32 can't hold it to a quarter micron",
+                item, value, F32_SAFE_COORDINATE
+            ),
+            ExportWarning::PrecisionLoss { item, value, written } => {
+                write!(f, "{} is {} but written as \"{}\", which doesn't read back the same", item, value, written)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ExportError {
+    Io(io::Error),
+    LayerMap(LayerMapError),
+    /// A pad or text uses a layer name KiCad doesn't know, e.g. "F.paste"
+    InvalidLayer { item: String, layer: String },
+    /// A surface mount pad with layers on both sides, e.g. F.Cu with B.Mask
+    MixedSides { item: String, layers: Vec<String> },
+    /// No exporter is registered under this format name
+    UnknownFormat(String),
+    /// The written text didn't read back for re-layout in the requested output style
+    OutputStyle(SExprError),
+    /// Exporter specific failure
+    Other(String),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::Io(e) => write!(f, "I/O error: {}", e),
+            ExportError::LayerMap(e) => write!(f, "{}", e),
+            ExportError::InvalidLayer { item, layer } => write!(f, "{} is on unknown layer \"{}\"", item, layer),
+            ExportError::MixedSides { item, layers } => {
+                write!(f, "surface mount {} has layers on both sides ({})", item, layers.join(" "))
+            }
+            ExportError::UnknownFormat(name) => write!(f, "no exporter registered for format \"{}\"", name),
+            ExportError::OutputStyle(e) => write!(f, "written file could not be laid out: {}", e),
+            ExportError::Other(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExportError::Io(e) => Some(e),
+            ExportError::LayerMap(e) => Some(e),
+            ExportError::OutputStyle(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ExportError {
+    fn from(e: io::Error) -> Self {
+        ExportError::Io(e)
+    }
+}
+
+impl From<LayerMapError> for ExportError {
+    fn from(e: LayerMapError) -> Self {
+        ExportError::LayerMap(e)
+    }
+}
+
+impl From<SExprError> for ExportError {
+    fn from(e: SExprError) -> Self {
+        ExportError::OutputStyle(e)
+    }
+}
+
+/// Writes a single footprint in some file format
+pub trait FootprintExporter {
+    /// Format name used for dispatch, e.g. "kicad" or "mycorp"
+    fn name(&self) -> &str;
+    /// File extension without the dot, e.g. "kicad_mod"
+    fn file_extension(&self) -> &str;
+    /// Directory a whole library is written into, e.g. "Resistor_SMD.pretty" for KiCad
+    fn library_dir_name(&self, library: &str) -> String {
+        format!("{}.{}", library, self.name())
+    }
+    fn export(&self, component: &dyn BoardComposableObject, options: &ExportOptions) -> Result<Vec<u8>, ExportError>;
+}
+
+/// Writes a complete board in some file format
+pub trait BoardExporter {
+    fn name(&self) -> &str;
+    fn file_extension(&self) -> &str;
+    fn export(&self, board: &Board, options: &ExportOptions) -> Result<Vec<u8>, ExportError>;
+}
+
+/// Exporters available by format name
+pub struct ExporterRegistry {
+    footprint: Vec<Box<dyn FootprintExporter>>,
+    board: Vec<Box<dyn BoardExporter>>,
+}
+
+impl ExporterRegistry {
+    /// A registry with no exporters at all
+    pub fn empty() -> Self {
+        Self { footprint: Vec::new(), board: Vec::new() }
+    }
+
+    /// The built-in KiCad, SVG, DXF, JSON, CPL, BOM, stencil, mask, silkscreen, copper,
+    /// drill and backdrill exporters
+    pub fn with_builtin() -> Self {
+        let mut registry = Self::empty();
+        registry.register_footprint_exporter(Box::new(crate::kicad_pcb_export::KiCadFootprintExporter));
+        registry.register_footprint_exporter(Box::new(crate::svg_export::SvgFootprintExporter));
+        registry.register_footprint_exporter(Box::new(crate::dxf_export::DxfFootprintExporter));
+        registry.register_footprint_exporter(Box::new(crate::json_export::JsonFootprintExporter));
+        registry.register_board_exporter(Box::new(crate::kicad_board_export::KiCadBoardExporter));
+        registry.register_board_exporter(Box::new(crate::json_export::JsonBoardExporter));
+        registry.register_board_exporter(Box::new(crate::cpl_export::CplBoardExporter::default()));
+        registry.register_board_exporter(Box::new(crate::bom_export::BomBoardExporter));
+        registry.register_board_exporter(Box::new(crate::stencil_export::StencilBoardExporter::default()));
+        registry.register_board_exporter(Box::new(crate::mask_export::MaskBoardExporter::default()));
+        registry.register_board_exporter(Box::new(crate::silk_export::SilkBoardExporter::default()));
+        registry.register_board_exporter(Box::new(crate::gerber_export::CopperBoardExporter::default()));
+        registry.register_board_exporter(Box::new(DrillBoardExporter::new(Plating::Plated)));
+        registry.register_board_exporter(Box::new(DrillBoardExporter::new(Plating::NonPlated)));
+        registry.register_board_exporter(Box::new(BackdrillBoardExporter::new(Side::Front)));
+        registry.register_board_exporter(Box::new(BackdrillBoardExporter::new(Side::Back)));
+        registry
+    }
+
+    /// Register a footprint exporter, replacing any existing one with the same name
+    pub fn register_footprint_exporter(&mut self, exporter: Box<dyn FootprintExporter>) {
+        self.footprint.retain(|existing| existing.name() != exporter.name());
+        self.footprint.push(exporter);
+    }
+
+    /// Register a board exporter, replacing any existing one with the same name
+    pub fn register_board_exporter(&mut self, exporter: Box<dyn BoardExporter>) {
+        self.board.retain(|existing| existing.name() != exporter.name());
+        self.board.push(exporter);
+    }
+
+    pub fn footprint_exporter(&self, name: &str) -> Result<&dyn FootprintExporter, ExportError> {
+        self.footprint
+            .iter()
+            .find(|exporter| exporter.name() == name)
+            .map(|exporter| exporter.as_ref())
+            .ok_or_else(|| ExportError::UnknownFormat(name.to_string()))
+    }
+
+    pub fn board_exporter(&self, name: &str) -> Result<&dyn BoardExporter, ExportError> {
+        self.board
+            .iter()
+            .find(|exporter| exporter.name() == name)
+            .map(|exporter| exporter.as_ref())
+            .ok_or_else(|| ExportError::UnknownFormat(name.to_string()))
+    }
+
+    pub fn footprint_formats(&self) -> impl Iterator<Item = &str> {
+        self.footprint.iter().map(|exporter| exporter.name())
+    }
+
+    pub fn board_formats(&self) -> impl Iterator<Item = &str> {
+        self.board.iter().map(|exporter| exporter.name())
+    }
+}
+
+impl Default for ExporterRegistry {
+    fn default() -> Self {
+        Self::with_builtin()
+    }
+}
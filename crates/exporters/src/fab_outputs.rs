@@ -0,0 +1,126 @@
+//! Fabrication outputs of a board in one directory
+//!
+//! `fab_outputs` lists the files a board goes to the fab and the assembly house with, each
+//! as a file name and the exporter writing it:
+//!
+//! - the `.kicad_pcb` board file
+//! - a Gerber per copper layer, and the mask, paste and silkscreen Gerbers of both sides
+//! - the plated and the non-plated drill files, plus a backdrill file for each side vias
+//!   are backdrilled from
+//! - the bill of materials and the placement list, KiCad's CSV layouts
+//!
+//! Files are named after the board as KiCad's plotter names them, e.g. "demo.kicad_pcb",
+//! "demo-F_Cu.gtl", "demo-B_Mask.gbs", "demo-PTH.drl" and "demo-bom.csv"; boards without a
+//! name are called "board". Every Gerber, drill and placement file measures from the same
+//! `FabOrigin`, so they line up. `write_fab_outputs` writes the list into a directory,
+//! creating it when needed, and fails on the first exporter or I/O error.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use copper_substrate::board::{Board, FabOrigin};
+use copper_substrate::prelude::*;
+
+use crate::bom_export::BomBoardExporter;
+use crate::cpl_export::{CplBoardExporter, CplPreset};
+use crate::drill_export::{BackdrillBoardExporter, DrillBoardExporter, Plating};
+use crate::exporter::{BoardExporter, ExportError, ExportOptions};
+use crate::gerber_export::CopperBoardExporter;
+use crate::kicad_board_export::KiCadBoardExporter;
+use crate::mask_export::MaskBoardExporter;
+use crate::silk_export::SilkBoardExporter;
+use crate::stencil_export::StencilBoardExporter;
+
+/// One file of the fabrication outputs
+pub struct FabOutput {
+    pub file_name: String,
+    pub exporter: Box<dyn BoardExporter>,
+}
+
+/// "F.Cu" as it appears in file names, "F_Cu"
+fn layer_file_name(layer: &BoardLayer) -> String {
+    layer.to_kicad_string().replace('.', "_")
+}
+
+/// The files of `board`'s fabrication outputs, see the module documentation
+pub fn fab_outputs(board: &Board, origin: FabOrigin) -> Vec<FabOutput> {
+    let name = if board.name.is_empty() { "board" } else { board.name.as_str() };
+    let mut outputs: Vec<(String, Box<dyn BoardExporter>)> = vec![("".to_string(), Box::new(KiCadBoardExporter))];
+    for layer in board.copper_layers() {
+        outputs.push((format!("-{}", layer_file_name(&layer)), Box::new(CopperBoardExporter::new(layer).with_origin(origin))));
+    }
+    for side in [Side::Front, Side::Back] {
+        let mask = BoardLayer::new(LayerType::Mask, side);
+        outputs.push((format!("-{}", layer_file_name(&mask)), Box::new(MaskBoardExporter::new(side).with_origin(origin))));
+        let paste = BoardLayer::new(LayerType::Paste, side);
+        outputs.push((format!("-{}", layer_file_name(&paste)), Box::new(StencilBoardExporter::new(side).with_origin(origin))));
+        let silk = BoardLayer::new(LayerType::SilkScreen, side);
+        outputs.push((format!("-{}", layer_file_name(&silk)), Box::new(SilkBoardExporter::new(side).with_origin(origin))));
+    }
+    outputs.push(("-PTH".to_string(), Box::new(DrillBoardExporter::new(Plating::Plated).with_origin(origin))));
+    outputs.push(("-NPTH".to_string(), Box::new(DrillBoardExporter::new(Plating::NonPlated).with_origin(origin))));
+    for (side, suffix) in [(Side::Front, "-backdrill-front"), (Side::Back, "-backdrill-back")] {
+        if board.vias().iter().any(|via| via.backdrill.as_ref().is_some_and(|backdrill| backdrill.side == side)) {
+            outputs.push((suffix.to_string(), Box::new(BackdrillBoardExporter::new(side).with_origin(origin))));
+        }
+    }
+    outputs.push(("-bom".to_string(), Box::new(BomBoardExporter)));
+    outputs.push(("-cpl".to_string(), Box::new(CplBoardExporter::new(CplPreset::KiCad).with_origin(origin))));
+    outputs
+        .into_iter()
+        .map(|(suffix, exporter)| FabOutput { file_name: format!("{}{}.{}", name, suffix, exporter.file_extension()), exporter })
+        .collect()
+}
+
+/// Write every file of `fab_outputs` into `dir`, returning their paths in list order
+pub fn write_fab_outputs(board: &Board, dir: &Path, origin: FabOrigin, options: &ExportOptions) -> Result<Vec<PathBuf>, ExportError> {
+    fs::create_dir_all(dir)?;
+    let mut written = Vec::new();
+    for output in fab_outputs(board, origin) {
+        let path = dir.join(&output.file_name);
+        fs::write(&path, output.exporter.export(board, options)?)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use copper_substrate::backdrill::Backdrill;
+    use copper_substrate::presets;
+
+    use super::*;
+
+    #[test]
+    fn files_are_named_after_the_board_and_layer() {
+        let mut board = Board::new("demo").with_rectangular_outline(20.0, 10.0).with_stackup(Stackup::four_layer_default());
+        board.place("R1", presets::resistor_0603("10k"), (5.0, 5.0), 0.0).unwrap();
+        let names = |board: &Board| fab_outputs(board, FabOrigin::Absolute).into_iter().map(|output| output.file_name).collect::<Vec<_>>();
+        assert_eq!(
+            names(&board),
+            [
+                "demo.kicad_pcb",
+                "demo-F_Cu.gtl",
+                "demo-In1_Cu.gbr",
+                "demo-In2_Cu.gbr",
+                "demo-B_Cu.gbl",
+                "demo-F_Mask.gts",
+                "demo-F_Paste.gtp",
+                "demo-F_SilkS.gto",
+                "demo-B_Mask.gbs",
+                "demo-B_Paste.gbp",
+                "demo-B_SilkS.gbo",
+                "demo-PTH.drl",
+                "demo-NPTH.drl",
+                "demo-bom.csv",
+                "demo-cpl.csv",
+            ]
+        );
+        let via = Via::new("SIG", Point::new(10.0, 5.0), 0.6, 0.3)
+            .with_backdrill(Backdrill::new(Side::Back, BoardLayer::new(LayerType::Copper, Side::Inner(1)), 0.5));
+        board.add_via(via);
+        board.name.clear();
+        let names = names(&board);
+        assert!(names.contains(&"board-backdrill-back.drl".to_string()) && !names.iter().any(|name| name.contains("backdrill-front")), "{:?}", names);
+    }
+}
@@ -0,0 +1,353 @@
+//! Footprint definitions in TOML
+//!
+//! A definition file names one of the built-in generators and its parameters, so footprints
+//! can be designed by editing a text file instead of recompiling:
+//!
+//! ```toml
+//! generator = "chip"
+//! type = "resistor"
+//! value = "10k"
+//! size = "0603"
+//!
+//! [fillets]
+//! toe = 0.4
+//! ```
+//!
+//! Chips take either a standard `size` or `body_length`, `body_width` and
+//! `terminal_length`, and optionally a `pin1` marker ("dot", "chamfer", "line",
+//! "triangle" or "none"). Stencil markers use `generator = "stencil_fiducials"` with
+//! `diameter` and `spacing`, or `generator = "stencil_target"` with `size` and
+//! `line_width`. QFNs use `generator = "qfn"` with an `outline` code from the built-in
+//! outline library (e.g. "MO-220-4x4-24") and `pins`, or `pins`, `pitch`, `body_length`
+//! and `body_width`, and optionally `terminal_length`, `terminal_width`, `pad_length`,
+//! `exposed_pad = [x, y]` (or `[0, 0]` for none), `fillets` and `pin1`. Parameters are
+//! validated before anything is generated. `sweep` builds families of footprints from
+//! the same files.
+//!
+//! `generator = "part"` leaves the choice of generator to `resolve`: a `type` (any
+//! functional type by name, e.g. "resistor" or "mcu"), `value`, a `package` such as
+//! "0603", "SOIC-8" or "TO-252-3", and optionally `hand_solder`, `density` ("least",
+//! "nominal" or "most") and `thermal_vias`, which puts the default via grid in the pad
+//! and checks it against the standard fab profile.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use copper_substrate::prelude::*;
+use copper_substrate::stencil::STENCIL_FIDUCIAL_DIAMETER;
+use copper_substrate::thermal_vias::ThermalViaOptions;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DefinitionError {
+    Io(String),
+    Parse(String),
+    /// `type` names no functional type a chip can have
+    UnknownType(String),
+    /// `size` isn't one of the standard chip sizes
+    UnknownSize(String),
+    /// A parameter is missing, not positive, or inconsistent with another
+    Invalid(String),
+}
+
+impl fmt::Display for DefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DefinitionError::Io(message) => write!(f, "cannot read definition: {}", message),
+            DefinitionError::Parse(message) => write!(f, "invalid definition: {}", message),
+            DefinitionError::UnknownType(name) => write!(f, "unknown chip type \"{}\"", name),
+            DefinitionError::UnknownSize(code) => write!(f, "unknown chip size \"{}\"", code),
+            DefinitionError::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for DefinitionError {}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "generator", rename_all = "snake_case", deny_unknown_fields)]
+enum Definition {
+    Chip {
+        #[serde(rename = "type")]
+        chip_type: String,
+        #[serde(default)]
+        value: String,
+        size: Option<String>,
+        body_length: Option<f32>,
+        body_width: Option<f32>,
+        terminal_length: Option<f32>,
+        fillets: Option<FilletsDefinition>,
+        pin1: Option<Pin1Style>,
+    },
+    Qfn {
+        outline: Option<String>,
+        pins: usize,
+        pitch: Option<f32>,
+        body_length: Option<f32>,
+        body_width: Option<f32>,
+        terminal_length: Option<f32>,
+        terminal_width: Option<f32>,
+        pad_length: Option<f32>,
+        exposed_pad: Option<(f32, f32)>,
+        fillets: Option<FilletsDefinition>,
+        pin1: Option<Pin1Style>,
+    },
+    Part {
+        #[serde(rename = "type")]
+        part_type: String,
+        #[serde(default)]
+        value: String,
+        package: String,
+        #[serde(default)]
+        hand_solder: bool,
+        density: Option<String>,
+        #[serde(default)]
+        thermal_vias: bool,
+    },
+    StencilFiducials {
+        #[serde(default = "default_fiducial_diameter")]
+        diameter: f32,
+        spacing: f32,
+    },
+    StencilTarget {
+        size: f32,
+        #[serde(default = "default_target_line_width")]
+        line_width: f32,
+    },
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FilletsDefinition {
+    toe: Option<f32>,
+    heel: Option<f32>,
+    side: Option<f32>,
+}
+
+fn default_fiducial_diameter() -> f32 {
+    STENCIL_FIDUCIAL_DIAMETER
+}
+
+fn default_target_line_width() -> f32 {
+    0.2
+}
+
+fn positive(name: &str, value: f32) -> Result<f32, DefinitionError> {
+    if value.is_finite() && value > 0.0 {
+        Ok(value)
+    } else {
+        Err(DefinitionError::Invalid(format!("{} must be positive, got {}", name, value)))
+    }
+}
+
+fn chip_type(name: &str, value: String) -> Result<FunctionalType, DefinitionError> {
+    match name.to_ascii_lowercase().as_str() {
+        "resistor" => Ok(FunctionalType::Resistor(value)),
+        "capacitor" => Ok(FunctionalType::Capacitor(value)),
+        "inductor" => Ok(FunctionalType::Inductor(value)),
+        "led" => Ok(FunctionalType::LED(value)),
+        "fuse" => Ok(FunctionalType::Fuse(value)),
+        "protection" => Ok(FunctionalType::Protection(value)),
+        _ => Err(DefinitionError::UnknownType(name.to_string())),
+    }
+}
+
+impl FilletsDefinition {
+    fn or(self, defaults: ChipFillets) -> ChipFillets {
+        ChipFillets {
+            toe: self.toe.unwrap_or(defaults.toe),
+            heel: self.heel.unwrap_or(defaults.heel),
+            side: self.side.unwrap_or(defaults.side),
+        }
+    }
+}
+
+/// Parse and validate a definition and build its footprint
+pub fn parse_definition(text: &str) -> Result<Box<dyn BoardComposableObject>, DefinitionError> {
+    let definition: Definition = toml::from_str(text).map_err(|e| DefinitionError::Parse(e.to_string().trim_end().to_string()))?;
+    build(definition)
+}
+
+/// Validate a definition already read into a TOML table and build its footprint
+pub(crate) fn definition_from_table(table: toml::Table) -> Result<Box<dyn BoardComposableObject>, DefinitionError> {
+    let definition: Definition =
+        toml::Value::Table(table).try_into().map_err(|e: toml::de::Error| DefinitionError::Parse(e.to_string().trim_end().to_string()))?;
+    build(definition)
+}
+
+fn build(definition: Definition) -> Result<Box<dyn BoardComposableObject>, DefinitionError> {
+    match definition {
+        Definition::Chip { chip_type: name, value, size, body_length, body_width, terminal_length, fillets, pin1 } => {
+            let functional_type = chip_type(&name, value)?;
+            let mut chip = match (size, body_length, body_width, terminal_length) {
+                (Some(code), None, None, None) => {
+                    ChipFootprint::standard(functional_type, &code).ok_or(DefinitionError::UnknownSize(code))?
+                }
+                (None, Some(length), Some(width), Some(terminal)) => ChipFootprint::new(
+                    functional_type,
+                    positive("body_length", length)?,
+                    positive("body_width", width)?,
+                    positive("terminal_length", terminal)?,
+                ),
+                _ => {
+                    return Err(DefinitionError::Invalid(
+                        "a chip needs either size or all of body_length, body_width and terminal_length".to_string(),
+                    ));
+                }
+            };
+            if 2.0 * chip.terminal_length >= chip.body_length {
+                return Err(DefinitionError::Invalid(format!(
+                    "terminals of {} mm leave no gap on a {} mm body",
+                    chip.terminal_length, chip.body_length
+                )));
+            }
+            chip.fillets = fillets.unwrap_or_default().or(chip.fillets);
+            chip.pin1 = pin1.unwrap_or(chip.pin1);
+            let ((pad_x, pad_y), _) = chip.land_pattern();
+            if pad_x <= 0.0 || pad_y <= 0.0 {
+                return Err(DefinitionError::Invalid(format!("fillets give an empty pad ({} x {} mm)", pad_x, pad_y)));
+            }
+            Ok(Box::new(chip))
+        }
+        Definition::Qfn {
+            outline,
+            pins,
+            pitch,
+            body_length,
+            body_width,
+            terminal_length,
+            terminal_width,
+            pad_length,
+            exposed_pad,
+            fillets,
+            pin1,
+        } => {
+            if pins == 0 || pins % 4 != 0 {
+                return Err(DefinitionError::Invalid(format!("a QFN needs a multiple of four pins, got {}", pins)));
+            }
+            let mut qfn = match (outline, pitch, body_length, body_width) {
+                (Some(code), None, None, None) => {
+                    Qfn::from_jedec(&code, pins).map_err(|e| DefinitionError::Invalid(e.to_string()))?
+                }
+                (None, Some(pitch), Some(length), Some(width)) => Qfn::new(
+                    pins,
+                    positive("pitch", pitch)?,
+                    positive("body_length", length)?,
+                    positive("body_width", width)?,
+                ),
+                _ => {
+                    return Err(DefinitionError::Invalid(
+                        "a QFN needs either outline or all of pitch, body_length and body_width".to_string(),
+                    ));
+                }
+            };
+            if terminal_length.is_some() || terminal_width.is_some() {
+                let length = positive("terminal_length", terminal_length.unwrap_or(qfn.terminal_length.nominal))?;
+                let width = positive("terminal_width", terminal_width.unwrap_or(qfn.terminal_width.nominal))?;
+                qfn = qfn.with_terminals(length, width);
+            }
+            if let Some(length) = pad_length {
+                qfn = qfn.with_pad_length(positive("pad_length", length)?);
+            }
+            match exposed_pad {
+                Some((0.0, 0.0)) => qfn.exposed_pad = None,
+                Some((x, y)) => qfn.exposed_pad = Some((positive("exposed_pad", x)?, positive("exposed_pad", y)?)),
+                None => {}
+            }
+            qfn.fillets = fillets.unwrap_or_default().or(qfn.fillets);
+            qfn.pin1 = pin1.unwrap_or(qfn.pin1);
+            let ((pad_length, pad_width), center) = qfn.land_pattern(qfn.body_width.min(qfn.body_length));
+            if pad_length <= 0.0 || pad_width <= 0.0 {
+                return Err(DefinitionError::Invalid(format!("fillets give an empty pad ({} x {} mm)", pad_length, pad_width)));
+            }
+            if pad_width >= qfn.pitch.nominal {
+                return Err(DefinitionError::Invalid(format!("{} mm wide pads overlap at {} mm pitch", pad_width, qfn.pitch.nominal)));
+            }
+            if let Some((x, y)) = qfn.exposed_pad
+                && center - pad_length / 2.0 <= x.max(y) / 2.0
+            {
+                return Err(DefinitionError::Invalid(format!(
+                    "{} mm pads reach the {} x {} mm exposed pad",
+                    pad_length, x, y
+                )));
+            }
+            Ok(Box::new(qfn))
+        }
+        Definition::Part { part_type, value, package, hand_solder, density, thermal_vias } => {
+            let functional_type = FunctionalType::from_kind_name(&part_type, &value)
+                .ok_or_else(|| DefinitionError::Invalid(format!("unknown part type \"{}\"", part_type)))?;
+            let mut options = ResolveOptions::default();
+            if hand_solder {
+                options = options.with_hand_solder();
+            }
+            options.density = match density.as_deref().map(str::to_ascii_lowercase).as_deref() {
+                None | Some("nominal") => Density::Nominal,
+                Some("least") => Density::Least,
+                Some("most") => Density::Most,
+                Some(other) => {
+                    return Err(DefinitionError::Invalid(format!("density must be least, nominal or most, got \"{}\"", other)));
+                }
+            };
+            if thermal_vias {
+                options = options.with_thermal_vias(ThermalViaOptions::default(), FabProfile::standard());
+            }
+            resolve(&functional_type, &Package::parse(&package), &options).map_err(|e| DefinitionError::Invalid(e.to_string()))
+        }
+        Definition::StencilFiducials { diameter, spacing } => {
+            let diameter = positive("diameter", diameter)?;
+            if spacing <= diameter {
+                return Err(DefinitionError::Invalid(format!(
+                    "spacing {} mm must exceed the {} mm diameter",
+                    spacing, diameter
+                )));
+            }
+            Ok(Box::new(StencilFiducialPair::new(diameter, spacing)))
+        }
+        Definition::StencilTarget { size, line_width } => {
+            let size = positive("size", size)?;
+            let line_width = positive("line_width", line_width)?;
+            Ok(Box::new(StencilTarget::new(size, line_width)))
+        }
+    }
+}
+
+/// Read and parse the definition file at `path`
+pub fn read_definition(path: &Path) -> Result<Box<dyn BoardComposableObject>, DefinitionError> {
+    let text = fs::read_to_string(path).map_err(|e| DefinitionError::Io(e.to_string()))?;
+    parse_definition(&text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parts_resolve_to_a_generator() {
+        let footprint = parse_definition(
+            r#"
+            generator = "part"
+            type = "resistor"
+            value = "10k"
+            package = "0603"
+            hand_solder = true
+            "#,
+        )
+        .unwrap();
+        assert_eq!(footprint.footprint_name(), "R_0603_1608Metric_Pad0.85x0.95mm_HandSolder");
+        assert_eq!(footprint.functional_type(), FunctionalType::Resistor("10k".to_string()));
+
+        let regulator = "generator = \"part\"\ntype = \"IntegratedCircuit\"\npackage = \"TO-252-3\"\nthermal_vias = true";
+        assert_eq!(parse_definition(regulator).unwrap().footprint_name(), "TO-252-3_TabPin2_ThermalVias");
+    }
+
+    #[test]
+    fn parts_that_cannot_be_built_name_the_alternatives() {
+        let error = |text: &str| parse_definition(text).err().map(|error| error.to_string()).unwrap_or_default();
+        let qfn = "generator = \"part\"\ntype = \"mcu\"\npackage = \"QFN-24\"\nhand_solder = true";
+        assert!(error(qfn).contains("closest: SOIC-24"), "{}", error(qfn));
+        assert!(error("generator = \"part\"\ntype = \"resistor\"\npackage = \"0604\"").contains("0603"));
+        assert!(error("generator = \"part\"\ntype = \"widget\"\npackage = \"0603\"").contains("unknown part type"));
+        assert!(error("generator = \"part\"\ntype = \"resistor\"\npackage = \"0603\"\ndensity = \"high\"").contains("density"));
+    }
+}
@@ -0,0 +1,424 @@
+//! Importing vendor downloaded footprints
+//!
+//! Footprints downloaded from SnapEDA, Ultra Librarian and similar sites place correctly
+//! but rarely follow the KiCad Library Conventions (KLC): line widths are whatever the
+//! vendor tool used, courtyards are missing or don't enclose the pads, many files still use
+//! KiCad 5 syntax and some spell layers with the names KiCad shows in its UI
+//! ("F.Silkscreen"). `import_footprint` reads such a file, KiCad 5 forms included, and
+//! normalizes it:
+//!
+//! - layer names are rewritten to the names KiCad writes in files, e.g. "F.SilkS"
+//! - silkscreen, fab and courtyard lines get the KLC widths
+//! - a front courtyard that is missing, open or doesn't enclose the pads and the fab
+//!   outline is replaced by a rectangle around them, rounded out to the 0.01 mm grid
+//!
+//! Edits are spliced into the source the way `KiCadFootprint` writes pad edits, so
+//! everything else stays as the vendor wrote it, and every edit is reported.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+use copper_substrate::layer_type::canonical_kicad_layer_name;
+use copper_substrate::prelude::*;
+use uuid::Uuid;
+
+use crate::kicad_footprint_parse::{FootprintParseError, KiCadFootprint, ParseOptions};
+use crate::sexpr::{self, quote, SExpr, SpanTree};
+
+/// Graphic items whose outline counts towards the courtyard and whose width is checked
+const GRAPHIC_TOKENS: &[&str] = &["fp_line", "fp_rect", "fp_circle", "fp_arc", "fp_poly"];
+
+/// Endpoints closer than this (mm) join when checking that a courtyard is closed
+const JOIN_TOLERANCE: f32 = 0.001;
+
+/// KLC line widths and courtyard rules applied by `import_footprint`
+#[derive(Debug, Clone, PartialEq)]
+pub struct NormalizeOptions {
+    pub silk_width: f32,
+    pub fab_width: f32,
+    pub courtyard_width: f32,
+    /// Clearance from the pads and fab outline to a regenerated courtyard
+    pub courtyard_margin: f32,
+    /// Grid the corners of a regenerated courtyard are rounded out to
+    pub courtyard_grid: f32,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        Self { silk_width: 0.12, fab_width: 0.1, courtyard_width: 0.05, courtyard_margin: 0.25, courtyard_grid: 0.01 }
+    }
+}
+
+impl NormalizeOptions {
+    /// KLC uses 0.5 mm around connectors and BGAs and 0.15 mm around parts below 0603
+    pub fn with_courtyard_margin(mut self, margin: f32) -> Self {
+        self.courtyard_margin = margin;
+        self
+    }
+}
+
+/// Why the front courtyard was regenerated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CourtyardIssue {
+    Missing,
+    /// The courtyard lines don't form closed outlines
+    Open,
+    /// The courtyard doesn't enclose every pad and the fab outline
+    TooSmall,
+}
+
+impl fmt::Display for CourtyardIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CourtyardIssue::Missing => write!(f, "missing"),
+            CourtyardIssue::Open => write!(f, "not closed"),
+            CourtyardIssue::TooSmall => write!(f, "doesn't enclose the pads and fab outline"),
+        }
+    }
+}
+
+/// One edit made by `import_footprint`
+#[derive(Debug, Clone)]
+pub enum NormalizeChange {
+    /// Every occurrence of layer `from` was renamed
+    LayerRenamed { from: String, to: String, count: usize },
+    /// A graphic item's line width, `offset` being the item's byte offset in the source
+    LineWidth { item: String, layer: String, offset: usize, from: f32, to: f32 },
+    /// The front courtyard was replaced by a rectangle; `removed` counts the old items
+    CourtyardRegenerated { issue: CourtyardIssue, removed: usize, bounds: Rectangle },
+}
+
+impl fmt::Display for NormalizeChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NormalizeChange::LayerRenamed { from, to, count } => {
+                write!(f, "renamed layer {} to {} ({} occurrences)", from, to, count)
+            }
+            NormalizeChange::LineWidth { item, layer, offset, from, to } => {
+                write!(f, "{} on {} at byte {}: width {} -> {}", item, layer, offset, from, to)
+            }
+            NormalizeChange::CourtyardRegenerated { issue, removed, bounds } => {
+                let corners = format!("({}, {}) - ({}, {})", bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y);
+                match issue {
+                    CourtyardIssue::Missing => write!(f, "courtyard missing, added {}", corners),
+                    _ => write!(f, "courtyard {}, replaced {} items with {}", issue, removed, corners),
+                }
+            }
+        }
+    }
+}
+
+/// A normalized footprint and the edits that made it
+#[derive(Debug, Clone)]
+pub struct ImportedFootprint {
+    pub footprint: KiCadFootprint,
+    pub changes: Vec<NormalizeChange>,
+}
+
+/// Read and normalize a vendor footprint, see the module documentation. Tokens the
+/// reader doesn't model are kept and listed by the footprint's `unknown_tokens`.
+pub fn import_footprint(text: &str, options: &NormalizeOptions) -> Result<ImportedFootprint, FootprintParseError> {
+    let (root, spans) = sexpr::parse_with_spans(text)?;
+    if !matches!(root.head(), Some("footprint") | Some("module")) {
+        return Err(FootprintParseError::NotAFootprint);
+    }
+    let pads = KiCadFootprint::parse(text, ParseOptions::default())?.pad_descriptors();
+    let items: Vec<(&SExpr, &SpanTree)> = root.children().iter().zip(&spans.items).skip(2).collect();
+    let layer_of = |expr: &SExpr| expr.value_of("layer").map(|name| canonical_kicad_layer_name(name).unwrap_or(name.to_string()));
+    let is_graphic = |expr: &SExpr| expr.head().is_some_and(|head| GRAPHIC_TOKENS.contains(&head));
+
+    // The front courtyard stays unless it is missing, open or too small
+    let courtyard: Vec<usize> = (0..items.len())
+        .filter(|&i| is_graphic(items[i].0) && layer_of(items[i].0).as_deref() == Some("F.CrtYd"))
+        .collect();
+    let mut required: Vec<Point> = pads
+        .iter()
+        .filter(|pad| pad.side() != Some(PadSide::Back))
+        .map(|pad| pad.outline_aabb())
+        .flat_map(|bounds| [Point::new(bounds.min_x, bounds.min_y), Point::new(bounds.max_x, bounds.max_y)])
+        .collect();
+    for (expr, _) in items.iter().filter(|(expr, _)| is_graphic(expr) && layer_of(expr).as_deref() == Some("F.Fab")) {
+        required.extend(outline_points(expr));
+    }
+    let issue = courtyard_issue(courtyard.iter().map(|&i| items[i].0), Rectangle::enclosing(&required));
+    let removed: Vec<usize> = if issue.is_some() { courtyard.clone() } else { Vec::new() };
+
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+    let mut changes = Vec::new();
+    let mut renames: BTreeMap<(String, String), usize> = BTreeMap::new();
+    for (index, (expr, span)) in items.iter().enumerate() {
+        if removed.contains(&index) {
+            edits.push(remove(text, span.span.clone()));
+            continue;
+        }
+        rename_layers(expr, span, &mut edits, &mut renames);
+        if is_graphic(expr)
+            && let Some(layer) = layer_of(expr)
+            && let Some(edit) = width_edit(expr, span, &layer, options)
+        {
+            changes.push(NormalizeChange::LineWidth {
+                item: expr.head().unwrap_or_default().to_string(),
+                layer,
+                offset: span.span.start,
+                from: edit.1,
+                to: edit.2,
+            });
+            edits.push(edit.0);
+        }
+    }
+    changes.splice(
+        0..0,
+        renames.into_iter().map(|((from, to), count)| NormalizeChange::LayerRenamed { from, to, count }),
+    );
+
+    if let Some(issue) = issue
+        && let Some(required) = Rectangle::enclosing(&required)
+    {
+        let bounds = courtyard_bounds(&required, options);
+        // After the last item ahead of the pads that stays
+        let first_pad = items.iter().position(|(expr, _)| expr.head() == Some("pad")).unwrap_or(items.len());
+        let anchor = (0..first_pad).rev().find(|i| !removed.contains(i)).map_or(spans.items[1].span.clone(), |i| items[i].1.span.clone());
+        let style = LineStyle::of(&items);
+        let lines = courtyard_lines(&bounds, options.courtyard_width, style);
+        let separator = &text[text[..anchor.start].trim_end().len()..anchor.start];
+        let inserted: String = lines.iter().map(|line| format!("{}{}", separator, line)).collect();
+        edits.push((anchor.end..anchor.end, inserted));
+        changes.push(NormalizeChange::CourtyardRegenerated { issue, removed: removed.len(), bounds });
+    }
+
+    edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+    let mut normalized = text.to_string();
+    for (range, replacement) in edits {
+        normalized.replace_range(range, &replacement);
+    }
+    let footprint = KiCadFootprint::parse(&normalized, ParseOptions::default())?;
+    Ok(ImportedFootprint { footprint, changes })
+}
+
+/// Import a `.kicad_mod` file, taking the library name from an enclosing `<name>.pretty`
+pub fn import_footprint_file(path: &Path, options: &NormalizeOptions) -> io::Result<ImportedFootprint> {
+    let text = fs::read_to_string(path)?;
+    let mut imported = import_footprint(&text, options).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if let Some(library) = path.parent().and_then(Path::file_name).and_then(|dir| dir.to_str()?.strip_suffix(".pretty")) {
+        imported.footprint = imported.footprint.with_library_name(library);
+    }
+    Ok(imported)
+}
+
+/// An edit removing `range` along with the whitespace before it
+fn remove(text: &str, range: Range<usize>) -> (Range<usize>, String) {
+    let start = text[..range.start].trim_end().len();
+    (start..range.end, String::new())
+}
+
+/// Rewrite the names in `(layer ...)` and `(layers ...)` lists anywhere below `expr`
+fn rename_layers(
+    expr: &SExpr,
+    span: &SpanTree,
+    edits: &mut Vec<(Range<usize>, String)>,
+    renames: &mut BTreeMap<(String, String), usize>,
+) {
+    if matches!(expr.head(), Some("layer") | Some("layers")) {
+        for (name, name_span) in expr.children().iter().zip(&span.items).skip(1) {
+            if let Some(name) = name.as_str()
+                && let Some(canonical) = canonical_kicad_layer_name(name)
+                && canonical != name
+            {
+                edits.push((name_span.span.clone(), quote(&canonical)));
+                *renames.entry((name.to_string(), canonical)).or_default() += 1;
+            }
+        }
+        return;
+    }
+    for (child, child_span) in expr.children().iter().zip(&span.items) {
+        rename_layers(child, child_span, edits, renames);
+    }
+}
+
+/// The edit setting the KLC width on a graphic item, with the old and new width. Filled
+/// shapes keep theirs, it changes their size.
+fn width_edit(expr: &SExpr, span: &SpanTree, layer: &str, options: &NormalizeOptions) -> Option<((Range<usize>, String), f32, f32)> {
+    let target = match BoardLayer::from_kicad_str(layer)?.layer {
+        LayerType::SilkScreen => options.silk_width,
+        LayerType::Fabrication => options.fab_width,
+        LayerType::Courtyard => options.courtyard_width,
+        _ => return None,
+    };
+    if matches!(expr.value_of("fill"), Some("solid") | Some("yes")) {
+        return None;
+    }
+    let child = |expr: &SExpr, name: &str| expr.children().iter().position(|item| item.head() == Some(name));
+    let (width, width_span) = match child(expr, "stroke") {
+        Some(stroke) => {
+            let index = child(&expr.children()[stroke], "width")?;
+            (&expr.children()[stroke].children()[index], &span.items[stroke].items[index])
+        }
+        None => {
+            let index = child(expr, "width")?;
+            (&expr.children()[index], &span.items[index])
+        }
+    };
+    let current: f32 = width.children().get(1)?.as_str()?.parse().ok()?;
+    ((current - target).abs() > 1e-4).then(|| ((width_span.span.clone(), format!("(width {})", target)), current, target))
+}
+
+/// The point of a `(name x y)` list
+fn xy(list: &SExpr) -> Option<Point> {
+    let values = list.children();
+    Some(Point::new(values.get(1)?.as_str()?.parse().ok()?, values.get(2)?.as_str()?.parse().ok()?))
+}
+
+fn point_of(expr: &SExpr, name: &str) -> Option<Point> {
+    xy(expr.find(name)?)
+}
+
+/// Points bounding a graphic item: corners, circle extremes, polygon vertices. KiCad 5
+/// arcs, given by center and angle, count as their full circle.
+fn outline_points(expr: &SExpr) -> Vec<Point> {
+    let circle = |center: Point, edge: Point| {
+        let radius = center.distance_to(&edge);
+        vec![Point::new(center.x - radius, center.y - radius), Point::new(center.x + radius, center.y + radius)]
+    };
+    let points = |names: &[&str]| names.iter().filter_map(|name| point_of(expr, name)).collect::<Vec<_>>();
+    match expr.head() {
+        Some("fp_circle") => point_of(expr, "center").zip(point_of(expr, "end")).map_or(Vec::new(), |(c, e)| circle(c, e)),
+        Some("fp_arc") if expr.find("angle").is_some() => {
+            point_of(expr, "start").zip(point_of(expr, "end")).map_or(Vec::new(), |(c, e)| circle(c, e))
+        }
+        // Vertices may be (xy x y) or, since KiCad 7, (arc (start) (mid) (end))
+        Some("fp_poly") => expr
+            .find("pts")
+            .map(|pts| {
+                pts.children()
+                    .iter()
+                    .flat_map(|vertex| match vertex.head() {
+                        Some("xy") => xy(vertex).into_iter().collect(),
+                        Some("arc") => ["start", "mid", "end"].iter().filter_map(|name| point_of(vertex, name)).collect(),
+                        _ => Vec::new(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        _ => points(&["start", "mid", "end"]),
+    }
+}
+
+/// The open ends of a line or arc; `None` for shapes that are closed on their own
+fn segment_ends(expr: &SExpr) -> Option<(Point, Point)> {
+    match expr.head()? {
+        "fp_line" => Some((point_of(expr, "start")?, point_of(expr, "end")?)),
+        // KiCad 5: centered on start, from end clockwise through angle degrees
+        "fp_arc" if expr.find("angle").is_some() => {
+            let (center, from) = (point_of(expr, "start")?, point_of(expr, "end")?);
+            let angle: f32 = expr.value_of("angle")?.parse().ok()?;
+            let to = Point::new(from.x - center.x, from.y - center.y).rotated(-angle).offset(center.x, center.y);
+            Some((from, to))
+        }
+        "fp_arc" => Some((point_of(expr, "start")?, point_of(expr, "end")?)),
+        _ => None,
+    }
+}
+
+fn courtyard_issue<'a>(items: impl Iterator<Item = &'a SExpr>, required: Option<Rectangle>) -> Option<CourtyardIssue> {
+    let items: Vec<&SExpr> = items.collect();
+    if items.is_empty() {
+        return Some(CourtyardIssue::Missing);
+    }
+    // Closed when every line end meets another one
+    let mut ends: HashMap<(i64, i64), usize> = HashMap::new();
+    for (start, end) in items.iter().filter_map(|expr| segment_ends(expr)) {
+        for point in [start, end] {
+            let key = ((point.x / JOIN_TOLERANCE).round() as i64, (point.y / JOIN_TOLERANCE).round() as i64);
+            *ends.entry(key).or_default() += 1;
+        }
+    }
+    if ends.values().any(|count| count % 2 == 1) {
+        return Some(CourtyardIssue::Open);
+    }
+    let points: Vec<Point> = items.iter().flat_map(|expr| outline_points(expr)).collect();
+    let encloses = |outer: &Rectangle, inner: &Rectangle| {
+        outer.min_x <= inner.min_x + JOIN_TOLERANCE
+            && outer.min_y <= inner.min_y + JOIN_TOLERANCE
+            && outer.max_x >= inner.max_x - JOIN_TOLERANCE
+            && outer.max_y >= inner.max_y - JOIN_TOLERANCE
+    };
+    match (Rectangle::enclosing(&points), required) {
+        (Some(courtyard), Some(required)) if !encloses(&courtyard, &required) => Some(CourtyardIssue::TooSmall),
+        _ => None,
+    }
+}
+
+/// `required` grown by the margin and rounded out to the courtyard grid
+fn courtyard_bounds(required: &Rectangle, options: &NormalizeOptions) -> Rectangle {
+    // Counted in whole grid steps per millimeter so the corners print as their grid
+    // values, with some slack for values already on the grid
+    let steps = (1.0 / options.courtyard_grid as f64).round();
+    let down = |value: f32| ((value as f64 * steps + 1e-3).floor() / steps) as f32;
+    let up = |value: f32| ((value as f64 * steps - 1e-3).ceil() / steps) as f32;
+    let margin = options.courtyard_margin;
+    Rectangle {
+        min_x: down(required.min_x - margin),
+        min_y: down(required.min_y - margin),
+        max_x: up(required.max_x + margin),
+        max_y: up(required.max_y + margin),
+    }
+}
+
+/// How the file writes its lines, so added ones match
+#[derive(Debug, Clone, Copy)]
+struct LineStyle {
+    /// KiCad 6 and later `(stroke (width ..) (type solid))` rather than `(width ..)`
+    stroke: bool,
+    quoted: bool,
+    /// "uuid", "tstamp" or none
+    id: Option<&'static str>,
+}
+
+impl LineStyle {
+    fn of(items: &[(&SExpr, &SpanTree)]) -> Self {
+        let graphics: Vec<&SExpr> = items.iter().map(|(expr, _)| *expr).filter(|expr| expr.head() == Some("fp_line")).collect();
+        let any = |name: &str| graphics.iter().any(|expr| expr.find(name).is_some());
+        let quoted = items
+            .iter()
+            .filter_map(|(expr, _)| expr.find("layer"))
+            .any(|layer| matches!(layer.children().get(1), Some(SExpr::Str(_))));
+        let id = if any("uuid") {
+            Some("uuid")
+        } else if any("tstamp") {
+            Some("tstamp")
+        } else {
+            None
+        };
+        Self { stroke: any("stroke") || (!any("width") && quoted), quoted, id }
+    }
+}
+
+fn courtyard_lines(bounds: &Rectangle, width: f32, style: LineStyle) -> Vec<String> {
+    let corners = [
+        (bounds.min_x, bounds.min_y),
+        (bounds.max_x, bounds.min_y),
+        (bounds.max_x, bounds.max_y),
+        (bounds.min_x, bounds.max_y),
+    ];
+    let layer = if style.quoted { quote("F.CrtYd") } else { "F.CrtYd".to_string() };
+    (0..4)
+        .map(|i| {
+            let (start, end) = (corners[i], corners[(i + 1) % 4]);
+            let mut line = format!("(fp_line (start {} {}) (end {} {})", start.0, start.1, end.0, end.1);
+            if style.stroke {
+                line.push_str(&format!(" (stroke (width {}) (type solid)) (layer {})", width, layer));
+            } else {
+                line.push_str(&format!(" (layer {}) (width {})", layer, width));
+            }
+            if let Some(id) = style.id {
+                line.push_str(&format!(" ({} {})", id, quote(&Uuid::new_v4().to_string())));
+            }
+            line.push(')');
+            line
+        })
+        .collect()
+}
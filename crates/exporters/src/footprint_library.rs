@@ -0,0 +1,496 @@
+//! Footprint library writer
+//!
+//! Collects components into a named KiCad `.pretty` library, writes one `.kicad_mod` per
+//! footprint and, when installing into a project, registers the library in the project's
+//! fp-lib-table in the same step. A library can also be bundled into a single zip
+//! archive holding the `.pretty` directory, for sharing or KiCad's archive import.
+//! With `with_models` the 3D models are shipped alongside in `<name>.3dshapes/`, see
+//! `library_models`. Footprint graphics are simplified on the way out (duplicate and
+//! collinear lines merged, see `graphics::simplify`) unless turned off. A library given a
+//! version with `with_version` also writes a manifest for changelogs, see
+//! `library_manifest`. Footprints are written with the library's `ExportOptions`, so a
+//! library can be written reproducibly with `UuidMode::Deterministic`.
+
+use std::fmt::Write;
+use std::fs;
+use std::io::{self, BufWriter, Write as _};
+use std::path::{Path, PathBuf};
+
+use copper_substrate::analysis::{OrientationReport, PinMappingReport, SpacingOptions, SpacingReport};
+use copper_substrate::graphics::DEFAULT_SIMPLIFY_TOLERANCE;
+use copper_substrate::prelude::*;
+
+use crate::exporter::{ExportError, ExportOptions, ExporterRegistry, FootprintExporter};
+use crate::fp_lib_table::{FpLibTable, LibTableEntry, LibraryUri, FP_LIB_TABLE_FILE};
+use crate::kicad_pcb_export::try_to_kicad_footprint_with_options;
+use crate::kicad_sym_export::to_kicad_symbol_lib;
+use crate::library_manifest::{LibraryManifest, LibraryVersion, MANIFEST_FILE};
+use crate::library_models::{LibraryModels, ModelReport, ResolvedModel};
+use crate::sexpr::quote;
+
+/// Name of the Markdown report at the root of a library archive
+pub const ARCHIVE_REPORT_FILE: &str = "report.md";
+
+pub struct FootprintLibrary {
+    pub name: String,
+    pub description: Option<String>,
+    footprints: Vec<Box<dyn BoardComposableObject>>,
+    /// Symbol of each footprint, where one was given, for the pin mapping check
+    symbols: Vec<Option<Box<dyn ElectricalComponent>>>,
+    exporters: ExporterRegistry,
+    models: Option<LibraryModels>,
+    /// Tolerance graphics are simplified with on export, `None` to write them as generated
+    simplify_tolerance: Option<f32>,
+    /// Release metadata; the manifest is only written for versioned libraries
+    version: Option<LibraryVersion>,
+    /// How `.kicad_mod` files are written
+    export_options: ExportOptions,
+}
+
+impl FootprintLibrary {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            description: None,
+            footprints: Vec::new(),
+            symbols: Vec::new(),
+            exporters: ExporterRegistry::with_builtin(),
+            models: None,
+            simplify_tolerance: Some(DEFAULT_SIMPLIFY_TOLERANCE),
+            version: None,
+            export_options: ExportOptions::default(),
+        }
+    }
+
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Ship 3D models with the library instead of pointing into KiCad's model library
+    pub fn with_models(mut self, models: LibraryModels) -> Self {
+        self.models = Some(models);
+        self
+    }
+
+    /// Simplify graphics with `tolerance` when writing, or write them as generated with `None`
+    pub fn with_graphics_simplification(mut self, tolerance: Option<f32>) -> Self {
+        self.simplify_tolerance = tolerance;
+        self
+    }
+
+    /// Release the library as `version`, writing `library_manifest.json` with it
+    pub fn with_version(mut self, version: LibraryVersion) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Write the `.kicad_mod` files with `options` instead of the defaults
+    pub fn with_export_options(mut self, options: ExportOptions) -> Self {
+        self.export_options = options;
+        self
+    }
+
+    pub fn export_options(&self) -> &ExportOptions {
+        &self.export_options
+    }
+
+    pub fn version(&self) -> Option<&LibraryVersion> {
+        self.version.as_ref()
+    }
+
+    pub fn models(&self) -> Option<&LibraryModels> {
+        self.models.as_ref()
+    }
+
+    pub fn add<T: BoardComposableObject + 'static>(&mut self, component: T) -> &mut Self {
+        self.footprints.push(Box::new(component));
+        self.symbols.push(None);
+        self
+    }
+
+    /// Add a footprint together with the symbol it is placed with, so the report checks
+    /// that the symbol's pins and the footprint's pads agree
+    pub fn add_with_symbol<T, S>(&mut self, component: T, symbol: S) -> &mut Self
+    where
+        T: BoardComposableObject + 'static,
+        S: ElectricalComponent + 'static,
+    {
+        self.footprints.push(Box::new(component));
+        self.symbols.push(Some(Box::new(symbol)));
+        self
+    }
+
+    /// Pin mapping of each footprint added with a symbol
+    pub fn pin_mapping_reports(&self) -> Vec<(String, PinMappingReport)> {
+        self.footprints
+            .iter()
+            .zip(&self.symbols)
+            .filter_map(|(footprint, symbol)| {
+                let symbol = symbol.as_ref()?;
+                Some((footprint.footprint_name(), PinMappingReport::check(symbol.as_ref(), footprint.as_ref())))
+            })
+            .collect()
+    }
+
+    /// Zero orientation audit of the footprints, see `analysis::orientation`
+    pub fn orientation_report(&self) -> OrientationReport {
+        OrientationReport::for_footprints(self.footprints.iter().map(|footprint| footprint.as_ref()))
+    }
+
+    pub fn footprints(&self) -> &[Box<dyn BoardComposableObject>] {
+        &self.footprints
+    }
+
+    /// Directory name of the library, e.g. `Resistor_SMD.pretty`
+    pub fn pretty_dir_name(&self) -> String {
+        format!("{}.pretty", self.name)
+    }
+
+    /// Directory name of the shipped 3D models, e.g. `Resistor_SMD.3dshapes`
+    pub fn models_dir_name(&self) -> String {
+        format!("{}.3dshapes", self.name)
+    }
+
+    /// The model of each footprint with `with_models` set, `None` for footprints without a
+    /// model or whose model wasn't found
+    fn resolved_models(&self) -> Vec<Option<ResolvedModel>> {
+        self.footprints
+            .iter()
+            .map(|footprint| Some((self.models.as_ref()?, footprint.model_3d()?)).and_then(|(models, model)| models.resolve(&model)))
+            .collect()
+    }
+
+    /// Footprint file content, its model path pointing at the shipped model if there is one
+    fn footprint_content(&self, footprint: &dyn BoardComposableObject, resolved: Option<&ResolvedModel>) -> io::Result<String> {
+        let content = match self.simplify_tolerance {
+            Some(tolerance) => try_to_kicad_footprint_with_options(&Simplified::new(footprint, tolerance), &self.export_options),
+            None => try_to_kicad_footprint_with_options(footprint, &self.export_options),
+        }
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        match (&self.models, resolved, footprint.model_3d()) {
+            (Some(models), Some(resolved), Some(model)) => Ok(content.replacen(
+                &format!("(model {}", quote(&model.path)),
+                &format!("(model {}", quote(&models.model_path(&self.name, &resolved.file_name))),
+                1,
+            )),
+            _ => Ok(content),
+        }
+    }
+
+    /// Manifest of the footprints as they are written, see `library_manifest`
+    pub fn manifest(&self) -> io::Result<LibraryManifest> {
+        let mut files = Vec::new();
+        for (footprint, model) in self.footprints.iter().zip(self.resolved_models()) {
+            files.push((format!("{}.kicad_mod", footprint.footprint_name()), self.footprint_content(footprint.as_ref(), model.as_ref())?));
+        }
+        LibraryManifest::new(&self.name, self.version.as_ref(), files.iter().map(|(file, text)| (file.as_str(), text.as_str())))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+    }
+
+    /// Which footprints' models `with_models` finds, without writing anything
+    pub fn model_report(&self) -> ModelReport {
+        let mut report = ModelReport::default();
+        for (footprint, resolved) in self.footprints.iter().zip(self.resolved_models()) {
+            match (resolved, footprint.model_3d()) {
+                (Some(resolved), _) => report.shipped.push((footprint.footprint_name(), resolved.file_name)),
+                (None, Some(model)) if self.models.is_some() => report.missing.push((footprint.footprint_name(), model.path)),
+                _ => {}
+            }
+        }
+        report
+    }
+
+    /// Write `<dir>/<name>.pretty/` with one `.kicad_mod` per footprint, returning the
+    /// path of the `.pretty` directory. With `with_models` the models found are copied
+    /// into `<dir>/<name>.3dshapes/`; see `model_report` for the ones that weren't. A
+    /// versioned library also gets its manifest. A footprint with an unknown layer name
+    /// fails with `InvalidData`.
+    pub fn write_to(&self, dir: &Path) -> io::Result<PathBuf> {
+        let pretty = dir.join(self.pretty_dir_name());
+        fs::create_dir_all(&pretty)?;
+        if self.version.is_some() {
+            fs::write(pretty.join(MANIFEST_FILE), self.manifest()?.to_json())?;
+        }
+        let resolved = self.resolved_models();
+        for (footprint, model) in self.footprints.iter().zip(&resolved) {
+            let file = pretty.join(format!("{}.kicad_mod", footprint.footprint_name()));
+            fs::write(file, self.footprint_content(footprint.as_ref(), model.as_ref())?)?;
+        }
+        if resolved.iter().any(Option::is_some) {
+            let models_dir = dir.join(self.models_dir_name());
+            fs::create_dir_all(&models_dir)?;
+            for model in resolved.iter().flatten() {
+                model.copy_into(&models_dir)?;
+            }
+        }
+        Ok(pretty)
+    }
+
+    /// Write the library as a zip archive at `path` holding `<name>.pretty/` with one
+    /// `.kicad_mod` per footprint and the manifest of a versioned library, the shipped
+    /// models in `<name>.3dshapes/` when `with_models` is set, and the Markdown report at
+    /// the root. Footprints are generated and written one at a time. Every entry carries
+    /// the same fixed timestamp and permissions, so with `UuidMode::Deterministic` in the
+    /// export options the same library gives a byte-identical archive, provided a
+    /// versioned library's timestamp is set. Generated footprints otherwise get fresh
+    /// UUIDs on every write.
+    pub fn write_zip(&self, path: &Path) -> io::Result<PathBuf> {
+        let file = fs::File::create(path)?;
+        let mut zip = zip::ZipWriter::new(BufWriter::new(file));
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .last_modified_time(zip::DateTime::default())
+            .unix_permissions(0o644);
+
+        let pretty = self.pretty_dir_name();
+        zip.add_directory(pretty.as_str(), options.unix_permissions(0o755)).map_err(io::Error::other)?;
+        let resolved = self.resolved_models();
+        for (footprint, model) in self.footprints.iter().zip(&resolved) {
+            let content = self.footprint_content(footprint.as_ref(), model.as_ref())?;
+            zip.start_file(format!("{}/{}.kicad_mod", pretty, footprint.footprint_name()), options)
+                .map_err(io::Error::other)?;
+            zip.write_all(content.as_bytes())?;
+        }
+        if self.version.is_some() {
+            zip.start_file(format!("{}/{}", pretty, MANIFEST_FILE), options).map_err(io::Error::other)?;
+            zip.write_all(self.manifest()?.to_json().as_bytes())?;
+        }
+        if resolved.iter().any(Option::is_some) {
+            let models_dir = self.models_dir_name();
+            zip.add_directory(models_dir.as_str(), options.unix_permissions(0o755)).map_err(io::Error::other)?;
+            let mut written = Vec::new();
+            for model in resolved.iter().flatten() {
+                if written.contains(&model.file_name) {
+                    continue;
+                }
+                written.push(model.file_name.clone());
+                zip.start_file(format!("{}/{}", models_dir, model.file_name), options).map_err(io::Error::other)?;
+                zip.write_all(&model.data()?)?;
+            }
+        }
+        zip.start_file(ARCHIVE_REPORT_FILE, options).map_err(io::Error::other)?;
+        zip.write_all(self.report().as_bytes())?;
+
+        zip.finish().map_err(io::Error::other)?.flush()?;
+        Ok(path.to_path_buf())
+    }
+
+    /// Make an additional output format available to [`FootprintLibrary::write_format`]
+    pub fn register_exporter(&mut self, exporter: Box<dyn FootprintExporter>) -> &mut Self {
+        self.exporters.register_footprint_exporter(exporter);
+        self
+    }
+
+    pub fn exporters(&self) -> &ExporterRegistry {
+        &self.exporters
+    }
+
+    /// Write every footprint with the exporter registered as `format` into the exporter's
+    /// library directory under `dir`, returning that directory
+    pub fn write_format(&self, dir: &Path, format: &str, options: &ExportOptions) -> Result<PathBuf, ExportError> {
+        let exporter = self.exporters.footprint_exporter(format)?;
+        let library_dir = dir.join(exporter.library_dir_name(&self.name));
+        fs::create_dir_all(&library_dir)?;
+        for footprint in &self.footprints {
+            let bytes = match self.simplify_tolerance {
+                Some(tolerance) => exporter.export(&Simplified::new(footprint.as_ref(), tolerance), options)?,
+                None => exporter.export(footprint.as_ref(), options)?,
+            };
+            let file = library_dir.join(format!("{}.{}", footprint.footprint_name(), exporter.file_extension()));
+            fs::write(file, bytes)?;
+        }
+        Ok(library_dir)
+    }
+
+    /// Markdown report describing the library contents, with a spacing section per footprint
+    /// and the pin mapping of footprints added with a symbol
+    pub fn report(&self) -> String {
+        let mut output = String::new();
+        writeln!(output, "# {}", self.name).unwrap();
+        if let Some(description) = &self.description {
+            writeln!(output, "\n{}", description).unwrap();
+        }
+        writeln!(output, "\n| Footprint | Pads | Description |").unwrap();
+        writeln!(output, "|---|---|---|").unwrap();
+        for footprint in &self.footprints {
+            writeln!(output, "| {} | {} | {} |",
+                     footprint.footprint_name(),
+                     footprint.pad_descriptors().len(),
+                     footprint.description().unwrap_or_default()).unwrap();
+        }
+
+        writeln!(output, "\n## Pad spacing").unwrap();
+        for footprint in &self.footprints {
+            let spacing = SpacingReport::for_component(footprint.as_ref(), SpacingOptions::default());
+            writeln!(output, "\n### {}\n", footprint.footprint_name()).unwrap();
+            for line in spacing.summary().lines() {
+                writeln!(output, "- {}", line).unwrap();
+            }
+        }
+
+        let pin_mappings = self.pin_mapping_reports();
+        if !pin_mappings.is_empty() {
+            writeln!(output, "\n## Pin mapping").unwrap();
+            for (footprint, mapping) in &pin_mappings {
+                writeln!(output, "\n### {}\n", footprint).unwrap();
+                for line in mapping.summary().lines() {
+                    writeln!(output, "- {}", line).unwrap();
+                }
+            }
+        }
+
+        if self.models.is_some() {
+            let models = self.model_report();
+            writeln!(output, "\n## 3D models\n").unwrap();
+            writeln!(output, "{} shipped in {}, {} missing", models.shipped.len(), self.models_dir_name(), models.missing.len()).unwrap();
+            for (footprint, path) in &models.missing {
+                writeln!(output, "- {}: {} not found", footprint, path).unwrap();
+            }
+        }
+        output
+    }
+
+    /// Write `<dir>/<name>.kicad_sym` with a box-and-pins symbol per footprint, each
+    /// with its Footprint field pointing into this library
+    pub fn write_symbol_library(&self, dir: &Path) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let components: Vec<&dyn BoardComposableObject> = self.footprints.iter().map(|f| f.as_ref()).collect();
+        let file = dir.join(format!("{}.kicad_sym", self.name));
+        fs::write(&file, to_kicad_symbol_lib(&components, &self.name))?;
+        Ok(file)
+    }
+
+    /// fp-lib-table entry pointing at this library once written into `dir`
+    pub fn lib_table_entry(&self, dir: &Path, uri: LibraryUri) -> io::Result<LibTableEntry> {
+        let uri = match uri {
+            LibraryUri::ProjectRelative => format!("${{KIPRJMOD}}/{}", self.pretty_dir_name()),
+            LibraryUri::Absolute => fs::canonicalize(dir)?.join(self.pretty_dir_name()).to_string_lossy().into_owned(),
+        };
+        Ok(LibTableEntry::kicad(&self.name, &uri, self.description.as_deref().unwrap_or_default()))
+    }
+
+    /// Write the library into a KiCad project folder and register it in the project's
+    /// fp-lib-table using a `${KIPRJMOD}` relative path
+    pub fn install_into_project(&self, project_dir: &Path) -> io::Result<PathBuf> {
+        self.install_into_project_with_uri(project_dir, LibraryUri::ProjectRelative)
+    }
+
+    /// As [`FootprintLibrary::install_into_project`], choosing how the library path is recorded
+    pub fn install_into_project_with_uri(&self, project_dir: &Path, uri: LibraryUri) -> io::Result<PathBuf> {
+        let pretty = self.write_to(project_dir)?;
+
+        let table_path = project_dir.join(FP_LIB_TABLE_FILE);
+        let mut table = FpLibTable::load(&table_path)?;
+        table.upsert(self.lib_table_entry(project_dir, uri)?);
+        table.write_atomic(&table_path)?;
+
+        Ok(pretty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use copper_substrate::presets;
+
+    use super::*;
+    use crate::uuids::UuidMode;
+
+    /// Empty scratch directory unique to one test
+    fn scratch(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("copper-footprint-library-{}-{}", std::process::id(), test));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn passives() -> FootprintLibrary {
+        let mut library = FootprintLibrary::new("Passives").with_description("Generated passives");
+        library.add(presets::resistor_0603("10k")).add(presets::capacitor_1206("1u"));
+        library
+    }
+
+    #[test]
+    fn install_into_project_keeps_other_libraries() {
+        let project = scratch("install");
+        let vendor = "(fp_lib_table\n  (version 7)\n  \
+            (lib (name \"Vendor\")(type \"KiCad\")(uri \"${KIPRJMOD}/vendor.pretty\")(options \"\")(descr \"\"))\n)\n";
+        fs::write(project.join(FP_LIB_TABLE_FILE), vendor).unwrap();
+
+        let pretty = passives().install_into_project(&project).unwrap();
+        assert_eq!(pretty, project.join("Passives.pretty"));
+        assert_eq!(fs::read_dir(&pretty).unwrap().count(), 2);
+        assert_eq!(fs::read_to_string(project.join("fp-lib-table.bak")).unwrap(), vendor);
+
+        let table = FpLibTable::load(&project.join(FP_LIB_TABLE_FILE)).unwrap();
+        assert_eq!(table.get("Vendor").unwrap().uri, "${KIPRJMOD}/vendor.pretty");
+        let entry = table.get("Passives").unwrap();
+        assert_eq!((entry.uri.as_str(), entry.description.as_str()), ("${KIPRJMOD}/Passives.pretty", "Generated passives"));
+
+        // Installing again updates the entry instead of adding a second one
+        passives().install_into_project(&project).unwrap();
+        assert_eq!(FpLibTable::load(&project.join(FP_LIB_TABLE_FILE)).unwrap().entries().count(), 2);
+        fs::remove_dir_all(&project).unwrap();
+    }
+
+    #[test]
+    fn zip_holds_the_pretty_directory_and_report() {
+        let dir = scratch("zip");
+        let options = ExportOptions { uuids: UuidMode::Deterministic, ..ExportOptions::default() };
+        let library = passives().with_export_options(options);
+        let archive = library.write_zip(&dir.join("Passives.zip")).unwrap();
+        let pretty = library.write_to(&dir).unwrap();
+
+        let mut zip = zip::ZipArchive::new(fs::File::open(&archive).unwrap()).unwrap();
+        let mut names: Vec<&str> = zip.file_names().collect();
+        names.sort_unstable();
+        assert_eq!(
+            names,
+            [
+                "Passives.pretty/",
+                "Passives.pretty/C_1206_3216Metric.kicad_mod",
+                "Passives.pretty/R_0603_1608Metric.kicad_mod",
+                ARCHIVE_REPORT_FILE
+            ]
+        );
+        let mut zipped = String::new();
+        io::Read::read_to_string(&mut zip.by_name("Passives.pretty/R_0603_1608Metric.kicad_mod").unwrap(), &mut zipped).unwrap();
+        assert_eq!(zipped, fs::read_to_string(pretty.join("R_0603_1608Metric.kicad_mod")).unwrap());
+
+        // Deterministic UUIDs make the archive reproducible
+        let again = library.write_zip(&dir.join("Again.zip")).unwrap();
+        assert_eq!(fs::read(&archive).unwrap(), fs::read(&again).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Symbol with pins numbered 1 to n
+    struct Numbered(usize);
+
+    impl ElectricalComponent for Numbered {
+        fn pins(&self) -> Vec<Pin> {
+            (1..=self.0)
+                .map(|n| Pin { id: n as u32, number: n.to_string(), name: None, position: (0.0, 0.0), electrical_type: ElectricalType::Passive })
+                .collect()
+        }
+        fn net_connections(&self) -> HashMap<PinId, NetId> {
+            HashMap::new()
+        }
+    }
+
+    #[test]
+    fn report_carries_the_pin_mapping_of_parts_with_symbols() {
+        let soic = Soic::from_jedec("MS-012-AA", 8).unwrap();
+        let mut library = FootprintLibrary::new("Pin_Mapping");
+        library.add_with_symbol(soic.clone(), Numbered(8)).add_with_symbol(presets::resistor_0603("10k"), Numbered(3)).add(soic);
+        let reports = library.pin_mapping_reports();
+        assert_eq!(reports.len(), 2);
+        assert!(reports[0].1.is_ok());
+        assert!(!reports[1].1.is_ok(), "pin 3 has no pad");
+        let report = library.report();
+        let section = &report[report.find("## Pin mapping").expect("a pin mapping section")..];
+        assert!(section.contains("SOIC-8_3.9x4.9mm_P1.27mm") && section.contains("8 pins mapped"), "{}", section);
+        assert!(section.contains("R_0603_1608Metric"), "{}", section);
+    }
+}
@@ -0,0 +1,287 @@
+//! Numbers and angles as every exporter writes them
+//!
+//! Footprints and boards are modelled in millimeters with Y down and angles in degrees
+//! counter-clockwise as seen on screen, which is also how KiCad files store them. Other
+//! formats differ, and convert here rather than at each write:
+//!
+//! - SVG keeps Y down, but `rotate()` turns clockwise.
+//! - DXF and Gerber are Y up. Turning Y up flips the coordinates but not the drawing, so a
+//!   counter-clockwise angle keeps its value. Negating it as well mirrors the drawing.
+//! - KiCad's legacy formats count tenths of a degree.
+//!
+//! Numbers are written in the shortest form that reads back as the same f32, which
+//! `precision_warnings` relies on, and negative zero is written as 0.
+//!
+//! KiCad keeps coordinates in integer nanometers. Transform math leaves values such as
+//! 1.0000001 that no KiCad file holds, so footprint and board files get a last pass,
+//! `quantize_numbers`, that rounds every length they contain to a `Resolution`, 1 nm
+//! unless the options ask for 1 µm. Angles and ratios are kept, as KiCad keeps them. The rounded number is written from its integer count
+//! of steps, so it reads back as exactly the quantized value and never as -0.
+
+use std::fmt;
+
+/// Decimal places of Gerber coordinates, format 4.6
+pub const GERBER_DECIMALS: i32 = 6;
+
+/// Grid the numbers of KiCad files are rounded to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Resolution {
+    /// KiCad's internal unit
+    #[default]
+    Nanometer,
+    /// 0.001 mm, for files diffed by hand or fed to tools on a micron grid
+    Micrometer,
+}
+
+impl Resolution {
+    /// Decimal places a quantized number has at most
+    pub fn decimals(self) -> u32 {
+        match self {
+            Resolution::Nanometer => 6,
+            Resolution::Micrometer => 3,
+        }
+    }
+
+    /// Whole steps of the grid in `value` millimeters, rounded half away from zero
+    fn steps(self, value: f64) -> i64 {
+        (value * 10f64.powi(self.decimals() as i32)).round() as i64
+    }
+
+    /// `value` rounded to the grid
+    pub fn quantize(self, value: f64) -> f64 {
+        self.steps(value) as f64 / 10f64.powi(self.decimals() as i32)
+    }
+
+    /// `value` rounded to the grid and written in the fewest digits, "0" for zero
+    pub fn format(self, value: f64) -> String {
+        let steps = self.steps(value);
+        let scale = 10u64.pow(self.decimals());
+        let (whole, fraction) = (steps.unsigned_abs() / scale, steps.unsigned_abs() % scale);
+        let sign = if steps < 0 { "-" } else { "" };
+        if fraction == 0 {
+            return format!("{}{}", sign, whole);
+        }
+        let digits = format!("{:0width$}", fraction, width = self.decimals() as usize);
+        format!("{}{}.{}", sign, whole, digits.trim_end_matches('0'))
+    }
+}
+
+/// Whether an s-expression atom is a plain decimal number, as opposed to a name, a hex
+/// mask such as 0x00010fc_ffffffff, or "inf"
+fn is_number(token: &str) -> bool {
+    let digits = token.strip_prefix('-').unwrap_or(token);
+    digits.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && token.parse::<f64>().is_ok()
+}
+
+/// Lists whose numbers are ratios or angles rather than lengths, which KiCad stores as
+/// they are; their nested lists too, as in `(scale (xyz 1 1 1))`. The angle of an
+/// `(at x y angle)` is also kept.
+const UNQUANTIZED_LISTS: [&str; 7] = ["roundrect_rratio", "chamfer_ratio", "scale", "rotate", "angle", "hatch_orientation", "thermal_bridge_angle"];
+
+/// An open list while quantizing: its head once read, and how many numbers it has had
+#[derive(Default)]
+struct OpenList {
+    head: Option<String>,
+    numbers: usize,
+}
+
+/// `text`, an s-expression file, with every length rounded to `resolution`, see the
+/// module documentation. Quoted strings, angles and the ratios of `UNQUANTIZED_LISTS`
+/// are left as they are.
+pub fn quantize_numbers(text: &str, resolution: Resolution) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut token = String::new();
+    let mut lists: Vec<OpenList> = Vec::new();
+    // How many of the open lists are in UNQUANTIZED_LISTS
+    let mut kept = 0;
+    let (mut quoted, mut escaped) = (false, false);
+    let flush = |output: &mut String, token: &mut String, lists: &mut Vec<OpenList>, kept: &mut usize| {
+        if token.is_empty() {
+            return;
+        }
+        let mut write = token.clone();
+        match lists.last_mut() {
+            Some(list) if list.head.is_none() => {
+                if UNQUANTIZED_LISTS.contains(&token.as_str()) {
+                    *kept += 1;
+                }
+                list.head = Some(token.clone());
+            }
+            list if is_number(token) => {
+                let angle = list.is_some_and(|list| {
+                    list.numbers += 1;
+                    list.head.as_deref() == Some("at") && list.numbers == 3
+                });
+                if *kept == 0 && !angle {
+                    write = resolution.format(token.parse().unwrap());
+                }
+            }
+            _ => {}
+        }
+        output.push_str(&write);
+        token.clear();
+    };
+    for c in text.chars() {
+        if quoted {
+            output.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                quoted = false;
+            }
+            continue;
+        }
+        match c {
+            '"' | '(' | ')' => {
+                flush(&mut output, &mut token, &mut lists, &mut kept);
+                match c {
+                    '(' => lists.push(OpenList::default()),
+                    ')' => {
+                        if lists.pop().and_then(|list| list.head).is_some_and(|head| UNQUANTIZED_LISTS.contains(&head.as_str())) {
+                            kept -= 1;
+                        }
+                    }
+                    _ => quoted = true,
+                }
+                output.push(c);
+            }
+            c if c.is_whitespace() => {
+                flush(&mut output, &mut token, &mut lists, &mut kept);
+                output.push(c);
+            }
+            c => token.push(c),
+        }
+    }
+    flush(&mut output, &mut token, &mut lists, &mut kept);
+    output
+}
+
+/// A number as exporters write it: shortest round-trip form, never "-0"
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decimal(pub f32);
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Adding zero turns -0 into 0
+        write!(f, "{}", self.0 + 0.0)
+    }
+}
+
+/// A point written as "x,y", the form of SVG point lists
+pub fn point_pair(x: f32, y: f32) -> String {
+    format!("{},{}", Decimal(x), Decimal(y))
+}
+
+/// Y of a footprint or board coordinate in a Y up format such as DXF
+pub fn y_up(y: f32) -> f32 {
+    -y
+}
+
+/// A millimeter value as a Gerber fixed point integer with `GERBER_DECIMALS` decimals
+pub fn gerber_fixed(value: f32) -> i64 {
+    (f64::from(value) * 10f64.powi(GERBER_DECIMALS)).round() as i64
+}
+
+/// A rotation, held counter-clockwise as seen on screen whatever it was read from
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Angle {
+    degrees: f32,
+}
+
+impl Angle {
+    pub const ZERO: Angle = Angle { degrees: 0.0 };
+
+    /// Degrees counter-clockwise, as in KiCad files, the footprint model and DXF
+    pub fn ccw(degrees: f32) -> Self {
+        Self { degrees }
+    }
+
+    /// Degrees clockwise, as in SVG's `rotate()`
+    pub fn cw(degrees: f32) -> Self {
+        Self { degrees: -degrees }
+    }
+
+    /// Tenths of a degree counter-clockwise, as in KiCad's legacy formats
+    pub fn from_decidegrees(tenths: i32) -> Self {
+        Self { degrees: tenths as f32 / 10.0 }
+    }
+
+    /// An optional model angle, no rotation when `None`
+    pub fn from_option(degrees: Option<f32>) -> Self {
+        Self::ccw(degrees.unwrap_or(0.0))
+    }
+
+    pub fn ccw_degrees(self) -> f32 {
+        self.degrees
+    }
+
+    pub fn cw_degrees(self) -> f32 {
+        -self.degrees
+    }
+
+    /// Tenths of a degree counter-clockwise, rounded
+    pub fn decidegrees(self) -> i32 {
+        (self.degrees * 10.0).round() as i32
+    }
+
+    /// The same rotation within [0, 360)
+    pub fn normalized(self) -> Self {
+        Self { degrees: self.degrees.rem_euclid(360.0) }
+    }
+
+    /// The rotation seen in a mirror, as on a footprint flipped to the back
+    pub fn mirrored(self) -> Self {
+        Self { degrees: -self.degrees }
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.normalized().degrees == 0.0
+    }
+}
+
+impl std::ops::Add for Angle {
+    type Output = Angle;
+
+    fn add(self, other: Angle) -> Angle {
+        Angle { degrees: self.degrees + other.degrees }
+    }
+}
+
+/// Written counter-clockwise in degrees, the form KiCad and DXF take
+impl fmt::Display for Angle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Decimal(self.degrees).fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_are_written_from_whole_steps() {
+        assert_eq!(Resolution::Nanometer.format(1.0000001), "1");
+        assert_eq!(Resolution::Nanometer.format(-0.0), "0");
+        assert_eq!(Resolution::Nanometer.format(-0.0000004), "0");
+        assert_eq!(Resolution::Nanometer.format(0.107836), "0.107836");
+        assert_eq!(Resolution::Nanometer.format(-12.70000049), "-12.7");
+        assert_eq!(Resolution::Micrometer.format(0.107836), "0.108");
+        assert_eq!(Resolution::Micrometer.format(-0.0005), "-0.001");
+        assert_eq!(Resolution::Micrometer.quantize(2.0004), 2.0);
+    }
+
+    #[test]
+    fn only_lengths_are_quantized() {
+        let text = "(pad \"1.0000001\" smd roundrect (at -0 1.0000001 33.3333333) (size 0.7000001 0.8) (roundrect_rratio 0.2071068)\n\t(layers \"F.Cu\") (net 3) (tstamp 0x00010fc_ffffffff))";
+        assert_eq!(
+            quantize_numbers(text, Resolution::Nanometer),
+            "(pad \"1.0000001\" smd roundrect (at 0 1 33.3333333) (size 0.7 0.8) (roundrect_rratio 0.2071068)\n\t(layers \"F.Cu\") (net 3) (tstamp 0x00010fc_ffffffff))"
+        );
+        let model = "(model \"a \\\"b\\\" 0.5\" (offset (xyz 0.1234 0 0)) (scale (xyz 1.0000001 1 1)) (rotate (xyz 0 0 90.0000001)))";
+        assert_eq!(quantize_numbers(model, Resolution::Micrometer), "(model \"a \\\"b\\\" 0.5\" (offset (xyz 0.123 0 0)) (scale (xyz 1.0000001 1 1)) (rotate (xyz 0 0 90.0000001)))");
+    }
+}
@@ -0,0 +1,241 @@
+//! KiCad footprint library table (fp-lib-table) maintenance
+//!
+//! Registers generated `.pretty` libraries in a project's fp-lib-table so they show up in
+//! pcbnew without hand editing. Entries we don't touch are written back byte for byte, and
+//! updated entries are spliced into the original text in place.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::sexpr::{self, SExprError};
+
+pub const FP_LIB_TABLE_FILE: &str = "fp-lib-table";
+
+/// How a library's location is written into the table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LibraryUri {
+    /// `${KIPRJMOD}/<name>.pretty`, portable with the project folder
+    ProjectRelative,
+    /// Absolute filesystem path to the `.pretty` directory
+    Absolute,
+}
+
+/// One `(lib ...)` row of the table
+#[derive(Debug, Clone, PartialEq)]
+pub struct LibTableEntry {
+    pub name: String,
+    pub lib_type: String,
+    pub uri: String,
+    pub options: String,
+    pub description: String,
+}
+
+impl LibTableEntry {
+    /// Entry for a KiCad format (`.pretty`) library
+    pub fn kicad(name: &str, uri: &str, description: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            lib_type: "KiCad".to_string(),
+            uri: uri.to_string(),
+            options: String::new(),
+            description: description.to_string(),
+        }
+    }
+
+    fn to_sexpr(&self) -> String {
+        format!(
+            "(lib (name {})(type {})(uri {})(options {})(descr {}))",
+            sexpr::quote(&self.name),
+            sexpr::quote(&self.lib_type),
+            sexpr::quote(&self.uri),
+            sexpr::quote(&self.options),
+            sexpr::quote(&self.description)
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TableRow {
+    entry: LibTableEntry,
+    /// Location in the source text, `None` for rows added since loading
+    span: Option<Range<usize>>,
+    modified: bool,
+}
+
+/// An fp-lib-table loaded from disk (or empty), editable in place
+#[derive(Debug, Clone, Default)]
+pub struct FpLibTable {
+    source: String,
+    root_close: usize,
+    rows: Vec<TableRow>,
+}
+
+impl FpLibTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse the text of an fp-lib-table; blank input yields an empty table
+    pub fn parse(text: &str) -> Result<Self, SExprError> {
+        if text.trim().is_empty() {
+            return Ok(Self::new());
+        }
+        let document = sexpr::parse_document(text)?;
+        if document.root.head() != Some("fp_lib_table") {
+            return Err(SExprError { message: "not an fp_lib_table".to_string(), offset: 0 });
+        }
+
+        let rows = document.root.children()[1..]
+            .iter()
+            .zip(document.child_spans)
+            .filter(|(child, _)| child.head() == Some("lib"))
+            .map(|(child, span)| TableRow {
+                entry: LibTableEntry {
+                    name: child.value_of("name").unwrap_or_default().to_string(),
+                    lib_type: child.value_of("type").unwrap_or_default().to_string(),
+                    uri: child.value_of("uri").unwrap_or_default().to_string(),
+                    options: child.value_of("options").unwrap_or_default().to_string(),
+                    description: child.value_of("descr").unwrap_or_default().to_string(),
+                },
+                span: Some(span),
+                modified: false,
+            })
+            .collect();
+
+        Ok(Self { source: text.to_string(), root_close: document.root_close, rows })
+    }
+
+    /// Load a table from disk, treating a missing file as an empty table
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &LibTableEntry> {
+        self.rows.iter().map(|row| &row.entry)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&LibTableEntry> {
+        self.entries().find(|entry| entry.name == name)
+    }
+
+    /// Add `entry`, or replace the existing entry with the same name
+    pub fn upsert(&mut self, entry: LibTableEntry) {
+        match self.rows.iter_mut().find(|row| row.entry.name == entry.name) {
+            Some(row) => {
+                if row.entry != entry {
+                    row.entry = entry;
+                    row.modified = true;
+                }
+            }
+            None => self.rows.push(TableRow { entry, span: None, modified: true }),
+        }
+    }
+
+    /// Write the table, keeping a `.bak` copy of the previous file and replacing it atomically
+    pub fn write_atomic(&self, path: &Path) -> io::Result<()> {
+        if path.exists() {
+            fs::copy(path, with_suffix(path, ".bak"))?;
+        }
+        let temp = with_suffix(path, ".tmp");
+        fs::write(&temp, self.to_string())?;
+        fs::rename(&temp, path)
+    }
+}
+
+impl fmt::Display for FpLibTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let added = self.rows.iter().filter(|row| row.span.is_none());
+
+        if self.source.is_empty() {
+            writeln!(f, "(fp_lib_table")?;
+            writeln!(f, "  (version 7)")?;
+            for row in added {
+                writeln!(f, "  {}", row.entry.to_sexpr())?;
+            }
+            return writeln!(f, ")");
+        }
+
+        // Splice modified rows over their original text, everything else is copied verbatim
+        let mut cursor = 0;
+        for row in self.rows.iter().filter(|row| row.modified) {
+            if let Some(span) = &row.span {
+                f.write_str(&self.source[cursor..span.start])?;
+                f.write_str(&row.entry.to_sexpr())?;
+                cursor = span.end;
+            }
+        }
+        f.write_str(&self.source[cursor..self.root_close])?;
+        for row in added {
+            writeln!(f, "  {}", row.entry.to_sexpr())?;
+        }
+        f.write_str(&self.source[self.root_close..])
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> std::path::PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THIRD_PARTY: &str = "(fp_lib_table\n  (version 7)\n  \
+        (lib (name \"Vendor\")(type \"KiCad\")(uri \"${KIPRJMOD}/vendor.pretty\")(options \"\")(descr \"Vendor parts\"))\n  \
+        (lib (name \"Generated\")(type \"KiCad\")(uri \"/old/generated.pretty\")(options \"\")(descr \"\"))\n)\n";
+
+    #[test]
+    fn adds_to_empty_table() {
+        let mut table = FpLibTable::parse("").unwrap();
+        table.upsert(LibTableEntry::kicad("Generated", "${KIPRJMOD}/Generated.pretty", "Made here"));
+        let text = table.to_string();
+        assert!(text.starts_with("(fp_lib_table\n  (version 7)\n"), "{}", text);
+        let reread = FpLibTable::parse(&text).unwrap();
+        assert_eq!(reread.entries().count(), 1);
+        assert_eq!(reread.get("Generated").unwrap().uri, "${KIPRJMOD}/Generated.pretty");
+        assert_eq!(reread.get("Generated").unwrap().description, "Made here");
+    }
+
+    #[test]
+    fn updates_existing_entry_in_place() {
+        let mut table = FpLibTable::parse(THIRD_PARTY).unwrap();
+        table.upsert(LibTableEntry::kicad("Generated", "${KIPRJMOD}/Generated.pretty", ""));
+        let text = table.to_string();
+        let reread = FpLibTable::parse(&text).unwrap();
+        assert_eq!(reread.entries().map(|entry| entry.name.as_str()).collect::<Vec<_>>(), ["Vendor", "Generated"]);
+        assert_eq!(reread.get("Generated").unwrap().uri, "${KIPRJMOD}/Generated.pretty");
+        assert!(!text.contains("/old/generated.pretty"));
+    }
+
+    #[test]
+    fn preserves_third_party_entry() {
+        let mut table = FpLibTable::parse(THIRD_PARTY).unwrap();
+        table.upsert(LibTableEntry::kicad("New", "${KIPRJMOD}/New.pretty", ""));
+        let text = table.to_string();
+        let vendor = "(lib (name \"Vendor\")(type \"KiCad\")(uri \"${KIPRJMOD}/vendor.pretty\")(options \"\")(descr \"Vendor parts\"))";
+        assert!(text.contains(vendor), "{}", text);
+        assert!(text.contains("(uri \"/old/generated.pretty\")"));
+        assert_eq!(FpLibTable::parse(&text).unwrap().entries().count(), 3);
+    }
+
+    #[test]
+    fn unchanged_table_is_written_back_verbatim() {
+        let mut table = FpLibTable::parse(THIRD_PARTY).unwrap();
+        table.upsert(table.get("Vendor").unwrap().clone());
+        assert_eq!(table.to_string(), THIRD_PARTY);
+    }
+
+    #[test]
+    fn rejects_other_documents() {
+        assert!(FpLibTable::parse("(sym_lib_table (version 7))").is_err());
+    }
+}
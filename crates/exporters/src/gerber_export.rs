@@ -0,0 +1,719 @@
+//! Gerber X2 layer writer and copper layers
+//!
+//! Every Gerber layer is written here: the copper layers below and the paste layers of
+//! `stencil_export`. Round pads and vias are flashed with a circular aperture, all other
+//! pad shapes are written as regions from their outline; tracks and lines are stroked
+//! with a round aperture of their width. Copper zones and thieving are left to KiCad's
+//! filler and are not written.
+//!
+//! In the default `GerberDialect::X2` the header carries the file attributes
+//! (.FileFunction, .FilePolarity, .Part), every aperture its .AperFunction from the pad
+//! type and fabrication property (`AperFunction::of_pad`), and every pad object its
+//! component (.C) and pin (.P) so CAM tools know which reference and pad a flash belongs
+//! to. `GerberDialect::Rs274x` leaves all attributes out for tools that reject them.
+//!
+//! Apertures are defined once per size and function in the header, before any object uses
+//! them. Coordinates are `Board::fab_point`s, from the board or the auxiliary origin.
+
+use std::fmt::Write;
+
+use copper_substrate::board::{Board, FabOrigin, PlacedComponent};
+use copper_substrate::prelude::*;
+
+use crate::exporter::{BoardExporter, ExportError, ExportOptions};
+use crate::format::gerber_fixed;
+
+/// First aperture number, D10 and up are free for apertures
+const FIRST_APERTURE: u32 = 10;
+
+/// Apertures closer than this in diameter (mm) are the same aperture
+const APERTURE_TOLERANCE: f32 = 0.0005;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GerberDialect {
+    /// RS-274X with X2 file, aperture and object attributes
+    #[default]
+    X2,
+    /// Plain RS-274X without attributes
+    Rs274x,
+}
+
+/// What an aperture is used for, written as its .AperFunction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AperFunction {
+    ViaPad,
+    /// Plated through hole component pad
+    ComponentPad,
+    /// Surface mount pad defined by its copper
+    SmdPad,
+    HeatsinkPad,
+    TestPad,
+    LocalFiducial,
+    GlobalFiducial,
+    /// Copper around a non plated hole
+    WasherPad,
+    /// Pad with no electrical function
+    MechanicalPad,
+    /// Tracks
+    Conductor,
+    /// Footprint graphics on copper
+    NonConductor,
+}
+
+impl AperFunction {
+    /// Function of a pad from its fabrication property, else its type
+    pub fn of_pad(pad: &PadDescriptor) -> Self {
+        match (pad.fab_property, &pad.pad_type) {
+            (Some(PadFabProperty::LocalFiducial), _) => AperFunction::LocalFiducial,
+            (Some(PadFabProperty::GlobalFiducial), _) => AperFunction::GlobalFiducial,
+            (Some(PadFabProperty::TestPoint), _) => AperFunction::TestPad,
+            (Some(PadFabProperty::Heatsink), _) => AperFunction::HeatsinkPad,
+            (Some(PadFabProperty::Mechanical), _) => AperFunction::MechanicalPad,
+            (None, PadType::NPTH) => AperFunction::WasherPad,
+            (None, PadType::ThroughHole) => AperFunction::ComponentPad,
+            (None, PadType::SMD) => AperFunction::SmdPad,
+        }
+    }
+
+    /// Value of the .AperFunction attribute
+    pub fn attribute(self) -> &'static str {
+        match self {
+            AperFunction::ViaPad => "ViaPad",
+            AperFunction::ComponentPad => "ComponentPad",
+            AperFunction::SmdPad => "SMDPad,CuDef",
+            AperFunction::HeatsinkPad => "HeatsinkPad",
+            AperFunction::TestPad => "TestPad",
+            AperFunction::LocalFiducial => "FiducialPad,Local",
+            AperFunction::GlobalFiducial => "FiducialPad,Global",
+            AperFunction::WasherPad => "WasherPad",
+            AperFunction::MechanicalPad => "OtherPad,Mechanical",
+            AperFunction::Conductor => "Conductor",
+            AperFunction::NonConductor => "NonConductor",
+        }
+    }
+}
+
+/// Geometry of one object in fabrication coordinates (Y up)
+pub(crate) enum Shape {
+    Flash { center: Point, diameter: f32 },
+    Region(Vec<Point>),
+    Stroke { points: Vec<Point>, width: f32 },
+    Circle { center: Point, radius: f32, width: f32 },
+}
+
+/// Component pad an object belongs to, for the .C and .P object attributes; the number
+/// is empty for pads that aren't pins
+pub(crate) struct PadRef {
+    pub reference: String,
+    pub number: String,
+    pub pin_function: Option<String>,
+}
+
+pub(crate) struct GerberObject {
+    pub shape: Shape,
+    pub function: Option<AperFunction>,
+    pub pad: Option<PadRef>,
+}
+
+impl GerberObject {
+    pub fn new(shape: Shape) -> Self {
+        Self { shape, function: None, pad: None }
+    }
+
+    pub fn with_function(mut self, function: AperFunction) -> Self {
+        self.function = Some(function);
+        self
+    }
+
+    fn aperture_key(&self) -> Option<(f32, Option<AperFunction>)> {
+        match self.shape {
+            Shape::Flash { diameter, .. } => Some((diameter, self.function)),
+            Shape::Stroke { width, .. } | Shape::Circle { width, .. } => Some((width, self.function)),
+            Shape::Region(_) => None,
+        }
+    }
+}
+
+/// Gerber coordinate in format 4.6 of a fabrication point
+fn coordinate(point: Point) -> String {
+    format!("X{}Y{}", gerber_fixed(point.x), gerber_fixed(point.y))
+}
+
+/// An attribute field with the characters Gerber reserves escaped as `\uXXXX`
+fn attribute_field(value: &str) -> String {
+    let mut field = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            ',' | '*' | '%' | '\\' => write!(field, "\\u{:04X}", c as u32).unwrap(),
+            _ => field.push(c),
+        }
+    }
+    field
+}
+
+/// Objects of one placed footprint's pads on `local_layer`, the footprint's own name for
+/// the board layer being written
+pub(crate) fn pad_objects(
+    board: &Board,
+    placed: &PlacedComponent,
+    local_layer: &str,
+    arc_tolerance: f32,
+    origin: FabOrigin,
+) -> Vec<GerberObject> {
+    let to_fab = |point: Point| board.fab_point(board.board_point(placed, point), origin);
+    // Paste and mask sit on the outer copper of their side, which a padstack may shape differently
+    let geometry_layer = if local_layer.ends_with(".Cu") {
+        local_layer
+    } else if local_layer.starts_with("B.") {
+        "B.Cu"
+    } else {
+        "F.Cu"
+    };
+    // A bare non-plated hole has no copper to flash, and holes never take paste
+    let written = |pad: &&PadDescriptor| {
+        pad.has_layer(local_layer)
+            && (!local_layer.ends_with(".Cu") || pad.has_copper())
+            && !(local_layer.ends_with(".Paste") && pad.is_npth())
+    };
+    let mut objects = Vec::new();
+    for pad in placed.component.pad_descriptors().iter().filter(written) {
+        let shaped = pad.on_layer(geometry_layer);
+        let shape = if matches!(shaped.shape, PadShape::Circle) {
+            Shape::Flash { center: to_fab(Point::from(shaped.position)), diameter: shaped.size.0 }
+        } else {
+            Shape::Region(shaped.outline_polygon(arc_tolerance).into_iter().map(to_fab).collect())
+        };
+        objects.push(GerberObject {
+            shape,
+            function: Some(AperFunction::of_pad(pad)),
+            pad: Some(PadRef {
+                reference: placed.reference.clone(),
+                number: pad.number.to_string(),
+                pin_function: pad.pin_function.clone(),
+            }),
+        });
+    }
+    objects
+}
+
+/// Board side a footprint element lands on; back side placements are mirrored
+pub(crate) fn board_side(placed: &PlacedComponent, element: &GraphicElement) -> Side {
+    match (placed.side, element.side) {
+        (Side::Back, Side::Front) => Side::Back,
+        (Side::Back, Side::Back) => Side::Front,
+        (_, side) => side,
+    }
+}
+
+/// Objects of the footprint graphics drawn on `layer` that land on `side` of the board
+pub(crate) fn graphic_objects(
+    board: &Board,
+    placed: &PlacedComponent,
+    layer: LayerType,
+    side: Side,
+    function: Option<AperFunction>,
+    origin: FabOrigin,
+) -> Vec<GerberObject> {
+    let to_fab = |point: (f32, f32)| board.fab_point(board.board_point(placed, Point::from(point)), origin);
+    placed
+        .component
+        .graphic_elements()
+        .iter()
+        .filter(|element| element.layer == layer && board_side(placed, element) == side)
+        .flat_map(|element| element_shapes(element, to_fab))
+        .map(|shape| GerberObject { shape, function, pad: None })
+        .collect()
+}
+
+/// Objects of the board level graphics drawn on `layer` of `side`
+pub(crate) fn board_graphic_objects(
+    board: &Board,
+    layer: LayerType,
+    side: Side,
+    function: Option<AperFunction>,
+    origin: FabOrigin,
+) -> Vec<GerberObject> {
+    let to_fab = |point: (f32, f32)| board.fab_point(Point::from(point), origin);
+    board
+        .graphics()
+        .iter()
+        .filter(|element| element.layer == layer && element.side == side)
+        .flat_map(|element| element_shapes(element, to_fab))
+        .map(|shape| GerberObject { shape, function, pad: None })
+        .collect()
+}
+
+/// Shapes drawing one graphic element, its points taken to fabrication coordinates by
+/// `to_fab`. Solid polygons are a region plus their outline stroke, which a zero width
+/// leaves out.
+pub(crate) fn element_shapes(element: &GraphicElement, to_fab: impl Fn((f32, f32)) -> Point) -> Vec<Shape> {
+    let width = element.stroke.width;
+    let mut shapes = Vec::new();
+    match element.element_type {
+        GraphicType::Line { start, end } => {
+            shapes.push(Shape::Stroke { points: vec![to_fab(start), to_fab(end)], width });
+        }
+        GraphicType::Rectangle { ref bounds } => {
+            let corners = [
+                (bounds.min_x, bounds.min_y),
+                (bounds.max_x, bounds.min_y),
+                (bounds.max_x, bounds.max_y),
+                (bounds.min_x, bounds.max_y),
+                (bounds.min_x, bounds.min_y),
+            ];
+            shapes.push(Shape::Stroke { points: corners.into_iter().map(&to_fab).collect(), width });
+        }
+        GraphicType::Circle { center, radius } => {
+            shapes.push(Shape::Circle { center: to_fab(center), radius, width });
+        }
+        GraphicType::Polygon { ref points, fill } => {
+            let solid = fill == Some(FillStyle::Solid);
+            if solid {
+                shapes.push(Shape::Region(points.iter().map(|point| to_fab(*point)).collect()));
+            }
+            if !solid || width > 0.0 {
+                let closed = points.iter().chain(points.first()).map(|point| to_fab(*point)).collect();
+                shapes.push(Shape::Stroke { points: closed, width });
+            }
+            for line in expand_hatch(element).iter().skip(1) {
+                if let GraphicType::Line { start, end } = line.element_type {
+                    shapes.push(Shape::Stroke { points: vec![to_fab(start), to_fab(end)], width: line.stroke.width });
+                }
+            }
+        }
+        // Measurement callouts belong on the fab drawing
+        GraphicType::Dimension(_) => {}
+    }
+    shapes
+}
+
+/// Whether drawn objects add material (copper, paste) or take it away. Solder mask
+/// Gerbers are negative: what is drawn is an opening in the mask.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FilePolarity {
+    Positive,
+    Negative,
+}
+
+/// Write a complete layer. `file_function` is the .FileFunction value, e.g. "Paste,Top".
+/// Objects are always drawn dark (%LPD), `polarity` only sets the .FilePolarity
+/// attribute that tells CAM tools how to read them.
+pub(crate) fn write_layer(file_function: &str, polarity: FilePolarity, objects: &[GerberObject], dialect: GerberDialect) -> String {
+    let x2 = dialect == GerberDialect::X2;
+    // Apertures in order of first use, one per size and, with attributes, per function
+    let mut apertures: Vec<(f32, Option<AperFunction>)> = Vec::new();
+    let same = |a: &(f32, Option<AperFunction>), b: &(f32, Option<AperFunction>)| {
+        (a.0 - b.0).abs() < APERTURE_TOLERANCE && (!x2 || a.1 == b.1)
+    };
+    for key in objects.iter().filter_map(GerberObject::aperture_key) {
+        if !apertures.iter().any(|existing| same(existing, &key)) {
+            apertures.push(key);
+        }
+    }
+    let aperture = |key: (f32, Option<AperFunction>)| {
+        FIRST_APERTURE + apertures.iter().position(|existing| same(existing, &key)).unwrap_or(0) as u32
+    };
+
+    let mut output = String::new();
+    if x2 {
+        writeln!(output, "%TF.GenerationSoftware,Atlantix-EDA,copper-substrate,{}*%", env!("CARGO_PKG_VERSION")).unwrap();
+        writeln!(output, "%TF.FileFunction,{}*%", file_function).unwrap();
+        let polarity = if polarity == FilePolarity::Negative { "Negative" } else { "Positive" };
+        writeln!(output, "%TF.FilePolarity,{}*%", polarity).unwrap();
+        writeln!(output, "%TF.Part,Single*%").unwrap();
+    } else {
+        writeln!(output, "G04 copper-substrate {}, {}*", env!("CARGO_PKG_VERSION"), file_function).unwrap();
+    }
+    writeln!(output, "%FSLAX46Y46*%").unwrap();
+    writeln!(output, "%MOMM*%").unwrap();
+    writeln!(output, "%LPD*%").unwrap();
+    writeln!(output, "G75*").unwrap();
+    let mut defining = None;
+    for (index, (diameter, function)) in apertures.iter().enumerate() {
+        if x2 && *function != defining {
+            match function {
+                Some(function) => writeln!(output, "%TA.AperFunction,{}*%", function.attribute()).unwrap(),
+                None => writeln!(output, "%TD.AperFunction*%").unwrap(),
+            }
+            defining = *function;
+        }
+        writeln!(output, "%ADD{}C,{:.6}*%", FIRST_APERTURE + index as u32, diameter).unwrap();
+    }
+    if defining.is_some() {
+        writeln!(output, "%TD.AperFunction*%").unwrap();
+    }
+
+    let mut current = None;
+    for object in objects {
+        // Regions take the aperture attributes current when they are drawn
+        let region_function = match object.shape {
+            Shape::Region(_) if x2 => object.function,
+            _ => None,
+        };
+        if let Some(function) = region_function {
+            writeln!(output, "%TA.AperFunction,{}*%", function.attribute()).unwrap();
+        }
+        let pad = object.pad.as_ref().filter(|_| x2);
+        if let Some(pad) = pad {
+            let reference = attribute_field(&pad.reference);
+            writeln!(output, "%TO.C,{}*%", reference).unwrap();
+            // Unnumbered pads such as fiducials belong to the component but are no pin
+            match &pad.pin_function {
+                _ if pad.number.is_empty() => {}
+                Some(function) => {
+                    writeln!(output, "%TO.P,{},{},{}*%", reference, attribute_field(&pad.number), attribute_field(function))
+                        .unwrap()
+                }
+                None => writeln!(output, "%TO.P,{},{}*%", reference, attribute_field(&pad.number)).unwrap(),
+            }
+        }
+        if let Some(key) = object.aperture_key() {
+            let number = aperture(key);
+            if current != Some(number) {
+                writeln!(output, "D{}*", number).unwrap();
+                current = Some(number);
+            }
+        }
+        match &object.shape {
+            Shape::Flash { center, .. } => {
+                writeln!(output, "{}D03*", coordinate(*center)).unwrap();
+            }
+            Shape::Region(outline) => {
+                if let Some(first) = outline.first() {
+                    writeln!(output, "G36*").unwrap();
+                    writeln!(output, "{}D02*", coordinate(*first)).unwrap();
+                    writeln!(output, "G01*").unwrap();
+                    for point in outline.iter().skip(1).chain([first]) {
+                        writeln!(output, "{}D01*", coordinate(*point)).unwrap();
+                    }
+                    writeln!(output, "G37*").unwrap();
+                }
+            }
+            Shape::Stroke { points, .. } => {
+                writeln!(output, "G01*").unwrap();
+                for (index, point) in points.iter().enumerate() {
+                    writeln!(output, "{}{}*", coordinate(*point), if index == 0 { "D02" } else { "D01" }).unwrap();
+                }
+            }
+            Shape::Circle { center, radius, .. } => {
+                let start = center.offset(*radius, 0.0);
+                writeln!(output, "{}D02*", coordinate(start)).unwrap();
+                // Full counter-clockwise circle back to the start, offset to the center is -radius
+                writeln!(output, "G03*").unwrap();
+                writeln!(output, "{}I{}J0D01*", coordinate(start), gerber_fixed(-*radius)).unwrap();
+            }
+        }
+        if pad.is_some() || region_function.is_some() {
+            writeln!(output, "%TD*%").unwrap();
+        }
+    }
+    writeln!(output, "M02*").unwrap();
+    output
+}
+
+/// .FileFunction of a copper layer, numbered from the top of the board's copper layers
+fn copper_file_function(board: &Board, layer: BoardLayer) -> String {
+    let layers = board.copper_layers();
+    let number = layers.iter().position(|candidate| *candidate == layer).map_or(1, |index| index + 1);
+    let position = match layer.side {
+        Side::Front => "Top",
+        Side::Back => "Bot",
+        Side::Inner(_) => "Inr",
+    };
+    format!("Copper,L{},{}", number, position)
+}
+
+/// Gerber for one copper layer: pads, vias spanning it, tracks on it and footprint and
+/// board graphics drawn on it. Back side placements are mirrored, so their F.Cu lands on
+/// B.Cu.
+pub fn to_copper_gerber(board: &Board, layer: BoardLayer, arc_tolerance: f32, origin: FabOrigin, dialect: GerberDialect) -> String {
+    let mut objects = Vec::new();
+    for placed in board.components() {
+        let is_back = placed.side == Side::Back;
+        let local = match layer.side {
+            Side::Front if is_back => BoardLayer::back(LayerType::Copper),
+            Side::Back if is_back => BoardLayer::front(LayerType::Copper),
+            _ => layer,
+        };
+        objects.extend(pad_objects(board, placed, &local.to_kicad_string(), arc_tolerance, origin));
+        objects.extend(graphic_objects(board, placed, LayerType::Copper, layer.side, Some(AperFunction::NonConductor), origin));
+    }
+    objects.extend(board_graphic_objects(board, LayerType::Copper, layer.side, Some(AperFunction::NonConductor), origin));
+    for via in board.vias().iter().filter(|via| via.spans(&layer)) {
+        let center = board.fab_point(via.position, origin);
+        objects.push(GerberObject::new(Shape::Flash { center, diameter: via.diameter }).with_function(AperFunction::ViaPad));
+    }
+    for track in board.tracks().iter().filter(|track| track.layer == layer) {
+        let points = vec![board.fab_point(track.start, origin), board.fab_point(track.end, origin)];
+        objects.push(GerberObject::new(Shape::Stroke { points, width: track.width }).with_function(AperFunction::Conductor));
+    }
+    // Arc tracks are drawn as linear strokes along the arc
+    for arc in board.arcs().iter().filter(|arc| arc.layer == layer) {
+        let chords = arc.chords(arc_tolerance);
+        let points = chords.iter().map(|(start, _)| *start).chain([arc.end]).map(|point| board.fab_point(point, origin)).collect();
+        objects.push(GerberObject::new(Shape::Stroke { points, width: arc.width }).with_function(AperFunction::Conductor));
+    }
+    write_layer(&copper_file_function(board, layer), FilePolarity::Positive, &objects, dialect)
+}
+
+/// Writes one copper layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CopperBoardExporter {
+    pub layer: BoardLayer,
+    /// Absolute board coordinates unless set to the auxiliary origin
+    pub origin: FabOrigin,
+    pub dialect: GerberDialect,
+}
+
+impl CopperBoardExporter {
+    pub fn new(layer: BoardLayer) -> Self {
+        Self { layer, origin: FabOrigin::Absolute, dialect: GerberDialect::default() }
+    }
+
+    pub fn with_origin(mut self, origin: FabOrigin) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Write plain RS-274X, or X2 with attributes (the default)
+    pub fn with_dialect(mut self, dialect: GerberDialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+}
+
+impl Default for CopperBoardExporter {
+    fn default() -> Self {
+        Self::new(BoardLayer::front(LayerType::Copper))
+    }
+}
+
+impl BoardExporter for CopperBoardExporter {
+    fn name(&self) -> &str {
+        "copper"
+    }
+
+    /// Protel names for the outer layers, "gbr" for inner ones
+    fn file_extension(&self) -> &str {
+        match self.layer.side {
+            Side::Front => "gtl",
+            Side::Back => "gbl",
+            Side::Inner(_) => "gbr",
+        }
+    }
+
+    fn export(&self, board: &Board, options: &ExportOptions) -> Result<Vec<u8>, ExportError> {
+        Ok(to_copper_gerber(board, self.layer, options.arc_tolerance, self.origin, self.dialect).into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use copper_substrate::routing::Via;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::stencil_export::to_paste_gerber;
+
+    #[derive(BoardComponent)]
+    #[footprint(name = "TestPoint_Pad_1.0x0.6mm", library = "TestPoint", kind = "Connector", smt)]
+    #[pad(number = "1", position = (0.0, 0.0), size = (1.0, 0.6), shape = "rect")]
+    struct SmdPad(#[value] &'static str);
+
+    /// 1 mm copper dot with no number, marked as a board fiducial
+    struct Fiducial;
+
+    impl BoardComposableObject for Fiducial {
+        fn is_smt(&self) -> bool {
+            true
+        }
+        fn is_electrical(&self) -> bool {
+            false
+        }
+        fn terminal_count(&self) -> usize {
+            0
+        }
+        fn functional_type(&self) -> FunctionalType {
+            FunctionalType::Fiducial("Board".to_string())
+        }
+        fn footprint_name(&self) -> String {
+            "Fiducial_1mm_Mask2mm".to_string()
+        }
+        fn library_name(&self) -> String {
+            "Fiducial".to_string()
+        }
+        fn bounding_box(&self) -> Rectangle {
+            Rectangle { min_x: -0.5, min_y: -0.5, max_x: 0.5, max_y: 0.5 }
+        }
+        fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+            vec![PadDescriptor {
+                number: PadNumber::default(),
+                pin_function: None,
+                pad_type: PadType::SMD,
+                shape: PadShape::Circle,
+                position: (0.0, 0.0),
+                rotation: None,
+                size: (1.0, 1.0),
+                drill_size: None,
+                drill_slot: None,
+                layers: vec!["F.Cu".into(), "F.Mask".into()],
+                roundrect_ratio: None,
+                tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+                padstack: None,
+                fab_property: Some(PadFabProperty::GlobalFiducial),
+                solder_mask_margin: None,
+                routing_hint: None,
+                uuid: Uuid::new_v4(),
+            }]
+        }
+        fn fp_text_elements(&self) -> Vec<FpText> {
+            vec![]
+        }
+        fn graphic_elements(&self) -> Vec<GraphicElement> {
+            vec![]
+        }
+        fn model_3d(&self) -> Option<Model3D> {
+            None
+        }
+    }
+
+    /// One drawn object: operation, aperture diameter ("region" for regions) and coordinates
+    type Drawn = (char, String, String);
+
+    /// Read a layer as a viewer would, returning what it draws, or what the reader rejected
+    fn read_gerber(text: &str) -> Result<Vec<Drawn>, String> {
+        let mut apertures: HashMap<String, String> = HashMap::new();
+        let mut current: Option<String> = None;
+        let mut in_region = false;
+        let mut drawn = Vec::new();
+        let mut ended = false;
+        let coordinate = |word: &str| {
+            let body = word.trim_end_matches("D01").trim_end_matches("D02").trim_end_matches("D03");
+            body.starts_with('X') && body[1..].split(['Y', 'I', 'J']).all(|n| n.parse::<i64>().is_ok())
+        };
+        for line in text.lines() {
+            if ended {
+                return Err(format!("{:?} after M02", line));
+            }
+            if let Some(extended) = line.strip_prefix('%') {
+                let command = extended.strip_suffix("*%").ok_or_else(|| format!("unterminated {:?}", line))?;
+                if let Some(definition) = command.strip_prefix("ADD") {
+                    let (number, template) = definition.split_once('C').ok_or_else(|| format!("unknown aperture {:?}", line))?;
+                    let diameter = template.strip_prefix(',').ok_or_else(|| format!("bad aperture {:?}", line))?;
+                    diameter.parse::<f64>().map_err(|_| format!("bad diameter {:?}", line))?;
+                    apertures.insert(format!("D{}", number), diameter.to_string());
+                } else if command.starts_with("TF.") || command.starts_with("TA.") || command.starts_with("TO.") {
+                    if !command.contains(',') {
+                        return Err(format!("attribute without a value {:?}", line));
+                    }
+                } else if !(["FSLAX46Y46", "MOMM", "LPD", "TD"].contains(&command) || command.starts_with("TD.")) {
+                    return Err(format!("unknown extended command {:?}", line));
+                }
+                continue;
+            }
+            let word = line.strip_suffix('*').ok_or_else(|| format!("unterminated {:?}", line))?;
+            match word {
+                "G75" | "G01" | "G03" => {}
+                "G36" if !in_region => in_region = true,
+                "G37" if in_region => in_region = false,
+                "M02" => ended = true,
+                _ if word.starts_with("G04 ") => {}
+                _ if word.starts_with('D') && word[1..].parse::<u32>().is_ok_and(|n| n >= 10) => {
+                    if !apertures.contains_key(word) {
+                        return Err(format!("aperture {} used before it is defined", word));
+                    }
+                    current = Some(word.to_string());
+                }
+                _ if coordinate(word) => {
+                    let operation = word.chars().last().unwrap_or_default();
+                    let aperture = match (&current, in_region) {
+                        (_, true) => "region".to_string(),
+                        (Some(aperture), false) => apertures[aperture].clone(),
+                        (None, false) if operation == '2' => String::new(),
+                        (None, false) => return Err(format!("{:?} draws with no aperture", line)),
+                    };
+                    drawn.push((operation, aperture, word[..word.len() - 3].to_string()));
+                }
+                _ => return Err(format!("unknown command {:?}", line)),
+            }
+        }
+        if !ended || in_region {
+            return Err("layer does not end with M02 outside a region".to_string());
+        }
+        Ok(drawn)
+    }
+
+    /// Attribute lines only, the part X2 adds
+    fn attributes(text: &str) -> Vec<&str> {
+        text.lines().filter(|line| line.starts_with("%T")).collect()
+    }
+
+    fn board() -> Board {
+        let mut board = Board::new("x2").with_rectangular_outline(20.0, 10.0);
+        board.place("TP1", SmdPad("SIG"), (5.0, 5.0), 0.0).unwrap();
+        board.place("FID1", Fiducial, (15.0, 5.0), 0.0).unwrap();
+        board.add_via(Via::new("GND", Point::new(10.0, 5.0), 0.6, 0.3));
+        board
+    }
+
+    fn copper(board: &Board, layer: BoardLayer, dialect: GerberDialect) -> String {
+        to_copper_gerber(board, layer, 0.01, FabOrigin::Absolute, dialect)
+    }
+
+    #[test]
+    fn top_copper_carries_file_aperture_and_object_attributes() {
+        let top = copper(&board(), BoardLayer::front(LayerType::Copper), GerberDialect::X2);
+        for line in [
+            "%TF.FileFunction,Copper,L1,Top*%",
+            "%TF.FilePolarity,Positive*%",
+            "%TF.Part,Single*%",
+            "%TA.AperFunction,FiducialPad,Global*%",
+            "%TA.AperFunction,ViaPad*%",
+            "%TD.AperFunction*%",
+            "%TA.AperFunction,SMDPad,CuDef*%",
+            "%TO.C,TP1*%",
+            "%TO.P,TP1,1*%",
+            "%TO.C,FID1*%",
+            "%TD*%",
+        ] {
+            assert!(top.lines().any(|candidate| candidate == line), "{} missing from\n{}", line, top);
+        }
+        // The fiducial has no number, so it is no pin
+        assert!(!top.contains("%TO.P,FID1"));
+    }
+
+    #[test]
+    fn bottom_copper_and_paste_only_carry_what_is_on_them() {
+        let board = board();
+        let bottom = copper(&board, BoardLayer::back(LayerType::Copper), GerberDialect::X2);
+        assert!(bottom.contains("%TF.FileFunction,Copper,L2,Bot*%") && bottom.contains("%TA.AperFunction,ViaPad*%"));
+        assert!(!bottom.contains("SMDPad") && !bottom.contains("Fiducial"));
+
+        let paste = to_paste_gerber(&board, Side::Front, 0.01, FabOrigin::Absolute, GerberDialect::X2);
+        assert!(paste.contains("%TF.FileFunction,Paste,Top*%") && paste.contains("%TO.P,TP1,1*%"));
+        assert!(!paste.contains("ViaPad") && !paste.contains("FID1"));
+    }
+
+    #[test]
+    fn x2_and_plain_rs274x_read_alike() {
+        let board = board();
+        let (front, back) = (BoardLayer::front(LayerType::Copper), BoardLayer::back(LayerType::Copper));
+        for (name, x2, plain) in [
+            ("F.Cu", copper(&board, front, GerberDialect::X2), copper(&board, front, GerberDialect::Rs274x)),
+            ("B.Cu", copper(&board, back, GerberDialect::X2), copper(&board, back, GerberDialect::Rs274x)),
+            (
+                "F.Paste",
+                to_paste_gerber(&board, Side::Front, 0.01, FabOrigin::Absolute, GerberDialect::X2),
+                to_paste_gerber(&board, Side::Front, 0.01, FabOrigin::Absolute, GerberDialect::Rs274x),
+            ),
+        ] {
+            let with_attributes = read_gerber(&x2).unwrap_or_else(|e| panic!("{} X2: {}", name, e));
+            let without = read_gerber(&plain).unwrap_or_else(|e| panic!("{} RS-274X: {}", name, e));
+            assert!(attributes(&plain).is_empty(), "{} RS-274X has attributes:\n{}", name, plain);
+            assert_eq!(with_attributes, without, "{} draws differently without attributes", name);
+            assert!(!with_attributes.is_empty());
+        }
+    }
+}
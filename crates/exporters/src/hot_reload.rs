@@ -0,0 +1,194 @@
+//! Hot reloading of TOML footprint definitions
+//!
+//! `DefinitionWatcher` watches a directory with notify and reports a definition file once
+//! its events have been quiet for the debounce delay, so an editor's save (often a
+//! truncate, a write and a rename) reloads once. `ReloadPipeline` regenerates the
+//! footprint of each reported file and rewrites its `.kicad_mod`. A file that fails to
+//! read, parse, validate or export keeps its last good footprint and carries the error
+//! until the next successful reload.
+//!
+//! The pipeline and the `Debouncer` take paths and instants rather than events, so both
+//! run without a watcher or a window.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use copper_substrate::prelude::*;
+use notify::{RecursiveMode, Watcher};
+
+use crate::footprint_definition::read_definition;
+use crate::kicad_pcb_export::try_to_kicad_footprint;
+
+/// Extension of footprint definition files
+pub const DEFINITION_EXTENSION: &str = "toml";
+/// Quiet time after the last event before a file is reloaded
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(250);
+
+pub fn is_definition_file(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == DEFINITION_EXTENSION)
+}
+
+/// Collects changed paths and releases each once no event has touched it for `delay`
+#[derive(Debug, Clone)]
+pub struct Debouncer {
+    delay: Duration,
+    pending: BTreeMap<PathBuf, Instant>,
+}
+
+impl Debouncer {
+    pub fn new(delay: Duration) -> Self {
+        Self { delay, pending: BTreeMap::new() }
+    }
+
+    /// Record an event on `path` at `now`, restarting its quiet period
+    pub fn touch(&mut self, path: PathBuf, now: Instant) {
+        self.pending.insert(path, now);
+    }
+
+    /// Paths quiet since `now - delay`, removed from the pending set, in path order
+    pub fn ready(&mut self, now: Instant) -> Vec<PathBuf> {
+        let ready: Vec<PathBuf> = self
+            .pending
+            .iter()
+            .filter(|(_, last)| now.duration_since(**last) >= self.delay)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in &ready {
+            self.pending.remove(path);
+        }
+        ready
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// Watches a directory for changed definition files
+pub struct DefinitionWatcher {
+    // Dropping the watcher stops the events
+    _watcher: notify::RecommendedWatcher,
+    events: mpsc::Receiver<notify::Result<notify::Event>>,
+    debouncer: Debouncer,
+}
+
+impl DefinitionWatcher {
+    pub fn new(dir: &Path, delay: Duration) -> notify::Result<Self> {
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        Ok(Self { _watcher: watcher, events, debouncer: Debouncer::new(delay) })
+    }
+
+    /// Definition files whose changes have settled, plus any watcher errors. Call
+    /// regularly; nothing is reported between calls.
+    pub fn poll(&mut self) -> (Vec<PathBuf>, Vec<notify::Error>) {
+        let now = Instant::now();
+        let mut errors = Vec::new();
+        for event in self.events.try_iter() {
+            match event {
+                Ok(event) if !event.kind.is_access() => {
+                    for path in event.paths.into_iter().filter(|path| is_definition_file(path)) {
+                        self.debouncer.touch(path, now);
+                    }
+                }
+                Ok(_) => {}
+                Err(error) => errors.push(error),
+            }
+        }
+        (self.debouncer.ready(now), errors)
+    }
+
+    /// Whether changes are waiting for their quiet period to pass
+    pub fn has_pending(&self) -> bool {
+        !self.debouncer.is_idle()
+    }
+}
+
+/// State of one definition file
+pub struct LoadedDefinition {
+    /// The last footprint that loaded and exported cleanly
+    pub footprint: Option<Box<dyn BoardComposableObject>>,
+    /// Why the latest reload failed, if it did
+    pub error: Option<String>,
+    /// The `.kicad_mod` written for `footprint`
+    pub written: Option<PathBuf>,
+    /// Number of successful loads
+    pub generation: u32,
+}
+
+/// Outcome of reloading one file
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReloadOutcome {
+    /// Regenerated and written to the path
+    Updated(PathBuf),
+    /// Failed; the last good footprint, if any, stays in place
+    Failed(String),
+    /// The file is gone and was dropped from the pipeline
+    Removed,
+}
+
+/// Regenerates footprints from definition files and writes them to `output_dir`
+pub struct ReloadPipeline {
+    output_dir: PathBuf,
+    definitions: BTreeMap<PathBuf, LoadedDefinition>,
+}
+
+impl ReloadPipeline {
+    pub fn new(output_dir: &Path) -> Self {
+        Self { output_dir: output_dir.to_path_buf(), definitions: BTreeMap::new() }
+    }
+
+    /// Load every definition file in `dir`, as on startup
+    pub fn load_dir(&mut self, dir: &Path) -> std::io::Result<Vec<(PathBuf, ReloadOutcome)>> {
+        let mut paths: Vec<PathBuf> =
+            fs::read_dir(dir)?.filter_map(|entry| entry.ok().map(|entry| entry.path())).filter(|p| is_definition_file(p)).collect();
+        paths.sort();
+        Ok(paths.into_iter().map(|path| (path.clone(), self.reload(&path))).collect())
+    }
+
+    /// Regenerate the footprint of `path` and rewrite its `.kicad_mod`
+    pub fn reload(&mut self, path: &Path) -> ReloadOutcome {
+        if !path.exists() {
+            self.definitions.remove(path);
+            return ReloadOutcome::Removed;
+        }
+        let result = read_definition(path).map_err(|e| e.to_string()).and_then(|footprint| {
+            let content = try_to_kicad_footprint(footprint.as_ref()).map_err(|e| e.to_string())?;
+            let file = self.output_dir.join(format!("{}.kicad_mod", footprint.footprint_name()));
+            fs::create_dir_all(&self.output_dir).and_then(|_| fs::write(&file, content)).map_err(|e| e.to_string())?;
+            Ok((footprint, file))
+        });
+        let entry = self.definitions.entry(path.to_path_buf()).or_insert_with(|| LoadedDefinition {
+            footprint: None,
+            error: None,
+            written: None,
+            generation: 0,
+        });
+        match result {
+            Ok((footprint, file)) => {
+                entry.footprint = Some(footprint);
+                entry.error = None;
+                entry.written = Some(file.clone());
+                entry.generation += 1;
+                ReloadOutcome::Updated(file)
+            }
+            Err(error) => {
+                entry.error = Some(error.clone());
+                ReloadOutcome::Failed(error)
+            }
+        }
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&LoadedDefinition> {
+        self.definitions.get(path)
+    }
+
+    /// All loaded definition files in path order
+    pub fn definitions(&self) -> impl Iterator<Item = (&Path, &LoadedDefinition)> {
+        self.definitions.iter().map(|(path, definition)| (path.as_path(), definition))
+    }
+}
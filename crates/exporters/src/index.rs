@@ -0,0 +1,260 @@
+//! Search index of generated footprints
+//!
+//! A flat, tokenized record of each footprint for part search: name, library, tags,
+//! functional type and value, pad count and body size, plus the lower case words of the
+//! text fields. `build` makes the index, `SearchIndex::to_json` writes it for other tools, and
+//! `SearchQuery` filters it by name prefix, tags, words and body size.
+//!
+//! Tags are normalized as the KiCad export writes them (see `describe::normalize_tags`),
+//! so a tag filter finds a footprint whatever case or quoting its tags were written in.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use copper_substrate::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// One footprint in the index
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub name: String,
+    pub library: String,
+    /// Normalized tags in their written order
+    pub tags: Vec<String>,
+    /// Functional type without its value, e.g. "Resistor"
+    pub functional_type: String,
+    /// Functional type value, e.g. "10k"
+    pub value: String,
+    /// Pads with copper, so paste windows and the like aren't counted
+    pub pad_count: usize,
+    /// Body width (X) and height (Y) in mm
+    pub body_size: (f32, f32),
+    /// Lower case words of the name, library, functional type, value and tags, without
+    /// repeats
+    pub tokens: Vec<String>,
+}
+
+impl IndexEntry {
+    pub fn new<T: BoardComposableObject + ?Sized>(footprint: &T) -> Self {
+        let functional_type = footprint.functional_type();
+        let body = footprint.bounding_box();
+        let mut entry = Self {
+            name: footprint.footprint_name(),
+            library: footprint.library_name(),
+            tags: footprint.tags().map(|tags| tag_list(&tags)).unwrap_or_default(),
+            functional_type: functional_type.kind_name().to_string(),
+            value: functional_type.value().to_string(),
+            pad_count: footprint.pad_descriptors().iter().filter(|pad| pad.has_copper()).count(),
+            body_size: (body.width(), body.height()),
+            tokens: Vec::new(),
+        };
+        let text = [&entry.name, &entry.library, &entry.functional_type, &entry.value].map(String::as_str).join(" ");
+        for token in tokenize(&text).chain(entry.tags.iter().cloned()) {
+            if !entry.tokens.contains(&token) {
+                entry.tokens.push(token);
+            }
+        }
+        entry
+    }
+
+    /// The longer side of the body
+    pub fn body_length(&self) -> f32 {
+        self.body_size.0.max(self.body_size.1)
+    }
+}
+
+/// Each tag of `tags`, normalized
+fn tag_list(tags: &str) -> Vec<String> {
+    normalize_tags(tags).split(' ').filter(|tag| !tag.is_empty()).map(str::to_string).collect()
+}
+
+/// Lower case runs of letters, digits and inner dots, so "R_0805_2012Metric" gives "r",
+/// "0805" and "2012metric", and "P0.5mm" stays whole
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric() && c != '.')
+        .map(|word| word.trim_matches('.').to_lowercase())
+        .filter(|word| !word.is_empty())
+}
+
+/// Index every footprint of `footprints`, in order
+pub fn build(footprints: &[&dyn BoardComposableObject]) -> SearchIndex {
+    SearchIndex { entries: footprints.iter().map(|footprint| IndexEntry::new(*footprint)).collect() }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchIndex {
+    pub entries: Vec<IndexEntry>,
+}
+
+impl SearchIndex {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("index entries are plain data")
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(text)
+    }
+
+    pub fn write_to(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_json())
+    }
+
+    /// Entries matching every condition of `query`, in index order
+    pub fn search(&self, query: &SearchQuery) -> Vec<&IndexEntry> {
+        self.entries.iter().filter(|entry| query.matches(entry)).collect()
+    }
+}
+
+/// Conditions an entry must all meet; the empty query matches everything
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchQuery {
+    /// Start of the name, ignoring case
+    pub name_prefix: Option<String>,
+    /// Tags the entry must all have, normalized
+    pub tags: Vec<String>,
+    /// Words each starting one of the entry's tokens
+    pub words: Vec<String>,
+    /// Inclusive range (mm) of the longer body side
+    pub body_length: Option<(f32, f32)>,
+}
+
+impl SearchQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_name_prefix(mut self, prefix: &str) -> Self {
+        self.name_prefix = Some(prefix.to_lowercase());
+        self
+    }
+
+    /// Require `tag`, normalized as the index is, so "QFN" and "\"qfn\"" find "qfn"
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tags.extend(tag_list(tag));
+        self
+    }
+
+    /// Require a token starting with each word of `text`
+    pub fn with_words(mut self, text: &str) -> Self {
+        self.words.extend(tokenize(text));
+        self
+    }
+
+    /// Longer body side from `min` to `max` mm
+    pub fn with_body_length(mut self, min: f32, max: f32) -> Self {
+        self.body_length = Some((min, max));
+        self
+    }
+
+    pub fn matches(&self, entry: &IndexEntry) -> bool {
+        self.name_prefix.as_ref().is_none_or(|prefix| entry.name.to_lowercase().starts_with(prefix))
+            && self.tags.iter().all(|tag| entry.tags.contains(tag))
+            && self.words.iter().all(|word| entry.tokens.iter().any(|token| token.starts_with(word.as_str())))
+            && self.body_length.is_none_or(|(min, max)| (min..=max).contains(&entry.body_length()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 30 chips, 7 SOICs, 4 QFNs, 8 DIPs and a mounting hole
+    fn footprints() -> Vec<Box<dyn BoardComposableObject>> {
+        let mut footprints: Vec<Box<dyn BoardComposableObject>> = Vec::new();
+        for size in ["0201", "0402", "0603", "0805", "1206", "1210"] {
+            for kind in [
+                FunctionalType::Resistor("10k".to_string()),
+                FunctionalType::Capacitor("100n".to_string()),
+                FunctionalType::Inductor("4u7".to_string()),
+                FunctionalType::LED("red".to_string()),
+                FunctionalType::FerriteBead("600R".to_string()),
+            ] {
+                footprints.push(Box::new(ChipFootprint::standard(kind, size).unwrap()));
+            }
+        }
+        for (code, pins) in [
+            ("MS-012-AA", 8),
+            ("MS-012-AB", 14),
+            ("MS-012-AC", 16),
+            ("MS-013-AA", 16),
+            ("MS-013-AC", 20),
+            ("MS-013-AD", 24),
+            ("MS-013-AE", 28),
+        ] {
+            footprints.push(Box::new(Soic::from_jedec(code, pins).unwrap()));
+        }
+        for (code, pins) in [("MO-220-3x3-16", 16), ("MO-220-4x4-16", 16), ("MO-220-4x4-24", 24), ("MO-220-5x5-32", 32)] {
+            footprints.push(Box::new(Qfn::from_jedec(code, pins).unwrap()));
+        }
+        for pins in [4, 6, 8, 14, 16, 18, 20, 24] {
+            footprints.push(Box::new(Dip::new(pins).unwrap()));
+        }
+        footprints.push(Box::new(MountingHole::new(3.2)));
+        footprints
+    }
+
+    fn index() -> SearchIndex {
+        let footprints = footprints();
+        let references: Vec<&dyn BoardComposableObject> = footprints.iter().map(Box::as_ref).collect();
+        build(&references)
+    }
+
+    fn names<'a>(entries: &[&'a IndexEntry]) -> Vec<&'a str> {
+        entries.iter().map(|entry| entry.name.as_str()).collect()
+    }
+
+    #[test]
+    fn indexes_every_footprint() {
+        let index = index();
+        assert_eq!(index.entries.len(), 50);
+        let soic = &index.entries[30];
+        assert_eq!(soic.name, "SOIC-8_3.9x4.9mm_P1.27mm");
+        assert_eq!(soic.library, "Package_SO");
+        assert_eq!(soic.functional_type, "IntegratedCircuit");
+        assert_eq!(soic.pad_count, 8);
+        assert_eq!(soic.body_size, (3.9, 4.9));
+        assert!(soic.tokens.starts_with(&["soic".to_string(), "8".to_string(), "3.9x4.9mm".to_string(), "p1.27mm".to_string()]));
+        // The QFN's paste windows aren't pads to count
+        assert_eq!(index.entries[39].pad_count, 25);
+    }
+
+    #[test]
+    fn queries_combine_name_tags_words_and_size() {
+        let index = index();
+        assert_eq!(index.search(&SearchQuery::new()).len(), 50);
+        assert_eq!(index.search(&SearchQuery::new().with_name_prefix("soic-")).len(), 7);
+        assert_eq!(index.search(&SearchQuery::new().with_name_prefix("R_")).len(), 6);
+        assert_eq!(index.search(&SearchQuery::new().with_tag("QFN")).len(), 4);
+        assert_eq!(index.search(&SearchQuery::new().with_tag("\"resistor\" 0603")).len(), 1);
+        let wide = index.search(&SearchQuery::new().with_name_prefix("SOIC").with_body_length(10.0, 20.0));
+        assert_eq!(
+            names(&wide),
+            ["SOIC-16W_7.5x10.3mm_P1.27mm", "SOIC-20W_7.5x12.8mm_P1.27mm", "SOIC-24W_7.5x15.4mm_P1.27mm", "SOIC-28W_7.5x17.9mm_P1.27mm"]
+        );
+        let small = index.search(&SearchQuery::new().with_body_length(0.0, 0.6));
+        assert_eq!(small.len(), 5, "{:?}", names(&small));
+        assert!(small.iter().all(|entry| entry.name.contains("0201")));
+        let red = index.search(&SearchQuery::new().with_words("led RED 12"));
+        assert_eq!(names(&red), ["LED_1206_3216Metric", "LED_1210_3225Metric"]);
+        assert!(index.search(&SearchQuery::new().with_tag("bga")).is_empty());
+    }
+
+    #[test]
+    fn json_round_trips() {
+        let index = index();
+        let json = index.to_json();
+        assert_eq!(SearchIndex::from_json(&json).unwrap(), index);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["entries"][0]["name"], "R_0201_0603Metric");
+        assert!(SearchIndex::from_json("{\"entries\": 1}").is_err());
+    }
+
+    #[test]
+    fn messy_tags_are_normalized() {
+        assert_eq!(normalize_tags("  Resistor \"SMD\"  0805\tresistor RESISTOR  \\\"quoted\\\" 'x' \"\" "), "resistor smd 0805 quoted x");
+        assert_eq!(normalize_tags("\"\" ' \\"), "");
+        let texts = SearchQuery::new().with_tag("\"Resistor\" SMD");
+        assert_eq!(texts.tags, ["resistor", "smd"]);
+    }
+}
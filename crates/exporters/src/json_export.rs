@@ -0,0 +1,270 @@
+//! JSON metadata export
+//!
+//! A machine readable summary of footprints and boards for downstream tooling (part
+//! search, PLM systems, web previews). This is metadata, not a lossless copy of the model.
+
+use copper_substrate::board::Board;
+use copper_substrate::prelude::*;
+use serde_json::{json, Value};
+
+use crate::exporter::{BoardExporter, ExportError, ExportOptions, FootprintExporter};
+
+fn pad_type_name(pad_type: &PadType) -> &'static str {
+    match pad_type {
+        PadType::SMD => "smd",
+        PadType::ThroughHole => "thru_hole",
+        PadType::NPTH => "np_thru_hole",
+    }
+}
+
+fn pad_shape_name(shape: &PadShape) -> &'static str {
+    match shape {
+        PadShape::Circle => "circle",
+        PadShape::Rect => "rect",
+        PadShape::Oval => "oval",
+        PadShape::RoundRect => "roundrect",
+        PadShape::Custom(_) => "custom",
+    }
+}
+
+pub fn pad_to_json(pad: &PadDescriptor) -> Value {
+    json!({
+        "number": pad.number,
+        "pin_function": pad.pin_function,
+        "type": pad_type_name(&pad.pad_type),
+        "shape": pad_shape_name(&pad.shape),
+        "position": [pad.position.0, pad.position.1],
+        "rotation": pad.rotation.unwrap_or(0.0),
+        "size": [pad.size.0, pad.size.1],
+        "drill": pad.drill_size,
+        "drill_slot": pad.drill_slot.map(|(width, height)| [width, height]),
+        "layers": pad.layers,
+        "padstack": pad.padstack.as_ref().filter(|padstack| !padstack.is_empty()).map(|padstack| json!({
+            "inner": padstack.inner.as_ref().map(layer_geometry_to_json),
+            "back": padstack.back.as_ref().map(layer_geometry_to_json),
+        })),
+        "routing_hint": pad.routing_hint.as_ref().map(routing_hint_to_json),
+    })
+}
+
+fn routing_hint_to_json(hint: &RoutingHint) -> Value {
+    let exits: Vec<[f32; 2]> = hint.exits.iter().map(|range| [range.from, range.to]).collect();
+    json!({ "exits": exits, "preferred_layer": hint.preferred_layer, "no_via_under_pad": hint.no_via_under_pad })
+}
+
+fn layer_geometry_to_json(geometry: &PadLayerGeometry) -> Value {
+    json!({ "shape": pad_shape_name(&geometry.shape), "size": [geometry.size.0, geometry.size.1] })
+}
+
+fn rectangle_to_json(rect: &Rectangle) -> Value {
+    json!({ "min": [rect.min_x, rect.min_y], "max": [rect.max_x, rect.max_y] })
+}
+
+pub fn footprint_to_json<T: BoardComposableObject + ?Sized>(component: &T) -> Value {
+    let functional_type = component.functional_type();
+    json!({
+        "name": component.footprint_name(),
+        "library": component.library_name(),
+        "description": component.description(),
+        "tags": component.tags().map(|tags| normalize_tags(&tags)),
+        "functional_type": {
+            "kind": functional_type.kind_name(),
+            "value": functional_type.value(),
+        },
+        "smt": component.is_smt(),
+        "process": component.process().names(),
+        "terminal_count": component.terminal_count(),
+        "bounding_box": rectangle_to_json(&component.bounding_box()),
+        "courtyard": rectangle_to_json(&component.generate_courtyard().bounds),
+        "pads": component.pad_descriptors().iter().map(pad_to_json).collect::<Vec<_>>(),
+        "model_3d": component.model_3d().map(|model| model.path),
+    })
+}
+
+pub fn board_to_json(board: &Board) -> Value {
+    let components: Vec<Value> = board
+        .components()
+        .iter()
+        .map(|placed| {
+            let (position, rotation) = board.exported_placement(placed);
+            json!({
+                "reference": placed.reference,
+                "footprint": format!("{}:{}", placed.component.library_name(), placed.component.footprint_name()),
+                "value": placed.component.functional_type().value(),
+                "position": [position.0, position.1],
+                "rotation": rotation,
+                "side": if placed.side == Side::Back { "bottom" } else { "top" },
+                "process": placed.component.process().names(),
+            })
+        })
+        .collect();
+    json!({ "name": board.name, "components": components })
+}
+
+/// Per net lengths in millimeters and delays in picoseconds, in net name order
+pub fn net_length_report_to_json(report: &NetLengthReport) -> Value {
+    let nets: Vec<Value> = report
+        .nets
+        .iter()
+        .map(|net| {
+            json!({
+                "net": net.net,
+                "routed": net.routed,
+                "total_length": net.total_length(),
+                "track_length": net.track_length,
+                "via_count": net.via_count,
+                "via_length": net.via_length,
+                "delay_ps": net.delay,
+                "pin_to_pin": net.pin_to_pin.as_ref().map(|path| json!({
+                    "pads": [path.pads.0, path.pads.1],
+                    "length": path.length,
+                    "delay_ps": path.delay,
+                })),
+            })
+        })
+        .collect();
+    json!({ "nets": nets })
+}
+
+fn side_names(sides: Option<PadSide>) -> Vec<&'static str> {
+    match sides {
+        Some(PadSide::Both) => vec!["top", "bottom"],
+        Some(PadSide::Front) => vec!["top"],
+        Some(PadSide::Back) => vec!["bottom"],
+        None => Vec::new(),
+    }
+}
+
+/// Probe access per net in net name order, with the summary percentages
+pub fn test_coverage_report_to_json(report: &TestCoverageReport) -> Value {
+    let nets: Vec<Value> = report
+        .nets
+        .iter()
+        .map(|net| {
+            let accesses: Vec<Value> = net
+                .accesses
+                .iter()
+                .map(|access| json!({
+                    "kind": access.kind.to_string(),
+                    "element": access.element,
+                    "x": access.location.x,
+                    "y": access.location.y,
+                    "sides": side_names(Some(access.sides)),
+                }))
+                .collect();
+            let suggestions: Vec<Value> = net
+                .suggestions
+                .iter()
+                .map(|suggestion| json!({
+                    "x": suggestion.location.x,
+                    "y": suggestion.location.y,
+                    "side": if suggestion.side == Side::Back { "bottom" } else { "top" },
+                    "track": suggestion.track,
+                }))
+                .collect();
+            json!({
+                "net": net.net,
+                "covered": net.is_covered(),
+                "sides": side_names(net.sides()),
+                "accesses": accesses,
+                "suggestions": suggestions,
+            })
+        })
+        .collect();
+    json!({
+        "coverage": report.coverage(),
+        "front_coverage": report.front_coverage(),
+        "back_coverage": report.back_coverage(),
+        "nets": nets,
+    })
+}
+
+fn to_pretty_bytes(value: &Value) -> Result<Vec<u8>, ExportError> {
+    serde_json::to_vec_pretty(value).map_err(|e| ExportError::Other(e.to_string()))
+}
+
+pub struct JsonFootprintExporter;
+
+impl FootprintExporter for JsonFootprintExporter {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn file_extension(&self) -> &str {
+        "json"
+    }
+
+    fn export(&self, component: &dyn BoardComposableObject, _options: &ExportOptions) -> Result<Vec<u8>, ExportError> {
+        to_pretty_bytes(&footprint_to_json(component))
+    }
+}
+
+pub struct JsonBoardExporter;
+
+impl BoardExporter for JsonBoardExporter {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn file_extension(&self) -> &str {
+        "json"
+    }
+
+    fn export(&self, board: &Board, _options: &ExportOptions) -> Result<Vec<u8>, ExportError> {
+        to_pretty_bytes(&board_to_json(board))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use copper_substrate::routing::Track;
+
+    use super::*;
+
+    #[test]
+    fn net_lengths_serialize_per_net() {
+        let mut board = Board::new("json").with_stackup(Stackup::two_layer(1.6));
+        board.add_track(Track::new("CLK", BoardLayer::front(LayerType::Copper), Point::new(0.0, 0.0), Point::new(3.0, 4.0), 0.2));
+        let value = net_length_report_to_json(&board.net_length_report().unwrap());
+        let net = &value["nets"][0];
+        assert_eq!(net["net"], "CLK");
+        assert_eq!(net["routed"], true);
+        assert_eq!(net["track_length"], 5.0);
+        assert_eq!(net["via_count"], 0);
+        assert!(net["delay_ps"].as_f64().unwrap() > 5.0 * 3.3);
+        assert!(net["pin_to_pin"].is_null());
+    }
+
+    #[test]
+    fn test_coverage_lists_sides_and_suggestions() {
+        let mut board = Board::new("json").with_rectangular_outline(30.0, 10.0);
+        let open = TentingSettings { front: TentingType::None, back: TentingType::Full };
+        board.add_via(Via::new("GND", Point::new(2.0, 2.0), 0.6, 0.3).with_tenting(open));
+        board.add_track(Track::new("SIG", BoardLayer::back(LayerType::Copper), Point::new(5.0, 5.0), Point::new(25.0, 5.0), 0.2));
+        let value = test_coverage_report_to_json(&board.test_coverage_report());
+        assert_eq!(value["coverage"], 50.0);
+        assert_eq!(value["nets"][0]["net"], "GND");
+        assert_eq!(value["nets"][0]["sides"], json!(["top"]));
+        assert_eq!(value["nets"][0]["accesses"][0]["kind"], "via");
+        assert_eq!(value["nets"][1]["covered"], false);
+        assert_eq!(value["nets"][1]["suggestions"], json!([{ "x": 15.0, "y": 5.0, "side": "bottom", "track": 0 }]));
+    }
+
+    #[test]
+    fn pads_carry_their_routing_hints() {
+        let value = footprint_to_json(&Soic::new(8, 1.27, 4.9, 3.9));
+        let hint = &value["pads"][0]["routing_hint"];
+        assert_eq!(hint["exits"], json!([[135.0, 225.0]]));
+        assert_eq!(hint["preferred_layer"], "F.Cu");
+        assert_eq!(hint["no_via_under_pad"], true);
+        assert_eq!(value["pads"][4]["routing_hint"]["exits"], json!([[315.0, 45.0]]));
+    }
+
+    #[test]
+    fn components_list_their_processes() {
+        assert_eq!(footprint_to_json(&Soic::new(8, 1.27, 4.9, 3.9))["process"], json!(["reflow", "wave", "hand_solder"]));
+        let mut board = Board::new("json");
+        board.place("U1", Qfn::new(16, 0.5, 3.0, 3.0), (10.0, 10.0), 0.0).unwrap();
+        assert_eq!(board_to_json(&board)["components"][0]["process"], json!(["reflow"]));
+    }
+}
@@ -0,0 +1,596 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use copper_substrate::board::Board;
+use copper_substrate::board_interface::{GraphicElement, GraphicType};
+use copper_substrate::geometry::Point;
+use copper_substrate::hatch::{expand_hatch, FillStyle};
+use copper_substrate::layer_type::{BoardLayer, Side};
+use copper_substrate::routing::ViaKind;
+use copper_substrate::stackup::{Stackup, StackupLayerKind};
+use copper_substrate::thieving::ThievingShape;
+use copper_substrate::zone::Zone;
+
+use crate::exporter::{BoardExporter, ExportError, ExportOptions, ExportWarning, F32_SAFE_COORDINATE};
+use crate::format::{quantize_numbers, Angle};
+use crate::kicad_format::apply_output_style;
+use crate::kicad_pcb_export::{write_dimension, write_footprint_with_options, FootprintPlacement, KiCadVersion};
+use crate::sexpr;
+
+/// KiCad 9 layer table after the copper layers: (ordinal, canonical name, type, user name)
+const TECHNICAL_LAYER_TABLE: &[(u32, &str, &str, Option<&str>)] = &[
+    (9, "F.Adhes", "user", Some("F.Adhesive")),
+    (11, "B.Adhes", "user", Some("B.Adhesive")),
+    (13, "F.Paste", "user", None),
+    (15, "B.Paste", "user", None),
+    (5, "F.SilkS", "user", Some("F.Silkscreen")),
+    (7, "B.SilkS", "user", Some("B.Silkscreen")),
+    (1, "F.Mask", "user", None),
+    (3, "B.Mask", "user", None),
+    (17, "Dwgs.User", "user", Some("User.Drawings")),
+    (19, "Cmts.User", "user", Some("User.Comments")),
+    (21, "Eco1.User", "user", Some("User.Eco1")),
+    (23, "Eco2.User", "user", Some("User.Eco2")),
+    (25, "Edge.Cuts", "user", None),
+    (27, "Margin", "user", None),
+    (31, "F.CrtYd", "user", Some("F.Courtyard")),
+    (29, "B.CrtYd", "user", Some("B.Courtyard")),
+    (35, "F.Fab", "user", None),
+    (33, "B.Fab", "user", None),
+];
+
+/// KiCad 9 ordinal of a copper layer: F.Cu 0, B.Cu 2, then In1.Cu 4, In2.Cu 6, ...
+fn copper_ordinal(layer: &BoardLayer) -> u32 {
+    match layer.side {
+        Side::Front => 0,
+        Side::Back => 2,
+        Side::Inner(index) => 2 * index as u32 + 2,
+    }
+}
+
+/// Write the layer table for `copper`, the board's copper layers from top to bottom
+pub fn write_layer_table(output: &mut String, copper: &[BoardLayer]) {
+    writeln!(output, "\t(layers").unwrap();
+    for layer in copper {
+        writeln!(output, "\t\t({} \"{}\" signal)", copper_ordinal(layer), layer.to_kicad_string()).unwrap();
+    }
+    for (ordinal, name, layer_type, user_name) in TECHNICAL_LAYER_TABLE {
+        match user_name {
+            Some(user_name) => writeln!(output, "\t\t({} \"{}\" {} {})", ordinal, name, layer_type, sexpr::quote(user_name)).unwrap(),
+            None => writeln!(output, "\t\t({} \"{}\" {})", ordinal, name, layer_type).unwrap(),
+        }
+    }
+    writeln!(output, "\t)").unwrap();
+}
+
+/// Write the board stackup into the setup section. KiCad names dielectrics "dielectric N"
+/// from the top; the stackup's own name for them is kept as the material when no library
+/// material is known.
+pub fn write_stackup(output: &mut String, stackup: &Stackup) {
+    writeln!(output, "\t\t(stackup").unwrap();
+    let mut dielectrics = 0;
+    for layer in &stackup.layers {
+        match layer.kind {
+            StackupLayerKind::Copper => {
+                writeln!(output, "\t\t\t(layer {} (type \"copper\") (thickness {}))", sexpr::quote(&layer.name), layer.thickness).unwrap();
+            }
+            StackupLayerKind::Dielectric { epsilon_r, loss_tangent } => {
+                dielectrics += 1;
+                let (layer_type, material) = match &layer.material {
+                    Some(material) => (material.kind.to_string(), material.name.clone()),
+                    None => ("core".to_string(), layer.name.clone()),
+                };
+                writeln!(output, "\t\t\t(layer \"dielectric {}\" (type \"{}\") (thickness {}) (material {}) (epsilon_r {}) (loss_tangent {}))",
+                         dielectrics, layer_type, layer.thickness, sexpr::quote(&material), epsilon_r, loss_tangent).unwrap();
+            }
+        }
+    }
+    writeln!(output, "\t\t\t(copper_finish \"None\")").unwrap();
+    writeln!(output, "\t\t\t(dielectric_constraints no)").unwrap();
+    writeln!(output, "\t\t)").unwrap();
+}
+
+/// Indent every line of a nested block by one tab
+fn indent_block(block: &str) -> String {
+    block.lines().map(|line| format!("\t{}\n", line)).collect()
+}
+
+/// Write a board level polygon, filled for copper and open for the board edge
+fn write_gr_poly(output: &mut String, points: &[Point], layer: &str, filled: bool) {
+    writeln!(output, "\t(gr_poly").unwrap();
+    writeln!(output, "\t\t(pts").unwrap();
+    for point in points {
+        writeln!(output, "\t\t\t(xy {} {})", point.x, point.y).unwrap();
+    }
+    writeln!(output, "\t\t)").unwrap();
+    writeln!(output, "\t\t(stroke (width {}) (type solid))", if filled { 0.0 } else { 0.05 }).unwrap();
+    writeln!(output, "\t\t(fill {})", if filled { "yes" } else { "no" }).unwrap();
+    writeln!(output, "\t\t(layer \"{}\")", layer).unwrap();
+    writeln!(output, "\t\t(uuid \"{}\")", uuid::Uuid::new_v4()).unwrap();
+    writeln!(output, "\t)").unwrap();
+}
+
+/// Write a board level graphic as the `gr_*` equivalent of its footprint item
+fn write_board_graphic(output: &mut String, element: &GraphicElement) {
+    let expanded = expand_hatch(element);
+    if expanded.len() > 1 {
+        for part in &expanded {
+            write_board_graphic(output, part);
+        }
+        return;
+    }
+    let layer = element.kicad_layer();
+    let layer = layer.as_str();
+    let stroke = format!("(stroke (width {}) (type solid))", element.stroke.width);
+    match &element.element_type {
+        GraphicType::Line { start, end } => {
+            writeln!(output, "\t(gr_line (start {} {}) (end {} {}) {} (layer \"{}\") (uuid \"{}\"))",
+                     start.0, start.1, end.0, end.1, stroke, layer, element.uuid).unwrap();
+        }
+        GraphicType::Rectangle { bounds } => {
+            writeln!(output, "\t(gr_rect (start {} {}) (end {} {}) {} (fill no) (layer \"{}\") (uuid \"{}\"))",
+                     bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y, stroke, layer, element.uuid).unwrap();
+        }
+        GraphicType::Circle { center, radius } => {
+            writeln!(output, "\t(gr_circle (center {} {}) (end {} {}) {} (fill no) (layer \"{}\") (uuid \"{}\"))",
+                     center.0, center.1, center.0 + radius, center.1, stroke, layer, element.uuid).unwrap();
+        }
+        GraphicType::Polygon { points, fill } => {
+            let points: Vec<String> = points.iter().map(|(x, y)| format!("(xy {} {})", x, y)).collect();
+            let fill = if *fill == Some(FillStyle::Solid) { "yes" } else { "no" };
+            writeln!(output, "\t(gr_poly (pts {}) {} (fill {}) (layer \"{}\") (uuid \"{}\"))",
+                     points.join(" "), stroke, fill, layer, element.uuid).unwrap();
+        }
+        GraphicType::Dimension(dimension) => write_dimension(output, dimension, layer, element.stroke.width, &element.uuid),
+    }
+}
+
+/// Thieving copper is written as filled board graphics, which KiCad treats as copper on
+/// no net
+fn write_thieving_shape(output: &mut String, shape: &ThievingShape, layer: &str) {
+    match shape {
+        ThievingShape::Dot { center, diameter } => {
+            writeln!(output, "\t(gr_circle").unwrap();
+            writeln!(output, "\t\t(center {} {})", center.x, center.y).unwrap();
+            writeln!(output, "\t\t(end {} {})", center.x + diameter / 2.0, center.y).unwrap();
+            writeln!(output, "\t\t(stroke (width 0) (type solid))").unwrap();
+            writeln!(output, "\t\t(fill yes)").unwrap();
+            writeln!(output, "\t\t(layer \"{}\")", layer).unwrap();
+            writeln!(output, "\t\t(uuid \"{}\")", uuid::Uuid::new_v4()).unwrap();
+            writeln!(output, "\t)").unwrap();
+        }
+        ThievingShape::Polygon(points) => write_gr_poly(output, points, layer, true),
+    }
+}
+
+/// Write a copper zone unfilled, with its fill settings for KiCad to fill it; see `zone`
+/// for how hatches map onto KiCad's
+fn write_zone(output: &mut String, zone: &Zone, net: u32) {
+    let layers: Vec<String> = zone.layers.iter().map(|layer| sexpr::quote(&layer.to_kicad_string())).collect();
+    writeln!(output, "\t(zone").unwrap();
+    writeln!(output, "\t\t(net {})", net).unwrap();
+    writeln!(output, "\t\t(net_name {})", sexpr::quote(&zone.net)).unwrap();
+    writeln!(output, "\t\t(layers {})", layers.join(" ")).unwrap();
+    writeln!(output, "\t\t(uuid \"{}\")", uuid::Uuid::new_v4()).unwrap();
+    writeln!(output, "\t\t(hatch edge 0.5)").unwrap();
+    writeln!(output, "\t\t(connect_pads").unwrap();
+    writeln!(output, "\t\t\t(clearance {})", zone.clearance).unwrap();
+    writeln!(output, "\t\t)").unwrap();
+    writeln!(output, "\t\t(min_thickness {})", zone.min_thickness).unwrap();
+    writeln!(output, "\t\t(filled_areas_thickness no)").unwrap();
+    writeln!(output, "\t\t(fill").unwrap();
+    if zone.fill.hatch().is_some() {
+        writeln!(output, "\t\t\t(mode hatch)").unwrap();
+    }
+    writeln!(output, "\t\t\t(thermal_gap 0.5)").unwrap();
+    writeln!(output, "\t\t\t(thermal_bridge_width 0.5)").unwrap();
+    if let Some((pitch, width, angle)) = zone.fill.hatch() {
+        writeln!(output, "\t\t\t(hatch_thickness {})", width).unwrap();
+        writeln!(output, "\t\t\t(hatch_gap {})", (pitch - width).max(0.0)).unwrap();
+        writeln!(output, "\t\t\t(hatch_orientation {})", Angle::ccw(angle)).unwrap();
+        writeln!(output, "\t\t\t(hatch_border_algorithm hatch_thickness)").unwrap();
+        writeln!(output, "\t\t\t(hatch_min_hole_area 0.3)").unwrap();
+    }
+    writeln!(output, "\t\t)").unwrap();
+    writeln!(output, "\t\t(polygon").unwrap();
+    writeln!(output, "\t\t\t(pts").unwrap();
+    for point in &zone.outline {
+        writeln!(output, "\t\t\t\t(xy {} {})", point.x, point.y).unwrap();
+    }
+    writeln!(output, "\t\t\t)").unwrap();
+    writeln!(output, "\t\t)").unwrap();
+    writeln!(output, "\t)").unwrap();
+}
+
+/// Net numbers for the routed nets and then the pad nets, after those of the carried over
+/// `(net ...)` items
+fn net_numbers(board: &Board, carried: &[&String]) -> (HashMap<String, u32>, Vec<(u32, String)>) {
+    let mut numbers = HashMap::new();
+    for item in carried {
+        if let Ok(net) = sexpr::parse(item)
+            && let (Some(number), Some(name)) = (net.children().get(1).and_then(|n| n.as_str()?.parse().ok()), net.children().get(2).and_then(|n| n.as_str()))
+        {
+            numbers.insert(name.to_string(), number);
+        }
+    }
+    let mut next = numbers.values().max().map_or(1, |max| max + 1);
+    let mut added = Vec::new();
+    let routed = board
+        .tracks()
+        .iter()
+        .map(|track| &track.net)
+        .chain(board.arcs().iter().map(|arc| &arc.net))
+        .chain(board.vias().iter().map(|via| &via.net))
+        .chain(board.zones().iter().map(|zone| &zone.net));
+    let pads = board.components().iter().flat_map(|placed| {
+        let pads = placed.component.pad_descriptors();
+        pads.into_iter().filter_map(|pad| board.pad_net(&placed.reference, &pad.number))
+    });
+    for net in routed.map(String::as_str).chain(pads) {
+        if !numbers.contains_key(net) {
+            numbers.insert(net.to_string(), next);
+            added.push((next, net.to_string()));
+            next += 1;
+        }
+    }
+    (numbers, added)
+}
+
+/// Generate a `.kicad_pcb` board file with every placed component
+pub fn to_kicad_pcb(board: &Board) -> String {
+    to_kicad_pcb_with_options(board, &ExportOptions::default())
+}
+
+/// `to_kicad_pcb` laid out in `options.output_style`. Panics if the written text doesn't
+/// read back for the `KiCadCanonical` layout, a bug in the writer;
+/// `try_to_kicad_pcb_with_options` returns it as an error.
+pub fn to_kicad_pcb_with_options(board: &Board, options: &ExportOptions) -> String {
+    try_to_kicad_pcb_with_options(board, options).unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// `to_kicad_pcb_with_options`, with layout failures returned rather than panicking
+pub fn try_to_kicad_pcb_with_options(board: &Board, options: &ExportOptions) -> Result<String, ExportError> {
+    let mut output = String::new();
+
+    writeln!(output, "(kicad_pcb").unwrap();
+    writeln!(output, "\t(version 20241229)").unwrap();
+    writeln!(output, "\t(generator \"custom_pcb_tool\")").unwrap();
+    writeln!(output, "\t(generator_version \"1.0\")").unwrap();
+    writeln!(output, "\t(general").unwrap();
+    let thickness = board.stackup().map_or(1.6, |stackup| stackup.total_thickness());
+    writeln!(output, "\t\t(thickness {})", thickness).unwrap();
+    writeln!(output, "\t\t(legacy_teardrops no)").unwrap();
+    writeln!(output, "\t)").unwrap();
+    writeln!(output, "\t(paper \"A4\")").unwrap();
+    write_layer_table(&mut output, &board.copper_layers());
+    writeln!(output, "\t(setup").unwrap();
+    if let Some(stackup) = board.stackup() {
+        write_stackup(&mut output, stackup);
+    }
+    writeln!(output, "\t\t(pad_to_mask_clearance 0)").unwrap();
+    // KiCad leaves origins at the page corner out
+    for (name, origin) in [("aux_axis_origin", board.aux_origin()), ("grid_origin", board.grid_origin())] {
+        if origin != Point::default() {
+            writeln!(output, "\t\t({} {} {})", name, origin.x, origin.y).unwrap();
+        }
+    }
+    writeln!(output, "\t)").unwrap();
+    writeln!(output, "\t(net 0 \"\")").unwrap();
+    // Nets used by carried over routing, which has to come after the footprints
+    let (nets, routing): (Vec<&String>, Vec<&String>) = board.passthrough().iter().partition(|item| item.starts_with("(net "));
+    for net in &nets {
+        writeln!(output, "\t{}", net).unwrap();
+    }
+    let (net_numbers, routed_nets) = net_numbers(board, &nets);
+    for (number, name) in &routed_nets {
+        writeln!(output, "\t(net {} {})", number, sexpr::quote(name)).unwrap();
+    }
+
+    // Uuids of the items groups can hold, decided up front so groups can list them
+    let new_uuids = |count: usize| -> Vec<String> { (0..count).map(|_| uuid::Uuid::new_v4().to_string()).collect() };
+    let (footprint_uuids, track_uuids, via_uuids) =
+        (new_uuids(board.components().len()), new_uuids(board.tracks().len()), new_uuids(board.vias().len()));
+
+    // Boards are always written for the current release
+    let footprint_options = ExportOptions { kicad_version: KiCadVersion::default(), ..options.clone() };
+    for (placed, uuid) in board.components().iter().zip(&footprint_uuids) {
+        let (position, rotation) = board.exported_placement(placed);
+        let placement = FootprintPlacement {
+            reference: &placed.reference,
+            position,
+            rotation,
+            side: placed.side,
+            normalize_text_rotation: options.normalize_text_rotation,
+            uuid: Some(uuid),
+            pad_nets: placed
+                .component
+                .pad_descriptors()
+                .iter()
+                .filter_map(|pad| {
+                    let net = board.pad_net(&placed.reference, &pad.number)?;
+                    Some((pad.number.to_string(), (net_numbers[net], net)))
+                })
+                .collect(),
+        };
+        let mut footprint = String::new();
+        write_footprint_with_options(&mut footprint, placed.component.as_ref(), Some(&placement), &footprint_options);
+        output.push_str(&indent_block(&footprint));
+    }
+
+    if let Some(outline) = board.outline() {
+        write_gr_poly(&mut output, outline, "Edge.Cuts", false);
+    }
+    for element in board.graphics() {
+        write_board_graphic(&mut output, element);
+    }
+    for copper in board.thieving() {
+        write_thieving_shape(&mut output, &copper.shape, &copper.layer.to_kicad_string());
+    }
+    for (track, uuid) in board.tracks().iter().zip(&track_uuids) {
+        writeln!(output, "\t(segment (start {} {}) (end {} {}) (width {}) (layer \"{}\") (net {}) (uuid \"{}\"))",
+                 track.start.x, track.start.y, track.end.x, track.end.y, track.width, track.layer.to_kicad_string(),
+                 net_numbers[&track.net], uuid).unwrap();
+    }
+    for arc in board.arcs() {
+        writeln!(output, "\t(arc (start {} {}) (mid {} {}) (end {} {}) (width {}) (layer \"{}\") (net {}) (uuid \"{}\"))",
+                 arc.start.x, arc.start.y, arc.mid.x, arc.mid.y, arc.end.x, arc.end.y, arc.width, arc.layer.to_kicad_string(),
+                 net_numbers[&arc.net], uuid::Uuid::new_v4()).unwrap();
+    }
+    for (via, uuid) in board.vias().iter().zip(&via_uuids) {
+        // KiCad has one type for blind and buried vias, told apart by their layers
+        let kind = match via.kind {
+            ViaKind::Through => "",
+            ViaKind::Blind | ViaKind::Buried => " blind",
+            ViaKind::Micro => " micro",
+        };
+        writeln!(output, "\t(via{} (at {} {}) (size {}) (drill {}) (layers \"{}\" \"{}\") (net {}) (uuid \"{}\"))",
+                 kind, via.position.x, via.position.y, via.diameter, via.drill, via.layers.0.to_kicad_string(),
+                 via.layers.1.to_kicad_string(), net_numbers[&via.net], uuid).unwrap();
+    }
+    for zone in board.zones() {
+        write_zone(&mut output, zone, net_numbers[&zone.net]);
+    }
+    for group in board.groups() {
+        let footprints = group
+            .references
+            .iter()
+            .filter_map(|reference| board.components().iter().position(|placed| &placed.reference == reference))
+            .map(|index| &footprint_uuids[index]);
+        let tracks = group.tracks.iter().filter_map(|index| track_uuids.get(*index));
+        let vias = group.vias.iter().filter_map(|index| via_uuids.get(*index));
+        let members: Vec<String> = footprints.chain(tracks).chain(vias).map(|uuid| format!("\"{}\"", uuid)).collect();
+        writeln!(output, "\t(group {}", sexpr::quote(&group.name)).unwrap();
+        writeln!(output, "\t\t(uuid \"{}\")", uuid::Uuid::new_v4()).unwrap();
+        writeln!(output, "\t\t(members {})", members.join(" ")).unwrap();
+        writeln!(output, "\t)").unwrap();
+    }
+    for item in routing {
+        writeln!(output, "\t{}", item).unwrap();
+    }
+
+    writeln!(output, ")").unwrap();
+    Ok(apply_output_style(&quantize_numbers(&output, options.resolution), options)?)
+}
+
+/// Coordinates `to_kicad_pcb` can't write faithfully: board items kept in f32 (tracks,
+/// vias, zones, thieving, graphics, the outline and origins) beyond
+/// `F32_SAFE_COORDINATE`, and any coordinate, placements included, whose text doesn't
+/// read back as the value written. Placements are f64, so parts far from the origin
+/// export exactly.
+pub fn precision_warnings(board: &Board) -> Vec<ExportWarning> {
+    let mut warnings = Vec::new();
+    let mut check = |item: String, value: f32| {
+        if value.is_finite() && value.abs() > F32_SAFE_COORDINATE {
+            warnings.push(ExportWarning::CoordinateRange { item, value });
+        } else if !value.is_finite() || value.to_string().parse::<f32>() != Ok(value) {
+            warnings.push(ExportWarning::PrecisionLoss { item, value: f64::from(value), written: value.to_string() });
+        }
+    };
+    let points = |points: &[Point]| points.iter().flat_map(|point| [point.x, point.y]).collect::<Vec<_>>();
+
+    for (name, origin) in [("aux origin", board.aux_origin()), ("grid origin", board.grid_origin())] {
+        for value in [origin.x, origin.y] {
+            check(name.to_string(), value);
+        }
+    }
+    for value in board.outline().map(points).unwrap_or_default() {
+        check("board outline".to_string(), value);
+    }
+    for (index, element) in board.graphics().iter().enumerate() {
+        let values = match &element.element_type {
+            GraphicType::Line { start, end } => vec![start.0, start.1, end.0, end.1],
+            GraphicType::Rectangle { bounds } => vec![bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y],
+            GraphicType::Circle { center, radius } => vec![center.0 - radius, center.1 - radius, center.0 + radius, center.1 + radius],
+            GraphicType::Polygon { points, .. } => points.iter().flat_map(|&(x, y)| [x, y]).collect(),
+            GraphicType::Dimension(dimension) => vec![dimension.start.0, dimension.start.1, dimension.end.0, dimension.end.1],
+        };
+        for value in values {
+            check(format!("graphic {}", index + 1), value);
+        }
+    }
+    for (index, copper) in board.thieving().iter().enumerate() {
+        let values = match &copper.shape {
+            ThievingShape::Dot { center, .. } => vec![center.x, center.y],
+            ThievingShape::Polygon(polygon) => points(polygon),
+        };
+        for value in values {
+            check(format!("thieving copper {}", index + 1), value);
+        }
+    }
+    for (index, track) in board.tracks().iter().enumerate() {
+        for value in points(&[track.start, track.end]) {
+            check(format!("track {} ({})", index + 1, track.net), value);
+        }
+    }
+    for (index, arc) in board.arcs().iter().enumerate() {
+        for value in points(&[arc.start, arc.mid, arc.end]) {
+            check(format!("arc {} ({})", index + 1, arc.net), value);
+        }
+    }
+    for (index, via) in board.vias().iter().enumerate() {
+        for value in [via.position.x, via.position.y] {
+            check(format!("via {} ({})", index + 1, via.net), value);
+        }
+    }
+    for zone in board.zones() {
+        for value in points(&zone.outline) {
+            check(format!("zone {}", zone.net), value);
+        }
+    }
+
+    for placed in board.components() {
+        let ((x, y), _) = board.exported_placement(placed);
+        for value in [x, y] {
+            let written = value.to_string();
+            if !value.is_finite() || written.parse::<f64>() != Ok(value) {
+                warnings.push(ExportWarning::PrecisionLoss { item: format!("{} position", placed.reference), value, written });
+            }
+        }
+    }
+    warnings
+}
+
+pub struct KiCadBoardExporter;
+
+impl BoardExporter for KiCadBoardExporter {
+    fn name(&self) -> &str {
+        "kicad"
+    }
+
+    fn file_extension(&self) -> &str {
+        "kicad_pcb"
+    }
+
+    fn export(&self, board: &Board, options: &ExportOptions) -> Result<Vec<u8>, ExportError> {
+        Ok(try_to_kicad_pcb_with_options(board, options)?.into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use copper_substrate::block::{Block, BlockPlacement};
+    use copper_substrate::board_interface::Rectangle;
+    use copper_substrate::exposed_copper::ExposedCopper;
+    use copper_substrate::layer_type::LayerType;
+    use copper_substrate::placement::PlacementPolicy;
+    use copper_substrate::presets;
+    use copper_substrate::routing::{Track, TrackArc, Via};
+
+    use crate::kicad_board_import::read_board;
+
+    use super::*;
+
+    #[test]
+    fn exposed_copper_is_written_on_its_side() {
+        let mut board = Board::new("touch");
+        let window = ExposedCopper::rounded_rectangle(Rectangle { min_x: 0.0, min_y: 0.0, max_x: 4.0, max_y: 2.0 }, 0.5).with_side(Side::Back);
+        for element in window.graphic_elements() {
+            board.add_graphic(element);
+        }
+        let text = to_kicad_pcb(&board);
+        let polys: Vec<&str> = text.lines().filter(|line| line.contains("(gr_poly")).collect();
+        assert_eq!(polys.len(), 2, "{}", text);
+        assert!(polys[0].contains("(fill yes) (layer \"B.Cu\")"), "{}", polys[0]);
+        assert!(polys[1].contains("(fill yes) (layer \"B.Mask\")"), "{}", polys[1]);
+        crate::sexpr::parse(&text).unwrap();
+    }
+
+    #[test]
+    fn snapped_placement_is_what_gets_written() {
+        let mut board = Board::new("snap");
+        board.place("R1", presets::resistor_0603("10k"), (1.234, 5.678), 37.0).unwrap();
+        // Policy applied after the fact: stored state keeps the raw values, the file doesn't
+        board.set_policy(PlacementPolicy::new(0.05, 45.0).unwrap());
+        assert_eq!(board.components()[0].position, (1.234, 5.678));
+        let text = to_kicad_pcb(&board);
+        assert!(text.contains("(at 1.25 5.7 45)"), "{}", text);
+        assert!(!text.contains("1.234"));
+    }
+
+    /// x = 10000.0005 mm, which f32 can't hold: its nearest values there are 10000 and
+    /// 10000.001
+    #[test]
+    fn far_placement_is_written_exactly() {
+        let mut board = Board::new("far");
+        board.place("R1", presets::resistor_0603("10k"), (10000.0005_f64, 2500.0125_f64), 0.0).unwrap();
+        board.place("R2", presets::resistor_0603("1k"), (12.7_f32, 5.1_f32), 90.0).unwrap();
+        let text = to_kicad_pcb(&board);
+        assert!(text.contains("(at 10000.0005 2500.0125)"), "{}", text);
+        assert!(text.contains("(at 12.7 5.1 90)"), "f32 positions widen without binary noise");
+        assert_eq!(precision_warnings(&board), []);
+    }
+
+    #[test]
+    fn block_copied_far_out_keeps_its_offsets() {
+        let mut block = Block::new("pair");
+        block.add_component("R1", Rc::new(presets::resistor_0603("100")), (0.0005_f64, 0.0_f64), 0.0, Side::Front);
+        let mut board = Board::new("far");
+        board.instantiate_block(&block, &BlockPlacement::new("PAIR", Point::new(10000.0, 0.0), 0.0)).unwrap();
+        assert_eq!(board.components()[0].position, (10000.0005, 0.0));
+    }
+
+    #[test]
+    fn far_and_non_finite_f32_coordinates_are_flagged() {
+        let mut board = Board::new("far");
+        let front = BoardLayer::front(LayerType::Copper);
+        board.add_track(Track::new("N1", front, Point::new(10000.0, 2500.0), Point::new(10001.0, 2500.0), 0.2));
+        board.add_via(Via::new("N1", Point::new(f32::NAN, 0.0), 0.6, 0.3));
+        let warnings = precision_warnings(&board);
+        let range: Vec<f32> = warnings
+            .iter()
+            .filter_map(|warning| match warning {
+                ExportWarning::CoordinateRange { value, .. } => Some(*value),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(range, [10000.0, 10001.0]);
+        assert_eq!(warnings.iter().filter(|warning| matches!(warning, ExportWarning::PrecisionLoss { .. })).count(), 1);
+    }
+
+    #[test]
+    fn hatched_zones_carry_kicads_hatch_settings() {
+        let mut board = Board::new("flex").with_rectangular_outline(20.0, 10.0);
+        let outline = vec![Point::new(1.0, 1.0), Point::new(19.0, 1.0), Point::new(19.0, 9.0), Point::new(1.0, 9.0)];
+        let ground = FillStyle::CrossHatch { pitch: 1.5, width: 0.3, angle: 45.0 };
+        board.add_zone(Zone::new("GND", &[BoardLayer::back(LayerType::Copper)], outline).with_fill(ground));
+        let pcb = to_kicad_pcb(&board);
+        let zone: String = pcb.lines().skip_while(|line| !line.contains("(zone")).take(24).collect::<Vec<_>>().join("\n");
+        // KiCad's gap is the clear space between lines, not the pitch
+        for expected in ["(net_name \"GND\")", "(layers \"B.Cu\")", "(mode hatch)", "(hatch_thickness 0.3)", "(hatch_gap 1.2)", "(hatch_orientation 45)"] {
+            assert!(zone.contains(expected), "{} missing from\n{}", expected, zone);
+        }
+    }
+
+    #[test]
+    fn arc_tracks_are_written_in_three_point_form() {
+        let mut board = Board::new("rf").with_rectangular_outline(20.0, 10.0);
+        let front = BoardLayer::front(LayerType::Copper);
+        board.add_track(Track::new("RF", front, Point::new(1.0, 5.0), Point::new(5.0, 5.0), 0.3));
+        board.add_arc(TrackArc::new("RF", front, Point::new(5.0, 5.0), Point::new(6.5, 5.5), Point::new(7.0, 7.0), 0.3));
+        let pcb = to_kicad_pcb(&board);
+        assert!(pcb.contains("(arc (start 5 5) (mid 6.5 5.5) (end 7 7) (width 0.3) (layer \"F.Cu\") (net 1) (uuid"), "{}", pcb);
+        let read = read_board(&pcb).unwrap();
+        assert_eq!((read.tracks(), read.arcs()), (board.tracks(), board.arcs()));
+    }
+
+    #[test]
+    fn pad_nets_are_written_and_read_back() {
+        let mut board = Board::new("nets").with_rectangular_outline(20.0, 10.0);
+        board.place("R1", presets::resistor_0603("10k"), (5.0, 5.0), 0.0).unwrap();
+        board.place_shared("R2", Rc::new(presets::resistor_0603("10k")), (12.0, 5.0), 90.0, Side::Back).unwrap();
+        board.connect_pad("R1", "1", "VCC");
+        board.connect_pad("R1", "2", "OUT");
+        board.connect_pad("R2", "1", "OUT");
+        board.add_track(Track::new("OUT", BoardLayer::front(LayerType::Copper), Point::new(5.8, 5.0), Point::new(9.0, 5.0), 0.2));
+        let pcb = to_kicad_pcb(&board);
+        // Routed nets are numbered first, pad nets after
+        assert!(pcb.contains("(net 1 \"OUT\")") && pcb.contains("(net 2 \"VCC\")"), "{}", pcb);
+        assert_eq!(pcb.matches("(net 1 \"OUT\")").count(), 3, "{}", pcb);
+
+        let read = read_board(&pcb).unwrap();
+        for (reference, number) in [("R1", "1"), ("R1", "2"), ("R2", "1"), ("R2", "2")] {
+            assert_eq!(read.pad_net(reference, number), board.pad_net(reference, number), "{}.{}", reference, number);
+        }
+    }
+}
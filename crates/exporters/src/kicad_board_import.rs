@@ -0,0 +1,293 @@
+//! Reading the copper of a `.kicad_pcb`
+//!
+//! `read_board` turns a board file into a `Board` holding what ends up as copper: every
+//! footprint placed with its pads, tracks, vias and zones, plus the board outline. It is
+//! for looking at a finished board, e.g. rasterizing its artwork (see
+//! `copper_substrate::artwork`) for the 3D viewer, not for editing it: board texts and
+//! graphics are left out, and regenerated boards keep hand edits through
+//! `kicad_board_sync` instead.
+//!
+//! - footprints are read with `KiCadFootprint::parse` and placed as found. KiCad stores
+//!   back side footprints already flipped, so every footprint is placed on the front and
+//!   its pads keep the layers the file gives them. Pads on a net are connected to it.
+//! - vias keep their span and, from KiCad 9 files, the sides they are tented on
+//! - arc tracks are kept as `TrackArc`s, in the file's three point form
+//! - zones take their filled polygons when the file has them, so the artwork shows the
+//!   pour KiCad computed, and their outline otherwise; keepout zones are skipped
+//! - the outline comes from a `gr_rect`, `gr_poly` or `gr_circle` on Edge.Cuts, or from
+//!   `gr_line` and `gr_arc` segments chained end to end. Edges that don't close into one
+//!   loop fall back to their bounding box.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+use copper_substrate::board::Board;
+use copper_substrate::geometry::{push_arc, CircularArc};
+use copper_substrate::prelude::*;
+
+use crate::kicad_board_sync::footprint_instance;
+use crate::kicad_footprint_parse::{FootprintParseError, KiCadFootprint, ParseOptions};
+use crate::sexpr::{self, SExpr, SExprError};
+
+/// Arc tolerance of arc tracks and curved board edges (mm)
+const IMPORT_ARC_TOLERANCE: f32 = 0.005;
+
+/// Edge.Cuts segment ends closer than this (mm) join into one outline
+const EDGE_JOIN_TOLERANCE: f32 = 0.001;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoardReadError {
+    Syntax(SExprError),
+    /// The root list is not `(kicad_pcb ...)`
+    NotABoard,
+    /// A copper item is missing a value or has one that isn't a number
+    Invalid { item: String, message: String },
+    /// A footprint's own contents didn't parse
+    Footprint { reference: String, error: FootprintParseError },
+}
+
+impl fmt::Display for BoardReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardReadError::Syntax(error) => write!(f, "{}", error),
+            BoardReadError::NotABoard => write!(f, "not a KiCad board"),
+            BoardReadError::Invalid { item, message } => write!(f, "{}: {}", item, message),
+            BoardReadError::Footprint { reference, error } => write!(f, "footprint {}: {}", reference, error),
+        }
+    }
+}
+
+impl std::error::Error for BoardReadError {}
+
+impl From<SExprError> for BoardReadError {
+    fn from(error: SExprError) -> Self {
+        BoardReadError::Syntax(error)
+    }
+}
+
+fn invalid(item: &str, message: &str) -> BoardReadError {
+    BoardReadError::Invalid { item: item.to_string(), message: message.to_string() }
+}
+
+fn number(item: &str, value: Option<&SExpr>) -> Result<f32, BoardReadError> {
+    value
+        .and_then(SExpr::as_str)
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| invalid(item, "expected a number"))
+}
+
+/// `(name x y)` as a point
+fn point(item: &str, expr: &SExpr, name: &str) -> Result<Point, BoardReadError> {
+    let values = expr.find(name).ok_or_else(|| invalid(item, &format!("missing ({} ...)", name)))?.children();
+    Ok(Point::new(number(item, values.get(1))?, number(item, values.get(2))?))
+}
+
+/// The `(xy x y)` points of `(pts ...)`
+fn points(item: &str, expr: &SExpr) -> Result<Vec<Point>, BoardReadError> {
+    let pts = expr.find("pts").ok_or_else(|| invalid(item, "missing (pts ...)"))?;
+    pts.find_all("xy")
+        .map(|xy| Ok(Point::new(number(item, xy.children().get(1))?, number(item, xy.children().get(2))?)))
+        .collect()
+}
+
+/// Layer names of `(layer "F.Cu")` or `(layers "F.Cu" "B.Cu")`
+fn layer_names(expr: &SExpr) -> Vec<&str> {
+    match expr.find("layers") {
+        Some(layers) => layers.children()[1..].iter().filter_map(SExpr::as_str).collect(),
+        None => expr.value_of("layer").into_iter().collect(),
+    }
+}
+
+/// Points along the arc from `start` through `mid` to `end`, both ends included; a
+/// straight line when the three are in line
+fn arc_points(start: Point, mid: Point, end: Point) -> Vec<Point> {
+    CircularArc::through(start, mid, end).map_or(vec![start, end], |arc| arc.points(IMPORT_ARC_TOLERANCE))
+}
+
+/// Net names by number, from the board's `(net 1 "GND")` list
+fn net_names(root: &SExpr) -> HashMap<&str, &str> {
+    root.find_all("net")
+        .filter_map(|net| Some((net.children().get(1)?.as_str()?, net.children().get(2)?.as_str()?)))
+        .collect()
+}
+
+/// Name of an item's net: KiCad 8 gives its number, looked up in `nets`
+fn net_of<'a>(expr: &'a SExpr, nets: &HashMap<&str, &'a str>) -> &'a str {
+    expr.value_of("net_name")
+        .or_else(|| expr.value_of("net").map(|net| nets.get(net).copied().unwrap_or(net)))
+        .unwrap_or_default()
+}
+
+/// Footprint angle of a pad stored in board space
+fn local_angle(angle: Option<f32>, footprint_rotation: f32) -> Option<f32> {
+    let angle = (angle.unwrap_or(0.0) - footprint_rotation).rem_euclid(360.0);
+    if angle.abs() < 1e-4 || (360.0 - angle).abs() < 1e-4 { None } else { Some(angle) }
+}
+
+fn read_track(board: &mut Board, expr: &SExpr, nets: &HashMap<&str, &str>) -> Result<(), BoardReadError> {
+    let item = expr.head().unwrap_or_default();
+    let Some(layer) = expr.value_of("layer").and_then(BoardLayer::from_kicad_str) else {
+        return Ok(());
+    };
+    let width = number(item, expr.find("width").and_then(|width| width.children().get(1)))?;
+    let (start, end) = (point(item, expr, "start")?, point(item, expr, "end")?);
+    if item == "arc" {
+        board.add_arc(TrackArc::new(net_of(expr, nets), layer, start, point(item, expr, "mid")?, end, width));
+    } else {
+        board.add_track(Track::new(net_of(expr, nets), layer, start, end, width));
+    }
+    Ok(())
+}
+
+fn read_via(board: &mut Board, expr: &SExpr, nets: &HashMap<&str, &str>) -> Result<(), BoardReadError> {
+    let position = point("via", expr, "at")?;
+    let diameter = number("via", expr.find("size").and_then(|size| size.children().get(1)))?;
+    let drill = number("via", expr.find("drill").and_then(|drill| drill.children().get(1)))?;
+    let mut via = Via::new(net_of(expr, nets), position, diameter, drill);
+    let layers: Vec<BoardLayer> = layer_names(expr).into_iter().filter_map(BoardLayer::from_kicad_str).collect();
+    if let [from, to] = layers[..] {
+        let keyword = |word: &str| expr.children().iter().any(|child| child.as_str() == Some(word));
+        let outer = |layer: &BoardLayer| !matches!(layer.side, Side::Inner(_));
+        let kind = match (keyword("micro"), outer(&from) && outer(&to), outer(&from) || outer(&to)) {
+            (true, _, _) => ViaKind::Micro,
+            (_, true, _) => ViaKind::Through,
+            (_, _, true) => ViaKind::Blind,
+            _ => ViaKind::Buried,
+        };
+        via = via.with_span(kind, from, to);
+    }
+    // Files without a tenting list leave vias to the board setting, tented by default.
+    // KiCad 9 lists the tented sides, `(tenting front back)` or `(tenting (front yes) ...)`.
+    if let Some(tenting) = expr.find("tenting") {
+        let tented = |side: &str| {
+            let covered = tenting.children().iter().any(|child| child.as_str() == Some(side)) || tenting.value_of(side) == Some("yes");
+            if covered { TentingType::Full } else { TentingType::None }
+        };
+        via = via.with_tenting(TentingSettings { front: tented("front"), back: tented("back") });
+    }
+    board.add_via(via);
+    Ok(())
+}
+
+fn read_zone(board: &mut Board, expr: &SExpr, nets: &HashMap<&str, &str>) -> Result<(), BoardReadError> {
+    if expr.find("keepout").is_some() {
+        return Ok(());
+    }
+    let net = net_of(expr, nets);
+    let filled: Vec<&SExpr> = expr.find_all("filled_polygon").collect();
+    if filled.is_empty() {
+        let layers: Vec<BoardLayer> = layer_names(expr).into_iter().filter_map(BoardLayer::from_kicad_str).collect();
+        let polygon = expr.find("polygon").ok_or_else(|| invalid("zone", "missing (polygon ...)"))?;
+        board.add_zone(Zone::new(net, &layers, points("zone", polygon)?));
+    }
+    for polygon in filled {
+        if let Some(layer) = polygon.value_of("layer").and_then(BoardLayer::from_kicad_str) {
+            board.add_zone(Zone::new(net, &[layer], points("zone", polygon)?));
+        }
+    }
+    Ok(())
+}
+
+/// Board outline from the Edge.Cuts items, see the module documentation
+fn read_outline(root: &SExpr) -> Result<Option<Vec<Point>>, BoardReadError> {
+    let mut segments: Vec<Vec<Point>> = Vec::new();
+    for expr in root.children().iter().filter(|expr| expr.value_of("layer") == Some("Edge.Cuts")) {
+        let item = expr.head().unwrap_or_default();
+        match item {
+            "gr_rect" => {
+                let (start, end) = (point(item, expr, "start")?, point(item, expr, "end")?);
+                return Ok(Some(vec![start, Point::new(end.x, start.y), end, Point::new(start.x, end.y)]));
+            }
+            "gr_poly" => return Ok(Some(points(item, expr)?)),
+            "gr_circle" => {
+                let (center, end) = (point(item, expr, "center")?, point(item, expr, "end")?);
+                let mut circle = Vec::new();
+                push_arc(&mut circle, center, center.distance_to(&end), 0.0, 360.0, IMPORT_ARC_TOLERANCE);
+                return Ok(Some(circle));
+            }
+            "gr_line" => segments.push(vec![point(item, expr, "start")?, point(item, expr, "end")?]),
+            "gr_arc" => segments.push(arc_points(point(item, expr, "start")?, point(item, expr, "mid")?, point(item, expr, "end")?)),
+            _ => {}
+        }
+    }
+    let Some(mut outline) = (!segments.is_empty()).then(|| segments.remove(0)) else {
+        return Ok(None);
+    };
+    let joins = |a: &Point, b: &Point| a.distance_to(b) <= EDGE_JOIN_TOLERANCE;
+    while let Some(index) = segments.iter().position(|segment| {
+        let last = outline.last().unwrap();
+        joins(&segment[0], last) || joins(segment.last().unwrap(), last)
+    }) {
+        let mut segment = segments.remove(index);
+        if !joins(&segment[0], outline.last().unwrap()) {
+            segment.reverse();
+        }
+        outline.extend(segment.into_iter().skip(1));
+    }
+    let closed = outline.len() > 3 && joins(&outline[0], outline.last().unwrap());
+    if closed && segments.is_empty() {
+        outline.pop();
+        return Ok(Some(outline));
+    }
+    // Open or several loops: the bounding box of every edge
+    outline.extend(segments.into_iter().flatten());
+    Ok(Rectangle::enclosing(&outline).map(|bounds| {
+        vec![
+            Point::new(bounds.min_x, bounds.min_y),
+            Point::new(bounds.max_x, bounds.min_y),
+            Point::new(bounds.max_x, bounds.max_y),
+            Point::new(bounds.min_x, bounds.max_y),
+        ]
+    }))
+}
+
+/// The copper of a `.kicad_pcb`, see the module documentation
+pub fn read_board(text: &str) -> Result<Board, BoardReadError> {
+    let document = sexpr::parse_document(text)?;
+    let root = &document.root;
+    if root.head() != Some("kicad_pcb") {
+        return Err(BoardReadError::NotABoard);
+    }
+    let nets = net_names(root);
+    let mut board = Board::new("");
+    board.set_outline(read_outline(root)?);
+
+    for (expr, span) in root.children()[1..].iter().zip(&document.child_spans) {
+        match expr.head() {
+            Some("footprint") => {
+                let instance = footprint_instance(expr).ok_or_else(|| invalid("footprint", "missing reference or position"))?;
+                let mut footprint = KiCadFootprint::parse(&text[span.clone()], ParseOptions::default())
+                    .map_err(|error| BoardReadError::Footprint { reference: instance.reference.clone(), error })?;
+                for pad in footprint.pads_mut() {
+                    pad.rotation = local_angle(pad.rotation, instance.rotation);
+                }
+                board
+                    .place_shared(&instance.reference, Rc::new(footprint), instance.position, instance.rotation, Side::Front)
+                    .map_err(|error| invalid(&instance.reference, &error.to_string()))?;
+                for pad in expr.find_all("pad") {
+                    let net = net_of(pad, &nets);
+                    if let (Some(number), false) = (pad.children().get(1).and_then(SExpr::as_str), net.is_empty()) {
+                        board.connect_pad(&instance.reference, number, net);
+                    }
+                }
+            }
+            Some("segment") | Some("arc") => read_track(&mut board, expr, &nets)?,
+            Some("via") => read_via(&mut board, expr, &nets)?,
+            Some("zone") => read_zone(&mut board, expr, &nets)?,
+            _ => {}
+        }
+    }
+    Ok(board)
+}
+
+/// Read a `.kicad_pcb` from disk, naming the board after the file
+pub fn read_board_file(path: &Path) -> io::Result<Board> {
+    let text = fs::read_to_string(path)?;
+    let mut board = read_board(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    board.name = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default().to_string();
+    Ok(board)
+}
@@ -0,0 +1,194 @@
+//! Back-annotation of placements from an edited `.kicad_pcb`
+//!
+//! Boards are generated, a layout engineer moves parts around in pcbnew and the board is
+//! then regenerated. Syncing reads the edited file back and copies each footprint's
+//! position, rotation and side onto the component with the same reference, so the
+//! regenerated board keeps their work. Footprints are matched by reference only; a
+//! footprint swapped for another in KiCad still syncs and is reported. The auxiliary and
+//! grid origins set in pcbnew are copied too.
+//!
+//! Tracks, vias and zones drawn by hand can optionally be carried along as opaque text and
+//! are written back unchanged on the next export.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use copper_substrate::board::Board;
+use copper_substrate::geometry::Point;
+use copper_substrate::layer_type::Side;
+
+use crate::sexpr::{self, SExpr, SExprError};
+
+/// Board items kept verbatim when pass-through is enabled
+const PASSTHROUGH_ITEMS: &[&str] = &["net", "segment", "arc", "via", "zone"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SyncOptions {
+    /// Keep hand drawn tracks, vias and zones (and the nets they use) for the next export
+    pub keep_routing: bool,
+}
+
+/// A footprint instance read from the board file
+#[derive(Debug, Clone, PartialEq)]
+pub struct KiCadFootprintInstance {
+    pub reference: String,
+    /// Library id, e.g. "Resistor_SMD:R_0805_2012Metric"
+    pub footprint: String,
+    pub position: (f64, f64),
+    pub rotation: f32,
+    pub side: Side,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SyncReport {
+    /// References whose position, rotation or side changed
+    pub updated: Vec<String>,
+    /// References on the board that the file doesn't contain
+    pub missing_in_file: Vec<String>,
+    /// References in the file that the board doesn't contain
+    pub missing_on_board: Vec<String>,
+    /// Matched references whose footprint differs: (reference, board footprint, file footprint)
+    pub footprint_changed: Vec<(String, String, String)>,
+    /// Number of routing items carried over
+    pub passthrough_items: usize,
+}
+
+impl SyncReport {
+    /// Whether every reference matched on both sides
+    pub fn is_complete(&self) -> bool {
+        self.missing_in_file.is_empty() && self.missing_on_board.is_empty()
+    }
+}
+
+impl fmt::Display for SyncReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Updated placements: {}", self.updated.len())?;
+        if !self.missing_in_file.is_empty() {
+            writeln!(f, "Not in the KiCad file: {}", self.missing_in_file.join(", "))?;
+        }
+        if !self.missing_on_board.is_empty() {
+            writeln!(f, "Not on the board: {}", self.missing_on_board.join(", "))?;
+        }
+        for (reference, board, file) in &self.footprint_changed {
+            writeln!(f, "{} footprint changed from {} to {}", reference, board, file)?;
+        }
+        if self.passthrough_items > 0 {
+            writeln!(f, "Routing items kept: {}", self.passthrough_items)?;
+        }
+        Ok(())
+    }
+}
+
+/// Footprint instances of a `.kicad_pcb`, in file order
+pub fn read_footprint_instances(text: &str) -> Result<Vec<KiCadFootprintInstance>, SExprError> {
+    let root = sexpr::parse(text)?;
+    if root.head() != Some("kicad_pcb") {
+        return Err(SExprError { message: "not a kicad_pcb".to_string(), offset: 0 });
+    }
+    Ok(root.find_all("footprint").filter_map(footprint_instance).collect())
+}
+
+pub(crate) fn footprint_instance(footprint: &SExpr) -> Option<KiCadFootprintInstance> {
+    let number = |expr: Option<&SExpr>| expr.and_then(SExpr::as_str).and_then(|s| s.parse::<f64>().ok());
+    let at = footprint.find("at")?.children();
+    // KiCad 8+ stores the reference as a property, older files as fp_text
+    let reference = footprint
+        .find_all("property")
+        .find(|property| property.children().get(1).and_then(SExpr::as_str) == Some("Reference"))
+        .or_else(|| {
+            footprint
+                .find_all("fp_text")
+                .find(|text| text.children().get(1).and_then(SExpr::as_str) == Some("reference"))
+        })?
+        .children()
+        .get(2)?
+        .as_str()?;
+
+    Some(KiCadFootprintInstance {
+        reference: reference.to_string(),
+        footprint: footprint.children().get(1)?.as_str()?.to_string(),
+        position: (number(at.get(1))?, number(at.get(2))?),
+        rotation: number(at.get(3)).unwrap_or(0.0) as f32,
+        side: if footprint.value_of("layer") == Some("B.Cu") { Side::Back } else { Side::Front },
+    })
+}
+
+/// Top level routing items of a `.kicad_pcb` as their original text, net 0 excluded since
+/// every export declares it
+fn passthrough_items(text: &str) -> Result<Vec<String>, SExprError> {
+    let document = sexpr::parse_document(text)?;
+    Ok(document.root.children()[1..]
+        .iter()
+        .zip(document.child_spans)
+        .filter(|(child, _)| child.head().is_some_and(|head| PASSTHROUGH_ITEMS.contains(&head)))
+        .filter(|(child, _)| !(child.head() == Some("net") && child.children().get(1).and_then(SExpr::as_str) == Some("0")))
+        .map(|(_, span)| text[span].to_string())
+        .collect())
+}
+
+/// `(name x y)` in the setup section, the page corner when KiCad left it out
+fn setup_origin(root: &SExpr, name: &str) -> Point {
+    let values = root.find("setup").and_then(|setup| setup.find(name)).map(SExpr::children).unwrap_or_default();
+    let coordinate = |index: usize| values.get(index).and_then(SExpr::as_str).and_then(|v| v.parse().ok()).unwrap_or(0.0);
+    Point::new(coordinate(1), coordinate(2))
+}
+
+/// Apply the placements in the text of a `.kicad_pcb` to `board`. Positions are stored as
+/// found; the board's placement policy still snaps them on export, see `audit_placements`.
+pub fn sync_placements_from_str(board: &mut Board, text: &str, options: SyncOptions) -> Result<SyncReport, SExprError> {
+    let instances = read_footprint_instances(text)?;
+    let mut report = SyncReport::default();
+
+    for instance in &instances {
+        let Some(placed) = board.component_mut(&instance.reference) else {
+            report.missing_on_board.push(instance.reference.clone());
+            continue;
+        };
+        let board_footprint = format!("{}:{}", placed.component.library_name(), placed.component.footprint_name());
+        if board_footprint != instance.footprint {
+            report.footprint_changed.push((instance.reference.clone(), board_footprint, instance.footprint.clone()));
+        }
+        if placed.position != instance.position || placed.rotation != instance.rotation || placed.side != instance.side {
+            placed.position = instance.position;
+            placed.rotation = instance.rotation;
+            placed.side = instance.side;
+            report.updated.push(instance.reference.clone());
+        }
+    }
+    report.missing_in_file = board
+        .components()
+        .iter()
+        .filter(|placed| !instances.iter().any(|instance| instance.reference == placed.reference))
+        .map(|placed| placed.reference.clone())
+        .collect();
+
+    let root = sexpr::parse(text)?;
+    board.set_aux_origin(setup_origin(&root, "aux_axis_origin"));
+    board.set_grid_origin(setup_origin(&root, "grid_origin"));
+
+    if options.keep_routing {
+        let items = passthrough_items(text)?;
+        report.passthrough_items = items.iter().filter(|item| !item.starts_with("(net ")).count();
+        board.set_passthrough(items);
+    }
+    Ok(report)
+}
+
+/// Read an edited `.kicad_pcb` from disk and apply its placements to `board`
+pub fn sync_placements_from(board: &mut Board, path: &Path, options: SyncOptions) -> io::Result<SyncReport> {
+    let text = fs::read_to_string(path)?;
+    sync_placements_from_str(board, &text, options).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// `board.sync_placements_from(path)` without routing pass-through
+pub trait KiCadPlacementSync {
+    fn sync_placements_from(&mut self, path: &Path) -> io::Result<SyncReport>;
+}
+
+impl KiCadPlacementSync for Board {
+    fn sync_placements_from(&mut self, path: &Path) -> io::Result<SyncReport> {
+        sync_placements_from(self, path, SyncOptions::default())
+    }
+}
@@ -0,0 +1,1062 @@
+//! Reading `.kicad_mod` footprints for round-trip editing
+//!
+//! `KiCadFootprint::parse` reads a library footprint into the substrate model (pads, text,
+//! lines, rectangles, circles and the 3D model) so it can be checked, rendered or placed
+//! like a generated one. The source text is kept: writing the footprint back splices
+//! only the pad fields that were edited into the original, so everything the model
+//! doesn't cover (custom properties, arcs and polygons, zones, embedded fonts, future
+//! KiCad tokens) is written back verbatim where it was, formatting included.
+//!
+//! Tokens the reader doesn't understand are listed by `unknown_tokens`. Pipelines that
+//! need every token accounted for parse with `ParseOptions::strict`, which refuses such
+//! footprints instead.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+use copper_substrate::prelude::*;
+use copper_substrate::routing_hint::apply_hints_property;
+use uuid::Uuid;
+
+use crate::kicad_pcb_export::drill_expression;
+use crate::sexpr::{self, quote, SExpr, SExprError, SpanTree};
+
+/// Footprint level tokens the reader models or deliberately treats as metadata
+const KNOWN_FOOTPRINT_TOKENS: &[&str] = &[
+    "version", "generator", "generator_version", "layer", "tedit", "uuid", "tstamp", "descr", "tags", "property", "attr",
+    "fp_text", "fp_line", "fp_rect", "fp_circle", "fp_poly", "pad", "model", "duplicate_pad_numbers_are_jumpers", "embedded_fonts",
+    "zone", "net_tie_pad_groups",
+];
+
+/// Pad tokens the reader models or deliberately treats as metadata
+const KNOWN_PAD_TOKENS: &[&str] = &[
+    "at", "size", "drill", "layers", "roundrect_rratio", "uuid", "tstamp", "pinfunction", "pintype", "options", "primitives",
+    "padstack", "property",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Fail on tokens the reader doesn't understand instead of carrying them through
+    pub strict: bool,
+}
+
+impl ParseOptions {
+    pub fn strict() -> Self {
+        Self { strict: true }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FootprintParseError {
+    Syntax(SExprError),
+    /// The root list is not `(footprint ...)` or `(module ...)`
+    NotAFootprint,
+    /// An item is missing a required value or has one that isn't a number
+    Invalid { item: String, message: String },
+    /// Strict mode met a token it doesn't understand, at `path` (e.g. "pad 1 > teardrops")
+    Unknown { path: String, offset: usize },
+}
+
+impl fmt::Display for FootprintParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FootprintParseError::Syntax(error) => write!(f, "{}", error),
+            FootprintParseError::NotAFootprint => write!(f, "not a KiCad footprint"),
+            FootprintParseError::Invalid { item, message } => write!(f, "{}: {}", item, message),
+            FootprintParseError::Unknown { path, offset } => write!(f, "unknown token {} at byte {}", path, offset),
+        }
+    }
+}
+
+impl std::error::Error for FootprintParseError {}
+
+impl From<SExprError> for FootprintParseError {
+    fn from(error: SExprError) -> Self {
+        FootprintParseError::Syntax(error)
+    }
+}
+
+/// A pad as read and as currently edited
+#[derive(Debug, Clone)]
+struct ParsedPad {
+    original: PadDescriptor,
+    pad: PadDescriptor,
+    expr: SExpr,
+    spans: SpanTree,
+}
+
+/// A text as read and as currently edited
+#[derive(Debug, Clone)]
+struct ParsedText {
+    original: FpText,
+    text: FpText,
+    expr: SExpr,
+    spans: SpanTree,
+}
+
+/// A line, rectangle, circle or polygon as read and as currently edited
+#[derive(Debug, Clone)]
+struct ParsedGraphic {
+    original: GraphicElement,
+    element: GraphicElement,
+    expr: SExpr,
+    spans: SpanTree,
+}
+
+/// A footprint read from a `.kicad_mod`, see the module documentation
+#[derive(Debug, Clone)]
+pub struct KiCadFootprint {
+    source: String,
+    name: String,
+    library: String,
+    description: Option<String>,
+    tags: Option<String>,
+    /// Keywords of the `(attr ...)` token, e.g. "smd" or "exclude_from_bom"
+    attributes: Vec<String>,
+    /// `duplicate_pad_numbers_are_jumpers` and `net_tie_pad_groups`; repeated numbers are
+    /// taken as terminals in `pad_grouping`, as KiCad reads them
+    pad_grouping: PadGrouping,
+    texts: Vec<ParsedText>,
+    graphics: Vec<ParsedGraphic>,
+    model: Option<Model3D>,
+    pads: Vec<ParsedPad>,
+    /// Keepout zones; zones that fill copper are kept in the source only
+    rule_areas: Vec<RuleArea>,
+    unknown: Vec<String>,
+}
+
+fn invalid(item: &str, message: &str) -> FootprintParseError {
+    FootprintParseError::Invalid { item: item.to_string(), message: message.to_string() }
+}
+
+fn number(item: &str, value: Option<&SExpr>) -> Result<f32, FootprintParseError> {
+    value
+        .and_then(SExpr::as_str)
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| invalid(item, "expected a number"))
+}
+
+/// `(name x y)` as a point
+fn point(item: &str, expr: &SExpr, name: &str) -> Result<(f32, f32), FootprintParseError> {
+    let values = expr.find(name).ok_or_else(|| invalid(item, &format!("missing ({} ...)", name)))?.children();
+    Ok((number(item, values.get(1))?, number(item, values.get(2))?))
+}
+
+/// Position and optional rotation of `(at x y [angle])`
+fn placement(item: &str, expr: &SExpr) -> Result<((f32, f32), Option<f32>), FootprintParseError> {
+    let position = point(item, expr, "at")?;
+    let rotation = expr.find("at").and_then(|at| at.children().get(3)).map(|a| number(item, Some(a))).transpose()?;
+    Ok((position, rotation.filter(|r| *r != 0.0)))
+}
+
+/// Outline of a custom pad, supported when its primitives are a single `gr_poly`
+fn custom_outline(item: &str, expr: &SExpr) -> Result<Vec<(f32, f32)>, FootprintParseError> {
+    let primitives = expr.find("primitives").map(|p| &p.children()[1..]).unwrap_or_default();
+    let [polygon] = primitives else {
+        return Err(invalid(item, "custom pads need a single gr_poly primitive"));
+    };
+    if polygon.head() != Some("gr_poly") {
+        return Err(invalid(item, "custom pads need a single gr_poly primitive"));
+    }
+    let pts = polygon.find("pts").ok_or_else(|| invalid(item, "missing (pts ...)"))?;
+    pts.children()[1..]
+        .iter()
+        .filter(|xy| xy.head() == Some("xy"))
+        .map(|xy| Ok((number(item, xy.children().get(1))?, number(item, xy.children().get(2))?)))
+        .collect()
+}
+
+fn font(expr: &SExpr) -> FontSettings {
+    let font = expr.find("effects").and_then(|effects| effects.find("font"));
+    let size = font.and_then(|font| point("font", font, "size").ok()).unwrap_or((1.0, 1.0));
+    let thickness = font.and_then(|font| font.value_of("thickness")).and_then(|t| t.parse().ok()).unwrap_or(0.15);
+    FontSettings { size, thickness }
+}
+
+/// A bare `hide` atom or `(hide yes)`, on the text itself or in its effects
+fn hidden(expr: &SExpr) -> bool {
+    let hides = |expr: &SExpr| {
+        expr.children().iter().any(|item| matches!(item, SExpr::Atom(atom) if atom == "hide")) || expr.value_of("hide") == Some("yes")
+    };
+    hides(expr) || expr.find("effects").is_some_and(hides)
+}
+
+/// `(justify ...)` in the text effects, `None` when absent
+fn justify(expr: &SExpr) -> Option<Justify> {
+    let keywords = expr.find("effects")?.find("justify")?;
+    let mut justify = Justify::default();
+    for keyword in keywords.children()[1..].iter().filter_map(SExpr::as_str) {
+        match keyword {
+            "left" => justify.horizontal = HorizontalJustify::Left,
+            "right" => justify.horizontal = HorizontalJustify::Right,
+            "top" => justify.vertical = VerticalJustify::Top,
+            "bottom" => justify.vertical = VerticalJustify::Bottom,
+            "mirror" => justify.mirror = true,
+            _ => {}
+        }
+    }
+    Some(justify)
+}
+
+/// An item's uuid, or its legacy hex timestamp widened to one the way KiCad reads it. Items
+/// without either get a fresh one, as KiCad gives them on load.
+fn uuid_of(expr: &SExpr) -> Uuid {
+    let text = expr.value_of("uuid").or_else(|| expr.value_of("tstamp")).unwrap_or_default();
+    Uuid::parse_str(text)
+        .ok()
+        .or_else(|| u32::from_str_radix(text, 16).ok().map(|tstamp| Uuid::from_u128(tstamp.into())))
+        .unwrap_or_else(Uuid::new_v4)
+}
+
+fn pad_type_keyword(pad_type: &PadType) -> &'static str {
+    match pad_type {
+        PadType::SMD => "smd",
+        PadType::ThroughHole => "thru_hole",
+        PadType::NPTH => "np_thru_hole",
+    }
+}
+
+fn pad_shape_keyword(shape: &PadShape) -> &'static str {
+    match shape {
+        PadShape::RoundRect => "roundrect",
+        PadShape::Rect => "rect",
+        PadShape::Circle => "circle",
+        PadShape::Oval => "oval",
+        PadShape::Custom(_) => "custom",
+    }
+}
+
+fn parse_pad_shape(item: &str, expr: &SExpr, keyword: Option<&str>) -> Result<PadShape, FootprintParseError> {
+    Ok(match keyword {
+        Some("roundrect") => PadShape::RoundRect,
+        Some("rect") => PadShape::Rect,
+        Some("circle") => PadShape::Circle,
+        Some("oval") => PadShape::Oval,
+        Some("custom") => PadShape::Custom(custom_outline(item, expr)?),
+        other => return Err(invalid(item, &format!("unsupported pad shape {:?}", other))),
+    })
+}
+
+/// KiCad 9 `(padstack ...)`: "Inner" or any inner copper layer sets the inner geometry,
+/// "B.Cu" the back. Layers the same as the front are dropped, KiCad lists them anyway.
+fn padstack(item: &str, expr: &SExpr, front: &PadLayerGeometry) -> Result<Option<PadStack>, FootprintParseError> {
+    let Some(padstack) = expr.find("padstack") else {
+        return Ok(None);
+    };
+    let same_as_front = |geometry: &PadLayerGeometry| {
+        !matches!(geometry.shape, PadShape::Custom(_))
+            && pad_shape_keyword(&geometry.shape) == pad_shape_keyword(&front.shape)
+            && geometry.size == front.size
+            && geometry.roundrect_ratio == front.roundrect_ratio
+    };
+    let mut stack = PadStack::default();
+    for layer in padstack.children()[1..].iter().filter(|child| child.head() == Some("layer")) {
+        let name = layer.children().get(1).and_then(SExpr::as_str).unwrap_or_default();
+        let geometry = PadLayerGeometry {
+            shape: parse_pad_shape(item, layer, layer.value_of("shape"))?,
+            size: point(item, layer, "size")?,
+            roundrect_ratio: layer.find("roundrect_rratio").map(|r| number(item, r.children().get(1))).transpose()?,
+        };
+        if same_as_front(&geometry) {
+            continue;
+        }
+        match (name, PadLayerClass::of_layer(name)) {
+            ("Inner", _) | (_, Some(PadLayerClass::Inner)) => {
+                stack.inner.get_or_insert(geometry);
+            }
+            (_, Some(PadLayerClass::Back)) => stack.back = Some(geometry),
+            _ => {}
+        }
+    }
+    Ok((!stack.is_empty()).then_some(stack))
+}
+
+fn parse_pad(expr: &SExpr) -> Result<PadDescriptor, FootprintParseError> {
+    let items = expr.children();
+    let pad_number = items.get(1).and_then(SExpr::as_str).ok_or_else(|| invalid("pad", "missing number"))?.to_string();
+    let item = format!("pad {}", pad_number);
+    let pad_type = match items.get(2).and_then(SExpr::as_str) {
+        Some("smd") => PadType::SMD,
+        Some("thru_hole") => PadType::ThroughHole,
+        Some("np_thru_hole") => PadType::NPTH,
+        other => return Err(invalid(&item, &format!("unsupported pad type {:?}", other))),
+    };
+    let shape = parse_pad_shape(&item, expr, items.get(3).and_then(SExpr::as_str))?;
+    let (position, rotation) = placement(&item, expr)?;
+    // (drill 1.2) or (drill oval 1.2 2.4); a drill offset isn't modelled
+    let (drill, drill_slot) = match expr.find("drill") {
+        Some(drill) => {
+            let children = drill.children();
+            let oval = children.get(1).and_then(SExpr::as_str) == Some("oval");
+            let values: Vec<&SExpr> = children[1..].iter().filter(|v| v.as_str().is_some_and(|v| v != "oval")).collect();
+            let width = number(&item, values.first().copied())?;
+            let height = if oval { values.get(1).map(|value| number(&item, Some(*value))).transpose()? } else { None };
+            let slot = height.filter(|height| *height != width).map(|height| (width, height));
+            (Some(slot.map_or(width, |(width, height)| width.min(height))), slot)
+        }
+        None => (None, None),
+    };
+    let front = PadLayerGeometry {
+        shape,
+        size: point(&item, expr, "size")?,
+        roundrect_ratio: expr.find("roundrect_rratio").map(|r| number(&item, r.children().get(1))).transpose()?,
+    };
+    let padstack = padstack(&item, expr, &front)?;
+    Ok(PadDescriptor {
+        number: pad_number.into(),
+        pin_function: expr.value_of("pinfunction").map(str::to_string),
+        pad_type,
+        shape: front.shape,
+        position,
+        rotation,
+        size: front.size,
+        drill_size: drill,
+        drill_slot,
+        layers: expr
+            .find("layers")
+            .map(|layers| layers.children()[1..].iter().filter_map(SExpr::as_str).map(LayerName::from).collect())
+            .unwrap_or_default(),
+        roundrect_ratio: front.roundrect_ratio,
+        tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+        padstack,
+        fab_property: match expr.value_of("property") {
+            Some("pad_prop_heatsink") => Some(PadFabProperty::Heatsink),
+            Some("pad_prop_mechanical") => Some(PadFabProperty::Mechanical),
+            Some("pad_prop_testpoint") => Some(PadFabProperty::TestPoint),
+            Some("pad_prop_fiducial_loc") => Some(PadFabProperty::LocalFiducial),
+            Some("pad_prop_fiducial_glob") => Some(PadFabProperty::GlobalFiducial),
+            _ => None,
+        },
+        solder_mask_margin: expr.find("solder_mask_margin").map(|m| number(&item, m.children().get(1))).transpose()?,
+        routing_hint: None,
+        uuid: uuid_of(expr),
+    })
+}
+
+fn parse_text(expr: &SExpr) -> Result<Option<FpText>, FootprintParseError> {
+    let items = expr.children();
+    let (text_type, text) = match (expr.head(), items.get(1).and_then(SExpr::as_str), items.get(2).and_then(SExpr::as_str)) {
+        (Some("fp_text"), Some("reference"), Some(text)) | (Some("property"), Some("Reference"), Some(text)) => {
+            (FpTextType::Reference, text)
+        }
+        (Some("fp_text"), Some("value"), Some(text)) | (Some("property"), Some("Value"), Some(text)) => (FpTextType::Value, text),
+        (Some("fp_text"), Some("user"), Some(text)) => (FpTextType::User, text),
+        // Other properties (datasheet, description) are hidden fields, not drawn text
+        _ => return Ok(None),
+    };
+    let (position, rotation) = placement("text", expr)?;
+    Ok(Some(FpText {
+        text_type,
+        text: text.to_string(),
+        position,
+        rotation,
+        layer: expr.value_of("layer").unwrap_or("F.SilkS").into(),
+        uuid: uuid_of(expr),
+        font: font(expr),
+        hidden: hidden(expr),
+        justify: justify(expr),
+        role: None,
+    }))
+}
+
+fn parse_graphic(expr: &SExpr) -> Result<Option<GraphicElement>, FootprintParseError> {
+    let head = expr.head().unwrap_or_default();
+    let Some(BoardLayer { layer, side }) = expr.value_of("layer").and_then(BoardLayer::from_kicad_str) else {
+        return Ok(None);
+    };
+    let width = expr
+        .find("stroke")
+        .and_then(|stroke| stroke.value_of("width"))
+        .or_else(|| expr.value_of("width"))
+        .and_then(|w| w.parse().ok())
+        .unwrap_or(0.1);
+    let stroke_type = match expr.find("stroke").and_then(|stroke| stroke.value_of("type")) {
+        Some("dash") => StrokeType::Dashed,
+        Some("dot") => StrokeType::Dotted,
+        _ => StrokeType::Solid,
+    };
+    let element_type = match head {
+        "fp_line" => GraphicType::Line { start: point(head, expr, "start")?, end: point(head, expr, "end")? },
+        "fp_rect" => {
+            let (start, end) = (point(head, expr, "start")?, point(head, expr, "end")?);
+            GraphicType::Rectangle {
+                bounds: Rectangle { min_x: start.0.min(end.0), min_y: start.1.min(end.1), max_x: start.0.max(end.0), max_y: start.1.max(end.1) },
+            }
+        }
+        "fp_circle" => {
+            let (center, end) = (point(head, expr, "center")?, point(head, expr, "end")?);
+            GraphicType::Circle { center, radius: Point::from(center).distance_to(&Point::from(end)) }
+        }
+        "fp_poly" => {
+            let pts = expr.find("pts").ok_or_else(|| invalid(head, "missing pts"))?;
+            let mut points = Vec::new();
+            for xy in pts.find_all("xy") {
+                points.push((number(head, xy.children().get(1))?, number(head, xy.children().get(2))?));
+            }
+            let fill = matches!(expr.value_of("fill"), Some("yes") | Some("solid")).then_some(FillStyle::Solid);
+            GraphicType::Polygon { points, fill }
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(GraphicElement { element_type, layer, side, stroke: Stroke { width, stroke_type }, uuid: uuid_of(expr), role: None }))
+}
+
+fn parse_model(expr: &SExpr) -> Result<Model3D, FootprintParseError> {
+    let path = expr.children().get(1).and_then(SExpr::as_str).ok_or_else(|| invalid("model", "missing path"))?;
+    let xyz = |name: &str, default: (f32, f32, f32)| -> Result<(f32, f32, f32), FootprintParseError> {
+        // KiCad 5 wrote the offset as (at (xyz ...)) in inches
+        let (found, unit) = match expr.find(name) {
+            None if name == "offset" => (expr.find("at"), 25.4),
+            found => (found, 1.0),
+        };
+        match found.and_then(|e| e.find("xyz")) {
+            Some(xyz) => {
+                let v = xyz.children();
+                Ok((number("model", v.get(1))? * unit, number("model", v.get(2))? * unit, number("model", v.get(3))? * unit))
+            }
+            None => Ok(default),
+        }
+    };
+    Ok(Model3D {
+        path: path.to_string(),
+        offset: xyz("offset", (0.0, 0.0, 0.0))?,
+        scale: xyz("scale", (1.0, 1.0, 1.0))?,
+        rotation: xyz("rotate", (0.0, 0.0, 0.0))?,
+    })
+}
+
+/// A `(zone ...)` with a `(keepout ...)`, `None` for ordinary zones
+fn parse_rule_area(expr: &SExpr) -> Result<Option<RuleArea>, FootprintParseError> {
+    let Some(keepout) = expr.find("keepout") else {
+        return Ok(None);
+    };
+    let layers: Vec<String> = match expr.find("layers").or_else(|| expr.find("layer")) {
+        Some(layers) => layers.children()[1..].iter().filter_map(SExpr::as_str).map(str::to_string).collect(),
+        None => return Err(invalid("zone", "missing layers")),
+    };
+    let pts = expr.find("polygon").and_then(|polygon| polygon.find("pts")).ok_or_else(|| invalid("zone", "missing polygon"))?;
+    let mut polygon = Vec::new();
+    for xy in pts.find_all("xy") {
+        polygon.push((number("zone", xy.children().get(1))?, number("zone", xy.children().get(2))?));
+    }
+    let layer_names: Vec<&str> = layers.iter().map(String::as_str).collect();
+    let keeps_out = |name: &str| keepout.value_of(name) == Some("not_allowed");
+    let mut area = RuleArea::keepout(expr.value_of("name").unwrap_or(""), polygon, &layer_names);
+    (area.no_tracks, area.no_vias, area.no_copper_pour) = (keeps_out("tracks"), keeps_out("vias"), keeps_out("copperpour"));
+    Ok(Some(area))
+}
+
+impl KiCadFootprint {
+    pub fn parse(text: &str, options: ParseOptions) -> Result<Self, FootprintParseError> {
+        let (root, spans) = sexpr::parse_with_spans(text)?;
+        if !matches!(root.head(), Some("footprint") | Some("module")) {
+            return Err(FootprintParseError::NotAFootprint);
+        }
+        let items = root.children();
+        let name = items.get(1).and_then(SExpr::as_str).ok_or(FootprintParseError::NotAFootprint)?;
+        let mut footprint = Self {
+            source: text.to_string(),
+            name: name.to_string(),
+            library: String::new(),
+            description: None,
+            tags: None,
+            attributes: Vec::new(),
+            pad_grouping: PadGrouping::new(),
+            texts: Vec::new(),
+            graphics: Vec::new(),
+            model: None,
+            pads: Vec::new(),
+            rule_areas: Vec::new(),
+            unknown: Vec::new(),
+        };
+        let mut unknown = |path: String, span: &SpanTree| -> Result<(), FootprintParseError> {
+            if options.strict {
+                return Err(FootprintParseError::Unknown { path, offset: span.span.start });
+            }
+            footprint.unknown.push(path);
+            Ok(())
+        };
+
+        let mut pads = Vec::new();
+        let mut hints = None;
+        for (expr, span) in items.iter().zip(&spans.items).skip(2) {
+            let Some(head) = expr.head() else {
+                unknown(expr.as_str().unwrap_or("()").to_string(), span)?;
+                continue;
+            };
+            if !KNOWN_FOOTPRINT_TOKENS.contains(&head) {
+                unknown(head.to_string(), span)?;
+                continue;
+            }
+            match head {
+                "descr" => footprint.description = expr.children().get(1).and_then(SExpr::as_str).map(str::to_string),
+                "tags" => footprint.tags = expr.children().get(1).and_then(SExpr::as_str).map(str::to_string),
+                "attr" => footprint.attributes = expr.children()[1..].iter().filter_map(SExpr::as_str).map(str::to_string).collect(),
+                "duplicate_pad_numbers_are_jumpers" => {
+                    footprint.pad_grouping.duplicates_are_jumpers = expr.children().get(1).and_then(SExpr::as_str) == Some("yes");
+                }
+                // Each group a string of comma separated numbers, e.g. "1, 2"
+                "net_tie_pad_groups" => {
+                    footprint.pad_grouping.net_ties = expr.children()[1..]
+                        .iter()
+                        .filter_map(SExpr::as_str)
+                        .map(|group| group.split(',').map(str::trim).filter(|number| !number.is_empty()).map(str::to_string).collect())
+                        .collect();
+                }
+                "property" if expr.children().get(1).and_then(SExpr::as_str) == Some(ROUTING_HINTS_PROPERTY) => {
+                    hints = expr.children().get(2).and_then(SExpr::as_str).map(str::to_string);
+                }
+                "fp_text" | "property" => {
+                    if let Some(text) = parse_text(expr)? {
+                        footprint.texts.push(ParsedText { original: text.clone(), text, expr: expr.clone(), spans: span.clone() });
+                    }
+                }
+                "fp_line" | "fp_rect" | "fp_circle" | "fp_poly" => {
+                    if let Some(element) = parse_graphic(expr)? {
+                        footprint.graphics.push(ParsedGraphic { original: element.clone(), element, expr: expr.clone(), spans: span.clone() });
+                    }
+                }
+                "model" => footprint.model = Some(parse_model(expr)?),
+                "zone" => footprint.rule_areas.extend(parse_rule_area(expr)?),
+                "pad" => {
+                    let pad = parse_pad(expr)?;
+                    for child in expr.children().iter().zip(&span.items).skip(4) {
+                        if let (Some(token), child_span) = (child.0.head(), child.1)
+                            && !KNOWN_PAD_TOKENS.contains(&token)
+                        {
+                            unknown(format!("pad {} > {}", pad.number, token), child_span)?;
+                        }
+                    }
+                    pads.push(ParsedPad { original: pad.clone(), pad, expr: expr.clone(), spans: span.clone() });
+                }
+                _ => {}
+            }
+        }
+        if let Some(hints) = hints {
+            let mut descriptors: Vec<PadDescriptor> = pads.iter().map(|parsed| parsed.pad.clone()).collect();
+            apply_hints_property(&mut descriptors, &hints).map_err(|e| FootprintParseError::Invalid {
+                item: format!("property \"{}\"", ROUTING_HINTS_PROPERTY),
+                message: e.to_string(),
+            })?;
+            for (parsed, pad) in pads.iter_mut().zip(descriptors) {
+                parsed.original.routing_hint = pad.routing_hint.clone();
+                parsed.pad = pad;
+            }
+        }
+        footprint.pads = pads;
+        Ok(footprint)
+    }
+
+    /// Read a `.kicad_mod`, taking the library name from an enclosing `<name>.pretty`
+    pub fn read(path: &Path, options: ParseOptions) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let mut footprint = Self::parse(&text, options).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if let Some(library) = path.parent().and_then(Path::file_name).and_then(|dir| dir.to_str()?.strip_suffix(".pretty")) {
+            footprint.library = library.to_string();
+        }
+        Ok(footprint)
+    }
+
+    pub fn with_library_name(mut self, library: &str) -> Self {
+        self.library = library.to_string();
+        self
+    }
+
+    /// Paths of the tokens carried through without being understood, e.g.
+    /// "embedded_fonts" or "pad 1 > teardrops"
+    pub fn unknown_tokens(&self) -> &[String] {
+        &self.unknown
+    }
+
+    pub fn attributes(&self) -> &[String] {
+        &self.attributes
+    }
+
+    /// The first pad numbered `number`, for editing. Position, rotation, size, drill,
+    /// layers, shape, type and corner ratio edits are written back; other fields are not.
+    pub fn pad_mut(&mut self, number: &str) -> Option<&mut PadDescriptor> {
+        self.pads.iter_mut().find(|parsed| parsed.pad.number == number).map(|parsed| &mut parsed.pad)
+    }
+
+    pub fn pads_mut(&mut self) -> impl Iterator<Item = &mut PadDescriptor> {
+        self.pads.iter_mut().map(|parsed| &mut parsed.pad)
+    }
+
+    /// Restyle the reference text by `policy`. Font size, thickness and visibility edits
+    /// are written back; hidden references stay in the file with a hide flag.
+    pub fn apply_text_policy(&mut self, policy: &TextPolicy) {
+        let (functional_type, body) = (self.functional_type(), self.bounding_box());
+        for parsed in &mut self.texts {
+            policy.apply(std::slice::from_mut(&mut parsed.text), &functional_type, &body);
+        }
+    }
+
+    /// Take the fonts and visibility of `texts` and the stroke widths of `graphics`, in
+    /// the order `fp_text_elements` and `graphic_elements` return them. False, with
+    /// nothing taken, when anything else differs: such edits can't be written back.
+    fn restyle(&mut self, texts: &[FpText], graphics: &[GraphicElement]) -> bool {
+        let mut drawn: Vec<&mut ParsedGraphic> =
+            self.graphics.iter_mut().filter(|parsed| parsed.element.layer != LayerType::Courtyard).collect();
+        let same_text = |old: &FpText, new: &FpText| {
+            std::mem::discriminant(&old.text_type) == std::mem::discriminant(&new.text_type)
+                && (&old.text, old.position, old.rotation, &old.layer, old.uuid, old.justify)
+                    == (&new.text, new.position, new.rotation, &new.layer, new.uuid, new.justify)
+        };
+        let same_graphic = |old: &GraphicElement, new: &GraphicElement| {
+            (&old.element_type, old.layer, old.side, &old.stroke.stroke_type, old.uuid)
+                == (&new.element_type, new.layer, new.side, &new.stroke.stroke_type, new.uuid)
+        };
+        if texts.len() != self.texts.len()
+            || graphics.len() != drawn.len()
+            || !self.texts.iter().zip(texts).all(|(parsed, text)| same_text(&parsed.text, text))
+            || !drawn.iter().zip(graphics).all(|(parsed, element)| same_graphic(&parsed.element, element))
+        {
+            return false;
+        }
+        for (parsed, text) in self.texts.iter_mut().zip(texts) {
+            (parsed.text.font, parsed.text.hidden) = (text.font.clone(), text.hidden);
+        }
+        for (parsed, element) in drawn.iter_mut().zip(graphics) {
+            parsed.element.stroke.width = element.stroke.width;
+        }
+        true
+    }
+
+    /// The footprint text: the source with pad, text and stroke width edits spliced in
+    pub fn to_kicad_string(&self) -> String {
+        let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+        for parsed in &self.pads {
+            edits.extend(self.pad_edits(parsed));
+        }
+        for parsed in &self.texts {
+            edits.extend(self.text_edits(parsed));
+        }
+        for parsed in &self.graphics {
+            edits.extend(self.graphic_edits(parsed));
+        }
+        edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+        let mut output = self.source.clone();
+        for (range, text) in edits {
+            output.replace_range(range, &text);
+        }
+        output
+    }
+
+    /// An edit putting `text` after `anchor`, with the same whitespace that precedes it
+    fn insert_after(&self, anchor: Range<usize>, text: String) -> (Range<usize>, String) {
+        let separator = &self.source[self.source[..anchor.start].trim_end().len()..anchor.start];
+        (anchor.end..anchor.end, format!("{}{}", separator, text))
+    }
+
+    /// An edit removing `range` along with the whitespace before it
+    fn remove(&self, range: Range<usize>) -> (Range<usize>, String) {
+        let start = self.source[..range.start].trim_end().len();
+        (start..range.end, String::new())
+    }
+
+    /// Source edits that bring a pad's text in line with its edited descriptor
+    fn pad_edits(&self, parsed: &ParsedPad) -> Vec<(Range<usize>, String)> {
+        let (old, new) = (&parsed.original, &parsed.pad);
+        let items = parsed.expr.children();
+        let child = |name: &str| items.iter().position(|item| item.head() == Some(name)).map(|i| parsed.spans.items[i].span.clone());
+        let insert_after = |anchor, text| self.insert_after(anchor, text);
+        let remove = |range| self.remove(range);
+
+        let mut edits = Vec::new();
+        if old.number != new.number {
+            edits.push((parsed.spans.items[1].span.clone(), quote(&new.number)));
+        }
+        if pad_type_keyword(&old.pad_type) != pad_type_keyword(&new.pad_type) {
+            edits.push((parsed.spans.items[2].span.clone(), pad_type_keyword(&new.pad_type).to_string()));
+        }
+        if pad_shape_keyword(&old.shape) != pad_shape_keyword(&new.shape) {
+            edits.push((parsed.spans.items[3].span.clone(), pad_shape_keyword(&new.shape).to_string()));
+        }
+        if (old.position, old.rotation) != (new.position, new.rotation)
+            && let Some(at) = child("at")
+        {
+            let text = match new.rotation {
+                Some(rotation) => format!("(at {} {} {})", new.position.0, new.position.1, rotation),
+                None => format!("(at {} {})", new.position.0, new.position.1),
+            };
+            edits.push((at, text));
+        }
+        let size = child("size");
+        if old.size != new.size
+            && let Some(size) = size.clone()
+        {
+            edits.push((size, format!("(size {} {})", new.size.0, new.size.1)));
+        }
+        if (old.drill_size, old.drill_slot) != (new.drill_size, new.drill_slot) {
+            match (child("drill"), drill_expression(new)) {
+                (Some(drill), Some(text)) => edits.push((drill, text)),
+                (Some(drill), None) => edits.push(remove(drill)),
+                (None, Some(text)) => edits.extend(size.clone().map(|size| insert_after(size, text))),
+                (None, None) => {}
+            }
+        }
+        let layers = child("layers");
+        if old.layers != new.layers
+            && let Some(layers) = layers.clone()
+        {
+            let names: Vec<String> = new.layers.iter().map(|layer| quote(layer)).collect();
+            edits.push((layers, format!("(layers {})", names.join(" "))));
+        }
+        if old.roundrect_ratio != new.roundrect_ratio {
+            match (child("roundrect_rratio"), new.roundrect_ratio) {
+                (Some(ratio), Some(value)) => edits.push((ratio, format!("(roundrect_rratio {})", value))),
+                (Some(ratio), None) => edits.push(remove(ratio)),
+                (None, Some(value)) => {
+                    edits.extend(layers.or(size).map(|anchor| insert_after(anchor, format!("(roundrect_rratio {})", value))))
+                }
+                (None, None) => {}
+            }
+        }
+        edits
+    }
+
+    /// Source edits that bring a text's font and visibility in line with its edited settings
+    fn text_edits(&self, parsed: &ParsedText) -> Vec<(Range<usize>, String)> {
+        let (old, new) = (&parsed.original, &parsed.text);
+        let items = parsed.expr.children();
+        let position = |expr: &SExpr, name: &str| expr.children().iter().position(|item| item.head() == Some(name));
+
+        let mut edits = Vec::new();
+        let effects = position(&parsed.expr, "effects");
+        let font = effects.and_then(|e| position(&items[e], "font").map(|f| (&items[e].children()[f], &parsed.spans.items[e].items[f])));
+        if let Some((font, font_spans)) = font {
+            let child = |name: &str| position(font, name).map(|i| font_spans.items[i].span.clone());
+            let size = child("size");
+            if old.font.size != new.font.size
+                && let Some(size) = size.clone()
+            {
+                edits.push((size, format!("(size {} {})", new.font.size.0, new.font.size.1)));
+            }
+            if old.font.thickness != new.font.thickness {
+                let thickness = format!("(thickness {})", new.font.thickness);
+                match child("thickness") {
+                    Some(range) => edits.push((range, thickness)),
+                    None => edits.extend(size.map(|size| self.insert_after(size, thickness))),
+                }
+            }
+        }
+
+        if old.hidden != new.hidden {
+            if new.hidden {
+                // Legacy fp_text takes a bare keyword, properties the KiCad 8 form
+                let hide = if parsed.expr.head() == Some("fp_text") { "hide" } else { "(hide yes)" };
+                let anchor = position(&parsed.expr, "layer").or(position(&parsed.expr, "at"));
+                edits.extend(anchor.map(|i| self.insert_after(parsed.spans.items[i].span.clone(), hide.to_string())));
+            } else {
+                let is_hide = |item: &SExpr| matches!(item, SExpr::Atom(atom) if atom == "hide") || item.head() == Some("hide");
+                for (item, span) in items.iter().zip(&parsed.spans.items) {
+                    if is_hide(item) {
+                        edits.push(self.remove(span.span.clone()));
+                    }
+                }
+                if let Some(e) = effects {
+                    for (item, span) in items[e].children().iter().zip(&parsed.spans.items[e].items) {
+                        if is_hide(item) {
+                            edits.push(self.remove(span.span.clone()));
+                        }
+                    }
+                }
+            }
+        }
+        edits
+    }
+
+    /// The source edit that brings a graphic's stroke width in line with its edited one
+    fn graphic_edits(&self, parsed: &ParsedGraphic) -> Option<(Range<usize>, String)> {
+        let width = parsed.element.stroke.width;
+        if parsed.original.stroke.width == width {
+            return None;
+        }
+        let items = parsed.expr.children();
+        let position = |expr: &SExpr, name: &str| expr.children().iter().position(|item| item.head() == Some(name));
+        // KiCad 6 and later nest the width in (stroke ...), KiCad 5 wrote it directly
+        let nested = position(&parsed.expr, "stroke").and_then(|s| position(&items[s], "width").map(|w| parsed.spans.items[s].items[w].span.clone()));
+        match nested.or_else(|| position(&parsed.expr, "width").map(|w| parsed.spans.items[w].span.clone())) {
+            Some(range) => Some((range, format!("(width {})", width))),
+            None => position(&parsed.expr, "layer")
+                .map(|layer| self.insert_after(parsed.spans.items[layer].span.clone(), format!("(stroke (width {}) (type solid))", width))),
+        }
+    }
+}
+
+impl BoardComposableObject for KiCadFootprint {
+    fn is_smt(&self) -> bool {
+        self.attributes.iter().any(|a| a == "smd")
+            || (!self.pads.is_empty() && self.pads.iter().all(|parsed| matches!(parsed.pad.pad_type, PadType::SMD)))
+    }
+
+    fn is_electrical(&self) -> bool {
+        self.pads.iter().any(|parsed| !parsed.pad.number.is_empty() && parsed.pad.has_copper())
+    }
+
+    fn is_assembled(&self) -> bool {
+        !self.attributes.iter().any(|a| a == "exclude_from_bom" || a == "exclude_from_pos_files")
+    }
+
+    fn allows_soldermask_bridges(&self) -> bool {
+        self.attributes.iter().any(|a| a == "allow_soldermask_bridges")
+    }
+
+    /// KiCad takes every number a file repeats as deliberate, one terminal unless the file
+    /// makes them jumpers
+    fn pad_grouping(&self) -> PadGrouping {
+        let mut grouping = self.pad_grouping.clone();
+        if !grouping.duplicates_are_jumpers {
+            let numbers: Vec<&str> = self.pads.iter().map(|parsed| parsed.pad.number.as_str()).filter(|number| !number.is_empty()).collect();
+            for (index, number) in numbers.iter().enumerate() {
+                if numbers[..index].contains(number) && !grouping.terminals.iter().any(|terminal| terminal == number) {
+                    grouping.terminals.push(number.to_string());
+                }
+            }
+        }
+        grouping
+    }
+
+    fn terminal_count(&self) -> usize {
+        let mut numbers: Vec<&str> =
+            self.pads.iter().map(|parsed| parsed.pad.number.as_str()).filter(|number| !number.is_empty()).collect();
+        numbers.sort_unstable();
+        numbers.dedup();
+        numbers.len()
+    }
+
+    /// Guessed from the KiCad name prefix ("R_", "C_", "LED_", ...), with the value text
+    fn functional_type(&self) -> FunctionalType {
+        let value = self
+            .texts
+            .iter()
+            .find(|parsed| matches!(parsed.text.text_type, FpTextType::Value))
+            .map_or_else(|| self.name.clone(), |parsed| parsed.text.text.clone());
+        match self.name.split('_').next().unwrap_or_default() {
+            "R" => FunctionalType::Resistor(value),
+            "C" | "CP" => FunctionalType::Capacitor(value),
+            "L" => FunctionalType::Inductor(value),
+            "LED" => FunctionalType::LED(value),
+            "D" => FunctionalType::Protection(value),
+            "Fuse" => FunctionalType::Fuse(value),
+            "Fiducial" => FunctionalType::Fiducial(value),
+            "Crystal" => FunctionalType::Crystal(value),
+            "Oscillator" => FunctionalType::Oscillator(value),
+            "MountingHole" => FunctionalType::MountingHole(value),
+            _ if self.library.starts_with("Connector") => FunctionalType::Connector(value),
+            _ => FunctionalType::IntegratedCircuit(value),
+        }
+    }
+
+    fn footprint_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn library_name(&self) -> String {
+        self.library.clone()
+    }
+
+    /// The fab layer outline, or the pads when the footprint has none
+    fn bounding_box(&self) -> Rectangle {
+        self.computed_bounding_box()
+    }
+
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        self.pads.iter().map(|parsed| parsed.pad.clone()).collect()
+    }
+
+    fn description(&self) -> Option<String> {
+        self.description.clone()
+    }
+
+    fn tags(&self) -> Option<String> {
+        self.tags.clone()
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        self.texts.iter().map(|parsed| parsed.text.clone()).collect()
+    }
+
+    /// The lines, rectangles and circles outside the courtyard, which `generate_courtyard`
+    /// stands for
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        self.graphics.iter().map(|parsed| &parsed.element).filter(|element| element.layer != LayerType::Courtyard).cloned().collect()
+    }
+
+    fn model_3d(&self) -> Option<Model3D> {
+        self.model.clone()
+    }
+
+    fn kicad_source(&self) -> Option<String> {
+        Some(self.to_kicad_string())
+    }
+
+    /// The source with the font, visibility and stroke width edits spliced in; other
+    /// edits can't be
+    fn kicad_source_with(&self, texts: &[FpText], graphics: &[GraphicElement]) -> Option<String> {
+        let mut edited = self.clone();
+        edited.restyle(texts, graphics).then(|| edited.to_kicad_string())
+    }
+
+    fn rule_areas(&self) -> Vec<RuleArea> {
+        self.rule_areas.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use copper_substrate::analysis::{FootprintDrc, PinMappingReport};
+    use copper_substrate::pad_groups::diff_pads;
+
+    use super::*;
+    use crate::kicad_pcb_export::{to_kicad_footprint, to_kicad_footprint_for, write_detailed_pad, KiCadVersion};
+    use crate::kicad_sym_export::to_kicad_symbol;
+
+    const QFN: &str = include_str!("../../../examples/fixtures/snapeda_QFN-16_3x3.kicad_mod");
+
+    /// Minimums above everything the QFN's silkscreen has
+    fn strict_silk() -> FabProfile {
+        FabProfile { min_silk_line_width: 0.25, min_silk_text_height: 1.2, min_silk_text_thickness: 0.18, ..FabProfile::standard() }
+    }
+
+    #[test]
+    fn silk_sized_footprint_keeps_its_source() {
+        let sized = KiCadFootprint::parse(QFN, ParseOptions::default()).unwrap().with_silk_minimums(&strict_silk());
+        assert_eq!(sized.changes().len(), 3);
+        let written = to_kicad_footprint(&sized);
+        assert_eq!(written.matches("(fp_arc").count(), 1);
+        assert_eq!(written.matches("(property").count(), 2);
+        assert_eq!(written.matches("(width 0.25)").count(), 2);
+        assert_eq!(written.matches("(width 0.127)").count(), 1, "the fab outline keeps its width");
+        assert!(written.contains("(size 1.2 1.2)\n\t\t\t\t(thickness 0.18)"), "{}", written);
+
+        let reread = KiCadFootprint::parse(&written, ParseOptions::default()).unwrap();
+        assert!(FootprintDrc::new(strict_silk()).silk_size_violations(&reread).is_empty());
+    }
+
+    #[test]
+    fn silk_sized_footprint_within_minimums_is_written_verbatim() {
+        let sized = KiCadFootprint::parse(QFN, ParseOptions::default()).unwrap().with_silk_minimums(&FabProfile::standard());
+        assert!(sized.changes().is_empty());
+        assert_eq!(sized.kicad_source().as_deref(), Some(QFN));
+    }
+
+    #[test]
+    fn scaling_refuses_parsed_footprints() {
+        let footprint = KiCadFootprint::parse(QFN, ParseOptions::default()).unwrap();
+        let error = footprint.scaled(1.2, 1.2, &FabProfile::standard()).unwrap_err();
+        assert_eq!(error, ScaleError::ParsedSource { footprint: "QFN50P300X300X80-17N".to_string() });
+    }
+
+    #[test]
+    fn rebasing_refuses_to_shift_parsed_footprints() {
+        let footprint = KiCadFootprint::parse(QFN, ParseOptions::default()).unwrap();
+        let error = footprint.clone().rebase_to(Anchor::Pin1).unwrap_err();
+        assert_eq!(error, RebaseError::ParsedSource { footprint: "QFN50P300X300X80-17N".to_string(), shift: (1.45, 0.75) });
+        let unshifted = footprint.rebase_to(Anchor::Custom(Point::new(0.0, 0.0))).unwrap();
+        assert_eq!(unshifted.kicad_source().as_deref(), Some(QFN));
+    }
+
+    #[test]
+    fn simplification_leaves_parsed_footprints_as_read() {
+        let footprint = KiCadFootprint::parse(QFN, ParseOptions::default()).unwrap();
+        let simplified = Simplified::new(&footprint, 0.5);
+        assert_eq!(simplified.graphic_elements(), footprint.graphic_elements());
+        assert_eq!(to_kicad_footprint(&simplified), QFN);
+    }
+
+    #[test]
+    fn moved_text_is_not_spliced() {
+        let footprint = KiCadFootprint::parse(QFN, ParseOptions::default()).unwrap();
+        let mut texts = footprint.fp_text_elements();
+        texts[0].position.0 += 1.0;
+        assert_eq!(footprint.kicad_source_with(&texts, &footprint.graphic_elements()), None);
+    }
+
+    #[test]
+    fn heatsink_pads_survive_a_round_trip() {
+        let footprint = KiCadFootprint::parse(QFN, ParseOptions::default()).unwrap();
+        let mut exposed = footprint.pad_descriptors().into_iter().find(|pad| pad.number == "17").unwrap();
+        exposed.fab_property = Some(PadFabProperty::Heatsink);
+        let mut written = String::new();
+        write_detailed_pad(&mut written, &exposed);
+        assert!(written.contains("(property pad_prop_heatsink)"), "{}", written);
+
+        let original = QFN.lines().find(|line| line.contains("(pad \"17\"")).unwrap();
+        let text = QFN.replace(original, written.trim_end());
+        let reread = KiCadFootprint::parse(&text, ParseOptions::default()).unwrap();
+        let pad = reread.pad_descriptors().into_iter().find(|pad| pad.number == "17").unwrap();
+        assert_eq!(pad.fab_property, Some(PadFabProperty::Heatsink));
+
+        // A heatsink needs no symbol pin
+        let pins: Vec<Pin> = (1..=16)
+            .map(|n| Pin { id: n, number: n.to_string(), name: None, position: (0.0, 0.0), electrical_type: ElectricalType::Passive })
+            .collect();
+        let report = PinMappingReport::for_pins(&pins, &reread.pad_descriptors(), "QFN-16");
+        assert!(report.issues.is_empty(), "{}", report);
+        // and may still get one, being copper; a mechanical pad gets none
+        assert!(to_kicad_symbol(&reread).contains("(number \"17\""));
+        let mechanical = KiCadFootprint::parse(&text.replace("pad_prop_heatsink", "pad_prop_mechanical"), ParseOptions::default()).unwrap();
+        let symbol = to_kicad_symbol(&mechanical);
+        assert!(symbol.contains("(number \"16\"") && !symbol.contains("(number \"17\""), "{}", symbol);
+    }
+
+    #[test]
+    fn pad_mask_margins_survive_a_round_trip() {
+        let footprint = KiCadFootprint::parse(QFN, ParseOptions::default()).unwrap();
+        let pad = footprint.pad_descriptors().into_iter().find(|pad| pad.number == "17").unwrap();
+        assert_eq!(pad.solder_mask_margin, None);
+        let mut written = String::new();
+        write_detailed_pad(&mut written, &pad.with_solder_mask_margin(-0.05));
+        assert!(written.contains("(solder_mask_margin -0.05)"), "{}", written);
+
+        let original = QFN.lines().find(|line| line.contains("(pad \"17\"")).unwrap();
+        let reread = KiCadFootprint::parse(&QFN.replace(original, written.trim_end()), ParseOptions::default()).unwrap();
+        let pad = reread.pad_descriptors().into_iter().find(|pad| pad.number == "17").unwrap();
+        assert_eq!(pad.solder_mask_margin, Some(-0.05));
+    }
+
+    #[test]
+    fn routing_hints_survive_a_round_trip() {
+        let qfn = Qfn::new(16, 0.5, 3.0, 3.0).with_exposed_pad(Some((1.7, 1.7)));
+        let by_number = |pads: Vec<PadDescriptor>| -> BTreeMap<String, Option<RoutingHint>> {
+            pads.into_iter().map(|pad| (pad.number.to_string(), pad.routing_hint)).collect()
+        };
+        let hints = by_number(qfn.pad_descriptors());
+        for version in [KiCadVersion::V7, KiCadVersion::V9] {
+            let written = to_kicad_footprint_for(&qfn, version);
+            assert_eq!(written.matches("(property \"Routing hints\"").count(), 1, "{}", written);
+            let reread = KiCadFootprint::parse(&written, ParseOptions { strict: true }).unwrap();
+            assert_eq!(by_number(reread.pad_descriptors()), hints);
+        }
+
+        let broken = to_kicad_footprint(&qfn).replace("1,2,3,4=", "1,2,3,40=");
+        assert!(matches!(KiCadFootprint::parse(&broken, ParseOptions::default()), Err(FootprintParseError::Invalid { .. })));
+    }
+
+    #[test]
+    fn pad_groups_survive_a_round_trip() {
+        let bridged = SolderJumper::two_way(true);
+        let written = to_kicad_footprint(&bridged);
+        assert!(written.contains("\t(net_tie_pad_groups \"1, 2\")\n\t(duplicate_pad_numbers_are_jumpers no)"), "{}", written);
+        let reread = KiCadFootprint::parse(&written, ParseOptions::strict()).unwrap();
+        assert_eq!(reread.pad_grouping(), bridged.pad_grouping());
+        assert!(FootprintDrc::default().check(&reread).is_empty(), "{:?}", FootprintDrc::default().check(&reread));
+        assert!(diff_pads(&bridged.pad_descriptors(), &reread.pad_descriptors()).is_empty());
+
+        let mut jumpers = Footprint::capture(&Dpak::standard("TO-252", 3).unwrap());
+        jumpers.pad_grouping = PadGrouping::new().with_jumpers();
+        let written = to_kicad_footprint(&jumpers);
+        assert!(written.contains("(duplicate_pad_numbers_are_jumpers yes)"), "{}", written);
+        assert!(!to_kicad_footprint_for(&jumpers, KiCadVersion::V8).contains("duplicate_pad_numbers_are_jumpers"));
+        assert_eq!(KiCadFootprint::parse(&written, ParseOptions::strict()).unwrap().pad_grouping(), jumpers.pad_grouping);
+        // A file's repeated numbers are terminals unless it says otherwise
+        let terminals = KiCadFootprint::parse(&written.replace("jumpers yes", "jumpers no"), ParseOptions::strict()).unwrap();
+        assert_eq!(terminals.pad_grouping(), PadGrouping::new().with_terminal("2"));
+    }
+}
@@ -0,0 +1,279 @@
+//! Layout of KiCad s-expression files
+//!
+//! The exporters build files as they go, in the `Compact` style: short lists such as a
+//! text's `(effects (font ...))` share a line. pcbnew lays files out differently when it
+//! saves them, so a footprint opened and saved again in KiCad diffs on every line. The
+//! `KiCadCanonical` style re-lays the written expression the way pcbnew 8 and 9 do:
+//!
+//! - every list opens on a new line, indented one tab per enclosing list
+//! - a list closes on its own line when its last item is a list, and on the same line
+//!   otherwise
+//! - runs of `(xy ...)` points share a line until it reaches column 99
+//! - long runs of atoms, such as group members, wrap after column 72
+//!
+//! Either style can be written with spaces instead of tabs and CRLF line endings.
+
+use crate::exporter::ExportOptions;
+use crate::sexpr::{self, quote, SExpr, SExprError};
+
+/// pcbnew starts a new line for the next `(xy ...)` point from this column
+const XY_COLUMN_LIMIT: usize = 99;
+/// pcbnew wraps atoms of one list onto a new line from this column
+const TOKEN_WRAP_COLUMN: usize = 72;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputStyle {
+    /// The layout the exporters write, short lists kept on one line
+    #[default]
+    Compact,
+    /// pcbnew's layout, see the module documentation
+    KiCadCanonical,
+}
+
+/// One level of indentation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Indent {
+    /// A tab, as KiCad writes
+    #[default]
+    Tabs,
+    Spaces(usize),
+}
+
+impl Indent {
+    fn unit(self) -> String {
+        match self {
+            Indent::Tabs => "\t".to_string(),
+            Indent::Spaces(count) => " ".repeat(count),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+/// `expr` laid out as pcbnew saves it, ending with a line break
+pub fn to_canonical(expr: &SExpr, indent: Indent, line_ending: LineEnding) -> String {
+    let mut printer = Printer { output: String::new(), column: 0, in_xy: false, indent: indent.unit(), newline: line_ending.as_str() };
+    printer.expr(expr, 0);
+    printer.output.push_str(printer.newline);
+    printer.output
+}
+
+/// Lay out `text`, a file written in the `Compact` style, as `options` ask. The
+/// `KiCadCanonical` style re-reads the text, and fails rather than fall back to the
+/// compact layout when it isn't one s-expression.
+pub fn apply_output_style(text: &str, options: &ExportOptions) -> Result<String, SExprError> {
+    if options.output_style == OutputStyle::KiCadCanonical {
+        return Ok(to_canonical(&sexpr::parse(text)?, options.indent, options.line_ending));
+    }
+    if options.indent == Indent::Tabs && options.line_ending == LineEnding::Lf {
+        return Ok(text.to_string());
+    }
+    let unit = options.indent.unit();
+    let newline = options.line_ending.as_str();
+    Ok(text
+        .lines()
+        .map(|line| {
+            let body = line.trim_start_matches('\t');
+            format!("{}{}{}", unit.repeat(line.len() - body.len()), body, newline)
+        })
+        .collect())
+}
+
+struct Printer {
+    output: String,
+    column: usize,
+    /// Whether the last list opened was an `(xy ...)` point
+    in_xy: bool,
+    indent: String,
+    newline: &'static str,
+}
+
+impl Printer {
+    fn push(&mut self, text: &str) {
+        self.output.push_str(text);
+        self.column += text.chars().count();
+    }
+
+    fn line_break(&mut self, depth: usize) {
+        self.output.push_str(self.newline);
+        self.column = 0;
+        let indent = self.indent.repeat(depth);
+        self.push(&indent);
+    }
+
+    /// Write `expr` as an item of a list nested `depth` deep
+    fn expr(&mut self, expr: &SExpr, depth: usize) {
+        match expr {
+            SExpr::Atom(atom) => self.push(atom),
+            SExpr::Str(text) => self.push(&quote(text)),
+            SExpr::List(items) => self.list(items, depth),
+        }
+    }
+
+    fn list(&mut self, items: &[SExpr], depth: usize) {
+        let is_xy = matches!(items.first(), Some(SExpr::Atom(head)) if head == "xy");
+        if self.output.is_empty() {
+            self.push("(");
+        } else if self.in_xy && is_xy && self.column < XY_COLUMN_LIMIT {
+            self.push(" (");
+        } else {
+            self.line_break(depth);
+            self.push("(");
+        }
+        self.in_xy = is_xy;
+
+        let (mut wrapped, mut last_was_list) = (false, false);
+        for (index, item) in items.iter().enumerate() {
+            if let SExpr::List(children) = item {
+                self.list(children, depth + 1);
+                last_was_list = true;
+                continue;
+            }
+            if index > 0 {
+                if self.in_xy || self.column < TOKEN_WRAP_COLUMN {
+                    self.push(" ");
+                } else {
+                    self.line_break(depth + 1);
+                    wrapped = true;
+                }
+            }
+            self.expr(item, depth + 1);
+            last_was_list = false;
+        }
+        if last_was_list || wrapped {
+            self.line_break(depth);
+        }
+        self.push(")");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use copper_substrate::board::Board;
+    use copper_substrate::presets;
+
+    use super::*;
+    use crate::exporter::ExportError;
+    use crate::kicad_board_export::to_kicad_pcb_with_options;
+    use crate::kicad_pcb_export::{to_kicad_footprint, to_kicad_footprint_with_options, KiCadVersion};
+
+    /// The expression with every uuid blanked, since each export draws new ones
+    fn without_uuids(expr: &SExpr) -> SExpr {
+        match expr {
+            SExpr::List(items) if matches!(expr.head(), Some("uuid" | "tstamp")) => SExpr::List(items[..1].to_vec()),
+            SExpr::List(items) => SExpr::List(items.iter().map(without_uuids).collect()),
+            other => other.clone(),
+        }
+    }
+
+    fn kicad_8() -> ExportOptions {
+        ExportOptions { kicad_version: KiCadVersion::V8, ..ExportOptions::default() }
+    }
+
+    fn canonical() -> ExportOptions {
+        ExportOptions { output_style: OutputStyle::KiCadCanonical, ..kicad_8() }
+    }
+
+    #[test]
+    fn saved_layout_is_reproduced_line_for_line() {
+        // Laid out by hand to pcbnew 8's rules, not saved by pcbnew itself
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples/fixtures/kicad8_saved.kicad_mod");
+        let saved = std::fs::read_to_string(path).unwrap();
+        let relaid = to_canonical(&sexpr::parse(&saved).unwrap(), Indent::Tabs, LineEnding::Lf);
+        for (line, (ours, theirs)) in relaid.lines().zip(saved.lines()).enumerate() {
+            assert_eq!(ours, theirs, "line {}", line + 1);
+        }
+        assert_eq!(relaid, saved);
+    }
+
+    #[test]
+    fn canonical_export_holds_the_compact_expression() {
+        let resistor = presets::resistor_0805("10k");
+        let compact_text = to_kicad_footprint_with_options(&resistor, &kicad_8());
+        let canonical_text = to_kicad_footprint_with_options(&resistor, &canonical());
+        let expr = sexpr::parse(&canonical_text).unwrap();
+        assert_eq!(without_uuids(&expr), without_uuids(&sexpr::parse(&compact_text).unwrap()));
+        assert_eq!(to_canonical(&expr, Indent::Tabs, LineEnding::Lf), canonical_text);
+        assert!(canonical_text.contains("\t\t(effects\n\t\t\t(font\n\t\t\t\t(size 1 1)\n"), "{}", canonical_text);
+        // Every list but the root and runs of points opens its own line
+        for line in canonical_text.lines().skip(1) {
+            let body = line.trim_start_matches('\t');
+            assert!(body.starts_with('(') || body == ")", "{:?}", line);
+            assert!(!body.contains(") (") || body.starts_with("(xy "), "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn the_default_stays_compact() {
+        let resistor = presets::resistor_0805("10k");
+        assert_eq!(
+            without_uuids(&sexpr::parse(&to_kicad_footprint(&resistor)).unwrap()),
+            without_uuids(&sexpr::parse(&to_kicad_footprint_with_options(&resistor, &ExportOptions::default())).unwrap())
+        );
+    }
+
+    #[test]
+    fn spaces_and_crlf_work_in_both_styles() {
+        let resistor = presets::resistor_0805("10k");
+        let spaced = ExportOptions { indent: Indent::Spaces(2), line_ending: LineEnding::CrLf, ..canonical() };
+        let spaced_compact = ExportOptions { output_style: OutputStyle::Compact, ..spaced.clone() };
+        for options in [&spaced, &spaced_compact] {
+            let text = to_kicad_footprint_with_options(&resistor, options);
+            assert!(!text.contains('\t'));
+            assert!(text.split_inclusive('\n').all(|line| line.ends_with("\r\n")));
+            assert!(text.contains("\r\n  (layer \"F.Cu\")\r\n"));
+            sexpr::parse(&text).unwrap();
+        }
+    }
+
+    #[test]
+    fn long_atom_lists_wrap_after_column_72() {
+        let members: Vec<SExpr> = (0..6).map(|i| SExpr::Str(format!("00000000-0000-0000-0000-00000000000{}", i))).collect();
+        let group = SExpr::List(vec![
+            SExpr::Atom("group".to_string()),
+            SExpr::Str("G".to_string()),
+            SExpr::List([vec![SExpr::Atom("members".to_string())], members].concat()),
+        ]);
+        let text = to_canonical(&group, Indent::Tabs, LineEnding::Lf);
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines.len() > 4 && lines.iter().all(|line| line.len() < TOKEN_WRAP_COLUMN + 40), "{}", text);
+        assert_eq!(lines[lines.len() - 2], "\t)");
+    }
+
+    #[test]
+    fn boards_export_in_either_style() {
+        let mut board = Board::new("styled").with_rectangular_outline(20.0, 10.0);
+        board.place("R1", presets::resistor_0805("1k"), (10.0, 5.0), 0.0).unwrap();
+        for options in [&kicad_8(), &canonical()] {
+            let text = to_kicad_pcb_with_options(&board, options);
+            assert!(sexpr::parse(&text).unwrap().find("footprint").is_some());
+        }
+    }
+
+    #[test]
+    fn text_that_does_not_parse_is_an_error_not_a_compact_file() {
+        let unclosed = "(footprint \"R\"\n\t(layer \"F.Cu\")\n";
+        assert!(apply_output_style(unclosed, &canonical()).is_err());
+        // The compact style only re-indents, so it has nothing to parse
+        let spaced = ExportOptions { indent: Indent::Spaces(2), ..kicad_8() };
+        assert_eq!(apply_output_style(unclosed, &spaced).unwrap(), "(footprint \"R\"\n  (layer \"F.Cu\")\n");
+        let error = ExportError::from(apply_output_style(unclosed, &canonical()).unwrap_err());
+        assert!(matches!(error, ExportError::OutputStyle(_)), "{}", error);
+    }
+}
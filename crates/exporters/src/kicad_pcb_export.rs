@@ -1,39 +1,129 @@
+use std::collections::HashMap;
 use std::fmt::Write;
+use copper_substrate::dimension::{DIMENSION_ARROW_LENGTH, DIMENSION_EXTENSION_OFFSET, DIMENSION_EXTENSION_OVERSHOOT, DIMENSION_LEADER_LENGTH};
+use copper_substrate::geometry::widen;
+use copper_substrate::layer_type::is_kicad_layer_name;
 use copper_substrate::prelude::*;
+use copper_substrate::routing_hint::hints_property;
 
+use crate::element_order::{sort_graphics, sort_pads, sort_texts};
+use crate::exporter::{ExportError, ExportOptions, ExportWarning, FootprintExporter};
+use crate::format::{quantize_numbers, Angle, Decimal};
+use crate::kicad_format::apply_output_style;
+use crate::sexpr::{quote, SExprError};
+use crate::uuids::{with_namespaced_uuids, UuidMode};
+use uuid::Uuid;
+
+/// KiCad release a footprint file is written for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum KiCadVersion {
+    V7,
+    V8,
+    #[default]
+    V9,
+}
+
+impl KiCadVersion {
+    /// `(version ...)` of footprint files
+    fn footprint_format(self) -> u32 {
+        match self {
+            KiCadVersion::V7 => 20221018,
+            KiCadVersion::V8 => 20240108,
+            KiCadVersion::V9 => 20250401,
+        }
+    }
+
+    /// Per-layer pad geometry is new in KiCad 9
+    pub fn has_padstacks(self) -> bool {
+        self >= KiCadVersion::V9
+    }
+}
+
+impl std::fmt::Display for KiCadVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KiCadVersion::V7 => f.write_str("KiCad 7"),
+            KiCadVersion::V8 => f.write_str("KiCad 8"),
+            KiCadVersion::V9 => f.write_str("KiCad 9"),
+        }
+    }
+}
+
+/// Keywords of the `(justify ...)` token, `None` when centered and not mirrored
+fn justify_keywords(justify: &Justify) -> Option<String> {
+    let mut keywords = Vec::new();
+    match justify.horizontal {
+        HorizontalJustify::Left => keywords.push("left"),
+        HorizontalJustify::Right => keywords.push("right"),
+        HorizontalJustify::Center => {}
+    }
+    match justify.vertical {
+        VerticalJustify::Top => keywords.push("top"),
+        VerticalJustify::Bottom => keywords.push("bottom"),
+        VerticalJustify::Center => {}
+    }
+    if justify.mirror {
+        keywords.push("mirror");
+    }
+    if keywords.is_empty() { None } else { Some(keywords.join(" ")) }
+}
+
+/// Fold a text angle into KiCad's readable range (-90°, 90°], stored as [0, 360). Text
+/// pointing the other way is turned by 180° and its justification swapped so it keeps its
+/// anchor, the same normalization KiCad applies when rotating a footprint.
+pub fn readable_text_angle(angle: f32, justify: Justify) -> (f32, Justify) {
+    let angle = angle.rem_euclid(360.0);
+    if angle > 90.0 && angle <= 270.0 {
+        ((angle - 180.0).rem_euclid(360.0), justify.rotated_half_turn())
+    } else {
+        (angle, justify)
+    }
+}
 
 /// Helper functions for KiCad output formatting
 pub fn write_fp_text(output: &mut String, fp_text: &FpText) {
+    write_fp_text_for(output, fp_text, KiCadVersion::default());
+}
+
+/// `write_fp_text` for an older KiCad, which only knows the bare `hide` keyword
+fn write_fp_text_for(output: &mut String, fp_text: &FpText, version: KiCadVersion) {
     let text_type_str = match fp_text.text_type {
         FpTextType::Reference => "reference",
         FpTextType::Value => "value",
         FpTextType::User => "user",
     };
     
-    write!(output, "\t(fp_text {} \"{}\"", text_type_str, fp_text.text).unwrap();
+    write!(output, "\t(fp_text {} {}", text_type_str, quote(&fp_text.text)).unwrap();
     
+    let (x, y) = (Decimal(fp_text.position.0), Decimal(fp_text.position.1));
     if let Some(rotation) = fp_text.rotation {
-        write!(output, " (at {} {} {})", fp_text.position.0, fp_text.position.1, rotation).unwrap();
+        write!(output, " (at {} {} {})", x, y, Angle::ccw(rotation)).unwrap();
     } else {
-        write!(output, " (at {} {})", fp_text.position.0, fp_text.position.1).unwrap();
+        write!(output, " (at {} {})", x, y).unwrap();
     }
     
-    writeln!(output, " (layer \"{}\")", fp_text.layer).unwrap();
-    writeln!(output, "\t\t(effects (font (size {} {}) (thickness {})))", 
-             fp_text.font.size.0, fp_text.font.size.1, fp_text.font.thickness).unwrap();
+    write!(output, " (layer \"{}\")", fp_text.layer).unwrap();
+    // Hidden text is still written so KiCad can show it on demand
+    if fp_text.hidden {
+        write!(output, "{}", if version >= KiCadVersion::V8 { " (hide yes)" } else { " hide" }).unwrap();
+    }
+    writeln!(output).unwrap();
+    write!(output, "\t\t(effects (font (size {} {}) (thickness {}))",
+           fp_text.font.size.0, fp_text.font.size.1, fp_text.font.thickness).unwrap();
+    if let Some(keywords) = fp_text.justify.as_ref().and_then(justify_keywords) {
+        write!(output, " (justify {})", keywords).unwrap();
+    }
+    writeln!(output, ")").unwrap();
     writeln!(output, "\t\t(tstamp \"{}\")", fp_text.uuid).unwrap();
     writeln!(output, "\t)").unwrap();
 }
 
 pub fn write_property(output: &mut String, prop: &FootprintProperty) {
-    writeln!(output, "\t(property \"{}\" \"{}\"", prop.name, prop.value).unwrap();
+    writeln!(output, "\t(property {} {}", quote(&prop.name), quote(&prop.value)).unwrap();
     
     // Write position with optional rotation
-    if let Some(rotation) = prop.rotation {
-        writeln!(output, "\t\t(at {} {} {})", prop.position.0, prop.position.1, rotation).unwrap();
-    } else {
-        writeln!(output, "\t\t(at {} {} 0)", prop.position.0, prop.position.1).unwrap();
-    }
+    let angle = Angle::from_option(prop.rotation);
+    writeln!(output, "\t\t(at {} {} {})", Decimal(prop.position.0), Decimal(prop.position.1), angle).unwrap();
     
     // Write unlocked if true
     if prop.unlocked {
@@ -54,48 +144,212 @@ pub fn write_property(output: &mut String, prop: &FootprintProperty) {
     writeln!(output, "\t\t\t\t(size {} {})", prop.font.size.0, prop.font.size.1).unwrap();
     writeln!(output, "\t\t\t\t(thickness {})", prop.font.thickness).unwrap();
     writeln!(output, "\t\t\t)").unwrap();
+    if let Some(keywords) = prop.justify.as_ref().and_then(justify_keywords) {
+        writeln!(output, "\t\t\t(justify {})", keywords).unwrap();
+    }
     writeln!(output, "\t\t)").unwrap();
     writeln!(output, "\t)").unwrap();
 }
 
 pub fn write_graphic_element(output: &mut String, element: &GraphicElement) {
+    write_graphic_element_on_layer(output, element, &element.kicad_layer());
+}
+
+/// Write a graphic element on an explicit layer, used when a placed footprint is flipped
+/// to the back side
+pub fn write_graphic_element_on_layer(output: &mut String, element: &GraphicElement, layer: &str) {
+    // KiCad graphics fill solid or not at all, hatches are written as their lines
+    let expanded = expand_hatch(element);
+    if expanded.len() > 1 {
+        for part in &expanded {
+            write_graphic_element_on_layer(output, part, layer);
+        }
+        return;
+    }
     match &element.element_type {
         GraphicType::Line { start, end } => {
             writeln!(output, "\t(fp_line").unwrap();
             writeln!(output, "\t\t(start {} {})", start.0, start.1).unwrap();
             writeln!(output, "\t\t(end {} {})", end.0, end.1).unwrap();
-            writeln!(output, "\t\t(stroke").unwrap();
-            writeln!(output, "\t\t\t(width {})", element.stroke.width).unwrap();
-            writeln!(output, "\t\t\t(type solid)").unwrap();
+        }
+        GraphicType::Rectangle { bounds } => {
+            writeln!(output, "\t(fp_rect").unwrap();
+            writeln!(output, "\t\t(start {} {})", bounds.min_x, bounds.min_y).unwrap();
+            writeln!(output, "\t\t(end {} {})", bounds.max_x, bounds.max_y).unwrap();
+        }
+        GraphicType::Circle { center, radius } => {
+            writeln!(output, "\t(fp_circle").unwrap();
+            writeln!(output, "\t\t(center {} {})", center.0, center.1).unwrap();
+            writeln!(output, "\t\t(end {} {})", center.0 + radius, center.1).unwrap();
+        }
+        GraphicType::Polygon { points, .. } => {
+            writeln!(output, "\t(fp_poly").unwrap();
+            writeln!(output, "\t\t(pts").unwrap();
+            for (x, y) in points {
+                writeln!(output, "\t\t\t(xy {} {})", x, y).unwrap();
+            }
             writeln!(output, "\t\t)").unwrap();
-            writeln!(output, "\t\t(layer \"{}\")", element.layer.to_kicad_string()).unwrap();
-            writeln!(output, "\t\t(tstamp \"{}\")", element.uuid).unwrap();
-            writeln!(output, "\t)").unwrap();
-        },
+        }
+        GraphicType::Dimension(dimension) => {
+            write_dimension(output, dimension, layer, element.stroke.width, &element.uuid);
+            return;
+        }
+    }
+    writeln!(output, "\t\t(stroke").unwrap();
+    writeln!(output, "\t\t\t(width {})", element.stroke.width).unwrap();
+    writeln!(output, "\t\t\t(type {})", match element.stroke.stroke_type {
+        StrokeType::Solid => "solid",
+        StrokeType::Dashed => "dash",
+        StrokeType::Dotted => "dot",
+    }).unwrap();
+    writeln!(output, "\t\t)").unwrap();
+    match &element.element_type {
+        GraphicType::Line { .. } => {}
+        GraphicType::Polygon { fill: Some(FillStyle::Solid), .. } => writeln!(output, "\t\t(fill yes)").unwrap(),
+        _ => writeln!(output, "\t\t(fill no)").unwrap(),
+    }
+    writeln!(output, "\t\t(layer \"{}\")", layer).unwrap();
+    writeln!(output, "\t\t(tstamp \"{}\")", element.uuid).unwrap();
+    writeln!(output, "\t)").unwrap();
+}
+
+/// Write a KiCad `(dimension ...)` item. The token is the same inside a footprint and at
+/// board level; KiCad recomputes the arrows from the points, the text is written as shown.
+pub fn write_dimension(output: &mut String, dimension: &Dimension, layer: &str, thickness: f32, uuid: &Uuid) {
+    let kind = match dimension.kind {
+        DimensionKind::Aligned => "aligned",
+        DimensionKind::Orthogonal { .. } => "orthogonal",
+        DimensionKind::Radial => "radial",
+        DimensionKind::Leader => "leader",
+    };
+    writeln!(output, "\t(dimension").unwrap();
+    writeln!(output, "\t\t(type {})", kind).unwrap();
+    writeln!(output, "\t\t(layer \"{}\")", layer).unwrap();
+    writeln!(output, "\t\t(uuid \"{}\")", uuid).unwrap();
+    writeln!(output, "\t\t(pts (xy {} {}) (xy {} {}))", dimension.start.0, dimension.start.1, dimension.end.0, dimension.end.1).unwrap();
+    match dimension.kind {
+        DimensionKind::Aligned => writeln!(output, "\t\t(height {})", dimension.height).unwrap(),
+        DimensionKind::Orthogonal { vertical } => {
+            writeln!(output, "\t\t(height {})", dimension.height).unwrap();
+            writeln!(output, "\t\t(orientation {})", if vertical { 1 } else { 0 }).unwrap();
+        }
+        DimensionKind::Radial => writeln!(output, "\t\t(leader_length {})", DIMENSION_LEADER_LENGTH).unwrap(),
+        DimensionKind::Leader => {}
+    }
+
+    // KiCad unit codes: 0 inches, 1 mils, 2 millimeters; units_format 0 hides the suffix
+    let units = match dimension.format.units {
+        DimensionUnits::Inches => 0,
+        DimensionUnits::Mils => 1,
+        DimensionUnits::Millimeters => 2,
+    };
+    writeln!(output, "\t\t(format").unwrap();
+    writeln!(output, "\t\t\t(prefix \"{}\")", if dimension.kind == DimensionKind::Radial { "R " } else { "" }).unwrap();
+    writeln!(output, "\t\t\t(suffix \"\")").unwrap();
+    writeln!(output, "\t\t\t(units {})", units).unwrap();
+    writeln!(output, "\t\t\t(units_format {})", if dimension.format.show_units { 1 } else { 0 }).unwrap();
+    writeln!(output, "\t\t\t(precision {})", dimension.format.precision).unwrap();
+    if let Some(text) = &dimension.override_text {
+        writeln!(output, "\t\t\t(override_value {})", quote(text)).unwrap();
+    }
+    writeln!(output, "\t\t)").unwrap();
+
+    writeln!(output, "\t\t(style").unwrap();
+    writeln!(output, "\t\t\t(thickness {})", thickness).unwrap();
+    writeln!(output, "\t\t\t(arrow_length {})", DIMENSION_ARROW_LENGTH).unwrap();
+    writeln!(output, "\t\t\t(text_position_mode 0)").unwrap();
+    match dimension.kind {
+        DimensionKind::Leader => writeln!(output, "\t\t\t(text_frame 0)").unwrap(),
         _ => {
-            // Implement other graphic types as needed
+            writeln!(output, "\t\t\t(arrow_direction outward)").unwrap();
+            writeln!(output, "\t\t\t(extension_height {})", DIMENSION_EXTENSION_OVERSHOOT).unwrap();
         }
     }
+    writeln!(output, "\t\t\t(extension_offset {})", DIMENSION_EXTENSION_OFFSET).unwrap();
+    writeln!(output, "\t\t\t(keep_text_aligned yes)").unwrap();
+    writeln!(output, "\t\t)").unwrap();
+
+    let geometry = dimension.geometry();
+    writeln!(output, "\t\t(gr_text {}", quote(&dimension.text())).unwrap();
+    let (x, y) = (Decimal(geometry.text_position.x), Decimal(geometry.text_position.y));
+    writeln!(output, "\t\t\t(at {} {} {})", x, y, Angle::ccw(geometry.text_angle)).unwrap();
+    writeln!(output, "\t\t\t(layer \"{}\")", layer).unwrap();
+    writeln!(output, "\t\t\t(effects (font (size {} {}) (thickness {})))",
+             dimension.text_height, dimension.text_height, dimension.text_height * 0.15).unwrap();
+    writeln!(output, "\t\t)").unwrap();
+    writeln!(output, "\t)").unwrap();
+}
+
+fn pad_shape_keyword(shape: &PadShape) -> &'static str {
+    match shape {
+        PadShape::RoundRect => "roundrect",
+        PadShape::Rect => "rect",
+        PadShape::Circle => "circle",
+        PadShape::Oval => "oval",
+        PadShape::Custom(_) => "custom",
+    }
+}
+
+/// Custom pad outline: the anchor rectangle merged with a filled polygon
+fn write_custom_primitives(output: &mut String, points: &[(f32, f32)], indent: &str) {
+    writeln!(output, "{}(options (clearance outline) (anchor rect))", indent).unwrap();
+    writeln!(output, "{}(primitives", indent).unwrap();
+    write!(output, "{}\t(gr_poly (pts", indent).unwrap();
+    for (x, y) in points {
+        write!(output, " (xy {} {})", x, y).unwrap();
+    }
+    writeln!(output, ") (width 0) (fill yes))").unwrap();
+    writeln!(output, "{})", indent).unwrap();
 }
 
+fn pad_property_keyword(property: PadFabProperty) -> &'static str {
+    match property {
+        PadFabProperty::Heatsink => "pad_prop_heatsink",
+        PadFabProperty::Mechanical => "pad_prop_mechanical",
+        PadFabProperty::TestPoint => "pad_prop_testpoint",
+        PadFabProperty::LocalFiducial => "pad_prop_fiducial_loc",
+        PadFabProperty::GlobalFiducial => "pad_prop_fiducial_glob",
+    }
+}
+
+/// `(drill 1.2)`, or `(drill oval 1.2 2.4)` for a slot
+pub(crate) fn drill_expression(pad: &PadDescriptor) -> Option<String> {
+    match pad.drill_slot {
+        Some((width, height)) => Some(format!("(drill oval {} {})", Decimal(width), Decimal(height))),
+        None => pad.drill_size.map(|drill| format!("(drill {})", drill)),
+    }
+}
+
+/// Write a pad, with its padstack if it has one; see `write_footprint_for` for older targets
 pub fn write_detailed_pad(output: &mut String, pad: &PadDescriptor) {
-    write!(output, "\t(pad \"{}\" {} {}", 
-           pad.number, 
+    write_pad_on_net(output, pad, None);
+}
+
+/// `write_detailed_pad` with the pad's board net, as number and name
+fn write_pad_on_net(output: &mut String, pad: &PadDescriptor, net: Option<(u32, &str)>) {
+    write!(output, "\t(pad {} {} {}", 
+           quote(&pad.number), 
            match pad.pad_type {
                PadType::SMD => "smd",
                PadType::ThroughHole => "thru_hole",
                PadType::NPTH => "np_thru_hole",
            },
-           match pad.shape {
-               PadShape::RoundRect => "roundrect",
-               PadShape::Rect => "rect",
-               PadShape::Circle => "circle",
-               PadShape::Oval => "oval",
-           }).unwrap();
+           pad_shape_keyword(&pad.shape)).unwrap();
            
     writeln!(output).unwrap();
-    writeln!(output, "\t\t(at {} {})", pad.position.0, pad.position.1).unwrap();
+    let (x, y) = (Decimal(pad.position.0), Decimal(pad.position.1));
+    if let Some(rotation) = pad.rotation {
+        writeln!(output, "\t\t(at {} {} {})", x, y, Angle::ccw(rotation)).unwrap();
+    } else {
+        writeln!(output, "\t\t(at {} {})", x, y).unwrap();
+    }
     writeln!(output, "\t\t(size {} {})", pad.size.0, pad.size.1).unwrap();
+    if let Some(drill) = drill_expression(pad) {
+        writeln!(output, "\t\t{}", drill).unwrap();
+    }
+    if let Some(property) = pad.fab_property {
+        writeln!(output, "\t\t(property {})", pad_property_keyword(property)).unwrap();
+    }
     
     // Layers
     write!(output, "\t\t(layers").unwrap();
@@ -108,60 +362,389 @@ pub fn write_detailed_pad(output: &mut String, pad: &PadDescriptor) {
     if let Some(ratio) = pad.roundrect_ratio {
         writeln!(output, "\t\t(roundrect_rratio {})", ratio).unwrap();
     }
+    if let Some((number, name)) = net {
+        writeln!(output, "\t\t(net {} {})", number, quote(name)).unwrap();
+    }
+    if let Some(margin) = pad.solder_mask_margin {
+        writeln!(output, "\t\t(solder_mask_margin {})", Decimal(margin)).unwrap();
+    }
+
+    if let PadShape::Custom(points) = &pad.shape {
+        write_custom_primitives(output, points, "\t\t");
+    }
+
+    // KiCad 9 padstack: front geometry above, back and inner layers listed where they differ
+    if let Some(padstack) = pad.padstack.as_ref().filter(|padstack| !padstack.is_empty()) {
+        writeln!(output, "\t\t(padstack (mode front_inner_back)").unwrap();
+        for (layer, geometry) in [("Inner", &padstack.inner), ("B.Cu", &padstack.back)] {
+            let geometry = geometry.clone().unwrap_or_else(|| pad.geometry_on(PadLayerClass::Front));
+            writeln!(output, "\t\t\t(layer \"{}\"", layer).unwrap();
+            writeln!(output, "\t\t\t\t(shape {})", pad_shape_keyword(&geometry.shape)).unwrap();
+            writeln!(output, "\t\t\t\t(size {} {})", geometry.size.0, geometry.size.1).unwrap();
+            if let Some(ratio) = geometry.roundrect_ratio {
+                writeln!(output, "\t\t\t\t(roundrect_rratio {})", ratio).unwrap();
+            }
+            if let PadShape::Custom(points) = &geometry.shape {
+                write_custom_primitives(output, points, "\t\t\t\t");
+            }
+            writeln!(output, "\t\t\t)").unwrap();
+        }
+        writeln!(output, "\t\t)").unwrap();
+    }
     
     writeln!(output, "\t\t(tstamp \"{}\")", pad.uuid).unwrap();
     writeln!(output, "\t)").unwrap();
 }
 
-pub fn to_kicad_footprint<T: BoardComposableObject>(component: &T) -> String {
-    let mut output = String::new();
-    
+/// Where a footprint sits when it is written inside a board rather than as a library file
+#[derive(Debug, Clone)]
+pub struct FootprintPlacement<'a> {
+    pub reference: &'a str,
+    /// Board coordinates, f64 like `PlacedComponent::position`
+    pub position: (f64, f64),
+    pub rotation: f32,
+    pub side: Side,
+    /// Keep text upright, see `readable_text_angle`
+    pub normalize_text_rotation: bool,
+    /// Footprint uuid, a fresh one when `None`; boards pass theirs so groups can refer to it
+    pub uuid: Option<&'a str>,
+    /// Board net number and name of each connected pad, by pad number
+    pub pad_nets: HashMap<String, (u32, &'a str)>,
+}
+
+impl FootprintPlacement<'_> {
+    fn is_back(&self) -> bool {
+        self.side == Side::Back
+    }
+}
+
+/// Swap the side prefix of a KiCad layer name ("F.SilkS" <-> "B.SilkS"), leaving
+/// wildcard and inner layers alone
+pub fn flip_layer_name(layer: &str) -> String {
+    if let Some(rest) = layer.strip_prefix("F.") {
+        format!("B.{}", rest)
+    } else if let Some(rest) = layer.strip_prefix("B.") {
+        format!("F.{}", rest)
+    } else {
+        layer.to_string()
+    }
+}
+
+fn flip_point(point: (f32, f32)) -> (f32, f32) {
+    (point.0, -point.1)
+}
+
+/// Mirror a graphic element top to bottom, as KiCad does when flipping a footprint
+fn flip_graphic_element(element: &GraphicElement) -> GraphicElement {
+    let mut flipped = element.clone();
+    flipped.element_type = match &element.element_type {
+        GraphicType::Line { start, end } => GraphicType::Line { start: flip_point(*start), end: flip_point(*end) },
+        GraphicType::Rectangle { bounds } => GraphicType::Rectangle {
+            bounds: Rectangle { min_x: bounds.min_x, min_y: -bounds.max_y, max_x: bounds.max_x, max_y: -bounds.min_y },
+        },
+        GraphicType::Circle { center, radius } => GraphicType::Circle { center: flip_point(*center), radius: *radius },
+        GraphicType::Polygon { points, fill } => GraphicType::Polygon {
+            points: points.iter().map(|point| flip_point(*point)).collect(),
+            // Mirroring turns hatch lines the other way
+            fill: fill.map(|fill| match fill {
+                FillStyle::Hatch { pitch, width, angle } => FillStyle::Hatch { pitch, width, angle: Angle::ccw(angle).mirrored().ccw_degrees() },
+                FillStyle::CrossHatch { pitch, width, angle } => {
+                    FillStyle::CrossHatch { pitch, width, angle: Angle::ccw(angle).mirrored().ccw_degrees() }
+                }
+                FillStyle::Solid => FillStyle::Solid,
+            }),
+        },
+        GraphicType::Dimension(dimension) => {
+            let mut flipped = dimension.clone();
+            flipped.start = flip_point(dimension.start);
+            flipped.end = flip_point(dimension.end);
+            // Mirroring reverses the offset side, except for vertical dimensions measured along Y
+            if dimension.kind != (DimensionKind::Orthogonal { vertical: true }) {
+                flipped.height = -dimension.height;
+            }
+            GraphicType::Dimension(flipped)
+        }
+    };
+    flipped
+}
+
+/// Swap the front and back geometry of a pad on a flipped footprint
+fn flip_padstack(mut pad: PadDescriptor) -> PadDescriptor {
+    let Some(back) = pad.padstack.as_ref().and_then(|padstack| padstack.back.clone()) else {
+        return pad;
+    };
+    let front = pad.geometry_on(PadLayerClass::Front);
+    (pad.shape, pad.size, pad.roundrect_ratio) = (back.shape, back.size, back.roundrect_ratio);
+    if let Some(padstack) = pad.padstack.as_mut() {
+        padstack.back = Some(front);
+    }
+    pad
+}
+
+/// Combine a footprint-local angle with the footprint orientation, KiCad stores pad and
+/// text angles in board space
+fn placed_angle(local: Option<f32>, placement: &FootprintPlacement<'_>) -> Option<f32> {
+    let local = Angle::from_option(local);
+    let local = if placement.is_back() { local.mirrored() } else { local };
+    let angle = (local + Angle::ccw(placement.rotation)).normalized();
+    if angle.is_zero() { None } else { Some(angle.ccw_degrees()) }
+}
+
+/// Write a rule area as a keepout zone. KiCad stores footprint zones in board coordinates,
+/// so a placed area is moved, rotated and flipped with its footprint.
+fn write_rule_area(output: &mut String, area: &RuleArea, placement: Option<&FootprintPlacement<'_>>) {
+    let allowed = |keep_out: bool| if keep_out { "not_allowed" } else { "allowed" };
+    let mut layers = area.layers.clone();
+    let mut points: Vec<(f64, f64)> = area.polygon.iter().map(|&(x, y)| (widen(x), widen(y))).collect();
+    if let Some(placement) = placement {
+        let mut polygon = area.polygon.clone();
+        if placement.is_back() {
+            layers = layers.iter().map(|layer| flip_layer_name(layer)).collect();
+            polygon = polygon.into_iter().map(flip_point).collect();
+        }
+        // Offset in f64 so parts far from the origin keep their exact position
+        let (x, y) = placement.position;
+        points = polygon
+            .into_iter()
+            .map(|point| Point::from(point).rotated(placement.rotation))
+            .map(|point| (x + widen(point.x), y + widen(point.y)))
+            .collect();
+    }
+    let layers: Vec<String> = layers.iter().map(|layer| quote(layer)).collect();
+
+    writeln!(output, "	(zone").unwrap();
+    writeln!(output, "		(net 0)").unwrap();
+    writeln!(output, "		(net_name \"\")").unwrap();
+    writeln!(output, "		(layers {})", layers.join(" ")).unwrap();
+    writeln!(output, "		(uuid \"{}\")", uuid::Uuid::new_v4()).unwrap();
+    if !area.name.is_empty() {
+        writeln!(output, "		(name {})", quote(&area.name)).unwrap();
+    }
+    writeln!(output, "		(hatch edge 0.5)").unwrap();
+    writeln!(output, "		(connect_pads").unwrap();
+    writeln!(output, "			(clearance 0)").unwrap();
+    writeln!(output, "		)").unwrap();
+    writeln!(output, "		(min_thickness 0.25)").unwrap();
+    writeln!(output, "		(filled_areas_thickness no)").unwrap();
+    writeln!(output, "		(keepout").unwrap();
+    writeln!(output, "			(tracks {})", allowed(area.no_tracks)).unwrap();
+    writeln!(output, "			(vias {})", allowed(area.no_vias)).unwrap();
+    writeln!(output, "			(pads allowed)").unwrap();
+    writeln!(output, "			(copperpour {})", allowed(area.no_copper_pour)).unwrap();
+    writeln!(output, "			(footprints allowed)").unwrap();
+    writeln!(output, "		)").unwrap();
+    writeln!(output, "		(fill").unwrap();
+    writeln!(output, "			(thermal_gap 0.5)").unwrap();
+    writeln!(output, "			(thermal_bridge_width 0.5)").unwrap();
+    writeln!(output, "		)").unwrap();
+    writeln!(output, "		(polygon").unwrap();
+    writeln!(output, "			(pts").unwrap();
+    for (x, y) in points {
+        writeln!(output, "				(xy {} {})", x, y).unwrap();
+    }
+    writeln!(output, "			)").unwrap();
+    writeln!(output, "		)").unwrap();
+    writeln!(output, "	)").unwrap();
+}
+
+/// Write a complete footprint, either as a standalone library footprint or, with a
+/// placement, as a footprint instance inside a board
+pub fn write_footprint<T: BoardComposableObject + ?Sized>(output: &mut String, component: &T, placement: Option<&FootprintPlacement<'_>>) {
+    write_footprint_for(output, component, placement, KiCadVersion::default());
+}
+
+/// `write_footprint` for a given KiCad release. Older releases get no tokens they can't
+/// read: padstacks collapse to the front geometry on every layer, see `version_warnings`.
+pub fn write_footprint_for<T: BoardComposableObject + ?Sized>(
+    output: &mut String,
+    component: &T,
+    placement: Option<&FootprintPlacement<'_>>,
+    version: KiCadVersion,
+) {
+    write_footprint_with_options(output, component, placement, &ExportOptions { kicad_version: version, ..ExportOptions::default() });
+}
+
+/// `write_footprint` for `options.kicad_version`, in canonical element order unless
+/// `options.canonical_order` is off
+pub fn write_footprint_with_options<T: BoardComposableObject + ?Sized>(
+    output: &mut String,
+    component: &T,
+    placement: Option<&FootprintPlacement<'_>>,
+    options: &ExportOptions,
+) {
+    let version = options.kicad_version;
     // Header
-    writeln!(output, "(footprint \"{}\"", component.footprint_name()).unwrap();
-    writeln!(output, "\t(version 20250401)").unwrap();
-    writeln!(output, "\t(generator \"custom_pcb_tool\")").unwrap();
-    writeln!(output, "\t(generator_version \"1.0\")").unwrap();
-    writeln!(output, "\t(layer \"F.Cu\")").unwrap();
+    match placement {
+        Some(_) => {
+            let name = format!("{}:{}", component.library_name(), component.footprint_name());
+            writeln!(output, "(footprint {}", quote(&name)).unwrap();
+        }
+        None => {
+            writeln!(output, "(footprint {}", quote(&component.footprint_name())).unwrap();
+            writeln!(output, "\t(version {})", version.footprint_format()).unwrap();
+            writeln!(output, "\t(generator \"custom_pcb_tool\")").unwrap();
+            if version >= KiCadVersion::V8 {
+                writeln!(output, "\t(generator_version \"1.0\")").unwrap();
+            }
+        }
+    }
+    let is_back = placement.is_some_and(|p| p.is_back());
+    writeln!(output, "\t(layer \"{}\")", if is_back { "B.Cu" } else { "F.Cu" }).unwrap();
+    if let Some(placement) = placement {
+        match placement.uuid {
+            Some(uuid) => writeln!(output, "\t(uuid \"{}\")", uuid).unwrap(),
+            None => writeln!(output, "\t(uuid \"{}\")", uuid::Uuid::new_v4()).unwrap(),
+        }
+        if placement.rotation != 0.0 {
+            writeln!(output, "\t(at {} {} {})", placement.position.0, placement.position.1, Angle::ccw(placement.rotation)).unwrap();
+        } else {
+            writeln!(output, "\t(at {} {})", placement.position.0, placement.position.1).unwrap();
+        }
+    }
     
     // Description and tags
     if let Some(desc) = component.description() {
-        writeln!(output, "\t(descr \"{}\")", desc).unwrap();
+        writeln!(output, "\t(descr {})", quote(&desc)).unwrap();
     }
-    if let Some(tags) = component.tags() {
-        writeln!(output, "\t(tags \"{}\")", tags).unwrap();
+    // Tags from hand-written footprints may hold quotes or stray case; KiCad's own
+    // libraries have neither
+    if let Some(tags) = component.tags().map(|tags| normalize_tags(&tags)).filter(|tags| !tags.is_empty()) {
+        writeln!(output, "\t(tags {})", quote(&tags)).unwrap();
     }
     
     // Remove properties section as we're using fp_text instead
     
-    // Attributes
-    let is_smt = component.pad_descriptors().iter().any(|pad| matches!(pad.pad_type, PadType::SMD));
-    if is_smt {
-        writeln!(output, "\t(attr smd)").unwrap();
+    // Attributes: any plated hole makes a through hole part, whichever sides its other
+    // pads are on
+    let mut pads = component.pad_descriptors();
+    if options.canonical_order {
+        sort_pads(&mut pads);
+    }
+    let mount = if pads.iter().any(|pad| matches!(pad.pad_type, PadType::ThroughHole)) {
+        Some("through_hole")
+    } else if pads.iter().any(|pad| matches!(pad.pad_type, PadType::SMD)) {
+        Some("smd")
+    } else {
+        None
+    };
+    let mut attributes: Vec<&str> = mount.into_iter().collect();
+    if !component.is_assembled() {
+        attributes.extend(["exclude_from_pos_files", "exclude_from_bom"]);
+    }
+    if component.allows_soldermask_bridges() {
+        attributes.push("allow_soldermask_bridges");
+    }
+    if !attributes.is_empty() {
+        writeln!(output, "\t(attr {})", attributes.join(" ")).unwrap();
+    }
+    let grouping = component.pad_grouping();
+    if !grouping.net_ties.is_empty() {
+        let ties: Vec<String> = grouping.net_ties.iter().map(|tie| quote(&tie.join(", "))).collect();
+        writeln!(output, "\t(net_tie_pad_groups {})", ties.join(" ")).unwrap();
+    }
+    if version >= KiCadVersion::V9 {
+        let jumpers = if grouping.duplicates_are_jumpers { "yes" } else { "no" };
+        writeln!(output, "\t(duplicate_pad_numbers_are_jumpers {})", jumpers).unwrap();
     }
-    writeln!(output, "\t(duplicate_pad_numbers_are_jumpers no)").unwrap();
     
     // fp_text elements
-    for fp_text in component.fp_text_elements() {
-        write_fp_text(&mut output, &fp_text);
+    let mut fp_texts = component.fp_text_elements();
+    if options.canonical_order {
+        sort_texts(&mut fp_texts);
+    }
+    for mut fp_text in fp_texts {
+        if let Some(placement) = placement {
+            let mut justify = fp_text.justify.unwrap_or_default();
+            if matches!(fp_text.text_type, FpTextType::Reference) {
+                fp_text.text = placement.reference.to_string();
+            }
+            fp_text.rotation = placed_angle(fp_text.rotation, placement);
+            if placement.normalize_text_rotation {
+                let (angle, readable) = readable_text_angle(fp_text.rotation.unwrap_or(0.0), justify);
+                fp_text.rotation = if angle == 0.0 { None } else { Some(angle) };
+                justify = readable;
+            }
+            if placement.is_back() {
+                fp_text.position = flip_point(fp_text.position);
+                fp_text.layer = flip_layer_name(&fp_text.layer).into();
+                // Flipping mirrors the text, so front text reads correctly from the back
+                justify.mirror = !justify.mirror;
+            }
+            fp_text.justify = (justify != Justify::default()).then_some(justify);
+        }
+        write_fp_text_for(output, &fp_text, version);
+    }
+
+    // Routing hints, as a hidden property since KiCad pads have no properties of their own
+    if let Some(hints) = hints_property(&pads) {
+        if version >= KiCadVersion::V8 {
+            write_property(output, &FootprintProperty {
+                name: ROUTING_HINTS_PROPERTY.to_string(),
+                value: hints,
+                position: (0.0, 0.0),
+                rotation: None,
+                layer: if is_back { "B.Fab" } else { "F.Fab" }.into(),
+                hidden: true,
+                unlocked: false,
+                uuid: uuid::Uuid::new_v4(),
+                font: FontSettings { size: (1.0, 1.0), thickness: 0.15 },
+                justify: None,
+            });
+        } else {
+            writeln!(output, "\t(property {} {})", quote(ROUTING_HINTS_PROPERTY), quote(&hints)).unwrap();
+        }
     }
     
-    // Graphic elements (combine user-defined + auto-generated courtyard)
+    // Graphic elements (combine user-defined + auto-generated courtyards)
     let mut all_graphics = component.graphic_elements();
-    let courtyard = component.generate_courtyard();
-    all_graphics.extend(courtyard.to_graphic_elements());
-    
+    for courtyard in component.generate_courtyards() {
+        all_graphics.extend(courtyard.to_graphic_elements());
+    }
+    if options.canonical_order {
+        sort_graphics(&mut all_graphics);
+    }
+
     for element in all_graphics {
-        write_graphic_element(&mut output, &element);
+        if is_back {
+            let layer = flip_layer_name(&element.kicad_layer());
+            write_graphic_element_on_layer(output, &flip_graphic_element(&element), &layer);
+        } else {
+            write_graphic_element(output, &element);
+        }
+    }
+
+    // Actuation envelope, dashed on its user layer
+    if let Some(envelope) = component.actuation_envelope() {
+        let element = envelope.to_graphic_element();
+        let element = if is_back { flip_graphic_element(&element) } else { element };
+        write_graphic_element_on_layer(output, &element, &envelope.layer);
     }
     
     // Pads
-    for pad in component.pad_descriptors() {
-        write_detailed_pad(&mut output, &pad);
+    for mut pad in pads {
+        if let Some(placement) = placement {
+            pad.rotation = placed_angle(pad.rotation, placement);
+            if placement.is_back() {
+                pad.position = flip_point(pad.position);
+                pad.layers = pad.layers.iter().map(|layer| flip_layer_name(layer).into()).collect();
+                pad = flip_padstack(pad);
+            }
+        }
+        if !version.has_padstacks() {
+            pad.padstack = None;
+        }
+        let net = placement.and_then(|placement| placement.pad_nets.get(pad.number.as_str())).copied();
+        write_pad_on_net(output, &pad, net);
+    }
+
+    // Keepouts
+    for area in component.rule_areas() {
+        write_rule_area(output, &area, placement);
     }
     
     // 3D model reference
     if let Some(model) = component.model_3d() {
-        writeln!(output, "\t(model \"{}\"", model.path).unwrap();
+        writeln!(output, "\t(model {}", quote(&model.path)).unwrap();
         writeln!(output, "\t\t(offset").unwrap();
         writeln!(output, "\t\t\t(xyz {} {} {})", 
                  model.offset.0, model.offset.1, model.offset.2).unwrap();
@@ -177,7 +760,197 @@ pub fn to_kicad_footprint<T: BoardComposableObject>(component: &T) -> String {
         writeln!(output, "\t)").unwrap();
     }
     
-    writeln!(output, "\t(embedded_fonts no)").unwrap();
+    if version >= KiCadVersion::V9 {
+        writeln!(output, "\t(embedded_fonts no)").unwrap();
+    }
     writeln!(output, ")").unwrap();
-    output
-}
\ No newline at end of file
+}
+
+pub fn to_kicad_footprint<T: BoardComposableObject + ?Sized>(component: &T) -> String {
+    to_kicad_footprint_for(component, KiCadVersion::default())
+}
+
+/// `to_kicad_footprint` for a given KiCad release. Parsed footprints keep their source text
+/// only for the current release, older ones are written out again.
+pub fn to_kicad_footprint_for<T: BoardComposableObject + ?Sized>(component: &T, version: KiCadVersion) -> String {
+    to_kicad_footprint_with_options(component, &ExportOptions { kicad_version: version, ..ExportOptions::default() })
+}
+
+/// `to_kicad_footprint` for `options.kicad_version`, in canonical element order unless
+/// `options.canonical_order` is off, laid out in `options.output_style` and with UUIDs
+/// by `options.uuids`. Parsed footprints keep their source text only for the current
+/// release.
+///
+/// Panics if the written text doesn't read back for the `KiCadCanonical` layout, a bug
+/// in the writer; `try_to_kicad_footprint_with_options` returns it as an error.
+pub fn to_kicad_footprint_with_options<T: BoardComposableObject + ?Sized>(component: &T, options: &ExportOptions) -> String {
+    styled_footprint(component, options).unwrap_or_else(|error| panic!("written footprint could not be laid out: {}", error))
+}
+
+fn styled_footprint<T: BoardComposableObject + ?Sized>(component: &T, options: &ExportOptions) -> Result<String, SExprError> {
+    let output = match component.kicad_source().filter(|_| options.kicad_version == KiCadVersion::default()) {
+        Some(source) => apply_output_style(&source, options)?,
+        None => {
+            let mut output = String::new();
+            write_footprint_with_options(&mut output, component, None, options);
+            apply_output_style(&quantize_numbers(&output, options.resolution), options)?
+        }
+    };
+    Ok(match options.uuids {
+        UuidMode::AsGiven => output,
+        UuidMode::Deterministic => with_namespaced_uuids(&output, &component.footprint_name(), &options.uuid_namespace),
+    })
+}
+
+/// What writing `component` for `version` loses: pads whose padstack collapses to the
+/// front geometry on releases before KiCad 9
+pub fn version_warnings<T: BoardComposableObject + ?Sized>(component: &T, version: KiCadVersion) -> Vec<ExportWarning> {
+    if version.has_padstacks() {
+        return Vec::new();
+    }
+    component
+        .pad_descriptors()
+        .into_iter()
+        .filter(|pad| pad.has_padstack())
+        .map(|pad| ExportWarning::PadStackCollapsed { pad: pad.number.into(), version })
+        .collect()
+}
+
+/// Check every pad, text and graphic layer against KiCad's layer names, so a typo fails
+/// the export instead of producing a footprint with pads on a layer that doesn't exist.
+/// Surface mount pads must also keep their copper, paste and mask on one side.
+pub fn validate_layers<T: BoardComposableObject + ?Sized>(component: &T) -> Result<(), ExportError> {
+    for pad in component.pad_descriptors() {
+        if let Some(layer) = pad.layers.iter().find(|layer| !is_kicad_layer_name(layer)) {
+            return Err(ExportError::InvalidLayer { item: format!("pad {}", pad.number), layer: layer.to_string() });
+        }
+        if matches!(pad.pad_type, PadType::SMD) && pad.side() == Some(PadSide::Both) {
+            return Err(ExportError::MixedSides { item: format!("pad {}", pad.number), layers: pad.layers.iter().map(LayerName::to_string).collect() });
+        }
+    }
+    for element in component.graphic_elements() {
+        let layer = element.kicad_layer();
+        if !is_kicad_layer_name(&layer) {
+            return Err(ExportError::InvalidLayer { item: "graphic".to_string(), layer });
+        }
+    }
+    for fp_text in component.fp_text_elements() {
+        if !is_kicad_layer_name(&fp_text.layer) {
+            return Err(ExportError::InvalidLayer { item: format!("text \"{}\"", fp_text.text), layer: fp_text.layer.into() });
+        }
+    }
+    Ok(())
+}
+
+/// `to_kicad_footprint` after `validate_layers`
+pub fn try_to_kicad_footprint<T: BoardComposableObject + ?Sized>(component: &T) -> Result<String, ExportError> {
+    try_to_kicad_footprint_for(component, KiCadVersion::default())
+}
+
+/// `to_kicad_footprint_for` after `validate_layers`
+pub fn try_to_kicad_footprint_for<T: BoardComposableObject + ?Sized>(component: &T, version: KiCadVersion) -> Result<String, ExportError> {
+    validate_layers(component)?;
+    Ok(to_kicad_footprint_for(component, version))
+}
+
+/// `to_kicad_footprint_with_options` after `validate_layers`, with layout failures
+/// returned rather than panicking
+pub fn try_to_kicad_footprint_with_options<T: BoardComposableObject + ?Sized>(
+    component: &T,
+    options: &ExportOptions,
+) -> Result<String, ExportError> {
+    validate_layers(component)?;
+    Ok(styled_footprint(component, options)?)
+}
+
+pub struct KiCadFootprintExporter;
+
+impl FootprintExporter for KiCadFootprintExporter {
+    fn name(&self) -> &str {
+        "kicad"
+    }
+
+    fn file_extension(&self) -> &str {
+        "kicad_mod"
+    }
+
+    fn library_dir_name(&self, library: &str) -> String {
+        format!("{}.pretty", library)
+    }
+
+    fn export(&self, component: &dyn BoardComposableObject, options: &ExportOptions) -> Result<Vec<u8>, ExportError> {
+        Ok(try_to_kicad_footprint_with_options(component, options)?.into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hatched_graphics_are_written_as_outline_and_lines() {
+        let bounds = Rectangle { min_x: -2.0, min_y: -1.0, max_x: 2.0, max_y: 1.0 };
+        let element = GraphicElement {
+            element_type: GraphicType::filled_rectangle(&bounds, FillStyle::Hatch { pitch: 0.5, width: 0.1, angle: 0.0 }),
+            layer: LayerType::Fabrication,
+            side: Side::Front,
+            stroke: Stroke { width: 0.1, stroke_type: StrokeType::Solid },
+            uuid: Uuid::nil(),
+            role: None,
+        };
+        let mut footprint = String::new();
+        write_graphic_element(&mut footprint, &element);
+        assert_eq!(footprint.matches("(fp_poly").count(), 1, "{}", footprint);
+        assert_eq!(footprint.matches("(fp_line").count(), 4, "{}", footprint);
+        assert!(!footprint.contains("(fill yes)"));
+
+        let solid = GraphicElement { element_type: GraphicType::filled_rectangle(&bounds, FillStyle::Solid), ..element };
+        let mut footprint = String::new();
+        write_graphic_element(&mut footprint, &solid);
+        assert!(footprint.contains("(fill yes)") && !footprint.contains("(fp_line"), "{}", footprint);
+    }
+
+    #[derive(BoardComponent)]
+    #[footprint(name = "R_Quoted", library = "Test", kind = "Resistor", bounding_box = (-1.0, -0.5, 1.0, 0.5))]
+    #[footprint(tags = "Resistor \"SMD\" resistor\\ 0805")]
+    #[pad(number = "1", position = (-0.9, 0.0), size = (0.8, 1.2), shape = "rect")]
+    #[pad(number = "2", position = (0.9, 0.0), size = (0.8, 1.2), shape = "rect")]
+    struct QuotedTags(#[value] &'static str);
+
+    #[test]
+    fn tags_are_written_normalized() {
+        let footprint = to_kicad_footprint(&QuotedTags("10k"));
+        assert!(footprint.contains("\t(tags \"resistor smd 0805\")\n"), "{}", footprint);
+        crate::sexpr::parse(&footprint).unwrap();
+    }
+
+    #[test]
+    fn paste_is_written_only_for_pin_in_paste() {
+        let plain = to_kicad_footprint(&Dip::new(8).unwrap());
+        assert!(!plain.contains("Paste"), "{}", plain);
+
+        let pin = PinSection::Rect { width: 0.5, thickness: 0.25 };
+        let on_pads = Dip::new(8).unwrap().with_pin_in_paste(PinInPaste::new(pin)).unwrap();
+        let footprint = to_kicad_footprint(&on_pads);
+        assert_eq!(footprint.matches("\"*.Cu\" \"*.Mask\" \"F.Paste\"").count(), 8, "{}", footprint);
+
+        let overflowing = Dip::new(8).unwrap().with_pin_in_paste(PinInPaste::new(pin).with_overflow(Overflow::default())).unwrap();
+        let footprint = to_kicad_footprint(&overflowing);
+        assert_eq!(footprint.matches("(layers \"F.Paste\")").count(), 8, "{}", footprint);
+        crate::sexpr::parse(&footprint).unwrap();
+    }
+
+    #[test]
+    fn envelopes_are_written_dashed_on_their_user_layer() {
+        let envelope = ActuationEnvelope::rect("lever", &Rectangle { min_x: -1.0, min_y: -3.0, max_x: 1.0, max_y: -0.5 });
+        let footprint = Footprint { actuation_envelope: Some(envelope.clone()), ..Footprint::capture(&QuotedTags("10k")) };
+        let written = to_kicad_footprint(&footprint);
+        let poly = written.split("\t(fp_poly").nth(1).unwrap();
+        assert!(poly.contains("(type dash)") && poly.contains("(layer \"Dwgs.User\")"), "{}", written);
+        assert!(poly.contains("(xy -1 -3)") && poly.contains("(fill no)"), "{}", poly);
+        crate::sexpr::parse(&written).unwrap();
+
+        let footprint = Footprint { actuation_envelope: Some(envelope.with_layer("User.Comments")), ..footprint };
+        assert!(to_kicad_footprint(&footprint).contains("(layer \"Cmts.User\")"));
+    }
+}
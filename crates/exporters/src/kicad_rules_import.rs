@@ -0,0 +1,440 @@
+//! Design rules from a KiCad project
+//!
+//! Reads the net classes and board minimums of a `.kicad_pro` and the custom rules of its
+//! `.kicad_dru`, so routing DRC (`Board::routing_violations`) enforces the limits KiCad
+//! will instead of a second copy kept by hand.
+//!
+//! - Net classes keep their clearance, track width and via size, each raised to the
+//!   board minimum where that is larger. Nets join classes by name and by wildcard
+//!   pattern, in either the KiCad 8 form (`netclass_assignments`, `netclass_patterns`) or
+//!   the older per-class `nets` lists.
+//! - The minimum through hole and via annular ring go into a `FabProfile`.
+//! - Custom rules are read in their simple forms: a `clearance` constraint between nets
+//!   of a class or name becomes a `ClearanceRule`, and `track_width`, `via_diameter` and
+//!   `hole_size` constraints on a class replace that class's minimum. Conditions may test
+//!   `A.NetClass`, `A.NetName` and `A.hasNetclass()` (and `B.` for clearances), joined by
+//!   `&&`; only the `min` of a constraint is enforced.
+//!
+//! Rules using anything else (layer or severity clauses, other constraints, `||` or other
+//! properties in conditions) are skipped whole and listed in `unsupported` rather than
+//! half applied. KiCad has no setting for stacked or any-layer micro-vias, so the board's
+//! `ViaRules` are left as they are.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use copper_substrate::board::Board;
+use copper_substrate::prelude::*;
+use serde_json::Value;
+
+use crate::sexpr::{self, SExpr, SExprError};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RulesImportError {
+    /// The project file isn't JSON
+    Project(String),
+    Syntax(SExprError),
+    /// A value that should be a length isn't one
+    Invalid { item: String, message: String },
+}
+
+impl fmt::Display for RulesImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RulesImportError::Project(message) => write!(f, "invalid KiCad project: {}", message),
+            RulesImportError::Syntax(error) => write!(f, "invalid custom rules: {}", error),
+            RulesImportError::Invalid { item, message } => write!(f, "{}: {}", item, message),
+        }
+    }
+}
+
+impl std::error::Error for RulesImportError {}
+
+impl From<SExprError> for RulesImportError {
+    fn from(error: SExprError) -> Self {
+        RulesImportError::Syntax(error)
+    }
+}
+
+/// A custom rule left out, and the construct that made it
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnsupportedRule {
+    pub rule: String,
+    pub construct: String,
+}
+
+impl fmt::Display for UnsupportedRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rule \"{}\" skipped: {} is not supported", self.rule, self.construct)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KiCadDesignRules {
+    pub default_class: NetClass,
+    pub classes: Vec<NetClass>,
+    /// Net name and the class it is in
+    pub net_classes: Vec<(String, String)>,
+    /// Wildcard net name pattern and class, for nets not named in `net_classes`
+    pub net_class_patterns: Vec<(String, String)>,
+    pub clearance_rules: Vec<ClearanceRule>,
+    /// Smallest plated hole, vias included
+    pub min_through_hole: Option<f32>,
+    pub min_annular_ring: Option<f32>,
+    pub unsupported: Vec<UnsupportedRule>,
+}
+
+/// Number at `key` of a JSON object
+fn number(object: &Value, key: &str) -> Option<f32> {
+    object.get(key)?.as_f64().map(|value| value as f32)
+}
+
+fn invalid(item: &str, message: &str) -> RulesImportError {
+    RulesImportError::Invalid { item: item.to_string(), message: message.to_string() }
+}
+
+/// A custom rule length such as "0.5mm", "8mil" or a bare number of millimeters
+fn length(item: &str, text: &str) -> Result<f32, RulesImportError> {
+    let text = text.trim();
+    let (digits, scale) = [("mm", 1.0), ("um", 0.001), ("mil", 0.0254), ("in", 25.4)]
+        .into_iter()
+        .find_map(|(unit, scale)| text.strip_suffix(unit).map(|digits| (digits, scale)))
+        .unwrap_or((text, 1.0));
+    digits.trim().parse::<f32>().map(|value| value * scale).map_err(|_| invalid(item, &format!("\"{}\" is not a length", text)))
+}
+
+impl KiCadDesignRules {
+    /// Rules of a `.kicad_pro` file's contents
+    pub fn from_project(text: &str) -> Result<Self, RulesImportError> {
+        let project: Value = serde_json::from_str(text).map_err(|e| RulesImportError::Project(e.to_string()))?;
+        let minimums = &project["board"]["design_settings"]["rules"];
+        let net_settings = &project["net_settings"];
+
+        let mut default_class = NetClass::default();
+        let mut classes = Vec::new();
+        let mut net_classes = Vec::new();
+        for class in net_settings["classes"].as_array().into_iter().flatten() {
+            let name = class["name"].as_str().unwrap_or_default();
+            // KiCad 9 leaves values a class doesn't override unset, falling back to Default
+            let base = &default_class;
+            let mut imported = NetClass::new(
+                name,
+                number(class, "clearance").unwrap_or(base.clearance),
+                number(class, "track_width").unwrap_or(base.track_width),
+                number(class, "via_diameter").unwrap_or(base.via_diameter),
+                number(class, "via_drill").unwrap_or(base.via_drill),
+            );
+            let raise = |value: &mut f32, key: &str| *value = value.max(number(minimums, key).unwrap_or(0.0));
+            raise(&mut imported.clearance, "min_clearance");
+            raise(&mut imported.track_width, "min_track_width");
+            raise(&mut imported.via_diameter, "min_via_diameter");
+            raise(&mut imported.via_drill, "min_through_hole_diameter");
+            for net in class["nets"].as_array().into_iter().flatten().filter_map(Value::as_str) {
+                net_classes.push((net.to_string(), name.to_string()));
+            }
+            if name == "Default" {
+                default_class = imported;
+            } else {
+                classes.push(imported);
+            }
+        }
+
+        // KiCad 9 may list several classes per net; the first is the one that sets the rules
+        for (net, class) in net_settings["netclass_assignments"].as_object().into_iter().flatten() {
+            let class = class.as_str().or_else(|| class.as_array()?.first()?.as_str());
+            if let Some(class) = class {
+                net_classes.push((net.clone(), class.to_string()));
+            }
+        }
+        let net_class_patterns = net_settings["netclass_patterns"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| Some((entry["pattern"].as_str()?.to_string(), entry["netclass"].as_str()?.to_string())))
+            .collect();
+
+        Ok(Self {
+            default_class,
+            classes,
+            net_classes,
+            net_class_patterns,
+            clearance_rules: Vec::new(),
+            min_through_hole: number(minimums, "min_through_hole_diameter"),
+            min_annular_ring: number(minimums, "min_via_annular_width"),
+            unsupported: Vec::new(),
+        })
+    }
+
+    /// Add the custom rules of a `.kicad_dru` file's contents, see the module documentation
+    pub fn with_custom_rules(mut self, text: &str) -> Result<Self, RulesImportError> {
+        // Rules files are a sequence of lists with `#` comment lines
+        let lists: String = text.lines().filter(|line| !line.trim_start().starts_with('#')).collect::<Vec<_>>().join("\n");
+        let root = sexpr::parse(&format!("(kicad_dru\n{}\n)", lists))?;
+        for rule in root.find_all("rule") {
+            let name = rule.children().get(1).and_then(SExpr::as_str).unwrap_or_default().to_string();
+            if let Err(construct) = self.add_custom_rule(&name, rule)? {
+                self.unsupported.push(UnsupportedRule { rule: name, construct });
+            }
+        }
+        Ok(self)
+    }
+
+    /// Apply one rule, or tell which construct it uses that can't be applied
+    fn add_custom_rule(&mut self, name: &str, rule: &SExpr) -> Result<Result<(), String>, RulesImportError> {
+        let mut condition = (NetCondition::Any, NetCondition::Any);
+        let mut constraints = Vec::new();
+        for clause in &rule.children()[2.min(rule.children().len())..] {
+            match clause.head() {
+                Some("condition") => match parse_condition(clause.children().get(1).and_then(SExpr::as_str).unwrap_or_default()) {
+                    Ok(parsed) => condition = parsed,
+                    Err(construct) => return Ok(Err(construct)),
+                },
+                Some("constraint") => constraints.push(clause),
+                Some(other) => return Ok(Err(format!("a {} clause", other))),
+                None => return Ok(Err("a clause that isn't a list".to_string())),
+            }
+        }
+
+        let mut clearance_rules = Vec::new();
+        let mut class_minimums = Vec::new();
+        for constraint in constraints {
+            let kind = constraint.children().get(1).and_then(SExpr::as_str).unwrap_or_default();
+            if !["clearance", "track_width", "via_diameter", "hole_size"].contains(&kind) {
+                return Ok(Err(format!("the {} constraint", kind)));
+            }
+            let Some(min) = constraint.find("min").and_then(|min| min.children().get(1)).and_then(SExpr::as_str) else {
+                return Ok(Err(format!("a {} constraint without a minimum", kind)));
+            };
+            let min = length(&format!("rule \"{}\"", name), min)?;
+            match kind {
+                "clearance" => clearance_rules.push(ClearanceRule::new(name, condition.0.clone(), condition.1.clone(), min)),
+                _ => {
+                    let class = match &condition {
+                        (NetCondition::Any, NetCondition::Any) => None,
+                        (NetCondition::Class(class), NetCondition::Any) => Some(class.clone()),
+                        _ => return Ok(Err(format!("a {} constraint on anything but a net class", kind))),
+                    };
+                    if class.as_ref().is_some_and(|class| self.class_mut(class).is_none()) {
+                        return Ok(Err(format!("the unknown net class \"{}\"", class.unwrap_or_default())));
+                    }
+                    class_minimums.push((kind, class, min));
+                }
+            }
+        }
+
+        self.clearance_rules.extend(clearance_rules);
+        for (kind, class, min) in class_minimums {
+            let classes: Vec<&mut NetClass> = match class {
+                Some(class) => self.class_mut(&class).into_iter().collect(),
+                None => self.classes.iter_mut().chain([&mut self.default_class]).collect(),
+            };
+            for class in classes {
+                match kind {
+                    "track_width" => class.track_width = min,
+                    "via_diameter" => class.via_diameter = min,
+                    _ => class.via_drill = min,
+                }
+            }
+        }
+        Ok(Ok(()))
+    }
+
+    fn class_mut(&mut self, name: &str) -> Option<&mut NetClass> {
+        if self.default_class.name == name {
+            return Some(&mut self.default_class);
+        }
+        self.classes.iter_mut().find(|class| class.name == name)
+    }
+
+    /// Put the classes, net assignments and clearance rules on `board`
+    pub fn apply_to(&self, board: &mut Board) {
+        board.set_default_net_class(self.default_class.clone());
+        for class in &self.classes {
+            board.add_net_class(class.clone());
+        }
+        for (net, class) in &self.net_classes {
+            board.assign_net_class(net, class);
+        }
+        for (pattern, class) in &self.net_class_patterns {
+            board.assign_net_class_pattern(pattern, class);
+        }
+        for rule in &self.clearance_rules {
+            board.add_clearance_rule(rule.clone());
+        }
+    }
+
+    /// `base` with the project's minimum hole and annular ring where it sets them
+    pub fn fab_profile(&self, base: FabProfile) -> FabProfile {
+        FabProfile {
+            min_drill: self.min_through_hole.unwrap_or(base.min_drill),
+            min_annular_ring: self.min_annular_ring.unwrap_or(base.min_annular_ring),
+            ..base
+        }
+    }
+}
+
+/// Conditions on the two items of a condition string, or the construct that isn't supported
+fn parse_condition(text: &str) -> Result<(NetCondition, NetCondition), String> {
+    let mut conditions = (NetCondition::Any, NetCondition::Any);
+    for term in text.split("&&").map(str::trim) {
+        let term = term.strip_prefix('(').and_then(|term| term.strip_suffix(')')).unwrap_or(term).trim();
+        let unsupported = || format!("the condition \"{}\"", term);
+        if term.contains("||") || term.contains("!=") {
+            return Err(unsupported());
+        }
+        let (item, test) = term.split_once('.').ok_or_else(unsupported)?;
+        let unquote = |value: &str| {
+            let value = value.trim();
+            value
+                .strip_prefix('\'')
+                .and_then(|value| value.strip_suffix('\''))
+                .or_else(|| value.strip_prefix('"').and_then(|value| value.strip_suffix('"')))
+                .map(str::to_string)
+        };
+        let condition = if let Some((property, value)) = test.split_once("==") {
+            let value = unquote(value).ok_or_else(unsupported)?;
+            match property.trim() {
+                "NetClass" => NetCondition::Class(value),
+                "NetName" => NetCondition::Net(value),
+                _ => return Err(unsupported()),
+            }
+        } else if let Some(argument) = test.strip_prefix("hasNetclass(").and_then(|rest| rest.strip_suffix(')')) {
+            NetCondition::Class(unquote(argument).ok_or_else(unsupported)?)
+        } else {
+            return Err(unsupported());
+        };
+        let slot = match item.trim() {
+            "A" => &mut conditions.0,
+            "B" => &mut conditions.1,
+            _ => return Err(unsupported()),
+        };
+        if *slot != NetCondition::Any {
+            return Err(format!("two conditions on {}", item.trim()));
+        }
+        *slot = condition;
+    }
+    Ok(conditions)
+}
+
+/// Read `<name>.kicad_pro` and, when there is one next to it, `<name>.kicad_dru`
+pub fn read_project_rules(project: &Path) -> io::Result<KiCadDesignRules> {
+    let to_io = |error: RulesImportError| io::Error::new(io::ErrorKind::InvalidData, error);
+    let rules = KiCadDesignRules::from_project(&fs::read_to_string(project)?).map_err(to_io)?;
+    let custom = project.with_extension("kicad_dru");
+    if !custom.exists() {
+        return Ok(rules);
+    }
+    rules.with_custom_rules(&fs::read_to_string(custom)?).map_err(to_io)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use copper_substrate::routing::RoutingViolation;
+
+    use super::*;
+
+    fn project() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples/fixtures/rules_project/rules_project.kicad_pro")
+    }
+
+    /// Rule and nets of every violation, sorted
+    fn summary(board: &Board) -> Vec<String> {
+        let mut found: Vec<String> = board
+            .routing_violations()
+            .iter()
+            .map(|violation| match violation {
+                RoutingViolation::Clearance { net_a, net_b, required, .. } => {
+                    format!("clearance {} {} {:.2}", net_a.as_deref().unwrap_or("-"), net_b.as_deref().unwrap_or("-"), required)
+                }
+                RoutingViolation::TrackWidth { net, required, .. } => format!("track_width {} {:.2}", net, required),
+                RoutingViolation::ViaDiameter { net, required, .. } => format!("via_diameter {} {:.2}", net, required),
+                RoutingViolation::ViaDrill { net, required, .. } => format!("via_drill {} {:.2}", net, required),
+                other => format!("{:?}", other),
+            })
+            .collect();
+        found.sort();
+        found
+    }
+
+    fn routed_board(rules: &KiCadDesignRules) -> Board {
+        let copper = BoardLayer::front(LayerType::Copper);
+        let mut board = Board::new("rules").with_rectangular_outline(30.0, 12.0);
+        rules.apply_to(&mut board);
+        // 0.425 mm between GND and SIG: enough for the classes, not for the custom rule
+        board.add_track(Track::new("GND", copper, Point::new(2.0, 2.0), Point::new(12.0, 2.0), 0.5));
+        board.add_track(Track::new("SIG", copper, Point::new(2.0, 2.8), Point::new(12.0, 2.8), 0.25));
+        board.add_track(Track::new("SIG2", copper, Point::new(2.0, 3.3), Point::new(12.0, 3.3), 0.25));
+        board.add_track(Track::new("+3V3", copper, Point::new(2.0, 6.0), Point::new(12.0, 6.0), 0.4));
+        board.add_track(Track::new("CLK", copper, Point::new(2.0, 9.0), Point::new(12.0, 9.0), 0.2));
+        board.add_via(Via::new("GND", Point::new(20.0, 2.0), 0.6, 0.3));
+        board
+    }
+
+    #[test]
+    fn project_classes_and_assignments_are_read() {
+        let rules = read_project_rules(&project()).unwrap();
+        assert_eq!(rules.default_class, NetClass::new("Default", 0.2, 0.25, 0.6, 0.3));
+        assert_eq!(rules.classes, [NetClass::new("Power", 0.3, 0.5, 0.8, 0.4)]);
+        assert_eq!(rules.net_classes, [("GND".to_string(), "Power".to_string())]);
+        assert_eq!(rules.net_class_patterns, [("+*V*".to_string(), "Power".to_string())]);
+        assert_eq!(
+            rules.clearance_rules,
+            [ClearanceRule::new("Power to signal", NetCondition::Class("Power".into()), NetCondition::Class("Default".into()), 0.5)]
+        );
+    }
+
+    #[test]
+    fn rules_that_cannot_be_applied_are_reported_by_name() {
+        let rules = read_project_rules(&project()).unwrap();
+        let skipped: Vec<&str> = rules.unsupported.iter().map(|rule| rule.rule.as_str()).collect();
+        assert_eq!(skipped, ["Outer power width", "Clock length"]);
+    }
+
+    #[test]
+    fn routing_drc_matches_the_custom_rules() {
+        let board = routed_board(&read_project_rules(&project()).unwrap());
+        assert_eq!(board.net_class("+3V3").name, "Power");
+        assert_eq!(
+            summary(&board),
+            [
+                "clearance GND SIG 0.50",
+                "track_width +3V3 0.50",
+                "track_width CLK 0.25",
+                "via_diameter GND 0.80",
+                "via_drill GND 0.40",
+            ]
+        );
+    }
+
+    #[test]
+    fn without_the_rules_file_only_the_classes_apply() {
+        // The GND to SIG gap passes and CLK's 0.2 mm is wide enough
+        let text = fs::read_to_string(project()).unwrap();
+        let board = routed_board(&KiCadDesignRules::from_project(&text).unwrap());
+        assert_eq!(summary(&board), ["track_width +3V3 0.50", "via_diameter GND 0.80", "via_drill GND 0.40"]);
+    }
+
+    #[test]
+    fn board_minimums_reach_the_fab_profile() {
+        let fab = read_project_rules(&project()).unwrap().fab_profile(FabProfile::standard());
+        assert_eq!((fab.min_drill, fab.min_annular_ring), (0.3, 0.13));
+    }
+
+    #[test]
+    fn either_or_conditions_are_reported_not_widened() {
+        let text = fs::read_to_string(project()).unwrap();
+        let widened = KiCadDesignRules::from_project(&text)
+            .unwrap()
+            .with_custom_rules(
+                "(version 1)\n(rule \"either\" (constraint clearance (min 1mm)) (condition \"A.NetName == 'A' || A.NetName == 'B'\"))",
+            )
+            .unwrap();
+        assert!(widened.clearance_rules.is_empty());
+        assert_eq!(widened.unsupported.len(), 1);
+        assert_eq!(widened.unsupported[0].rule, "either");
+    }
+}
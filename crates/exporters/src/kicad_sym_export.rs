@@ -0,0 +1,193 @@
+use std::fmt::Write;
+use copper_substrate::prelude::*;
+
+use crate::sexpr::quote;
+
+/// Symbol grid used by the KiCad symbol libraries (50 mil)
+const GRID: f32 = 1.27;
+const PIN_PITCH: f32 = 2.54;
+const PIN_LENGTH: f32 = 2.54;
+/// Pin length of two terminal passives, matching Device:R
+const PASSIVE_PIN_LENGTH: f32 = 1.27;
+const FONT_SIZE: f32 = 1.27;
+
+/// A symbol pin derived from the footprint pads
+struct SymbolPin {
+    number: String,
+    name: String,
+    electrical_type: &'static str,
+}
+
+/// One pin per distinct pad number, in pad order. Mechanical (NPTH, marked mechanical or
+/// fiducial) pads and pads that share a number with an earlier pad (thermal pads, jumpers) do not
+/// get their own pin.
+fn symbol_pins<T: BoardComposableObject + ?Sized>(component: &T) -> Vec<SymbolPin> {
+    let passive = component.is_passive() || component.functional_type().is_two_terminal_passive();
+    let mut pins: Vec<SymbolPin> = Vec::new();
+    for pad in component.pad_descriptors() {
+        let mechanical = pad.fab_property.is_some_and(|property| !property.is_electrical());
+        if matches!(pad.pad_type, PadType::NPTH) || pad.number.is_empty() || mechanical {
+            continue;
+        }
+        if pins.iter().any(|pin| pin.number == pad.number) {
+            continue;
+        }
+        let name = match &pad.pin_function {
+            Some(function) => function.clone(),
+            None if passive => "~".to_string(),
+            None => pad.number.to_string(),
+        };
+        pins.push(SymbolPin {
+            number: pad.number.into(),
+            name,
+            electrical_type: if passive { "passive" } else { "unspecified" },
+        });
+    }
+    pins
+}
+
+fn write_symbol_property(output: &mut String, name: &str, value: &str, at: (f32, f32, f32), hidden: bool) {
+    writeln!(output, "\t\t(property {} {}", quote(name), quote(value)).unwrap();
+    writeln!(output, "\t\t\t(at {} {} {})", at.0, at.1, at.2).unwrap();
+    writeln!(output, "\t\t\t(effects").unwrap();
+    writeln!(output, "\t\t\t\t(font").unwrap();
+    writeln!(output, "\t\t\t\t\t(size {} {})", FONT_SIZE, FONT_SIZE).unwrap();
+    writeln!(output, "\t\t\t\t)").unwrap();
+    if hidden {
+        writeln!(output, "\t\t\t\t(hide yes)").unwrap();
+    }
+    writeln!(output, "\t\t\t)").unwrap();
+    writeln!(output, "\t\t)").unwrap();
+}
+
+fn write_symbol_pin(output: &mut String, pin: &SymbolPin, at: (f32, f32, f32), length: f32) {
+    writeln!(output, "\t\t\t(pin {} line", pin.electrical_type).unwrap();
+    writeln!(output, "\t\t\t\t(at {} {} {})", at.0, at.1, at.2).unwrap();
+    writeln!(output, "\t\t\t\t(length {})", length).unwrap();
+    for (token, text) in [("name", &pin.name), ("number", &pin.number)] {
+        writeln!(output, "\t\t\t\t({} {}", token, quote(text)).unwrap();
+        writeln!(output, "\t\t\t\t\t(effects").unwrap();
+        writeln!(output, "\t\t\t\t\t\t(font").unwrap();
+        writeln!(output, "\t\t\t\t\t\t\t(size {} {})", FONT_SIZE, FONT_SIZE).unwrap();
+        writeln!(output, "\t\t\t\t\t\t)").unwrap();
+        writeln!(output, "\t\t\t\t\t)").unwrap();
+        writeln!(output, "\t\t\t\t)").unwrap();
+    }
+    writeln!(output, "\t\t\t)").unwrap();
+}
+
+fn write_rectangle(output: &mut String, start: (f32, f32), end: (f32, f32)) {
+    writeln!(output, "\t\t\t(rectangle").unwrap();
+    writeln!(output, "\t\t\t\t(start {} {})", start.0, start.1).unwrap();
+    writeln!(output, "\t\t\t\t(end {} {})", end.0, end.1).unwrap();
+    writeln!(output, "\t\t\t\t(stroke").unwrap();
+    writeln!(output, "\t\t\t\t\t(width 0.254)").unwrap();
+    writeln!(output, "\t\t\t\t\t(type default)").unwrap();
+    writeln!(output, "\t\t\t\t)").unwrap();
+    writeln!(output, "\t\t\t\t(fill").unwrap();
+    writeln!(output, "\t\t\t\t\t(type background)").unwrap();
+    writeln!(output, "\t\t\t\t)").unwrap();
+    writeln!(output, "\t\t\t)").unwrap();
+}
+
+/// Snap a symbol coordinate up to the 50 mil grid so pins land on connectable points
+fn snap_up(value: f32) -> f32 {
+    (value / GRID).ceil() * GRID
+}
+
+/// Write a single `(symbol ...)` block whose Footprint field links to `library:footprint`.
+///
+/// Two terminal passives get a vertical body with a pin at each end (like Device:R), every
+/// other component becomes a box with pins split between the left and right sides.
+pub fn write_symbol<T: BoardComposableObject + ?Sized>(output: &mut String, component: &T, library: &str) {
+    let name = component.footprint_name();
+    let functional_type = component.functional_type();
+    let pins = symbol_pins(component);
+    let two_terminal = pins.len() == 2 && functional_type.is_two_terminal_passive();
+
+    writeln!(output, "\t(symbol {}", quote(&name)).unwrap();
+    if two_terminal {
+        writeln!(output, "\t\t(pin_numbers").unwrap();
+        writeln!(output, "\t\t\t(hide yes)").unwrap();
+        writeln!(output, "\t\t)").unwrap();
+        writeln!(output, "\t\t(pin_names").unwrap();
+        writeln!(output, "\t\t\t(offset 0)").unwrap();
+        writeln!(output, "\t\t\t(hide yes)").unwrap();
+        writeln!(output, "\t\t)").unwrap();
+    }
+    writeln!(output, "\t\t(exclude_from_sim no)").unwrap();
+    writeln!(output, "\t\t(in_bom yes)").unwrap();
+    writeln!(output, "\t\t(on_board yes)").unwrap();
+
+    // Body geometry first so the fields can be placed around it
+    let (half_width, half_height) = if two_terminal {
+        (1.016, 2.54)
+    } else {
+        let rows = pins.len().div_ceil(2).max(1) as f32;
+        let longest_name = pins.iter().map(|pin| pin.name.chars().count()).max().unwrap_or(1) as f32;
+        (snap_up(longest_name * FONT_SIZE * 0.6 + 2.0 * GRID), snap_up(rows * PIN_PITCH / 2.0))
+    };
+
+    let footprint_field = format!("{}:{}", library, name);
+    let reference_at = if two_terminal { (2.032, 0.0, 90.0) } else { (0.0, half_height + GRID, 0.0) };
+    let value_at = if two_terminal { (0.0, 0.0, 90.0) } else { (0.0, -half_height - GRID, 0.0) };
+    write_symbol_property(output, "Reference", functional_type.reference_prefix(), reference_at, false);
+    write_symbol_property(output, "Value", functional_type.value(), value_at, false);
+    write_symbol_property(output, "Footprint", &footprint_field, (0.0, 0.0, 0.0), true);
+    write_symbol_property(output, "Datasheet", "~", (0.0, 0.0, 0.0), true);
+    write_symbol_property(output, "Description", component.description().as_deref().unwrap_or_default(), (0.0, 0.0, 0.0), true);
+
+    writeln!(output, "\t\t(symbol {}", quote(&format!("{}_0_1", name))).unwrap();
+    write_rectangle(output, (-half_width, -half_height), (half_width, half_height));
+    writeln!(output, "\t\t)").unwrap();
+
+    writeln!(output, "\t\t(symbol {}", quote(&format!("{}_1_1", name))).unwrap();
+    if two_terminal {
+        let pin_y = half_height + PASSIVE_PIN_LENGTH;
+        write_symbol_pin(output, &pins[0], (0.0, pin_y, 270.0), PASSIVE_PIN_LENGTH);
+        write_symbol_pin(output, &pins[1], (0.0, -pin_y, 90.0), PASSIVE_PIN_LENGTH);
+    } else {
+        // Counter-clockwise like an IC package: down the left side, then up the right side
+        let left_count = pins.len().div_ceil(2);
+        let top = half_height - PIN_PITCH / 2.0;
+        let pin_x = half_width + PIN_LENGTH;
+        for (i, pin) in pins.iter().enumerate() {
+            if i < left_count {
+                write_symbol_pin(output, pin, (-pin_x, top - i as f32 * PIN_PITCH, 0.0), PIN_LENGTH);
+            } else {
+                let row = pins.len() - 1 - i;
+                write_symbol_pin(output, pin, (pin_x, top - row as f32 * PIN_PITCH, 180.0), PIN_LENGTH);
+            }
+        }
+    }
+    writeln!(output, "\t\t)").unwrap();
+    writeln!(output, "\t\t(embedded_fonts no)").unwrap();
+    writeln!(output, "\t)").unwrap();
+}
+
+/// Generate a complete `.kicad_sym` library with one symbol per component, each linked to
+/// its footprint in the footprint library `library`
+pub fn to_kicad_symbol_lib(components: &[&dyn BoardComposableObject], library: &str) -> String {
+    let mut output = String::new();
+    writeln!(output, "(kicad_symbol_lib").unwrap();
+    writeln!(output, "\t(version 20241209)").unwrap();
+    writeln!(output, "\t(generator \"custom_pcb_tool\")").unwrap();
+    writeln!(output, "\t(generator_version \"1.0\")").unwrap();
+    for component in components {
+        write_symbol(&mut output, *component, library);
+    }
+    writeln!(output, ")").unwrap();
+    output
+}
+
+/// Symbol library for a single component, linked to its own `library_name()`
+pub fn to_kicad_symbol<T: BoardComposableObject + ?Sized>(component: &T) -> String {
+    let mut output = String::new();
+    writeln!(output, "(kicad_symbol_lib").unwrap();
+    writeln!(output, "\t(version 20241209)").unwrap();
+    writeln!(output, "\t(generator \"custom_pcb_tool\")").unwrap();
+    writeln!(output, "\t(generator_version \"1.0\")").unwrap();
+    write_symbol(&mut output, component, &component.library_name());
+    writeln!(output, ")").unwrap();
+    output
+}
@@ -0,0 +1,132 @@
+//! Layer mapping for exporters that don't use KiCad layer names
+//!
+//! DXF, gerber and other targets each have their own layer conventions, and fabs often
+//! want a layer somewhere specific (e.g. courtyard on a particular mechanical layer). A
+//! `LayerMap` maps every substrate layer to the target's identifier. Exporters resolve
+//! layers through it and fail on unmapped layers instead of guessing.
+//!
+//! Overrides can be loaded from TOML, keyed by KiCad layer name:
+//!
+//! ```toml
+//! [layers]
+//! "F.CrtYd" = "MECH15"
+//! "In1.Cu" = "INNER1"
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use copper_substrate::prelude::*;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayerMapError {
+    /// The exporter needed a layer the map has no entry for
+    Unmapped { layer: BoardLayer, target: String },
+    /// An override named a layer that isn't a concrete KiCad layer
+    UnknownLayer(String),
+    Parse(String),
+}
+
+impl fmt::Display for LayerMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayerMapError::Unmapped { layer, target } => {
+                write!(f, "layer {} has no mapping for the {} exporter", layer.to_kicad_string(), target)
+            }
+            LayerMapError::UnknownLayer(name) => write!(f, "unknown layer name \"{}\"", name),
+            LayerMapError::Parse(message) => write!(f, "invalid layer map: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for LayerMapError {}
+
+#[derive(Debug, Deserialize)]
+struct LayerMapFile {
+    #[serde(default)]
+    layers: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LayerMap {
+    /// Name of the exporter this map targets, used in error messages
+    pub target: String,
+    entries: HashMap<BoardLayer, String>,
+}
+
+impl LayerMap {
+    /// An empty map for `target`; every layer must be set explicitly
+    pub fn new(target: &str) -> Self {
+        Self { target: target.to_string(), entries: HashMap::new() }
+    }
+
+    /// Default DXF layer names for both outer sides
+    pub fn dxf_default() -> Self {
+        let mut map = Self::new("dxf");
+        for (side, prefix) in [(Side::Front, "TOP"), (Side::Back, "BOTTOM")] {
+            map.set(BoardLayer::new(LayerType::Copper, side), &format!("{}_COPPER", prefix));
+            map.set(BoardLayer::new(LayerType::SilkScreen, side), &format!("{}_SILK", prefix));
+            map.set(BoardLayer::new(LayerType::Courtyard, side), &format!("{}_COURTYARD", prefix));
+            map.set(BoardLayer::new(LayerType::Fabrication, side), &format!("{}_ASSEMBLY", prefix));
+            map.set(BoardLayer::new(LayerType::Mask, side), &format!("{}_MASK", prefix));
+            map.set(BoardLayer::new(LayerType::Paste, side), &format!("{}_PASTE", prefix));
+        }
+        map
+    }
+
+    /// Default gerber file extensions (Protel naming) for the printed layers
+    pub fn gerber_default() -> Self {
+        let mut map = Self::new("gerber");
+        map.set(BoardLayer::front(LayerType::Copper), "GTL");
+        map.set(BoardLayer::back(LayerType::Copper), "GBL");
+        map.set(BoardLayer::front(LayerType::SilkScreen), "GTO");
+        map.set(BoardLayer::back(LayerType::SilkScreen), "GBO");
+        map.set(BoardLayer::front(LayerType::Mask), "GTS");
+        map.set(BoardLayer::back(LayerType::Mask), "GBS");
+        map.set(BoardLayer::front(LayerType::Paste), "GTP");
+        map.set(BoardLayer::back(LayerType::Paste), "GBP");
+        map
+    }
+
+    pub fn set(&mut self, layer: BoardLayer, target: &str) -> &mut Self {
+        self.entries.insert(layer, target.to_string());
+        self
+    }
+
+    /// Target layer identifier for `layer`, or an error if it isn't mapped
+    pub fn resolve(&self, layer: &BoardLayer) -> Result<&str, LayerMapError> {
+        self.entries.get(layer).map(String::as_str).ok_or_else(|| LayerMapError::Unmapped {
+            layer: *layer,
+            target: self.target.clone(),
+        })
+    }
+
+    /// Apply TOML overrides on top of the current entries
+    pub fn apply_toml(&mut self, text: &str) -> Result<(), LayerMapError> {
+        let file: LayerMapFile = toml::from_str(text).map_err(|e| LayerMapError::Parse(e.to_string()))?;
+        for (name, target) in file.layers {
+            let layer = BoardLayer::from_kicad_str(&name).ok_or(LayerMapError::UnknownLayer(name))?;
+            self.set(layer, &target);
+        }
+        Ok(())
+    }
+
+    /// `base` with the TOML overrides in `text` applied
+    pub fn with_toml(mut base: LayerMap, text: &str) -> Result<LayerMap, LayerMapError> {
+        base.apply_toml(text)?;
+        Ok(base)
+    }
+}
+
+/// Expand a pad layer string, which may be a KiCad wildcard such as "*.Cu" or "F&B.Cu",
+/// into the concrete outer layers it covers
+pub fn expand_layer_name(name: &str) -> Vec<BoardLayer> {
+    match name.split_once('.') {
+        Some(("*", suffix)) | Some(("F&B", suffix)) => match LayerType::from_kicad_suffix(suffix) {
+            Some(layer) => vec![BoardLayer::front(layer), BoardLayer::back(layer)],
+            None => Vec::new(),
+        },
+        _ => BoardLayer::from_kicad_str(name).into_iter().collect(),
+    }
+}
@@ -1,9 +1,54 @@
+pub mod assembly_drawing;
+pub mod assembly_export;
+pub mod block_file;
+pub mod board_svg;
+pub mod bom_export;
+pub mod cpl_export;
+pub mod csf;
+pub mod drill_export;
+pub mod dxf_export;
+pub mod element_order;
+pub mod exporter;
+pub mod fab_outputs;
+pub mod footprint_definition;
+pub mod footprint_import;
+pub mod footprint_library;
+pub mod format;
+pub mod fp_lib_table;
+pub mod gerber_export;
+pub mod hot_reload;
+pub mod index;
+pub mod json_export;
+pub mod kicad_format;
+pub mod kicad_board_export;
+pub mod kicad_board_import;
+pub mod kicad_board_sync;
+pub mod kicad_footprint_parse;
 pub mod kicad_pcb_export;
+pub mod kicad_rules_import;
+pub mod kicad_sym_export;
+pub mod layer_map;
+pub mod library_manifest;
+pub mod library_models;
+pub mod mask_export;
+pub mod occupancy_export;
+pub mod sexpr;
+pub mod silk_export;
+pub mod stencil_export;
+pub mod svg_export;
+pub mod svg_golden;
+pub mod sweep;
+pub mod uuids;
+pub mod wrl_model;
 
+pub use exporter::{BoardExporter, ExportError, ExportOptions, ExporterRegistry, FootprintExporter};
+pub use board_svg::BoardSvg;
+pub use footprint_library::FootprintLibrary;
+pub use kicad_board_sync::KiCadPlacementSync;
 pub use kicad_pcb_export::*;
 use copper_substrate::prelude::*;
 
 // Helper function to generate KiCad footprints
-pub fn to_kicad_footprint<T: BoardComposableObject>(component: &T) -> String {
+pub fn to_kicad_footprint<T: BoardComposableObject + ?Sized>(component: &T) -> String {
     kicad_pcb_export::to_kicad_footprint(component)
-}
\ No newline at end of file
+}
@@ -0,0 +1,504 @@
+//! Library versions and changelogs
+//!
+//! A versioned `FootprintLibrary` writes `library_manifest.json` into its `.pretty`
+//! directory next to the footprints, where KiCad ignores it. The manifest records the
+//! library's semantic version, the generator that wrote it, when, and a fingerprint of
+//! each footprint: a content hash and a hash per section of the file (pads, graphics,
+//! text, 3D model, description, attributes).
+//!
+//! `library_changelog` compares an earlier manifest with a library about to be released:
+//! footprints added, removed, renamed and modified, each modified one with the sections
+//! that changed. A footprint whose content is unchanged under a new name is a rename.
+//! The old side can be loaded from a manifest file or a `.pretty` directory; a directory
+//! without a manifest, e.g. a release from before versioning, is fingerprinted from its
+//! files.
+//!
+//! Hashes leave out uuids, generator fields and the footprint's own name, so a footprint
+//! regenerated unchanged compares equal. They are 64-bit FNV-1a, stable between builds.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::footprint_library::FootprintLibrary;
+use crate::kicad_format::{to_canonical, Indent, LineEnding};
+use crate::sexpr::{self, SExpr};
+
+/// Name of the manifest inside a `.pretty` directory
+pub const MANIFEST_FILE: &str = "library_manifest.json";
+
+/// Items left out of footprint hashes: they change on every export or with the generator
+const UNHASHED: &[&str] = &["uuid", "tstamp", "version", "generator", "generator_version"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ManifestError {
+    Io(String),
+    Parse(String),
+    /// Not a semantic version, e.g. "3.1" or "v3.1.0"
+    InvalidVersion(String),
+    /// A footprint file in a library directory that doesn't parse
+    Footprint { file: String, message: String },
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManifestError::Io(message) => write!(f, "cannot read library: {}", message),
+            ManifestError::Parse(message) => write!(f, "invalid library manifest: {}", message),
+            ManifestError::InvalidVersion(version) => write!(f, "\"{}\" is not a semantic version (MAJOR.MINOR.PATCH)", version),
+            ManifestError::Footprint { file, message } => write!(f, "{}: {}", file, message),
+        }
+    }
+}
+
+impl std::error::Error for ManifestError {}
+
+impl From<io::Error> for ManifestError {
+    fn from(e: io::Error) -> Self {
+        ManifestError::Io(e.to_string())
+    }
+}
+
+/// Release metadata of a library
+#[derive(Debug, Clone, PartialEq)]
+pub struct LibraryVersion {
+    /// Semantic version, MAJOR.MINOR.PATCH with optional pre-release and build suffixes
+    pub version: String,
+    pub generator: String,
+    /// Seconds since the Unix epoch, the time of writing when `None`. Set it for
+    /// byte-identical archives.
+    pub timestamp: Option<u64>,
+}
+
+impl LibraryVersion {
+    pub fn new(version: &str) -> Result<Self, ManifestError> {
+        if !is_semantic_version(version) {
+            return Err(ManifestError::InvalidVersion(version.to_string()));
+        }
+        Ok(Self {
+            version: version.to_string(),
+            generator: format!("copper-exporters {}", env!("CARGO_PKG_VERSION")),
+            timestamp: None,
+        })
+    }
+
+    pub fn with_generator(mut self, generator: &str) -> Self {
+        self.generator = generator.to_string();
+        self
+    }
+
+    pub fn with_timestamp(mut self, seconds: u64) -> Self {
+        self.timestamp = Some(seconds);
+        self
+    }
+}
+
+/// Whether `version` is MAJOR.MINOR.PATCH, optionally followed by "-pre.release" and
+/// "+build" identifiers, with no leading zeros in the numbers
+fn is_semantic_version(version: &str) -> bool {
+    let (version, build) = version.split_once('+').map_or((version, None), |(version, build)| (version, Some(build)));
+    let (core, pre) = version.split_once('-').map_or((version, None), |(core, pre)| (core, Some(pre)));
+    let identifiers = |text: &str| {
+        text.split('.').all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+    };
+    let numbers: Vec<&str> = core.split('.').collect();
+    numbers.len() == 3
+        && numbers.iter().all(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()) && (n.len() == 1 || !n.starts_with('0')))
+        && pre.is_none_or(identifiers)
+        && build.is_none_or(identifiers)
+}
+
+/// Fingerprint of one footprint, see the module documentation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FootprintEntry {
+    pub name: String,
+    pub content_hash: String,
+    pub pads: usize,
+    /// Section name to hash; sections the footprint doesn't have are left out
+    pub sections: BTreeMap<String, String>,
+}
+
+impl FootprintEntry {
+    /// Fingerprint of a `.kicad_mod` file's text
+    pub fn from_kicad_mod(text: &str) -> Result<Self, String> {
+        let expr = sexpr::parse(text).map_err(|e| e.to_string())?;
+        let name = expr.children().get(1).and_then(SExpr::as_str).ok_or("no footprint name")?.to_string();
+        let mut sections: BTreeMap<&str, String> = BTreeMap::new();
+        let mut pads = 0;
+        for item in expr.children().iter().skip(2) {
+            let head = item.head().unwrap_or_default();
+            if UNHASHED.contains(&head) {
+                continue;
+            }
+            pads += usize::from(head == "pad");
+            let text = to_canonical(&normalized(item, &name), Indent::Tabs, LineEnding::Lf);
+            sections.entry(section_of(head)).or_default().push_str(&text);
+        }
+        let content_hash = fnv1a(sections.values().map(String::as_str).collect::<String>().as_bytes());
+        let sections = sections.into_iter().map(|(section, text)| (section.to_string(), fnv1a(text.as_bytes()))).collect();
+        Ok(Self { name, content_hash, pads, sections })
+    }
+}
+
+/// Section of a footprint file a top-level item belongs to
+fn section_of(head: &str) -> &'static str {
+    match head {
+        "pad" => "pads",
+        "fp_line" | "fp_rect" | "fp_circle" | "fp_arc" | "fp_poly" | "fp_curve" | "dimension" => "graphics",
+        "fp_text" | "fp_text_box" | "property" => "text",
+        "model" => "model",
+        "descr" | "tags" => "description",
+        _ => "attributes",
+    }
+}
+
+/// `expr` without uuids, with strings naming the footprint replaced by a placeholder
+fn normalized(expr: &SExpr, name: &str) -> SExpr {
+    match expr {
+        SExpr::List(items) => SExpr::List(
+            items
+                .iter()
+                .filter(|item| !item.head().is_some_and(|head| head == "uuid" || head == "tstamp"))
+                .map(|item| normalized(item, name))
+                .collect(),
+        ),
+        SExpr::Str(text) if text == name => SExpr::Str("${FOOTPRINT}".to_string()),
+        other => other.clone(),
+    }
+}
+
+/// 64-bit FNV-1a, as 16 hex digits
+fn fnv1a(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3));
+    format!("{:016x}", hash)
+}
+
+/// Contents of `library_manifest.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LibraryManifest {
+    pub library: String,
+    /// `None` for a library fingerprinted from a directory without a manifest
+    pub version: Option<String>,
+    pub generator: Option<String>,
+    /// Seconds since the Unix epoch
+    pub timestamp: Option<u64>,
+    /// Sorted by name
+    pub footprints: Vec<FootprintEntry>,
+}
+
+impl LibraryManifest {
+    /// Manifest of the footprint files `(name, .kicad_mod text)` of a library
+    pub fn new<'a>(
+        library: &str,
+        version: Option<&LibraryVersion>,
+        files: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Result<Self, ManifestError> {
+        let mut footprints = files
+            .into_iter()
+            .map(|(file, text)| {
+                FootprintEntry::from_kicad_mod(text).map_err(|message| ManifestError::Footprint { file: file.to_string(), message })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        footprints.sort_by(|a, b| a.name.cmp(&b.name));
+        let now = || SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+        Ok(Self {
+            library: library.to_string(),
+            version: version.map(|version| version.version.clone()),
+            generator: version.map(|version| version.generator.clone()),
+            timestamp: version.map(|version| version.timestamp.unwrap_or_else(now)),
+            footprints,
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("manifests always serialize")
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, ManifestError> {
+        serde_json::from_str(text).map_err(|e| ManifestError::Parse(e.to_string()))
+    }
+
+    /// Read a manifest file, or the manifest of a `.pretty` directory. A directory
+    /// without one is fingerprinted from its `.kicad_mod` files and has no version.
+    pub fn load(path: &Path) -> Result<Self, ManifestError> {
+        if !path.is_dir() {
+            return Self::from_json(&fs::read_to_string(path)?);
+        }
+        let manifest = path.join(MANIFEST_FILE);
+        if manifest.is_file() {
+            return Self::from_json(&fs::read_to_string(manifest)?);
+        }
+        let mut files = Vec::new();
+        for entry in fs::read_dir(path)? {
+            let file = entry?.path();
+            if file.extension().is_some_and(|extension| extension == "kicad_mod") {
+                files.push((file.file_name().unwrap_or_default().to_string_lossy().into_owned(), fs::read_to_string(&file)?));
+            }
+        }
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        Self::new(&name, None, files.iter().map(|(file, text)| (file.as_str(), text.as_str())))
+    }
+
+    pub fn footprint(&self, name: &str) -> Option<&FootprintEntry> {
+        self.footprints.iter().find(|entry| entry.name == name)
+    }
+}
+
+/// What changed between two releases of a library
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Changelog {
+    pub library: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// (old name, new name)
+    pub renamed: Vec<(String, String)>,
+    /// Footprint and a summary line per changed section
+    pub modified: Vec<(String, Vec<String>)>,
+}
+
+impl Changelog {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.renamed.is_empty() && self.modified.is_empty()
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let version = |version: &Option<String>| version.clone().unwrap_or_else(|| "unversioned".to_string());
+        let mut output = String::new();
+        writeln!(output, "# {} {} → {}", self.library, version(&self.from), version(&self.to)).unwrap();
+        if self.is_empty() {
+            writeln!(output, "\nNo footprint changes.").unwrap();
+            return output;
+        }
+        for (title, names) in [("Added", &self.added), ("Removed", &self.removed)] {
+            if !names.is_empty() {
+                writeln!(output, "\n## {}\n", title).unwrap();
+                for name in names {
+                    writeln!(output, "- {}", name).unwrap();
+                }
+            }
+        }
+        if !self.renamed.is_empty() {
+            writeln!(output, "\n## Renamed\n").unwrap();
+            for (old, new) in &self.renamed {
+                writeln!(output, "- {} → {}", old, new).unwrap();
+            }
+        }
+        if !self.modified.is_empty() {
+            writeln!(output, "\n## Modified\n").unwrap();
+            for (name, changes) in &self.modified {
+                writeln!(output, "- **{}**: {}", name, changes.join(", ")).unwrap();
+            }
+        }
+        output
+    }
+}
+
+/// Summary lines of the sections that differ between two fingerprints of a footprint
+fn section_changes(old: &FootprintEntry, new: &FootprintEntry) -> Vec<String> {
+    let mut names: Vec<&String> = old.sections.keys().chain(new.sections.keys()).collect();
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .filter(|section| old.sections.get(*section) != new.sections.get(*section))
+        .map(|section| match section.as_str() {
+            "pads" if old.pads != new.pads => format!("pads {} → {}", old.pads, new.pads),
+            "model" => "3D model changed".to_string(),
+            section => format!("{} changed", section),
+        })
+        .collect()
+}
+
+/// Changes from the release described by `old` to `new`, see the module documentation
+pub fn library_changelog(old: &LibraryManifest, new: &FootprintLibrary) -> Result<Changelog, ManifestError> {
+    let new_manifest = new.manifest()?;
+    let mut changelog = Changelog {
+        library: new.name.clone(),
+        from: old.version.clone(),
+        to: new_manifest.version.clone(),
+        ..Changelog::default()
+    };
+    let mut removed: Vec<&FootprintEntry> = Vec::new();
+    for entry in &old.footprints {
+        match new_manifest.footprint(&entry.name) {
+            Some(current) if current.content_hash != entry.content_hash => {
+                changelog.modified.push((entry.name.clone(), section_changes(entry, current)));
+            }
+            Some(_) => {}
+            None => removed.push(entry),
+        }
+    }
+    for entry in new_manifest.footprints.iter().filter(|entry| old.footprint(&entry.name).is_none()) {
+        match removed.iter().position(|old| old.content_hash == entry.content_hash) {
+            Some(index) => changelog.renamed.push((removed.remove(index).name.clone(), entry.name.clone())),
+            None => changelog.added.push(entry.name.clone()),
+        }
+    }
+    changelog.removed = removed.into_iter().map(|entry| entry.name.clone()).collect();
+    Ok(changelog)
+}
+
+#[cfg(test)]
+mod tests {
+    use copper_substrate::presets;
+    use copper_substrate::prelude::*;
+
+    use super::*;
+
+    /// A chip footprint published under another name, its value text following the name
+    struct Renamed(ChipFootprint, &'static str);
+
+    impl BoardComposableObject for Renamed {
+        fn is_smt(&self) -> bool {
+            true
+        }
+        fn is_electrical(&self) -> bool {
+            true
+        }
+        fn is_passive(&self) -> bool {
+            self.0.is_passive()
+        }
+        fn terminal_count(&self) -> usize {
+            2
+        }
+        fn functional_type(&self) -> FunctionalType {
+            self.0.functional_type()
+        }
+        fn footprint_name(&self) -> String {
+            self.1.to_string()
+        }
+        fn library_name(&self) -> String {
+            self.0.library_name()
+        }
+        fn bounding_box(&self) -> Rectangle {
+            self.0.bounding_box()
+        }
+        fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+            self.0.pad_descriptors()
+        }
+        fn description_context(&self) -> Option<DescriptionContext> {
+            self.0.description_context()
+        }
+        fn fp_text_elements(&self) -> Vec<FpText> {
+            let mut texts = self.0.fp_text_elements();
+            for text in texts.iter_mut().filter(|text| text.text == self.0.footprint_name()) {
+                text.text = self.1.to_string();
+            }
+            texts
+        }
+        fn graphic_elements(&self) -> Vec<GraphicElement> {
+            self.0.graphic_elements()
+        }
+        fn model_3d(&self) -> Option<Model3D> {
+            self.0.model_3d()
+        }
+        fn courtyard_margin(&self) -> f32 {
+            self.0.courtyard_margin()
+        }
+        fn generate_courtyard(&self) -> Courtyard {
+            self.0.generate_courtyard()
+        }
+    }
+
+    fn release_1_0() -> FootprintLibrary {
+        let mut library = FootprintLibrary::new("Passives").with_version(LibraryVersion::new("1.0.0").unwrap().with_timestamp(1_700_000_000));
+        library
+            .add(presets::resistor_0603("10k"))
+            .add(presets::resistor_0805("10k"))
+            .add(presets::capacitor_0402("100n"))
+            .add(presets::capacitor_1206("10u"));
+        library
+    }
+
+    /// 1.1.0: one footprint kept, one widened, one renamed, one removed and one added
+    fn release_1_1() -> FootprintLibrary {
+        let profile = FabProfile { min_silk_line_width: 0.2, ..FabProfile::standard() };
+        let mut library = FootprintLibrary::new("Passives").with_version(LibraryVersion::new("1.1.0").unwrap());
+        library
+            .add(presets::resistor_0603("10k"))
+            .add(presets::resistor_0805("10k").with_silk_minimums(&profile))
+            .add(Renamed(presets::capacitor_0402("100n"), "C_0402_1005Metric_Small"))
+            .add(presets::capacitor_0603("1u"));
+        library
+    }
+
+    fn scratch(test: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("copper-manifest-{}-{}", std::process::id(), test));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn manifest_is_written_into_the_pretty_directory() {
+        let work = scratch("written");
+        let old = release_1_0();
+        let pretty = old.write_to(&work).unwrap();
+        let written = LibraryManifest::load(&pretty.join(MANIFEST_FILE)).unwrap();
+        assert_eq!(written, old.manifest().unwrap());
+        assert_eq!(written.version.as_deref(), Some("1.0.0"));
+        assert_eq!((written.timestamp, written.footprints.len()), (Some(1_700_000_000), 4));
+        assert!(written.generator.as_deref().is_some_and(|generator| generator.starts_with("copper-exporters")));
+        fs::remove_dir_all(work).unwrap();
+    }
+
+    #[test]
+    fn an_unchanged_library_gives_an_empty_changelog() {
+        let manifest = release_1_0().manifest().unwrap();
+        let changelog = library_changelog(&manifest, &release_1_0()).unwrap();
+        assert!(changelog.is_empty(), "{:#?}", changelog);
+    }
+
+    #[test]
+    fn changelog_lists_each_kind_of_change() {
+        let changelog = library_changelog(&release_1_0().manifest().unwrap(), &release_1_1()).unwrap();
+        assert_eq!((changelog.from.as_deref(), changelog.to.as_deref()), (Some("1.0.0"), Some("1.1.0")));
+        assert_eq!(changelog.added, ["C_0603_1608Metric"]);
+        assert_eq!(changelog.removed, ["C_1206_3216Metric"]);
+        assert_eq!(changelog.renamed, [("C_0402_1005Metric".to_string(), "C_0402_1005Metric_Small".to_string())]);
+        assert_eq!(changelog.modified, [("R_0805_2012Metric".to_string(), vec!["graphics changed".to_string()])]);
+        let markdown = changelog.to_markdown();
+        for heading in ["# Passives 1.0.0 → 1.1.0", "## Added", "## Removed", "## Renamed", "## Modified"] {
+            assert!(markdown.contains(heading), "{}", heading);
+        }
+    }
+
+    #[test]
+    fn a_release_without_a_manifest_is_fingerprinted_from_its_files() {
+        let work = scratch("from-files");
+        let pretty = release_1_0().write_to(&work).unwrap();
+        let written = LibraryManifest::load(&pretty.join(MANIFEST_FILE)).unwrap();
+        fs::remove_file(pretty.join(MANIFEST_FILE)).unwrap();
+        let from_files = LibraryManifest::load(&pretty).unwrap();
+        assert_eq!(from_files.version, None);
+        assert_eq!(from_files.footprints, written.footprints);
+
+        let new = release_1_1();
+        let expected = library_changelog(&written, &new).unwrap();
+        let again = library_changelog(&from_files, &new).unwrap();
+        assert_eq!(
+            (&again.added, &again.removed, &again.renamed, &again.modified),
+            (&expected.added, &expected.removed, &expected.renamed, &expected.modified)
+        );
+        assert!(again.to_markdown().starts_with("# Passives unversioned → 1.1.0"));
+        fs::remove_dir_all(work).unwrap();
+    }
+
+    #[test]
+    fn versions_follow_semver() {
+        for invalid in ["1.0", "v1.0.0", "01.0.0", "1.0.0-", "1.0.0+b@d"] {
+            assert!(matches!(LibraryVersion::new(invalid), Err(ManifestError::InvalidVersion(_))), "{}", invalid);
+        }
+        for valid in ["0.1.0", "2.0.0-rc.1", "1.2.3+build.5", "1.0.0-alpha-1"] {
+            assert!(LibraryVersion::new(valid).is_ok(), "{}", valid);
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! 3D models shipped with a footprint library
+//!
+//! Generated footprints point their models at `${KICAD9_3DMODEL_DIR}`, which only works
+//! where KiCad's own model library is installed. `LibraryModels` tells
+//! `FootprintLibrary::write_to` to ship the models instead: each footprint's model is
+//! looked up by file name in a source directory (or among models generated in memory),
+//! copied into `<Lib>.3dshapes/` next to the `.pretty` directory, and the footprint's model
+//! path is rewritten to point there. Offset, scale and rotation are kept.
+//!
+//! A model may exist as both STEP and WRL; the first extension in the preference order
+//! that exists is used, whatever extension the footprint asked for. The source directory
+//! is searched directly and in a subdirectory named like the model's original directory
+//! (e.g. `Resistor_SMD.3dshapes`), so a copy of KiCad's model library works as a source.
+//! Footprints whose model can't be found keep their original path and are listed as
+//! missing in the `ModelReport`.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use copper_substrate::prelude::*;
+
+use crate::wrl_model::WrlModel;
+
+/// Default preference order of model file extensions
+pub const MODEL_EXTENSIONS: &[&str] = &["step", "stp", "wrl"];
+
+/// How rewritten model paths are written into footprints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelPathMode {
+    /// `${KIPRJMOD}/<Lib>.3dshapes/<file>`, for libraries installed into a project
+    ProjectRelative,
+    /// `<Lib>.3dshapes/<file>`, which KiCad resolves against the project directory
+    Relative,
+}
+
+#[derive(Debug, Clone)]
+enum ModelSource {
+    File(PathBuf),
+    Generated(Vec<u8>),
+}
+
+/// Where a footprint's model was found
+#[derive(Debug, Clone)]
+pub struct ResolvedModel {
+    /// File name in the `.3dshapes` directory
+    pub file_name: String,
+    source: ModelSource,
+}
+
+/// Where to find models and how to reference them, see the module documentation
+#[derive(Debug, Clone)]
+pub struct LibraryModels {
+    pub source_dir: Option<PathBuf>,
+    pub path_mode: ModelPathMode,
+    /// File extensions without the dot, most preferred first
+    pub extensions: Vec<String>,
+    /// Models generated in memory, by file name
+    generated: BTreeMap<String, Vec<u8>>,
+}
+
+impl LibraryModels {
+    /// Models copied from `source_dir`
+    pub fn from_dir(source_dir: &Path) -> Self {
+        Self { source_dir: Some(source_dir.to_path_buf()), ..Self::default() }
+    }
+
+    pub fn with_path_mode(mut self, path_mode: ModelPathMode) -> Self {
+        self.path_mode = path_mode;
+        self
+    }
+
+    pub fn with_extensions(mut self, extensions: &[&str]) -> Self {
+        self.extensions = extensions.iter().map(|extension| extension.trim_start_matches('.').to_ascii_lowercase()).collect();
+        self
+    }
+
+    /// Ship `data` as `file_name` (e.g. "R_0603_1608Metric.wrl"), taking precedence over
+    /// the source directory
+    pub fn with_generated(mut self, file_name: &str, data: Vec<u8>) -> Self {
+        self.generated.insert(file_name.to_string(), data);
+        self
+    }
+
+    /// Ship a box model of `component` generated by `model`, see `wrl_model`, under its
+    /// model's file name with a `.wrl` extension. Nothing changes for a component
+    /// without a model path. Put "wrl" first in the extensions to prefer it over STEP files
+    /// in the source directory.
+    pub fn with_generated_model<T: BoardComposableObject + ?Sized>(self, component: &T, model: &WrlModel) -> Self {
+        let Some(model_3d) = component.model_3d() else {
+            return self;
+        };
+        let file = model_3d.path.rsplit(['/', '\\']).next().unwrap_or(&model_3d.path);
+        let stem = file.rsplit_once('.').map_or(file, |(stem, _)| stem);
+        self.with_generated(&format!("{}.wrl", stem), model.to_wrl(component).into_bytes())
+    }
+
+    /// Find the file for `model`, trying the extensions in order of preference
+    pub fn resolve(&self, model: &Model3D) -> Option<ResolvedModel> {
+        // KiCad paths use forward slashes whatever the platform
+        let file = model.path.rsplit(['/', '\\']).next().unwrap_or(&model.path);
+        let stem = file.rsplit_once('.').map_or(file, |(stem, _)| stem);
+        let parent = model.path.rsplit(['/', '\\']).nth(1).filter(|parent| !parent.starts_with('$'));
+        for extension in &self.extensions {
+            let file_name = format!("{}.{}", stem, extension);
+            if let Some(data) = self.generated.get(&file_name) {
+                return Some(ResolvedModel { file_name, source: ModelSource::Generated(data.clone()) });
+            }
+            let Some(source_dir) = &self.source_dir else { continue };
+            let candidates = [Some(source_dir.join(&file_name)), parent.map(|parent| source_dir.join(parent).join(&file_name))];
+            if let Some(path) = candidates.into_iter().flatten().find(|path| path.is_file()) {
+                return Some(ResolvedModel { file_name, source: ModelSource::File(path) });
+            }
+        }
+        None
+    }
+
+    /// Path written into footprints for a model shipped as `file_name` with library `library`
+    pub fn model_path(&self, library: &str, file_name: &str) -> String {
+        match self.path_mode {
+            ModelPathMode::ProjectRelative => format!("${{KIPRJMOD}}/{}.3dshapes/{}", library, file_name),
+            ModelPathMode::Relative => format!("{}.3dshapes/{}", library, file_name),
+        }
+    }
+}
+
+impl Default for LibraryModels {
+    fn default() -> Self {
+        Self {
+            source_dir: None,
+            path_mode: ModelPathMode::ProjectRelative,
+            extensions: MODEL_EXTENSIONS.iter().map(|extension| extension.to_string()).collect(),
+            generated: BTreeMap::new(),
+        }
+    }
+}
+
+impl ResolvedModel {
+    pub fn data(&self) -> io::Result<Vec<u8>> {
+        match &self.source {
+            ModelSource::File(path) => fs::read(path),
+            ModelSource::Generated(data) => Ok(data.clone()),
+        }
+    }
+
+    /// Copy into `dir`, leaving an identical file already there untouched
+    pub fn copy_into(&self, dir: &Path) -> io::Result<PathBuf> {
+        let data = self.data()?;
+        let target = dir.join(&self.file_name);
+        if fs::read(&target).ok().as_deref() != Some(data.as_slice()) {
+            fs::write(&target, data)?;
+        }
+        Ok(target)
+    }
+}
+
+/// Which footprints got a shipped model and which are missing one
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelReport {
+    /// Footprint name and shipped model file name
+    pub shipped: Vec<(String, String)>,
+    /// Footprint name and the model path that wasn't found
+    pub missing: Vec<(String, String)>,
+}
+
+impl ModelReport {
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+impl fmt::Display for ModelReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} models shipped, {} missing", self.shipped.len(), self.missing.len())?;
+        for (footprint, path) in &self.missing {
+            writeln!(f, "missing model for {}: {}", footprint, path)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+//! Solder mask layers as Gerber X2
+//!
+//! Mask Gerbers are negative: every object drawn is an opening in the mask, written dark
+//! like any other object, and the header says `.FilePolarity,Negative` so CAM tools read
+//! it that way. The openings are:
+//!
+//! - each pad on the side's mask layer, as its `PadDescriptor::mask_aperture` at the
+//!   given margin: round pads flashed, other shapes as regions
+//! - vias left untented on that side (see `Via::is_exposed`), flashed at their diameter
+//!   plus the margin
+//! - footprint and board graphics drawn on the mask layer: solid polygons as regions,
+//!   lines and outlines stroked with their width. `ExposedCopper` pairs such an opening
+//!   with the copper under it.
+//!
+//! Pads carry the same X2 attributes as on copper, see `gerber_export`. Coordinates are
+//! `Board::fab_point`s, from the board or the auxiliary origin.
+
+use copper_substrate::board::{Board, FabOrigin, PlacedComponent};
+use copper_substrate::prelude::*;
+
+use crate::exporter::{BoardExporter, ExportError, ExportOptions};
+use crate::gerber_export::{
+    board_graphic_objects, graphic_objects, write_layer, AperFunction, FilePolarity, GerberDialect, GerberObject, PadRef, Shape,
+};
+
+/// Mask openings of one placed footprint's pads that land on `side` of the board
+fn pad_openings(board: &Board, placed: &PlacedComponent, side: Side, mask_margin: f32, arc_tolerance: f32, origin: FabOrigin) -> Vec<GerberObject> {
+    let to_fab = |point: Point| board.fab_point(board.board_point(placed, point), origin);
+    // Back side placements are mirrored, so their F.Mask ends up on B.Mask
+    let local_layer = if placed.side == side { "F.Mask" } else { "B.Mask" };
+    let mut objects = Vec::new();
+    for pad in placed.component.pad_descriptors().iter().filter(|pad| pad.has_layer(local_layer)) {
+        let Some(aperture) = pad.mask_aperture(mask_margin, arc_tolerance) else { continue };
+        let shape = match (&pad.shape, pad.mask_aperture_aabb(mask_margin)) {
+            (PadShape::Circle, Some(bounds)) => Shape::Flash { center: to_fab(Point::from(pad.position)), diameter: bounds.width() },
+            _ => Shape::Region(aperture.into_iter().map(to_fab).collect()),
+        };
+        objects.push(GerberObject {
+            shape,
+            function: Some(AperFunction::of_pad(pad)),
+            pad: Some(PadRef {
+                reference: placed.reference.clone(),
+                number: pad.number.to_string(),
+                pin_function: pad.pin_function.clone(),
+            }),
+        });
+    }
+    objects
+}
+
+/// Gerber solder mask layer for one side of the board, coordinates measured from `origin`
+pub fn to_mask_gerber(board: &Board, side: Side, mask_margin: f32, arc_tolerance: f32, origin: FabOrigin, dialect: GerberDialect) -> String {
+    let mut objects = Vec::new();
+    for placed in board.components() {
+        objects.extend(pad_openings(board, placed, side, mask_margin, arc_tolerance, origin));
+        objects.extend(graphic_objects(board, placed, LayerType::Mask, side, None, origin));
+    }
+    for via in board.vias().iter().filter(|via| via.is_exposed(side)) {
+        let shape = Shape::Flash { center: board.fab_point(via.position, origin), diameter: via.diameter + 2.0 * mask_margin };
+        objects.push(GerberObject::new(shape).with_function(AperFunction::ViaPad));
+    }
+    objects.extend(board_graphic_objects(board, LayerType::Mask, side, None, origin));
+    let file_function = if side == Side::Front { "Soldermask,Top" } else { "Soldermask,Bot" };
+    write_layer(file_function, FilePolarity::Negative, &objects, dialect)
+}
+
+/// Writes the solder mask layer of one side, at `ExportOptions::mask_margin`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaskBoardExporter {
+    pub side: Side,
+    /// Absolute board coordinates unless set to the auxiliary origin
+    pub origin: FabOrigin,
+    pub dialect: GerberDialect,
+}
+
+impl MaskBoardExporter {
+    pub fn new(side: Side) -> Self {
+        Self { side, origin: FabOrigin::Absolute, dialect: GerberDialect::default() }
+    }
+
+    pub fn with_origin(mut self, origin: FabOrigin) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Write plain RS-274X, or X2 with attributes (the default)
+    pub fn with_dialect(mut self, dialect: GerberDialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+}
+
+impl Default for MaskBoardExporter {
+    fn default() -> Self {
+        Self::new(Side::Front)
+    }
+}
+
+impl BoardExporter for MaskBoardExporter {
+    fn name(&self) -> &str {
+        "mask"
+    }
+
+    fn file_extension(&self) -> &str {
+        if self.side == Side::Front { "gts" } else { "gbs" }
+    }
+
+    fn export(&self, board: &Board, options: &ExportOptions) -> Result<Vec<u8>, ExportError> {
+        Ok(to_mask_gerber(board, self.side, options.mask_margin, options.arc_tolerance, self.origin, self.dialect).into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use copper_substrate::presets;
+
+    use super::*;
+    use crate::gerber_export::to_copper_gerber;
+
+    /// A 10 x 6 mm ENIG touch pad with rounded corners at (20, 10) on a 40 x 20 mm board
+    fn touch_pad_board() -> Board {
+        let mut board = Board::new("touch").with_rectangular_outline(40.0, 20.0);
+        let pad = ExposedCopper::rounded_rectangle(Rectangle { min_x: 15.0, min_y: 7.0, max_x: 25.0, max_y: 13.0 }, 1.0).with_mask_margin(0.1);
+        for element in pad.graphic_elements() {
+            board.add_graphic(element);
+        }
+        board
+    }
+
+    /// Coordinates of the region outlines in a Gerber, as (x, y) pairs in mm
+    fn region_points(gerber: &str) -> Vec<Vec<(f32, f32)>> {
+        let mut regions = Vec::new();
+        let mut current: Option<Vec<(f32, f32)>> = None;
+        for line in gerber.lines() {
+            match line {
+                "G36*" => current = Some(Vec::new()),
+                "G37*" => regions.extend(current.take()),
+                _ => {
+                    if let (Some(points), Some(rest)) = (current.as_mut(), line.strip_prefix('X')) {
+                        let (x, rest) = rest.split_once('Y').unwrap();
+                        let y = &rest[..rest.len() - 4];
+                        points.push((x.parse::<f32>().unwrap() / 1e6, y.parse::<f32>().unwrap() / 1e6));
+                    }
+                }
+            }
+        }
+        regions
+    }
+
+    fn width(region: &[(f32, f32)]) -> f32 {
+        let xs = region.iter().map(|point| point.0);
+        xs.clone().fold(f32::MIN, f32::max) - xs.fold(f32::MAX, f32::min)
+    }
+
+    #[test]
+    fn mask_gerber_opens_the_touch_pad() {
+        let board = touch_pad_board();
+        let mask = to_mask_gerber(&board, Side::Front, 0.0, 0.01, FabOrigin::Absolute, GerberDialect::X2);
+        assert!(mask.contains("%TF.FileFunction,Soldermask,Top*%"), "{}", mask);
+        assert!(mask.contains("%TF.FilePolarity,Negative*%"), "{}", mask);
+        // The opening is drawn dark, which in a negative file clears the mask
+        assert!(!mask.contains("%LPC*%"));
+        let regions = region_points(&mask);
+        assert_eq!(regions.len(), 1, "{}", mask);
+        assert!((width(&regions[0]) - 10.2).abs() < 1e-3, "{:?}", regions);
+        // Rounded corners, not a plain rectangle; Y is up in fabrication coordinates
+        assert!(regions[0].len() > 8);
+        assert!(regions[0].iter().all(|&(_, y)| (-13.1 - 1e-3..=-6.9 + 1e-3).contains(&y)), "{:?}", regions);
+        // Nothing drawn on the back
+        let back = to_mask_gerber(&board, Side::Back, 0.0, 0.01, FabOrigin::Absolute, GerberDialect::X2);
+        assert!(region_points(&back).is_empty());
+    }
+
+    #[test]
+    fn copper_gerber_holds_the_pour() {
+        let board = touch_pad_board();
+        let copper = to_copper_gerber(&board, BoardLayer::front(LayerType::Copper), 0.01, FabOrigin::Absolute, GerberDialect::X2);
+        assert!(copper.contains("%TF.FilePolarity,Positive*%"));
+        let regions = region_points(&copper);
+        assert_eq!(regions.len(), 1, "{}", copper);
+        assert!((width(&regions[0]) - 10.0).abs() < 1e-3, "{:?}", regions);
+        // Zero width outlines are not stroked
+        assert!(!copper.contains("C,0.000000"), "{}", copper);
+    }
+
+    #[test]
+    fn pads_and_untented_vias_are_opened_at_the_margin() {
+        let mut board = Board::new("mask").with_rectangular_outline(20.0, 10.0);
+        board.place("R1", presets::resistor_0603("10k"), (5.0, 5.0), 0.0).unwrap();
+        board.place_shared("R2", Rc::new(presets::resistor_0603("10k")), (15.0, 5.0), 0.0, Side::Back).unwrap();
+        board.add_via(Via::new("GND", Point::new(10.0, 2.0), 0.6, 0.3));
+        let open = TentingSettings { front: TentingType::None, back: TentingType::None };
+        board.add_via(Via::new("GND", Point::new(10.0, 8.0), 0.6, 0.3).with_tenting(open));
+
+        let front = to_mask_gerber(&board, Side::Front, 0.05, 0.01, FabOrigin::Absolute, GerberDialect::X2);
+        assert_eq!(front.matches("%TO.C,R1*%").count(), 2, "{}", front);
+        assert!(!front.contains("R2"));
+        // Only the untented via, grown by the margin on each side
+        assert!(front.contains("%ADD10C,0.700000*%"), "{}", front);
+        assert_eq!(front.matches("D03*").count(), 1, "{}", front);
+        let back = to_mask_gerber(&board, Side::Back, 0.05, 0.01, FabOrigin::Absolute, GerberDialect::X2);
+        assert_eq!(back.matches("%TO.C,R2*%").count(), 2, "{}", back);
+    }
+}
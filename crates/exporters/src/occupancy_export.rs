@@ -0,0 +1,152 @@
+//! Occupancy grids as PNG and run-length encoded JSON
+//!
+//! External placers read `Board::occupancy_grid` either as an 8-bit grayscale PNG, one
+//! pixel per cell with row 0 at the top (free white, occupied black, outside mid grey), or
+//! as JSON. The JSON runs go through the cells row by row from the top, each run a pair of
+//! cell value (0 free, 1 occupied, 2 outside) and count, so a grid of `columns` by `rows`
+//! cells has runs adding up to `columns * rows`.
+
+use copper_substrate::board::Board;
+use copper_substrate::prelude::*;
+use serde_json::{json, Value};
+
+use crate::exporter::{BoardExporter, ExportError, ExportOptions};
+
+fn pixel(occupancy: Occupancy) -> u8 {
+    match occupancy {
+        Occupancy::Free => 255,
+        Occupancy::Occupied => 0,
+        Occupancy::Outside => 128,
+    }
+}
+
+fn code(occupancy: Occupancy) -> u8 {
+    match occupancy {
+        Occupancy::Free => 0,
+        Occupancy::Occupied => 1,
+        Occupancy::Outside => 2,
+    }
+}
+
+pub fn occupancy_to_png(grid: &OccupancyGrid) -> Result<Vec<u8>, ExportError> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, grid.columns as u32, grid.rows as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| ExportError::Other(e.to_string()))?;
+    let pixels: Vec<u8> = grid.cells().iter().map(|cell| pixel(*cell)).collect();
+    writer.write_image_data(&pixels).map_err(|e| ExportError::Other(e.to_string()))?;
+    writer.finish().map_err(|e| ExportError::Other(e.to_string()))?;
+    Ok(bytes)
+}
+
+pub fn occupancy_to_json(grid: &OccupancyGrid) -> Value {
+    let mut runs: Vec<(u8, usize)> = Vec::new();
+    for cell in grid.cells() {
+        match runs.last_mut() {
+            Some((value, count)) if *value == code(*cell) => *count += 1,
+            _ => runs.push((code(*cell), 1)),
+        }
+    }
+    json!({
+        "side": if grid.side == Side::Back { "bottom" } else { "top" },
+        "origin": [grid.origin.x, grid.origin.y],
+        "resolution": grid.resolution,
+        "columns": grid.columns,
+        "rows": grid.rows,
+        "values": { "free": 0, "occupied": 1, "outside": 2 },
+        "runs": runs.iter().map(|(value, count)| json!([value, count])).collect::<Vec<_>>(),
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OccupancyFormat {
+    Png,
+    Json,
+}
+
+/// Writes the occupancy grid of one side
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OccupancyExporter {
+    pub side: Side,
+    /// Cell size in millimeters
+    pub resolution: f32,
+    pub format: OccupancyFormat,
+}
+
+impl OccupancyExporter {
+    pub fn new(side: Side, resolution: f32) -> Self {
+        Self { side, resolution, format: OccupancyFormat::Png }
+    }
+
+    pub fn with_format(mut self, format: OccupancyFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+impl Default for OccupancyExporter {
+    fn default() -> Self {
+        Self::new(Side::Front, 0.5)
+    }
+}
+
+impl BoardExporter for OccupancyExporter {
+    fn name(&self) -> &str {
+        "occupancy"
+    }
+
+    fn file_extension(&self) -> &str {
+        match self.format {
+            OccupancyFormat::Png => "png",
+            OccupancyFormat::Json => "json",
+        }
+    }
+
+    fn export(&self, board: &Board, _options: &ExportOptions) -> Result<Vec<u8>, ExportError> {
+        let grid = board.occupancy_grid(self.resolution, self.side);
+        match self.format {
+            OccupancyFormat::Png => occupancy_to_png(&grid),
+            OccupancyFormat::Json => serde_json::to_vec_pretty(&occupancy_to_json(&grid)).map_err(|e| ExportError::Other(e.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use copper_substrate::presets;
+
+    use super::*;
+
+    fn grid() -> OccupancyGrid {
+        let mut board = Board::new("occupancy").with_rectangular_outline(20.0, 10.0);
+        board.place("R1", presets::resistor_0805("10k"), (10.03, 5.07), 30.0).unwrap();
+        board.occupancy_grid(0.25, Side::Front)
+    }
+
+    #[test]
+    fn json_runs_expand_to_the_cells() {
+        let grid = grid();
+        let json = occupancy_to_json(&grid);
+        assert_eq!((json["columns"].as_u64(), json["rows"].as_u64()), (Some(80), Some(40)));
+        let mut cells = Vec::new();
+        for run in json["runs"].as_array().unwrap() {
+            let (value, count) = (run[0].as_u64().unwrap() as u8, run[1].as_u64().unwrap() as usize);
+            cells.extend(std::iter::repeat_n(value, count));
+        }
+        assert_eq!(cells, grid.cells().iter().map(|cell| code(*cell)).collect::<Vec<_>>());
+        // Neighbouring runs never repeat a value
+        assert!(json["runs"].as_array().unwrap().windows(2).all(|pair| pair[0][0] != pair[1][0]));
+    }
+
+    #[test]
+    fn png_has_a_pixel_per_cell() {
+        let grid = grid();
+        let png = occupancy_to_png(&grid).unwrap();
+        let mut reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((info.width, info.height, info.color_type), (80, 40, png::ColorType::Grayscale));
+        assert_eq!(pixels, grid.cells().iter().map(|cell| pixel(*cell)).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,242 @@
+//! Minimal s-expression reader for KiCad files
+//!
+//! KiCad stores footprints, boards and library tables as s-expressions. This reader keeps
+//! quoted strings distinct from bare atoms so values can be written back the way they
+//! were found, and records the byte span of the children of the root list so callers can
+//! splice edited entries into the original text without disturbing its formatting. Edits
+//! deeper in the tree use `parse_with_spans`, which records the span of every node.
+
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SExpr {
+    /// Bare symbol or number, e.g. `fp_lib_table` or `0.25`
+    Atom(String),
+    /// Quoted string, stored unescaped
+    Str(String),
+    List(Vec<SExpr>),
+}
+
+impl SExpr {
+    /// Leading atom of a list, e.g. `lib` for `(lib (name "x"))`
+    pub fn head(&self) -> Option<&str> {
+        match self {
+            SExpr::List(items) => match items.first() {
+                Some(SExpr::Atom(head)) => Some(head),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Text of an atom or string
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            SExpr::Atom(s) | SExpr::Str(s) => Some(s),
+            SExpr::List(_) => None,
+        }
+    }
+
+    pub fn children(&self) -> &[SExpr] {
+        match self {
+            SExpr::List(items) => items,
+            _ => &[],
+        }
+    }
+
+    /// First child list whose head is `name`
+    pub fn find(&self, name: &str) -> Option<&SExpr> {
+        self.children().iter().find(|child| child.head() == Some(name))
+    }
+
+    /// Every child list whose head is `name`
+    pub fn find_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a SExpr> + 'a {
+        self.children().iter().filter(move |child| child.head() == Some(name))
+    }
+
+    /// First argument of the child list `name`, e.g. `"x"` for `(name "x")`
+    pub fn value_of(&self, name: &str) -> Option<&str> {
+        self.find(name)?.children().get(1)?.as_str()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SExprError {
+    pub message: String,
+    pub offset: usize,
+}
+
+impl fmt::Display for SExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for SExprError {}
+
+/// A parsed root list plus the source span of each of its children
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub root: SExpr,
+    pub child_spans: Vec<Range<usize>>,
+    /// Byte offset of the root list's closing parenthesis
+    pub root_close: usize,
+}
+
+/// Byte spans of an expression and, for lists, of each item, mirroring the `SExpr` tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpanTree {
+    pub span: Range<usize>,
+    pub items: Vec<SpanTree>,
+}
+
+/// Parse a single s-expression
+pub fn parse(input: &str) -> Result<SExpr, SExprError> {
+    parse_document(input).map(|doc| doc.root)
+}
+
+/// Parse a single s-expression along with the source span of every node in it
+pub fn parse_with_spans(input: &str) -> Result<(SExpr, SpanTree), SExprError> {
+    let mut parser = Parser { input, pos: 0 };
+    parser.skip_whitespace();
+    match parser.peek() {
+        Some('(') => parser.parse_spanned(),
+        _ => Err(parser.error("expected '('")),
+    }
+}
+
+/// Parse a single root list, recording where each of its children sits in `input`
+pub fn parse_document(input: &str) -> Result<Document, SExprError> {
+    let mut parser = Parser { input, pos: 0 };
+    parser.skip_whitespace();
+    parser.expect('(')?;
+
+    let mut items = Vec::new();
+    let mut child_spans = Vec::new();
+    loop {
+        parser.skip_whitespace();
+        match parser.peek() {
+            Some(')') => break,
+            None => return Err(parser.error("unterminated list")),
+            _ => {
+                let start = parser.pos;
+                let child = parser.parse_expr()?;
+                // The head atom is not an entry worth splicing
+                if !items.is_empty() {
+                    child_spans.push(start..parser.pos);
+                }
+                items.push(child);
+            }
+        }
+    }
+    let root_close = parser.pos;
+    parser.expect(')')?;
+    Ok(Document { root: SExpr::List(items), child_spans, root_close })
+}
+
+/// Quote and escape a string for KiCad output
+pub fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn error(&self, message: &str) -> SExprError {
+        SExprError { message: message.to_string(), offset: self.pos }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), SExprError> {
+        match self.bump() {
+            Some(c) if c == expected => Ok(()),
+            _ => Err(self.error(&format!("expected '{}'", expected))),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    fn parse_expr(&mut self) -> Result<SExpr, SExprError> {
+        self.parse_spanned().map(|(expr, _)| expr)
+    }
+
+    fn parse_spanned(&mut self) -> Result<(SExpr, SpanTree), SExprError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let expr = match self.peek() {
+            Some('(') => {
+                self.bump();
+                let mut items = Vec::new();
+                let mut spans = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(')') => {
+                            self.bump();
+                            return Ok((SExpr::List(items), SpanTree { span: start..self.pos, items: spans }));
+                        }
+                        None => return Err(self.error("unterminated list")),
+                        _ => {
+                            let (item, span) = self.parse_spanned()?;
+                            items.push(item);
+                            spans.push(span);
+                        }
+                    }
+                }
+            }
+            Some('"') => {
+                self.bump();
+                let mut value = String::new();
+                loop {
+                    match self.bump() {
+                        Some('"') => break SExpr::Str(value),
+                        Some('\\') => match self.bump() {
+                            Some('n') => value.push('\n'),
+                            Some(c) => value.push(c),
+                            None => return Err(self.error("unterminated string")),
+                        },
+                        Some(c) => value.push(c),
+                        None => return Err(self.error("unterminated string")),
+                    }
+                }
+            }
+            Some(')') => return Err(self.error("unexpected ')'")),
+            None => return Err(self.error("unexpected end of input")),
+            Some(_) => {
+                while self.peek().is_some_and(|c| !c.is_whitespace() && c != '(' && c != ')' && c != '"') {
+                    self.bump();
+                }
+                SExpr::Atom(self.input[start..self.pos].to_string())
+            }
+        };
+        Ok((expr, SpanTree { span: start..self.pos, items: Vec::new() }))
+    }
+}
@@ -0,0 +1,149 @@
+//! Silkscreen layers as Gerber X2
+//!
+//! A silkscreen Gerber holds what is printed on one side of the board:
+//!
+//! - footprint and board graphics drawn on the silkscreen, as on any other layer (see
+//!   `gerber_export`)
+//! - visible footprint texts on F.SilkS or B.SilkS, plotted with `stroke_font` as round
+//!   strokes of the text thickness, the way KiCad plots its own font
+//!
+//! Back side placements are mirrored, so their F.SilkS lands on the back and reads
+//! correctly from there. Hidden texts and texts on other layers, such as the value on the
+//! fab layer, are not printed. Coordinates are `Board::fab_point`s, from the board or the
+//! auxiliary origin.
+
+use copper_substrate::board::{Board, FabOrigin, PlacedComponent};
+use copper_substrate::prelude::*;
+
+use crate::exporter::{BoardExporter, ExportError, ExportOptions};
+use crate::gerber_export::{board_graphic_objects, graphic_objects, write_layer, FilePolarity, GerberDialect, GerberObject, Shape};
+
+/// Strokes of one placed footprint's visible silkscreen texts that land on `side`
+fn text_objects(board: &Board, placed: &PlacedComponent, side: Side, origin: FabOrigin) -> Vec<GerberObject> {
+    let to_fab = |point: Point| board.fab_point(board.board_point(placed, point), origin);
+    // Back side placements are mirrored, so their F.SilkS ends up on B.SilkS
+    let local_layer = if placed.side == side { "F.SilkS" } else { "B.SilkS" };
+    let mut objects = Vec::new();
+    for text in placed.component.fp_text_elements().iter().filter(|text| !text.hidden && text.layer == local_layer) {
+        for stroke in text.strokes() {
+            let points = stroke.into_iter().map(to_fab).collect();
+            objects.push(GerberObject::new(Shape::Stroke { points, width: text.font.thickness }));
+        }
+    }
+    objects
+}
+
+/// Gerber silkscreen layer for one side of the board, coordinates measured from `origin`
+pub fn to_silk_gerber(board: &Board, side: Side, origin: FabOrigin, dialect: GerberDialect) -> String {
+    let mut objects = Vec::new();
+    for placed in board.components() {
+        objects.extend(graphic_objects(board, placed, LayerType::SilkScreen, side, None, origin));
+        objects.extend(text_objects(board, placed, side, origin));
+    }
+    objects.extend(board_graphic_objects(board, LayerType::SilkScreen, side, None, origin));
+    let file_function = if side == Side::Front { "Legend,Top" } else { "Legend,Bot" };
+    write_layer(file_function, FilePolarity::Positive, &objects, dialect)
+}
+
+/// Writes the silkscreen layer of one side
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SilkBoardExporter {
+    pub side: Side,
+    /// Absolute board coordinates unless set to the auxiliary origin
+    pub origin: FabOrigin,
+    pub dialect: GerberDialect,
+}
+
+impl SilkBoardExporter {
+    pub fn new(side: Side) -> Self {
+        Self { side, origin: FabOrigin::Absolute, dialect: GerberDialect::default() }
+    }
+
+    pub fn with_origin(mut self, origin: FabOrigin) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Write plain RS-274X, or X2 with attributes (the default)
+    pub fn with_dialect(mut self, dialect: GerberDialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+}
+
+impl Default for SilkBoardExporter {
+    fn default() -> Self {
+        Self::new(Side::Front)
+    }
+}
+
+impl BoardExporter for SilkBoardExporter {
+    fn name(&self) -> &str {
+        "silkscreen"
+    }
+
+    fn file_extension(&self) -> &str {
+        if self.side == Side::Front { "gto" } else { "gbo" }
+    }
+
+    fn export(&self, board: &Board, _options: &ExportOptions) -> Result<Vec<u8>, ExportError> {
+        Ok(to_silk_gerber(board, self.side, self.origin, self.dialect).into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use copper_substrate::presets;
+
+    use super::*;
+
+    /// Draws (D01) and moves (D02) of a Gerber as fabrication points in mm, with whether
+    /// each was a draw
+    fn operations(gerber: &str) -> Vec<(Point, bool)> {
+        gerber
+            .lines()
+            .filter_map(|line| {
+                let rest = line.strip_prefix('X')?;
+                let (x, rest) = rest.split_once('Y')?;
+                let (y, operation) = rest.split_at(rest.len() - 4);
+                let draw = match operation {
+                    "D01*" => true,
+                    "D02*" => false,
+                    _ => return None,
+                };
+                Some((Point::new(x.parse::<f32>().ok()? / 1e6, y.parse::<f32>().ok()? / 1e6), draw))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reference_text_is_plotted_as_strokes() {
+        let mut board = Board::new("silk").with_rectangular_outline(30.0, 20.0);
+        board.place("R1", presets::resistor_0603("10k"), (10.0, 10.0), 0.0).unwrap();
+        board.place_shared("R2", Rc::new(presets::resistor_0603("10k")), (20.0, 10.0), 0.0, Side::Back).unwrap();
+
+        let front = to_silk_gerber(&board, Side::Front, FabOrigin::Absolute, GerberDialect::X2);
+        assert!(front.contains("%TF.FileFunction,Legend,Top*%"), "{}", front);
+        assert!(front.ends_with("M02*\n"), "{}", front);
+        let reference = board.components()[0].component.fp_text_elements().into_iter().find(|text| text.text_type == FpTextType::Reference).unwrap();
+        assert_eq!(reference.layer, "F.SilkS");
+        // The reference's strokes, where its stroke bounds put them on the board
+        let bounds = reference.stroke_bounds().unwrap();
+        let half = reference.font.thickness / 2.0;
+        let (min, max) = (board.fab_point(Point::new(10.0 + bounds.min_x + half, 10.0 + bounds.max_y - half), FabOrigin::Absolute), board.fab_point(Point::new(10.0 + bounds.max_x - half, 10.0 + bounds.min_y + half), FabOrigin::Absolute));
+        let inside = |point: &Point| (min.x - 1e-5..=max.x + 1e-5).contains(&point.x) && (min.y - 1e-5..=max.y + 1e-5).contains(&point.y);
+        let text_points: Vec<Point> = operations(&front).into_iter().map(|(point, _)| point).filter(inside).collect();
+        let expected: usize = reference.strokes().iter().map(Vec::len).sum();
+        assert!(text_points.len() >= expected, "{} of {} points in {:?}..{:?}\n{}", text_points.len(), expected, min, max, front);
+        let (left, right) = text_points.iter().fold((f32::MAX, f32::MIN), |(left, right), point| (left.min(point.x), right.max(point.x)));
+        assert!((right - left - (bounds.width() - 2.0 * half)).abs() < 1e-4, "{} {} {:?}", left, right, bounds);
+
+        // R2's reference is printed on the back only, mirrored
+        let back = to_silk_gerber(&board, Side::Back, FabOrigin::Absolute, GerberDialect::X2);
+        assert!(back.contains("%TF.FileFunction,Legend,Bot*%"));
+        assert!(operations(&back).iter().all(|(point, _)| point.x > 15.0), "{}", back);
+        assert!(operations(&front).iter().all(|(point, _)| point.x < 15.0), "{}", front);
+    }
+}
@@ -0,0 +1,81 @@
+//! Solder paste (stencil) layer as Gerber X2
+//!
+//! The stencil is cut from the paste layer alone, so this is the one output where paste-only
+//! footprints such as stencil fiducials and crosshair targets matter. Every pad on the
+//! requested side's paste layer is written, with or without copper: round pads are flashed
+//! with a circular aperture, all other shapes are written as regions from their outline.
+//! Lines, rectangles and circles drawn on the paste layer are stroked with a round aperture
+//! of the stroke width.
+//!
+//! Pads carry the same X2 attributes as on copper, see `gerber_export`. Coordinates are
+//! `Board::fab_point`s, from the board or the auxiliary origin.
+
+use copper_substrate::board::{Board, FabOrigin, PlacedComponent};
+use copper_substrate::prelude::*;
+
+use crate::exporter::{BoardExporter, ExportError, ExportOptions};
+use crate::gerber_export::{graphic_objects, pad_objects, write_layer, FilePolarity, GerberDialect};
+
+/// Paste layer of the footprint that lands on `side` of the board. Back side placements
+/// are mirrored, so their F.Paste ends up on B.Paste.
+fn local_paste_layer(placed: &PlacedComponent, side: Side) -> BoardLayer {
+    if placed.side == side { BoardLayer::front(LayerType::Paste) } else { BoardLayer::back(LayerType::Paste) }
+}
+
+/// Gerber paste layer for one side of the board, coordinates measured from `origin`
+pub fn to_paste_gerber(board: &Board, side: Side, arc_tolerance: f32, origin: FabOrigin, dialect: GerberDialect) -> String {
+    let mut objects = Vec::new();
+    for placed in board.components() {
+        let layer = local_paste_layer(placed, side).to_kicad_string();
+        objects.extend(pad_objects(board, placed, &layer, arc_tolerance, origin));
+        objects.extend(graphic_objects(board, placed, LayerType::Paste, side, None, origin));
+    }
+    let file_function = if side == Side::Front { "Paste,Top" } else { "Paste,Bot" };
+    write_layer(file_function, FilePolarity::Positive, &objects, dialect)
+}
+
+/// Writes the paste layer of one side
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StencilBoardExporter {
+    pub side: Side,
+    /// Absolute board coordinates unless set to the auxiliary origin
+    pub origin: FabOrigin,
+    pub dialect: GerberDialect,
+}
+
+impl StencilBoardExporter {
+    pub fn new(side: Side) -> Self {
+        Self { side, origin: FabOrigin::Absolute, dialect: GerberDialect::default() }
+    }
+
+    pub fn with_origin(mut self, origin: FabOrigin) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Write plain RS-274X, or X2 with attributes (the default)
+    pub fn with_dialect(mut self, dialect: GerberDialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+}
+
+impl Default for StencilBoardExporter {
+    fn default() -> Self {
+        Self::new(Side::Front)
+    }
+}
+
+impl BoardExporter for StencilBoardExporter {
+    fn name(&self) -> &str {
+        "stencil"
+    }
+
+    fn file_extension(&self) -> &str {
+        if self.side == Side::Front { "gtp" } else { "gbp" }
+    }
+
+    fn export(&self, board: &Board, options: &ExportOptions) -> Result<Vec<u8>, ExportError> {
+        Ok(to_paste_gerber(board, self.side, options.arc_tolerance, self.origin, self.dialect).into_bytes())
+    }
+}
@@ -0,0 +1,301 @@
+use std::fmt::Write;
+use copper_substrate::prelude::*;
+
+use crate::exporter::{ExportError, ExportOptions, FootprintExporter};
+use crate::format::{point_pair, Angle, Decimal};
+
+/// Margin around the footprint in the SVG viewBox (mm)
+const SVG_MARGIN: f32 = 0.5;
+/// Opacity of back side pads and graphics, drawn under the front as if seen through the board
+const SVG_BACK_OPACITY: f32 = 0.5;
+/// Stroke width of solder mask opening outlines (mm)
+const SVG_MASK_STROKE: f32 = 0.02;
+/// Stroke width and dash pattern of actuation envelopes (mm)
+const SVG_ENVELOPE_STROKE: f32 = 0.05;
+const SVG_ENVELOPE_DASH: &str = "0.4 0.2";
+
+/// Display colour per KiCad layer name, roughly the pcbnew defaults
+pub fn layer_color(layer: &str) -> &'static str {
+    match layer {
+        "F.Cu" => "#c83434",
+        "B.Cu" => "#4d7fc4",
+        "F.SilkS" => "#f2eda1",
+        "B.SilkS" => "#e8b2a7",
+        "F.Fab" => "#afafaf",
+        "B.Fab" => "#585d84",
+        "F.CrtYd" => "#ff26e2",
+        "B.CrtYd" => "#26e9ff",
+        "F.Mask" | "B.Mask" => "#d864ff",
+        "F.Paste" | "B.Paste" => "#b5b5b5",
+        "Dwgs.User" => "#c2c2c2",
+        "Cmts.User" => "#5994dc",
+        _ => "#808080",
+    }
+}
+
+/// Layer a pad is drawn on: its first copper layer, front preferred
+fn pad_display_layer(pad: &PadDescriptor) -> &'static str {
+    if pad.has_layer("F.Cu") {
+        "F.Cu"
+    } else if pad.has_layer("B.Cu") {
+        "B.Cu"
+    } else if pad.has_layer("F.Paste") {
+        "F.Paste"
+    } else {
+        "F.Mask"
+    }
+}
+
+/// A pad and its hole. A non-plated hole without copper around it is drawn as the hole
+/// alone; slots are drawn as their oval outline.
+pub fn write_svg_pad(output: &mut String, pad: &PadDescriptor, arc_tolerance: f32) {
+    let points = |outline: Vec<Point>| outline.iter().map(|p| point_pair(p.x, p.y)).collect::<Vec<String>>().join(" ");
+    let color = layer_color(pad_display_layer(pad));
+    if !pad.is_npth() || pad.has_copper() {
+        let outline = points(pad.outline_polygon(arc_tolerance));
+        if pad.side() == Some(PadSide::Back) {
+            writeln!(output, "  <polygon class=\"pad back\" data-pad=\"{}\" points=\"{}\" fill=\"{}\" fill-opacity=\"{}\" stroke=\"{}\" stroke-width=\"0.05\"/>",
+                     xml_escape(&pad.number), outline, color, SVG_BACK_OPACITY, color).unwrap();
+        } else {
+            writeln!(output, "  <polygon class=\"pad\" data-pad=\"{}\" points=\"{}\" fill=\"{}\"/>",
+                     xml_escape(&pad.number), outline, color).unwrap();
+        }
+    }
+    let class = if pad.is_npth() { "drill npth" } else { "drill" };
+    match (pad.drill_slot, pad.drill_outline(arc_tolerance)) {
+        (Some(_), Some(outline)) => {
+            writeln!(output, "  <polygon class=\"{}\" points=\"{}\" fill=\"#000000\"/>", class, points(outline)).unwrap();
+        }
+        _ => {
+            if let Some(drill) = pad.drill_size {
+                writeln!(output, "  <circle class=\"{}\" cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"#000000\"/>",
+                         class, Decimal(pad.position.0), Decimal(pad.position.1), Decimal(drill / 2.0)).unwrap();
+            }
+        }
+    }
+}
+
+/// Outline of a pad's solder mask opening, see `PadDescriptor::mask_aperture`; nothing for
+/// a pad on no mask layer
+pub fn write_svg_mask_opening(output: &mut String, pad: &PadDescriptor, mask_margin: f32, arc_tolerance: f32) {
+    let Some(aperture) = pad.mask_aperture(mask_margin, arc_tolerance) else {
+        return;
+    };
+    let points: Vec<String> = aperture.iter().map(|p| point_pair(p.x, p.y)).collect();
+    let layer = if pad.side() == Some(PadSide::Back) { "B.Mask" } else { "F.Mask" };
+    let opacity = if layer == "B.Mask" { format!(" stroke-opacity=\"{}\"", SVG_BACK_OPACITY) } else { String::new() };
+    writeln!(output, "  <polygon class=\"mask\" data-mask=\"{}\" points=\"{}\" stroke=\"{}\" stroke-width=\"{}\" fill=\"none\"{}/>",
+             xml_escape(&pad.number), points.join(" "), layer_color(layer), SVG_MASK_STROKE, opacity).unwrap();
+}
+
+pub fn write_svg_graphic_element(output: &mut String, element: &GraphicElement) {
+    let layer = element.kicad_layer();
+    let layer = layer.as_str();
+    let mut stroke = format!("stroke=\"{}\" stroke-width=\"{}\" stroke-linecap=\"round\" fill=\"none\"",
+                             layer_color(layer), Decimal(element.stroke.width));
+    if element.side == Side::Back {
+        stroke.push_str(&format!(" stroke-opacity=\"{}\"", SVG_BACK_OPACITY));
+    }
+    match &element.element_type {
+        GraphicType::Line { start, end } => {
+            writeln!(output, "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" {}/>", Decimal(start.0), Decimal(start.1), Decimal(end.0), Decimal(end.1), stroke).unwrap();
+        }
+        GraphicType::Rectangle { bounds } => {
+            writeln!(output, "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" {}/>",
+                     Decimal(bounds.min_x), Decimal(bounds.min_y), Decimal(bounds.width()), Decimal(bounds.height()), stroke).unwrap();
+        }
+        GraphicType::Circle { center, radius } => {
+            writeln!(output, "  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" {}/>", Decimal(center.0), Decimal(center.1), Decimal(*radius), stroke).unwrap();
+        }
+        // Mask graphics are openings, outlined like the pads' mask openings
+        GraphicType::Polygon { points, fill: Some(FillStyle::Solid) } if element.layer == LayerType::Mask => {
+            let points: Vec<String> = points.iter().map(|&(x, y)| point_pair(x, y)).collect();
+            let opacity = if element.side == Side::Back { format!(" stroke-opacity=\"{}\"", SVG_BACK_OPACITY) } else { String::new() };
+            writeln!(output, "  <polygon class=\"mask\" points=\"{}\" stroke=\"{}\" stroke-width=\"{}\" fill=\"none\"{}/>",
+                     points.join(" "), layer_color(layer), Decimal(SVG_MASK_STROKE.max(element.stroke.width)), opacity).unwrap();
+        }
+        GraphicType::Polygon { points, fill } => {
+            let points: Vec<String> = points.iter().map(|&(x, y)| point_pair(x, y)).collect();
+            let stroke = if *fill == Some(FillStyle::Solid) { stroke.replace("fill=\"none\"", &format!("fill=\"{}\"", layer_color(layer))) } else { stroke };
+            writeln!(output, "  <polygon points=\"{}\" {}/>", points.join(" "), stroke).unwrap();
+            for line in expand_hatch(element).iter().skip(1) {
+                write_svg_graphic_element(output, line);
+            }
+        }
+        GraphicType::Dimension(dimension) => write_svg_dimension(output, dimension, layer, &stroke),
+    }
+}
+
+/// An actuation envelope's outline, dashed in the colour of its layer
+pub fn write_svg_envelope(output: &mut String, envelope: &ActuationEnvelope, points: &[Point], reference: Option<&str>) {
+    let points: Vec<String> = points.iter().map(|p| point_pair(p.x, p.y)).collect();
+    let reference = reference.map(|reference| format!(" data-reference=\"{}\"", xml_escape(reference))).unwrap_or_default();
+    writeln!(output, "  <polygon class=\"envelope\" data-name=\"{}\"{} points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\" stroke-dasharray=\"{}\"/>",
+             xml_escape(&envelope.name), reference, points.join(" "), layer_color(&envelope.layer), Decimal(SVG_ENVELOPE_STROKE), SVG_ENVELOPE_DASH).unwrap();
+}
+
+/// Dimension lines, open arrowheads and the measurement text, grouped so viewers can pick
+/// the whole callout
+pub fn write_svg_dimension(output: &mut String, dimension: &Dimension, layer: &str, stroke: &str) {
+    let geometry = dimension.geometry();
+    writeln!(output, "  <g class=\"dimension\">").unwrap();
+    for (start, end) in &geometry.lines {
+        writeln!(output, "    <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" {}/>", Decimal(start.x), Decimal(start.y), Decimal(end.x), Decimal(end.y), stroke).unwrap();
+    }
+    for (tip, left, right) in &geometry.arrowheads {
+        writeln!(output, "    <polyline class=\"arrow\" points=\"{} {} {}\" {}/>",
+                 point_pair(left.x, left.y), point_pair(tip.x, tip.y), point_pair(right.x, right.y), stroke).unwrap();
+    }
+    let position = geometry.text_position;
+    writeln!(output, "    <text x=\"{}\" y=\"{}\" font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"{}\" transform=\"rotate({} {} {})\">{}</text>",
+             Decimal(position.x), Decimal(position.y), Decimal(dimension.text_height), layer_color(layer),
+             Decimal(Angle::ccw(geometry.text_angle).cw_degrees()), Decimal(position.x), Decimal(position.y),
+             xml_escape(&dimension.text())).unwrap();
+    writeln!(output, "  </g>").unwrap();
+}
+
+/// A footprint text as the strokes it is plotted with (see `stroke_font`), one path with
+/// round ends at the font thickness in its layer's colour. The text itself is kept in
+/// `data-text`.
+pub fn write_svg_text(output: &mut String, fp_text: &FpText) {
+    write_svg_text_as(output, fp_text, "text", layer_color(&fp_text.layer));
+}
+
+/// `write_svg_text` with another class and colour
+pub fn write_svg_text_as(output: &mut String, fp_text: &FpText, class: &str, color: &str) {
+    let mut path = Vec::new();
+    for stroke in fp_text.strokes() {
+        for (index, point) in stroke.iter().enumerate() {
+            path.push(format!("{}{} {}", if index == 0 { "M" } else { "L" }, Decimal(point.x), Decimal(point.y)));
+        }
+    }
+    writeln!(output, "  <path class=\"{}\" data-text=\"{}\" d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\" stroke-linecap=\"round\" stroke-linejoin=\"round\"/>",
+             class, xml_escape(&fp_text.text), path.join(" "), color, Decimal(fp_text.font.thickness)).unwrap();
+}
+
+pub fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Render a footprint to SVG in millimeter user units. SVG and KiCad are both Y down, but
+/// SVG rotates clockwise, see `format`.
+/// Back side pads and graphics come first, so the front is drawn over them. Each pad's
+/// solder mask opening is outlined over it, at the pad's own margin or none. An actuation
+/// envelope is drawn dashed.
+pub fn to_svg<T: BoardComposableObject + ?Sized>(component: &T, arc_tolerance: f32) -> String {
+    to_svg_with_mask_margin(component, arc_tolerance, 0.0)
+}
+
+/// `to_svg` with the solder mask openings of pads without a margin of their own grown by
+/// `mask_margin`
+pub fn to_svg_with_mask_margin<T: BoardComposableObject + ?Sized>(component: &T, arc_tolerance: f32, mask_margin: f32) -> String {
+    let courtyards = component.generate_courtyards();
+    let mut bounds = courtyards[0].bounds.clone();
+    for courtyard in &courtyards[1..] {
+        bounds.min_x = bounds.min_x.min(courtyard.bounds.min_x);
+        bounds.min_y = bounds.min_y.min(courtyard.bounds.min_y);
+        bounds.max_x = bounds.max_x.max(courtyard.bounds.max_x);
+        bounds.max_y = bounds.max_y.max(courtyard.bounds.max_y);
+    }
+    let envelope = component.actuation_envelope();
+    for point in envelope.iter().flat_map(ActuationEnvelope::points) {
+        bounds.min_x = bounds.min_x.min(point.x);
+        bounds.min_y = bounds.min_y.min(point.y);
+        bounds.max_x = bounds.max_x.max(point.x);
+        bounds.max_y = bounds.max_y.max(point.y);
+    }
+    for pad in component.pad_descriptors() {
+        let pad_bounds = pad.outline_aabb();
+        bounds.min_x = bounds.min_x.min(pad_bounds.min_x);
+        bounds.min_y = bounds.min_y.min(pad_bounds.min_y);
+        bounds.max_x = bounds.max_x.max(pad_bounds.max_x);
+        bounds.max_y = bounds.max_y.max(pad_bounds.max_y);
+    }
+
+    let mut output = String::new();
+    writeln!(output, "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" width=\"{}mm\" height=\"{}mm\">",
+             Decimal(bounds.min_x - SVG_MARGIN), Decimal(bounds.min_y - SVG_MARGIN),
+             Decimal(bounds.width() + 2.0 * SVG_MARGIN), Decimal(bounds.height() + 2.0 * SVG_MARGIN),
+             Decimal(bounds.width() + 2.0 * SVG_MARGIN), Decimal(bounds.height() + 2.0 * SVG_MARGIN)).unwrap();
+    writeln!(output, "  <title>{}</title>", xml_escape(&component.footprint_name())).unwrap();
+
+    let mut all_graphics = component.graphic_elements();
+    for courtyard in &courtyards {
+        all_graphics.extend(courtyard.to_graphic_elements());
+    }
+    let (back_graphics, front_graphics): (Vec<_>, Vec<_>) = all_graphics.iter().partition(|element| element.side == Side::Back);
+    let (back_pads, front_pads): (Vec<_>, Vec<_>) =
+        component.pad_descriptors().into_iter().partition(|pad| pad.side() == Some(PadSide::Back));
+    for element in back_graphics {
+        write_svg_graphic_element(&mut output, element);
+    }
+    for pad in back_pads.iter().chain(&front_pads) {
+        write_svg_pad(&mut output, pad, arc_tolerance);
+        write_svg_mask_opening(&mut output, pad, mask_margin, arc_tolerance);
+    }
+    for element in front_graphics {
+        write_svg_graphic_element(&mut output, element);
+    }
+    if let Some(envelope) = &envelope {
+        write_svg_envelope(&mut output, envelope, &envelope.points(), None);
+    }
+    for fp_text in component.fp_text_elements().into_iter().filter(|text| !text.hidden) {
+        write_svg_text(&mut output, &fp_text);
+    }
+
+    writeln!(output, "</svg>").unwrap();
+    output
+}
+
+pub struct SvgFootprintExporter;
+
+impl FootprintExporter for SvgFootprintExporter {
+    fn name(&self) -> &str {
+        "svg"
+    }
+
+    fn file_extension(&self) -> &str {
+        "svg"
+    }
+
+    fn export(&self, component: &dyn BoardComposableObject, options: &ExportOptions) -> Result<Vec<u8>, ExportError> {
+        Ok(to_svg_with_mask_margin(component, options.arc_tolerance, options.mask_margin).into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Width of pad 1's outlined mask opening
+    fn mask_width(svg: &str) -> f32 {
+        let line = svg.lines().find(|line| line.contains("data-mask=\"1\"")).unwrap();
+        let points = line.split("points=\"").nth(1).unwrap().split('"').next().unwrap();
+        let xs: Vec<f32> = points.split(' ').map(|pair| pair.split(',').next().unwrap().parse().unwrap()).collect();
+        xs.iter().copied().fold(f32::MIN, f32::max) - xs.iter().copied().fold(f32::MAX, f32::min)
+    }
+
+    #[test]
+    fn mask_openings_are_outlined_at_the_margin() {
+        let chip = ChipFootprint::standard(FunctionalType::Resistor("10k".to_string()), "0805").unwrap();
+        let copper = chip.pad_descriptors()[0].size.0;
+        for margin in [0.05, 0.0, -0.05] {
+            let width = mask_width(&to_svg_with_mask_margin(&chip, 0.001, margin));
+            assert!((width - (copper + 2.0 * margin)).abs() < 1e-3, "margin {}: {}", margin, width);
+        }
+        assert_eq!(to_svg(&chip, 0.01).matches("class=\"mask\"").count(), 2);
+    }
+
+    #[test]
+    fn mask_graphics_are_outlined_as_openings() {
+        let chip = ChipFootprint::standard(FunctionalType::Resistor("10k".to_string()), "0805").unwrap();
+        let logo = ExposedCopper::rounded_rectangle(Rectangle { min_x: -0.5, min_y: 1.5, max_x: 0.5, max_y: 2.0 }, 0.1);
+        let footprint = Footprint { graphics: logo.graphic_elements(), ..Footprint::capture(&chip) };
+        let svg = to_svg(&footprint, 0.01);
+        let openings: Vec<&str> = svg.lines().filter(|line| line.contains("class=\"mask\"") && !line.contains("data-mask")).collect();
+        assert_eq!(openings.len(), 1, "{}", svg);
+        assert!(openings[0].contains("fill=\"none\""));
+        // The copper under it is drawn filled
+        assert!(svg.contains(&format!("fill=\"{}\"", layer_color("F.Cu"))));
+    }
+}
@@ -0,0 +1,365 @@
+//! Golden SVG visual regression
+//!
+//! Geometry changes are easy to merge and hard to review as numbers. `GoldenSet` renders
+//! footprints with `to_svg` and compares each rendering with an SVG checked in under a
+//! golden directory, element by element. `to_svg` writes one element per line, so
+//! elements are compared line by line. The tag and the text between the numbers must be
+//! the same, and every number must lie within the tolerance of the golden's. Colors and
+//! names are text, so a digit inside a word such as `#c83434` or `SOIC-8` is not a
+//! number.
+//!
+//! When a check fails, two files are written to the artifact directory for review:
+//! - the rendering, as `<name>.svg`;
+//! - `<name>.diff.svg`, with the golden on the left and the rendering on the right. The
+//!   elements that differ are drawn over in magenta on both sides.
+//!
+//! The artifact directory is `target/svg-goldens` by default.
+//!
+//! With `COPPER_BLESS_GOLDENS=1` set, renderings are written as the new goldens instead of
+//! being compared:
+//! `COPPER_BLESS_GOLDENS=1 cargo test -p copper-exporters --test svg_goldens`.
+
+use std::fmt;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use copper_substrate::prelude::*;
+
+use crate::svg_export::{to_svg, xml_escape};
+
+/// Environment variable that turns checks into writing the goldens
+pub const BLESS_ENV: &str = "COPPER_BLESS_GOLDENS";
+/// Arc tolerance goldens are rendered with (mm)
+pub const GOLDEN_ARC_TOLERANCE: f32 = 0.01;
+/// Default tolerance on each number of an element (mm for coordinates)
+pub const GOLDEN_TOLERANCE: f64 = 1e-3;
+/// Gap between the two sides of a diff image, and the height of their captions (mm)
+const DIFF_GAP: f64 = 2.0;
+
+/// How a rendering differs from its golden, by 1-based line
+#[derive(Debug, Clone, PartialEq)]
+pub enum GoldenDifference {
+    /// The element differs beyond its numbers: another tag, class, layer or text
+    Element { line: usize, expected: String, actual: String },
+    /// A number of the element lies further than the tolerance from the golden's
+    Number { line: usize, expected: f64, actual: f64 },
+    /// The rendering has a different number of lines; those past the shorter one aren't
+    /// compared
+    LineCount { expected: usize, actual: usize },
+}
+
+impl GoldenDifference {
+    /// The line the difference is on, `None` for a line count
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            GoldenDifference::Element { line, .. } | GoldenDifference::Number { line, .. } => Some(*line),
+            GoldenDifference::LineCount { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for GoldenDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GoldenDifference::Element { line, expected, actual } => {
+                write!(f, "line {}: expected\n    {}\n  found\n    {}", line, expected.trim(), actual.trim())
+            }
+            GoldenDifference::Number { line, expected, actual } => {
+                write!(f, "line {}: {} instead of {}, off by {:.4}", line, actual, expected, (actual - expected).abs())
+            }
+            GoldenDifference::LineCount { expected, actual } => write!(f, "{} lines instead of {}", actual, expected),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum GoldenError {
+    Io(io::Error),
+    /// No golden of this name; bless to create it
+    Missing { name: String, path: PathBuf },
+    /// The rendering differs from the golden, reviewable in the `diff` image
+    Mismatch { name: String, differences: Vec<GoldenDifference>, diff: PathBuf },
+}
+
+impl fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GoldenError::Io(error) => write!(f, "{}", error),
+            GoldenError::Missing { name, path } => {
+                write!(f, "{}: no golden at {}, set {}=1 to write it", name, path.display(), BLESS_ENV)
+            }
+            GoldenError::Mismatch { name, differences, diff } => {
+                writeln!(f, "{}: {} differences from the golden, see {}", name, differences.len(), diff.display())?;
+                for difference in differences.iter().take(10) {
+                    writeln!(f, "  {}", difference)?;
+                }
+                if differences.len() > 10 {
+                    writeln!(f, "  ...")?;
+                }
+                write!(f, "set {}=1 to accept the rendering", BLESS_ENV)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GoldenError {}
+
+impl From<io::Error> for GoldenError {
+    fn from(error: io::Error) -> Self {
+        GoldenError::Io(error)
+    }
+}
+
+/// A line with its numbers taken out: the text around them with `{}` in their place, and
+/// the numbers in order
+fn split_numbers(line: &str) -> (String, Vec<f64>) {
+    let bytes = line.as_bytes();
+    let (mut text, mut numbers) = (String::new(), Vec::new());
+    let mut i = 0;
+    let word = |byte: u8| byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b'.' | b'-');
+    while i < bytes.len() {
+        let in_word = i > 0 && (bytes[i - 1].is_ascii_alphanumeric() || matches!(bytes[i - 1], b'#' | b'_' | b'.'));
+        let starts_number = bytes[i].is_ascii_digit()
+            || (matches!(bytes[i], b'-' | b'.') && bytes.get(i + 1).is_some_and(u8::is_ascii_digit));
+        if !starts_number || in_word {
+            // Whole words stay text, digits and all
+            let start = i;
+            i += 1;
+            if word(bytes[start]) {
+                while i < bytes.len() && word(bytes[i]) {
+                    i += 1;
+                }
+            }
+            text.push_str(&line[start..i]);
+            continue;
+        }
+        let start = i;
+        i += 1;
+        while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'.') {
+            i += 1;
+        }
+        if i < bytes.len() && matches!(bytes[i], b'e' | b'E') {
+            let exponent = if bytes.get(i + 1) == Some(&b'-') { i + 2 } else { i + 1 };
+            if bytes.get(exponent).is_some_and(u8::is_ascii_digit) {
+                i = exponent;
+                while i < bytes.len() && bytes[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+        }
+        match line[start..i].parse() {
+            Ok(number) => {
+                text.push_str("{}");
+                numbers.push(number);
+            }
+            Err(_) => text.push_str(&line[start..i]),
+        }
+    }
+    (text, numbers)
+}
+
+/// Differences of `actual` from `expected`, see the module documentation
+pub fn compare_svg(expected: &str, actual: &str, tolerance: f64) -> Vec<GoldenDifference> {
+    let (expected_lines, actual_lines): (Vec<&str>, Vec<&str>) = (expected.lines().collect(), actual.lines().collect());
+    let mut differences = Vec::new();
+    for (index, (want, got)) in expected_lines.iter().zip(&actual_lines).enumerate() {
+        let line = index + 1;
+        let ((want_text, want_numbers), (got_text, got_numbers)) = (split_numbers(want), split_numbers(got));
+        if want_text != got_text || want_numbers.len() != got_numbers.len() {
+            differences.push(GoldenDifference::Element { line, expected: want.to_string(), actual: got.to_string() });
+            continue;
+        }
+        // One number per element is enough to point at it
+        if let Some((&expected, &actual)) = want_numbers.iter().zip(&got_numbers).find(|(a, b)| (*a - *b).abs() > tolerance) {
+            differences.push(GoldenDifference::Number { line, expected, actual });
+        }
+    }
+    if expected_lines.len() != actual_lines.len() {
+        differences.push(GoldenDifference::LineCount { expected: expected_lines.len(), actual: actual_lines.len() });
+    }
+    differences
+}
+
+/// The viewBox of an SVG's root element as (x, y, width, height)
+fn view_box(svg: &str) -> Option<[f64; 4]> {
+    let root = svg.lines().find(|line| line.trim_start().starts_with("<svg"))?;
+    let values: Vec<f64> = root.split("viewBox=\"").nth(1)?.split('"').next()?.split_whitespace().map(str::parse).collect::<Result<_, _>>().ok()?;
+    values.try_into().ok()
+}
+
+/// One side of a diff image: the elements of `svg` nested at `x`, those on a line in
+/// `changed` drawn again in the highlight style
+fn write_side(output: &mut String, svg: &str, caption: &str, x: f64, changed: &[usize]) {
+    let [min_x, min_y, width, height] = view_box(svg).unwrap_or([0.0, 0.0, 1.0, 1.0]);
+    writeln!(output, "  <text x=\"{}\" y=\"{}\" font-size=\"{}\" fill=\"#ffffff\">{}</text>", x, DIFF_GAP * 0.75, DIFF_GAP * 0.6, xml_escape(caption)).unwrap();
+    writeln!(output, "  <svg x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" viewBox=\"{} {} {} {}\">", x, DIFF_GAP, width, height, min_x, min_y, width, height).unwrap();
+    let elements = svg.lines().enumerate().filter(|(_, line)| {
+        let line = line.trim_start();
+        !line.starts_with("<svg") && !line.starts_with("</svg")
+    });
+    for (index, line) in elements {
+        writeln!(output, "  {}", line).unwrap();
+        if changed.contains(&(index + 1)) {
+            writeln!(output, "  <g class=\"changed\">{}</g>", line.trim()).unwrap();
+        }
+    }
+    writeln!(output, "  </svg>").unwrap();
+}
+
+/// The golden and the rendering side by side, the elements in `differences` highlighted
+pub fn side_by_side(expected: &str, actual: &str, differences: &[GoldenDifference]) -> String {
+    let changed: Vec<usize> = differences.iter().filter_map(GoldenDifference::line).collect();
+    let [_, _, expected_width, expected_height] = view_box(expected).unwrap_or([0.0, 0.0, 1.0, 1.0]);
+    let [_, _, actual_width, actual_height] = view_box(actual).unwrap_or([0.0, 0.0, 1.0, 1.0]);
+    let (width, height) = (expected_width + DIFF_GAP + actual_width, DIFF_GAP + expected_height.max(actual_height));
+
+    let mut output = String::new();
+    writeln!(output, "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {} {}\" width=\"{}mm\" height=\"{}mm\">", width, height, width, height).unwrap();
+    // Style rules win over the elements' presentation attributes
+    writeln!(output, "  <style>.changed > * {{ stroke: #ff00ff; stroke-width: 0.1; fill: #ff00ff; fill-opacity: 0.4; }}</style>").unwrap();
+    writeln!(output, "  <rect x=\"0\" y=\"0\" width=\"{}\" height=\"{}\" fill=\"#001023\"/>", width, height).unwrap();
+    write_side(&mut output, expected, "golden", 0.0, &changed);
+    write_side(&mut output, actual, "rendering", expected_width + DIFF_GAP, &changed);
+    writeln!(output, "</svg>").unwrap();
+    output
+}
+
+/// Renderings checked against the goldens in one directory, see the module documentation
+#[derive(Debug, Clone)]
+pub struct GoldenSet {
+    dir: PathBuf,
+    artifacts: PathBuf,
+    tolerance: f64,
+    bless: bool,
+}
+
+impl GoldenSet {
+    /// Goldens in `dir`, blessing when `BLESS_ENV` is set to anything but "0"
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        let bless = std::env::var(BLESS_ENV).is_ok_and(|value| !value.is_empty() && value != "0");
+        let target = std::env::var_os("CARGO_TARGET_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("../../target"));
+        Self { dir: dir.into(), artifacts: target.join("svg-goldens"), tolerance: GOLDEN_TOLERANCE, bless }
+    }
+
+    /// Where renderings and diff images of failed checks go
+    pub fn with_artifact_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.artifacts = dir.into();
+        self
+    }
+
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn with_bless(mut self, bless: bool) -> Self {
+        self.bless = bless;
+        self
+    }
+
+    pub fn golden_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.svg", name))
+    }
+
+    /// Check `component` rendered by `to_svg` against the golden `name`
+    pub fn check<T: BoardComposableObject + ?Sized>(&self, name: &str, component: &T) -> Result<(), GoldenError> {
+        self.check_svg(name, &to_svg(component, GOLDEN_ARC_TOLERANCE))
+    }
+
+    /// Check an SVG against the golden `name`, or write it as the golden when blessing
+    pub fn check_svg(&self, name: &str, svg: &str) -> Result<(), GoldenError> {
+        let path = self.golden_path(name);
+        if self.bless {
+            fs::create_dir_all(&self.dir)?;
+            if fs::read_to_string(&path).ok().as_deref() != Some(svg) {
+                fs::write(&path, svg)?;
+            }
+            return Ok(());
+        }
+        let expected = match fs::read_to_string(&path) {
+            Ok(expected) => expected,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                self.write_artifact(&format!("{}.svg", name), svg)?;
+                return Err(GoldenError::Missing { name: name.to_string(), path });
+            }
+            Err(error) => return Err(error.into()),
+        };
+        let differences = compare_svg(&expected, svg, self.tolerance);
+        if differences.is_empty() {
+            return Ok(());
+        }
+        self.write_artifact(&format!("{}.svg", name), svg)?;
+        let diff = self.write_artifact(&format!("{}.diff.svg", name), &side_by_side(&expected, svg, &differences))?;
+        Err(GoldenError::Mismatch { name: name.to_string(), differences, diff })
+    }
+
+    fn write_artifact(&self, file: &str, text: &str) -> io::Result<PathBuf> {
+        fs::create_dir_all(&self.artifacts)?;
+        let path = self.artifacts.join(file);
+        fs::write(&path, text)?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("copper_svg_golden_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn chip() -> ChipFootprint {
+        ChipFootprint::standard(FunctionalType::Resistor("10k".to_string()), "0603").unwrap()
+    }
+
+    #[test]
+    fn words_keep_their_digits() {
+        let (text, numbers) = split_numbers("  <polygon class=\"pad\" data-pad=\"A1\" points=\"-0.8,1e-3 .5,2\" fill=\"#c83434\"/>");
+        assert_eq!(text, "  <polygon class=\"pad\" data-pad=\"A1\" points=\"{},{} {},{}\" fill=\"#c83434\"/>");
+        assert_eq!(numbers, vec![-0.8, 1e-3, 0.5, 2.0]);
+        assert_eq!(split_numbers("<title>SOIC-8_3.9x4.9mm</title>").1, Vec::<f64>::new());
+    }
+
+    #[test]
+    fn numbers_within_the_tolerance_compare_equal() {
+        let golden = to_svg(&chip(), GOLDEN_ARC_TOLERANCE);
+        let nudged = golden.replacen("stroke-width=\"0.05\"", "stroke-width=\"0.0504\"", 1);
+        assert!(compare_svg(&golden, &golden, GOLDEN_TOLERANCE).is_empty());
+        assert!(compare_svg(&golden, &nudged, GOLDEN_TOLERANCE).is_empty());
+        assert!(matches!(compare_svg(&golden, &nudged, 1e-4)[..], [GoldenDifference::Number { .. }]));
+
+        let recolored = golden.replacen("#c83434", "#4d7fc4", 1);
+        assert!(matches!(compare_svg(&golden, &recolored, GOLDEN_TOLERANCE)[..], [GoldenDifference::Element { .. }]));
+        let shorter: String = golden.lines().skip(1).map(|line| format!("{}\n", line)).collect();
+        assert!(compare_svg(&golden, &shorter, GOLDEN_TOLERANCE).contains(&GoldenDifference::LineCount {
+            expected: golden.lines().count(),
+            actual: golden.lines().count() - 1,
+        }));
+    }
+
+    #[test]
+    fn bless_writes_goldens_and_mismatches_leave_a_diff() {
+        let dir = scratch("bless");
+        let goldens = GoldenSet::new(dir.join("goldens")).with_artifact_dir(dir.join("artifacts")).with_bless(false);
+        assert!(matches!(goldens.check("chip", &chip()), Err(GoldenError::Missing { .. })));
+        goldens.clone().with_bless(true).check("chip", &chip()).unwrap();
+        goldens.check("chip", &chip()).unwrap();
+
+        let wider = ChipFootprint::standard(FunctionalType::Resistor("10k".to_string()), "0805").unwrap();
+        let Err(GoldenError::Mismatch { differences, diff, .. }) = goldens.check("chip", &wider) else {
+            panic!("a wider chip matched the golden");
+        };
+        assert!(!differences.is_empty());
+        let image = fs::read_to_string(&diff).unwrap();
+        assert!(image.contains("class=\"changed\"") && image.contains(">golden<") && image.contains(">rendering<"), "{}", image);
+        assert_eq!(fs::read_to_string(dir.join("artifacts/chip.svg")).unwrap(), to_svg(&wider, GOLDEN_ARC_TOLERANCE));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
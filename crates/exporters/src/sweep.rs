@@ -0,0 +1,555 @@
+//! Parameter sweeps over footprint definitions
+//!
+//! A sweep file is a footprint definition (see `footprint_definition`) with a `[sweep]`
+//! table naming one of its parameters and the values to try. Each value gives one
+//! footprint of a family, and the metrics picked for the sweep are recorded next to it,
+//! so a generator parameter can be tuned by comparing a table instead of opening each
+//! footprint:
+//!
+//! ```toml
+//! generator = "qfn"
+//! outline = "MO-220-4x4-24"
+//! pins = 24
+//!
+//! [sweep]
+//! parameter = "pad_length"
+//! range = { from = 0.7, to = 1.0, step = 0.05 }
+//! name = "QFN-24-1EP_4x4mm_P0.5mm_L{pad_length}"
+//! metrics = ["min_gap", "courtyard_area", "paste_coverage", "klc"]
+//! ```
+//!
+//! The parameter is a dotted path into the definition, e.g. "fillets.toe", and is swept
+//! over an inclusive `range` or an explicit list of `values`; whole values of a parameter
+//! the definition gives as an integer stay integers. The name template replaces
+//! `{<parameter>}` with the value, `{name}` with the generator's own footprint name and
+//! `{index}` with the variant's position; without one, variants are named
+//! "<name>_<parameter><value>". Metrics are the smallest copper gap between pads, the
+//! courtyard area, the paste aperture area over the copper pad area and the pass or
+//! violation count of the footprint DRC (`FootprintDrc` with the default fab profile),
+//! and default to all four. `SweepResult::write_to` writes the family as a `.pretty`
+//! library and the metrics as CSV beside it.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use copper_substrate::analysis::{FootprintDrc, SpacingOptions, SpacingReport};
+use copper_substrate::geometry::polygon_area;
+use copper_substrate::prelude::*;
+use serde::Deserialize;
+
+use crate::footprint_definition::{definition_from_table, DefinitionError};
+use crate::footprint_library::FootprintLibrary;
+
+/// Most variants one sweep may produce
+pub const MAX_VARIANTS: usize = 1000;
+/// Library the variants are written to when the sweep names none
+pub const DEFAULT_SWEEP_LIBRARY: &str = "Sweep";
+/// Arc tolerance of the pad outlines whose areas are compared (1 micron)
+const AREA_ARC_TOLERANCE: f32 = 0.001;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SweepError {
+    Io(String),
+    Parse(String),
+    /// The file has no `[sweep]` table
+    MissingSweep,
+    /// The range is empty, steps backwards or yields too many variants, or values and a
+    /// range are both given or both missing
+    InvalidRange(String),
+    /// The parameter path runs through something that isn't a table
+    InvalidParameter(String),
+    /// Two variants get the same name from the template
+    DuplicateName(String),
+    /// The definition of one variant is invalid
+    Definition { value: f64, error: DefinitionError },
+}
+
+impl fmt::Display for SweepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SweepError::Io(message) => write!(f, "cannot read sweep: {}", message),
+            SweepError::Parse(message) => write!(f, "invalid sweep: {}", message),
+            SweepError::MissingSweep => write!(f, "the definition has no [sweep] table"),
+            SweepError::InvalidRange(message) => write!(f, "invalid sweep range: {}", message),
+            SweepError::InvalidParameter(path) => write!(f, "\"{}\" is not a parameter path of the definition", path),
+            SweepError::DuplicateName(name) => write!(f, "more than one variant is named \"{}\"", name),
+            SweepError::Definition { value, error } => write!(f, "at {}: {}", format_value(*value), error),
+        }
+    }
+}
+
+impl std::error::Error for SweepError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SweepError::Definition { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// What is recorded for each variant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SweepMetric {
+    /// Smallest copper gap between pads of different numbers (mm)
+    MinGap,
+    /// Area of the front courtyard (mm²)
+    CourtyardArea,
+    /// Paste aperture area over the copper area of the front pads
+    PasteCoverage,
+    /// Footprint DRC status, "pass" or the number of violations
+    Klc,
+}
+
+impl SweepMetric {
+    pub const ALL: [SweepMetric; 4] = [SweepMetric::MinGap, SweepMetric::CourtyardArea, SweepMetric::PasteCoverage, SweepMetric::Klc];
+
+    /// CSV column name
+    pub fn column(&self) -> &'static str {
+        match self {
+            SweepMetric::MinGap => "min_gap",
+            SweepMetric::CourtyardArea => "courtyard_area",
+            SweepMetric::PasteCoverage => "paste_coverage",
+            SweepMetric::Klc => "klc",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RangeSection {
+    from: f64,
+    to: f64,
+    step: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SweepSection {
+    parameter: String,
+    range: Option<RangeSection>,
+    values: Option<Vec<f64>>,
+    name: Option<String>,
+    metrics: Option<Vec<SweepMetric>>,
+    library: Option<String>,
+}
+
+/// A definition and the parameter values to build it with, see the module documentation
+#[derive(Debug, Clone)]
+pub struct Sweep {
+    /// The definition without its `[sweep]` table
+    definition: toml::Table,
+    pub parameter: String,
+    pub values: Vec<f64>,
+    pub name_template: String,
+    pub metrics: Vec<SweepMetric>,
+    pub library: String,
+}
+
+impl Sweep {
+    pub fn parse(text: &str) -> Result<Self, SweepError> {
+        let mut definition: toml::Table = toml::from_str(text).map_err(|e| parse_error(&e))?;
+        let section = definition.remove("sweep").ok_or(SweepError::MissingSweep)?;
+        let section: SweepSection = section.try_into().map_err(|e| parse_error(&e))?;
+        let values = match (section.range, section.values) {
+            (Some(range), None) => range_values(&range)?,
+            (None, Some(values)) if values.is_empty() => return Err(SweepError::InvalidRange("no values".to_string())),
+            (None, Some(values)) if values.len() > MAX_VARIANTS => {
+                return Err(SweepError::InvalidRange(format!("{} values, at most {} allowed", values.len(), MAX_VARIANTS)));
+            }
+            (None, Some(values)) => values,
+            _ => return Err(SweepError::InvalidRange("give either range or values".to_string())),
+        };
+        let parameter = section.parameter;
+        Ok(Self {
+            definition,
+            name_template: section.name.unwrap_or_else(|| format!("{{name}}_{}{{{}}}", parameter, parameter)),
+            parameter,
+            values,
+            metrics: section.metrics.unwrap_or_else(|| SweepMetric::ALL.to_vec()),
+            library: section.library.unwrap_or_else(|| DEFAULT_SWEEP_LIBRARY.to_string()),
+        })
+    }
+
+    /// Read and parse the sweep file at `path`
+    pub fn read(path: &Path) -> Result<Self, SweepError> {
+        let text = fs::read_to_string(path).map_err(|e| SweepError::Io(e.to_string()))?;
+        Self::parse(&text)
+    }
+
+    /// The definition of the variant at `value`
+    fn variant_definition(&self, value: f64) -> Result<toml::Table, SweepError> {
+        let mut definition = self.definition.clone();
+        let mut table = &mut definition;
+        let mut keys = self.parameter.split('.').peekable();
+        while let Some(key) = keys.next() {
+            if key.is_empty() {
+                return Err(SweepError::InvalidParameter(self.parameter.clone()));
+            }
+            if keys.peek().is_none() {
+                // Whole numbers stay integers where the definition has one, e.g. pins
+                let value = match table.get(key) {
+                    Some(toml::Value::Integer(_)) if value.fract() == 0.0 => toml::Value::Integer(value as i64),
+                    _ => toml::Value::Float(value),
+                };
+                table.insert(key.to_string(), value);
+                break;
+            }
+            let entry = table.entry(key).or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            table = entry.as_table_mut().ok_or_else(|| SweepError::InvalidParameter(self.parameter.clone()))?;
+        }
+        Ok(definition)
+    }
+
+    /// Build every variant and record its metrics
+    pub fn run(&self) -> Result<SweepResult, SweepError> {
+        let mut library = FootprintLibrary::new(&self.library);
+        let mut rows: Vec<SweepRow> = Vec::with_capacity(self.values.len());
+        for (index, &value) in self.values.iter().enumerate() {
+            let footprint = definition_from_table(self.variant_definition(value)?)
+                .map_err(|error| SweepError::Definition { value, error })?;
+            let name = self
+                .name_template
+                .replace(&format!("{{{}}}", self.parameter), &format_value(value))
+                .replace("{name}", &footprint.footprint_name())
+                .replace("{index}", &index.to_string());
+            if rows.iter().any(|row| row.name == name) {
+                return Err(SweepError::DuplicateName(name));
+            }
+            let variant = Variant { footprint, name: name.clone() };
+            rows.push(SweepRow { name, value, metrics: VariantMetrics::of(&variant) });
+            library.add(variant);
+        }
+        Ok(SweepResult { parameter: self.parameter.clone(), metrics: self.metrics.clone(), rows, library })
+    }
+}
+
+fn parse_error(error: &toml::de::Error) -> SweepError {
+    SweepError::Parse(error.to_string().trim_end().to_string())
+}
+
+/// Values `from`, `from + step`, ... up to `to` inclusive, within a thousandth of a step
+fn range_values(range: &RangeSection) -> Result<Vec<f64>, SweepError> {
+    let RangeSection { from, to, step } = *range;
+    if step.is_nan() || step <= 0.0 || !from.is_finite() || !to.is_finite() {
+        return Err(SweepError::InvalidRange(format!("step {} from {} to {}", step, from, to)));
+    }
+    if to < from {
+        return Err(SweepError::InvalidRange(format!("{} is below {}", to, from)));
+    }
+    let steps = ((to - from) / step + 1e-3).floor();
+    if steps >= MAX_VARIANTS as f64 {
+        return Err(SweepError::InvalidRange(format!("{} values, at most {} allowed", steps + 1.0, MAX_VARIANTS)));
+    }
+    // Rounded to a micron so 0.7 + 3 * 0.05 is 0.85 and not 0.8500000000000001
+    Ok((0..=steps as usize).map(|index| ((from + index as f64 * step) * 1e6).round() / 1e6).collect())
+}
+
+/// The shortest decimal that reads back as `value`
+fn format_value(value: f64) -> String {
+    format!("{}", value)
+}
+
+/// Metrics of one variant, all computed whichever the sweep records
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariantMetrics {
+    pub min_gap: Option<f32>,
+    pub courtyard_area: f32,
+    /// `None` when no front pad has copper
+    pub paste_coverage: Option<f32>,
+    pub klc_violations: usize,
+}
+
+impl VariantMetrics {
+    pub fn of<T: BoardComposableObject + ?Sized>(footprint: &T) -> Self {
+        let pads = footprint.pad_descriptors();
+        let area = |layer: &str, copper: bool| -> f32 {
+            pads.iter()
+                .filter(|pad| pad.has_layer(layer) && (!copper || pad.has_copper()))
+                .map(|pad| polygon_area(&pad.outline_polygon(AREA_ARC_TOLERANCE)))
+                .sum()
+        };
+        let copper = area("F.Cu", true);
+        let courtyard = footprint.generate_courtyard().bounds;
+        Self {
+            min_gap: SpacingReport::for_pads(&pads, SpacingOptions::default()).copper.map(|gap| gap.gap),
+            courtyard_area: courtyard.width() * courtyard.height(),
+            paste_coverage: (copper > 0.0).then(|| area("F.Paste", false) / copper),
+            klc_violations: FootprintDrc::default().check(footprint).len(),
+        }
+    }
+
+    /// The CSV cell of `metric`, empty where there is nothing to measure
+    pub fn cell(&self, metric: SweepMetric) -> String {
+        match metric {
+            SweepMetric::MinGap => self.min_gap.map(|gap| format!("{:.4}", gap)).unwrap_or_default(),
+            SweepMetric::CourtyardArea => format!("{:.4}", self.courtyard_area),
+            SweepMetric::PasteCoverage => self.paste_coverage.map(|coverage| format!("{:.4}", coverage)).unwrap_or_default(),
+            SweepMetric::Klc if self.klc_violations == 0 => "pass".to_string(),
+            SweepMetric::Klc => format!("{} violations", self.klc_violations),
+        }
+    }
+}
+
+/// One variant of a sweep
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepRow {
+    pub name: String,
+    pub value: f64,
+    pub metrics: VariantMetrics,
+}
+
+/// The footprint family of a sweep and the metrics of each variant
+pub struct SweepResult {
+    pub parameter: String,
+    /// Metrics written to the CSV, in column order
+    pub metrics: Vec<SweepMetric>,
+    /// One row per variant, in sweep order
+    pub rows: Vec<SweepRow>,
+    /// The variants, named from the template
+    pub library: FootprintLibrary,
+}
+
+impl SweepResult {
+    /// Name, parameter value and the recorded metrics of each variant
+    pub fn to_csv(&self) -> String {
+        let mut csv = std::iter::once("name").chain(std::iter::once(self.parameter.as_str())).map(str::to_string).collect::<Vec<_>>();
+        csv.extend(self.metrics.iter().map(|metric| metric.column().to_string()));
+        let mut text = csv.join(",") + "\n";
+        for row in &self.rows {
+            let mut cells = vec![csv_field(&row.name), format_value(row.value)];
+            cells.extend(self.metrics.iter().map(|&metric| row.metrics.cell(metric)));
+            text += &(cells.join(",") + "\n");
+        }
+        text
+    }
+
+    /// Write the library into `dir` and the CSV beside it as `<library>_sweep.csv`,
+    /// returning both paths
+    pub fn write_to(&self, dir: &Path) -> io::Result<(PathBuf, PathBuf)> {
+        let pretty = self.library.write_to(dir)?;
+        let csv = dir.join(format!("{}_sweep.csv", self.library.name));
+        fs::write(&csv, self.to_csv())?;
+        Ok((pretty, csv))
+    }
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// A generated footprint published under the sweep's name for it, its value text
+/// following the name
+struct Variant {
+    footprint: Box<dyn BoardComposableObject>,
+    name: String,
+}
+
+impl BoardComposableObject for Variant {
+    fn is_smt(&self) -> bool {
+        self.footprint.is_smt()
+    }
+    fn is_electrical(&self) -> bool {
+        self.footprint.is_electrical()
+    }
+    fn is_passive(&self) -> bool {
+        self.footprint.is_passive()
+    }
+    fn terminal_count(&self) -> usize {
+        self.footprint.terminal_count()
+    }
+    fn is_assembled(&self) -> bool {
+        self.footprint.is_assembled()
+    }
+    fn allows_soldermask_bridges(&self) -> bool {
+        self.footprint.allows_soldermask_bridges()
+    }
+    fn pad_grouping(&self) -> PadGrouping {
+        self.footprint.pad_grouping()
+    }
+    fn functional_type(&self) -> FunctionalType {
+        self.footprint.functional_type()
+    }
+    fn footprint_name(&self) -> String {
+        self.name.clone()
+    }
+    fn library_name(&self) -> String {
+        self.footprint.library_name()
+    }
+    fn bounding_box(&self) -> Rectangle {
+        self.footprint.bounding_box()
+    }
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        self.footprint.pad_descriptors()
+    }
+    fn description_context(&self) -> Option<DescriptionContext> {
+        self.footprint.description_context()
+    }
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        let generated = self.footprint.footprint_name();
+        let mut texts = self.footprint.fp_text_elements();
+        for text in texts.iter_mut().filter(|text| text.text == generated) {
+            text.text = self.name.clone();
+        }
+        texts
+    }
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        self.footprint.graphic_elements()
+    }
+    fn model_3d(&self) -> Option<Model3D> {
+        self.footprint.model_3d()
+    }
+    fn rule_areas(&self) -> Vec<RuleArea> {
+        self.footprint.rule_areas()
+    }
+    fn actuation_envelope(&self) -> Option<ActuationEnvelope> {
+        self.footprint.actuation_envelope()
+    }
+
+    fn process(&self) -> ProcessSet {
+        self.footprint.process()
+    }
+    fn courtyard_margin(&self) -> f32 {
+        self.footprint.courtyard_margin()
+    }
+    fn generate_courtyard(&self) -> Courtyard {
+        self.footprint.generate_courtyard()
+    }
+    fn generate_courtyards(&self) -> Vec<Courtyard> {
+        self.footprint.generate_courtyards()
+    }
+    fn anchor(&self) -> Anchor {
+        self.footprint.anchor()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use copper_substrate::analysis::DEFAULT_PAD_CLEARANCE;
+
+    use super::*;
+
+    const QFN_PAD_LENGTH: &str = r#"
+generator = "qfn"
+outline = "MO-220-4x4-24"
+pins = 24
+
+[sweep]
+parameter = "pad_length"
+range = { from = 0.7, to = 1.0, step = 0.05 }
+name = "QFN-24-1EP_4x4mm_P0.5mm_L{pad_length}"
+library = "QFN_Sweep"
+"#;
+
+    fn scratch(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("copper-sweep-{}-{}", std::process::id(), test));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn ranges_include_both_ends() {
+        let sweep = Sweep::parse(QFN_PAD_LENGTH).unwrap();
+        assert_eq!(sweep.values, vec![0.7, 0.75, 0.8, 0.85, 0.9, 0.95, 1.0]);
+        assert_eq!(sweep.metrics, SweepMetric::ALL.to_vec());
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples/fixtures/qfn_pad_length_sweep.toml");
+        assert_eq!(Sweep::read(&fixture).unwrap().values, sweep.values);
+    }
+
+    #[test]
+    fn longer_qfn_pads_trade_gap_for_paste_and_courtyard() {
+        let result = Sweep::parse(QFN_PAD_LENGTH).unwrap().run().unwrap();
+        assert_eq!(result.rows.len(), 7);
+        assert_eq!(result.rows[3].name, "QFN-24-1EP_4x4mm_P0.5mm_L0.85");
+        for pair in result.rows.windows(2) {
+            let (shorter, longer) = (&pair[0].metrics, &pair[1].metrics);
+            assert!(longer.min_gap.unwrap() <= shorter.min_gap.unwrap() + 1e-6, "{:?} {:?}", shorter, longer);
+            assert!(longer.courtyard_area > shorter.courtyard_area, "{:?} {:?}", shorter, longer);
+            assert!(longer.paste_coverage.unwrap() > shorter.paste_coverage.unwrap(), "{:?} {:?}", shorter, longer);
+        }
+        let (first, last) = (&result.rows[0].metrics, &result.rows[6].metrics);
+        assert!(last.min_gap.unwrap() < first.min_gap.unwrap());
+        assert_eq!(first.klc_violations, 0);
+    }
+
+    #[test]
+    fn pads_reaching_into_the_clearance_fail_the_drc() {
+        let text = QFN_PAD_LENGTH.replace("range = { from = 0.7, to = 1.0, step = 0.05 }", "values = [0.7, 1.2]");
+        let result = Sweep::parse(&text).unwrap().run().unwrap();
+        assert_eq!(result.rows[0].metrics.cell(SweepMetric::Klc), "pass");
+        // 1.2 mm pads end 0.05 mm from the exposed pad
+        assert!(result.rows[1].metrics.min_gap.unwrap() < DEFAULT_PAD_CLEARANCE);
+        assert!(result.rows[1].metrics.cell(SweepMetric::Klc).ends_with(" violations"));
+    }
+
+    #[test]
+    fn csv_holds_the_picked_metrics_in_order() {
+        let text = QFN_PAD_LENGTH.replace("library = \"QFN_Sweep\"", "metrics = [\"klc\", \"min_gap\"]");
+        let result = Sweep::parse(&text).unwrap().run().unwrap();
+        let csv = result.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "name,pad_length,klc,min_gap");
+        assert_eq!(lines.len(), 8);
+        assert!(lines[1].starts_with("QFN-24-1EP_4x4mm_P0.5mm_L0.7,0.7,pass,0.2500"), "{}", lines[1]);
+        assert!(lines[7].starts_with("QFN-24-1EP_4x4mm_P0.5mm_L1,1,"), "{}", lines[7]);
+    }
+
+    #[test]
+    fn nested_parameters_and_value_lists() {
+        let text = "generator = \"qfn\"\noutline = \"MO-220-4x4-24\"\npins = 24\n\n\
+                    [sweep]\nparameter = \"fillets.toe\"\nvalues = [0.2, 0.3]\n";
+        let result = Sweep::parse(text).unwrap().run().unwrap();
+        let names: Vec<&str> = result.rows.iter().map(|row| row.name.as_str()).collect();
+        assert_eq!(names, ["QFN-24-1EP_4x4mm_P0.5mm_EP2.6x2.6mm_fillets.toe0.2", "QFN-24-1EP_4x4mm_P0.5mm_EP2.6x2.6mm_fillets.toe0.3"]);
+        assert!(result.rows[1].metrics.courtyard_area > result.rows[0].metrics.courtyard_area);
+    }
+
+    #[test]
+    fn writes_the_family_and_the_csv() {
+        let dir = scratch("write");
+        let result = Sweep::parse(QFN_PAD_LENGTH).unwrap().run().unwrap();
+        let (pretty, csv) = result.write_to(&dir).unwrap();
+        assert_eq!(fs::read_dir(&pretty).unwrap().count(), 7);
+        let footprint = fs::read_to_string(pretty.join("QFN-24-1EP_4x4mm_P0.5mm_L0.9.kicad_mod")).unwrap();
+        assert!(footprint.contains("(fp_text value \"QFN-24-1EP_4x4mm_P0.5mm_L0.9\""), "{}", footprint);
+        assert_eq!(fs::read_to_string(&csv).unwrap(), result.to_csv());
+        assert_eq!(csv.file_name().unwrap(), "QFN_Sweep_sweep.csv");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn bad_sweeps_are_refused() {
+        let definition = "generator = \"qfn\"\noutline = \"MO-220-4x4-24\"\npins = 24\n";
+        let sweep = |section: &str| Sweep::parse(&format!("{}\n[sweep]\n{}", definition, section));
+        assert_eq!(Sweep::parse(definition).unwrap_err(), SweepError::MissingSweep);
+        assert!(matches!(sweep("parameter = \"pad_length\""), Err(SweepError::InvalidRange(_))));
+        assert!(matches!(
+            sweep("parameter = \"pad_length\"\nrange = { from = 1.0, to = 0.7, step = 0.05 }"),
+            Err(SweepError::InvalidRange(_))
+        ));
+        assert!(matches!(
+            sweep("parameter = \"pad_length\"\nrange = { from = 0.7, to = 1.0, step = 0.0 }"),
+            Err(SweepError::InvalidRange(_))
+        ));
+        assert!(matches!(sweep("parameter = \"pad_length\"\nvalues = [1]\nmetrics = [\"area\"]"), Err(SweepError::Parse(_))));
+        // pins is a number, not a table
+        assert!(matches!(
+            sweep("parameter = \"pins.count\"\nvalues = [1]").unwrap().run(),
+            Err(SweepError::InvalidParameter(_))
+        ));
+        // 2 mm pads reach the exposed pad
+        let error = sweep("parameter = \"pad_length\"\nvalues = [0.8, 2.0]").unwrap().run().err().unwrap();
+        assert!(matches!(error, SweepError::Definition { value: 2.0, .. }), "{}", error);
+        assert!(matches!(
+            sweep("parameter = \"pad_length\"\nvalues = [0.8, 0.9]\nname = \"QFN\"").unwrap().run(),
+            Err(SweepError::DuplicateName(_))
+        ));
+    }
+}
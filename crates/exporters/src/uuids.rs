@@ -0,0 +1,184 @@
+//! Reproducible element UUIDs
+//!
+//! Generators give every pad, text and graphic a fresh v4 UUID, so the same footprint is
+//! written differently on every run and a regenerated library diffs on every element.
+//! With `UuidMode::Deterministic` the footprint writer replaces each `(uuid ...)` and
+//! `(tstamp ...)` value with `deterministic_uuid` of the footprint name and the value's
+//! position in the file: the same footprint is then written byte for byte the same, and
+//! third parties can recompute the IDs. UUIDs shared by several elements of the input
+//! stay shared.
+//!
+//! Names alone collide between organizations generating the same footprint names with
+//! different content, so `ExportOptions::uuid_namespace` takes a `UuidNamespace`: an
+//! organization namespace UUID and optionally a library version. The `index`th distinct
+//! UUID of footprint `name` is then
+//!
+//! ```text
+//! uuid_v5(organization, "<name>/<index>")              without a version
+//! uuid_v5(organization, "<name>@<version>/<index>")    with one
+//! ```
+//!
+//! with `UUID_NAMESPACE` as the organization when none is set. `UuidNamespace::for_domain`
+//! derives an organization namespace from a domain name the organization owns.
+
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+/// Namespace of the deterministic UUIDs: the v5 UUID of
+/// "https://github.com/Atlantix-EDA/copper-substrate/uuid" in the URL namespace
+pub const UUID_NAMESPACE: Uuid = Uuid::from_u128(0x34c3550e_fc65_50a0_a0b9_85fbeddd3e5a);
+
+/// How footprint writers fill in element UUIDs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UuidMode {
+    /// As the footprint gives them
+    #[default]
+    AsGiven,
+    /// Derived from the footprint name and position in the file, see the module
+    /// documentation
+    Deterministic,
+}
+
+/// Organization and library version the deterministic UUIDs are derived in, see the
+/// module documentation
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct UuidNamespace {
+    /// Namespace UUID of the organization, `UUID_NAMESPACE` when `None`
+    pub organization: Option<Uuid>,
+    /// Library version, e.g. "1.2.0", so each release gets its own UUIDs
+    pub version: Option<String>,
+}
+
+impl UuidNamespace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn for_organization(organization: Uuid) -> Self {
+        Self { organization: Some(organization), version: None }
+    }
+
+    /// The organization namespace of `domain`: its v5 UUID in the DNS namespace
+    pub fn for_domain(domain: &str) -> Self {
+        Self::for_organization(Uuid::new_v5(&Uuid::NAMESPACE_DNS, domain.as_bytes()))
+    }
+
+    pub fn with_version(mut self, version: &str) -> Self {
+        self.version = Some(version.to_string());
+        self
+    }
+
+    /// The namespace UUIDs are derived in
+    pub fn organization(&self) -> Uuid {
+        self.organization.unwrap_or(UUID_NAMESPACE)
+    }
+
+    /// The `index`th distinct UUID written in `footprint`, counting from 0
+    pub fn uuid(&self, footprint: &str, index: usize) -> Uuid {
+        let name = match &self.version {
+            Some(version) => format!("{}@{}/{}", footprint, version, index),
+            None => format!("{}/{}", footprint, index),
+        };
+        Uuid::new_v5(&self.organization(), name.as_bytes())
+    }
+}
+
+/// The v5 UUID of "<footprint>/<index>" in `UUID_NAMESPACE`, for the `index`th distinct
+/// UUID written in the footprint, counting from 0
+pub fn deterministic_uuid(footprint: &str, index: usize) -> Uuid {
+    UuidNamespace::default().uuid(footprint, index)
+}
+
+/// `text` with every `(uuid ...)` and `(tstamp ...)` value replaced by its
+/// `deterministic_uuid`
+pub fn with_deterministic_uuids(text: &str, footprint: &str) -> String {
+    with_namespaced_uuids(text, footprint, &UuidNamespace::default())
+}
+
+/// `with_deterministic_uuids` with the UUIDs derived in `namespace`
+pub fn with_namespaced_uuids(text: &str, footprint: &str, namespace: &UuidNamespace) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut assigned: HashMap<&str, Uuid> = HashMap::new();
+    let mut rest = text;
+    while let Some(start) = ["(uuid ", "(tstamp "].iter().filter_map(|token| rest.find(token).map(|at| at + token.len())).min() {
+        let Some(length) = rest[start..].find(')') else { break };
+        let value = rest[start..start + length].trim().trim_matches('"');
+        let count = assigned.len();
+        let uuid = *assigned.entry(value).or_insert_with(|| namespace.uuid(footprint, count));
+        output.push_str(&rest[..start]);
+        output.push_str(&format!("\"{}\"", uuid));
+        rest = &rest[start + length..];
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "(footprint \"R\"\n\t(pad \"1\" smd rect (tstamp \"a\"))\n\t(fp_line (uuid \"b\"))\n\t(fp_line (uuid a))\n)\n";
+
+    #[test]
+    fn namespace_is_the_documented_derivation() {
+        assert_eq!(UUID_NAMESPACE, Uuid::new_v5(&Uuid::NAMESPACE_URL, b"https://github.com/Atlantix-EDA/copper-substrate/uuid"));
+    }
+
+    #[test]
+    fn values_are_replaced_in_order_and_shared_values_stay_shared() {
+        let (first, second) = (deterministic_uuid("R", 0), deterministic_uuid("R", 1));
+        let expected = format!(
+            "(footprint \"R\"\n\t(pad \"1\" smd rect (tstamp \"{0}\"))\n\t(fp_line (uuid \"{1}\"))\n\t(fp_line (uuid \"{0}\"))\n)\n",
+            first, second
+        );
+        assert_eq!(with_deterministic_uuids(TEXT, "R"), expected);
+    }
+
+    #[test]
+    fn other_footprints_get_other_uuids() {
+        assert_eq!(with_deterministic_uuids(TEXT, "R"), with_deterministic_uuids(TEXT, "R"));
+        assert_ne!(with_deterministic_uuids(TEXT, "R"), with_deterministic_uuids(TEXT, "C"));
+    }
+
+    #[test]
+    fn namespaces_follow_the_documented_derivation() {
+        let acme = UuidNamespace::for_domain("acme.example");
+        let organization = Uuid::new_v5(&Uuid::NAMESPACE_DNS, b"acme.example");
+        assert_eq!(acme.uuid("R", 3), Uuid::new_v5(&organization, b"R/3"));
+        assert_eq!(acme.clone().with_version("1.2.0").uuid("R", 3), Uuid::new_v5(&organization, b"R@1.2.0/3"));
+        assert_eq!(UuidNamespace::new().uuid("R", 3), deterministic_uuid("R", 3));
+        assert_eq!(with_namespaced_uuids(TEXT, "R", &UuidNamespace::default()), with_deterministic_uuids(TEXT, "R"));
+    }
+
+    #[test]
+    fn a_namespace_changes_only_the_uuids() {
+        use crate::exporter::ExportOptions;
+        use crate::kicad_pcb_export::to_kicad_footprint_with_options;
+        use copper_substrate::prelude::*;
+
+        let soic = Soic::from_jedec("MS-012-AA", 8).unwrap();
+        let write = |namespace: UuidNamespace| {
+            let options = ExportOptions { uuids: UuidMode::Deterministic, uuid_namespace: namespace, ..ExportOptions::default() };
+            to_kicad_footprint_with_options(&soic, &options)
+        };
+        let (ours, theirs) = (write(UuidNamespace::default()), write(UuidNamespace::for_domain("acme.example")));
+        let released = write(UuidNamespace::for_domain("acme.example").with_version("2.0.0"));
+        assert_eq!(ours.lines().count(), theirs.lines().count());
+        for other in [&theirs, &released] {
+            let differing: Vec<(&str, &str)> = ours.lines().zip(other.lines()).filter(|(a, b)| a != b).collect();
+            assert!(!differing.is_empty());
+            for (a, b) in differing {
+                assert!(a.contains("(uuid ") || a.contains("(tstamp "), "{}", a);
+                let strip = |line: &str| line.split('"').filter(|part| Uuid::parse_str(part).is_err()).collect::<String>();
+                assert_eq!(strip(a), strip(b));
+            }
+        }
+        // Every UUID changes
+        let uuids = |text: &str| -> Vec<String> {
+            text.split('"').filter(|part| Uuid::parse_str(part).is_ok()).map(str::to_string).collect()
+        };
+        assert!(uuids(&ours).iter().all(|uuid| !uuids(&theirs).contains(uuid)));
+        assert_eq!(uuids(&ours).len(), uuids(&theirs).len());
+    }
+}
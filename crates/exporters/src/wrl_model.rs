@@ -0,0 +1,304 @@
+//! Generated box WRL models
+//!
+//! A stand-in 3D model for footprints without one: the body is a box over the fab
+//! outline, raised by the standoff. Through hole pads also get leads: tinned cylinders
+//! from the pad up to the body, and down through the board to `protrusion` below it.
+//! Horizontally mounted parts have kinked leads. These run up from the pad to the middle
+//! of the body's height, then straight across to the nearest side of the body.
+//! SMD pads get no leads; the body sits on its standoff over them.
+//!
+//! Coordinates follow KiCad's model conventions:
+//! - Z points up, with the top of the board at 0.
+//! - Y points up, the other way from the footprint's Y.
+//! - One VRML unit is 0.1 inch (2.54 mm).
+//!
+//! The footprint's model is then placed with a zero offset and a scale of 1.
+//! `ModelGeometry` keeps the shapes in millimeters for checking, and `to_wrl` writes them.
+//! `LibraryModels::with_generated_model` ships the result with a library.
+
+use std::fmt::Write;
+
+use copper_substrate::prelude::*;
+
+/// Millimeters per VRML unit in KiCad models
+pub const WRL_UNIT_MM: f32 = 2.54;
+
+/// Diffuse colour of the body, black plastic
+const BODY_COLOR: (f32, f32, f32) = (0.15, 0.15, 0.15);
+/// Diffuse and specular colour of the leads, tinned metal
+const LEAD_COLOR: ((f32, f32, f32), (f32, f32, f32)) = ((0.78, 0.78, 0.76), (0.9, 0.9, 0.88));
+/// Lead diameter as a fraction of the drill when none is given
+const LEAD_DRILL_RATIO: f32 = 0.7;
+
+/// How through hole leads run from the board to the body
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LeadForm {
+    /// Straight up into the body, for parts standing on the board
+    #[default]
+    Straight,
+    /// Up to mid body height, then across to the nearest side of the body, for parts lying
+    /// on the board
+    Kinked,
+}
+
+/// Sizes of a generated model, all in mm
+#[derive(Debug, Clone, PartialEq)]
+pub struct WrlModel {
+    pub body_height: f32,
+    /// Gap between the board and the underside of the body
+    pub standoff: f32,
+    pub board_thickness: f32,
+    /// How far leads stick out below the board
+    pub protrusion: f32,
+    /// `LEAD_DRILL_RATIO` of each pad's drill when `None`
+    pub lead_diameter: Option<f32>,
+    pub lead_form: LeadForm,
+}
+
+impl Default for WrlModel {
+    fn default() -> Self {
+        Self { body_height: 1.0, standoff: 0.0, board_thickness: 1.6, protrusion: 1.5, lead_diameter: None, lead_form: LeadForm::Straight }
+    }
+}
+
+impl WrlModel {
+    pub fn new(body_height: f32) -> Self {
+        Self { body_height, ..Self::default() }
+    }
+
+    pub fn with_standoff(mut self, standoff: f32) -> Self {
+        self.standoff = standoff;
+        self
+    }
+
+    pub fn with_board_thickness(mut self, thickness: f32) -> Self {
+        self.board_thickness = thickness;
+        self
+    }
+
+    pub fn with_protrusion(mut self, protrusion: f32) -> Self {
+        self.protrusion = protrusion;
+        self
+    }
+
+    pub fn with_lead_diameter(mut self, diameter: f32) -> Self {
+        self.lead_diameter = Some(diameter);
+        self
+    }
+
+    pub fn with_lead_form(mut self, form: LeadForm) -> Self {
+        self.lead_form = form;
+        self
+    }
+
+    /// Body and leads of `component` in model coordinates
+    pub fn geometry<T: BoardComposableObject + ?Sized>(&self, component: &T) -> ModelGeometry {
+        let outline = body_outline(component);
+        let bottom = self.standoff;
+        let body = Cuboid {
+            min: [outline.min_x, -outline.max_y, bottom],
+            max: [outline.max_x, -outline.min_y, bottom + self.body_height],
+        };
+        let mut leads = Vec::new();
+        for pad in component.pad_descriptors().iter().filter(|pad| matches!(pad.pad_type, PadType::ThroughHole)) {
+            let Some(drill) = pad.drill_size else { continue };
+            let diameter = self.lead_diameter.unwrap_or(drill * LEAD_DRILL_RATIO);
+            let foot = [pad.position.0, -pad.position.1];
+            let tip = -(self.board_thickness + self.protrusion);
+            let top = match self.lead_form {
+                LeadForm::Straight => bottom,
+                LeadForm::Kinked => bottom + self.body_height / 2.0,
+            };
+            leads.push(Cylinder { start: [foot[0], foot[1], tip], end: [foot[0], foot[1], top], diameter });
+            if self.lead_form == LeadForm::Kinked {
+                let reach = body.nearest_point(foot);
+                if (reach[0] - foot[0]).abs() > f32::EPSILON || (reach[1] - foot[1]).abs() > f32::EPSILON {
+                    leads.push(Cylinder { start: [foot[0], foot[1], top], end: [reach[0], reach[1], top], diameter });
+                }
+            }
+        }
+        ModelGeometry { body, leads }
+    }
+
+    /// `geometry` written as VRML 2.0
+    pub fn to_wrl<T: BoardComposableObject + ?Sized>(&self, component: &T) -> String {
+        self.geometry(component).to_wrl()
+    }
+}
+
+/// The fab outline of `component`, or its bounding box when it has none
+fn body_outline<T: BoardComposableObject + ?Sized>(component: &T) -> Rectangle {
+    let mut points = Vec::new();
+    for element in component.graphic_elements() {
+        if !matches!(element.role, Some(ElementRole::FabOutline(_))) {
+            continue;
+        }
+        match element.element_type {
+            GraphicType::Line { start, end } => points.extend([Point::from(start), Point::from(end)]),
+            GraphicType::Rectangle { bounds } => {
+                points.extend([Point::new(bounds.min_x, bounds.min_y), Point::new(bounds.max_x, bounds.max_y)])
+            }
+            GraphicType::Polygon { points: corners, .. } => points.extend(corners.into_iter().map(Point::from)),
+            GraphicType::Circle { center, radius } => points.extend([
+                Point::new(center.0 - radius, center.1 - radius),
+                Point::new(center.0 + radius, center.1 + radius),
+            ]),
+            GraphicType::Dimension(_) => {}
+        }
+    }
+    Rectangle::enclosing(&points).unwrap_or_else(|| component.bounding_box())
+}
+
+/// Axis-aligned box in model coordinates (mm)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cuboid {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl Cuboid {
+    pub fn center(&self) -> [f32; 3] {
+        [0, 1, 2].map(|axis| (self.min[axis] + self.max[axis]) / 2.0)
+    }
+
+    pub fn size(&self) -> [f32; 3] {
+        [0, 1, 2].map(|axis| self.max[axis] - self.min[axis])
+    }
+
+    /// The point of the box nearest `from`, seen from above; `from` itself when it is
+    /// already over the box
+    fn nearest_point(&self, from: [f32; 2]) -> [f32; 2] {
+        [0, 1].map(|axis| from[axis].clamp(self.min[axis], self.max[axis]))
+    }
+}
+
+/// Round lead segment in model coordinates (mm)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cylinder {
+    pub start: [f32; 3],
+    pub end: [f32; 3],
+    pub diameter: f32,
+}
+
+impl Cylinder {
+    pub fn length(&self) -> f32 {
+        (0..3).map(|axis| (self.end[axis] - self.start[axis]).powi(2)).sum::<f32>().sqrt()
+    }
+}
+
+/// Body and lead segments of a generated model
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelGeometry {
+    pub body: Cuboid,
+    pub leads: Vec<Cylinder>,
+}
+
+impl ModelGeometry {
+    /// VRML 2.0 in KiCad's units, see the module documentation
+    pub fn to_wrl(&self) -> String {
+        let unit = |value: f32| value / WRL_UNIT_MM;
+        let triple = |values: [f32; 3]| values.map(|value| format!("{:.5}", unit(value))).join(" ");
+        let mut output = String::from("#VRML V2.0 utf8\n");
+        let body = &self.body;
+        writeln!(output, "Transform {{\n  translation {}\n  children [", triple(body.center())).unwrap();
+        write_shape(&mut output, &format!("Box {{ size {} }}", triple(body.size())), BODY_COLOR, None);
+        writeln!(output, "  ]\n}}").unwrap();
+        for lead in &self.leads {
+            let center = [0, 1, 2].map(|axis| (lead.start[axis] + lead.end[axis]) / 2.0);
+            // VRML cylinders run along Y; turn that onto the lead's axis
+            let axis = [0, 1, 2].map(|i| (lead.end[i] - lead.start[i]) / lead.length().max(f32::EPSILON));
+            let (rotation_axis, angle) = if axis[2].abs() > 1.0 - 1e-6 {
+                // A quarter turn about X takes Y onto Z
+                ([1.0, 0.0, 0.0], axis[2].signum() * std::f32::consts::FRAC_PI_2)
+            } else {
+                ([0.0, 0.0, 1.0], (-axis[0]).atan2(axis[1]))
+            };
+            writeln!(output, "Transform {{\n  translation {}", triple(center)).unwrap();
+            writeln!(output, "  rotation {} {} {} {:.6}", rotation_axis[0], rotation_axis[1], rotation_axis[2], angle).unwrap();
+            writeln!(output, "  children [").unwrap();
+            let cylinder = format!("Cylinder {{ radius {:.5} height {:.5} }}", unit(lead.diameter / 2.0), unit(lead.length()));
+            write_shape(&mut output, &cylinder, LEAD_COLOR.0, Some(LEAD_COLOR.1));
+            writeln!(output, "  ]\n}}").unwrap();
+        }
+        output
+    }
+}
+
+fn write_shape(output: &mut String, geometry: &str, diffuse: (f32, f32, f32), specular: Option<(f32, f32, f32)>) {
+    writeln!(output, "    Shape {{").unwrap();
+    write!(output, "      appearance Appearance {{ material Material {{ diffuseColor {} {} {}", diffuse.0, diffuse.1, diffuse.2).unwrap();
+    if let Some(specular) = specular {
+        write!(output, " specularColor {} {} {} shininess 0.6", specular.0, specular.1, specular.2).unwrap();
+    }
+    writeln!(output, " }} }}\n      geometry {}\n    }}", geometry).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOLERANCE: f32 = 1e-4;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < TOLERANCE
+    }
+
+    #[test]
+    fn straight_leads_stand_on_the_pads() {
+        let dip = Dip::new(8).unwrap();
+        let model = WrlModel::new(3.3).with_standoff(0.5).with_protrusion(2.0);
+        let geometry = model.geometry(&dip);
+        let pads = dip.pad_descriptors();
+        assert_eq!(geometry.leads.len(), 8);
+        for (lead, pad) in geometry.leads.iter().zip(&pads) {
+            // Footprint Y down, model Y up
+            assert!(close(lead.start[0], pad.position.0) && close(lead.start[1], -pad.position.1), "{:?} {:?}", lead, pad.position);
+            assert_eq!((lead.start[0], lead.start[1]), (lead.end[0], lead.end[1]));
+            assert!(close(lead.start[2], -3.6) && close(lead.end[2], 0.5), "{:?}", lead);
+            assert!(close(lead.diameter, pad.drill_size.unwrap() * LEAD_DRILL_RATIO));
+        }
+        // The body is raised by the standoff
+        assert!(close(geometry.body.min[2], 0.5) && close(geometry.body.max[2], 3.8));
+        let fab = dip.graphics_with_role(&ElementRole::FabOutline(None));
+        let GraphicType::Polygon { points, .. } = &fab[0].element_type else { panic!("{:?}", fab) };
+        let min_x = points.iter().map(|point| point.0).fold(f32::MAX, f32::min);
+        assert!(close(geometry.body.min[0], min_x));
+    }
+
+    #[test]
+    fn kinked_leads_run_across_to_the_body() {
+        let dip = Dip::new(8).unwrap();
+        let geometry = WrlModel::new(2.0).with_lead_form(LeadForm::Kinked).with_lead_diameter(0.5).geometry(&dip);
+        let body = &geometry.body;
+        assert_eq!(geometry.leads.len(), 16);
+        for pair in geometry.leads.chunks(2) {
+            let (up, across) = (&pair[0], &pair[1]);
+            assert!(close(up.end[2], 1.0) && close(across.start[2], 1.0) && close(across.end[2], 1.0));
+            assert_eq!(up.end, across.start);
+            // Straight in from the side of the body, ending on its outline
+            let end_x = across.end[0];
+            assert!(close(end_x, body.min[0]) || close(end_x, body.max[0]), "{:?} {:?}", across, body);
+            assert!(close(across.end[1], across.start[1]));
+            assert!(close(across.diameter, 0.5));
+        }
+    }
+
+    #[test]
+    fn smd_parts_get_a_body_only_in_kicad_units() {
+        let chip = ChipFootprint::standard(FunctionalType::Resistor("10k".to_string()), "0805").unwrap();
+        let geometry = WrlModel::new(0.5).geometry(&chip);
+        assert!(geometry.leads.is_empty());
+        assert_eq!(geometry.body.min[2], 0.0);
+        assert!(close(geometry.body.size()[0], chip.bounding_box().width()));
+
+        let wrl = geometry.to_wrl();
+        assert!(wrl.starts_with("#VRML V2.0 utf8\n"));
+        let size = geometry.body.size().map(|value| format!("{:.5}", value / WRL_UNIT_MM)).join(" ");
+        assert!(wrl.contains(&format!("Box {{ size {} }}", size)), "{}", wrl);
+
+        let dip = WrlModel::new(3.3).to_wrl(&Dip::new(8).unwrap());
+        assert_eq!(dip.matches("Cylinder {").count(), 8);
+        assert_eq!(dip.matches("rotation 1 0 0 1.570796").count(), 8);
+        assert!(dip.contains("specularColor"));
+    }
+}
@@ -0,0 +1,108 @@
+//! A board from footprints to fabrication files in one program
+//!
+//! Three footprints generated from their parameters are placed on a board, connected by a
+//! netlist, autorouted and poured with ground, checked with the board DRC and
+//! written out as a fab package. The `.kicad_pcb` is then read back: every placement and
+//! pad net must survive the trip. `examples/board_in_100_lines.rs` is the same design as a
+//! program to read.
+//!
+//! The board is also rendered to SVG, every layer and both sides, and compared with the
+//! goldens under `tests/goldens/` (see `svg_golden`). Accept intended changes with
+//! `COPPER_BLESS_GOLDENS=1 cargo test -p copper-exporters --test board_end_to_end`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use copper_exporters::fab_outputs::write_fab_outputs;
+use copper_exporters::kicad_board_import::read_board_file;
+use copper_exporters::board_svg::{board_svg_views, BoardSvgOptions};
+use copper_exporters::svg_golden::GoldenSet;
+use copper_exporters::{BoardSvg, ExportOptions};
+use copper_substrate::analysis::{Finding, Severity};
+use copper_substrate::board::{Board, FabOrigin};
+use copper_substrate::prelude::*;
+
+/// A 40 x 30 mm two layer board: an SOIC-8 with a pull-up on pin 8 feeding an RC filter,
+/// VCC and the filter node routed, GND poured on both sides and stitched with a via
+fn design() -> Board {
+    let mut board = Board::new("rc_filter").with_rectangular_outline(40.0, 30.0);
+    board.place("U1", Soic::from_jedec("MS-012-AA", 8).unwrap(), (15.0, 15.0), 0.0).unwrap();
+    board.place("R1", ChipFootprint::standard(FunctionalType::Resistor("10k".into()), "0603").unwrap(), (25.0, 10.0), 0.0).unwrap();
+    board.place("C1", ChipFootprint::standard(FunctionalType::Capacitor("100n".into()), "0402").unwrap(), (32.0, 10.0), 0.0).unwrap();
+
+    let netlist = Netlist::new().with_net("VCC", &["U1.8", "R1.1"]).with_net("FILT", &["R1.2", "C1.1"]).with_net("GND", &["U1.4", "C1.2"]);
+    board.apply_netlist(&netlist).unwrap();
+
+    let report = board.autoroute(&AutorouteOptions::default().with_nets(&["VCC", "FILT"]));
+    assert_eq!(report.completion(), 100.0, "{}", report);
+    assert_eq!(report.routed.len(), 2);
+
+    let top = BoardLayer::front(LayerType::Copper);
+
+    let outline = board.outline().unwrap().to_vec();
+    for layer in [top, BoardLayer::back(LayerType::Copper)] {
+        board.add_zone(Zone::new("GND", &[layer], outline.clone()));
+    }
+    board.add_via(Via::new("GND", Point::new(5.0, 25.0), 0.6, 0.3));
+    board
+}
+
+/// A fresh directory for this run's files
+fn output_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("copper_board_end_to_end_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn a_designed_board_passes_drc_and_survives_its_own_files() {
+    let board = design();
+    let errors: Vec<Finding> = board.drc().into_iter().filter(|finding| finding.severity == Severity::Error).collect();
+    assert!(errors.is_empty(), "{:#?}", errors);
+
+    let dir = output_dir();
+    let written = write_fab_outputs(&board, &dir, FabOrigin::Absolute, &ExportOptions::default()).unwrap();
+    let file = |name: &str| fs::read_to_string(dir.join(name)).unwrap_or_else(|error| panic!("{}: {}", name, error));
+    assert_eq!(written.len(), 13);
+    assert!(written.iter().all(|path| path.metadata().unwrap().len() > 0));
+
+    assert!(file("rc_filter-F_Cu.gtl").contains("%TO.C,U1*%"));
+    assert!(file("rc_filter-F_SilkS.gto").contains("%TF.FileFunction,Legend,Top*%"));
+    assert_eq!(file("rc_filter-PTH.drl").lines().filter(|line| line.starts_with('X')).count(), 1);
+    let bom = file("rc_filter-bom.csv");
+    assert!(bom.contains("R1,10k,") && bom.contains("C1,100n,") && bom.contains("U1,"), "{}", bom);
+    assert_eq!(file("rc_filter-cpl.csv").lines().count(), 4);
+
+    let read = read_board_file(&dir.join("rc_filter.kicad_pcb")).unwrap();
+    assert_eq!(read.name, "rc_filter");
+    assert_eq!(read.components().len(), board.components().len());
+    for (original, placed) in board.components().iter().zip(read.components()) {
+        assert_eq!(placed.reference, original.reference);
+        assert_eq!((placed.side, placed.rotation), (original.side, original.rotation));
+        let moved = (placed.position.0 - original.position.0).hypot(placed.position.1 - original.position.1);
+        assert!(moved < 1e-6, "{} moved by {}", placed.reference, moved);
+        for pad in original.component.pad_descriptors() {
+            let (reference, number) = (&original.reference, pad.number.as_str());
+            assert_eq!(read.pad_net(reference, number), board.pad_net(reference, number), "{}.{}", reference, number);
+        }
+    }
+    assert_eq!(read.nets(), board.nets());
+    assert_eq!(read.tracks(), board.tracks());
+    assert_eq!(read.zones().len(), 2);
+    assert!(read.unconnected_pads().is_empty(), "{:?}", read.unconnected_pads());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn every_layer_renders_as_its_golden() {
+    let board = design();
+    let goldens = GoldenSet::new(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/goldens"));
+    let options = BoardSvgOptions::default();
+    let failures: Vec<String> = board_svg_views(&board)
+        .into_iter()
+        .filter_map(|view| goldens.check_svg(&format!("board_{}_{}", board.name, view.file_stem()), &board.render_svg(view, &options)).err())
+        .map(|error| error.to_string())
+        .collect();
+    assert!(failures.is_empty(), "\n{}", failures.join("\n\n"));
+}
@@ -0,0 +1,29 @@
+//! Property tests of the native `.csf` footprint file
+//!
+//! Random footprints (see `support`) are captured, written in both encodings and read
+//! back. Unlike the KiCad round trip nothing is lost on the way, so the footprint read
+//! must equal the one written exactly, floats and UUIDs included.
+
+mod support;
+
+use copper_exporters::csf::{csf_encoding, CsfEncoding, CsfFile};
+use copper_substrate::prelude::*;
+use proptest::prelude::*;
+use support::footprint;
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(500))]
+
+    #[test]
+    fn footprints_read_back_exactly(footprint in footprint()) {
+        let captured = Footprint::capture(&footprint);
+        for encoding in [CsfEncoding::Json, CsfEncoding::Cbor] {
+            let bytes = captured.to_csf(encoding).unwrap();
+            prop_assert_eq!(csf_encoding(&bytes), encoding);
+            let read = Footprint::from_csf(&bytes).unwrap();
+            prop_assert_eq!(&read, &captured);
+            // Writing what was read gives the same file
+            prop_assert_eq!(read.to_csf(encoding).unwrap(), bytes);
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Property tests of the rounding of KiCad file coordinates
+//!
+//! Every length a KiCad board or footprint file holds is rounded to the export's
+//! `Resolution` (see `format`). The number written must read back as exactly the
+//! quantized value, quantizing a written file again must change nothing, and a point
+//! turned four quarter turns must be written as it was.
+
+use copper_exporters::format::{quantize_numbers, Resolution};
+use copper_exporters::kicad_board_import::read_board;
+use copper_exporters::kicad_board_export::to_kicad_pcb_with_options;
+use copper_exporters::ExportOptions;
+use copper_substrate::board::Board;
+use copper_substrate::geometry::widen;
+use copper_substrate::prelude::*;
+use proptest::prelude::*;
+
+const RESOLUTIONS: [Resolution; 2] = [Resolution::Nanometer, Resolution::Micrometer];
+
+fn coordinate() -> impl Strategy<Value = f32> {
+    -500.0f32..500.0
+}
+
+fn point() -> impl Strategy<Value = Point> {
+    (coordinate(), coordinate()).prop_map(|(x, y)| Point::new(x, y))
+}
+
+fn board_with_track(start: Point, end: Point) -> Board {
+    let mut board = Board::new("quantize");
+    board.add_track(Track::new("N1", BoardLayer::front(LayerType::Copper), start, end, 0.2));
+    board
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(1000))]
+
+    #[test]
+    fn written_numbers_read_back_as_the_quantized_value(value in -4096.0f64..4096.0) {
+        for resolution in RESOLUTIONS {
+            let written = resolution.format(value);
+            prop_assert_eq!(written.parse::<f64>().unwrap(), resolution.quantize(value), "{}", written);
+            prop_assert_eq!(resolution.format(resolution.quantize(value)), written.clone());
+            prop_assert!(written != "-0");
+            prop_assert!((resolution.quantize(value) - value).abs() <= 0.5 / 10f64.powi(resolution.decimals() as i32) + 1e-12);
+        }
+    }
+
+    #[test]
+    fn board_files_hold_the_quantized_coordinates(start in point(), end in point()) {
+        let board = board_with_track(start, end);
+        for resolution in RESOLUTIONS {
+            let pcb = to_kicad_pcb_with_options(&board, &ExportOptions { resolution, ..ExportOptions::default() });
+            prop_assert_eq!(quantize_numbers(&pcb, resolution), pcb.clone());
+            let read = read_board(&pcb).unwrap();
+            let track = &read.tracks()[0];
+            let expected = |value: f32| resolution.format(widen(value)).parse::<f32>().unwrap();
+            prop_assert_eq!(track.start, Point::new(expected(start.x), expected(start.y)));
+            prop_assert_eq!(track.end, Point::new(expected(end.x), expected(end.y)));
+        }
+    }
+
+    #[test]
+    fn four_quarter_turns_are_written_unchanged(start in point(), end in point()) {
+        let turned = |point: Point| (0..4).fold(point, |point, _| point.rotated(90.0));
+        let tracks = |board: &Board| {
+            let pcb = to_kicad_pcb_with_options(board, &ExportOptions::default());
+            read_board(&pcb).unwrap().tracks().to_vec()
+        };
+        prop_assert_eq!(tracks(&board_with_track(turned(start), turned(end))), tracks(&board_with_track(start, end)));
+    }
+}
@@ -0,0 +1,195 @@
+//! Property tests of the KiCad footprint writer against the parser
+//!
+//! Random but well formed footprints (see `support`) are written for KiCad, parsed back
+//! strictly and compared field by field within float tolerance, in the canonical element
+//! order the writer uses.
+//!
+//! Damaged copies of the output (bytes dropped, inserted, replaced, truncated) are fed to
+//! the parser too, which may reject them but must not panic. A failing case is shrunk to
+//! a small footprint and recorded under `proptest-regressions/`.
+
+mod support;
+
+use copper_exporters::element_order::{sort_graphics, sort_pads, sort_texts};
+use copper_exporters::kicad_footprint_parse::{KiCadFootprint, ParseOptions};
+use copper_exporters::kicad_pcb_export::to_kicad_footprint;
+use copper_substrate::prelude::*;
+use proptest::prelude::*;
+use proptest::sample::{select, Index};
+use proptest::test_runner::TestCaseError;
+use support::{footprint, RandomFootprint};
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() <= 1e-5 * (1.0 + a.abs())
+}
+
+fn close_point(a: (f32, f32), b: (f32, f32)) -> bool {
+    close(a.0, b.0) && close(a.1, b.1)
+}
+
+/// Angles compare to 0.1 millidegree, a missing angle is zero
+fn same_angle(a: Option<f32>, b: Option<f32>) -> bool {
+    let key = |angle: Option<f32>| (angle.unwrap_or(0.0) * 1e4).round() + 0.0;
+    key(a) == key(b)
+}
+
+fn same_kind<T>(a: &T, b: &T) -> bool {
+    std::mem::discriminant(a) == std::mem::discriminant(b)
+}
+
+fn mismatch(what: &str, wrote: &dyn std::fmt::Debug, read: &dyn std::fmt::Debug) -> Result<(), TestCaseError> {
+    Err(TestCaseError::fail(format!("{}: wrote {:?}, read {:?}", what, wrote, read)))
+}
+
+fn compare(original: &RandomFootprint) -> Result<(), TestCaseError> {
+    let text = to_kicad_footprint(original);
+    let parsed = KiCadFootprint::parse(&text, ParseOptions { strict: true })
+        .map_err(|error| TestCaseError::fail(format!("parse failed: {}\n{}", error, text)))?;
+
+    if parsed.footprint_name() != original.name {
+        return mismatch("name", &original.name, &parsed.footprint_name());
+    }
+    if parsed.description() != original.description {
+        return mismatch("description", &original.description, &parsed.description());
+    }
+    // Tags are written normalized
+    let tags = original.tags.as_deref().map(normalize_tags).filter(|tags| !tags.is_empty());
+    if parsed.tags() != tags {
+        return mismatch("tags", &tags, &parsed.tags());
+    }
+
+    // The writer puts elements in canonical order
+    let (mut original_pads, mut original_texts, mut original_graphics) = (original.pads.clone(), original.texts.clone(), original.graphics.clone());
+    sort_pads(&mut original_pads);
+    sort_texts(&mut original_texts);
+    sort_graphics(&mut original_graphics);
+
+    let pads = parsed.pad_descriptors();
+    if pads.len() != original_pads.len() {
+        return mismatch("pad count", &original_pads.len(), &pads.len());
+    }
+    for (a, b) in original_pads.iter().zip(&pads) {
+        let same = a.number == b.number
+            && same_kind(&a.pad_type, &b.pad_type)
+            && same_kind(&a.shape, &b.shape)
+            && close_point(a.position, b.position)
+            && close_point(a.size, b.size)
+            && same_angle(a.rotation, b.rotation)
+            // Drills are written rounded to the nanometer, like every length
+            && a.drill_size.is_some() == b.drill_size.is_some()
+            && a.drill_size.zip(b.drill_size).is_none_or(|(a, b)| close(a, b))
+            && a.layers == b.layers
+            && a.roundrect_ratio == b.roundrect_ratio
+            && a.has_padstack() == b.has_padstack()
+            && a.fab_property == b.fab_property;
+        if !same {
+            return mismatch("pad", a, b);
+        }
+    }
+
+    let texts = parsed.fp_text_elements();
+    if texts.len() != original_texts.len() {
+        return mismatch("text count", &original_texts.len(), &texts.len());
+    }
+    for (a, b) in original_texts.iter().zip(&texts) {
+        let same = a.text == b.text
+            && close_point(a.position, b.position)
+            && same_angle(a.rotation, b.rotation)
+            && a.layer == b.layer
+            && a.hidden == b.hidden
+            && a.justify.unwrap_or_default() == b.justify.unwrap_or_default()
+            && close_point(a.font.size, b.font.size)
+            && close(a.font.thickness, b.font.thickness);
+        if !same {
+            return mismatch("text", a, b);
+        }
+    }
+
+    let graphics = parsed.graphic_elements();
+    if graphics.len() != original_graphics.len() {
+        return mismatch("graphic count", &original_graphics.len(), &graphics.len());
+    }
+    for (a, b) in original_graphics.iter().zip(&graphics) {
+        let same_shape = match (&a.element_type, &b.element_type) {
+            (GraphicType::Line { start: s1, end: e1 }, GraphicType::Line { start: s2, end: e2 }) => {
+                close_point(*s1, *s2) && close_point(*e1, *e2)
+            }
+            (GraphicType::Circle { center: c1, radius: r1 }, GraphicType::Circle { center: c2, radius: r2 }) => {
+                close_point(*c1, *c2) && close(*r1, *r2)
+            }
+            (GraphicType::Rectangle { bounds: b1 }, GraphicType::Rectangle { bounds: b2 }) => {
+                close_point((b1.min_x, b1.min_y), (b2.min_x, b2.min_y)) && close_point((b1.max_x, b1.max_y), (b2.max_x, b2.max_y))
+            }
+            _ => false,
+        };
+        let same = same_shape
+            && a.kicad_layer() == b.kicad_layer()
+            && close(a.stroke.width, b.stroke.width)
+            && same_kind(&a.stroke.stroke_type, &b.stroke.stroke_type);
+        if !same {
+            return mismatch("graphic", a, b);
+        }
+    }
+    Ok(())
+}
+
+/// One edit of a written file
+#[derive(Debug, Clone)]
+enum Damage {
+    Remove(Index),
+    Insert(Index, u8),
+    Replace(Index, u8),
+    Truncate(Index),
+}
+
+fn damage() -> impl Strategy<Value = Damage> {
+    prop_oneof![
+        any::<Index>().prop_map(Damage::Remove),
+        (any::<Index>(), select(b"()\"\\ -.e9".to_vec())).prop_map(|(at, byte)| Damage::Insert(at, byte)),
+        (any::<Index>(), any::<u8>()).prop_map(|(at, byte)| Damage::Replace(at, byte)),
+        any::<Index>().prop_map(Damage::Truncate),
+    ]
+}
+
+fn apply(text: String, damages: &[Damage]) -> String {
+    let mut bytes = text.into_bytes();
+    for damage in damages {
+        if bytes.is_empty() {
+            break;
+        }
+        match *damage {
+            Damage::Remove(at) => {
+                bytes.remove(at.index(bytes.len()));
+            }
+            Damage::Insert(at, byte) => bytes.insert(at.index(bytes.len() + 1), byte),
+            Damage::Replace(at, byte) => {
+                let at = at.index(bytes.len());
+                bytes[at] = byte;
+            }
+            Damage::Truncate(at) => bytes.truncate(at.index(bytes.len())),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(1000))]
+
+    #[test]
+    fn written_footprints_parse_back_the_same(footprint in footprint()) {
+        compare(&footprint)?;
+    }
+
+    #[test]
+    fn damaged_files_are_rejected_without_panicking(footprint in footprint(), damages in prop::collection::vec(damage(), 1..8)) {
+        let damaged = apply(to_kicad_footprint(&footprint), &damages);
+        // Whatever parses must also survive being read, written and parsed again
+        if let Ok(parsed) = KiCadFootprint::parse(&damaged, ParseOptions::default()) {
+            parsed.pad_descriptors();
+            parsed.fp_text_elements();
+            parsed.graphic_elements();
+            let written = parsed.to_kicad_string();
+            prop_assert!(KiCadFootprint::parse(&written, ParseOptions::default()).is_ok(), "{}", written);
+        }
+    }
+}
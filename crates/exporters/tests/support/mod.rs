@@ -0,0 +1,254 @@
+//! Random footprint generators shared by the property tests
+//!
+//! The footprints have pads of every shape, some through hole with a padstack, texts
+//! with justification, and lines, rectangles and circles with every stroke type. Names,
+//! descriptions and texts are built from strings that need escaping and coordinates
+//! include negative zero, values near f32 epsilon and the extent of a large board.
+
+use copper_substrate::prelude::*;
+use proptest::prelude::*;
+use proptest::sample::select;
+use uuid::Uuid;
+/// Strings the writer has to escape or that look like syntax
+const AWKWARD: &[&str] = &["", "\"", "\\", "\n", "a\"b", "()", "(x)", " ", "\t", "é€", "\\n", "#", "${REFERENCE}", "hide", "\\\""];
+
+#[derive(Debug, Clone)]
+pub struct RandomFootprint {
+    pub name: String,
+    pub description: Option<String>,
+    pub tags: Option<String>,
+    pub pads: Vec<PadDescriptor>,
+    pub texts: Vec<FpText>,
+    pub graphics: Vec<GraphicElement>,
+}
+
+impl BoardComposableObject for RandomFootprint {
+    fn is_smt(&self) -> bool {
+        true
+    }
+
+    fn is_electrical(&self) -> bool {
+        true
+    }
+
+    fn terminal_count(&self) -> usize {
+        self.pads.len()
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        FunctionalType::Resistor("10k".to_string())
+    }
+
+    fn footprint_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn library_name(&self) -> String {
+        "Random".to_string()
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        Rectangle { min_x: -1.0, min_y: -1.0, max_x: 1.0, max_y: 1.0 }
+    }
+
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        self.pads.clone()
+    }
+
+    fn description(&self) -> Option<String> {
+        self.description.clone()
+    }
+
+    fn tags(&self) -> Option<String> {
+        self.tags.clone()
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        self.texts.clone()
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        self.graphics.clone()
+    }
+
+    fn model_3d(&self) -> Option<Model3D> {
+        None
+    }
+}
+
+/// Up to three pieces, each an awkward string or a plain word
+fn text() -> impl Strategy<Value = String> {
+    let piece = prop_oneof![select(AWKWARD).prop_map(String::from), (0..100u32).prop_map(|n| format!("w{}", n))];
+    prop::collection::vec(piece, 0..4).prop_map(|pieces| pieces.concat())
+}
+
+/// A value in [low, high), or one rounded, tiny, negative zero or stretched
+fn number(low: f32, high: f32) -> impl Strategy<Value = f32> {
+    prop_oneof![
+        4 => low..high,
+        1 => (low..high).prop_map(|value| (value * 100.0).round() / 100.0),
+        1 => (low..high).prop_map(|value| value / 1000.0),
+        1 => Just(-0.0f32),
+        1 => (low..high).prop_map(|value| value * 1e-6),
+        1 => (low..high).prop_map(|value| value * 20.0),
+        1 => (low..high).prop_map(|value| value * f32::EPSILON),
+    ]
+}
+
+fn size(low: f32, high: f32) -> impl Strategy<Value = f32> {
+    number(low, high).prop_map(move |value| value.abs().max(low))
+}
+
+fn point(extent: f32) -> impl Strategy<Value = (f32, f32)> {
+    (number(-extent, extent), number(-extent, extent))
+}
+
+fn angle() -> impl Strategy<Value = Option<f32>> {
+    prop::option::of(number(0.1, 359.0))
+}
+
+fn pad_shape() -> impl Strategy<Value = PadShape> {
+    prop_oneof![
+        Just(PadShape::Circle),
+        Just(PadShape::Rect),
+        Just(PadShape::Oval),
+        Just(PadShape::RoundRect),
+        number(3.0, 5.0).prop_map(|x| PadShape::Custom(vec![(-3.0, -3.0), (3.0, -3.0), (x, 3.0), (-3.0, 3.0)])),
+    ]
+}
+
+/// A pad with its own number, or `None` to be numbered by position
+fn pad() -> impl Strategy<Value = (Option<PadNumber>, PadDescriptor)> {
+    let fab_property = select(vec![
+        None,
+        Some(PadFabProperty::Heatsink),
+        Some(PadFabProperty::Mechanical),
+        Some(PadFabProperty::TestPoint),
+        Some(PadFabProperty::LocalFiducial),
+        Some(PadFabProperty::GlobalFiducial),
+    ]);
+    let numbered = prop_oneof![2 => Just(None), 1 => text().prop_map(|number| Some(PadNumber::from(number)))];
+    (
+        numbered,
+        prop::bool::weighted(1.0 / 3.0),
+        any::<bool>(),
+        (size(0.1, 5.0), size(0.1, 5.0)),
+        pad_shape(),
+        number(0.0, 0.5),
+        point(50.0),
+        angle(),
+        fab_property,
+    )
+        .prop_map(|(number, through_hole, stacked, size, shape, ratio, position, rotation, fab_property)| {
+            let padstack = (through_hole && stacked)
+                .then(|| PadStack::inner(PadLayerGeometry::new(PadShape::Circle, (size.0 * 0.9, size.0 * 0.9))));
+            let pad = PadDescriptor {
+                number: PadNumber::default(),
+                pin_function: None,
+                pad_type: if through_hole { PadType::ThroughHole } else { PadType::SMD },
+                roundrect_ratio: matches!(shape, PadShape::RoundRect).then(|| ratio.abs()),
+                shape,
+                position,
+                rotation,
+                size,
+                drill_size: through_hole.then(|| size.0.min(size.1) / 2.0),
+                drill_slot: None,
+                layers: if through_hole { PadLayers::tht() } else { PadLayers::smd_front() },
+                tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+                padstack,
+                fab_property,
+                solder_mask_margin: None,
+                routing_hint: None,
+                uuid: Uuid::nil(),
+            };
+            (number, pad)
+        })
+}
+
+fn fp_text() -> impl Strategy<Value = FpText> {
+    let justify = (
+        select(vec![HorizontalJustify::Left, HorizontalJustify::Center, HorizontalJustify::Right]),
+        select(vec![VerticalJustify::Top, VerticalJustify::Center, VerticalJustify::Bottom]),
+        any::<bool>(),
+    )
+        .prop_map(|(horizontal, vertical, mirror)| Justify::new(horizontal, vertical).with_mirror(mirror));
+    (
+        text(),
+        point(50.0),
+        angle(),
+        select(vec!["F.SilkS", "F.Fab", "B.SilkS", "User.1"]),
+        (size(0.2, 3.0), size(0.2, 3.0)),
+        size(0.05, 0.5),
+        any::<bool>(),
+        prop::option::of(justify),
+    )
+        .prop_map(|(text, position, rotation, layer, font_size, thickness, hidden, justify)| FpText {
+            text_type: FpTextType::User,
+            text,
+            position,
+            rotation,
+            layer: layer.into(),
+            uuid: Uuid::nil(),
+            font: FontSettings { size: font_size, thickness },
+            hidden,
+            justify,
+            role: None,
+        })
+}
+
+fn graphic() -> impl Strategy<Value = GraphicElement> {
+    let element_type = prop_oneof![
+        (point(9.0), point(9.0)).prop_map(|(start, end)| GraphicType::Line { start, end }),
+        (point(9.0), size(0.1, 5.0)).prop_map(|(center, radius)| GraphicType::Circle { center, radius }),
+        (size(0.1, 3.0), size(0.1, 3.0)).prop_map(|(half_x, half_y)| GraphicType::Rectangle {
+            bounds: Rectangle { min_x: -half_x, min_y: -half_y, max_x: half_x, max_y: half_y }
+        }),
+    ];
+    (
+        element_type,
+        // Courtyards are regenerated on export, so only drawn layers round trip
+        select(vec![LayerType::SilkScreen, LayerType::Fabrication]),
+        size(0.01, 0.5),
+        select(vec![StrokeType::Solid, StrokeType::Dashed, StrokeType::Dotted]),
+    )
+        .prop_map(|(element_type, layer, width, stroke_type)| GraphicElement {
+            element_type,
+            layer,
+            side: Side::Front,
+            stroke: Stroke { width, stroke_type },
+            uuid: Uuid::nil(),
+            role: None,
+        })
+}
+
+/// Items are numbered and given UUIDs by position, the first texts being the reference
+/// and value
+pub fn footprint() -> impl Strategy<Value = RandomFootprint> {
+    (
+        text(),
+        prop::option::of(text()),
+        prop::option::of(text()),
+        prop::collection::vec(pad(), 0..6),
+        prop::collection::vec(fp_text(), 0..4),
+        prop::collection::vec(graphic(), 0..4),
+    )
+        .prop_map(|(name, description, tags, pads, mut texts, mut graphics)| {
+            let pads = pads
+                .into_iter()
+                .enumerate()
+                .map(|(index, (number, mut pad))| {
+                    pad.number = number.unwrap_or_else(|| (index + 1).to_string().into());
+                    pad.uuid = Uuid::from_u128(index as u128);
+                    pad
+                })
+                .collect();
+            for (index, text) in texts.iter_mut().enumerate() {
+                text.text_type = [FpTextType::Reference, FpTextType::Value, FpTextType::User][index.min(2)].clone();
+                text.uuid = Uuid::from_u128(1 << 124 | index as u128);
+            }
+            for (index, graphic) in graphics.iter_mut().enumerate() {
+                graphic.uuid = Uuid::from_u128(2 << 124 | index as u128);
+            }
+            RandomFootprint { name: format!("FP{}", name), description, tags, pads, texts, graphics }
+        })
+}
@@ -0,0 +1,83 @@
+//! Golden SVG renderings of the generators and fixtures
+//!
+//! Each footprint is rendered with `to_svg` and compared with its golden under
+//! `tests/goldens/`, see `svg_golden`. A failure leaves the rendering and a side-by-side
+//! diff in `target/svg-goldens/`; after reviewing them, accept intended changes with
+//! `COPPER_BLESS_GOLDENS=1 cargo test -p copper-exporters --test svg_goldens`.
+//!
+//! Every footprint of a test is checked before the test fails, so one run reports, and
+//! blesses, them all.
+
+use std::path::Path;
+
+use copper_exporters::kicad_footprint_parse::{KiCadFootprint, ParseOptions};
+use copper_exporters::svg_golden::GoldenSet;
+use copper_substrate::prelude::*;
+use copper_substrate::solder_jumper::JumperBridge;
+
+fn goldens() -> GoldenSet {
+    GoldenSet::new(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/goldens"))
+}
+
+/// Check every footprint, then fail with all the mismatches at once
+fn check_all(footprints: Vec<(&str, Box<dyn BoardComposableObject>)>) {
+    let goldens = goldens();
+    let failures: Vec<String> = footprints
+        .iter()
+        .filter_map(|(name, footprint)| goldens.check(name, footprint.as_ref()).err())
+        .map(|error| error.to_string())
+        .collect();
+    assert!(failures.is_empty(), "\n{}", failures.join("\n\n"));
+}
+
+fn fixture(file: &str) -> KiCadFootprint {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples/fixtures").join(file);
+    KiCadFootprint::read(&path, ParseOptions::default()).unwrap()
+}
+
+/// Minimums above everything the fixtures' silkscreen has
+fn strict_silk() -> FabProfile {
+    FabProfile { min_silk_line_width: 0.25, min_silk_text_height: 1.2, min_silk_text_thickness: 0.18, ..FabProfile::standard() }
+}
+
+#[test]
+fn parametric_generators_match_their_goldens() {
+    let resistor = FunctionalType::Resistor("10k".to_string());
+    check_all(vec![
+        ("chip_0402", Box::new(ChipFootprint::standard(resistor.clone(), "0402").unwrap())),
+        ("chip_0805", Box::new(ChipFootprint::standard(resistor, "0805").unwrap())),
+        ("soic_8", Box::new(Soic::new(8, 1.27, 4.9, 3.9))),
+        ("qfn_16_ep", Box::new(Qfn::new(16, 0.5, 3.0, 3.0).with_exposed_pad(Some((1.7, 1.7))))),
+        ("dip_8", Box::new(Dip::new(8).unwrap())),
+        ("crystal_3225", Box::new(Crystal::standard("3225").unwrap())),
+        ("crystal_hc49u", Box::new(Crystal::hc49u())),
+        ("dpak_to252_3", Box::new(Dpak::standard("TO-252", 3).unwrap())),
+        ("mounting_hole_m3", Box::new(MountingHole::for_screw("M3").unwrap())),
+        ("solder_jumper_3way", Box::new(SolderJumper::three_way(JumperBridge::Open))),
+    ]);
+}
+
+#[test]
+fn imported_fixtures_match_their_goldens() {
+    check_all(vec![
+        ("snapeda_qfn_16", Box::new(fixture("snapeda_QFN-16_3x3.kicad_mod"))),
+        ("snapeda_soic_8", Box::new(fixture("snapeda_SOIC127P600X175-8N.kicad_mod"))),
+        ("ultralibrarian_sot_23", Box::new(fixture("ultralibrarian_SOT-23.kicad_mod"))),
+    ]);
+}
+
+#[test]
+fn generated_courtyards_and_silkscreen_match_their_goldens() {
+    let soic = || Soic::new(8, 1.27, 4.9, 3.9);
+    check_all(vec![
+        // Pads on both sides get a courtyard each
+        ("courtyard_edge_mount_sma", Box::new(EdgeMountSma::new(1.6))),
+        ("courtyard_dip_8_socket", Box::new(Dip::new(8).unwrap().with_socket())),
+        ("silk_pin1_dot", Box::new(soic().with_pin1(Pin1Style::Dot))),
+        ("silk_pin1_chamfer", Box::new(soic().with_pin1(Pin1Style::Chamfer))),
+        ("silk_pin1_line", Box::new(soic().with_pin1(Pin1Style::Line))),
+        ("silk_pin1_triangle", Box::new(soic().with_pin1(Pin1Style::Triangle))),
+        ("silk_sized_soic_8", Box::new(SilkSized::new(soic(), &strict_silk()))),
+        ("silk_sized_snapeda_qfn_16", Box::new(fixture("snapeda_QFN-16_3x3.kicad_mod").with_silk_minimums(&strict_silk()))),
+    ]);
+}
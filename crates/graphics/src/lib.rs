@@ -295,6 +295,141 @@ impl Default for PcbStackRenderer {
     }
 }
 
+/// One board of a multi-board assembly, placed like `BoardPlacement` in copper-substrate
+/// and the `placement` entries of an assembly JSON
+#[derive(Debug, Clone)]
+pub struct AssemblyBoardView {
+    pub name: String,
+    /// Board outline extent along X and Y, millimeters
+    pub width: f32,
+    pub height: f32,
+    pub thickness: f32,
+    /// Board origin in assembly millimeters: X right, Y down the board, Z up
+    pub position: (f32, f32, f32),
+    /// Rotation about Z in degrees, counter-clockwise as on the board
+    pub rotation: f32,
+    /// Tilt of the board about its X axis in degrees, the flex fold
+    pub fold: f32,
+    pub color: Srgba,
+}
+
+impl AssemblyBoardView {
+    /// A flat board at the assembly origin
+    pub fn new(name: &str, width: f32, height: f32, thickness: f32) -> Self {
+        Self {
+            name: name.to_string(),
+            width,
+            height,
+            thickness,
+            position: (0.0, 0.0, 0.0),
+            rotation: 0.0,
+            fold: 0.0,
+            color: Srgba::new(30, 110, 50, 255),
+        }
+    }
+
+    /// Place the board origin at `position` in the assembly
+    pub fn with_placement(mut self, position: (f32, f32, f32), rotation: f32, fold: f32) -> Self {
+        self.position = position;
+        self.rotation = rotation;
+        self.fold = fold;
+        self
+    }
+
+    /// Scene position of a board point `depth` below the top surface. The assembly frame
+    /// is left handed (Y down, Z up), so Y and Z swap on the way to three-d's Y up.
+    pub fn scene_point(&self, x: f32, y: f32, depth: f32) -> Vec3 {
+        let (fold_sin, fold_cos) = self.fold.to_radians().sin_cos();
+        let (folded_y, z) = (y * fold_cos + depth * fold_sin, y * fold_sin - depth * fold_cos);
+        let (sin, cos) = (-self.rotation.to_radians()).sin_cos();
+        let (ax, ay) = (x * cos - folded_y * sin, x * sin + folded_y * cos);
+        let (px, py, pz) = self.position;
+        vec3(px + ax, pz + z, py + ay)
+    }
+}
+
+/// Renders the boards of an assembly as solid slabs, folded boards at their angle
+pub struct AssemblyRenderer {
+    pub boards: Vec<AssemblyBoardView>,
+    rendered_boards: Vec<Gm<Mesh, PhysicalMaterial>>,
+}
+
+impl AssemblyRenderer {
+    /// Create an empty assembly renderer
+    pub fn new() -> Self {
+        Self {
+            boards: Vec::new(),
+            rendered_boards: Vec::new(),
+        }
+    }
+
+    /// Add a board to the assembly
+    pub fn add_board(&mut self, board: AssemblyBoardView) {
+        self.boards.push(board);
+    }
+
+    /// Build the board meshes from the board definitions
+    pub fn build(&mut self, context: &Context) {
+        self.rendered_boards.clear();
+
+        for board in &self.boards {
+            let (w, h, t) = (board.width, board.height, board.thickness);
+            // Same corner order as `LayerMeshFactory::create_layer_mesh`, with board Y
+            // along the mesh Z and the thickness along the mesh Y
+            let positions = vec![
+                board.scene_point(0.0, 0.0, t),
+                board.scene_point(w, 0.0, t),
+                board.scene_point(w, 0.0, 0.0),
+                board.scene_point(0.0, 0.0, 0.0),
+                board.scene_point(0.0, h, t),
+                board.scene_point(w, h, t),
+                board.scene_point(w, h, 0.0),
+                board.scene_point(0.0, h, 0.0),
+            ];
+            let indices = vec![
+                0, 2, 1, 0, 3, 2,
+                4, 5, 6, 4, 6, 7,
+                0, 1, 5, 0, 5, 4,
+                2, 7, 6, 2, 3, 7,
+                0, 4, 7, 0, 7, 3,
+                1, 2, 6, 1, 6, 5,
+            ];
+
+            let mut cpu_mesh = CpuMesh {
+                positions: Positions::F32(positions),
+                indices: Indices::U32(indices),
+                ..Default::default()
+            };
+            cpu_mesh.compute_normals();
+
+            let material = MaterialFactory::create_opaque_material(context, board.color, 0.7, 0.0);
+            self.rendered_boards.push(Gm::new(Mesh::new(context, &cpu_mesh), material));
+        }
+    }
+
+    /// Get reference to rendered boards for drawing
+    pub fn rendered_boards(&self) -> &[Gm<Mesh, PhysicalMaterial>] {
+        &self.rendered_boards
+    }
+
+    /// Highest point of the assembly above the first board's top side
+    pub fn total_height(&self) -> f32 {
+        self.boards
+            .iter()
+            .flat_map(|board| {
+                [(0.0, 0.0), (board.width, 0.0), (0.0, board.height), (board.width, board.height)]
+                    .map(|(x, y)| board.scene_point(x, y, 0.0).y)
+            })
+            .fold(0.0, f32::max)
+    }
+}
+
+impl Default for AssemblyRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Predefined layer configurations
 pub mod presets {
     use super::*;
@@ -405,6 +540,18 @@ pub mod presets {
         
         stack
     }
+
+    /// Two 50 x 30 mm boards joined by a flex, the second folded back over the first
+    /// 6 mm above it, as in the flex_assembly example of copper-exporters
+    pub fn flex_fold_assembly() -> AssemblyRenderer {
+        let mut assembly = AssemblyRenderer::new();
+        assembly.add_board(AssemblyBoardView::new("main", 50.0, 30.0, 1.6));
+        assembly.add_board(
+            AssemblyBoardView::new("display", 50.0, 30.0, 1.6)
+                .with_placement((0.0, 30.0, 6.0), 0.0, 180.0)
+        );
+        assembly
+    }
 }
 
 /// Macro for easily creating layer stacks
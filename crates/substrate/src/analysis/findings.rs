@@ -0,0 +1,471 @@
+//! Severities and suppressions for check findings
+//!
+//! Every violation type converts into a `Finding`: a rule name, the footprint or reference
+//! designator it concerns, optionally the element within it, a severity and a message.
+//! Findings also carry where they are and the elements involved (`ElementRef`), so a
+//! viewer can take the user to them and highlight what is wrong.
+//! A `FindingPolicy` then remaps rule severities and suppresses intentional deviations
+//! (silkscreen under a shield, a deliberately off-grid test point). Each suppression must
+//! carry a justification. Suppressions that match nothing are reported as stale so they
+//! don't outlive the problem they excused.
+//!
+//! Policies are usually loaded from TOML:
+//!
+//! ```toml
+//! [severity]
+//! placement_rotation = "info"
+//!
+//! [[suppress]]
+//! rule = "placement_off_grid"
+//! footprint = "TP1"
+//! justification = "Test point follows the probe fixture, not the grid"
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Deserialize;
+
+use crate::board_interface::BoundingBoxMismatch;
+use crate::geometry::Point;
+use crate::placement::PlacementViolation;
+use crate::thieving::ClearanceViolation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// An element a finding concerns
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ElementRef {
+    /// Index into the footprint's `pad_descriptors()`
+    Pad(usize),
+    /// Index into the footprint's `graphic_elements()`
+    Graphic(usize),
+    /// Index into the footprint's `fp_text_elements()`
+    Text(usize),
+    /// Index into `Board::tracks`
+    Track(usize),
+    /// Index into `Board::arcs`
+    Arc(usize),
+    /// Index into `Board::vias`
+    Via(usize),
+    /// Pad of a placed component, named "reference.number"
+    BoardPad(String),
+    /// Placed component by reference designator
+    Component(String),
+}
+
+impl fmt::Display for ElementRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ElementRef::Pad(index) => write!(f, "pad {}", index),
+            ElementRef::Graphic(index) => write!(f, "graphic {}", index),
+            ElementRef::Text(index) => write!(f, "text {}", index),
+            ElementRef::Track(index) => write!(f, "track {}", index),
+            ElementRef::Arc(index) => write!(f, "arc {}", index),
+            ElementRef::Via(index) => write!(f, "via {}", index),
+            ElementRef::BoardPad(name) => write!(f, "pad {}", name),
+            ElementRef::Component(reference) => f.write_str(reference),
+        }
+    }
+}
+
+/// One problem reported by a check
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    /// Stable rule name, e.g. "placement_off_grid"
+    pub rule: String,
+    /// Footprint name or reference designator the finding concerns, "board" for board
+    /// level objects
+    pub footprint: String,
+    /// Element within the footprint, e.g. a pad number
+    pub element: Option<String>,
+    pub severity: Severity,
+    pub message: String,
+    /// Where the problem is, in footprint coordinates for footprint checks and board
+    /// coordinates for board checks
+    pub location: Option<Point>,
+    /// Elements to highlight
+    pub elements: Vec<ElementRef>,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}] {}", self.severity, self.rule, self.message)
+    }
+}
+
+/// A check result that can be reported as a `Finding`
+pub trait Violation: fmt::Display {
+    fn rule(&self) -> &'static str;
+    /// Severity before any policy remapping
+    fn default_severity(&self) -> Severity;
+    fn footprint(&self) -> String;
+    fn element(&self) -> Option<String> {
+        None
+    }
+    /// See `Finding::location`
+    fn location(&self) -> Option<Point> {
+        None
+    }
+    fn elements(&self) -> Vec<ElementRef> {
+        Vec::new()
+    }
+
+    fn to_finding(&self) -> Finding {
+        Finding {
+            rule: self.rule().to_string(),
+            footprint: self.footprint(),
+            element: self.element(),
+            severity: self.default_severity(),
+            message: self.to_string(),
+            location: self.location(),
+            elements: self.elements(),
+        }
+    }
+}
+
+impl Violation for PlacementViolation {
+    fn rule(&self) -> &'static str {
+        match self {
+            PlacementViolation::OffGrid { .. } => "placement_off_grid",
+            PlacementViolation::DisallowedRotation { .. } => "placement_rotation",
+        }
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn footprint(&self) -> String {
+        self.reference().to_string()
+    }
+
+    fn location(&self) -> Option<Point> {
+        let (x, y) = self.position();
+        Some(Point::new(x as f32, y as f32))
+    }
+
+    fn elements(&self) -> Vec<ElementRef> {
+        vec![ElementRef::Component(self.reference().to_string())]
+    }
+}
+
+impl Violation for ClearanceViolation {
+    fn rule(&self) -> &'static str {
+        "thieving_clearance"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    /// The component of the pad it is too close to, or "board" for the edge
+    fn footprint(&self) -> String {
+        match self.object.strip_prefix("pad ").and_then(|pad| pad.rsplit_once('.')) {
+            Some((reference, _)) => reference.to_string(),
+            None => "board".to_string(),
+        }
+    }
+
+    fn element(&self) -> Option<String> {
+        match self.object.strip_prefix("pad ").and_then(|pad| pad.rsplit_once('.')) {
+            Some((_, number)) => Some(number.to_string()),
+            None => Some(self.object.clone()),
+        }
+    }
+
+    fn location(&self) -> Option<Point> {
+        Some(self.location)
+    }
+
+    fn elements(&self) -> Vec<ElementRef> {
+        self.object.strip_prefix("pad ").map(|pad| ElementRef::BoardPad(pad.to_string())).into_iter().collect()
+    }
+}
+
+impl Violation for BoundingBoxMismatch {
+    fn rule(&self) -> &'static str {
+        "bounding_box_mismatch"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn footprint(&self) -> String {
+        self.footprint.clone()
+    }
+
+    /// Center of the box computed from the footprint
+    fn location(&self) -> Option<Point> {
+        let computed = &self.computed;
+        Some(Point::new((computed.min_x + computed.max_x) / 2.0, (computed.min_y + computed.max_y) / 2.0))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PolicyError {
+    Parse(String),
+    /// A suppression without a justification
+    MissingJustification { rule: String, footprint: String },
+}
+
+impl fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyError::Parse(message) => write!(f, "invalid finding policy: {}", message),
+            PolicyError::MissingJustification { rule, footprint } => {
+                write!(f, "suppression of {} on {} has no justification", rule, footprint)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PolicyError {}
+
+/// An accepted deviation: findings of `rule` on `footprint` (and `element`, when given)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Suppression {
+    pub rule: String,
+    pub footprint: String,
+    #[serde(default)]
+    pub element: Option<String>,
+    pub justification: String,
+}
+
+impl Suppression {
+    pub fn new(rule: &str, footprint: &str, justification: &str) -> Self {
+        Self { rule: rule.to_string(), footprint: footprint.to_string(), element: None, justification: justification.to_string() }
+    }
+
+    /// Only suppress findings on this element of the footprint
+    pub fn with_element(mut self, element: &str) -> Self {
+        self.element = Some(element.to_string());
+        self
+    }
+
+    pub fn matches(&self, finding: &Finding) -> bool {
+        self.rule == finding.rule
+            && self.footprint == finding.footprint
+            && self.element.as_ref().is_none_or(|element| finding.element.as_ref() == Some(element))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyFile {
+    #[serde(default)]
+    severity: BTreeMap<String, Severity>,
+    #[serde(default)]
+    suppress: Vec<Suppression>,
+}
+
+/// Severity overrides by rule and the accepted deviations
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FindingPolicy {
+    pub severities: BTreeMap<String, Severity>,
+    pub suppressions: Vec<Suppression>,
+}
+
+impl FindingPolicy {
+    pub fn from_toml(text: &str) -> Result<Self, PolicyError> {
+        let file: PolicyFile = toml::from_str(text).map_err(|e| PolicyError::Parse(e.to_string().trim_end().to_string()))?;
+        let policy = Self { severities: file.severity, suppressions: file.suppress };
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    pub fn with_severity(mut self, rule: &str, severity: Severity) -> Self {
+        self.severities.insert(rule.to_string(), severity);
+        self
+    }
+
+    pub fn with_suppression(mut self, suppression: Suppression) -> Self {
+        self.suppressions.push(suppression);
+        self
+    }
+
+    /// Every suppression must say why
+    pub fn validate(&self) -> Result<(), PolicyError> {
+        match self.suppressions.iter().find(|s| s.justification.trim().is_empty()) {
+            Some(s) => Err(PolicyError::MissingJustification { rule: s.rule.clone(), footprint: s.footprint.clone() }),
+            None => Ok(()),
+        }
+    }
+
+    /// Remap severities, then split the findings into reported and suppressed ones
+    pub fn apply(&self, findings: impl IntoIterator<Item = Finding>) -> PolicyReport {
+        let mut used = vec![false; self.suppressions.len()];
+        let mut report = PolicyReport::default();
+        for mut finding in findings {
+            if let Some(severity) = self.severities.get(&finding.rule) {
+                finding.severity = *severity;
+            }
+            match self.suppressions.iter().position(|s| s.matches(&finding)) {
+                Some(index) => {
+                    used[index] = true;
+                    report.suppressed.push((finding, self.suppressions[index].clone()));
+                }
+                None => report.reported.push(finding),
+            }
+        }
+        report.stale = self.suppressions.iter().zip(used).filter(|(_, used)| !used).map(|(s, _)| s.clone()).collect();
+        report
+    }
+}
+
+/// Findings after a policy was applied
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PolicyReport {
+    pub reported: Vec<Finding>,
+    /// Suppressed findings with the suppression that matched them
+    pub suppressed: Vec<(Finding, Suppression)>,
+    /// Suppressions that matched no finding
+    pub stale: Vec<Suppression>,
+}
+
+impl PolicyReport {
+    pub fn count(&self, severity: Severity) -> usize {
+        self.reported.iter().filter(|finding| finding.severity == severity).count()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.count(Severity::Error) > 0
+    }
+
+    /// Process exit code for batch runs: nonzero only when errors remain
+    pub fn exit_code(&self) -> i32 {
+        if self.has_errors() { 1 } else { 0 }
+    }
+}
+
+impl fmt::Display for PolicyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for finding in &self.reported {
+            writeln!(f, "{}", finding)?;
+        }
+        for suppression in &self.stale {
+            let element = suppression.element.as_ref().map(|e| format!(" {}", e)).unwrap_or_default();
+            writeln!(f, "stale suppression: {} on {}{} no longer matches", suppression.rule, suppression.footprint, element)?;
+        }
+        write!(
+            f,
+            "{} errors, {} warnings, {} info, {} suppressed",
+            self.count(Severity::Error),
+            self.count(Severity::Warning),
+            self.count(Severity::Info),
+            self.suppressed.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::layer_type::{BoardLayer, LayerType};
+    use crate::placement::PlacementPolicy;
+    use crate::presets;
+
+    fn too_close_to(object: &str) -> ClearanceViolation {
+        ClearanceViolation { layer: BoardLayer::front(LayerType::Copper), location: Point::new(1.0, 2.0), object: object.to_string(), distance: 0.1 }
+    }
+
+    fn shield_policy() -> FindingPolicy {
+        FindingPolicy::default().with_suppression(Suppression::new("thieving_clearance", "J1", "Shield pad, thieving stops at the can").with_element("1"))
+    }
+
+    #[test]
+    fn one_of_two_findings_of_a_rule_is_suppressed() {
+        let findings = [too_close_to("pad J1.1"), too_close_to("pad J1.2")].map(|violation| violation.to_finding());
+        let report = shield_policy().apply(findings);
+        assert_eq!(report.reported.len(), 1, "{}", report);
+        assert_eq!(report.reported[0].element.as_deref(), Some("2"));
+        assert_eq!(report.suppressed.len(), 1);
+        assert_eq!(report.suppressed[0].0.element.as_deref(), Some("1"));
+        assert!(report.stale.is_empty());
+        assert_eq!(report.exit_code(), 1);
+    }
+
+    #[test]
+    fn fixing_a_suppressed_finding_makes_its_suppression_stale() {
+        let report = shield_policy().apply([too_close_to("pad J1.2").to_finding()]);
+        assert_eq!(report.reported.len(), 1);
+        assert!(report.suppressed.is_empty());
+        assert_eq!(report.stale, shield_policy().suppressions);
+        assert!(report.to_string().contains("stale suppression: thieving_clearance on J1 1 no longer matches"), "{}", report);
+    }
+
+    #[test]
+    fn suppressing_placements_from_an_audit() {
+        let mut board = Board::new("audit");
+        board.place("R1", presets::resistor_0603("10k"), (1.234, 5.6), 0.0).unwrap();
+        board.place("TP1", presets::resistor_0603("10k"), (3.333, 5.6), 0.0).unwrap();
+        board.set_policy(PlacementPolicy::new(0.05, 90.0).unwrap());
+        let policy = FindingPolicy::default()
+            .with_suppression(Suppression::new("placement_off_grid", "TP1", "Test point follows the probe fixture, not the grid"));
+
+        let report = policy.apply(board.audit_placements().iter().map(Violation::to_finding));
+        assert_eq!(report.reported.iter().map(|finding| finding.footprint.as_str()).collect::<Vec<_>>(), ["R1"]);
+        assert_eq!(report.suppressed.len(), 1);
+        // Warnings alone don't fail a batch run
+        assert_eq!((report.count(Severity::Warning), report.exit_code()), (1, 0));
+
+        board.component_mut("TP1").unwrap().position = (3.35, 5.6);
+        let report = policy.apply(board.audit_placements().iter().map(Violation::to_finding));
+        assert_eq!(report.stale.len(), 1);
+        assert!(report.suppressed.is_empty());
+    }
+
+    #[test]
+    fn severities_are_remapped_before_suppression() {
+        let policy = FindingPolicy::default().with_severity("thieving_clearance", Severity::Info);
+        let report = policy.apply([too_close_to("board edge").to_finding()]);
+        assert_eq!(report.reported[0].severity, Severity::Info);
+        assert_eq!(report.reported[0].footprint, "board");
+        assert_eq!(report.exit_code(), 0);
+    }
+
+    #[test]
+    fn policies_load_from_toml() {
+        let policy = FindingPolicy::from_toml(
+            r#"
+            [severity]
+            placement_rotation = "info"
+
+            [[suppress]]
+            rule = "thieving_clearance"
+            footprint = "J1"
+            element = "1"
+            justification = "Shield pad, thieving stops at the can"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(policy, shield_policy().with_severity("placement_rotation", Severity::Info));
+    }
+
+    #[test]
+    fn suppressions_need_a_justification() {
+        let text = "[[suppress]]\nrule = \"placement_off_grid\"\nfootprint = \"TP1\"\njustification = \" \"\n";
+        let error = FindingPolicy::from_toml(text).unwrap_err();
+        assert_eq!(error, PolicyError::MissingJustification { rule: "placement_off_grid".to_string(), footprint: "TP1".to_string() });
+        assert!(matches!(FindingPolicy::from_toml("[[suppress]]\nrule = \"x\"\nfootprint = \"TP1\"\n"), Err(PolicyError::Parse(_))));
+    }
+}
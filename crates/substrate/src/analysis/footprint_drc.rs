@@ -0,0 +1,589 @@
+//! Design rule check of a single footprint
+//!
+//! Checks what a board house and the assembly line will object to before the footprint
+//! reaches a board: pads and drills below the `FabProfile` minimums, annular rings too thin
+//! to drill reliably, copper gaps between pads of different numbers below the pad
+//! clearance, numbers repeated on pads the footprint doesn't declare as one terminal or a
+//! jumper (see `pad_groups`), silkscreen graphics and text drawn over the solder mask openings of pads
+//! (text by its `stroke_font` strokes), silkscreen strokes and visible texts
+//! below the profile's silkscreen minimums, and assembled parts without any silkscreen.
+//! Pads of an accidental duplicate are held to the pad clearance as if numbered apart,
+//! pads of a net tie are not.
+//! Parts no longer than `ULTRA_SMALL_BODY_LENGTH` (0201, 01005) are exempt from the last:
+//! KLC leaves their silkscreen out, there is no room for it. Every violation
+//! carries the pad or graphic indices involved and a location in footprint coordinates, so
+//! a viewer can take the user to it.
+
+use std::fmt;
+
+use crate::board_interface::{
+    BoardComposableObject, GraphicElement, GraphicType, PadDescriptor, BOUNDING_BOX_TOLERANCE,
+};
+use crate::chip::ULTRA_SMALL_BODY_LENGTH;
+use crate::fab_profile::FabProfile;
+use crate::geometry::{point_in_polygon, point_segment_distance, polygon_distance, push_arc, segment_distance, Point};
+use crate::layer_type::{BoardLayer, LayerType};
+use crate::pad_groups::{pad_groups, PadGroupKind, PadGroupingError};
+use crate::padstack::PadLayerClass;
+
+use super::findings::{ElementRef, Finding, Severity, Violation};
+use super::spacing::SPACING_ARC_TOLERANCE;
+
+/// Copper to copper gap between pads of different numbers a standard fab etches reliably
+pub const DEFAULT_PAD_CLEARANCE: f32 = 0.15;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FootprintViolation {
+    /// A pad narrower than the fab's smallest pad
+    PadTooSmall { footprint: String, pad: usize, number: String, size: (f32, f32), minimum: f32, location: Point },
+    DrillTooSmall { footprint: String, pad: usize, number: String, drill: f32, minimum: f32, location: Point },
+    /// Copper left around a plated hole on the pad's narrow side
+    AnnularRing { footprint: String, pad: usize, number: String, ring: f32, minimum: f32, location: Point },
+    /// A number on pads the footprint doesn't declare as one terminal or a jumper, located
+    /// at the first of them
+    DuplicatePadNumber { footprint: String, pads: Vec<usize>, number: String, location: Point },
+    /// The footprint's `PadGrouping` doesn't fit its pads
+    InvalidPadGrouping { footprint: String, error: PadGroupingError },
+    /// Two pads with different numbers closer than the pad clearance
+    PadClearance { footprint: String, pads: (usize, usize), numbers: (String, String), gap: f32, minimum: f32, location: Point },
+    /// A silkscreen stroke over the solder mask opening of a pad, which the fab clips
+    SilkOverPad { footprint: String, graphic: usize, pad: usize, number: String, location: Point },
+    /// A stroke of a visible silkscreen text over the solder mask opening of a pad
+    SilkTextOverPad { footprint: String, text: usize, content: String, pad: usize, number: String, location: Point },
+    /// An assembled part with nothing on its silkscreen, located at the body center
+    MissingSilk { footprint: String, body: (f32, f32), location: Point },
+    /// A silkscreen stroke thinner than the fab prints
+    SilkLineTooThin { footprint: String, graphic: usize, width: f32, minimum: f32, location: Point },
+    /// Visible silkscreen text lower or thinner than the fab prints legibly; `minimum` is
+    /// (height, thickness)
+    SilkTextTooSmall { footprint: String, text: usize, content: String, height: f32, thickness: f32, minimum: (f32, f32), location: Point },
+}
+
+impl fmt::Display for FootprintViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FootprintViolation::PadTooSmall { number, size, minimum, .. } => {
+                write!(f, "pad {} is {:.3} x {:.3} mm, smaller than {:.3} mm", number, size.0, size.1, minimum)
+            }
+            FootprintViolation::DrillTooSmall { number, drill, minimum, .. } => {
+                write!(f, "pad {} drill is {:.3} mm, {:.3} mm required", number, drill, minimum)
+            }
+            FootprintViolation::AnnularRing { number, ring, minimum, .. } => {
+                write!(f, "pad {} annular ring is {:.3} mm, {:.3} mm required", number, ring, minimum)
+            }
+            FootprintViolation::DuplicatePadNumber { pads, number, .. } => {
+                write!(f, "{} pads are numbered {} without being declared one terminal", pads.len(), number)
+            }
+            FootprintViolation::InvalidPadGrouping { error, .. } => write!(f, "pad grouping: {}", error),
+            FootprintViolation::PadClearance { numbers, gap, minimum, .. } => {
+                write!(f, "pads {} and {} are {:.3} mm apart, {:.3} mm required", numbers.0, numbers.1, gap, minimum)
+            }
+            FootprintViolation::SilkOverPad { graphic, number, .. } => {
+                write!(f, "silkscreen graphic {} is drawn over pad {}", graphic, number)
+            }
+            FootprintViolation::SilkTextOverPad { content, number, .. } => {
+                write!(f, "silkscreen text \"{}\" is drawn over pad {}", content, number)
+            }
+            FootprintViolation::MissingSilk { body, .. } => {
+                write!(f, "no silkscreen on a {:.2} x {:.2} mm body", body.0, body.1)
+            }
+            FootprintViolation::SilkLineTooThin { graphic, width, minimum, .. } => {
+                write!(f, "silkscreen graphic {} is {:.3} mm wide, {:.3} mm required", graphic, width, minimum)
+            }
+            FootprintViolation::SilkTextTooSmall { content, height, thickness, minimum, .. } => write!(
+                f,
+                "silkscreen text \"{}\" is {:.3} mm high and {:.3} mm thick, {:.3} mm and {:.3} mm required",
+                content, height, thickness, minimum.0, minimum.1
+            ),
+        }
+    }
+}
+
+impl Violation for FootprintViolation {
+    fn rule(&self) -> &'static str {
+        match self {
+            FootprintViolation::PadTooSmall { .. } => "pad_size",
+            FootprintViolation::DrillTooSmall { .. } => "drill_size",
+            FootprintViolation::AnnularRing { .. } => "annular_ring",
+            FootprintViolation::DuplicatePadNumber { .. } => "duplicate_pad_number",
+            FootprintViolation::InvalidPadGrouping { .. } => "pad_grouping",
+            FootprintViolation::PadClearance { .. } => "pad_clearance",
+            FootprintViolation::SilkOverPad { .. } | FootprintViolation::SilkTextOverPad { .. } => "silk_over_pad",
+            FootprintViolation::MissingSilk { .. } => "silk_missing",
+            FootprintViolation::SilkLineTooThin { .. } => "silk_line_width",
+            FootprintViolation::SilkTextTooSmall { .. } => "silk_text_size",
+        }
+    }
+
+    fn default_severity(&self) -> Severity {
+        match self {
+            FootprintViolation::SilkOverPad { .. }
+            | FootprintViolation::SilkTextOverPad { .. }
+            | FootprintViolation::MissingSilk { .. }
+            | FootprintViolation::SilkLineTooThin { .. }
+            | FootprintViolation::SilkTextTooSmall { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+
+    fn footprint(&self) -> String {
+        match self {
+            FootprintViolation::PadTooSmall { footprint, .. }
+            | FootprintViolation::DrillTooSmall { footprint, .. }
+            | FootprintViolation::AnnularRing { footprint, .. }
+            | FootprintViolation::DuplicatePadNumber { footprint, .. }
+            | FootprintViolation::InvalidPadGrouping { footprint, .. }
+            | FootprintViolation::PadClearance { footprint, .. }
+            | FootprintViolation::SilkOverPad { footprint, .. }
+            | FootprintViolation::SilkTextOverPad { footprint, .. }
+            | FootprintViolation::MissingSilk { footprint, .. }
+            | FootprintViolation::SilkLineTooThin { footprint, .. }
+            | FootprintViolation::SilkTextTooSmall { footprint, .. } => footprint.clone(),
+        }
+    }
+
+    fn element(&self) -> Option<String> {
+        match self {
+            FootprintViolation::PadTooSmall { number, .. }
+            | FootprintViolation::DrillTooSmall { number, .. }
+            | FootprintViolation::AnnularRing { number, .. }
+            | FootprintViolation::DuplicatePadNumber { number, .. }
+            | FootprintViolation::SilkOverPad { number, .. }
+            | FootprintViolation::SilkTextOverPad { number, .. } => Some(number.clone()),
+            FootprintViolation::PadClearance { numbers, .. } => Some(format!("{} / {}", numbers.0, numbers.1)),
+            FootprintViolation::SilkTextTooSmall { content, .. } => Some(content.clone()),
+            FootprintViolation::InvalidPadGrouping { .. } | FootprintViolation::MissingSilk { .. } | FootprintViolation::SilkLineTooThin { .. } => None,
+        }
+    }
+
+    fn location(&self) -> Option<Point> {
+        match self {
+            FootprintViolation::PadTooSmall { location, .. }
+            | FootprintViolation::DrillTooSmall { location, .. }
+            | FootprintViolation::AnnularRing { location, .. }
+            | FootprintViolation::DuplicatePadNumber { location, .. }
+            | FootprintViolation::PadClearance { location, .. }
+            | FootprintViolation::SilkOverPad { location, .. }
+            | FootprintViolation::SilkTextOverPad { location, .. }
+            | FootprintViolation::MissingSilk { location, .. }
+            | FootprintViolation::SilkLineTooThin { location, .. }
+            | FootprintViolation::SilkTextTooSmall { location, .. } => Some(*location),
+            FootprintViolation::InvalidPadGrouping { .. } => None,
+        }
+    }
+
+    fn elements(&self) -> Vec<ElementRef> {
+        match self {
+            FootprintViolation::PadTooSmall { pad, .. }
+            | FootprintViolation::DrillTooSmall { pad, .. }
+            | FootprintViolation::AnnularRing { pad, .. } => vec![ElementRef::Pad(*pad)],
+            FootprintViolation::DuplicatePadNumber { pads, .. } => pads.iter().map(|&pad| ElementRef::Pad(pad)).collect(),
+            FootprintViolation::PadClearance { pads, .. } => vec![ElementRef::Pad(pads.0), ElementRef::Pad(pads.1)],
+            FootprintViolation::SilkOverPad { graphic, pad, .. } => vec![ElementRef::Graphic(*graphic), ElementRef::Pad(*pad)],
+            FootprintViolation::SilkTextOverPad { text, pad, .. } => vec![ElementRef::Text(*text), ElementRef::Pad(*pad)],
+            FootprintViolation::SilkLineTooThin { graphic, .. } => vec![ElementRef::Graphic(*graphic)],
+            FootprintViolation::SilkTextTooSmall { text, .. } => vec![ElementRef::Text(*text)],
+            FootprintViolation::InvalidPadGrouping { .. } | FootprintViolation::MissingSilk { .. } => Vec::new(),
+        }
+    }
+}
+
+/// Limits a footprint is checked against
+#[derive(Debug, Clone, PartialEq)]
+pub struct FootprintDrc {
+    pub profile: FabProfile,
+    pub pad_clearance: f32,
+    /// Largest body side (mm) of parts allowed to have no silkscreen
+    pub silk_exempt_body: f32,
+    /// Solder mask expansion per side for pads without their own, see
+    /// `PadDescriptor::mask_aperture`
+    pub mask_margin: f32,
+}
+
+impl Default for FootprintDrc {
+    fn default() -> Self {
+        Self::new(FabProfile::default())
+    }
+}
+
+impl FootprintDrc {
+    pub fn new(profile: FabProfile) -> Self {
+        Self { profile, pad_clearance: DEFAULT_PAD_CLEARANCE, silk_exempt_body: ULTRA_SMALL_BODY_LENGTH, mask_margin: 0.0 }
+    }
+
+    pub fn with_pad_clearance(mut self, clearance: f32) -> Self {
+        self.pad_clearance = clearance;
+        self
+    }
+
+    pub fn with_silk_exempt_body(mut self, max_body: f32) -> Self {
+        self.silk_exempt_body = max_body;
+        self
+    }
+
+    pub fn with_mask_margin(mut self, margin: f32) -> Self {
+        self.mask_margin = margin;
+        self
+    }
+
+    /// Every violation, pad by pad in pad order, then pad numbering, then pad pairs, then
+    /// silkscreen, then silkscreen sizes
+    pub fn check<T: BoardComposableObject + ?Sized>(&self, component: &T) -> Vec<FootprintViolation> {
+        let footprint = component.footprint_name();
+        let pads = component.pad_descriptors();
+        let outlines: Vec<Vec<Point>> = pads.iter().map(|pad| pad.outline_polygon(SPACING_ARC_TOLERANCE)).collect();
+        let mut violations = Vec::new();
+
+        for (index, pad) in pads.iter().enumerate() {
+            let number = pad.number.to_string();
+            let location = Point::from(pad.position);
+            if pad.has_copper() && pad.size.0.min(pad.size.1) < self.profile.min_pad_size - f32::EPSILON {
+                violations.push(FootprintViolation::PadTooSmall {
+                    footprint: footprint.clone(),
+                    pad: index,
+                    number: number.clone(),
+                    size: pad.size,
+                    minimum: self.profile.min_pad_size,
+                    location,
+                });
+            }
+            let Some(drill) = pad.drill_size else {
+                continue;
+            };
+            if drill < self.profile.min_drill - f32::EPSILON {
+                violations.push(FootprintViolation::DrillTooSmall {
+                    footprint: footprint.clone(),
+                    pad: index,
+                    number: number.clone(),
+                    drill,
+                    minimum: self.profile.min_drill,
+                    location,
+                });
+            }
+            let ring = pad.annular_ring(PadLayerClass::Front).unwrap_or((pad.size.0.min(pad.size.1) - drill) / 2.0);
+            if !pad.is_npth() && ring < self.profile.min_annular_ring - f32::EPSILON {
+                violations.push(FootprintViolation::AnnularRing {
+                    footprint: footprint.clone(),
+                    pad: index,
+                    number,
+                    ring,
+                    minimum: self.profile.min_annular_ring,
+                    location,
+                });
+            }
+        }
+
+        let grouping = component.pad_grouping();
+        if let Err(error) = grouping.validate(&pads) {
+            violations.push(FootprintViolation::InvalidPadGrouping { footprint: footprint.clone(), error });
+        }
+        let groups = pad_groups(&pads, &grouping);
+        for group in groups.iter().filter(|group| group.kind == PadGroupKind::Undeclared) {
+            violations.push(FootprintViolation::DuplicatePadNumber {
+                footprint: footprint.clone(),
+                pads: group.pads.clone(),
+                number: group.number.clone(),
+                location: Point::from(pads[group.pads[0]].position),
+            });
+        }
+        let undeclared = |number: &str| groups.iter().any(|group| group.number == number && group.kind == PadGroupKind::Undeclared);
+
+        for a in 0..pads.len() {
+            for b in a + 1..pads.len() {
+                let (pad_a, pad_b) = (&pads[a], &pads[b]);
+                // Pads sharing a number are one terminal unless that is a mistake, pads of a
+                // net tie are joined on purpose, unnumbered ones belong to no net
+                let numbered = !pad_a.number.is_empty() && !pad_b.number.is_empty();
+                let distinct = pad_a.number != pad_b.number || undeclared(&pad_a.number);
+                let tied = grouping.net_tie_of(&pad_a.number).is_some_and(|tie| grouping.net_tie_of(&pad_b.number) == Some(tie));
+                if !numbered || !distinct || tied || !share_copper(pad_a, pad_b) {
+                    continue;
+                }
+                let gap = polygon_distance(&outlines[a], &outlines[b]);
+                if gap < self.pad_clearance - f32::EPSILON {
+                    let (a_center, b_center) = (Point::from(pad_a.position), Point::from(pad_b.position));
+                    let location = Point::new((a_center.x + b_center.x) / 2.0, (a_center.y + b_center.y) / 2.0);
+                    violations.push(FootprintViolation::PadClearance {
+                        footprint: footprint.clone(),
+                        pads: (a, b),
+                        numbers: (pad_a.number.to_string(), pad_b.number.to_string()),
+                        gap,
+                        minimum: self.pad_clearance,
+                        location,
+                    });
+                }
+            }
+        }
+
+        let graphics = component.graphic_elements();
+        let body = component.bounding_box();
+        let body_size = (body.width(), body.height());
+        let has_silk = graphics.iter().any(|element| element.layer == LayerType::SilkScreen);
+        if !has_silk && component.is_assembled() && body_size.0.max(body_size.1) > self.silk_exempt_body + f32::EPSILON {
+            let location = Point::new((body.min_x + body.max_x) / 2.0, (body.min_y + body.max_y) / 2.0);
+            violations.push(FootprintViolation::MissingSilk { footprint: footprint.clone(), body: body_size, location });
+        }
+        for (graphic, element) in graphics.iter().enumerate() {
+            if element.layer != LayerType::SilkScreen {
+                continue;
+            }
+            let mask_layer = BoardLayer::new(LayerType::Mask, element.side).to_kicad_string();
+            for (index, location) in self.strokes_over_pads(&pads, &mask_layer, &stroke_segments(element), element.stroke.width) {
+                violations.push(FootprintViolation::SilkOverPad {
+                    footprint: footprint.clone(),
+                    graphic,
+                    pad: index,
+                    number: pads[index].number.to_string(),
+                    location,
+                });
+            }
+        }
+        // Texts by the strokes they are plotted with, see `stroke_font`
+        for (text, fp_text) in component.fp_text_elements().iter().enumerate() {
+            if fp_text.hidden || !fp_text.layer.ends_with(".SilkS") {
+                continue;
+            }
+            let mask_layer = if fp_text.layer.starts_with("B.") { "B.Mask" } else { "F.Mask" };
+            let segments: Vec<(Point, Point)> = fp_text
+                .strokes()
+                .iter()
+                .flat_map(|stroke| if stroke.len() == 1 { vec![(stroke[0], stroke[0])] } else { stroke.windows(2).map(|pair| (pair[0], pair[1])).collect() })
+                .collect();
+            for (index, location) in self.strokes_over_pads(&pads, mask_layer, &segments, fp_text.font.thickness) {
+                violations.push(FootprintViolation::SilkTextOverPad {
+                    footprint: footprint.clone(),
+                    text,
+                    content: fp_text.text.clone(),
+                    pad: index,
+                    number: pads[index].number.to_string(),
+                    location,
+                });
+            }
+        }
+        violations.extend(self.silk_size_violations(component));
+        violations
+    }
+
+    /// Silkscreen strokes and visible silkscreen texts below the profile's minimums, in
+    /// graphic then text order. Filled shapes drawn without an outline are left out.
+    pub fn silk_size_violations<T: BoardComposableObject + ?Sized>(&self, component: &T) -> Vec<FootprintViolation> {
+        let footprint = component.footprint_name();
+        let mut violations = Vec::new();
+        for (graphic, element) in component.graphic_elements().iter().enumerate() {
+            let filled = matches!(element.element_type, GraphicType::Polygon { fill: Some(_), .. });
+            let width = element.stroke.width;
+            if element.layer != LayerType::SilkScreen || (filled && width <= 0.0) {
+                continue;
+            }
+            if width < self.profile.min_silk_line_width - f32::EPSILON {
+                let location = stroke_segments(element)
+                    .first()
+                    .map_or(Point::default(), |(start, end)| Point::new((start.x + end.x) / 2.0, (start.y + end.y) / 2.0));
+                violations.push(FootprintViolation::SilkLineTooThin {
+                    footprint: footprint.clone(),
+                    graphic,
+                    width,
+                    minimum: self.profile.min_silk_line_width,
+                    location,
+                });
+            }
+        }
+        let minimum = (self.profile.min_silk_text_height, self.profile.min_silk_text_thickness);
+        for (index, text) in component.fp_text_elements().iter().enumerate() {
+            if text.hidden || !text.layer.ends_with(".SilkS") {
+                continue;
+            }
+            let (height, thickness) = (text.font.size.1, text.font.thickness);
+            if height < minimum.0 - f32::EPSILON || thickness < minimum.1 - f32::EPSILON {
+                violations.push(FootprintViolation::SilkTextTooSmall {
+                    footprint: footprint.clone(),
+                    text: index,
+                    content: text.text.clone(),
+                    height,
+                    thickness,
+                    minimum,
+                    location: Point::from(text.position),
+                });
+            }
+        }
+        violations
+    }
+
+    /// Pads whose opening on `mask_layer` a stroke of `width` along `segments` reaches, with
+    /// the point of the first such stroke nearest the pad center
+    fn strokes_over_pads(&self, pads: &[PadDescriptor], mask_layer: &str, segments: &[(Point, Point)], width: f32) -> Vec<(usize, Point)> {
+        let openings = pads.iter().enumerate().filter(|(_, pad)| pad.has_layer(mask_layer)).filter_map(|(index, pad)| {
+            pad.mask_aperture(self.mask_margin, SPACING_ARC_TOLERANCE).map(|aperture| (index, pad, aperture))
+        });
+        let mut over = Vec::new();
+        for (index, pad, aperture) in openings {
+            let first = segments.iter().find(|(start, end)| segment_polygon_distance(*start, *end, &aperture) < width / 2.0);
+            if let Some((start, end)) = first {
+                over.push((index, point_segment_distance(Point::from(pad.position), *start, *end).1));
+            }
+        }
+        over
+    }
+
+    /// `check` and the bounding box comparison, as findings
+    pub fn findings<T: BoardComposableObject + ?Sized>(&self, component: &T) -> Vec<Finding> {
+        let mut findings: Vec<Finding> = self.check(component).iter().map(Violation::to_finding).collect();
+        findings.extend(component.bounding_box_mismatch(BOUNDING_BOX_TOLERANCE).map(|mismatch| mismatch.to_finding()));
+        findings
+    }
+}
+
+/// Whether the two pads have copper on a common outer layer
+fn share_copper(a: &PadDescriptor, b: &PadDescriptor) -> bool {
+    a.has_copper() && b.has_copper() && ["F.Cu", "B.Cu"].iter().any(|layer| a.has_layer(layer) && b.has_layer(layer))
+}
+
+/// Center lines of a graphic's strokes; dimensions are left out
+fn stroke_segments(element: &GraphicElement) -> Vec<(Point, Point)> {
+    let closed = |points: Vec<Point>| -> Vec<(Point, Point)> {
+        (0..points.len()).map(|i| (points[i], points[(i + 1) % points.len()])).collect()
+    };
+    match &element.element_type {
+        GraphicType::Line { start, end } => vec![(Point::from(*start), Point::from(*end))],
+        GraphicType::Rectangle { bounds } => closed(vec![
+            Point::new(bounds.min_x, bounds.min_y),
+            Point::new(bounds.max_x, bounds.min_y),
+            Point::new(bounds.max_x, bounds.max_y),
+            Point::new(bounds.min_x, bounds.max_y),
+        ]),
+        GraphicType::Circle { center, radius } => {
+            let mut points = vec![Point::new(center.0 + radius, center.1)];
+            push_arc(&mut points, Point::from(*center), *radius, 0.0, 360.0, SPACING_ARC_TOLERANCE);
+            points.pop();
+            closed(points)
+        }
+        GraphicType::Polygon { points, .. } => closed(points.iter().copied().map(Point::from).collect()),
+        GraphicType::Dimension(_) => Vec::new(),
+    }
+}
+
+/// Distance from a segment to a closed polygon, zero when it enters it
+fn segment_polygon_distance(start: Point, end: Point, polygon: &[Point]) -> f32 {
+    if point_in_polygon(start, polygon) || point_in_polygon(end, polygon) {
+        return 0.0;
+    }
+    (0..polygon.len())
+        .map(|i| segment_distance(start, end, polygon[i], polygon[(i + 1) % polygon.len()]))
+        .fold(f32::INFINITY, f32::min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_interface::{FpText, FpTextType};
+    use crate::chip::ChipFootprint;
+    use crate::footprint::Footprint;
+    use crate::dpak::Dpak;
+    use crate::edge_mount::EdgeMountSma;
+    use crate::functional_types::FunctionalType;
+    use crate::pad_groups::{diff_pads, PadGrouping};
+    use crate::roles::ElementRole;
+    use crate::soic::Soic;
+    use crate::solder_jumper::SolderJumper;
+
+    fn silk_over_pads<T: BoardComposableObject + ?Sized>(component: &T, mask_margin: f32) -> Vec<usize> {
+        let drc = FootprintDrc::default().with_mask_margin(mask_margin);
+        drc.check(component)
+            .into_iter()
+            .filter_map(|violation| match violation {
+                FootprintViolation::SilkOverPad { graphic, .. } => Some(graphic),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn silk_over_pad_follows_the_mask_opening() {
+        let chip = ChipFootprint::standard(FunctionalType::Resistor("10k".to_string()), "0805").unwrap();
+        // The generated silkscreen is about 0.21 mm plus its half width from the rounded
+        // pad corners, so only openings grown by more than that reach it
+        assert!(silk_over_pads(&chip, 0.0).is_empty());
+        assert!(silk_over_pads(&chip, 0.1).is_empty());
+        assert!(silk_over_pads(&chip, 0.2).is_empty());
+        assert_eq!(silk_over_pads(&chip, 0.25), [1, 1, 2, 2]);
+
+        // A line 0.04 mm from pad 1's copper is over the opening unless the mask shrinks
+        let pad = chip.pad_descriptors()[0].outline_aabb();
+        let x = pad.max_x + 0.04;
+        let line = GraphicElement::new(GraphicType::Line { start: (x, pad.min_y), end: (x, pad.max_y) }, LayerType::SilkScreen, 0.12);
+        let probed = chip.replace_role(ElementRole::UserDefined("probe".to_string()), vec![line]);
+        let probe = probed.graphic_elements().len() - 1;
+        assert_eq!(silk_over_pads(&probed, 0.0), [probe]);
+        assert!(silk_over_pads(&probed, -0.05).is_empty());
+        assert_eq!(silk_over_pads(&probed, 0.1).iter().filter(|&&graphic| graphic == probe).count(), 1);
+    }
+
+    #[test]
+    fn silk_text_over_a_pad_is_found_by_its_strokes() {
+        let chip = ChipFootprint::standard(FunctionalType::Resistor("10k".to_string()), "0805").unwrap();
+        let text_over = |component: &dyn BoardComposableObject| {
+            FootprintDrc::default()
+                .check(component)
+                .into_iter()
+                .filter_map(|violation| match violation {
+                    FootprintViolation::SilkTextOverPad { number, .. } => Some(number),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        assert!(text_over(&chip).is_empty());
+
+        // Text centered between the pads: "1" fits the gap, "11111" reaches both pads
+        let pads = chip.pad_descriptors();
+        let gap = pads[1].outline_aabb().min_x - pads[0].outline_aabb().max_x;
+        let labelled = |content: &str| {
+            let mut footprint = Footprint::capture(&chip);
+            footprint.texts.push(FpText::new(FpTextType::User, content, (0.0, 0.0), "F.SilkS"));
+            footprint
+        };
+        assert!(labelled("1").texts.last().unwrap().stroke_bounds().unwrap().width() < gap);
+        assert!(text_over(&labelled("1")).is_empty());
+        assert_eq!(text_over(&labelled("11111")), ["1", "2"]);
+        // On the fab layer it is no concern of the silkscreen
+        let mut fab = labelled("11111");
+        fab.texts.last_mut().unwrap().layer = "F.Fab".into();
+        assert!(text_over(&fab).is_empty());
+    }
+
+    #[test]
+    fn a_net_tie_passes_and_an_undeclared_duplicate_does_not() {
+        let rules = |component: &dyn BoardComposableObject| FootprintDrc::default().check(component).iter().map(Violation::rule).collect::<Vec<_>>();
+        // The bridged jumper's link reaches into pad 2, tying the two nets on purpose
+        let bridged = SolderJumper::two_way(true);
+        assert_eq!(bridged.pad_grouping(), PadGrouping::new().with_net_tie(&["1", "2"]));
+        assert!(rules(&bridged).is_empty(), "{:?}", FootprintDrc::default().check(&bridged));
+        assert!(diff_pads(&bridged.pad_descriptors(), &bridged.pad_descriptors()).is_empty());
+        let mut untied = Footprint::capture(&bridged);
+        untied.pad_grouping = PadGrouping::new();
+        assert_eq!(rules(&untied), ["pad_clearance"]);
+        untied.pad_grouping = PadGrouping::new().with_net_tie(&["1", "4"]);
+        assert_eq!(rules(&untied), ["pad_grouping", "pad_clearance"]);
+
+        // Repeated numbers declared as one terminal
+        assert!(!rules(&Dpak::standard("TO-252", 3).unwrap()).contains(&"duplicate_pad_number"));
+        assert!(!rules(&EdgeMountSma::new(1.6)).contains(&"duplicate_pad_number"));
+
+        // Pad 2 of a SOIC numbered 1 by mistake
+        let mut soic = Footprint::capture(&Soic::new(8, 1.27, 4.9, 3.9));
+        soic.pads[1].number = "1".into();
+        let duplicates = |component: &Footprint| {
+            FootprintDrc::default().check(component).into_iter().filter(|violation| violation.rule() == "duplicate_pad_number").collect::<Vec<_>>()
+        };
+        assert_eq!(
+            duplicates(&soic),
+            [FootprintViolation::DuplicatePadNumber { footprint: soic.name.clone(), pads: vec![0, 1], number: "1".to_string(), location: soic.pads[0].position.into() }]
+        );
+        // The mistaken pads are held to the pad clearance, declared ones are one terminal
+        soic.pads[1].position.1 = soic.pads[0].position.1 + soic.pads[0].size.1 + 0.1;
+        let clearance = |component: &Footprint| rules(component).iter().filter(|&&rule| rule == "pad_clearance").count();
+        assert_eq!(clearance(&soic), 1);
+        soic.pad_grouping = PadGrouping::new().with_terminal("1");
+        assert!(duplicates(&soic).is_empty());
+        assert_eq!(clearance(&soic), 0);
+    }
+}
@@ -0,0 +1,18 @@
+//! Geometric and electrical analysis of footprints
+//!
+//! Read-only calculations over `BoardComposableObject`s that produce reports rather than
+//! modifying geometry.
+
+pub mod findings;
+pub mod footprint_drc;
+pub mod orientation;
+pub mod parasitics;
+pub mod pin_mapping;
+pub mod spacing;
+
+pub use findings::{ElementRef, Finding, FindingPolicy, PolicyError, PolicyReport, Severity, Suppression, Violation};
+pub use footprint_drc::{FootprintDrc, FootprintViolation, DEFAULT_PAD_CLEARANCE};
+pub use orientation::{OrientationEntry, OrientationIssue, OrientationReport};
+pub use parasitics::{pad_capacitance, pad_pair_inductance, ParasiticEstimate};
+pub use pin_mapping::{PinMappingIssue, PinMappingReport};
+pub use spacing::{PadGap, SpacingOptions, SpacingReport};
@@ -0,0 +1,358 @@
+//! Zero orientation audit
+//!
+//! Pick-and-place errors often come from footprints drawn with different zero rotations:
+//! one SOIC with pin 1 top left, another with it bottom left. The audit finds each
+//! footprint's pin 1 bearing at zero rotation. That is the direction from the center of
+//! the numbered copper pads to pad 1 (A1 on a BGA), in degrees counter-clockwise from +X
+//! as seen on screen, rounded to the nearest 45 degrees.
+//!
+//! Footprints are grouped by `PackageFamily`, guessed from the functional type and the
+//! footprint name. A footprint is reported when its bearing differs from:
+//! - the bearing most footprints of its family share, when more than half share one;
+//! - IPC-7351's zero orientation. That puts pin 1 top left (135 degrees) on SOIC, SOT,
+//!   QFP, QFN and BGA packages, and on the left (180 degrees) on polarized chips.
+//!   Connectors have no rule.
+//!
+//! Footprints of unknown family, and those without a pad 1, are listed but not judged.
+//!
+//! `OrientationReport::suggested_offsets` gives the rotation to add to each reported
+//! footprint's placement rotations in a pick-and-place file. This turns a part taken from
+//! the reel in IPC zero orientation onto the footprint. Without a rule, the family
+//! majority stands in for IPC.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::board::Board;
+use crate::board_interface::{BoardComposableObject, PadType};
+use crate::functional_types::FunctionalType;
+use crate::pin1::PackageFamily;
+
+use super::findings::{Severity, Violation};
+
+/// A footprint and its pin 1 bearing at zero rotation
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrientationEntry {
+    pub footprint: String,
+    pub family: Option<PackageFamily>,
+    /// Rounded to 45 degrees, `None` without a pad 1
+    pub bearing: Option<f32>,
+    /// References placing the footprint, empty when audited as a library
+    pub references: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrientationIssue {
+    /// Pin 1 points elsewhere than in most footprints of the family
+    FamilyOutlier { footprint: String, family: PackageFamily, bearing: f32, majority: f32 },
+    /// Pin 1 isn't where IPC-7351 puts it at zero rotation
+    IpcZero { footprint: String, family: PackageFamily, bearing: f32, expected: f32 },
+}
+
+impl fmt::Display for OrientationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrientationIssue::FamilyOutlier { footprint, family, bearing, majority } => {
+                write!(f, "{} has pin 1 at {}°, most {} footprints at {}°", footprint, bearing, family, majority)
+            }
+            OrientationIssue::IpcZero { footprint, family, bearing, expected } => {
+                write!(f, "{} has pin 1 at {}°, IPC-7351 puts {} pin 1 at {}°", footprint, bearing, family, expected)
+            }
+        }
+    }
+}
+
+impl Violation for OrientationIssue {
+    fn rule(&self) -> &'static str {
+        match self {
+            OrientationIssue::FamilyOutlier { .. } => "orientation_family_outlier",
+            OrientationIssue::IpcZero { .. } => "orientation_ipc_zero",
+        }
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn footprint(&self) -> String {
+        match self {
+            OrientationIssue::FamilyOutlier { footprint, .. } | OrientationIssue::IpcZero { footprint, .. } => footprint.clone(),
+        }
+    }
+}
+
+/// Pin 1 bearing IPC-7351 gives `family` at zero rotation
+pub fn ipc_zero_bearing(family: PackageFamily) -> Option<f32> {
+    match family {
+        PackageFamily::Chip => Some(180.0),
+        PackageFamily::Soic | PackageFamily::Sot | PackageFamily::Qfp | PackageFamily::Qfn | PackageFamily::Bga => Some(135.0),
+        PackageFamily::Connector => None,
+    }
+}
+
+/// The family of a footprint, from its functional type or failing that its name
+pub fn package_family<T: BoardComposableObject + ?Sized>(component: &T) -> Option<PackageFamily> {
+    match component.functional_type() {
+        FunctionalType::Connector(_) => return Some(PackageFamily::Connector),
+        FunctionalType::LED(_) | FunctionalType::Protection(_) if component.terminal_count() == 2 => {
+            return Some(PackageFamily::Chip);
+        }
+        _ => {}
+    }
+    let name = component.footprint_name().to_uppercase();
+    // SSOP, TSSOP and the like are SOICs; SOT before SOP so SOT-23 isn't one
+    [
+        ("BGA", PackageFamily::Bga),
+        ("QFN", PackageFamily::Qfn),
+        ("DFN", PackageFamily::Qfn),
+        ("QFP", PackageFamily::Qfp),
+        ("SOT", PackageFamily::Sot),
+        ("SOIC", PackageFamily::Soic),
+        ("SOP", PackageFamily::Soic),
+    ]
+    .into_iter()
+    .find(|(token, _)| name.contains(token))
+    .map(|(_, family)| family)
+}
+
+/// Direction from the center of the numbered copper pads to pad 1 (or A1), in degrees
+/// counter-clockwise from +X on screen, not rounded
+pub fn pin1_bearing<T: BoardComposableObject + ?Sized>(component: &T) -> Option<f32> {
+    let pads: Vec<_> = component
+        .pad_descriptors()
+        .into_iter()
+        .filter(|pad| !pad.number.is_empty() && !matches!(pad.pad_type, PadType::NPTH) && pad.has_copper())
+        .collect();
+    let pin1 = pads.iter().find(|pad| pad.number == "1").or_else(|| pads.iter().find(|pad| pad.number == "A1"))?;
+    let (xs, ys) = (pads.iter().map(|pad| pad.position.0), pads.iter().map(|pad| pad.position.1));
+    let center_x = (xs.clone().fold(f32::INFINITY, f32::min) + xs.fold(f32::NEG_INFINITY, f32::max)) / 2.0;
+    let center_y = (ys.clone().fold(f32::INFINITY, f32::min) + ys.fold(f32::NEG_INFINITY, f32::max)) / 2.0;
+    let (dx, dy) = (pin1.position.0 - center_x, pin1.position.1 - center_y);
+    if dx.hypot(dy) < 1e-6 {
+        return None;
+    }
+    // Y points down on screen
+    Some((-dy).atan2(dx).to_degrees().rem_euclid(360.0))
+}
+
+fn rounded_to(angle: f32, step: f32) -> f32 {
+    ((angle / step).round() * step).rem_euclid(360.0)
+}
+
+/// Zero orientation audit of a library or board, see the module documentation
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OrientationReport {
+    pub entries: Vec<OrientationEntry>,
+    pub issues: Vec<OrientationIssue>,
+}
+
+impl OrientationReport {
+    /// Audit library footprints, each by itself
+    pub fn for_footprints<'a>(footprints: impl IntoIterator<Item = &'a dyn BoardComposableObject>) -> Self {
+        let entries = footprints
+            .into_iter()
+            .map(|footprint| OrientationEntry {
+                footprint: footprint.footprint_name(),
+                family: package_family(footprint),
+                bearing: pin1_bearing(footprint).map(|bearing| rounded_to(bearing, 45.0)),
+                references: Vec::new(),
+            })
+            .collect();
+        Self::judge(entries)
+    }
+
+    /// Audit the footprints placed on `board`, each footprint name once
+    pub fn for_board(board: &Board) -> Self {
+        let mut entries: Vec<OrientationEntry> = Vec::new();
+        for placed in board.components() {
+            let footprint = placed.component.footprint_name();
+            if let Some(entry) = entries.iter_mut().find(|entry| entry.footprint == footprint) {
+                entry.references.push(placed.reference.clone());
+                continue;
+            }
+            let component = placed.component.as_ref();
+            entries.push(OrientationEntry {
+                footprint,
+                family: package_family(component),
+                bearing: pin1_bearing(component).map(|bearing| rounded_to(bearing, 45.0)),
+                references: vec![placed.reference.clone()],
+            });
+        }
+        Self::judge(entries)
+    }
+
+    fn judge(entries: Vec<OrientationEntry>) -> Self {
+        let mut issues = Vec::new();
+        for entry in &entries {
+            let (Some(family), Some(bearing)) = (entry.family, entry.bearing) else { continue };
+            if let Some(majority) = Self::majority(&entries, family).filter(|majority| *majority != bearing) {
+                issues.push(OrientationIssue::FamilyOutlier { footprint: entry.footprint.clone(), family, bearing, majority });
+            }
+            if let Some(expected) = ipc_zero_bearing(family).filter(|expected| *expected != bearing) {
+                issues.push(OrientationIssue::IpcZero { footprint: entry.footprint.clone(), family, bearing, expected });
+            }
+        }
+        Self { entries, issues }
+    }
+
+    /// The bearing more than half the judged footprints of `family` share
+    fn majority(entries: &[OrientationEntry], family: PackageFamily) -> Option<f32> {
+        let bearings: Vec<f32> = entries.iter().filter(|entry| entry.family == Some(family)).filter_map(|entry| entry.bearing).collect();
+        bearings
+            .iter()
+            .copied()
+            .find(|bearing| 2 * bearings.iter().filter(|other| *other == bearing).count() > bearings.len())
+    }
+
+    /// Whether every judged footprint agrees with its family and IPC-7351
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Degrees to add to the placement rotations of each reported footprint, by footprint
+    /// name, in multiples of 90; see the module documentation
+    pub fn suggested_offsets(&self) -> BTreeMap<String, f32> {
+        let mut offsets = BTreeMap::new();
+        for entry in &self.entries {
+            let (Some(family), Some(bearing)) = (entry.family, entry.bearing) else { continue };
+            let Some(reference) = ipc_zero_bearing(family).or_else(|| Self::majority(&self.entries, family)) else { continue };
+            let offset = rounded_to(bearing - reference, 90.0);
+            if offset != 0.0 {
+                offsets.insert(entry.footprint.clone(), offset);
+            }
+        }
+        offsets
+    }
+}
+
+impl fmt::Display for OrientationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.issues.is_empty() {
+            return writeln!(f, "{} footprints oriented consistently", self.entries.len());
+        }
+        for issue in &self.issues {
+            writeln!(f, "{}: {}", issue.default_severity(), issue)?;
+        }
+        for (footprint, offset) in self.suggested_offsets() {
+            writeln!(f, "rotate {} by {}°", footprint, offset)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_interface::{FpText, GraphicElement, Model3D, PadDescriptor, Rectangle};
+    use crate::chip::ChipFootprint;
+    use crate::geometry::Point;
+    use crate::qfn::Qfn;
+    use crate::soic::Soic;
+
+    /// A SOIC drawn a quarter turn counter-clockwise, pin 1 bottom left
+    struct Turned(Soic);
+
+    impl BoardComposableObject for Turned {
+        fn is_smt(&self) -> bool {
+            true
+        }
+
+        fn is_electrical(&self) -> bool {
+            true
+        }
+
+        fn terminal_count(&self) -> usize {
+            self.0.terminal_count()
+        }
+
+        fn functional_type(&self) -> FunctionalType {
+            self.0.functional_type()
+        }
+
+        fn footprint_name(&self) -> String {
+            format!("{}_Turned", self.0.footprint_name())
+        }
+
+        fn library_name(&self) -> String {
+            self.0.library_name()
+        }
+
+        fn bounding_box(&self) -> Rectangle {
+            let body = self.0.bounding_box();
+            Rectangle { min_x: body.min_y, min_y: body.min_x, max_x: body.max_y, max_y: body.max_x }
+        }
+
+        fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+            self.0
+                .pad_descriptors()
+                .into_iter()
+                .map(|pad| {
+                    let turned = Point::from(pad.position).rotated(90.0);
+                    PadDescriptor { position: (turned.x, turned.y), size: (pad.size.1, pad.size.0), ..pad }
+                })
+                .collect()
+        }
+
+        fn fp_text_elements(&self) -> Vec<FpText> {
+            Vec::new()
+        }
+
+        fn graphic_elements(&self) -> Vec<GraphicElement> {
+            Vec::new()
+        }
+
+        fn model_3d(&self) -> Option<Model3D> {
+            None
+        }
+    }
+
+    #[test]
+    fn generators_put_pin1_where_ipc_does() {
+        let led = ChipFootprint::standard(FunctionalType::LED("red".to_string()), "0603").unwrap();
+        let qfn = Qfn::new(16, 0.5, 3.0, 3.0).with_exposed_pad(Some((1.7, 1.7)));
+        let soic = Soic::new(8, 1.27, 4.9, 3.9);
+        let footprints: [&dyn BoardComposableObject; 3] = [&led, &qfn, &soic];
+        let report = OrientationReport::for_footprints(footprints);
+        assert!(report.is_clean(), "{}", report);
+        let bearings: Vec<_> = report.entries.iter().map(|entry| (entry.family, entry.bearing)).collect();
+        assert_eq!(
+            bearings,
+            [
+                (Some(PackageFamily::Chip), Some(180.0)),
+                (Some(PackageFamily::Qfn), Some(135.0)),
+                (Some(PackageFamily::Soic), Some(135.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_turned_soic_stands_out_among_correct_ones() {
+        let (narrow, wide, long) = (Soic::new(8, 1.27, 4.9, 3.9), Soic::new(16, 1.27, 10.3, 7.5), Soic::new(14, 1.27, 8.65, 3.9));
+        let turned = Turned(Soic::new(8, 1.27, 4.9, 3.9));
+        let footprints: [&dyn BoardComposableObject; 4] = [&narrow, &turned, &wide, &long];
+        let report = OrientationReport::for_footprints(footprints);
+        let name = turned.footprint_name();
+        assert_eq!(
+            report.issues,
+            [
+                OrientationIssue::FamilyOutlier { footprint: name.clone(), family: PackageFamily::Soic, bearing: 225.0, majority: 135.0 },
+                OrientationIssue::IpcZero { footprint: name.clone(), family: PackageFamily::Soic, bearing: 225.0, expected: 135.0 },
+            ]
+        );
+        assert_eq!(report.suggested_offsets(), BTreeMap::from([(name, 90.0)]));
+        assert_eq!(report.issues[0].to_finding().rule, "orientation_family_outlier");
+    }
+
+    #[test]
+    fn a_board_lists_each_footprint_once() {
+        let mut board = Board::new("orientation").with_rectangular_outline(30.0, 30.0);
+        board.place("U1", Soic::new(8, 1.27, 4.9, 3.9), (5.0, 5.0), 0.0).unwrap();
+        board.place("U2", Turned(Soic::new(8, 1.27, 4.9, 3.9)), (15.0, 5.0), 90.0).unwrap();
+        board.place("U3", Soic::new(8, 1.27, 4.9, 3.9), (25.0, 5.0), 0.0).unwrap();
+        let report = OrientationReport::for_board(&board);
+        assert_eq!(report.entries.len(), 2);
+        assert_eq!(report.entries[0].references, ["U1", "U3"]);
+        // Two footprints, one each way: no majority, only the IPC rule
+        assert!(matches!(report.issues[..], [OrientationIssue::IpcZero { .. }]), "{}", report);
+    }
+}
@@ -0,0 +1,183 @@
+//! Rough pad parasitics for signal integrity estimates
+//!
+//! These are closed form approximations intended to be within a factor of two of a field
+//! solver, good enough to tell whether a footprint's pads matter at a given edge rate.
+//!
+//! * Pad capacitance uses the parallel plate formula `C = e0 * er * A / h` with the pad
+//!   dimensions each grown by `h` to account for fringing fields (`A = (w + h)(l + h)`).
+//! * Pad pair inductance treats the path between the two pad centers as a strip of the
+//!   pads' narrower width over the reference plane and uses the Hammerstad/Wheeler
+//!   microstrip per-unit-length inductance, which is independent of the dielectric:
+//!   `L' = mu0 / (w/h + 1.393 + 0.667 ln(w/h + 1.444))` for `w/h >= 1` and
+//!   `L' = (mu0 / 2pi) ln(8h/w + w/4h)` for narrower strips.
+//!
+//! Lengths are taken in millimeters, results are returned in picofarads and nanohenries.
+
+use std::fmt;
+
+use crate::board_interface::PadDescriptor;
+use crate::stackup::{Stackup, StackupError};
+
+/// Permittivity of free space in F/m
+pub const EPSILON_0: f64 = 8.854_187_812_8e-12;
+/// Permeability of free space in H/m
+pub const MU_0: f64 = 1.256_637_062_12e-6;
+
+/// A value together with the assumptions it was computed under
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParasiticEstimate {
+    pub value: f64,
+    pub unit: &'static str,
+    pub assumptions: Vec<String>,
+}
+
+impl fmt::Display for ParasiticEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.4} {} ({})", self.value, self.unit, self.assumptions.join("; "))
+    }
+}
+
+/// Capacitance of a pad on `layer` to its reference plane, in pF
+pub fn pad_capacitance(pad: &PadDescriptor, stackup: &Stackup, layer: &str) -> Result<ParasiticEstimate, StackupError> {
+    let gap = stackup.reference_gap(layer)?;
+    let h = gap.height as f64 * 1e-3;
+    let (w, l) = (pad.size.0 as f64 * 1e-3, pad.size.1 as f64 * 1e-3);
+
+    let area = (w + h) * (l + h);
+    let farads = EPSILON_0 * gap.epsilon_r as f64 * area / h;
+
+    Ok(ParasiticEstimate {
+        value: farads * 1e12,
+        unit: "pF",
+        assumptions: vec![
+            format!("pad {} on {} over {}", pad.number, layer, gap.reference_layer),
+            format!("h = {:.4} mm, er = {:.2}", gap.height, gap.epsilon_r),
+            "parallel plate with each pad edge extended by h/2 for fringing".to_string(),
+            "rectangular pad area, corner rounding ignored".to_string(),
+        ],
+    })
+}
+
+/// Microstrip inductance per meter of a strip `w` wide at height `h` over a plane (H/m)
+pub fn microstrip_inductance_per_meter(w: f64, h: f64) -> f64 {
+    let ratio = w / h;
+    if ratio >= 1.0 {
+        MU_0 / (ratio + 1.393 + 0.667 * (ratio + 1.444).ln())
+    } else {
+        MU_0 / (2.0 * std::f64::consts::PI) * (8.0 / ratio + ratio / 4.0).ln()
+    }
+}
+
+/// Loop inductance of the current path between two pads on `layer`, returning through
+/// the reference plane, in nH
+pub fn pad_pair_inductance(
+    pad_a: &PadDescriptor,
+    pad_b: &PadDescriptor,
+    stackup: &Stackup,
+    layer: &str,
+) -> Result<ParasiticEstimate, StackupError> {
+    let gap = stackup.reference_gap(layer)?;
+    let h = gap.height as f64 * 1e-3;
+
+    let (dx, dy) = (pad_b.position.0 - pad_a.position.0, pad_b.position.1 - pad_a.position.1);
+    let length = (dx as f64).hypot(dy as f64) * 1e-3;
+    // Width of the path is the pad dimension across the current flow
+    let across = |pad: &PadDescriptor| if dx.abs() >= dy.abs() { pad.size.1 } else { pad.size.0 };
+    let width = across(pad_a).min(across(pad_b)) as f64 * 1e-3;
+
+    let henries = microstrip_inductance_per_meter(width, h) * length;
+
+    Ok(ParasiticEstimate {
+        value: henries * 1e9,
+        unit: "nH",
+        assumptions: vec![
+            format!("pads {} to {} on {} over {}", pad_a.number, pad_b.number, layer, gap.reference_layer),
+            format!("path length {:.4} mm (center to center), width {:.4} mm, h = {:.4} mm", length * 1e3, width * 1e3, gap.height),
+            "strip over plane (Hammerstad/Wheeler microstrip) loop inductance".to_string(),
+        ],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_interface::{PadShape, PadType, TentingSettings, TentingType};
+    use crate::ids::fresh_uuid;
+    use crate::layer_type::PadLayers;
+    use crate::stackup::StackupLayer;
+
+    /// 0402 land, IPC-7351 nominal: 0.5 x 0.6mm pads with centers 1.0mm apart
+    fn pad_0402(number: &str, x: f32) -> PadDescriptor {
+        PadDescriptor {
+            number: number.into(),
+            pin_function: None,
+            pad_type: PadType::SMD,
+            shape: PadShape::Rect,
+            position: (x, 0.0),
+            rotation: None,
+            size: (0.5, 0.6),
+            drill_size: None,
+            drill_slot: None,
+            layers: PadLayers::smd_front(),
+            roundrect_ratio: None,
+            tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+            padstack: None,
+            fab_property: None,
+            solder_mask_margin: None,
+            routing_hint: None,
+            uuid: fresh_uuid(),
+        }
+    }
+
+    /// F.Cu over 0.1mm of FR-4 (εr 4.3) to a plane
+    fn thin_dielectric() -> Stackup {
+        Stackup::new(vec![
+            StackupLayer::copper("F.Cu", 0.035),
+            StackupLayer::dielectric("Prepreg", 0.1, 4.3, 0.02),
+            StackupLayer::copper("In1.Cu", 0.035),
+            StackupLayer::dielectric("Core", 1.3, 4.5, 0.02),
+            StackupLayer::copper("B.Cu", 0.035),
+        ])
+    }
+
+    #[test]
+    fn pad_capacitance_0402_over_thin_dielectric() {
+        let estimate = pad_capacitance(&pad_0402("1", -0.5), &thin_dielectric(), "F.Cu").unwrap();
+        assert_eq!(estimate.unit, "pF");
+        // By hand: 8.854e-12 * 4.3 * (0.6 * 0.7)e-6 / 0.1e-3 = 0.1599 pF
+        assert!((estimate.value - 0.1599).abs() < 5e-4, "{}", estimate);
+        // Field solvers put an 0402 pad over 4 mil of FR-4 at roughly 0.1 to 0.15 pF;
+        // the target is a factor of two
+        assert!((0.05..=0.3).contains(&estimate.value), "{}", estimate);
+        assert!(estimate.assumptions.iter().any(|line| line.contains("h = 0.1000 mm, er = 4.30")));
+    }
+
+    #[test]
+    fn pad_pair_inductance_0402_over_thin_dielectric() {
+        let estimate = pad_pair_inductance(&pad_0402("1", -0.5), &pad_0402("2", 0.5), &thin_dielectric(), "F.Cu").unwrap();
+        assert_eq!(estimate.unit, "nH");
+        // By hand: w/h = 6, L' = mu0 / (6 + 1.393 + 0.667 ln 7.444) = 1.439e-7 H/m over 1mm
+        assert!((estimate.value - 0.1439).abs() < 5e-4, "{}", estimate);
+        // Below the plain parallel plate value mu0 h l / w, as fringing widens the path
+        assert!(estimate.value < MU_0 * 0.1 / 0.6 * 1e-3 * 1e9);
+    }
+
+    #[test]
+    fn microstrip_inductance_matches_fifty_ohm_rule_of_thumb() {
+        // A 50 ohm microstrip on FR-4 (w = 2h) has about 8 nH per inch (Bogatin)
+        let nh_per_inch = microstrip_inductance_per_meter(0.2e-3, 0.1e-3) * 0.0254 * 1e9;
+        assert!((nh_per_inch - 8.0).abs() < 8.0 * 0.2, "{}", nh_per_inch);
+        // Narrow strips use the log form, which meets the wide form near w = h
+        let (narrow, wide) = (microstrip_inductance_per_meter(0.999e-4, 1e-4), microstrip_inductance_per_meter(1e-4, 1e-4));
+        assert!((narrow - wide).abs() / wide < 0.05);
+    }
+
+    #[test]
+    fn missing_reference_plane_is_an_error() {
+        let single = Stackup::new(vec![StackupLayer::copper("F.Cu", 0.035), StackupLayer::dielectric("Core", 1.5, 4.5, 0.02)]);
+        assert_eq!(
+            pad_capacitance(&pad_0402("1", 0.0), &single, "F.Cu"),
+            Err(StackupError::NoReferencePlane("F.Cu".to_string()))
+        );
+    }
+}
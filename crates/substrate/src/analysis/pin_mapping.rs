@@ -0,0 +1,278 @@
+//! Symbol pin to footprint pad mapping
+//!
+//! A symbol and the footprint it is placed with must agree on numbering: every symbol pin
+//! names exactly one pad number and every numbered pad has a pin. Pads marked as heatsink,
+//! mechanical or fiducial (`PadFabProperty`) may go without a pin, as may unnumbered and
+//! non plated holes, so a QFN whose exposed pad isn't on the symbol passes. Where both the pin has a
+//! name and the pad a pin function, the two must match, ignoring case.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::board_interface::{BoardComposableObject, ElectricalComponent, PadDescriptor, PadFabProperty, PadType, Pin};
+
+use super::findings::{Severity, Violation};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PinMappingIssue {
+    /// A symbol pin whose number no pad has
+    MissingPad { footprint: String, pin: String },
+    /// A numbered pad no symbol pin maps to, not marked heatsink, mechanical or fiducial
+    UnmappedPad { footprint: String, pad: String },
+    /// Several symbol pins with the same number
+    DuplicatePin { footprint: String, pin: String, count: usize },
+    /// The pin name and the pad's pin function disagree
+    FunctionMismatch { footprint: String, pad: String, pin_name: String, pin_function: String },
+}
+
+impl fmt::Display for PinMappingIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PinMappingIssue::MissingPad { footprint, pin } => write!(f, "pin {} has no pad on {}", pin, footprint),
+            PinMappingIssue::UnmappedPad { footprint, pad } => write!(f, "pad {} of {} has no symbol pin", pad, footprint),
+            PinMappingIssue::DuplicatePin { footprint, pin, count } => {
+                write!(f, "{} symbol pins are numbered {} for {}", count, pin, footprint)
+            }
+            PinMappingIssue::FunctionMismatch { footprint, pad, pin_name, pin_function } => {
+                write!(f, "pin {} is named \"{}\" but pad {} of {} is \"{}\"", pad, pin_name, pad, footprint, pin_function)
+            }
+        }
+    }
+}
+
+impl Violation for PinMappingIssue {
+    fn rule(&self) -> &'static str {
+        match self {
+            PinMappingIssue::MissingPad { .. } => "pin_missing_pad",
+            PinMappingIssue::UnmappedPad { .. } => "pad_without_pin",
+            PinMappingIssue::DuplicatePin { .. } => "duplicate_pin",
+            PinMappingIssue::FunctionMismatch { .. } => "pin_function_mismatch",
+        }
+    }
+
+    fn default_severity(&self) -> Severity {
+        match self {
+            PinMappingIssue::FunctionMismatch { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+
+    fn footprint(&self) -> String {
+        match self {
+            PinMappingIssue::MissingPad { footprint, .. }
+            | PinMappingIssue::UnmappedPad { footprint, .. }
+            | PinMappingIssue::DuplicatePin { footprint, .. }
+            | PinMappingIssue::FunctionMismatch { footprint, .. } => footprint.clone(),
+        }
+    }
+
+    fn element(&self) -> Option<String> {
+        Some(match self {
+            PinMappingIssue::MissingPad { pin, .. } | PinMappingIssue::DuplicatePin { pin, .. } => pin.clone(),
+            PinMappingIssue::UnmappedPad { pad, .. } | PinMappingIssue::FunctionMismatch { pad, .. } => pad.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PinMappingReport {
+    /// Pin numbers that map onto a pad
+    pub mapped: usize,
+    pub issues: Vec<PinMappingIssue>,
+}
+
+impl PinMappingReport {
+    pub fn check<S, F>(symbol: &S, footprint: &F) -> Self
+    where
+        S: ElectricalComponent + ?Sized,
+        F: BoardComposableObject + ?Sized,
+    {
+        Self::for_pins(&symbol.pins(), &footprint.pad_descriptors(), &footprint.footprint_name())
+    }
+
+    /// Check `pins` against `pads`, naming `footprint` in the issues
+    pub fn for_pins(pins: &[Pin], pads: &[PadDescriptor], footprint: &str) -> Self {
+        let mut report = Self::default();
+        let mut by_number: BTreeMap<&str, Vec<&Pin>> = BTreeMap::new();
+        for pin in pins {
+            by_number.entry(pin.number.as_str()).or_default().push(pin);
+        }
+
+        for (&number, pins) in &by_number {
+            if pins.len() > 1 {
+                report.issues.push(PinMappingIssue::DuplicatePin {
+                    footprint: footprint.to_string(),
+                    pin: number.to_string(),
+                    count: pins.len(),
+                });
+            }
+            let matching: Vec<&PadDescriptor> = pads.iter().filter(|pad| pad.number == number).collect();
+            if matching.is_empty() {
+                report.issues.push(PinMappingIssue::MissingPad { footprint: footprint.to_string(), pin: number.to_string() });
+                continue;
+            }
+            report.mapped += 1;
+            let function = matching.iter().find_map(|pad| pad.pin_function.as_deref());
+            for name in pins.iter().filter_map(|pin| pin.name.as_deref()) {
+                if let Some(function) = function.filter(|function| !function.trim().eq_ignore_ascii_case(name.trim())) {
+                    report.issues.push(PinMappingIssue::FunctionMismatch {
+                        footprint: footprint.to_string(),
+                        pad: number.to_string(),
+                        pin_name: name.to_string(),
+                        pin_function: function.to_string(),
+                    });
+                }
+            }
+        }
+
+        let mut unmapped: Vec<&str> = Vec::new();
+        for pad in pads.iter().filter(|pad| needs_pin(pad) && !by_number.contains_key(pad.number.as_str())) {
+            if !unmapped.contains(&pad.number.as_str()) {
+                unmapped.push(pad.number.as_str());
+            }
+        }
+        for pad in unmapped {
+            report.issues.push(PinMappingIssue::UnmappedPad { footprint: footprint.to_string(), pad: pad.to_string() });
+        }
+        report
+    }
+
+    /// True when nothing of error severity was found
+    pub fn is_ok(&self) -> bool {
+        self.issues.iter().all(|issue| issue.default_severity() < Severity::Error)
+    }
+
+    /// One line per issue, or a single line saying how many pins mapped
+    pub fn summary(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for PinMappingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.issues.is_empty() {
+            return writeln!(f, "{} pins mapped", self.mapped);
+        }
+        for issue in &self.issues {
+            writeln!(f, "{}: {}", issue.default_severity(), issue)?;
+        }
+        Ok(())
+    }
+}
+
+/// Numbered copper pads are terminals unless marked heatsink, mechanical or fiducial
+fn needs_pin(pad: &PadDescriptor) -> bool {
+    !pad.number.is_empty() && !matches!(pad.pad_type, PadType::NPTH) && pad.fab_property.is_none_or(PadFabProperty::needs_pin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_interface::ElectricalType;
+    use crate::fixed_geometry::{FixedGeometry, FixedPadShape, PadSpec};
+    use crate::soic::Soic;
+
+    /// Pins numbered 1 to `count`, unnamed
+    fn numbered(count: usize) -> Vec<Pin> {
+        (1..=count)
+            .map(|n| Pin {
+                id: n as u32,
+                number: n.to_string(),
+                name: None,
+                position: (0.0, n as f32 * 2.54),
+                electrical_type: ElectricalType::Passive,
+            })
+            .collect()
+    }
+
+    /// 3x3 mm QFN-16 with exposed pad 17, marked `exposed` or not
+    fn qfn16(exposed: Option<PadFabProperty>) -> Vec<PadDescriptor> {
+        let pad = |number: usize, position, size| PadSpec {
+            number: number.to_string().into(),
+            position,
+            size,
+            shape: FixedPadShape::RoundRect,
+            drill: None,
+            roundrect_ratio: None,
+            layers: None,
+        };
+        let mut pads = Vec::new();
+        for i in 0..4 {
+            let offset = -0.75 + 0.5 * i as f32;
+            pads.push(pad(1 + i, (-1.45, offset), (0.8, 0.25)));
+            pads.push(pad(5 + i, (offset, 1.45), (0.25, 0.8)));
+            pads.push(pad(9 + i, (1.45, -offset), (0.8, 0.25)));
+            pads.push(pad(13 + i, (-offset, -1.45), (0.25, 0.8)));
+        }
+        pads.push(PadSpec { shape: FixedPadShape::Rect, ..pad(17, (0.0, 0.0), (1.7, 1.7)) });
+        let mut pads = FixedGeometry { pads, ..FixedGeometry::default() }.pad_descriptors();
+        pads.iter_mut().filter(|pad| pad.number == "17").for_each(|pad| pad.fab_property = exposed);
+        pads
+    }
+
+    fn rules(report: &PinMappingReport) -> Vec<(&'static str, Option<String>)> {
+        report.issues.iter().map(|issue| (issue.rule(), issue.element())).collect()
+    }
+
+    #[test]
+    fn matching_symbol_and_footprint_pass() {
+        let soic = Soic::from_jedec("MS-012-AA", 8).unwrap();
+        let report = PinMappingReport::for_pins(&numbered(8), &soic.pad_descriptors(), "SOIC-8");
+        assert!(report.is_ok() && report.issues.is_empty(), "{}", report);
+        assert_eq!(report.mapped, 8);
+        assert_eq!(report.summary(), "8 pins mapped\n");
+    }
+
+    #[test]
+    fn exposed_pad_without_pin_passes_once_marked() {
+        let marked = PinMappingReport::for_pins(&numbered(16), &qfn16(Some(PadFabProperty::Heatsink)), "QFN-16");
+        assert!(marked.is_ok() && marked.issues.is_empty(), "{}", marked);
+        let unmarked = PinMappingReport::for_pins(&numbered(16), &qfn16(None), "QFN-16");
+        assert_eq!(rules(&unmarked), [("pad_without_pin", Some("17".to_string()))]);
+        assert!(!unmarked.is_ok());
+    }
+
+    #[test]
+    fn missing_pin_is_an_error() {
+        // Pin 4 left off the symbol and a ninth pin the SOIC doesn't have
+        let mut pins = numbered(9);
+        pins.remove(3);
+        let soic = Soic::from_jedec("MS-012-AA", 8).unwrap();
+        let report = PinMappingReport::for_pins(&pins, &soic.pad_descriptors(), "SOIC-8");
+        assert!(!report.is_ok());
+        assert_eq!(rules(&report), [("pin_missing_pad", Some("9".to_string())), ("pad_without_pin", Some("4".to_string()))]);
+        assert!(report.issues.iter().all(|issue| issue.default_severity() == Severity::Error));
+    }
+
+    #[test]
+    fn pins_may_not_repeat() {
+        let mut pins = numbered(8);
+        pins[7].number = "7".to_string();
+        let soic = Soic::from_jedec("MS-012-AA", 8).unwrap();
+        let report = PinMappingReport::for_pins(&pins, &soic.pad_descriptors(), "SOIC-8");
+        assert!(report.issues.contains(&PinMappingIssue::DuplicatePin { footprint: "SOIC-8".to_string(), pin: "7".to_string(), count: 2 }));
+        assert!(report.issues.contains(&PinMappingIssue::UnmappedPad { footprint: "SOIC-8".to_string(), pad: "8".to_string() }));
+    }
+
+    #[test]
+    fn names_are_compared_with_pin_functions_ignoring_case() {
+        let mut pads = Soic::from_jedec("MS-012-AA", 8).unwrap().pad_descriptors();
+        pads[0].pin_function = Some("GND".to_string());
+        pads[1].pin_function = Some("vcc".to_string());
+        let mut pins = numbered(8);
+        pins[0].name = Some("VDD".to_string());
+        pins[1].name = Some("VCC".to_string());
+        let report = PinMappingReport::for_pins(&pins, &pads, "SOIC-8");
+        assert!(report.is_ok());
+        assert_eq!(
+            report.issues,
+            [PinMappingIssue::FunctionMismatch {
+                footprint: "SOIC-8".to_string(),
+                pad: "1".to_string(),
+                pin_name: "VDD".to_string(),
+                pin_function: "GND".to_string(),
+            }]
+        );
+        assert_eq!(report.issues[0].to_finding().severity, Severity::Warning);
+    }
+}
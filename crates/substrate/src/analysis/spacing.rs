@@ -0,0 +1,204 @@
+//! Pad to pad spacing report
+//!
+//! For fine pitch parts the numbers that matter are the smallest copper gap between pads,
+//! the narrowest solder mask web left between mask openings and the smallest gap between
+//! paste apertures. All three are true edge to edge distances between tessellated pad
+//! outlines, not center pitch arithmetic.
+
+use std::fmt;
+
+use crate::board_interface::{BoardComposableObject, PadDescriptor};
+use crate::geometry::{polygon_distance, Point};
+
+/// Arc tolerance used when tessellating pads for spacing checks (1 micron)
+pub const SPACING_ARC_TOLERANCE: f32 = 0.001;
+
+/// Smallest gap found on one layer class and the pads on either side of it
+#[derive(Debug, Clone, PartialEq)]
+pub struct PadGap {
+    pub pad_a: String,
+    pub pad_b: String,
+    pub gap: f32,
+}
+
+/// Expansion applied to the copper outline to get the opening on each layer class
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpacingOptions {
+    /// Solder mask expansion per side, KiCad's default is 0; a pad's own margin wins, see
+    /// `PadDescriptor::mask_aperture`
+    pub mask_margin: f32,
+    /// Paste margin per side, normally zero or negative
+    pub paste_margin: f32,
+}
+
+impl Default for SpacingOptions {
+    fn default() -> Self {
+        Self { mask_margin: 0.0, paste_margin: 0.0 }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpacingReport {
+    /// Minimum copper to copper gap between pads with different numbers
+    pub copper: Option<PadGap>,
+    /// Minimum solder mask web between mask openings
+    pub mask_web: Option<PadGap>,
+    /// Minimum gap between paste apertures
+    pub paste: Option<PadGap>,
+}
+
+impl SpacingReport {
+    /// Compute the spacing report for every pad pair of a component. Footprints that allow
+    /// solder mask bridges have no mask web to report.
+    pub fn for_component<T: BoardComposableObject + ?Sized>(component: &T, options: SpacingOptions) -> Self {
+        let mut report = Self::for_pads(&component.pad_descriptors(), options);
+        if component.allows_soldermask_bridges() {
+            report.mask_web = None;
+        }
+        report
+    }
+
+    pub fn for_pads(pads: &[PadDescriptor], options: SpacingOptions) -> Self {
+        Self {
+            copper: min_gap(pads, "Cu", 0.0, false),
+            mask_web: min_gap(pads, "Mask", options.mask_margin, true),
+            paste: min_gap(pads, "Paste", options.paste_margin, true),
+        }
+    }
+
+    /// Multi line human readable summary naming the pads involved
+    pub fn summary(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for SpacingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (label, gap) in [("Copper gap", &self.copper), ("Mask web", &self.mask_web), ("Paste gap", &self.paste)] {
+            match gap {
+                Some(gap) => writeln!(f, "{}: {:.3} mm (pads {} / {})", label, gap.gap, gap.pad_a, gap.pad_b)?,
+                None => writeln!(f, "{}: n/a", label)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Smallest gap between outlines of pads on the same side for one layer kind; mask
+/// outlines are the pads' mask apertures
+fn min_gap(pads: &[PadDescriptor], kind: &str, margin: f32, include_same_number: bool) -> Option<PadGap> {
+    let mut best: Option<PadGap> = None;
+    for side in ["F", "B"] {
+        let layer = format!("{}.{}", side, kind);
+        let outlines: Vec<(&PadDescriptor, Vec<Point>)> = pads
+            .iter()
+            .filter(|pad| pad.has_layer(&layer) && (kind != "Cu" || pad.has_copper()))
+            .filter_map(|pad| {
+                let outline = match kind {
+                    "Mask" => pad.mask_aperture(margin, SPACING_ARC_TOLERANCE)?,
+                    _ => expanded(pad, margin).outline_polygon(SPACING_ARC_TOLERANCE),
+                };
+                Some((pad, outline))
+            })
+            .collect();
+
+        for (i, (pad_a, outline_a)) in outlines.iter().enumerate() {
+            for (pad_b, outline_b) in &outlines[i + 1..] {
+                if !include_same_number && pad_a.number == pad_b.number {
+                    continue;
+                }
+                let gap = polygon_distance(outline_a, outline_b);
+                if best.as_ref().is_none_or(|b| gap < b.gap) {
+                    best = Some(PadGap { pad_a: pad_a.number.to_string(), pad_b: pad_b.number.to_string(), gap });
+                }
+            }
+        }
+    }
+    best
+}
+
+/// Pad grown (or shrunk) by `margin` on every side
+fn expanded(pad: &PadDescriptor, margin: f32) -> PadDescriptor {
+    let mut pad = pad.clone();
+    let grow = |size: (f32, f32)| ((size.0 + 2.0 * margin).max(0.0), (size.1 + 2.0 * margin).max(0.0));
+    pad.size = grow(pad.size);
+    for geometry in pad.padstack.iter_mut().flat_map(|padstack| [&mut padstack.back, &mut padstack.inner]).flatten() {
+        geometry.size = grow(geometry.size);
+    }
+    pad
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_interface::{PadShape, PadType, TentingSettings, TentingType};
+    use crate::ids::fresh_uuid;
+    use crate::layer_type::PadLayers;
+
+    const PITCH: f32 = 0.4;
+    const PAD_WIDTH: f32 = 0.2;
+
+    fn pad(number: usize, position: (f32, f32), size: (f32, f32)) -> PadDescriptor {
+        PadDescriptor {
+            number: number.to_string().into(),
+            pin_function: None,
+            pad_type: PadType::SMD,
+            shape: PadShape::RoundRect,
+            position,
+            rotation: None,
+            size,
+            drill_size: None,
+            drill_slot: None,
+            layers: PadLayers::smd_front(),
+            roundrect_ratio: Some(0.25),
+            tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+            padstack: None,
+            fab_property: None,
+            solder_mask_margin: None,
+            routing_hint: None,
+            uuid: fresh_uuid(),
+        }
+    }
+
+    /// QFN-20 3x3mm, 0.4mm pitch: five 0.2 x 0.6mm pads per side
+    fn qfn20() -> Vec<PadDescriptor> {
+        let mut pads = Vec::new();
+        for side in 0..4 {
+            for i in 0..5 {
+                let along = (i as f32 - 2.0) * PITCH;
+                let (position, size) = match side {
+                    0 => ((-1.45, along), (0.6, PAD_WIDTH)),
+                    1 => ((along, 1.45), (PAD_WIDTH, 0.6)),
+                    2 => ((1.45, -along), (0.6, PAD_WIDTH)),
+                    _ => ((-along, -1.45), (PAD_WIDTH, 0.6)),
+                };
+                pads.push(pad(side * 5 + i + 1, position, size));
+            }
+        }
+        pads
+    }
+
+    #[test]
+    fn fine_pitch_copper_gap_is_pitch_minus_width() {
+        let report = SpacingReport::for_pads(&qfn20(), SpacingOptions::default());
+        let copper = report.copper.unwrap();
+        assert!((copper.gap - (PITCH - PAD_WIDTH)).abs() < 1e-4, "{:?}", copper);
+        let (a, b): (usize, usize) = (copper.pad_a.parse().unwrap(), copper.pad_b.parse().unwrap());
+        assert_eq!(b - a, 1, "{:?}", copper);
+    }
+
+    #[test]
+    fn margins_shrink_mask_web_and_widen_paste_gap() {
+        let options = SpacingOptions { mask_margin: 0.05, paste_margin: -0.025 };
+        let report = SpacingReport::for_pads(&qfn20(), options);
+        assert!((report.mask_web.unwrap().gap - 0.1).abs() < 1e-4);
+        assert!((report.paste.unwrap().gap - 0.25).abs() < 1e-4);
+    }
+
+    #[test]
+    fn summary_names_the_pads() {
+        let pads = [pad(1, (0.0, 0.0), (0.2, 0.6)), pad(2, (0.4, 0.0), (0.2, 0.6))];
+        let summary = SpacingReport::for_pads(&pads, SpacingOptions::default()).summary();
+        assert!(summary.contains("Copper gap: 0.200 mm (pads 1 / 2)"), "{}", summary);
+    }
+}
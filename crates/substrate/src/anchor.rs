@@ -0,0 +1,313 @@
+//! Footprint origin conventions
+//!
+//! Generators and pick-and-place machines disagree about where a footprint's (0, 0) is:
+//! most IPC style footprints sit on the body centroid, connector and header generators
+//! often put pin 1 at the origin. `BoardComposableObject::anchor` says which convention a
+//! footprint follows, and `rebase_to` wraps a component so a different point becomes the
+//! origin, shifting pads, text, graphics, courtyard and the 3D model offset alike.
+//! Footprints read from a `.kicad_mod` can only be rebased to where their origin already
+//! is, their source can't take moved coordinates.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::assembly_process::ProcessSet;
+use crate::board_interface::{
+    BoardComposableObject, FpText, GraphicElement, GraphicType, Model3D, PadDescriptor, Rectangle,
+};
+use crate::courtyard::{ActuationEnvelope, Courtyard};
+use crate::describe::DescriptionContext;
+use crate::functional_types::FunctionalType;
+use crate::geometry::Point;
+use crate::pad_groups::PadGrouping;
+use crate::rule_area::RuleArea;
+
+/// Which point of a footprint is its origin
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Anchor {
+    /// Center of the body bounding box
+    Centroid,
+    /// Center of pad "1", or of the first pad when none is numbered 1
+    Pin1,
+    /// An explicit point in the footprint's current coordinates
+    Custom(Point),
+}
+
+impl Anchor {
+    /// Location of the anchor in the component's current footprint coordinates
+    pub fn resolve<T: BoardComposableObject + ?Sized>(&self, component: &T) -> Point {
+        match self {
+            Anchor::Centroid => body_centroid(component),
+            Anchor::Pin1 => {
+                let pads = component.pad_descriptors();
+                pads.iter()
+                    .find(|pad| pad.number == "1")
+                    .or(pads.first())
+                    .map(|pad| Point::from(pad.position))
+                    .unwrap_or_default()
+            }
+            Anchor::Custom(point) => *point,
+        }
+    }
+}
+
+/// How the true part centroid is derived, assembly houses differ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CentroidSource {
+    /// Center of the body bounding box
+    #[default]
+    BodyBounds,
+    /// Mean of the pad centers, for bodies with off-center tabs or overhangs
+    PadCentroid,
+}
+
+impl CentroidSource {
+    /// Part centroid in footprint coordinates
+    pub fn centroid<T: BoardComposableObject + ?Sized>(&self, component: &T) -> Point {
+        match self {
+            CentroidSource::BodyBounds => body_centroid(component),
+            CentroidSource::PadCentroid => pad_centroid(component).unwrap_or_else(|| body_centroid(component)),
+        }
+    }
+}
+
+/// Center of the body bounding box in footprint coordinates
+pub fn body_centroid<T: BoardComposableObject + ?Sized>(component: &T) -> Point {
+    let bounds = component.bounding_box();
+    Point::new((bounds.min_x + bounds.max_x) / 2.0, (bounds.min_y + bounds.max_y) / 2.0)
+}
+
+/// Mean of the pad centers, `None` for a footprint without pads
+pub fn pad_centroid<T: BoardComposableObject + ?Sized>(component: &T) -> Option<Point> {
+    let pads = component.pad_descriptors();
+    if pads.is_empty() {
+        return None;
+    }
+    let count = pads.len() as f32;
+    let (x, y) = pads.iter().fold((0.0, 0.0), |(x, y), pad| (x + pad.position.0, y + pad.position.1));
+    Some(Point::new(x / count, y / count))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RebaseError {
+    /// The footprint was read from a file and is written from its source, which can't be
+    /// shifted
+    ParsedSource { footprint: String, shift: (f32, f32) },
+}
+
+impl fmt::Display for RebaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RebaseError::ParsedSource { footprint, shift } => write!(
+                f,
+                "{} was read from a file, shifting it by ({:.3}, {:.3}) mm would drop what its model doesn't cover",
+                footprint, shift.0, shift.1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RebaseError {}
+
+/// A component with all of its geometry shifted so `anchor` sits at the origin
+#[derive(Debug, Clone)]
+pub struct Rebased<T> {
+    inner: T,
+    anchor: Anchor,
+    /// Added to every footprint coordinate of the inner component
+    shift: (f32, f32),
+}
+
+impl<T: BoardComposableObject> Rebased<T> {
+    /// Fails for a footprint written from a parsed source when the anchor isn't already
+    /// at its origin
+    pub fn new(inner: T, anchor: Anchor) -> Result<Self, RebaseError> {
+        let point = anchor.resolve(&inner);
+        let shift = (-point.x, -point.y);
+        if shift != (0.0, 0.0) && inner.kicad_source().is_some() {
+            return Err(RebaseError::ParsedSource { footprint: inner.footprint_name(), shift });
+        }
+        Ok(Self { inner, anchor, shift })
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Offset added to every coordinate of the inner component
+    pub fn shift(&self) -> (f32, f32) {
+        self.shift
+    }
+
+    fn shifted(&self, point: (f32, f32)) -> (f32, f32) {
+        (point.0 + self.shift.0, point.1 + self.shift.1)
+    }
+
+    fn shifted_rectangle(&self, rect: &Rectangle) -> Rectangle {
+        Rectangle {
+            min_x: rect.min_x + self.shift.0,
+            min_y: rect.min_y + self.shift.1,
+            max_x: rect.max_x + self.shift.0,
+            max_y: rect.max_y + self.shift.1,
+        }
+    }
+}
+
+impl<T: BoardComposableObject> BoardComposableObject for Rebased<T> {
+    fn is_smt(&self) -> bool {
+        self.inner.is_smt()
+    }
+
+    fn is_electrical(&self) -> bool {
+        self.inner.is_electrical()
+    }
+
+    fn is_passive(&self) -> bool {
+        self.inner.is_passive()
+    }
+
+    fn is_assembled(&self) -> bool {
+        self.inner.is_assembled()
+    }
+
+    fn allows_soldermask_bridges(&self) -> bool {
+        self.inner.allows_soldermask_bridges()
+    }
+
+    fn pad_grouping(&self) -> PadGrouping {
+        self.inner.pad_grouping()
+    }
+
+    fn terminal_count(&self) -> usize {
+        self.inner.terminal_count()
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        self.inner.functional_type()
+    }
+
+    fn footprint_name(&self) -> String {
+        self.inner.footprint_name()
+    }
+
+    fn library_name(&self) -> String {
+        self.inner.library_name()
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        self.shifted_rectangle(&self.inner.bounding_box())
+    }
+
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        self.inner
+            .pad_descriptors()
+            .into_iter()
+            .map(|mut pad| {
+                pad.position = self.shifted(pad.position);
+                pad
+            })
+            .collect()
+    }
+
+    fn description_context(&self) -> Option<DescriptionContext> {
+        self.inner.description_context()
+    }
+
+    fn description(&self) -> Option<String> {
+        self.inner.description()
+    }
+
+    fn tags(&self) -> Option<String> {
+        self.inner.tags()
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        self.inner
+            .fp_text_elements()
+            .into_iter()
+            .map(|mut text| {
+                text.position = self.shifted(text.position);
+                text
+            })
+            .collect()
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        self.inner
+            .graphic_elements()
+            .into_iter()
+            .map(|mut element| {
+                element.element_type = match element.element_type {
+                    GraphicType::Line { start, end } => GraphicType::Line { start: self.shifted(start), end: self.shifted(end) },
+                    GraphicType::Rectangle { bounds } => GraphicType::Rectangle { bounds: self.shifted_rectangle(&bounds) },
+                    GraphicType::Circle { center, radius } => GraphicType::Circle { center: self.shifted(center), radius },
+                    GraphicType::Polygon { points, fill } => {
+                        GraphicType::Polygon { points: points.into_iter().map(|point| self.shifted(point)).collect(), fill }
+                    }
+                    GraphicType::Dimension(mut dimension) => {
+                        dimension.start = self.shifted(dimension.start);
+                        dimension.end = self.shifted(dimension.end);
+                        GraphicType::Dimension(dimension)
+                    }
+                };
+                element
+            })
+            .collect()
+    }
+
+    /// KiCad model offsets are Y up, so a footprint shift of (dx, dy) moves the model by
+    /// (dx, -dy)
+    fn model_3d(&self) -> Option<Model3D> {
+        self.inner.model_3d().map(|mut model| {
+            model.offset.0 += self.shift.0;
+            model.offset.1 -= self.shift.1;
+            model
+        })
+    }
+
+    /// Only a source of a footprint that isn't shifted, see `Rebased::new`
+    fn kicad_source(&self) -> Option<String> {
+        self.inner.kicad_source()
+    }
+
+    fn kicad_source_with(&self, texts: &[FpText], graphics: &[GraphicElement]) -> Option<String> {
+        self.inner.kicad_source_with(texts, graphics)
+    }
+
+    fn rule_areas(&self) -> Vec<RuleArea> {
+        self.inner
+            .rule_areas()
+            .into_iter()
+            .map(|mut area| {
+                area.polygon = area.polygon.iter().map(|point| self.shifted(*point)).collect();
+                area
+            })
+            .collect()
+    }
+
+    fn actuation_envelope(&self) -> Option<ActuationEnvelope> {
+        self.inner.actuation_envelope().map(|mut envelope| {
+            envelope.outline = envelope.outline.iter().map(|point| self.shifted(*point)).collect();
+            envelope
+        })
+    }
+
+    fn process(&self) -> ProcessSet {
+        self.inner.process()
+    }
+
+    fn courtyard_margin(&self) -> f32 {
+        self.inner.courtyard_margin()
+    }
+
+    fn generate_courtyard(&self) -> Courtyard {
+        let mut courtyard = self.inner.generate_courtyard();
+        courtyard.bounds = self.shifted_rectangle(&courtyard.bounds);
+        courtyard
+    }
+
+    fn anchor(&self) -> Anchor {
+        self.anchor
+    }
+}
@@ -0,0 +1,161 @@
+//! Copper artwork rasters
+//!
+//! `Board::copper_artwork` draws the copper of one layer (see `Board::copper_outlines`)
+//! into a bitmap for previews and textures, sampling the outlines the way the copper
+//! density map does (see `copper_density`). The bitmap covers the board outline's
+//! bounding box, or the copper's on boards without an outline, row by row from the top;
+//! `to_rgba` colors it, copper over a background.
+//!
+//! `Board::mask_artwork` draws a solder mask layer the same way, its pixels set where the
+//! mask is open: the pads' mask openings (see `Board::mask_openings`). It covers the same
+//! area as the copper artwork of its side, pixel for pixel at the same resolution, so a
+//! renderer can lay the mask over the copper and let the copper show only at openings.
+//!
+//! Drawing a large board at a fine resolution takes a while, so callers cache artwork.
+//! `Board::copper_fingerprint` hashes a layer's copper and extent without drawing it, and
+//! `Board::mask_fingerprint` a mask's openings: the artwork only needs drawing again when
+//! the fingerprint differs from the one it carries.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::board::Board;
+use crate::board_interface::Rectangle;
+use crate::copper_density::sample_copper;
+use crate::geometry::Point;
+use crate::layer_type::{BoardLayer, LayerType, Side};
+use crate::thieving::THIEVING_ARC_TOLERANCE;
+
+/// Largest bitmap side in pixels; boards that would be wider are drawn coarser
+pub const MAX_ARTWORK_PIXELS: usize = 4096;
+
+/// One copper or mask layer as a bitmap, see the module documentation
+#[derive(Debug, Clone, PartialEq)]
+pub struct CopperArtwork {
+    pub layer: BoardLayer,
+    /// Top left corner of pixel (0, 0)
+    pub origin: Point,
+    /// Pixel size in millimeters
+    pub pixel_size: f32,
+    pub width: usize,
+    pub height: usize,
+    /// `Board::copper_fingerprint` (or `mask_fingerprint`) of the layer when it was drawn
+    pub fingerprint: u64,
+    /// Copper flags row by row from the top, opening flags on a mask layer
+    pixels: Vec<bool>,
+}
+
+impl CopperArtwork {
+    /// Whether pixel (`x`, `y`) is copper, or open on a mask layer; false off the bitmap
+    pub fn is_copper(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.pixels[y * self.width + x]
+    }
+
+    /// Copper flags row by row from the top
+    pub fn pixels(&self) -> &[bool] {
+        &self.pixels
+    }
+
+    /// Board area the bitmap covers
+    pub fn bounds(&self) -> Rectangle {
+        Rectangle {
+            min_x: self.origin.x,
+            min_y: self.origin.y,
+            max_x: self.origin.x + self.width as f32 * self.pixel_size,
+            max_y: self.origin.y + self.height as f32 * self.pixel_size,
+        }
+    }
+
+    /// Pixel holding a board point, `None` off the bitmap
+    pub fn pixel_at(&self, point: Point) -> Option<(usize, usize)> {
+        let x = ((point.x - self.origin.x) / self.pixel_size).floor();
+        let y = ((point.y - self.origin.y) / self.pixel_size).floor();
+        (x >= 0.0 && y >= 0.0 && (x as usize) < self.width && (y as usize) < self.height).then_some((x as usize, y as usize))
+    }
+
+    /// Fraction of the pixels that are copper
+    pub fn coverage(&self) -> f32 {
+        if self.pixels.is_empty() {
+            return 0.0;
+        }
+        self.pixels.iter().filter(|&&copper| copper).count() as f32 / self.pixels.len() as f32
+    }
+
+    /// RGBA pixels row by row from the top, `copper` where there is copper and
+    /// `background` elsewhere
+    pub fn to_rgba(&self, copper: [u8; 4], background: [u8; 4]) -> Vec<[u8; 4]> {
+        self.pixels.iter().map(|&is_copper| if is_copper { copper } else { background }).collect()
+    }
+}
+
+/// Hash of a layer's name, the area its artwork covers and the outlines drawn on it
+fn fingerprint(layer: BoardLayer, extent: Option<&Rectangle>, outlines: &[Vec<Point>]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    layer.to_kicad_string().hash(&mut hasher);
+    if let Some(extent) = extent {
+        [extent.min_x, extent.min_y, extent.max_x, extent.max_y].map(f32::to_bits).hash(&mut hasher);
+    }
+    for outline in outlines {
+        outline.len().hash(&mut hasher);
+        for point in outline {
+            (point.x.to_bits(), point.y.to_bits()).hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+/// `outlines` drawn over `extent` at `pixels_per_mm`, at most `MAX_ARTWORK_PIXELS` on a
+/// side; an empty bitmap without an extent
+fn draw(layer: BoardLayer, extent: Option<Rectangle>, outlines: &[Vec<Point>], pixels_per_mm: f32) -> CopperArtwork {
+    let fingerprint = fingerprint(layer, extent.as_ref(), outlines);
+    let Some(extent) = extent.filter(|_| pixels_per_mm > 0.0) else {
+        return CopperArtwork { layer, origin: Point::default(), pixel_size: 0.0, width: 0, height: 0, fingerprint, pixels: Vec::new() };
+    };
+    let (extent_width, extent_height) = (extent.max_x - extent.min_x, extent.max_y - extent.min_y);
+    let pixel_size = (1.0 / pixels_per_mm).max(extent_width.max(extent_height) / MAX_ARTWORK_PIXELS as f32);
+    let width = ((extent_width / pixel_size).ceil() as usize).clamp(1, MAX_ARTWORK_PIXELS);
+    let height = ((extent_height / pixel_size).ceil() as usize).clamp(1, MAX_ARTWORK_PIXELS);
+    let origin = Point::new(extent.min_x, extent.min_y);
+    let pixels = sample_copper(outlines, origin, pixel_size, width, height);
+    CopperArtwork { layer, origin, pixel_size, width, height, fingerprint, pixels }
+}
+
+impl Board {
+    /// Hash of the copper on `layer` and of the area its artwork covers, see the module
+    /// documentation
+    pub fn copper_fingerprint(&self, layer: BoardLayer) -> u64 {
+        let copper = self.copper_outlines(layer);
+        fingerprint(layer, self.copper_extent(&copper).as_ref(), &copper)
+    }
+
+    /// The copper on `layer` drawn at `pixels_per_mm`, at most `MAX_ARTWORK_PIXELS` on a
+    /// side; an empty bitmap without copper or outline
+    pub fn copper_artwork(&self, layer: BoardLayer, pixels_per_mm: f32) -> CopperArtwork {
+        let copper = self.copper_outlines(layer);
+        draw(layer, self.copper_extent(&copper), &copper, pixels_per_mm)
+    }
+
+    /// Solder mask openings on one side in board coordinates: the outline of every pad on
+    /// the side's mask layer, as the pad is shaped there. Vias are tented, as KiCad leaves
+    /// them by default.
+    pub fn mask_openings(&self, side: Side) -> Vec<Vec<Point>> {
+        let mask = BoardLayer::new(LayerType::Mask, side);
+        self.pad_outlines(mask, THIEVING_ARC_TOLERANCE).into_iter().map(|(_, outline)| outline).collect()
+    }
+
+    /// Area a side's mask artwork covers: that of the side's copper artwork
+    fn mask_extent(&self, side: Side) -> Option<Rectangle> {
+        self.copper_extent(&self.copper_outlines(BoardLayer::new(LayerType::Copper, side)))
+    }
+
+    /// Hash of the mask openings on `side` and of the area its artwork covers
+    pub fn mask_fingerprint(&self, side: Side) -> u64 {
+        fingerprint(BoardLayer::new(LayerType::Mask, side), self.mask_extent(side).as_ref(), &self.mask_openings(side))
+    }
+
+    /// The solder mask on `side` drawn at `pixels_per_mm`, pixels set at openings, lined up
+    /// with `copper_artwork` of the same side at the same resolution
+    pub fn mask_artwork(&self, side: Side, pixels_per_mm: f32) -> CopperArtwork {
+        draw(BoardLayer::new(LayerType::Mask, side), self.mask_extent(side), &self.mask_openings(side), pixels_per_mm)
+    }
+}
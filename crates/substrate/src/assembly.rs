@@ -0,0 +1,342 @@
+//! Multi-board assemblies
+//!
+//! An `Assembly` holds several boards that ship as one product, e.g. the two rigid halves
+//! of a flex-rigid design. Each board keeps its own coordinates; its `BoardPlacement` puts
+//! it in the assembly frame, which is the board frame (millimeters, Y down) with Z up
+//! out of the first board's top side. Flex sections are modeled as a fold: the board
+//! plane tilts about its own X axis through the placement origin before it is rotated and
+//! moved, so a board folded by 180 degrees lies upside down above its position.
+//!
+//! Boards are joined by interconnects, board-to-board connectors or flex tails, named by
+//! the reference of the connector footprint on each board. Signals crossing between
+//! boards are assembly nets: each maps one net of each board it reaches, whatever that
+//! board calls it locally. `Assembly::exported_board` renames mapped nets to the assembly
+//! name so the separately written board files agree on it.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::board::Board;
+use crate::geometry::Point;
+
+/// Where a board sits in the assembly frame, see the module documentation
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BoardPlacement {
+    /// Assembly coordinates of the board origin, millimeters
+    pub position: (f32, f32, f32),
+    /// Rotation about the Z axis in degrees, counter-clockwise as on the board
+    pub rotation: f32,
+    /// Tilt of the board plane about its X axis in degrees; positive lifts the board's
+    /// positive Y half, 0 lies flat
+    pub fold: f32,
+}
+
+impl BoardPlacement {
+    pub fn new(x: f32, y: f32, z: f32) -> Self {
+        Self { position: (x, y, z), ..Self::default() }
+    }
+
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_fold(mut self, fold: f32) -> Self {
+        self.fold = fold;
+        self
+    }
+
+    /// Assembly coordinates of `point` on the board's top surface
+    pub fn assembly_point(&self, point: Point) -> (f32, f32, f32) {
+        let (sin, cos) = self.fold.to_radians().sin_cos();
+        let flat = Point::new(point.x, point.y * cos).rotated(self.rotation);
+        let (x, y, z) = self.position;
+        (x + flat.x, y + flat.y, z + point.y * sin)
+    }
+}
+
+/// A board of an assembly and its placement
+#[derive(Debug, Clone)]
+pub struct AssemblyBoard {
+    pub board: Board,
+    pub placement: BoardPlacement,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InterconnectKind {
+    /// Mating connectors, boards stacked or edge to edge
+    BoardToBoard,
+    /// A flex tail of this length between the two connectors, millimeters
+    Flex { length: f32 },
+}
+
+/// One end of an interconnect: the connector footprint `reference` on board `board`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectorEnd {
+    pub board: String,
+    pub reference: String,
+}
+
+impl ConnectorEnd {
+    pub fn new(board: &str, reference: &str) -> Self {
+        Self { board: board.to_string(), reference: reference.to_string() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interconnect {
+    pub kind: InterconnectKind,
+    pub ends: (ConnectorEnd, ConnectorEnd),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AssemblyError {
+    /// The assembly already has a board of that name
+    DuplicateBoard(String),
+    UnknownBoard(String),
+    /// No footprint of that reference is placed on the board
+    UnknownComponent { board: String, reference: String },
+    /// No pad, track or via of the board is on that net
+    UnknownNet { board: String, net: String },
+    /// The board net already belongs to another assembly net
+    NetMappedTwice { board: String, net: String, assembly_net: String },
+}
+
+impl fmt::Display for AssemblyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssemblyError::DuplicateBoard(name) => write!(f, "the assembly already has a board named {}", name),
+            AssemblyError::UnknownBoard(name) => write!(f, "the assembly has no board named {}", name),
+            AssemblyError::UnknownComponent { board, reference } => write!(f, "board {} has no component {}", board, reference),
+            AssemblyError::UnknownNet { board, net } => write!(f, "board {} has no net {}", board, net),
+            AssemblyError::NetMappedTwice { board, net, assembly_net } => {
+                write!(f, "net {} of board {} is already mapped to {}", net, board, assembly_net)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AssemblyError {}
+
+/// Something `Assembly::check` finds that makes the exports disagree with the design
+#[derive(Debug, Clone, PartialEq)]
+pub enum AssemblyIssue {
+    /// An assembly net that reaches only one board
+    SingleBoardNet(String),
+    /// An unmapped board net with the name of an assembly net it doesn't belong to, which
+    /// would read as connected across boards in the exports
+    NameClash { board: String, net: String },
+    /// A board no interconnect reaches, in an assembly of more than one
+    UnconnectedBoard(String),
+}
+
+impl fmt::Display for AssemblyIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssemblyIssue::SingleBoardNet(net) => write!(f, "assembly net {} reaches only one board", net),
+            AssemblyIssue::NameClash { board, net } => {
+                write!(f, "net {} of board {} is not mapped but has the name of an assembly net", net, board)
+            }
+            AssemblyIssue::UnconnectedBoard(board) => write!(f, "board {} has no interconnect", board),
+        }
+    }
+}
+
+/// Boards, their placements, interconnects and shared nets; see the module documentation
+#[derive(Debug, Clone, Default)]
+pub struct Assembly {
+    pub name: String,
+    boards: Vec<AssemblyBoard>,
+    interconnects: Vec<Interconnect>,
+    /// Assembly net name to (board name, board net) pairs
+    nets: BTreeMap<String, Vec<(String, String)>>,
+}
+
+impl Assembly {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string(), ..Self::default() }
+    }
+
+    /// Add `board` under its own name
+    pub fn add_board(&mut self, board: Board, placement: BoardPlacement) -> Result<(), AssemblyError> {
+        if self.board(&board.name).is_some() {
+            return Err(AssemblyError::DuplicateBoard(board.name));
+        }
+        self.boards.push(AssemblyBoard { board, placement });
+        Ok(())
+    }
+
+    pub fn boards(&self) -> &[AssemblyBoard] {
+        &self.boards
+    }
+
+    pub fn board(&self, name: &str) -> Option<&AssemblyBoard> {
+        self.boards.iter().find(|entry| entry.board.name == name)
+    }
+
+    fn known_board(&self, name: &str) -> Result<&Board, AssemblyError> {
+        self.board(name).map(|entry| &entry.board).ok_or_else(|| AssemblyError::UnknownBoard(name.to_string()))
+    }
+
+    /// Join the connectors at `from` and `to`
+    pub fn connect(&mut self, kind: InterconnectKind, from: ConnectorEnd, to: ConnectorEnd) -> Result<(), AssemblyError> {
+        for end in [&from, &to] {
+            if self.known_board(&end.board)?.component(&end.reference).is_none() {
+                return Err(AssemblyError::UnknownComponent { board: end.board.clone(), reference: end.reference.clone() });
+            }
+        }
+        self.interconnects.push(Interconnect { kind, ends: (from, to) });
+        Ok(())
+    }
+
+    pub fn interconnects(&self) -> &[Interconnect] {
+        &self.interconnects
+    }
+
+    /// Make `board_net` of board `board` part of the assembly net `net`
+    pub fn map_net(&mut self, net: &str, board: &str, board_net: &str) -> Result<(), AssemblyError> {
+        if !self.known_board(board)?.nets().contains(board_net) {
+            return Err(AssemblyError::UnknownNet { board: board.to_string(), net: board_net.to_string() });
+        }
+        if let Some(assembly_net) = self.assembly_net(board, board_net) {
+            return Err(AssemblyError::NetMappedTwice {
+                board: board.to_string(),
+                net: board_net.to_string(),
+                assembly_net: assembly_net.to_string(),
+            });
+        }
+        self.nets.entry(net.to_string()).or_default().push((board.to_string(), board_net.to_string()));
+        Ok(())
+    }
+
+    /// Assembly net names and the (board, board net) pairs they map, by name
+    pub fn nets(&self) -> impl Iterator<Item = (&str, &[(String, String)])> {
+        self.nets.iter().map(|(net, members)| (net.as_str(), members.as_slice()))
+    }
+
+    /// The assembly net `board_net` of board `board` belongs to
+    pub fn assembly_net(&self, board: &str, board_net: &str) -> Option<&str> {
+        self.nets
+            .iter()
+            .find(|(_, members)| members.iter().any(|(name, net)| name == board && net == board_net))
+            .map(|(net, _)| net.as_str())
+    }
+
+    /// Board net to assembly net for the mapped nets of board `board`
+    pub fn net_renames(&self, board: &str) -> BTreeMap<String, String> {
+        let mut renames = BTreeMap::new();
+        for (net, members) in &self.nets {
+            for (_, board_net) in members.iter().filter(|(name, _)| name == board) {
+                renames.insert(board_net.clone(), net.clone());
+            }
+        }
+        renames
+    }
+
+    /// A copy of board `name` with its mapped nets renamed to their assembly nets, the
+    /// board as it is written out
+    pub fn exported_board(&self, name: &str) -> Option<Board> {
+        let mut board = self.board(name)?.board.clone();
+        board.rename_nets(&self.net_renames(name));
+        Some(board)
+    }
+
+    pub fn check(&self) -> Vec<AssemblyIssue> {
+        let mut issues = Vec::new();
+        for (net, members) in &self.nets {
+            let first = &members[0].0;
+            if members.iter().all(|(board, _)| board == first) {
+                issues.push(AssemblyIssue::SingleBoardNet(net.clone()));
+            }
+        }
+        for entry in &self.boards {
+            let name = &entry.board.name;
+            for net in entry.board.nets() {
+                if self.nets.contains_key(net) && self.assembly_net(name, net).is_none() {
+                    issues.push(AssemblyIssue::NameClash { board: name.clone(), net: net.to_string() });
+                }
+            }
+            let connected = self.interconnects.iter().any(|link| link.ends.0.board == *name || link.ends.1.board == *name);
+            if self.boards.len() > 1 && !connected {
+                issues.push(AssemblyIssue::UnconnectedBoard(name.clone()));
+            }
+        }
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dip::Dip;
+
+    fn assembly() -> Assembly {
+        let mut main = Board::new("main").with_rectangular_outline(40.0, 30.0);
+        main.place("J1", Dip::new(8).unwrap(), (35.0, 15.0), 0.0).unwrap();
+        main.connect_pad("J1", "1", "VBUS");
+        main.connect_pad("J1", "2", "GND");
+        let mut display = Board::new("display").with_rectangular_outline(40.0, 20.0);
+        display.place("J2", Dip::new(8).unwrap(), (5.0, 10.0), 0.0).unwrap();
+        display.connect_pad("J2", "1", "PWR_IN");
+        display.connect_pad("J2", "2", "GND");
+
+        let mut assembly = Assembly::new("handheld");
+        assembly.add_board(main, BoardPlacement::default()).unwrap();
+        assembly.add_board(display, BoardPlacement::new(0.0, 30.0, 0.0).with_fold(90.0)).unwrap();
+        assembly.connect(InterconnectKind::Flex { length: 12.0 }, ConnectorEnd::new("main", "J1"), ConnectorEnd::new("display", "J2")).unwrap();
+        assembly
+    }
+
+    #[test]
+    fn mapped_nets_take_the_assembly_name_on_every_board() {
+        let mut assembly = assembly();
+        assembly.map_net("VBUS", "main", "VBUS").unwrap();
+        assembly.map_net("VBUS", "display", "PWR_IN").unwrap();
+        assembly.map_net("GND", "main", "GND").unwrap();
+        assembly.map_net("GND", "display", "GND").unwrap();
+        assert_eq!(assembly.check(), vec![]);
+
+        let display = assembly.exported_board("display").unwrap();
+        assert_eq!(display.pad_net("J2", "1"), Some("VBUS"));
+        assert_eq!(display.pad_net("J2", "2"), Some("GND"));
+        assert_eq!(assembly.board("display").unwrap().board.pad_net("J2", "1"), Some("PWR_IN"));
+        assert_eq!(assembly.exported_board("main").unwrap().pad_net("J1", "1"), Some("VBUS"));
+    }
+
+    #[test]
+    fn inconsistent_mappings_are_refused_or_reported() {
+        let mut assembly = assembly();
+        assert_eq!(
+            assembly.map_net("VBUS", "display", "VBUS"),
+            Err(AssemblyError::UnknownNet { board: "display".to_string(), net: "VBUS".to_string() })
+        );
+        assembly.map_net("VBUS", "main", "VBUS").unwrap();
+        assert!(matches!(assembly.map_net("VIN", "main", "VBUS"), Err(AssemblyError::NetMappedTwice { .. })));
+        assert!(matches!(
+            assembly.connect(InterconnectKind::BoardToBoard, ConnectorEnd::new("main", "J9"), ConnectorEnd::new("display", "J2")),
+            Err(AssemblyError::UnknownComponent { .. })
+        ));
+
+        // GND is mapped on the main board only, so the display's own GND looks shared
+        assembly.map_net("GND", "main", "GND").unwrap();
+        assert_eq!(
+            assembly.check(),
+            vec![
+                AssemblyIssue::SingleBoardNet("GND".to_string()),
+                AssemblyIssue::SingleBoardNet("VBUS".to_string()),
+                AssemblyIssue::NameClash { board: "display".to_string(), net: "GND".to_string() },
+            ]
+        );
+    }
+
+    #[test]
+    fn folds_tilt_the_board_about_its_x_axis() {
+        let up = BoardPlacement::new(0.0, 30.0, 0.0).with_fold(90.0);
+        let (x, y, z) = up.assembly_point(Point::new(10.0, 20.0));
+        assert!((x - 10.0).abs() < 1e-4 && (y - 30.0).abs() < 1e-4 && (z - 20.0).abs() < 1e-4);
+
+        let over = BoardPlacement::new(0.0, 0.0, 5.0).with_fold(180.0).with_rotation(90.0);
+        let (x, y, z) = over.assembly_point(Point::new(10.0, 20.0));
+        assert!((x + 20.0).abs() < 1e-4 && (y + 10.0).abs() < 1e-4 && (z - 5.0).abs() < 1e-4, "{:?}", (x, y, z));
+    }
+}
@@ -0,0 +1,424 @@
+//! Assembly processes
+//!
+//! Every component reports the processes it can be soldered or fitted with
+//! (`BoardComposableObject::process`): reflow, wave, selective soldering, hand soldering
+//! and press-fit. Without an override the set follows the pads, see `default_process`;
+//! generators narrow it where the package calls for it (a QFN's bottom terminations can
+//! only be reflowed).
+//!
+//! A board may declare a `ProcessPlan`: the processes each side goes through, the surface
+//! finish and the class of hole tolerance the fab holds. `Board::process_violations` checks
+//! the placed parts against it:
+//!
+//! - a part no process of its side can solder (`process_incompatible`). Through-hole parts
+//!   are soldered from either side, so they are checked against both sides' processes.
+//! - a surface mount part on a side that goes through the wave without being wave
+//!   solderable (`process_wave_exposed`): it is washed by the solder wave even when it was
+//!   reflowed first, and fine pitch leads bridge.
+//! - a part only hand soldering fits (`process_hand_solder`), for information. Plans list
+//!   `HandSolder` on the sides with a hand soldering station.
+//! - a press-fit part on a board without the press-fit hole tolerance class
+//!   (`press_fit_hole_tolerance`) or on a finish press-fit pins don't take
+//!   (`press_fit_finish`).
+//!
+//! Boards without a plan aren't checked. The findings are warnings, but for hand
+//! soldering, and come with `Board::drc`.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::findings::{ElementRef, Severity, Violation};
+use crate::board::Board;
+use crate::board_interface::{BoardComposableObject, PadFabProperty, PadType};
+use crate::geometry::Point;
+use crate::layer_type::Side;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum AssemblyProcess {
+    Reflow,
+    Wave,
+    Selective,
+    HandSolder,
+    PressFit,
+}
+
+impl AssemblyProcess {
+    pub const ALL: [AssemblyProcess; 5] =
+        [AssemblyProcess::Reflow, AssemblyProcess::Wave, AssemblyProcess::Selective, AssemblyProcess::HandSolder, AssemblyProcess::PressFit];
+
+    /// Name used in the exports
+    pub fn name(self) -> &'static str {
+        match self {
+            AssemblyProcess::Reflow => "reflow",
+            AssemblyProcess::Wave => "wave",
+            AssemblyProcess::Selective => "selective",
+            AssemblyProcess::HandSolder => "hand_solder",
+            AssemblyProcess::PressFit => "press_fit",
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// A set of `AssemblyProcess`es
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub struct ProcessSet(u8);
+
+impl ProcessSet {
+    pub const EMPTY: ProcessSet = ProcessSet(0);
+
+    pub fn of(processes: &[AssemblyProcess]) -> Self {
+        processes.iter().fold(Self::EMPTY, |set, &process| set.with(process))
+    }
+
+    pub fn with(self, process: AssemblyProcess) -> Self {
+        Self(self.0 | process.bit())
+    }
+
+    pub fn without(self, process: AssemblyProcess) -> Self {
+        Self(self.0 & !process.bit())
+    }
+
+    pub fn contains(self, process: AssemblyProcess) -> bool {
+        self.0 & process.bit() != 0
+    }
+
+    pub fn union(self, other: ProcessSet) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: ProcessSet) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The processes in the order of `AssemblyProcess::ALL`
+    pub fn iter(self) -> impl Iterator<Item = AssemblyProcess> {
+        AssemblyProcess::ALL.into_iter().filter(move |&process| self.contains(process))
+    }
+
+    /// `AssemblyProcess::name`s of the processes
+    pub fn names(self) -> Vec<&'static str> {
+        self.iter().map(AssemblyProcess::name).collect()
+    }
+}
+
+impl From<AssemblyProcess> for ProcessSet {
+    fn from(process: AssemblyProcess) -> Self {
+        Self::EMPTY.with(process)
+    }
+}
+
+/// The names separated by spaces, "" for the empty set
+impl fmt::Display for ProcessSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.names().join(" "))
+    }
+}
+
+/// Processes a component's pads allow: reflow for surface mount pads, wave and selective
+/// soldering for plated through-hole pads, and hand soldering unless a surface mount pad
+/// is a heatsink an iron can't reach. Parts without solderable pads get the empty set.
+pub fn default_process<T: BoardComposableObject + ?Sized>(component: &T) -> ProcessSet {
+    let mut set = ProcessSet::EMPTY;
+    let mut hidden_heatsink = false;
+    for pad in component.pad_descriptors() {
+        match pad.pad_type {
+            PadType::SMD => {
+                set = set.with(AssemblyProcess::Reflow);
+                hidden_heatsink |= pad.fab_property == Some(PadFabProperty::Heatsink);
+            }
+            PadType::ThroughHole => set = set.with(AssemblyProcess::Wave).with(AssemblyProcess::Selective),
+            PadType::NPTH => {}
+        }
+    }
+    if !set.is_empty() && !hidden_heatsink {
+        set = set.with(AssemblyProcess::HandSolder);
+    }
+    set
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SurfaceFinish {
+    Hasl,
+    LeadFreeHasl,
+    Enig,
+    Enepig,
+    ImmersionTin,
+    ImmersionSilver,
+    Osp,
+    HardGold,
+}
+
+impl SurfaceFinish {
+    /// Whether press-fit pins can go into holes of this finish: HASL leaves the holes too
+    /// uneven for the press-fit zone to hold
+    pub fn suits_press_fit(self) -> bool {
+        !matches!(self, SurfaceFinish::Hasl | SurfaceFinish::LeadFreeHasl)
+    }
+}
+
+/// Finished hole size tolerance class of the fab
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum HoleTolerance {
+    /// ±0.1 mm
+    #[default]
+    Standard,
+    /// ±0.05 mm, what press-fit pins need
+    PressFit,
+}
+
+impl HoleTolerance {
+    /// Allowed deviation of a finished hole either way, mm
+    pub fn plus_minus(self) -> f32 {
+        match self {
+            HoleTolerance::Standard => 0.1,
+            HoleTolerance::PressFit => 0.05,
+        }
+    }
+}
+
+/// Processes each side of a board goes through, and the fab properties they depend on
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProcessPlan {
+    pub front: ProcessSet,
+    pub back: ProcessSet,
+    pub finish: Option<SurfaceFinish>,
+    pub hole_tolerance: HoleTolerance,
+}
+
+impl ProcessPlan {
+    pub fn new(front: impl Into<ProcessSet>, back: impl Into<ProcessSet>) -> Self {
+        Self { front: front.into(), back: back.into(), finish: None, hole_tolerance: HoleTolerance::Standard }
+    }
+
+    pub fn with_finish(mut self, finish: SurfaceFinish) -> Self {
+        self.finish = Some(finish);
+        self
+    }
+
+    pub fn with_hole_tolerance(mut self, hole_tolerance: HoleTolerance) -> Self {
+        self.hole_tolerance = hole_tolerance;
+        self
+    }
+
+    /// Processes of a side; inner layers have none
+    pub fn side(&self, side: Side) -> ProcessSet {
+        match side {
+            Side::Front => self.front,
+            Side::Back => self.back,
+            Side::Inner(_) => ProcessSet::EMPTY,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessIssue {
+    /// No process the part allows is planned where it sits
+    Incompatible,
+    /// A surface mount part that isn't wave solderable on a wave soldered side
+    WaveExposed,
+    /// Only hand soldering fits the part
+    HandSolderOnly,
+    /// A press-fit part on a board without the press-fit hole tolerance class
+    PressFitHoleTolerance,
+    /// A press-fit part on a finish that doesn't take press-fit pins
+    PressFitFinish,
+}
+
+/// A placed part at odds with the board's `ProcessPlan`, see the module documentation
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessViolation {
+    pub reference: String,
+    pub side: Side,
+    pub issue: ProcessIssue,
+    /// Processes the part allows
+    pub process: ProcessSet,
+    /// Processes planned where it sits
+    pub planned: ProcessSet,
+    pub location: Point,
+}
+
+impl fmt::Display for ProcessViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = if self.side == Side::Front { "front" } else { "back" };
+        match self.issue {
+            ProcessIssue::Incompatible => {
+                write!(f, "{} ({}) can't be soldered by the processes planned for it on the {} ({})", self.reference, self.process, side, self.planned)
+            }
+            ProcessIssue::WaveExposed => write!(f, "{} ({}) goes through the wave on the {} but isn't wave solderable", self.reference, self.process, side),
+            ProcessIssue::HandSolderOnly => write!(f, "{} has to be hand soldered on the {}", self.reference, side),
+            ProcessIssue::PressFitHoleTolerance => write!(f, "{} is press-fit but the board's holes aren't held to the press-fit tolerance", self.reference),
+            ProcessIssue::PressFitFinish => write!(f, "{} is press-fit but the board's finish doesn't take press-fit pins", self.reference),
+        }
+    }
+}
+
+impl Violation for ProcessViolation {
+    fn rule(&self) -> &'static str {
+        match self.issue {
+            ProcessIssue::Incompatible => "process_incompatible",
+            ProcessIssue::WaveExposed => "process_wave_exposed",
+            ProcessIssue::HandSolderOnly => "process_hand_solder",
+            ProcessIssue::PressFitHoleTolerance => "press_fit_hole_tolerance",
+            ProcessIssue::PressFitFinish => "press_fit_finish",
+        }
+    }
+
+    fn default_severity(&self) -> Severity {
+        match self.issue {
+            ProcessIssue::HandSolderOnly => Severity::Info,
+            _ => Severity::Warning,
+        }
+    }
+
+    fn footprint(&self) -> String {
+        self.reference.clone()
+    }
+
+    fn location(&self) -> Option<Point> {
+        Some(self.location)
+    }
+
+    fn elements(&self) -> Vec<ElementRef> {
+        vec![ElementRef::Component(self.reference.clone())]
+    }
+}
+
+impl Board {
+    pub fn with_process_plan(mut self, plan: ProcessPlan) -> Self {
+        self.process_plan = Some(plan);
+        self
+    }
+
+    pub fn process_plan(&self) -> Option<&ProcessPlan> {
+        self.process_plan.as_ref()
+    }
+
+    pub fn set_process_plan(&mut self, plan: Option<ProcessPlan>) {
+        self.process_plan = plan;
+    }
+
+    /// Placed parts at odds with the process plan, none without one; see the module
+    /// documentation
+    pub fn process_violations(&self) -> Vec<ProcessViolation> {
+        let Some(plan) = self.process_plan else {
+            return Vec::new();
+        };
+        let mut violations = Vec::new();
+        for placed in self.components() {
+            let process = placed.component.process();
+            if process.is_empty() {
+                continue;
+            }
+            let pads = placed.component.pad_descriptors();
+            let through_hole = pads.iter().any(|pad| pad.pad_type == PadType::ThroughHole);
+            let surface_mount = pads.iter().any(|pad| pad.pad_type == PadType::SMD);
+            let own_side = plan.side(placed.side);
+            let planned = if through_hole { plan.front.union(plan.back) } else { own_side };
+            let mut report = |issue| {
+                violations.push(ProcessViolation {
+                    reference: placed.reference.clone(),
+                    side: placed.side,
+                    issue,
+                    process,
+                    planned,
+                    location: self.board_point(placed, Point::default()),
+                })
+            };
+            let fitting = process.intersection(planned);
+            if surface_mount && own_side.contains(AssemblyProcess::Wave) && !process.contains(AssemblyProcess::Wave) {
+                report(ProcessIssue::WaveExposed);
+            } else if fitting.is_empty() {
+                report(ProcessIssue::Incompatible);
+            } else if fitting == AssemblyProcess::HandSolder.into() {
+                report(ProcessIssue::HandSolderOnly);
+            }
+            if process.contains(AssemblyProcess::PressFit) {
+                if plan.hole_tolerance != HoleTolerance::PressFit {
+                    report(ProcessIssue::PressFitHoleTolerance);
+                }
+                if plan.finish.is_some_and(|finish| !finish.suits_press_fit()) {
+                    report(ProcessIssue::PressFitFinish);
+                }
+            }
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::findings::Finding;
+    use crate::dip::Dip;
+    use crate::footprint::Footprint;
+    use crate::presets;
+    use crate::qfn::Qfn;
+    use std::rc::Rc;
+
+    fn wave_bottom() -> ProcessPlan {
+        ProcessPlan::new(AssemblyProcess::Reflow, AssemblyProcess::Wave)
+    }
+
+    #[test]
+    fn defaults_follow_the_pads() {
+        assert_eq!(Qfn::new(16, 0.5, 3.0, 3.0).process(), AssemblyProcess::Reflow.into());
+        assert_eq!(default_process(&Dip::new(8).unwrap()), ProcessSet::of(&[AssemblyProcess::Wave, AssemblyProcess::Selective, AssemblyProcess::HandSolder]));
+        let resistor = presets::resistor_0603("10k");
+        assert!(resistor.process().contains(AssemblyProcess::Reflow) && resistor.process().contains(AssemblyProcess::HandSolder));
+        assert_eq!(Footprint::capture(&resistor).process(), resistor.process());
+        assert_eq!(ProcessSet::of(&[AssemblyProcess::PressFit, AssemblyProcess::Reflow]).to_string(), "reflow press_fit");
+    }
+
+    #[test]
+    fn bottom_side_qfn_on_a_wave_side_is_flagged() {
+        let mut board = Board::new("wave").with_process_plan(wave_bottom());
+        board.place_shared("U1", Rc::new(Qfn::new(16, 0.5, 3.0, 3.0)), (10.0, 10.0), 0.0, Side::Back).unwrap();
+        board.place_shared("U2", Rc::new(Qfn::new(16, 0.5, 3.0, 3.0)), (20.0, 10.0), 0.0, Side::Front).unwrap();
+        board.place_shared("J1", Rc::new(Dip::new(8).unwrap()), (30.0, 10.0), 0.0, Side::Front).unwrap();
+        let violations = board.process_violations();
+        assert_eq!(violations.len(), 1, "{violations:?}");
+        assert_eq!((violations[0].reference.as_str(), violations[0].issue), ("U1", ProcessIssue::WaveExposed));
+        let findings: Vec<Finding> = board.drc().into_iter().filter(|finding| finding.rule.starts_with("process_")).collect();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Warning);
+        assert_eq!(findings[0].footprint, "U1");
+
+        board.set_process_plan(None);
+        assert!(board.process_violations().is_empty());
+    }
+
+    #[test]
+    fn reflow_sides_leave_through_hole_parts_to_the_iron() {
+        let plan = ProcessPlan::new(ProcessSet::of(&[AssemblyProcess::Reflow, AssemblyProcess::HandSolder]), AssemblyProcess::Reflow);
+        let mut board = Board::new("reflow").with_process_plan(plan);
+        board.place_shared("J1", Rc::new(Dip::new(8).unwrap()), (30.0, 10.0), 0.0, Side::Front).unwrap();
+        let violations = board.process_violations();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].issue, ProcessIssue::HandSolderOnly);
+        assert_eq!(violations[0].default_severity(), Severity::Info);
+
+        board.set_process_plan(Some(ProcessPlan::new(AssemblyProcess::Reflow, AssemblyProcess::Reflow)));
+        assert_eq!(board.process_violations()[0].issue, ProcessIssue::Incompatible);
+    }
+
+    #[test]
+    fn press_fit_needs_its_hole_tolerance_and_finish() {
+        let press_fit = Footprint { process: Some(AssemblyProcess::PressFit.into()), ..Footprint::capture(&Dip::new(8).unwrap()) };
+        let plan = ProcessPlan::new(AssemblyProcess::PressFit, ProcessSet::EMPTY).with_finish(SurfaceFinish::Hasl);
+        let mut board = Board::new("press").with_process_plan(plan);
+        board.place_shared("J1", Rc::new(press_fit), (10.0, 10.0), 0.0, Side::Front).unwrap();
+        let rules: Vec<&str> = board.process_violations().iter().map(Violation::rule).collect();
+        assert_eq!(rules, ["press_fit_hole_tolerance", "press_fit_finish"]);
+
+        board.set_process_plan(Some(plan.with_finish(SurfaceFinish::Enig).with_hole_tolerance(HoleTolerance::PressFit)));
+        assert!(board.process_violations().is_empty());
+    }
+}
@@ -0,0 +1,700 @@
+//! Grid autorouter for two layer test boards
+//!
+//! `Board::autoroute` routes what `Board::unconnected_pads` reports on F.Cu and B.Cu, for
+//! coupons and breakouts rather than dense designs. The board is laid out as a grid of
+//! `AutorouteOptions::grid` pitch, and each net's tree grows pad by pad with an A* search
+//! from everything already connected to the nearest pad left. Steps go along the grid and
+//! its diagonals; changing layers drops a through via. Turns and vias cost extra, so routes
+//! stay straight and on one layer where they can.
+//!
+//! A grid point is open to a net when a track of its class width centered there keeps its
+//! clearance to the copper of every other net (pads, tracks, arcs and vias, with the
+//! clearance of their classes or a clearance rule, as `Board::routing_violations` reckons
+//! it), to the board edge, and out of footprint keepouts. Points are checked with an extra
+//! half diagonal of the grid, so the straight steps between them keep the clearance too.
+//! Vias need their own diameter clear in the same way on both layers and never sit on a
+//! pad. Zones don't block anything, they are filled around the routes.
+//!
+//! Nets go shortest first, by the half perimeter of their pads' bounds. When some fail,
+//! the routes are ripped up and everything is routed again with the failed nets first, up
+//! to `rip_up_passes` times; the board keeps the pass that made the most connections.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use std::fmt;
+
+use crate::board::Board;
+use crate::board_interface::Rectangle;
+use crate::geometry::{point_in_polygon, point_segment_distance, Point};
+use crate::layer_type::{BoardLayer, LayerType, Side};
+use crate::routing::{Track, Via, ROUTING_ARC_TOLERANCE};
+
+/// Cost of a step along the grid; a diagonal step costs `DIAGONAL_COST`
+const STEP_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+/// Extra cost of changing direction, in the same units
+const TURN_COST: u32 = 5;
+/// Grid directions, straight ones first; index `DIRECTIONS.len()` is "no direction yet"
+const DIRECTIONS: [(i32, i32); 8] = [(1, 0), (0, 1), (-1, 0), (0, -1), (1, 1), (-1, 1), (-1, -1), (1, -1)];
+
+/// Settings of `Board::autoroute`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutorouteOptions {
+    /// Grid pitch (mm)
+    pub grid: f32,
+    /// Cost of a via, in grid steps
+    pub via_cost: f32,
+    /// How often failed nets are moved to the front and everything is routed again
+    pub rip_up_passes: usize,
+    /// Nets to route, every unconnected net when empty
+    pub nets: Vec<String>,
+}
+
+impl Default for AutorouteOptions {
+    fn default() -> Self {
+        Self { grid: 0.2, via_cost: 10.0, rip_up_passes: 3, nets: Vec::new() }
+    }
+}
+
+impl AutorouteOptions {
+    pub fn with_grid(mut self, grid: f32) -> Self {
+        self.grid = grid;
+        self
+    }
+
+    pub fn with_via_cost(mut self, steps: f32) -> Self {
+        self.via_cost = steps;
+        self
+    }
+
+    pub fn with_rip_up_passes(mut self, passes: usize) -> Self {
+        self.rip_up_passes = passes;
+        self
+    }
+
+    /// Route only `nets`
+    pub fn with_nets(mut self, nets: &[&str]) -> Self {
+        self.nets = nets.iter().map(|net| net.to_string()).collect();
+        self
+    }
+}
+
+/// What `Board::autoroute` did. A connection joins one island of a net's pads (see
+/// `UnconnectedPads`) to the rest.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AutorouteReport {
+    /// Nets routed completely, in the order of the kept pass
+    pub routed: Vec<String>,
+    /// Nets with connections left unrouted
+    pub failed: Vec<String>,
+    pub connections: usize,
+    pub completed: usize,
+    /// Tracks and vias added
+    pub tracks: usize,
+    pub vias: usize,
+    /// Passes run, the first included
+    pub passes: usize,
+}
+
+impl AutorouteReport {
+    /// Share of the connections made, in percent; 100 when there was nothing to route
+    pub fn completion(&self) -> f32 {
+        if self.connections == 0 { 100.0 } else { self.completed as f32 * 100.0 / self.connections as f32 }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.completed == self.connections
+    }
+}
+
+impl fmt::Display for AutorouteReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "routed {} of {} connections ({:.1}%) with {} tracks and {} vias in {} passes",
+            self.completed,
+            self.connections,
+            self.completion(),
+            self.tracks,
+            self.vias,
+            self.passes
+        )?;
+        if !self.failed.is_empty() {
+            write!(f, "; failed: {}", self.failed.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Copper of another net, or a keepout, on one of the two layers
+#[derive(Debug, Clone)]
+enum Shape {
+    Capsule { start: Point, end: Point, radius: f32 },
+    Polygon(Vec<Point>),
+}
+
+impl Shape {
+    /// Distance from `point` to the shape's edge, zero inside it
+    fn distance(&self, point: Point) -> f32 {
+        match self {
+            Shape::Capsule { start, end, radius } => (point_segment_distance(point, *start, *end).0 - radius).max(0.0),
+            Shape::Polygon(polygon) => polygon_distance_from(point, polygon),
+        }
+    }
+
+    fn bounds(&self) -> Option<Rectangle> {
+        match self {
+            Shape::Capsule { start, end, radius } => Some(Rectangle {
+                min_x: start.x.min(end.x) - radius,
+                min_y: start.y.min(end.y) - radius,
+                max_x: start.x.max(end.x) + radius,
+                max_y: start.y.max(end.y) + radius,
+            }),
+            Shape::Polygon(polygon) => Rectangle::enclosing(polygon),
+        }
+    }
+}
+
+fn polygon_distance_from(point: Point, polygon: &[Point]) -> f32 {
+    if point_in_polygon(point, polygon) {
+        return 0.0;
+    }
+    (0..polygon.len()).map(|i| point_segment_distance(point, polygon[i], polygon[(i + 1) % polygon.len()]).0).fold(f32::INFINITY, f32::min)
+}
+
+/// Copper on a layer (0 front, 1 back) of a net, `None` for pads on no net
+#[derive(Debug, Clone)]
+struct Obstacle {
+    net: Option<String>,
+    layer: usize,
+    shape: Shape,
+    /// Pads keep vias off even on their own net
+    pad: bool,
+}
+
+/// Footprint keepout on a layer
+#[derive(Debug, Clone)]
+struct Keepout {
+    layer: usize,
+    polygon: Vec<Point>,
+    no_tracks: bool,
+    no_vias: bool,
+}
+
+/// Snap to 0.1 µm so routes are written without float noise; adding zero turns -0 into 0
+fn snap(value: f32) -> f32 {
+    (value * 1e4).round() / 1e4 + 0.0
+}
+
+/// The routing grid over the board, two layers deep
+#[derive(Debug, Clone, Copy)]
+struct Grid {
+    origin: Point,
+    pitch: f32,
+    columns: usize,
+    rows: usize,
+}
+
+impl Grid {
+    fn cells(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    fn center(&self, cell: usize) -> Point {
+        let (column, row) = (cell % self.columns, cell / self.columns);
+        Point::new(snap(self.origin.x + (column as f32 + 0.5) * self.pitch), snap(self.origin.y + (row as f32 + 0.5) * self.pitch))
+    }
+
+    /// Cells whose centers lie within `bounds` grown by `margin`
+    fn cells_near(&self, bounds: &Rectangle, margin: f32) -> impl Iterator<Item = usize> + '_ {
+        let index = |value: f32, origin: f32, count: usize| ((value - origin) / self.pitch - 0.5).clamp(0.0, count as f32 - 1.0);
+        let (first_column, last_column) = (index(bounds.min_x - margin, self.origin.x, self.columns).ceil() as usize, index(bounds.max_x + margin, self.origin.x, self.columns).floor() as usize);
+        let (first_row, last_row) = (index(bounds.min_y - margin, self.origin.y, self.rows).ceil() as usize, index(bounds.max_y + margin, self.origin.y, self.rows).floor() as usize);
+        (first_row..=last_row).flat_map(move |row| (first_column..=last_column).map(move |column| row * self.columns + column))
+    }
+
+    /// The neighbour of `cell` one step in `direction`
+    fn step(&self, cell: usize, direction: (i32, i32)) -> Option<usize> {
+        let column = (cell % self.columns) as i32 + direction.0;
+        let row = (cell / self.columns) as i32 + direction.1;
+        (column >= 0 && row >= 0 && (column as usize) < self.columns && (row as usize) < self.rows).then(|| row as usize * self.columns + column as usize)
+    }
+}
+
+/// Where one net may go: per layer, cells a track may pass, and cells a via may sit on
+struct Openings {
+    track: [Vec<bool>; 2],
+    via: Vec<bool>,
+}
+
+/// One pass of routing state: the fixed obstacles, and the pads' cells on each layer
+struct Router<'a> {
+    board: &'a Board,
+    grid: Grid,
+    options: &'a AutorouteOptions,
+    layers: [BoardLayer; 2],
+    outline: Option<Vec<Point>>,
+    obstacles: Vec<Obstacle>,
+    keepouts: Vec<Keepout>,
+    /// Pad name to its copper outline on each layer it has copper on
+    pads: BTreeMap<String, Vec<(usize, Vec<Point>)>>,
+}
+
+impl<'a> Router<'a> {
+    fn new(board: &'a Board, grid: Grid, options: &'a AutorouteOptions) -> Self {
+        let layers = [BoardLayer::front(LayerType::Copper), BoardLayer::back(LayerType::Copper)];
+        let mut router = Router { board, grid, options, layers, outline: board.outline().map(<[Point]>::to_vec), obstacles: Vec::new(), keepouts: Vec::new(), pads: BTreeMap::new() };
+        for (layer, board_layer) in layers.iter().enumerate() {
+            for (name, outline) in board.pad_outlines(*board_layer, ROUTING_ARC_TOLERANCE) {
+                let net = name.rsplit_once('.').and_then(|(reference, number)| board.pad_net(reference, number)).map(str::to_string);
+                router.obstacles.push(Obstacle { net, layer, shape: Shape::Polygon(outline.clone()), pad: true });
+                router.pads.entry(name).or_default().push((layer, outline));
+            }
+        }
+        for track in board.tracks() {
+            router.add_capsule(&track.net, track.layer, track.start, track.end, track.width / 2.0);
+        }
+        for arc in board.arcs() {
+            for (start, end) in arc.chords(ROUTING_ARC_TOLERANCE) {
+                router.add_capsule(&arc.net, arc.layer, start, end, arc.width / 2.0);
+            }
+        }
+        for via in board.vias() {
+            for layer in layers.iter().filter(|layer| via.spans(layer)) {
+                router.add_capsule(&via.net, *layer, via.position, via.position, via.diameter / 2.0);
+            }
+        }
+        for placed in board.components() {
+            for area in placed.component.rule_areas() {
+                let polygon: Vec<Point> = area.polygon.iter().map(|point| board.board_point(placed, Point::from(*point))).collect();
+                for (layer, board_layer) in layers.iter().enumerate() {
+                    // Area layers are named as the footprint sees them
+                    let local = match board_layer.side {
+                        Side::Front if placed.side == Side::Back => BoardLayer::back(LayerType::Copper),
+                        Side::Back if placed.side == Side::Back => BoardLayer::front(LayerType::Copper),
+                        _ => *board_layer,
+                    };
+                    if area.has_layer(&local.to_kicad_string()) && (area.no_tracks || area.no_vias) {
+                        router.keepouts.push(Keepout { layer, polygon: polygon.clone(), no_tracks: area.no_tracks, no_vias: area.no_vias });
+                    }
+                }
+            }
+        }
+        router
+    }
+
+    fn add_capsule(&mut self, net: &str, layer: BoardLayer, start: Point, end: Point, radius: f32) {
+        if let Some(layer) = self.layers.iter().position(|l| *l == layer) {
+            self.obstacles.push(Obstacle { net: Some(net.to_string()), layer, shape: Shape::Capsule { start, end, radius }, pad: false });
+        }
+    }
+
+    /// Cells of `net` that keep clear of everything else, see the module documentation
+    fn openings(&self, net: &str) -> Openings {
+        let class = self.board.net_class(net);
+        let (half_track, half_via) = (class.track_width / 2.0, class.via_diameter / 2.0);
+        // Steps between open cells stay within half a diagonal of one
+        let slack = self.grid.pitch * std::f32::consts::FRAC_1_SQRT_2;
+        let cells = self.grid.cells();
+        let mut openings = Openings { track: [vec![true; cells], vec![true; cells]], via: vec![true; cells] };
+
+        let edge = class.clearance;
+        for cell in 0..cells {
+            let center = self.grid.center(cell);
+            let Some(outline) = &self.outline else { break };
+            let inside = point_in_polygon(center, outline);
+            let to_edge = (0..outline.len()).map(|i| point_segment_distance(center, outline[i], outline[(i + 1) % outline.len()]).0).fold(f32::INFINITY, f32::min);
+            if !inside || to_edge < edge + half_track + slack {
+                openings.track[0][cell] = false;
+                openings.track[1][cell] = false;
+            }
+            if !inside || to_edge < edge + half_via + slack {
+                openings.via[cell] = false;
+            }
+        }
+
+        let mut clearances: HashMap<Option<&str>, f32> = HashMap::new();
+        for obstacle in &self.obstacles {
+            let own = obstacle.net.as_deref() == Some(net);
+            if own && !obstacle.pad {
+                continue;
+            }
+            let clearance = if own { 0.0 } else { *clearances.entry(obstacle.net.as_deref()).or_insert_with(|| self.board.required_clearance(Some(net), obstacle.net.as_deref())) };
+            let (track_reach, via_reach) = (clearance + half_track + slack, clearance + half_via + slack);
+            let Some(bounds) = obstacle.shape.bounds() else { continue };
+            for cell in self.grid.cells_near(&bounds, track_reach.max(via_reach)) {
+                let distance = obstacle.shape.distance(self.grid.center(cell));
+                if !own && distance < track_reach {
+                    openings.track[obstacle.layer][cell] = false;
+                }
+                // A via on its own pad would be a via in pad
+                if distance < if own { half_via } else { via_reach } {
+                    openings.via[cell] = false;
+                }
+            }
+        }
+
+        for keepout in &self.keepouts {
+            let Some(bounds) = Rectangle::enclosing(&keepout.polygon) else { continue };
+            for cell in self.grid.cells_near(&bounds, half_track.max(half_via) + slack) {
+                let distance = polygon_distance_from(self.grid.center(cell), &keepout.polygon);
+                if keepout.no_tracks && distance < half_track + slack {
+                    openings.track[keepout.layer][cell] = false;
+                }
+                if keepout.no_vias && distance < half_via + slack {
+                    openings.via[cell] = false;
+                }
+            }
+        }
+        openings
+    }
+
+    /// Open cells of a pad on each layer
+    fn pad_cells(&self, pad: &str, openings: &Openings) -> Vec<(usize, usize)> {
+        let mut cells = Vec::new();
+        for (layer, outline) in self.pads.get(pad).into_iter().flatten() {
+            let Some(bounds) = Rectangle::enclosing(outline) else { continue };
+            cells.extend(
+                self.grid.cells_near(&bounds, 0.0).filter(|&cell| openings.track[*layer][cell] && point_in_polygon(self.grid.center(cell), outline)).map(|cell| (*layer, cell)),
+            );
+        }
+        cells
+    }
+
+    /// Cheapest path from any of `sources` to a cell marked in `targets`, as (layer, cell)
+    /// from source to target
+    fn search(&self, openings: &Openings, sources: &[(usize, usize)], targets: &[Vec<bool>; 2], target_bounds: &[Rectangle]) -> Option<Vec<(usize, usize)>> {
+        let grid = self.grid;
+        let none = DIRECTIONS.len();
+        let states = 2 * grid.cells() * (none + 1);
+        let state = |layer: usize, cell: usize, direction: usize| (layer * grid.cells() + cell) * (none + 1) + direction;
+        let via_cost = (self.options.via_cost.max(0.0) * STEP_COST as f32).round() as u32;
+        // Grid steps from a cell to the nearest target bounds, diagonals first
+        let heuristic = |cell: usize| {
+            let center = grid.center(cell);
+            target_bounds
+                .iter()
+                .map(|bounds| {
+                    let dx = ((bounds.min_x - center.x).max(center.x - bounds.max_x).max(0.0) / grid.pitch).floor() as u32;
+                    let dy = ((bounds.min_y - center.y).max(center.y - bounds.max_y).max(0.0) / grid.pitch).floor() as u32;
+                    DIAGONAL_COST * dx.min(dy) + STEP_COST * dx.abs_diff(dy)
+                })
+                .min()
+                .unwrap_or(0)
+        };
+
+        let mut cost = vec![u32::MAX; states];
+        let mut parent = vec![u32::MAX; states];
+        let mut queue = BinaryHeap::new();
+        for &(layer, cell) in sources {
+            let start = state(layer, cell, none);
+            cost[start] = 0;
+            queue.push(Reverse((heuristic(cell), start)));
+        }
+        while let Some(Reverse((_, current))) = queue.pop() {
+            let direction = current % (none + 1);
+            let layer = current / (none + 1) / grid.cells();
+            let cell = current / (none + 1) % grid.cells();
+            if targets[layer][cell] {
+                let mut path = vec![(layer, cell)];
+                let mut at = current;
+                while parent[at] != u32::MAX {
+                    at = parent[at] as usize;
+                    path.push((at / (none + 1) / grid.cells(), at / (none + 1) % grid.cells()));
+                }
+                path.reverse();
+                path.dedup();
+                return Some(path);
+            }
+            let here = cost[current];
+            let mut relax = |next: usize, step: u32, cell: usize, queue: &mut BinaryHeap<Reverse<(u32, usize)>>| {
+                if here + step < cost[next] {
+                    cost[next] = here + step;
+                    parent[next] = current as u32;
+                    queue.push(Reverse((here + step + heuristic(cell), next)));
+                }
+            };
+            for (index, &offset) in DIRECTIONS.iter().enumerate() {
+                let Some(next) = grid.step(cell, offset) else { continue };
+                if !openings.track[layer][next] {
+                    continue;
+                }
+                let step = if index < 4 { STEP_COST } else { DIAGONAL_COST } + if direction != none && direction != index { TURN_COST } else { 0 };
+                relax(state(layer, next, index), step, next, &mut queue);
+            }
+            if openings.via[cell] && openings.track[1 - layer][cell] {
+                relax(state(1 - layer, cell, none), via_cost, cell, &mut queue);
+            }
+        }
+        None
+    }
+
+    /// Tracks and vias along a path, straight runs merged into one track
+    fn copper(&self, net: &str, path: &[(usize, usize)]) -> (Vec<Track>, Vec<Via>) {
+        let class = self.board.net_class(net);
+        let (mut tracks, mut vias) = (Vec::new(), Vec::new());
+        let mut run_start = 0;
+        for index in 1..=path.len() {
+            let layer_change = index < path.len() && path[index].0 != path[index - 1].0;
+            if index < path.len() && !layer_change {
+                continue;
+            }
+            // One layer from run_start to index - 1: a track per straight stretch
+            let run = &path[run_start..index];
+            let mut corner = 0;
+            for i in 1..run.len() {
+                let turns = i + 1 == run.len() || {
+                    let (a, b, c) = (self.grid.center(run[i - 1].1), self.grid.center(run[i].1), self.grid.center(run[i + 1].1));
+                    ((b.x - a.x) * (c.y - b.y) - (b.y - a.y) * (c.x - b.x)).abs() > 1e-6
+                };
+                if turns {
+                    let layer = self.layers[run[0].0];
+                    tracks.push(Track::new(net, layer, self.grid.center(run[corner].1), self.grid.center(run[i].1), class.track_width));
+                    corner = i;
+                }
+            }
+            if layer_change {
+                vias.push(Via::new(net, self.grid.center(path[index].1), class.via_diameter, class.via_drill));
+            }
+            run_start = index;
+        }
+        (tracks, vias)
+    }
+}
+
+impl Board {
+    /// Route the pads `unconnected_pads` reports on the two outer copper layers and say
+    /// how far it got; see the module documentation
+    pub fn autoroute(&mut self, options: &AutorouteOptions) -> AutorouteReport {
+        // Islands to connect per net, and the pads already joined to the rest
+        let mut islands: BTreeMap<String, Vec<Vec<String>>> = BTreeMap::new();
+        for island in self.unconnected_pads() {
+            if options.nets.is_empty() || options.nets.contains(&island.net) {
+                islands.entry(island.net).or_default().push(island.pads);
+            }
+        }
+        let connections = islands.values().map(Vec::len).sum();
+        let Some(bounds) = self.outline().and_then(Rectangle::enclosing).or_else(|| self.pads_bounds()) else {
+            return AutorouteReport { failed: islands.into_keys().collect(), connections, ..AutorouteReport::default() };
+        };
+        let pitch = options.grid;
+        let grid = Grid {
+            origin: Point::new(bounds.min_x, bounds.min_y),
+            pitch,
+            columns: ((bounds.max_x - bounds.min_x) / pitch).ceil().max(1.0) as usize,
+            rows: ((bounds.max_y - bounds.min_y) / pitch).ceil().max(1.0) as usize,
+        };
+
+        let mut order: Vec<String> = islands.keys().cloned().collect();
+        let spans: BTreeMap<String, f32> = order.iter().map(|net| (net.clone(), self.net_span(net))).collect();
+        order.sort_by(|a, b| spans[a].total_cmp(&spans[b]));
+
+        let start = self.snapshot();
+        let mut best: Option<(AutorouteReport, _)> = None;
+        let mut passes = 0;
+        while passes <= options.rip_up_passes {
+            self.restore(&start);
+            let report = self.route_pass(grid, options, &order, &islands);
+            passes += 1;
+            if best.as_ref().is_none_or(|(kept, _): &(AutorouteReport, _)| report.completed > kept.completed) {
+                best = Some((report.clone(), self.snapshot()));
+            }
+            if report.failed.is_empty() {
+                break;
+            }
+            // Failed nets first next time, the rest in their order
+            let failed = report.failed;
+            order.retain(|net| !failed.contains(net));
+            order.splice(0..0, failed);
+        }
+        let (mut report, snapshot) = best.expect("at least one pass");
+        self.restore(&snapshot);
+        report.connections = connections;
+        report.passes = passes;
+        report
+    }
+
+    /// Route every net of `order` once over the current board
+    fn route_pass(&mut self, grid: Grid, options: &AutorouteOptions, order: &[String], islands: &BTreeMap<String, Vec<Vec<String>>>) -> AutorouteReport {
+        let mut report = AutorouteReport::default();
+        let mut added_tracks = Vec::new();
+        let mut added_vias = Vec::new();
+        {
+            let mut router = Router::new(self, grid, options);
+            for net in order {
+                let openings = router.openings(net);
+                let unconnected = &islands[net];
+                let joined: Vec<&String> = router.pads.keys().filter(|pad| {
+                    let (reference, number) = pad.rsplit_once('.').unwrap_or_default();
+                    router.board.pad_net(reference, number) == Some(net.as_str()) && !unconnected.iter().flatten().any(|listed| listed == *pad)
+                }).collect();
+                let mut sources: Vec<(usize, usize)> = joined.iter().flat_map(|pad| router.pad_cells(pad, &openings)).collect();
+                let mut left: Vec<&Vec<String>> = unconnected.iter().collect();
+                // A net cut in pieces with none of them the main one starts from its first island
+                if sources.is_empty() && !left.is_empty() {
+                    let first = left.remove(0);
+                    sources = first.iter().flat_map(|pad| router.pad_cells(pad, &openings)).collect();
+                    report.completed += 1;
+                }
+                let mut complete = true;
+                while !left.is_empty() {
+                    let mut targets = [vec![false; grid.cells()], vec![false; grid.cells()]];
+                    let mut owner = HashMap::new();
+                    let mut target_bounds = Vec::new();
+                    for (index, island) in left.iter().enumerate() {
+                        for pad in island.iter() {
+                            for (layer, cell) in router.pad_cells(pad, &openings) {
+                                targets[layer][cell] = true;
+                                owner.insert((layer, cell), index);
+                            }
+                            target_bounds.extend(router.pads.get(pad).into_iter().flatten().filter_map(|(_, outline)| Rectangle::enclosing(outline)));
+                        }
+                    }
+                    let found = if sources.is_empty() { None } else { router.search(&openings, &sources, &targets, &target_bounds) };
+                    let Some(path) = found else {
+                        complete = false;
+                        break;
+                    };
+                    let island = left.remove(owner[path.last().unwrap()]);
+                    let (tracks, vias) = router.copper(net, &path);
+                    for track in &tracks {
+                        router.add_capsule(net, track.layer, track.start, track.end, track.width / 2.0);
+                    }
+                    for via in &vias {
+                        for layer in router.layers {
+                            router.add_capsule(net, layer, via.position, via.position, via.diameter / 2.0);
+                        }
+                    }
+                    sources.extend(path.iter().copied());
+                    for via in &vias {
+                        let cell = path.iter().find(|(_, cell)| grid.center(*cell) == via.position).map(|(_, cell)| *cell);
+                        sources.extend(cell.into_iter().flat_map(|cell| [(0, cell), (1, cell)]));
+                    }
+                    sources.extend(island.iter().flat_map(|pad| router.pad_cells(pad, &openings)));
+                    added_tracks.extend(tracks);
+                    added_vias.extend(vias);
+                    report.completed += 1;
+                }
+                if complete { report.routed.push(net.clone()) } else { report.failed.push(net.clone()) }
+            }
+        }
+        report.tracks = added_tracks.len();
+        report.vias = added_vias.len();
+        for track in added_tracks {
+            self.add_track(track);
+        }
+        for via in added_vias {
+            self.add_via(via);
+        }
+        report
+    }
+
+    /// Half perimeter of the bounds of a net's pads
+    fn net_span(&self, net: &str) -> f32 {
+        let centers = self.components().iter().flat_map(|placed| {
+            placed
+                .component
+                .pad_descriptors()
+                .into_iter()
+                .filter(|pad| self.pad_net(&placed.reference, &pad.number) == Some(net))
+                .map(|pad| self.board_point(placed, Point::from(pad.position)))
+                .collect::<Vec<_>>()
+        });
+        let points: Vec<Point> = centers.collect();
+        Rectangle::enclosing(&points).map_or(0.0, |bounds| bounds.width() + bounds.height())
+    }
+
+    /// Bounds of every pad on the board, for boards without an outline
+    fn pads_bounds(&self) -> Option<Rectangle> {
+        let points: Vec<Point> = [BoardLayer::front(LayerType::Copper), BoardLayer::back(LayerType::Copper)]
+            .into_iter()
+            .flat_map(|layer| self.pad_outlines(layer, ROUTING_ARC_TOLERANCE))
+            .flat_map(|(_, outline)| outline)
+            .collect();
+        Rectangle::enclosing(&points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::Severity;
+    use crate::footprint::Footprint;
+    use crate::netlist::Netlist;
+    use crate::presets;
+    use crate::rule_area::RuleArea;
+
+    /// Three 0603 resistors in a row, A and B between neighbours and GND from the first to
+    /// the last, past the middle one
+    fn board() -> Board {
+        let mut board = Board::new("autoroute").with_rectangular_outline(30.0, 20.0);
+        for (index, x) in [5.0, 15.0, 25.0].into_iter().enumerate() {
+            board.place(&format!("R{}", index + 1), presets::resistor_0603("10k"), (x, 10.0), 0.0).unwrap();
+        }
+        board.apply_netlist(&Netlist::new().with_net("A", &["R1.2", "R2.1"]).with_net("B", &["R2.2", "R3.1"]).with_net("GND", &["R1.1", "R3.2"])).unwrap();
+        board
+    }
+
+    /// A padless footprint holding a copper keepout of `bounds`, centered on the board
+    fn keepout(board: &mut Board, bounds: Rectangle) {
+        let mut footprint = Footprint::capture(&presets::resistor_0603("10k"));
+        footprint.pads.clear();
+        footprint.texts.clear();
+        footprint.graphics.clear();
+        footprint.courtyards.clear();
+        footprint.rule_areas = vec![RuleArea::keepout_rect("keepout", &bounds, &["*.Cu"])];
+        board.place("K1", footprint, (10.0, 10.0), 0.0).unwrap();
+    }
+
+    fn errors(board: &Board) -> Vec<String> {
+        board.drc().into_iter().filter(|finding| finding.severity == Severity::Error).map(|finding| finding.message).collect()
+    }
+
+    #[test]
+    fn a_simple_board_routes_completely_and_cleanly() {
+        let mut board = board();
+        let report = board.autoroute(&AutorouteOptions::default());
+        assert!(report.is_complete(), "{}", report);
+        assert_eq!(report.completion(), 100.0);
+        assert_eq!((report.connections, report.passes), (3, 1));
+        // Shortest first: A and B span 10 mm, GND 20 mm
+        assert_eq!(report.routed.last().map(String::as_str), Some("GND"), "{:?}", report.routed);
+        assert_eq!(report.tracks, board.tracks().len());
+        assert_eq!(report.vias, board.vias().len());
+        assert!(board.unconnected_pads().is_empty());
+        assert!(errors(&board).is_empty(), "{:#?}", errors(&board));
+
+        // Nothing left to route, nothing added
+        let again = board.autoroute(&AutorouteOptions::default());
+        assert_eq!((again.connections, again.tracks, again.vias), (0, 0, 0));
+        assert_eq!(again.completion(), 100.0);
+    }
+
+    #[test]
+    fn routes_go_around_keepouts() {
+        let mut board = board();
+        // Straight across the gap between R1 and R2
+        keepout(&mut board, Rectangle { min_x: -1.5, min_y: -3.0, max_x: 1.5, max_y: 3.0 });
+        let report = board.autoroute(&AutorouteOptions::default().with_nets(&["A"]));
+        assert!(report.is_complete(), "{}", report);
+        assert!(board.tracks().len() > 1, "{:?}", board.tracks());
+        assert!(board.tracks().iter().all(|track| track.net == "A"));
+        assert!(board.routing_violations().is_empty(), "{:#?}", board.routing_violations());
+        assert_eq!(board.unconnected_pads().len(), 2, "B and GND are left");
+    }
+
+    #[test]
+    fn a_net_that_cannot_be_routed_is_reported() {
+        let mut board = board();
+        // Wall to wall between R1 and R2, on both layers
+        keepout(&mut board, Rectangle { min_x: -1.5, min_y: -12.0, max_x: 1.5, max_y: 12.0 });
+        let report = board.autoroute(&AutorouteOptions::default().with_rip_up_passes(1));
+        assert_eq!(report.failed, ["A", "GND"]);
+        assert_eq!(report.routed, ["B"]);
+        assert_eq!((report.connections, report.completed, report.passes), (3, 1, 2));
+        assert!(!report.is_complete());
+        assert!(report.to_string().ends_with("; failed: A, GND"), "{}", report);
+        assert!(board.tracks().iter().all(|track| track.net == "B"));
+        assert!(errors(&board).iter().all(|message| !message.contains("keepout")));
+    }
+}
@@ -0,0 +1,320 @@
+//! Back drilling of via stubs
+//!
+//! A through via carries signal only between the outermost layers it connects; the barrel
+//! beyond them is a stub that resonates at high data rates. Fabs remove it by drilling the
+//! via again from the far side with a larger drill to a controlled depth. A `Backdrill` on
+//! a via names the side it is drilled from, the first copper layer the drill must leave
+//! intact, the drill diameter and the dielectric margin to stop short of that layer by.
+//!
+//! `backdrill_span` works the depth out from the stackup: from the board surface on the
+//! drilled side to the near face of the must-not-cut foil, less the margin.
+//! `Board::backdrill_report` lists every backdrilled via with its depth and checks it:
+//! the drill must be wider than the via's, start on an outer layer the via reaches, stop
+//! inside the via span and cut no layer the via is connected on. Boards without a stackup
+//! are taken as plain 1.6mm two layer boards, as in `net_length`.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::board::Board;
+use crate::geometry::{point_in_polygon, Point};
+use crate::layer_type::{BoardLayer, LayerType, Side};
+use crate::routing::Via;
+use crate::stackup::Stackup;
+
+/// Dielectric left between the end of a backdrill and the must-not-cut foil (mm)
+pub const DEFAULT_BACKDRILL_MARGIN: f32 = 0.15;
+
+/// Arc tolerance when tessellating pads to find the layers a via connects on
+const BACKDRILL_ARC_TOLERANCE: f32 = 0.01;
+
+/// How a via's stub is drilled out, see the module documentation
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Backdrill {
+    /// Side the drill enters from, `Side::Front` or `Side::Back`
+    pub side: Side,
+    /// The copper layer closest to `side` that must keep its connection
+    pub must_not_cut: BoardLayer,
+    pub diameter: f32,
+    pub margin: f32,
+}
+
+impl Backdrill {
+    pub fn new(side: Side, must_not_cut: BoardLayer, diameter: f32) -> Self {
+        Self { side, must_not_cut, diameter, margin: DEFAULT_BACKDRILL_MARGIN }
+    }
+
+    pub fn with_margin(mut self, margin: f32) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// The outer copper layer the drill starts on
+    pub fn start_layer(&self) -> BoardLayer {
+        BoardLayer::new(LayerType::Copper, self.side)
+    }
+}
+
+/// What a backdrill takes away, worked out from the stackup
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackdrillSpan {
+    /// Depth from the board surface on the drilled side (mm)
+    pub depth: f32,
+    /// Copper layers the drill reaches into, from the surface in
+    pub cut_layers: Vec<BoardLayer>,
+    /// Via barrel removed, from the middle of the start foil to the end of the drill
+    pub stub_removed: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BackdrillIssue {
+    /// The side isn't `Side::Front` or `Side::Back`
+    InnerSide(Side),
+    /// A layer the stackup doesn't have
+    UnknownLayer(String),
+    /// The must-not-cut layer is the start layer or outside the via's span
+    OutsideSpan(BoardLayer),
+    /// The via doesn't reach the outer layer on the drilled side
+    NotOnDrilledSide,
+    /// The must-not-cut layer is within the margin of the surface, mm
+    TooShallow { depth: f32 },
+    /// A backdrill no wider than the via drill leaves the plating in place
+    DrillTooSmall { drill: f32, backdrill: f32 },
+    /// The drill reaches into a layer the via is connected on
+    CutsConnectedLayer(BoardLayer),
+}
+
+impl fmt::Display for BackdrillIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackdrillIssue::InnerSide(side) => write!(f, "backdrill from {:?}, not an outer side", side),
+            BackdrillIssue::UnknownLayer(name) => write!(f, "layer \"{}\" is not in the stackup", name),
+            BackdrillIssue::OutsideSpan(layer) => {
+                write!(f, "must-not-cut layer {} is not inside the via span", layer.to_kicad_string())
+            }
+            BackdrillIssue::NotOnDrilledSide => f.write_str("the via doesn't reach the drilled side"),
+            BackdrillIssue::TooShallow { depth } => write!(f, "backdrill depth {:.3} mm leaves nothing to remove", depth),
+            BackdrillIssue::DrillTooSmall { drill, backdrill } => {
+                write!(f, "backdrill {:.3} mm is not wider than the {:.3} mm via drill", backdrill, drill)
+            }
+            BackdrillIssue::CutsConnectedLayer(layer) => {
+                write!(f, "backdrill cuts {}, where the via is connected", layer.to_kicad_string())
+            }
+        }
+    }
+}
+
+impl std::error::Error for BackdrillIssue {}
+
+/// Depth and cut layers of `backdrill` in `stackup`; see the module documentation
+pub fn backdrill_span(stackup: &Stackup, backdrill: &Backdrill) -> Result<BackdrillSpan, BackdrillIssue> {
+    // Layers from the drilled surface in
+    let layers: Vec<_> = match backdrill.side {
+        Side::Front => stackup.layers.iter().collect(),
+        Side::Back => stackup.layers.iter().rev().collect(),
+        side @ Side::Inner(_) => return Err(BackdrillIssue::InnerSide(side)),
+    };
+    let start = backdrill.start_layer().to_kicad_string();
+    if layers.first().is_none_or(|layer| layer.name != start) {
+        return Err(BackdrillIssue::UnknownLayer(start));
+    }
+    let keep = backdrill.must_not_cut.to_kicad_string();
+    let index = layers.iter().position(|layer| layer.name == keep).ok_or_else(|| BackdrillIssue::UnknownLayer(keep.clone()))?;
+    if index == 0 {
+        return Err(BackdrillIssue::OutsideSpan(backdrill.must_not_cut));
+    }
+    let near_face: f32 = layers[..index].iter().map(|layer| layer.thickness).sum();
+    let depth = near_face - backdrill.margin;
+    if depth <= 0.0 {
+        return Err(BackdrillIssue::TooShallow { depth });
+    }
+    let mut cut_layers = Vec::new();
+    let mut top = 0.0;
+    for layer in &layers[..index] {
+        if layer.is_copper() && top < depth {
+            cut_layers.extend(BoardLayer::from_kicad_str(&layer.name));
+        }
+        top += layer.thickness;
+    }
+    let stub_removed = (depth - layers[0].thickness / 2.0).max(0.0);
+    Ok(BackdrillSpan { depth, cut_layers, stub_removed })
+}
+
+/// A backdrilled via and what the stackup makes of it
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackdrillEntry {
+    /// Index into `Board::vias`
+    pub via: usize,
+    pub net: String,
+    pub position: Point,
+    pub backdrill: Backdrill,
+    /// `None` when the stackup can't place the backdrill, see `issues`
+    pub span: Option<BackdrillSpan>,
+    pub issues: Vec<BackdrillIssue>,
+}
+
+/// Every backdrilled via of a board, in via order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BackdrillReport {
+    pub entries: Vec<BackdrillEntry>,
+}
+
+impl BackdrillReport {
+    pub fn is_clean(&self) -> bool {
+        self.entries.iter().all(|entry| entry.issues.is_empty())
+    }
+
+    /// Entries drilled from `side` without issues, the holes of that side's drill file
+    pub fn drillable(&self, side: Side) -> impl Iterator<Item = (&BackdrillEntry, &BackdrillSpan)> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.backdrill.side == side && entry.issues.is_empty())
+            .filter_map(|entry| Some((entry, entry.span.as_ref()?)))
+    }
+}
+
+impl fmt::Display for BackdrillReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.entries.iter().map(|entry| entry.net.len()).chain(["Net".len()]).max().unwrap_or_default();
+        writeln!(
+            f,
+            "{:>4}  {:<width$}  {:>9}  {:>9}  {:<5}  {:<12}  {:>8}  {:>8}",
+            "Via", "Net", "X mm", "Y mm", "From", "Must not cut", "Drill mm", "Depth mm"
+        )?;
+        for entry in &self.entries {
+            let depth = entry.span.as_ref().map_or("-".to_string(), |span| format!("{:.3}", span.depth));
+            writeln!(
+                f,
+                "{:>4}  {:<width$}  {:>9.3}  {:>9.3}  {:<5}  {:<12}  {:>8.3}  {:>8}",
+                entry.via,
+                entry.net,
+                entry.position.x,
+                entry.position.y,
+                entry.backdrill.start_layer().to_kicad_string(),
+                entry.backdrill.must_not_cut.to_kicad_string(),
+                entry.backdrill.diameter,
+                depth
+            )?;
+            for issue in &entry.issues {
+                writeln!(f, "      {}", issue)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Board {
+    /// Copper layers `via` is connected on: a track or arc of its net ends in it, or a pad
+    /// of its net covers it
+    pub fn via_connected_layers(&self, via: &Via) -> Vec<BoardLayer> {
+        let radius = via.diameter / 2.0;
+        let ends_in = |layer: &BoardLayer| {
+            let tracks = self.tracks().iter().filter(|track| track.net == via.net && track.layer == *layer);
+            let arcs = self.arcs().iter().filter(|arc| arc.net == via.net && arc.layer == *layer);
+            tracks
+                .flat_map(|track| [track.start, track.end])
+                .chain(arcs.flat_map(|arc| [arc.start, arc.end]))
+                .any(|end| end.distance_to(&via.position) <= radius)
+        };
+        let pad_on = |layer: &BoardLayer| {
+            self.pad_outlines(*layer, BACKDRILL_ARC_TOLERANCE).into_iter().any(|(name, outline)| {
+                self.routing.pad_nets.get(&name) == Some(&via.net) && point_in_polygon(via.position, &outline)
+            })
+        };
+        self.copper_layers().into_iter().filter(|layer| via.spans(layer) && (ends_in(layer) || pad_on(layer))).collect()
+    }
+
+    /// Every backdrilled via with its depth and issues; see the module documentation
+    pub fn backdrill_report(&self) -> BackdrillReport {
+        let stackup = self.stackup().cloned().unwrap_or_else(|| Stackup::two_layer(1.6));
+        let mut report = BackdrillReport::default();
+        for (index, via) in self.vias().iter().enumerate() {
+            let Some(backdrill) = via.backdrill else { continue };
+            let mut issues = Vec::new();
+            if backdrill.diameter <= via.drill {
+                issues.push(BackdrillIssue::DrillTooSmall { drill: via.drill, backdrill: backdrill.diameter });
+            }
+            if !matches!(backdrill.side, Side::Inner(_)) && !via.spans(&backdrill.start_layer()) {
+                issues.push(BackdrillIssue::NotOnDrilledSide);
+            }
+            if !via.spans(&backdrill.must_not_cut) {
+                issues.push(BackdrillIssue::OutsideSpan(backdrill.must_not_cut));
+            }
+            let span = match backdrill_span(&stackup, &backdrill) {
+                Ok(span) => {
+                    let connected = self.via_connected_layers(via);
+                    let cut = span.cut_layers.iter().filter(|layer| connected.contains(layer));
+                    issues.extend(cut.map(|layer| BackdrillIssue::CutsConnectedLayer(*layer)));
+                    Some(span)
+                }
+                Err(issue) => {
+                    if !issues.contains(&issue) {
+                        issues.push(issue);
+                    }
+                    None
+                }
+            };
+            report.entries.push(BackdrillEntry { via: index, net: via.net.clone(), position: via.position, backdrill, span, issues });
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routing::Track;
+    use crate::stackup::StackupLayer;
+
+    /// 1.51 mm six layer board, 35 micron copper throughout
+    fn six_layer() -> Stackup {
+        Stackup::new(vec![
+            StackupLayer::copper("F.Cu", 0.035),
+            StackupLayer::dielectric("Prepreg 1", 0.2, 4.2, 0.01),
+            StackupLayer::copper("In1.Cu", 0.035),
+            StackupLayer::dielectric("Core 1", 0.3, 4.3, 0.01),
+            StackupLayer::copper("In2.Cu", 0.035),
+            StackupLayer::dielectric("Prepreg 2", 0.3, 4.2, 0.01),
+            StackupLayer::copper("In3.Cu", 0.035),
+            StackupLayer::dielectric("Core 2", 0.3, 4.3, 0.01),
+            StackupLayer::copper("In4.Cu", 0.035),
+            StackupLayer::dielectric("Prepreg 3", 0.2, 4.2, 0.01),
+            StackupLayer::copper("B.Cu", 0.035),
+        ])
+    }
+
+    /// A via from F.Cu down to In2.Cu, backdrilled from the back up to In2.Cu
+    fn backdrilled_board(must_not_cut: BoardLayer) -> Board {
+        let mut board = Board::new("backdrill").with_stackup(six_layer());
+        let via = Point::new(10.0, 5.0);
+        board.add_track(Track::new("SIG", BoardLayer::front(LayerType::Copper), Point::new(0.0, 5.0), via, 0.1));
+        board.add_track(Track::new("SIG", BoardLayer::inner_copper(2), via, Point::new(20.0, 5.0), 0.1));
+        board.add_via(Via::new("SIG", via, 0.5, 0.25).with_backdrill(Backdrill::new(Side::Back, must_not_cut, 0.45)));
+        board
+    }
+
+    #[test]
+    fn depth_stops_the_margin_short_of_the_kept_layer() {
+        let report = backdrilled_board(BoardLayer::inner_copper(2)).backdrill_report();
+        assert!(report.is_clean(), "{}", report);
+        let span = report.entries[0].span.as_ref().unwrap();
+        // B.Cu, prepreg, In4, core, In3 and prepreg up to In2.Cu, less the margin
+        let near_face = 0.035 + 0.2 + 0.035 + 0.3 + 0.035 + 0.3;
+        assert!((span.depth - (near_face - DEFAULT_BACKDRILL_MARGIN)).abs() < 1e-5, "{}", span.depth);
+        assert_eq!(span.cut_layers, [BoardLayer::back(LayerType::Copper), BoardLayer::inner_copper(4), BoardLayer::inner_copper(3)]);
+        assert!((span.stub_removed - (span.depth - 0.0175)).abs() < 1e-5);
+        assert!(report.to_string().contains("B.Cu   In2.Cu"), "{}", report);
+    }
+
+    #[test]
+    fn cutting_a_connected_layer_or_a_narrow_drill_is_an_issue() {
+        let report = backdrilled_board(BoardLayer::inner_copper(1)).backdrill_report();
+        assert_eq!(report.entries[0].issues, [BackdrillIssue::CutsConnectedLayer(BoardLayer::inner_copper(2))]);
+
+        let mut board = backdrilled_board(BoardLayer::inner_copper(2));
+        board.add_via(Via::new("SIG", Point::new(30.0, 5.0), 0.5, 0.25).with_backdrill(Backdrill::new(Side::Back, BoardLayer::back(LayerType::Copper), 0.2)));
+        let issues = &board.backdrill_report().entries[1].issues;
+        assert_eq!(issues, &[BackdrillIssue::DrillTooSmall { drill: 0.25, backdrill: 0.2 }, BackdrillIssue::OutsideSpan(BoardLayer::back(LayerType::Copper))]);
+    }
+}
@@ -0,0 +1,283 @@
+//! Reusable layout blocks
+//!
+//! A `Block` is a piece of validated layout kept for reuse across boards, e.g. a buck
+//! converter: placed footprints, the tracks and vias between them and the nets of their
+//! pads, all in the block's own coordinates around a local origin.
+//!
+//! `Board::instantiate_block` copies a block onto a board at a position and rotation.
+//! References are renumbered into a free hundred (U1, R3 become U101, R103 for the first
+//! copy, U201, R203 for the second), so blocks never clash with each other or with the
+//! rest of the board. Nets are renamed through the placement's map (VIN to VIN_A); nets
+//! the map leaves out are local to the copy and get the group name as a prefix
+//! ("BUCK_A/SW"), so map shared nets such as GND onto themselves.
+//!
+//! Every copy becomes a `BoardGroup` named after the placement, which exporters write as
+//! a group so the copy stays selectable as one.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::board::{Board, IntoBoardPosition, PlacedComponent};
+use crate::board_interface::BoardComposableObject;
+use crate::geometry::{rotate_position, widen, Point};
+use crate::layer_type::Side;
+use crate::placement::{Enforcement, PlacementError};
+use crate::routing::{Track, Via};
+
+/// Items of a board that belong together, e.g. one copy of a block
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct BoardGroup {
+    pub name: String,
+    pub references: Vec<String>,
+    /// Indexes into `Board::tracks`
+    pub tracks: Vec<usize>,
+    /// Indexes into `Board::vias`
+    pub vias: Vec<usize>,
+}
+
+/// A named piece of layout in local coordinates, see the module documentation
+#[derive(Debug, Clone, Default)]
+pub struct Block {
+    pub name: String,
+    components: Vec<PlacedComponent>,
+    tracks: Vec<Track>,
+    vias: Vec<Via>,
+    /// "reference.number" to net name
+    pad_nets: BTreeMap<String, String>,
+}
+
+impl Block {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string(), ..Self::default() }
+    }
+
+    /// Everything on `board`, moved so that `origin` becomes the block's origin
+    pub fn from_board(name: &str, board: &Board, origin: Point) -> Self {
+        let local = |point: Point| point.offset(-origin.x, -origin.y);
+        let mut block = Self::new(name);
+        for placed in board.components() {
+            let ((x, y), rotation) = board.exported_placement(placed);
+            let position = (x - widen(origin.x), y - widen(origin.y));
+            block.add_component(&placed.reference, placed.component.clone(), position, rotation, placed.side);
+            for pad in placed.component.pad_descriptors() {
+                if let Some(net) = board.pad_net(&placed.reference, &pad.number) {
+                    block.connect_pad(&placed.reference, &pad.number, net);
+                }
+            }
+        }
+        for track in board.tracks() {
+            block.add_track(Track { start: local(track.start), end: local(track.end), ..track.clone() });
+        }
+        for via in board.vias() {
+            block.add_via(Via { position: local(via.position), ..via.clone() });
+        }
+        block
+    }
+
+    pub fn add_component(
+        &mut self,
+        reference: &str,
+        component: Rc<dyn BoardComposableObject>,
+        position: impl IntoBoardPosition,
+        rotation: f32,
+        side: Side,
+    ) {
+        let position = position.into_board_position();
+        self.components.push(PlacedComponent { reference: reference.to_string(), component, position, rotation, side });
+    }
+
+    pub fn add_track(&mut self, track: Track) {
+        self.tracks.push(track);
+    }
+
+    pub fn add_via(&mut self, via: Via) {
+        self.vias.push(via);
+    }
+
+    /// Connect pad `number` of component `reference` to `net`
+    pub fn connect_pad(&mut self, reference: &str, number: &str, net: &str) {
+        self.pad_nets.insert(format!("{}.{}", reference, number), net.to_string());
+    }
+
+    pub fn components(&self) -> &[PlacedComponent] {
+        &self.components
+    }
+
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    pub fn vias(&self) -> &[Via] {
+        &self.vias
+    }
+
+    /// Pad nets as ("reference.number", net), in pad name order
+    pub fn pad_nets(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pad_nets.iter().map(|(pad, net)| (pad.as_str(), net.as_str()))
+    }
+
+    /// Every net of the block's pads, tracks and vias
+    pub fn nets(&self) -> BTreeSet<&str> {
+        self.pad_nets
+            .values()
+            .map(String::as_str)
+            .chain(self.tracks.iter().map(|track| track.net.as_str()))
+            .chain(self.vias.iter().map(|via| via.net.as_str()))
+            .collect()
+    }
+}
+
+/// Where and how a block is copied onto a board
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockPlacement {
+    /// Name of the group the copy becomes, also the prefix of its local nets
+    pub group: String,
+    /// Board position of the block origin
+    pub position: Point,
+    /// Degrees counter-clockwise about the block origin
+    pub rotation: f32,
+    /// Block net name to board net name
+    pub nets: BTreeMap<String, String>,
+}
+
+impl BlockPlacement {
+    pub fn new(group: &str, position: Point, rotation: f32) -> Self {
+        Self { group: group.to_string(), position, rotation, nets: BTreeMap::new() }
+    }
+
+    /// Connect block net `from` to board net `to`
+    pub fn with_net(mut self, from: &str, to: &str) -> Self {
+        self.nets.insert(from.to_string(), to.to_string());
+        self
+    }
+
+    /// Board net of block net `net`
+    pub fn board_net(&self, net: &str) -> String {
+        self.nets.get(net).cloned().unwrap_or_else(|| format!("{}/{}", self.group, net))
+    }
+
+    fn board_point(&self, local: Point) -> Point {
+        local.rotated(self.rotation).offset(self.position.x, self.position.y)
+    }
+
+    fn board_position(&self, local: (f64, f64)) -> (f64, f64) {
+        let (x, y) = rotate_position(local, self.rotation);
+        (x + widen(self.position.x), y + widen(self.position.y))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockError {
+    /// A reference that isn't a prefix followed by a number, so it can't be renumbered
+    Reference(String),
+    /// The board already has a group of that name
+    DuplicateGroup(String),
+    /// The board's policy rejects a component of the copy; nothing was placed
+    Placement { reference: String, error: PlacementError },
+}
+
+impl fmt::Display for BlockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockError::Reference(reference) => write!(f, "reference {} has no number to renumber", reference),
+            BlockError::DuplicateGroup(name) => write!(f, "the board already has a group named {}", name),
+            BlockError::Placement { reference, error } => write!(f, "{}: {}", reference, error),
+        }
+    }
+}
+
+impl std::error::Error for BlockError {}
+
+/// "U12" as ("U", 12)
+fn split_reference(reference: &str) -> Option<(&str, u32)> {
+    let prefix = reference.trim_end_matches(|c: char| c.is_ascii_digit());
+    let number = reference[prefix.len()..].parse().ok()?;
+    (!prefix.is_empty()).then_some((prefix, number))
+}
+
+impl Board {
+    pub fn add_group(&mut self, group: BoardGroup) {
+        self.groups.push(group);
+    }
+
+    pub fn groups(&self) -> &[BoardGroup] {
+        &self.groups
+    }
+
+    pub fn group(&self, name: &str) -> Option<&BoardGroup> {
+        self.groups.iter().find(|group| group.name == name)
+    }
+
+    /// Copy `block` onto the board as a new group; see the module documentation. Under
+    /// `Enforcement::Snap` each component snaps on its own, so keep block placements on
+    /// the policy grid to preserve the layout exactly.
+    pub fn instantiate_block(&mut self, block: &Block, placement: &BlockPlacement) -> Result<&BoardGroup, BlockError> {
+        if self.group(&placement.group).is_some() {
+            return Err(BlockError::DuplicateGroup(placement.group.clone()));
+        }
+        let mut numbered = Vec::new();
+        for placed in &block.components {
+            numbered.push(split_reference(&placed.reference).ok_or_else(|| BlockError::Reference(placed.reference.clone()))?);
+        }
+
+        // The first hundred (or thousand, ...) whose references are all free
+        let largest = numbered.iter().map(|(_, number)| *number).max().unwrap_or_default();
+        let mut step = 100;
+        while step <= largest {
+            step *= 10;
+        }
+        let renumbered = |offset: u32| -> Vec<String> {
+            numbered.iter().map(|(prefix, number)| format!("{}{}", prefix, offset + number)).collect()
+        };
+        let mut offset = step;
+        while renumbered(offset).iter().any(|reference| self.component(reference).is_some()) {
+            offset += step;
+        }
+        let references = renumbered(offset);
+
+        let placements: Vec<((f64, f64), f32)> = block
+            .components
+            .iter()
+            .map(|placed| (placement.board_position(placed.position), (placed.rotation + placement.rotation).rem_euclid(360.0)))
+            .collect();
+        if self.policy().enforcement == Enforcement::Reject {
+            for (reference, (position, rotation)) in references.iter().zip(&placements) {
+                let violations = self.policy().check(reference, *position, *rotation);
+                if !violations.is_empty() {
+                    return Err(BlockError::Placement { reference: reference.clone(), error: PlacementError { violations } });
+                }
+            }
+        }
+
+        let mut group = BoardGroup { name: placement.group.clone(), ..BoardGroup::default() };
+        for ((placed, reference), (position, rotation)) in block.components.iter().zip(&references).zip(placements) {
+            self.place_shared(reference, placed.component.clone(), position, rotation, placed.side)
+                .map_err(|error| BlockError::Placement { reference: reference.clone(), error })?;
+            group.references.push(reference.clone());
+        }
+        for (pad, net) in &block.pad_nets {
+            let Some((reference, number)) = pad.rsplit_once('.') else { continue };
+            if let Some(index) = block.components.iter().position(|placed| placed.reference == reference) {
+                self.connect_pad(&references[index], number, &placement.board_net(net));
+            }
+        }
+        for track in &block.tracks {
+            group.tracks.push(self.tracks().len());
+            self.add_track(Track {
+                net: placement.board_net(&track.net),
+                start: placement.board_point(track.start),
+                end: placement.board_point(track.end),
+                ..track.clone()
+            });
+        }
+        for via in &block.vias {
+            group.vias.push(self.vias().len());
+            self.add_via(Via { net: placement.board_net(&via.net), position: placement.board_point(via.position), ..via.clone() });
+        }
+        self.groups.push(group);
+        Ok(self.groups.last().unwrap())
+    }
+}
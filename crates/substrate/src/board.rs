@@ -0,0 +1,429 @@
+//! Board model
+//!
+//! A board is a set of placed Board Composable Objects. Each placement carries the
+//! reference designator and where the component sits: position in board coordinates
+//! (millimeters, Y down, f64 see `IntoBoardPosition`), rotation in degrees
+//! counter-clockwise and the side it is mounted on. The component itself is shared, so
+//! the same footprint definition can be placed any number of times.
+//!
+//! Placements go through the board's `PlacementPolicy`, which snaps or rejects positions
+//! and rotations outside the layout standard. An optional outline describes the board
+//! edge and an optional `Stackup` its layers (two copper layers without one), and thieving copper (see `thieving`), copper zones (see `zone`) and board level drawings such as dimensions
+//! are kept on the board alongside components. Geometric queries (`components_near`,
+//! `pads_in_rect`, `nearest_pad`) go through indexes kept in sync with the placements.
+//!
+//! Fabrication outputs (paste gerbers, placement files) share one coordinate convention,
+//! `Board::fab_point`: Y up, measured either from the board coordinate origin or from the
+//! auxiliary (drill/place) origin, as KiCad's "use drill/place file origin" option does.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::assembly_process::ProcessPlan;
+use crate::block::BoardGroup;
+use crate::board_interface::{BoardComposableObject, GraphicElement, PadDescriptor};
+use crate::geometry::{widen, Point};
+use crate::layer_type::{BoardLayer, LayerType, Side};
+use crate::occupancy::PlacementKeepout;
+use crate::placement::{Enforcement, PlacementError, PlacementPolicy, PlacementViolation};
+use crate::routing::Routing;
+use crate::spatial_index::BoardSpatial;
+use crate::stackup::Stackup;
+use crate::thieving::ThievingCopper;
+use crate::zone::Zone;
+
+/// Origin fabrication outputs measure coordinates from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FabOrigin {
+    /// Board coordinates as they are, KiCad's page origin
+    #[default]
+    Absolute,
+    /// The board's auxiliary (drill/place file) origin
+    Aux,
+}
+
+/// A placement position in board millimeters. Placements are kept in f64 so a part far
+/// from the origin sits exactly where it was put: f32 only resolves about a micron at
+/// 10 m. Footprint-local geometry, tracks and the other board items stay in f32. Both f32
+/// and f64 pairs and `Point`s are accepted wherever a placement position is.
+pub trait IntoBoardPosition {
+    fn into_board_position(self) -> (f64, f64);
+}
+
+impl IntoBoardPosition for (f64, f64) {
+    fn into_board_position(self) -> (f64, f64) {
+        self
+    }
+}
+
+impl IntoBoardPosition for (f32, f32) {
+    fn into_board_position(self) -> (f64, f64) {
+        (widen(self.0), widen(self.1))
+    }
+}
+
+impl IntoBoardPosition for Point {
+    fn into_board_position(self) -> (f64, f64) {
+        (widen(self.x), widen(self.y))
+    }
+}
+
+#[derive(Clone)]
+pub struct PlacedComponent {
+    pub reference: String,
+    pub component: Rc<dyn BoardComposableObject>,
+    /// Board coordinates in f64, see `IntoBoardPosition`. This was `(f32, f32)` before
+    /// placements moved to f64; code reading it as f32 has to narrow with `as f32`.
+    pub position: (f64, f64),
+    pub rotation: f32,
+    /// Front or Back; components are never placed on inner layers
+    pub side: Side,
+}
+
+impl std::fmt::Debug for PlacedComponent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PlacedComponent")
+            .field("reference", &self.reference)
+            .field("footprint", &self.component.footprint_name())
+            .field("position", &self.position)
+            .field("rotation", &self.rotation)
+            .field("side", &self.side)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Board {
+    pub name: String,
+    /// Shared with snapshots until changed, see `snapshot`
+    pub(crate) components: Rc<Vec<PlacedComponent>>,
+    policy: PlacementPolicy,
+    /// Board edge as a closed polygon in board coordinates, last point not repeated
+    outline: Option<Vec<Point>>,
+    stackup: Option<Stackup>,
+    /// Drill/place file origin, KiCad's `aux_axis_origin`
+    aux_origin: Point,
+    /// Origin of the editor grid, KiCad's `grid_origin`
+    grid_origin: Point,
+    pub(crate) thieving: Vec<ThievingCopper>,
+    /// Board level graphics in board coordinates, e.g. dimensions for the fab drawing
+    graphics: Vec<GraphicElement>,
+    /// Items carried over verbatim from an edited board file (hand routed tracks, vias,
+    /// zones and their nets), written back unchanged on export
+    passthrough: Vec<String>,
+    /// Tracks, vias, net classes and pad nets, see `routing`
+    pub(crate) routing: Routing,
+    /// Items selected together, e.g. copies of a block, see `block`
+    pub(crate) groups: Vec<BoardGroup>,
+    /// Areas kept free of components, see `occupancy`
+    pub(crate) keepouts: Vec<PlacementKeepout>,
+    /// Copper pours, see `zone`
+    pub(crate) zones: Vec<Zone>,
+    /// Processes each side is assembled with, see `assembly_process`
+    pub(crate) process_plan: Option<ProcessPlan>,
+    /// Component and pad indexes for geometric queries, see `spatial_index`
+    pub(crate) spatial: RefCell<BoardSpatial>,
+}
+
+impl Board {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            components: Rc::default(),
+            policy: PlacementPolicy::unrestricted(),
+            outline: None,
+            stackup: None,
+            aux_origin: Point::default(),
+            grid_origin: Point::default(),
+            thieving: Vec::new(),
+            graphics: Vec::new(),
+            passthrough: Vec::new(),
+            routing: Routing::default(),
+            groups: Vec::new(),
+            keepouts: Vec::new(),
+            zones: Vec::new(),
+            process_plan: None,
+            spatial: RefCell::default(),
+        }
+    }
+
+    pub fn with_outline(mut self, outline: Vec<Point>) -> Self {
+        self.outline = Some(outline);
+        self
+    }
+
+    /// Rectangular board edge from (0, 0) to (width, height)
+    pub fn with_rectangular_outline(self, width: f32, height: f32) -> Self {
+        self.with_outline(vec![
+            Point::new(0.0, 0.0),
+            Point::new(width, 0.0),
+            Point::new(width, height),
+            Point::new(0.0, height),
+        ])
+    }
+
+    pub fn outline(&self) -> Option<&[Point]> {
+        self.outline.as_deref()
+    }
+
+    pub fn set_outline(&mut self, outline: Option<Vec<Point>>) {
+        self.outline = outline;
+    }
+
+    pub fn with_stackup(mut self, stackup: Stackup) -> Self {
+        self.stackup = Some(stackup);
+        self
+    }
+
+    pub fn stackup(&self) -> Option<&Stackup> {
+        self.stackup.as_ref()
+    }
+
+    pub fn set_stackup(&mut self, stackup: Option<Stackup>) {
+        self.stackup = stackup;
+    }
+
+    pub fn aux_origin(&self) -> Point {
+        self.aux_origin
+    }
+
+    pub fn set_aux_origin(&mut self, origin: Point) {
+        self.aux_origin = origin;
+    }
+
+    pub fn grid_origin(&self) -> Point {
+        self.grid_origin
+    }
+
+    pub fn set_grid_origin(&mut self, origin: Point) {
+        self.grid_origin = origin;
+    }
+
+    /// A board point in fabrication coordinates: relative to `origin` and Y up, as Gerber,
+    /// drill and placement files expect. The one place board Y down is turned around.
+    pub fn fab_point(&self, point: Point, origin: FabOrigin) -> Point {
+        let origin = match origin {
+            FabOrigin::Absolute => Point::default(),
+            FabOrigin::Aux => self.aux_origin,
+        };
+        Point::new(point.x - origin.x, origin.y - point.y)
+    }
+
+    /// Copper layers from top to bottom: the stackup's, or F.Cu and B.Cu without one
+    pub fn copper_layers(&self) -> Vec<BoardLayer> {
+        match &self.stackup {
+            Some(stackup) => stackup.copper_layers().filter_map(|layer| BoardLayer::from_kicad_str(&layer.name)).collect(),
+            None => vec![BoardLayer::front(LayerType::Copper), BoardLayer::back(LayerType::Copper)],
+        }
+    }
+
+    pub fn with_policy(mut self, policy: PlacementPolicy) -> Self {
+        self.policy = policy;
+        self.spatial.get_mut().dirty = true;
+        self
+    }
+
+    pub fn policy(&self) -> &PlacementPolicy {
+        &self.policy
+    }
+
+    /// Replace the policy; existing placements are left as they are, see `audit_placements`
+    pub fn set_policy(&mut self, policy: PlacementPolicy) {
+        self.policy = policy;
+        self.spatial.get_mut().dirty = true;
+    }
+
+    /// Place a component on the front side
+    pub fn place<T: BoardComposableObject + 'static>(
+        &mut self,
+        reference: &str,
+        component: T,
+        position: impl IntoBoardPosition,
+        rotation: f32,
+    ) -> Result<&mut PlacedComponent, PlacementError> {
+        self.place_shared(reference, Rc::new(component), position, rotation, Side::Front)
+    }
+
+    /// Place an already shared component definition on either side
+    pub fn place_shared(
+        &mut self,
+        reference: &str,
+        component: Rc<dyn BoardComposableObject>,
+        position: impl IntoBoardPosition,
+        rotation: f32,
+        side: Side,
+    ) -> Result<&mut PlacedComponent, PlacementError> {
+        let position = position.into_board_position();
+        let (position, rotation) = match self.policy.enforcement {
+            Enforcement::Snap => (self.policy.snap_position(position), self.policy.snap_rotation(reference, rotation)),
+            Enforcement::Reject => {
+                let violations = self.policy.check(reference, position, rotation);
+                if !violations.is_empty() {
+                    return Err(PlacementError { violations });
+                }
+                (position, rotation)
+            }
+        };
+        self.spatial.get_mut().dirty = true;
+        let components = Rc::make_mut(&mut self.components);
+        components.push(PlacedComponent { reference: reference.to_string(), component, position, rotation, side });
+        Ok(components.last_mut().unwrap())
+    }
+
+    /// Policy violations of the current placements, e.g. after a bulk import
+    pub fn audit_placements(&self) -> Vec<PlacementViolation> {
+        self.components
+            .iter()
+            .flat_map(|placed| self.policy.check(&placed.reference, placed.position, placed.rotation))
+            .collect()
+    }
+
+    /// Position and rotation as written to output files, snapped to the policy so
+    /// placements edited after the fact still export on grid
+    pub fn exported_placement(&self, placed: &PlacedComponent) -> ((f64, f64), f32) {
+        (self.policy.snap_position(placed.position), self.policy.snap_rotation(&placed.reference, placed.rotation))
+    }
+
+    pub fn add_graphic(&mut self, element: GraphicElement) {
+        self.graphics.push(element);
+    }
+
+    pub fn graphics(&self) -> &[GraphicElement] {
+        &self.graphics
+    }
+
+    pub fn passthrough(&self) -> &[String] {
+        &self.passthrough
+    }
+
+    pub fn set_passthrough(&mut self, items: Vec<String>) {
+        self.passthrough = items;
+    }
+
+    /// Board coordinates of a point given in the placed footprint's own coordinates, using
+    /// the exported placement. Back side footprints are mirrored top to bottom first.
+    pub fn board_point(&self, placed: &PlacedComponent, local: Point) -> Point {
+        let ((x, y), rotation) = self.exported_placement(placed);
+        let local = if placed.side == Side::Back { Point::new(local.x, -local.y) } else { local };
+        let turned = local.rotated(rotation);
+        // Offset in f64 so the sum is rounded once
+        Point::new((x + f64::from(turned.x)) as f32, (y + f64::from(turned.y)) as f32)
+    }
+
+    /// Outlines of every pad on `layer` in board coordinates, named "reference.number".
+    /// Back side placements are mirrored, so their F.Cu pads land on B.Cu. Non-plated
+    /// holes without copper around them aren't on any copper layer.
+    pub fn pad_outlines(&self, layer: BoardLayer, arc_tolerance: f32) -> Vec<(String, Vec<Point>)> {
+        let mut outlines = Vec::new();
+        for placed in self.components.iter() {
+            let is_back = placed.side == Side::Back;
+            let local_layer = match layer.side {
+                Side::Front if is_back => BoardLayer::back(layer.layer),
+                Side::Back if is_back => BoardLayer::front(layer.layer),
+                _ => layer,
+            };
+            let local_layer = local_layer.to_kicad_string();
+            let pads = placed.component.pad_descriptors();
+            let on_layer =
+                |pad: &&PadDescriptor| pad.has_layer(&local_layer) && (layer.layer != LayerType::Copper || pad.has_copper());
+            for pad in pads.iter().filter(on_layer) {
+                let outline = pad
+                    .on_layer(&local_layer)
+                    .outline_polygon(arc_tolerance)
+                    .into_iter()
+                    .map(|p| self.board_point(placed, p))
+                    .collect();
+                outlines.push((format!("{}.{}", placed.reference, pad.number), outline));
+            }
+        }
+        outlines
+    }
+
+    pub fn components(&self) -> &[PlacedComponent] {
+        &self.components
+    }
+
+    pub fn components_mut(&mut self) -> &mut [PlacedComponent] {
+        self.spatial.get_mut().dirty = true;
+        Rc::make_mut(&mut self.components).as_mut_slice()
+    }
+
+    pub fn component(&self, reference: &str) -> Option<&PlacedComponent> {
+        self.components.iter().find(|placed| placed.reference == reference)
+    }
+
+    pub fn component_mut(&mut self, reference: &str) -> Option<&mut PlacedComponent> {
+        let index = self.components.iter().position(|placed| placed.reference == reference)?;
+        self.spatial.get_mut().dirty = true;
+        Rc::make_mut(&mut self.components).get_mut(index)
+    }
+
+    /// A copy without the spatial indexes, sharing placements and routed copper with this
+    /// board until either changes them
+    pub(crate) fn shared_copy(&self) -> Board {
+        Board {
+            name: self.name.clone(),
+            components: Rc::clone(&self.components),
+            policy: self.policy.clone(),
+            outline: self.outline.clone(),
+            stackup: self.stackup.clone(),
+            aux_origin: self.aux_origin,
+            grid_origin: self.grid_origin,
+            thieving: self.thieving.clone(),
+            graphics: self.graphics.clone(),
+            passthrough: self.passthrough.clone(),
+            routing: self.routing.clone(),
+            groups: self.groups.clone(),
+            keepouts: self.keepouts.clone(),
+            zones: self.zones.clone(),
+            process_plan: self.process_plan,
+            spatial: RefCell::default(),
+        }
+    }
+
+    /// Take over everything from `other` but the spatial indexes, which refresh from the
+    /// placements that changed on the next query
+    pub(crate) fn assign_shared(&mut self, other: &Board) {
+        let spatial = std::mem::take(self.spatial.get_mut());
+        *self = other.shared_copy();
+        *self.spatial.get_mut() = spatial;
+        self.spatial.get_mut().dirty = true;
+    }
+
+    /// Whether everything but the placements and routed copper is the same
+    pub(crate) fn same_settings(&self, other: &Board) -> bool {
+        self.name == other.name
+            && self.policy == other.policy
+            && self.outline == other.outline
+            && self.stackup == other.stackup
+            && self.aux_origin == other.aux_origin
+            && self.grid_origin == other.grid_origin
+            && self.thieving == other.thieving
+            && self.graphics == other.graphics
+            && self.passthrough == other.passthrough
+            && self.groups == other.groups
+            && self.keepouts == other.keepouts
+            && self.zones == other.zones
+            && self.routing.same_rules(&other.routing)
+    }
+
+    pub fn remove(&mut self, reference: &str) -> Option<PlacedComponent> {
+        let index = self.components.iter().position(|placed| placed.reference == reference)?;
+        self.spatial.get_mut().dirty = true;
+        Some(Rc::make_mut(&mut self.components).remove(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fab_points_are_y_up_from_the_chosen_origin() {
+        let mut board = Board::new("origin");
+        board.set_aux_origin(Point::new(10.0, 20.0));
+        assert_eq!(board.fab_point(Point::new(12.5, 7.25), FabOrigin::Absolute), Point::new(12.5, -7.25));
+        assert_eq!(board.fab_point(Point::new(12.5, 7.25), FabOrigin::Aux), Point::new(2.5, 12.75));
+        assert_eq!(board.fab_point(board.aux_origin(), FabOrigin::Aux), Point::new(0.0, 0.0));
+    }
+}
@@ -0,0 +1,462 @@
+//! Board DRC
+//!
+//! `Board::drc` runs the board level checks in one pass and gives their findings, errors
+//! first: the routed copper (`Board::routing_violations`), the placement policy
+//! (`Board::audit_placements`), pads their net leaves unconnected, courtyards inside
+//! another part's actuation envelope and parts at odds with the board's process plan (see
+//! `assembly_process`).
+//!
+//! Pads of a net are connected when copper of that net joins them: track and arc ends
+//! landing in a pad, on another track or in a via, vias joining the layers they span, and
+//! zones joining whatever of their net lies inside their outline on their layers, as KiCad
+//! will fill them. Pads sharing a number in a footprint that declares them jumpers (see
+//! `pad_groups`) are joined inside the part. A net in several islands is reported once per island past the one with
+//! the most pads, naming the pads of that island. Nets with a single pad are left alone.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::analysis::findings::{ElementRef, Finding, Severity, Violation};
+use crate::board::Board;
+use crate::geometry::{point_in_polygon, point_segment_distance, Point};
+use crate::layer_type::{BoardLayer, Side};
+use crate::routing::ROUTING_ARC_TOLERANCE;
+
+/// Track ends closer than this are joined
+const JOIN_TOLERANCE: f32 = 0.001;
+
+/// "reference.number" of a pad, its center and the board layers of its copper
+type NetPad = (String, Point, Vec<BoardLayer>);
+
+/// Pads of a net cut off from the rest of it, see the module documentation
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnconnectedPads {
+    pub net: String,
+    /// "reference.number", in board order
+    pub pads: Vec<String>,
+    /// Center of the first of them
+    pub location: Point,
+}
+
+impl fmt::Display for UnconnectedPads {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} not connected to the rest of net {}", self.pads.join(", "), self.net)
+    }
+}
+
+impl Violation for UnconnectedPads {
+    fn rule(&self) -> &'static str {
+        "unconnected_pads"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn footprint(&self) -> String {
+        "board".to_string()
+    }
+
+    fn location(&self) -> Option<Point> {
+        Some(self.location)
+    }
+
+    fn elements(&self) -> Vec<ElementRef> {
+        self.pads.iter().map(|pad| ElementRef::BoardPad(pad.clone())).collect()
+    }
+}
+
+/// A part's courtyard inside the actuation envelope of another part on the same side (see
+/// `ActuationEnvelope`). The envelope itself may cross the board edge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvelopeIntrusion {
+    /// The part the envelope belongs to
+    pub reference: String,
+    pub envelope: String,
+    /// The part in the way
+    pub intruder: String,
+    pub side: Side,
+    /// A point where the two overlap
+    pub location: Point,
+}
+
+impl fmt::Display for EnvelopeIntrusion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = if self.side == Side::Front { "front" } else { "back" };
+        write!(f, "{} is inside the {} envelope of {} on the {}", self.intruder, self.envelope, self.reference, side)
+    }
+}
+
+impl Violation for EnvelopeIntrusion {
+    fn rule(&self) -> &'static str {
+        "actuation_envelope"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn footprint(&self) -> String {
+        self.reference.clone()
+    }
+
+    fn location(&self) -> Option<Point> {
+        Some(self.location)
+    }
+
+    fn elements(&self) -> Vec<ElementRef> {
+        vec![ElementRef::Component(self.reference.clone()), ElementRef::Component(self.intruder.clone())]
+    }
+}
+
+/// Where segments `a1`-`a2` and `b1`-`b2` cross, `None` when they only touch or miss
+fn crossing(a1: Point, a2: Point, b1: Point, b2: Point) -> Option<Point> {
+    let side = |p: Point, q: Point, r: Point| (q.x - p.x) * (r.y - p.y) - (q.y - p.y) * (r.x - p.x);
+    let (d1, d2) = (side(b1, b2, a1), side(b1, b2, a2));
+    let (d3, d4) = (side(a1, a2, b1), side(a1, a2, b2));
+    if d1 * d2 >= 0.0 || d3 * d4 >= 0.0 {
+        return None;
+    }
+    let t = d1 / (d1 - d2);
+    Some(Point::new(a1.x + t * (a2.x - a1.x), a1.y + t * (a2.y - a1.y)))
+}
+
+/// A point two overlapping polygons share: a corner of one inside the other, or where
+/// their edges cross. Polygons that only touch don't overlap.
+fn overlap_point(a: &[Point], b: &[Point]) -> Option<Point> {
+    if let Some(point) = a.iter().find(|point| point_in_polygon(**point, b)).or_else(|| b.iter().find(|point| point_in_polygon(**point, a))) {
+        return Some(*point);
+    }
+    for i in 0..a.len() {
+        for j in 0..b.len() {
+            if let Some(point) = crossing(a[i], a[(i + 1) % a.len()], b[j], b[(j + 1) % b.len()]) {
+                return Some(point);
+            }
+        }
+    }
+    None
+}
+
+/// Union-find over the copper items of one net
+struct Islands {
+    parent: Vec<usize>,
+}
+
+impl Islands {
+    fn new(count: usize) -> Self {
+        Self { parent: (0..count).collect() }
+    }
+
+    fn find(&mut self, item: usize) -> usize {
+        let mut root = item;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        self.parent[item] = root;
+        root
+    }
+
+    fn join(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parent[a] = b;
+    }
+}
+
+impl Board {
+    /// Every board level finding, see the module documentation
+    pub fn drc(&self) -> Vec<Finding> {
+        let mut findings: Vec<Finding> = self.routing_violations().iter().map(Violation::to_finding).collect();
+        findings.extend(self.audit_placements().iter().map(Violation::to_finding));
+        findings.extend(self.unconnected_pads().iter().map(Violation::to_finding));
+        findings.extend(self.envelope_intrusions().iter().map(Violation::to_finding));
+        findings.extend(self.process_violations().iter().map(Violation::to_finding));
+        findings.sort_by_key(|finding| std::cmp::Reverse(finding.severity));
+        findings
+    }
+
+    /// Islands of each net's pads that copper doesn't join to the rest, see the module
+    /// documentation
+    pub fn unconnected_pads(&self) -> Vec<UnconnectedPads> {
+        let layers = self.copper_layers();
+        let pad_outlines: Vec<_> = layers.iter().map(|&layer| (layer, self.pad_outlines(layer, ROUTING_ARC_TOLERANCE))).collect();
+        // Pads of each net in board order; back side placements are mirrored
+        let mut nets: BTreeMap<&str, Vec<NetPad>> = BTreeMap::new();
+        let mut jumpers: Vec<String> = Vec::new();
+        for placed in self.components() {
+            let jumpered = placed.component.pad_grouping().duplicates_are_jumpers;
+            let local = |layer: BoardLayer| match layer.side {
+                Side::Front if placed.side == Side::Back => BoardLayer::back(layer.layer),
+                Side::Back if placed.side == Side::Back => BoardLayer::front(layer.layer),
+                _ => layer,
+            };
+            for pad in placed.component.pad_descriptors() {
+                if let Some(net) = self.pad_net(&placed.reference, &pad.number) {
+                    let center = self.board_point(placed, Point::from(pad.position));
+                    let name = format!("{}.{}", placed.reference, pad.number);
+                    if jumpered {
+                        jumpers.push(name.clone());
+                    }
+                    let copper = layers.iter().copied().filter(|&layer| pad.has_copper() && pad.has_layer(&local(layer).to_kicad_string())).collect();
+                    nets.entry(net).or_default().push((name, center, copper));
+                }
+            }
+        }
+
+        let mut unconnected = Vec::new();
+        for (net, pads) in nets.into_iter().filter(|(_, pads)| pads.len() > 1) {
+            // Items: pads, then track pieces, vias and zones, each a union-find node
+            let mut segments: Vec<(usize, BoardLayer, Point, Point, f32)> = Vec::new();
+            let mut count = pads.len();
+            for track in self.tracks().iter().filter(|track| track.net == net) {
+                segments.push((count, track.layer, track.start, track.end, track.width));
+                count += 1;
+            }
+            for arc in self.arcs().iter().filter(|arc| arc.net == net) {
+                for (start, end) in arc.chords(ROUTING_ARC_TOLERANCE) {
+                    segments.push((count, arc.layer, start, end, arc.width));
+                }
+                count += 1;
+            }
+            let vias: Vec<(usize, _)> = self.vias().iter().filter(|via| via.net == net).map(|via| (post_increment(&mut count), via)).collect();
+            let zones: Vec<(usize, _)> = self.zones().iter().filter(|zone| zone.net == net).map(|zone| (post_increment(&mut count), zone)).collect();
+            let mut islands = Islands::new(count);
+
+            let pads = &pads;
+            let pad_copper: Vec<(usize, BoardLayer, &[Point])> = pad_outlines
+                .iter()
+                .flat_map(|(layer, outlines)| {
+                    outlines.iter().filter_map(move |(name, outline)| {
+                        // Pads sharing a number share a name, so tell them apart by layer and
+                        // center
+                        let index = pads
+                            .iter()
+                            .position(|(pad, center, copper)| pad == name && copper.contains(layer) && point_in_polygon(*center, outline))
+                            .or_else(|| pads.iter().position(|(pad, ..)| pad == name))?;
+                        Some((index, *layer, outline.as_slice()))
+                    })
+                })
+                .collect();
+            let pads_at = |layer: BoardLayer, point: Point| {
+                pad_copper.iter().filter(move |(_, l, outline)| *l == layer && point_in_polygon(point, outline)).map(|(index, ..)| *index)
+            };
+
+            for &(item, layer, start, end, _) in &segments {
+                for point in [start, end] {
+                    for pad in pads_at(layer, point) {
+                        islands.join(item, pad);
+                    }
+                    for &(other, other_layer, a, b, width) in &segments {
+                        if other != item && other_layer == layer && point_segment_distance(point, a, b).0 <= width / 2.0 + JOIN_TOLERANCE {
+                            islands.join(item, other);
+                        }
+                    }
+                }
+            }
+            for &(item, via) in &vias {
+                for &layer in layers.iter().filter(|layer| via.spans(layer)) {
+                    for pad in pads_at(layer, via.position) {
+                        islands.join(item, pad);
+                    }
+                    for &(other, _, a, b, width) in segments.iter().filter(|segment| segment.1 == layer) {
+                        let (distance, _) = point_segment_distance(via.position, a, b);
+                        let lands = a.distance_to(&via.position).min(b.distance_to(&via.position)) <= via.diameter / 2.0;
+                        if lands || distance <= width / 2.0 {
+                            islands.join(item, other);
+                        }
+                    }
+                }
+            }
+            for &(item, zone) in &zones {
+                let inside = |point: Point| point_in_polygon(point, &zone.outline);
+                for &layer in &zone.layers {
+                    for &(pad, _, _) in pad_copper.iter().filter(|(pad, l, _)| *l == layer && inside(pads[*pad].1)) {
+                        islands.join(item, pad);
+                    }
+                    for &(other, ..) in segments.iter().filter(|segment| segment.1 == layer && (inside(segment.2) || inside(segment.3))) {
+                        islands.join(item, other);
+                    }
+                    for &(other, _) in vias.iter().filter(|(_, via)| via.spans(&layer) && inside(via.position)) {
+                        islands.join(item, other);
+                    }
+                    for &(other, _) in zones.iter().filter(|(_, other)| other.layers.contains(&layer) && other.outline.iter().any(|point| inside(*point))) {
+                        islands.join(item, other);
+                    }
+                }
+            }
+
+            for (a, (name, ..)) in pads.iter().enumerate().filter(|(_, (name, ..))| jumpers.contains(name)) {
+                for b in (a + 1..pads.len()).filter(|&b| pads[b].0 == *name) {
+                    islands.join(a, b);
+                }
+            }
+
+            let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+            for pad in 0..pads.len() {
+                groups.entry(islands.find(pad)).or_default().push(pad);
+            }
+            let mut groups: Vec<Vec<usize>> = groups.into_values().collect();
+            // The island with the most pads, the first of them on a tie, counts as the net
+            let main = (0..groups.len()).max_by_key(|&index| (groups[index].len(), std::cmp::Reverse(groups[index][0]))).unwrap_or(0);
+            groups.remove(main);
+            groups.sort();
+            for group in groups {
+                unconnected.push(UnconnectedPads {
+                    net: net.to_string(),
+                    pads: group.iter().map(|&pad| pads[pad].0.clone()).collect(),
+                    location: pads[group[0]].1,
+                });
+            }
+        }
+        unconnected
+    }
+}
+
+impl Board {
+    /// Courtyards of other parts inside each part's actuation envelope, on either side
+    pub fn envelope_intrusions(&self) -> Vec<EnvelopeIntrusion> {
+        let mut intrusions = Vec::new();
+        for side in [Side::Front, Side::Back] {
+            let courtyards = self.courtyard_polygons(side);
+            for (reference, envelope) in self.actuation_envelopes(side) {
+                let name = self.component(&reference).and_then(|placed| placed.component.actuation_envelope()).map(|envelope| envelope.name).unwrap_or_default();
+                // A part with two courtyards on this side is reported once
+                let mut reported: Vec<&str> = Vec::new();
+                for (intruder, courtyard) in courtyards.iter().filter(|(other, _)| *other != reference) {
+                    let Some(location) = overlap_point(&envelope, courtyard).filter(|_| !reported.contains(&intruder.as_str())) else { continue };
+                    reported.push(intruder);
+                    intrusions.push(EnvelopeIntrusion { reference: reference.clone(), envelope: name.clone(), intruder: intruder.clone(), side, location });
+                }
+            }
+        }
+        intrusions
+    }
+}
+
+fn post_increment(count: &mut usize) -> usize {
+    *count += 1;
+    *count - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    use crate::analysis::findings::Finding;
+    use crate::board_interface::Rectangle;
+    use crate::courtyard::ActuationEnvelope;
+    use crate::edge_mount::EdgeMountSma;
+    use crate::footprint::Footprint;
+    use crate::layer_type::LayerType;
+    use crate::pad_groups::PadGrouping;
+    use crate::netlist::{Elbow, Netlist};
+    use crate::presets;
+    use crate::routing::Via;
+    use crate::zone::Zone;
+
+    /// Three 0603 resistors in a row, in series from GND back to GND
+    fn board() -> Board {
+        let mut board = Board::new("drc").with_rectangular_outline(30.0, 20.0);
+        for (index, x) in [5.0, 15.0, 25.0].into_iter().enumerate() {
+            board.place(&format!("R{}", index + 1), presets::resistor_0603("10k"), (x, 10.0), 0.0).unwrap();
+        }
+        board.apply_netlist(&Netlist::new().with_net("A", &["R1.2", "R2.1"]).with_net("B", &["R2.2", "R3.1"]).with_net("GND", &["R1.1", "R3.2"])).unwrap();
+        board
+    }
+
+    #[test]
+    fn unrouted_pads_are_reported_per_island() {
+        let top = BoardLayer::front(LayerType::Copper);
+        let mut board = board();
+        board.route_pads("R1.2", "R2.1", top, Elbow::default()).unwrap();
+        let unconnected = board.unconnected_pads();
+        assert_eq!(unconnected.len(), 2, "{:?}", unconnected);
+        assert_eq!((unconnected[0].net.as_str(), unconnected[0].pads.clone()), ("B", vec!["R3.1".to_string()]));
+        assert_eq!((unconnected[1].net.as_str(), unconnected[1].pads.clone()), ("GND", vec!["R3.2".to_string()]));
+
+        board.route_pads("R2.2", "R3.1", top, Elbow::default()).unwrap();
+        let unconnected = board.unconnected_pads();
+        assert_eq!(unconnected.len(), 1);
+        assert_eq!(unconnected[0].pads, ["R3.2"]);
+        let finding = unconnected[0].to_finding();
+        assert_eq!((finding.rule.as_str(), finding.severity), ("unconnected_pads", Severity::Error));
+    }
+
+    #[test]
+    fn zones_join_what_lies_inside_them() {
+        let mut board = board();
+        let top = BoardLayer::front(LayerType::Copper);
+        for (from, to) in [("R1.2", "R2.1"), ("R2.2", "R3.1")] {
+            board.route_pads(from, to, top, Elbow::default()).unwrap();
+        }
+        let outline = |min_x: f32, max_x: f32| vec![Point::new(min_x, 0.0), Point::new(max_x, 0.0), Point::new(max_x, 20.0), Point::new(min_x, 20.0)];
+        board.add_zone(Zone::new("GND", &[BoardLayer::back(LayerType::Copper)], outline(0.0, 30.0)));
+        // On the back, where the resistors have no copper
+        assert_eq!(board.unconnected_pads().len(), 1);
+        board.add_zone(Zone::new("GND", &[top], outline(0.0, 4.5)));
+        board.add_zone(Zone::new("GND", &[top], outline(25.5, 30.0)));
+        board.add_via(Via::new("GND", Point::new(2.0, 2.0), 0.6, 0.3));
+        board.add_via(Via::new("GND", Point::new(28.0, 2.0), 0.6, 0.3));
+        assert!(board.unconnected_pads().is_empty(), "{:?}", board.unconnected_pads());
+        assert!(board.drc().iter().all(|finding| finding.severity != Severity::Error), "{:?}", board.drc());
+    }
+
+    #[test]
+    fn pads_sharing_a_number_connect_by_copper_or_as_jumpers() {
+        // The four ground legs of an edge mount jack, two on each side
+        let mut sma = Footprint::capture(&EdgeMountSma::new(1.6));
+        let legs = |board: &Board| board.unconnected_pads().len();
+        let mut board = Board::new("legs").with_rectangular_outline(20.0, 20.0);
+        board.place("J1", sma.clone(), (10.0, 5.0), 0.0).unwrap();
+        board.apply_netlist(&Netlist::new().with_net("GND", &["J1.2"])).unwrap();
+        assert_eq!(legs(&board), 3);
+
+        // Each leg is found under the zone on its own side
+        let outline = vec![Point::new(0.0, 0.0), Point::new(20.0, 0.0), Point::new(20.0, 20.0), Point::new(0.0, 20.0)];
+        board.add_zone(Zone::new("GND", &[BoardLayer::front(LayerType::Copper)], outline.clone()));
+        assert_eq!(legs(&board), 2);
+        board.add_zone(Zone::new("GND", &[BoardLayer::back(LayerType::Copper)], outline));
+        assert_eq!(legs(&board), 1);
+        board.add_via(Via::new("GND", Point::new(2.0, 18.0), 0.6, 0.3));
+        assert_eq!(legs(&board), 0, "{:?}", board.unconnected_pads());
+
+        // Jumpers are joined inside the part
+        sma.pad_grouping = PadGrouping::new().with_jumpers();
+        let mut board = Board::new("legs").with_rectangular_outline(20.0, 20.0);
+        board.place("J1", sma, (10.0, 5.0), 0.0).unwrap();
+        board.apply_netlist(&Netlist::new().with_net("GND", &["J1.2"])).unwrap();
+        assert_eq!(legs(&board), 0);
+    }
+
+    #[test]
+    fn parts_inside_an_envelope_are_flagged_and_the_board_edge_is_not() {
+        // An 0603 whose envelope runs 6 mm up from its body, off the top edge at y = 0
+        let mut socket = Footprint::capture(&presets::resistor_0603("10k"));
+        socket.actuation_envelope = Some(ActuationEnvelope::rect("card", &Rectangle { min_x: -1.0, min_y: -6.0, max_x: 1.0, max_y: -0.5 }));
+        let mut board = Board::new("envelope").with_rectangular_outline(30.0, 20.0);
+        board.place("J1", socket.clone(), (10.0, 2.0), 0.0).unwrap();
+        board.place("R1", presets::resistor_0603("10k"), (20.0, 10.0), 0.0).unwrap();
+        assert!(board.envelope_intrusions().is_empty());
+        assert!(board.drc().iter().all(|finding| finding.rule != "actuation_envelope"));
+
+        // A resistor on the back under the envelope is fine, one on the front is not
+        board.place_shared("R2", Rc::new(presets::resistor_0603("10k")), (10.0, 5.0), 0.0, Side::Back).unwrap();
+        board.place("R3", presets::resistor_0603("10k"), (10.5, 7.0), 0.0).unwrap();
+        board.place("J2", socket, (10.0, 10.0), 0.0).unwrap();
+        let intrusions = board.envelope_intrusions();
+        assert_eq!(intrusions.len(), 1, "{:?}", intrusions);
+        let intrusion = &intrusions[0];
+        assert_eq!((intrusion.reference.as_str(), intrusion.intruder.as_str(), intrusion.side), ("J2", "R3", Side::Front));
+        assert!((4.0..=9.5).contains(&intrusion.location.y), "{:?}", intrusion.location);
+        assert_eq!(intrusion.to_string(), "R3 is inside the card envelope of J2 on the front");
+        let findings: Vec<Finding> = board.drc().into_iter().filter(|finding| finding.rule == "actuation_envelope").collect();
+        assert_eq!(findings.len(), 1);
+        assert_eq!((findings[0].severity, findings[0].footprint.as_str()), (Severity::Error, "J2"));
+
+        // On the back the envelope is mirrored, so it reaches down from the part
+        let mut back = Board::new("back").with_rectangular_outline(30.0, 20.0);
+        back.place_shared("J1", Rc::new(Footprint { actuation_envelope: Some(ActuationEnvelope::rect("card", &Rectangle { min_x: -1.0, min_y: -6.0, max_x: 1.0, max_y: -0.5 })), ..Footprint::capture(&presets::resistor_0603("10k")) }), (10.0, 5.0), 0.0, Side::Back).unwrap();
+        back.place_shared("R1", Rc::new(presets::resistor_0603("10k")), (10.0, 9.0), 0.0, Side::Back).unwrap();
+        assert_eq!(back.envelope_intrusions().len(), 1, "{:?}", back.actuation_envelopes(Side::Back));
+    }
+}
@@ -11,15 +11,45 @@
 //! KiCad footprints, bounding boxes, pad descriptors, and other properties necessary for PCB design.
 //! 
 use std::collections::HashMap;
-use crate::layer_type::LayerType;
-use crate::courtyard::Courtyard;
+use std::fmt;
+use compact_str::CompactString;
+use uuid::Uuid;
+use serde::{Deserialize, Serialize};
+use crate::ids::fresh_uuid;
+use crate::layer_type::{kicad_layer_matches, BoardLayer, LayerName, LayerType, PadSide, Side};
+use crate::anchor::{Anchor, RebaseError, Rebased};
+use crate::assembly_process::{default_process, ProcessSet};
+use crate::courtyard::{ActuationEnvelope, Courtyard};
+use crate::describe::DescriptionContext;
+use crate::dimension::Dimension;
+use crate::fab_profile::FabProfile;
 use crate::functional_types::FunctionalType;
+use crate::geometry::Point;
+use crate::hatch::FillStyle;
+use crate::pad_groups::PadGrouping;
+use crate::padstack::PadStack;
+use crate::roles::{ElementRole, RoleReplaced};
+use crate::routing_hint::RoutingHint;
+use crate::rule_area::RuleArea;
+use crate::scaling::{ScaleError, Scaled};
+use crate::silk_minimums::SilkSized;
+use crate::text_policy::{TextPolicy, TextStyled};
 pub trait BoardComposableObject {
     // Basic 
     fn is_smt(&self) -> bool;
     fn is_electrical(&self) -> bool;
     fn is_passive(&self) -> bool { false } // Default to false, can be overridden
     fn terminal_count(&self) -> usize;
+    /// Whether the footprint is a part to buy and place. Paste-only footprints such as
+    /// stencil fiducials aren't, and are left out of the BOM and placement files.
+    fn is_assembled(&self) -> bool {
+        self.is_electrical() || self.pad_descriptors().iter().any(|pad| pad.has_copper())
+    }
+    /// Whether pads may share a solder mask opening with no web between them, as on solder
+    /// jumpers; KiCad's `allow_soldermask_bridges` attribute
+    fn allows_soldermask_bridges(&self) -> bool { false }
+    /// What pads sharing a number, or joined by copper, are meant to be; see `pad_groups`
+    fn pad_grouping(&self) -> PadGrouping { PadGrouping::default() }
 
     // Core identification
     fn functional_type(&self) -> FunctionalType;
@@ -29,13 +59,64 @@ pub trait BoardComposableObject {
     // Geometric properties
     fn bounding_box(&self) -> Rectangle;
     fn pad_descriptors(&self) -> Vec<PadDescriptor>;
-    
+
+    /// The body as drawn: the extent of the Fab layer graphics, or of the pads when
+    /// nothing is drawn there
+    fn computed_bounding_box(&self) -> Rectangle {
+        fab_extent(&self.graphic_elements()).unwrap_or_else(|| {
+            let points: Vec<Point> = self.pad_descriptors().iter().flat_map(|pad| pad.outline_polygon(0.01)).collect();
+            Rectangle::enclosing(&points).unwrap_or(Rectangle { min_x: 0.0, min_y: 0.0, max_x: 0.0, max_y: 0.0 })
+        })
+    }
+
+    /// The declared `bounding_box` when one of its edges is more than `tolerance` from
+    /// `computed_bounding_box`. Without a Fab drawing the pads only bound the body from
+    /// below, so only a box that leaves pads out disagrees.
+    fn bounding_box_mismatch(&self, tolerance: f32) -> Option<BoundingBoxMismatch> {
+        let declared = self.bounding_box();
+        let computed = self.computed_bounding_box();
+        // Positive where the declared edge lies inside the computed one
+        let inside = [
+            declared.min_x - computed.min_x,
+            declared.min_y - computed.min_y,
+            computed.max_x - declared.max_x,
+            computed.max_y - declared.max_y,
+        ];
+        let drawn = fab_extent(&self.graphic_elements()).is_some();
+        let deviation = inside
+            .into_iter()
+            .map(|edge| if drawn { edge.abs() } else { edge.max(0.0) })
+            .fold(0.0f32, f32::max);
+        (deviation > tolerance).then(|| BoundingBoxMismatch { footprint: self.footprint_name(), declared, computed, deviation })
+    }
+
     // Footprint generation - could be used for KiCad or **other** formats
-    fn description(&self) -> Option<String>;
-    fn tags(&self) -> Option<String>;
+    /// What the description and tags are built from, for generators using the templates
+    /// in `describe`
+    fn description_context(&self) -> Option<DescriptionContext> { None }
+    fn description(&self) -> Option<String> {
+        self.description_context().map(|context| context.description())
+    }
+    fn tags(&self) -> Option<String> {
+        self.description_context().map(|context| context.tags())
+    }
     fn fp_text_elements(&self) -> Vec<FpText>;
     fn graphic_elements(&self) -> Vec<GraphicElement>;
     fn model_3d(&self) -> Option<Model3D>;
+    /// Text to write as the `.kicad_mod` instead of generating one, for footprints read
+    /// from a file that must round-trip unchanged
+    fn kicad_source(&self) -> Option<String> { None }
+    /// `kicad_source` with `texts` and `graphics`, restyled copies of what
+    /// `fp_text_elements` and `graphic_elements` return, spliced in. `None` without a
+    /// source or for edits the source can't take; wrappers that only restyle use it to
+    /// keep the source of a footprint read from a file.
+    fn kicad_source_with(&self, _texts: &[FpText], _graphics: &[GraphicElement]) -> Option<String> { None }
+    /// Keepouts that travel with the footprint, see `rule_area`
+    fn rule_areas(&self) -> Vec<RuleArea> { Vec::new() }
+    /// Room a moving or mating part needs beyond the courtyard, see `ActuationEnvelope`
+    fn actuation_envelope(&self) -> Option<ActuationEnvelope> { None }
+    /// Processes the part can be assembled with, see `assembly_process`
+    fn process(&self) -> ProcessSet { default_process(self) }
     
     // Courtyard generation
     fn courtyard_margin(&self) -> f32 { 0.25 } // Default 0.25mm margin
@@ -44,6 +125,80 @@ pub trait BoardComposableObject {
         let bbox = self.bounding_box();
         Courtyard::new(bbox, self.courtyard_margin())
     }
+
+    /// Every courtyard to draw: the front one, plus a back one around the back-only pads
+    /// when there are any
+    fn generate_courtyards(&self) -> Vec<Courtyard> {
+        let mut courtyards = vec![self.generate_courtyard()];
+        let back_pads: Vec<Point> = self
+            .pad_descriptors()
+            .iter()
+            .filter(|pad| pad.side() == Some(PadSide::Back))
+            .map(|pad| pad.outline_aabb())
+            .flat_map(|bounds| [Point::new(bounds.min_x, bounds.min_y), Point::new(bounds.max_x, bounds.max_y)])
+            .collect();
+        if let Some(bounds) = Rectangle::enclosing(&back_pads) {
+            courtyards.push(Courtyard::new(bounds, self.courtyard_margin()).on_side(Side::Back));
+        }
+        courtyards
+    }
+
+    // Origin convention
+    /// Which point of the footprint sits at (0, 0); generators with pin 1 at the origin override this
+    fn anchor(&self) -> Anchor { Anchor::Centroid }
+
+    /// The same component with `anchor` moved to the origin, see `Rebased::new`
+    fn rebase_to(self, anchor: Anchor) -> Result<Rebased<Self>, RebaseError>
+    where
+        Self: Sized,
+    {
+        Rebased::new(self, anchor)
+    }
+
+    /// The same component stretched by `sx` along X and `sy` along Y, refused if a pad or
+    /// drill would fall below the minimums of `profile`
+    fn scaled(self, sx: f32, sy: f32, profile: &FabProfile) -> Result<Scaled<Self>, ScaleError>
+    where
+        Self: Sized,
+    {
+        Scaled::new(self, sx, sy, profile)
+    }
+
+    /// The same component with its reference text styled by `policy`
+    fn with_text_policy(self, policy: &TextPolicy) -> TextStyled<Self>
+    where
+        Self: Sized,
+    {
+        TextStyled::new(self, policy)
+    }
+
+    /// The same component with its silkscreen strokes and text raised to the minimums of
+    /// `profile`, see `silk_minimums`
+    fn with_silk_minimums(self, profile: &FabProfile) -> SilkSized<Self>
+    where
+        Self: Sized,
+    {
+        SilkSized::new(self, profile)
+    }
+
+    /// Graphics drawn in `role`, in order
+    fn graphics_with_role(&self, role: &ElementRole) -> Vec<GraphicElement> {
+        self.graphic_elements().into_iter().filter(|element| element.role.as_ref() == Some(role)).collect()
+    }
+
+    /// Texts in `role`, in order
+    fn texts_with_role(&self, role: &ElementRole) -> Vec<FpText> {
+        self.fp_text_elements().into_iter().filter(|text| text.role.as_ref() == Some(role)).collect()
+    }
+
+    /// The same component with the graphics in `role` replaced by `replacement`, see
+    /// `roles`
+    fn replace_role(self, role: ElementRole, replacement: Vec<GraphicElement>) -> RoleReplaced<Self>
+    where
+        Self: Sized,
+    {
+        RoleReplaced::new(std::rc::Rc::new(self), role, replacement)
+    }
 }
 
 /// Associated constants moved to a separate trait for dyn compatibility
@@ -58,7 +213,7 @@ pub trait BoardComposableObjectInfo {
 
 
 /// Core geometric types
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Rectangle {
     pub min_x: f32,
     pub min_y: f32,
@@ -66,117 +221,525 @@ pub struct Rectangle {
     pub max_y: f32,
 }
 
+/// Extent of the Fab layer graphics, `None` when nothing is drawn there
+fn fab_extent(elements: &[GraphicElement]) -> Option<Rectangle> {
+    let mut points = Vec::new();
+    for element in elements.iter().filter(|element| element.layer == LayerType::Fabrication) {
+        match &element.element_type {
+            GraphicType::Line { start, end } => points.extend([Point::from(*start), Point::from(*end)]),
+            GraphicType::Rectangle { bounds } => {
+                points.extend([Point::new(bounds.min_x, bounds.min_y), Point::new(bounds.max_x, bounds.max_y)])
+            }
+            GraphicType::Circle { center, radius } => {
+                points.extend([Point::new(center.0 - radius, center.1 - radius), Point::new(center.0 + radius, center.1 + radius)])
+            }
+            GraphicType::Polygon { points: corners, .. } => points.extend(corners.iter().map(|corner| Point::from(*corner))),
+            GraphicType::Dimension(_) => {}
+        }
+    }
+    Rectangle::enclosing(&points)
+}
+
+/// Tolerance of `bounding_box_mismatch` that absorbs rounding of the declared box
+pub const BOUNDING_BOX_TOLERANCE: f32 = 0.01;
+
+/// A declared bounding box that disagrees with the one computed from the footprint's
+/// graphics
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundingBoxMismatch {
+    pub footprint: String,
+    pub declared: Rectangle,
+    pub computed: Rectangle,
+    /// Largest distance between matching edges
+    pub deviation: f32,
+}
+
+impl BoundingBoxMismatch {
+    /// Whether the declared box leaves part of the computed one out
+    pub fn is_undersized(&self) -> bool {
+        self.declared.min_x > self.computed.min_x
+            || self.declared.min_y > self.computed.min_y
+            || self.declared.max_x < self.computed.max_x
+            || self.declared.max_y < self.computed.max_y
+    }
+}
+
+impl fmt::Display for BoundingBoxMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: declared bounding box ({:.3}, {:.3})..({:.3}, {:.3}) is {} against the drawn body ({:.3}, {:.3})..({:.3}, {:.3}), off by {:.3} mm",
+            self.footprint,
+            self.declared.min_x,
+            self.declared.min_y,
+            self.declared.max_x,
+            self.declared.max_y,
+            if self.is_undersized() { "undersized" } else { "oversized" },
+            self.computed.min_x,
+            self.computed.min_y,
+            self.computed.max_x,
+            self.computed.max_y,
+            self.deviation
+        )
+    }
+}
+
+
+/// Pad number such as "1" or "EP"; numbers of up to 24 bytes are stored inline
+pub type PadNumber = CompactString;
 
 /// KiCad-specific structures
-#[derive(Debug, Clone)]
+///
+/// Struct literals need `PadNumber`, `LayerName` and `Uuid` values; `new` also takes
+/// `&str` and `String` for the number and layers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PadDescriptor {
-    pub number: String,
+    pub number: PadNumber,
+    pub pin_function: Option<String>, // Signal name, e.g. "GND" or "SDA"
     pub pad_type: PadType,
     pub shape: PadShape,
     pub position: (f32, f32),
+    pub rotation: Option<f32>,
     pub size: (f32, f32),
     pub drill_size: Option<f32>,
-    pub layers: Vec<String>,
+    /// Oval drill of a slotted hole, width and height before the pad's rotation;
+    /// `drill_size` is then its narrow side
+    pub drill_slot: Option<(f32, f32)>,
+    pub layers: Vec<LayerName>,
     pub roundrect_ratio: Option<f32>,  // For roundrect pads
     pub tenting: TentingSettings,
-    pub uuid: String,
+    /// Back and inner copper where it differs from the front, see `padstack`
+    pub padstack: Option<PadStack>,
+    /// What the pad is for when it isn't an ordinary terminal
+    pub fab_property: Option<PadFabProperty>,
+    /// Solder mask expansion per side for this pad alone, over the global margin; see
+    /// `mask_aperture`
+    pub solder_mask_margin: Option<f32>,
+    /// How routers should reach the pad, see `routing_hint`
+    pub routing_hint: Option<RoutingHint>,
+    /// Written as text only on export
+    #[serde(with = "uuid::serde::hyphenated")]
+    pub uuid: Uuid,
 }
 
-#[derive(Debug, Clone)]
+/// KiCad's pad fabrication property, for pads that aren't ordinary terminals
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PadFabProperty {
+    /// Exposed thermal pad or tab, KiCad's `pad_prop_heatsink`
+    Heatsink,
+    /// Mounting or retention pad with no electrical function, KiCad's `pad_prop_mechanical`
+    Mechanical,
+    /// Test point pad, KiCad's `pad_prop_testpoint`
+    TestPoint,
+    /// Fiducial for one component, KiCad's `pad_prop_fiducial_loc`
+    LocalFiducial,
+    /// Fiducial for the whole board, KiCad's `pad_prop_fiducial_glob`
+    GlobalFiducial,
+}
+
+impl PadFabProperty {
+    /// Whether the pad is a terminal that needs a pin on the symbol; heatsink pads may
+    /// have one but need not
+    pub fn needs_pin(self) -> bool {
+        self == PadFabProperty::TestPoint
+    }
+
+    /// Whether the pad connects to anything at all
+    pub fn is_electrical(self) -> bool {
+        matches!(self, PadFabProperty::Heatsink | PadFabProperty::TestPoint)
+    }
+}
+
+impl PadDescriptor {
+    /// An untented pad with no drill, rotation, padstack, fab property or routing hint, and
+    /// a fresh uuid
+    pub fn new<L: Into<LayerName>>(
+        number: impl Into<PadNumber>,
+        pad_type: PadType,
+        shape: PadShape,
+        position: (f32, f32),
+        size: (f32, f32),
+        layers: impl IntoIterator<Item = L>,
+    ) -> Self {
+        Self {
+            number: number.into(),
+            pin_function: None,
+            pad_type,
+            shape,
+            position,
+            rotation: None,
+            size,
+            drill_size: None,
+            drill_slot: None,
+            layers: layers.into_iter().map(Into::into).collect(),
+            roundrect_ratio: None,
+            tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+            padstack: None,
+            fab_property: None,
+            solder_mask_margin: None,
+            routing_hint: None,
+            uuid: fresh_uuid(),
+        }
+    }
+
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = Some(rotation);
+        self
+    }
+
+    /// Round drill of `diameter`
+    pub fn with_drill(mut self, diameter: f32) -> Self {
+        self.drill_size = Some(diameter);
+        self
+    }
+
+    pub fn with_roundrect_ratio(mut self, ratio: f32) -> Self {
+        self.roundrect_ratio = Some(ratio);
+        self
+    }
+
+    pub fn with_fab_property(mut self, property: PadFabProperty) -> Self {
+        self.fab_property = Some(property);
+        self
+    }
+
+    /// Solder mask margin per side for this pad, over the global one
+    pub fn with_solder_mask_margin(mut self, margin: f32) -> Self {
+        self.solder_mask_margin = Some(margin);
+        self
+    }
+
+    pub fn with_routing_hint(mut self, hint: RoutingHint) -> Self {
+        self.routing_hint = Some(hint);
+        self
+    }
+
+    /// A fixed uuid instead of a fresh one; text uuids go through `Uuid::parse_str`
+    pub fn with_uuid(mut self, uuid: impl Into<Uuid>) -> Self {
+        self.uuid = uuid.into();
+        self
+    }
+
+    /// Whether the pad is present on `layer` (e.g. "F.Cu"), honouring KiCad's `*.Cu` and
+    /// `F&B.Cu` wildcard layer names
+    pub fn has_layer(&self, layer: &str) -> bool {
+        self.layers.iter().any(|pattern| kicad_layer_matches(pattern, layer))
+    }
+
+    /// Whether the pad has copper on any layer. Stencil fiducials and other paste-only
+    /// apertures don't, and are skipped by copper checks. Neither does a non-plated hole
+    /// listed on copper layers but no larger than its drill, as KiCad writes them.
+    pub fn has_copper(&self) -> bool {
+        let bare_hole = self.is_npth()
+            && self.drill_extent().is_some_and(|(width, height)| {
+                self.size.0 <= width + f32::EPSILON && self.size.1 <= height + f32::EPSILON
+            });
+        !bare_hole && self.layers.iter().any(|l| l.ends_with(".Cu"))
+    }
+
+    /// Whether the pad is a non-plated hole
+    pub fn is_npth(&self) -> bool {
+        matches!(self.pad_type, PadType::NPTH)
+    }
+
+    /// Drill width and height before rotation: the slot, or the round drill both ways
+    pub fn drill_extent(&self) -> Option<(f32, f32)> {
+        self.drill_slot.or(self.drill_size.map(|drill| (drill, drill)))
+    }
+
+    /// Outer sides the pad's layers are on; "*" and "F&B" names count for both.
+    /// `None` for pads on no front or back layer.
+    pub fn side(&self) -> Option<PadSide> {
+        let prefixes = self.layers.iter().filter_map(|layer| layer.split_once('.').map(|(prefix, _)| prefix));
+        let (mut front, mut back) = (false, false);
+        for prefix in prefixes {
+            front |= matches!(prefix, "F" | "*" | "F&B");
+            back |= matches!(prefix, "B" | "*" | "F&B");
+        }
+        match (front, back) {
+            (true, true) => Some(PadSide::Both),
+            (true, false) => Some(PadSide::Front),
+            (false, true) => Some(PadSide::Back),
+            (false, false) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum PadType {
     SMD,
     ThroughHole,
     NPTH, // Non-plated through hole
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum PadShape {
     Circle,
     Rect,
     Oval,
     RoundRect,
+    /// Filled polygon in pad coordinates, KiCad's custom pad with one `gr_poly` primitive.
+    /// The rectangular anchor of the pad size must lie inside it.
+    Custom(Vec<(f32, f32)>),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TentingSettings {
     pub front: TentingType,
     pub back: TentingType,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TentingType {
     None,
     Full,
     Partial,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FpText {
     pub text_type: FpTextType,
     pub text: String,
     pub position: (f32, f32),
     pub rotation: Option<f32>,
-    pub layer: String,
-    pub uuid: String,
+    pub layer: LayerName,
+    #[serde(with = "uuid::serde::hyphenated")]
+    pub uuid: Uuid,
     pub font: FontSettings,
+    /// Written but not shown, KiCad can still show it on demand
+    pub hidden: bool,
+    /// Anchor and mirroring, `None` for centered unmirrored text
+    pub justify: Option<Justify>,
+    /// What the text is for, see `roles`
+    pub role: Option<ElementRole>,
 }
 
-#[derive(Debug, Clone)]
+impl FpText {
+    /// Centered, shown text in KiCad's default 1 mm font, with a fresh uuid. The text and
+    /// layer take `&str` or `String`.
+    pub fn new(text_type: FpTextType, text: impl Into<String>, position: (f32, f32), layer: impl Into<LayerName>) -> Self {
+        Self {
+            text_type,
+            text: text.into(),
+            position,
+            rotation: None,
+            layer: layer.into(),
+            uuid: fresh_uuid(),
+            font: FontSettings { size: (1.0, 1.0), thickness: 0.15 },
+            hidden: false,
+            justify: None,
+            role: None,
+        }
+    }
+
+    pub fn with_font(mut self, font: FontSettings) -> Self {
+        self.font = font;
+        self
+    }
+
+    pub fn with_hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    /// A fixed uuid instead of a fresh one; text uuids go through `Uuid::parse_str`
+    pub fn with_uuid(mut self, uuid: impl Into<Uuid>) -> Self {
+        self.uuid = uuid.into();
+        self
+    }
+
+    pub fn with_role(mut self, role: ElementRole) -> Self {
+        self.role = Some(role);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum FpTextType {
     Reference,
     Value,
     User,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum HorizontalJustify {
+    Left,
+    #[default]
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum VerticalJustify {
+    Top,
+    #[default]
+    Center,
+    Bottom,
+}
+
+/// Text anchor relative to its position, centered unless stated otherwise. Mirrored text
+/// reads correctly when the board is seen from the back, as on B.SilkS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Justify {
+    pub horizontal: HorizontalJustify,
+    pub vertical: VerticalJustify,
+    pub mirror: bool,
+}
+
+impl Justify {
+    pub fn new(horizontal: HorizontalJustify, vertical: VerticalJustify) -> Self {
+        Self { horizontal, vertical, mirror: false }
+    }
+
+    pub fn with_mirror(mut self, mirror: bool) -> Self {
+        self.mirror = mirror;
+        self
+    }
+
+    /// Justification after the text is turned by 180°, left becomes right and top becomes bottom
+    pub fn rotated_half_turn(self) -> Self {
+        let horizontal = match self.horizontal {
+            HorizontalJustify::Left => HorizontalJustify::Right,
+            HorizontalJustify::Center => HorizontalJustify::Center,
+            HorizontalJustify::Right => HorizontalJustify::Left,
+        };
+        let vertical = match self.vertical {
+            VerticalJustify::Top => VerticalJustify::Bottom,
+            VerticalJustify::Center => VerticalJustify::Center,
+            VerticalJustify::Bottom => VerticalJustify::Top,
+        };
+        Self { horizontal, vertical, mirror: self.mirror }
+    }
+
+    /// Offset from the anchor to the text center, as fractions of the text width and
+    /// height along the unrotated text (x right, y down). Mirrored text runs the other way.
+    pub fn center_offset(&self) -> (f32, f32) {
+        let x = match self.horizontal {
+            HorizontalJustify::Left => 0.5,
+            HorizontalJustify::Center => 0.0,
+            HorizontalJustify::Right => -0.5,
+        };
+        let y = match self.vertical {
+            VerticalJustify::Top => 0.5,
+            VerticalJustify::Center => 0.0,
+            VerticalJustify::Bottom => -0.5,
+        };
+        (if self.mirror { -x } else { x }, y)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FootprintProperty {
     pub name: String,
     pub value: String,
     pub position: (f32, f32),
     pub rotation: Option<f32>,
-    pub layer: String,
+    pub layer: LayerName,
     pub hidden: bool,
     pub unlocked: bool,
-    pub uuid: String,
+    pub uuid: Uuid,
     pub font: FontSettings,
+    /// Anchor and mirroring, `None` for centered unmirrored text
+    pub justify: Option<Justify>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FontSettings {
     pub size: (f32, f32),
     pub thickness: f32,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GraphicElement {
     pub element_type: GraphicType,
     pub layer: LayerType,
+    /// Front or back, so two-sided footprints can draw on B.SilkS or B.Fab
+    pub side: Side,
     pub stroke: Stroke,
-    pub uuid: String,
+    #[serde(with = "uuid::serde::hyphenated")]
+    pub uuid: Uuid,
+    /// What the element draws, see `roles`
+    pub role: Option<ElementRole>,
 }
 
-#[derive(Debug, Clone)]
+impl GraphicElement {
+    /// A solid stroked element on the front, with a fresh uuid
+    pub fn new(element_type: GraphicType, layer: LayerType, width: f32) -> Self {
+        Self {
+            element_type,
+            layer,
+            side: Side::Front,
+            stroke: Stroke { width, stroke_type: StrokeType::Solid },
+            uuid: fresh_uuid(),
+            role: None,
+        }
+    }
+
+    pub fn with_side(mut self, side: Side) -> Self {
+        self.side = side;
+        self
+    }
+
+    pub fn with_stroke_type(mut self, stroke_type: StrokeType) -> Self {
+        self.stroke.stroke_type = stroke_type;
+        self
+    }
+
+    /// A fixed uuid instead of a fresh one; text uuids go through `Uuid::parse_str`
+    pub fn with_uuid(mut self, uuid: impl Into<Uuid>) -> Self {
+        self.uuid = uuid.into();
+        self
+    }
+
+    pub fn with_role(mut self, role: ElementRole) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    pub fn board_layer(&self) -> BoardLayer {
+        BoardLayer::new(self.layer, self.side)
+    }
+
+    /// KiCad name of the layer the element is drawn on, e.g. "B.Fab"
+    pub fn kicad_layer(&self) -> String {
+        self.board_layer().to_kicad_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum GraphicType {
     Line { start: (f32, f32), end: (f32, f32) },
     Rectangle { bounds: Rectangle },
     Circle { center: (f32, f32), radius: f32 },
+    /// Closed polygon, last point not repeated; unfilled, filled solid or hatched
+    Polygon { points: Vec<(f32, f32)>, fill: Option<FillStyle> },
+    /// Measurement callout for fab drawings
+    Dimension(Dimension),
 }
 
+impl GraphicType {
+    /// `bounds` as a polygon, for rectangles that are filled or hatched
+    pub fn filled_rectangle(bounds: &Rectangle, fill: FillStyle) -> Self {
+        let points = vec![(bounds.min_x, bounds.min_y), (bounds.max_x, bounds.min_y), (bounds.max_x, bounds.max_y), (bounds.min_x, bounds.max_y)];
+        GraphicType::Polygon { points, fill: Some(fill) }
+    }
+}
 
-#[derive(Debug, Clone)]
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Stroke {
     pub width: f32,
     pub stroke_type: StrokeType,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum StrokeType {
     Solid,
     Dashed,
     Dotted,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Model3D {
     pub path: String,
     pub offset: (f32, f32, f32),
@@ -209,6 +772,8 @@ pub type NetId = u32;
 pub struct Pin {
     pub id: PinId,
     pub number: String,
+    /// Symbol pin name, compared with the pad's pin function
+    pub name: Option<String>,
     pub position: (f32, f32),
     pub electrical_type: ElectricalType,
 }
@@ -232,10 +797,9 @@ pub trait KiCadExportable {
 
 // Implementation moved to copper-exporters crate to avoid circular dependency
 
-/// Rendering traits (unchanged from original)
-pub trait ComponentRenderer {
-    fn render(&self, component: &dyn BoardComposableObject, ctx: &mut egui::Painter);
-}
+/// Rendering lives in `render`, behind the `render-traits` feature
+#[cfg(feature = "render-traits")]
+pub use crate::render::ComponentRenderer;
 
 
 pub trait LayerAware {
@@ -247,4 +811,33 @@ pub trait LayerAware {
 pub trait ElectricalComponent {
     fn pins(&self) -> Vec<Pin>;
     fn net_connections(&self) -> HashMap<PinId, NetId>;
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constructors_take_strings_for_numbers_and_layers() {
+        let owned = String::from("EP");
+        let pad = PadDescriptor::new(owned.clone(), PadType::SMD, PadShape::Rect, (0.0, 0.0), (2.0, 2.0), ["F.Cu", "F.Mask"]);
+        assert_eq!(pad.number, "EP");
+        assert!(pad.has_layer("F.Cu") && pad.has_layer("F.Mask") && !pad.has_layer("F.Paste"));
+        let pad = PadDescriptor::new("1", PadType::ThroughHole, PadShape::Circle, (0.0, 0.0), (1.7, 1.7), vec![owned])
+            .with_drill(1.0)
+            .with_uuid(Uuid::nil());
+        assert_eq!((pad.layers[0].as_str(), pad.drill_size, pad.uuid), ("EP", Some(1.0), Uuid::nil()));
+
+        let text = FpText::new(FpTextType::Value, String::from("10k"), (0.0, 1.5), String::from("F.Fab"));
+        assert_eq!((text.text.as_str(), text.layer.as_str(), text.hidden), ("10k", "F.Fab", false));
+        assert_ne!(text.uuid, FpText::new(FpTextType::User, "x", (0.0, 0.0), "F.SilkS").uuid);
+    }
+
+    #[test]
+    fn graphic_elements_default_to_solid_strokes_on_the_front() {
+        let line = GraphicElement::new(GraphicType::Line { start: (0.0, 0.0), end: (1.0, 0.0) }, LayerType::SilkScreen, 0.12);
+        assert_eq!(line.kicad_layer(), "F.SilkS");
+        assert_eq!(line.stroke, Stroke { width: 0.12, stroke_type: StrokeType::Solid });
+        let back = line.with_side(Side::Back).with_stroke_type(StrokeType::Dashed);
+        assert_eq!((back.kicad_layer().as_str(), back.stroke.stroke_type), ("B.SilkS", StrokeType::Dashed));
+    }
+}
@@ -0,0 +1,449 @@
+//! Two terminal chip footprint generator
+//!
+//! Rectangular end-terminal chips (resistors, capacitors, inductors, LEDs) from their body
+//! dimensions. Land patterns follow the IPC-7351 construction: the outer pad span is the
+//! body length plus a toe fillet on each end, the inner gap is the gap between the
+//! terminations minus a heel fillet, and pads are as wide as the body plus a side fillet.
+//! Any body length and width works, so odd sizes such as 0604 LEDs need no special case.
+//!
+//! Names follow KiCad's "R_0603_1608Metric" convention and are derived from the body
+//! size, see `chip_footprint_name`. Hand solder variants reach further out for the iron
+//! and add the pad size, "R_0603_1608Metric_Pad0.85x0.95mm_HandSolder", as KiCad's do.
+//!
+//! Bodies up to `ULTRA_SMALL_BODY_LENGTH` long (0201 and 01005) follow tighter rules, as
+//! KiCad's own 0201 footprints do: smaller fillets, a 0.2 mm (0201) or 0.15 mm (01005)
+//! courtyard margin, no silkscreen at all, a small hidden reference and paste apertures
+//! slightly smaller than the pads.
+
+use crate::assembly_process::{default_process, AssemblyProcess, ProcessSet};
+use crate::board_interface::{
+    BoardComposableObject, FontSettings, FpText, FpTextType, GraphicElement, GraphicType, Model3D, PadDescriptor,
+    PadShape, PadType, Rectangle, Stroke, StrokeType, TentingSettings, TentingType,
+};
+use crate::courtyard::Courtyard;
+use crate::describe::{Density, DescriptionContext};
+use crate::functional_types::FunctionalType;
+use crate::ids::fresh_uuid;
+use crate::layer_type::{LayerType, PadLayers, Side};
+use crate::pin1::{pin1_marker, PackageFamily, Pin1Style};
+use crate::roles::{Edge, ElementRole};
+
+/// Standard chip sizes: imperial code, body length, body width and termination length (mm)
+pub const STANDARD_CHIP_SIZES: &[(&str, f32, f32, f32)] = &[
+    ("01005", 0.4, 0.2, 0.1),
+    ("0201", 0.6, 0.3, 0.15),
+    ("0402", 1.0, 0.5, 0.25),
+    ("0603", 1.6, 0.8, 0.3),
+    ("0805", 2.0, 1.25, 0.4),
+    ("1206", 3.2, 1.6, 0.5),
+    ("1210", 3.2, 2.5, 0.5),
+    ("1812", 4.5, 3.2, 0.5),
+    ("2010", 5.0, 2.5, 0.6),
+    ("2512", 6.3, 3.2, 0.6),
+];
+
+/// Imperial names whose metric equivalent isn't the rounded conversion
+const IMPERIAL_BY_METRIC: &[(&str, &str)] = &[
+    ("0402", "01005"),
+    ("0603", "0201"),
+    ("1005", "0402"),
+    ("1608", "0603"),
+    ("2012", "0805"),
+    ("3216", "1206"),
+    ("3225", "1210"),
+    ("4532", "1812"),
+    ("5025", "2010"),
+    ("6332", "2512"),
+];
+
+const FAB_LINE_WIDTH: f32 = 0.1;
+const SILK_LINE_WIDTH: f32 = 0.12;
+/// Clearance from silkscreen line centers to pad copper
+const SILK_PAD_CLEARANCE: f32 = 0.2;
+
+/// Longest body (mm) that gets the ultra-small rules: 0201 and 01005
+pub const ULTRA_SMALL_BODY_LENGTH: f32 = 0.6;
+/// Longest body (mm) that gets the 01005 courtyard margin and fillets
+const BODY_LENGTH_01005: f32 = 0.4;
+/// How much further each pad of a hand solder variant reaches past the body (mm)
+pub const HAND_SOLDER_EXTENSION: f32 = 0.2;
+/// Shortest body (mm) with a hand solder variant, 0603 as in KiCad's libraries
+pub const HAND_SOLDER_MIN_BODY_LENGTH: f32 = 1.6;
+/// Shortest body (mm) glued down and wave soldered on the bottom side, 0603
+const WAVE_MIN_BODY_LENGTH: f32 = 1.6;
+
+/// Paste aperture size over pad size, along each side, on ultra-small chips
+const ULTRA_SMALL_PASTE_SCALE: f32 = 0.9;
+/// Reference text size on ultra-small chips, hidden unless a `TextPolicy` shows it
+const ULTRA_SMALL_REFERENCE_SIZE: f32 = 0.3;
+
+/// Metric size code from body length and width, e.g. "1608" for 1.6 x 0.8 mm
+pub fn metric_size_code(length: f32, width: f32) -> String {
+    // Codes truncate to tenths of a millimeter (1.25 mm -> 12); the epsilon keeps 0.3 at 03
+    let tenths = |v: f32| ((v * 10.0 + 1e-3).floor() as u32).min(99);
+    format!("{:02}{:02}", tenths(length), tenths(width))
+}
+
+/// Imperial size code, e.g. "0603", using the established name for standard sizes and the
+/// body size in hundredths of an inch otherwise
+pub fn imperial_size_code(length: f32, width: f32) -> String {
+    let metric = metric_size_code(length, width);
+    if let Some((_, imperial)) = IMPERIAL_BY_METRIC.iter().find(|(m, _)| *m == metric) {
+        return imperial.to_string();
+    }
+    let hundredths = |v: f32| ((v / 0.254).round() as u32).min(99);
+    format!("{:02}{:02}", hundredths(length), hundredths(width))
+}
+
+/// KiCad style name of a chip footprint, e.g. "R_0603_1608Metric"
+pub fn chip_footprint_name(prefix: &str, length: f32, width: f32) -> String {
+    format!("{}_{}_{}Metric", prefix, imperial_size_code(length, width), metric_size_code(length, width))
+}
+
+/// Replace the size codes in a KiCad style name ("..._0603_1608Metric...") with the codes
+/// for a new body size. `None` when the name carries no metric size code.
+pub fn regenerate_size_name(name: &str, length: f32, width: f32) -> Option<String> {
+    let mut parts: Vec<String> = name.split('_').map(str::to_string).collect();
+    let is_code = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    let metric = parts.iter().position(|p| p.strip_suffix("Metric").is_some_and(is_code))?;
+    parts[metric] = format!("{}Metric", metric_size_code(length, width));
+    if metric > 0 && is_code(&parts[metric - 1]) {
+        parts[metric - 1] = imperial_size_code(length, width);
+    }
+    Some(parts.join("_"))
+}
+
+/// Solder fillet goals of the land pattern (mm)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChipFillets {
+    pub toe: f32,
+    pub heel: f32,
+    pub side: f32,
+}
+
+impl Default for ChipFillets {
+    /// IPC-7351 nominal density
+    fn default() -> Self {
+        Self { toe: 0.35, heel: 0.0, side: 0.075 }
+    }
+}
+
+impl ChipFillets {
+    /// Fillets for a body `body_length` long: the defaults, or smaller ones for 0201 and
+    /// 01005, whose pads would otherwise be longer than the body. The 0201 values give
+    /// KiCad's R_0201_0603Metric land pattern.
+    pub fn for_body_length(body_length: f32) -> Self {
+        if body_length <= BODY_LENGTH_01005 + f32::EPSILON {
+            Self { toe: 0.15, heel: 0.0, side: 0.05 }
+        } else if body_length <= ULTRA_SMALL_BODY_LENGTH + f32::EPSILON {
+            Self { toe: 0.275, heel: 0.035, side: 0.05 }
+        } else {
+            Self::default()
+        }
+    }
+
+    /// Fillets of an IPC-7351 density level for a body `body_length` long, one level
+    /// moving the toe by 0.2 mm and the side by 0.05 mm (0.05 mm and nothing on 0201 and
+    /// 01005) from `for_body_length`
+    pub fn for_density(body_length: f32, density: Density) -> Self {
+        let nominal = Self::for_body_length(body_length);
+        let (toe, side) = if body_length <= ULTRA_SMALL_BODY_LENGTH + f32::EPSILON { (0.05, 0.0) } else { (0.2, 0.05) };
+        let step = match density {
+            Density::Least => -1.0,
+            Density::Nominal => 0.0,
+            Density::Most => 1.0,
+        };
+        Self { toe: nominal.toe + step * toe, heel: nominal.heel, side: (nominal.side + step * side).max(0.0) }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ChipFootprint {
+    pub functional_type: FunctionalType,
+    /// Body length along X, terminal to terminal
+    pub body_length: f32,
+    /// Body width along Y
+    pub body_width: f32,
+    /// Length of each end termination
+    pub terminal_length: f32,
+    pub fillets: ChipFillets,
+    /// Pin 1 (cathode) marker, KiCad's line for polarized parts and none otherwise
+    pub pin1: Pin1Style,
+    /// Where the body size came from, quoted in the description
+    pub body_size_source: Option<String>,
+    /// Pads `HAND_SOLDER_EXTENSION` longer on the outside, see the module documentation
+    pub hand_solder: bool,
+}
+
+impl ChipFootprint {
+    pub fn new(functional_type: FunctionalType, body_length: f32, body_width: f32, terminal_length: f32) -> Self {
+        let pin1 = match functional_type {
+            FunctionalType::LED(_) | FunctionalType::Protection(_) => Pin1Style::kicad_default(PackageFamily::Chip),
+            _ => Pin1Style::None,
+        };
+        Self {
+            functional_type,
+            body_length,
+            body_width,
+            terminal_length,
+            fillets: ChipFillets::for_body_length(body_length),
+            pin1,
+            body_size_source: None,
+            hand_solder: false,
+        }
+    }
+
+    /// A standard size by imperial code, e.g. "0603"; `None` for sizes not in
+    /// `STANDARD_CHIP_SIZES`
+    pub fn standard(functional_type: FunctionalType, imperial: &str) -> Option<Self> {
+        let (_, length, width, terminal) = STANDARD_CHIP_SIZES.iter().find(|(code, ..)| *code == imperial)?;
+        Some(Self::new(functional_type, *length, *width, *terminal))
+    }
+
+    pub fn with_fillets(mut self, fillets: ChipFillets) -> Self {
+        self.fillets = fillets;
+        self
+    }
+
+    pub fn with_pin1(mut self, pin1: Pin1Style) -> Self {
+        self.pin1 = pin1;
+        self
+    }
+
+    /// Datasheet or standard the body size was taken from, e.g. "IPC-SM-782 page 72"
+    pub fn with_body_size_source(mut self, source: &str) -> Self {
+        self.body_size_source = Some(source.to_string());
+        self
+    }
+
+    pub fn with_hand_solder(mut self) -> Self {
+        self.hand_solder = true;
+        self
+    }
+
+    /// Whether the ultra-small rules apply, see the module documentation
+    pub fn is_ultra_small(&self) -> bool {
+        self.body_length <= ULTRA_SMALL_BODY_LENGTH + f32::EPSILON
+    }
+
+    /// KiCad name prefix and library for the functional type
+    fn kicad_naming(&self) -> (&'static str, &'static str) {
+        match self.functional_type {
+            FunctionalType::Resistor(_) => ("R", "Resistor_SMD"),
+            FunctionalType::Capacitor(_) => ("C", "Capacitor_SMD"),
+            // KiCad draws ferrite beads on the inductor footprints
+            FunctionalType::Inductor(_) | FunctionalType::FerriteBead(_) => ("L", "Inductor_SMD"),
+            FunctionalType::LED(_) => ("LED", "LED_SMD"),
+            FunctionalType::Fuse(_) => ("Fuse", "Fuse"),
+            FunctionalType::Protection(_) => ("D", "Diode_SMD"),
+            _ => (self.functional_type.reference_prefix(), "Chip_SMD"),
+        }
+    }
+
+    /// Pad size (along X, along Y) and the X distance of each pad center from the origin
+    pub fn land_pattern(&self) -> ((f32, f32), f32) {
+        let extension = if self.hand_solder { HAND_SOLDER_EXTENSION } else { 0.0 };
+        let outer = self.body_length + 2.0 * (self.fillets.toe + extension);
+        let inner = (self.body_length - 2.0 * self.terminal_length - 2.0 * self.fillets.heel).max(0.0);
+        let size = ((outer - inner) / 2.0, self.body_width + 2.0 * self.fillets.side);
+        (size, (outer + inner) / 4.0)
+    }
+
+    /// Body and pads together
+    fn extent(&self) -> Rectangle {
+        let ((pad_x, pad_y), center) = self.land_pattern();
+        let half_x = (center + pad_x / 2.0).max(self.body_length / 2.0);
+        let half_y = (pad_y / 2.0).max(self.body_width / 2.0);
+        Rectangle { min_x: -half_x, min_y: -half_y, max_x: half_x, max_y: half_y }
+    }
+
+    fn line(start: (f32, f32), end: (f32, f32), layer: LayerType, width: f32) -> GraphicElement {
+        GraphicElement::new(GraphicType::Line { start, end }, layer, width)
+    }
+}
+
+impl BoardComposableObject for ChipFootprint {
+    fn is_smt(&self) -> bool {
+        true
+    }
+
+    fn is_electrical(&self) -> bool {
+        true
+    }
+
+    fn is_passive(&self) -> bool {
+        self.functional_type.is_two_terminal_passive()
+    }
+
+    fn terminal_count(&self) -> usize {
+        2
+    }
+
+    /// Reflow and the iron, and the wave for 0603 and larger glued to the bottom side
+    fn process(&self) -> ProcessSet {
+        let process = default_process(self);
+        if self.body_length >= WAVE_MIN_BODY_LENGTH - f32::EPSILON { process.with(AssemblyProcess::Wave) } else { process }
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        self.functional_type.clone()
+    }
+
+    fn footprint_name(&self) -> String {
+        let name = chip_footprint_name(self.kicad_naming().0, self.body_length, self.body_width);
+        if !self.hand_solder {
+            return name;
+        }
+        let ((pad_x, pad_y), _) = self.land_pattern();
+        format!("{}_Pad{:.2}x{:.2}mm_HandSolder", name, pad_x, pad_y)
+    }
+
+    fn library_name(&self) -> String {
+        self.kicad_naming().1.to_string()
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        let (half_x, half_y) = (self.body_length / 2.0, self.body_width / 2.0);
+        Rectangle { min_x: -half_x, min_y: -half_y, max_x: half_x, max_y: half_y }
+    }
+
+    /// Ultra-small chips print paste through separate, slightly smaller unnumbered
+    /// apertures, so their copper pads leave the paste layer out
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        let (size, center) = self.land_pattern();
+        let pad = |number: &str, x: f32, size: (f32, f32), layers| PadDescriptor {
+            number: number.into(),
+            pin_function: None,
+            pad_type: PadType::SMD,
+            shape: PadShape::RoundRect,
+            position: (x, 0.0),
+            rotation: None,
+            size,
+            drill_size: None,
+            drill_slot: None,
+            layers,
+            roundrect_ratio: Some(0.25),
+            tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+            padstack: None,
+            fab_property: None,
+            solder_mask_margin: None,
+            routing_hint: None,
+            uuid: fresh_uuid(),
+        };
+        let mut copper = PadLayers::smd_front();
+        if self.is_ultra_small() {
+            copper.retain(|layer| layer != "F.Paste");
+        }
+        let mut pads: Vec<PadDescriptor> =
+            [("1", -center), ("2", center)].into_iter().map(|(number, x)| pad(number, x, size, copper.clone())).collect();
+        if self.is_ultra_small() {
+            let paste = (size.0 * ULTRA_SMALL_PASTE_SCALE, size.1 * ULTRA_SMALL_PASTE_SCALE);
+            pads.extend([-center, center].into_iter().map(|x| pad("", x, paste, PadLayers::paste_front())));
+        }
+        pads
+    }
+
+    /// Custom fillets follow no IPC density level, so their descriptions leave it out
+    fn description_context(&self) -> Option<DescriptionContext> {
+        let imperial = imperial_size_code(self.body_length, self.body_width);
+        let density = [Density::Nominal, Density::Least, Density::Most]
+            .into_iter()
+            .find(|&density| self.fillets == ChipFillets::for_density(self.body_length, density));
+        Some(
+            DescriptionContext::new("chip", self.functional_type.clone(), &imperial)
+                .with_density(density)
+                .with_field("body_size_imperial", &imperial)
+                .with_field("body_size_metric", metric_size_code(self.body_length, self.body_width))
+                .with_field("body_size_source", self.body_size_source.clone().unwrap_or_default()),
+        )
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        let text_y = self.extent().max_y + self.courtyard_margin() + 0.7;
+        let fab_size = (self.body_width * 0.5).clamp(0.25, 1.0);
+        let text = |text_type, text: String, position, layer: &str, size: f32| FpText {
+            text_type,
+            text,
+            position,
+            rotation: None,
+            layer: layer.into(),
+            uuid: fresh_uuid(),
+            font: FontSettings { size: (size, size), thickness: size * 0.15 },
+            hidden: false,
+            justify: None,
+            role: None,
+        };
+        let mut reference = text(FpTextType::Reference, "REF**".to_string(), (0.0, -text_y), "F.SilkS", 1.0);
+        if self.is_ultra_small() {
+            // No room on the board for a readable reference next to the part
+            let size = ULTRA_SMALL_REFERENCE_SIZE;
+            reference.font = FontSettings { size: (size, size), thickness: size * 0.15 };
+            reference.hidden = true;
+        }
+        vec![
+            reference,
+            text(FpTextType::Value, self.footprint_name(), (0.0, text_y), "F.Fab", 1.0),
+            text(FpTextType::User, "${REFERENCE}".to_string(), (0.0, 0.0), "F.Fab", fab_size),
+        ]
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        let body = self.bounding_box();
+        let mut elements = vec![GraphicElement {
+            element_type: GraphicType::Rectangle { bounds: body.clone() },
+            layer: LayerType::Fabrication,
+            side: Side::Front,
+            stroke: Stroke { width: FAB_LINE_WIDTH, stroke_type: StrokeType::Solid },
+            uuid: fresh_uuid(),
+            role: Some(ElementRole::FabOutline(None)),
+        }];
+
+        // Silkscreen lines along both long sides, between the pads, when there is room. A
+        // line marker replaces the one on the pin 1 side. Ultra-small chips get none.
+        let ((pad_x, pad_y), center) = self.land_pattern();
+        let half_span = center - pad_x / 2.0 - SILK_PAD_CLEARANCE;
+        if half_span > 0.0 && !self.is_ultra_small() {
+            let y = (pad_y / 2.0).max(body.max_y) + SILK_LINE_WIDTH / 2.0;
+            for y in [-y, y] {
+                if y < 0.0 && self.pin1 == Pin1Style::Line {
+                    continue;
+                }
+                let edge = if y < 0.0 { Edge::Top } else { Edge::Bottom };
+                let line = Self::line((-half_span, y), (half_span, y), LayerType::SilkScreen, SILK_LINE_WIDTH);
+                elements.push(line.with_role(ElementRole::SilkOutline(Some(edge))));
+            }
+        }
+        let marker = pin1_marker(self.pin1, &body, &self.pad_descriptors(), "1");
+        elements.extend(marker.into_iter().filter(|element| !(self.is_ultra_small() && element.layer == LayerType::SilkScreen)));
+        elements
+    }
+
+    fn model_3d(&self) -> Option<Model3D> {
+        Some(Model3D {
+            // Hand solder variants share the model of the plain footprint
+            path: format!(
+                "${{KICAD9_3DMODEL_DIR}}/{}.3dshapes/{}.wrl",
+                self.library_name(),
+                chip_footprint_name(self.kicad_naming().0, self.body_length, self.body_width)
+            ),
+            offset: (0.0, 0.0, 0.0),
+            scale: (1.0, 1.0, 1.0),
+            rotation: (0.0, 0.0, 0.0),
+        })
+    }
+
+    fn courtyard_margin(&self) -> f32 {
+        if self.body_length <= BODY_LENGTH_01005 + f32::EPSILON {
+            0.15
+        } else if self.is_ultra_small() {
+            0.2
+        } else {
+            0.25
+        }
+    }
+
+    /// Courtyard around the pads as well as the body
+    fn generate_courtyard(&self) -> Courtyard {
+        Courtyard::new(self.extent(), self.courtyard_margin())
+    }
+}
@@ -0,0 +1,227 @@
+//! Connector orientation marks for assembly drawings
+//!
+//! Assembly drawings need to show which way a connector mates and where its key is.
+//! `ConnectorMarks` draws these cues on the fab layer around a connector body. There are
+//! three kinds:
+//!
+//! - a mating face arrow, pointing out of the face the mating part plugs into
+//! - keying marks, a filled diamond at each key or polarization feature
+//! - an insertion arrow, beside the face and pointing into it
+//!
+//! Each element and label carries its `ElementRole`. `Board::connector_marks` uses the
+//! role to collect the marks of every placed part in board coordinates, and the
+//! exporters' assembly drawing highlights them and adds a legend.
+//!
+//! Graphics have no user layer to go on, so the marks and their labels are on F.Fab.
+
+use crate::board::Board;
+use crate::board_interface::{FontSettings, FpText, FpTextType, GraphicElement, GraphicType, Rectangle};
+use crate::geometry::Point;
+use crate::hatch::FillStyle;
+use crate::layer_type::LayerType;
+use crate::roles::{Edge, ElementRole};
+
+const FAB_LINE_WIDTH: f32 = 0.1;
+/// Length of the mating face and insertion arrows, head included
+pub const ARROW_LENGTH: f32 = 2.0;
+const ARROW_HEAD: f32 = 0.6;
+/// Corner to corner size of a keying diamond
+const KEY_SIZE: f32 = 0.8;
+const LABEL_SIZE: f32 = 0.5;
+
+/// Roles of the connector marks, in legend order
+pub const CONNECTOR_ROLES: [ElementRole; 3] = [ElementRole::MatingFace, ElementRole::KeyingMark, ElementRole::InsertionArrow];
+
+/// Orientation marks of one connector, see the module documentation
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConnectorMarks {
+    /// Body edge the mating part plugs into
+    pub mating_face: Option<Edge>,
+    /// Centers of the keying marks, footprint coordinates
+    pub keying: Vec<(f32, f32)>,
+    /// Whether to draw the insertion arrow. It needs a mating face.
+    pub insertion_arrow: bool,
+}
+
+impl ConnectorMarks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_mating_face(mut self, face: Edge) -> Self {
+        self.mating_face = Some(face);
+        self
+    }
+
+    pub fn with_keying_mark(mut self, position: (f32, f32)) -> Self {
+        self.keying.push(position);
+        self
+    }
+
+    pub fn with_insertion_arrow(mut self) -> Self {
+        self.insertion_arrow = true;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mating_face.is_none() && self.keying.is_empty() && !self.insertion_arrow
+    }
+
+    /// Arrows and keying marks around `body`
+    pub fn graphic_elements(&self, body: &Rectangle) -> Vec<GraphicElement> {
+        let mut elements = Vec::new();
+        if let Some(face) = self.mating_face {
+            let (middle, out) = face_middle(body, face);
+            elements.extend(arrow(middle, middle.offset(out.x * ARROW_LENGTH, out.y * ARROW_LENGTH), ElementRole::MatingFace));
+            if self.insertion_arrow {
+                let along = face_length(body, face);
+                let tip = middle.offset(-out.y * along / 4.0, out.x * along / 4.0);
+                let tail = tip.offset(out.x * ARROW_LENGTH, out.y * ARROW_LENGTH);
+                elements.extend(arrow(tail, tip, ElementRole::InsertionArrow));
+            }
+        }
+        for &(x, y) in &self.keying {
+            let half = KEY_SIZE / 2.0;
+            let points = vec![(x, y - half), (x + half, y), (x, y + half), (x - half, y)];
+            let diamond = GraphicType::Polygon { points, fill: Some(FillStyle::Solid) };
+            elements.push(GraphicElement::new(diamond, LayerType::Fabrication, FAB_LINE_WIDTH).with_role(ElementRole::KeyingMark));
+        }
+        elements
+    }
+
+    /// "MATE" past the mating face arrow and "KEY" under each keying mark
+    pub fn fp_text_elements(&self, body: &Rectangle) -> Vec<FpText> {
+        let label = |text: &str, position: Point, role: ElementRole| {
+            FpText::new(FpTextType::User, text, (position.x, position.y), "F.Fab")
+                .with_font(FontSettings { size: (LABEL_SIZE, LABEL_SIZE), thickness: LABEL_SIZE * 0.15 })
+                .with_role(role)
+        };
+        let mut texts = Vec::new();
+        if let Some(face) = self.mating_face {
+            let (middle, out) = face_middle(body, face);
+            let distance = ARROW_LENGTH + LABEL_SIZE;
+            texts.push(label("MATE", middle.offset(out.x * distance, out.y * distance), ElementRole::MatingFace));
+        }
+        for &(x, y) in &self.keying {
+            texts.push(label("KEY", Point::new(x, y + KEY_SIZE / 2.0 + LABEL_SIZE), ElementRole::KeyingMark));
+        }
+        texts
+    }
+}
+
+/// Middle of a body edge and the unit vector out of it
+fn face_middle(body: &Rectangle, face: Edge) -> (Point, Point) {
+    let (center_x, center_y) = ((body.min_x + body.max_x) / 2.0, (body.min_y + body.max_y) / 2.0);
+    match face {
+        Edge::Left => (Point::new(body.min_x, center_y), Point::new(-1.0, 0.0)),
+        Edge::Right => (Point::new(body.max_x, center_y), Point::new(1.0, 0.0)),
+        Edge::Top => (Point::new(center_x, body.min_y), Point::new(0.0, -1.0)),
+        Edge::Bottom => (Point::new(center_x, body.max_y), Point::new(0.0, 1.0)),
+    }
+}
+
+fn face_length(body: &Rectangle, face: Edge) -> f32 {
+    match face {
+        Edge::Left | Edge::Right => body.height(),
+        Edge::Top | Edge::Bottom => body.width(),
+    }
+}
+
+/// A shaft and a filled head from `tail` to `tip`
+fn arrow(tail: Point, tip: Point, role: ElementRole) -> Vec<GraphicElement> {
+    let length = tail.distance_to(&tip);
+    let (dx, dy) = ((tip.x - tail.x) / length, (tip.y - tail.y) / length);
+    let base = tip.offset(-dx * ARROW_HEAD, -dy * ARROW_HEAD);
+    let half = ARROW_HEAD / 2.0;
+    let head = vec![(tip.x, tip.y), (base.x - dy * half, base.y + dx * half), (base.x + dy * half, base.y - dx * half)];
+    let shaft = GraphicType::Line { start: (tail.x, tail.y), end: (base.x, base.y) };
+    let head = GraphicType::Polygon { points: head, fill: Some(FillStyle::Solid) };
+    [shaft, head]
+        .into_iter()
+        .map(|shape| GraphicElement::new(shape, LayerType::Fabrication, FAB_LINE_WIDTH).with_role(role.clone()))
+        .collect()
+}
+
+/// A connector mark of a placed part, in board coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectorMark {
+    pub reference: String,
+    pub role: ElementRole,
+    /// Two points for a line, the corners of a polygon otherwise
+    pub points: Vec<Point>,
+    pub filled: bool,
+}
+
+impl Board {
+    /// The connector marks of every placed part, graphics in a role of `CONNECTOR_ROLES`
+    /// moved to board coordinates. Labels are left to the footprints.
+    pub fn connector_marks(&self) -> Vec<ConnectorMark> {
+        let mut marks = Vec::new();
+        for placed in self.components() {
+            for element in placed.component.graphic_elements() {
+                let Some(role) = element.role.filter(|role| CONNECTOR_ROLES.contains(role)) else {
+                    continue;
+                };
+                let (points, filled) = match element.element_type {
+                    GraphicType::Line { start, end } => (vec![start, end], false),
+                    GraphicType::Polygon { points, fill } => (points, fill.is_some()),
+                    GraphicType::Rectangle { bounds } => {
+                        let corners = vec![
+                            (bounds.min_x, bounds.min_y),
+                            (bounds.max_x, bounds.min_y),
+                            (bounds.max_x, bounds.max_y),
+                            (bounds.min_x, bounds.max_y),
+                        ];
+                        (corners, false)
+                    }
+                    GraphicType::Circle { .. } | GraphicType::Dimension(_) => continue,
+                };
+                let points = points.into_iter().map(|point| self.board_point(placed, Point::from(point))).collect();
+                marks.push(ConnectorMark { reference: placed.reference.clone(), role, points, filled });
+            }
+        }
+        marks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body() -> Rectangle {
+        Rectangle { min_x: -4.0, min_y: -3.0, max_x: 4.0, max_y: 3.0 }
+    }
+
+    fn tip(elements: &[GraphicElement], role: ElementRole) -> (f32, f32) {
+        elements
+            .iter()
+            .filter(|element| element.role == Some(role.clone()))
+            .find_map(|element| match &element.element_type {
+                GraphicType::Polygon { points, .. } => Some(points[0]),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn arrows_leave_and_enter_the_mating_face() {
+        let marks = ConnectorMarks::new().with_mating_face(Edge::Top).with_insertion_arrow();
+        let elements = marks.graphic_elements(&body());
+        assert_eq!(tip(&elements, ElementRole::MatingFace), (0.0, -3.0 - ARROW_LENGTH));
+        // The insertion arrow ends on the face, beside the mating face arrow
+        let (x, y) = tip(&elements, ElementRole::InsertionArrow);
+        assert!((y + 3.0).abs() < 1e-6 && x.abs() > 1.0, "{:?}", (x, y));
+        assert!(elements.iter().all(|element| element.layer == LayerType::Fabrication));
+    }
+
+    #[test]
+    fn keying_marks_are_labelled() {
+        let marks = ConnectorMarks::new().with_keying_mark((-3.0, 2.0));
+        assert_eq!(marks.graphic_elements(&body()).len(), 1);
+        let texts = marks.fp_text_elements(&body());
+        assert_eq!(texts.len(), 1);
+        assert_eq!((texts[0].text.as_str(), texts[0].role.clone()), ("KEY", Some(ElementRole::KeyingMark)));
+        // Nothing to insert into without a mating face
+        assert!(ConnectorMarks::new().with_insertion_arrow().graphic_elements(&body()).is_empty());
+    }
+}
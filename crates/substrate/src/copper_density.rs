@@ -0,0 +1,211 @@
+//! Copper density maps
+//!
+//! Uneven copper plates unevenly and warps the board, so fabs want the copper on each layer
+//! balanced. `Board::copper_density_grid` divides a layer into square cells and measures
+//! the fraction of each cell covered by copper: pads, tracks, vias, zones and thieving.
+//! The cells are those of the occupancy grid (see `occupancy`), so they start at the top
+//! left corner of the board outline and cells entirely off the board have no value.
+//!
+//! Coverage is sampled every `COVERAGE_RESOLUTION` like `thieving::copper_coverage`, so
+//! thin tracks register and overlapping copper counts once, with at least
+//! `DENSITY_SAMPLES` samples along each side of a cell.
+//! Zones count with their whole outline (or its hatch), as KiCad's fill clearances around
+//! other nets aren't known here. Cells crossed by the board edge are measured against the
+//! whole cell.
+
+use crate::board::Board;
+use crate::board_interface::Rectangle;
+use crate::geometry::{point_in_polygon, push_arc, Point};
+use crate::hatch::FillStyle;
+use crate::layer_type::{BoardLayer, LayerType, Side};
+use crate::occupancy::Occupancy;
+use crate::thieving::{COVERAGE_RESOLUTION, THIEVING_ARC_TOLERANCE};
+
+/// Fewest samples along each side of a cell
+pub const DENSITY_SAMPLES: usize = 16;
+
+/// Copper coverage of one layer cell by cell, see the module documentation
+#[derive(Debug, Clone, PartialEq)]
+pub struct CopperDensityGrid {
+    pub layer: BoardLayer,
+    /// Top left corner of cell (0, 0)
+    pub origin: Point,
+    /// Cell size in millimeters
+    pub cell_size: f32,
+    pub columns: usize,
+    pub rows: usize,
+    /// Covered fraction of each cell row by row from the top, `None` off the board
+    cells: Vec<Option<f32>>,
+}
+
+impl CopperDensityGrid {
+    /// Covered fraction of a cell, 0 to 1; `None` off the board or off the grid
+    pub fn get(&self, column: usize, row: usize) -> Option<f32> {
+        (column < self.columns && row < self.rows).then(|| self.cells[row * self.columns + column]).flatten()
+    }
+
+    /// Cells row by row from the top
+    pub fn cells(&self) -> &[Option<f32>] {
+        &self.cells
+    }
+
+    /// Board area of a cell
+    pub fn cell_bounds(&self, column: usize, row: usize) -> Rectangle {
+        let min_x = self.origin.x + column as f32 * self.cell_size;
+        let min_y = self.origin.y + row as f32 * self.cell_size;
+        Rectangle { min_x, min_y, max_x: min_x + self.cell_size, max_y: min_y + self.cell_size }
+    }
+
+    /// Mean coverage of the cells on the board, 0 without any
+    pub fn average(&self) -> f32 {
+        let on_board: Vec<f32> = self.cells.iter().flatten().copied().collect();
+        if on_board.is_empty() {
+            return 0.0;
+        }
+        on_board.iter().sum::<f32>() / on_board.len() as f32
+    }
+
+    /// Lowest and highest coverage of the cells on the board
+    pub fn range(&self) -> Option<(f32, f32)> {
+        let mut values = self.cells.iter().flatten().copied();
+        let first = values.next()?;
+        Some(values.fold((first, first), |(low, high), value| (low.min(value), high.max(value))))
+    }
+}
+
+/// A track as a closed outline with round ends
+fn track_outline(start: Point, end: Point, width: f32) -> Vec<Point> {
+    let radius = width / 2.0;
+    let angle = (end.y - start.y).atan2(end.x - start.x).to_degrees();
+    let mut outline = vec![Point::new(start.x + radius * (angle + 90.0).to_radians().cos(), start.y + radius * (angle + 90.0).to_radians().sin())];
+    push_arc(&mut outline, start, radius, angle + 90.0, 180.0, THIEVING_ARC_TOLERANCE);
+    outline.push(Point::new(end.x + radius * (angle - 90.0).to_radians().cos(), end.y + radius * (angle - 90.0).to_radians().sin()));
+    push_arc(&mut outline, end, radius, angle - 90.0, 180.0, THIEVING_ARC_TOLERANCE);
+    outline
+}
+
+fn circle_outline(center: Point, diameter: f32) -> Vec<Point> {
+    let mut outline = Vec::new();
+    push_arc(&mut outline, center, diameter / 2.0, 0.0, 360.0, THIEVING_ARC_TOLERANCE);
+    outline
+}
+
+/// Sample `copper` at the centers of a `columns` by `rows` raster of `step` millimeter
+/// squares whose top left corner is `origin`, row by row from the top: true where a sample
+/// falls inside any outline. The copper density map and the artwork raster (see
+/// `artwork`) both draw with this.
+pub(crate) fn sample_copper(copper: &[Vec<Point>], origin: Point, step: f32, columns: usize, rows: usize) -> Vec<bool> {
+    let sample = |column: usize, row: usize| Point::new(origin.x + (column as f32 + 0.5) * step, origin.y + (row as f32 + 0.5) * step);
+    let mut covered = vec![false; columns * rows];
+    for outline in copper {
+        let Some(bounds) = Rectangle::enclosing(outline) else { continue };
+        let first_column = ((bounds.min_x - origin.x) / step).floor().max(0.0) as usize;
+        let last_column = (((bounds.max_x - origin.x) / step).ceil().max(0.0) as usize).min(columns);
+        let first_row = ((bounds.min_y - origin.y) / step).floor().max(0.0) as usize;
+        let last_row = (((bounds.max_y - origin.y) / step).ceil().max(0.0) as usize).min(rows);
+        for row in first_row..last_row {
+            for column in first_column..last_column {
+                let index = row * columns + column;
+                if !covered[index] && point_in_polygon(sample(column, row), outline) {
+                    covered[index] = true;
+                }
+            }
+        }
+    }
+    covered
+}
+
+impl Board {
+    /// Every piece of copper on `layer` as closed outlines in board coordinates: pads,
+    /// tracks (arc tracks as a capsule per chord), vias, zones and thieving. Hatched zones
+    /// give one outline per hatch line, as the grid KiCad fills them with.
+    pub fn copper_outlines(&self, layer: BoardLayer) -> Vec<Vec<Point>> {
+        self.layer_copper(layer, None)
+    }
+
+    /// The copper of `net` on `layer`, drawn as `copper_outlines` draws it. Thieving
+    /// belongs to no net and pads without a net to none either, so both are left out.
+    pub(crate) fn net_copper_outlines(&self, layer: BoardLayer, net: &str) -> Vec<Vec<Point>> {
+        self.layer_copper(layer, Some(net))
+    }
+
+    fn layer_copper(&self, layer: BoardLayer, net: Option<&str>) -> Vec<Vec<Point>> {
+        if layer.layer != LayerType::Copper {
+            return Vec::new();
+        }
+        let on_net = |item_net: &str| net.is_none_or(|net| net == item_net);
+        let mut outlines: Vec<Vec<Point>> = self
+            .pad_outlines(layer, THIEVING_ARC_TOLERANCE)
+            .into_iter()
+            .filter(|(pad, _)| net.is_none() || self.routing.pad_nets.get(pad).is_some_and(|pad_net| on_net(pad_net)))
+            .map(|(_, outline)| outline)
+            .collect();
+        for track in self.tracks().iter().filter(|track| track.layer == layer && on_net(&track.net)) {
+            outlines.push(track_outline(track.start, track.end, track.width));
+        }
+        for arc in self.arcs().iter().filter(|arc| arc.layer == layer && on_net(&arc.net)) {
+            outlines.extend(arc.chords(THIEVING_ARC_TOLERANCE).into_iter().map(|(start, end)| track_outline(start, end, arc.width)));
+        }
+        for via in self.vias().iter().filter(|via| via.spans(&layer) && on_net(&via.net)) {
+            outlines.push(circle_outline(via.position, via.diameter));
+        }
+        for zone in self.zones().iter().filter(|zone| zone.layers.contains(&layer) && on_net(&zone.net)) {
+            match zone.fill.hatch() {
+                None => outlines.push(zone.outline.clone()),
+                Some((pitch, width, angle)) => {
+                    let grid = FillStyle::CrossHatch { pitch, width, angle };
+                    outlines.extend(grid.hatch_lines(&zone.outline).into_iter().map(|(start, end)| track_outline(start, end, width)));
+                }
+            }
+        }
+        if net.is_none() {
+            outlines.extend(
+                self.thieving().iter().filter(|copper| copper.layer == layer).map(|copper| copper.shape.outline(THIEVING_ARC_TOLERANCE)),
+            );
+        }
+        outlines
+    }
+
+    /// Area copper maps cover: the board outline's bounding box, or that of `copper` when
+    /// the board has no outline
+    pub(crate) fn copper_extent(&self, copper: &[Vec<Point>]) -> Option<Rectangle> {
+        match self.outline() {
+            Some(outline) => Rectangle::enclosing(outline),
+            None => Rectangle::enclosing(&copper.iter().flatten().copied().collect::<Vec<_>>()),
+        }
+    }
+
+    /// Copper coverage of `layer` in cells of `cell_size` millimeters, see the module
+    /// documentation. Boards without an outline are measured over the copper's extent.
+    pub fn copper_density_grid(&self, layer: BoardLayer, cell_size: f32) -> CopperDensityGrid {
+        let copper = self.copper_outlines(layer);
+        let Some(extent) = self.copper_extent(&copper).filter(|_| cell_size > 0.0) else {
+            return CopperDensityGrid { layer, origin: Point::default(), cell_size, columns: 0, rows: 0, cells: Vec::new() };
+        };
+        let side = if layer.side == Side::Back { Side::Back } else { Side::Front };
+        let board = self.outline_grid(&extent, cell_size, side);
+        let (columns, rows) = (board.columns, board.rows);
+
+        // One flag per sample point, `samples` per cell along each axis
+        let samples = ((cell_size / COVERAGE_RESOLUTION).ceil() as usize).max(DENSITY_SAMPLES);
+        let sample_columns = columns * samples;
+        let covered = sample_copper(&copper, board.origin, cell_size / samples as f32, sample_columns, rows * samples);
+
+        let per_cell = (samples * samples) as f32;
+        let mut cells = Vec::with_capacity(columns * rows);
+        for row in 0..rows {
+            for column in 0..columns {
+                if board.get(column, row) == Some(Occupancy::Outside) {
+                    cells.push(None);
+                    continue;
+                }
+                let count = (0..samples)
+                    .flat_map(|y| (0..samples).map(move |x| (row * samples + y) * sample_columns + column * samples + x))
+                    .filter(|&index| covered[index])
+                    .count();
+                cells.push(Some(count as f32 / per_cell));
+            }
+        }
+        CopperDensityGrid { layer, origin: board.origin, cell_size, columns, rows, cells }
+    }
+}
@@ -1,13 +1,17 @@
-use uuid::Uuid;
-use crate::layer_type::LayerType;
+use serde::{Deserialize, Serialize};
+
+use crate::ids::fresh_uuid;
+use crate::geometry::Point;
+use crate::layer_type::{canonical_kicad_layer_name, LayerType, Side};
 use crate::board_interface::{Rectangle, GraphicElement, GraphicType, Stroke, StrokeType};
 
 /// Courtyard structure
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Courtyard {
     pub bounds: Rectangle,
     pub margin: f32,
     pub layer: LayerType, // Usually F.CrtYd or B.CrtYd
+    pub side: Side,
 }
 
 impl Courtyard {
@@ -21,8 +25,26 @@ impl Courtyard {
             },
             margin,
             layer: LayerType::Courtyard,
+            side: Side::Front,
         }
     }
+
+    /// The same courtyard drawn on `side`, e.g. B.CrtYd for the back of a two-sided footprint
+    pub fn on_side(mut self, side: Side) -> Self {
+        self.side = side;
+        self
+    }
+
+    /// The same courtyard grown to the next `grid` lines, as library courtyards are drawn;
+    /// a little slack keeps coordinates already on the grid from moving out by float noise
+    pub fn rounded_out(mut self, grid: f32) -> Self {
+        let snap = |v: f32| (v * 1e4).round() / 1e4 + 0.0;
+        let down = |v: f32| snap((v / grid + 1e-3).floor() * grid);
+        let up = |v: f32| snap((v / grid - 1e-3).ceil() * grid);
+        let bounds = &self.bounds;
+        self.bounds = Rectangle { min_x: down(bounds.min_x), min_y: down(bounds.min_y), max_x: up(bounds.max_x), max_y: up(bounds.max_y) };
+        self
+    }
     
     pub fn to_graphic_elements(&self) -> Vec<GraphicElement> {
         vec![
@@ -31,37 +53,96 @@ impl Courtyard {
                     start: (self.bounds.min_x, self.bounds.min_y),
                     end: (self.bounds.max_x, self.bounds.min_y),
                 },
-                layer: self.layer.clone(),
+                layer: self.layer,
+                side: self.side,
                 stroke: Stroke { width: 0.05, stroke_type: StrokeType::Solid },
-                uuid: Uuid::new_v4().to_string(),
+                uuid: fresh_uuid(),
+                role: None,
             },
             GraphicElement {
                 element_type: GraphicType::Line {
                     start: (self.bounds.max_x, self.bounds.min_y),
                     end: (self.bounds.max_x, self.bounds.max_y),
                 },
-                layer: self.layer.clone(),
+                layer: self.layer,
+                side: self.side,
                 stroke: Stroke { width: 0.05, stroke_type: StrokeType::Solid },
-                uuid: Uuid::new_v4().to_string(),
+                uuid: fresh_uuid(),
+                role: None,
             },
             GraphicElement {
                 element_type: GraphicType::Line {
                     start: (self.bounds.max_x, self.bounds.max_y),
                     end: (self.bounds.min_x, self.bounds.max_y),
                 },
-                layer: self.layer.clone(),
+                layer: self.layer,
+                side: self.side,
                 stroke: Stroke { width: 0.05, stroke_type: StrokeType::Solid },
-                uuid: Uuid::new_v4().to_string(),
+                uuid: fresh_uuid(),
+                role: None,
             },
             GraphicElement {
                 element_type: GraphicType::Line {
                     start: (self.bounds.min_x, self.bounds.max_y),
                     end: (self.bounds.min_x, self.bounds.min_y),
                 },
-                layer: self.layer.clone(),
+                layer: self.layer,
+                side: self.side,
                 stroke: Stroke { width: 0.05, stroke_type: StrokeType::Solid },
-                uuid: Uuid::new_v4().to_string(),
+                uuid: fresh_uuid(),
+                role: None,
             },
         ]
     }
-}
\ No newline at end of file
+}
+
+/// Layer envelopes are drawn on unless told otherwise, User.Drawings in the KiCad UI
+pub const ENVELOPE_LAYER: &str = "Dwgs.User";
+
+/// Space a moving part sweeps or a mating part takes beyond the static courtyard: a slide
+/// switch lever's travel, a trimmer's screwdriver, a card sticking out of its socket.
+/// Other parts must keep out of it like a courtyard, but it may hang over the board edge.
+/// It is drawn dashed on a user layer, `ENVELOPE_LAYER` unless set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActuationEnvelope {
+    /// What moves there, e.g. "card insertion"
+    pub name: String,
+    /// Closed polygon in footprint coordinates, last point not repeated
+    pub outline: Vec<(f32, f32)>,
+    /// KiCad layer name
+    pub layer: String,
+}
+
+impl ActuationEnvelope {
+    pub fn new(name: &str, outline: Vec<(f32, f32)>) -> Self {
+        Self { name: name.to_string(), outline, layer: ENVELOPE_LAYER.to_string() }
+    }
+
+    /// `new` over a rectangle
+    pub fn rect(name: &str, bounds: &Rectangle) -> Self {
+        Self::new(name, vec![(bounds.min_x, bounds.min_y), (bounds.max_x, bounds.min_y), (bounds.max_x, bounds.max_y), (bounds.min_x, bounds.max_y)])
+    }
+
+    /// Draw on `layer`, which may be given by its UI name ("User.Comments")
+    pub fn with_layer(mut self, layer: &str) -> Self {
+        self.layer = canonical_kicad_layer_name(layer).unwrap_or_else(|| layer.to_string());
+        self
+    }
+
+    pub fn points(&self) -> Vec<Point> {
+        self.outline.iter().map(|point| Point::from(*point)).collect()
+    }
+
+    /// The outline as a dashed polygon; its `layer` is a placeholder, write it on
+    /// `self.layer`
+    pub fn to_graphic_element(&self) -> GraphicElement {
+        GraphicElement {
+            element_type: GraphicType::Polygon { points: self.outline.clone(), fill: None },
+            layer: LayerType::Courtyard,
+            side: Side::Front,
+            stroke: Stroke { width: 0.05, stroke_type: StrokeType::Dashed },
+            uuid: fresh_uuid(),
+            role: None,
+        }
+    }
+}
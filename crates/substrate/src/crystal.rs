@@ -0,0 +1,365 @@
+//! Crystal and oscillator footprints
+//!
+//! SMD crystals come in ceramic packages named by body size, "3225" for 3.2 x 2.5 mm,
+//! with two pads on the short ends or four in the corners. Four pad packages count
+//! counterclockwise from the bottom left, KiCad's orientation: the crystal sits between
+//! pins 1 and 3 and pins 2 and 4 are case ground. Oscillator modules use the same
+//! packages with ground on pin 2 only (1 enable, 3 output, 4 supply).
+//!
+//! Land patterns are KiCad's 3225 pattern scaled to the body: pads reach `PAD_OVERHANG`
+//! past the body on the outside, 7/16 of the body length long and, for four pads, 12/25
+//! of the body width tall; the two pad packages use 3/4 of the width. Crystals have no
+//! polarity, so silkscreen and fab outlines are plain rectangles without a pin 1 mark.
+//!
+//! The through hole HC-49/U stands upright on two leads 4.88 mm apart, pin 1 at the
+//! origin as KiCad places it. Its round ended can may get a third pad for a case ground
+//! wire, numbered 3 between the leads, so the crystal keeps pins 1 and 2.
+//!
+//! Courtyards are the usual margin around body and pads, rounded out to
+//! `COURTYARD_GRID` as in the KiCad library.
+
+use crate::anchor::Anchor;
+use crate::board_interface::{
+    BoardComposableObject, FontSettings, FpText, FpTextType, GraphicElement, GraphicType, Model3D, PadDescriptor,
+    PadShape, PadType, Rectangle, Stroke, StrokeType, TentingSettings, TentingType,
+};
+use crate::courtyard::Courtyard;
+use crate::describe::DescriptionContext;
+use crate::functional_types::FunctionalType;
+use crate::geometry::{push_arc, Point};
+use crate::ids::fresh_uuid;
+use crate::layer_type::{LayerType, PadLayers, Side};
+use crate::roles::ElementRole;
+
+/// Standard SMD crystal packages: size code, body length, body width and pad count
+pub const STANDARD_CRYSTAL_SIZES: &[(&str, f32, f32, usize)] = &[
+    ("2012", 2.0, 1.2, 2),
+    ("2016", 2.0, 1.6, 4),
+    ("2520", 2.5, 2.0, 4),
+    ("3225", 3.2, 2.5, 4),
+    ("5032", 5.0, 3.2, 4),
+];
+
+/// How far SMD pads reach past the body on the outside
+pub const PAD_OVERHANG: f32 = 0.2;
+
+/// Courtyard corners are rounded out to this grid
+pub const COURTYARD_GRID: f32 = 0.1;
+
+/// HC-49/U lead spacing
+pub const HC49_PITCH: f32 = 4.88;
+
+/// HC-49/U can, length across the leads by width
+const HC49_BODY: (f32, f32) = (11.05, 4.65);
+const HC49_PAD: (f32, f32) = (1.5, 2.0);
+const HC49_DRILL: f32 = 0.8;
+
+const FAB_LINE_WIDTH: f32 = 0.1;
+const SILK_LINE_WIDTH: f32 = 0.12;
+/// Clearance from silkscreen line centers to pad copper, or to the can of an HC-49
+const SILK_PAD_CLEARANCE: f32 = 0.2;
+/// Largest deviation of the HC-49 outline from its round ends
+const ARC_TOLERANCE: f32 = 0.01;
+
+/// Package of a `Crystal`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CrystalPackage {
+    /// Ceramic SMD package with 2 or 4 pads
+    Smd { pads: usize },
+    /// Upright through hole HC-49/U, optionally with a case ground pad
+    Hc49U { ground_pad: bool },
+}
+
+/// Crystal or oscillator footprint, see the module documentation
+#[derive(Debug, Clone)]
+pub struct Crystal {
+    pub functional_type: FunctionalType,
+    pub package: CrystalPackage,
+    /// Body size along X
+    pub body_length: f32,
+    /// Body size along Y
+    pub body_width: f32,
+}
+
+impl Crystal {
+    /// SMD crystal of any body size with 2 or 4 pads
+    pub fn smd(body_length: f32, body_width: f32, pads: usize) -> Self {
+        Self {
+            functional_type: FunctionalType::Crystal(String::new()),
+            package: CrystalPackage::Smd { pads },
+            body_length,
+            body_width,
+        }
+    }
+
+    /// A package from `STANDARD_CRYSTAL_SIZES` by size code, e.g. "3225"
+    pub fn standard(code: &str) -> Option<Self> {
+        STANDARD_CRYSTAL_SIZES
+            .iter()
+            .find(|(standard, ..)| *standard == code)
+            .map(|&(_, length, width, pads)| Self::smd(length, width, pads))
+    }
+
+    /// Upright HC-49/U on two leads
+    pub fn hc49u() -> Self {
+        Self {
+            functional_type: FunctionalType::Crystal(String::new()),
+            package: CrystalPackage::Hc49U { ground_pad: false },
+            body_length: HC49_BODY.0,
+            body_width: HC49_BODY.1,
+        }
+    }
+
+    pub fn with_functional_type(mut self, functional_type: FunctionalType) -> Self {
+        self.functional_type = functional_type;
+        self
+    }
+
+    /// Add the HC-49 case ground pad; SMD packages are left as they are
+    pub fn with_ground_pad(mut self) -> Self {
+        if let CrystalPackage::Hc49U { ground_pad } = &mut self.package {
+            *ground_pad = true;
+        }
+        self
+    }
+
+    fn is_oscillator(&self) -> bool {
+        matches!(self.functional_type, FunctionalType::Oscillator(_))
+    }
+
+    /// Pads that go to ground, by number
+    pub fn ground_pins(&self) -> Vec<&'static str> {
+        match self.package {
+            CrystalPackage::Smd { pads: 4 } if self.is_oscillator() => vec!["2"],
+            CrystalPackage::Smd { pads: 4 } => vec!["2", "4"],
+            CrystalPackage::Hc49U { ground_pad: true } => vec!["3"],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Pad size (along X, along Y) and the distance of the pad centers from the origin
+    /// along X and Y, for SMD packages
+    pub fn land_pattern(&self) -> ((f32, f32), (f32, f32)) {
+        let length = self.body_length * 7.0 / 16.0;
+        let height = if self.pad_count() == 4 { self.body_width * 12.0 / 25.0 } else { self.body_width * 0.75 };
+        let x = self.body_length / 2.0 + PAD_OVERHANG - length / 2.0;
+        let y = if self.pad_count() == 4 { self.body_width / 2.0 + PAD_OVERHANG - height / 2.0 } else { 0.0 };
+        ((round(length), round(height)), (round(x), round(y)))
+    }
+
+    fn pad_count(&self) -> usize {
+        match self.package {
+            CrystalPackage::Smd { pads } => pads,
+            CrystalPackage::Hc49U { ground_pad } => 2 + usize::from(ground_pad),
+        }
+    }
+
+    /// Body and pads together
+    fn extent(&self) -> Rectangle {
+        self.pad_descriptors().iter().map(|pad| pad.outline_aabb()).fold(self.bounding_box(), |extent, pad| Rectangle {
+            min_x: extent.min_x.min(pad.min_x),
+            min_y: extent.min_y.min(pad.min_y),
+            max_x: extent.max_x.max(pad.max_x),
+            max_y: extent.max_y.max(pad.max_y),
+        })
+    }
+
+    /// Round ended outline of the HC-49 can, grown by `offset`
+    fn hc49_outline(&self, offset: f32) -> Vec<(f32, f32)> {
+        let radius = self.body_width / 2.0 + offset;
+        let straight = self.body_length / 2.0 - self.body_width / 2.0;
+        let center = HC49_PITCH / 2.0;
+        let mut points = vec![Point::new(center - straight, -radius)];
+        push_arc(&mut points, Point::new(center - straight, 0.0), radius, 270.0, -180.0, ARC_TOLERANCE);
+        points.push(Point::new(center + straight, radius));
+        push_arc(&mut points, Point::new(center + straight, 0.0), radius, 90.0, -180.0, ARC_TOLERANCE);
+        points.into_iter().map(|p| (round(p.x), round(p.y))).collect()
+    }
+
+    fn graphic(element_type: GraphicType, layer: LayerType, width: f32) -> GraphicElement {
+        GraphicElement {
+            element_type,
+            layer,
+            side: Side::Front,
+            stroke: Stroke { width, stroke_type: StrokeType::Solid },
+            uuid: fresh_uuid(),
+            role: None,
+        }
+    }
+}
+
+/// Snap computed coordinates to 0.1 µm so the output has no float noise; adding zero
+/// turns -0 into 0
+fn round(value: f32) -> f32 {
+    (value * 1e4).round() / 1e4 + 0.0
+}
+
+impl BoardComposableObject for Crystal {
+    fn is_smt(&self) -> bool {
+        matches!(self.package, CrystalPackage::Smd { .. })
+    }
+
+    fn is_electrical(&self) -> bool {
+        true
+    }
+
+    fn terminal_count(&self) -> usize {
+        self.pad_count()
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        self.functional_type.clone()
+    }
+
+    /// KiCad's "Crystal_SMD_3225-4Pin_3.2x2.5mm" and "Crystal_HC49-U_Vertical", with
+    /// "Oscillator" for oscillators
+    fn footprint_name(&self) -> String {
+        let kind = if self.is_oscillator() { "Oscillator" } else { "Crystal" };
+        match self.package {
+            CrystalPackage::Smd { pads } => {
+                let code = format!("{:02}{:02}", (self.body_length * 10.0).round(), (self.body_width * 10.0).round());
+                format!("{}_SMD_{}-{}Pin_{:.1}x{:.1}mm", kind, code, pads, self.body_length, self.body_width)
+            }
+            CrystalPackage::Hc49U { ground_pad: false } => format!("{}_HC49-U_Vertical", kind),
+            CrystalPackage::Hc49U { ground_pad: true } => format!("{}_HC49-U-3Pin_Vertical", kind),
+        }
+    }
+
+    fn library_name(&self) -> String {
+        if self.is_oscillator() { "Oscillator" } else { "Crystal" }.to_string()
+    }
+
+    /// The body: centered for SMD packages, around the leads for the HC-49
+    fn bounding_box(&self) -> Rectangle {
+        let (half_x, half_y) = (self.body_length / 2.0, self.body_width / 2.0);
+        let center = if self.is_smt() { 0.0 } else { HC49_PITCH / 2.0 };
+        Rectangle { min_x: round(center - half_x), min_y: -half_y, max_x: round(center + half_x), max_y: half_y }
+    }
+
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        let ground = self.ground_pins();
+        let pad = |number: &str, position: (f32, f32), size: (f32, f32)| PadDescriptor {
+            number: number.into(),
+            pin_function: ground.contains(&number).then(|| "GND".to_string()),
+            pad_type: if self.is_smt() { PadType::SMD } else { PadType::ThroughHole },
+            shape: if self.is_smt() { PadShape::Rect } else { PadShape::Oval },
+            position,
+            rotation: None,
+            size,
+            drill_size: (!self.is_smt()).then_some(HC49_DRILL),
+            drill_slot: None,
+            layers: if self.is_smt() { PadLayers::smd_front() } else { PadLayers::tht() },
+            roundrect_ratio: None,
+            tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+            padstack: None,
+            fab_property: None,
+            solder_mask_margin: None,
+            routing_hint: None,
+            uuid: fresh_uuid(),
+        };
+        match self.package {
+            CrystalPackage::Smd { pads: 4 } => {
+                let (size, (x, y)) = self.land_pattern();
+                let corners = [(-x, y), (x, y), (x, -y), (-x, -y)];
+                corners.iter().enumerate().map(|(index, &position)| pad(&(index + 1).to_string(), position, size)).collect()
+            }
+            CrystalPackage::Smd { .. } => {
+                let (size, (x, _)) = self.land_pattern();
+                vec![pad("1", (-x, 0.0), size), pad("2", (x, 0.0), size)]
+            }
+            CrystalPackage::Hc49U { ground_pad } => {
+                let mut pads = vec![pad("1", (0.0, 0.0), HC49_PAD), pad("2", (HC49_PITCH, 0.0), HC49_PAD)];
+                if ground_pad {
+                    pads.push(pad("3", (HC49_PITCH / 2.0, 0.0), HC49_PAD));
+                }
+                pads
+            }
+        }
+    }
+
+    fn description_context(&self) -> Option<DescriptionContext> {
+        let (mount, package) = match self.package {
+            CrystalPackage::Smd { .. } => ("SMD", self.footprint_name().split('_').nth(2).unwrap_or_default().to_string()),
+            CrystalPackage::Hc49U { .. } => ("THT", "HC-49/U".to_string()),
+        };
+        Some(
+            DescriptionContext::new("crystal", self.functional_type.clone(), &package)
+                .with_density(None)
+                .with_option(mount)
+                .with_field("mount", mount)
+                .with_field("pads", self.pad_count())
+                .with_field("body_length", format!("{:.2}", self.body_length).trim_end_matches('0').trim_end_matches('.'))
+                .with_field("body_width", format!("{:.2}", self.body_width).trim_end_matches('0').trim_end_matches('.'))
+                .with_field("ground_pins", self.ground_pins().join("/")),
+        )
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        let courtyard = self.generate_courtyard().bounds;
+        let center = (courtyard.min_x + courtyard.max_x) / 2.0;
+        let fab_size = (self.body_width * 0.25).clamp(0.5, 1.0);
+        let text = |text_type, text: String, position, layer: &str, size: f32| FpText {
+            text_type,
+            text,
+            position,
+            rotation: None,
+            layer: layer.into(),
+            uuid: fresh_uuid(),
+            font: FontSettings { size: (size, size), thickness: size * 0.15 },
+            hidden: false,
+            justify: None,
+            role: None,
+        };
+        vec![
+            text(FpTextType::Reference, "REF**".to_string(), (center, round(courtyard.min_y - 0.75)), "F.SilkS", 1.0),
+            text(FpTextType::Value, self.footprint_name(), (center, round(courtyard.max_y + 0.75)), "F.Fab", 1.0),
+            text(FpTextType::User, "${REFERENCE}".to_string(), (center, 0.0), "F.Fab", fab_size),
+        ]
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        match self.package {
+            CrystalPackage::Smd { .. } => {
+                // Silkscreen all around the pads, nothing to say which way round
+                let pads = self.extent();
+                let silk = SILK_PAD_CLEARANCE;
+                let silk = Rectangle { min_x: pads.min_x - silk, min_y: pads.min_y - silk, max_x: pads.max_x + silk, max_y: pads.max_y + silk };
+                vec![
+                    Self::graphic(GraphicType::Rectangle { bounds: self.bounding_box() }, LayerType::Fabrication, FAB_LINE_WIDTH)
+                        .with_role(ElementRole::FabOutline(None)),
+                    Self::graphic(GraphicType::Rectangle { bounds: silk }, LayerType::SilkScreen, SILK_LINE_WIDTH)
+                        .with_role(ElementRole::SilkOutline(None)),
+                ]
+            }
+            CrystalPackage::Hc49U { .. } => vec![
+                Self::graphic(GraphicType::Polygon { points: self.hc49_outline(0.0), fill: None }, LayerType::Fabrication, FAB_LINE_WIDTH)
+                    .with_role(ElementRole::FabOutline(None)),
+                Self::graphic(
+                    GraphicType::Polygon { points: self.hc49_outline(SILK_PAD_CLEARANCE), fill: None },
+                    LayerType::SilkScreen,
+                    SILK_LINE_WIDTH,
+                )
+                .with_role(ElementRole::SilkOutline(None)),
+            ],
+        }
+    }
+
+    fn model_3d(&self) -> Option<Model3D> {
+        Some(Model3D {
+            path: format!("${{KICAD9_3DMODEL_DIR}}/{}.3dshapes/{}.wrl", self.library_name(), self.footprint_name()),
+            offset: (0.0, 0.0, 0.0),
+            scale: (1.0, 1.0, 1.0),
+            rotation: (0.0, 0.0, 0.0),
+        })
+    }
+
+    /// Through hole footprints keep pin 1 at the origin, as KiCad's do
+    fn anchor(&self) -> Anchor {
+        if self.is_smt() { Anchor::Centroid } else { Anchor::Pin1 }
+    }
+
+    /// Courtyard around the pads as well as the body, rounded out to `COURTYARD_GRID`
+    fn generate_courtyard(&self) -> Courtyard {
+        Courtyard::new(self.extent(), self.courtyard_margin()).rounded_out(COURTYARD_GRID)
+    }
+}
@@ -0,0 +1,338 @@
+//! Templated footprint descriptions and tags
+//!
+//! Generators describe their footprints through a `DescriptionContext`: the template
+//! family ("chip", "soic"), the functional type, the package and whatever else the
+//! template may ask for. `BoardComposableObject::description` fills the family's template
+//! from it, and `tags` derives the tags from the functional type, the package and the
+//! options, so every generated footprint reads the same way:
+//!
+//! "Resistor SMD 0805 (2012 Metric), square (rectangular) end terminal, IPC_7351 nominal,
+//! generated with copper-substrate"
+//!
+//! Templates name placeholders in braces. Text in square brackets is dropped when any
+//! placeholder inside is missing or empty, so optional parts need no separate template;
+//! a missing placeholder outside brackets is left as written, so a typo shows. Double a
+//! brace or bracket for a literal one.
+//!
+//! | placeholder           | value                                                   |
+//! |-----------------------|---------------------------------------------------------|
+//! | `{kind}`              | functional type, e.g. "Resistor"                        |
+//! | `{value}`             | functional type value, e.g. "10k"                       |
+//! | `{package}`           | package name, e.g. "0805" or "SOIC-8"                   |
+//! | `{density}`           | IPC-7351 density level, empty for custom land patterns  |
+//! | `{generator}`         | attribution, see `set_attribution`                      |
+//! | `{generator_version}` | version of this crate                                   |
+//!
+//! plus the generator's own fields, e.g. `{body_size_metric}` for chips.
+//!
+//! Tags are normalized with `normalize_tags`: split on whitespace, lower case, without
+//! quotes or backslashes and without repeats, so they survive any file format's quoting
+//! and search the same way whoever wrote them.
+//!
+//! Templates are kept per language; a language without its own template for a family
+//! falls back to English. The attribution, the language and any added templates are
+//! process wide, set once by the program that writes a library.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::{LazyLock, RwLock};
+
+use crate::functional_types::FunctionalType;
+
+/// Default attribution, as in "generated with copper-substrate"
+pub const GENERATOR_NAME: &str = "copper-substrate";
+
+pub const GENERATOR_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Language the built-in templates are written in, and the fallback for others
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Template family used when a generator's own has no template
+pub const DEFAULT_FAMILY: &str = "default";
+
+const BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    (DEFAULT_FAMILY, "{kind} {package}[, IPC_7351 {density}], generated with {generator}"),
+    (
+        "chip",
+        "{kind} SMD {body_size_imperial} ({body_size_metric} Metric), square (rectangular) end terminal\
+         [, IPC_7351 {density}][, (Body size source: {body_size_source})], generated with {generator}",
+    ),
+    (
+        "crystal",
+        "{kind} {mount} {package}, {pads} pads, {body_length}x{body_width} mm body[, ground pins {ground_pins}], \
+         generated with {generator}",
+    ),
+    (
+        "dip",
+        "{pins}-lead through-hole mounted DIP package, row spacing {row_spacing} mm ({row_mils} mils)[, {variant}], \
+         generated with {generator}",
+    ),
+    (
+        "dpak",
+        "{package}-{leads}, {leads} leads, {pitch} mm pitch, tab pin {tab_pin}, {body_length}x{body_width} mm body\
+         [, {thermal_vias} thermal vias][, IPC_7351 {density}], generated with {generator}",
+    ),
+    (
+        "soic",
+        "SOIC, {pins} Pin[ ({outline})], {body_width}x{body_length} mm body, {pitch} mm pitch, \
+         {height} mm max height[, {tolerances}][, IPC_7351 {density}], generated with {generator}",
+    ),
+    (
+        "qfn",
+        "QFN, {pins} Pin[ ({outline})], {body_width}x{body_length} mm body, {pitch} mm pitch\
+         [, {exposed_pad_x}x{exposed_pad_y} mm exposed pad], {height} mm max height[, {tolerances}]\
+         [, IPC_7351 {density}], generated with {generator}",
+    ),
+];
+
+/// IPC-7351 land pattern density level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Density {
+    /// Level C, smallest lands for dense boards
+    Least,
+    /// Level B
+    #[default]
+    Nominal,
+    /// Level A, largest lands for hand soldering and rework
+    Most,
+}
+
+impl fmt::Display for Density {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Density::Least => "least",
+            Density::Nominal => "nominal",
+            Density::Most => "most",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateError {
+    /// A "{" or "[" that is never closed, or a stray "}" or "]"
+    Unbalanced { template: String, position: usize },
+    /// Optional groups can't hold other groups
+    NestedGroup { template: String, position: usize },
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::Unbalanced { template, position } => {
+                write!(f, "unbalanced brace or bracket at {} in template \"{}\"", position, template)
+            }
+            TemplateError::NestedGroup { template, position } => {
+                write!(f, "optional group inside another at {} in template \"{}\"", position, template)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// Fill `template` with `lookup`, see the module documentation for the syntax
+pub fn render(template: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String, TemplateError> {
+    let unbalanced = |position| TemplateError::Unbalanced { template: template.to_string(), position };
+    let mut output = String::with_capacity(template.len());
+    // Text of the open optional group and whether all its placeholders were filled
+    let mut group: Option<(String, bool)> = None;
+    let mut chars = template.char_indices().peekable();
+    while let Some((position, c)) = chars.next() {
+        match c {
+            '{' | '}' | '[' | ']' if chars.peek().map(|&(_, next)| next) == Some(c) => {
+                chars.next();
+                sink(&mut output, &mut group).push(c);
+            }
+            '{' => {
+                let start = position + 1;
+                let end = loop {
+                    match chars.next() {
+                        Some((end, '}')) => break end,
+                        Some(_) => {}
+                        None => return Err(unbalanced(position)),
+                    }
+                };
+                let name = &template[start..end];
+                match (lookup(name).filter(|value| !value.is_empty()), &mut group) {
+                    (Some(value), _) => sink(&mut output, &mut group).push_str(&value),
+                    (None, Some((_, complete))) => *complete = false,
+                    (None, None) => output.push_str(&template[position..=end]),
+                }
+            }
+            '[' => {
+                if group.is_some() {
+                    return Err(TemplateError::NestedGroup { template: template.to_string(), position });
+                }
+                group = Some((String::new(), true));
+            }
+            ']' => {
+                let (text, complete) = group.take().ok_or_else(|| unbalanced(position))?;
+                if complete {
+                    output.push_str(&text);
+                }
+            }
+            '}' => return Err(unbalanced(position)),
+            c => sink(&mut output, &mut group).push(c),
+        }
+    }
+    if group.is_some() {
+        return Err(unbalanced(template.len()));
+    }
+    Ok(output)
+}
+
+/// Where rendered text goes: the open optional group, else the output
+fn sink<'a>(output: &'a mut String, group: &'a mut Option<(String, bool)>) -> &'a mut String {
+    match group {
+        Some((text, _)) => text,
+        None => output,
+    }
+}
+
+struct Settings {
+    attribution: String,
+    language: String,
+    /// (family, language) to template
+    templates: HashMap<(String, String), String>,
+}
+
+static SETTINGS: LazyLock<RwLock<Settings>> = LazyLock::new(|| {
+    let templates = BUILTIN_TEMPLATES
+        .iter()
+        .map(|(family, template)| ((family.to_string(), DEFAULT_LANGUAGE.to_string()), template.to_string()))
+        .collect();
+    RwLock::new(Settings { attribution: GENERATOR_NAME.to_string(), language: DEFAULT_LANGUAGE.to_string(), templates })
+});
+
+fn settings() -> std::sync::RwLockReadGuard<'static, Settings> {
+    SETTINGS.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn settings_mut() -> std::sync::RwLockWriteGuard<'static, Settings> {
+    SETTINGS.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Who descriptions say generated the footprint, `GENERATOR_NAME` unless set
+pub fn set_attribution(attribution: &str) {
+    settings_mut().attribution = attribution.to_string();
+}
+
+pub fn attribution() -> String {
+    settings().attribution.clone()
+}
+
+/// Language descriptions are written in, e.g. "de"; `DEFAULT_LANGUAGE` unless set
+pub fn set_language(language: &str) {
+    settings_mut().language = language.to_string();
+}
+
+pub fn language() -> String {
+    settings().language.clone()
+}
+
+/// Add or replace the template of `family` in `language`
+pub fn register_template(family: &str, language: &str, template: &str) -> Result<(), TemplateError> {
+    render(template, |_| None)?;
+    settings_mut().templates.insert((family.to_string(), language.to_string()), template.to_string());
+    Ok(())
+}
+
+/// The template `family` uses in `language`: its own, then the family's English one, then
+/// the default family's in `language` and in English
+pub fn template(family: &str, language: &str) -> String {
+    let settings = settings();
+    [(family, language), (family, DEFAULT_LANGUAGE), (DEFAULT_FAMILY, language), (DEFAULT_FAMILY, DEFAULT_LANGUAGE)]
+        .iter()
+        .find_map(|(family, language)| settings.templates.get(&(family.to_string(), language.to_string())))
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// What a generator knows about a footprint for its description and tags, see the module
+/// documentation
+#[derive(Debug, Clone)]
+pub struct DescriptionContext {
+    /// Template family, e.g. "chip"
+    pub family: String,
+    pub functional_type: FunctionalType,
+    /// Package name as tagged, e.g. "0805"
+    pub package: String,
+    /// `None` for land patterns that follow no IPC density level
+    pub density: Option<Density>,
+    /// Options worth a tag, e.g. "handsolder"
+    pub options: Vec<String>,
+    /// The generator's own placeholders
+    pub fields: BTreeMap<String, String>,
+}
+
+impl DescriptionContext {
+    pub fn new(family: &str, functional_type: FunctionalType, package: &str) -> Self {
+        Self {
+            family: family.to_string(),
+            functional_type,
+            package: package.to_string(),
+            density: Some(Density::Nominal),
+            options: Vec::new(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_density(mut self, density: Option<Density>) -> Self {
+        self.density = density;
+        self
+    }
+
+    pub fn with_option(mut self, option: &str) -> Self {
+        self.options.push(option.to_string());
+        self
+    }
+
+    /// Set placeholder `name`; empty values count as missing
+    pub fn with_field(mut self, name: &str, value: impl ToString) -> Self {
+        self.fields.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Value of placeholder `name`
+    pub fn placeholder(&self, name: &str) -> Option<String> {
+        match name {
+            "kind" => Some(self.functional_type.kind_name().to_string()),
+            "value" => Some(self.functional_type.value().to_string()),
+            "package" => Some(self.package.clone()),
+            "density" => self.density.map(|density| density.to_string()),
+            "generator" => Some(attribution()),
+            "generator_version" => Some(GENERATOR_VERSION.to_string()),
+            _ => self.fields.get(name).cloned(),
+        }
+    }
+
+    /// The family's template in the current language, filled in
+    pub fn description(&self) -> String {
+        let template = template(&self.family, &language());
+        // Templates are checked when registered and the built-in ones are well formed
+        render(&template, |name| self.placeholder(name)).unwrap_or(template)
+    }
+
+    /// Functional type, package and options, normalized
+    pub fn tags(&self) -> String {
+        let kind = match self.functional_type {
+            FunctionalType::IntegratedCircuit(_) => "ic",
+            _ => self.functional_type.kind_name(),
+        };
+        let tags: Vec<&str> = [kind, &self.package].into_iter().chain(self.options.iter().map(String::as_str)).collect();
+        normalize_tags(&tags.join(" "))
+    }
+}
+
+/// `tags` split on whitespace, lower cased, stripped of quotes and backslashes, and
+/// joined by single spaces in their first order without repeats
+pub fn normalize_tags(tags: &str) -> String {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags.split_whitespace() {
+        let tag: String = tag.chars().filter(|c| !matches!(c, '"' | '\'' | '`' | '\\')).flat_map(char::to_lowercase).collect();
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized.join(" ")
+}
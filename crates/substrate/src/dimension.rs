@@ -0,0 +1,262 @@
+//! Dimension annotations
+//!
+//! Measurement callouts for fab and assembly drawings, modelled on KiCad's dimension
+//! items: an aligned or orthogonal dimension measures between two points, a radial one
+//! from a circle center to its rim and a leader points at something with free text. The
+//! text is formatted from the measured geometry ("12.70 mm") unless overridden.
+//!
+//! `Dimension::geometry` resolves the drawing into lines and a text anchor so every
+//! renderer draws the same arrows; KiCad output only needs the defining points.
+
+use serde::{Deserialize, Serialize};
+
+use crate::geometry::Point;
+
+/// Gap between the measured point and the start of an extension line (KiCad default)
+pub const DIMENSION_EXTENSION_OFFSET: f32 = 0.5;
+/// How far extension lines run past the dimension line (KiCad default)
+pub const DIMENSION_EXTENSION_OVERSHOOT: f32 = 0.58;
+/// Length of each arrowhead stroke (KiCad default)
+pub const DIMENSION_ARROW_LENGTH: f32 = 1.27;
+/// Half angle between the two strokes of an arrowhead in degrees
+pub const DIMENSION_ARROW_ANGLE: f32 = 27.5;
+/// Length of the radial dimension leader past the rim (KiCad default)
+pub const DIMENSION_LEADER_LENGTH: f32 = 3.81;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DimensionKind {
+    /// Measures the straight distance from start to end
+    Aligned,
+    /// Measures only the horizontal or vertical component
+    Orthogonal { vertical: bool },
+    /// Measures the radius, start is the center and end a point on the rim
+    Radial,
+    /// Arrow from start to the text at end, no measured value
+    Leader,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DimensionUnits {
+    Millimeters,
+    Inches,
+    Mils,
+}
+
+impl DimensionUnits {
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            DimensionUnits::Millimeters => "mm",
+            DimensionUnits::Inches => "in",
+            DimensionUnits::Mils => "mils",
+        }
+    }
+
+    /// Convert a length in millimeters to these units
+    pub fn from_mm(&self, value: f32) -> f32 {
+        match self {
+            DimensionUnits::Millimeters => value,
+            DimensionUnits::Inches => value / 25.4,
+            DimensionUnits::Mils => value / 0.0254,
+        }
+    }
+}
+
+/// How the measured value is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DimensionFormat {
+    pub units: DimensionUnits,
+    /// Digits after the decimal point
+    pub precision: u8,
+    /// Append the unit suffix ("12.70 mm" rather than "12.70")
+    pub show_units: bool,
+}
+
+impl Default for DimensionFormat {
+    fn default() -> Self {
+        Self { units: DimensionUnits::Millimeters, precision: 2, show_units: true }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dimension {
+    pub kind: DimensionKind,
+    pub start: (f32, f32),
+    pub end: (f32, f32),
+    /// Offset of the dimension line from the measured points. Aligned dimensions offset
+    /// to the right of start -> end as seen on screen, orthogonal ones along +Y (+X when
+    /// vertical). Ignored by radial and leader dimensions.
+    pub height: f32,
+    pub text_height: f32,
+    pub format: DimensionFormat,
+    /// Text shown instead of the measured value
+    pub override_text: Option<String>,
+}
+
+/// A dimension resolved into strokes and a text anchor
+#[derive(Debug, Clone, PartialEq)]
+pub struct DimensionGeometry {
+    /// Dimension line and extension lines
+    pub lines: Vec<(Point, Point)>,
+    /// Each arrowhead as its tip and the ends of its two strokes
+    pub arrowheads: Vec<(Point, Point, Point)>,
+    /// Center of the text
+    pub text_position: Point,
+    /// Text angle in degrees counter-clockwise, kept readable
+    pub text_angle: f32,
+}
+
+impl Dimension {
+    pub fn aligned(start: (f32, f32), end: (f32, f32), height: f32) -> Self {
+        Self::new(DimensionKind::Aligned, start, end, height)
+    }
+
+    pub fn orthogonal(start: (f32, f32), end: (f32, f32), height: f32, vertical: bool) -> Self {
+        Self::new(DimensionKind::Orthogonal { vertical }, start, end, height)
+    }
+
+    pub fn radial(center: (f32, f32), rim: (f32, f32)) -> Self {
+        Self::new(DimensionKind::Radial, center, rim, 0.0)
+    }
+
+    pub fn leader(target: (f32, f32), text_position: (f32, f32), text: &str) -> Self {
+        Self::new(DimensionKind::Leader, target, text_position, 0.0).with_text(text)
+    }
+
+    fn new(kind: DimensionKind, start: (f32, f32), end: (f32, f32), height: f32) -> Self {
+        Self { kind, start, end, height, text_height: 1.0, format: DimensionFormat::default(), override_text: None }
+    }
+
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.override_text = Some(text.to_string());
+        self
+    }
+
+    pub fn with_format(mut self, format: DimensionFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_text_height(mut self, text_height: f32) -> Self {
+        self.text_height = text_height;
+        self
+    }
+
+    /// Measured length in millimeters, `None` for leaders
+    pub fn measured_value(&self) -> Option<f32> {
+        let (dx, dy) = (self.end.0 - self.start.0, self.end.1 - self.start.1);
+        match self.kind {
+            DimensionKind::Aligned | DimensionKind::Radial => Some(dx.hypot(dy)),
+            DimensionKind::Orthogonal { vertical: false } => Some(dx.abs()),
+            DimensionKind::Orthogonal { vertical: true } => Some(dy.abs()),
+            DimensionKind::Leader => None,
+        }
+    }
+
+    /// The measured value formatted without any override, e.g. "12.70 mm" or "R 1.50 mm"
+    pub fn value_text(&self) -> String {
+        let Some(value) = self.measured_value() else {
+            return String::new();
+        };
+        let mut text = format!("{:.*}", self.format.precision as usize, self.format.units.from_mm(value));
+        if self.format.show_units {
+            text = format!("{} {}", text, self.format.units.suffix());
+        }
+        if self.kind == DimensionKind::Radial { format!("R {}", text) } else { text }
+    }
+
+    /// Text as displayed: the override if set, the formatted measurement otherwise
+    pub fn text(&self) -> String {
+        self.override_text.clone().unwrap_or_else(|| self.value_text())
+    }
+
+    pub fn geometry(&self) -> DimensionGeometry {
+        let start = Point::from(self.start);
+        let end = Point::from(self.end);
+        match self.kind {
+            DimensionKind::Aligned => {
+                let direction = unit(start, end);
+                let normal = Point::new(-direction.y, direction.x);
+                self.linear_geometry(start, end, end, normal)
+            }
+            DimensionKind::Orthogonal { vertical } => {
+                let normal = if vertical { Point::new(1.0, 0.0) } else { Point::new(0.0, 1.0) };
+                // The dimension line runs along the measuring axis through the start point
+                let axis_end = if vertical { Point::new(start.x, end.y) } else { Point::new(end.x, start.y) };
+                self.linear_geometry(start, end, axis_end, normal)
+            }
+            DimensionKind::Radial => {
+                let direction = unit(start, end);
+                let knee = end.offset(direction.x * DIMENSION_LEADER_LENGTH, direction.y * DIMENSION_LEADER_LENGTH);
+                DimensionGeometry {
+                    lines: vec![(end, knee)],
+                    arrowheads: vec![arrowhead(end, direction)],
+                    text_position: knee.offset(direction.x * self.text_height, direction.y * self.text_height),
+                    text_angle: 0.0,
+                }
+            }
+            DimensionKind::Leader => DimensionGeometry {
+                lines: vec![(start, end)],
+                arrowheads: vec![arrowhead(start, unit(start, end))],
+                text_position: end,
+                text_angle: 0.0,
+            },
+        }
+    }
+
+    /// Dimension line offset by `height` from `start` and `axis_end`, with an extension
+    /// line from each measured point to it and arrows pointing outwards at both ends
+    fn linear_geometry(&self, start: Point, end: Point, axis_end: Point, normal: Point) -> DimensionGeometry {
+        let sign = if self.height < 0.0 { -1.0 } else { 1.0 };
+        let offset = along(normal, self.height);
+        let (a, b) = (start.offset(offset.x, offset.y), axis_end.offset(offset.x, offset.y));
+        let extension = |from: Point, to: Point| {
+            let direction = if from.distance_to(&to) > 0.0 { unit(from, to) } else { along(normal, sign) };
+            let gap = along(direction, DIMENSION_EXTENSION_OFFSET);
+            let overshoot = along(direction, DIMENSION_EXTENSION_OVERSHOOT);
+            (from.offset(gap.x, gap.y), to.offset(overshoot.x, overshoot.y))
+        };
+
+        let direction = unit(a, b);
+        let text_offset = along(normal, sign * self.text_height);
+        let middle = Point::new((a.x + b.x) / 2.0, (a.y + b.y) / 2.0);
+        // Screen angle of the line, counter-clockwise with Y down
+        let angle = (-direction.y).atan2(direction.x).to_degrees();
+        DimensionGeometry {
+            lines: vec![(a, b), extension(start, a), extension(end, b)],
+            arrowheads: vec![arrowhead(a, direction), arrowhead(b, Point::new(-direction.x, -direction.y))],
+            text_position: middle.offset(text_offset.x, text_offset.y),
+            text_angle: readable_angle(angle),
+        }
+    }
+}
+
+fn along(direction: Point, length: f32) -> Point {
+    Point::new(direction.x * length, direction.y * length)
+}
+
+/// Unit vector from `a` towards `b`, +X for coincident points
+fn unit(a: Point, b: Point) -> Point {
+    let length = a.distance_to(&b);
+    if length > 0.0 { Point::new((b.x - a.x) / length, (b.y - a.y) / length) } else { Point::new(1.0, 0.0) }
+}
+
+/// Arrowhead with its tip at `tip` and strokes trailing back along `direction`
+fn arrowhead(tip: Point, direction: Point) -> (Point, Point, Point) {
+    let stroke = along(direction, DIMENSION_ARROW_LENGTH);
+    let left = stroke.rotated(DIMENSION_ARROW_ANGLE);
+    let right = stroke.rotated(-DIMENSION_ARROW_ANGLE);
+    (tip, tip.offset(left.x, left.y), tip.offset(right.x, right.y))
+}
+
+/// Fold an angle into (-90, 90] so text never reads upside down
+fn readable_angle(angle: f32) -> f32 {
+    // Adding zero turns -0 into 0 so files don't read "(at x y -0)"
+    let angle = angle.rem_euclid(360.0) + 0.0;
+    if angle > 90.0 && angle <= 270.0 {
+        angle - 180.0
+    } else if angle > 270.0 {
+        angle - 360.0
+    } else {
+        angle
+    }
+}
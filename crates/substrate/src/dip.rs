@@ -0,0 +1,360 @@
+//! Through hole DIP footprints
+//!
+//! Dual in-line packages have two rows of leads at `DIP_PITCH`, 7.62 mm (300 mil) apart
+//! for narrow bodies and 15.24 mm (600 mil) for wide ones. Footprints follow KiCad's
+//! Package_DIP library: pin 1 at the origin, pins counting down the left row and back up
+//! the right one. Pin 1 is a square pad and the rest are ovals, with KiCad's `DIP_DRILL`
+//! and `DIP_PAD`. "LongPads" stretches the pads across the row for hand soldering.
+//!
+//! Pin 1's end of the body is marked with a notch on the silkscreen and the fab layer,
+//! which also chamfers the pin 1 corner. The silkscreen outline runs between the pad rows,
+//! as the body sits between them. Socketed footprints add the socket's outline, reaching
+//! `SOCKET_OVERHANG` past the pad rows, and the courtyard grows around it.
+//!
+//! The pads have no paste unless `with_pin_in_paste` sizes apertures for pin-in-paste
+//! reflow, see `pin_in_paste`.
+
+use compact_str::ToCompactString;
+
+use crate::anchor::Anchor;
+use crate::board_interface::{
+    BoardComposableObject, FontSettings, FpText, FpTextType, GraphicElement, GraphicType, Model3D, PadDescriptor,
+    PadShape, PadType, Rectangle, Stroke, StrokeType, TentingSettings, TentingType,
+};
+use crate::courtyard::Courtyard;
+use crate::describe::DescriptionContext;
+use crate::functional_types::FunctionalType;
+use crate::geometry::{push_arc, Point};
+use crate::ids::fresh_uuid;
+use crate::layer_type::{LayerType, PadLayers, Side};
+use crate::pin_in_paste::{PasteAperture, PinInPaste, PinInPasteError};
+use crate::roles::ElementRole;
+
+/// Standard row spacings and the body width of each
+pub const DIP_ROW_SPACINGS: &[(f32, f32)] = &[(7.62, 6.35), (15.24, 13.97)];
+
+pub const DIP_PITCH: f32 = 2.54;
+pub const DIP_DRILL: f32 = 0.8;
+/// Pad diameter, and pad height of "LongPads"
+pub const DIP_PAD: f32 = 1.6;
+/// Pad length across the row of "LongPads"
+pub const LONG_PAD_LENGTH: f32 = 2.4;
+/// How far a socket reaches past the pad rows
+pub const SOCKET_OVERHANG: f32 = 1.27;
+/// How far a socket reaches past the ends of the body
+const SOCKET_END_OVERHANG: f32 = 0.06;
+
+/// Courtyard corners are rounded out to this grid
+pub const COURTYARD_GRID: f32 = 0.05;
+
+const FAB_LINE_WIDTH: f32 = 0.1;
+const SILK_LINE_WIDTH: f32 = 0.12;
+/// Clearance from silkscreen line edges to pad copper
+const SILK_PAD_CLEARANCE: f32 = 0.3;
+/// Size of the fab outline's pin 1 chamfer
+const FAB_CHAMFER: f32 = 1.0;
+const NOTCH_RADIUS: f32 = 1.0;
+/// Largest deviation of the notch from a true arc
+const ARC_TOLERANCE: f32 = 0.01;
+
+/// DIP footprint, see the module documentation
+#[derive(Debug, Clone)]
+pub struct Dip {
+    pub functional_type: FunctionalType,
+    pub pins: usize,
+    /// Distance between the pad rows
+    pub row_spacing: f32,
+    pub body_width: f32,
+    pub socket: bool,
+    pub long_pads: bool,
+    pub pin_in_paste: Option<PinInPaste>,
+}
+
+impl Dip {
+    /// A narrow DIP with `pins` pins, `None` unless the count is even and at least 4
+    pub fn new(pins: usize) -> Option<Self> {
+        (pins >= 4 && pins.is_multiple_of(2)).then(|| Self {
+            functional_type: FunctionalType::IntegratedCircuit(String::new()),
+            pins,
+            row_spacing: DIP_ROW_SPACINGS[0].0,
+            body_width: DIP_ROW_SPACINGS[0].1,
+            socket: false,
+            long_pads: false,
+            pin_in_paste: None,
+        })
+    }
+
+    pub fn with_functional_type(mut self, functional_type: FunctionalType) -> Self {
+        self.functional_type = functional_type;
+        self
+    }
+
+    /// Rows `row_spacing` apart, with the standard body width for standard spacings and
+    /// the row spacing less one pitch otherwise
+    pub fn with_row_spacing(mut self, row_spacing: f32) -> Self {
+        self.row_spacing = row_spacing;
+        self.body_width = DIP_ROW_SPACINGS
+            .iter()
+            .find(|(spacing, _)| (spacing - row_spacing).abs() < 1e-3)
+            .map_or(row_spacing - DIP_PITCH / 2.0, |&(_, width)| width);
+        self
+    }
+
+    pub fn with_socket(mut self) -> Self {
+        self.socket = true;
+        self
+    }
+
+    pub fn with_long_pads(mut self) -> Self {
+        self.long_pads = true;
+        self
+    }
+
+    /// Paste apertures for pin-in-paste reflow, checked against the DIP drill
+    pub fn with_pin_in_paste(mut self, options: PinInPaste) -> Result<Self, PinInPasteError> {
+        options.validate(DIP_DRILL)?;
+        self.pin_in_paste = Some(options);
+        Ok(self)
+    }
+
+    /// The paste aperture and solder volumes of each pin, empty without pin-in-paste
+    pub fn pin_in_paste_report(&self) -> Vec<PasteAperture> {
+        self.pin_in_paste.as_ref().map_or_else(Vec::new, |options| options.report(&self.through_hole_pads(), DIP_PITCH))
+    }
+
+    /// The pads without paste
+    fn through_hole_pads(&self) -> Vec<PadDescriptor> {
+        let per_row = self.pins / 2;
+        (0..self.pins)
+            .map(|index| {
+                let (x, row) = if index < per_row { (0.0, index) } else { (self.row_spacing, self.pins - 1 - index) };
+                PadDescriptor {
+                    number: (index + 1).to_compact_string(),
+                    pin_function: None,
+                    pad_type: PadType::ThroughHole,
+                    shape: if index == 0 { PadShape::Rect } else { PadShape::Oval },
+                    position: (x, round(row as f32 * DIP_PITCH)),
+                    rotation: None,
+                    size: self.pad_size(),
+                    drill_size: Some(DIP_DRILL),
+                    drill_slot: None,
+                    layers: PadLayers::tht(),
+                    roundrect_ratio: None,
+                    tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+                    padstack: None,
+                    fab_property: None,
+                    solder_mask_margin: None,
+                    routing_hint: None,
+                    uuid: fresh_uuid(),
+                }
+            })
+            .collect()
+    }
+
+    fn pad_size(&self) -> (f32, f32) {
+        (if self.long_pads { LONG_PAD_LENGTH } else { DIP_PAD }, DIP_PAD)
+    }
+
+    /// Body length along the rows
+    fn body_length(&self) -> f32 {
+        (self.pins / 2) as f32 * DIP_PITCH
+    }
+
+    /// The body between the rows, half a pitch past the end pins
+    fn body(&self) -> Rectangle {
+        let inset = (self.row_spacing - self.body_width) / 2.0;
+        Rectangle {
+            min_x: round(inset),
+            min_y: -DIP_PITCH / 2.0,
+            max_x: round(self.row_spacing - inset),
+            max_y: round(self.body_length() - DIP_PITCH / 2.0),
+        }
+    }
+
+    /// Socket outline, around the pad rows
+    fn socket_outline(&self) -> Rectangle {
+        let body = self.body();
+        Rectangle {
+            min_x: -SOCKET_OVERHANG,
+            min_y: round(body.min_y - SOCKET_END_OVERHANG),
+            max_x: round(self.row_spacing + SOCKET_OVERHANG),
+            max_y: round(body.max_y + SOCKET_END_OVERHANG),
+        }
+    }
+
+    /// Body, socket and pads together
+    fn extent(&self) -> Rectangle {
+        self.pad_descriptors().iter().map(|pad| pad.outline_aabb()).fold(self.bounding_box(), |extent, other| Rectangle {
+            min_x: extent.min_x.min(other.min_x),
+            min_y: extent.min_y.min(other.min_y),
+            max_x: extent.max_x.max(other.max_x),
+            max_y: extent.max_y.max(other.max_y),
+        })
+    }
+
+    /// Points of the notch in a horizontal edge at `y`, from left to right, dipping into
+    /// the body
+    fn notch(&self, y: f32) -> Vec<(f32, f32)> {
+        let center = Point::new(self.row_spacing / 2.0, y);
+        let mut points = vec![Point::new(center.x - NOTCH_RADIUS, y)];
+        push_arc(&mut points, center, NOTCH_RADIUS, 180.0, -180.0, ARC_TOLERANCE);
+        points.into_iter().map(|p| (round(p.x), round(p.y))).collect()
+    }
+
+    fn graphic(element_type: GraphicType, layer: LayerType, width: f32) -> GraphicElement {
+        GraphicElement {
+            element_type,
+            layer,
+            side: Side::Front,
+            stroke: Stroke { width, stroke_type: StrokeType::Solid },
+            uuid: fresh_uuid(),
+            role: None,
+        }
+    }
+}
+
+/// Snap computed coordinates to 0.1 µm so the output has no float noise; adding zero
+/// turns -0 into 0
+fn round(value: f32) -> f32 {
+    (value * 1e4).round() / 1e4 + 0.0
+}
+
+impl BoardComposableObject for Dip {
+    fn is_smt(&self) -> bool {
+        false
+    }
+
+    fn is_electrical(&self) -> bool {
+        true
+    }
+
+    fn terminal_count(&self) -> usize {
+        self.pins
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        self.functional_type.clone()
+    }
+
+    /// KiCad's "DIP-14_W7.62mm", with "_Socket" and "_LongPads" in that order
+    fn footprint_name(&self) -> String {
+        let mut name = format!("DIP-{}_W{}mm", self.pins, self.row_spacing);
+        if self.socket {
+            name += "_Socket";
+        }
+        if self.long_pads {
+            name += "_LongPads";
+        }
+        name
+    }
+
+    fn library_name(&self) -> String {
+        "Package_DIP".to_string()
+    }
+
+    /// What the fab layer draws: the body, and the socket around it when there is one
+    fn bounding_box(&self) -> Rectangle {
+        self.computed_bounding_box()
+    }
+
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        let pads = self.through_hole_pads();
+        match &self.pin_in_paste {
+            Some(options) => options.apply(pads, DIP_PITCH),
+            None => pads,
+        }
+    }
+
+    fn description_context(&self) -> Option<DescriptionContext> {
+        let variant: Vec<&str> =
+            [(self.socket, "Socket"), (self.long_pads, "LongPads")].into_iter().filter(|(on, _)| *on).map(|(_, name)| name).collect();
+        let mut context = DescriptionContext::new("dip", self.functional_type.clone(), &format!("DIP-{}", self.pins))
+            .with_density(None)
+            .with_field("pins", self.pins)
+            .with_field("row_spacing", self.row_spacing)
+            .with_field("row_mils", (self.row_spacing / 0.0254).round())
+            .with_field("variant", variant.join(", "));
+        for option in variant {
+            context = context.with_option(option);
+        }
+        Some(context)
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        let courtyard = self.generate_courtyard().bounds;
+        let center = self.row_spacing / 2.0;
+        let text = |text_type, text: String, position, layer: &str| FpText {
+            text_type,
+            text,
+            position,
+            rotation: None,
+            layer: layer.into(),
+            uuid: fresh_uuid(),
+            font: FontSettings { size: (1.0, 1.0), thickness: 0.15 },
+            hidden: false,
+            justify: None,
+            role: None,
+        };
+        let body = self.body();
+        vec![
+            text(FpTextType::Reference, "REF**".to_string(), (center, round(courtyard.min_y - 0.75)), "F.SilkS"),
+            text(FpTextType::Value, self.footprint_name(), (center, round(courtyard.max_y + 0.75)), "F.Fab"),
+            text(FpTextType::User, "${REFERENCE}".to_string(), (center, round((body.min_y + body.max_y) / 2.0)), "F.Fab"),
+        ]
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        let body = self.body();
+
+        // Fab: the body with its pin 1 corner chamfered and the notch in the pin 1 end
+        let mut fab = vec![(body.min_x, round(body.min_y + FAB_CHAMFER)), (round(body.min_x + FAB_CHAMFER), body.min_y)];
+        fab.extend(self.notch(body.min_y));
+        fab.extend([(body.max_x, body.min_y), (body.max_x, body.max_y), (body.min_x, body.max_y)]);
+        let fab = Self::graphic(GraphicType::Polygon { points: fab, fill: None }, LayerType::Fabrication, FAB_LINE_WIDTH);
+        let mut elements = vec![fab.with_role(ElementRole::FabOutline(None))];
+
+        // Silkscreen: between the pad rows, just outside the body at the ends
+        let inset = round(self.pad_size().0 / 2.0 + SILK_PAD_CLEARANCE + SILK_LINE_WIDTH / 2.0);
+        let (top, bottom) = (round(body.min_y - SILK_LINE_WIDTH / 2.0), round(body.max_y + SILK_LINE_WIDTH / 2.0));
+        let mut silk = vec![(inset, top)];
+        silk.extend(self.notch(top));
+        silk.extend([(round(self.row_spacing - inset), top), (round(self.row_spacing - inset), bottom), (inset, bottom)]);
+        let silk = Self::graphic(GraphicType::Polygon { points: silk, fill: None }, LayerType::SilkScreen, SILK_LINE_WIDTH);
+        elements.push(silk.with_role(ElementRole::SilkOutline(None)));
+
+        if self.socket {
+            let socket = self.socket_outline();
+            let grow = SILK_LINE_WIDTH / 2.0;
+            let silk = Rectangle {
+                min_x: round(socket.min_x - grow),
+                min_y: round(socket.min_y - grow),
+                max_x: round(socket.max_x + grow),
+                max_y: round(socket.max_y + grow),
+            };
+            elements.push(Self::graphic(GraphicType::Rectangle { bounds: socket }, LayerType::Fabrication, FAB_LINE_WIDTH));
+            elements.push(Self::graphic(GraphicType::Rectangle { bounds: silk }, LayerType::SilkScreen, SILK_LINE_WIDTH));
+        }
+        elements
+    }
+
+    /// Long pads share the package's model
+    fn model_3d(&self) -> Option<Model3D> {
+        let name = format!("DIP-{}_W{}mm{}", self.pins, self.row_spacing, if self.socket { "_Socket" } else { "" });
+        Some(Model3D {
+            path: format!("${{KICAD9_3DMODEL_DIR}}/{}.3dshapes/{}.wrl", self.library_name(), name),
+            offset: (0.0, 0.0, 0.0),
+            scale: (1.0, 1.0, 1.0),
+            rotation: (0.0, 0.0, 0.0),
+        })
+    }
+
+    /// Pin 1 stays at the origin, as in KiCad's library
+    fn anchor(&self) -> Anchor {
+        Anchor::Pin1
+    }
+
+    /// Courtyard around body, pads and socket, rounded out to `COURTYARD_GRID`
+    fn generate_courtyard(&self) -> Courtyard {
+        Courtyard::new(self.extent(), self.courtyard_margin()).rounded_out(COURTYARD_GRID)
+    }
+}
@@ -0,0 +1,432 @@
+//! TO-252 (DPAK) and TO-263 (D2PAK) power package footprints
+//!
+//! These packages lie with their leads on one side and a large metal tab on the other,
+//! soldered down for heat. Footprints follow KiCad's Package_TO_SOT_SMD library: leads to
+//! the left numbered from the top, the tab pad to the right, the package centered on the
+//! origin from lead tips to tab end. Outlines and land patterns come from
+//! `DPAK_OUTLINES` and lead arrangements from `DPAK_VARIANTS`.
+//!
+//! The tab is electrically one of the pins, usually a MOSFET's drain or a regulator's
+//! ground. Two lead packages have the middle lead cut off and number the tab 2 in its
+//! place. Others either give the tab the middle lead's number, KiCad's "_TabPin2" for
+//! three leads, or the number after the last lead, "_TabPin4". A tab sharing a lead's
+//! number is the same terminal, exported as two pads with one number.
+//!
+//! The tab pad leaves paste out and gets a grid of paste windows (see `paste_windows`)
+//! instead, since a full print would float the part. Thermal vias (see `thermal_vias`)
+//! are optional. The courtyard is the IPC-7351 nominal margin around body and pads, rounded
+//! out to `COURTYARD_GRID`.
+
+use crate::board_interface::{
+    BoardComposableObject, FontSettings, FpText, FpTextType, GraphicElement, GraphicType, Model3D, PadDescriptor,
+    PadShape, PadType, Rectangle, Stroke, StrokeType, TentingSettings, TentingType,
+};
+use crate::courtyard::Courtyard;
+use crate::describe::{Density, DescriptionContext};
+use crate::fab_profile::FabProfile;
+use crate::functional_types::FunctionalType;
+use crate::ids::fresh_uuid;
+use crate::layer_type::{LayerName, LayerType, PadLayers, Side};
+use crate::pad_groups::PadGrouping;
+use crate::paste_windows::{layout_paste_windows, PasteWindowOptions};
+use crate::roles::ElementRole;
+use crate::thermal_vias::{layout_thermal_vias, ThermalViaError, ThermalViaOptions};
+
+/// Body, tab and land pattern of a package family, along X from the leads to the tab
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DpakOutline {
+    pub package: &'static str,
+    /// Lead tips to the end of the tab
+    pub overall_length: f32,
+    pub body_length: f32,
+    pub body_width: f32,
+    /// How far the tab sticks out of the body
+    pub tab_protrusion: f32,
+    pub tab_width: f32,
+    /// Lead pad length along X and its center
+    pub lead_pad_length: f32,
+    pub lead_pad_x: f32,
+    /// Tab pad size and its center along X
+    pub tab_pad: (f32, f32),
+    pub tab_pad_x: f32,
+}
+
+pub const DPAK_OUTLINES: &[DpakOutline] = &[
+    DpakOutline {
+        package: "TO-252",
+        overall_length: 9.9,
+        body_length: 6.1,
+        body_width: 6.5,
+        tab_protrusion: 1.0,
+        tab_width: 5.4,
+        lead_pad_length: 2.2,
+        lead_pad_x: -4.2,
+        tab_pad: (6.4, 5.8),
+        tab_pad_x: 2.1,
+    },
+    DpakOutline {
+        package: "TO-263",
+        overall_length: 15.25,
+        body_length: 9.15,
+        body_width: 10.16,
+        tab_protrusion: 1.5,
+        tab_width: 8.0,
+        lead_pad_length: 4.55,
+        lead_pad_x: -5.775,
+        tab_pad: (9.4, 10.8),
+        tab_pad_x: 3.35,
+    },
+];
+
+/// Lead arrangement of one package variant
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DpakVariant {
+    pub package: &'static str,
+    pub leads: usize,
+    pub pitch: f32,
+    pub lead_width: f32,
+    /// Lead pad size along Y
+    pub lead_pad_width: f32,
+}
+
+pub const DPAK_VARIANTS: &[DpakVariant] = &[
+    DpakVariant { package: "TO-252", leads: 2, pitch: 2.28, lead_width: 0.8, lead_pad_width: 1.2 },
+    DpakVariant { package: "TO-252", leads: 3, pitch: 2.28, lead_width: 0.8, lead_pad_width: 1.2 },
+    DpakVariant { package: "TO-263", leads: 2, pitch: 2.54, lead_width: 0.8, lead_pad_width: 1.1 },
+    DpakVariant { package: "TO-263", leads: 3, pitch: 2.54, lead_width: 0.8, lead_pad_width: 1.1 },
+    DpakVariant { package: "TO-263", leads: 5, pitch: 1.7, lead_width: 0.8, lead_pad_width: 1.1 },
+    DpakVariant { package: "TO-263", leads: 7, pitch: 1.27, lead_width: 0.6, lead_pad_width: 0.8 },
+];
+
+/// Courtyard corners are rounded out to this grid, as IPC-7351 rounds them
+pub const COURTYARD_GRID: f32 = 0.05;
+
+/// Largest corner radius of the roundrect pads, as in KiCad's library
+const MAX_CORNER_RADIUS: f32 = 0.25;
+const FAB_LINE_WIDTH: f32 = 0.1;
+const SILK_LINE_WIDTH: f32 = 0.12;
+/// Clearance from silkscreen line edges to pad copper
+const SILK_PAD_CLEARANCE: f32 = 0.2;
+/// Size of the fab outline's pin 1 chamfer
+const FAB_CHAMFER: f32 = 1.0;
+
+/// Which number the tab pad gets, see the module documentation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TabNumber {
+    /// The middle lead's number, as a MOSFET's drain
+    #[default]
+    Center,
+    /// The number after the last lead
+    Next,
+}
+
+/// TO-252 or TO-263 footprint, see the module documentation
+#[derive(Debug, Clone)]
+pub struct Dpak {
+    pub functional_type: FunctionalType,
+    pub outline: DpakOutline,
+    pub variant: DpakVariant,
+    pub tab_number: TabNumber,
+    /// Vias in the tab and the profile they were checked against
+    pub thermal_vias: Option<(ThermalViaOptions, FabProfile)>,
+}
+
+impl Dpak {
+    /// A package from `DPAK_VARIANTS`, e.g. ("TO-263", 5)
+    pub fn standard(package: &str, leads: usize) -> Option<Self> {
+        let variant = *DPAK_VARIANTS.iter().find(|variant| variant.package == package && variant.leads == leads)?;
+        let outline = *DPAK_OUTLINES.iter().find(|outline| outline.package == package)?;
+        Some(Self {
+            functional_type: FunctionalType::IntegratedCircuit(String::new()),
+            outline,
+            variant,
+            tab_number: TabNumber::default(),
+            thermal_vias: None,
+        })
+    }
+
+    pub fn with_functional_type(mut self, functional_type: FunctionalType) -> Self {
+        self.functional_type = functional_type;
+        self
+    }
+
+    /// Ignored by two lead packages, whose tab is always 2
+    pub fn with_tab_number(mut self, tab_number: TabNumber) -> Self {
+        self.tab_number = tab_number;
+        self
+    }
+
+    /// Thermal vias in the tab pad, failing when `profile` can't make them or they don't
+    /// fit
+    pub fn with_thermal_vias(mut self, options: ThermalViaOptions, profile: FabProfile) -> Result<Self, ThermalViaError> {
+        layout_thermal_vias(&self.tab_pad(), &options, &profile)?;
+        self.thermal_vias = Some((options, profile));
+        Ok(self)
+    }
+
+    /// Lead positions along the package side, with the middle one of two lead packages
+    /// left out so the rest keep their numbers
+    fn lead_positions(&self) -> Vec<(String, f32)> {
+        let positions = if self.variant.leads == 2 { 3 } else { self.variant.leads };
+        let first = (positions - 1) as f32 * self.variant.pitch / -2.0;
+        (0..positions)
+            .filter(|&index| self.variant.leads != 2 || index != 1)
+            .map(|index| ((index + 1).to_string(), round(first + index as f32 * self.variant.pitch)))
+            .collect()
+    }
+
+    /// The tab pad's number, see `TabNumber`
+    pub fn tab_pin(&self) -> String {
+        match (self.variant.leads, self.tab_number) {
+            (2, _) => "2".to_string(),
+            (leads, TabNumber::Center) => (leads / 2 + 1).to_string(),
+            (leads, TabNumber::Next) => (leads + 1).to_string(),
+        }
+    }
+
+    fn roundrect_ratio(size: (f32, f32)) -> f32 {
+        round((MAX_CORNER_RADIUS / size.0.min(size.1)).min(0.25))
+    }
+
+    fn smd_pad(number: &str, position: (f32, f32), size: (f32, f32), layers: Vec<LayerName>) -> PadDescriptor {
+        PadDescriptor {
+            number: number.into(),
+            pin_function: None,
+            pad_type: PadType::SMD,
+            shape: PadShape::RoundRect,
+            position,
+            rotation: None,
+            size,
+            drill_size: None,
+            drill_slot: None,
+            layers,
+            roundrect_ratio: Some(Self::roundrect_ratio(size)),
+            tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+            padstack: None,
+            fab_property: None,
+            solder_mask_margin: None,
+            routing_hint: None,
+            uuid: fresh_uuid(),
+        }
+    }
+
+    /// The tab pad with its paste, before paste windows replace that
+    fn tab_pad(&self) -> PadDescriptor {
+        Self::smd_pad(&self.tab_pin(), (self.outline.tab_pad_x, 0.0), self.outline.tab_pad, PadLayers::smd_front())
+    }
+
+    /// Lead tips, body and tab along X
+    fn body_span(&self) -> (f32, f32, f32) {
+        let tab_end = self.outline.overall_length / 2.0;
+        let body_right = tab_end - self.outline.tab_protrusion;
+        (round(-tab_end), round(body_right - self.outline.body_length), round(body_right))
+    }
+
+    /// Package and pads together
+    fn extent(&self) -> Rectangle {
+        self.pad_descriptors().iter().map(|pad| pad.outline_aabb()).fold(self.bounding_box(), |extent, pad| Rectangle {
+            min_x: extent.min_x.min(pad.min_x),
+            min_y: extent.min_y.min(pad.min_y),
+            max_x: extent.max_x.max(pad.max_x),
+            max_y: extent.max_y.max(pad.max_y),
+        })
+    }
+
+    /// Body and tab as one outline, chamfered at the pin 1 corner
+    fn fab_outline(&self) -> Vec<(f32, f32)> {
+        let (_, left, right) = self.body_span();
+        let (half_body, half_tab) = (self.outline.body_width / 2.0, self.outline.tab_width / 2.0);
+        let tab_end = round(right + self.outline.tab_protrusion);
+        vec![
+            (left, round(-half_body + FAB_CHAMFER)),
+            (round(left + FAB_CHAMFER), -half_body),
+            (right, -half_body),
+            (right, -half_tab),
+            (tab_end, -half_tab),
+            (tab_end, half_tab),
+            (right, half_tab),
+            (right, half_body),
+            (left, half_body),
+        ]
+    }
+
+    fn graphic(element_type: GraphicType, layer: LayerType, width: f32) -> GraphicElement {
+        GraphicElement {
+            element_type,
+            layer,
+            side: Side::Front,
+            stroke: Stroke { width, stroke_type: StrokeType::Solid },
+            uuid: fresh_uuid(),
+            role: None,
+        }
+    }
+}
+
+/// Snap computed coordinates to 0.1 µm so the output has no float noise; adding zero
+/// turns -0 into 0
+fn round(value: f32) -> f32 {
+    (value * 1e4).round() / 1e4 + 0.0
+}
+
+fn mm(value: f32) -> String {
+    format!("{}", (value * 100.0).round() / 100.0)
+}
+
+impl BoardComposableObject for Dpak {
+    fn is_smt(&self) -> bool {
+        true
+    }
+
+    fn is_electrical(&self) -> bool {
+        true
+    }
+
+    /// Leads and tab, counting a tab that shares a lead's number once
+    fn terminal_count(&self) -> usize {
+        let leads = self.lead_positions();
+        leads.len() + usize::from(!leads.iter().any(|(number, _)| *number == self.tab_pin()))
+    }
+
+    /// A tab sharing a lead's number is that lead's terminal, joined to it on the board
+    fn pad_grouping(&self) -> PadGrouping {
+        let tab = self.tab_pin();
+        if self.lead_positions().iter().any(|(number, _)| *number == tab) {
+            PadGrouping::new().with_terminal(&tab)
+        } else {
+            PadGrouping::new()
+        }
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        self.functional_type.clone()
+    }
+
+    /// KiCad's "TO-252-2" and "TO-263-5_TabPin3", with "_ThermalVias" when there are vias
+    fn footprint_name(&self) -> String {
+        let mut name = format!("{}-{}", self.outline.package, self.variant.leads);
+        if self.variant.leads != 2 {
+            name += &format!("_TabPin{}", self.tab_pin());
+        }
+        if self.thermal_vias.is_some() {
+            name += "_ThermalVias";
+        }
+        name
+    }
+
+    fn library_name(&self) -> String {
+        "Package_TO_SOT_SMD".to_string()
+    }
+
+    /// The whole package from lead tips to tab end, across the body
+    fn bounding_box(&self) -> Rectangle {
+        let half = self.outline.overall_length / 2.0;
+        let width = self.outline.body_width / 2.0;
+        Rectangle { min_x: round(-half), min_y: -width, max_x: round(half), max_y: width }
+    }
+
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        let size = (self.outline.lead_pad_length, self.variant.lead_pad_width);
+        let mut pads: Vec<PadDescriptor> = self
+            .lead_positions()
+            .into_iter()
+            .map(|(number, y)| Self::smd_pad(&number, (self.outline.lead_pad_x, y), size, PadLayers::smd_front()))
+            .collect();
+
+        let mut tab = self.tab_pad();
+        let windows = layout_paste_windows(&tab, &PasteWindowOptions::default(), &FabProfile::default());
+        if windows.is_ok() {
+            tab.layers.retain(|layer| layer != "F.Paste");
+        }
+        let vias = match &self.thermal_vias {
+            Some((options, profile)) => layout_thermal_vias(&tab, options, profile).unwrap_or_default(),
+            None => Vec::new(),
+        };
+        pads.push(tab);
+        pads.extend(windows.map(|windows| windows.pads).unwrap_or_default());
+        pads.extend(vias);
+        pads
+    }
+
+    fn description_context(&self) -> Option<DescriptionContext> {
+        let vias = match &self.thermal_vias {
+            Some((options, profile)) => layout_thermal_vias(&self.tab_pad(), options, profile).map_or(0, |vias| vias.len()),
+            None => 0,
+        };
+        Some(
+            DescriptionContext::new("dpak", self.functional_type.clone(), self.outline.package)
+                .with_density(Some(Density::Nominal))
+                .with_option(&format!("TabPin{}", self.tab_pin()))
+                .with_field("leads", self.variant.leads)
+                .with_field("pitch", mm(self.variant.pitch))
+                .with_field("tab_pin", self.tab_pin())
+                .with_field("body_length", mm(self.outline.body_length))
+                .with_field("body_width", mm(self.outline.body_width))
+                .with_field("thermal_vias", if vias > 0 { vias.to_string() } else { String::new() }),
+        )
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        let courtyard = self.generate_courtyard().bounds;
+        let text = |text_type, text: String, position, layer: &str, size: f32| FpText {
+            text_type,
+            text,
+            position,
+            rotation: None,
+            layer: layer.into(),
+            uuid: fresh_uuid(),
+            font: FontSettings { size: (size, size), thickness: size * 0.15 },
+            hidden: false,
+            justify: None,
+            role: None,
+        };
+        let (_, left, right) = self.body_span();
+        vec![
+            text(FpTextType::Reference, "REF**".to_string(), (0.0, round(courtyard.min_y - 0.75)), "F.SilkS", 1.0),
+            text(FpTextType::Value, self.footprint_name(), (0.0, round(courtyard.max_y + 0.75)), "F.Fab", 1.0),
+            text(FpTextType::User, "${REFERENCE}".to_string(), (round((left + right) / 2.0), 0.0), "F.Fab", 1.0),
+        ]
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        let (tips, left, right) = self.body_span();
+        let fab = Self::graphic(GraphicType::Polygon { points: self.fab_outline(), fill: None }, LayerType::Fabrication, FAB_LINE_WIDTH);
+        let mut elements = vec![fab.with_role(ElementRole::FabOutline(None))];
+        for (_, y) in self.lead_positions() {
+            let half = self.variant.lead_width / 2.0;
+            let bounds = Rectangle { min_x: tips, min_y: round(y - half), max_x: left, max_y: round(y + half) };
+            elements.push(Self::graphic(GraphicType::Rectangle { bounds }, LayerType::Fabrication, FAB_LINE_WIDTH));
+        }
+
+        // Silkscreen along both long sides of the body, stopping short of the tab pad when
+        // it is wider than the body; the top line reaches out past pad 1 to mark it
+        let y = round(self.outline.body_width / 2.0 + SILK_LINE_WIDTH / 2.0 + 0.05);
+        let tab = self.tab_pad().outline_aabb();
+        let end = if tab.max_y + SILK_PAD_CLEARANCE + SILK_LINE_WIDTH / 2.0 > y {
+            right.min(round(tab.min_x - SILK_PAD_CLEARANCE - SILK_LINE_WIDTH / 2.0))
+        } else {
+            right
+        };
+        let pin1 = round(self.outline.lead_pad_x - self.outline.lead_pad_length / 2.0);
+        for (start, y) in [(pin1, -y), (left, y)] {
+            let line = GraphicType::Line { start: (start, y), end: (end, y) };
+            elements.push(Self::graphic(line, LayerType::SilkScreen, SILK_LINE_WIDTH));
+        }
+        elements
+    }
+
+    fn model_3d(&self) -> Option<Model3D> {
+        let name = format!("{}-{}", self.outline.package, self.variant.leads);
+        let name = if self.variant.leads == 2 { name } else { format!("{}_TabPin{}", name, self.tab_pin()) };
+        Some(Model3D {
+            path: format!("${{KICAD9_3DMODEL_DIR}}/{}.3dshapes/{}.wrl", self.library_name(), name),
+            offset: (0.0, 0.0, 0.0),
+            scale: (1.0, 1.0, 1.0),
+            rotation: (0.0, 0.0, 0.0),
+        })
+    }
+
+    /// Courtyard around the pads as well as the package, rounded out to `COURTYARD_GRID`
+    fn generate_courtyard(&self) -> Courtyard {
+        Courtyard::new(self.extent(), self.courtyard_margin()).rounded_out(COURTYARD_GRID)
+    }
+}
@@ -0,0 +1,253 @@
+//! Edge mount connectors
+//!
+//! An edge launch SMA straddles the board edge: the center pin lands on a front pad and
+//! the ground legs clamp the board from both sides, so one footprint has copper on F.Cu
+//! and B.Cu. The board edge runs along the X axis at y = 0 with the board towards +Y; the
+//! connector body overhangs the edge towards -Y. Back pads carry their own B.Fab and
+//! B.SilkS outlines, and the default courtyards add a B.CrtYd around them.
+//!
+//! `with_mating_face`, `with_keying_mark` and `with_insertion_arrow` add orientation marks
+//! for assembly drawings around the part of the body past the edge (see
+//! `connector_marks`). The mating face is `Edge::Top`, off the board.
+
+use crate::board_interface::{
+    BoardComposableObject, FontSettings, FpText, FpTextType, GraphicElement, GraphicType, Model3D, PadDescriptor,
+    PadShape, PadType, Rectangle, Stroke, StrokeType, TentingSettings, TentingType,
+};
+use crate::connector_marks::ConnectorMarks;
+use crate::functional_types::FunctionalType;
+use crate::ids::fresh_uuid;
+use crate::layer_type::{LayerName, LayerType, PadLayers, Side};
+use crate::pad_groups::PadGrouping;
+use crate::roles::Edge;
+
+const FAB_LINE_WIDTH: f32 = 0.1;
+const SILK_LINE_WIDTH: f32 = 0.12;
+/// Gap between silkscreen and pad copper
+const SILK_PAD_CLEARANCE: f32 = 0.2;
+
+/// Edge launch SMA jack for a board of `board_thickness`; see the module documentation
+#[derive(Debug, Clone)]
+pub struct EdgeMountSma {
+    /// Board thickness the ground legs clamp, in millimeters
+    pub board_thickness: f32,
+    /// Center pin pad, width along the edge by length into the board
+    pub signal_pad: (f32, f32),
+    /// Each ground leg pad, on both sides of the board
+    pub ground_pad: (f32, f32),
+    /// Distance along the edge from the center pin to each ground leg
+    pub ground_offset: f32,
+    /// Width of the square flange along the edge
+    pub flange_width: f32,
+    /// How far the body sticks out past the board edge
+    pub overhang: f32,
+    /// Assembly drawing marks, none by default
+    pub marks: ConnectorMarks,
+}
+
+impl EdgeMountSma {
+    pub fn new(board_thickness: f32) -> Self {
+        Self {
+            board_thickness,
+            signal_pad: (1.0, 4.0),
+            ground_pad: (1.5, 4.0),
+            ground_offset: 2.725,
+            flange_width: 6.35,
+            overhang: 9.5,
+            marks: ConnectorMarks::new(),
+        }
+    }
+
+    pub fn with_marks(mut self, marks: ConnectorMarks) -> Self {
+        self.marks = marks;
+        self
+    }
+
+    /// Arrow out of the jack, past the board edge
+    pub fn with_mating_face(mut self) -> Self {
+        self.marks = self.marks.with_mating_face(Edge::Top);
+        self
+    }
+
+    pub fn with_keying_mark(mut self, position: (f32, f32)) -> Self {
+        self.marks = self.marks.with_keying_mark(position);
+        self
+    }
+
+    pub fn with_insertion_arrow(mut self) -> Self {
+        self.marks = self.marks.with_insertion_arrow();
+        self
+    }
+
+    /// Flange and body past the board edge
+    fn body(&self) -> Rectangle {
+        let half_flange = self.flange_width / 2.0;
+        Rectangle { min_x: -half_flange, min_y: -self.overhang, max_x: half_flange, max_y: 0.0 }
+    }
+
+    fn pad(number: &str, position: (f32, f32), size: (f32, f32), layers: Vec<LayerName>) -> PadDescriptor {
+        PadDescriptor {
+            number: number.into(),
+            pin_function: None,
+            pad_type: PadType::SMD,
+            shape: PadShape::Rect,
+            position,
+            rotation: None,
+            size,
+            drill_size: None,
+            drill_slot: None,
+            layers,
+            roundrect_ratio: None,
+            tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+            padstack: None,
+            fab_property: None,
+            solder_mask_margin: None,
+            routing_hint: None,
+            uuid: fresh_uuid(),
+        }
+    }
+
+    fn element(element_type: GraphicType, layer: LayerType, side: Side, width: f32) -> GraphicElement {
+        GraphicElement {
+            element_type,
+            layer,
+            side,
+            stroke: Stroke { width, stroke_type: StrokeType::Solid },
+            uuid: fresh_uuid(),
+            role: None,
+        }
+    }
+
+    /// A closed outline as four lines, which every exporter writes
+    fn outline(bounds: &Rectangle, layer: LayerType, side: Side, width: f32) -> Vec<GraphicElement> {
+        let corners = [
+            (bounds.min_x, bounds.min_y),
+            (bounds.max_x, bounds.min_y),
+            (bounds.max_x, bounds.max_y),
+            (bounds.min_x, bounds.max_y),
+        ];
+        (0..4)
+            .map(|i| Self::element(GraphicType::Line { start: corners[i], end: corners[(i + 1) % 4] }, layer, side, width))
+            .collect()
+    }
+
+    /// Outer edge of the ground legs along X
+    fn legs_half_width(&self) -> f32 {
+        self.ground_offset + self.ground_pad.0 / 2.0
+    }
+
+    fn pads_length(&self) -> f32 {
+        self.signal_pad.1.max(self.ground_pad.1)
+    }
+}
+
+impl BoardComposableObject for EdgeMountSma {
+    fn is_smt(&self) -> bool {
+        true
+    }
+
+    fn is_electrical(&self) -> bool {
+        true
+    }
+
+    fn terminal_count(&self) -> usize {
+        2
+    }
+
+    /// The four ground legs are one terminal
+    fn pad_grouping(&self) -> PadGrouping {
+        PadGrouping::new().with_terminal("2")
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        FunctionalType::Connector("SMA".to_string())
+    }
+
+    fn footprint_name(&self) -> String {
+        format!("SMA_EdgeMount_Board{}mm", self.board_thickness)
+    }
+
+    fn library_name(&self) -> String {
+        "Connector_Coaxial".to_string()
+    }
+
+    /// Body past the edge and legs on the board
+    fn bounding_box(&self) -> Rectangle {
+        let half = (self.flange_width / 2.0).max(self.legs_half_width());
+        Rectangle { min_x: -half, min_y: -self.overhang, max_x: half, max_y: self.pads_length() }
+    }
+
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        let signal_y = self.signal_pad.1 / 2.0;
+        let ground_y = self.ground_pad.1 / 2.0;
+        let mut pads = vec![Self::pad("1", (0.0, signal_y), self.signal_pad, PadLayers::smd_front())];
+        for layers in [PadLayers::smd_front(), PadLayers::smd_back()] {
+            for x in [-self.ground_offset, self.ground_offset] {
+                pads.push(Self::pad("2", (x, ground_y), self.ground_pad, layers.clone()));
+            }
+        }
+        pads
+    }
+
+    fn description(&self) -> Option<String> {
+        Some(format!(
+            "SMA edge mount jack for {}mm boards, ground legs on both sides of the board",
+            self.board_thickness
+        ))
+    }
+
+    fn tags(&self) -> Option<String> {
+        Some("SMA edge mount coaxial RF connector".to_string())
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        let text_y = self.pads_length() + self.courtyard_margin() + 1.0;
+        let text = |text_type, text: String, position, layer: &str| FpText {
+            text_type,
+            text,
+            position,
+            rotation: None,
+            layer: layer.into(),
+            uuid: fresh_uuid(),
+            font: FontSettings { size: (1.0, 1.0), thickness: 0.15 },
+            hidden: false,
+            justify: None,
+            role: None,
+        };
+        let mut texts = vec![
+            text(FpTextType::Reference, "REF**".to_string(), (0.0, text_y), "F.SilkS"),
+            text(FpTextType::Value, self.footprint_name(), (0.0, text_y + 1.5), "F.Fab"),
+            text(FpTextType::User, "${REFERENCE}".to_string(), (0.0, -self.overhang / 2.0), "F.Fab"),
+        ];
+        texts.extend(self.marks.fp_text_elements(&self.body()));
+        texts
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        let body = self.body();
+        let mut elements = Self::outline(&body, LayerType::Fabrication, Side::Front, FAB_LINE_WIDTH);
+
+        // The legs on both sides, on fab, with silkscreen lines just outside them
+        let half_legs = self.legs_half_width();
+        let legs = Rectangle { min_x: -half_legs, min_y: 0.0, max_x: half_legs, max_y: self.ground_pad.1 };
+        let silk_x = half_legs + SILK_PAD_CLEARANCE + SILK_LINE_WIDTH / 2.0;
+        for side in [Side::Front, Side::Back] {
+            elements.extend(Self::outline(&legs, LayerType::Fabrication, side, FAB_LINE_WIDTH));
+            for x in [-silk_x, silk_x] {
+                let line = GraphicType::Line { start: (x, 0.0), end: (x, self.ground_pad.1) };
+                elements.push(Self::element(line, LayerType::SilkScreen, side, SILK_LINE_WIDTH));
+            }
+        }
+        elements.extend(self.marks.graphic_elements(&body));
+        elements
+    }
+
+    fn model_3d(&self) -> Option<Model3D> {
+        Some(Model3D {
+            path: format!("${{KICAD9_3DMODEL_DIR}}/{}.3dshapes/{}.wrl", self.library_name(), self.footprint_name()),
+            offset: (0.0, 0.0, 0.0),
+            scale: (1.0, 1.0, 1.0),
+            rotation: (0.0, 0.0, 0.0),
+        })
+    }
+}
@@ -0,0 +1,173 @@
+//! Exposed copper: a copper shape and the solder mask opening over it
+//!
+//! Logos in bare copper, ENIG touch pads and plating windows along a board edge are
+//! copper the mask must leave open, drawn as graphics rather than pads. Graphics on a mask
+//! layer are openings, as in KiCad: the mask is cleared wherever they are drawn. The
+//! exporters write them into the mask Gerbers (see `mask_export`) and outline them as
+//! openings in SVG.
+//!
+//! `ExposedCopper` pairs a filled copper polygon with a filled polygon on the mask layer
+//! of the same side, grown by `mask_margin` so a misregistered mask doesn't creep onto the
+//! copper. A negative margin leaves mask over the copper's edge instead. Rounded
+//! rectangles grow into rounded rectangles with the corner radius grown by the margin, a
+//! true offset; other outlines are offset at mitered corners, as custom pads are.
+//!
+//! The pair goes into a footprint's `graphic_elements`, or onto a board with
+//! `Board::add_graphic`.
+
+use crate::board_interface::{GraphicElement, GraphicType, Rectangle};
+use crate::geometry::{push_arc, Point};
+use crate::hatch::FillStyle;
+use crate::layer_type::{LayerType, Side};
+use crate::pad_geometry::offset_polygon;
+
+/// Mask opening past the copper on every side (mm)
+pub const DEFAULT_EXPOSED_MASK_MARGIN: f32 = 0.05;
+
+/// Largest deviation of rounded corners from the true arc (mm)
+pub const EXPOSED_ARC_TOLERANCE: f32 = 0.005;
+
+/// Outline of the exposed copper, see the module documentation
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExposedShape {
+    RoundedRectangle { bounds: Rectangle, radius: f32 },
+    /// Closed outline, last point not repeated
+    Polygon(Vec<(f32, f32)>),
+}
+
+/// Copper and its mask opening, see the module documentation
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExposedCopper {
+    pub shape: ExposedShape,
+    pub mask_margin: f32,
+    pub side: Side,
+}
+
+impl ExposedCopper {
+    /// A rectangle with corners rounded by `radius`, 0 for square corners
+    pub fn rounded_rectangle(bounds: Rectangle, radius: f32) -> Self {
+        Self::new(ExposedShape::RoundedRectangle { bounds, radius })
+    }
+
+    pub fn polygon(points: Vec<(f32, f32)>) -> Self {
+        Self::new(ExposedShape::Polygon(points))
+    }
+
+    fn new(shape: ExposedShape) -> Self {
+        Self { shape, mask_margin: DEFAULT_EXPOSED_MASK_MARGIN, side: Side::Front }
+    }
+
+    pub fn with_mask_margin(mut self, margin: f32) -> Self {
+        self.mask_margin = margin;
+        self
+    }
+
+    pub fn with_side(mut self, side: Side) -> Self {
+        self.side = side;
+        self
+    }
+
+    pub fn copper_outline(&self) -> Vec<(f32, f32)> {
+        self.outline(0.0)
+    }
+
+    /// The copper outline grown by `mask_margin`
+    pub fn mask_outline(&self) -> Vec<(f32, f32)> {
+        self.outline(self.mask_margin)
+    }
+
+    fn outline(&self, grow: f32) -> Vec<(f32, f32)> {
+        match &self.shape {
+            ExposedShape::RoundedRectangle { bounds, radius } => {
+                let grown = Rectangle {
+                    min_x: bounds.min_x - grow,
+                    min_y: bounds.min_y - grow,
+                    max_x: bounds.max_x + grow,
+                    max_y: bounds.max_y + grow,
+                };
+                // A square corner grows square; a negative margin can't shrink the radius below 0
+                let radius = if *radius > 0.0 { (radius + grow).max(0.0) } else { 0.0 };
+                rounded_rectangle(&grown, radius).into_iter().map(|point| (point.x, point.y)).collect()
+            }
+            ExposedShape::Polygon(points) if grow != 0.0 => {
+                let points: Vec<Point> = points.iter().map(|&point| Point::from(point)).collect();
+                offset_polygon(&points, grow).into_iter().map(|point| (point.x, point.y)).collect()
+            }
+            ExposedShape::Polygon(points) => points.clone(),
+        }
+    }
+
+    /// The filled copper polygon and the filled mask opening, both on `side`
+    pub fn graphic_elements(&self) -> Vec<GraphicElement> {
+        [(LayerType::Copper, self.copper_outline()), (LayerType::Mask, self.mask_outline())]
+            .into_iter()
+            .map(|(layer, points)| {
+                GraphicElement::new(GraphicType::Polygon { points, fill: Some(FillStyle::Solid) }, layer, 0.0).with_side(self.side)
+            })
+            .collect()
+    }
+}
+
+/// Outline of `bounds` with its corners rounded by `radius`, clockwise on screen from the
+/// top left corner
+fn rounded_rectangle(bounds: &Rectangle, radius: f32) -> Vec<Point> {
+    let radius = radius.min(bounds.width() / 2.0).min(bounds.height() / 2.0).max(0.0);
+    if radius == 0.0 {
+        return vec![
+            Point::new(bounds.min_x, bounds.min_y),
+            Point::new(bounds.max_x, bounds.min_y),
+            Point::new(bounds.max_x, bounds.max_y),
+            Point::new(bounds.min_x, bounds.max_y),
+        ];
+    }
+    let corners = [
+        (Point::new(bounds.max_x - radius, bounds.min_y + radius), 270.0),
+        (Point::new(bounds.max_x - radius, bounds.max_y - radius), 0.0),
+        (Point::new(bounds.min_x + radius, bounds.max_y - radius), 90.0),
+        (Point::new(bounds.min_x + radius, bounds.min_y + radius), 180.0),
+    ];
+    let mut points = vec![Point::new(bounds.min_x + radius, bounds.min_y)];
+    for (center, start) in corners {
+        push_arc(&mut points, center, radius, start, 90.0, EXPOSED_ARC_TOLERANCE);
+    }
+    // The last arc ends where the outline started
+    points.pop();
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds(points: &[(f32, f32)]) -> Rectangle {
+        let points: Vec<Point> = points.iter().map(|&point| Point::from(point)).collect();
+        Rectangle::enclosing(&points).unwrap()
+    }
+
+    #[test]
+    fn rounded_openings_grow_by_the_margin() {
+        let pad = ExposedCopper::rounded_rectangle(Rectangle { min_x: -2.0, min_y: -1.0, max_x: 2.0, max_y: 1.0 }, 0.5).with_mask_margin(0.1);
+        let (copper, mask) = (pad.copper_outline(), pad.mask_outline());
+        assert_eq!(bounds(&copper), Rectangle { min_x: -2.0, min_y: -1.0, max_x: 2.0, max_y: 1.0 });
+        let grown = bounds(&mask);
+        assert!((grown.min_x + 2.1).abs() < 1e-5 && (grown.max_y - 1.1).abs() < 1e-5, "{:?}", grown);
+        // Every mask corner point lies 0.6 from its corner's center, the grown radius
+        let corner = Point::new(1.5, 0.5);
+        let far: Vec<_> = mask.iter().map(|&point| Point::from(point)).filter(|point| point.x > 1.5 && point.y > 0.5).collect();
+        assert!(far.len() > 2 && far.iter().all(|point| (point.distance_to(&corner) - 0.6).abs() < 1e-4), "{:?}", far);
+        assert!(copper.first() != copper.last());
+    }
+
+    #[test]
+    fn elements_pair_copper_and_mask_on_one_side() {
+        let triangle = vec![(0.0, 0.0), (4.0, 0.0), (2.0, 3.0)];
+        let logo = ExposedCopper::polygon(triangle.clone()).with_side(Side::Back);
+        let elements = logo.graphic_elements();
+        assert_eq!(elements.len(), 2);
+        assert_eq!(elements[0].kicad_layer(), "B.Cu");
+        assert_eq!(elements[1].kicad_layer(), "B.Mask");
+        assert_eq!(elements[0].element_type, GraphicType::Polygon { points: triangle, fill: Some(FillStyle::Solid) });
+        let GraphicType::Polygon { points, .. } = &elements[1].element_type else { panic!() };
+        assert!(bounds(points).width() > 4.0 + 2.0 * DEFAULT_EXPOSED_MASK_MARGIN);
+    }
+}
@@ -0,0 +1,65 @@
+//! Fabrication capabilities
+//!
+//! The smallest features a board house can reliably make. Generators and transformations
+//! that derive geometry (scaling, paste windows) check their output against a profile
+//! instead of silently producing pads that can't be built. The silkscreen minimums are
+//! checked by `FootprintDrc` and applied by `BoardComposableObject::with_silk_minimums`.
+
+/// Minimum feature sizes of a fab, all in millimeters
+#[derive(Debug, Clone, PartialEq)]
+pub struct FabProfile {
+    pub name: String,
+    /// Smallest pad width or height
+    pub min_pad_size: f32,
+    /// Smallest finished drill diameter
+    pub min_drill: f32,
+    /// Smallest copper ring around a plated hole
+    pub min_annular_ring: f32,
+    /// Narrowest stencil web between two paste windows
+    pub min_paste_web: f32,
+    /// Smallest paste window side that still releases paste
+    pub min_paste_aperture: f32,
+    /// Thinnest silkscreen stroke that prints unbroken
+    pub min_silk_line_width: f32,
+    /// Smallest silkscreen text height and stroke thickness that stay legible
+    pub min_silk_text_height: f32,
+    pub min_silk_text_thickness: f32,
+}
+
+impl FabProfile {
+    /// Typical limits of a standard (non-HDI) prototype service
+    pub fn standard() -> Self {
+        Self {
+            name: "Standard".to_string(),
+            min_pad_size: 0.2,
+            min_drill: 0.3,
+            min_annular_ring: 0.13,
+            min_paste_web: 0.2,
+            min_paste_aperture: 0.3,
+            min_silk_line_width: 0.1,
+            min_silk_text_height: 0.6,
+            min_silk_text_thickness: 0.1,
+        }
+    }
+
+    /// Limits of an advanced service with laser drilling
+    pub fn advanced() -> Self {
+        Self {
+            name: "Advanced".to_string(),
+            min_pad_size: 0.1,
+            min_drill: 0.15,
+            min_annular_ring: 0.075,
+            min_paste_web: 0.15,
+            min_paste_aperture: 0.2,
+            min_silk_line_width: 0.075,
+            min_silk_text_height: 0.5,
+            min_silk_text_thickness: 0.075,
+        }
+    }
+}
+
+impl Default for FabProfile {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
@@ -0,0 +1,405 @@
+//! Dog-bone fanout of BGA balls
+//!
+//! `Board::fanout_bga` escapes the balls of a placed BGA the way a test board is usually
+//! routed by hand: each ball gets a via in the middle of the four balls diagonal to it,
+//! joined to the ball by a short track on the mounting side. Vias point away from the
+//! package center, quadrant by quadrant, so no two balls share a via site. Balls on the
+//! center row or column of an odd array have no quadrant and alternate sides ring by ring.
+//!
+//! Balls in the outer rings escape between their neighbours on the surface and are left
+//! alone: the outer ring always, and one more ring for every track that fits between two
+//! balls at the net class width and clearance. The rings inside are given an escape layer,
+//! `rings_per_layer` rings to each inner copper layer from the top down, then the opposite
+//! outer layer once the inner layers run out.
+//!
+//! Vias and tracks take the sizes of the ball's net class unless the options give them.
+//! Everything placed is checked with `routing_violations`, and the vias and tracks of
+//! balls taking part in a violation are taken out again and the balls reported, usually
+//! because the pitch is too fine for the via and its clearance.
+//!
+//! No via is placed where its copper would reach into a pad whose routing hint forbids
+//! vias under it (see `routing_hint`); those balls are reported as refused and left alone.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::board::Board;
+use crate::board_interface::PadType;
+use crate::geometry::{point_in_polygon, point_segment_distance, Point};
+use crate::layer_type::{BoardLayer, LayerType, Side};
+use crate::routing::{RoutingItem, RoutingViolation, Track, Via, ROUTING_ARC_TOLERANCE};
+
+/// Balls closer than this along an axis are on the same row or column
+const GRID_TOLERANCE: f32 = 1e-3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FanoutOptions {
+    /// Outer rings left to escape on the surface; `None` works it out from the pitch, see
+    /// the module documentation
+    pub surface_rings: Option<usize>,
+    /// Via copper diameter and drill, the net class's when `None`
+    pub via_diameter: Option<f32>,
+    pub via_drill: Option<f32>,
+    /// Width of the track from ball to via, the net class's when `None`
+    pub track_width: Option<f32>,
+    /// Rings escaping on each inner layer; 0 counts as 1
+    pub rings_per_layer: usize,
+}
+
+impl Default for FanoutOptions {
+    fn default() -> Self {
+        Self { surface_rings: None, via_diameter: None, via_drill: None, track_width: None, rings_per_layer: 1 }
+    }
+}
+
+impl FanoutOptions {
+    pub fn with_surface_rings(mut self, rings: usize) -> Self {
+        self.surface_rings = Some(rings);
+        self
+    }
+
+    pub fn with_via(mut self, diameter: f32, drill: f32) -> Self {
+        self.via_diameter = Some(diameter);
+        self.via_drill = Some(drill);
+        self
+    }
+
+    pub fn with_track_width(mut self, width: f32) -> Self {
+        self.track_width = Some(width);
+        self
+    }
+
+    pub fn with_rings_per_layer(mut self, rings: usize) -> Self {
+        self.rings_per_layer = rings;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FanoutError {
+    /// No component of this reference is placed
+    UnknownComponent(String),
+    /// The component's SMD pads don't form a grid of at least two rows and two columns
+    NoBallGrid(String),
+}
+
+impl fmt::Display for FanoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FanoutError::UnknownComponent(reference) => write!(f, "no component {} on the board", reference),
+            FanoutError::NoBallGrid(reference) => write!(f, "{} has no grid of SMD balls to fan out", reference),
+        }
+    }
+}
+
+impl std::error::Error for FanoutError {}
+
+/// A ball joined to its via
+#[derive(Debug, Clone, PartialEq)]
+pub struct FannedBall {
+    pub number: String,
+    /// 0 for the outer ring
+    pub ring: usize,
+    /// Index into `Board::vias`
+    pub via: usize,
+    /// Index into `Board::tracks`
+    pub track: usize,
+    /// Copper layer the ball is meant to escape on from its via
+    pub escape_layer: BoardLayer,
+}
+
+/// A ball left without a fanout
+#[derive(Debug, Clone, PartialEq)]
+pub struct FanoutFailure {
+    pub number: String,
+    /// The first violation found with the ball's via and track in place. Track and via
+    /// indices in it are those of the removed attempt, not of the board.
+    pub violation: RoutingViolation,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FanoutReport {
+    pub fanned: Vec<FannedBall>,
+    /// Balls of the outer rings, left to escape on the surface
+    pub surface: Vec<String>,
+    pub failed: Vec<FanoutFailure>,
+    /// Balls whose via would reach into a pad that takes no vias
+    pub refused: Vec<String>,
+    /// Rings escaping on the surface, whether given or worked out
+    pub surface_rings: usize,
+    pub pitch: f32,
+}
+
+impl FanoutReport {
+    /// Whether every ball inside the surface rings was fanned out
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty() && self.refused.is_empty()
+    }
+}
+
+/// A ball of the grid in the footprint's own coordinates
+struct Ball {
+    number: String,
+    position: Point,
+    ring: usize,
+    /// Direction of the via from the ball, each -1 or 1
+    direction: (f32, f32),
+}
+
+/// Whether a via of `radius` at `center` has copper inside `outline`
+fn via_reaches(center: Point, radius: f32, outline: &[Point]) -> bool {
+    point_in_polygon(center, outline)
+        || (0..outline.len()).any(|i| point_segment_distance(center, outline[i], outline[(i + 1) % outline.len()]).0 < radius)
+}
+
+/// Sorted distinct values, merging those within `GRID_TOLERANCE`
+fn distinct(mut values: Vec<f32>) -> Vec<f32> {
+    values.sort_by(f32::total_cmp);
+    values.dedup_by(|a, b| (*a - *b).abs() < GRID_TOLERANCE);
+    values
+}
+
+impl Board {
+    /// Dog-bone fanout of the BGA placed as `reference`, see the module documentation.
+    /// Balls on no net are connected to a net of their own, named as KiCad names them.
+    pub fn fanout_bga(&mut self, reference: &str, options: &FanoutOptions) -> Result<FanoutReport, FanoutError> {
+        let placed = self.component(reference).ok_or_else(|| FanoutError::UnknownComponent(reference.to_string()))?.clone();
+        let descriptors = placed.component.pad_descriptors();
+        let pads: Vec<(String, Point, f32)> = descriptors
+            .iter()
+            .filter(|pad| matches!(pad.pad_type, PadType::SMD) && pad.has_copper() && !pad.number.is_empty())
+            .map(|pad| (pad.number.to_string(), Point::new(pad.position.0, pad.position.1), pad.size.0.max(pad.size.1)))
+            .collect();
+        let columns = distinct(pads.iter().map(|(_, position, _)| position.x).collect());
+        let rows = distinct(pads.iter().map(|(_, position, _)| position.y).collect());
+        if columns.len() < 2 || rows.len() < 2 {
+            return Err(FanoutError::NoBallGrid(reference.to_string()));
+        }
+        let pitch = columns.windows(2).chain(rows.windows(2)).map(|pair| pair[1] - pair[0]).fold(f32::INFINITY, f32::min);
+        let index = |value: f32, axis: &[f32]| ((value - axis[0]) / pitch).round() as usize;
+        let (last_column, last_row) = (index(columns[columns.len() - 1], &columns), index(rows[rows.len() - 1], &rows));
+        let center = Point::new((columns[0] + columns[columns.len() - 1]) / 2.0, (rows[0] + rows[rows.len() - 1]) / 2.0);
+        let balls: Vec<Ball> = pads
+            .iter()
+            .map(|(number, position, _)| {
+                let (column, row) = (index(position.x, &columns), index(position.y, &rows));
+                let ring = column.min(row).min(last_column - column).min(last_row - row);
+                let side = |offset: f32| match offset {
+                    offset if offset.abs() < GRID_TOLERANCE => if ring % 2 == 0 { 1.0 } else { -1.0 },
+                    offset => offset.signum(),
+                };
+                let direction = (side(position.x - center.x), side(position.y - center.y));
+                Ball { number: number.clone(), position: *position, ring, direction }
+            })
+            .collect();
+
+        let net_of = |board: &Board, number: &str| match board.pad_net(reference, number) {
+            Some(net) => net.to_string(),
+            None => format!("Net-({}-Pad{})", reference, number),
+        };
+        let surface_rings = options.surface_rings.unwrap_or_else(|| {
+            // Tracks fitting between two balls of the outer ring, each needing its
+            // clearance on one side and the last one on both
+            let ball = pads.iter().map(|(_, _, size)| *size).fold(0.0, f32::max);
+            let class = balls.first().map(|ball| self.net_class(&net_of(self, &ball.number)).clone()).unwrap_or_default();
+            let width = options.track_width.unwrap_or(class.track_width);
+            let channels = ((pitch - ball - class.clearance) / (width + class.clearance)).floor().max(0.0);
+            1 + channels as usize
+        });
+
+        // Pads the routing hints keep vias out of, in footprint coordinates
+        let no_via: Vec<Vec<Point>> = descriptors
+            .iter()
+            .filter(|pad| pad.routing_hint.as_ref().is_some_and(|hint| hint.no_via_under_pad))
+            .map(|pad| pad.outline_polygon(ROUTING_ARC_TOLERANCE))
+            .collect();
+
+        let copper = self.copper_layers();
+        let (mounting, opposite) = match placed.side {
+            Side::Back => (BoardLayer::back(LayerType::Copper), BoardLayer::front(LayerType::Copper)),
+            _ => (BoardLayer::front(LayerType::Copper), BoardLayer::back(LayerType::Copper)),
+        };
+        let inner = &copper[1..copper.len().saturating_sub(1).max(1)];
+        let rings_per_layer = options.rings_per_layer.max(1);
+        let escape_layer = |ring: usize| {
+            let step = (ring - surface_rings) / rings_per_layer;
+            let layer = match placed.side {
+                Side::Back => inner.iter().rev().nth(step),
+                _ => inner.get(step),
+            };
+            layer.copied().unwrap_or(opposite)
+        };
+
+        let mut report = FanoutReport { surface_rings, pitch, ..FanoutReport::default() };
+        let (first_track, first_via) = (self.tracks().len(), self.vias().len());
+        let mut attempts = Vec::new();
+        for ball in balls {
+            if ball.ring < surface_rings {
+                report.surface.push(ball.number);
+                continue;
+            }
+            let net = net_of(self, &ball.number);
+            let class = self.net_class(&net).clone();
+            let site = ball.position.offset(ball.direction.0 * pitch / 2.0, ball.direction.1 * pitch / 2.0);
+            let diameter = options.via_diameter.unwrap_or(class.via_diameter);
+            if no_via.iter().any(|outline| via_reaches(site, diameter / 2.0, outline)) {
+                report.refused.push(ball.number);
+                continue;
+            }
+            if self.pad_net(reference, &ball.number).is_none() {
+                self.connect_pad(reference, &ball.number, &net);
+            }
+            let (start, end) = (self.board_point(&placed, ball.position), self.board_point(&placed, site));
+            let width = options.track_width.unwrap_or(class.track_width);
+            let drill = options.via_drill.unwrap_or(class.via_drill);
+            attempts.push((ball, Track::new(&net, mounting, start, end, width), Via::new(&net, end, diameter, drill)));
+        }
+
+        // Take out the balls involved in violations until none are left; each pass
+        // removes at least one, so this ends
+        loop {
+            Rc::make_mut(&mut self.routing.tracks).truncate(first_track);
+            Rc::make_mut(&mut self.routing.vias).truncate(first_via);
+            for (_, track, via) in &attempts {
+                self.add_track(track.clone());
+                self.add_via(via.clone());
+            }
+            let ball_of = |item: &RoutingItem| match item {
+                RoutingItem::Track(index) if *index >= first_track => Some(index - first_track),
+                RoutingItem::Via(index) if *index >= first_via => Some(index - first_via),
+                _ => None,
+            };
+            let mut blamed: HashMap<usize, RoutingViolation> = HashMap::new();
+            for violation in self.routing_violations() {
+                let items = match &violation {
+                    RoutingViolation::Clearance { a, b, .. } => vec![a.clone(), b.clone()],
+                    RoutingViolation::TrackWidth { track, .. } => vec![RoutingItem::Track(*track)],
+                    RoutingViolation::ArcWidth { arc, .. } => vec![RoutingItem::Arc(*arc)],
+                    RoutingViolation::ViaDiameter { via, .. }
+                    | RoutingViolation::ViaDrill { via, .. }
+                    | RoutingViolation::ViaSpan { via, .. } => vec![RoutingItem::Via(*via)],
+                    RoutingViolation::Keepout { item, .. } => vec![item.clone()],
+                };
+                for attempt in items.iter().filter_map(ball_of) {
+                    blamed.entry(attempt).or_insert_with(|| violation.clone());
+                }
+            }
+            if blamed.is_empty() {
+                break;
+            }
+            let mut kept = Vec::new();
+            for (attempt, entry) in attempts.into_iter().enumerate() {
+                match blamed.remove(&attempt) {
+                    Some(violation) => report.failed.push(FanoutFailure { number: entry.0.number, violation }),
+                    None => kept.push(entry),
+                }
+            }
+            attempts = kept;
+        }
+
+        for (offset, (ball, _, _)) in attempts.into_iter().enumerate() {
+            let escape_layer = escape_layer(ball.ring);
+            report.fanned.push(FannedBall {
+                number: ball.number,
+                ring: ball.ring,
+                via: first_via + offset,
+                track: first_track + offset,
+                escape_layer,
+            });
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_interface::{BoardComposableObject, FpText, GraphicElement, Model3D, PadDescriptor, PadShape, Rectangle};
+    use crate::functional_types::FunctionalType;
+    use crate::routing::NetClass;
+    use crate::routing_hint::RoutingHint;
+
+    /// A 6x6 grid of 0.4 mm balls at 0.8 mm pitch, each with `hint`
+    struct Grid {
+        hint: Option<RoutingHint>,
+    }
+
+    impl BoardComposableObject for Grid {
+        fn is_smt(&self) -> bool {
+            true
+        }
+
+        fn is_electrical(&self) -> bool {
+            true
+        }
+
+        fn terminal_count(&self) -> usize {
+            36
+        }
+
+        fn functional_type(&self) -> FunctionalType {
+            FunctionalType::IntegratedCircuit("BGA".to_string())
+        }
+
+        fn footprint_name(&self) -> String {
+            "BGA-36_6x6_P0.8mm".to_string()
+        }
+
+        fn library_name(&self) -> String {
+            "Package_BGA".to_string()
+        }
+
+        fn bounding_box(&self) -> Rectangle {
+            Rectangle { min_x: -2.8, min_y: -2.8, max_x: 2.8, max_y: 2.8 }
+        }
+
+        fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+            (0..36)
+                .map(|index| {
+                    let position = ((index % 6) as f32 * 0.8 - 2.0, (index / 6) as f32 * 0.8 - 2.0);
+                    let pad = PadDescriptor::new((index + 1).to_string(), PadType::SMD, PadShape::Circle, position, (0.4, 0.4), ["F.Cu", "F.Paste", "F.Mask"]);
+                    PadDescriptor { routing_hint: self.hint.clone(), ..pad }
+                })
+                .collect()
+        }
+
+        fn fp_text_elements(&self) -> Vec<FpText> {
+            Vec::new()
+        }
+
+        fn graphic_elements(&self) -> Vec<GraphicElement> {
+            Vec::new()
+        }
+
+        fn model_3d(&self) -> Option<Model3D> {
+            None
+        }
+    }
+
+    /// A board with the grid placed as U1; its 0.8 mm vias reach into the diagonal balls
+    fn board_with(hint: Option<RoutingHint>) -> Board {
+        let mut board = Board::new("fanout").with_rectangular_outline(20.0, 20.0);
+        board.set_default_net_class(NetClass::new("BGA", 0.1, 0.1, 0.8, 0.3));
+        board.place("U1", Grid { hint }, (10.0, 10.0), 0.0).unwrap();
+        board
+    }
+
+    #[test]
+    fn no_via_goes_under_a_flagged_ball() {
+        let mut board = board_with(Some(RoutingHint::new().with_no_via_under_pad()));
+        let report = board.fanout_bga("U1", &FanoutOptions::default().with_surface_rings(1)).unwrap();
+        assert_eq!(report.refused.len(), 16, "{:#?}", report);
+        assert!(report.fanned.is_empty() && report.failed.is_empty() && !report.is_complete());
+        assert!(board.vias().is_empty() && board.tracks().is_empty());
+        assert_eq!(board.pad_net("U1", &report.refused[0]), None);
+    }
+
+    #[test]
+    fn unflagged_balls_fail_on_clearance_instead() {
+        let mut board = board_with(None);
+        let report = board.fanout_bga("U1", &FanoutOptions::default().with_surface_rings(1)).unwrap();
+        assert!(report.refused.is_empty());
+        assert_eq!(report.failed.len(), 16, "{:#?}", report);
+    }
+}
@@ -0,0 +1,290 @@
+//! Fixed footprint geometry
+//!
+//! Pads, texts and lines of footprints whose geometry never changes, such as a single
+//! resistor size or a particular connector. `#[derive(BoardComponent)]` builds its
+//! `BoardComposableObject` from one of these, read from a TOML file or written inline in
+//! attributes. A file lists each item as a table:
+//!
+//! ```toml
+//! [[pad]]
+//! number = "1"
+//! position = [-0.95, 0.0]
+//! size = [1.0, 1.45]
+//! shape = "roundrect"
+//! roundrect_ratio = 0.25
+//!
+//! [[text]]
+//! kind = "reference"
+//! text = "REF**"
+//! position = [0.0, -1.16]
+//! layer = "F.SilkS"
+//!
+//! [[line]]
+//! start = [-1.0, -0.625]
+//! end = [1.0, -0.625]
+//! layer = "F.Fab"
+//! ```
+//!
+//! Pads with a drill are plated through hole on every copper layer, the others surface
+//! mount on the front, unless `layers` names them. A drill without a pad number is a
+//! non-plated hole with a mask opening and no copper. Texts are 1 mm high with strokes
+//! 15% of that unless `size` and `thickness` say otherwise; lines are 0.1 mm wide unless
+//! `width` does. Lengths are in millimeters.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use serde::Deserialize;
+
+use crate::board_interface::{
+    FontSettings, FpText, FpTextType, GraphicElement, GraphicType, PadDescriptor, PadNumber, PadShape, PadType,
+    Stroke, StrokeType, TentingSettings, TentingType,
+};
+use crate::ids::fresh_uuid;
+use crate::layer_type::{is_kicad_layer_name, BoardLayer, LayerName, PadLayers};
+
+const DEFAULT_TEXT_SIZE: f32 = 1.0;
+const TEXT_THICKNESS_RATIO: f32 = 0.15;
+const DEFAULT_LINE_WIDTH: f32 = 0.1;
+/// KiCad's corner ratio for rounded rectangles that don't give one
+const DEFAULT_ROUNDRECT_RATIO: f32 = 0.25;
+
+/// Pad shapes a fixed footprint can use; custom shapes need a generator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FixedPadShape {
+    Circle,
+    Rect,
+    Oval,
+    #[serde(rename = "roundrect")]
+    RoundRect,
+}
+
+impl From<FixedPadShape> for PadShape {
+    fn from(shape: FixedPadShape) -> Self {
+        match shape {
+            FixedPadShape::Circle => PadShape::Circle,
+            FixedPadShape::Rect => PadShape::Rect,
+            FixedPadShape::Oval => PadShape::Oval,
+            FixedPadShape::RoundRect => PadShape::RoundRect,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PadSpec {
+    pub number: PadNumber,
+    pub position: (f32, f32),
+    pub size: (f32, f32),
+    pub shape: FixedPadShape,
+    /// Round drill diameter, making the pad plated through hole, or a non-plated hole
+    /// when it has no number
+    #[serde(default)]
+    pub drill: Option<f32>,
+    #[serde(default)]
+    pub roundrect_ratio: Option<f32>,
+    /// `None` for the usual layers of the pad type
+    #[serde(default)]
+    pub layers: Option<Vec<LayerName>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FixedTextKind {
+    Reference,
+    Value,
+    User,
+}
+
+impl From<FixedTextKind> for FpTextType {
+    fn from(kind: FixedTextKind) -> Self {
+        match kind {
+            FixedTextKind::Reference => FpTextType::Reference,
+            FixedTextKind::Value => FpTextType::Value,
+            FixedTextKind::User => FpTextType::User,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TextSpec {
+    pub kind: FixedTextKind,
+    pub text: String,
+    pub position: (f32, f32),
+    pub layer: LayerName,
+    #[serde(default)]
+    pub size: Option<f32>,
+    #[serde(default)]
+    pub thickness: Option<f32>,
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LineSpec {
+    pub start: (f32, f32),
+    pub end: (f32, f32),
+    /// A single layer such as "F.SilkS" or "B.Fab"
+    pub layer: LayerName,
+    #[serde(default)]
+    pub width: Option<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeometryError {
+    Parse(String),
+    /// A pad that is empty, has no number or a drill as wide as itself
+    InvalidPad { pad: String, reason: String },
+    /// A layer KiCad doesn't have, or a wildcard where one layer is needed
+    InvalidLayer { item: String, layer: String },
+}
+
+impl fmt::Display for GeometryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeometryError::Parse(message) => write!(f, "invalid footprint geometry file: {}", message),
+            GeometryError::InvalidPad { pad, reason } => write!(f, "pad \"{}\" {}", pad, reason),
+            GeometryError::InvalidLayer { item, layer } => write!(f, "{} is on unknown layer \"{}\"", item, layer),
+        }
+    }
+}
+
+impl std::error::Error for GeometryError {}
+
+/// Everything a fixed footprint draws, see the module documentation
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FixedGeometry {
+    #[serde(default, rename = "pad")]
+    pub pads: Vec<PadSpec>,
+    #[serde(default, rename = "text")]
+    pub texts: Vec<TextSpec>,
+    #[serde(default, rename = "line")]
+    pub lines: Vec<LineSpec>,
+}
+
+impl FixedGeometry {
+    pub fn from_toml(text: &str) -> Result<Self, GeometryError> {
+        Self::default().with_toml(text)
+    }
+
+    /// Add the pads, texts and lines of a TOML file after those already here
+    pub fn with_toml(mut self, text: &str) -> Result<Self, GeometryError> {
+        let file: FixedGeometry = toml::from_str(text).map_err(|e| GeometryError::Parse(e.to_string().trim_end().to_string()))?;
+        file.validate()?;
+        self.pads.extend(file.pads);
+        self.texts.extend(file.texts);
+        self.lines.extend(file.lines);
+        Ok(self)
+    }
+
+    /// copper-derive repeats these checks for `#[pads(file)]` files at compile time, in
+    /// its `geometry_file` module; a change here belongs there too
+    pub fn validate(&self) -> Result<(), GeometryError> {
+        for pad in &self.pads {
+            let invalid = |reason: &str| GeometryError::InvalidPad { pad: pad.number.to_string(), reason: reason.to_string() };
+            if pad.number.is_empty() && pad.drill.is_none() {
+                return Err(invalid("has no number"));
+            }
+            if pad.size.0 <= 0.0 || pad.size.1 <= 0.0 {
+                return Err(invalid("has no area"));
+            }
+            if pad.drill.is_some_and(|drill| drill <= 0.0) {
+                return Err(invalid("has no drill"));
+            }
+            // Unnumbered holes aren't plated and need no copper around them
+            if !pad.number.is_empty() && pad.drill.is_some_and(|drill| drill >= pad.size.0.min(pad.size.1)) {
+                return Err(invalid("has a drill that leaves no copper around it"));
+            }
+            if let Some(layer) = pad.layers.iter().flatten().find(|layer| !is_kicad_layer_name(layer)) {
+                return Err(GeometryError::InvalidLayer { item: format!("pad \"{}\"", pad.number), layer: layer.to_string() });
+            }
+        }
+        if let Some(text) = self.texts.iter().find(|text| !is_kicad_layer_name(&text.layer)) {
+            return Err(GeometryError::InvalidLayer { item: format!("text \"{}\"", text.text), layer: text.layer.to_string() });
+        }
+        if let Some(line) = self.lines.iter().find(|line| BoardLayer::from_kicad_str(&line.layer).is_none()) {
+            return Err(GeometryError::InvalidLayer { item: "line".to_string(), layer: line.layer.to_string() });
+        }
+        Ok(())
+    }
+
+    /// Distinct pad numbers; unnumbered holes aren't terminals
+    pub fn terminal_count(&self) -> usize {
+        self.pads.iter().filter(|pad| !pad.number.is_empty()).map(|pad| &pad.number).collect::<HashSet<_>>().len()
+    }
+
+    pub fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        self.pads
+            .iter()
+            .map(|pad| {
+                let (pad_type, layers) = match pad.drill {
+                    Some(_) if pad.number.is_empty() => (PadType::NPTH, PadLayers::npth()),
+                    Some(_) => (PadType::ThroughHole, PadLayers::tht()),
+                    None => (PadType::SMD, PadLayers::smd_front()),
+                };
+                PadDescriptor {
+                    number: pad.number.clone(),
+                    pin_function: None,
+                    pad_type,
+                    shape: pad.shape.into(),
+                    position: pad.position,
+                    rotation: None,
+                    size: pad.size,
+                    drill_size: pad.drill,
+                    drill_slot: None,
+                    layers: pad.layers.clone().unwrap_or(layers),
+                    roundrect_ratio: (pad.shape == FixedPadShape::RoundRect)
+                        .then(|| pad.roundrect_ratio.unwrap_or(DEFAULT_ROUNDRECT_RATIO)),
+                    tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+                    padstack: None,
+                    fab_property: None,
+                    solder_mask_margin: None,
+                    routing_hint: None,
+                    uuid: fresh_uuid(),
+                }
+            })
+            .collect()
+    }
+
+    pub fn fp_text_elements(&self) -> Vec<FpText> {
+        self.texts
+            .iter()
+            .map(|text| {
+                let size = text.size.unwrap_or(DEFAULT_TEXT_SIZE);
+                FpText {
+                    text_type: text.kind.into(),
+                    text: text.text.clone(),
+                    position: text.position,
+                    rotation: None,
+                    layer: text.layer.clone(),
+                    uuid: fresh_uuid(),
+                    font: FontSettings { size: (size, size), thickness: text.thickness.unwrap_or(size * TEXT_THICKNESS_RATIO) },
+                    hidden: text.hidden,
+                    justify: None,
+                    role: None,
+                }
+            })
+            .collect()
+    }
+
+    pub fn graphic_elements(&self) -> Vec<GraphicElement> {
+        self.lines
+            .iter()
+            .filter_map(|line| {
+                let layer = BoardLayer::from_kicad_str(&line.layer)?;
+                Some(GraphicElement {
+                    element_type: GraphicType::Line { start: line.start, end: line.end },
+                    layer: layer.layer,
+                    side: layer.side,
+                    stroke: Stroke { width: line.width.unwrap_or(DEFAULT_LINE_WIDTH), stroke_type: StrokeType::Solid },
+                    uuid: fresh_uuid(),
+                    role: None,
+                })
+            })
+            .collect()
+    }
+}
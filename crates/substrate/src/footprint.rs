@@ -0,0 +1,389 @@
+//! Footprints as plain data
+//!
+//! Generators work their geometry out on demand and parsed footprints keep theirs in the
+//! parser's types, so neither can be stored as it is. `Footprint::capture` records
+//! everything a `BoardComposableObject` reports (identity and flags, pads with their
+//! padstacks and routing hints, texts and graphics with their roles, courtyards, keepouts,
+//! the 3D model, the anchor and any KiCad source) into a value that compares with `==` and
+//! serializes with serde. The snapshot answers every trait method with what was recorded,
+//! so it stands in for the original anywhere, without the generator that drew it.
+//!
+//! `Board::to_record` and `Board::from_record` do the same for a whole board, its
+//! components captured as footprints. The exporters' native format (`csf`) stores both.
+
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::anchor::Anchor;
+use crate::assembly_process::{default_process, ProcessPlan, ProcessSet};
+use crate::block::BoardGroup;
+use crate::board::{Board, PlacedComponent};
+use crate::board_interface::{BoardComposableObject, FpText, GraphicElement, Model3D, PadDescriptor, Rectangle};
+use crate::courtyard::{ActuationEnvelope, Courtyard};
+use crate::functional_types::FunctionalType;
+use crate::geometry::Point;
+use crate::layer_type::Side;
+use crate::occupancy::PlacementKeepout;
+use crate::pad_groups::PadGrouping;
+use crate::placement::PlacementPolicy;
+use crate::routing::Routing;
+use crate::rule_area::RuleArea;
+use crate::stackup::Stackup;
+use crate::thieving::ThievingCopper;
+use crate::zone::Zone;
+
+/// Everything a footprint reports, see the module documentation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Footprint {
+    pub name: String,
+    pub library: String,
+    pub functional_type: FunctionalType,
+    pub smt: bool,
+    pub electrical: bool,
+    pub passive: bool,
+    pub assembled: bool,
+    pub allows_soldermask_bridges: bool,
+    /// Left out of records written before pad groups were
+    #[serde(default)]
+    pub pad_grouping: PadGrouping,
+    pub terminal_count: usize,
+    pub bounding_box: Rectangle,
+    pub description: Option<String>,
+    pub tags: Option<String>,
+    pub pads: Vec<PadDescriptor>,
+    pub texts: Vec<FpText>,
+    pub graphics: Vec<GraphicElement>,
+    pub model: Option<Model3D>,
+    pub rule_areas: Vec<RuleArea>,
+    /// Left out of records written before envelopes were
+    #[serde(default)]
+    pub actuation_envelope: Option<ActuationEnvelope>,
+    /// Left out of records written before processes were; those answer the default for
+    /// their pads
+    #[serde(default)]
+    pub process: Option<ProcessSet>,
+    pub courtyard_margin: f32,
+    pub courtyards: Vec<Courtyard>,
+    pub anchor: Anchor,
+    /// See `BoardComposableObject::kicad_source`
+    pub kicad_source: Option<String>,
+}
+
+impl Footprint {
+    pub fn capture<T: BoardComposableObject + ?Sized>(component: &T) -> Self {
+        Self {
+            name: component.footprint_name(),
+            library: component.library_name(),
+            functional_type: component.functional_type(),
+            smt: component.is_smt(),
+            electrical: component.is_electrical(),
+            passive: component.is_passive(),
+            assembled: component.is_assembled(),
+            allows_soldermask_bridges: component.allows_soldermask_bridges(),
+            pad_grouping: component.pad_grouping(),
+            terminal_count: component.terminal_count(),
+            bounding_box: component.bounding_box(),
+            description: component.description(),
+            tags: component.tags(),
+            pads: component.pad_descriptors(),
+            texts: component.fp_text_elements(),
+            graphics: component.graphic_elements(),
+            model: component.model_3d(),
+            rule_areas: component.rule_areas(),
+            actuation_envelope: component.actuation_envelope(),
+            process: Some(component.process()),
+            courtyard_margin: component.courtyard_margin(),
+            courtyards: component.generate_courtyards(),
+            anchor: component.anchor(),
+            kicad_source: component.kicad_source(),
+        }
+    }
+}
+
+impl BoardComposableObject for Footprint {
+    fn is_smt(&self) -> bool {
+        self.smt
+    }
+
+    fn is_electrical(&self) -> bool {
+        self.electrical
+    }
+
+    fn is_passive(&self) -> bool {
+        self.passive
+    }
+
+    fn terminal_count(&self) -> usize {
+        self.terminal_count
+    }
+
+    fn is_assembled(&self) -> bool {
+        self.assembled
+    }
+
+    fn allows_soldermask_bridges(&self) -> bool {
+        self.allows_soldermask_bridges
+    }
+
+    fn pad_grouping(&self) -> PadGrouping {
+        self.pad_grouping.clone()
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        self.functional_type.clone()
+    }
+
+    fn footprint_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn library_name(&self) -> String {
+        self.library.clone()
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        self.bounding_box.clone()
+    }
+
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        self.pads.clone()
+    }
+
+    fn description(&self) -> Option<String> {
+        self.description.clone()
+    }
+
+    fn tags(&self) -> Option<String> {
+        self.tags.clone()
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        self.texts.clone()
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        self.graphics.clone()
+    }
+
+    fn model_3d(&self) -> Option<Model3D> {
+        self.model.clone()
+    }
+
+    fn kicad_source(&self) -> Option<String> {
+        self.kicad_source.clone()
+    }
+
+    fn rule_areas(&self) -> Vec<RuleArea> {
+        self.rule_areas.clone()
+    }
+
+    fn actuation_envelope(&self) -> Option<ActuationEnvelope> {
+        self.actuation_envelope.clone()
+    }
+
+    fn process(&self) -> ProcessSet {
+        self.process.unwrap_or_else(|| default_process(self))
+    }
+
+    fn courtyard_margin(&self) -> f32 {
+        self.courtyard_margin
+    }
+
+    /// The first recorded courtyard, the front one
+    fn generate_courtyard(&self) -> Courtyard {
+        match self.courtyards.first() {
+            Some(courtyard) => courtyard.clone(),
+            None => Courtyard::new(self.bounding_box.clone(), self.courtyard_margin),
+        }
+    }
+
+    fn generate_courtyards(&self) -> Vec<Courtyard> {
+        self.courtyards.clone()
+    }
+
+    fn anchor(&self) -> Anchor {
+        self.anchor
+    }
+}
+
+/// A placed component of a `BoardRecord`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComponentRecord {
+    pub reference: String,
+    /// Index into `BoardRecord::footprints`
+    pub footprint: usize,
+    pub position: (f64, f64),
+    pub rotation: f32,
+    pub side: Side,
+}
+
+/// A board as plain data, see the module documentation. Components placing the same
+/// footprint share one entry of `footprints`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoardRecord {
+    pub name: String,
+    pub footprints: Vec<Footprint>,
+    pub components: Vec<ComponentRecord>,
+    pub policy: PlacementPolicy,
+    pub outline: Option<Vec<Point>>,
+    pub stackup: Option<Stackup>,
+    pub aux_origin: Point,
+    pub grid_origin: Point,
+    pub thieving: Vec<ThievingCopper>,
+    pub graphics: Vec<GraphicElement>,
+    pub passthrough: Vec<String>,
+    routing: Routing,
+    pub groups: Vec<BoardGroup>,
+    pub keepouts: Vec<PlacementKeepout>,
+    pub zones: Vec<Zone>,
+    /// Left out of records written before process plans were
+    #[serde(default)]
+    pub process_plan: Option<ProcessPlan>,
+}
+
+/// Serialize a map in key order, so the same board is always written the same way
+pub(crate) fn sorted_map<K: Ord + Serialize, V: Serialize, S: serde::Serializer>(
+    map: &HashMap<K, V>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+/// `sorted_map` of a map shared with snapshots
+pub(crate) fn sorted_shared_map<K: Ord + Serialize, V: Serialize, S: serde::Serializer>(
+    map: &Rc<HashMap<K, V>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    sorted_map(map, serializer)
+}
+
+impl Board {
+    /// The board with its components captured as `Footprint`s
+    pub fn to_record(&self) -> BoardRecord {
+        let mut shared: Vec<&Rc<dyn BoardComposableObject>> = Vec::new();
+        let mut footprints = Vec::new();
+        let mut components = Vec::with_capacity(self.components.len());
+        for placed in self.components.iter() {
+            let footprint = match shared.iter().position(|component| Rc::ptr_eq(component, &placed.component)) {
+                Some(index) => index,
+                None => {
+                    shared.push(&placed.component);
+                    footprints.push(Footprint::capture(&*placed.component));
+                    footprints.len() - 1
+                }
+            };
+            components.push(ComponentRecord {
+                reference: placed.reference.clone(),
+                footprint,
+                position: placed.position,
+                rotation: placed.rotation,
+                side: placed.side,
+            });
+        }
+        BoardRecord {
+            name: self.name.clone(),
+            footprints,
+            components,
+            policy: self.policy().clone(),
+            outline: self.outline().map(<[Point]>::to_vec),
+            stackup: self.stackup().cloned(),
+            aux_origin: self.aux_origin(),
+            grid_origin: self.grid_origin(),
+            thieving: self.thieving.clone(),
+            graphics: self.graphics().to_vec(),
+            passthrough: self.passthrough().to_vec(),
+            routing: self.routing.clone(),
+            groups: self.groups.clone(),
+            keepouts: self.keepouts.clone(),
+            zones: self.zones.clone(),
+            process_plan: self.process_plan,
+        }
+    }
+
+    /// The board a record describes. Components are placed as recorded, without checking
+    /// them against the placement policy again; those naming a footprint the record
+    /// doesn't have are left out.
+    pub fn from_record(record: BoardRecord) -> Board {
+        let footprints: Vec<Rc<dyn BoardComposableObject>> =
+            record.footprints.into_iter().map(|footprint| Rc::new(footprint) as Rc<dyn BoardComposableObject>).collect();
+        let components = record
+            .components
+            .into_iter()
+            .filter_map(|component| {
+                Some(PlacedComponent {
+                    component: footprints.get(component.footprint)?.clone(),
+                    reference: component.reference,
+                    position: component.position,
+                    rotation: component.rotation,
+                    side: component.side,
+                })
+            })
+            .collect();
+        let mut board = Board::new(&record.name).with_policy(record.policy);
+        board.components = Rc::new(components);
+        board.set_outline(record.outline);
+        board.set_stackup(record.stackup);
+        board.set_aux_origin(record.aux_origin);
+        board.set_grid_origin(record.grid_origin);
+        board.thieving = record.thieving;
+        for graphic in record.graphics {
+            board.add_graphic(graphic);
+        }
+        board.set_passthrough(record.passthrough);
+        board.routing = record.routing;
+        board.groups = record.groups;
+        board.keepouts = record.keepouts;
+        board.zones = record.zones;
+        board.process_plan = record.process_plan;
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backdrill::Backdrill;
+    use crate::layer_type::{BoardLayer, LayerType};
+    use crate::presets;
+    use crate::routing::{Track, TrackArc, Via};
+
+    #[test]
+    fn capture_answers_like_the_generator() {
+        // Generators hand out fresh UUIDs on every call
+        let positions = |pads: Vec<PadDescriptor>| pads.into_iter().map(|pad| (pad.number, pad.position, pad.size)).collect::<Vec<_>>();
+        let resistor = presets::resistor_0402("10k");
+        let footprint = Footprint::capture(&resistor);
+        assert_eq!(footprint.footprint_name(), resistor.footprint_name());
+        assert_eq!(positions(footprint.pad_descriptors()), positions(resistor.pad_descriptors()));
+        assert_eq!(footprint.graphic_elements().len(), resistor.graphic_elements().len());
+        assert_eq!(footprint.generate_courtyards(), resistor.generate_courtyards());
+        assert_eq!(footprint.description(), resistor.description());
+        assert_eq!(Footprint::capture(&footprint), footprint);
+    }
+
+    #[test]
+    fn board_record_round_trips() {
+        let copper = BoardLayer::front(LayerType::Copper);
+        let mut board = Board::new("record").with_rectangular_outline(20.0, 10.0).with_stackup(Stackup::two_layer(1.6));
+        let resistor: Rc<dyn BoardComposableObject> = Rc::new(presets::resistor_0603("1k"));
+        board.place_shared("R1", resistor.clone(), (5.0, 5.0), 0.0, Side::Front).unwrap();
+        board.place_shared("R2", resistor, (15.0, 5.0), 90.0, Side::Back).unwrap();
+        board.connect_pad("R1", "2", "SIG");
+        board.connect_pad("R2", "1", "SIG");
+        board.add_track(Track::new("SIG", copper, Point::new(5.8, 5.0), Point::new(10.0, 5.0), 0.2));
+        board.add_arc(TrackArc::new("SIG", copper, Point::new(10.0, 5.0), Point::new(12.0, 4.0), Point::new(14.0, 5.0), 0.2));
+        let backdrill = Backdrill::new(Side::Back, copper, 0.6);
+        board.add_via(Via::new("SIG", Point::new(10.0, 5.0), 0.6, 0.3).with_backdrill(backdrill));
+        board.add_zone(Zone::new("GND", &[BoardLayer::back(LayerType::Copper)], vec![Point::new(0.0, 0.0), Point::new(20.0, 0.0), Point::new(20.0, 10.0)]));
+
+        let record = board.to_record();
+        assert_eq!(record.footprints.len(), 1, "R1 and R2 share their footprint");
+        let loaded = Board::from_record(record.clone());
+        assert_eq!(loaded.to_record(), record);
+        assert_eq!(loaded.pad_net("R2", "1"), Some("SIG"));
+        assert_eq!(loaded.vias()[0].backdrill, Some(backdrill));
+        assert!(Rc::ptr_eq(&loaded.components()[0].component, &loaded.components()[1].component));
+    }
+}
@@ -4,14 +4,18 @@
 //! can have in a PCB design, from passive components like resistors and capacitors
 //! to active components like integrated circuits and microcontrollers.
 
+use serde::{Deserialize, Serialize};
+
 /// Functional Type Enumeration
 /// 
 /// where string specifies the type, i.e. FPGA(Artix7) or MCU(Pico2) 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum FunctionalType {
     Resistor(String),
     Capacitor(String),
     Inductor(String),
+    /// Ferrite bead for EMI suppression, e.g. FerriteBead("600R@100MHz")
+    FerriteBead(String),
     Connector(String),
     Fuse(String),
     Protection(String),
@@ -25,4 +29,140 @@ pub enum FunctionalType {
     IsolationIC(String),
     OpAmp(String),
     Timer(String),
-}
\ No newline at end of file
+    /// Optical alignment mark, e.g. a stencil fiducial; not an electrical part
+    Fiducial(String),
+    /// Board level link set by solder or a shunt, e.g. Jumper("SolderJumper_2_Open")
+    Jumper(String),
+    /// Quartz crystal or ceramic resonator, by frequency, e.g. Crystal("16MHz")
+    Crystal(String),
+    /// Clock oscillator module with its own driver, e.g. Oscillator("25MHz")
+    Oscillator(String),
+    /// Hole for a screw, standoff or alignment pin, e.g. MountingHole("M3")
+    MountingHole(String),
+}
+impl FunctionalType {
+    /// Reference designator prefix conventionally used for this type, e.g. "R" or "U"
+    pub fn reference_prefix(&self) -> &'static str {
+        match self {
+            FunctionalType::Resistor(_) => "R",
+            FunctionalType::Capacitor(_) => "C",
+            FunctionalType::Inductor(_) => "L",
+            FunctionalType::FerriteBead(_) => "FB",
+            FunctionalType::Connector(_) => "J",
+            FunctionalType::Fuse(_) => "F",
+            FunctionalType::Protection(_) | FunctionalType::LED(_) => "D",
+            FunctionalType::LCD(_) => "DS",
+            FunctionalType::IntegratedCircuit(_)
+            | FunctionalType::ADC(_)
+            | FunctionalType::DAC(_)
+            | FunctionalType::FPGA(_)
+            | FunctionalType::MCU(_)
+            | FunctionalType::IsolationIC(_)
+            | FunctionalType::OpAmp(_)
+            | FunctionalType::Timer(_) => "U",
+            FunctionalType::Fiducial(_) => "FID",
+            FunctionalType::Jumper(_) => "JP",
+            FunctionalType::Crystal(_) => "Y",
+            FunctionalType::Oscillator(_) => "X",
+            FunctionalType::MountingHole(_) => "H",
+        }
+    }
+
+    /// The specifying string, i.e. "10k" for Resistor("10k") or "Artix7" for FPGA("Artix7")
+    pub fn value(&self) -> &str {
+        match self {
+            FunctionalType::Resistor(v)
+            | FunctionalType::Capacitor(v)
+            | FunctionalType::Inductor(v)
+            | FunctionalType::FerriteBead(v)
+            | FunctionalType::Connector(v)
+            | FunctionalType::Fuse(v)
+            | FunctionalType::Protection(v)
+            | FunctionalType::IntegratedCircuit(v)
+            | FunctionalType::ADC(v)
+            | FunctionalType::DAC(v)
+            | FunctionalType::FPGA(v)
+            | FunctionalType::MCU(v)
+            | FunctionalType::LED(v)
+            | FunctionalType::LCD(v)
+            | FunctionalType::IsolationIC(v)
+            | FunctionalType::OpAmp(v)
+            | FunctionalType::Timer(v)
+            | FunctionalType::Fiducial(v)
+            | FunctionalType::Jumper(v)
+            | FunctionalType::Crystal(v)
+            | FunctionalType::Oscillator(v)
+            | FunctionalType::MountingHole(v) => v,
+        }
+    }
+
+    /// Two terminal passives drawn as a simple body between two pins
+    pub fn is_two_terminal_passive(&self) -> bool {
+        matches!(
+            self,
+            FunctionalType::Resistor(_)
+                | FunctionalType::Capacitor(_)
+                | FunctionalType::Inductor(_)
+                | FunctionalType::FerriteBead(_)
+        )
+    }
+
+    /// The type whose `kind_name` is `name`, ignoring case, specified by `value`
+    pub fn from_kind_name(name: &str, value: &str) -> Option<Self> {
+        let value = value.to_string();
+        let functional_type = match name.to_ascii_lowercase().as_str() {
+            "resistor" => FunctionalType::Resistor(value),
+            "capacitor" => FunctionalType::Capacitor(value),
+            "inductor" => FunctionalType::Inductor(value),
+            "ferritebead" => FunctionalType::FerriteBead(value),
+            "connector" => FunctionalType::Connector(value),
+            "fuse" => FunctionalType::Fuse(value),
+            "protection" => FunctionalType::Protection(value),
+            "integratedcircuit" => FunctionalType::IntegratedCircuit(value),
+            "adc" => FunctionalType::ADC(value),
+            "dac" => FunctionalType::DAC(value),
+            "fpga" => FunctionalType::FPGA(value),
+            "mcu" => FunctionalType::MCU(value),
+            "led" => FunctionalType::LED(value),
+            "lcd" => FunctionalType::LCD(value),
+            "isolationic" => FunctionalType::IsolationIC(value),
+            "opamp" => FunctionalType::OpAmp(value),
+            "timer" => FunctionalType::Timer(value),
+            "fiducial" => FunctionalType::Fiducial(value),
+            "jumper" => FunctionalType::Jumper(value),
+            "crystal" => FunctionalType::Crystal(value),
+            "oscillator" => FunctionalType::Oscillator(value),
+            "mountinghole" => FunctionalType::MountingHole(value),
+            _ => return None,
+        };
+        Some(functional_type)
+    }
+
+    /// Variant name without the specifying string, e.g. "Resistor"
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            FunctionalType::Resistor(_) => "Resistor",
+            FunctionalType::Capacitor(_) => "Capacitor",
+            FunctionalType::Inductor(_) => "Inductor",
+            FunctionalType::FerriteBead(_) => "FerriteBead",
+            FunctionalType::Connector(_) => "Connector",
+            FunctionalType::Fuse(_) => "Fuse",
+            FunctionalType::Protection(_) => "Protection",
+            FunctionalType::IntegratedCircuit(_) => "IntegratedCircuit",
+            FunctionalType::ADC(_) => "ADC",
+            FunctionalType::DAC(_) => "DAC",
+            FunctionalType::FPGA(_) => "FPGA",
+            FunctionalType::MCU(_) => "MCU",
+            FunctionalType::LED(_) => "LED",
+            FunctionalType::LCD(_) => "LCD",
+            FunctionalType::IsolationIC(_) => "IsolationIC",
+            FunctionalType::OpAmp(_) => "OpAmp",
+            FunctionalType::Timer(_) => "Timer",
+            FunctionalType::Fiducial(_) => "Fiducial",
+            FunctionalType::Jumper(_) => "Jumper",
+            FunctionalType::Crystal(_) => "Crystal",
+            FunctionalType::Oscillator(_) => "Oscillator",
+            FunctionalType::MountingHole(_) => "MountingHole",
+        }
+    }
+}
@@ -0,0 +1,460 @@
+//! Planar geometry primitives
+//!
+//! Small, dependency free 2D helpers shared by everything that needs to turn board
+//! objects into real geometry - exporters, renderers and design rule checks alike.
+//! All coordinates are in millimeters using the KiCad convention of Y pointing down.
+
+use serde::{Deserialize, Serialize};
+
+use crate::board_interface::Rectangle;
+
+/// A point (or vector) in the footprint/board plane
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Point {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    /// Rotate about the origin by `degrees`, counter-clockwise as seen on screen (KiCad convention).
+    /// Quarter turns are exact, so turning a point four times by 90° gives it back.
+    pub fn rotated(&self, degrees: f32) -> Self {
+        let (sin, cos) = match quarter_turn(degrees) {
+            Some((sin, cos)) => (sin as f32, cos as f32),
+            None => (-degrees.to_radians()).sin_cos(),
+        };
+        Self {
+            x: self.x * cos - self.y * sin,
+            y: self.x * sin + self.y * cos,
+        }
+    }
+
+    pub fn offset(&self, dx: f32, dy: f32) -> Self {
+        Self { x: self.x + dx, y: self.y + dy }
+    }
+
+    pub fn distance_to(&self, other: &Point) -> f32 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+}
+
+/// The f64 of the decimal an f32 prints as, so 0.1f32 widens to 0.1 rather than
+/// 0.10000000149011612. Use it where an f32 becomes an f64 that is written out.
+pub fn widen(value: f32) -> f64 {
+    value.to_string().parse().unwrap_or(f64::from(value))
+}
+
+/// Exact sine and cosine of `-degrees` for a whole number of quarter turns, which the
+/// trigonometric functions miss by a few ulps
+fn quarter_turn(degrees: f32) -> Option<(f64, f64)> {
+    match degrees.rem_euclid(360.0) {
+        0.0 => Some((0.0, 1.0)),
+        90.0 => Some((-1.0, 0.0)),
+        180.0 => Some((0.0, -1.0)),
+        270.0 => Some((1.0, 0.0)),
+        _ => None,
+    }
+}
+
+/// `Point::rotated` for an f64 placement position, see `board::IntoBoardPosition`
+pub fn rotate_position((x, y): (f64, f64), degrees: f32) -> (f64, f64) {
+    let (sin, cos) = quarter_turn(degrees).unwrap_or_else(|| (-f64::from(degrees).to_radians()).sin_cos());
+    (x * cos - y * sin, x * sin + y * cos)
+}
+
+impl From<(f32, f32)> for Point {
+    fn from((x, y): (f32, f32)) -> Self {
+        Self { x, y }
+    }
+}
+
+impl Rectangle {
+    pub fn width(&self) -> f32 {
+        self.max_x - self.min_x
+    }
+
+    pub fn height(&self) -> f32 {
+        self.max_y - self.min_y
+    }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        point.x >= self.min_x && point.x <= self.max_x && point.y >= self.min_y && point.y <= self.max_y
+    }
+
+    /// Smallest rectangle enclosing every point, `None` for an empty slice
+    pub fn enclosing(points: &[Point]) -> Option<Rectangle> {
+        let first = points.first()?;
+        let mut rect = Rectangle { min_x: first.x, min_y: first.y, max_x: first.x, max_y: first.y };
+        for p in &points[1..] {
+            rect.min_x = rect.min_x.min(p.x);
+            rect.min_y = rect.min_y.min(p.y);
+            rect.max_x = rect.max_x.max(p.x);
+            rect.max_y = rect.max_y.max(p.y);
+        }
+        Some(rect)
+    }
+}
+
+/// Number of segments needed to approximate an arc of `radius` sweeping `sweep_degrees`
+/// so that no chord deviates from the true arc by more than `tolerance`
+pub fn arc_segment_count(radius: f32, sweep_degrees: f32, tolerance: f32) -> usize {
+    if radius <= 0.0 {
+        return 1;
+    }
+    let tolerance = tolerance.clamp(f32::EPSILON, radius);
+    // Sagitta of a chord subtending angle a: r * (1 - cos(a / 2))
+    let max_step = 2.0 * (1.0 - tolerance / radius).acos();
+    let segments = (sweep_degrees.abs().to_radians() / max_step).ceil() as usize;
+    segments.max(1)
+}
+
+/// Append the points of an arc to `out`, excluding the start point and including the end point
+pub fn push_arc(out: &mut Vec<Point>, center: Point, radius: f32, start_degrees: f32, sweep_degrees: f32, tolerance: f32) {
+    let segments = arc_segment_count(radius, sweep_degrees, tolerance);
+    for i in 1..=segments {
+        let angle = (start_degrees + sweep_degrees * i as f32 / segments as f32).to_radians();
+        out.push(Point::new(center.x + radius * angle.cos(), center.y + radius * angle.sin()));
+    }
+}
+
+/// Signed area of a simple polygon (shoelace formula), positive for counter-clockwise winding
+pub fn polygon_signed_area(polygon: &[Point]) -> f32 {
+    let n = polygon.len();
+    if n < 3 {
+        return 0.0;
+    }
+    let twice_area: f32 = (0..n)
+        .map(|i| {
+            let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum();
+    twice_area / 2.0
+}
+
+/// Unsigned area of a simple polygon
+pub fn polygon_area(polygon: &[Point]) -> f32 {
+    polygon_signed_area(polygon).abs()
+}
+
+/// Shortest distance from `point` to the segment `a`-`b`, along with the closest point on it
+pub fn point_segment_distance(point: Point, a: Point, b: Point) -> (f32, Point) {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared > 0.0 {
+        (((point.x - a.x) * dx + (point.y - a.y) * dy) / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let closest = Point::new(a.x + t * dx, a.y + t * dy);
+    (point.distance_to(&closest), closest)
+}
+
+/// Whether segments `a1`-`a2` and `b1`-`b2` touch or cross
+pub fn segments_intersect(a1: Point, a2: Point, b1: Point, b2: Point) -> bool {
+    fn orientation(p: Point, q: Point, r: Point) -> f32 {
+        (q.x - p.x) * (r.y - p.y) - (q.y - p.y) * (r.x - p.x)
+    }
+    let d1 = orientation(b1, b2, a1);
+    let d2 = orientation(b1, b2, a2);
+    let d3 = orientation(a1, a2, b1);
+    let d4 = orientation(a1, a2, b2);
+    if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0)) && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0)) {
+        return true;
+    }
+    // Collinear or touching cases
+    point_segment_distance(a1, b1, b2).0 == 0.0
+        || point_segment_distance(a2, b1, b2).0 == 0.0
+        || point_segment_distance(b1, a1, a2).0 == 0.0
+        || point_segment_distance(b2, a1, a2).0 == 0.0
+}
+
+/// Shortest distance between two segments
+pub fn segment_distance(a1: Point, a2: Point, b1: Point, b2: Point) -> f32 {
+    if segments_intersect(a1, a2, b1, b2) {
+        return 0.0;
+    }
+    point_segment_distance(a1, b1, b2).0
+        .min(point_segment_distance(a2, b1, b2).0)
+        .min(point_segment_distance(b1, a1, a2).0)
+        .min(point_segment_distance(b2, a1, a2).0)
+}
+
+/// Even-odd point in polygon test
+pub fn point_in_polygon(point: Point, polygon: &[Point]) -> bool {
+    let mut inside = false;
+    let n = polygon.len();
+    for i in 0..n {
+        let (a, b) = (polygon[i], polygon[(i + n - 1) % n]);
+        if (a.y > point.y) != (b.y > point.y) && point.x < (b.x - a.x) * (point.y - a.y) / (b.y - a.y) + a.x {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// The pieces of segment `start`-`end` inside a closed polygon (even-odd), in order along
+/// the segment. Pieces meeting at a vertex are joined.
+pub fn clip_segment(start: Point, end: Point, polygon: &[Point]) -> Vec<(Point, Point)> {
+    let n = polygon.len();
+    if n < 3 {
+        return Vec::new();
+    }
+    let d = (end.x - start.x, end.y - start.y);
+    let at = |t: f32| Point::new(start.x + d.0 * t, start.y + d.1 * t);
+    let mut cuts = vec![0.0, 1.0];
+    for i in 0..n {
+        let (p, q) = (polygon[i], polygon[(i + 1) % n]);
+        let e = (q.x - p.x, q.y - p.y);
+        let denom = d.0 * e.1 - d.1 * e.0;
+        // Parallel edges don't cut; pieces along them are settled by the midpoint test
+        if denom.abs() <= f32::EPSILON {
+            continue;
+        }
+        let w = (p.x - start.x, p.y - start.y);
+        let (t, u) = ((w.0 * e.1 - w.1 * e.0) / denom, (w.0 * d.1 - w.1 * d.0) / denom);
+        if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+            cuts.push(t);
+        }
+    }
+    cuts.sort_by(f32::total_cmp);
+    cuts.dedup();
+
+    let mut pieces: Vec<(f32, f32)> = Vec::new();
+    for pair in cuts.windows(2) {
+        let (t0, t1) = (pair[0], pair[1]);
+        if t1 - t0 <= f32::EPSILON || !point_in_polygon(at((t0 + t1) / 2.0), polygon) {
+            continue;
+        }
+        match pieces.last_mut() {
+            Some(last) if last.1 == t0 => last.1 = t1,
+            _ => pieces.push((t0, t1)),
+        }
+    }
+    pieces.into_iter().map(|(t0, t1)| (at(t0), at(t1))).collect()
+}
+
+/// Edge to edge distance between two closed polygons, zero when they touch or overlap
+pub fn polygon_distance(a: &[Point], b: &[Point]) -> f32 {
+    if a.is_empty() || b.is_empty() {
+        return f32::INFINITY;
+    }
+    if point_in_polygon(a[0], b) || point_in_polygon(b[0], a) {
+        return 0.0;
+    }
+    polygon_edge_distance(a, b)
+}
+
+/// Shortest distance between the outlines of two closed polygons, ignoring containment, so
+/// a polygon well inside another is as far from it as from its nearest edge
+pub fn polygon_edge_distance(a: &[Point], b: &[Point]) -> f32 {
+    let mut distance = f32::INFINITY;
+    for i in 0..a.len() {
+        let (a1, a2) = (a[i], a[(i + 1) % a.len()]);
+        for j in 0..b.len() {
+            let (b1, b2) = (b[j], b[(j + 1) % b.len()]);
+            distance = distance.min(segment_distance(a1, a2, b1, b2));
+            if distance == 0.0 {
+                return 0.0;
+            }
+        }
+    }
+    distance
+}
+
+/// A circular arc, its angles in degrees as `push_arc` takes them: from +X towards +Y, so
+/// a positive sweep turns clockwise as seen on screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CircularArc {
+    pub center: Point,
+    pub radius: f32,
+    pub start_degrees: f32,
+    pub sweep_degrees: f32,
+}
+
+impl CircularArc {
+    /// The arc from `start` through `mid` to `end`, as KiCad stores arcs; `None` when the
+    /// points are collinear or two of them coincide
+    pub fn through(start: Point, mid: Point, end: Point) -> Option<Self> {
+        let (ax, ay) = (f64::from(start.x), f64::from(start.y));
+        let (bx, by) = (f64::from(mid.x), f64::from(mid.y));
+        let (cx, cy) = (f64::from(end.x), f64::from(end.y));
+        // Twice the triangle's area, compared with its sides so the test doesn't depend on scale
+        let cross = (bx - ax) * (cy - ay) - (by - ay) * (cx - ax);
+        let sides = (bx - ax).hypot(by - ay) * (cx - ax).hypot(cy - ay);
+        if sides == 0.0 || cross.abs() <= 1e-6 * sides {
+            return None;
+        }
+        let d = 2.0 * cross;
+        let (a2, b2, c2) = (ax * ax + ay * ay, bx * bx + by * by, cx * cx + cy * cy);
+        let ux = (a2 * (by - cy) + b2 * (cy - ay) + c2 * (ay - by)) / d;
+        let uy = (a2 * (cx - bx) + b2 * (ax - cx) + c2 * (bx - ax)) / d;
+        let angle = |x: f64, y: f64| (y - uy).atan2(x - ux).to_degrees();
+        let (from, through, to) = (angle(ax, ay), angle(bx, by), angle(cx, cy));
+        let to_mid = (through - from).rem_euclid(360.0);
+        let to_end = (to - from).rem_euclid(360.0);
+        let sweep = if to_mid <= to_end { to_end } else { to_end - 360.0 };
+        Some(Self {
+            center: Point::new(ux as f32, uy as f32),
+            radius: (ax - ux).hypot(ay - uy) as f32,
+            start_degrees: from as f32,
+            sweep_degrees: sweep as f32,
+        })
+    }
+
+    pub fn point_at(&self, degrees: f32) -> Point {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Point::new(self.center.x + self.radius * cos, self.center.y + self.radius * sin)
+    }
+
+    pub fn start(&self) -> Point {
+        self.point_at(self.start_degrees)
+    }
+
+    pub fn end(&self) -> Point {
+        self.point_at(self.start_degrees + self.sweep_degrees)
+    }
+
+    pub fn length(&self) -> f32 {
+        self.radius * self.sweep_degrees.abs().to_radians()
+    }
+
+    /// Whether the arc passes the direction `degrees` from its center
+    pub fn contains_angle(&self, degrees: f32) -> bool {
+        let offset = (degrees - self.start_degrees) * self.sweep_degrees.signum();
+        offset.rem_euclid(360.0) <= self.sweep_degrees.abs()
+    }
+
+    /// The arc as a polyline from start to end, no chord further than `tolerance` from it
+    pub fn points(&self, tolerance: f32) -> Vec<Point> {
+        let mut points = vec![self.start()];
+        push_arc(&mut points, self.center, self.radius, self.start_degrees, self.sweep_degrees, tolerance);
+        points
+    }
+
+    /// Exact bounds: the end points and the quadrant points the arc passes
+    pub fn bounds(&self) -> Rectangle {
+        let mut points = vec![self.start(), self.end()];
+        points.extend([0.0, 90.0, 180.0, 270.0].into_iter().filter(|angle| self.contains_angle(*angle)).map(|angle| self.point_at(angle)));
+        Rectangle::enclosing(&points).unwrap()
+    }
+}
+
+/// Shortest distance from `point` to `arc`, along with the closest point on it
+pub fn point_arc_distance(point: Point, arc: &CircularArc) -> (f32, Point) {
+    let (dx, dy) = (point.x - arc.center.x, point.y - arc.center.y);
+    let direction = dy.atan2(dx).to_degrees();
+    if arc.contains_angle(direction) {
+        let closest = arc.point_at(direction);
+        return (point.distance_to(&closest), closest);
+    }
+    let (start, end) = (arc.start(), arc.end());
+    let (to_start, to_end) = (point.distance_to(&start), point.distance_to(&end));
+    if to_start <= to_end {
+        (to_start, start)
+    } else {
+        (to_end, end)
+    }
+}
+
+/// Shortest distance between segment `a`-`b` and `arc`, with the closest points on the
+/// segment and on the arc
+pub fn segment_arc_distance(a: Point, b: Point, arc: &CircularArc) -> (f32, Point, Point) {
+    // Where the segment crosses the arc's circle within the arc's sweep
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let (fx, fy) = (a.x - arc.center.x, a.y - arc.center.y);
+    let (qa, qb, qc) = (dx * dx + dy * dy, 2.0 * (fx * dx + fy * dy), fx * fx + fy * fy - arc.radius * arc.radius);
+    let discriminant = qb * qb - 4.0 * qa * qc;
+    if qa > 0.0 && discriminant >= 0.0 {
+        for t in [(-qb - discriminant.sqrt()) / (2.0 * qa), (-qb + discriminant.sqrt()) / (2.0 * qa)] {
+            let crossing = Point::new(a.x + t * dx, a.y + t * dy);
+            let direction = (crossing.y - arc.center.y).atan2(crossing.x - arc.center.x).to_degrees();
+            if (0.0..=1.0).contains(&t) && arc.contains_angle(direction) {
+                return (0.0, crossing, crossing);
+            }
+        }
+    }
+    // Otherwise the closest points are an end of one of them, or the segment's point
+    // nearest the center
+    let from_segment = |p: Point| {
+        let (distance, on_arc) = point_arc_distance(p, arc);
+        (distance, p, on_arc)
+    };
+    let from_arc = |p: Point| {
+        let (distance, on_segment) = point_segment_distance(p, a, b);
+        (distance, on_segment, p)
+    };
+    let nearest_center = point_segment_distance(arc.center, a, b).1;
+    let candidates = [from_segment(a), from_segment(b), from_segment(nearest_center), from_arc(arc.start()), from_arc(arc.end())];
+    candidates.into_iter().min_by(|x, y| x.0.total_cmp(&y.0)).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-4;
+
+    /// Radius 10 about the origin from (10, 0) to (0, 10)
+    fn quarter_circle() -> CircularArc {
+        let mid = Point::new(10.0 * std::f32::consts::FRAC_1_SQRT_2, 10.0 * std::f32::consts::FRAC_1_SQRT_2);
+        CircularArc::through(Point::new(10.0, 0.0), mid, Point::new(0.0, 10.0)).unwrap()
+    }
+
+    #[test]
+    fn quarter_turns_are_exact() {
+        let point = Point::new(1.1, -2.3);
+        assert_eq!(point.rotated(90.0), Point::new(-2.3, -1.1));
+        assert_eq!(point.rotated(-90.0), Point::new(2.3, 1.1));
+        assert_eq!(point.rotated(180.0).rotated(540.0), point);
+        assert_eq!(rotate_position((1.1, -2.3), 270.0), (2.3, 1.1));
+        assert!(point.rotated(45.0).distance_to(&Point::new(-0.8485281, -2.4041631)) < EPSILON);
+    }
+
+    #[test]
+    fn quarter_circle_through_three_points() {
+        let arc = quarter_circle();
+        assert!(arc.center.distance_to(&Point::new(0.0, 0.0)) < EPSILON, "{:?}", arc);
+        assert!((arc.radius - 10.0).abs() < EPSILON);
+        assert!((arc.sweep_degrees - 90.0).abs() < EPSILON);
+        assert!((arc.length() - 5.0 * std::f32::consts::PI).abs() < EPSILON, "{}", arc.length());
+        // The same arc the other way round sweeps back
+        let back = CircularArc::through(arc.end(), arc.point_at(45.0), arc.start()).unwrap();
+        assert!((back.sweep_degrees + 90.0).abs() < EPSILON && (back.length() - arc.length()).abs() < EPSILON);
+        assert!(CircularArc::through(Point::new(0.0, 0.0), Point::new(1.0, 1.0), Point::new(2.0, 2.0)).is_none());
+        // Away from the origin
+        let moved = CircularArc::through(Point::new(13.0, 4.0), arc.point_at(45.0).offset(3.0, 4.0), Point::new(3.0, 14.0)).unwrap();
+        assert!(moved.center.distance_to(&Point::new(3.0, 4.0)) < EPSILON, "{:?}", moved);
+        let bounds = arc.bounds();
+        assert!(bounds.min_x.abs() < EPSILON && (bounds.max_x - 10.0).abs() < EPSILON);
+        assert!(bounds.min_y.abs() < EPSILON && (bounds.max_y - 10.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn distances_to_a_quarter_circle() {
+        let arc = quarter_circle();
+        // Radially outside and inside the sweep: |d - r|
+        let (distance, closest) = point_arc_distance(Point::new(20.0, 20.0), &arc);
+        assert!((distance - (20.0 * std::f32::consts::SQRT_2 - 10.0)).abs() < EPSILON, "{}", distance);
+        assert!(closest.distance_to(&arc.point_at(45.0)) < EPSILON);
+        assert!((point_arc_distance(Point::new(3.0, 4.0), &arc).0 - 5.0).abs() < EPSILON);
+        // Outside the sweep the nearer end is closest
+        let (distance, closest) = point_arc_distance(Point::new(10.0, -5.0), &arc);
+        assert!((distance - 5.0).abs() < EPSILON && closest.distance_to(&Point::new(10.0, 0.0)) < EPSILON);
+
+        // A segment along the 45 degree tangent line, 2 mm out from the arc
+        let out = 12.0 * std::f32::consts::FRAC_1_SQRT_2;
+        let (distance, on_segment, on_arc) = segment_arc_distance(Point::new(out - 5.0, out + 5.0), Point::new(out + 5.0, out - 5.0), &arc);
+        assert!((distance - 2.0).abs() < EPSILON, "{}", distance);
+        assert!(on_segment.distance_to(&Point::new(out, out)) < EPSILON && on_arc.distance_to(&arc.point_at(45.0)) < EPSILON);
+        // A segment crossing the arc touches it, one crossing the circle off the sweep doesn't
+        assert_eq!(segment_arc_distance(Point::new(0.0, 0.0), Point::new(20.0, 20.0), &arc).0, 0.0);
+        let (distance, on_segment, on_arc) = segment_arc_distance(Point::new(-1.0, 0.0), Point::new(-20.0, 0.0), &arc);
+        assert!((distance - 101f32.sqrt()).abs() < EPSILON, "{}", distance);
+        assert!(on_segment.distance_to(&Point::new(-1.0, 0.0)) < EPSILON && on_arc.distance_to(&Point::new(0.0, 10.0)) < EPSILON);
+    }
+}
@@ -0,0 +1,321 @@
+//! Graphic element clean-up before export
+//!
+//! Outlines built by composing or clipping shapes often draw the same line twice or split
+//! one edge into several collinear pieces. `simplify` removes the redundancy: lines on the
+//! same layer and side with the same stroke merge when they are collinear and touch or
+//! overlap, and exact duplicates of other elements are dropped. Nothing moves by more than
+//! the tolerance, so the drawing looks the same.
+//!
+//! KiCad has no open polyline for footprint graphics, so chains of lines stay separate
+//! lines. `Simplified` applies the pass to a footprint's graphics at export time.
+//!
+//! Footprints read from a `.kicad_mod` are exempt: they are written from their source,
+//! which keeps the drawing its author made, and `Simplified` reports their graphics as
+//! they are so checks and renders agree with the file.
+
+use crate::anchor::Anchor;
+use crate::assembly_process::ProcessSet;
+use crate::board_interface::{
+    BoardComposableObject, FpText, GraphicElement, GraphicType, Model3D, PadDescriptor, Rectangle,
+};
+use crate::courtyard::{ActuationEnvelope, Courtyard};
+use crate::describe::DescriptionContext;
+use crate::functional_types::FunctionalType;
+use crate::geometry::{point_segment_distance, Point};
+use crate::pad_groups::PadGrouping;
+use crate::rule_area::RuleArea;
+
+/// Tolerance used by the footprint library writer, in millimeters
+pub const DEFAULT_SIMPLIFY_TOLERANCE: f32 = 0.001;
+
+fn same_pen(a: &GraphicElement, b: &GraphicElement) -> bool {
+    a.layer == b.layer && a.side == b.side && a.stroke == b.stroke
+}
+
+fn close(a: (f32, f32), b: (f32, f32), tolerance: f32) -> bool {
+    Point::from(a).distance_to(&Point::from(b)) <= tolerance
+}
+
+fn same_rectangle(a: &Rectangle, b: &Rectangle, tolerance: f32) -> bool {
+    close((a.min_x, a.min_y), (b.min_x, b.min_y), tolerance) && close((a.max_x, a.max_y), (b.max_x, b.max_y), tolerance)
+}
+
+/// Whether two elements other than lines draw the same thing
+fn duplicate(a: &GraphicElement, b: &GraphicElement, tolerance: f32) -> bool {
+    if !same_pen(a, b) {
+        return false;
+    }
+    match (&a.element_type, &b.element_type) {
+        (GraphicType::Rectangle { bounds: a }, GraphicType::Rectangle { bounds: b }) => same_rectangle(a, b, tolerance),
+        (GraphicType::Circle { center: a, radius: ra }, GraphicType::Circle { center: b, radius: rb }) => {
+            close(*a, *b, tolerance) && (ra - rb).abs() <= tolerance
+        }
+        _ => false,
+    }
+}
+
+/// The single line covering two collinear lines that touch or overlap, if there is one
+/// within `tolerance` of both
+fn merged_line(a: ((f32, f32), (f32, f32)), b: ((f32, f32), (f32, f32)), tolerance: f32) -> Option<((f32, f32), (f32, f32))> {
+    let length = |(start, end): ((f32, f32), (f32, f32))| Point::from(start).distance_to(&Point::from(end));
+    let (long, short) = if length(a) >= length(b) { (a, b) } else { (b, a) };
+    let long_length = length(long);
+    if long_length <= tolerance {
+        return (length(short) <= tolerance && close(long.0, short.0, tolerance)).then_some(long);
+    }
+    let direction = ((long.1.0 - long.0.0) / long_length, (long.1.1 - long.0.1) / long_length);
+    // Distance along and across the long line
+    let along = |(x, y): (f32, f32)| (x - long.0.0) * direction.0 + (y - long.0.1) * direction.1;
+    let across = |(x, y): (f32, f32)| ((x - long.0.0) * direction.1 - (y - long.0.1) * direction.0).abs();
+    if across(short.0) > tolerance || across(short.1) > tolerance {
+        return None;
+    }
+    let (short_min, short_max) = (along(short.0).min(along(short.1)), along(short.0).max(along(short.1)));
+    if short_min > long_length + tolerance || short_max < -tolerance {
+        return None;
+    }
+
+    // Keep the original end points, so merging never moves a line end
+    let points = [long.0, long.1, short.0, short.1];
+    let first = points.iter().copied().min_by(|p, q| along(*p).total_cmp(&along(*q)))?;
+    let last = points.iter().copied().max_by(|p, q| along(*p).total_cmp(&along(*q)))?;
+    let covered = points
+        .iter()
+        .all(|point| point_segment_distance(Point::from(*point), Point::from(first), Point::from(last)).0 <= tolerance);
+    covered.then_some((first, last))
+}
+
+/// `elements` without duplicates and with collinear touching lines merged, see the module
+/// documentation. Elements keep the order of their first appearance.
+pub fn simplify(elements: Vec<GraphicElement>, tolerance: f32) -> Vec<GraphicElement> {
+    let mut result: Vec<GraphicElement> = Vec::with_capacity(elements.len());
+    for element in elements {
+        if result.iter().any(|kept| duplicate(kept, &element, tolerance)) {
+            continue;
+        }
+        result.push(element);
+    }
+
+    // Merge lines until nothing changes; a merge can make a line reach another one
+    let line = |element: &GraphicElement| match element.element_type {
+        GraphicType::Line { start, end } => Some((start, end)),
+        _ => None,
+    };
+    let mut merged_any = true;
+    while merged_any {
+        merged_any = false;
+        'search: for i in 0..result.len() {
+            for j in i + 1..result.len() {
+                if !same_pen(&result[i], &result[j]) {
+                    continue;
+                }
+                let (Some(a), Some(b)) = (line(&result[i]), line(&result[j])) else { continue };
+                if let Some((start, end)) = merged_line(a, b, tolerance) {
+                    result[i].element_type = GraphicType::Line { start, end };
+                    result.remove(j);
+                    merged_any = true;
+                    break 'search;
+                }
+            }
+        }
+    }
+    result
+}
+
+/// A footprint whose graphics are passed through `simplify` when exported. Footprints
+/// carrying their KiCad source are exempt, see the module documentation.
+pub struct Simplified<'a> {
+    inner: &'a dyn BoardComposableObject,
+    tolerance: f32,
+}
+
+impl<'a> Simplified<'a> {
+    pub fn new(inner: &'a dyn BoardComposableObject, tolerance: f32) -> Self {
+        Self { inner, tolerance }
+    }
+}
+
+impl BoardComposableObject for Simplified<'_> {
+    fn is_smt(&self) -> bool {
+        self.inner.is_smt()
+    }
+
+    fn is_electrical(&self) -> bool {
+        self.inner.is_electrical()
+    }
+
+    fn is_passive(&self) -> bool {
+        self.inner.is_passive()
+    }
+
+    fn is_assembled(&self) -> bool {
+        self.inner.is_assembled()
+    }
+
+    fn allows_soldermask_bridges(&self) -> bool {
+        self.inner.allows_soldermask_bridges()
+    }
+
+    fn pad_grouping(&self) -> PadGrouping {
+        self.inner.pad_grouping()
+    }
+
+    fn terminal_count(&self) -> usize {
+        self.inner.terminal_count()
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        self.inner.functional_type()
+    }
+
+    fn footprint_name(&self) -> String {
+        self.inner.footprint_name()
+    }
+
+    fn library_name(&self) -> String {
+        self.inner.library_name()
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        self.inner.bounding_box()
+    }
+
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        self.inner.pad_descriptors()
+    }
+
+    fn description_context(&self) -> Option<DescriptionContext> {
+        self.inner.description_context()
+    }
+
+    fn description(&self) -> Option<String> {
+        self.inner.description()
+    }
+
+    fn tags(&self) -> Option<String> {
+        self.inner.tags()
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        self.inner.fp_text_elements()
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        if self.inner.kicad_source().is_some() {
+            return self.inner.graphic_elements();
+        }
+        simplify(self.inner.graphic_elements(), self.tolerance)
+    }
+
+    fn model_3d(&self) -> Option<Model3D> {
+        self.inner.model_3d()
+    }
+
+    fn kicad_source(&self) -> Option<String> {
+        self.inner.kicad_source()
+    }
+
+    fn kicad_source_with(&self, texts: &[FpText], graphics: &[GraphicElement]) -> Option<String> {
+        self.inner.kicad_source_with(texts, graphics)
+    }
+
+    fn rule_areas(&self) -> Vec<RuleArea> {
+        self.inner.rule_areas()
+    }
+
+    fn actuation_envelope(&self) -> Option<ActuationEnvelope> {
+        self.inner.actuation_envelope()
+    }
+
+    fn process(&self) -> ProcessSet {
+        self.inner.process()
+    }
+
+    fn courtyard_margin(&self) -> f32 {
+        self.inner.courtyard_margin()
+    }
+
+    fn generate_courtyard(&self) -> Courtyard {
+        self.inner.generate_courtyard()
+    }
+
+    fn generate_courtyards(&self) -> Vec<Courtyard> {
+        self.inner.generate_courtyards()
+    }
+
+    fn anchor(&self) -> Anchor {
+        self.inner.anchor()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_interface::{Stroke, StrokeType};
+    use crate::ids::fresh_uuid;
+    use crate::layer_type::{LayerType, Side};
+
+    fn line(start: (f32, f32), end: (f32, f32)) -> GraphicElement {
+        GraphicElement {
+            element_type: GraphicType::Line { start, end },
+            layer: LayerType::SilkScreen,
+            side: Side::Front,
+            stroke: Stroke { width: 0.12, stroke_type: StrokeType::Solid },
+            uuid: fresh_uuid(),
+            role: None,
+        }
+    }
+
+    fn ends(elements: &[GraphicElement]) -> Vec<((f32, f32), (f32, f32))> {
+        elements
+            .iter()
+            .map(|element| match element.element_type {
+                GraphicType::Line { start, end } => (start, end),
+                _ => panic!("not a line: {:?}", element),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn half_edges_of_a_rectangle_merge_into_four_lines() {
+        let corners = [(0.0, 0.0), (2.0, 0.0), (2.0, 1.0), (0.0, 1.0)];
+        let mut halves = Vec::new();
+        for (i, &start) in corners.iter().enumerate() {
+            let end = corners[(i + 1) % 4];
+            let middle = ((start.0 + end.0) / 2.0, (start.1 + end.1) / 2.0);
+            halves.extend([line(start, middle), line(middle, end)]);
+        }
+        assert_eq!(halves.len(), 8);
+        let simplified = simplify(halves, DEFAULT_SIMPLIFY_TOLERANCE);
+        assert_eq!(
+            ends(&simplified),
+            [((0.0, 0.0), (2.0, 0.0)), ((2.0, 0.0), (2.0, 1.0)), ((2.0, 1.0), (0.0, 1.0)), ((0.0, 1.0), (0.0, 0.0))]
+        );
+    }
+
+    #[test]
+    fn overlapping_lines_that_are_not_collinear_are_left_alone() {
+        let crossing = vec![line((0.0, 0.0), (2.0, 2.0)), line((0.0, 2.0), (2.0, 0.0))];
+        // Sharing an end point at an angle, and parallel but offset by more than the tolerance
+        let corner = vec![line((0.0, 0.0), (1.0, 0.0)), line((1.0, 0.0), (1.5, 0.01))];
+        let parallel = vec![line((0.0, 0.0), (2.0, 0.0)), line((1.0, 0.01), (3.0, 0.01))];
+        for lines in [crossing, corner, parallel] {
+            let expected = ends(&lines);
+            assert_eq!(ends(&simplify(lines, DEFAULT_SIMPLIFY_TOLERANCE)), expected);
+        }
+    }
+
+    #[test]
+    fn collinear_overlap_keeps_the_outer_end_points() {
+        let lines = vec![line((0.0, 0.0), (2.0, 0.0)), line((3.0, 0.0), (1.0, 0.0))];
+        assert_eq!(ends(&simplify(lines, DEFAULT_SIMPLIFY_TOLERANCE)), [((0.0, 0.0), (3.0, 0.0))]);
+    }
+
+    #[test]
+    fn lines_with_different_pens_are_not_merged() {
+        let mut thick = line((1.0, 0.0), (2.0, 0.0));
+        thick.stroke.width = 0.2;
+        let lines = vec![line((0.0, 0.0), (1.0, 0.0)), thick];
+        assert_eq!(simplify(lines, DEFAULT_SIMPLIFY_TOLERANCE).len(), 2);
+    }
+}
@@ -0,0 +1,176 @@
+//! Hatched fills
+//!
+//! Flex boards pour hatched ground to stay bendable, and fab drawings hatch areas for
+//! emphasis. `FillStyle` says how a zone or a filled polygon is filled: solid, one set of
+//! parallel lines, or two sets at right angles. KiCad zones hatch natively (see `zone`);
+//! everywhere else the hatch is drawn as lines, from `FillStyle::hatch_lines` or
+//! `expand_hatch`.
+//!
+//! Hatch lines lie on a grid fixed to the board origin, turned by the hatch angle, so
+//! neighbouring shapes hatched alike line up. Each line is clipped to the polygon with
+//! `geometry::clip_segment`, so its center line never leaves the polygon; the stroke
+//! overhangs the edge by half the line width, which the polygon's outline covers.
+
+use serde::{Deserialize, Serialize};
+
+use crate::board_interface::{GraphicElement, GraphicType, Rectangle, Stroke, StrokeType};
+use crate::geometry::{clip_segment, Point};
+use crate::ids::fresh_uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum FillStyle {
+    #[default]
+    Solid,
+    /// Parallel lines `width` wide and `pitch` apart center to center, at `angle` degrees
+    /// counter-clockwise from the X axis
+    Hatch { pitch: f32, width: f32, angle: f32 },
+    /// A hatch at `angle` and another at right angles to it
+    CrossHatch { pitch: f32, width: f32, angle: f32 },
+}
+
+impl FillStyle {
+    /// Pitch, line width and angle of a hatch, `None` for a solid fill
+    pub fn hatch(&self) -> Option<(f32, f32, f32)> {
+        match *self {
+            FillStyle::Solid => None,
+            FillStyle::Hatch { pitch, width, angle } | FillStyle::CrossHatch { pitch, width, angle } => Some((pitch, width, angle)),
+        }
+    }
+
+    /// Center lines of the hatch inside a closed polygon, empty for a solid fill or a
+    /// pitch that isn't positive
+    pub fn hatch_lines(&self, polygon: &[Point]) -> Vec<(Point, Point)> {
+        let Some((pitch, _, angle)) = self.hatch() else {
+            return Vec::new();
+        };
+        let mut lines = hatch_set(polygon, pitch, angle);
+        if matches!(self, FillStyle::CrossHatch { .. }) {
+            lines.extend(hatch_set(polygon, pitch, angle + 90.0));
+        }
+        lines
+    }
+}
+
+/// One set of parallel lines at `angle`, on lines half a pitch off the origin
+fn hatch_set(polygon: &[Point], pitch: f32, angle: f32) -> Vec<(Point, Point)> {
+    // Turned by -angle the lines run along X
+    if pitch.is_nan() || pitch <= 0.0 || polygon.len() < 3 {
+        return Vec::new();
+    }
+    let turned: Vec<Point> = polygon.iter().map(|point| point.rotated(-angle)).collect();
+    let Some(bounds) = Rectangle::enclosing(&turned) else {
+        return Vec::new();
+    };
+    let mut lines = Vec::new();
+    let mut row = (bounds.min_y / pitch - 0.5).ceil();
+    while (row + 0.5) * pitch <= bounds.max_y {
+        let y = (row + 0.5) * pitch;
+        let (start, end) = (Point::new(bounds.min_x - pitch, y), Point::new(bounds.max_x + pitch, y));
+        lines.extend(clip_segment(start, end, &turned).into_iter().map(|(a, b)| (a.rotated(angle), b.rotated(angle))));
+        row += 1.0;
+    }
+    lines
+}
+
+/// `element` ready for a target without hatched fills: a hatched polygon becomes its
+/// unfilled outline plus one line per hatch line, anything else is returned as it is
+pub fn expand_hatch(element: &GraphicElement) -> Vec<GraphicElement> {
+    let GraphicType::Polygon { points, fill: Some(fill) } = &element.element_type else {
+        return vec![element.clone()];
+    };
+    let Some((_, width, _)) = fill.hatch() else {
+        return vec![element.clone()];
+    };
+    let mut outline = element.clone();
+    outline.element_type = GraphicType::Polygon { points: points.clone(), fill: None };
+    let polygon: Vec<Point> = points.iter().map(|point| Point::from(*point)).collect();
+    let lines = fill.hatch_lines(&polygon).into_iter().map(|(start, end)| GraphicElement {
+        element_type: GraphicType::Line { start: (start.x, start.y), end: (end.x, end.y) },
+        layer: element.layer,
+        side: element.side,
+        stroke: Stroke { width, stroke_type: StrokeType::Solid },
+        uuid: fresh_uuid(),
+        role: None,
+    });
+    std::iter::once(outline).chain(lines).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::geometry::{point_in_polygon, point_segment_distance};
+    use crate::layer_type::{LayerType, Side};
+
+    fn square() -> Vec<Point> {
+        vec![Point::new(0.0, 0.0), Point::new(10.0, 0.0), Point::new(10.0, 10.0), Point::new(0.0, 10.0)]
+    }
+
+    fn ell() -> Vec<Point> {
+        vec![Point::new(0.0, 0.0), Point::new(8.0, 0.0), Point::new(8.0, 3.0), Point::new(3.0, 3.0), Point::new(3.0, 8.0), Point::new(0.0, 8.0)]
+    }
+
+    /// Whether a hatch line lies inside `polygon`, its ends allowed on the edge
+    fn inside(polygon: &[Point], (start, end): (Point, Point)) -> bool {
+        let on_edge = |point: Point| (0..polygon.len()).any(|i| point_segment_distance(point, polygon[i], polygon[(i + 1) % polygon.len()]).0 < 1e-4);
+        let middle = Point::new((start.x + end.x) / 2.0, (start.y + end.y) / 2.0);
+        [start, end].into_iter().all(|point| on_edge(point) || point_in_polygon(point, polygon)) && point_in_polygon(middle, polygon)
+    }
+
+    #[test]
+    fn cross_hatched_square_has_ten_lines_each_way() {
+        let lines = FillStyle::CrossHatch { pitch: 1.0, width: 0.2, angle: 0.0 }.hatch_lines(&square());
+        assert_eq!(lines.len(), 20);
+        assert!(lines.iter().all(|line| inside(&square(), *line)));
+        assert!(lines.iter().all(|(start, end)| (start.distance_to(end) - 10.0).abs() < 1e-4));
+        let horizontal = lines.iter().filter(|(start, end)| (start.y - end.y).abs() < 1e-4).count();
+        assert_eq!(horizontal, 10);
+    }
+
+    #[test]
+    fn lines_sit_half_a_pitch_off_the_origin() {
+        let lines = FillStyle::Hatch { pitch: 1.0, width: 0.2, angle: 0.0 }.hatch_lines(&square());
+        let mut rows: Vec<f32> = lines.iter().map(|(start, _)| start.y).collect();
+        rows.sort_by(f32::total_cmp);
+        assert!(rows.iter().enumerate().all(|(i, y)| (y - (i as f32 + 0.5)).abs() < 1e-4), "{:?}", rows);
+    }
+
+    #[test]
+    fn concave_shapes_are_clipped() {
+        let lines = FillStyle::Hatch { pitch: 0.5, width: 0.1, angle: 45.0 }.hatch_lines(&ell());
+        assert!(!lines.is_empty());
+        assert!(lines.iter().all(|line| inside(&ell(), *line)));
+        // Horizontal lines above the notch stop at x = 3
+        let above_notch = FillStyle::Hatch { pitch: 1.0, width: 0.1, angle: 0.0 }.hatch_lines(&ell());
+        assert!(above_notch.iter().filter(|(start, _)| start.y > 3.0).all(|(start, end)| start.x.max(end.x) <= 3.0 + 1e-4));
+    }
+
+    #[test]
+    fn solid_fills_and_bad_pitches_draw_nothing() {
+        assert!(FillStyle::Solid.hatch_lines(&square()).is_empty());
+        assert!(FillStyle::Hatch { pitch: 0.0, width: 0.1, angle: 0.0 }.hatch_lines(&square()).is_empty());
+        assert!(FillStyle::Hatch { pitch: f32::NAN, width: 0.1, angle: 0.0 }.hatch_lines(&square()).is_empty());
+    }
+
+    #[test]
+    fn expanded_hatch_is_an_outline_and_stroked_lines() {
+        let bounds = Rectangle { min_x: -2.0, min_y: -1.0, max_x: 2.0, max_y: 1.0 };
+        let element = GraphicElement {
+            element_type: GraphicType::filled_rectangle(&bounds, FillStyle::Hatch { pitch: 0.5, width: 0.1, angle: 0.0 }),
+            layer: LayerType::Fabrication,
+            side: Side::Front,
+            stroke: Stroke { width: 0.12, stroke_type: StrokeType::Solid },
+            uuid: Uuid::nil(),
+            role: None,
+        };
+        let expanded = expand_hatch(&element);
+        assert_eq!(expanded.len(), 5);
+        assert!(matches!(&expanded[0].element_type, GraphicType::Polygon { fill: None, .. }));
+        assert_eq!(expanded[0].stroke.width, 0.12);
+        assert!(expanded[1..].iter().all(|line| matches!(line.element_type, GraphicType::Line { .. }) && line.stroke.width == 0.1));
+
+        let solid = GraphicElement { element_type: GraphicType::filled_rectangle(&bounds, FillStyle::Solid), ..element };
+        assert_eq!(expand_hatch(&solid), vec![solid]);
+    }
+}
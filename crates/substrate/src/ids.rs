@@ -0,0 +1,39 @@
+//! Fresh element uuids
+//!
+//! With the `uuid-gen` feature, on by default, every new pad, text and graphic gets a
+//! random v4 uuid. Without it nothing pulls in a random number generator: uuids count up
+//! from a process-wide counter as v8 (custom) uuids, unique within the process and the
+//! same from run to run. Either way `with_uuid` sets a fixed one, and text uuids go
+//! through `Uuid::parse_str`.
+
+use uuid::Uuid;
+
+/// A uuid for a new element, see the module documentation
+#[cfg(feature = "uuid-gen")]
+pub fn fresh_uuid() -> Uuid {
+    Uuid::new_v4()
+}
+
+/// A uuid for a new element, see the module documentation
+#[cfg(not(feature = "uuid-gen"))]
+pub fn fresh_uuid() -> Uuid {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    /// Marks the counted uuids apart from other v8 uuids
+    const PREFIX: u64 = 0x636f_7070_6572_0000;
+    static NEXT: AtomicU64 = AtomicU64::new(1);
+    let count = NEXT.fetch_add(1, Ordering::Relaxed);
+    uuid::Builder::from_custom_bytes(((PREFIX as u128) << 64 | count as u128).to_be_bytes()).into_uuid()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fresh_uuids_differ() {
+        let (first, second) = (fresh_uuid(), fresh_uuid());
+        assert_ne!(first, second);
+        assert!(!first.is_nil());
+    }
+}
@@ -0,0 +1,434 @@
+//! Frequency dependent loss of routed nets
+//!
+//! `Board::insertion_loss_report` estimates the loss of every routed net at one frequency,
+//! in dB, from its tracks and the stackup under them. Tracks on the outer copper layers are
+//! microstrip over the nearest plane, tracks on inner layers stripline between the copper
+//! layers on either side, as in `Stackup::effective_epsilon_r`. Each net's tracks are
+//! grouped by layer and width into sections, and the loss of the net is the sum of its
+//! sections'.
+//!
+//! The loss of a section is split in two, using closed form approximations that agree
+//! with the usual rules of thumb to about 20%:
+//!
+//! * Conductor loss `8.686 R / 2Z0` dB per unit length. The AC resistance `R = ρ/(w δ)`
+//!   takes the current in one skin depth `δ = sqrt(ρ/(π f μ0))` across the strip width
+//!   (Bogatin's `36/(w Z0) sqrt(f)` dB/inch, w in mils), or the whole foil once it is
+//!   thinner than δ. Copper is smooth: roughness, which adds tens of percent above a few
+//!   GHz, isn't modelled.
+//! * Dielectric loss `27.3 q εr tanδ / (sqrt(εeff) λ0)` dB per unit length, where the
+//!   filling factor `q = (εeff - 1)/(εr - 1)` is the share of the field in the dielectric:
+//!   1 for stripline, giving Bogatin's `2.3 f tanδ sqrt(εr)` dB/inch with f in GHz.
+//!
+//! The impedance the conductor loss needs is Hammerstad and Wheeler's for microstrip (see
+//! `analysis::parasitics`), and IPC-2141's `60/sqrt(εr) ln(4b / (0.67π(0.8w + t)))` for
+//! stripline of plane spacing `b`, taken as centered between the planes. Permittivity and
+//! loss tangent are the stackup's, thickness weighted over the dielectrics the field is
+//! in; a stackup built from library materials has them at its design frequency, so for a
+//! sweep build one per frequency with `StackupBuilder::at_frequency`. Vias, pads and
+//! reflections are not counted. Boards without a stackup are taken as plain 1.6mm two
+//! layer boards.
+//!
+//! The math is available on its own, in SI units, for checking against published values.
+
+use std::collections::BTreeSet;
+use std::f64::consts::PI;
+use std::fmt;
+
+use crate::analysis::parasitics::MU_0;
+use crate::board::Board;
+use crate::stackup::{Stackup, StackupError};
+
+/// Resistivity of annealed copper at 20 °C, Ω·m
+pub const COPPER_RESISTIVITY: f64 = 1.724e-8;
+/// Speed of light in vacuum, m/s
+pub const SPEED_OF_LIGHT: f64 = 299_792_458.0;
+/// Decibels per neper
+const DB_PER_NEPER: f64 = 8.685_889;
+
+/// How a track's field is contained, from the layer it is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineGeometry {
+    /// Outer layer track over a plane, partly in air
+    Microstrip,
+    /// Inner layer track between two planes, entirely in dielectric
+    Stripline,
+}
+
+impl fmt::Display for LineGeometry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LineGeometry::Microstrip => "microstrip",
+            LineGeometry::Stripline => "stripline",
+        })
+    }
+}
+
+/// Skin depth of copper at `frequency` Hz, in meters
+pub fn skin_depth(frequency: f64) -> f64 {
+    (COPPER_RESISTIVITY / (PI * frequency * MU_0)).sqrt()
+}
+
+/// AC resistance per meter of a strip `width` wide and `thickness` thick (m) at
+/// `frequency` Hz, with the current in one skin depth or the whole foil if thinner
+pub fn conductor_resistance_per_meter(width: f64, thickness: f64, frequency: f64) -> f64 {
+    COPPER_RESISTIVITY / (width * skin_depth(frequency).min(thickness))
+}
+
+/// Conductor loss in dB/m of a strip `width` by `thickness` (m) of impedance `z0` Ω
+pub fn conductor_loss_db_per_meter(width: f64, thickness: f64, z0: f64, frequency: f64) -> f64 {
+    DB_PER_NEPER * conductor_resistance_per_meter(width, thickness, frequency) / (2.0 * z0)
+}
+
+/// Dielectric loss in dB/m of a line in a dielectric of `epsilon_r` and `loss_tangent`
+/// with effective permittivity `epsilon_r_effective` (equal to `epsilon_r` for stripline)
+pub fn dielectric_loss_db_per_meter(epsilon_r: f64, epsilon_r_effective: f64, loss_tangent: f64, frequency: f64) -> f64 {
+    let filling = if epsilon_r > 1.0 { ((epsilon_r_effective - 1.0) / (epsilon_r - 1.0)).clamp(0.0, 1.0) } else { 1.0 };
+    let wavelength = SPEED_OF_LIGHT / frequency;
+    PI * DB_PER_NEPER * filling * epsilon_r * loss_tangent / (epsilon_r_effective.sqrt() * wavelength)
+}
+
+/// Effective permittivity of microstrip `width` wide at `height` over its plane
+pub fn microstrip_effective_epsilon_r(width: f64, height: f64, epsilon_r: f64) -> f64 {
+    (epsilon_r + 1.0) / 2.0 + (epsilon_r - 1.0) / 2.0 / (1.0 + 12.0 * height / width).sqrt()
+}
+
+/// Characteristic impedance of microstrip `width` wide at `height` over its plane, Ω
+pub fn microstrip_impedance(width: f64, height: f64, epsilon_r_effective: f64) -> f64 {
+    let ratio = width / height;
+    if ratio <= 1.0 {
+        60.0 / epsilon_r_effective.sqrt() * (8.0 / ratio + ratio / 4.0).ln()
+    } else {
+        120.0 * PI / (epsilon_r_effective.sqrt() * (ratio + 1.393 + 0.667 * (ratio + 1.444).ln()))
+    }
+}
+
+/// Characteristic impedance of stripline `width` by `thickness` centered between planes
+/// `spacing` apart, Ω
+pub fn stripline_impedance(width: f64, thickness: f64, spacing: f64, epsilon_r: f64) -> f64 {
+    60.0 / epsilon_r.sqrt() * (4.0 * spacing / (0.67 * PI * (0.8 * width + thickness))).ln()
+}
+
+/// A net's tracks of one width on one layer
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineSection {
+    pub layer: String,
+    pub geometry: LineGeometry,
+    /// Millimeters
+    pub width: f32,
+    /// Millimeters
+    pub length: f32,
+    /// Ohms
+    pub impedance: f32,
+    pub epsilon_r_effective: f32,
+    /// dB
+    pub conductor_loss: f32,
+    /// dB
+    pub dielectric_loss: f32,
+}
+
+impl LineSection {
+    pub fn total_loss(&self) -> f32 {
+        self.conductor_loss + self.dielectric_loss
+    }
+}
+
+/// Estimated loss of one routed net, in dB
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetLoss {
+    pub net: String,
+    /// Routed track length in millimeters
+    pub length: f32,
+    pub conductor_loss: f32,
+    pub dielectric_loss: f32,
+    /// By layer, then width
+    pub sections: Vec<LineSection>,
+    /// Tracks on more than one layer: the sections are added up, the layer changes
+    /// between them are not counted
+    pub mixed_layers: bool,
+    /// What each section was computed from and what was left out
+    pub assumptions: Vec<String>,
+}
+
+impl NetLoss {
+    pub fn total_loss(&self) -> f32 {
+        self.conductor_loss + self.dielectric_loss
+    }
+}
+
+/// Loss of every routed net at one frequency, in net name order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InsertionLossReport {
+    pub frequency_ghz: f32,
+    pub nets: Vec<NetLoss>,
+}
+
+impl InsertionLossReport {
+    pub fn net(&self, name: &str) -> Option<&NetLoss> {
+        self.nets.iter().find(|net| net.net == name)
+    }
+
+    /// Table of all nets, most loss first
+    pub fn table(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for InsertionLossReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut nets: Vec<&NetLoss> = self.nets.iter().collect();
+        nets.sort_by(|a, b| b.total_loss().total_cmp(&a.total_loss()).then_with(|| a.net.cmp(&b.net)));
+        let width = nets.iter().map(|net| net.net.len()).chain(["Net".len()]).max().unwrap_or_default();
+        writeln!(f, "Insertion loss at {} GHz", self.frequency_ghz)?;
+        writeln!(
+            f,
+            "{:<width$}  {:>10}  {:>12}  {:>13}  {:>8}",
+            "Net", "Length mm", "Conductor dB", "Dielectric dB", "Total dB"
+        )?;
+        for net in nets {
+            write!(
+                f,
+                "{:<width$}  {:>10.3}  {:>12.4}  {:>13.4}  {:>8.4}",
+                net.net,
+                net.length,
+                net.conductor_loss,
+                net.dielectric_loss,
+                net.total_loss()
+            )?;
+            writeln!(f, "{}", if net.mixed_layers { "  mixed layers" } else { "" })?;
+        }
+        Ok(())
+    }
+}
+
+/// Geometry, impedance and loss per meter of tracks `width` wide on `layer`, with the
+/// assumption line describing them
+struct LineModel {
+    geometry: LineGeometry,
+    impedance: f64,
+    epsilon_r_effective: f64,
+    db_per_meter: (f64, f64),
+    assumption: String,
+}
+
+fn line_model(stackup: &Stackup, layer: &str, width: f32, frequency: f64) -> Result<LineModel, StackupError> {
+    let copper: Vec<&str> = stackup.copper_layers().map(|copper| copper.name.as_str()).collect();
+    let position = copper.iter().position(|&name| name == layer).ok_or_else(|| StackupError::UnknownLayer(layer.to_string()))?;
+    let foil = stackup.layer(layer).map_or(0.0, |copper| copper.thickness);
+    let thickness = foil as f64 * 1e-3;
+    let w = width as f64 * 1e-3;
+
+    if position == 0 || position == copper.len() - 1 {
+        let gap = stackup.reference_gap(layer)?;
+        let (h, er) = (gap.height as f64 * 1e-3, gap.epsilon_r as f64);
+        let tan_d = stackup.loss_tangent_between(layer, &gap.reference_layer)? as f64;
+        let er_eff = microstrip_effective_epsilon_r(w, h, er);
+        let z0 = microstrip_impedance(w, h, er_eff);
+        return Ok(LineModel {
+            geometry: LineGeometry::Microstrip,
+            impedance: z0,
+            epsilon_r_effective: er_eff,
+            db_per_meter: (
+                conductor_loss_db_per_meter(w, thickness, z0, frequency),
+                dielectric_loss_db_per_meter(er, er_eff, tan_d, frequency),
+            ),
+            assumption: format!(
+                "{} microstrip over {}: w = {} mm, t = {} mm, h = {:.4} mm, er = {:.2}, tan d = {:.4}, Z0 = {:.1} ohm",
+                layer,
+                gap.reference_layer,
+                width,
+                foil,
+                gap.height,
+                er,
+                tan_d,
+                z0
+            ),
+        });
+    }
+
+    let (above, below) = (copper[position - 1], copper[position + 1]);
+    let plane_thickness = |name: &str| stackup.layer(name).map_or(0.0, |plane| plane.thickness);
+    let spacing = stackup.layer_distance(above, below)? - (plane_thickness(above) + plane_thickness(below)) / 2.0;
+    let er = stackup.epsilon_r_between(above, below)? as f64;
+    let tan_d = stackup.loss_tangent_between(above, below)? as f64;
+    let z0 = stripline_impedance(w, thickness, spacing as f64 * 1e-3, er);
+    Ok(LineModel {
+        geometry: LineGeometry::Stripline,
+        impedance: z0,
+        epsilon_r_effective: er,
+        db_per_meter: (conductor_loss_db_per_meter(w, thickness, z0, frequency), dielectric_loss_db_per_meter(er, er, tan_d, frequency)),
+        assumption: format!(
+            "{} stripline between {} and {}: w = {} mm, t = {} mm, b = {:.4} mm, er = {:.2}, tan d = {:.4}, Z0 = {:.1} ohm",
+            layer,
+            above,
+            below,
+            width,
+            foil,
+            spacing,
+            er,
+            tan_d,
+            z0
+        ),
+    })
+}
+
+impl Board {
+    /// Conductor and dielectric loss of every net with tracks at `ghz`; see the module
+    /// documentation. Fails when a track is on a layer the stackup doesn't have, or an
+    /// outer layer has no plane to reference.
+    pub fn insertion_loss_report(&self, ghz: f32) -> Result<InsertionLossReport, StackupError> {
+        let default_stackup = self.stackup().is_none();
+        let stackup = self.stackup().cloned().unwrap_or_else(|| Stackup::two_layer(1.6));
+        let frequency = ghz as f64 * 1e9;
+
+        let nets: BTreeSet<&str> =
+            self.tracks().iter().map(|track| track.net.as_str()).chain(self.arcs().iter().map(|arc| arc.net.as_str())).collect();
+        let mut report = InsertionLossReport { frequency_ghz: ghz, nets: Vec::new() };
+        for net in nets {
+            // Length per layer and width, layers in stackup order
+            let mut lengths: Vec<(usize, String, f32, f32)> = Vec::new();
+            let tracks = self.tracks().iter().filter(|track| track.net == net).map(|track| (track.layer, track.width, track.start.distance_to(&track.end)));
+            let arcs = self.arcs().iter().filter(|arc| arc.net == net).map(|arc| (arc.layer, arc.width, arc.length()));
+            for (layer, track_width, length) in tracks.chain(arcs) {
+                let layer = layer.to_kicad_string();
+                let order = stackup.layers.iter().position(|l| l.name == layer).ok_or(StackupError::UnknownLayer(layer.clone()))?;
+                match lengths.iter_mut().find(|(_, l, width, _)| *l == layer && *width == track_width) {
+                    Some((.., total)) => *total += length,
+                    None => lengths.push((order, layer, track_width, length)),
+                }
+            }
+            lengths.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.2.total_cmp(&b.2)));
+
+            let mut sections = Vec::new();
+            let mut assumptions = Vec::new();
+            for (_, layer, width, length) in lengths {
+                let model = line_model(&stackup, &layer, width, frequency)?;
+                let meters = length as f64 * 1e-3;
+                sections.push(LineSection {
+                    layer,
+                    geometry: model.geometry,
+                    width,
+                    length,
+                    impedance: model.impedance as f32,
+                    epsilon_r_effective: model.epsilon_r_effective as f32,
+                    conductor_loss: (model.db_per_meter.0 * meters) as f32,
+                    dielectric_loss: (model.db_per_meter.1 * meters) as f32,
+                });
+                assumptions.push(model.assumption);
+            }
+
+            let layer_count = sections.iter().map(|section| section.layer.as_str()).collect::<BTreeSet<_>>().len();
+            if default_stackup {
+                assumptions.push("no stackup, taken as a 1.6mm two layer FR-4 board".to_string());
+            }
+            assumptions.push(format!("smooth copper, skin depth {:.2} um at {} GHz", skin_depth(frequency) * 1e6, ghz));
+            if layer_count > 1 {
+                assumptions.push(format!("mixed routing on {} layers, layer changes not counted", layer_count));
+            }
+            assumptions.push("vias, pads and reflections not counted".to_string());
+
+            report.nets.push(NetLoss {
+                net: net.to_string(),
+                length: sections.iter().map(|section| section.length).sum(),
+                conductor_loss: sections.iter().map(|section| section.conductor_loss).sum(),
+                dielectric_loss: sections.iter().map(|section| section.dielectric_loss).sum(),
+                sections,
+                mixed_layers: layer_count > 1,
+                assumptions,
+            });
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Point;
+    use crate::layer_type::{BoardLayer, LayerType};
+    use crate::routing::Track;
+
+    const METERS_PER_INCH: f64 = 0.0254;
+
+    fn assert_within(value: f64, expected: f64, tolerance: f64) {
+        assert!((value - expected).abs() <= expected.abs() * tolerance, "{} is not within {}% of {}", value, tolerance * 100.0, expected);
+    }
+
+    #[test]
+    fn skin_depth_of_copper() {
+        // sqrt(ρ / (π f μ0)) = 2.09 µm at 1 GHz, 6.61 µm at 100 MHz
+        assert_within(skin_depth(1e9), 2.0897e-6, 1e-3);
+        assert_within(skin_depth(1e8), 6.6082e-6, 1e-3);
+    }
+
+    #[test]
+    fn conductor_loss_follows_the_rule_of_thumb() {
+        // Bogatin: 36 / (w Z0) sqrt(f) dB/inch for w in mils and f in GHz, 5 mil 50 ohm line
+        let (w, t) = (5.0 * 25.4e-6, 35e-6);
+        assert_within(conductor_loss_db_per_meter(w, t, 50.0, 1e9) * METERS_PER_INCH, 0.144, 0.2);
+        assert_within(conductor_loss_db_per_meter(w, t, 50.0, 10e9) * METERS_PER_INCH, 0.455, 0.2);
+        // Below about 3.5 MHz 1 oz foil is thinner than a skin depth and carries current
+        // through its whole thickness
+        assert_within(conductor_resistance_per_meter(w, t, 1e6), COPPER_RESISTIVITY / (w * t), 1e-9);
+    }
+
+    #[test]
+    fn dielectric_loss_follows_the_rule_of_thumb() {
+        // Bogatin: 2.3 f tanδ sqrt(εr) dB/inch, f in GHz
+        assert_within(dielectric_loss_db_per_meter(4.0, 4.0, 0.02, 1e9) * METERS_PER_INCH, 0.092, 0.2);
+        let ratio = dielectric_loss_db_per_meter(4.0, 4.0, 0.02, 10e9) / dielectric_loss_db_per_meter(4.0, 4.0, 0.02, 1e9);
+        assert_within(ratio, 10.0, 1e-9);
+        // Part of a microstrip's field is in air, so it loses less than a stripline
+        let er_eff = microstrip_effective_epsilon_r(0.4e-3, 0.2e-3, 4.4);
+        assert!(dielectric_loss_db_per_meter(4.4, er_eff, 0.02, 1e9) < dielectric_loss_db_per_meter(4.4, 4.4, 0.02, 1e9));
+    }
+
+    #[test]
+    fn microstrip_twice_as_wide_as_high_is_about_fifty_ohm() {
+        let er_eff = microstrip_effective_epsilon_r(0.4e-3, 0.2e-3, 4.4);
+        assert_within(microstrip_impedance(0.4e-3, 0.2e-3, er_eff), 50.0, 0.1);
+    }
+
+    #[test]
+    fn stripline_impedance_matches_ipc_2141() {
+        // 60 / sqrt(4.3) ln(4 b / (0.67 π (0.8 w + t))) with w 0.15, t 0.035 and b 0.5 mm
+        assert_within(stripline_impedance(0.15e-3, 35e-6, 0.5e-3, 4.3), 52.465, 1e-3);
+        assert!(stripline_impedance(0.1e-3, 35e-6, 0.5e-3, 4.3) > stripline_impedance(0.15e-3, 35e-6, 0.5e-3, 4.3));
+    }
+
+    #[test]
+    fn report_tells_microstrip_from_stripline() {
+        let mut board = Board::new("loss").with_stackup(Stackup::four_layer_default());
+        let (front, inner) = (BoardLayer::front(LayerType::Copper), BoardLayer::inner_copper(1));
+        board.add_track(Track::new("CLK", front, Point::new(5.0, 5.0), Point::new(30.4, 5.0), 0.3));
+        board.add_track(Track::new("DDR", inner, Point::new(5.0, 10.0), Point::new(30.4, 10.0), 0.15));
+        board.add_track(Track::new("MIX", front, Point::new(5.0, 20.0), Point::new(15.0, 20.0), 0.3));
+        board.add_track(Track::new("MIX", inner, Point::new(15.0, 20.0), Point::new(25.0, 20.0), 0.15));
+
+        let report = board.insertion_loss_report(1.0).unwrap();
+        let clk = report.net("CLK").unwrap();
+        assert_eq!((clk.sections.len(), clk.sections[0].geometry, clk.mixed_layers), (1, LineGeometry::Microstrip, false));
+        let expected = conductor_loss_db_per_meter(0.3e-3, 35e-6, clk.sections[0].impedance as f64, 1e9) * METERS_PER_INCH;
+        assert_within(clk.conductor_loss as f64, expected, 1e-3);
+
+        let ddr = report.net("DDR").unwrap();
+        assert_eq!(ddr.sections[0].geometry, LineGeometry::Stripline);
+        let er = Stackup::four_layer_default().epsilon_r_between("F.Cu", "In2.Cu").unwrap() as f64;
+        assert_within(ddr.dielectric_loss as f64, dielectric_loss_db_per_meter(er, er, 0.02, 1e9) * METERS_PER_INCH, 1e-3);
+
+        let mix = report.net("MIX").unwrap();
+        assert!(mix.mixed_layers);
+        assert_eq!(mix.sections.iter().map(|section| section.layer.as_str()).collect::<Vec<_>>(), ["F.Cu", "In1.Cu"]);
+
+        let high = board.insertion_loss_report(10.0).unwrap();
+        let clk_high = high.net("CLK").unwrap();
+        assert_within((clk_high.dielectric_loss / clk.dielectric_loss) as f64, 10.0, 0.01);
+        assert_within((clk_high.conductor_loss / clk.conductor_loss) as f64, 10f64.sqrt(), 0.01);
+    }
+
+    #[test]
+    fn inner_layers_need_a_stackup() {
+        let mut board = Board::new("plain");
+        board.add_track(Track::new("B", BoardLayer::inner_copper(1), Point::new(1.0, 5.0), Point::new(10.0, 5.0), 0.25));
+        assert!(matches!(board.insertion_loss_report(1.0), Err(StackupError::UnknownLayer(_))));
+    }
+}
@@ -1,4 +1,11 @@
-#[derive(Debug, Clone)]
+use compact_str::CompactString;
+use serde::{Deserialize, Serialize};
+
+/// A KiCad layer name as pads and texts list it, e.g. "F.Cu" or "*.Mask". Every name KiCad
+/// defines fits inline, so layer lists don't allocate a string per name.
+pub type LayerName = CompactString;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum LayerType {
     SilkScreen,    // F.SilkS - visible markings
     Courtyard,     // F.CrtYd - component boundary
@@ -19,4 +26,249 @@ impl LayerType {
             LayerType::Paste => "F.Paste",
         }
     }
-}
\ No newline at end of file
+
+    /// KiCad layer name suffix, i.e. the part after "F." or "B."
+    pub fn kicad_suffix(&self) -> &'static str {
+        match self {
+            LayerType::SilkScreen => "SilkS",
+            LayerType::Courtyard => "CrtYd",
+            LayerType::Fabrication => "Fab",
+            LayerType::Copper => "Cu",
+            LayerType::Mask => "Mask",
+            LayerType::Paste => "Paste",
+        }
+    }
+
+    pub fn from_kicad_suffix(suffix: &str) -> Option<Self> {
+        match suffix {
+            "SilkS" | "Silkscreen" => Some(LayerType::SilkScreen),
+            "CrtYd" | "Courtyard" => Some(LayerType::Courtyard),
+            "Fab" => Some(LayerType::Fabrication),
+            "Cu" => Some(LayerType::Copper),
+            "Mask" => Some(LayerType::Mask),
+            "Paste" => Some(LayerType::Paste),
+            _ => None,
+        }
+    }
+}
+
+/// Whether KiCad layer name `pattern` covers `layer` (e.g. "F.Cu"), honouring the `*.Cu`
+/// and `F&B.Cu` wildcards
+pub fn kicad_layer_matches(pattern: &str, layer: &str) -> bool {
+    let Some((side, kind)) = layer.split_once('.') else {
+        return pattern == layer;
+    };
+    match pattern.split_once('.') {
+        Some(("*", k)) => k == kind,
+        Some(("F&B", k)) => k == kind && (side == "F" || side == "B"),
+        _ => pattern == layer,
+    }
+}
+
+/// Which side of the board a layer belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Side {
+    Front,
+    Back,
+    /// Inner copper layer, numbered from 1 like KiCad's In1.Cu
+    Inner(u8),
+}
+
+/// Outer sides a pad is on, derived from its layer list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PadSide {
+    Front,
+    Back,
+    /// Through hole pads, and surface pads of two-sided footprints listed on both sides
+    Both,
+}
+
+impl PadSide {
+    pub fn has_front(&self) -> bool {
+        matches!(self, PadSide::Front | PadSide::Both)
+    }
+
+    pub fn has_back(&self) -> bool {
+        matches!(self, PadSide::Back | PadSide::Both)
+    }
+}
+
+/// A layer kind on a specific side, e.g. B.SilkS or In2.Cu
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BoardLayer {
+    pub layer: LayerType,
+    pub side: Side,
+}
+
+impl BoardLayer {
+    pub fn new(layer: LayerType, side: Side) -> Self {
+        Self { layer, side }
+    }
+
+    pub fn front(layer: LayerType) -> Self {
+        Self { layer, side: Side::Front }
+    }
+
+    pub fn back(layer: LayerType) -> Self {
+        Self { layer, side: Side::Back }
+    }
+
+    pub fn inner_copper(index: u8) -> Self {
+        Self { layer: LayerType::Copper, side: Side::Inner(index) }
+    }
+
+    pub fn to_kicad_string(&self) -> String {
+        match self.side {
+            Side::Front => format!("F.{}", self.layer.kicad_suffix()),
+            Side::Back => format!("B.{}", self.layer.kicad_suffix()),
+            Side::Inner(index) => format!("In{}.{}", index, self.layer.kicad_suffix()),
+        }
+    }
+
+    /// Parse a single concrete KiCad layer name such as "B.Paste" or "In3.Cu".
+    /// Wildcard names like "*.Cu" are not single layers and return `None`.
+    pub fn from_kicad_str(name: &str) -> Option<Self> {
+        let (side, suffix) = name.split_once('.')?;
+        let layer = LayerType::from_kicad_suffix(suffix)?;
+        let side = match side {
+            "F" => Side::Front,
+            "B" => Side::Back,
+            inner => match inner.strip_prefix("In")?.parse::<u8>() {
+                Ok(index) if layer == LayerType::Copper && index > 0 => Side::Inner(index),
+                _ => return None,
+            },
+        };
+        Some(Self { layer, side })
+    }
+}
+
+impl From<LayerType> for BoardLayer {
+    /// Layer types on their own refer to the front side, as in `to_kicad_string`
+    fn from(layer: LayerType) -> Self {
+        BoardLayer::front(layer)
+    }
+}
+
+
+/// Canonical KiCad layer names that are not tied to a side
+const KICAD_SIDELESS_LAYERS: &[&str] = &["Dwgs.User", "Cmts.User", "Eco1.User", "Eco2.User", "Edge.Cuts", "Margin"];
+
+/// Suffixes that exist on both outer sides ("F.Adhes", "B.Adhes", ...)
+const KICAD_SIDED_SUFFIXES: &[&str] = &["Cu", "Adhes", "Paste", "SilkS", "Mask", "CrtYd", "Fab"];
+
+/// KiCad supports up to 30 inner copper layers
+pub const KICAD_MAX_INNER_COPPER: u8 = 30;
+
+/// KiCad 9 numbers its user layers User.1 to User.45
+pub const KICAD_MAX_USER_LAYER: u8 = 45;
+
+/// Whether `name` is a layer KiCad accepts in a footprint, including inner copper
+/// ("In1.Cu"), user layers ("User.3") and pad wildcards ("*.Cu", "F&B.Cu"). Names are
+/// case sensitive, "F.paste" is rejected.
+pub fn is_kicad_layer_name(name: &str) -> bool {
+    if KICAD_SIDELESS_LAYERS.contains(&name) {
+        return true;
+    }
+    let Some((prefix, suffix)) = name.split_once('.') else {
+        return false;
+    };
+    match prefix {
+        "F" | "B" | "*" => KICAD_SIDED_SUFFIXES.contains(&suffix),
+        "F&B" => suffix == "Cu",
+        "User" => suffix.parse::<u8>().is_ok_and(|index| (1..=KICAD_MAX_USER_LAYER).contains(&index)),
+        inner => {
+            suffix == "Cu"
+                && inner
+                    .strip_prefix("In")
+                    .and_then(|index| index.parse::<u8>().ok())
+                    .is_some_and(|index| (1..=KICAD_MAX_INNER_COPPER).contains(&index))
+        }
+    }
+}
+
+/// Long suffixes KiCad 7 and later show in the UI, which some tools write into files
+const KICAD_SUFFIX_ALIASES: &[(&str, &str)] =
+    &[("Silkscreen", "SilkS"), ("Courtyard", "CrtYd"), ("Adhesive", "Adhes"), ("Fabrication", "Fab")];
+
+/// UI names of the sideless user layers
+const KICAD_SIDELESS_ALIASES: &[(&str, &str)] = &[
+    ("User.Drawings", "Dwgs.User"),
+    ("User.Comments", "Cmts.User"),
+    ("User.Eco1", "Eco1.User"),
+    ("User.Eco2", "Eco2.User"),
+];
+
+/// The name KiCad writes in files for `name`, which may use a UI name ("F.Silkscreen",
+/// "User.Drawings") or the wrong case ("f.cu"). `None` when `name` isn't a KiCad layer in
+/// any spelling.
+pub fn canonical_kicad_layer_name(name: &str) -> Option<String> {
+    if is_kicad_layer_name(name) {
+        return Some(name.to_string());
+    }
+    let sideless = KICAD_SIDELESS_LAYERS.iter().map(|layer| (*layer, *layer)).chain(KICAD_SIDELESS_ALIASES.iter().copied());
+    if let Some((_, canonical)) = sideless.into_iter().find(|(alias, _)| alias.eq_ignore_ascii_case(name)) {
+        return Some(canonical.to_string());
+    }
+    let (prefix, suffix) = name.split_once('.')?;
+    let suffix = KICAD_SIDED_SUFFIXES
+        .iter()
+        .map(|suffix| (*suffix, *suffix))
+        .chain(KICAD_SUFFIX_ALIASES.iter().copied())
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(suffix))?
+        .1;
+    let upper = prefix.to_ascii_uppercase();
+    let prefix = match upper.strip_prefix("IN") {
+        Some(index) => format!("In{}", index),
+        None => upper,
+    };
+    let canonical = format!("{}.{}", prefix, suffix);
+    is_kicad_layer_name(&canonical).then_some(canonical)
+}
+
+/// Standard pad layer sets, so footprints don't spell layer names by hand
+pub struct PadLayers;
+
+impl PadLayers {
+    /// Surface mount pad on the front: copper, paste and mask
+    pub fn smd_front() -> Vec<LayerName> {
+        Self::strings(&["F.Cu", "F.Paste", "F.Mask"])
+    }
+
+    /// Surface mount pad on the back: copper, paste and mask
+    pub fn smd_back() -> Vec<LayerName> {
+        Self::strings(&["B.Cu", "B.Paste", "B.Mask"])
+    }
+
+    /// Plated through hole pad: every copper layer and both masks
+    pub fn tht() -> Vec<LayerName> {
+        Self::strings(&["*.Cu", "*.Mask"])
+    }
+
+    /// Plated through hole with copper only, the mask closed over it; for thermal vias
+    pub fn tht_tented() -> Vec<LayerName> {
+        Self::strings(&["*.Cu"])
+    }
+
+    /// Paste aperture without copper or mask, for stencil fiducials and alignment marks
+    pub fn paste_front() -> Vec<LayerName> {
+        Self::strings(&["F.Paste"])
+    }
+
+    pub fn paste_back() -> Vec<LayerName> {
+        Self::strings(&["B.Paste"])
+    }
+
+    /// Non-plated hole with a mask opening and no copper
+    pub fn npth() -> Vec<LayerName> {
+        Self::strings(&["*.Mask"])
+    }
+
+    /// Non-plated hole drilled through the mask, with no opening of its own
+    pub fn npth_unmasked() -> Vec<LayerName> {
+        Vec::new()
+    }
+
+    fn strings(layers: &[&str]) -> Vec<LayerName> {
+        layers.iter().map(|layer| LayerName::from(*layer)).collect()
+    }
+}
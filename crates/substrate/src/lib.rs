@@ -1,6 +1,73 @@
+pub mod analysis;
+pub mod assembly;
+pub mod assembly_process;
+pub mod anchor;
+pub mod artwork;
+pub mod autoroute;
+pub mod backdrill;
+pub mod block;
+pub mod board;
+pub mod board_drc;
 pub mod board_interface; 
+pub mod chip;
+pub mod connector_marks;
+pub mod copper_density;
 pub mod courtyard;
+pub mod crystal;
+pub mod describe;
+pub mod dimension;
+pub mod dip;
+pub mod dpak;
+pub mod edge_mount;
+pub mod exposed_copper;
+pub mod fab_profile;
+pub mod fanout;
+pub mod fixed_geometry;
+pub mod footprint;
 pub mod functional_types;
+pub mod geometry;
+pub mod graphics;
+pub mod hatch;
+pub mod ids;
+pub mod insertion_loss;
 pub mod layer_type;
+pub mod materials;
+pub mod mounting_hole;
+pub mod net_length;
+pub mod netlist;
+pub mod occupancy;
+pub mod package_outlines;
 pub mod package_types;
-pub mod prelude;
\ No newline at end of file
+pub mod pad_geometry;
+pub mod pad_groups;
+pub mod padstack;
+pub mod paste_windows;
+pub mod pin1;
+pub mod pin_in_paste;
+pub mod placement;
+pub mod prelude;
+pub mod presets;
+pub mod qfn;
+#[cfg(feature = "render-traits")]
+pub mod render;
+pub mod resolve;
+pub mod roles;
+pub mod routing;
+pub mod routing_hint;
+pub mod rule_area;
+pub mod scaling;
+pub mod silk_minimums;
+pub mod snapshot;
+pub mod soic;
+pub mod solder_jumper;
+pub mod spatial_index;
+pub mod stackup;
+pub mod stencil;
+pub mod stroke_font;
+pub mod test_coverage;
+pub mod text_policy;
+pub mod thermal_balance;
+pub mod thermal_vias;
+pub mod thieving;
+pub mod tolerance;
+pub mod zone;
\ No newline at end of file
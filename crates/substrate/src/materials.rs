@@ -0,0 +1,202 @@
+//! Dielectric material library
+//!
+//! Named laminates (cores) and prepreg glass styles with their pressed thickness,
+//! permittivity, loss tangent and resin content, so stackups can be built from names
+//! instead of hand typed numbers (see `Stackup::builder`).
+//!
+//! Permittivity falls slowly with frequency. Each material gives εr at 1 GHz and at 10 GHz
+//! and values in between (and beyond) are interpolated linearly in log frequency, which
+//! is close enough for FR-4 class materials over the range boards are designed for.
+//!
+//! The built-in values are typical datasheet numbers; a fab's own materials can be added
+//! from TOML:
+//!
+//! ```toml
+//! [[material]]
+//! name = "IT-180A"
+//! kind = "core"
+//! thickness = 0.2
+//! epsilon_r_1ghz = 4.3
+//! epsilon_r_10ghz = 4.1
+//! loss_tangent = 0.015
+//! resin_content = 52
+//! ```
+//!
+//! Prepreg thickness is per ply, core thickness is the nominal laminate thickness.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Frequency the stackup builder evaluates permittivity at unless told otherwise
+pub const DEFAULT_DESIGN_FREQUENCY_GHZ: f32 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaterialKind {
+    /// Cured laminate, copper clad on both sides
+    Core,
+    /// Glass cloth and uncured resin bonding cores and foils
+    Prepreg,
+}
+
+impl fmt::Display for MaterialKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MaterialKind::Core => "core",
+            MaterialKind::Prepreg => "prepreg",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DielectricMaterial {
+    pub name: String,
+    pub kind: MaterialKind,
+    /// Millimeters per ply for prepreg, nominal laminate thickness for cores
+    pub thickness: f32,
+    pub epsilon_r_1ghz: f32,
+    pub epsilon_r_10ghz: f32,
+    pub loss_tangent: f32,
+    /// Resin content in percent by weight, `None` where the datasheet gives none
+    #[serde(default)]
+    pub resin_content: Option<f32>,
+}
+
+impl DielectricMaterial {
+    /// εr at `ghz`, from the two-point model in the module documentation
+    pub fn epsilon_r_at(&self, ghz: f32) -> f32 {
+        let decades = ghz.max(f32::EPSILON).log10();
+        (self.epsilon_r_1ghz + (self.epsilon_r_10ghz - self.epsilon_r_1ghz) * decades).max(1.0)
+    }
+
+    fn validate(&self) -> Result<(), MaterialError> {
+        let values = [
+            ("thickness", self.thickness),
+            ("epsilon_r_1ghz", self.epsilon_r_1ghz),
+            ("epsilon_r_10ghz", self.epsilon_r_10ghz),
+        ];
+        for (field, value) in values {
+            if !(value.is_finite() && value > 0.0) {
+                return Err(MaterialError::Invalid(format!("{}: {} must be positive, got {}", self.name, field, value)));
+            }
+        }
+        if !(self.loss_tangent.is_finite() && self.loss_tangent >= 0.0) {
+            return Err(MaterialError::Invalid(format!("{}: loss_tangent must not be negative, got {}", self.name, self.loss_tangent)));
+        }
+        if let Some(resin) = self.resin_content.filter(|resin| !(0.0..=100.0).contains(resin)) {
+            return Err(MaterialError::Invalid(format!("{}: resin_content must be a percentage, got {}", self.name, resin)));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MaterialError {
+    /// No material of that name in the library
+    Unknown(String),
+    /// A core used as prepreg or the other way around
+    WrongKind { name: String, expected: MaterialKind },
+    Parse(String),
+    Invalid(String),
+}
+
+impl fmt::Display for MaterialError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MaterialError::Unknown(name) => write!(f, "unknown material \"{}\"", name),
+            MaterialError::WrongKind { name, expected } => write!(f, "material \"{}\" is not a {}", name, expected),
+            MaterialError::Parse(message) => write!(f, "invalid material file: {}", message),
+            MaterialError::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for MaterialError {}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MaterialFile {
+    #[serde(default)]
+    material: Vec<DielectricMaterial>,
+}
+
+/// Materials by name, see the module documentation
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MaterialLibrary {
+    materials: BTreeMap<String, DielectricMaterial>,
+}
+
+impl MaterialLibrary {
+    /// Common FR-4 laminates, Rogers 4350B, polyimide and the 106, 1080, 2116 and 7628
+    /// prepreg glass styles
+    pub fn builtin() -> Self {
+        let material = |name: &str, kind, thickness, er_1ghz, er_10ghz, loss_tangent, resin_content| DielectricMaterial {
+            name: name.to_string(),
+            kind,
+            thickness,
+            epsilon_r_1ghz: er_1ghz,
+            epsilon_r_10ghz: er_10ghz,
+            loss_tangent,
+            resin_content,
+        };
+        let mut library = Self::default();
+        for entry in [
+            material("FR-4", MaterialKind::Core, 1.065, 4.6, 4.4, 0.020, Some(45.0)),
+            material("FR-4 High Tg", MaterialKind::Core, 1.065, 4.5, 4.3, 0.017, Some(45.0)),
+            material("FR-4 Low Loss", MaterialKind::Core, 1.065, 3.9, 3.8, 0.009, Some(50.0)),
+            material("Rogers 4350B", MaterialKind::Core, 0.254, 3.50, 3.48, 0.0037, None),
+            material("Polyimide", MaterialKind::Core, 0.05, 3.5, 3.4, 0.008, None),
+            material("106", MaterialKind::Prepreg, 0.0508, 3.9, 3.7, 0.021, Some(72.0)),
+            material("1080", MaterialKind::Prepreg, 0.0711, 4.0, 3.8, 0.020, Some(65.0)),
+            material("2116", MaterialKind::Prepreg, 0.1143, 4.2, 4.0, 0.019, Some(55.0)),
+            material("7628", MaterialKind::Prepreg, 0.2104, 4.4, 4.2, 0.018, Some(49.0)),
+        ] {
+            library.materials.insert(entry.name.clone(), entry);
+        }
+        library
+    }
+
+    /// Add a material, replacing any of the same name
+    pub fn register(&mut self, material: DielectricMaterial) -> Result<(), MaterialError> {
+        material.validate()?;
+        self.materials.insert(material.name.clone(), material);
+        Ok(())
+    }
+
+    /// Register every `[[material]]` of a TOML file
+    pub fn load_toml(&mut self, text: &str) -> Result<(), MaterialError> {
+        let file: MaterialFile = toml::from_str(text).map_err(|e| MaterialError::Parse(e.to_string().trim_end().to_string()))?;
+        for material in &file.material {
+            material.validate()?;
+        }
+        for material in file.material {
+            self.materials.insert(material.name.clone(), material);
+        }
+        Ok(())
+    }
+
+    pub fn with_toml(mut self, text: &str) -> Result<Self, MaterialError> {
+        self.load_toml(text)?;
+        Ok(self)
+    }
+
+    pub fn get(&self, name: &str) -> Result<&DielectricMaterial, MaterialError> {
+        self.materials.get(name).ok_or_else(|| MaterialError::Unknown(name.to_string()))
+    }
+
+    /// `name`, which must be a `kind` material
+    pub fn get_kind(&self, name: &str, kind: MaterialKind) -> Result<&DielectricMaterial, MaterialError> {
+        let material = self.get(name)?;
+        if material.kind != kind {
+            return Err(MaterialError::WrongKind { name: name.to_string(), expected: kind });
+        }
+        Ok(material)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.materials.keys().map(String::as_str)
+    }
+}
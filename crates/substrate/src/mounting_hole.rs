@@ -0,0 +1,181 @@
+//! Mounting holes and slots
+//!
+//! A bare non-plated hole for a screw, standoff or alignment pin, round or slotted. The
+//! hole has no copper and no pad number, so it is no terminal, trips no copper check and
+//! stays out of the netlist, the paste layers and the BOM and placement files; the drill
+//! files list it with the other non-plated holes. The solder mask opens over the hole
+//! unless `without_mask_opening` says otherwise.
+//!
+//! A screw head or washer, when given, is drawn on F.Fab and kept inside the courtyard.
+//! Names follow KiCad's "MountingHole_3.2mm_M3"; slots are "MountingHole_Slot_1.2x2.4mm".
+
+use crate::board_interface::{
+    BoardComposableObject, FpText, FpTextType, GraphicElement, GraphicType, Model3D, PadDescriptor,
+    PadShape, PadType, Rectangle, Stroke, StrokeType, TentingSettings, TentingType,
+};
+use crate::functional_types::FunctionalType;
+use crate::ids::fresh_uuid;
+use crate::layer_type::{LayerType, PadLayers, Side};
+
+const FAB_LINE_WIDTH: f32 = 0.1;
+/// Gap between the hole, or the head around it, and the reference text
+const TEXT_CLEARANCE: f32 = 1.0;
+
+/// ISO 273 medium clearance holes and a head twice the thread size, as in KiCad's
+/// MountingHole library: thread, hole diameter, head diameter
+const SCREWS: [(&str, f32, f32); 6] =
+    [("M2", 2.2, 4.0), ("M2.5", 2.7, 5.0), ("M3", 3.2, 6.0), ("M4", 4.3, 8.0), ("M5", 5.3, 10.0), ("M6", 6.4, 12.0)];
+
+/// A non-plated mounting hole or slot; see the module documentation
+#[derive(Debug, Clone)]
+pub struct MountingHole {
+    /// Hole width along X by height along Y, equal for a round hole; the longer side of
+    /// a slot sets its direction
+    pub drill: (f32, f32),
+    /// Screw head or washer diameter the hole leaves room for
+    pub head: Option<f32>,
+    /// Thread the hole is sized for, e.g. "M3"
+    pub screw: Option<String>,
+    pub mask_opening: bool,
+}
+
+impl MountingHole {
+    /// A round hole of `diameter`
+    pub fn new(diameter: f32) -> Self {
+        Self { drill: (diameter, diameter), head: None, screw: None, mask_opening: true }
+    }
+
+    /// The clearance hole for a metric screw from M2 to M6, with room for its head;
+    /// `None` for other threads
+    pub fn for_screw(thread: &str) -> Option<Self> {
+        let (screw, diameter, head) = SCREWS.iter().find(|(screw, _, _)| *screw == thread)?;
+        Some(Self { head: Some(*head), screw: Some(screw.to_string()), ..Self::new(*diameter) })
+    }
+
+    /// A slot `width` along X by `height` along Y, for alignment pins and card retention
+    pub fn slot(width: f32, height: f32) -> Self {
+        Self { drill: (width, height), ..Self::new(width.min(height)) }
+    }
+
+    pub fn with_head(mut self, diameter: f32) -> Self {
+        self.head = Some(diameter);
+        self
+    }
+
+    /// Leave the solder mask over the board around the hole
+    pub fn without_mask_opening(mut self) -> Self {
+        self.mask_opening = false;
+        self
+    }
+
+    pub fn is_slot(&self) -> bool {
+        self.drill.0 != self.drill.1
+    }
+
+    fn text(text_type: FpTextType, text: String, y: f32, layer: &str) -> FpText {
+        FpText::new(text_type, text, (0.0, y), layer)
+    }
+}
+
+impl BoardComposableObject for MountingHole {
+    fn is_smt(&self) -> bool {
+        false
+    }
+
+    fn is_electrical(&self) -> bool {
+        false
+    }
+
+    fn terminal_count(&self) -> usize {
+        0
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        FunctionalType::MountingHole(self.screw.clone().unwrap_or_else(|| self.footprint_name()))
+    }
+
+    fn footprint_name(&self) -> String {
+        match (&self.screw, self.is_slot()) {
+            (_, true) => format!("MountingHole_Slot_{}x{}mm", self.drill.0, self.drill.1),
+            (Some(screw), false) => format!("MountingHole_{}mm_{}", self.drill.0, screw),
+            (None, false) => format!("MountingHole_{}mm", self.drill.0),
+        }
+    }
+
+    fn library_name(&self) -> String {
+        "MountingHole".to_string()
+    }
+
+    /// The head where there is one, else the hole
+    fn bounding_box(&self) -> Rectangle {
+        let (half_x, half_y) = match self.head {
+            Some(head) => (head.max(self.drill.0) / 2.0, head.max(self.drill.1) / 2.0),
+            None => (self.drill.0 / 2.0, self.drill.1 / 2.0),
+        };
+        Rectangle { min_x: -half_x, min_y: -half_y, max_x: half_x, max_y: half_y }
+    }
+
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        vec![PadDescriptor {
+            number: "".into(),
+            pin_function: None,
+            pad_type: PadType::NPTH,
+            shape: if self.is_slot() { PadShape::Oval } else { PadShape::Circle },
+            position: (0.0, 0.0),
+            rotation: None,
+            size: self.drill,
+            drill_size: Some(self.drill.0.min(self.drill.1)),
+            drill_slot: self.is_slot().then_some(self.drill),
+            layers: if self.mask_opening { PadLayers::npth() } else { PadLayers::npth_unmasked() },
+            roundrect_ratio: None,
+            tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+            padstack: None,
+            fab_property: None,
+            solder_mask_margin: None,
+            routing_hint: None,
+            uuid: fresh_uuid(),
+        }]
+    }
+
+    fn description(&self) -> Option<String> {
+        let hole = if self.is_slot() {
+            format!("Mounting slot {}x{}mm", self.drill.0, self.drill.1)
+        } else {
+            format!("Mounting Hole {}mm", self.drill.0)
+        };
+        let screw = self.screw.as_ref().map_or_else(String::new, |screw| format!(", {}", screw));
+        Some(format!("{}, no annular{}", hole, screw))
+    }
+
+    fn tags(&self) -> Option<String> {
+        let screw = self.screw.as_ref().map_or_else(String::new, |screw| format!(" {}", screw));
+        Some(format!("mounting hole {}mm no annular{}", self.drill.0.min(self.drill.1), screw))
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        let text_y = self.bounding_box().max_y + TEXT_CLEARANCE;
+        vec![
+            Self::text(FpTextType::Reference, "REF**".to_string(), -text_y, "F.SilkS"),
+            Self::text(FpTextType::Value, self.footprint_name(), text_y, "F.Fab"),
+        ]
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        self.head
+            .map(|head| GraphicElement {
+                element_type: GraphicType::Circle { center: (0.0, 0.0), radius: head / 2.0 },
+                layer: LayerType::Fabrication,
+                side: Side::Front,
+                stroke: Stroke { width: FAB_LINE_WIDTH, stroke_type: StrokeType::Solid },
+                uuid: fresh_uuid(),
+                role: None,
+            })
+            .into_iter()
+            .collect()
+    }
+
+    /// A hole, there is no part to model
+    fn model_3d(&self) -> Option<Model3D> {
+        None
+    }
+}
@@ -0,0 +1,472 @@
+//! Routed length and propagation delay per net
+//!
+//! `Board::net_length_report` adds up the routed copper of every net: track lengths (arc
+//! tracks along the arc) plus the via barrel a signal runs through, which is the stackup distance between the
+//! outermost layers a via actually connects (tracks or pads on those layers), not its whole
+//! drilled span. Stubs beyond the outermost connected layers add no length or delay;
+//! their barrel is reported apart as the stub length, less what backdrilling removes (see
+//! `backdrill`).
+//!
+//! Delay is estimated per piece of copper: tracks use the effective permittivity of their
+//! layer and width (microstrip on outer layers, stripline on inner ones, see
+//! `Stackup::effective_epsilon_r`), via barrels the permittivity of the dielectrics they
+//! cross. Boards without a stackup are taken as plain 1.6mm two layer boards.
+//!
+//! For nets with exactly two pads the report also gives the pin to pin length and delay,
+//! the shortest routed path between the pads, so stubs and branches are left out.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::backdrill::backdrill_span;
+use crate::board::Board;
+use crate::geometry::{point_in_polygon, Point};
+use crate::layer_type::{BoardLayer, Side};
+use crate::stackup::{Stackup, StackupError};
+
+/// Delay of light in vacuum, picoseconds per millimeter
+pub const VACUUM_DELAY_PS_PER_MM: f32 = 3.335_641;
+
+/// Track ends closer than this are joined
+const JOIN_TOLERANCE: f32 = 0.001;
+/// Arc tolerance when tessellating pads to find the copper they touch
+const NET_LENGTH_ARC_TOLERANCE: f32 = 0.01;
+
+/// Routed path between the two pads of a two pad net
+#[derive(Debug, Clone, PartialEq)]
+pub struct PinToPin {
+    /// Pad names, "reference.number"
+    pub pads: (String, String),
+    pub length: f32,
+    /// Picoseconds
+    pub delay: f32,
+}
+
+/// Routed length of one net, lengths in millimeters
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetLength {
+    pub net: String,
+    /// False for nets with no tracks or vias, which report zero everywhere
+    pub routed: bool,
+    pub track_length: f32,
+    pub via_count: usize,
+    /// Barrel length between the layers each via connects
+    pub via_length: f32,
+    /// Via barrel left beyond the connected layers once backdrilled stubs are removed
+    pub stub_length: f32,
+    /// Estimated propagation delay over all of the net's copper, in picoseconds
+    pub delay: f32,
+    /// `None` unless the net has two pads and they are connected
+    pub pin_to_pin: Option<PinToPin>,
+}
+
+impl NetLength {
+    pub fn total_length(&self) -> f32 {
+        self.track_length + self.via_length
+    }
+}
+
+/// Net lengths in net name order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetLengthReport {
+    pub nets: Vec<NetLength>,
+}
+
+impl NetLengthReport {
+    pub fn net(&self, name: &str) -> Option<&NetLength> {
+        self.nets.iter().find(|net| net.net == name)
+    }
+
+    /// Table of all nets, longest first
+    pub fn table(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for NetLengthReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut nets: Vec<&NetLength> = self.nets.iter().collect();
+        nets.sort_by(|a, b| b.total_length().total_cmp(&a.total_length()).then_with(|| a.net.cmp(&b.net)));
+        let width = nets.iter().map(|net| net.net.len()).chain(["Net".len()]).max().unwrap_or_default();
+        writeln!(
+            f,
+            "{:<width$}  {:>10}  {:>10}  {:>4}  {:>8}  {:>8}  {:>9}  {:>10}",
+            "Net", "Length mm", "Tracks mm", "Vias", "Via mm", "Stub mm", "Delay ps", "Pin-pin mm"
+        )?;
+        for net in nets {
+            let pin_to_pin = net.pin_to_pin.as_ref().map_or("-".to_string(), |path| format!("{:.3}", path.length));
+            write!(
+                f,
+                "{:<width$}  {:>10.3}  {:>10.3}  {:>4}  {:>8.3}  {:>8.3}  {:>9.2}  {:>10}",
+                net.net,
+                net.total_length(),
+                net.track_length,
+                net.via_count,
+                net.via_length,
+                net.stub_length,
+                net.delay,
+                pin_to_pin
+            )?;
+            writeln!(f, "{}", if net.routed { "" } else { "  unrouted" })?;
+        }
+        Ok(())
+    }
+}
+
+/// Copper of one net as a graph: track ends, via layers and pads as nodes
+#[derive(Default)]
+struct NetGraph {
+    /// Neighbour, length and delay
+    edges: Vec<Vec<(usize, f32, f32)>>,
+}
+
+impl NetGraph {
+    fn add_node(&mut self) -> usize {
+        self.edges.push(Vec::new());
+        self.edges.len() - 1
+    }
+
+    fn connect(&mut self, a: usize, b: usize, length: f32, delay: f32) {
+        self.edges[a].push((b, length, delay));
+        self.edges[b].push((a, length, delay));
+    }
+
+    /// Shortest path by length from `from` to `to`, as (length, delay)
+    fn shortest_path(&self, from: usize, to: usize) -> Option<(f32, f32)> {
+        let mut best: Vec<Option<(f32, f32)>> = vec![None; self.edges.len()];
+        let mut done = vec![false; self.edges.len()];
+        best[from] = Some((0.0, 0.0));
+        while let Some(node) = (0..self.edges.len())
+            .filter(|&node| !done[node] && best[node].is_some())
+            .min_by(|&a, &b| best[a].unwrap().0.total_cmp(&best[b].unwrap().0))
+        {
+            if node == to {
+                return best[to];
+            }
+            done[node] = true;
+            let (length, delay) = best[node].unwrap();
+            for &(next, edge_length, edge_delay) in &self.edges[node] {
+                if best[next].is_none_or(|(known, _)| length + edge_length < known) {
+                    best[next] = Some((length + edge_length, delay + edge_delay));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Board {
+    /// Routed length and delay of every net with pads, tracks or vias; see the module
+    /// documentation. Fails when routed copper is on a layer the stackup doesn't have.
+    pub fn net_length_report(&self) -> Result<NetLengthReport, StackupError> {
+        let stackup = self.stackup().cloned().unwrap_or_else(|| Stackup::two_layer(1.6));
+        let layers = self.copper_layers();
+        let pad_outlines: Vec<_> = layers.iter().map(|&layer| (layer, self.pad_outlines(layer, NET_LENGTH_ARC_TOLERANCE))).collect();
+
+        let nets: BTreeSet<&str> = self
+            .routing
+            .pad_nets
+            .values()
+            .map(String::as_str)
+            .chain(self.tracks().iter().map(|track| track.net.as_str()))
+            .chain(self.arcs().iter().map(|arc| arc.net.as_str()))
+            .chain(self.vias().iter().map(|via| via.net.as_str()))
+            .collect();
+
+        let mut report = NetLengthReport::default();
+        for net in nets {
+            let mut graph = NetGraph::default();
+
+            // Pads of the net with their outline on each copper layer they are on
+            let mut pads: Vec<(String, usize)> = Vec::new();
+            let mut pad_copper: Vec<(usize, BoardLayer, &[Point])> = Vec::new();
+            for (layer, outlines) in &pad_outlines {
+                for (name, outline) in outlines {
+                    if self.routing.pad_nets.get(name).map(String::as_str) != Some(net) {
+                        continue;
+                    }
+                    let node = match pads.iter().find(|(pad, _)| pad == name) {
+                        Some((_, node)) => *node,
+                        None => {
+                            let node = graph.add_node();
+                            pads.push((name.clone(), node));
+                            node
+                        }
+                    };
+                    pad_copper.push((node, *layer, outline));
+                }
+            }
+            let pad_at = |layer: BoardLayer, point: Point| {
+                pad_copper.iter().filter(move |(_, l, outline)| *l == layer && point_in_polygon(point, outline)).map(|(node, ..)| *node)
+            };
+
+            // Track and arc ends, joined to each other and to pads they land in
+            let pieces = self.tracks().iter().filter(|track| track.net == net).map(|track| {
+                (track.layer, track.start, track.end, track.width, track.start.distance_to(&track.end))
+            });
+            let arcs = self.arcs().iter().filter(|arc| arc.net == net).map(|arc| (arc.layer, arc.start, arc.end, arc.width, arc.length()));
+            let mut track_ends: Vec<(usize, BoardLayer, Point)> = Vec::new();
+            let (mut track_length, mut delay) = (0.0, 0.0);
+            for (track_layer, track_start, track_end, width, length) in pieces.chain(arcs) {
+                let layer_name = track_layer.to_kicad_string();
+                let track_delay = length * stackup.effective_epsilon_r(&layer_name, width)?.sqrt() * VACUUM_DELAY_PS_PER_MM;
+                let (start, end) = (graph.add_node(), graph.add_node());
+                graph.connect(start, end, length, track_delay);
+                track_length += length;
+                delay += track_delay;
+                for (node, point) in [(start, track_start), (end, track_end)] {
+                    for &(other, layer, other_point) in &track_ends {
+                        if layer == track_layer && point.distance_to(&other_point) <= JOIN_TOLERANCE {
+                            graph.connect(node, other, 0.0, 0.0);
+                        }
+                    }
+                    for pad in pad_at(track_layer, point) {
+                        graph.connect(node, pad, 0.0, 0.0);
+                    }
+                    track_ends.push((node, track_layer, point));
+                }
+            }
+
+            // Vias: a node per spanned layer, barrel between consecutive ones
+            let (mut via_count, mut via_length, mut stub_length) = (0, 0.0, 0.0);
+            for via in self.vias().iter().filter(|via| via.net == net) {
+                via_count += 1;
+                let mut spanned: Vec<(usize, BoardLayer, bool)> = Vec::new();
+                for &layer in layers.iter().filter(|layer| via.spans(layer)) {
+                    let node = graph.add_node();
+                    let mut connected = false;
+                    for &(end, _, _) in track_ends
+                        .iter()
+                        .filter(|(_, l, point)| *l == layer && point.distance_to(&via.position) <= via.diameter / 2.0)
+                    {
+                        graph.connect(node, end, 0.0, 0.0);
+                        connected = true;
+                    }
+                    for pad in pad_at(layer, via.position) {
+                        graph.connect(node, pad, 0.0, 0.0);
+                        connected = true;
+                    }
+                    if let Some(&(previous, previous_layer, _)) = spanned.last() {
+                        let (a, b) = (previous_layer.to_kicad_string(), layer.to_kicad_string());
+                        let length = stackup.layer_distance(&a, &b)?;
+                        let barrel_delay = length * stackup.epsilon_r_between(&a, &b)?.sqrt() * VACUUM_DELAY_PS_PER_MM;
+                        graph.connect(previous, node, length, barrel_delay);
+                    }
+                    spanned.push((node, layer, connected));
+                }
+                // Only the barrel between the outermost connected layers carries signal
+                let connected: Vec<String> =
+                    spanned.iter().filter(|(_, _, connected)| *connected).map(|(_, layer, _)| layer.to_kicad_string()).collect();
+                if let (Some(top), Some(bottom)) = (connected.first(), connected.last()) {
+                    via_length += stackup.layer_distance(top, bottom)?;
+                    delay += stackup.layer_distance(top, bottom)? * stackup.epsilon_r_between(top, bottom)?.sqrt() * VACUUM_DELAY_PS_PER_MM;
+                    let (first, last) = (via.layers.0.to_kicad_string(), via.layers.1.to_kicad_string());
+                    let (mut above, mut below) = (stackup.layer_distance(&first, top)?, stackup.layer_distance(bottom, &last)?);
+                    if let Some(span) = via.backdrill.as_ref().and_then(|backdrill| Some((backdrill.side, backdrill_span(&stackup, backdrill).ok()?))) {
+                        match span {
+                            (Side::Front, span) => above = (above - span.stub_removed).max(0.0),
+                            (_, span) => below = (below - span.stub_removed).max(0.0),
+                        }
+                    }
+                    stub_length += above + below;
+                }
+            }
+
+            let pin_to_pin = match pads.as_slice() {
+                [(a, from), (b, to)] => graph.shortest_path(*from, *to).map(|(length, delay)| PinToPin {
+                    pads: (a.clone(), b.clone()),
+                    length,
+                    delay,
+                }),
+                _ => None,
+            };
+            report.nets.push(NetLength {
+                net: net.to_string(),
+                routed: track_length > 0.0 || via_count > 0,
+                track_length,
+                via_count,
+                via_length,
+                stub_length,
+                delay,
+                pin_to_pin,
+            });
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_interface::Rectangle;
+    use crate::layer_type::LayerType;
+    use crate::presets;
+    use crate::backdrill::{Backdrill, DEFAULT_BACKDRILL_MARGIN};
+    use crate::routing::{Track, TrackArc, Via};
+
+    const MICRON: f32 = 0.001;
+    /// F.Cu to B.Cu of `Stackup::two_layer(1.6)`: the core plus half of each foil
+    const THROUGH_BARREL: f32 = 1.53 + 0.035;
+
+    fn front() -> BoardLayer {
+        BoardLayer::front(LayerType::Copper)
+    }
+
+    fn back() -> BoardLayer {
+        BoardLayer::back(LayerType::Copper)
+    }
+
+    fn assert_microns(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < MICRON, "{} != {}", actual, expected);
+    }
+
+    fn pad_center(board: &Board, reference: &str, number: &str) -> Point {
+        let everything = Rectangle { min_x: -100.0, min_y: -100.0, max_x: 100.0, max_y: 100.0 };
+        board.pads_in_rect(&everything).into_iter().find(|pad| pad.reference == reference && pad.number.as_str() == number).unwrap().center
+    }
+
+    /// Microstrip delay on the outer layers of the plain two layer board
+    fn outer_delay(length: f32, width: f32) -> f32 {
+        let (er, h) = (4.5, 1.53);
+        let effective = (er + 1.0) / 2.0 + (er - 1.0) / 2.0 / (1.0 + 12.0 * h / width).sqrt();
+        length * effective.sqrt() * VACUUM_DELAY_PS_PER_MM
+    }
+
+    /// R1.2 to R2.1 on a two layer board: F.Cu to a via, B.Cu down and across, a second
+    /// via, F.Cu into R2.1. A 2 mm stub hangs off the first via on F.Cu.
+    fn two_layer_board() -> (Board, f32, f32) {
+        let mut board = Board::new("net_length").with_rectangular_outline(30.0, 20.0).with_stackup(Stackup::two_layer(1.6));
+        board.place("R1", presets::resistor_0603("10k"), (2.0, 2.0), 0.0).unwrap();
+        board.place("R2", presets::resistor_0603("10k"), (14.0, 10.0), 0.0).unwrap();
+        board.place("R3", presets::resistor_0603("10k"), (20.0, 2.0), 0.0).unwrap();
+        board.connect_pad("R1", "2", "SIG");
+        board.connect_pad("R2", "1", "SIG");
+        board.connect_pad("R3", "1", "NC");
+        let (start, end) = (pad_center(&board, "R1", "2"), pad_center(&board, "R2", "1"));
+        let (first_via, second_via) = (Point::new(6.0, 2.0), Point::new(9.0, 10.0));
+        let (into_via, out_of_via) = (start.distance_to(&first_via), second_via.distance_to(&end));
+
+        board.add_track(Track::new("SIG", front(), start, first_via, 0.2));
+        board.add_track(Track::new("SIG", front(), first_via, Point::new(6.0, 0.0), 0.2));
+        board.add_via(Via::new("SIG", first_via, 0.6, 0.3));
+        // 4 mm then a 3-4-5 diagonal
+        board.add_track(Track::new("SIG", back(), first_via, Point::new(6.0, 6.0), 0.2));
+        board.add_track(Track::new("SIG", back(), Point::new(6.0, 6.0), second_via, 0.2));
+        board.add_via(Via::new("SIG", second_via, 0.6, 0.3));
+        board.add_track(Track::new("SIG", front(), second_via, end, 0.2));
+        (board, into_via, out_of_via)
+    }
+
+    #[test]
+    fn totals_add_tracks_and_via_barrels() {
+        let (board, into_via, out_of_via) = two_layer_board();
+        let report = board.net_length_report().unwrap();
+        let net = report.net("SIG").unwrap();
+        assert!(net.routed);
+        assert_microns(net.track_length, into_via + 2.0 + 4.0 + 5.0 + out_of_via);
+        assert_eq!(net.via_count, 2);
+        assert_microns(net.via_length, 2.0 * THROUGH_BARREL);
+        assert_microns(net.total_length(), net.track_length + 2.0 * THROUGH_BARREL);
+    }
+
+    #[test]
+    fn pin_to_pin_leaves_out_the_stub() {
+        let (board, into_via, out_of_via) = two_layer_board();
+        let report = board.net_length_report().unwrap();
+        let path = report.net("SIG").unwrap().pin_to_pin.clone().unwrap();
+        assert_eq!(path.pads, ("R1.2".to_string(), "R2.1".to_string()));
+        assert_microns(path.length, into_via + 4.0 + 5.0 + out_of_via + 2.0 * THROUGH_BARREL);
+    }
+
+    #[test]
+    fn delay_uses_each_layers_permittivity() {
+        let (board, into_via, out_of_via) = two_layer_board();
+        let report = board.net_length_report().unwrap();
+        let net = report.net("SIG").unwrap();
+        let barrel = THROUGH_BARREL * 4.5f32.sqrt() * VACUUM_DELAY_PS_PER_MM;
+        let tracks = outer_delay(into_via + 2.0 + 4.0 + 5.0 + out_of_via, 0.2);
+        assert!((net.delay - (tracks + 2.0 * barrel)).abs() < 0.01, "{} ps", net.delay);
+        let path = net.pin_to_pin.as_ref().unwrap();
+        assert!((path.delay - (outer_delay(into_via + 4.0 + 5.0 + out_of_via, 0.2) + 2.0 * barrel)).abs() < 0.01);
+    }
+
+    #[test]
+    fn arc_bends_count_their_arc_length() {
+        // 5 mm along X, a quarter circle of radius 2 turning down, 5 mm down
+        let mut board = Board::new("bend").with_stackup(Stackup::two_layer(1.6));
+        let mid = Point::new(5.0 + 2.0 * std::f32::consts::FRAC_1_SQRT_2, 2.0 - 2.0 * std::f32::consts::FRAC_1_SQRT_2);
+        board.add_track(Track::new("RF", front(), Point::new(0.0, 0.0), Point::new(5.0, 0.0), 0.3));
+        board.add_arc(TrackArc::new("RF", front(), Point::new(5.0, 0.0), mid, Point::new(7.0, 2.0), 0.3));
+        board.add_track(Track::new("RF", front(), Point::new(7.0, 2.0), Point::new(7.0, 7.0), 0.3));
+        let net = board.net_length_report().unwrap().nets.remove(0);
+        let length = 10.0 + std::f32::consts::PI;
+        assert_microns(net.track_length, length);
+        assert!((net.delay - outer_delay(length, 0.3)).abs() < 0.01, "{} ps", net.delay);
+    }
+
+    #[test]
+    fn only_the_connected_part_of_a_via_counts() {
+        let mut board = Board::new("stub").with_stackup(Stackup::four_layer_default());
+        let inner = BoardLayer::inner_copper(1);
+        board.add_track(Track::new("DQ0", front(), Point::new(0.0, 0.0), Point::new(5.0, 0.0), 0.15));
+        board.add_via(Via::new("DQ0", Point::new(5.0, 0.0), 0.6, 0.3));
+        board.add_track(Track::new("DQ0", inner, Point::new(5.0, 0.0), Point::new(5.0, 10.0), 0.15));
+        let net = board.net_length_report().unwrap().nets.remove(0);
+        // F.Cu to In1.Cu: prepreg plus half of each foil; the rest of the barrel is stub
+        let barrel = 0.2104 + (0.035 + 0.0152) / 2.0;
+        assert_microns(net.via_length, barrel);
+        assert_microns(net.track_length, 15.0);
+
+        let stripline_er = (0.2104 * 4.4 + 1.065 * 4.6) / (0.2104 + 1.065);
+        let stripline = 10.0 * f32::sqrt(stripline_er) * VACUUM_DELAY_PS_PER_MM;
+        let via = barrel * 4.4f32.sqrt() * VACUUM_DELAY_PS_PER_MM;
+        let microstrip = Stackup::four_layer_default().effective_epsilon_r("F.Cu", 0.15).unwrap().sqrt() * 5.0 * VACUUM_DELAY_PS_PER_MM;
+        assert!((net.delay - (stripline + via + microstrip)).abs() < 0.01, "{} ps", net.delay);
+    }
+
+    #[test]
+    fn backdrilling_shortens_the_stub() {
+        let route = |via: Via| {
+            let mut board = Board::new("stub").with_stackup(Stackup::four_layer_default());
+            board.add_track(Track::new("DQ0", front(), Point::new(0.0, 0.0), Point::new(5.0, 0.0), 0.15));
+            board.add_via(via);
+            board.add_track(Track::new("DQ0", BoardLayer::inner_copper(1), Point::new(5.0, 0.0), Point::new(5.0, 10.0), 0.15));
+            board.net_length_report().unwrap().nets.remove(0)
+        };
+        let via = Via::new("DQ0", Point::new(5.0, 0.0), 0.6, 0.3);
+        let plain = route(via.clone());
+        // In1.Cu down to B.Cu: core, In2.Cu, prepreg and half of each outer foil
+        assert_microns(plain.stub_length, (0.0152 + 0.035) / 2.0 + 1.065 + 0.0152 + 0.2104);
+
+        let drilled = route(via.with_backdrill(Backdrill::new(Side::Back, BoardLayer::inner_copper(1), 0.5)));
+        // What is left runs from the middle of the In1.Cu foil to the end of the drill
+        assert_microns(drilled.stub_length, 0.0152 / 2.0 + DEFAULT_BACKDRILL_MARGIN);
+        assert_eq!((drilled.via_length, drilled.delay), (plain.via_length, plain.delay));
+    }
+
+    #[test]
+    fn unrouted_nets_report_zero() {
+        let (board, ..) = two_layer_board();
+        let report = board.net_length_report().unwrap();
+        let nc = report.net("NC").unwrap();
+        assert!(!nc.routed);
+        assert_eq!((nc.total_length(), nc.via_count, nc.delay, nc.pin_to_pin.clone()), (0.0, 0, 0.0, None));
+    }
+
+    #[test]
+    fn table_is_sorted_longest_first() {
+        let (board, ..) = two_layer_board();
+        let table = board.net_length_report().unwrap().table();
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[0].starts_with("Net"));
+        assert!(lines[1].starts_with("SIG"));
+        assert!(lines[2].starts_with("NC") && lines[2].ends_with("unrouted"), "{}", table);
+    }
+
+    #[test]
+    fn copper_off_the_stackup_is_an_error() {
+        let mut board = Board::new("mismatch").with_stackup(Stackup::two_layer(1.6));
+        board.add_track(Track::new("X", BoardLayer::inner_copper(2), Point::new(0.0, 0.0), Point::new(1.0, 0.0), 0.2));
+        assert_eq!(board.net_length_report(), Err(StackupError::UnknownLayer("In2.Cu".to_string())));
+    }
+}
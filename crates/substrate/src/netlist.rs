@@ -0,0 +1,250 @@
+//! Netlists
+//!
+//! A `Netlist` names the pads each net connects, written "reference.number" like the pads
+//! of `Board::pad_net`: `Netlist::new().with_net("GND", &["U1.4", "C1.2"])`. It usually
+//! comes from a schematic, but nothing here depends on where.
+//!
+//! `Board::apply_netlist` checks every pad against the placed footprints before it
+//! connects any, so a misspelt reference leaves the board as it was. A pad listed under
+//! two nets is an error as well. Pads the netlist leaves out keep the net they had.
+//!
+//! `Board::route_pads` then draws copper between two pads of a net: a straight track when
+//! the pads line up, else two tracks meeting at a right angle corner, at the track width
+//! of the net's class. It doesn't look for obstacles; `Board::drc` tells whether the
+//! route crosses anything.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::board::Board;
+use crate::geometry::Point;
+use crate::layer_type::BoardLayer;
+use crate::routing::{Track, ROUTING_ARC_TOLERANCE};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetlistError {
+    /// A pad not written "reference.number"
+    PadName { net: String, pad: String },
+    /// No component on the board has this reference
+    UnknownReference { net: String, reference: String },
+    /// The component has no pad with this number
+    UnknownPad { net: String, reference: String, number: String },
+    /// The pad is listed under two nets
+    PadInTwoNets { pad: String, first: String, second: String },
+}
+
+impl fmt::Display for NetlistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetlistError::PadName { net, pad } => write!(f, "net {}: \"{}\" is not reference.number", net, pad),
+            NetlistError::UnknownReference { net, reference } => write!(f, "net {}: no component {} on the board", net, reference),
+            NetlistError::UnknownPad { net, reference, number } => write!(f, "net {}: {} has no pad {}", net, reference, number),
+            NetlistError::PadInTwoNets { pad, first, second } => write!(f, "pad {} is in both {} and {}", pad, first, second),
+        }
+    }
+}
+
+impl std::error::Error for NetlistError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RouteError {
+    /// No placed pad of this name, "reference.number"
+    UnknownPad(String),
+    /// The pad is on no net; apply a netlist first
+    NoNet(String),
+    DifferentNets { from: String, to: String },
+    /// The pad has no copper on the layer routed on
+    NotOnLayer { pad: String, layer: BoardLayer },
+}
+
+impl fmt::Display for RouteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RouteError::UnknownPad(pad) => write!(f, "no pad {} on the board", pad),
+            RouteError::NoNet(pad) => write!(f, "pad {} is on no net", pad),
+            RouteError::DifferentNets { from, to } => write!(f, "pads {} and {} are on different nets", from, to),
+            RouteError::NotOnLayer { pad, layer } => write!(f, "pad {} has no copper on {}", pad, layer.to_kicad_string()),
+        }
+    }
+}
+
+impl std::error::Error for RouteError {}
+
+/// Which way a route leaves its first pad when the pads don't line up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Elbow {
+    /// Along X to the second pad's column, then along Y
+    #[default]
+    HorizontalFirst,
+    VerticalFirst,
+}
+
+/// Pads of each net by net name, see the module documentation
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Netlist {
+    nets: BTreeMap<String, Vec<String>>,
+}
+
+impl Netlist {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `pads` to `net`, after any it already has
+    pub fn with_net(mut self, net: &str, pads: &[&str]) -> Self {
+        self.add_net(net, pads);
+        self
+    }
+
+    pub fn add_net(&mut self, net: &str, pads: &[&str]) {
+        self.nets.entry(net.to_string()).or_default().extend(pads.iter().map(|pad| pad.to_string()));
+    }
+
+    /// Nets in name order with their pads in the order given
+    pub fn nets(&self) -> impl Iterator<Item = (&str, &[String])> {
+        self.nets.iter().map(|(net, pads)| (net.as_str(), pads.as_slice()))
+    }
+
+    pub fn pads(&self, net: &str) -> &[String] {
+        self.nets.get(net).map_or(&[], Vec::as_slice)
+    }
+
+    /// The net `pad` is listed under, the first one if there are several
+    pub fn net_of(&self, pad: &str) -> Option<&str> {
+        self.nets.iter().find(|(_, pads)| pads.iter().any(|listed| listed == pad)).map(|(net, _)| net.as_str())
+    }
+}
+
+impl Board {
+    /// Connect the pads of every net in `netlist`, or none of them if any pad is wrong;
+    /// see the module documentation
+    pub fn apply_netlist(&mut self, netlist: &Netlist) -> Result<(), NetlistError> {
+        let mut connections: BTreeMap<&str, (&str, &str, &str)> = BTreeMap::new();
+        for (net, pads) in netlist.nets() {
+            for pad in pads {
+                let Some((reference, number)) = pad.split_once('.').filter(|(reference, number)| !reference.is_empty() && !number.is_empty())
+                else {
+                    return Err(NetlistError::PadName { net: net.to_string(), pad: pad.clone() });
+                };
+                let placed = self
+                    .component(reference)
+                    .ok_or_else(|| NetlistError::UnknownReference { net: net.to_string(), reference: reference.to_string() })?;
+                if !placed.component.pad_descriptors().iter().any(|descriptor| descriptor.number == number) {
+                    return Err(NetlistError::UnknownPad { net: net.to_string(), reference: reference.to_string(), number: number.to_string() });
+                }
+                if let Some((first, ..)) = connections.get(pad.as_str()).filter(|(first, ..)| *first != net) {
+                    return Err(NetlistError::PadInTwoNets { pad: pad.clone(), first: first.to_string(), second: net.to_string() });
+                }
+                connections.insert(pad, (net, reference, number));
+            }
+        }
+        for (net, reference, number) in connections.into_values() {
+            self.connect_pad(reference, number, net);
+        }
+        Ok(())
+    }
+
+    /// Center and net of pad "reference.number", checking it has copper on `layer`
+    fn routable_pad(&self, pad: &str, layer: BoardLayer) -> Result<(Point, &str), RouteError> {
+        let unknown = || RouteError::UnknownPad(pad.to_string());
+        let (reference, number) = pad.split_once('.').ok_or_else(unknown)?;
+        let placed = self.component(reference).ok_or_else(unknown)?;
+        let descriptors = placed.component.pad_descriptors();
+        let descriptor = descriptors.iter().find(|descriptor| descriptor.number == number).ok_or_else(unknown)?;
+        let net = self.pad_net(reference, number).ok_or_else(|| RouteError::NoNet(pad.to_string()))?;
+        if !self.pad_outlines(layer, ROUTING_ARC_TOLERANCE).iter().any(|(name, _)| name == pad) {
+            return Err(RouteError::NotOnLayer { pad: pad.to_string(), layer });
+        }
+        Ok((self.board_point(placed, Point::from(descriptor.position)), net))
+    }
+
+    /// Route pad `from` to pad `to` on `layer`, both "reference.number" and on the same
+    /// net; see the module documentation. Returns the indexes of the new tracks.
+    pub fn route_pads(&mut self, from: &str, to: &str, layer: BoardLayer, elbow: Elbow) -> Result<Vec<usize>, RouteError> {
+        let (start, net) = self.routable_pad(from, layer)?;
+        let (end, to_net) = self.routable_pad(to, layer)?;
+        if net != to_net {
+            return Err(RouteError::DifferentNets { from: from.to_string(), to: to.to_string() });
+        }
+        let net = net.to_string();
+        let corner = match elbow {
+            Elbow::HorizontalFirst => Point::new(end.x, start.y),
+            Elbow::VerticalFirst => Point::new(start.x, end.y),
+        };
+        let width = self.net_class(&net).track_width;
+        let mut added = Vec::new();
+        for (a, b) in [(start, corner), (corner, end)] {
+            if a.distance_to(&b) > 0.0 {
+                added.push(self.tracks().len());
+                self.add_track(Track::new(&net, layer, a, b, width));
+            }
+        }
+        Ok(added)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer_type::LayerType;
+    use crate::presets;
+
+    fn board() -> Board {
+        let mut board = Board::new("netlist").with_rectangular_outline(20.0, 10.0);
+        board.place("R1", presets::resistor_0603("10k"), (5.0, 5.0), 0.0).unwrap();
+        board.place("R2", presets::resistor_0603("10k"), (10.0, 5.0), 0.0).unwrap();
+        board
+    }
+
+    #[test]
+    fn nets_connect_every_listed_pad() {
+        let mut board = board();
+        let netlist = Netlist::new().with_net("VCC", &["R1.1"]).with_net("MID", &["R1.2", "R2.1"]).with_net("VCC", &["R2.2"]);
+        assert_eq!(netlist.pads("VCC"), ["R1.1", "R2.2"]);
+        assert_eq!(netlist.net_of("R2.1"), Some("MID"));
+        board.apply_netlist(&netlist).unwrap();
+        assert_eq!(board.pad_net("R1", "1"), Some("VCC"));
+        assert_eq!(board.pad_net("R2", "1"), Some("MID"));
+        assert_eq!(board.nets().into_iter().collect::<Vec<_>>(), ["MID", "VCC"]);
+    }
+
+    #[test]
+    fn a_wrong_pad_connects_nothing() {
+        let mut board = board();
+        let typo = Netlist::new().with_net("A", &["R1.1"]).with_net("B", &["R3.1"]);
+        assert_eq!(board.apply_netlist(&typo), Err(NetlistError::UnknownReference { net: "B".into(), reference: "R3".into() }));
+        assert_eq!(board.pad_net("R1", "1"), None);
+
+        let missing = Netlist::new().with_net("A", &["R1.3"]);
+        assert!(matches!(board.apply_netlist(&missing), Err(NetlistError::UnknownPad { .. })));
+        let twice = Netlist::new().with_net("A", &["R1.1"]).with_net("B", &["R1.1"]);
+        assert!(matches!(board.apply_netlist(&twice), Err(NetlistError::PadInTwoNets { .. })));
+        let unnamed = Netlist::new().with_net("A", &["R1"]);
+        assert!(matches!(board.apply_netlist(&unnamed), Err(NetlistError::PadName { .. })));
+        assert!(board.nets().is_empty());
+    }
+
+    #[test]
+    fn routes_turn_one_corner_at_the_class_width() {
+        let mut board = board();
+        board.place("R3", presets::resistor_0603("10k"), (10.0, 8.0), 90.0).unwrap();
+        board.apply_netlist(&Netlist::new().with_net("MID", &["R1.2", "R3.1", "R2.1"])).unwrap();
+        let top = BoardLayer::front(LayerType::Copper);
+        // R1.2 and R2.1 face each other on one row: a single track
+        assert_eq!(board.route_pads("R1.2", "R2.1", top, Elbow::default()).unwrap(), [0]);
+        let added = board.route_pads("R1.2", "R3.1", top, Elbow::VerticalFirst).unwrap();
+        assert_eq!(added, [1, 2]);
+        let (first, second) = (&board.tracks()[1], &board.tracks()[2]);
+        assert_eq!(first.start.x, first.end.x);
+        assert_eq!(first.end, second.start);
+        assert_eq!(second.start.y, second.end.y);
+        assert!(board.tracks().iter().all(|track| track.net == "MID" && track.width == board.default_net_class().track_width));
+
+        let bottom = BoardLayer::back(LayerType::Copper);
+        assert!(matches!(board.route_pads("R1.2", "R2.1", bottom, Elbow::default()), Err(RouteError::NotOnLayer { .. })));
+        assert_eq!(board.route_pads("R1.1", "R2.1", top, Elbow::default()), Err(RouteError::NoNet("R1.1".into())));
+        assert_eq!(board.route_pads("R1.2", "R9.1", top, Elbow::default()), Err(RouteError::UnknownPad("R9.1".into())));
+    }
+}
@@ -0,0 +1,379 @@
+//! Placement occupancy grids
+//!
+//! External placers and autorouters work on bitmaps rather than polygons.
+//! `Board::occupancy_grid` rasterizes one side of the board into square cells that are
+//! free, occupied, or outside the board. Occupied cells are those touched by a courtyard,
+//! an actuation envelope (see `ActuationEnvelope`) or a placement keepout on that side;
+//! rotated courtyards are rasterized as the rotated polygon, not its bounding box.
+//!
+//! Rasterization is conservative: a cell counts as occupied as soon as any part of it
+//! overlaps an obstacle, and cells crossed by the board edge count as occupied too, so a
+//! free cell is free everywhere. Only cells entirely off the board are outside. Shapes that
+//! merely touch a cell's border leave it free.
+//!
+//! The grid starts at the top left corner of the board outline (of the obstacles on boards
+//! without one), row 0 at the top as board Y points down.
+
+use serde::{Deserialize, Serialize};
+
+use crate::board::Board;
+use crate::board_interface::Rectangle;
+use crate::geometry::{point_in_polygon, segments_intersect, Point};
+use crate::layer_type::Side;
+
+/// Shapes closer than this to a cell's border don't count as overlapping it
+const OCCUPANCY_EPSILON: f32 = 1e-4;
+
+/// Area where nothing may be placed, in board coordinates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlacementKeepout {
+    /// Closed polygon, last point not repeated
+    pub outline: Vec<Point>,
+    /// The side it applies to, `None` for both
+    pub side: Option<Side>,
+}
+
+impl PlacementKeepout {
+    pub fn new(outline: Vec<Point>) -> Self {
+        Self { outline, side: None }
+    }
+
+    pub fn on_side(mut self, side: Side) -> Self {
+        self.side = Some(side);
+        self
+    }
+
+    pub fn applies_to(&self, side: Side) -> bool {
+        self.side.is_none_or(|keepout| keepout == side)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Occupancy {
+    Free,
+    Occupied,
+    /// Entirely off the board
+    Outside,
+}
+
+/// One side of a board as a bitmap, see the module documentation
+#[derive(Debug, Clone, PartialEq)]
+pub struct OccupancyGrid {
+    /// Top left corner of cell (0, 0)
+    pub origin: Point,
+    /// Cell size in millimeters
+    pub resolution: f32,
+    pub columns: usize,
+    pub rows: usize,
+    pub side: Side,
+    /// Row by row from the top
+    cells: Vec<Occupancy>,
+}
+
+impl OccupancyGrid {
+    /// A grid of `columns` by `rows` free cells
+    pub fn new(origin: Point, resolution: f32, columns: usize, rows: usize, side: Side) -> Self {
+        Self { origin, resolution, columns, rows, side, cells: vec![Occupancy::Free; columns * rows] }
+    }
+
+    pub fn get(&self, column: usize, row: usize) -> Option<Occupancy> {
+        (column < self.columns && row < self.rows).then(|| self.cells[row * self.columns + column])
+    }
+
+    pub fn set(&mut self, column: usize, row: usize, occupancy: Occupancy) {
+        if column < self.columns && row < self.rows {
+            self.cells[row * self.columns + column] = occupancy;
+        }
+    }
+
+    /// Cells row by row from the top
+    pub fn cells(&self) -> &[Occupancy] {
+        &self.cells
+    }
+
+    pub fn count(&self, occupancy: Occupancy) -> usize {
+        self.cells.iter().filter(|cell| **cell == occupancy).count()
+    }
+
+    /// Board area of a cell
+    pub fn cell_bounds(&self, column: usize, row: usize) -> Rectangle {
+        let min_x = self.origin.x + column as f32 * self.resolution;
+        let min_y = self.origin.y + row as f32 * self.resolution;
+        Rectangle { min_x, min_y, max_x: min_x + self.resolution, max_y: min_y + self.resolution }
+    }
+
+    /// Cell containing a board point
+    pub fn cell_at(&self, point: Point) -> Option<(usize, usize)> {
+        let column = ((point.x - self.origin.x) / self.resolution).floor();
+        let row = ((point.y - self.origin.y) / self.resolution).floor();
+        (column >= 0.0 && row >= 0.0 && (column as usize) < self.columns && (row as usize) < self.rows)
+            .then_some((column as usize, row as usize))
+    }
+
+    /// Columns and rows of the cells overlapping `rect`, clamped to the grid; `None` when
+    /// `rect` reaches past the grid
+    fn cell_range(&self, rect: &Rectangle) -> Option<(usize, usize, usize, usize)> {
+        let column = |x: f32| (x - self.origin.x) / self.resolution;
+        let row = |y: f32| (y - self.origin.y) / self.resolution;
+        let (first_column, last_column) = ((column(rect.min_x) + OCCUPANCY_EPSILON).floor(), (column(rect.max_x) - OCCUPANCY_EPSILON).ceil() - 1.0);
+        let (first_row, last_row) = ((row(rect.min_y) + OCCUPANCY_EPSILON).floor(), (row(rect.max_y) - OCCUPANCY_EPSILON).ceil() - 1.0);
+        let inside = first_column >= 0.0 && first_row >= 0.0 && last_column < self.columns as f32 && last_row < self.rows as f32;
+        inside.then_some((first_column as usize, first_row as usize, last_column.max(first_column) as usize, last_row.max(first_row) as usize))
+    }
+
+    /// Whether every cell overlapping `rect` is free; false when `rect` leaves the grid.
+    /// The inverse of the rasterization, for placers asking whether a part fits.
+    pub fn is_region_free(&self, rect: &Rectangle) -> bool {
+        let Some((first_column, first_row, last_column, last_row)) = self.cell_range(rect) else {
+            return false;
+        };
+        (first_row..=last_row).all(|row| (first_column..=last_column).all(|column| self.get(column, row) == Some(Occupancy::Free)))
+    }
+
+    /// Mark every cell `polygon` overlaps as occupied
+    pub fn occupy_polygon(&mut self, polygon: &[Point]) {
+        let Some(bounds) = Rectangle::enclosing(polygon) else { return };
+        let clamp = |value: f32, count: usize| value.clamp(0.0, count as f32 - 1.0) as usize;
+        if self.columns == 0 || self.rows == 0 {
+            return;
+        }
+        let first_column = clamp(((bounds.min_x - self.origin.x) / self.resolution).floor(), self.columns);
+        let last_column = clamp(((bounds.max_x - self.origin.x) / self.resolution).floor(), self.columns);
+        let first_row = clamp(((bounds.min_y - self.origin.y) / self.resolution).floor(), self.rows);
+        let last_row = clamp(((bounds.max_y - self.origin.y) / self.resolution).floor(), self.rows);
+        for row in first_row..=last_row {
+            for column in first_column..=last_column {
+                if self.get(column, row) == Some(Occupancy::Free) && polygon_overlaps_rect(polygon, &self.cell_bounds(column, row)) {
+                    self.set(column, row, Occupancy::Occupied);
+                }
+            }
+        }
+    }
+}
+
+/// Corners of `rect` shrunk by the epsilon, so contact along its border doesn't count
+fn inner_corners(rect: &Rectangle) -> [Point; 4] {
+    let (min_x, min_y) = (rect.min_x + OCCUPANCY_EPSILON, rect.min_y + OCCUPANCY_EPSILON);
+    let (max_x, max_y) = (rect.max_x - OCCUPANCY_EPSILON, rect.max_y - OCCUPANCY_EPSILON);
+    [Point::new(min_x, min_y), Point::new(max_x, min_y), Point::new(max_x, max_y), Point::new(min_x, max_y)]
+}
+
+/// Whether an edge of `polygon` enters the rectangle with corners `corners`
+fn edges_enter(polygon: &[Point], corners: &[Point; 4]) -> bool {
+    let inside = |point: &Point| point.x > corners[0].x && point.x < corners[2].x && point.y > corners[0].y && point.y < corners[2].y;
+    polygon.iter().any(inside)
+        || (0..polygon.len()).any(|i| {
+            let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+            (0..4).any(|j| segments_intersect(a, b, corners[j], corners[(j + 1) % 4]))
+        })
+}
+
+/// Whether a closed polygon and a rectangle share any area, ignoring contact along borders
+fn polygon_overlaps_rect(polygon: &[Point], rect: &Rectangle) -> bool {
+    let corners = inner_corners(rect);
+    corners.iter().any(|corner| point_in_polygon(*corner, polygon)) || edges_enter(polygon, &corners)
+}
+
+/// Whether a closed polygon covers all of a rectangle, ignoring contact along borders
+fn polygon_covers_rect(polygon: &[Point], rect: &Rectangle) -> bool {
+    let corners = inner_corners(rect);
+    corners.iter().all(|corner| point_in_polygon(*corner, polygon)) && !edges_enter(polygon, &corners)
+}
+
+impl Board {
+    pub fn add_keepout(&mut self, keepout: PlacementKeepout) {
+        self.keepouts.push(keepout);
+    }
+
+    pub fn keepouts(&self) -> &[PlacementKeepout] {
+        &self.keepouts
+    }
+
+    /// Courtyards on `side` as polygons in board coordinates, one per placed courtyard
+    pub fn courtyard_polygons(&self, side: Side) -> Vec<(String, Vec<Point>)> {
+        let mut polygons = Vec::new();
+        for placed in self.components() {
+            for courtyard in placed.component.generate_courtyards() {
+                let board_side = match (courtyard.side, placed.side) {
+                    (Side::Front, Side::Back) => Side::Back,
+                    (Side::Back, Side::Back) => Side::Front,
+                    (courtyard_side, _) => courtyard_side,
+                };
+                if board_side != side {
+                    continue;
+                }
+                let bounds = courtyard.bounds;
+                let corners = [
+                    Point::new(bounds.min_x, bounds.min_y),
+                    Point::new(bounds.max_x, bounds.min_y),
+                    Point::new(bounds.max_x, bounds.max_y),
+                    Point::new(bounds.min_x, bounds.max_y),
+                ];
+                polygons.push((placed.reference.clone(), corners.iter().map(|corner| self.board_point(placed, *corner)).collect()));
+            }
+        }
+        polygons
+    }
+
+    /// Actuation envelopes of the parts placed on `side`, by reference, in board coordinates
+    pub fn actuation_envelopes(&self, side: Side) -> Vec<(String, Vec<Point>)> {
+        self.components()
+            .iter()
+            .filter(|placed| placed.side == side)
+            .filter_map(|placed| {
+                let envelope = placed.component.actuation_envelope()?;
+                Some((placed.reference.clone(), envelope.points().into_iter().map(|point| self.board_point(placed, point)).collect()))
+            })
+            .collect()
+    }
+
+    /// Cells of `resolution` millimeters covering `extent`, those entirely off the board
+    /// outline outside and those crossed by it occupied; all free on boards without an
+    /// outline. `resolution` must be positive.
+    pub(crate) fn outline_grid(&self, extent: &Rectangle, resolution: f32, side: Side) -> OccupancyGrid {
+        let columns = (extent.width() / resolution - OCCUPANCY_EPSILON).ceil().max(1.0) as usize;
+        let rows = (extent.height() / resolution - OCCUPANCY_EPSILON).ceil().max(1.0) as usize;
+        let mut grid = OccupancyGrid::new(Point::new(extent.min_x, extent.min_y), resolution, columns, rows, side);
+
+        if let Some(outline) = self.outline() {
+            for row in 0..rows {
+                for column in 0..columns {
+                    let cell = grid.cell_bounds(column, row);
+                    if !polygon_overlaps_rect(outline, &cell) {
+                        grid.set(column, row, Occupancy::Outside);
+                    } else if !polygon_covers_rect(outline, &cell) {
+                        grid.set(column, row, Occupancy::Occupied);
+                    }
+                }
+            }
+        }
+        grid
+    }
+
+    /// Rasterize `side` into cells of `resolution` millimeters; see the module documentation
+    pub fn occupancy_grid(&self, resolution: f32, side: Side) -> OccupancyGrid {
+        let courtyards = self.courtyard_polygons(side);
+        let keepouts: Vec<&[Point]> =
+            self.keepouts.iter().filter(|keepout| keepout.applies_to(side)).map(|keepout| keepout.outline.as_slice()).collect();
+
+        let extent = match self.outline() {
+            Some(outline) => Rectangle::enclosing(outline),
+            None => Rectangle::enclosing(
+                &courtyards.iter().flat_map(|(_, polygon)| polygon.iter().copied()).chain(keepouts.iter().flat_map(|k| k.iter().copied())).collect::<Vec<_>>(),
+            ),
+        };
+        let Some(extent) = extent.filter(|_| resolution > 0.0) else {
+            return OccupancyGrid::new(Point::default(), resolution, 0, 0, side);
+        };
+        let mut grid = self.outline_grid(&extent, resolution, side);
+        for (_, polygon) in &courtyards {
+            grid.occupy_polygon(polygon);
+        }
+        for keepout in keepouts {
+            grid.occupy_polygon(keepout);
+        }
+        for (_, envelope) in self.actuation_envelopes(side) {
+            grid.occupy_polygon(&envelope);
+        }
+        grid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::polygon_area;
+    use crate::presets;
+
+    const POSITION: (f64, f64) = (10.03, 10.07);
+
+    /// Cells a convex polygon overlaps, counted per column strip: in each strip the polygon
+    /// spans a y interval, and every row that interval reaches is occupied
+    fn strip_count(polygon: &[Point], origin: Point, resolution: f32) -> usize {
+        let min_x = polygon.iter().map(|p| p.x).fold(f32::INFINITY, f32::min);
+        let max_x = polygon.iter().map(|p| p.x).fold(f32::NEG_INFINITY, f32::max);
+        let first = ((min_x - origin.x) / resolution).floor() as i32;
+        let last = ((max_x - origin.x) / resolution).ceil() as i32 - 1;
+        let mut cells = 0;
+        for column in first..=last {
+            let (x0, x1) = (origin.x + column as f32 * resolution, origin.x + (column + 1) as f32 * resolution);
+            // Vertices inside the strip and crossings of its sides
+            let mut ys: Vec<f32> = polygon.iter().filter(|p| p.x >= x0 && p.x <= x1).map(|p| p.y).collect();
+            for i in 0..polygon.len() {
+                let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+                for x in [x0, x1] {
+                    if (a.x - x) * (b.x - x) < 0.0 {
+                        ys.push(a.y + (b.y - a.y) * (x - a.x) / (b.x - a.x));
+                    }
+                }
+            }
+            let top = ys.iter().copied().fold(f32::INFINITY, f32::min);
+            let bottom = ys.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+            let rows = ((bottom - origin.y) / resolution).ceil() - ((top - origin.y) / resolution).floor();
+            cells += rows.max(0.0) as usize;
+        }
+        cells
+    }
+
+    /// An 0805 turned by 30 degrees on a 20 mm square board
+    fn rotated_0805() -> Board {
+        let mut board = Board::new("occupancy").with_rectangular_outline(20.0, 20.0);
+        board.place("R1", presets::resistor_0805("10k"), POSITION, 30.0).unwrap();
+        board
+    }
+
+    fn square(min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> Rectangle {
+        Rectangle { min_x, min_y, max_x, max_y }
+    }
+
+    #[test]
+    fn rotated_courtyard_occupies_the_cells_it_touches() {
+        let board = rotated_0805();
+        let (_, courtyard) = board.courtyard_polygons(Side::Front).remove(0);
+        for resolution in [0.25, 0.1] {
+            let grid = board.occupancy_grid(resolution, Side::Front);
+            let occupied = grid.count(Occupancy::Occupied);
+            let expected = strip_count(&courtyard, grid.origin, resolution);
+            assert!(occupied.abs_diff(expected) <= 1, "{} mm: {} occupied, {} expected", resolution, occupied, expected);
+            // Conservative: the occupied cells cover the courtyard
+            assert!(occupied as f32 * resolution * resolution >= polygon_area(&courtyard));
+            // The board starts at the origin, so no cell is outside it
+            assert_eq!(grid.count(Occupancy::Free) + occupied, grid.columns * grid.rows);
+        }
+    }
+
+    #[test]
+    fn cells_partly_off_the_board_are_occupied() {
+        let mut board = Board::new("offset").with_rectangular_outline(2.2, 1.0);
+        board.place("R1", presets::resistor_0805("10k"), (-10.0, -10.0), 0.0).unwrap();
+        let grid = board.occupancy_grid(0.5, Side::Back);
+        assert_eq!((grid.columns, grid.rows), (5, 2));
+        // The last column is 0.3 mm on the board and 0.2 mm off it
+        assert_eq!(grid.get(4, 0), Some(Occupancy::Occupied));
+        assert_eq!(grid.get(3, 1), Some(Occupancy::Free));
+        assert_eq!(grid.count(Occupancy::Outside), 0);
+    }
+
+    #[test]
+    fn region_queries_follow_the_grid() {
+        let board = rotated_0805();
+        let grid = board.occupancy_grid(0.25, Side::Front);
+        let center = Point::new(POSITION.0 as f32, POSITION.1 as f32);
+        assert!(!grid.is_region_free(&square(center.x - 0.2, center.y - 0.2, center.x + 0.2, center.y + 0.2)));
+        assert!(grid.is_region_free(&square(1.0, 1.0, 4.0, 3.0)));
+        assert!(!grid.is_region_free(&square(18.0, 18.0, 22.0, 19.0)), "off the board is not free");
+    }
+
+    #[test]
+    fn keepouts_apply_to_their_side_only() {
+        let mut board = rotated_0805();
+        board.add_keepout(PlacementKeepout::new(vec![Point::new(0.0, 0.0), Point::new(3.0, 0.0), Point::new(0.0, 3.0)]).on_side(Side::Back));
+        let back = board.occupancy_grid(0.5, Side::Back);
+        assert_eq!(back.get(0, 0), Some(Occupancy::Occupied));
+        assert_eq!(back.get(20, 20), Some(Occupancy::Free), "the resistor is on the front");
+        // The triangle's hypotenuse runs through cells on the diagonal; those beyond it stay free
+        assert_eq!(back.count(Occupancy::Occupied), 6 + 5 + 4 + 3 + 2 + 1);
+        let front = board.occupancy_grid(0.5, Side::Front);
+        assert_eq!(front.get(0, 0), Some(Occupancy::Free));
+    }
+}
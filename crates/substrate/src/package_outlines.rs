@@ -0,0 +1,408 @@
+//! Package outline library
+//!
+//! Body and lead dimensions of standard IC packages keyed by their JEDEC code, so
+//! generators can be built from a code instead of numbers typed from a datasheet (see
+//! `Soic::from_jedec`). Each `PackageOutline` also gives the body rectangle and the KLC
+//! style fab outline, chamfered at the pin 1 corner, for any footprint of that package.
+//!
+//! The built-in table holds the narrow (MS-012) and wide (MS-013) SOICs, SOT-23 in its
+//! three (TO-236-AB), five (MO-178-AA) and six (MO-178-AB) lead forms, and common MO-220
+//! QFNs. MO-220 variant letters encode thickness and terminal options that datasheets
+//! rarely quote, so the QFNs are keyed by body size and pin count instead, e.g.
+//! "MO-220-4x4-24". Values are nominal, except the height, which is the maximum seated
+//! height. Further outlines can be added from TOML:
+//!
+//! ```toml
+//! [[outline]]
+//! code = "MS-012-AA"
+//! family = "soic"
+//! pins = [8]
+//! body_length = 4.9
+//! body_width = 3.9
+//! height = 1.75
+//! pitch = 1.27
+//! lead_span = 6.0
+//! lead_length = 0.835
+//! lead_width = 0.41
+//! ```
+//!
+//! Lengths are in millimeters. The body length runs along the rows of leads (D in JEDEC
+//! drawings) and the body width across them (E1); for leadless packages the lead span is
+//! the body width and the lead length is the terminal length.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Deserialize;
+
+use crate::board_interface::{GraphicElement, GraphicType, Rectangle, Stroke, StrokeType};
+use crate::ids::fresh_uuid;
+use crate::layer_type::{LayerType, Side};
+use crate::pin1::PackageFamily;
+use crate::roles::ElementRole;
+
+const FAB_LINE_WIDTH: f32 = 0.1;
+/// KLC fab chamfer: 1 mm, or a quarter of the shorter body side on small parts
+const MAX_CHAMFER: f32 = 1.0;
+/// Most near matches an unknown code error lists
+const NEAR_MATCHES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutlineFamily {
+    /// Gull-wing leads on two long sides
+    Soic,
+    /// Small outline transistors, gull-wing leads on two sides of a small body
+    Sot,
+    /// Leadless, terminals on all four sides
+    Qfn,
+}
+
+impl OutlineFamily {
+    /// The family whose pin 1 conventions apply
+    pub fn package_family(&self) -> PackageFamily {
+        match self {
+            OutlineFamily::Soic => PackageFamily::Soic,
+            OutlineFamily::Sot => PackageFamily::Sot,
+            OutlineFamily::Qfn => PackageFamily::Qfn,
+        }
+    }
+
+    fn is_leadless(&self) -> bool {
+        *self == OutlineFamily::Qfn
+    }
+}
+
+impl fmt::Display for OutlineFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OutlineFamily::Soic => "SOIC",
+            OutlineFamily::Sot => "SOT",
+            OutlineFamily::Qfn => "QFN",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PackageOutline {
+    pub code: String,
+    pub family: OutlineFamily,
+    /// Pin counts the outline is defined for
+    pub pins: Vec<usize>,
+    /// Along the rows of leads (D)
+    pub body_length: f32,
+    /// Across the rows of leads (E1)
+    pub body_width: f32,
+    /// Maximum seated height (A)
+    pub height: f32,
+    /// Lead pitch (e)
+    pub pitch: f32,
+    /// Toe to toe across the rows (E)
+    pub lead_span: f32,
+    /// Foot length (L), the terminal length for leadless packages
+    pub lead_length: f32,
+    /// Lead width (b)
+    pub lead_width: f32,
+}
+
+impl PackageOutline {
+    /// Body rectangle centered on the origin, rows of leads running along Y
+    pub fn body(&self) -> Rectangle {
+        let (half_x, half_y) = (self.body_width / 2.0, self.body_length / 2.0);
+        Rectangle { min_x: -half_x, min_y: -half_y, max_x: half_x, max_y: half_y }
+    }
+
+    /// F.Fab body outline chamfered at the top left, pin 1, corner
+    pub fn fab_outline(&self) -> GraphicElement {
+        chamfered_fab_outline(&self.body())
+    }
+
+    fn validate(&self) -> Result<(), OutlineError> {
+        let values = [
+            ("body_length", self.body_length),
+            ("body_width", self.body_width),
+            ("height", self.height),
+            ("pitch", self.pitch),
+            ("lead_span", self.lead_span),
+            ("lead_length", self.lead_length),
+            ("lead_width", self.lead_width),
+        ];
+        for (field, value) in values {
+            if !(value.is_finite() && value > 0.0) {
+                return Err(OutlineError::Invalid(format!("{}: {} must be positive, got {}", self.code, field, value)));
+            }
+        }
+        if self.pins.is_empty() || self.pins.contains(&0) {
+            return Err(OutlineError::Invalid(format!("{}: pins must list at least one pin count", self.code)));
+        }
+        if !self.family.is_leadless() && self.lead_span < self.body_width {
+            return Err(OutlineError::Invalid(format!(
+                "{}: lead_span {} is narrower than the body ({})",
+                self.code, self.lead_span, self.body_width
+            )));
+        }
+        if 2.0 * self.lead_length >= self.lead_span {
+            return Err(OutlineError::Invalid(format!("{}: leads {} long don't fit a span of {}", self.code, self.lead_length, self.lead_span)));
+        }
+        Ok(())
+    }
+}
+
+/// KLC style fab outline of `body`: a closed polygon with the top left corner cut off
+pub(crate) fn chamfered_fab_outline(body: &Rectangle) -> GraphicElement {
+    let chamfer = MAX_CHAMFER.min((body.max_x - body.min_x).min(body.max_y - body.min_y) / 4.0);
+    let points = vec![
+        (body.min_x + chamfer, body.min_y),
+        (body.max_x, body.min_y),
+        (body.max_x, body.max_y),
+        (body.min_x, body.max_y),
+        (body.min_x, body.min_y + chamfer),
+    ];
+    GraphicElement {
+        element_type: GraphicType::Polygon { points, fill: None },
+        layer: LayerType::Fabrication,
+        side: Side::Front,
+        stroke: Stroke { width: FAB_LINE_WIDTH, stroke_type: StrokeType::Solid },
+        uuid: fresh_uuid(),
+        role: Some(ElementRole::FabOutline(None)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutlineError {
+    /// No outline of that code; `near` lists similar codes in the library
+    Unknown { code: String, near: Vec<String> },
+    /// The outline isn't defined for that many pins
+    PinCount { code: String, pins: usize, defined: Vec<usize> },
+    /// A generator was given an outline of another family
+    WrongFamily { code: String, family: OutlineFamily, expected: OutlineFamily },
+    Parse(String),
+    Invalid(String),
+}
+
+impl fmt::Display for OutlineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutlineError::Unknown { code, near } if near.is_empty() => write!(f, "unknown package outline \"{}\"", code),
+            OutlineError::Unknown { code, near } => {
+                write!(f, "unknown package outline \"{}\", did you mean {}?", code, near.join(", "))
+            }
+            OutlineError::PinCount { code, pins, defined } => {
+                let defined: Vec<String> = defined.iter().map(usize::to_string).collect();
+                write!(f, "{} has no {} pin variant (defined for {} pins)", code, pins, defined.join(", "))
+            }
+            OutlineError::WrongFamily { code, family, expected } => {
+                write!(f, "{} is a {} outline, not {}", code, family, expected)
+            }
+            OutlineError::Parse(message) => write!(f, "invalid package outline file: {}", message),
+            OutlineError::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for OutlineError {}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OutlineFile {
+    #[serde(default)]
+    outline: Vec<PackageOutline>,
+}
+
+/// Outlines by code, see the module documentation
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OutlineLibrary {
+    outlines: BTreeMap<String, PackageOutline>,
+}
+
+impl OutlineLibrary {
+    /// MS-012 and MS-013 SOICs, SOT-23 variants and common MO-220 QFNs
+    pub fn builtin() -> Self {
+        let outline = |code: &str, family, pins: &[usize], length, width, height, pitch, span, lead_length, lead_width| {
+            PackageOutline {
+                code: code.to_string(),
+                family,
+                pins: pins.to_vec(),
+                body_length: length,
+                body_width: width,
+                height,
+                pitch,
+                lead_span: span,
+                lead_length,
+                lead_width,
+            }
+        };
+        let mut library = Self::default();
+        for entry in [
+            outline("MS-012-AA", OutlineFamily::Soic, &[8], 4.9, 3.9, 1.75, 1.27, 6.0, 0.835, 0.41),
+            outline("MS-012-AB", OutlineFamily::Soic, &[14], 8.65, 3.9, 1.75, 1.27, 6.0, 0.835, 0.41),
+            outline("MS-012-AC", OutlineFamily::Soic, &[16], 9.9, 3.9, 1.75, 1.27, 6.0, 0.835, 0.41),
+            outline("MS-013-AA", OutlineFamily::Soic, &[16], 10.3, 7.5, 2.65, 1.27, 10.3, 0.835, 0.41),
+            outline("MS-013-AC", OutlineFamily::Soic, &[20], 12.8, 7.5, 2.65, 1.27, 10.3, 0.835, 0.41),
+            outline("MS-013-AD", OutlineFamily::Soic, &[24], 15.4, 7.5, 2.65, 1.27, 10.3, 0.835, 0.41),
+            outline("MS-013-AE", OutlineFamily::Soic, &[28], 17.9, 7.5, 2.65, 1.27, 10.3, 0.835, 0.41),
+            outline("TO-236-AB", OutlineFamily::Sot, &[3], 2.9, 1.3, 1.12, 0.95, 2.4, 0.45, 0.4),
+            outline("MO-178-AA", OutlineFamily::Sot, &[5], 2.9, 1.6, 1.45, 0.95, 2.8, 0.45, 0.4),
+            outline("MO-178-AB", OutlineFamily::Sot, &[6], 2.9, 1.6, 1.45, 0.95, 2.8, 0.45, 0.4),
+            outline("MO-220-3x3-16", OutlineFamily::Qfn, &[16], 3.0, 3.0, 1.0, 0.5, 3.0, 0.4, 0.25),
+            outline("MO-220-4x4-16", OutlineFamily::Qfn, &[16], 4.0, 4.0, 1.0, 0.65, 4.0, 0.4, 0.3),
+            outline("MO-220-4x4-24", OutlineFamily::Qfn, &[24], 4.0, 4.0, 1.0, 0.5, 4.0, 0.4, 0.25),
+            outline("MO-220-5x5-32", OutlineFamily::Qfn, &[32], 5.0, 5.0, 1.0, 0.5, 5.0, 0.4, 0.25),
+        ] {
+            library.outlines.insert(entry.code.clone(), entry);
+        }
+        library
+    }
+
+    /// Add an outline, replacing any of the same code
+    pub fn register(&mut self, outline: PackageOutline) -> Result<(), OutlineError> {
+        outline.validate()?;
+        self.outlines.insert(outline.code.clone(), outline);
+        Ok(())
+    }
+
+    /// Register every `[[outline]]` of a TOML file
+    pub fn load_toml(&mut self, text: &str) -> Result<(), OutlineError> {
+        let file: OutlineFile = toml::from_str(text).map_err(|e| OutlineError::Parse(e.to_string().trim_end().to_string()))?;
+        for outline in &file.outline {
+            outline.validate()?;
+        }
+        for outline in file.outline {
+            self.outlines.insert(outline.code.clone(), outline);
+        }
+        Ok(())
+    }
+
+    pub fn with_toml(mut self, text: &str) -> Result<Self, OutlineError> {
+        self.load_toml(text)?;
+        Ok(self)
+    }
+
+    pub fn get(&self, code: &str) -> Result<&PackageOutline, OutlineError> {
+        self.outlines.get(code).ok_or_else(|| OutlineError::Unknown { code: code.to_string(), near: self.near_matches(code) })
+    }
+
+    /// `code`, which must be defined for `pins` pins
+    pub fn get_with_pins(&self, code: &str, pins: usize) -> Result<&PackageOutline, OutlineError> {
+        let outline = self.get(code)?;
+        if !outline.pins.contains(&pins) {
+            return Err(OutlineError::PinCount { code: code.to_string(), pins, defined: outline.pins.clone() });
+        }
+        Ok(outline)
+    }
+
+    pub fn codes(&self) -> impl Iterator<Item = &str> {
+        self.outlines.keys().map(String::as_str)
+    }
+
+    /// Codes within a few edits of `code`, ignoring case, or sharing its series
+    /// ("MS-012" for "MS-012-AZ"), closest first
+    fn near_matches(&self, code: &str) -> Vec<String> {
+        let wanted = code.to_uppercase();
+        let series = wanted.rsplit_once('-').map_or(wanted.as_str(), |(series, _)| series);
+        let mut near: Vec<(usize, &str)> = self
+            .codes()
+            .map(|candidate| (edit_distance(&wanted, &candidate.to_uppercase()), candidate))
+            .filter(|(distance, candidate)| *distance <= 3 || (series.len() > 2 && candidate.starts_with(series)))
+            .collect();
+        near.sort();
+        near.into_iter().take(NEAR_MATCHES).map(|(_, candidate)| candidate.to_string()).collect()
+    }
+}
+
+/// Levenshtein distance in characters
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a != *b);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VENDOR_SOIC: &str = r#"
+        [[outline]]
+        code = "SOIC-8-EP-VENDOR"
+        family = "soic"
+        pins = [8]
+        body_length = 4.9
+        body_width = 3.9
+        height = 1.7
+        pitch = 1.27
+        lead_span = 6.0
+        lead_length = 0.8
+        lead_width = 0.42
+    "#;
+
+    #[test]
+    fn builtin_outlines_match_their_datasheets() {
+        let library = OutlineLibrary::builtin();
+        // Code, datasheet, body length x width, span, pitch, max height
+        for (code, datasheet, body, span, pitch, height) in [
+            ("MS-012-AA", "TI D (SOIC-8)", (4.9, 3.9), 6.0, 1.27, 1.75),
+            ("MS-013-AC", "TI DW (SOIC-20)", (12.8, 7.5), 10.3, 1.27, 2.65),
+            ("TO-236-AB", "Nexperia SOT23", (2.9, 1.3), 2.4, 0.95, 1.12),
+        ] {
+            let outline = library.get(code).unwrap();
+            let found = ((outline.body_length, outline.body_width), outline.lead_span, outline.pitch, outline.height);
+            assert_eq!(found, (body, span, pitch, height), "{} vs the {} datasheet", code, datasheet);
+        }
+    }
+
+    #[test]
+    fn unknown_codes_suggest_near_matches() {
+        let library = OutlineLibrary::builtin();
+        let Err(OutlineError::Unknown { near, .. }) = library.get("MS-012-AZ") else {
+            panic!("MS-012-AZ is not in the library");
+        };
+        assert!(near.iter().any(|code| code == "MS-012-AA"), "{:?}", near);
+        let Err(OutlineError::Unknown { near, .. }) = library.get("ms-012-aa") else {
+            panic!("codes are case sensitive");
+        };
+        assert_eq!(near.first().map(String::as_str), Some("MS-012-AA"));
+        let error = library.get("XYZ").unwrap_err();
+        assert_eq!(error, OutlineError::Unknown { code: "XYZ".to_string(), near: Vec::new() });
+        assert_eq!(error.to_string(), "unknown package outline \"XYZ\"");
+    }
+
+    #[test]
+    fn pin_counts_must_be_defined() {
+        let error = OutlineLibrary::builtin().get_with_pins("MS-012-AA", 14).unwrap_err();
+        assert_eq!(error, OutlineError::PinCount { code: "MS-012-AA".to_string(), pins: 14, defined: vec![8] });
+    }
+
+    #[test]
+    fn toml_outlines_join_the_library() {
+        let library = OutlineLibrary::builtin().with_toml(VENDOR_SOIC).unwrap();
+        let outline = library.get("SOIC-8-EP-VENDOR").unwrap();
+        assert_eq!((outline.family, outline.lead_width), (OutlineFamily::Soic, 0.42));
+        assert!(library.codes().any(|code| code == "MS-012-AA"));
+    }
+
+    #[test]
+    fn implausible_outlines_are_rejected() {
+        let narrow = VENDOR_SOIC.replace("lead_span = 6.0", "lead_span = 3.0");
+        assert!(matches!(OutlineLibrary::builtin().with_toml(&narrow), Err(OutlineError::Invalid(_))));
+        assert!(matches!(OutlineLibrary::builtin().with_toml("[[outline]]\ncode = 1\n"), Err(OutlineError::Parse(_))));
+    }
+
+    #[test]
+    fn fab_outline_is_a_chamfered_body() {
+        let qfn = OutlineLibrary::builtin().get("MO-220-4x4-24").unwrap().clone();
+        assert_eq!(qfn.body(), Rectangle { min_x: -2.0, min_y: -2.0, max_x: 2.0, max_y: 2.0 });
+        let fab = qfn.fab_outline();
+        assert_eq!(fab.layer, LayerType::Fabrication);
+        assert!(matches!(&fab.element_type, GraphicType::Polygon { points, fill: None } if points.len() == 5));
+    }
+}
@@ -3,17 +3,117 @@
 //! This module defines the various physical package types that electronic components
 //! can have, including surface mount (SMT), through-hole, BGA, and QFP packages.
 //! It provides the Package enum and the PackageType trait for polymorphic package handling.
+//!
+//! Packages with a generator behind them are named the way a BOM names them and parse
+//! from that text: "0603", "SOIC-8", "QFN-24", "DIP-8", "TO-252-3", or a JEDEC code of
+//! the built-in outline library such as "MS-013-AA". `resolve` turns them into footprints.
+
+use std::fmt;
+
+use crate::package_outlines::{OutlineFamily, OutlineLibrary};
 
 /// Package Enumeration
-/// 
+///
 /// Defines the different types of packages that components can have.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Package {
     SMT { size: (f32, f32), pitch: Option<f32> }, // 0603, 0805, etc.
     ThroughHole { spacing: f32, drill_size: f32 },
     BGA { pitch: f32, array_size: (u32, u32) },
     QFP { pitch: f32, pin_count: u32 },
+    /// Two terminal chip or SMD crystal by size code, e.g. "0603" or "3225"
+    Chip { code: String },
+    /// SOIC, on the narrowest built-in outline with `pins` unless `outline` names one
+    Soic { pins: usize, outline: Option<String> },
+    /// QFN, on the smallest built-in outline with `pins` unless `outline` names one
+    Qfn { pins: usize, outline: Option<String> },
+    /// Narrow through hole DIP
+    Dip { pins: usize },
+    /// TO-252 (DPAK) or TO-263 (D2PAK) with `leads` leads besides the tab
+    Dpak { package: String, leads: usize },
+    /// Anything else, by name, for resolver rules registered by the user
+    Named(String),
+}
+
+impl Package {
+    /// Read a package name, see the module documentation. Names that aren't recognized
+    /// become `Named`.
+    pub fn parse(text: &str) -> Self {
+        let text = text.trim();
+        let upper = text.to_ascii_uppercase();
+        let pins = |prefix: &str| upper.strip_prefix(prefix).and_then(|count| count.parse::<usize>().ok());
+        if !text.is_empty() && text.len() <= 5 && text.chars().all(|c| c.is_ascii_digit()) {
+            return Package::Chip { code: text.to_string() };
+        }
+        if let Some(pins) = pins("SOIC-") {
+            return Package::Soic { pins, outline: None };
+        }
+        if let Some(pins) = pins("QFN-") {
+            return Package::Qfn { pins, outline: None };
+        }
+        if let Some(pins) = pins("DIP-") {
+            return Package::Dip { pins };
+        }
+        if let Some((package, leads)) = upper.rsplit_once('-')
+            && (package == "TO-252" || package == "TO-263")
+            && let Ok(leads) = leads.parse()
+        {
+            return Package::Dpak { package: package.to_string(), leads };
+        }
+        let library = OutlineLibrary::builtin();
+        if let Ok(outline) = library.get(text).or_else(|_| library.get(&upper))
+            && let [pins] = outline.pins[..]
+        {
+            let code = Some(outline.code.clone());
+            match outline.family {
+                OutlineFamily::Soic => return Package::Soic { pins, outline: code },
+                OutlineFamily::Qfn => return Package::Qfn { pins, outline: code },
+                _ => {}
+            }
+        }
+        Package::Named(text.to_string())
+    }
+
+    /// Name the resolver registers rules under, e.g. "soic"; `Named` packages are their
+    /// own class without a trailing pin count ("SOT-23" for "SOT-23-5")
+    pub fn class(&self) -> String {
+        match self {
+            Package::SMT { .. } => "smt".to_string(),
+            Package::ThroughHole { .. } => "through_hole".to_string(),
+            Package::BGA { .. } => "bga".to_string(),
+            Package::QFP { .. } => "qfp".to_string(),
+            Package::Chip { .. } => "chip".to_string(),
+            Package::Soic { .. } => "soic".to_string(),
+            Package::Qfn { .. } => "qfn".to_string(),
+            Package::Dip { .. } => "dip".to_string(),
+            Package::Dpak { .. } => "dpak".to_string(),
+            Package::Named(name) => match name.rsplit_once('-') {
+                Some((family, count)) if !count.is_empty() && count.chars().all(|c| c.is_ascii_digit()) && family.contains('-') => {
+                    family.to_string()
+                }
+                _ => name.clone(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Package {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Package::SMT { size, .. } => write!(f, "SMT {}x{}mm", size.0, size.1),
+            Package::ThroughHole { spacing, drill_size } => write!(f, "THT {}mm pitch, {}mm drill", spacing, drill_size),
+            Package::BGA { pitch, array_size } => write!(f, "BGA {}x{} P{}mm", array_size.0, array_size.1, pitch),
+            Package::QFP { pitch, pin_count } => write!(f, "QFP-{} P{}mm", pin_count, pitch),
+            Package::Chip { code } => f.write_str(code),
+            Package::Soic { outline: Some(code), .. } | Package::Qfn { outline: Some(code), .. } => f.write_str(code),
+            Package::Soic { pins, outline: None } => write!(f, "SOIC-{}", pins),
+            Package::Qfn { pins, outline: None } => write!(f, "QFN-{}", pins),
+            Package::Dip { pins } => write!(f, "DIP-{}", pins),
+            Package::Dpak { package, leads } => write!(f, "{}-{}", package, leads),
+            Package::Named(name) => f.write_str(name),
+        }
+    }
 }
 
 pub trait PackageType: std::fmt::Debug + Clone {}
-impl PackageType for Package {}
\ No newline at end of file
+impl PackageType for Package {}
@@ -0,0 +1,272 @@
+//! Pad shape tessellation
+//!
+//! Turns a `PadDescriptor` (shape, size, rotation and position) into real 2D geometry so
+//! that SVG/gerber output, 3D pad extrusion and clearance checks all agree on what a pad
+//! looks like instead of each rolling their own approximation.
+//!
+//! Every supported shape is treated as a rounded rectangle: a plain rectangle has a zero
+//! corner radius, an oval has a radius of half its short side and a circle is an oval with
+//! equal sides. Custom pads are their polygon, which covers the anchor rectangle.
+
+use crate::board_interface::{PadDescriptor, PadShape, Rectangle};
+use crate::geometry::{polygon_area, polygon_signed_area, push_arc, Point};
+
+/// KiCad's default corner ratio for roundrect pads when none is specified
+pub const DEFAULT_ROUNDRECT_RATIO: f32 = 0.25;
+
+impl PadDescriptor {
+    /// Corner radius of the pad outline in millimeters
+    pub fn corner_radius(&self) -> f32 {
+        let (width, height) = self.shape_size();
+        let short_side = width.min(height);
+        match self.shape {
+            PadShape::Rect | PadShape::Custom(_) => 0.0,
+            PadShape::Circle | PadShape::Oval => short_side / 2.0,
+            PadShape::RoundRect => {
+                short_side * self.roundrect_ratio.unwrap_or(DEFAULT_ROUNDRECT_RATIO).clamp(0.0, 0.5)
+            }
+        }
+    }
+
+    /// Pad outline as a closed polygon (last point not repeated) in footprint coordinates.
+    ///
+    /// Corner arcs, oval end caps and circles are tessellated so that no edge deviates from
+    /// the true outline by more than `arc_tolerance` millimeters. The polygon is inscribed in
+    /// the true shape, so its area converges on the analytic pad area from below.
+    pub fn outline_polygon(&self, arc_tolerance: f32) -> Vec<Point> {
+        let rotation = self.rotation.unwrap_or(0.0);
+        if let PadShape::Custom(points) = &self.shape {
+            return points
+                .iter()
+                .map(|&(x, y)| Point::new(x, y).rotated(rotation).offset(self.position.0, self.position.1))
+                .collect();
+        }
+        let (width, height) = self.shape_size();
+        let radius = self.corner_radius();
+        let (inner_x, inner_y) = (width / 2.0 - radius, height / 2.0 - radius);
+
+        let mut local = Vec::new();
+        // Walk the corners clockwise on screen (Y down), each arc sweeping a quarter turn
+        let corners = [(inner_x, inner_y, 0.0), (-inner_x, inner_y, 90.0), (-inner_x, -inner_y, 180.0), (inner_x, -inner_y, 270.0)];
+        for (cx, cy, start) in corners {
+            let center = Point::new(cx, cy);
+            let start_rad = f32::to_radians(start);
+            local.push(Point::new(cx + radius * start_rad.cos(), cy + radius * start_rad.sin()));
+            if radius > 0.0 {
+                push_arc(&mut local, center, radius, start, 90.0, arc_tolerance);
+            }
+        }
+        dedup_closed(&mut local);
+
+        local
+            .into_iter()
+            .map(|p| p.rotated(rotation).offset(self.position.0, self.position.1))
+            .collect()
+    }
+
+    /// Exact axis-aligned bounds of the pad outline in footprint coordinates, accounting
+    /// for rotation and rounded corners (not derived from the tessellated polygon)
+    pub fn outline_aabb(&self) -> Rectangle {
+        if let PadShape::Custom(_) = self.shape {
+            let center = Rectangle { min_x: self.position.0, min_y: self.position.1, max_x: self.position.0, max_y: self.position.1 };
+            return Rectangle::enclosing(&self.outline_polygon(0.0)).unwrap_or(center);
+        }
+        let (width, height) = self.shape_size();
+        let radius = self.corner_radius();
+        let (inner_x, inner_y) = (width / 2.0 - radius, height / 2.0 - radius);
+        let rotation = self.rotation.unwrap_or(0.0);
+
+        let inner: Vec<Point> = [(inner_x, inner_y), (-inner_x, inner_y), (-inner_x, -inner_y), (inner_x, -inner_y)]
+            .into_iter()
+            .map(|(x, y)| Point::new(x, y).rotated(rotation))
+            .collect();
+        let inner = Rectangle::enclosing(&inner).expect("four corners");
+
+        Rectangle {
+            min_x: self.position.0 + inner.min_x - radius,
+            min_y: self.position.1 + inner.min_y - radius,
+            max_x: self.position.0 + inner.max_x + radius,
+            max_y: self.position.1 + inner.max_y + radius,
+        }
+    }
+
+    /// Analytic area of the pad outline in square millimeters
+    pub fn outline_area(&self) -> f32 {
+        if let PadShape::Custom(_) = self.shape {
+            return polygon_area(&self.outline_polygon(0.0));
+        }
+        let (width, height) = self.shape_size();
+        let radius = self.corner_radius();
+        width * height - (4.0 - std::f32::consts::PI) * radius * radius
+    }
+
+    /// Solder mask margin per side of the pad: its own `solder_mask_margin` if it has one,
+    /// otherwise `global_margin`
+    pub fn effective_mask_margin(&self, global_margin: f32) -> f32 {
+        self.solder_mask_margin.unwrap_or(global_margin)
+    }
+
+    /// Solder mask opening of the pad as a closed polygon in footprint coordinates,
+    /// tessellated like `outline_polygon`; `None` when the pad is on no mask layer.
+    ///
+    /// A pad with copper opens its copper grown by the effective margin, so a negative
+    /// margin leaves the opening smaller than the copper. The corners of the grown outline
+    /// are rounded by the margin, as a true offset is; custom pads are offset at mitered
+    /// corners. A pad with no copper is mask geometry drawn as is, and opens exactly its
+    /// outline whatever the margins.
+    pub fn mask_aperture(&self, global_margin: f32, arc_tolerance: f32) -> Option<Vec<Point>> {
+        self.mask_opening(global_margin).map(|opening| opening.outline_polygon(arc_tolerance))
+    }
+
+    /// Exact axis-aligned bounds of `mask_aperture`
+    pub fn mask_aperture_aabb(&self, global_margin: f32) -> Option<Rectangle> {
+        self.mask_opening(global_margin).map(|opening| opening.outline_aabb())
+    }
+
+    /// The pad shaped as its mask opening, see `mask_aperture`
+    fn mask_opening(&self, global_margin: f32) -> Option<PadDescriptor> {
+        if !self.layers.iter().any(|layer| layer.ends_with(".Mask")) {
+            return None;
+        }
+        let margin = self.effective_mask_margin(global_margin);
+        if !self.has_copper() || margin == 0.0 {
+            return Some(self.clone());
+        }
+        let mut opening = PadDescriptor { padstack: None, ..self.clone() };
+        if let PadShape::Custom(points) = &self.shape {
+            let points: Vec<Point> = points.iter().map(|&(x, y)| Point::new(x, y)).collect();
+            opening.shape = PadShape::Custom(offset_polygon(&points, margin).iter().map(|p| (p.x, p.y)).collect());
+            return Some(opening);
+        }
+        let (width, height) = self.shape_size();
+        let size = ((width + 2.0 * margin).max(0.0), (height + 2.0 * margin).max(0.0));
+        let short_side = size.0.min(size.1);
+        let radius = (self.corner_radius() + margin).clamp(0.0, short_side / 2.0);
+        opening.shape = PadShape::RoundRect;
+        opening.size = size;
+        opening.roundrect_ratio = Some(if short_side > 0.0 { radius / short_side } else { 0.0 });
+        Some(opening)
+    }
+
+    /// Ends of a slotted drill's center line in footprint coordinates, `None` for round
+    /// drills. A slot as wide as it is long is a round hole.
+    pub fn slot_ends(&self) -> Option<(Point, Point)> {
+        let (width, height) = self.drill_slot?;
+        let half_length = (width - height).abs() / 2.0;
+        if half_length <= f32::EPSILON {
+            return None;
+        }
+        let along = if width > height { Point::new(half_length, 0.0) } else { Point::new(0.0, half_length) };
+        let along = along.rotated(self.rotation.unwrap_or(0.0));
+        Some((
+            Point::new(self.position.0 - along.x, self.position.1 - along.y),
+            Point::new(self.position.0 + along.x, self.position.1 + along.y),
+        ))
+    }
+
+    /// Outline of the hole in footprint coordinates, tessellated like `outline_polygon`:
+    /// a circle, or an oval for a slot
+    pub fn drill_outline(&self, arc_tolerance: f32) -> Option<Vec<Point>> {
+        let (width, height) = self.drill_extent()?;
+        let hole = PadDescriptor {
+            shape: if self.drill_slot.is_some() { PadShape::Oval } else { PadShape::Circle },
+            size: (width, height),
+            padstack: None,
+            ..self.clone()
+        };
+        Some(hole.outline_polygon(arc_tolerance))
+    }
+
+    /// Circles only honour the X size, as in KiCad
+    fn shape_size(&self) -> (f32, f32) {
+        match self.shape {
+            PadShape::Circle => (self.size.0, self.size.0),
+            _ => self.size,
+        }
+    }
+}
+
+/// Remove consecutive coincident points, including across the closing edge
+fn dedup_closed(points: &mut Vec<Point>) {
+    const EPSILON: f32 = 1e-6;
+    points.dedup_by(|b, a| a.distance_to(b) < EPSILON);
+    while points.len() > 1 && points[0].distance_to(points.last().unwrap()) < EPSILON {
+        points.pop();
+    }
+}
+
+/// Polygon moved out by `distance` (in when negative), each corner mitered
+pub(crate) fn offset_polygon(polygon: &[Point], distance: f32) -> Vec<Point> {
+    // Outward normals are to the right of each edge when the signed area is positive
+    let outward = if polygon_signed_area(polygon) < 0.0 { -1.0 } else { 1.0 };
+    let normal = |a: Point, b: Point| {
+        let length = a.distance_to(&b).max(f32::EPSILON);
+        Point::new(outward * (b.y - a.y) / length, outward * (a.x - b.x) / length)
+    };
+    let count = polygon.len();
+    (0..count)
+        .map(|i| {
+            let (before, point, after) = (polygon[(i + count - 1) % count], polygon[i], polygon[(i + 1) % count]);
+            let (a, b) = (normal(before, point), normal(point, after));
+            let scale = distance / (1.0 + a.x * b.x + a.y * b.y).max(f32::EPSILON);
+            point.offset((a.x + b.x) * scale, (a.y + b.y) * scale)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_interface::PadType;
+    use crate::layer_type::PadLayers;
+
+    fn smd(shape: PadShape, size: (f32, f32)) -> PadDescriptor {
+        PadDescriptor::new("1", PadType::SMD, shape, (1.0, 2.0), size, PadLayers::smd_front())
+    }
+
+    fn bounds(pad: &PadDescriptor, global_margin: f32) -> (f32, f32) {
+        let aabb = pad.mask_aperture_aabb(global_margin).unwrap();
+        (aabb.width(), aabb.height())
+    }
+
+    #[test]
+    fn mask_openings_follow_the_margin() {
+        let pad = smd(PadShape::Rect, (1.0, 0.6));
+        assert_eq!(pad.mask_aperture(0.0, 0.01).unwrap(), pad.outline_polygon(0.01));
+        let grown = bounds(&pad, 0.05);
+        assert!((grown.0 - 1.1).abs() < 1e-5 && (grown.1 - 0.7).abs() < 1e-5, "{:?}", grown);
+        let shrunk = bounds(&pad, -0.05);
+        assert!((shrunk.0 - 0.9).abs() < 1e-5 && (shrunk.1 - 0.5).abs() < 1e-5, "{:?}", shrunk);
+        // A grown rectangle is rounded at its corners by the margin
+        let area = polygon_area(&pad.mask_aperture(0.05, 0.0001).unwrap());
+        assert!((area - (1.1 * 0.7 - (4.0 - std::f32::consts::PI) * 0.0025)).abs() < 1e-4, "{}", area);
+
+        let circle = smd(PadShape::Circle, (0.5, 0.5));
+        let grown = bounds(&circle, 0.1);
+        assert!((grown.0 - 0.7).abs() < 1e-5 && (grown.1 - 0.7).abs() < 1e-5, "{:?}", grown);
+    }
+
+    #[test]
+    fn a_pads_own_margin_wins() {
+        let pad = smd(PadShape::RoundRect, (1.0, 0.6)).with_solder_mask_margin(-0.02);
+        assert_eq!(pad.effective_mask_margin(0.1), -0.02);
+        let opening = bounds(&pad, 0.1);
+        assert!((opening.0 - 0.96).abs() < 1e-5 && (opening.1 - 0.56).abs() < 1e-5, "{:?}", opening);
+        let zero = pad.clone().with_solder_mask_margin(0.0);
+        assert_eq!(zero.mask_aperture(0.1, 0.01).unwrap(), zero.outline_polygon(0.01));
+    }
+
+    #[test]
+    fn mask_geometry_is_taken_as_drawn() {
+        let window = PadDescriptor::new("", PadType::SMD, PadShape::Rect, (0.0, 0.0), (2.0, 1.0), ["F.Mask"]);
+        assert_eq!(bounds(&window, 0.1), (2.0, 1.0));
+        let tented = PadDescriptor::new("1", PadType::SMD, PadShape::Rect, (0.0, 0.0), (2.0, 1.0), ["F.Cu"]);
+        assert!(tented.mask_aperture(0.1, 0.01).is_none());
+        // Custom pads are offset at every corner
+        let triangle = smd(PadShape::Custom(vec![(0.0, -0.5), (0.5, 0.5), (-0.5, 0.5)]), (0.2, 0.2));
+        let grown = triangle.mask_aperture(0.05, 0.01).unwrap();
+        let outline = triangle.outline_polygon(0.01);
+        assert!(polygon_area(&grown) > polygon_area(&outline));
+        assert!((grown[1].y - outline[1].y - 0.05).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,289 @@
+//! Pads that share a number or are joined inside the footprint
+//!
+//! Pads with the same number form a group. A footprint says what its groups are meant to
+//! be with `BoardComposableObject::pad_grouping`:
+//!
+//! - terminals: one terminal spread over pads the board joins, such as a DPAK's tab and
+//!   middle lead, which is how KiCad reads a repeated number
+//! - jumpers: every repeated number is a set of pads already joined inside the part, so
+//!   the board needn't join them; KiCad 9's `duplicate_pad_numbers_are_jumpers`
+//! - net ties: pads of different numbers, each on its own net, joined by copper in the
+//!   footprint; KiCad's `net_tie_pad_groups`
+//!
+//! A group whose copper touches is one pad drawn in pieces, such as vias in an exposed
+//! pad, and needs no declaration. A repeated number that is none of these is an
+//! accidental duplicate, which `FootprintDrc` reports.
+//!
+//! `diff_pads` compares two pad lists pad by pad. Pads are matched by number and position,
+//! so the pads of a group line up whatever order they come in.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::board_interface::PadDescriptor;
+use crate::geometry::{polygon_distance, Point};
+
+/// Largest distance between two pad positions taken as the same place (mm)
+const POSITION_TOLERANCE: f32 = 1e-4;
+/// Arc tolerance of the pad outlines checked for touching copper
+const OUTLINE_ARC_TOLERANCE: f32 = 0.005;
+
+/// What a footprint's repeated and joined pads are meant to be, see the module
+/// documentation
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PadGrouping {
+    /// Numbers deliberately repeated on the pads of one terminal
+    pub terminals: Vec<String>,
+    /// Pads sharing a number are joined inside the part
+    pub duplicates_are_jumpers: bool,
+    /// Numbers joined by copper in the footprint, one list per tie
+    pub net_ties: Vec<Vec<String>>,
+}
+
+impl PadGrouping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_terminal(mut self, number: &str) -> Self {
+        self.terminals.push(number.to_string());
+        self
+    }
+
+    pub fn with_jumpers(mut self) -> Self {
+        self.duplicates_are_jumpers = true;
+        self
+    }
+
+    pub fn with_net_tie(mut self, numbers: &[&str]) -> Self {
+        self.net_ties.push(numbers.iter().map(|number| number.to_string()).collect());
+        self
+    }
+
+    /// The net tie `number` belongs to
+    pub fn net_tie_of(&self, number: &str) -> Option<usize> {
+        self.net_ties.iter().position(|tie| tie.iter().any(|n| n == number))
+    }
+
+    /// Checks the declaration against the pads it describes
+    pub fn validate(&self, pads: &[PadDescriptor]) -> Result<(), PadGroupingError> {
+        let known = |number: &String| pads.iter().any(|pad| pad.number == number.as_str());
+        if let Some(number) = self.terminals.iter().chain(self.net_ties.iter().flatten()).find(|number| !known(number)) {
+            return Err(PadGroupingError::UnknownNumber(number.clone()));
+        }
+        for (index, tie) in self.net_ties.iter().enumerate() {
+            let mut numbers = tie.clone();
+            numbers.sort();
+            numbers.dedup();
+            if numbers.len() < 2 {
+                return Err(PadGroupingError::NetTieTooSmall(tie.clone()));
+            }
+            if let Some(number) = tie.iter().find(|number| self.net_ties[index + 1..].iter().any(|other| other.contains(number))) {
+                return Err(PadGroupingError::NumberInTwoNetTies(number.clone()));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PadGroupingError {
+    /// A declared terminal or net tie names a number no pad has
+    UnknownNumber(String),
+    /// A net tie with fewer than two different numbers
+    NetTieTooSmall(Vec<String>),
+    /// A number in two net ties, which would make them one
+    NumberInTwoNetTies(String),
+}
+
+impl fmt::Display for PadGroupingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PadGroupingError::UnknownNumber(number) => write!(f, "no pad is numbered {}", number),
+            PadGroupingError::NetTieTooSmall(numbers) => write!(f, "net tie \"{}\" joins fewer than two pads", numbers.join(", ")),
+            PadGroupingError::NumberInTwoNetTies(number) => write!(f, "pad {} is in two net ties", number),
+        }
+    }
+}
+
+impl std::error::Error for PadGroupingError {}
+
+/// What a group of same-number pads is, see the module documentation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PadGroupKind {
+    /// A number on one pad only
+    Single,
+    /// Pads whose copper touches, or with copper on at most one of them
+    Joined,
+    /// Pads of one declared terminal
+    Terminal,
+    /// Pads joined inside the part
+    Jumper,
+    /// A repeated number the footprint doesn't declare
+    Undeclared,
+}
+
+/// Pads sharing a number
+#[derive(Debug, Clone, PartialEq)]
+pub struct PadGroup {
+    pub number: String,
+    /// Indices of the pads, in pad order
+    pub pads: Vec<usize>,
+    pub kind: PadGroupKind,
+}
+
+/// Groups of the numbered pads in order of their first pad; unnumbered pads belong to
+/// none
+pub fn pad_groups(pads: &[PadDescriptor], grouping: &PadGrouping) -> Vec<PadGroup> {
+    let mut groups: Vec<PadGroup> = Vec::new();
+    for (index, pad) in pads.iter().enumerate().filter(|(_, pad)| !pad.number.is_empty()) {
+        match groups.iter_mut().find(|group| pad.number == group.number.as_str()) {
+            Some(group) => group.pads.push(index),
+            None => groups.push(PadGroup { number: pad.number.to_string(), pads: vec![index], kind: PadGroupKind::Single }),
+        }
+    }
+    for group in groups.iter_mut().filter(|group| group.pads.len() > 1) {
+        group.kind = if grouping.duplicates_are_jumpers {
+            PadGroupKind::Jumper
+        } else if grouping.terminals.contains(&group.number) {
+            PadGroupKind::Terminal
+        } else if copper_joined(pads, &group.pads) {
+            PadGroupKind::Joined
+        } else {
+            PadGroupKind::Undeclared
+        };
+    }
+    groups
+}
+
+/// Whether the copper of the pads is one piece on some layer, counting only pads with
+/// copper
+fn copper_joined(pads: &[PadDescriptor], group: &[usize]) -> bool {
+    let copper: Vec<&PadDescriptor> = group.iter().map(|&index| &pads[index]).filter(|pad| pad.has_copper()).collect();
+    let outlines: Vec<Vec<Point>> = copper.iter().map(|pad| pad.outline_polygon(OUTLINE_ARC_TOLERANCE)).collect();
+    let touch = |a: usize, b: usize| {
+        ["F.Cu", "B.Cu"].iter().any(|layer| copper[a].has_layer(layer) && copper[b].has_layer(layer))
+            && polygon_distance(&outlines[a], &outlines[b]) <= 0.0
+    };
+    // Grow the piece joined to the first pad until nothing more touches it
+    let mut joined: Vec<bool> = (0..copper.len()).map(|index| index == 0).collect();
+    let mut frontier: Vec<usize> = (0..copper.len().min(1)).collect();
+    while let Some(a) = frontier.pop() {
+        let reached: Vec<usize> = (0..copper.len()).filter(|&b| !joined[b] && touch(a, b)).collect();
+        for b in reached {
+            joined[b] = true;
+            frontier.push(b);
+        }
+    }
+    joined.iter().all(|&joined| joined)
+}
+
+/// How two pad lists differ, by pad index, see the module documentation
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PadDiff {
+    /// Pads of the later list with no pad of their number before
+    pub added: Vec<usize>,
+    /// Pads of the earlier list with no pad of their number after
+    pub removed: Vec<usize>,
+    /// Pads of the earlier list that moved, with the later pad taking their place
+    pub moved: Vec<(usize, usize)>,
+    /// Pads in the same place whose shape, size, layers, drill or properties changed
+    pub changed: Vec<(usize, usize)>,
+}
+
+impl PadDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.moved.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Pad by pad differences from `before` to `after`. Pads are matched by number and
+/// position first; pads of a number left over pair up in order as moved. UUIDs are not
+/// compared, generators draw fresh ones.
+pub fn diff_pads(before: &[PadDescriptor], after: &[PadDescriptor]) -> PadDiff {
+    let same_place = |a: &PadDescriptor, b: &PadDescriptor| Point::from(a.position).distance_to(&Point::from(b.position)) <= POSITION_TOLERANCE;
+    let same_pad = |a: &PadDescriptor, b: &PadDescriptor| PadDescriptor { uuid: b.uuid, position: b.position, ..a.clone() } == *b;
+    let mut diff = PadDiff::default();
+    let mut matched = vec![false; before.len()];
+    let mut left: Vec<usize> = Vec::new();
+    for (index, pad) in after.iter().enumerate() {
+        match (0..before.len()).find(|&old| !matched[old] && before[old].number == pad.number && same_place(&before[old], pad)) {
+            Some(old) => {
+                matched[old] = true;
+                if !same_pad(&before[old], pad) {
+                    diff.changed.push((old, index));
+                }
+            }
+            None => left.push(index),
+        }
+    }
+    for index in left {
+        match (0..before.len()).find(|&old| !matched[old] && before[old].number == after[index].number) {
+            Some(old) => {
+                matched[old] = true;
+                diff.moved.push((old, index));
+            }
+            None => diff.added.push(index),
+        }
+    }
+    diff.removed = (0..before.len()).filter(|&old| !matched[old]).collect();
+    diff.moved.sort();
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_interface::{PadShape, PadType};
+
+    fn pad(number: &str, x: f32) -> PadDescriptor {
+        PadDescriptor::new(number, PadType::SMD, PadShape::Rect, (x, 0.0), (1.0, 1.0), ["F.Cu", "F.Paste", "F.Mask"])
+    }
+
+    #[test]
+    fn repeated_numbers_are_grouped_by_what_the_footprint_declares() {
+        // 2 repeated apart, 3 split into two touching halves
+        let pads = vec![pad("1", 0.0), pad("2", 2.0), pad("2", 4.0), pad("3", 6.0), pad("3", 6.9), pad("", 8.0)];
+        let kinds = |grouping: &PadGrouping| -> Vec<(String, PadGroupKind)> {
+            pad_groups(&pads, grouping).into_iter().map(|group| (group.number, group.kind)).collect()
+        };
+        let numbered = |kind| vec![("1".to_string(), PadGroupKind::Single), ("2".to_string(), kind), ("3".to_string(), PadGroupKind::Joined)];
+        assert_eq!(kinds(&PadGrouping::new()), numbered(PadGroupKind::Undeclared));
+        assert_eq!(kinds(&PadGrouping::new().with_terminal("2")), numbered(PadGroupKind::Terminal));
+        let jumpers = kinds(&PadGrouping::new().with_jumpers());
+        assert_eq!(jumpers[1], ("2".to_string(), PadGroupKind::Jumper));
+        assert_eq!(jumpers[2], ("3".to_string(), PadGroupKind::Jumper));
+        assert_eq!(pad_groups(&pads, &PadGrouping::new())[1].pads, [1, 2]);
+    }
+
+    #[test]
+    fn declarations_must_name_pads_that_exist() {
+        let pads = vec![pad("1", 0.0), pad("2", 2.0), pad("3", 4.0)];
+        assert_eq!(PadGrouping::new().with_net_tie(&["1", "2"]).validate(&pads), Ok(()));
+        assert_eq!(PadGrouping::new().with_terminal("4").validate(&pads), Err(PadGroupingError::UnknownNumber("4".to_string())));
+        assert_eq!(
+            PadGrouping::new().with_net_tie(&["1", "1"]).validate(&pads),
+            Err(PadGroupingError::NetTieTooSmall(vec!["1".to_string(), "1".to_string()]))
+        );
+        assert_eq!(
+            PadGrouping::new().with_net_tie(&["1", "2"]).with_net_tie(&["2", "3"]).validate(&pads),
+            Err(PadGroupingError::NumberInTwoNetTies("2".to_string()))
+        );
+    }
+
+    #[test]
+    fn pads_of_a_group_match_by_position_in_any_order() {
+        let before = vec![pad("1", 0.0), pad("2", 2.0), pad("2", 4.0), pad("3", 6.0)];
+        let mut after = vec![pad("2", 4.0), pad("1", 0.0), pad("3", 6.0), pad("2", 2.0)];
+        assert!(diff_pads(&before, &after).is_empty(), "{:?}", diff_pads(&before, &after));
+
+        after[0].size = (1.2, 1.0);
+        after[2].position = (7.0, 0.0);
+        after.push(pad("4", 8.0));
+        let diff = diff_pads(&before, &after[1..]);
+        assert_eq!(diff, PadDiff { added: vec![3], removed: vec![2], moved: vec![(3, 1)], changed: vec![] });
+        let diff = diff_pads(&before, &after);
+        assert_eq!(diff, PadDiff { added: vec![4], removed: vec![], moved: vec![(3, 2)], changed: vec![(2, 0)] });
+    }
+}
@@ -0,0 +1,188 @@
+//! Per-layer pad geometry
+//!
+//! KiCad 9 padstacks let a through-hole pad change shape and size from one copper layer to
+//! the next. The usual case is a connector with full pads on the outer layers and a
+//! smaller annulus on inner layers, which leaves room to route between the pins.
+//!
+//! A `PadDescriptor`'s own shape and size are its front copper. A `PadStack` overrides the
+//! back copper, the inner copper, or both; layers without an override keep the front
+//! geometry, so a pad without a stack is the same on every layer.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::board_interface::{PadDescriptor, PadShape, PadType};
+use crate::fab_profile::FabProfile;
+
+/// Copper layers a pad stack tells apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PadLayerClass {
+    Front,
+    /// Every inner copper layer
+    Inner,
+    Back,
+}
+
+impl PadLayerClass {
+    /// Class of a copper layer name ("F.Cu", "In2.Cu", "B.Cu"), `None` for other layers
+    pub fn of_layer(layer: &str) -> Option<Self> {
+        match layer {
+            "F.Cu" => Some(PadLayerClass::Front),
+            "B.Cu" => Some(PadLayerClass::Back),
+            _ if layer.starts_with("In") && layer.ends_with(".Cu") => Some(PadLayerClass::Inner),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PadLayerClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PadLayerClass::Front => "front",
+            PadLayerClass::Inner => "inner",
+            PadLayerClass::Back => "back",
+        })
+    }
+}
+
+/// Shape and size of a pad on one class of copper layers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PadLayerGeometry {
+    pub shape: PadShape,
+    pub size: (f32, f32),
+    pub roundrect_ratio: Option<f32>,
+}
+
+impl PadLayerGeometry {
+    pub fn new(shape: PadShape, size: (f32, f32)) -> Self {
+        Self { shape, size, roundrect_ratio: None }
+    }
+
+    pub fn with_roundrect_ratio(mut self, ratio: f32) -> Self {
+        self.roundrect_ratio = Some(ratio);
+        self
+    }
+}
+
+/// Back and inner copper overrides of a pad; see the module documentation
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct PadStack {
+    pub back: Option<PadLayerGeometry>,
+    pub inner: Option<PadLayerGeometry>,
+}
+
+impl PadStack {
+    /// Outer layers as the pad itself, `geometry` on inner layers
+    pub fn inner(geometry: PadLayerGeometry) -> Self {
+        Self { back: None, inner: Some(geometry) }
+    }
+
+    pub fn with_back(mut self, geometry: PadLayerGeometry) -> Self {
+        self.back = Some(geometry);
+        self
+    }
+
+    pub fn with_inner(mut self, geometry: PadLayerGeometry) -> Self {
+        self.inner = Some(geometry);
+        self
+    }
+
+    /// Whether any layer differs from the front
+    pub fn is_empty(&self) -> bool {
+        self.back.is_none() && self.inner.is_none()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PadStackError {
+    /// The copper ring around a plated hole is narrower than the profile allows
+    AnnularRing { pad: String, layers: PadLayerClass, ring: f32, minimum: f32 },
+}
+
+impl fmt::Display for PadStackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PadStackError::AnnularRing { pad, layers, ring, minimum } => write!(
+                f,
+                "pad {} has a {:.3} mm annular ring on {} layers, below the {:.3} mm minimum",
+                pad, ring, layers, minimum
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PadStackError {}
+
+impl PadDescriptor {
+    pub fn with_padstack(mut self, padstack: PadStack) -> Self {
+        self.padstack = (!padstack.is_empty()).then_some(padstack);
+        self
+    }
+
+    /// Whether the pad's copper differs between layers
+    pub fn has_padstack(&self) -> bool {
+        self.padstack.as_ref().is_some_and(|padstack| !padstack.is_empty())
+    }
+
+    /// Shape and size on `class` layers
+    pub fn geometry_on(&self, class: PadLayerClass) -> PadLayerGeometry {
+        let padstack = self.padstack.as_ref();
+        let layer = match class {
+            PadLayerClass::Front => None,
+            PadLayerClass::Inner => padstack.and_then(|padstack| padstack.inner.as_ref()),
+            PadLayerClass::Back => padstack.and_then(|padstack| padstack.back.as_ref()),
+        };
+        layer.cloned().unwrap_or_else(|| PadLayerGeometry {
+            shape: self.shape.clone(),
+            size: self.size,
+            roundrect_ratio: self.roundrect_ratio,
+        })
+    }
+
+    /// The pad as it is on `layer` ("F.Cu", "In1.Cu", ...), without a stack. Non-copper
+    /// layers get the front geometry.
+    pub fn on_layer(&self, layer: &str) -> PadDescriptor {
+        let geometry = self.geometry_on(PadLayerClass::of_layer(layer).unwrap_or(PadLayerClass::Front));
+        PadDescriptor {
+            shape: geometry.shape,
+            size: geometry.size,
+            roundrect_ratio: geometry.roundrect_ratio,
+            padstack: None,
+            ..self.clone()
+        }
+    }
+
+    /// Narrowest copper around the drill on `class` layers, `None` unless the pad is plated
+    /// through. Measured from the short side, so custom pads use their anchor; a slot is
+    /// measured along each of its sides.
+    pub fn annular_ring(&self, class: PadLayerClass) -> Option<f32> {
+        if !matches!(self.pad_type, PadType::ThroughHole) {
+            return None;
+        }
+        let geometry = self.geometry_on(class);
+        let (width, height) = match geometry.shape {
+            PadShape::Circle => (geometry.size.0, geometry.size.0),
+            _ => geometry.size,
+        };
+        match self.drill_slot {
+            Some(slot) => Some((width - slot.0).min(height - slot.1) / 2.0),
+            None => Some((width.min(height) - self.drill_size?) / 2.0),
+        }
+    }
+
+    /// Check the annular ring on every class of copper layer against `profile`
+    pub fn check_annular_rings(&self, profile: &FabProfile) -> Result<(), PadStackError> {
+        for layers in [PadLayerClass::Front, PadLayerClass::Inner, PadLayerClass::Back] {
+            if let Some(ring) = self.annular_ring(layers).filter(|ring| *ring < profile.min_annular_ring) {
+                return Err(PadStackError::AnnularRing {
+                    pad: self.number.to_string(),
+                    layers,
+                    ring,
+                    minimum: profile.min_annular_ring,
+                });
+            }
+        }
+        Ok(())
+    }
+}
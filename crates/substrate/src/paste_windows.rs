@@ -0,0 +1,361 @@
+//! Paste windows for exposed thermal pads
+//!
+//! Printing a large exposed pad through one full aperture floats the part on too much
+//! solder and traps voids, so the paste is split into a grid of windows separated by
+//! stencil webs that covers a set fraction of the pad, typically 60 to 70%.
+//! `layout_paste_windows` picks the grid: for each column and row count it solves for the
+//! web that meets the target coverage with the outer windows flush with the pad edge,
+//! rounds the web to the stencil resolution and drops grids whose webs or windows are
+//! below the `FabProfile` minimums. Of the rest it prefers windows no larger than
+//! `max_window`, then fewer windows, then squarer ones. Pads too small for any grid get a
+//! single centered aperture shrunk to the target coverage.
+//!
+//! The windows are paste-only pads. The thermal pad itself should leave its paste layer
+//! out, or the full pad is printed underneath them.
+
+use std::fmt;
+
+use crate::board_interface::{PadDescriptor, PadNumber, PadShape, PadType, TentingSettings, TentingType};
+use crate::fab_profile::FabProfile;
+use crate::geometry::Point;
+use crate::ids::fresh_uuid;
+use crate::layer_type::{PadLayers, PadSide};
+
+pub const DEFAULT_PASTE_COVERAGE: f32 = 0.65;
+pub const DEFAULT_PASTE_TOLERANCE: f32 = 0.05;
+/// Webs are rounded to this step (mm), about what stencil cutting resolves
+const PASTE_RESOLUTION: f32 = 0.01;
+/// Most windows tried along each side of the pad
+const MAX_GRID: usize = 12;
+/// Corner ratio of the window pads, as KiCad's own exposed pad footprints use
+const WINDOW_ROUNDRECT_RATIO: f32 = 0.25;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PasteWindowOptions {
+    /// Paste area over pad area, above 0 and at most 1
+    pub coverage: f32,
+    /// Accepted difference between the achieved and the target coverage
+    pub tolerance: f32,
+    /// Preferred largest window side (mm); larger windows are used only when nothing
+    /// smaller fits
+    pub max_window: f32,
+}
+
+impl Default for PasteWindowOptions {
+    fn default() -> Self {
+        Self { coverage: DEFAULT_PASTE_COVERAGE, tolerance: DEFAULT_PASTE_TOLERANCE, max_window: 1.5 }
+    }
+}
+
+impl PasteWindowOptions {
+    pub fn new(coverage: f32) -> Self {
+        Self { coverage, ..Self::default() }
+    }
+
+    pub fn with_tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn with_max_window(mut self, max_window: f32) -> Self {
+        self.max_window = max_window;
+        self
+    }
+}
+
+/// A grid of equal windows centered on the pad
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PasteGrid {
+    pub columns: usize,
+    pub rows: usize,
+    /// Size of each window
+    pub window: (f32, f32),
+    /// Web between neighbouring windows along X and along Y
+    pub web: (f32, f32),
+}
+
+impl PasteGrid {
+    /// `columns` x `rows` windows spanning a pad of `pad_size` edge to edge with `web`
+    /// between them
+    pub fn new(pad_size: (f32, f32), columns: usize, rows: usize, web: f32) -> Self {
+        let window = |size: f32, count: usize| (size - (count - 1) as f32 * web) / count as f32;
+        Self { columns, rows, window: (window(pad_size.0, columns), window(pad_size.1, rows)), web: (web, web) }
+    }
+
+    /// The grid spanning `pad_size` whose web gives `coverage`, with the web rounded to
+    /// the stencil resolution. `None` for a single window, which has no web to solve for,
+    /// or when no web reaches the coverage.
+    pub fn for_coverage(pad_size: (f32, f32), columns: usize, rows: usize, coverage: f32) -> Option<Self> {
+        if columns * rows <= 1 {
+            return None;
+        }
+        let (width, height) = pad_size;
+        let (a, b) = ((columns - 1) as f32, (rows - 1) as f32);
+        // (width - a * web) * (height - b * web) = coverage * width * height
+        let removed = (1.0 - coverage) * width * height;
+        let linear = a * height + b * width;
+        let web = if a == 0.0 || b == 0.0 {
+            removed / linear
+        } else {
+            let discriminant = linear * linear - 4.0 * a * b * removed;
+            if discriminant < 0.0 {
+                return None;
+            }
+            (linear - discriminant.sqrt()) / (2.0 * a * b)
+        };
+        let web = (web / PASTE_RESOLUTION).round() * PASTE_RESOLUTION;
+        let grid = Self::new(pad_size, columns, rows, web);
+        (grid.window.0 > 0.0 && grid.window.1 > 0.0).then_some(grid)
+    }
+
+    pub fn window_count(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    /// Paste area over the area of a pad of `pad_size`
+    pub fn coverage(&self, pad_size: (f32, f32)) -> f32 {
+        self.window_count() as f32 * self.window.0 * self.window.1 / (pad_size.0 * pad_size.1)
+    }
+
+    /// Window centers relative to the pad center, row by row from the top left
+    pub fn centers(&self) -> Vec<(f32, f32)> {
+        let pitch = (self.window.0 + self.web.0, self.window.1 + self.web.1);
+        let start = ((self.columns - 1) as f32 * pitch.0 / -2.0, (self.rows - 1) as f32 * pitch.1 / -2.0);
+        (0..self.rows)
+            .flat_map(|row| (0..self.columns).map(move |column| (row, column)))
+            .map(|(row, column)| (start.0 + column as f32 * pitch.0, start.1 + row as f32 * pitch.1))
+            .collect()
+    }
+}
+
+/// What `layout_paste_windows` achieved
+#[derive(Debug, Clone, PartialEq)]
+pub struct PasteCoverageReport {
+    pub grid: PasteGrid,
+    pub coverage: f32,
+    pub target: f32,
+    pub within_tolerance: bool,
+    /// No grid met the fab minimums, a single reduced aperture is used instead
+    pub single_aperture: bool,
+}
+
+impl fmt::Display for PasteCoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let grid = &self.grid;
+        if self.single_aperture {
+            write!(f, "single {:.2} x {:.2} mm aperture", grid.window.0, grid.window.1)?;
+        } else {
+            write!(
+                f,
+                "{}x{} windows of {:.2} x {:.2} mm, {:.2} mm web",
+                grid.columns, grid.rows, grid.window.0, grid.window.1, grid.web.0
+            )?;
+        }
+        write!(f, ", {:.1}% coverage (target {:.1}%)", self.coverage * 100.0, self.target * 100.0)?;
+        if !self.within_tolerance {
+            write!(f, ", outside tolerance")?;
+        }
+        Ok(())
+    }
+}
+
+/// Paste window pads and how well they meet the target
+#[derive(Debug, Clone)]
+pub struct PasteLayout {
+    pub pads: Vec<PadDescriptor>,
+    pub report: PasteCoverageReport,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PasteWindowError {
+    /// The target coverage is not above 0 and at most 1
+    InvalidCoverage(f32),
+    /// The pad is smaller than the smallest aperture the profile allows
+    PadTooSmall { size: (f32, f32), min_aperture: f32 },
+}
+
+impl fmt::Display for PasteWindowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PasteWindowError::InvalidCoverage(coverage) => {
+                write!(f, "paste coverage must be above 0 and at most 1, got {}", coverage)
+            }
+            PasteWindowError::PadTooSmall { size, min_aperture } => write!(
+                f,
+                "{} x {} mm pad is smaller than the {} mm minimum paste aperture",
+                size.0, size.1, min_aperture
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PasteWindowError {}
+
+/// Lay out paste windows over `pad`, see the module documentation
+pub fn layout_paste_windows(
+    pad: &PadDescriptor,
+    options: &PasteWindowOptions,
+    profile: &FabProfile,
+) -> Result<PasteLayout, PasteWindowError> {
+    let target = options.coverage;
+    if !(target > 0.0 && target <= 1.0) {
+        return Err(PasteWindowError::InvalidCoverage(target));
+    }
+    let size = pad.size;
+    if size.0 < profile.min_paste_aperture || size.1 < profile.min_paste_aperture {
+        return Err(PasteWindowError::PadTooSmall { size, min_aperture: profile.min_paste_aperture });
+    }
+
+    let fits = |grid: &PasteGrid| {
+        grid.web.0 >= profile.min_paste_web - f32::EPSILON
+            && grid.window.0.min(grid.window.1) >= profile.min_paste_aperture
+            && (grid.coverage(size) - target).abs() <= options.tolerance
+    };
+    let preference = |grid: &PasteGrid| {
+        let (small, large) = (grid.window.0.min(grid.window.1), grid.window.0.max(grid.window.1));
+        ((large - options.max_window).max(0.0), grid.window_count(), large / small)
+    };
+    let best = (1..=MAX_GRID)
+        .flat_map(|columns| (1..=MAX_GRID).map(move |rows| (columns, rows)))
+        .filter_map(|(columns, rows)| PasteGrid::for_coverage(size, columns, rows, target))
+        .filter(fits)
+        .min_by(|a, b| preference(a).partial_cmp(&preference(b)).unwrap_or(std::cmp::Ordering::Equal));
+
+    let (grid, single_aperture) = match best {
+        Some(grid) => (grid, false),
+        None => {
+            let scale = target.sqrt();
+            let side = |pad_side: f32| (pad_side * scale).max(profile.min_paste_aperture).min(pad_side);
+            (PasteGrid { columns: 1, rows: 1, window: (side(size.0), side(size.1)), web: (0.0, 0.0) }, true)
+        }
+    };
+    let coverage = grid.coverage(size);
+    let report = PasteCoverageReport {
+        grid,
+        coverage,
+        target,
+        within_tolerance: (coverage - target).abs() <= options.tolerance,
+        single_aperture,
+    };
+
+    let rotation = pad.rotation.unwrap_or(0.0);
+    let layers = if pad.side() == Some(PadSide::Back) { PadLayers::paste_back() } else { PadLayers::paste_front() };
+    let pads = grid
+        .centers()
+        .into_iter()
+        .map(|center| {
+            let position = Point::from(center).rotated(rotation).offset(pad.position.0, pad.position.1);
+            PadDescriptor {
+                // Paste windows are unnumbered, as in KiCad's exposed pad footprints
+                number: PadNumber::default(),
+                pin_function: None,
+                pad_type: PadType::SMD,
+                shape: PadShape::RoundRect,
+                position: (position.x, position.y),
+                rotation: pad.rotation,
+                size: grid.window,
+                drill_size: None,
+                drill_slot: None,
+                layers: layers.clone(),
+                roundrect_ratio: Some(WINDOW_ROUNDRECT_RATIO),
+                tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+                padstack: None,
+                fab_property: None,
+                solder_mask_margin: None,
+                routing_hint: None,
+                uuid: fresh_uuid(),
+            }
+        })
+        .collect();
+    Ok(PasteLayout { pads, report })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(value: f32, expected: f32) {
+        assert!((value - expected).abs() < 1e-4, "{} != {}", value, expected);
+    }
+
+    fn exposed_pad(side: f32) -> PadDescriptor {
+        PadDescriptor {
+            number: "17".into(),
+            pin_function: None,
+            pad_type: PadType::SMD,
+            shape: PadShape::Rect,
+            position: (0.0, 0.0),
+            rotation: None,
+            size: (side, side),
+            drill_size: None,
+            drill_slot: None,
+            layers: PadLayers::smd_front(),
+            roundrect_ratio: None,
+            tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+            padstack: None,
+            fab_property: None,
+            solder_mask_margin: None,
+            routing_hint: None,
+            uuid: fresh_uuid(),
+        }
+    }
+
+    #[test]
+    fn three_by_three_grid_for_coverage() {
+        // (3 - 2 web)² = 0.64 * 3 * 3 gives a 0.3 mm web and 0.8 mm windows
+        let grid = PasteGrid::for_coverage((3.0, 3.0), 3, 3, 0.64).unwrap();
+        assert_eq!((grid.columns, grid.rows), (3, 3));
+        assert_close(grid.web.0, 0.3);
+        assert_close(grid.window.0, 0.8);
+        assert_close(grid.window.1, 0.8);
+        assert_close(grid.coverage((3.0, 3.0)), 0.64);
+        let centers = grid.centers();
+        assert_eq!(centers.len(), 9);
+        assert_close(centers[0].0, -1.1);
+        assert_close(centers[0].1, -1.1);
+        assert_close(centers[4].0, 0.0);
+        assert_close(centers[8].1, 1.1);
+    }
+
+    #[test]
+    fn four_by_four_grid_for_coverage() {
+        // A 0.3 mm web leaves 16 windows of (4.3 - 3 * 0.3) / 4 = 0.85 mm,
+        // 16 * 0.85² / 4.3² = 62.52% of the pad
+        let grid = PasteGrid::for_coverage((4.3, 4.3), 4, 4, 0.6252).unwrap();
+        assert_close(grid.web.0, 0.3);
+        assert_close(grid.window.0, 0.85);
+        assert_close(grid.coverage((4.3, 4.3)), 16.0 * 0.85 * 0.85 / (4.3 * 4.3));
+    }
+
+    #[test]
+    fn web_is_rounded_to_the_stencil_resolution() {
+        // The exact web for 56.25% on a 4 mm pad is 1/3 mm
+        let grid = PasteGrid::for_coverage((4.0, 4.0), 4, 4, 0.5625).unwrap();
+        assert_close(grid.web.0, 0.33);
+        assert_close(grid.window.0, (4.0 - 3.0 * 0.33) / 4.0);
+    }
+
+    #[test]
+    fn single_window_has_no_web_to_solve_for() {
+        assert_eq!(PasteGrid::for_coverage((3.0, 3.0), 1, 1, 0.64), None);
+    }
+
+    #[test]
+    fn small_pad_falls_back_to_one_reduced_aperture() {
+        // No two 0.3 mm windows and a 0.2 mm web fit on 0.5 mm
+        let layout = layout_paste_windows(&exposed_pad(0.5), &PasteWindowOptions::default(), &FabProfile::standard()).unwrap();
+        let report = &layout.report;
+        assert!(report.single_aperture && report.within_tolerance);
+        assert_eq!(layout.pads.len(), 1);
+        assert_close(report.grid.window.0, 0.5 * DEFAULT_PASTE_COVERAGE.sqrt());
+        assert_close(report.coverage, DEFAULT_PASTE_COVERAGE);
+    }
+
+    #[test]
+    fn fallback_aperture_is_not_shrunk_below_the_minimum() {
+        // 0.35 * sqrt(0.65) = 0.28 mm is below the 0.3 mm minimum aperture
+        let layout = layout_paste_windows(&exposed_pad(0.35), &PasteWindowOptions::default(), &FabProfile::standard()).unwrap();
+        assert_close(layout.report.grid.window.0, 0.3);
+        assert!(layout.report.single_aperture && !layout.report.within_tolerance);
+    }
+}
@@ -0,0 +1,273 @@
+//! Pin 1 indicators
+//!
+//! Organizations mark pin 1 differently, so generators take a `Pin1Style` and draw it with
+//! `pin1_marker` from the body outline and the pads:
+//!
+//! - `Dot`: a filled silkscreen dot just past the outer end of pin 1
+//! - `Chamfer`: a diagonal across the pin 1 corner of the fab outline
+//! - `Line`: a silkscreen line along the pin 1 side of the body, run out to the outer end
+//!   of pin 1 and stopped short of any pad in its way
+//! - `Triangle`: a silkscreen triangle past the outer end of pin 1, pointing at it
+//! - `None`: no marker
+//!
+//! Markers follow pin 1 rather than a fixed corner. The direction pin 1 faces out of the
+//! body decides which way they extend, and the body corner closest to pin 1 is the pin 1
+//! corner, so a QFP numbered from the left end of its top row is marked as well as one
+//! numbered from the top of its left column. Silkscreen keeps `SILK_PAD_CLEARANCE` from
+//! the solder mask opening of every pad, moving further out where a neighbouring pad is
+//! in the way; `pin1_marker_with_mask_margin` takes the mask margin, which grows or
+//! shrinks the openings. Every marker element has the `ElementRole::Pin1Marker` role.
+
+use std::fmt;
+
+use serde::Deserialize;
+
+use crate::board_interface::{GraphicElement, GraphicType, PadDescriptor, Rectangle, Stroke, StrokeType};
+use crate::ids::fresh_uuid;
+use crate::layer_type::{LayerType, Side};
+use crate::roles::ElementRole;
+
+const FAB_LINE_WIDTH: f32 = 0.1;
+const SILK_LINE_WIDTH: f32 = 0.12;
+/// Gap between silkscreen and pad mask openings
+const SILK_PAD_CLEARANCE: f32 = 0.2;
+const DOT_DIAMETER: f32 = 0.3;
+/// Side length of the triangle marker
+const TRIANGLE_SIZE: f32 = 0.5;
+/// KLC chamfer: 1 mm, or a quarter of the shorter body side on small parts
+const MAX_CHAMFER: f32 = 1.0;
+/// How far a marker moves per try when it would crowd a pad
+const CLEARANCE_STEP: f32 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pin1Style {
+    Dot,
+    Chamfer,
+    Line,
+    Triangle,
+    None,
+}
+
+/// Package families with their own pin 1 conventions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PackageFamily {
+    /// Polarized two terminal chips such as LEDs and diodes
+    Chip,
+    Soic,
+    Sot,
+    Qfp,
+    Qfn,
+    Bga,
+    Connector,
+}
+
+impl fmt::Display for PackageFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PackageFamily::Chip => "chip",
+            PackageFamily::Soic => "SOIC",
+            PackageFamily::Sot => "SOT",
+            PackageFamily::Qfp => "QFP",
+            PackageFamily::Qfn => "QFN",
+            PackageFamily::Bga => "BGA",
+            PackageFamily::Connector => "connector",
+        })
+    }
+}
+
+impl Pin1Style {
+    /// The marker the KiCad libraries use for `family`
+    pub fn kicad_default(family: PackageFamily) -> Self {
+        match family {
+            PackageFamily::Soic | PackageFamily::Sot | PackageFamily::Qfp | PackageFamily::Qfn => Pin1Style::Triangle,
+            PackageFamily::Bga => Pin1Style::Chamfer,
+            PackageFamily::Chip | PackageFamily::Connector => Pin1Style::Line,
+        }
+    }
+}
+
+/// Coordinates turned so `u` points the way pin 1 faces out of the body and `v` runs across
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    direction: (f32, f32),
+}
+
+impl Frame {
+    fn uv(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (dx, dy) = self.direction;
+        (x * dx + y * dy, y * dx - x * dy)
+    }
+
+    fn xy(&self, (u, v): (f32, f32)) -> (f32, f32) {
+        let (dx, dy) = self.direction;
+        (u * dx - v * dy, u * dy + v * dx)
+    }
+
+    /// `rect` in frame coordinates, `x` standing for `u` and `y` for `v`
+    fn rect(&self, rect: &Rectangle) -> Rectangle {
+        let (a, b) = (self.uv((rect.min_x, rect.min_y)), self.uv((rect.max_x, rect.max_y)));
+        Rectangle { min_x: a.0.min(b.0), min_y: a.1.min(b.1), max_x: a.0.max(b.0), max_y: a.1.max(b.1) }
+    }
+}
+
+fn grown(rect: &Rectangle, by: f32) -> Rectangle {
+    Rectangle { min_x: rect.min_x - by, min_y: rect.min_y - by, max_x: rect.max_x + by, max_y: rect.max_y + by }
+}
+
+fn overlaps(a: &Rectangle, b: &Rectangle) -> bool {
+    a.min_x < b.max_x && b.min_x < a.max_x && a.min_y < b.max_y && b.min_y < a.max_y
+}
+
+fn sign(value: f32) -> f32 {
+    if value <= 0.0 { -1.0 } else { 1.0 }
+}
+
+fn element(element_type: GraphicType, layer: LayerType, width: f32) -> GraphicElement {
+    GraphicElement {
+        element_type,
+        layer,
+        side: Side::Front,
+        stroke: Stroke { width, stroke_type: StrokeType::Solid },
+        uuid: fresh_uuid(),
+        role: Some(ElementRole::Pin1Marker),
+    }
+}
+
+/// Move a shape at `u` outwards until its bounds clear every obstacle
+fn clear_outwards(mut u: f32, bounds: impl Fn(f32) -> Rectangle, obstacles: &[Rectangle]) -> f32 {
+    for _ in 0..200 {
+        if !obstacles.iter().any(|obstacle| overlaps(&bounds(u), obstacle)) {
+            break;
+        }
+        u += CLEARANCE_STEP;
+    }
+    u
+}
+
+/// Graphics marking pad `pin1` in `style`, see the module documentation. `body` is the
+/// fab outline; nothing is drawn when no pad has that number.
+pub fn pin1_marker(style: Pin1Style, body: &Rectangle, pads: &[PadDescriptor], pin1: &str) -> Vec<GraphicElement> {
+    pin1_marker_with_mask_margin(style, body, pads, pin1, 0.0)
+}
+
+/// `pin1_marker` with silkscreen kept clear of the pads' mask openings grown by
+/// `mask_margin`, or by a pad's own margin where it has one
+pub fn pin1_marker_with_mask_margin(
+    style: Pin1Style,
+    body: &Rectangle,
+    pads: &[PadDescriptor],
+    pin1: &str,
+    mask_margin: f32,
+) -> Vec<GraphicElement> {
+    let Some(pad) = pads.iter().find(|pad| pad.number == pin1) else {
+        return Vec::new();
+    };
+    let center = ((body.min_x + body.max_x) / 2.0, (body.min_y + body.max_y) / 2.0);
+    let (dx, dy) = (pad.position.0 - center.0, pad.position.1 - center.1);
+    let corner = (if dx <= 0.0 { body.min_x } else { body.max_x }, if dy <= 0.0 { body.min_y } else { body.max_y });
+
+    if style == Pin1Style::Chamfer {
+        let size = MAX_CHAMFER.min(body.width().min(body.height()) / 4.0);
+        let (sx, sy) = (sign(dx), sign(dy));
+        let line = GraphicType::Line { start: (corner.0 - sx * size, corner.1), end: (corner.0, corner.1 - sy * size) };
+        return vec![element(line, LayerType::Fabrication, FAB_LINE_WIDTH)];
+    }
+
+    // Pin 1 faces out of whichever body side it is relatively furthest beyond
+    let reach = |offset: f32, half: f32| offset.abs() / half.max(f32::EPSILON);
+    let direction =
+        if reach(dx, body.width() / 2.0) >= reach(dy, body.height() / 2.0) { (sign(dx), 0.0) } else { (0.0, sign(dy)) };
+    let frame = Frame { direction };
+    // Tented pads keep the silkscreen off their copper all the same
+    let opening = |pad: &PadDescriptor| pad.mask_aperture_aabb(mask_margin).unwrap_or_else(|| pad.outline_aabb());
+    let pin = frame.rect(&opening(pad));
+    let others: Vec<Rectangle> = pads
+        .iter()
+        .filter(|other| other.number != pin1)
+        .map(|other| grown(&frame.rect(&opening(other)), SILK_PAD_CLEARANCE))
+        .collect();
+    let v = (pin.min_y + pin.max_y) / 2.0;
+    let silk = |element_type| element(element_type, LayerType::SilkScreen, SILK_LINE_WIDTH);
+
+    match style {
+        Pin1Style::Dot => {
+            let radius = DOT_DIAMETER / 2.0;
+            let bounds = |u: f32| Rectangle { min_x: u - radius, min_y: v - radius, max_x: u + radius, max_y: v + radius };
+            let u = clear_outwards(pin.max_x + SILK_PAD_CLEARANCE + radius, bounds, &others);
+            // A circle stroked as wide as its diameter fills in
+            let dot = GraphicType::Circle { center: frame.xy((u, v)), radius: radius / 2.0 };
+            vec![element(dot, LayerType::SilkScreen, radius)]
+        }
+        Pin1Style::Triangle => {
+            let (height, half_base) = (TRIANGLE_SIZE * 3f32.sqrt() / 2.0, TRIANGLE_SIZE / 2.0);
+            let half_width = SILK_LINE_WIDTH / 2.0;
+            let bounds = |u: f32| Rectangle {
+                min_x: u - half_width,
+                min_y: v - half_base - half_width,
+                max_x: u + height + half_width,
+                max_y: v + half_base + half_width,
+            };
+            let tip = clear_outwards(pin.max_x + SILK_PAD_CLEARANCE + half_width, bounds, &others);
+            let corners = [(tip, v), (tip + height, v - half_base), (tip + height, v + half_base)].map(|point| frame.xy(point));
+            (0..3).map(|i| silk(GraphicType::Line { start: corners[i], end: corners[(i + 1) % 3] })).collect()
+        }
+        Pin1Style::Line => {
+            // Along the body side at the pin 1 corner, clear of pin 1 itself
+            let body_uv = frame.rect(body);
+            let side = sign(frame.uv(corner).1 - (body_uv.min_y + body_uv.max_y) / 2.0);
+            let edge = |rect: &Rectangle| if side < 0.0 { rect.min_y } else { rect.max_y };
+            let line_v = side * (side * edge(&body_uv)).max(side * edge(&pin) + SILK_PAD_CLEARANCE) + side * SILK_LINE_WIDTH / 2.0;
+            let start = pin.max_x.max(body_uv.max_x);
+            let mut end = body_uv.min_x;
+            for other in &others {
+                let other = grown(other, SILK_LINE_WIDTH / 2.0);
+                if other.min_y < line_v && line_v < other.max_y && other.min_x < start {
+                    end = end.max(other.max_x);
+                }
+            }
+            if end >= start {
+                return Vec::new();
+            }
+            vec![silk(GraphicType::Line { start: frame.xy((start, line_v)), end: frame.xy((end, line_v)) })]
+        }
+        Pin1Style::Chamfer | Pin1Style::None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_interface::{PadShape, PadType};
+    use crate::layer_type::PadLayers;
+
+    fn pads() -> Vec<PadDescriptor> {
+        [("1", -2.0), ("2", 2.0)]
+            .map(|(number, x)| PadDescriptor::new(number, PadType::SMD, PadShape::Rect, (x, 0.0), (1.0, 0.5), PadLayers::smd_front()))
+            .to_vec()
+    }
+
+    fn dot_x(markers: Vec<GraphicElement>) -> f32 {
+        match markers[0].element_type {
+            GraphicType::Circle { center, .. } => center.0,
+            ref other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn silkscreen_clears_the_mask_opening() {
+        let body = Rectangle { min_x: -1.5, min_y: -1.0, max_x: 1.5, max_y: 1.0 };
+        let marker = |pads: &[PadDescriptor], margin: f32| dot_x(pin1_marker_with_mask_margin(Pin1Style::Dot, &body, pads, "1", margin));
+        // Pin 1's copper ends at x = -2.5; the dot keeps the clearance and its radius past the opening
+        let at_copper = -2.5 - SILK_PAD_CLEARANCE - DOT_DIAMETER / 2.0;
+        for margin in [0.1, 0.0, -0.05] {
+            assert!((marker(&pads(), margin) - (at_copper - margin)).abs() < 1e-5, "margin {}", margin);
+        }
+        assert_eq!(dot_x(pin1_marker(Pin1Style::Dot, &body, &pads(), "1")), marker(&pads(), 0.0));
+
+        // The pad's own margin wins over the global one
+        let mut own = pads();
+        own[0] = own[0].clone().with_solder_mask_margin(0.0);
+        assert!((marker(&own, 0.1) - at_copper).abs() < 1e-5);
+    }
+}
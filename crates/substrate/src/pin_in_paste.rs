@@ -0,0 +1,418 @@
+//! Pin-in-paste apertures for reflowing through hole parts
+//!
+//! Pin-in-paste (paste-in-hole) assembly prints paste over through hole pads, which
+//! normally have none; the pins push it into the holes and reflow turns it into the
+//! joints. The paste has to fill the hole around the pin and make a fillet on each side,
+//! and only about half of it is solder, so the apertures are sized from volumes:
+//!
+//! - hole: π (drill / 2)² × board thickness
+//! - pin: pin cross-section × board thickness
+//! - fillets: on each side a 45° concave fillet across the annular ring, as high as the
+//!   ring r is wide. Its cross-section (1 − π/4) r² swept around the hole, with the
+//!   centroid 0.2234 r out from the hole wall, gives the volume (Pappus).
+//! - paste: (hole − pin + fillets) / reduction factor
+//! - aperture area: paste / stencil thickness
+//!
+//! When the pad is large enough, the pad itself is the aperture. Otherwise `Overflow`
+//! grows a rectangular aperture past the pad onto the solder mask; the paste pulls back
+//! onto the pad as it melts. Pad rows run along Y, as in the DIP and header footprints,
+//! so the aperture stops `min_web` short of the next pad's along the row and
+//! `max_extension` past the pad across it. Joints the aperture can't supply are
+//! reported short.
+
+use std::f32::consts::PI;
+use std::fmt;
+
+use crate::board_interface::{PadDescriptor, PadNumber, PadShape, PadType, TentingSettings, TentingType};
+use crate::geometry::polygon_area;
+use crate::ids::fresh_uuid;
+use crate::layer_type::PadLayers;
+
+/// Standard FR-4 board thickness
+pub const DEFAULT_BOARD_THICKNESS: f32 = 1.6;
+/// Solder over paste volume of common type 3 and 4 pastes
+pub const DEFAULT_PASTE_REDUCTION: f32 = 0.5;
+pub const DEFAULT_STENCIL_THICKNESS: f32 = 0.12;
+/// Aperture sides are rounded up to this step (mm), about what stencil cutting resolves
+const APERTURE_RESOLUTION: f32 = 0.01;
+/// Distance of a concave 45° fillet's centroid from the wall, over the fillet size
+const FILLET_CENTROID: f32 = (10.0 - 3.0 * PI) / (12.0 - 3.0 * PI);
+const ARC_TOLERANCE: f32 = 0.005;
+
+/// Cross-section of the pin through the hole
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PinSection {
+    Round { diameter: f32 },
+    /// Square and rectangular pins, headers and stamped leads
+    Rect { width: f32, thickness: f32 },
+}
+
+impl PinSection {
+    pub fn area(&self) -> f32 {
+        match *self {
+            PinSection::Round { diameter } => PI * diameter * diameter / 4.0,
+            PinSection::Rect { width, thickness } => width * thickness,
+        }
+    }
+
+    /// Smallest round hole the pin passes through
+    pub fn diagonal(&self) -> f32 {
+        match *self {
+            PinSection::Round { diameter } => diameter,
+            PinSection::Rect { width, thickness } => width.hypot(thickness),
+        }
+    }
+}
+
+/// Growing apertures past their pad, see the module documentation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Overflow {
+    /// Furthest an aperture reaches past its pad across the row (mm)
+    pub max_extension: f32,
+    /// Narrowest stencil web left between apertures along the row (mm)
+    pub min_web: f32,
+}
+
+impl Default for Overflow {
+    fn default() -> Self {
+        Self { max_extension: 1.5, min_web: 0.2 }
+    }
+}
+
+/// Pin-in-paste settings of a through hole footprint
+#[derive(Debug, Clone, PartialEq)]
+pub struct PinInPaste {
+    pub board_thickness: f32,
+    pub pin: PinSection,
+    /// Solder volume over printed paste volume, above 0 and at most 1
+    pub paste_reduction: f32,
+    pub stencil_thickness: f32,
+    /// Apertures past the pad, or `None` to print the pad only
+    pub overflow: Option<Overflow>,
+}
+
+impl PinInPaste {
+    pub fn new(pin: PinSection) -> Self {
+        Self {
+            board_thickness: DEFAULT_BOARD_THICKNESS,
+            pin,
+            paste_reduction: DEFAULT_PASTE_REDUCTION,
+            stencil_thickness: DEFAULT_STENCIL_THICKNESS,
+            overflow: None,
+        }
+    }
+
+    pub fn with_board_thickness(mut self, board_thickness: f32) -> Self {
+        self.board_thickness = board_thickness;
+        self
+    }
+
+    pub fn with_paste_reduction(mut self, paste_reduction: f32) -> Self {
+        self.paste_reduction = paste_reduction;
+        self
+    }
+
+    pub fn with_stencil_thickness(mut self, stencil_thickness: f32) -> Self {
+        self.stencil_thickness = stencil_thickness;
+        self
+    }
+
+    pub fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = Some(overflow);
+        self
+    }
+
+    /// Check the settings against holes of `drill`
+    pub fn validate(&self, drill: f32) -> Result<(), PinInPasteError> {
+        for (name, value) in [("board thickness", self.board_thickness), ("stencil thickness", self.stencil_thickness)] {
+            if value.is_nan() || value <= 0.0 {
+                return Err(PinInPasteError::NotPositive { name, value });
+            }
+        }
+        if self.paste_reduction.is_nan() || self.paste_reduction <= 0.0 || self.paste_reduction > 1.0 {
+            return Err(PinInPasteError::InvalidReduction(self.paste_reduction));
+        }
+        if self.pin.diagonal() >= drill {
+            return Err(PinInPasteError::PinDoesNotFit { pin: self.pin.diagonal(), drill });
+        }
+        if let Some(overflow) = &self.overflow
+            && (overflow.max_extension < 0.0 || overflow.min_web < 0.0)
+        {
+            return Err(PinInPasteError::NotPositive { name: "overflow", value: overflow.max_extension.min(overflow.min_web) });
+        }
+        Ok(())
+    }
+
+    /// Volumes of a joint in a `drill` hole with a round pad of `pad` diameter
+    pub fn joint_volume(&self, drill: f32, pad: f32) -> JointVolume {
+        let ring = ((pad - drill) / 2.0).max(0.0);
+        let fillet_section = (1.0 - PI / 4.0) * ring * ring;
+        let fillet = 2.0 * PI * (drill / 2.0 + FILLET_CENTROID * ring) * fillet_section;
+        let hole = PI * drill * drill / 4.0 * self.board_thickness;
+        let pin = self.pin.area() * self.board_thickness;
+        let solder = hole - pin + 2.0 * fillet;
+        JointVolume { hole, pin, fillets: 2.0 * fillet, solder, paste: solder / self.paste_reduction }
+    }
+
+    /// Aperture of the through hole `pad`, with the next pad along the row `pitch` away
+    pub fn aperture(&self, pad: &PadDescriptor, pitch: f32) -> PasteAperture {
+        let drill = pad.drill_size.unwrap_or(0.0);
+        let volume = self.joint_volume(drill, pad.size.0.min(pad.size.1));
+        let needed = volume.paste / self.stencil_thickness;
+        let pad_area = polygon_area(&pad.outline_polygon(ARC_TOLERANCE));
+        let (size, area, overflow) = match &self.overflow {
+            Some(overflow) if needed > pad_area => {
+                let (width, height) = pad.size;
+                let height = round_up(needed.sqrt().min(pitch - overflow.min_web).max(height));
+                let width = round_up((needed / height).min(width + 2.0 * overflow.max_extension).max(width));
+                ((width, height), width * height, true)
+            }
+            _ => (pad.size, pad_area, false),
+        };
+        PasteAperture {
+            number: pad.number.clone(),
+            volume,
+            size,
+            printed_solder: area * self.stencil_thickness * self.paste_reduction,
+            overflow,
+        }
+    }
+
+    /// `pads` with their paste: `F.Paste` on each through hole pad the pad itself can
+    /// supply, a paste-only aperture pad over each one that overflows
+    pub fn apply(&self, pads: Vec<PadDescriptor>, pitch: f32) -> Vec<PadDescriptor> {
+        let mut apertures = Vec::new();
+        let mut pads: Vec<PadDescriptor> = pads
+            .into_iter()
+            .map(|mut pad| {
+                if !matches!(pad.pad_type, PadType::ThroughHole) {
+                    return pad;
+                }
+                let aperture = self.aperture(&pad, pitch);
+                if aperture.overflow {
+                    apertures.push(aperture_pad(&pad, aperture.size));
+                } else {
+                    pad.layers.push("F.Paste".into());
+                }
+                pad
+            })
+            .collect();
+        pads.extend(apertures);
+        pads
+    }
+
+    /// The aperture of every through hole pad of `pads`
+    pub fn report(&self, pads: &[PadDescriptor], pitch: f32) -> Vec<PasteAperture> {
+        pads.iter().filter(|pad| matches!(pad.pad_type, PadType::ThroughHole)).map(|pad| self.aperture(pad, pitch)).collect()
+    }
+}
+
+fn round_up(value: f32) -> f32 {
+    // Snapped to 0.1 µm first so that float noise doesn't push a whole step up
+    let value = (value * 1e4).round() / 1e4;
+    ((value / APERTURE_RESOLUTION).ceil() * APERTURE_RESOLUTION * 1e4).round() / 1e4
+}
+
+/// Paste-only pad of `size` centered on `pad`, unnumbered as paste windows are
+fn aperture_pad(pad: &PadDescriptor, size: (f32, f32)) -> PadDescriptor {
+    PadDescriptor {
+        number: PadNumber::default(),
+        pin_function: None,
+        pad_type: PadType::SMD,
+        shape: PadShape::Rect,
+        position: pad.position,
+        rotation: pad.rotation,
+        size,
+        drill_size: None,
+        drill_slot: None,
+        layers: PadLayers::paste_front(),
+        roundrect_ratio: None,
+        tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+        padstack: None,
+        fab_property: None,
+        solder_mask_margin: None,
+        routing_hint: None,
+        uuid: fresh_uuid(),
+    }
+}
+
+/// Volumes of one joint, in mm³
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JointVolume {
+    pub hole: f32,
+    pub pin: f32,
+    /// Both fillets together
+    pub fillets: f32,
+    /// Solder the joint needs: hole less pin, plus fillets
+    pub solder: f32,
+    /// Paste to print for it
+    pub paste: f32,
+}
+
+/// The paste of one pad
+#[derive(Debug, Clone, PartialEq)]
+pub struct PasteAperture {
+    pub number: PadNumber,
+    pub volume: JointVolume,
+    /// Aperture width and height (mm), the pad's own unless it overflows
+    pub size: (f32, f32),
+    /// Solder the aperture leaves after reflow (mm³)
+    pub printed_solder: f32,
+    /// Whether the aperture reaches past the pad
+    pub overflow: bool,
+}
+
+impl PasteAperture {
+    /// Whether the aperture prints less solder than the joint needs
+    pub fn is_short(&self) -> bool {
+        self.printed_solder < self.volume.solder * (1.0 - 1e-4)
+    }
+}
+
+impl fmt::Display for PasteAperture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "pad {}: {:.3} mm³ solder needed, {:.3} mm³ printed through {} x {} mm{}",
+            self.number,
+            self.volume.solder,
+            self.printed_solder,
+            self.size.0,
+            self.size.1,
+            if self.is_short() { " (short)" } else { "" }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PinInPasteError {
+    /// A thickness or overflow distance that must be above (or, for overflow, at least) 0
+    NotPositive { name: &'static str, value: f32 },
+    /// The paste reduction factor is not above 0 and at most 1
+    InvalidReduction(f32),
+    /// The pin's diagonal doesn't clear the drill
+    PinDoesNotFit { pin: f32, drill: f32 },
+}
+
+impl fmt::Display for PinInPasteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PinInPasteError::NotPositive { name, value } => write!(f, "{} must be positive, got {}", name, value),
+            PinInPasteError::InvalidReduction(reduction) => {
+                write!(f, "paste reduction factor must be above 0 and at most 1, got {}", reduction)
+            }
+            PinInPasteError::PinDoesNotFit { pin, drill } => {
+                write!(f, "{} mm pin does not fit a {} mm drill", pin, drill)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PinInPasteError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pad(size: (f32, f32), drill: f32) -> PadDescriptor {
+        PadDescriptor {
+            number: "1".into(),
+            pin_function: None,
+            pad_type: PadType::ThroughHole,
+            shape: PadShape::Rect,
+            position: (0.0, 0.0),
+            rotation: None,
+            size,
+            drill_size: Some(drill),
+            drill_slot: None,
+            layers: PadLayers::tht(),
+            roundrect_ratio: None,
+            tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+            padstack: None,
+            fab_property: None,
+            solder_mask_margin: None,
+            routing_hint: None,
+            uuid: fresh_uuid(),
+        }
+    }
+
+    fn close(actual: f32, expected: f32) -> bool {
+        (actual - expected).abs() < 1e-4
+    }
+
+    /// A 0.64 mm square header pin in a 1.0 mm hole with a 1.7 mm pad, on a 1.6 mm board
+    /// through a 0.127 mm (5 mil) stencil, worked by hand:
+    ///
+    /// hole 0.7854 × 1.6 = 1.25664, pin 0.4096 × 1.6 = 0.65536, ring 0.35, fillet section
+    /// 0.21460 × 0.1225 = 0.026289 at 0.5 + 0.22337 × 0.35 = 0.57818 from the axis, so
+    /// 2π × 0.57818 × 0.026289 = 0.095502 per side. Solder 1.25664 − 0.65536 + 0.19100 =
+    /// 0.79228, paste 1.58456 and an aperture area of 12.4769 mm².
+    fn header() -> PinInPaste {
+        PinInPaste::new(PinSection::Rect { width: 0.64, thickness: 0.64 }).with_stencil_thickness(0.127)
+    }
+
+    #[test]
+    fn worked_example_volumes() {
+        let volume = header().joint_volume(1.0, 1.7);
+        assert!(close(volume.hole, 1.25664), "{:?}", volume);
+        assert!(close(volume.pin, 0.65536), "{:?}", volume);
+        assert!(close(volume.fillets, 0.19100), "{:?}", volume);
+        assert!(close(volume.solder, 0.79228), "{:?}", volume);
+        assert!(close(volume.paste, 1.58456), "{:?}", volume);
+        assert!((volume.paste / 0.127 - 12.4769).abs() < 1e-3);
+    }
+
+    #[test]
+    fn pad_only_apertures_come_up_short() {
+        let aperture = header().aperture(&pad((1.7, 1.7), 1.0), 2.54);
+        assert!(!aperture.overflow);
+        assert_eq!(aperture.size, (1.7, 1.7));
+        // 2.89 mm² × 0.127 mm × 0.5
+        assert!(close(aperture.printed_solder, 0.183515));
+        assert!(aperture.is_short());
+        assert!(aperture.to_string().ends_with("(short)"), "{}", aperture);
+    }
+
+    #[test]
+    fn overflow_grows_across_the_row_within_the_web() {
+        let options = header().with_overflow(Overflow { max_extension: 2.0, min_web: 0.2 });
+        let aperture = options.aperture(&pad((1.7, 1.7), 1.0), 2.54);
+        assert!(aperture.overflow);
+        // 2.34 mm along the row leaves the web, 12.4769 / 2.34 = 5.332 rounds up to 5.34
+        assert_eq!(aperture.size, (5.34, 2.34));
+        assert!(!aperture.is_short(), "{}", aperture);
+
+        // Capped at 1.5 mm past each side of the pad, 4.7 mm across
+        let capped = header().with_overflow(Overflow::default()).aperture(&pad((1.7, 1.7), 1.0), 2.54);
+        assert_eq!(capped.size, (4.7, 2.34));
+        assert!(capped.is_short());
+    }
+
+    #[test]
+    fn pads_that_hold_enough_paste_print_only_themselves() {
+        // A pin filling most of the hole of a thin board, through a thick stencil
+        let options = PinInPaste::new(PinSection::Round { diameter: 0.95 })
+            .with_board_thickness(0.8)
+            .with_stencil_thickness(0.2)
+            .with_overflow(Overflow::default());
+        let pads = options.apply(vec![pad((1.6, 1.6), 1.0)], 2.54);
+        assert_eq!(pads.len(), 1);
+        assert!(pads[0].layers.iter().any(|layer| layer == "F.Paste"));
+        assert!(!options.aperture(&pads[0], 2.54).is_short());
+
+        let overflowing = header().with_overflow(Overflow::default()).apply(vec![pad((1.7, 1.7), 1.0)], 2.54);
+        assert_eq!(overflowing.len(), 2);
+        assert!(!overflowing[0].layers.iter().any(|layer| layer == "F.Paste"));
+        assert_eq!(overflowing[1].layers, PadLayers::paste_front());
+        assert!(!overflowing[1].has_copper());
+    }
+
+    #[test]
+    fn settings_are_checked() {
+        assert!(header().validate(1.0).is_ok());
+        // The 0.905 mm diagonal of the square pin jams in a 0.9 mm hole
+        assert!(matches!(header().validate(0.9), Err(PinInPasteError::PinDoesNotFit { .. })));
+        assert_eq!(header().with_paste_reduction(1.5).validate(1.0), Err(PinInPasteError::InvalidReduction(1.5)));
+        assert!(matches!(header().with_board_thickness(0.0).validate(1.0), Err(PinInPasteError::NotPositive { .. })));
+    }
+}
@@ -0,0 +1,274 @@
+//! Placement policy
+//!
+//! Layout standards restrict where parts may sit: positions on a fixed grid and rotations
+//! limited to a small set of angles, with individual references whitelisted for arbitrary
+//! rotation. A `PlacementPolicy` either snaps out-of-policy placements to the nearest
+//! allowed value or rejects them, and can audit placements that bypassed `Board::place`
+//! (bulk imports, direct edits through `components_mut`).
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Tolerance when comparing a coordinate or angle against the policy (mm or degrees)
+const POLICY_EPSILON: f32 = 1e-4;
+
+/// What `Board::place` does with a placement that violates the policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Enforcement {
+    /// Move the placement to the nearest grid point and allowed rotation
+    #[default]
+    Snap,
+    /// Refuse the placement with a `PlacementError`
+    Reject,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct PlacementPolicy {
+    /// Placement grid pitch in millimeters, `None` allows any position
+    pub grid: Option<f32>,
+    /// Allowed rotations in degrees within [0, 360), empty allows any rotation
+    pub allowed_rotations: Vec<f32>,
+    /// References exempt from the rotation restriction (they are still snapped to the grid)
+    pub per_ref_exceptions: BTreeSet<String>,
+    pub enforcement: Enforcement,
+}
+
+impl PlacementPolicy {
+    /// A policy that accepts every placement unchanged
+    pub fn unrestricted() -> Self {
+        Self::default()
+    }
+
+    /// Positions on `grid` and rotations in multiples of `rotation_step` degrees
+    pub fn new(grid: f32, rotation_step: f32) -> Result<Self, PolicyValueError> {
+        Self::default().with_grid(grid)?.with_rotation_step(rotation_step)
+    }
+
+    /// Fails unless `grid` is a positive number of millimeters
+    pub fn with_grid(mut self, grid: f32) -> Result<Self, PolicyValueError> {
+        if !(grid.is_finite() && grid > 0.0) {
+            return Err(PolicyValueError::Grid(grid));
+        }
+        self.grid = Some(grid);
+        Ok(self)
+    }
+
+    /// Allow every multiple of `step` degrees; fails unless `step` is positive
+    pub fn with_rotation_step(mut self, step: f32) -> Result<Self, PolicyValueError> {
+        if !(step.is_finite() && step > 0.0) {
+            return Err(PolicyValueError::RotationStep(step));
+        }
+        let count = ((360.0 / step).round() as usize).max(1);
+        self.allowed_rotations = (0..count).map(|i| i as f32 * step).collect();
+        Ok(self)
+    }
+
+    pub fn with_exception(mut self, reference: &str) -> Self {
+        self.per_ref_exceptions.insert(reference.to_string());
+        self
+    }
+
+    pub fn with_enforcement(mut self, enforcement: Enforcement) -> Self {
+        self.enforcement = enforcement;
+        self
+    }
+
+    /// Nearest grid coordinate. When the grid divides a millimeter evenly the result is
+    /// computed as steps / (steps per mm) so 5.7 comes out as 5.7 and not 5.7000003. A
+    /// grid that isn't positive, set through the field, leaves the value alone.
+    pub fn snap_coordinate(&self, value: f64) -> f64 {
+        let Some(grid) = self.grid.filter(|grid| *grid > 0.0) else { return value };
+        let grid = f64::from(grid);
+        let steps = (value / grid).round();
+        let per_mm = 1.0 / grid;
+        if (per_mm - per_mm.round()).abs() < f64::from(POLICY_EPSILON) {
+            steps / per_mm.round()
+        } else {
+            steps * grid
+        }
+    }
+
+    pub fn snap_position(&self, position: (f64, f64)) -> (f64, f64) {
+        (self.snap_coordinate(position.0), self.snap_coordinate(position.1))
+    }
+
+    /// Nearest allowed rotation for `reference`, normalized to [0, 360)
+    pub fn snap_rotation(&self, reference: &str, rotation: f32) -> f32 {
+        if self.allowed_rotations.is_empty() || self.per_ref_exceptions.contains(reference) {
+            return rotation;
+        }
+        let normalized = rotation.rem_euclid(360.0);
+        self.allowed_rotations
+            .iter()
+            .copied()
+            .min_by(|a, b| angle_distance(normalized, *a).total_cmp(&angle_distance(normalized, *b)))
+            .map(|allowed| allowed.rem_euclid(360.0))
+            .unwrap_or(rotation)
+    }
+
+    /// Every way the placement breaks the policy, empty when it conforms
+    pub fn check(&self, reference: &str, position: (f64, f64), rotation: f32) -> Vec<PlacementViolation> {
+        let mut violations = Vec::new();
+        let snapped = self.snap_position(position);
+        let epsilon = f64::from(POLICY_EPSILON);
+        if (snapped.0 - position.0).abs() > epsilon || (snapped.1 - position.1).abs() > epsilon {
+            violations.push(PlacementViolation::OffGrid { reference: reference.to_string(), position, nearest: snapped });
+        }
+        let snapped_rotation = self.snap_rotation(reference, rotation);
+        if angle_distance(rotation, snapped_rotation) > POLICY_EPSILON {
+            violations.push(PlacementViolation::DisallowedRotation {
+                reference: reference.to_string(),
+                position,
+                rotation,
+                nearest: snapped_rotation,
+            });
+        }
+        violations
+    }
+}
+
+/// Smallest angle between two rotations in degrees
+fn angle_distance(a: f32, b: f32) -> f32 {
+    let difference = (a - b).rem_euclid(360.0);
+    difference.min(360.0 - difference)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlacementViolation {
+    OffGrid { reference: String, position: (f64, f64), nearest: (f64, f64) },
+    DisallowedRotation { reference: String, position: (f64, f64), rotation: f32, nearest: f32 },
+}
+
+impl PlacementViolation {
+    pub fn reference(&self) -> &str {
+        match self {
+            PlacementViolation::OffGrid { reference, .. } => reference,
+            PlacementViolation::DisallowedRotation { reference, .. } => reference,
+        }
+    }
+
+    /// Where the component is placed
+    pub fn position(&self) -> (f64, f64) {
+        match self {
+            PlacementViolation::OffGrid { position, .. } | PlacementViolation::DisallowedRotation { position, .. } => *position,
+        }
+    }
+}
+
+impl fmt::Display for PlacementViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlacementViolation::OffGrid { reference, position, nearest } => write!(
+                f,
+                "{} at ({}, {}) is off grid, nearest grid point is ({}, {})",
+                reference, position.0, position.1, nearest.0, nearest.1
+            ),
+            PlacementViolation::DisallowedRotation { reference, rotation, nearest, .. } => write!(
+                f,
+                "{} rotation {}° is not allowed, nearest allowed rotation is {}°",
+                reference, rotation, nearest
+            ),
+        }
+    }
+}
+
+/// A grid or rotation step a `PlacementPolicy` can't be built with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PolicyValueError {
+    /// Grid pitch that isn't a positive number of millimeters
+    Grid(f32),
+    /// Rotation step that isn't a positive number of degrees
+    RotationStep(f32),
+}
+
+impl fmt::Display for PolicyValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyValueError::Grid(grid) => write!(f, "placement grid must be positive, got {}", grid),
+            PolicyValueError::RotationStep(step) => write!(f, "rotation step must be positive, got {}°", step),
+        }
+    }
+}
+
+impl std::error::Error for PolicyValueError {}
+
+/// A placement refused under `Enforcement::Reject`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlacementError {
+    pub violations: Vec<PlacementViolation>,
+}
+
+impl fmt::Display for PlacementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let messages: Vec<String> = self.violations.iter().map(|v| v.to_string()).collect();
+        f.write_str(&messages.join("; "))
+    }
+}
+
+impl std::error::Error for PlacementError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::presets;
+
+    #[test]
+    fn strict_policy_rejects_off_grid_placement() {
+        let policy = PlacementPolicy::new(0.05, 45.0).unwrap().with_enforcement(Enforcement::Reject);
+        let mut board = Board::new("strict").with_policy(policy);
+        let error = board.place("R1", presets::resistor_0603("10k"), (1.234, 5.678), 37.0).unwrap_err();
+        assert_eq!(error.violations.len(), 2, "{}", error);
+        assert!(matches!(&error.violations[0], PlacementViolation::OffGrid { nearest, .. } if *nearest == (1.25, 5.7)));
+        assert!(matches!(error.violations[1], PlacementViolation::DisallowedRotation { nearest, .. } if nearest == 45.0));
+        assert!(board.components().is_empty());
+    }
+
+    #[test]
+    fn snapping_policy_moves_placement_onto_grid() {
+        let mut board = Board::new("snap").with_policy(PlacementPolicy::new(0.05, 45.0).unwrap());
+        let placed = board.place("R1", presets::resistor_0603("10k"), (1.234, 5.678), 37.0).unwrap();
+        assert_eq!((placed.position, placed.rotation), ((1.25, 5.7), 45.0));
+        assert!(board.audit_placements().is_empty());
+    }
+
+    #[test]
+    fn exceptions_keep_their_rotation_but_not_their_position() {
+        let policy = PlacementPolicy::new(0.05, 90.0).unwrap().with_exception("U1");
+        assert_eq!(policy.snap_rotation("U1", 37.0), 37.0);
+        assert_eq!(policy.snap_rotation("U2", 37.0), 0.0);
+        assert_eq!(policy.snap_rotation("U2", -80.0), 270.0);
+        assert!(policy.check("U1", (0.1, 0.2), 37.0).is_empty());
+        assert_eq!(policy.check("U1", (0.11, 0.2), 37.0).len(), 1);
+    }
+
+    #[test]
+    fn audit_reports_placements_made_before_the_policy() {
+        let mut board = Board::new("audit");
+        board.place("R1", presets::resistor_0603("10k"), (1.234, 5.678), 37.0).unwrap();
+        board.set_policy(PlacementPolicy::new(0.05, 45.0).unwrap());
+        let violations = board.audit_placements();
+        assert_eq!(violations.iter().map(|violation| violation.reference()).collect::<Vec<_>>(), ["R1", "R1"]);
+        assert_eq!(board.exported_placement(&board.components()[0]), ((1.25, 5.7), 45.0));
+    }
+
+    #[test]
+    fn non_positive_grid_and_step_are_rejected() {
+        assert_eq!(PlacementPolicy::new(0.0, 45.0), Err(PolicyValueError::Grid(0.0)));
+        assert_eq!(PlacementPolicy::new(-0.05, 45.0), Err(PolicyValueError::Grid(-0.05)));
+        assert_eq!(PlacementPolicy::new(0.05, 0.0), Err(PolicyValueError::RotationStep(0.0)));
+        assert!(PlacementPolicy::unrestricted().with_grid(f32::NAN).is_err());
+        assert!(PlacementPolicy::unrestricted().with_rotation_step(f32::INFINITY).is_err());
+        // A zero grid set directly on the field leaves positions alone instead of making NaN
+        let policy = PlacementPolicy { grid: Some(0.0), ..PlacementPolicy::unrestricted() };
+        assert_eq!(policy.snap_coordinate(1.234), 1.234);
+    }
+
+    #[test]
+    fn coarse_rotation_step_allows_only_zero() {
+        let policy = PlacementPolicy::unrestricted().with_rotation_step(400.0).unwrap();
+        assert_eq!(policy.allowed_rotations, [0.0]);
+    }
+}
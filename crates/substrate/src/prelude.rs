@@ -1,7 +1,63 @@
 pub use crate::{
+    anchor::{Anchor, CentroidSource, RebaseError},
+    assembly::{Assembly, AssemblyError, AssemblyIssue, BoardPlacement, ConnectorEnd, InterconnectKind},
+    assembly_process::{AssemblyProcess, HoleTolerance, ProcessIssue, ProcessPlan, ProcessSet, ProcessViolation, SurfaceFinish},
+    artwork::CopperArtwork,
+    autoroute::{AutorouteOptions, AutorouteReport},
+    backdrill::{Backdrill, BackdrillIssue, BackdrillReport},
+    block::{Block, BlockError, BlockPlacement, BoardGroup},
+    board_drc::{EnvelopeIntrusion, UnconnectedPads},
     board_interface::*,
-    courtyard::Courtyard,
+    chip::{ChipFillets, ChipFootprint},
+    connector_marks::{ConnectorMark, ConnectorMarks},
+    copper_density::CopperDensityGrid,
+    courtyard::{ActuationEnvelope, Courtyard},
+    crystal::{Crystal, CrystalPackage},
+    describe::{normalize_tags, Density, DescriptionContext},
+    dimension::{Dimension, DimensionFormat, DimensionKind, DimensionUnits},
+    dip::Dip,
+    dpak::{Dpak, TabNumber},
+    edge_mount::EdgeMountSma,
+    exposed_copper::{ExposedCopper, ExposedShape},
+    fab_profile::FabProfile,
+    fanout::{FannedBall, FanoutError, FanoutFailure, FanoutOptions, FanoutReport},
+    fixed_geometry::{FixedGeometry, GeometryError},
+    footprint::{BoardRecord, Footprint},
     functional_types::FunctionalType,
-    layer_type::LayerType,
+    geometry::Point,
+    graphics::{simplify, Simplified},
+    hatch::{expand_hatch, FillStyle},
+    insertion_loss::{InsertionLossReport, LineGeometry, LineSection, NetLoss},
+    layer_type::{BoardLayer, LayerName, LayerType, PadLayers, PadSide, Side},
+    materials::{DielectricMaterial, MaterialError, MaterialKind, MaterialLibrary},
+    mounting_hole::MountingHole,
+    net_length::{NetLength, NetLengthReport, PinToPin},
+    netlist::{Elbow, Netlist, NetlistError, RouteError},
+    occupancy::{Occupancy, OccupancyGrid, PlacementKeepout},
+    package_outlines::{OutlineError, OutlineFamily, OutlineLibrary, PackageOutline},
     package_types::{Package, PackageType},
-};
\ No newline at end of file
+    pad_groups::{PadGroup, PadGroupKind, PadGrouping, PadGroupingError},
+    padstack::{PadLayerClass, PadLayerGeometry, PadStack, PadStackError},
+    pin1::{pin1_marker, pin1_marker_with_mask_margin, PackageFamily, Pin1Style},
+    pin_in_paste::{Overflow, PasteAperture, PinInPaste, PinSection},
+    qfn::Qfn,
+    resolve::{resolve, FootprintResolver, ResolveError, ResolveOptions},
+    roles::{Edge, ElementRole, RoleReplaced},
+    routing::{ClearanceRule, NetClass, NetCondition, Track, TrackArc, Via, ViaKind, ViaRules},
+    routing_hint::{ExitRange, RoutingHint, RoutingHintError, ROUTING_HINTS_PROPERTY},
+    rule_area::RuleArea,
+    scaling::{ScaleError, Scaled},
+    silk_minimums::{SilkChange, SilkSized},
+    snapshot::{BoardDiff, BoardSnapshot, UndoStack},
+    soic::Soic,
+    solder_jumper::{JumperBridge, SolderJumper},
+    stackup::{Stackup, StackupBuilder, StackupLayer},
+    stencil::{StencilFiducialPair, StencilTarget},
+    test_coverage::{NetCoverage, TestAccess, TestAccessKind, TestCoverageOptions, TestCoverageReport, TestPointSuggestion},
+    text_policy::{TextPolicy, TextRule, TextStyle},
+    thermal_balance::{ThermalBalanceOptions, ThermalBalanceReport, ThermalImbalance},
+    tolerance::{ManufacturingTolerances, Toleranced},
+    zone::Zone,
+};
+
+pub use copper_derive::BoardComponent;
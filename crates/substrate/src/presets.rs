@@ -0,0 +1,72 @@
+//! One-line presets for common passive footprints
+//!
+//! Quick scripts rarely care about body dimensions, so `presets::resistor_0603("10k")` or
+//! `presets::led_0805("red")` builds the standard chip footprint with its value already
+//! set. Every preset is a `ChipFootprint::standard` call, so the KiCad names, descriptions,
+//! tags and 3D model paths are the same as building the chip by hand. Ferrite beads use
+//! KiCad's inductor footprints.
+
+use crate::chip::ChipFootprint;
+use crate::functional_types::FunctionalType;
+
+fn standard(functional_type: FunctionalType, imperial: &str) -> ChipFootprint {
+    ChipFootprint::standard(functional_type, imperial).expect("preset sizes are in STANDARD_CHIP_SIZES")
+}
+
+macro_rules! presets {
+    ($kind:ident, $label:literal, $($name:ident => $size:literal),+ $(,)?) => {
+        $(
+            #[doc = concat!(" ", $size, " ", $label, " with the given value")]
+            pub fn $name(value: &str) -> ChipFootprint {
+                standard(FunctionalType::$kind(value.to_string()), $size)
+            }
+        )+
+    };
+}
+
+presets!(Resistor, "resistor",
+    resistor_01005 => "01005",
+    resistor_0201 => "0201",
+    resistor_0402 => "0402",
+    resistor_0603 => "0603",
+    resistor_0805 => "0805",
+    resistor_1206 => "1206",
+    resistor_1210 => "1210",
+);
+
+presets!(Capacitor, "capacitor",
+    capacitor_01005 => "01005",
+    capacitor_0201 => "0201",
+    capacitor_0402 => "0402",
+    capacitor_0603 => "0603",
+    capacitor_0805 => "0805",
+    capacitor_1206 => "1206",
+    capacitor_1210 => "1210",
+);
+
+presets!(Inductor, "inductor",
+    inductor_0201 => "0201",
+    inductor_0402 => "0402",
+    inductor_0603 => "0603",
+    inductor_0805 => "0805",
+    inductor_1206 => "1206",
+    inductor_1210 => "1210",
+);
+
+presets!(LED, "LED, the value is usually its colour",
+    led_0201 => "0201",
+    led_0402 => "0402",
+    led_0603 => "0603",
+    led_0805 => "0805",
+    led_1206 => "1206",
+    led_1210 => "1210",
+);
+
+presets!(FerriteBead, "ferrite bead",
+    ferrite_0201 => "0201",
+    ferrite_0402 => "0402",
+    ferrite_0603 => "0603",
+    ferrite_0805 => "0805",
+    ferrite_1206 => "1206",
+    ferrite_1210 => "1210",
+);
@@ -0,0 +1,457 @@
+//! QFN footprint generator
+//!
+//! Leadless packages with terminals on all four sides and an exposed pad in the middle.
+//! The pins are split evenly over the sides and count counterclockwise from the top of the
+//! left side, KiCad's orientation. Land patterns follow the IPC-7351 construction for
+//! no-lead terminals, as `Soic` does for gull-wing leads: the outer pad span is the body
+//! plus a toe fillet on each side and the inner gap the body less both terminals and a
+//! heel fillet each. `with_pad_length` overrides the resulting pad length, keeping the pad
+//! centers where the fillets put them, which is what a parameter sweep varies.
+//!
+//! Pitch and terminal dimensions are `Toleranced`, as for `Soic`: toleranced terminals
+//! give the IPC-7351 land of their envelope and are recorded in the description. The
+//! body is taken as exact.
+//!
+//! The exposed pad is printed through a grid of paste windows (see `paste_windows`)
+//! instead of one full aperture.
+
+use compact_str::ToCompactString;
+
+use crate::assembly_process::{AssemblyProcess, ProcessSet};
+use crate::board_interface::{
+    BoardComposableObject, FontSettings, FpText, FpTextType, GraphicElement, GraphicType, Model3D, PadDescriptor,
+    PadShape, PadType, Rectangle, Stroke, StrokeType, TentingSettings, TentingType,
+};
+use crate::chip::ChipFillets;
+use crate::courtyard::Courtyard;
+use crate::describe::{Density, DescriptionContext};
+use crate::fab_profile::FabProfile;
+use crate::functional_types::FunctionalType;
+use crate::ids::fresh_uuid;
+use crate::layer_type::{LayerType, PadLayers, Side};
+use crate::package_outlines::{chamfered_fab_outline, OutlineError, OutlineFamily, OutlineLibrary, PackageOutline};
+use crate::paste_windows::{layout_paste_windows, PasteWindowOptions};
+use crate::pin1::{pin1_marker, PackageFamily, Pin1Style};
+use crate::routing_hint::{outward_direction, RoutingHint};
+use crate::tolerance::{describe_tolerances, ipc_land, ManufacturingTolerances, Toleranced};
+
+const SILK_LINE_WIDTH: f32 = 0.12;
+/// Clearance from silkscreen line centers to pad copper
+const SILK_PAD_CLEARANCE: f32 = 0.2;
+/// Room the default exposed pad leaves between itself and the inner ends of the terminals
+const EXPOSED_PAD_INSET: f32 = 0.3;
+
+/// Fillet goals for no-lead terminals near IPC-7351 nominal density. IPC's side fillet is
+/// slightly negative; KiCad's QFNs keep the pads as wide as the terminals, and so does this.
+pub const NO_LEAD_FILLETS: ChipFillets = ChipFillets { toe: 0.3, heel: 0.0, side: 0.0 };
+
+/// No-lead fillet goals at `density`, IPC-7351's toes with the side fillet of
+/// `NO_LEAD_FILLETS`
+pub fn no_lead_fillets(density: Density) -> ChipFillets {
+    match density {
+        Density::Least => ChipFillets { toe: 0.2, ..NO_LEAD_FILLETS },
+        Density::Nominal => NO_LEAD_FILLETS,
+        Density::Most => ChipFillets { toe: 0.4, ..NO_LEAD_FILLETS },
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Qfn {
+    pub functional_type: FunctionalType,
+    /// Total pin count, a multiple of four, not counting the exposed pad
+    pub pins: usize,
+    pub pitch: Toleranced,
+    /// Body size along the left and right rows (Y)
+    pub body_length: f32,
+    /// Body size along the top and bottom rows (X)
+    pub body_width: f32,
+    /// Terminal length from the body edge inwards
+    pub terminal_length: Toleranced,
+    pub terminal_width: Toleranced,
+    /// Maximum seated height, used for the description
+    pub height: f32,
+    pub fillets: ChipFillets,
+    /// Board tolerances the land allows for when the terminals are toleranced
+    pub tolerances: ManufacturingTolerances,
+    /// Pad length replacing the one the fillets give, `None` to use the fillets
+    pub pad_length: Option<f32>,
+    /// Exposed pad size (X, Y), `None` for no exposed pad
+    pub exposed_pad: Option<(f32, f32)>,
+    pub pin1: Pin1Style,
+    /// Outline code the dimensions came from, if any
+    pub jedec: Option<String>,
+}
+
+impl Qfn {
+    /// A QFN with 0.4 mm terminals as wide as half the pitch and an exposed pad filling the
+    /// body inside the terminals. Set the real terminals with `with_terminals`.
+    pub fn new(pins: usize, pitch: impl Into<Toleranced>, body_length: f32, body_width: f32) -> Self {
+        let pitch = pitch.into();
+        let mut qfn = Self {
+            functional_type: FunctionalType::IntegratedCircuit(String::new()),
+            pins,
+            pitch,
+            body_length,
+            body_width,
+            terminal_length: Toleranced::exact(0.4),
+            terminal_width: Toleranced::exact(pitch.nominal / 2.0),
+            height: 1.0,
+            fillets: NO_LEAD_FILLETS,
+            tolerances: ManufacturingTolerances::default(),
+            pad_length: None,
+            exposed_pad: None,
+            pin1: Pin1Style::kicad_default(PackageFamily::Qfn),
+            jedec: None,
+        };
+        qfn.exposed_pad = Some(qfn.default_exposed_pad());
+        qfn
+    }
+
+    /// The QFN of an outline code in the built-in outline library, e.g. "MO-220-4x4-24"
+    pub fn from_jedec(code: &str, pins: usize) -> Result<Self, OutlineError> {
+        Self::from_outline(OutlineLibrary::builtin().get_with_pins(code, pins)?, pins)
+    }
+
+    /// The QFN of an outline from any library
+    pub fn from_outline(outline: &PackageOutline, pins: usize) -> Result<Self, OutlineError> {
+        if outline.family != OutlineFamily::Qfn {
+            return Err(OutlineError::WrongFamily { code: outline.code.clone(), family: outline.family, expected: OutlineFamily::Qfn });
+        }
+        if !outline.pins.contains(&pins) {
+            return Err(OutlineError::PinCount { code: outline.code.clone(), pins, defined: outline.pins.clone() });
+        }
+        let mut qfn = Self::new(pins, outline.pitch, outline.body_length, outline.body_width)
+            .with_terminals(outline.lead_length, outline.lead_width)
+            .with_height(outline.height);
+        qfn.jedec = Some(outline.code.clone());
+        Ok(qfn)
+    }
+
+    pub fn with_functional_type(mut self, functional_type: FunctionalType) -> Self {
+        self.functional_type = functional_type;
+        self
+    }
+
+    /// Length and width of the terminals. The default exposed pad follows the length.
+    pub fn with_terminals(mut self, length: impl Into<Toleranced>, width: impl Into<Toleranced>) -> Self {
+        let default_exposed_pad = self.exposed_pad == Some(self.default_exposed_pad());
+        self.terminal_length = length.into();
+        self.terminal_width = width.into();
+        if default_exposed_pad {
+            self.exposed_pad = Some(self.default_exposed_pad());
+        }
+        self
+    }
+
+    pub fn with_height(mut self, height: f32) -> Self {
+        self.height = height;
+        self
+    }
+
+    pub fn with_fillets(mut self, fillets: ChipFillets) -> Self {
+        self.fillets = fillets;
+        self
+    }
+
+    pub fn with_tolerances(mut self, tolerances: ManufacturingTolerances) -> Self {
+        self.tolerances = tolerances;
+        self
+    }
+
+    /// Pads `length` long instead of what the fillets give, centered where the fillets put
+    /// them
+    pub fn with_pad_length(mut self, length: f32) -> Self {
+        self.pad_length = Some(length);
+        self
+    }
+
+    pub fn with_exposed_pad(mut self, size: Option<(f32, f32)>) -> Self {
+        self.exposed_pad = size;
+        self
+    }
+
+    pub fn with_pin1(mut self, pin1: Pin1Style) -> Self {
+        self.pin1 = pin1;
+        self
+    }
+
+    fn default_exposed_pad(&self) -> (f32, f32) {
+        let inset = 2.0 * (self.terminal_length.nominal + EXPOSED_PAD_INSET);
+        (round(self.body_width - inset), round(self.body_length - inset))
+    }
+
+    /// Pad size (along the terminal, across it) and the distance of the pad centers from
+    /// the origin, for the rows across a body side `span` long
+    pub fn land_pattern(&self, span: f32) -> ((f32, f32), f32) {
+        let span = Toleranced::exact(span);
+        let land = ipc_land(span, self.terminal_length, self.terminal_width, self.fillets, self.tolerances);
+        ((self.pad_length.unwrap_or(land.pad_length()), land.width), land.row_offset())
+    }
+
+    /// Center and size of pin `index` (0 based)
+    fn pad_geometry(&self, index: usize) -> ((f32, f32), (f32, f32)) {
+        let per_side = self.pins / 4;
+        let pitch = self.pitch.nominal;
+        let first = -(per_side as f32 - 1.0) * pitch / 2.0;
+        let along = first + (index % per_side) as f32 * pitch;
+        let ((length, width), x) = self.land_pattern(self.body_width);
+        let (_, y) = self.land_pattern(self.body_length);
+        match index / per_side {
+            0 => ((-x, along), (length, width)),
+            1 => ((along, y), (width, length)),
+            2 => ((x, -along), (length, width)),
+            _ => ((-along, -y), (width, length)),
+        }
+    }
+
+    /// Body and pads together
+    fn extent(&self) -> Rectangle {
+        let ((length, _), x) = self.land_pattern(self.body_width);
+        let (_, y) = self.land_pattern(self.body_length);
+        let half_x = (x + length / 2.0).max(self.body_width / 2.0);
+        let half_y = (y + length / 2.0).max(self.body_length / 2.0);
+        Rectangle { min_x: -half_x, min_y: -half_y, max_x: half_x, max_y: half_y }
+    }
+
+    fn exposed_pad_descriptor(&self, size: (f32, f32)) -> PadDescriptor {
+        smd_pad(&(self.pins + 1).to_compact_string(), (0.0, 0.0), size)
+    }
+}
+
+fn smd_pad(number: &str, position: (f32, f32), size: (f32, f32)) -> PadDescriptor {
+    PadDescriptor {
+        number: number.into(),
+        pin_function: None,
+        pad_type: PadType::SMD,
+        shape: PadShape::RoundRect,
+        position,
+        rotation: None,
+        size,
+        drill_size: None,
+        drill_slot: None,
+        layers: PadLayers::smd_front(),
+        roundrect_ratio: Some(0.25),
+        tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+        padstack: None,
+        fab_property: None,
+        solder_mask_margin: None,
+        routing_hint: None,
+        uuid: fresh_uuid(),
+    }
+}
+
+/// Millimeters as KiCad names print them, without trailing zeros
+fn mm(value: f32) -> String {
+    format!("{}", round(value))
+}
+
+fn round(value: f32) -> f32 {
+    (value * 100.0).round() / 100.0
+}
+
+impl BoardComposableObject for Qfn {
+    fn is_smt(&self) -> bool {
+        true
+    }
+
+    fn is_electrical(&self) -> bool {
+        true
+    }
+
+    fn terminal_count(&self) -> usize {
+        self.pins + usize::from(self.exposed_pad.is_some())
+    }
+
+    /// Bottom terminations: reflow only, out of reach of both the wave and the iron
+    fn process(&self) -> ProcessSet {
+        AssemblyProcess::Reflow.into()
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        self.functional_type.clone()
+    }
+
+    /// KiCad's "QFN-24-1EP_4x4mm_P0.5mm_EP2.6x2.6mm", or "QFN-24_4x4mm_P0.5mm" without
+    /// an exposed pad
+    fn footprint_name(&self) -> String {
+        let size = format!("{}x{}mm_P{}mm", mm(self.body_width), mm(self.body_length), mm(self.pitch.nominal));
+        match self.exposed_pad {
+            Some((x, y)) => format!("QFN-{}-1EP_{}_EP{}x{}mm", self.pins, size, mm(x), mm(y)),
+            None => format!("QFN-{}_{}", self.pins, size),
+        }
+    }
+
+    fn library_name(&self) -> String {
+        "Package_DFN_QFN".to_string()
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        let (half_x, half_y) = (self.body_width / 2.0, self.body_length / 2.0);
+        Rectangle { min_x: -half_x, min_y: -half_y, max_x: half_x, max_y: half_y }
+    }
+
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        let mut pads: Vec<PadDescriptor> = (0..self.pins)
+            .map(|index| {
+                let (position, size) = self.pad_geometry(index);
+                smd_pad(&(index + 1).to_compact_string(), position, size)
+                    .with_routing_hint(RoutingHint::outward(outward_direction(position, size)))
+            })
+            .collect();
+        if let Some(size) = self.exposed_pad {
+            let mut exposed = self.exposed_pad_descriptor(size);
+            let windows = layout_paste_windows(&exposed, &PasteWindowOptions::default(), &FabProfile::default());
+            if windows.is_ok() {
+                exposed.layers.retain(|layer| layer != "F.Paste");
+            }
+            pads.push(exposed);
+            pads.extend(windows.map(|windows| windows.pads).unwrap_or_default());
+        }
+        pads
+    }
+
+    /// Custom fillets and pad lengths follow no IPC density level, so their descriptions
+    /// leave it out
+    fn description_context(&self) -> Option<DescriptionContext> {
+        let density = [Density::Nominal, Density::Least, Density::Most]
+            .into_iter()
+            .find(|&density| self.fillets == no_lead_fillets(density) && self.pad_length.is_none());
+        let terminals = [("terminal length", self.terminal_length), ("terminal width", self.terminal_width)];
+        let (exposed_x, exposed_y) = self.exposed_pad.map_or((String::new(), String::new()), |(x, y)| (mm(x), mm(y)));
+        Some(
+            DescriptionContext::new("qfn", self.functional_type.clone(), "QFN")
+                .with_density(density)
+                .with_option("QFN")
+                .with_option(&mm(self.pitch.nominal))
+                .with_field("pins", self.pins)
+                .with_field("outline", self.jedec.clone().unwrap_or_default())
+                .with_field("body_width", mm(self.body_width))
+                .with_field("body_length", mm(self.body_length))
+                .with_field("pitch", mm(self.pitch.nominal))
+                .with_field("height", mm(self.height))
+                .with_field("exposed_pad_x", exposed_x)
+                .with_field("exposed_pad_y", exposed_y)
+                .with_field("tolerances", describe_tolerances(&terminals, self.tolerances).unwrap_or_default()),
+        )
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        let text_y = self.extent().max_y + self.courtyard_margin() + 0.7;
+        let fab_size = (self.body_width * 0.25).clamp(0.5, 1.0);
+        let text = |text_type, text: String, position, layer: &str, size: f32| FpText {
+            text_type,
+            text,
+            position,
+            rotation: None,
+            layer: layer.into(),
+            uuid: fresh_uuid(),
+            font: FontSettings { size: (size, size), thickness: size * 0.15 },
+            hidden: false,
+            justify: None,
+            role: None,
+        };
+        vec![
+            text(FpTextType::Reference, "REF**".to_string(), (0.0, -text_y), "F.SilkS", 1.0),
+            text(FpTextType::Value, self.footprint_name(), (0.0, text_y), "F.Fab", 1.0),
+            text(FpTextType::User, "${REFERENCE}".to_string(), (0.0, 0.0), "F.Fab", fab_size),
+        ]
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        let body = self.bounding_box();
+        let mut elements = vec![chamfered_fab_outline(&body)];
+
+        // Silkscreen brackets on the body corners, stopping short of the pad rows
+        let pads = self.pad_descriptors();
+        let pitch = self.pitch.nominal;
+        let row_end = (self.pins / 4) as f32 * pitch / 2.0 - pitch / 2.0 + self.terminal_width.nominal / 2.0;
+        let (corner_x, corner_y) = (body.max_x + SILK_LINE_WIDTH / 2.0, body.max_y + SILK_LINE_WIDTH / 2.0);
+        let (end_x, end_y) = (row_end + SILK_PAD_CLEARANCE, row_end + SILK_PAD_CLEARANCE);
+        let line = |start, end| GraphicElement {
+            element_type: GraphicType::Line { start, end },
+            layer: LayerType::SilkScreen,
+            side: Side::Front,
+            stroke: Stroke { width: SILK_LINE_WIDTH, stroke_type: StrokeType::Solid },
+            uuid: fresh_uuid(),
+            role: None,
+        };
+        for (sx, sy) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+            if corner_x > end_x {
+                elements.push(line((sx * corner_x, sy * corner_y), (sx * end_x, sy * corner_y)));
+            }
+            if corner_y > end_y {
+                elements.push(line((sx * corner_x, sy * corner_y), (sx * corner_x, sy * end_y)));
+            }
+        }
+        elements.extend(pin1_marker(self.pin1, &body, &pads, "1"));
+        elements
+    }
+
+    fn model_3d(&self) -> Option<Model3D> {
+        Some(Model3D {
+            path: format!("${{KICAD9_3DMODEL_DIR}}/{}.3dshapes/{}.wrl", self.library_name(), self.footprint_name()),
+            offset: (0.0, 0.0, 0.0),
+            scale: (1.0, 1.0, 1.0),
+            rotation: (0.0, 0.0, 0.0),
+        })
+    }
+
+    /// Courtyard around the pads as well as the body
+    fn generate_courtyard(&self) -> Courtyard {
+        Courtyard::new(self.extent(), self.courtyard_margin())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{FootprintDrc, SpacingOptions, SpacingReport};
+
+    #[test]
+    fn mo_220_4x4_24_matches_kicad() {
+        let qfn = Qfn::from_jedec("MO-220-4x4-24", 24).unwrap();
+        assert_eq!(qfn.footprint_name(), "QFN-24-1EP_4x4mm_P0.5mm_EP2.6x2.6mm");
+        let pads = qfn.pad_descriptors();
+        // 24 terminals, the exposed pad and its paste windows
+        assert_eq!(pads.iter().filter(|pad| pad.has_copper()).count(), 25);
+        assert!(pads[24..].iter().skip(1).all(|pad| pad.number.is_empty() && !pad.has_copper()));
+        let first = &pads[0];
+        assert!((first.position.0 + 1.95).abs() < 1e-5 && (first.position.1 + 1.25).abs() < 1e-5, "{:?}", first.position);
+        assert!((first.size.0 - 0.7).abs() < 1e-5 && (first.size.1 - 0.25).abs() < 1e-5, "{:?}", first.size);
+        assert!(FootprintDrc::default().check(&qfn).is_empty(), "{:?}", FootprintDrc::default().check(&qfn));
+    }
+
+    #[test]
+    fn pins_count_counterclockwise_from_the_top_left() {
+        let pads = Qfn::from_jedec("MO-220-3x3-16", 16).unwrap().pad_descriptors();
+        let position = |number: &str| pads.iter().find(|pad| pad.number == number).unwrap().position;
+        let (one, four, five, nine, thirteen) = (position("1"), position("4"), position("5"), position("9"), position("13"));
+        assert!(one.0 < 0.0 && one.1 < four.1, "left side runs down");
+        assert!(five.1 > 0.0 && five.0 < position("8").0, "bottom side runs right");
+        assert!(nine.0 > 0.0 && nine.1 > position("12").1, "right side runs up");
+        assert!(thirteen.1 < 0.0 && thirteen.0 > position("16").0, "top side runs left");
+        // Top and bottom pads lie across the left and right ones
+        assert!(pads[0].size.0 > pads[0].size.1 && pads[4].size.1 > pads[4].size.0);
+    }
+
+    #[test]
+    fn longer_pads_close_the_gaps_and_grow_the_courtyard() {
+        let base = Qfn::from_jedec("MO-220-4x4-24", 24).unwrap();
+        let short = base.clone().with_pad_length(0.7);
+        let long = base.with_pad_length(1.0);
+        let gap = |qfn: &Qfn| SpacingReport::for_component(qfn, SpacingOptions::default()).copper.unwrap().gap;
+        assert!(gap(&long) < gap(&short));
+        assert!(long.generate_courtyard().bounds.width() > short.generate_courtyard().bounds.width());
+        // Custom pad lengths drop the density from the description
+        assert!(!long.description().unwrap().contains("IPC_7351"));
+    }
+
+    #[test]
+    fn only_qfn_outlines_build_a_qfn() {
+        assert!(matches!(
+            Qfn::from_jedec("MS-012-AA", 8),
+            Err(OutlineError::WrongFamily { family: OutlineFamily::Soic, expected: OutlineFamily::Qfn, .. })
+        ));
+        assert!(matches!(Qfn::from_jedec("MO-220-4x4-24", 16), Err(OutlineError::PinCount { .. })));
+    }
+}
@@ -0,0 +1,668 @@
+//! 2D footprint preview in egui
+//!
+//! `FootprintRenderer` is the default `ComponentRenderer`: it draws a footprint's pads,
+//! drills, silkscreen, fab, courtyard and text into an `egui::Painter` with KiCad's layer
+//! colors, highlights the pad under the pointer and shows its number and size next to it.
+//! Back side pads and graphics of two-sided footprints are drawn first and translucent,
+//! as if seen through the board.
+//! `ViewTransform` holds the millimeter to screen mapping and turns drag and scroll input
+//! into pan and zoom, so editors built on top only need to allocate a painter and forward
+//! the response.
+//! `BoardRenderer` draws a board's outline, pads, tracks, arc tracks and vias the same way.
+//!
+//! Both renderers draw the elements of a `Highlight` with a pulsing outline and mark its
+//! location. `ViolationPanel` lists DRC findings grouped by rule; selecting one highlights
+//! the elements involved and centers the view on it.
+//!
+//! The module needs egui and is built with the `render-traits` feature, on by default.
+
+use egui::{Color32, FontId, Pos2, Rect, Response, Shape, Vec2};
+
+use crate::analysis::findings::{ElementRef, Finding, Severity};
+use crate::board::Board;
+use crate::board_interface::{
+    BoardComposableObject, GraphicElement, GraphicType, Justify, PadDescriptor, Rectangle,
+};
+use crate::geometry::{point_in_polygon, push_arc, CircularArc, Point};
+use crate::hatch::{expand_hatch, FillStyle};
+use crate::layer_type::{PadSide, Side};
+
+/// Arc tolerance of the pad outlines as drawn (mm)
+const PREVIEW_ARC_TOLERANCE: f32 = 0.005;
+/// Strokes thinner than this many pixels are widened so they stay visible when zoomed out
+const MIN_STROKE_PIXELS: f32 = 1.0;
+/// Opacity of back side pads and graphics
+const BACK_SIDE_OPACITY: f32 = 0.5;
+/// Highlight pulses per second
+const PULSE_RATE: f64 = 1.5;
+const HIGHLIGHT_COLOR: Color32 = Color32::from_rgb(0x40, 0xff, 0xff);
+const VIA_COLOR: Color32 = Color32::from_rgb(0xec, 0xec, 0xec);
+/// Millimeters shown around a finding navigated to
+pub const DEFAULT_FOCUS_SPAN: f32 = 3.0;
+
+/// Draws a component into an egui painter
+pub trait ComponentRenderer {
+    fn render(&self, component: &dyn BoardComposableObject, ctx: &mut egui::Painter);
+}
+
+/// KiCad's default color of a layer, gray for layers without one
+pub fn layer_color(layer: &str) -> Color32 {
+    match layer {
+        "F.Cu" => Color32::from_rgb(0xc8, 0x34, 0x34),
+        "B.Cu" => Color32::from_rgb(0x4d, 0x7f, 0xc4),
+        "F.SilkS" => Color32::from_rgb(0xf2, 0xed, 0xa1),
+        "B.SilkS" => Color32::from_rgb(0xe8, 0xb2, 0xa7),
+        "F.Fab" => Color32::from_rgb(0xaf, 0xaf, 0xaf),
+        "B.Fab" => Color32::from_rgb(0x58, 0x5d, 0x84),
+        "F.CrtYd" => Color32::from_rgb(0xff, 0x26, 0xe2),
+        "B.CrtYd" => Color32::from_rgb(0x26, 0xe9, 0xff),
+        "F.Mask" | "B.Mask" => Color32::from_rgb(0xd8, 0x64, 0xff),
+        "F.Paste" | "B.Paste" => Color32::from_rgb(0xb5, 0xb5, 0xb5),
+        "Edge.Cuts" => Color32::from_rgb(0xd0, 0xd2, 0xcd),
+        _ => Color32::GRAY,
+    }
+}
+
+/// Color a pad is drawn in: plated through pads in KiCad's through-hole yellow, others in
+/// the color of their first copper layer, front preferred
+pub fn pad_color(pad: &PadDescriptor) -> Color32 {
+    if pad.drill_size.is_some() && pad.has_layer("F.Cu") && pad.has_layer("B.Cu") {
+        Color32::from_rgb(0xc2, 0xc2, 0x00)
+    } else if pad.has_layer("F.Cu") {
+        layer_color("F.Cu")
+    } else if pad.has_layer("B.Cu") {
+        layer_color("B.Cu")
+    } else if pad.has_layer("F.Paste") {
+        layer_color("F.Paste")
+    } else {
+        layer_color("F.Mask")
+    }
+}
+
+/// Pad indices back side pads first, so front and through hole pads are drawn over them
+fn draw_order(pads: &[PadDescriptor]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..pads.len()).collect();
+    order.sort_by_key(|&index| pads[index].side() != Some(PadSide::Back));
+    order
+}
+
+/// Mapping between footprint millimeters and screen pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewTransform {
+    /// Screen area the footprint is drawn into
+    pub viewport: Rect,
+    /// Pixels per millimeter
+    pub zoom: f32,
+    /// Screen offset of the footprint origin from the viewport center
+    pub pan: Vec2,
+}
+
+impl Default for ViewTransform {
+    fn default() -> Self {
+        Self { viewport: Rect::from_min_size(Pos2::ZERO, Vec2::splat(400.0)), zoom: 50.0, pan: Vec2::ZERO }
+    }
+}
+
+impl ViewTransform {
+    pub const MIN_ZOOM: f32 = 2.0;
+    pub const MAX_ZOOM: f32 = 2000.0;
+
+    pub fn to_screen(&self, point: (f32, f32)) -> Pos2 {
+        self.viewport.center() + self.pan + Vec2::new(point.0, point.1) * self.zoom
+    }
+
+    pub fn to_footprint(&self, pos: Pos2) -> Point {
+        let local = (pos - self.viewport.center() - self.pan) / self.zoom;
+        Point::new(local.x, local.y)
+    }
+
+    /// Zoom and center so `bounds` fills the viewport with a margin of `margin` pixels
+    pub fn fit(&mut self, bounds: &Rectangle, margin: f32) {
+        let available = (self.viewport.size() - Vec2::splat(2.0 * margin)).max(Vec2::splat(1.0));
+        let zoom_x = available.x / bounds.width().max(0.01);
+        let zoom_y = available.y / bounds.height().max(0.01);
+        self.zoom = zoom_x.min(zoom_y).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+        let center = Vec2::new(bounds.min_x + bounds.max_x, bounds.min_y + bounds.max_y) / 2.0;
+        self.pan = -center * self.zoom;
+    }
+
+    /// Center on `point`, zoomed so `span` millimeters fit the viewport
+    pub fn focus(&mut self, point: Point, span: f32) {
+        self.zoom = (self.viewport.size().min_elem() / span.max(0.01)).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+        self.pan = -Vec2::new(point.x, point.y) * self.zoom;
+    }
+
+    /// Zoom by `factor`, keeping the footprint point under `anchor` in place
+    pub fn zoom_about(&mut self, anchor: Pos2, factor: f32) {
+        let before = self.to_footprint(anchor);
+        self.zoom = (self.zoom * factor).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+        self.pan += anchor - self.to_screen((before.x, before.y));
+    }
+
+    /// Take the viewport from `response`, pan on drag and zoom about the pointer on scroll
+    pub fn handle_input(&mut self, response: &Response) {
+        self.viewport = response.rect;
+        self.pan += response.drag_delta();
+        if let Some(pointer) = response.hover_pos() {
+            let scroll = response.ctx.input(|input| input.smooth_scroll_delta.y);
+            if scroll != 0.0 {
+                self.zoom_about(pointer, (scroll * 0.002).exp());
+            }
+        }
+    }
+
+    fn stroke(&self, width: f32, color: Color32) -> egui::Stroke {
+        egui::Stroke::new((width * self.zoom).max(MIN_STROKE_PIXELS), color)
+    }
+}
+
+/// Elements to outline and the point to mark, usually those of a selected finding
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Highlight {
+    pub elements: Vec<ElementRef>,
+    pub location: Option<Point>,
+}
+
+impl Highlight {
+    pub fn of(finding: &Finding) -> Self {
+        Self { elements: finding.elements.clone(), location: finding.location }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty() && self.location.is_none()
+    }
+}
+
+/// 0 to 1 and back `PULSE_RATE` times a second; keeps frames coming while anything pulses
+fn pulse(ctx: &egui::Context) -> f32 {
+    ctx.request_repaint();
+    let time = ctx.input(|input| input.time);
+    (0.5 - 0.5 * (time * PULSE_RATE * std::f64::consts::TAU).cos()) as f32
+}
+
+/// Outline stroke of highlighted elements, `extra` pixels wider than the element's own
+fn highlight_stroke(pulse: f32, extra: f32) -> egui::Stroke {
+    egui::Stroke::new(extra + 1.5 + 2.5 * pulse, HIGHLIGHT_COLOR.gamma_multiply(0.6 + 0.4 * pulse))
+}
+
+/// Ring with four ticks around a finding's location
+fn draw_marker(painter: &egui::Painter, center: Pos2, pulse: f32) {
+    let radius = 10.0 + 6.0 * pulse;
+    let stroke = egui::Stroke::new(1.5, HIGHLIGHT_COLOR);
+    painter.circle_stroke(center, radius, stroke);
+    for direction in [Vec2::X, -Vec2::X, Vec2::Y, -Vec2::Y] {
+        painter.line_segment([center + direction * (radius - 4.0), center + direction * (radius + 6.0)], stroke);
+    }
+}
+
+/// Draws footprints for preview; see the module documentation
+#[derive(Debug, Clone, Default)]
+pub struct FootprintRenderer {
+    pub view: ViewTransform,
+    pub hide_courtyard: bool,
+    pub hide_text: bool,
+    pub highlight: Highlight,
+}
+
+impl FootprintRenderer {
+    pub fn new(view: ViewTransform) -> Self {
+        Self { view, hide_courtyard: false, hide_text: false, highlight: Highlight::default() }
+    }
+
+    /// Index of the pad whose outline contains `pos`, topmost (last drawn) first
+    pub fn pad_at(&self, component: &dyn BoardComposableObject, pos: Pos2) -> Option<usize> {
+        let point = self.view.to_footprint(pos);
+        let pads = component.pad_descriptors();
+        draw_order(&pads).into_iter().rev().find(|&index| point_in_polygon(point, &pads[index].outline_polygon(PREVIEW_ARC_TOLERANCE)))
+    }
+
+    /// Extent of everything drawn, for `ViewTransform::fit`
+    pub fn bounds(component: &dyn BoardComposableObject) -> Rectangle {
+        let mut points = Vec::new();
+        let courtyard = component.generate_courtyard().bounds;
+        for bounds in component.generate_courtyards().iter().map(|courtyard| &courtyard.bounds) {
+            points.push(Point::new(bounds.min_x, bounds.min_y));
+            points.push(Point::new(bounds.max_x, bounds.max_y));
+        }
+        for pad in component.pad_descriptors() {
+            points.extend(pad.outline_polygon(PREVIEW_ARC_TOLERANCE));
+        }
+        Rectangle::enclosing(&points).unwrap_or(courtyard)
+    }
+
+    fn draw_pad(&self, painter: &egui::Painter, pad: &PadDescriptor, highlighted: bool) {
+        let outline: Vec<Pos2> =
+            pad.outline_polygon(PREVIEW_ARC_TOLERANCE).iter().map(|p| self.view.to_screen((p.x, p.y))).collect();
+        let back = pad.side() == Some(PadSide::Back);
+        let stroke = match (highlighted, back) {
+            (true, _) => egui::Stroke::new(2.0, Color32::WHITE),
+            (false, true) => egui::Stroke::new(1.0, pad_color(pad)),
+            (false, false) => egui::Stroke::NONE,
+        };
+        let fill = if highlighted { pad_color(pad).lerp_to_gamma(Color32::WHITE, 0.3) } else { pad_color(pad) };
+        let fill = if back { fill.gamma_multiply(BACK_SIDE_OPACITY) } else { fill };
+        // A non-plated hole without copper is only its hole, outlined when highlighted
+        if pad.is_npth() && !pad.has_copper() {
+            let hole: Vec<Pos2> = pad
+                .drill_outline(PREVIEW_ARC_TOLERANCE)
+                .unwrap_or_default()
+                .iter()
+                .map(|p| self.view.to_screen((p.x, p.y)))
+                .collect();
+            painter.add(Shape::convex_polygon(hole, Color32::BLACK, stroke));
+            return;
+        }
+        painter.add(Shape::convex_polygon(outline, fill, stroke));
+        if let Some(hole) = pad.drill_outline(PREVIEW_ARC_TOLERANCE) {
+            let hole = hole.iter().map(|p| self.view.to_screen((p.x, p.y))).collect();
+            painter.add(Shape::convex_polygon(hole, Color32::BLACK, egui::Stroke::NONE));
+        }
+    }
+
+    fn draw_graphic(&self, painter: &egui::Painter, element: &GraphicElement) {
+        let color = layer_color(&element.kicad_layer());
+        let color = if element.side == Side::Back { color.gamma_multiply(BACK_SIDE_OPACITY) } else { color };
+        self.draw_graphic_stroked(painter, element, self.view.stroke(element.stroke.width, color), Some(color));
+    }
+
+    /// `element` drawn with `stroke`, solid polygons filled and hatching drawn only when
+    /// `fill_color` is given
+    fn draw_graphic_stroked(
+        &self,
+        painter: &egui::Painter,
+        element: &GraphicElement,
+        stroke: egui::Stroke,
+        fill_color: Option<Color32>,
+    ) {
+        let screen = |p: (f32, f32)| self.view.to_screen(p);
+        match &element.element_type {
+            GraphicType::Line { start, end } => {
+                painter.line_segment([screen(*start), screen(*end)], stroke);
+            }
+            GraphicType::Rectangle { bounds } => {
+                let corners = [
+                    (bounds.min_x, bounds.min_y),
+                    (bounds.max_x, bounds.min_y),
+                    (bounds.max_x, bounds.max_y),
+                    (bounds.min_x, bounds.max_y),
+                ];
+                painter.add(Shape::closed_line(corners.into_iter().map(screen).collect(), stroke));
+            }
+            GraphicType::Circle { center, radius } => {
+                painter.circle_stroke(screen(*center), radius * self.view.zoom, stroke);
+            }
+            GraphicType::Polygon { points, fill } => {
+                let outline: Vec<Pos2> = points.iter().copied().map(screen).collect();
+                match (fill, fill_color) {
+                    // egui only fills convex shapes; concave ones get a rough fill
+                    (Some(FillStyle::Solid), Some(color)) => painter.add(Shape::convex_polygon(outline, color, stroke)),
+                    _ => painter.add(Shape::closed_line(outline, stroke)),
+                };
+                for line in expand_hatch(element).iter().skip(1).filter(|_| fill_color.is_some()) {
+                    if let GraphicType::Line { start, end } = line.element_type {
+                        painter.line_segment([screen(start), screen(end)], self.view.stroke(line.stroke.width, stroke.color));
+                    }
+                }
+            }
+            GraphicType::Dimension(dimension) => {
+                let geometry = dimension.geometry();
+                for (a, b) in &geometry.lines {
+                    painter.line_segment([screen((a.x, a.y)), screen((b.x, b.y))], stroke);
+                }
+                for (tip, left, right) in &geometry.arrowheads {
+                    let points = [left, tip, right].into_iter().map(|p| screen((p.x, p.y))).collect();
+                    painter.add(Shape::line(points, stroke));
+                }
+                let position = geometry.text_position;
+                let at = (position.x, position.y, geometry.text_angle);
+                self.draw_text(painter, &dimension.text(), at, dimension.text_height, Justify::default(), stroke.color);
+            }
+        }
+    }
+
+    /// Text anchored at `at` (x, y, degrees counter-clockwise) by its justification. egui
+    /// can't mirror glyphs, so mirrored text only has its anchor swapped.
+    fn draw_text(&self, painter: &egui::Painter, text: &str, at: (f32, f32, f32), height: f32, justify: Justify, color: Color32) {
+        let (x, y, angle) = at;
+        let galley = painter.layout_no_wrap(text.to_string(), FontId::proportional(height * self.view.zoom), color);
+        // egui rotates clockwise about the top left corner of the text
+        let rotation = egui::emath::Rot2::from_angle(-angle.to_radians());
+        let (offset_x, offset_y) = justify.center_offset();
+        let size = galley.size();
+        let to_top_left = Vec2::new(size.x * (offset_x - 0.5), size.y * (offset_y - 0.5));
+        let top_left = self.view.to_screen((x, y)) + rotation * to_top_left;
+        painter.add(egui::epaint::TextShape::new(top_left, galley, color).with_angle(-angle.to_radians()));
+    }
+
+    /// Pulsing outlines of the highlighted pads and graphics, and the location marker
+    fn draw_highlight(&self, painter: &egui::Painter, pads: &[PadDescriptor], graphics: &[GraphicElement]) {
+        let pulse = pulse(painter.ctx());
+        for element in &self.highlight.elements {
+            match element {
+                ElementRef::Pad(index) => {
+                    let Some(pad) = pads.get(*index) else {
+                        continue;
+                    };
+                    let outline = pad.outline_polygon(PREVIEW_ARC_TOLERANCE).iter().map(|p| self.view.to_screen((p.x, p.y))).collect();
+                    painter.add(Shape::closed_line(outline, highlight_stroke(pulse, 0.0)));
+                }
+                ElementRef::Graphic(index) => {
+                    let Some(graphic) = graphics.get(*index) else {
+                        continue;
+                    };
+                    let own = self.view.stroke(graphic.stroke.width, HIGHLIGHT_COLOR).width;
+                    self.draw_graphic_stroked(painter, graphic, highlight_stroke(pulse, own), None);
+                }
+                _ => {}
+            }
+        }
+        if let Some(location) = self.highlight.location {
+            draw_marker(painter, self.view.to_screen((location.x, location.y)), pulse);
+        }
+    }
+
+    /// Number and size of a pad in a box next to `pointer`
+    fn draw_pad_tooltip(&self, painter: &egui::Painter, pad: &PadDescriptor, pointer: Pos2) {
+        let number = if pad.number.is_empty() { "(unnumbered)" } else { pad.number.as_str() };
+        let mut text = format!("Pad {}\n{:.3} x {:.3} mm", number, pad.size.0, pad.size.1);
+        match (pad.drill_slot, pad.drill_size) {
+            (Some(slot), _) => text.push_str(&format!("\nslot {:.3} x {:.3} mm", slot.0, slot.1)),
+            (None, Some(drill)) => text.push_str(&format!("\ndrill {:.3} mm", drill)),
+            (None, None) => {}
+        }
+        if pad.is_npth() {
+            text.push_str(", not plated");
+        }
+        let galley = painter.layout_no_wrap(text, FontId::proportional(13.0), Color32::WHITE);
+        let rect = Rect::from_min_size(pointer + Vec2::new(16.0, 16.0), galley.size()).expand(4.0);
+        painter.rect_filled(rect, 3.0, Color32::from_black_alpha(220));
+        painter.galley(rect.min + Vec2::splat(4.0), galley, Color32::WHITE);
+    }
+}
+
+impl ComponentRenderer for FootprintRenderer {
+    fn render(&self, component: &dyn BoardComposableObject, ctx: &mut egui::Painter) {
+        let painter = &*ctx;
+        let pointer = painter.ctx().pointer_hover_pos().filter(|pos| painter.clip_rect().contains(*pos));
+        let hovered = pointer.and_then(|pos| self.pad_at(component, pos));
+
+        let pads = component.pad_descriptors();
+        let mut graphics = component.graphic_elements();
+        if !self.hide_courtyard {
+            graphics.extend(component.generate_courtyards().iter().flat_map(|courtyard| courtyard.to_graphic_elements()));
+        }
+        let (back, front): (Vec<_>, Vec<_>) = graphics.iter().partition(|element| element.side == Side::Back);
+        for element in back {
+            self.draw_graphic(painter, element);
+        }
+        for index in draw_order(&pads) {
+            self.draw_pad(painter, &pads[index], hovered == Some(index));
+        }
+        for element in front {
+            self.draw_graphic(painter, element);
+        }
+        if !self.hide_text {
+            for text in component.fp_text_elements().into_iter().filter(|text| !text.hidden) {
+                let color = layer_color(&text.layer);
+                let at = (text.position.0, text.position.1, text.rotation.unwrap_or(0.0));
+                self.draw_text(painter, &text.text, at, text.font.size.1, text.justify.unwrap_or_default(), color);
+            }
+        }
+        if !self.highlight.is_empty() {
+            self.draw_highlight(painter, &pads, &graphics);
+        }
+        if let (Some(index), Some(pointer)) = (hovered, pointer) {
+            self.draw_pad_tooltip(painter, &pads[index], pointer);
+        }
+    }
+}
+
+/// Draws a board's outline, copper pads, tracks, arcs and vias; see the module documentation
+#[derive(Debug, Clone, Default)]
+pub struct BoardRenderer {
+    pub view: ViewTransform,
+    pub highlight: Highlight,
+}
+
+impl BoardRenderer {
+    pub fn new(view: ViewTransform) -> Self {
+        Self { view, highlight: Highlight::default() }
+    }
+
+    /// The outline's extent, or that of the copper for boards without one
+    pub fn bounds(board: &Board) -> Rectangle {
+        if let Some(bounds) = board.outline().and_then(Rectangle::enclosing) {
+            return bounds;
+        }
+        let mut points: Vec<Point> = board.tracks().iter().flat_map(|track| [track.start, track.end]).collect();
+        points.extend(board.arcs().iter().flat_map(|arc| arc.chords(PREVIEW_ARC_TOLERANCE)).flat_map(|(start, end)| [start, end]));
+        points.extend(board.vias().iter().map(|via| via.position));
+        for layer in board.copper_layers() {
+            points.extend(board.pad_outlines(layer, PREVIEW_ARC_TOLERANCE).into_iter().flat_map(|(_, outline)| outline));
+        }
+        Rectangle::enclosing(&points).unwrap_or(Rectangle { min_x: -1.0, min_y: -1.0, max_x: 1.0, max_y: 1.0 })
+    }
+
+    pub fn render(&self, board: &Board, painter: &egui::Painter) {
+        let screen = |p: Point| self.view.to_screen((p.x, p.y));
+        if let Some(outline) = board.outline() {
+            let stroke = egui::Stroke::new(MIN_STROKE_PIXELS, layer_color("Edge.Cuts"));
+            painter.add(Shape::closed_line(outline.iter().copied().map(screen).collect(), stroke));
+        }
+
+        // Bottom layer first, so the front copper ends up on top
+        let layers = board.copper_layers();
+        for (position, layer) in layers.iter().enumerate().rev() {
+            let name = layer.to_kicad_string();
+            // Layers below the front one are seen through the board
+            let color = if position == 0 { layer_color(&name) } else { layer_color(&name).gamma_multiply(BACK_SIDE_OPACITY) };
+            for (_, outline) in board.pad_outlines(*layer, PREVIEW_ARC_TOLERANCE) {
+                painter.add(Shape::convex_polygon(outline.into_iter().map(screen).collect(), color, egui::Stroke::NONE));
+            }
+            for track in board.tracks().iter().filter(|track| track.layer == *layer) {
+                let width = (track.width * self.view.zoom).max(MIN_STROKE_PIXELS);
+                painter.line_segment([screen(track.start), screen(track.end)], egui::Stroke::new(width, color));
+                painter.circle_filled(screen(track.start), width / 2.0, color);
+                painter.circle_filled(screen(track.end), width / 2.0, color);
+            }
+            for arc in board.arcs().iter().filter(|arc| arc.layer == *layer) {
+                let width = (arc.width * self.view.zoom).max(MIN_STROKE_PIXELS);
+                let points = arc.chords(PREVIEW_ARC_TOLERANCE).into_iter().map(|(start, _)| screen(start)).chain([screen(arc.end)]);
+                painter.add(Shape::line(points.collect(), egui::Stroke::new(width, color)));
+                painter.circle_filled(screen(arc.start), width / 2.0, color);
+                painter.circle_filled(screen(arc.end), width / 2.0, color);
+            }
+        }
+        for via in board.vias() {
+            painter.circle_filled(screen(via.position), via.diameter / 2.0 * self.view.zoom, VIA_COLOR);
+            painter.circle_filled(screen(via.position), via.drill / 2.0 * self.view.zoom, Color32::BLACK);
+        }
+        if !self.highlight.is_empty() {
+            self.draw_highlight(board, painter);
+        }
+    }
+
+    fn draw_highlight(&self, board: &Board, painter: &egui::Painter) {
+        let pulse = pulse(painter.ctx());
+        let stroke = highlight_stroke(pulse, 0.0);
+        let screen = |p: Point| self.view.to_screen((p.x, p.y));
+        let mut pads: Vec<(String, Vec<Point>)> = Vec::new();
+        for layer in board.copper_layers() {
+            pads.extend(board.pad_outlines(layer, PREVIEW_ARC_TOLERANCE));
+        }
+        for element in &self.highlight.elements {
+            match element {
+                ElementRef::Track(index) => {
+                    let Some(track) = board.tracks().get(*index) else {
+                        continue;
+                    };
+                    let outline = capsule_outline(track.start, track.end, track.width / 2.0);
+                    painter.add(Shape::closed_line(outline.into_iter().map(screen).collect(), stroke));
+                }
+                ElementRef::Arc(index) => {
+                    let Some(arc) = board.arcs().get(*index) else {
+                        continue;
+                    };
+                    let outline = match arc.geometry() {
+                        Some(geometry) => arc_outline(&geometry, arc.width / 2.0),
+                        None => capsule_outline(arc.start, arc.end, arc.width / 2.0),
+                    };
+                    painter.add(Shape::closed_line(outline.into_iter().map(screen).collect(), stroke));
+                }
+                ElementRef::Via(index) => {
+                    if let Some(via) = board.vias().get(*index) {
+                        painter.circle_stroke(screen(via.position), via.diameter / 2.0 * self.view.zoom, stroke);
+                    }
+                }
+                ElementRef::BoardPad(name) => {
+                    if let Some((_, outline)) = pads.iter().find(|(pad, _)| pad == name) {
+                        painter.add(Shape::closed_line(outline.iter().copied().map(screen).collect(), stroke));
+                    }
+                }
+                ElementRef::Component(reference) => {
+                    let prefix = format!("{}.", reference);
+                    for (_, outline) in pads.iter().filter(|(pad, _)| pad.starts_with(&prefix)) {
+                        painter.add(Shape::closed_line(outline.iter().copied().map(screen).collect(), stroke));
+                    }
+                }
+                ElementRef::Pad(_) | ElementRef::Graphic(_) | ElementRef::Text(_) => {}
+            }
+        }
+        if let Some(location) = self.highlight.location {
+            draw_marker(painter, screen(location), pulse);
+        }
+    }
+}
+
+/// Outline of an arc track: the arc widened by `radius` with round ends
+fn arc_outline(arc: &CircularArc, radius: f32) -> Vec<Point> {
+    let (start, end) = (arc.start_degrees, arc.start_degrees + arc.sweep_degrees);
+    // Caps turn the way the arc does, from one edge round its end to the other
+    let side = arc.sweep_degrees.signum();
+    let outer = CircularArc { radius: arc.radius + radius, ..*arc };
+    let inner = CircularArc { radius: (arc.radius - radius).max(0.0), start_degrees: end, sweep_degrees: -arc.sweep_degrees, ..*arc };
+    let mut outline = outer.points(PREVIEW_ARC_TOLERANCE);
+    push_arc(&mut outline, arc.end(), radius, end, 180.0 * side, PREVIEW_ARC_TOLERANCE);
+    outline.extend(inner.points(PREVIEW_ARC_TOLERANCE));
+    push_arc(&mut outline, arc.start(), radius, start + 180.0, 180.0 * side, PREVIEW_ARC_TOLERANCE);
+    outline
+}
+
+/// Outline of a track: the segment `start`-`end` widened by `radius` with round ends
+fn capsule_outline(start: Point, end: Point, radius: f32) -> Vec<Point> {
+    let angle = (end.y - start.y).atan2(end.x - start.x).to_degrees();
+    let at = |center: Point, degrees: f32| {
+        Point::new(center.x + radius * degrees.to_radians().cos(), center.y + radius * degrees.to_radians().sin())
+    };
+    let mut outline = vec![at(end, angle - 90.0)];
+    push_arc(&mut outline, end, radius, angle - 90.0, 180.0, PREVIEW_ARC_TOLERANCE);
+    outline.push(at(start, angle + 90.0));
+    push_arc(&mut outline, start, radius, angle + 90.0, 180.0, PREVIEW_ARC_TOLERANCE);
+    outline
+}
+
+/// Icon and color a severity is listed with
+pub fn severity_icon(severity: Severity) -> (&'static str, Color32) {
+    match severity {
+        Severity::Error => ("⛔", Color32::from_rgb(0xe0, 0x50, 0x40)),
+        Severity::Warning => ("⚠", Color32::from_rgb(0xe8, 0xc0, 0x40)),
+        Severity::Info => ("ℹ", Color32::from_rgb(0x60, 0x90, 0xe0)),
+    }
+}
+
+/// DRC findings grouped by rule. Clicking one selects it: the view is centered on its
+/// location and the elements involved are highlighted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ViolationPanel {
+    findings: Vec<Finding>,
+    selected: Option<usize>,
+    /// Millimeters shown around a selected finding
+    pub focus_span: f32,
+}
+
+impl Default for ViolationPanel {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl ViolationPanel {
+    pub fn new(findings: Vec<Finding>) -> Self {
+        Self { findings, selected: None, focus_span: DEFAULT_FOCUS_SPAN }
+    }
+
+    pub fn with_focus_span(mut self, span: f32) -> Self {
+        self.focus_span = span;
+        self
+    }
+
+    /// Replace the findings, dropping the selection
+    pub fn set_findings(&mut self, findings: Vec<Finding>) {
+        self.findings = findings;
+        self.selected = None;
+    }
+
+    pub fn findings(&self) -> &[Finding] {
+        &self.findings
+    }
+
+    pub fn selected(&self) -> Option<&Finding> {
+        self.findings.get(self.selected?)
+    }
+
+    /// Rules with the indices of their findings, rules with the most severe findings first
+    /// and otherwise in order of appearance
+    pub fn groups(&self) -> Vec<(String, Vec<usize>)> {
+        let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+        for (index, finding) in self.findings.iter().enumerate() {
+            match groups.iter_mut().find(|(rule, _)| *rule == finding.rule) {
+                Some((_, indices)) => indices.push(index),
+                None => groups.push((finding.rule.clone(), vec![index])),
+            }
+        }
+        let worst = |indices: &[usize]| indices.iter().map(|&index| self.findings[index].severity).max();
+        groups.sort_by_key(|(_, indices)| std::cmp::Reverse(worst(indices)));
+        groups
+    }
+
+    /// Select finding `index`, centering `view` on it when it has a location, and return
+    /// what to highlight
+    pub fn select(&mut self, index: usize, view: &mut ViewTransform) -> Highlight {
+        let Some(finding) = self.findings.get(index) else {
+            return Highlight::default();
+        };
+        self.selected = Some(index);
+        if let Some(location) = finding.location {
+            view.focus(location, self.focus_span);
+        }
+        Highlight::of(finding)
+    }
+
+    /// The grouped list; returns the new highlight when a finding was clicked
+    pub fn ui(&mut self, ui: &mut egui::Ui, view: &mut ViewTransform) -> Option<Highlight> {
+        if self.findings.is_empty() {
+            ui.weak("No violations");
+            return None;
+        }
+        let count = |severity| self.findings.iter().filter(|finding| finding.severity == severity).count();
+        ui.label(format!("{} errors, {} warnings, {} info", count(Severity::Error), count(Severity::Warning), count(Severity::Info)));
+        let mut clicked = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (rule, indices) in self.groups() {
+                egui::CollapsingHeader::new(format!("{} ({})", rule, indices.len())).default_open(true).show(ui, |ui| {
+                    for index in indices {
+                        let finding = &self.findings[index];
+                        let (icon, color) = severity_icon(finding.severity);
+                        ui.horizontal(|ui| {
+                            ui.colored_label(color, icon);
+                            if ui.selectable_label(self.selected == Some(index), &finding.message).clicked() {
+                                clicked = Some(index);
+                            }
+                        });
+                    }
+                });
+            }
+        });
+        clicked.map(|index| self.select(index, view))
+    }
+}
@@ -0,0 +1,449 @@
+//! Footprints from what a part is and how it is packaged
+//!
+//! `resolve` takes a functional type and a `Package` ("10k resistor in 0603") and picks
+//! the generator that builds it, so callers needn't know the generators' own APIs:
+//!
+//! ```ignore
+//! let package = Package::parse("0603");
+//! let options = ResolveOptions::default().with_hand_solder();
+//! let footprint = resolve(&FunctionalType::Resistor("10k".into()), &package, &options)?;
+//! assert_eq!(footprint.footprint_name(), "R_0603_1608Metric_Pad0.85x0.95mm_HandSolder");
+//! ```
+//!
+//! A `FootprintResolver` holds rules by package class (`Package::class`). A rule returns
+//! `None` for functional types it doesn't build, so one class can have several: chip
+//! codes build chips, except for crystals and oscillators, which get SMD crystal packages.
+//! Rules registered by the user are tried before the built-in ones, newest first, and can
+//! add classes for `Package::Named` packages or take over built-in ones.
+//!
+//! `ResolveOptions::density` picks the IPC-7351 level of the generators that have levels
+//! (chips, SOICs and QFNs); the others are built at their only one. Hand soldering takes
+//! KiCad's hand solder chips from 0603 up, and the largest lands on SOICs; through hole
+//! and TO-252/263 packages need nothing for it. Thermal vias go in the TO-252/263 tab.
+//! Combinations that can't be built fail with the closest ones that can.
+
+use std::fmt;
+
+use crate::board_interface::BoardComposableObject;
+use crate::chip::{ChipFillets, ChipFootprint, HAND_SOLDER_MIN_BODY_LENGTH, STANDARD_CHIP_SIZES};
+use crate::crystal::{Crystal, STANDARD_CRYSTAL_SIZES};
+use crate::describe::Density;
+use crate::dip::Dip;
+use crate::dpak::{Dpak, DPAK_VARIANTS};
+use crate::fab_profile::FabProfile;
+use crate::functional_types::FunctionalType;
+use crate::package_outlines::{edit_distance, OutlineFamily, OutlineLibrary};
+use crate::package_types::Package;
+use crate::qfn::{no_lead_fillets, Qfn};
+use crate::soic::{gull_wing_fillets, Soic};
+use crate::thermal_vias::ThermalViaOptions;
+
+/// Most alternatives an error lists
+const NEAR_MATCHES: usize = 3;
+/// Largest difference (mm) between an `SMT` body size and a standard chip size
+const SIZE_TOLERANCE: f32 = 0.05;
+
+/// What to build besides the package itself, see the module documentation
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResolveOptions {
+    pub hand_solder: bool,
+    pub density: Density,
+    /// Vias in the thermal pad, checked against the profile
+    pub thermal_vias: Option<(ThermalViaOptions, FabProfile)>,
+}
+
+impl ResolveOptions {
+    pub fn with_hand_solder(mut self) -> Self {
+        self.hand_solder = true;
+        self
+    }
+
+    pub fn with_density(mut self, density: Density) -> Self {
+        self.density = density;
+        self
+    }
+
+    pub fn with_thermal_vias(mut self, options: ThermalViaOptions, profile: FabProfile) -> Self {
+        self.thermal_vias = Some((options, profile));
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolveError {
+    /// No rule builds the functional type, by kind name, in the package
+    Unsupported { package: String, functional_type: String, near: Vec<String> },
+    /// The package can be built, but not with an option, e.g. "hand solder"
+    UnsupportedOption { package: String, option: String, near: Vec<String> },
+    /// The generator refused the package or options
+    Generator { package: String, message: String },
+}
+
+/// "; closest: A, B" or nothing
+fn closest(near: &[String]) -> String {
+    if near.is_empty() { String::new() } else { format!("; closest: {}", near.join(", ")) }
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolveError::Unsupported { package, functional_type, near } => {
+                write!(f, "no {} footprint in {}{}", functional_type, package, closest(near))
+            }
+            ResolveError::UnsupportedOption { package, option, near } => {
+                write!(f, "{} has no {} footprint{}", package, option, closest(near))
+            }
+            ResolveError::Generator { package, message } => write!(f, "cannot build {}: {}", package, message),
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
+pub type Resolved = Result<Box<dyn BoardComposableObject>, ResolveError>;
+
+/// A resolver rule: `None` for functional types it leaves to other rules
+pub type ResolveRule = Box<dyn Fn(&FunctionalType, &Package, &ResolveOptions) -> Option<Resolved>>;
+
+/// Rules by package class, see the module documentation
+pub struct FootprintResolver {
+    rules: Vec<(String, ResolveRule)>,
+}
+
+impl Default for FootprintResolver {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl FootprintResolver {
+    /// No rules at all
+    pub fn empty() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Chips, SMD crystals, SOICs, QFNs, DIPs and TO-252/263 packages
+    pub fn builtin() -> Self {
+        Self::empty()
+            .with_rule("chip", chip_rule)
+            .with_rule("chip", crystal_rule)
+            .with_rule("smt", smt_rule)
+            .with_rule("soic", soic_rule)
+            .with_rule("qfn", qfn_rule)
+            .with_rule("dip", dip_rule)
+            .with_rule("dpak", dpak_rule)
+    }
+
+    /// Add a rule for packages of `class`, tried before every rule added earlier
+    pub fn register(&mut self, class: &str, rule: impl Fn(&FunctionalType, &Package, &ResolveOptions) -> Option<Resolved> + 'static) {
+        self.rules.push((class.to_string(), Box::new(rule)));
+    }
+
+    pub fn with_rule(mut self, class: &str, rule: impl Fn(&FunctionalType, &Package, &ResolveOptions) -> Option<Resolved> + 'static) -> Self {
+        self.register(class, rule);
+        self
+    }
+
+    /// Classes with at least one rule, sorted
+    pub fn classes(&self) -> Vec<&str> {
+        let mut classes: Vec<&str> = self.rules.iter().map(|(class, _)| class.as_str()).collect();
+        classes.sort_unstable();
+        classes.dedup();
+        classes
+    }
+
+    pub fn resolve(&self, functional_type: &FunctionalType, package: &Package, options: &ResolveOptions) -> Resolved {
+        let class = package.class();
+        let mut matched = false;
+        for (_, rule) in self.rules.iter().rev().filter(|(rule_class, _)| *rule_class == class) {
+            matched = true;
+            if let Some(resolved) = rule(functional_type, package, options) {
+                return resolved;
+            }
+        }
+        // Without any rule for the class, the classes that have them are the alternatives
+        let near = if matched { Vec::new() } else { nearest_by_name(&class, self.classes().into_iter().map(str::to_string)) };
+        Err(ResolveError::Unsupported { package: package.to_string(), functional_type: functional_type.kind_name().to_string(), near })
+    }
+}
+
+/// Resolve with the built-in rules
+pub fn resolve(functional_type: &FunctionalType, package: &Package, options: &ResolveOptions) -> Resolved {
+    FootprintResolver::builtin().resolve(functional_type, package, options)
+}
+
+/// `candidates` closest to `wanted` in spelling, closest first
+fn nearest_by_name(wanted: &str, candidates: impl IntoIterator<Item = String>) -> Vec<String> {
+    let wanted = wanted.to_ascii_uppercase();
+    let mut near: Vec<(usize, String)> =
+        candidates.into_iter().map(|candidate| (edit_distance(&wanted, &candidate.to_ascii_uppercase()), candidate)).collect();
+    near.sort();
+    near.into_iter().take(NEAR_MATCHES).map(|(_, candidate)| candidate).collect()
+}
+
+/// `candidates` closest to `wanted` by a number each carries, closest first
+fn nearest_by<T: Copy + Into<f64>>(wanted: T, candidates: impl IntoIterator<Item = (T, String)>) -> Vec<String> {
+    let wanted = wanted.into();
+    let mut near: Vec<(T, String)> = candidates.into_iter().collect();
+    near.sort_by(|a, b| (a.0.into() - wanted).abs().total_cmp(&(b.0.into() - wanted).abs()));
+    near.into_iter().take(NEAR_MATCHES).map(|(_, candidate)| candidate).collect()
+}
+
+fn unsupported(functional_type: &FunctionalType, package: &Package, near: Vec<String>) -> Option<Resolved> {
+    Some(Err(ResolveError::Unsupported { package: package.to_string(), functional_type: functional_type.kind_name().to_string(), near }))
+}
+
+fn unsupported_option(package: &Package, option: &str, near: Vec<String>) -> Option<Resolved> {
+    Some(Err(ResolveError::UnsupportedOption { package: package.to_string(), option: option.to_string(), near }))
+}
+
+/// The packages thermal vias can go in
+fn thermal_via_packages() -> Vec<String> {
+    DPAK_VARIANTS.iter().map(|variant| format!("{}-{}", variant.package, variant.leads)).take(NEAR_MATCHES).collect()
+}
+
+/// Built-in outline codes of `family` with `pins`, from the preferred one as `key` orders
+/// them to the last
+fn outlines_with_pins(family: OutlineFamily, pins: usize, key: impl Fn(f32, f32) -> f32) -> Vec<String> {
+    let library = OutlineLibrary::builtin();
+    let mut outlines: Vec<_> = library.codes().filter_map(|code| library.get_with_pins(code, pins).ok()).filter(|outline| outline.family == family).collect();
+    outlines.sort_by(|a, b| key(a.body_length, a.body_width).total_cmp(&key(b.body_length, b.body_width)));
+    outlines.into_iter().map(|outline| outline.code.clone()).collect()
+}
+
+/// "SOIC-8" style names for the pin counts of `family` nearest `pins`
+fn nearest_pin_counts(family: OutlineFamily, prefix: &str, pins: usize) -> Vec<String> {
+    let library = OutlineLibrary::builtin();
+    let mut counts: Vec<u32> =
+        library.codes().filter_map(|code| library.get(code).ok()).filter(|outline| outline.family == family).flat_map(|outline| outline.pins.clone()).map(|pins| pins as u32).collect();
+    counts.sort_unstable();
+    counts.dedup();
+    nearest_by(pins as u32, counts.into_iter().map(|count| (count, format!("{}-{}", prefix, count))))
+}
+
+fn chip_rule(functional_type: &FunctionalType, package: &Package, options: &ResolveOptions) -> Option<Resolved> {
+    let Package::Chip { code } = package else { return None };
+    if matches!(functional_type, FunctionalType::Crystal(_) | FunctionalType::Oscillator(_)) {
+        return None;
+    }
+    let Some(chip) = ChipFootprint::standard(functional_type.clone(), code) else {
+        return unsupported(functional_type, package, nearest_by_name(code, STANDARD_CHIP_SIZES.iter().map(|(code, ..)| code.to_string())));
+    };
+    if options.thermal_vias.is_some() {
+        return unsupported_option(package, "thermal via", thermal_via_packages());
+    }
+    let fillets = ChipFillets::for_density(chip.body_length, options.density);
+    let mut chip = chip.with_fillets(fillets);
+    if options.hand_solder {
+        if chip.body_length < HAND_SOLDER_MIN_BODY_LENGTH - f32::EPSILON {
+            let hand_solderable = STANDARD_CHIP_SIZES.iter().filter(|(_, length, ..)| *length >= HAND_SOLDER_MIN_BODY_LENGTH - f32::EPSILON);
+            return unsupported_option(package, "hand solder", hand_solderable.map(|(code, ..)| code.to_string()).take(NEAR_MATCHES).collect());
+        }
+        chip = chip.with_hand_solder();
+    }
+    Some(Ok(Box::new(chip)))
+}
+
+fn crystal_rule(functional_type: &FunctionalType, package: &Package, options: &ResolveOptions) -> Option<Resolved> {
+    let Package::Chip { code } = package else { return None };
+    if !matches!(functional_type, FunctionalType::Crystal(_) | FunctionalType::Oscillator(_)) {
+        return None;
+    }
+    let Some(crystal) = Crystal::standard(code) else {
+        return unsupported(functional_type, package, nearest_by_name(code, STANDARD_CRYSTAL_SIZES.iter().map(|(code, ..)| code.to_string())));
+    };
+    if options.hand_solder {
+        return unsupported_option(package, "hand solder", Vec::new());
+    }
+    if options.thermal_vias.is_some() {
+        return unsupported_option(package, "thermal via", thermal_via_packages());
+    }
+    Some(Ok(Box::new(crystal.with_functional_type(functional_type.clone()))))
+}
+
+/// Body sizes that match a standard chip resolve as that chip
+fn smt_rule(functional_type: &FunctionalType, package: &Package, options: &ResolveOptions) -> Option<Resolved> {
+    let Package::SMT { size: (length, width), .. } = package else { return None };
+    let standard = STANDARD_CHIP_SIZES
+        .iter()
+        .find(|(_, standard_length, standard_width, _)| (standard_length - length).abs() <= SIZE_TOLERANCE && (standard_width - width).abs() <= SIZE_TOLERANCE);
+    match standard {
+        Some((code, ..)) => chip_rule(functional_type, &Package::Chip { code: code.to_string() }, options),
+        None => {
+            let sizes = STANDARD_CHIP_SIZES.iter().map(|(code, standard_length, standard_width, _)| {
+                ((standard_length - length).abs() + (standard_width - width).abs(), code.to_string())
+            });
+            unsupported(functional_type, package, nearest_by(0.0, sizes))
+        }
+    }
+}
+
+fn soic_rule(functional_type: &FunctionalType, package: &Package, options: &ResolveOptions) -> Option<Resolved> {
+    let Package::Soic { pins, outline } = package else { return None };
+    let Some(code) = outline.clone().or_else(|| outlines_with_pins(OutlineFamily::Soic, *pins, |_, width| width).into_iter().next()) else {
+        return unsupported(functional_type, package, nearest_pin_counts(OutlineFamily::Soic, "SOIC", *pins));
+    };
+    if options.thermal_vias.is_some() {
+        return unsupported_option(package, "thermal via", thermal_via_packages());
+    }
+    let soic = match Soic::from_jedec(&code, *pins) {
+        Ok(soic) => soic,
+        Err(error) => return Some(Err(ResolveError::Generator { package: package.to_string(), message: error.to_string() })),
+    };
+    // IPC-7351's most density is the level meant for hand soldering
+    let density = if options.hand_solder { Density::Most } else { options.density };
+    Some(Ok(Box::new(soic.with_functional_type(functional_type.clone()).with_fillets(gull_wing_fillets(density)))))
+}
+
+fn qfn_rule(functional_type: &FunctionalType, package: &Package, options: &ResolveOptions) -> Option<Resolved> {
+    let Package::Qfn { pins, outline } = package else { return None };
+    let Some(code) = outline.clone().or_else(|| outlines_with_pins(OutlineFamily::Qfn, *pins, |length, width| length * width).into_iter().next()) else {
+        return unsupported(functional_type, package, nearest_pin_counts(OutlineFamily::Qfn, "QFN", *pins));
+    };
+    if options.hand_solder {
+        return unsupported_option(package, "hand solder", nearest_pin_counts(OutlineFamily::Soic, "SOIC", *pins));
+    }
+    if options.thermal_vias.is_some() {
+        return unsupported_option(package, "thermal via", thermal_via_packages());
+    }
+    let qfn = match Qfn::from_jedec(&code, *pins) {
+        Ok(qfn) => qfn,
+        Err(error) => return Some(Err(ResolveError::Generator { package: package.to_string(), message: error.to_string() })),
+    };
+    Some(Ok(Box::new(qfn.with_functional_type(functional_type.clone()).with_fillets(no_lead_fillets(options.density)))))
+}
+
+fn dip_rule(functional_type: &FunctionalType, package: &Package, options: &ResolveOptions) -> Option<Resolved> {
+    let Package::Dip { pins } = package else { return None };
+    let Some(dip) = Dip::new(*pins) else {
+        let counts = (2..=20).map(|half| (half * 2, format!("DIP-{}", half * 2)));
+        return unsupported(functional_type, package, nearest_by(*pins as u32, counts));
+    };
+    if options.thermal_vias.is_some() {
+        return unsupported_option(package, "thermal via", thermal_via_packages());
+    }
+    Some(Ok(Box::new(dip.with_functional_type(functional_type.clone()))))
+}
+
+fn dpak_rule(functional_type: &FunctionalType, package: &Package, options: &ResolveOptions) -> Option<Resolved> {
+    let Package::Dpak { package: name, leads } = package else { return None };
+    let Some(dpak) = Dpak::standard(name, *leads) else {
+        let variants = DPAK_VARIANTS.iter().filter(|variant| variant.package == name).map(|variant| (variant.leads as u32, format!("{}-{}", variant.package, variant.leads)));
+        return unsupported(functional_type, package, nearest_by(*leads as u32, variants));
+    };
+    let mut dpak = dpak.with_functional_type(functional_type.clone());
+    if let Some((vias, profile)) = &options.thermal_vias {
+        dpak = match dpak.with_thermal_vias(vias.clone(), profile.clone()) {
+            Ok(dpak) => dpak,
+            Err(error) => return Some(Err(ResolveError::Generator { package: package.to_string(), message: error.to_string() })),
+        };
+    }
+    Some(Ok(Box::new(dpak)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(functional_type: FunctionalType, package: &str, options: &ResolveOptions) -> String {
+        match resolve(&functional_type, &Package::parse(package), options) {
+            Ok(footprint) => footprint.footprint_name(),
+            Err(error) => panic!("{} in {}: {}", functional_type.kind_name(), package, error),
+        }
+    }
+
+    #[test]
+    fn combinations_resolve_to_canonical_names() {
+        let plain = ResolveOptions::default();
+        let hand = ResolveOptions::default().with_hand_solder();
+        let vias = ResolveOptions::default().with_thermal_vias(ThermalViaOptions::default(), FabProfile::standard());
+        let cases = [
+            (FunctionalType::Resistor("10k".into()), "0603", &plain, "R_0603_1608Metric"),
+            (FunctionalType::Resistor("10k".into()), "0603", &hand, "R_0603_1608Metric_Pad0.85x0.95mm_HandSolder"),
+            (FunctionalType::Capacitor("100n".into()), "0402", &plain, "C_0402_1005Metric"),
+            (FunctionalType::Capacitor("10u".into()), "0805", &hand, "C_0805_2012Metric_Pad0.95x1.40mm_HandSolder"),
+            (FunctionalType::Inductor("4u7".into()), "1210", &plain, "L_1210_3225Metric"),
+            (FunctionalType::LED("red".into()), "0603", &plain, "LED_0603_1608Metric"),
+            (FunctionalType::Crystal("16MHz".into()), "3225", &plain, "Crystal_SMD_3225-4Pin_3.2x2.5mm"),
+            (FunctionalType::Oscillator("25MHz".into()), "2520", &plain, "Oscillator_SMD_2520-4Pin_2.5x2.0mm"),
+            (FunctionalType::OpAmp("TL072".into()), "SOIC-8", &plain, "SOIC-8_3.9x4.9mm_P1.27mm"),
+            (FunctionalType::IntegratedCircuit("74HC595".into()), "SOIC-16", &hand, "SOIC-16_3.9x9.9mm_P1.27mm"),
+            (FunctionalType::IsolationIC("ISO7741".into()), "MS-013-AA", &plain, "SOIC-16W_7.5x10.3mm_P1.27mm"),
+            (FunctionalType::MCU("ATtiny".into()), "QFN-24", &plain, "QFN-24-1EP_4x4mm_P0.5mm_EP2.6x2.6mm"),
+            (FunctionalType::Timer("NE555".into()), "DIP-8", &plain, "DIP-8_W7.62mm"),
+            (FunctionalType::IntegratedCircuit("LM1117".into()), "TO-252-3", &vias, "TO-252-3_TabPin2_ThermalVias"),
+        ];
+        for (functional_type, package, options, expected) in cases {
+            assert_eq!(name(functional_type, package, options), expected, "{}", package);
+        }
+    }
+
+    #[test]
+    fn body_sizes_resolve_as_the_standard_chip() {
+        let package = Package::SMT { size: (1.6, 0.8), pitch: None };
+        let footprint = resolve(&FunctionalType::Resistor("1k".into()), &package, &ResolveOptions::default()).unwrap();
+        assert_eq!(footprint.footprint_name(), "R_0603_1608Metric");
+    }
+
+    #[test]
+    fn density_sets_the_fillets_and_is_described() {
+        let most = ResolveOptions::default().with_density(Density::Most);
+        let resolved = resolve(&FunctionalType::Resistor("1k".into()), &Package::parse("0805"), &most).unwrap();
+        let nominal = resolve(&FunctionalType::Resistor("1k".into()), &Package::parse("0805"), &ResolveOptions::default()).unwrap();
+        assert_eq!(resolved.footprint_name(), nominal.footprint_name());
+        assert!(resolved.pad_descriptors()[0].size.0 > nominal.pad_descriptors()[0].size.0);
+        assert_eq!(resolved.description_context().unwrap().density, Some(Density::Most));
+        let soic = resolve(&FunctionalType::IntegratedCircuit(String::new()), &Package::parse("SOIC-8"), &ResolveOptions::default().with_density(Density::Least)).unwrap();
+        assert_eq!(soic.description_context().unwrap().density, Some(Density::Least));
+    }
+
+    #[test]
+    fn unsupported_combinations_list_the_closest_alternatives() {
+        let resistor = FunctionalType::Resistor("1k".into());
+        let ic = FunctionalType::IntegratedCircuit(String::new());
+        let error = |functional_type: &FunctionalType, package: &str, options: &ResolveOptions| match resolve(functional_type, &Package::parse(package), options) {
+            Ok(footprint) => panic!("{} resolved to {}", package, footprint.footprint_name()),
+            Err(error) => error,
+        };
+        let plain = ResolveOptions::default();
+        let hand = ResolveOptions::default().with_hand_solder();
+
+        let ResolveError::Unsupported { near, .. } = error(&resistor, "0604", &plain) else { panic!() };
+        assert_eq!(near[0], "0603");
+        let ResolveError::Unsupported { near, .. } = error(&ic, "SOIC-10", &plain) else { panic!() };
+        assert_eq!(near[..2], ["SOIC-8".to_string(), "SOIC-14".to_string()]);
+        assert_eq!(error(&ic, "QFN-24", &hand).to_string(), "QFN-24 has no hand solder footprint; closest: SOIC-24, SOIC-20, SOIC-28");
+        let ResolveError::UnsupportedOption { near, .. } = error(&resistor, "0402", &hand) else { panic!() };
+        assert_eq!(near[0], "0603");
+        let ResolveError::UnsupportedOption { near, .. } =
+            error(&ic, "SOIC-8", &ResolveOptions::default().with_thermal_vias(ThermalViaOptions::default(), FabProfile::standard()))
+        else {
+            panic!()
+        };
+        assert!(near.iter().all(|package| package.starts_with("TO-2")));
+        let ResolveError::Unsupported { near, .. } = error(&ic, "SOT-23-5", &plain) else { panic!() };
+        assert!(!near.is_empty());
+    }
+
+    #[test]
+    fn user_rules_add_classes_and_take_over_built_in_ones() {
+        let resolver = FootprintResolver::builtin()
+            .with_rule("SOT-23", |functional_type, package, _| {
+                let Package::Named(name) = package else { return None };
+                let pins = name.rsplit_once('-')?.1.parse().ok()?;
+                Some(Ok(Box::new(Soic::new(pins, 0.95, 2.9, 1.6).with_functional_type(functional_type.clone())) as Box<dyn BoardComposableObject>))
+            })
+            .with_rule("chip", |functional_type, _, _| {
+                matches!(functional_type, FunctionalType::Fuse(_)).then(|| Ok(Box::new(Dip::new(4).unwrap()) as Box<dyn BoardComposableObject>))
+            });
+        let ic = FunctionalType::IntegratedCircuit(String::new());
+        assert_eq!(Package::parse("SOT-23-5").class(), "SOT-23");
+        assert!(resolver.resolve(&ic, &Package::parse("SOT-23-5"), &ResolveOptions::default()).is_ok());
+        let fuse = resolver.resolve(&FunctionalType::Fuse("1A".into()), &Package::parse("1206"), &ResolveOptions::default()).unwrap();
+        assert_eq!(fuse.footprint_name(), "DIP-4_W7.62mm");
+        // Types the new rule passes on still reach the built-in chips
+        let resistor = resolver.resolve(&FunctionalType::Resistor("1k".into()), &Package::parse("1206"), &ResolveOptions::default()).unwrap();
+        assert_eq!(resistor.footprint_name(), "R_1206_3216Metric");
+        assert!(resolver.classes().contains(&"SOT-23"));
+    }
+}
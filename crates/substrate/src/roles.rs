@@ -0,0 +1,320 @@
+//! Semantic roles of graphics and texts
+//!
+//! A uuid tells elements apart but not what they are, so after composition or import there
+//! is no way to say "the fab outline" or "the pin 1 marker". Generators tag what they draw
+//! with an `ElementRole`: the fab and silkscreen outlines, whole or by edge, and the
+//! pin 1 markers of `pin1_marker`. Elements without a role, e.g. read from a `.kicad_mod`,
+//! can be tagged with `with_role`.
+//!
+//! `BoardComposableObject::graphics_with_role` finds elements by role, and
+//! `replace_role` swaps them out without touching the rest of the footprint; on a board,
+//! `Board::replace_role` does the same to one placed part. Roles are not written to
+//! KiCad files.
+
+use std::fmt;
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::anchor::Anchor;
+use crate::assembly_process::ProcessSet;
+use crate::board::Board;
+use crate::board_interface::{BoardComposableObject, FpText, GraphicElement, Model3D, PadDescriptor, Rectangle};
+use crate::courtyard::{ActuationEnvelope, Courtyard};
+use crate::describe::DescriptionContext;
+use crate::functional_types::FunctionalType;
+use crate::pad_groups::PadGrouping;
+use crate::rule_area::RuleArea;
+
+/// One side of an outline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Edge {
+    Left,
+    Right,
+    /// Toward negative Y
+    Top,
+    Bottom,
+}
+
+/// What a graphic or text is for
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ElementRole {
+    /// The body outline on the fab layer; `None` when one element draws all of it
+    FabOutline(Option<Edge>),
+    /// The body outline on the silkscreen, or the side of it that one element draws
+    SilkOutline(Option<Edge>),
+    Pin1Marker,
+    /// Cathode or polarity band of polarized two terminal parts
+    PolarityBand,
+    /// Arrow out of a connector's mating face, see `connector_marks`
+    MatingFace,
+    /// Key or polarization feature of a connector
+    KeyingMark,
+    /// Arrow showing which way the mating part goes in
+    InsertionArrow,
+    UserDefined(String),
+}
+
+impl fmt::Display for ElementRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let outline = |f: &mut fmt::Formatter<'_>, layer: &str, edge: &Option<Edge>| match edge {
+            Some(edge) => write!(f, "{} outline, {:?} edge", layer, edge),
+            None => write!(f, "{} outline", layer),
+        };
+        match self {
+            ElementRole::FabOutline(edge) => outline(f, "fab", edge),
+            ElementRole::SilkOutline(edge) => outline(f, "silkscreen", edge),
+            ElementRole::Pin1Marker => write!(f, "pin 1 marker"),
+            ElementRole::PolarityBand => write!(f, "polarity band"),
+            ElementRole::MatingFace => write!(f, "mating face"),
+            ElementRole::KeyingMark => write!(f, "keying mark"),
+            ElementRole::InsertionArrow => write!(f, "insertion arrow"),
+            ElementRole::UserDefined(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// `graphics` with `role` set on each
+pub(crate) fn with_role(graphics: Vec<GraphicElement>, role: ElementRole) -> Vec<GraphicElement> {
+    graphics.into_iter().map(|element| element.with_role(role.clone())).collect()
+}
+
+/// A component with the graphics of one role replaced
+#[derive(Debug, Clone)]
+pub struct RoleReplaced<T: ?Sized> {
+    inner: Rc<T>,
+    role: ElementRole,
+    replacement: Vec<GraphicElement>,
+}
+
+impl<T: BoardComposableObject + ?Sized> RoleReplaced<T> {
+    /// `replacement` takes the place of the first graphic in `role` and the others in that
+    /// role are dropped; with none in that role it is drawn last. The replacement
+    /// elements are given the role, so a later lookup finds them.
+    pub fn new(inner: Rc<T>, role: ElementRole, replacement: Vec<GraphicElement>) -> Self {
+        let replacement = with_role(replacement, role.clone());
+        Self { inner, role, replacement }
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn role(&self) -> &ElementRole {
+        &self.role
+    }
+
+    /// Whether the inner component draws anything in the role
+    pub fn replaces_any(&self) -> bool {
+        self.inner.graphic_elements().iter().any(|element| element.role.as_ref() == Some(&self.role))
+    }
+}
+
+impl<T: BoardComposableObject + ?Sized> BoardComposableObject for RoleReplaced<T> {
+    fn is_smt(&self) -> bool {
+        self.inner.is_smt()
+    }
+
+    fn is_electrical(&self) -> bool {
+        self.inner.is_electrical()
+    }
+
+    fn is_passive(&self) -> bool {
+        self.inner.is_passive()
+    }
+
+    fn is_assembled(&self) -> bool {
+        self.inner.is_assembled()
+    }
+
+    fn allows_soldermask_bridges(&self) -> bool {
+        self.inner.allows_soldermask_bridges()
+    }
+
+    fn pad_grouping(&self) -> PadGrouping {
+        self.inner.pad_grouping()
+    }
+
+    fn terminal_count(&self) -> usize {
+        self.inner.terminal_count()
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        self.inner.functional_type()
+    }
+
+    fn footprint_name(&self) -> String {
+        self.inner.footprint_name()
+    }
+
+    fn library_name(&self) -> String {
+        self.inner.library_name()
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        self.inner.bounding_box()
+    }
+
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        self.inner.pad_descriptors()
+    }
+
+    fn description_context(&self) -> Option<DescriptionContext> {
+        self.inner.description_context()
+    }
+
+    fn description(&self) -> Option<String> {
+        self.inner.description()
+    }
+
+    fn tags(&self) -> Option<String> {
+        self.inner.tags()
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        self.inner.fp_text_elements()
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        let mut elements = Vec::new();
+        let mut replacement = Some(self.replacement.clone());
+        for element in self.inner.graphic_elements() {
+            if element.role.as_ref() != Some(&self.role) {
+                elements.push(element);
+            } else if let Some(replacement) = replacement.take() {
+                elements.extend(replacement);
+            }
+        }
+        elements.extend(replacement.into_iter().flatten());
+        elements
+    }
+
+    fn model_3d(&self) -> Option<Model3D> {
+        self.inner.model_3d()
+    }
+
+    /// The inner source while nothing is replaced; a replaced graphic can't be spliced into
+    /// it, so the footprint is then written from the model
+    fn kicad_source(&self) -> Option<String> {
+        if !self.replaces_any() && self.replacement.is_empty() {
+            return self.inner.kicad_source();
+        }
+        self.inner.kicad_source_with(&self.fp_text_elements(), &self.graphic_elements())
+    }
+
+    fn kicad_source_with(&self, texts: &[FpText], graphics: &[GraphicElement]) -> Option<String> {
+        self.inner.kicad_source_with(texts, graphics)
+    }
+
+    fn rule_areas(&self) -> Vec<RuleArea> {
+        self.inner.rule_areas()
+    }
+
+    fn actuation_envelope(&self) -> Option<ActuationEnvelope> {
+        self.inner.actuation_envelope()
+    }
+
+    fn process(&self) -> ProcessSet {
+        self.inner.process()
+    }
+
+    fn courtyard_margin(&self) -> f32 {
+        self.inner.courtyard_margin()
+    }
+
+    fn generate_courtyard(&self) -> Courtyard {
+        self.inner.generate_courtyard()
+    }
+
+    fn generate_courtyards(&self) -> Vec<Courtyard> {
+        self.inner.generate_courtyards()
+    }
+
+    fn anchor(&self) -> Anchor {
+        self.inner.anchor()
+    }
+}
+
+impl Board {
+    /// Replace the graphics in `role` of the part placed as `reference`, leaving every
+    /// other part alone, even ones sharing its definition. False when no part has that
+    /// reference.
+    pub fn replace_role(&mut self, reference: &str, role: ElementRole, replacement: Vec<GraphicElement>) -> bool {
+        let Some(placed) = self.component_mut(reference) else {
+            return false;
+        };
+        placed.component = Rc::new(RoleReplaced::new(Rc::clone(&placed.component), role, replacement));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_interface::GraphicType;
+    use crate::chip::ChipFootprint;
+    use crate::layer_type::LayerType;
+    use crate::pin1::Pin1Style;
+    use crate::soic::Soic;
+
+    /// The elements without their uuids, which are fresh on every call
+    fn drawn(elements: &[GraphicElement]) -> Vec<GraphicElement> {
+        elements.iter().map(|element| element.clone().with_uuid(uuid::Uuid::nil())).collect()
+    }
+
+    fn dot(center: (f32, f32)) -> GraphicElement {
+        GraphicElement::new(GraphicType::Circle { center, radius: 0.2 }, LayerType::SilkScreen, 0.2)
+    }
+
+    #[test]
+    fn generators_tag_outlines_and_markers() {
+        let soic = Soic::from_jedec("MS-012-AA", 8).unwrap().with_pin1(Pin1Style::Dot);
+        assert_eq!(soic.graphics_with_role(&ElementRole::FabOutline(None)).len(), 1);
+        assert_eq!(soic.graphics_with_role(&ElementRole::Pin1Marker).len(), 1);
+        assert_eq!(soic.graphics_with_role(&ElementRole::SilkOutline(Some(Edge::Top))).len(), 1);
+        let chip = ChipFootprint::standard(FunctionalType::Resistor("10k".to_string()), "0805").unwrap();
+        assert_eq!(chip.graphics_with_role(&ElementRole::FabOutline(None)).len(), 1);
+        assert!(chip.graphics_with_role(&ElementRole::Pin1Marker).is_empty());
+        assert_eq!(ElementRole::FabOutline(Some(Edge::Left)).to_string(), "fab outline, Left edge");
+    }
+
+    #[test]
+    fn replacing_a_role_keeps_the_other_elements_in_place() {
+        let soic = Soic::from_jedec("MS-012-AA", 8).unwrap().with_pin1(Pin1Style::Dot);
+        let before = drawn(&soic.graphic_elements());
+        let marker = before.iter().position(|element| element.role == Some(ElementRole::Pin1Marker)).unwrap();
+        let replaced = soic.replace_role(ElementRole::Pin1Marker, vec![dot((-3.0, -2.0))]);
+        let after = drawn(&replaced.graphic_elements());
+        assert_eq!(after.len(), before.len());
+        for (index, (old, new)) in before.iter().zip(&after).enumerate() {
+            assert_eq!(old == new, index != marker, "element {}", index);
+        }
+        assert_eq!(after[marker].role, Some(ElementRole::Pin1Marker));
+        assert_eq!(after[marker].element_type, GraphicType::Circle { center: (-3.0, -2.0), radius: 0.2 });
+
+        // Nothing in the role: the replacement is drawn last
+        let chip = ChipFootprint::standard(FunctionalType::Resistor("10k".to_string()), "0805").unwrap();
+        let count = chip.graphic_elements().len();
+        let banded = chip.replace_role(ElementRole::PolarityBand, vec![dot((0.0, 0.0))]);
+        assert_eq!(banded.graphic_elements().len(), count + 1);
+        assert_eq!(banded.graphics_with_role(&ElementRole::PolarityBand).len(), 1);
+    }
+
+    #[test]
+    fn replacing_one_parts_marker_leaves_the_other_part_alone() {
+        let soic = Soic::from_jedec("MS-012-AA", 8).unwrap().with_pin1(Pin1Style::Dot);
+        let shared: Rc<dyn BoardComposableObject> = Rc::new(soic);
+        let mut board = Board::new("roles");
+        board.place_shared("U1", Rc::clone(&shared), (10.0, 10.0), 0.0, crate::layer_type::Side::Front).unwrap();
+        board.place_shared("U2", Rc::clone(&shared), (20.0, 10.0), 0.0, crate::layer_type::Side::Front).unwrap();
+        let graphics = |board: &Board, reference: &str| drawn(&board.component(reference).unwrap().component.graphic_elements());
+        let (u1, u2) = (graphics(&board, "U1"), graphics(&board, "U2"));
+
+        assert!(board.replace_role("U2", ElementRole::Pin1Marker, vec![dot((-3.0, -2.0))]));
+        assert!(!board.replace_role("U3", ElementRole::Pin1Marker, Vec::new()));
+        assert_eq!(graphics(&board, "U1"), u1);
+        let changed: Vec<usize> = (0..u2.len()).filter(|&index| graphics(&board, "U2")[index] != u2[index]).collect();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(u2[changed[0]].role, Some(ElementRole::Pin1Marker));
+    }
+}
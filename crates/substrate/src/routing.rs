@@ -0,0 +1,1219 @@
+//! Tracks, vias, net classes and routing DRC
+//!
+//! The board carries routed copper as plain data: straight tracks and arc tracks on one
+//! copper layer and vias, each on a named net. Arcs are kept in KiCad's three point form,
+//! start, mid and end. Vias are through vias unless given a blind, buried or
+//! micro-via span between two copper layers. Pads join nets through `Board::connect_pad`.
+//! Net classes set the clearance, minimum track width and minimum via size of their nets;
+//! nets without a class use the default class. Nets join a class by name or by a wildcard
+//! pattern, names first. Clearance rules, like KiCad's custom rules, replace the class
+//! clearance between the nets they match; the last matching rule wins.
+//!
+//! `Board::routing_violations` checks the routed copper against the classes: track width
+//! and via size, and copper to copper clearance between items on different nets (track to
+//! track, track to pad, via to track, via to via and via to pad; arcs like tracks). Pad to pad spacing is a
+//! property of the footprint and is left to `analysis::spacing`. Candidate pairs come from
+//! a spatial index per copper layer, and distances are exact edge to edge distances
+//! between track capsules, arcs and tessellated pad outlines. Arc to arc distances go
+//! through one arc's chords, within `ROUTING_ARC_TOLERANCE` of the true distance.
+//!
+//! Tracks, arcs and vias are also checked against the rule areas footprints carry (see
+//! `rule_area`): an item on a layer the area covers may not reach into it when the area
+//! keeps that kind of item out.
+//!
+//! Via spans are checked against the board's copper layers and its `ViaRules`: spans must
+//! end on copper layers the stackup has, blind vias run from an outer layer to an inner
+//! one, buried vias stay off the outer layers, and micro-vias cross a single dielectric
+//! (two when stacked micro-vias are allowed) from an outer layer (any layer pair with
+//! any-layer HDI).
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::findings::{ElementRef, Severity, Violation};
+use crate::backdrill::Backdrill;
+use crate::board::Board;
+use crate::board_interface::{Rectangle, TentingSettings, TentingType};
+use crate::footprint::{sorted_map, sorted_shared_map};
+use crate::geometry::{
+    point_in_polygon, point_segment_distance, segment_arc_distance, segments_intersect, CircularArc, Point,
+};
+use crate::layer_type::{BoardLayer, LayerType, Side};
+use crate::spatial_index::{SpatialIndex, BOARD_INDEX_CELL_SIZE};
+
+/// Arc tolerance when tessellating pads for clearance checks (1 micron)
+pub const ROUTING_ARC_TOLERANCE: f32 = 0.001;
+
+/// Slack on required values so exact-minimum designs pass despite float rounding
+const DRC_EPSILON: f32 = 1e-4;
+
+/// Design rules shared by a group of nets, all in millimeters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetClass {
+    pub name: String,
+    /// Minimum copper to copper distance to items on other nets
+    pub clearance: f32,
+    pub track_width: f32,
+    pub via_diameter: f32,
+    pub via_drill: f32,
+}
+
+impl NetClass {
+    pub fn new(name: &str, clearance: f32, track_width: f32, via_diameter: f32, via_drill: f32) -> Self {
+        Self { name: name.to_string(), clearance, track_width, via_diameter, via_drill }
+    }
+}
+
+impl Default for NetClass {
+    /// KiCad's "Default" class
+    fn default() -> Self {
+        Self::new("Default", 0.2, 0.2, 0.6, 0.3)
+    }
+}
+
+/// The nets a clearance rule applies to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum NetCondition {
+    Any,
+    /// Nets in the class of this name
+    Class(String),
+    Net(String),
+}
+
+/// Clearance between two kinds of net replacing the larger of their class clearances
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClearanceRule {
+    pub name: String,
+    /// What each of the two items must be on, in either order
+    pub between: (NetCondition, NetCondition),
+    pub clearance: f32,
+}
+
+impl ClearanceRule {
+    pub fn new(name: &str, a: NetCondition, b: NetCondition, clearance: f32) -> Self {
+        Self { name: name.to_string(), between: (a, b), clearance }
+    }
+}
+
+/// A straight track segment in board coordinates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Track {
+    pub net: String,
+    pub layer: BoardLayer,
+    pub start: Point,
+    pub end: Point,
+    pub width: f32,
+}
+
+impl Track {
+    pub fn new(net: &str, layer: BoardLayer, start: Point, end: Point, width: f32) -> Self {
+        Self { net: net.to_string(), layer, start, end, width }
+    }
+}
+
+/// A curved track in board coordinates, from `start` through `mid` to `end` on one circle,
+/// as KiCad stores arc tracks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackArc {
+    pub net: String,
+    pub layer: BoardLayer,
+    pub start: Point,
+    pub mid: Point,
+    pub end: Point,
+    pub width: f32,
+}
+
+impl TrackArc {
+    pub fn new(net: &str, layer: BoardLayer, start: Point, mid: Point, end: Point, width: f32) -> Self {
+        Self { net: net.to_string(), layer, start, mid, end, width }
+    }
+
+    /// The circle the arc runs on, `None` when its points are collinear and it is straight
+    pub fn geometry(&self) -> Option<CircularArc> {
+        CircularArc::through(self.start, self.mid, self.end)
+    }
+
+    /// Length along the arc, or along `start`-`mid`-`end` for a straight one
+    pub fn length(&self) -> f32 {
+        match self.geometry() {
+            Some(arc) => arc.length(),
+            None => self.start.distance_to(&self.mid) + self.mid.distance_to(&self.end),
+        }
+    }
+
+    /// The arc as straight pieces, none further than `tolerance` from it
+    pub fn chords(&self, tolerance: f32) -> Vec<(Point, Point)> {
+        let points = match self.geometry() {
+            Some(arc) => arc.points(tolerance),
+            None => vec![self.start, self.mid, self.end],
+        };
+        points.windows(2).map(|pair| (pair[0], pair[1])).collect()
+    }
+}
+
+/// How a via is drilled, which decides the layer spans it may have
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ViaKind {
+    /// Drilled through the board, on every copper layer
+    #[default]
+    Through,
+    /// From an outer layer to an inner one
+    Blind,
+    /// Between inner layers
+    Buried,
+    /// Laser drilled through one dielectric
+    Micro,
+}
+
+impl fmt::Display for ViaKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ViaKind::Through => "through",
+            ViaKind::Blind => "blind",
+            ViaKind::Buried => "buried",
+            ViaKind::Micro => "micro",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Via {
+    pub net: String,
+    pub position: Point,
+    pub diameter: f32,
+    pub drill: f32,
+    pub kind: ViaKind,
+    /// Top and bottom copper layer of the span
+    pub layers: (BoardLayer, BoardLayer),
+    /// Stub drilled out from one side, see `backdrill`
+    pub backdrill: Option<Backdrill>,
+    /// Solder mask over each side; vias are tented unless opened, as in KiCad
+    #[serde(default = "tented_by_default")]
+    pub tenting: TentingSettings,
+}
+
+fn tented_by_default() -> TentingSettings {
+    TentingSettings { front: TentingType::Full, back: TentingType::Full }
+}
+
+impl Via {
+    /// A through via, from F.Cu to B.Cu
+    pub fn new(net: &str, position: Point, diameter: f32, drill: f32) -> Self {
+        Self {
+            net: net.to_string(),
+            position,
+            diameter,
+            drill,
+            kind: ViaKind::Through,
+            layers: (BoardLayer::front(LayerType::Copper), BoardLayer::back(LayerType::Copper)),
+            backdrill: None,
+            tenting: tented_by_default(),
+        }
+    }
+
+    /// The same via as a `kind` via between two copper layers, given in either order
+    pub fn with_span(mut self, kind: ViaKind, from: BoardLayer, to: BoardLayer) -> Self {
+        self.kind = kind;
+        self.layers = if layer_order(&from) <= layer_order(&to) { (from, to) } else { (to, from) };
+        self
+    }
+
+    pub fn with_backdrill(mut self, backdrill: Backdrill) -> Self {
+        self.backdrill = Some(backdrill);
+        self
+    }
+
+    pub fn with_tenting(mut self, tenting: TentingSettings) -> Self {
+        self.tenting = tenting;
+        self
+    }
+
+    /// Whether the via's copper is bare on the outer `side`, for a probe or a test clip:
+    /// it reaches that outer layer and the mask leaves it open
+    pub fn is_exposed(&self, side: Side) -> bool {
+        let tenting = match side {
+            Side::Front => &self.tenting.front,
+            Side::Back => &self.tenting.back,
+            Side::Inner(_) => return false,
+        };
+        *tenting == TentingType::None && self.spans(&BoardLayer::new(LayerType::Copper, side))
+    }
+
+    /// Whether the via has copper on `layer`
+    pub fn spans(&self, layer: &BoardLayer) -> bool {
+        layer.layer == LayerType::Copper
+            && (layer_order(&self.layers.0)..=layer_order(&self.layers.1)).contains(&layer_order(layer))
+    }
+}
+
+/// Via spans the fab can build, see the module documentation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ViaRules {
+    /// Micro-vias may cross two dielectrics, as two micro-vias stacked on each other
+    pub allow_stacked_microvias: bool,
+    /// Any-layer HDI: micro-vias may join any two neighbouring copper layers, not only an
+    /// outer layer and the one below it
+    pub allow_any_layer: bool,
+}
+
+impl ViaRules {
+    pub fn with_stacked_microvias(mut self) -> Self {
+        self.allow_stacked_microvias = true;
+        self
+    }
+
+    pub fn with_any_layer(mut self) -> Self {
+        self.allow_any_layer = true;
+        self
+    }
+}
+
+/// What is wrong with a via's layer span
+#[derive(Debug, Clone, PartialEq)]
+pub enum ViaSpanIssue {
+    /// A span end the board has no copper layer for
+    UnknownLayer(BoardLayer),
+    /// The span starts and ends on the same layer
+    SingleLayer,
+    /// A through via that doesn't run from F.Cu to B.Cu
+    ThroughNotOuter,
+    /// A blind via that doesn't run from an outer layer to an inner one
+    BlindNotOuter,
+    /// A buried via ending on an outer layer
+    BuriedTouchesOuter,
+    /// A micro-via crossing more dielectrics than allowed
+    MicroViaTooDeep { dielectrics: usize, allowed: usize },
+    /// A micro-via between inner layers without any-layer HDI
+    MicroViaInner,
+}
+
+impl fmt::Display for ViaSpanIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ViaSpanIssue::UnknownLayer(layer) => write!(f, "{} is not a copper layer of the board", layer.to_kicad_string()),
+            ViaSpanIssue::SingleLayer => write!(f, "starts and ends on the same layer"),
+            ViaSpanIssue::ThroughNotOuter => write!(f, "through vias must run from F.Cu to B.Cu"),
+            ViaSpanIssue::BlindNotOuter => write!(f, "blind vias must run from an outer layer to an inner one"),
+            ViaSpanIssue::BuriedTouchesOuter => write!(f, "buried vias must not reach an outer layer"),
+            ViaSpanIssue::MicroViaTooDeep { dielectrics, allowed } => {
+                write!(f, "crosses {} dielectrics, micro-vias may cross {}", dielectrics, allowed)
+            }
+            ViaSpanIssue::MicroViaInner => write!(f, "micro-vias must start on an outer layer without any-layer HDI"),
+        }
+    }
+}
+
+/// Routed copper and net assignments of a board
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Routing {
+    /// Tracks, vias and pad nets are shared with snapshots until changed, see `snapshot`
+    pub(crate) tracks: Rc<Vec<Track>>,
+    pub(crate) arcs: Rc<Vec<TrackArc>>,
+    pub(crate) vias: Rc<Vec<Via>>,
+    default_class: NetClass,
+    classes: BTreeMap<String, NetClass>,
+    via_rules: ViaRules,
+    /// Net name to class name
+    #[serde(serialize_with = "sorted_map")]
+    net_classes: HashMap<String, String>,
+    /// Wildcard net name pattern and class name, for nets not assigned by name
+    net_class_patterns: Vec<(String, String)>,
+    clearance_rules: Vec<ClearanceRule>,
+    /// "reference.number" to net name
+    #[serde(serialize_with = "sorted_shared_map")]
+    pub(crate) pad_nets: Rc<HashMap<String, String>>,
+}
+
+impl Routing {
+    /// Whether the net classes and via rules are the same, whatever is routed
+    pub(crate) fn same_rules(&self, other: &Routing) -> bool {
+        self.default_class == other.default_class
+            && self.classes == other.classes
+            && self.net_classes == other.net_classes
+            && self.net_class_patterns == other.net_class_patterns
+            && self.clearance_rules == other.clearance_rules
+            && self.via_rules == other.via_rules
+    }
+}
+
+/// A copper item taking part in a routing check
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RoutingItem {
+    /// Index into `Board::tracks`
+    Track(usize),
+    /// Index into `Board::arcs`
+    Arc(usize),
+    /// Index into `Board::vias`
+    Via(usize),
+    /// Pad named "reference.number"
+    Pad(String),
+}
+
+impl RoutingItem {
+    pub fn element_ref(&self) -> ElementRef {
+        match self {
+            RoutingItem::Track(index) => ElementRef::Track(*index),
+            RoutingItem::Arc(index) => ElementRef::Arc(*index),
+            RoutingItem::Via(index) => ElementRef::Via(*index),
+            RoutingItem::Pad(name) => ElementRef::BoardPad(name.clone()),
+        }
+    }
+}
+
+impl fmt::Display for RoutingItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoutingItem::Track(index) => write!(f, "track {}", index),
+            RoutingItem::Arc(index) => write!(f, "arc {}", index),
+            RoutingItem::Via(index) => write!(f, "via {}", index),
+            RoutingItem::Pad(name) => write!(f, "pad {}", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RoutingViolation {
+    /// Two items on different nets closer than their class clearances or a clearance rule allow
+    Clearance {
+        a: RoutingItem,
+        b: RoutingItem,
+        /// Net of each item, `None` for a pad on no net
+        net_a: Option<String>,
+        net_b: Option<String>,
+        layer: BoardLayer,
+        required: f32,
+        actual: f32,
+        /// Midpoint between the closest points of the two items
+        location: Point,
+    },
+    TrackWidth { track: usize, net: String, required: f32, actual: f32, location: Point },
+    /// An arc track narrower than its class allows, checked like `TrackWidth`
+    ArcWidth { arc: usize, net: String, required: f32, actual: f32, location: Point },
+    ViaDiameter { via: usize, net: String, required: f32, actual: f32, location: Point },
+    ViaDrill { via: usize, net: String, required: f32, actual: f32, location: Point },
+    ViaSpan { via: usize, net: String, kind: ViaKind, layers: (BoardLayer, BoardLayer), issue: ViaSpanIssue, location: Point },
+    /// A track or via reaching into a footprint rule area that keeps it out
+    Keepout { item: RoutingItem, net: String, reference: String, area: String, layer: BoardLayer, location: Point },
+}
+
+impl RoutingViolation {
+    pub fn location(&self) -> Point {
+        match self {
+            RoutingViolation::Clearance { location, .. }
+            | RoutingViolation::TrackWidth { location, .. }
+            | RoutingViolation::ArcWidth { location, .. }
+            | RoutingViolation::ViaDiameter { location, .. }
+            | RoutingViolation::ViaDrill { location, .. }
+            | RoutingViolation::ViaSpan { location, .. }
+            | RoutingViolation::Keepout { location, .. } => *location,
+        }
+    }
+
+    /// The required value in millimeters, `None` for via spans and keepouts
+    pub fn required(&self) -> Option<f32> {
+        match self {
+            RoutingViolation::Clearance { required, .. }
+            | RoutingViolation::TrackWidth { required, .. }
+            | RoutingViolation::ArcWidth { required, .. }
+            | RoutingViolation::ViaDiameter { required, .. }
+            | RoutingViolation::ViaDrill { required, .. } => Some(*required),
+            RoutingViolation::ViaSpan { .. } | RoutingViolation::Keepout { .. } => None,
+        }
+    }
+
+    /// The measured value in millimeters, `None` for via spans and keepouts
+    pub fn actual(&self) -> Option<f32> {
+        match self {
+            RoutingViolation::Clearance { actual, .. }
+            | RoutingViolation::TrackWidth { actual, .. }
+            | RoutingViolation::ArcWidth { actual, .. }
+            | RoutingViolation::ViaDiameter { actual, .. }
+            | RoutingViolation::ViaDrill { actual, .. } => Some(*actual),
+            RoutingViolation::ViaSpan { .. } | RoutingViolation::Keepout { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for RoutingViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let net = |net: &Option<String>| net.clone().unwrap_or_else(|| "no net".to_string());
+        match self {
+            RoutingViolation::Clearance { a, b, net_a, net_b, layer, required, actual, location } => write!(
+                f,
+                "{}: {} ({}) is {:.3} mm from {} ({}), {:.3} mm required, at ({:.3}, {:.3})",
+                layer.to_kicad_string(),
+                a,
+                net(net_a),
+                actual,
+                b,
+                net(net_b),
+                required,
+                location.x,
+                location.y
+            ),
+            RoutingViolation::TrackWidth { track, net, required, actual, .. } => {
+                write!(f, "track {} ({}) is {:.3} mm wide, {:.3} mm required", track, net, actual, required)
+            }
+            RoutingViolation::ArcWidth { arc, net, required, actual, .. } => {
+                write!(f, "arc {} ({}) is {:.3} mm wide, {:.3} mm required", arc, net, actual, required)
+            }
+            RoutingViolation::ViaDiameter { via, net, required, actual, .. } => {
+                write!(f, "via {} ({}) diameter is {:.3} mm, {:.3} mm required", via, net, actual, required)
+            }
+            RoutingViolation::ViaDrill { via, net, required, actual, .. } => {
+                write!(f, "via {} ({}) drill is {:.3} mm, {:.3} mm required", via, net, actual, required)
+            }
+            RoutingViolation::ViaSpan { via, net, kind, layers, issue, .. } => write!(
+                f,
+                "{} via {} ({}) from {} to {}: {}",
+                kind,
+                via,
+                net,
+                layers.0.to_kicad_string(),
+                layers.1.to_kicad_string(),
+                issue
+            ),
+            RoutingViolation::Keepout { item, net, reference, area, layer, location } => {
+                let area = if area.is_empty() { String::new() } else { format!(" \"{}\"", area) };
+                write!(
+                    f,
+                    "{}: {} ({}) inside keepout{} of {}, at ({:.3}, {:.3})",
+                    layer.to_kicad_string(),
+                    item,
+                    net,
+                    area,
+                    reference,
+                    location.x,
+                    location.y
+                )
+            }
+        }
+    }
+}
+
+impl Violation for RoutingViolation {
+    fn rule(&self) -> &'static str {
+        match self {
+            RoutingViolation::Clearance { .. } => "copper_clearance",
+            RoutingViolation::TrackWidth { .. } | RoutingViolation::ArcWidth { .. } => "track_width",
+            RoutingViolation::ViaDiameter { .. } => "via_diameter",
+            RoutingViolation::ViaDrill { .. } => "via_drill",
+            RoutingViolation::ViaSpan { .. } => "via_span",
+            RoutingViolation::Keepout { .. } => "footprint_keepout",
+        }
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    /// The component of a pad or keepout involved, otherwise "board"
+    fn footprint(&self) -> String {
+        match self {
+            RoutingViolation::Keepout { reference, .. } => reference.clone(),
+            RoutingViolation::Clearance { a: RoutingItem::Pad(pad), .. }
+            | RoutingViolation::Clearance { b: RoutingItem::Pad(pad), .. } => {
+                pad.rsplit_once('.').map_or(pad.as_str(), |(reference, _)| reference).to_string()
+            }
+            _ => "board".to_string(),
+        }
+    }
+
+    fn element(&self) -> Option<String> {
+        match self {
+            RoutingViolation::Clearance { a, b, .. } => Some(format!("{} / {}", a, b)),
+            RoutingViolation::TrackWidth { track, .. } => Some(RoutingItem::Track(*track).to_string()),
+            RoutingViolation::ArcWidth { arc, .. } => Some(RoutingItem::Arc(*arc).to_string()),
+            RoutingViolation::ViaDiameter { via, .. }
+            | RoutingViolation::ViaDrill { via, .. }
+            | RoutingViolation::ViaSpan { via, .. } => Some(RoutingItem::Via(*via).to_string()),
+            RoutingViolation::Keepout { item, .. } => Some(item.to_string()),
+        }
+    }
+
+    fn location(&self) -> Option<Point> {
+        Some(RoutingViolation::location(self))
+    }
+
+    fn elements(&self) -> Vec<ElementRef> {
+        match self {
+            RoutingViolation::Clearance { a, b, .. } => vec![a.element_ref(), b.element_ref()],
+            RoutingViolation::TrackWidth { track, .. } => vec![ElementRef::Track(*track)],
+            RoutingViolation::ArcWidth { arc, .. } => vec![ElementRef::Arc(*arc)],
+            RoutingViolation::ViaDiameter { via, .. }
+            | RoutingViolation::ViaDrill { via, .. }
+            | RoutingViolation::ViaSpan { via, .. } => vec![ElementRef::Via(*via)],
+            RoutingViolation::Keepout { item, reference, .. } => vec![item.element_ref(), ElementRef::Component(reference.clone())],
+        }
+    }
+}
+
+/// Copper of one item on one layer: a capsule (track, or via with `start == end`), an arc
+/// `radius` either side of its centerline (arc track) or a closed polygon (pad)
+#[derive(Debug, Clone)]
+enum CopperShape {
+    Capsule { start: Point, end: Point, radius: f32 },
+    Arc { arc: CircularArc, radius: f32 },
+    Polygon(Vec<Point>),
+}
+
+impl CopperShape {
+    fn bounds(&self) -> Rectangle {
+        match self {
+            CopperShape::Capsule { start, end, radius } => Rectangle {
+                min_x: start.x.min(end.x) - radius,
+                min_y: start.y.min(end.y) - radius,
+                max_x: start.x.max(end.x) + radius,
+                max_y: start.y.max(end.y) + radius,
+            },
+            CopperShape::Arc { arc, radius } => {
+                let bounds = arc.bounds();
+                Rectangle {
+                    min_x: bounds.min_x - radius,
+                    min_y: bounds.min_y - radius,
+                    max_x: bounds.max_x + radius,
+                    max_y: bounds.max_y + radius,
+                }
+            }
+            CopperShape::Polygon(points) => Rectangle::enclosing(points).unwrap_or(Rectangle {
+                min_x: 0.0,
+                min_y: 0.0,
+                max_x: 0.0,
+                max_y: 0.0,
+            }),
+        }
+    }
+}
+
+/// Closest points of two segments and their distance
+fn segment_closest_points(a1: Point, a2: Point, b1: Point, b2: Point) -> (f32, Point, Point) {
+    if segments_intersect(a1, a2, b1, b2) {
+        // Crossing or touching; any point of the overlap will do as the location
+        let (_, on_b) = point_segment_distance(a1, b1, b2);
+        let (_, on_a) = point_segment_distance(on_b, a1, a2);
+        return (0.0, on_a, on_a);
+    }
+    let from_a = |p: Point| {
+        let (distance, closest) = point_segment_distance(p, b1, b2);
+        (distance, p, closest)
+    };
+    let from_b = |p: Point| {
+        let (distance, closest) = point_segment_distance(p, a1, a2);
+        (distance, closest, p)
+    };
+    let candidates = [from_a(a1), from_a(a2), from_b(b1), from_b(b2)];
+    candidates.into_iter().min_by(|x, y| x.0.total_cmp(&y.0)).unwrap()
+}
+
+/// Edge to edge distance between two copper shapes and the midpoint of the closest points
+fn copper_distance(a: &CopperShape, b: &CopperShape) -> (f32, Point) {
+    let midpoint = |p: Point, q: Point| Point::new((p.x + q.x) / 2.0, (p.y + q.y) / 2.0);
+    match (a, b) {
+        (CopperShape::Capsule { start: a1, end: a2, radius: ra }, CopperShape::Capsule { start: b1, end: b2, radius: rb }) => {
+            let (distance, p, q) = segment_closest_points(*a1, *a2, *b1, *b2);
+            ((distance - ra - rb).max(0.0), midpoint(p, q))
+        }
+        (CopperShape::Capsule { start, end, radius }, CopperShape::Polygon(polygon))
+        | (CopperShape::Polygon(polygon), CopperShape::Capsule { start, end, radius }) => {
+            if point_in_polygon(*start, polygon) {
+                return (0.0, *start);
+            }
+            let mut best = (f32::INFINITY, *start);
+            for i in 0..polygon.len() {
+                let (p1, p2) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+                let (distance, p, q) = segment_closest_points(*start, *end, p1, p2);
+                if distance < best.0 {
+                    best = (distance, midpoint(p, q));
+                }
+            }
+            ((best.0 - radius).max(0.0), best.1)
+        }
+        (CopperShape::Capsule { start, end, radius: rc }, CopperShape::Arc { arc, radius: ra })
+        | (CopperShape::Arc { arc, radius: ra }, CopperShape::Capsule { start, end, radius: rc }) => {
+            let (distance, p, q) = segment_arc_distance(*start, *end, arc);
+            ((distance - rc - ra).max(0.0), midpoint(p, q))
+        }
+        (CopperShape::Arc { arc: a, radius: ra }, CopperShape::Arc { arc: b, radius: rb }) => {
+            // Chords of the first against the exact second, off by at most the tolerance
+            let points = a.points(ROUTING_ARC_TOLERANCE);
+            let closest = points
+                .windows(2)
+                .map(|pair| segment_arc_distance(pair[0], pair[1], b))
+                .min_by(|x, y| x.0.total_cmp(&y.0))
+                .unwrap();
+            ((closest.0 - ra - rb).max(0.0), midpoint(closest.1, closest.2))
+        }
+        (CopperShape::Arc { arc, radius }, CopperShape::Polygon(polygon))
+        | (CopperShape::Polygon(polygon), CopperShape::Arc { arc, radius }) => {
+            if point_in_polygon(arc.start(), polygon) {
+                return (0.0, arc.start());
+            }
+            let mut best = (f32::INFINITY, arc.start());
+            for i in 0..polygon.len() {
+                let (distance, p, q) = segment_arc_distance(polygon[i], polygon[(i + 1) % polygon.len()], arc);
+                if distance < best.0 {
+                    best = (distance, midpoint(p, q));
+                }
+            }
+            ((best.0 - radius).max(0.0), best.1)
+        }
+        (CopperShape::Polygon(_), CopperShape::Polygon(_)) => (f32::INFINITY, Point::new(0.0, 0.0)),
+    }
+}
+
+/// Where a capsule reaches into a closed polygon, `None` when it stays outside or only
+/// touches the edge
+fn capsule_in_polygon(start: Point, end: Point, radius: f32, polygon: &[Point]) -> Option<Point> {
+    if polygon.len() < 3 {
+        return None;
+    }
+    if let Some(inside) = [start, end].into_iter().find(|point| point_in_polygon(*point, polygon)) {
+        return Some(inside);
+    }
+    (0..polygon.len())
+        .map(|i| segment_closest_points(start, end, polygon[i], polygon[(i + 1) % polygon.len()]))
+        .filter(|(distance, ..)| *distance < radius - DRC_EPSILON)
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, _, on_edge)| on_edge)
+}
+
+/// Whether `text` matches `pattern`, where `*` is any run of characters and `?` any one
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let (pattern, text): (Vec<char>, Vec<char>) = (pattern.chars().collect(), text.chars().collect());
+    // Backtrack to the last star when a literal fails to match
+    let (mut p, mut t, mut star) = (0, 0, None);
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Sort key putting copper layers in stackup order
+fn layer_order(layer: &BoardLayer) -> u16 {
+    match layer.side {
+        Side::Front => 0,
+        Side::Inner(index) => index as u16,
+        Side::Back => u16::MAX,
+    }
+}
+
+impl Board {
+    pub fn add_track(&mut self, track: Track) {
+        Rc::make_mut(&mut self.routing.tracks).push(track);
+    }
+
+    pub fn add_arc(&mut self, arc: TrackArc) {
+        Rc::make_mut(&mut self.routing.arcs).push(arc);
+    }
+
+    pub fn add_via(&mut self, via: Via) {
+        Rc::make_mut(&mut self.routing.vias).push(via);
+    }
+
+    pub fn tracks(&self) -> &[Track] {
+        &self.routing.tracks
+    }
+
+    pub fn arcs(&self) -> &[TrackArc] {
+        &self.routing.arcs
+    }
+
+    pub fn vias(&self) -> &[Via] {
+        &self.routing.vias
+    }
+
+    /// Rules for nets that are not assigned a class
+    pub fn set_default_net_class(&mut self, class: NetClass) {
+        self.routing.default_class = class;
+    }
+
+    /// Add or replace a class, by name
+    pub fn add_net_class(&mut self, class: NetClass) {
+        self.routing.classes.insert(class.name.clone(), class);
+    }
+
+    /// Put `net` in the class named `class`; unknown class names fall back to the default
+    pub fn assign_net_class(&mut self, net: &str, class: &str) {
+        self.routing.net_classes.insert(net.to_string(), class.to_string());
+    }
+
+    /// Put every net whose name matches `pattern` (`*` and `?` wildcards) in the class
+    /// named `class`, unless it is assigned by name
+    pub fn assign_net_class_pattern(&mut self, pattern: &str, class: &str) {
+        self.routing.net_class_patterns.push((pattern.to_string(), class.to_string()));
+    }
+
+    /// The class whose rules apply to `net`
+    pub fn net_class(&self, net: &str) -> &NetClass {
+        self.routing
+            .net_classes
+            .get(net)
+            .or_else(|| {
+                let mut patterns = self.routing.net_class_patterns.iter();
+                patterns.find(|(pattern, _)| wildcard_match(pattern, net)).map(|(_, class)| class)
+            })
+            .and_then(|class| self.routing.classes.get(class))
+            .unwrap_or(&self.routing.default_class)
+    }
+
+    pub fn default_net_class(&self) -> &NetClass {
+        &self.routing.default_class
+    }
+
+    /// Classes added with `add_net_class`, by name
+    pub fn net_classes(&self) -> impl Iterator<Item = &NetClass> {
+        self.routing.classes.values()
+    }
+
+    /// Add a rule after the existing ones, so it wins over them where both match
+    pub fn add_clearance_rule(&mut self, rule: ClearanceRule) {
+        self.routing.clearance_rules.push(rule);
+    }
+
+    pub fn clearance_rules(&self) -> &[ClearanceRule] {
+        &self.routing.clearance_rules
+    }
+
+    /// Every net of the board's pads, tracks, arcs and vias
+    pub fn nets(&self) -> BTreeSet<&str> {
+        self.routing
+            .pad_nets
+            .values()
+            .map(String::as_str)
+            .chain(self.routing.tracks.iter().map(|track| track.net.as_str()))
+            .chain(self.routing.arcs.iter().map(|arc| arc.net.as_str()))
+            .chain(self.routing.vias.iter().map(|via| via.net.as_str()))
+            .collect()
+    }
+
+    /// The via spans the fab can build, checked by `routing_violations`
+    pub fn set_via_rules(&mut self, rules: ViaRules) {
+        self.routing.via_rules = rules;
+    }
+
+    pub fn via_rules(&self) -> ViaRules {
+        self.routing.via_rules
+    }
+
+    /// What is wrong with the span of `via` on this board, if anything
+    pub fn via_span_issue(&self, via: &Via) -> Option<ViaSpanIssue> {
+        let copper = self.copper_layers();
+        let index = |layer: &BoardLayer| copper.iter().position(|copper| copper == layer);
+        let (top, bottom) = via.layers;
+        let (Some(start), Some(end)) = (index(&top), index(&bottom)) else {
+            let unknown = if index(&top).is_none() { top } else { bottom };
+            return Some(ViaSpanIssue::UnknownLayer(unknown));
+        };
+        if start == end {
+            return Some(ViaSpanIssue::SingleLayer);
+        }
+        let last = copper.len() - 1;
+        let outer = |index: usize| index == 0 || index == last;
+        let rules = self.routing.via_rules;
+        match via.kind {
+            ViaKind::Through if (start, end) != (0, last) => Some(ViaSpanIssue::ThroughNotOuter),
+            ViaKind::Blind if outer(start) == outer(end) => Some(ViaSpanIssue::BlindNotOuter),
+            ViaKind::Buried if outer(start) || outer(end) => Some(ViaSpanIssue::BuriedTouchesOuter),
+            ViaKind::Micro => {
+                let allowed = if rules.allow_stacked_microvias { 2 } else { 1 };
+                if end - start > allowed {
+                    Some(ViaSpanIssue::MicroViaTooDeep { dielectrics: end - start, allowed })
+                } else if !rules.allow_any_layer && !outer(start) && !outer(end) {
+                    Some(ViaSpanIssue::MicroViaInner)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Connect pad `number` of component `reference` to `net`
+    pub fn connect_pad(&mut self, reference: &str, number: &str, net: &str) {
+        Rc::make_mut(&mut self.routing.pad_nets).insert(format!("{}.{}", reference, number), net.to_string());
+    }
+
+    pub fn pad_net(&self, reference: &str, number: &str) -> Option<&str> {
+        self.routing.pad_nets.get(&format!("{}.{}", reference, number)).map(String::as_str)
+    }
+
+    /// Rename the nets of pads, tracks, arcs, vias, zones and class assignments in one pass, so
+    /// swapping two names works; nets `renames` leaves out keep theirs
+    pub fn rename_nets(&mut self, renames: &BTreeMap<String, String>) {
+        let renamed = |net: &String| renames.get(net).cloned();
+        if self.routing.pad_nets.values().any(|net| renames.contains_key(net)) {
+            for net in Rc::make_mut(&mut self.routing.pad_nets).values_mut() {
+                *net = renamed(net).unwrap_or_else(|| net.clone());
+            }
+        }
+        if self.routing.tracks.iter().any(|track| renames.contains_key(&track.net)) {
+            for track in Rc::make_mut(&mut self.routing.tracks) {
+                track.net = renamed(&track.net).unwrap_or_else(|| track.net.clone());
+            }
+        }
+        if self.routing.arcs.iter().any(|arc| renames.contains_key(&arc.net)) {
+            for arc in Rc::make_mut(&mut self.routing.arcs) {
+                arc.net = renamed(&arc.net).unwrap_or_else(|| arc.net.clone());
+            }
+        }
+        if self.routing.vias.iter().any(|via| renames.contains_key(&via.net)) {
+            for via in Rc::make_mut(&mut self.routing.vias) {
+                via.net = renamed(&via.net).unwrap_or_else(|| via.net.clone());
+            }
+        }
+        for zone in &mut self.zones {
+            zone.net = renamed(&zone.net).unwrap_or_else(|| zone.net.clone());
+        }
+        self.routing.net_classes =
+            self.routing.net_classes.drain().map(|(net, class)| (renamed(&net).unwrap_or(net), class)).collect();
+    }
+
+    /// Clearance required between two nets: that of the last clearance rule matching them,
+    /// else the larger of their classes'
+    pub(crate) fn required_clearance(&self, a: Option<&str>, b: Option<&str>) -> f32 {
+        let class = |net: Option<&str>| net.map_or(&self.routing.default_class, |net| self.net_class(net));
+        let matches = |condition: &NetCondition, net: Option<&str>| match condition {
+            NetCondition::Any => true,
+            NetCondition::Class(name) => class(net).name == *name,
+            NetCondition::Net(name) => net == Some(name.as_str()),
+        };
+        let rule = self.routing.clearance_rules.iter().rev().find(|rule| {
+            let (first, second) = &rule.between;
+            (matches(first, a) && matches(second, b)) || (matches(first, b) && matches(second, a))
+        });
+        match rule {
+            Some(rule) => rule.clearance,
+            None => class(a).clearance.max(class(b).clearance),
+        }
+    }
+
+    /// Every track, via and clearance violation of the routed copper; see the module
+    /// documentation
+    pub fn routing_violations(&self) -> Vec<RoutingViolation> {
+        let mut violations = Vec::new();
+        for (index, track) in self.routing.tracks.iter().enumerate() {
+            let required = self.net_class(&track.net).track_width;
+            if track.width < required - DRC_EPSILON {
+                let location = Point::new((track.start.x + track.end.x) / 2.0, (track.start.y + track.end.y) / 2.0);
+                violations.push(RoutingViolation::TrackWidth { track: index, net: track.net.clone(), required, actual: track.width, location });
+            }
+        }
+        for (index, arc) in self.routing.arcs.iter().enumerate() {
+            let required = self.net_class(&arc.net).track_width;
+            if arc.width < required - DRC_EPSILON {
+                violations.push(RoutingViolation::ArcWidth { arc: index, net: arc.net.clone(), required, actual: arc.width, location: arc.mid });
+            }
+        }
+        for (index, via) in self.routing.vias.iter().enumerate() {
+            let class = self.net_class(&via.net);
+            if via.diameter < class.via_diameter - DRC_EPSILON {
+                violations.push(RoutingViolation::ViaDiameter {
+                    via: index,
+                    net: via.net.clone(),
+                    required: class.via_diameter,
+                    actual: via.diameter,
+                    location: via.position,
+                });
+            }
+            if via.drill < class.via_drill - DRC_EPSILON {
+                violations.push(RoutingViolation::ViaDrill {
+                    via: index,
+                    net: via.net.clone(),
+                    required: class.via_drill,
+                    actual: via.drill,
+                    location: via.position,
+                });
+            }
+            if let Some(issue) = self.via_span_issue(via) {
+                violations.push(RoutingViolation::ViaSpan {
+                    via: index,
+                    net: via.net.clone(),
+                    kind: via.kind,
+                    layers: via.layers,
+                    issue,
+                    location: via.position,
+                });
+            }
+        }
+        violations.extend(self.clearance_violations());
+        violations.extend(self.keepout_violations());
+        violations
+    }
+
+    /// Tracks, arcs and vias inside the rule areas of placed footprints, each reported once
+    fn keepout_violations(&self) -> Vec<RoutingViolation> {
+        let copper = self.copper_layers();
+        let mut violations = Vec::new();
+        for placed in self.components() {
+            for area in placed.component.rule_areas() {
+                let polygon: Vec<Point> = area.polygon.iter().map(|point| self.board_point(placed, Point::from(*point))).collect();
+                // Area layers are named as the footprint sees them, so back placements swap sides
+                let applies = |layer: &BoardLayer| {
+                    let local = match layer.side {
+                        Side::Front if placed.side == Side::Back => BoardLayer::back(layer.layer),
+                        Side::Back if placed.side == Side::Back => BoardLayer::front(layer.layer),
+                        _ => *layer,
+                    };
+                    area.has_layer(&local.to_kicad_string())
+                };
+                let mut report = |item: RoutingItem, net: &str, layer: BoardLayer, location: Point| {
+                    violations.push(RoutingViolation::Keepout {
+                        item,
+                        net: net.to_string(),
+                        reference: placed.reference.clone(),
+                        area: area.name.clone(),
+                        layer,
+                        location,
+                    });
+                };
+                if area.no_tracks {
+                    for (index, track) in self.routing.tracks.iter().enumerate().filter(|(_, track)| applies(&track.layer)) {
+                        if let Some(location) = capsule_in_polygon(track.start, track.end, track.width / 2.0, &polygon) {
+                            report(RoutingItem::Track(index), &track.net, track.layer, location);
+                        }
+                    }
+                    for (index, arc) in self.routing.arcs.iter().enumerate().filter(|(_, arc)| applies(&arc.layer)) {
+                        let mut chords = arc.chords(ROUTING_ARC_TOLERANCE).into_iter();
+                        if let Some(location) = chords.find_map(|(start, end)| capsule_in_polygon(start, end, arc.width / 2.0, &polygon)) {
+                            report(RoutingItem::Arc(index), &arc.net, arc.layer, location);
+                        }
+                    }
+                }
+                if area.no_vias {
+                    for (index, via) in self.routing.vias.iter().enumerate() {
+                        let Some(layer) = copper.iter().find(|layer| via.spans(layer) && applies(layer)) else { continue };
+                        if let Some(location) = capsule_in_polygon(via.position, via.position, via.diameter / 2.0, &polygon) {
+                            report(RoutingItem::Via(index), &via.net, *layer, location);
+                        }
+                    }
+                }
+            }
+        }
+        violations
+    }
+
+    /// Clearance violations on every copper layer in stackup order. An item pair touching
+    /// on several layers (a via next to a through hole pad) is reported once, on the layer
+    /// where it is closest.
+    fn clearance_violations(&self) -> Vec<RoutingViolation> {
+        let mut layers = self.copper_layers();
+        for layer in self.routing.tracks.iter().map(|track| track.layer).chain(self.routing.arcs.iter().map(|arc| arc.layer)) {
+            if !layers.contains(&layer) {
+                layers.push(layer);
+            }
+        }
+        layers.sort_by_key(layer_order);
+
+        let max_clearance = self
+            .routing
+            .classes
+            .values()
+            .chain([&self.routing.default_class])
+            .map(|class| class.clearance)
+            .chain(self.routing.clearance_rules.iter().map(|rule| rule.clearance))
+            .fold(0.0, f32::max);
+
+        let mut found: Vec<RoutingViolation> = Vec::new();
+        let mut by_pair: HashMap<(RoutingItem, RoutingItem), usize> = HashMap::new();
+        for layer in layers {
+            let mut items: Vec<(RoutingItem, Option<&str>, CopperShape)> = Vec::new();
+            for (index, track) in self.routing.tracks.iter().enumerate().filter(|(_, track)| track.layer == layer) {
+                let shape = CopperShape::Capsule { start: track.start, end: track.end, radius: track.width / 2.0 };
+                items.push((RoutingItem::Track(index), Some(track.net.as_str()), shape));
+            }
+            for (index, arc) in self.routing.arcs.iter().enumerate().filter(|(_, arc)| arc.layer == layer) {
+                let radius = arc.width / 2.0;
+                let shape = match arc.geometry() {
+                    Some(geometry) => CopperShape::Arc { arc: geometry, radius },
+                    None => CopperShape::Capsule { start: arc.start, end: arc.end, radius },
+                };
+                items.push((RoutingItem::Arc(index), Some(arc.net.as_str()), shape));
+            }
+            for (index, via) in self.routing.vias.iter().enumerate().filter(|(_, via)| via.spans(&layer)) {
+                let shape = CopperShape::Capsule { start: via.position, end: via.position, radius: via.diameter / 2.0 };
+                items.push((RoutingItem::Via(index), Some(via.net.as_str()), shape));
+            }
+            let routed = items.len();
+            if routed == 0 {
+                continue;
+            }
+            for (name, outline) in self.pad_outlines(layer, ROUTING_ARC_TOLERANCE) {
+                let net = self.routing.pad_nets.get(&name).map(String::as_str);
+                items.push((RoutingItem::Pad(name), net, CopperShape::Polygon(outline)));
+            }
+
+            let mut index = SpatialIndex::new(BOARD_INDEX_CELL_SIZE);
+            for (key, (_, _, shape)) in items.iter().enumerate() {
+                index.insert(key, shape.bounds());
+            }
+            // Pads are only checked against routed items, so queries start from those
+            for first in 0..routed {
+                let (item_a, net_a, shape_a) = &items[first];
+                let bounds = shape_a.bounds();
+                let search = Rectangle {
+                    min_x: bounds.min_x - max_clearance,
+                    min_y: bounds.min_y - max_clearance,
+                    max_x: bounds.max_x + max_clearance,
+                    max_y: bounds.max_y + max_clearance,
+                };
+                let mut candidates = index.query_rect(&search);
+                candidates.sort_unstable();
+                for second in candidates.into_iter().filter(|second| *second > first) {
+                    let (item_b, net_b, shape_b) = &items[second];
+                    if net_a.is_some() && net_a == net_b {
+                        continue;
+                    }
+                    let required = self.required_clearance(*net_a, *net_b);
+                    let (actual, location) = copper_distance(shape_a, shape_b);
+                    if actual >= required - DRC_EPSILON {
+                        continue;
+                    }
+                    let violation = RoutingViolation::Clearance {
+                        a: item_a.clone(),
+                        b: item_b.clone(),
+                        net_a: net_a.map(str::to_string),
+                        net_b: net_b.map(str::to_string),
+                        layer,
+                        required,
+                        actual,
+                        location,
+                    };
+                    match by_pair.get(&(item_a.clone(), item_b.clone())) {
+                        Some(&existing) if found[existing].actual().is_some_and(|existing| existing <= actual) => {}
+                        Some(&existing) => found[existing] = violation,
+                        None => {
+                            by_pair.insert((item_a.clone(), item_b.clone()), found.len());
+                            found.push(violation);
+                        }
+                    }
+                }
+            }
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two 0.2 mm tracks with centers 0.35 mm apart, leaving a 0.15 mm gap, the first on
+    /// F.Cu and the second on `layer_b`
+    fn tracks_on(net_a: &str, net_b: &str, layer_b: BoardLayer) -> Board {
+        let mut board = Board::new("routing");
+        board.set_default_net_class(NetClass::new("Default", 0.2, 0.2, 0.6, 0.3));
+        board.add_track(Track::new(net_a, BoardLayer::front(LayerType::Copper), Point::new(0.0, 0.0), Point::new(10.0, 0.0), 0.2));
+        board.add_track(Track::new(net_b, layer_b, Point::new(0.0, 0.35), Point::new(10.0, 0.35), 0.2));
+        board
+    }
+
+    fn parallel_tracks(net_a: &str, net_b: &str) -> Board {
+        tracks_on(net_a, net_b, BoardLayer::front(LayerType::Copper))
+    }
+
+    #[test]
+    fn tracks_too_close_on_different_nets_are_flagged() {
+        let violations = parallel_tracks("A", "B").routing_violations();
+        assert_eq!(violations.len(), 1, "{:?}", violations);
+        let RoutingViolation::Clearance { a, b, net_a, net_b, layer, required, actual, .. } = &violations[0] else {
+            panic!("not a clearance violation: {:?}", violations[0]);
+        };
+        assert_eq!((a, b), (&RoutingItem::Track(0), &RoutingItem::Track(1)));
+        assert_eq!((net_a.as_deref(), net_b.as_deref()), (Some("A"), Some("B")));
+        assert_eq!(*layer, BoardLayer::front(LayerType::Copper));
+        assert_eq!(*required, 0.2);
+        assert!((actual - 0.15).abs() < 1e-4, "{}", actual);
+    }
+
+    #[test]
+    fn renaming_swaps_nets_in_one_pass() {
+        let mut board = parallel_tracks("A", "B");
+        board.add_net_class(NetClass::new("Wide", 0.3, 0.4, 0.8, 0.4));
+        board.assign_net_class("A", "Wide");
+        board.connect_pad("R1", "1", "B");
+        board.rename_nets(&BTreeMap::from([("A".to_string(), "B".to_string()), ("B".to_string(), "A".to_string())]));
+        assert_eq!((board.tracks()[0].net.as_str(), board.tracks()[1].net.as_str()), ("B", "A"));
+        assert_eq!(board.pad_net("R1", "1"), Some("A"));
+        assert_eq!(board.net_class("B").name, "Wide");
+    }
+
+    #[test]
+    fn tracks_on_the_same_net_may_touch() {
+        assert_eq!(parallel_tracks("A", "A").routing_violations(), []);
+    }
+
+    #[test]
+    fn clearance_rule_replaces_the_class_clearance() {
+        let mut board = parallel_tracks("A", "B");
+        board.add_clearance_rule(ClearanceRule::new("tight", NetCondition::Net("A".into()), NetCondition::Any, 0.1));
+        assert_eq!(board.routing_violations(), []);
+    }
+
+    #[test]
+    fn tracks_on_other_layers_do_not_interact() {
+        assert_eq!(tracks_on("A", "B", BoardLayer::back(LayerType::Copper)).routing_violations(), []);
+    }
+
+    #[test]
+    fn narrow_track_and_small_via_are_flagged() {
+        let mut board = Board::new("routing");
+        board.set_default_net_class(NetClass::new("Default", 0.2, 0.2, 0.6, 0.3));
+        board.add_track(Track::new("A", BoardLayer::front(LayerType::Copper), Point::new(0.0, 0.0), Point::new(5.0, 0.0), 0.15));
+        board.add_via(Via::new("B", Point::new(5.0, 5.0), 0.5, 0.25));
+        let violations = board.routing_violations();
+        assert!(matches!(violations[0], RoutingViolation::TrackWidth { track: 0, required: 0.2, actual: 0.15, .. }));
+        assert!(matches!(violations[1], RoutingViolation::ViaDiameter { via: 0, required: 0.6, actual: 0.5, .. }));
+        assert!(matches!(violations[2], RoutingViolation::ViaDrill { via: 0, required: 0.3, actual: 0.25, .. }));
+        assert_eq!(violations.len(), 3);
+    }
+
+    #[test]
+    fn arcs_are_checked_against_their_true_curve() {
+        // A quarter circle of radius 5 about the origin and a track along the 45 degree
+        // tangent, 0.35 mm out, leaving a 0.15 mm gap between the 0.2 mm copper
+        let mut board = Board::new("routing");
+        board.set_default_net_class(NetClass::new("Default", 0.2, 0.2, 0.6, 0.3));
+        let front = BoardLayer::front(LayerType::Copper);
+        let diagonal = 5.0 * std::f32::consts::FRAC_1_SQRT_2;
+        board.add_arc(TrackArc::new("A", front, Point::new(5.0, 0.0), Point::new(diagonal, diagonal), Point::new(0.0, 5.0), 0.2));
+        let out = 5.35 * std::f32::consts::FRAC_1_SQRT_2;
+        board.add_track(Track::new("B", front, Point::new(out - 1.0, out + 1.0), Point::new(out + 1.0, out - 1.0), 0.2));
+        assert!((board.arcs()[0].length() - 2.5 * std::f32::consts::PI).abs() < 1e-4);
+
+        let violations = board.routing_violations();
+        assert_eq!(violations.len(), 1, "{:?}", violations);
+        let RoutingViolation::Clearance { a, b, actual, .. } = &violations[0] else {
+            panic!("not a clearance violation: {:?}", violations[0]);
+        };
+        assert_eq!((a, b), (&RoutingItem::Track(0), &RoutingItem::Arc(0)));
+        assert!((actual - 0.15).abs() < 1e-3, "{}", actual);
+
+        // The chord from end to end runs 1.46 mm inside the curve; a track reaching it stays
+        // clear of the arc
+        let mut board = Board::new("routing");
+        board.set_default_net_class(NetClass::new("Default", 0.2, 0.1, 0.6, 0.3));
+        board.add_arc(TrackArc::new("A", front, Point::new(5.0, 0.0), Point::new(diagonal, diagonal), Point::new(0.0, 5.0), 0.2));
+        board.add_track(Track::new("B", front, Point::new(2.0, 2.0), Point::new(2.5, 2.5), 0.15));
+        assert_eq!(board.routing_violations(), []);
+    }
+}
@@ -0,0 +1,230 @@
+//! Pad entry and exit hints for routers
+//!
+//! A `RoutingHint` tells an autorouter how a pad wants to be reached: the directions a
+//! track may leave it in, the copper layer to leave on and whether a via may sit in the
+//! pad. Directions are degrees counter-clockwise from +X as seen on screen (KiCad's
+//! convention) in footprint coordinates, so they turn with the footprint. A pad without
+//! exit ranges may be left in any direction.
+//!
+//! Package generators give their perimeter pads `RoutingHint::outward`: leave away from
+//! the body on the mounting side, no via in the pad, since an open via wicks solder away
+//! from a fine pitch joint. Exposed pads carry no hint, they take thermal vias.
+//!
+//! KiCad has no pad properties, so exporters keep the hints in one footprint property,
+//! `ROUTING_HINTS_PROPERTY`, written by `hints_property` and read back by
+//! `apply_hints_property`: pads with the same hint share an entry, as in
+//! `1,2,3=exit 135..225 layer F.Cu no-via; 4=exit 225..315 layer F.Cu no-via`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::board_interface::PadDescriptor;
+use crate::layer_type::LayerName;
+
+/// Name of the footprint property holding the pads' hints
+pub const ROUTING_HINTS_PROPERTY: &str = "Routing hints";
+
+/// Exit directions from `from` counter-clockwise to `to`, degrees in [0, 360)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ExitRange {
+    pub from: f32,
+    pub to: f32,
+}
+
+impl ExitRange {
+    pub fn new(from: f32, to: f32) -> Self {
+        Self { from: from.rem_euclid(360.0), to: to.rem_euclid(360.0) }
+    }
+
+    /// Within `spread` degrees either side of `direction`
+    pub fn around(direction: f32, spread: f32) -> Self {
+        Self::new(direction - spread, direction + spread)
+    }
+
+    pub fn contains(&self, angle: f32) -> bool {
+        let span = (self.to - self.from).rem_euclid(360.0);
+        (angle - self.from).rem_euclid(360.0) <= span + 1e-3
+    }
+}
+
+/// How a pad wants to be routed, see the module documentation
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct RoutingHint {
+    /// Allowed exit directions; empty allows any
+    pub exits: Vec<ExitRange>,
+    /// Copper layer to leave the pad on, e.g. "F.Cu"
+    pub preferred_layer: Option<LayerName>,
+    /// No via may sit in the pad's copper
+    pub no_via_under_pad: bool,
+}
+
+impl RoutingHint {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Leave within 45 degrees of `direction` on F.Cu with no via in the pad, for a pad on
+    /// the edge of a package
+    pub fn outward(direction: f32) -> Self {
+        Self::new().with_exit(ExitRange::around(direction, 45.0)).with_preferred_layer("F.Cu").with_no_via_under_pad()
+    }
+
+    pub fn with_exit(mut self, range: ExitRange) -> Self {
+        self.exits.push(range);
+        self
+    }
+
+    pub fn with_preferred_layer(mut self, layer: &str) -> Self {
+        self.preferred_layer = Some(layer.into());
+        self
+    }
+
+    pub fn with_no_via_under_pad(mut self) -> Self {
+        self.no_via_under_pad = true;
+        self
+    }
+
+    /// Whether a track may leave the pad at `angle`
+    pub fn allows_exit(&self, angle: f32) -> bool {
+        self.exits.is_empty() || self.exits.iter().any(|range| range.contains(angle))
+    }
+}
+
+/// The direction a pad at `position` of `size` points away from a package centered on
+/// the origin: along its longer side, as the leads of a gull wing or no-lead package run
+pub fn outward_direction(position: (f32, f32), size: (f32, f32)) -> f32 {
+    // Y points down on screen, so +Y is 270 degrees
+    match (size.0 >= size.1, position) {
+        (true, (x, _)) if x >= 0.0 => 0.0,
+        (true, _) => 180.0,
+        (false, (_, y)) if y >= 0.0 => 270.0,
+        (false, _) => 90.0,
+    }
+}
+
+impl fmt::Display for RoutingHint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut words = Vec::new();
+        for range in &self.exits {
+            words.push(format!("exit {}..{}", range.from, range.to));
+        }
+        if let Some(layer) = &self.preferred_layer {
+            words.push(format!("layer {}", layer));
+        }
+        if self.no_via_under_pad {
+            words.push("no-via".to_string());
+        }
+        f.write_str(&words.join(" "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RoutingHintError {
+    /// A word that isn't `exit`, `layer` or `no-via`, or one missing its value
+    Syntax(String),
+    /// An entry for a pad number the footprint doesn't have
+    UnknownPad(String),
+}
+
+impl fmt::Display for RoutingHintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoutingHintError::Syntax(text) => write!(f, "can't read routing hint \"{}\"", text),
+            RoutingHintError::UnknownPad(number) => write!(f, "routing hint for unknown pad {}", number),
+        }
+    }
+}
+
+impl std::error::Error for RoutingHintError {}
+
+impl FromStr for RoutingHint {
+    type Err = RoutingHintError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let syntax = || RoutingHintError::Syntax(text.to_string());
+        let mut hint = RoutingHint::new();
+        let mut words = text.split_whitespace();
+        while let Some(word) = words.next() {
+            match word {
+                "exit" => {
+                    let (from, to) = words.next().and_then(|range| range.split_once("..")).ok_or_else(syntax)?;
+                    let angle = |value: &str| value.parse::<f32>().map_err(|_| syntax());
+                    hint.exits.push(ExitRange::new(angle(from)?, angle(to)?));
+                }
+                "layer" => hint.preferred_layer = Some(words.next().ok_or_else(syntax)?.into()),
+                "no-via" => hint.no_via_under_pad = true,
+                _ => return Err(syntax()),
+            }
+        }
+        Ok(hint)
+    }
+}
+
+/// The value of `ROUTING_HINTS_PROPERTY` for `pads`, `None` when none has a hint
+pub fn hints_property(pads: &[PadDescriptor]) -> Option<String> {
+    // Pads sharing a hint share an entry, in the order the hints first appear
+    let mut entries: Vec<(String, Vec<&str>)> = Vec::new();
+    for pad in pads.iter().filter(|pad| !pad.number.is_empty()) {
+        let Some(hint) = &pad.routing_hint else { continue };
+        let hint = hint.to_string();
+        match entries.iter_mut().find(|(text, _)| *text == hint) {
+            Some((_, numbers)) if !numbers.contains(&pad.number.as_str()) => numbers.push(&pad.number),
+            Some(_) => {}
+            None => entries.push((hint, vec![&pad.number])),
+        }
+    }
+    let entries: Vec<String> = entries.into_iter().map(|(hint, numbers)| format!("{}={}", numbers.join(","), hint)).collect();
+    (!entries.is_empty()).then(|| entries.join("; "))
+}
+
+/// Set the hints of `pads` from a `ROUTING_HINTS_PROPERTY` value; every pad of a number
+/// listed gets the hint
+pub fn apply_hints_property(pads: &mut [PadDescriptor], value: &str) -> Result<(), RoutingHintError> {
+    for entry in value.split(';').map(str::trim).filter(|entry| !entry.is_empty()) {
+        let (numbers, hint) = entry.split_once('=').ok_or_else(|| RoutingHintError::Syntax(entry.to_string()))?;
+        let hint: RoutingHint = hint.parse()?;
+        for number in numbers.split(',') {
+            let mut matching = pads.iter_mut().filter(|pad| pad.number == number).peekable();
+            if matching.peek().is_none() {
+                return Err(RoutingHintError::UnknownPad(number.to_string()));
+            }
+            for pad in matching {
+                pad.routing_hint = Some(hint.clone());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_interface::BoardComposableObject;
+    use crate::qfn::Qfn;
+
+    #[test]
+    fn exit_ranges_wrap_through_zero() {
+        let right = RoutingHint::outward(0.0);
+        assert!(right.allows_exit(30.0) && right.allows_exit(330.0) && right.allows_exit(-44.0));
+        assert!(!right.allows_exit(90.0) && !right.allows_exit(180.0));
+        assert!(RoutingHint::new().allows_exit(123.0));
+    }
+
+    #[test]
+    fn hints_survive_the_property_text() {
+        let mut pads = Qfn::new(16, 0.5, 3.0, 3.0).with_exposed_pad(Some((1.7, 1.7))).pad_descriptors();
+        let text = hints_property(&pads).unwrap();
+        assert!(text.starts_with("1,2,3,4=exit 135..225 layer F.Cu no-via; "), "{}", text);
+        assert!(!text.contains("17="), "the exposed pad takes thermal vias: {}", text);
+        let hints: Vec<Option<RoutingHint>> = pads.iter().map(|pad| pad.routing_hint.clone()).collect();
+        for pad in &mut pads {
+            pad.routing_hint = None;
+        }
+        apply_hints_property(&mut pads, &text).unwrap();
+        assert_eq!(pads.iter().map(|pad| pad.routing_hint.clone()).collect::<Vec<_>>(), hints);
+        assert_eq!(apply_hints_property(&mut pads, "99=no-via"), Err(RoutingHintError::UnknownPad("99".to_string())));
+        assert!(matches!(apply_hints_property(&mut pads, "1=exit north"), Err(RoutingHintError::Syntax(_))));
+    }
+}
@@ -0,0 +1,72 @@
+//! Rule areas carried by footprints
+//!
+//! Antenna modules and switching regulators need copper kept away from parts of the
+//! footprint, and the keepout should travel with the part. `BoardComposableObject::rule_areas`
+//! returns them in footprint coordinates; the KiCad writer emits each as a keepout
+//! `(zone ...)` inside the footprint and `Board::routing_violations` reports tracks and vias
+//! placed inside one.
+//!
+//! Layers are KiCad layer names as seen by the footprint, so a back side placement moves an
+//! `F.Cu` keepout to `B.Cu`. `*.Cu` covers every copper layer.
+
+use serde::{Deserialize, Serialize};
+
+use crate::board_interface::Rectangle;
+use crate::layer_type::kicad_layer_matches;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleArea {
+    /// Zone name shown in KiCad, may be empty
+    pub name: String,
+    /// Closed polygon in footprint coordinates, last point not repeated
+    pub polygon: Vec<(f32, f32)>,
+    pub layers: Vec<String>,
+    pub no_tracks: bool,
+    pub no_vias: bool,
+    pub no_copper_pour: bool,
+}
+
+impl RuleArea {
+    /// Keepout of tracks, vias and copper pours on `layers`
+    pub fn keepout(name: &str, polygon: Vec<(f32, f32)>, layers: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            polygon,
+            layers: layers.iter().map(|layer| layer.to_string()).collect(),
+            no_tracks: true,
+            no_vias: true,
+            no_copper_pour: true,
+        }
+    }
+
+    /// `keepout` over a rectangle
+    pub fn keepout_rect(name: &str, bounds: &Rectangle, layers: &[&str]) -> Self {
+        let polygon = vec![
+            (bounds.min_x, bounds.min_y),
+            (bounds.max_x, bounds.min_y),
+            (bounds.max_x, bounds.max_y),
+            (bounds.min_x, bounds.max_y),
+        ];
+        Self::keepout(name, polygon, layers)
+    }
+
+    pub fn with_tracks_allowed(mut self) -> Self {
+        self.no_tracks = false;
+        self
+    }
+
+    pub fn with_vias_allowed(mut self) -> Self {
+        self.no_vias = false;
+        self
+    }
+
+    pub fn with_copper_pour_allowed(mut self) -> Self {
+        self.no_copper_pour = false;
+        self
+    }
+
+    /// Whether the area applies to `layer`, e.g. "In1.Cu"
+    pub fn has_layer(&self, layer: &str) -> bool {
+        self.layers.iter().any(|pattern| kicad_layer_matches(pattern, layer))
+    }
+}
@@ -0,0 +1,334 @@
+//! Scaled footprint variants
+//!
+//! `BoardComposableObject::scaled` wraps a footprint so its geometry is stretched by
+//! separate X and Y factors: pads, text positions, graphics, courtyard, bounding box and
+//! the 3D model. Stroke widths and the courtyard margin stay as they are, font sizes too
+//! unless text scaling is turned on.
+//!
+//! Scaling is checked against a `FabProfile` so a shrunk footprint can't end up with pads
+//! or drills the fab can't make. Size-encoded names ("R_0603_1608Metric") are regenerated
+//! from the scaled body; a naming hook can replace that for other conventions.
+//!
+//! Footprints read from a `.kicad_mod` are refused: their source can't take moved
+//! coordinates, and regenerating the file would drop what the parser doesn't model.
+
+use std::fmt;
+use std::rc::Rc;
+
+use crate::anchor::Anchor;
+use crate::assembly_process::ProcessSet;
+use crate::board_interface::{
+    BoardComposableObject, FpText, GraphicElement, GraphicType, Model3D, PadDescriptor, PadShape, Rectangle,
+};
+use crate::chip::regenerate_size_name;
+use crate::courtyard::{ActuationEnvelope, Courtyard};
+use crate::fab_profile::FabProfile;
+use crate::functional_types::FunctionalType;
+use crate::geometry::Point;
+use crate::pad_groups::PadGrouping;
+use crate::padstack::PadStackError;
+use crate::rule_area::RuleArea;
+
+/// Footprint name from the original name and the scaled body bounds
+pub type NameHook = Rc<dyn Fn(&str, &Rectangle) -> String>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScaleError {
+    /// Scale factors must be finite and positive
+    InvalidFactor { sx: f32, sy: f32 },
+    /// A scaled pad is narrower than the profile allows
+    PadTooSmall { pad: String, size: (f32, f32), minimum: f32 },
+    /// A scaled drill is smaller than the profile allows
+    DrillTooSmall { pad: String, drill: f32, minimum: f32 },
+    /// A scaled plated pad leaves too little copper around its drill on some layers
+    AnnularRing(PadStackError),
+    /// The footprint was read from a file and is written from its source, which
+    /// scaling can't edit
+    ParsedSource { footprint: String },
+}
+
+impl fmt::Display for ScaleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScaleError::InvalidFactor { sx, sy } => write!(f, "invalid scale factors {} x {}", sx, sy),
+            ScaleError::PadTooSmall { pad, size, minimum } => {
+                write!(f, "pad {} would be {:.3} x {:.3} mm, below the {:.3} mm minimum", pad, size.0, size.1, minimum)
+            }
+            ScaleError::DrillTooSmall { pad, drill, minimum } => {
+                write!(f, "pad {} drill would be {:.3} mm, below the {:.3} mm minimum", pad, drill, minimum)
+            }
+            ScaleError::AnnularRing(e) => write!(f, "{}", e),
+            ScaleError::ParsedSource { footprint } => {
+                write!(f, "{} was read from a file, scaling it would drop what its model doesn't cover", footprint)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScaleError {}
+
+/// A component with its geometry scaled by (sx, sy) about the origin
+#[derive(Clone)]
+pub struct Scaled<T> {
+    inner: T,
+    sx: f32,
+    sy: f32,
+    scale_text: bool,
+    name_hook: Option<NameHook>,
+}
+
+impl<T: fmt::Debug> fmt::Debug for Scaled<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scaled")
+            .field("inner", &self.inner)
+            .field("sx", &self.sx)
+            .field("sy", &self.sy)
+            .field("scale_text", &self.scale_text)
+            .finish()
+    }
+}
+
+impl<T: BoardComposableObject> Scaled<T> {
+    /// Scale `inner`, failing if a pad, drill or annular ring would fall below the profile's
+    /// minimums, or if `inner` is written from a parsed source
+    pub fn new(inner: T, sx: f32, sy: f32, profile: &FabProfile) -> Result<Self, ScaleError> {
+        if !(sx.is_finite() && sy.is_finite() && sx > 0.0 && sy > 0.0) {
+            return Err(ScaleError::InvalidFactor { sx, sy });
+        }
+        if inner.kicad_source().is_some() {
+            return Err(ScaleError::ParsedSource { footprint: inner.footprint_name() });
+        }
+        let scaled = Self { inner, sx, sy, scale_text: false, name_hook: None };
+        for pad in scaled.pad_descriptors() {
+            if pad.size.0.min(pad.size.1) < profile.min_pad_size {
+                return Err(ScaleError::PadTooSmall { pad: pad.number.into(), size: pad.size, minimum: profile.min_pad_size });
+            }
+            if let Some(drill) = pad.drill_size.filter(|drill| *drill < profile.min_drill) {
+                return Err(ScaleError::DrillTooSmall { pad: pad.number.into(), drill, minimum: profile.min_drill });
+            }
+            pad.check_annular_rings(profile).map_err(ScaleError::AnnularRing)?;
+        }
+        Ok(scaled)
+    }
+
+    /// Scale font sizes and thicknesses along with text positions
+    pub fn with_text_scaling(mut self, scale_text: bool) -> Self {
+        self.scale_text = scale_text;
+        self
+    }
+
+    /// Name the scaled footprint with `hook` instead of regenerating the size codes
+    pub fn with_name_hook(mut self, hook: impl Fn(&str, &Rectangle) -> String + 'static) -> Self {
+        self.name_hook = Some(Rc::new(hook));
+        self
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn factors(&self) -> (f32, f32) {
+        (self.sx, self.sy)
+    }
+
+    fn scaled(&self, point: (f32, f32)) -> (f32, f32) {
+        (point.0 * self.sx, point.1 * self.sy)
+    }
+
+    fn scaled_rectangle(&self, rect: &Rectangle) -> Rectangle {
+        Rectangle { min_x: rect.min_x * self.sx, min_y: rect.min_y * self.sy, max_x: rect.max_x * self.sx, max_y: rect.max_y * self.sy }
+    }
+
+    /// Factors along a pad's own axes: swapped for pads turned a quarter turn. Pads at
+    /// other angles use the factors of the nearest quarter turn.
+    fn pad_factors(&self, pad: &PadDescriptor) -> (f32, f32) {
+        let quarter_turns = (pad.rotation.unwrap_or(0.0) / 90.0).round() as i32;
+        if quarter_turns.rem_euclid(2) == 1 { (self.sy, self.sx) } else { (self.sx, self.sy) }
+    }
+}
+
+impl<T: BoardComposableObject> BoardComposableObject for Scaled<T> {
+    fn is_smt(&self) -> bool {
+        self.inner.is_smt()
+    }
+
+    fn is_electrical(&self) -> bool {
+        self.inner.is_electrical()
+    }
+
+    fn is_passive(&self) -> bool {
+        self.inner.is_passive()
+    }
+
+    fn is_assembled(&self) -> bool {
+        self.inner.is_assembled()
+    }
+
+    fn allows_soldermask_bridges(&self) -> bool {
+        self.inner.allows_soldermask_bridges()
+    }
+
+    fn pad_grouping(&self) -> PadGrouping {
+        self.inner.pad_grouping()
+    }
+
+    fn terminal_count(&self) -> usize {
+        self.inner.terminal_count()
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        self.inner.functional_type()
+    }
+
+    fn footprint_name(&self) -> String {
+        let name = self.inner.footprint_name();
+        let body = self.bounding_box();
+        match &self.name_hook {
+            Some(hook) => hook(&name, &body),
+            None => regenerate_size_name(&name, body.max_x - body.min_x, body.max_y - body.min_y)
+                .unwrap_or_else(|| format!("{}_Scaled_{}x{}", name, self.sx, self.sy)),
+        }
+    }
+
+    fn library_name(&self) -> String {
+        self.inner.library_name()
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        self.scaled_rectangle(&self.inner.bounding_box())
+    }
+
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        self.inner
+            .pad_descriptors()
+            .into_iter()
+            .map(|mut pad| {
+                let (fx, fy) = self.pad_factors(&pad);
+                pad.position = self.scaled(pad.position);
+                pad.size = (pad.size.0 * fx, pad.size.1 * fy);
+                // A circle stretched unevenly is an oval
+                if matches!(pad.shape, PadShape::Circle) && fx != fy {
+                    pad.shape = PadShape::Oval;
+                }
+                for geometry in pad.padstack.iter_mut().flat_map(|padstack| [&mut padstack.back, &mut padstack.inner]).flatten() {
+                    geometry.size = (geometry.size.0 * fx, geometry.size.1 * fy);
+                    if matches!(geometry.shape, PadShape::Circle) && fx != fy {
+                        geometry.shape = PadShape::Oval;
+                    }
+                }
+                pad.drill_size = pad.drill_size.map(|drill| drill * fx.min(fy));
+                if let Some((width, height)) = pad.drill_slot {
+                    pad.drill_slot = Some((width * fx, height * fy));
+                    pad.drill_size = Some((width * fx).min(height * fy));
+                }
+                pad
+            })
+            .collect()
+    }
+
+    fn description(&self) -> Option<String> {
+        self.inner.description().map(|description| format!("{}, scaled {} x {}", description, self.sx, self.sy))
+    }
+
+    fn tags(&self) -> Option<String> {
+        self.inner.tags()
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        self.inner
+            .fp_text_elements()
+            .into_iter()
+            .map(|mut text| {
+                text.position = self.scaled(text.position);
+                if self.scale_text {
+                    text.font.size = self.scaled(text.font.size);
+                    text.font.thickness *= self.sx.min(self.sy);
+                }
+                text
+            })
+            .collect()
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        self.inner
+            .graphic_elements()
+            .into_iter()
+            .map(|mut element| {
+                element.element_type = match element.element_type {
+                    GraphicType::Line { start, end } => GraphicType::Line { start: self.scaled(start), end: self.scaled(end) },
+                    GraphicType::Rectangle { bounds } => GraphicType::Rectangle { bounds: self.scaled_rectangle(&bounds) },
+                    // Circles stay circles, with the mean of the two factors
+                    GraphicType::Circle { center, radius } => {
+                        GraphicType::Circle { center: self.scaled(center), radius: radius * (self.sx + self.sy) / 2.0 }
+                    }
+                    GraphicType::Polygon { points, fill } => {
+                        GraphicType::Polygon { points: points.into_iter().map(|point| self.scaled(point)).collect(), fill }
+                    }
+                    GraphicType::Dimension(mut dimension) => {
+                        dimension.start = self.scaled(dimension.start);
+                        dimension.end = self.scaled(dimension.end);
+                        GraphicType::Dimension(dimension)
+                    }
+                };
+                element
+            })
+            .collect()
+    }
+
+    fn model_3d(&self) -> Option<Model3D> {
+        self.inner.model_3d().map(|mut model| {
+            model.offset.0 *= self.sx;
+            model.offset.1 *= self.sy;
+            model.scale.0 *= self.sx;
+            model.scale.1 *= self.sy;
+            model
+        })
+    }
+
+    fn rule_areas(&self) -> Vec<RuleArea> {
+        self.inner
+            .rule_areas()
+            .into_iter()
+            .map(|mut area| {
+                area.polygon = area.polygon.iter().map(|point| self.scaled(*point)).collect();
+                area
+            })
+            .collect()
+    }
+
+    fn actuation_envelope(&self) -> Option<ActuationEnvelope> {
+        self.inner.actuation_envelope().map(|mut envelope| {
+            envelope.outline = envelope.outline.iter().map(|point| self.scaled(*point)).collect();
+            envelope
+        })
+    }
+
+    fn process(&self) -> ProcessSet {
+        self.inner.process()
+    }
+
+    fn courtyard_margin(&self) -> f32 {
+        self.inner.courtyard_margin()
+    }
+
+    /// The inner courtyard scaled, keeping its margin
+    fn generate_courtyard(&self) -> Courtyard {
+        let courtyard = self.inner.generate_courtyard();
+        let margin = courtyard.margin;
+        let bounds = &courtyard.bounds;
+        let inner = Rectangle {
+            min_x: bounds.min_x + margin,
+            min_y: bounds.min_y + margin,
+            max_x: bounds.max_x - margin,
+            max_y: bounds.max_y - margin,
+        };
+        Courtyard::new(self.scaled_rectangle(&inner), margin)
+    }
+
+    fn anchor(&self) -> Anchor {
+        match self.inner.anchor() {
+            Anchor::Custom(point) => Anchor::Custom(Point::new(point.x * self.sx, point.y * self.sy)),
+            anchor => anchor,
+        }
+    }
+}
@@ -0,0 +1,280 @@
+//! Silkscreen sizes a fab can print
+//!
+//! Strokes and text below a fab's silkscreen minimums print broken or smudged. The
+//! footprint check is part of `FootprintDrc`; `Board::silk_size_findings` runs it over
+//! every placed footprint and the board's own silkscreen.
+//!
+//! `BoardComposableObject::with_silk_minimums` is the fix to apply before export: it
+//! widens thin silkscreen strokes and enlarges small or thin visible silkscreen text up to
+//! the profile's minimums, and `SilkSized::changes` lists every change. Text keeps its
+//! proportions. Nothing moves, and graphics and text on other layers are left as they are.
+//! A footprint read from a `.kicad_mod` keeps its source, with the new sizes spliced in.
+
+use std::fmt;
+
+use crate::analysis::{ElementRef, Finding, FootprintDrc, FootprintViolation, Violation};
+use crate::anchor::Anchor;
+use crate::assembly_process::ProcessSet;
+use crate::board::Board;
+use crate::board_interface::{BoardComposableObject, FpText, GraphicElement, GraphicType, Model3D, PadDescriptor, Rectangle};
+use crate::courtyard::{ActuationEnvelope, Courtyard};
+use crate::describe::DescriptionContext;
+use crate::fab_profile::FabProfile;
+use crate::functional_types::FunctionalType;
+use crate::geometry::Point;
+use crate::layer_type::LayerType;
+use crate::pad_groups::PadGrouping;
+use crate::rule_area::RuleArea;
+
+/// One size raised by `SilkSized`
+#[derive(Debug, Clone, PartialEq)]
+pub enum SilkChange {
+    /// Index into `graphic_elements()`, stroke width before and after
+    LineWidth { graphic: usize, from: f32, to: f32 },
+    /// Index into `fp_text_elements()`, font size and thickness before and after
+    Text { text: usize, content: String, size: ((f32, f32), (f32, f32)), thickness: (f32, f32) },
+}
+
+impl fmt::Display for SilkChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SilkChange::LineWidth { graphic, from, to } => {
+                write!(f, "silkscreen graphic {} widened from {:.3} mm to {:.3} mm", graphic, from, to)
+            }
+            SilkChange::Text { content, size, thickness, .. } => write!(
+                f,
+                "silkscreen text \"{}\" from {:.3} x {:.3} mm, {:.3} mm thick to {:.3} x {:.3} mm, {:.3} mm thick",
+                content, size.0.0, size.0.1, thickness.0, size.1.0, size.1.1, thickness.1
+            ),
+        }
+    }
+}
+
+/// Whether `element` is a silkscreen stroke; filled shapes drawn without an outline aren't
+fn is_silk_stroke(element: &GraphicElement) -> bool {
+    let filled = matches!(element.element_type, GraphicType::Polygon { fill: Some(_), .. });
+    element.layer == LayerType::SilkScreen && !(filled && element.stroke.width <= 0.0)
+}
+
+/// A component with its silkscreen raised to the minimums of a `FabProfile`
+#[derive(Debug, Clone)]
+pub struct SilkSized<T> {
+    inner: T,
+    profile: FabProfile,
+}
+
+impl<T: BoardComposableObject> SilkSized<T> {
+    pub fn new(inner: T, profile: &FabProfile) -> Self {
+        Self { inner, profile: profile.clone() }
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn profile(&self) -> &FabProfile {
+        &self.profile
+    }
+
+    /// Every size raised, graphics then texts; empty when the inner footprint already
+    /// meets the profile
+    pub fn changes(&self) -> Vec<SilkChange> {
+        let mut changes = self.sized_graphics().1;
+        changes.extend(self.sized_texts().1);
+        changes
+    }
+
+    fn sized_graphics(&self) -> (Vec<GraphicElement>, Vec<SilkChange>) {
+        let minimum = self.profile.min_silk_line_width;
+        let mut graphics = self.inner.graphic_elements();
+        let mut changes = Vec::new();
+        for (graphic, element) in graphics.iter_mut().enumerate() {
+            if is_silk_stroke(element) && element.stroke.width < minimum {
+                changes.push(SilkChange::LineWidth { graphic, from: element.stroke.width, to: minimum });
+                element.stroke.width = minimum;
+            }
+        }
+        (graphics, changes)
+    }
+
+    fn sized_texts(&self) -> (Vec<FpText>, Vec<SilkChange>) {
+        let (min_height, min_thickness) = (self.profile.min_silk_text_height, self.profile.min_silk_text_thickness);
+        let mut texts = self.inner.fp_text_elements();
+        let mut changes = Vec::new();
+        for (index, text) in texts.iter_mut().enumerate() {
+            if text.hidden || !text.layer.ends_with(".SilkS") {
+                continue;
+            }
+            let (size, thickness) = (text.font.size, text.font.thickness);
+            if size.1 < min_height && size.1 > 0.0 {
+                let factor = min_height / size.1;
+                text.font.size = (size.0 * factor, min_height);
+            }
+            text.font.thickness = thickness.max(min_thickness);
+            if text.font.size != size || text.font.thickness != thickness {
+                changes.push(SilkChange::Text {
+                    text: index,
+                    content: text.text.clone(),
+                    size: (size, text.font.size),
+                    thickness: (thickness, text.font.thickness),
+                });
+            }
+        }
+        (texts, changes)
+    }
+}
+
+impl<T: BoardComposableObject> BoardComposableObject for SilkSized<T> {
+    fn is_smt(&self) -> bool {
+        self.inner.is_smt()
+    }
+
+    fn is_electrical(&self) -> bool {
+        self.inner.is_electrical()
+    }
+
+    fn is_passive(&self) -> bool {
+        self.inner.is_passive()
+    }
+
+    fn is_assembled(&self) -> bool {
+        self.inner.is_assembled()
+    }
+
+    fn allows_soldermask_bridges(&self) -> bool {
+        self.inner.allows_soldermask_bridges()
+    }
+
+    fn pad_grouping(&self) -> PadGrouping {
+        self.inner.pad_grouping()
+    }
+
+    fn terminal_count(&self) -> usize {
+        self.inner.terminal_count()
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        self.inner.functional_type()
+    }
+
+    fn footprint_name(&self) -> String {
+        self.inner.footprint_name()
+    }
+
+    fn library_name(&self) -> String {
+        self.inner.library_name()
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        self.inner.bounding_box()
+    }
+
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        self.inner.pad_descriptors()
+    }
+
+    fn description_context(&self) -> Option<DescriptionContext> {
+        self.inner.description_context()
+    }
+
+    fn description(&self) -> Option<String> {
+        self.inner.description()
+    }
+
+    fn tags(&self) -> Option<String> {
+        self.inner.tags()
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        self.sized_texts().0
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        self.sized_graphics().0
+    }
+
+    fn model_3d(&self) -> Option<Model3D> {
+        self.inner.model_3d()
+    }
+
+    /// The inner source with the raised sizes spliced in, so a footprint read from a file
+    /// keeps what the model doesn't cover
+    fn kicad_source(&self) -> Option<String> {
+        if self.changes().is_empty() {
+            return self.inner.kicad_source();
+        }
+        self.inner.kicad_source_with(&self.fp_text_elements(), &self.graphic_elements())
+    }
+
+    fn kicad_source_with(&self, texts: &[FpText], graphics: &[GraphicElement]) -> Option<String> {
+        self.inner.kicad_source_with(texts, graphics)
+    }
+
+    fn rule_areas(&self) -> Vec<RuleArea> {
+        self.inner.rule_areas()
+    }
+
+    fn actuation_envelope(&self) -> Option<ActuationEnvelope> {
+        self.inner.actuation_envelope()
+    }
+
+    fn process(&self) -> ProcessSet {
+        self.inner.process()
+    }
+
+    fn courtyard_margin(&self) -> f32 {
+        self.inner.courtyard_margin()
+    }
+
+    fn generate_courtyard(&self) -> Courtyard {
+        self.inner.generate_courtyard()
+    }
+
+    fn generate_courtyards(&self) -> Vec<Courtyard> {
+        self.inner.generate_courtyards()
+    }
+
+    fn anchor(&self) -> Anchor {
+        self.inner.anchor()
+    }
+}
+
+impl Board {
+    /// Silkscreen size violations of every placed footprint, reported against its
+    /// reference designator at board coordinates, then those of the board's own silkscreen
+    /// graphics, reported against "board"
+    pub fn silk_size_findings(&self, profile: &FabProfile) -> Vec<Finding> {
+        let drc = FootprintDrc::new(profile.clone());
+        let mut findings = Vec::new();
+        for placed in self.components() {
+            for violation in drc.silk_size_violations(&*placed.component) {
+                let mut finding = violation.to_finding();
+                finding.footprint = placed.reference.clone();
+                finding.location = finding.location.map(|location| self.board_point(placed, location));
+                finding.elements = vec![ElementRef::Component(placed.reference.clone())];
+                findings.push(finding);
+            }
+        }
+        for (graphic, element) in self.graphics().iter().enumerate() {
+            if !is_silk_stroke(element) || element.stroke.width >= profile.min_silk_line_width - f32::EPSILON {
+                continue;
+            }
+            let location = match &element.element_type {
+                GraphicType::Line { start, end } => Point::new((start.0 + end.0) / 2.0, (start.1 + end.1) / 2.0),
+                GraphicType::Rectangle { bounds } => Point::new(bounds.min_x, bounds.min_y),
+                GraphicType::Circle { center, .. } => Point::from(*center),
+                GraphicType::Polygon { points, .. } => points.first().copied().map(Point::from).unwrap_or_default(),
+                GraphicType::Dimension(dimension) => Point::from(dimension.start),
+            };
+            let violation = FootprintViolation::SilkLineTooThin {
+                footprint: "board".to_string(),
+                graphic,
+                width: element.stroke.width,
+                minimum: profile.min_silk_line_width,
+                location,
+            };
+            findings.push(violation.to_finding());
+        }
+        findings
+    }
+}
@@ -0,0 +1,373 @@
+//! Board snapshots and undo
+//!
+//! Scripts that edit a board (autoplace, tweak, check, revert) take a `BoardSnapshot`
+//! before each step and `restore` it to go back. Placements, tracks, arcs, vias and pad
+//! nets are shared between the board and its snapshots and only copied when the board
+//! changes them afterwards, so a snapshot costs a few reference counts whatever the board
+//! size, and nothing done to the board after the snapshot shows up in it. Restoring shares
+//! the collections back the same way and keeps the board's spatial indexes, which only
+//! re-index the placements that differ on the next query.
+//!
+//! `UndoStack` keeps a bounded history of snapshots with redo. `BoardSnapshot::diff` tells
+//! what changed between two snapshots for reporting: references added, removed, moved or
+//! given another footprint, tracks, arcs and vias added or removed, and pads whose net
+//! changed.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::rc::Rc;
+
+use crate::board::Board;
+
+/// The state of a board at one point, see the module documentation
+#[derive(Debug, Clone)]
+pub struct BoardSnapshot {
+    board: Board,
+}
+
+impl Board {
+    pub fn snapshot(&self) -> BoardSnapshot {
+        BoardSnapshot { board: self.shared_copy() }
+    }
+
+    /// Put the board back to the state of `snapshot`; the snapshot stays usable
+    pub fn restore(&mut self, snapshot: &BoardSnapshot) {
+        self.assign_shared(&snapshot.board);
+    }
+}
+
+/// What changed from one snapshot to a later one
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BoardDiff {
+    /// References only in the later snapshot
+    pub added: Vec<String>,
+    /// References only in the earlier snapshot
+    pub removed: Vec<String>,
+    /// References whose position, rotation or side changed
+    pub moved: Vec<String>,
+    /// References placed with a different component definition
+    pub replaced: Vec<String>,
+    /// Indexes into the later snapshot's tracks
+    pub added_tracks: Vec<usize>,
+    /// Indexes into the earlier snapshot's tracks
+    pub removed_tracks: Vec<usize>,
+    /// Indexes into the later snapshot's arc tracks
+    pub added_arcs: Vec<usize>,
+    /// Indexes into the earlier snapshot's arc tracks
+    pub removed_arcs: Vec<usize>,
+    /// Indexes into the later snapshot's vias
+    pub added_vias: Vec<usize>,
+    /// Indexes into the earlier snapshot's vias
+    pub removed_vias: Vec<usize>,
+    /// Pads ("reference.number") whose net was assigned, changed or dropped
+    pub pad_nets: Vec<String>,
+    /// Outline, stackup, policy, net classes, graphics or other board settings differ
+    pub settings_changed: bool,
+}
+
+impl BoardDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == BoardDiff::default()
+    }
+}
+
+impl fmt::Display for BoardDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no changes");
+        }
+        for (label, references) in
+            [("added", &self.added), ("removed", &self.removed), ("moved", &self.moved), ("replaced", &self.replaced)]
+        {
+            if !references.is_empty() {
+                writeln!(f, "{}: {}", label, references.join(", "))?;
+            }
+        }
+        for (label, added, removed) in
+            [("tracks", &self.added_tracks, &self.removed_tracks), ("arcs", &self.added_arcs, &self.removed_arcs), ("vias", &self.added_vias, &self.removed_vias)]
+        {
+            if !added.is_empty() || !removed.is_empty() {
+                writeln!(f, "{}: {} added, {} removed", label, added.len(), removed.len())?;
+            }
+        }
+        if !self.pad_nets.is_empty() {
+            writeln!(f, "pad nets changed: {}", self.pad_nets.join(", "))?;
+        }
+        if self.settings_changed {
+            writeln!(f, "board settings changed")?;
+        }
+        Ok(())
+    }
+}
+
+/// Items of `before` and of `after` without an equal partner in the other list, as
+/// indexes. Common leading and trailing items are matched first, so appending to or
+/// removing from a long list is cheap.
+fn unmatched<T: PartialEq>(before: &[T], after: &[T]) -> (Vec<usize>, Vec<usize>) {
+    let prefix = before.iter().zip(after).take_while(|(a, b)| a == b).count();
+    let suffix =
+        before[prefix..].iter().rev().zip(after[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let (before_end, after_end) = (before.len() - suffix, after.len() - suffix);
+    let mut matched = vec![false; after_end - prefix];
+    let mut removed = Vec::new();
+    for (index, item) in before.iter().enumerate().take(before_end).skip(prefix) {
+        let partner = (prefix..after_end).find(|&candidate| !matched[candidate - prefix] && after[candidate] == *item);
+        match partner {
+            Some(candidate) => matched[candidate - prefix] = true,
+            None => removed.push(index),
+        }
+    }
+    let added = (prefix..after_end).filter(|&index| !matched[index - prefix]).collect();
+    (removed, added)
+}
+
+impl BoardSnapshot {
+    /// What changed from this snapshot to `later`
+    pub fn diff(&self, later: &BoardSnapshot) -> BoardDiff {
+        let (before, after) = (&self.board, &later.board);
+        let mut diff = BoardDiff { settings_changed: !before.same_settings(after), ..BoardDiff::default() };
+
+        if !Rc::ptr_eq(&before.components, &after.components) {
+            let earlier: HashMap<&str, _> = before.components.iter().map(|placed| (placed.reference.as_str(), placed)).collect();
+            for placed in after.components.iter() {
+                let Some(old) = earlier.get(placed.reference.as_str()) else {
+                    diff.added.push(placed.reference.clone());
+                    continue;
+                };
+                if (old.position, old.rotation, old.side) != (placed.position, placed.rotation, placed.side) {
+                    diff.moved.push(placed.reference.clone());
+                }
+                if !Rc::ptr_eq(&old.component, &placed.component) {
+                    diff.replaced.push(placed.reference.clone());
+                }
+            }
+            let later: HashMap<&str, _> = after.components.iter().map(|placed| (placed.reference.as_str(), placed)).collect();
+            diff.removed =
+                before.components.iter().filter(|placed| !later.contains_key(placed.reference.as_str())).map(|placed| placed.reference.clone()).collect();
+        }
+
+        if !Rc::ptr_eq(&before.routing.tracks, &after.routing.tracks) {
+            (diff.removed_tracks, diff.added_tracks) = unmatched(&before.routing.tracks, &after.routing.tracks);
+        }
+        if !Rc::ptr_eq(&before.routing.arcs, &after.routing.arcs) {
+            (diff.removed_arcs, diff.added_arcs) = unmatched(&before.routing.arcs, &after.routing.arcs);
+        }
+        if !Rc::ptr_eq(&before.routing.vias, &after.routing.vias) {
+            (diff.removed_vias, diff.added_vias) = unmatched(&before.routing.vias, &after.routing.vias);
+        }
+        if !Rc::ptr_eq(&before.routing.pad_nets, &after.routing.pad_nets) {
+            let (old, new) = (&before.routing.pad_nets, &after.routing.pad_nets);
+            let mut pads: Vec<String> = old
+                .iter()
+                .filter(|(pad, net)| new.get(*pad) != Some(*net))
+                .map(|(pad, _)| pad.clone())
+                .chain(new.keys().filter(|pad| !old.contains_key(*pad)).cloned())
+                .collect();
+            pads.sort();
+            diff.pad_nets = pads;
+        }
+        diff
+    }
+}
+
+/// Snapshots compare equal when `diff` finds nothing
+impl PartialEq for BoardSnapshot {
+    fn eq(&self, other: &Self) -> bool {
+        self.diff(other).is_empty()
+    }
+}
+
+/// Bounded undo history of a board, see the module documentation
+#[derive(Debug, Clone)]
+pub struct UndoStack {
+    limit: usize,
+    undo: VecDeque<BoardSnapshot>,
+    redo: Vec<BoardSnapshot>,
+}
+
+impl UndoStack {
+    /// A history keeping the last `limit` checkpoints
+    pub fn new(limit: usize) -> Self {
+        Self { limit, undo: VecDeque::new(), redo: Vec::new() }
+    }
+
+    /// Remember the board as it is before an edit. The oldest checkpoint is dropped past
+    /// the limit and anything undone can no longer be redone.
+    pub fn checkpoint(&mut self, board: &Board) {
+        self.push_undo(board.snapshot());
+        self.redo.clear();
+    }
+
+    fn push_undo(&mut self, snapshot: BoardSnapshot) {
+        if self.limit == 0 {
+            return;
+        }
+        if self.undo.len() == self.limit {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(snapshot);
+    }
+
+    /// Go back to the last checkpoint, `false` when there is none
+    pub fn undo(&mut self, board: &mut Board) -> bool {
+        let Some(snapshot) = self.undo.pop_back() else {
+            return false;
+        };
+        self.redo.push(board.snapshot());
+        board.restore(&snapshot);
+        true
+    }
+
+    /// Reapply the last undone edit, `false` when there is none
+    pub fn redo(&mut self, board: &mut Board) -> bool {
+        let Some(snapshot) = self.redo.pop() else {
+            return false;
+        };
+        self.push_undo(board.snapshot());
+        board.restore(&snapshot);
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Checkpoints that can be undone
+    pub fn depth(&self) -> usize {
+        self.undo.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Point;
+    use crate::layer_type::{BoardLayer, LayerType};
+    use crate::presets;
+    use crate::routing::{Track, Via};
+
+    const COMPONENTS: usize = 50;
+
+    /// A row of resistors chained by nets, a track between each pair and a via every fifth
+    fn chained_board() -> Board {
+        let mut board = Board::new("snapshot").with_rectangular_outline(160.0, 10.0);
+        for i in 0..COMPONENTS {
+            let (x, y) = (i as f32 * 3.0 + 1.5, 5.0);
+            let reference = format!("R{}", i + 1);
+            board.place(&reference, presets::resistor_0603("10k"), (x as f64, y as f64), 0.0).unwrap();
+            board.connect_pad(&reference, "1", &format!("N{}", i));
+            board.connect_pad(&reference, "2", &format!("N{}", i + 1));
+            board.add_track(Track::new(&format!("N{}", i + 1), BoardLayer::front(LayerType::Copper), Point::new(x + 0.8, y), Point::new(x + 2.2, y), 0.2));
+            if i % 5 == 0 {
+                board.add_via(Via::new(&format!("N{}", i), Point::new(x, y + 1.0), 0.6, 0.3));
+            }
+        }
+        board
+    }
+
+    /// Move R10, remove R11, place R99, route on N3 and put R10.1 on GND
+    fn edit(board: &mut Board) {
+        board.component_mut("R10").unwrap().position = (1.0, 1.0);
+        board.remove("R11");
+        board.place("R99", presets::resistor_0603("10k"), (5.0, 8.0), 90.0).unwrap();
+        board.add_track(Track::new("N3", BoardLayer::back(LayerType::Copper), Point::new(0.0, 0.0), Point::new(2.0, 2.0), 0.25));
+        board.add_via(Via::new("N3", Point::new(2.0, 2.0), 0.6, 0.3));
+        board.connect_pad("R10", "1", "GND");
+    }
+
+    #[test]
+    fn edits_after_a_snapshot_stay_out_of_it() {
+        let mut board = chained_board();
+        let before = board.snapshot();
+        edit(&mut board);
+        board.restore(&before);
+        assert!(board.snapshot() == before);
+        assert_eq!(board.component("R10").unwrap().position, (28.5, 5.0));
+        assert!(board.component("R11").is_some() && board.component("R99").is_none());
+        assert_eq!((board.tracks().len(), board.vias().len()), (COMPONENTS, COMPONENTS / 5));
+        assert_eq!(board.pad_net("R10", "1"), Some("N9"));
+    }
+
+    #[test]
+    fn a_snapshot_can_be_restored_again_after_edits() {
+        let mut board = chained_board();
+        let before = board.snapshot();
+        edit(&mut board);
+        let after = board.snapshot();
+        board.restore(&before);
+        board.component_mut("R1").unwrap().rotation = 90.0;
+        board.restore(&after);
+        assert!(board.snapshot() == after);
+        assert_eq!(board.component("R10").unwrap().position, (1.0, 1.0));
+        assert_eq!(board.component("R1").unwrap().rotation, 0.0);
+        board.restore(&before);
+        assert!(board.snapshot() == before);
+    }
+
+    #[test]
+    fn restoring_refreshes_the_spatial_index() {
+        let mut board = chained_board();
+        let before = board.snapshot();
+        edit(&mut board);
+        let near_moved = |board: &Board| board.components_near(Point::new(1.0, 1.0), 0.1).iter().map(|p| p.reference.clone()).collect::<Vec<_>>();
+        assert_eq!(near_moved(&board), ["R10"]);
+        board.restore(&before);
+        assert!(near_moved(&board).is_empty());
+    }
+
+    #[test]
+    fn diff_names_what_changed() {
+        let mut board = chained_board();
+        let before = board.snapshot();
+        edit(&mut board);
+        let after = board.snapshot();
+        let diff = before.diff(&after);
+        assert_eq!(diff.added, ["R99"]);
+        assert_eq!(diff.removed, ["R11"]);
+        assert_eq!(diff.moved, ["R10"]);
+        assert!(diff.replaced.is_empty());
+        assert_eq!(diff.added_tracks, [COMPONENTS]);
+        assert!(diff.removed_tracks.is_empty());
+        assert_eq!(diff.added_vias, [COMPONENTS / 5]);
+        assert_eq!(diff.pad_nets, ["R10.1"]);
+        assert!(!diff.settings_changed);
+        let back = after.diff(&before);
+        assert_eq!((back.added, back.removed, back.removed_tracks), (vec!["R11".to_string()], vec!["R99".to_string()], vec![COMPONENTS]));
+        assert!(before.diff(&before).is_empty());
+        assert!(before != after);
+    }
+
+    #[test]
+    fn undo_history_is_bounded_and_redoable() {
+        let mut board = chained_board();
+        let mut history = UndoStack::new(3);
+        for step in 0..5 {
+            history.checkpoint(&board);
+            board.component_mut("R1").unwrap().position = (step as f64, 0.0);
+        }
+        assert_eq!(history.depth(), 3);
+        let mut undone = 0;
+        while history.undo(&mut board) {
+            undone += 1;
+        }
+        assert_eq!(undone, 3);
+        assert_eq!(board.component("R1").unwrap().position, (1.0, 0.0));
+        assert!(history.redo(&mut board) && history.redo(&mut board));
+        assert_eq!(board.component("R1").unwrap().position, (3.0, 0.0));
+        history.checkpoint(&board);
+        assert!(!history.can_redo());
+        assert!(history.can_undo());
+    }
+
+    #[test]
+    fn a_zero_limit_keeps_no_history() {
+        let mut board = chained_board();
+        let mut history = UndoStack::new(0);
+        history.checkpoint(&board);
+        assert!(!history.undo(&mut board));
+    }
+}
@@ -0,0 +1,431 @@
+//! SOIC footprint generator
+//!
+//! Gull-wing packages with two rows of leads: pins count down the left row from the top
+//! and back up the right row, KiCad's orientation. Land patterns follow the IPC-7351
+//! construction for gull-wing leads: the outer pad span is the lead span plus a toe
+//! fillet on each side, the inner gap is the span less both feet and a heel fillet each,
+//! and pads are as wide as the lead plus a side fillet.
+//!
+//! Lead span, foot length, lead width and pitch are `Toleranced`: with a datasheet's
+//! tolerances the land is built from the lead envelope (see `tolerance::ipc_land`) and the
+//! description records the ranges it assumed. Plain numbers are exact and give the nominal
+//! construction.
+//!
+//! `Soic::from_jedec` takes the dimensions from the package outline library (see
+//! `package_outlines`), so `Soic::from_jedec("MS-012-AA", 8)` is the same footprint as
+//! `Soic::new(8, 1.27, 4.9, 3.9)` with the MS-012 leads and height.
+
+use compact_str::ToCompactString;
+
+use crate::assembly_process::{default_process, AssemblyProcess, ProcessSet};
+use crate::board_interface::{
+    BoardComposableObject, FontSettings, FpText, FpTextType, GraphicElement, GraphicType, Model3D, PadDescriptor,
+    PadShape, PadType, Rectangle, Stroke, StrokeType, TentingSettings, TentingType,
+};
+use crate::chip::ChipFillets;
+use crate::courtyard::Courtyard;
+use crate::describe::{Density, DescriptionContext};
+use crate::functional_types::FunctionalType;
+use crate::ids::fresh_uuid;
+use crate::layer_type::{LayerType, PadLayers, Side};
+use crate::package_outlines::{chamfered_fab_outline, OutlineError, OutlineFamily, OutlineLibrary, PackageOutline};
+use crate::pin1::{pin1_marker, PackageFamily, Pin1Style};
+use crate::roles::{Edge, ElementRole};
+use crate::routing_hint::{outward_direction, RoutingHint};
+use crate::tolerance::{describe_tolerances, ipc_land, ManufacturingTolerances, Toleranced};
+
+const SILK_LINE_WIDTH: f32 = 0.12;
+/// Clearance from silkscreen line centers to pad copper
+const SILK_PAD_CLEARANCE: f32 = 0.2;
+/// Bodies wider than this get KiCad's "W" (wide body) suffix
+const WIDE_BODY: f32 = 5.0;
+/// Finest pitch (mm) wave soldered on the bottom side
+const WAVE_MIN_PITCH: f32 = 1.27;
+
+/// Fillet goals for gull-wing leads at IPC-7351 nominal density
+pub const GULL_WING_FILLETS: ChipFillets = ChipFillets { toe: 0.35, heel: 0.35, side: 0.03 };
+
+/// IPC-7351 gull-wing fillet goals at `density`, for pitches over 0.625 mm
+pub fn gull_wing_fillets(density: Density) -> ChipFillets {
+    match density {
+        Density::Least => ChipFillets { toe: 0.15, heel: 0.25, side: 0.01 },
+        Density::Nominal => GULL_WING_FILLETS,
+        Density::Most => ChipFillets { toe: 0.55, heel: 0.45, side: 0.05 },
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Soic {
+    pub functional_type: FunctionalType,
+    /// Total pin count, even
+    pub pins: usize,
+    pub pitch: Toleranced,
+    /// Body size along the rows (Y)
+    pub body_length: f32,
+    /// Body size across the rows (X)
+    pub body_width: f32,
+    /// Toe to toe across the rows
+    pub lead_span: Toleranced,
+    /// Foot length of each lead
+    pub lead_length: Toleranced,
+    pub lead_width: Toleranced,
+    /// Maximum seated height, used for the description
+    pub height: f32,
+    pub fillets: ChipFillets,
+    /// Board tolerances the land allows for when the leads are toleranced
+    pub tolerances: ManufacturingTolerances,
+    pub pin1: Pin1Style,
+    /// Outline code the dimensions came from, if any
+    pub jedec: Option<String>,
+}
+
+impl Soic {
+    /// A SOIC with MS-012 style leads: 0.835 mm feet, 0.41 mm wide, spanning 2.1 mm more
+    /// than the body. Set the real leads with `with_leads`.
+    pub fn new(pins: usize, pitch: impl Into<Toleranced>, body_length: f32, body_width: f32) -> Self {
+        Self {
+            functional_type: FunctionalType::IntegratedCircuit(String::new()),
+            pins,
+            pitch: pitch.into(),
+            body_length,
+            body_width,
+            lead_span: Toleranced::exact(body_width + 2.1),
+            lead_length: Toleranced::exact(0.835),
+            lead_width: Toleranced::exact(0.41),
+            height: 1.75,
+            fillets: GULL_WING_FILLETS,
+            tolerances: ManufacturingTolerances::default(),
+            pin1: Pin1Style::kicad_default(PackageFamily::Soic),
+            jedec: None,
+        }
+    }
+
+    /// The SOIC of a JEDEC code in the built-in outline library, e.g. "MS-012-AA"
+    pub fn from_jedec(code: &str, pins: usize) -> Result<Self, OutlineError> {
+        Self::from_outline(OutlineLibrary::builtin().get_with_pins(code, pins)?, pins)
+    }
+
+    /// The SOIC of an outline from any library
+    pub fn from_outline(outline: &PackageOutline, pins: usize) -> Result<Self, OutlineError> {
+        if outline.family != OutlineFamily::Soic {
+            return Err(OutlineError::WrongFamily { code: outline.code.clone(), family: outline.family, expected: OutlineFamily::Soic });
+        }
+        if !outline.pins.contains(&pins) {
+            return Err(OutlineError::PinCount { code: outline.code.clone(), pins, defined: outline.pins.clone() });
+        }
+        let mut soic = Self::new(pins, outline.pitch, outline.body_length, outline.body_width)
+            .with_leads(outline.lead_span, outline.lead_length, outline.lead_width)
+            .with_height(outline.height);
+        soic.jedec = Some(outline.code.clone());
+        Ok(soic)
+    }
+
+    pub fn with_functional_type(mut self, functional_type: FunctionalType) -> Self {
+        self.functional_type = functional_type;
+        self
+    }
+
+    /// Toe to toe span, foot length and width of the leads
+    pub fn with_leads(mut self, span: impl Into<Toleranced>, length: impl Into<Toleranced>, width: impl Into<Toleranced>) -> Self {
+        self.lead_span = span.into();
+        self.lead_length = length.into();
+        self.lead_width = width.into();
+        self
+    }
+
+    pub fn with_height(mut self, height: f32) -> Self {
+        self.height = height;
+        self
+    }
+
+    pub fn with_fillets(mut self, fillets: ChipFillets) -> Self {
+        self.fillets = fillets;
+        self
+    }
+
+    pub fn with_tolerances(mut self, tolerances: ManufacturingTolerances) -> Self {
+        self.tolerances = tolerances;
+        self
+    }
+
+    pub fn with_pin1(mut self, pin1: Pin1Style) -> Self {
+        self.pin1 = pin1;
+        self
+    }
+
+    /// Pad size (along X, along Y) and the X distance of each row of pad centers from the
+    /// origin
+    pub fn land_pattern(&self) -> ((f32, f32), f32) {
+        let land = ipc_land(self.lead_span, self.lead_length, self.lead_width, self.fillets, self.tolerances);
+        ((land.pad_length(), land.width), land.row_offset())
+    }
+
+    /// Lead ranges and board tolerances the land assumed, `None` for exact leads
+    fn tolerance_note(&self) -> Option<String> {
+        let leads = [("lead span", self.lead_span), ("lead length", self.lead_length), ("lead width", self.lead_width)];
+        describe_tolerances(&leads, self.tolerances)
+    }
+
+    /// Center of pin `index` (0 based)
+    fn pad_position(&self, index: usize, row_offset: f32) -> (f32, f32) {
+        let per_row = self.pins / 2;
+        let pitch = self.pitch.nominal;
+        let top = -(per_row as f32 - 1.0) * pitch / 2.0;
+        if index < per_row {
+            (-row_offset, top + index as f32 * pitch)
+        } else {
+            (row_offset, top + (self.pins - 1 - index) as f32 * pitch)
+        }
+    }
+
+    /// Body and pads together
+    fn extent(&self) -> Rectangle {
+        let ((pad_x, pad_y), center) = self.land_pattern();
+        let half_x = (center + pad_x / 2.0).max(self.body_width / 2.0);
+        let half_y = ((self.pins / 2) as f32 - 1.0).max(0.0) * self.pitch.nominal / 2.0 + pad_y / 2.0;
+        let half_y = half_y.max(self.body_length / 2.0);
+        Rectangle { min_x: -half_x, min_y: -half_y, max_x: half_x, max_y: half_y }
+    }
+
+    fn dimensions(&self) -> String {
+        format!("{}x{}mm_P{}mm", mm(self.body_width), mm(self.body_length), mm(self.pitch.nominal))
+    }
+}
+
+/// Millimeters as KiCad names print them, without trailing zeros
+fn mm(value: f32) -> String {
+    format!("{}", (value * 100.0).round() / 100.0)
+}
+
+impl BoardComposableObject for Soic {
+    fn is_smt(&self) -> bool {
+        true
+    }
+
+    fn is_electrical(&self) -> bool {
+        true
+    }
+
+    fn terminal_count(&self) -> usize {
+        self.pins
+    }
+
+    /// Reflow and the iron, and the wave at 1.27 mm pitch, with thieving pads trailing
+    /// each row
+    fn process(&self) -> ProcessSet {
+        let process = default_process(self);
+        if self.pitch.nominal >= WAVE_MIN_PITCH - f32::EPSILON { process.with(AssemblyProcess::Wave) } else { process }
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        self.functional_type.clone()
+    }
+
+    /// KiCad's "SOIC-8_3.9x4.9mm_P1.27mm", with a "W" after the pin count for wide bodies
+    fn footprint_name(&self) -> String {
+        let wide = if self.body_width > WIDE_BODY { "W" } else { "" };
+        format!("SOIC-{}{}_{}", self.pins, wide, self.dimensions())
+    }
+
+    fn library_name(&self) -> String {
+        "Package_SO".to_string()
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        let (half_x, half_y) = (self.body_width / 2.0, self.body_length / 2.0);
+        Rectangle { min_x: -half_x, min_y: -half_y, max_x: half_x, max_y: half_y }
+    }
+
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        let (size, center) = self.land_pattern();
+        (0..self.pins)
+            .map(|index| (index, self.pad_position(index, center)))
+            .map(|(index, position)| PadDescriptor {
+                number: (index + 1).to_compact_string(),
+                pin_function: None,
+                pad_type: PadType::SMD,
+                shape: PadShape::RoundRect,
+                position,
+                rotation: None,
+                size,
+                drill_size: None,
+                drill_slot: None,
+                layers: PadLayers::smd_front(),
+                roundrect_ratio: Some(0.25),
+                tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+                padstack: None,
+                fab_property: None,
+                solder_mask_margin: None,
+                routing_hint: Some(RoutingHint::outward(outward_direction(position, size))),
+                uuid: fresh_uuid(),
+            })
+            .collect()
+    }
+
+    /// Custom fillets follow no IPC density level, so their descriptions leave it out
+    fn description_context(&self) -> Option<DescriptionContext> {
+        let density = [Density::Nominal, Density::Least, Density::Most]
+            .into_iter()
+            .find(|&density| self.fillets == gull_wing_fillets(density));
+        Some(
+            DescriptionContext::new("soic", self.functional_type.clone(), "SOIC")
+                .with_density(density)
+                .with_option("SO")
+                .with_option(&mm(self.pitch.nominal))
+                .with_field("pins", self.pins)
+                .with_field("outline", self.jedec.clone().unwrap_or_default())
+                .with_field("body_width", mm(self.body_width))
+                .with_field("body_length", mm(self.body_length))
+                .with_field("pitch", mm(self.pitch.nominal))
+                .with_field("height", mm(self.height))
+                .with_field("tolerances", self.tolerance_note().unwrap_or_default()),
+        )
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        let text_y = self.extent().max_y + self.courtyard_margin() + 0.7;
+        let fab_size = (self.body_width * 0.25).clamp(0.5, 1.0);
+        let text = |text_type, text: String, position, layer: &str, size: f32| FpText {
+            text_type,
+            text,
+            position,
+            rotation: None,
+            layer: layer.into(),
+            uuid: fresh_uuid(),
+            font: FontSettings { size: (size, size), thickness: size * 0.15 },
+            hidden: false,
+            justify: None,
+            role: None,
+        };
+        vec![
+            text(FpTextType::Reference, "REF**".to_string(), (0.0, -text_y), "F.SilkS", 1.0),
+            text(FpTextType::Value, self.footprint_name(), (0.0, text_y), "F.Fab", 1.0),
+            text(FpTextType::User, "${REFERENCE}".to_string(), (0.0, 0.0), "F.Fab", fab_size),
+        ]
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        let body = self.bounding_box();
+        let mut elements = vec![chamfered_fab_outline(&body)];
+
+        // Silkscreen across the top and bottom of the body, cut back to clear the pad rows
+        // when they come close
+        let pads = self.pad_descriptors();
+        let ((pad_x, pad_y), center) = self.land_pattern();
+        let y = body.max_y + SILK_LINE_WIDTH / 2.0;
+        let pad_top = ((self.pins / 2) as f32 - 1.0).max(0.0) * self.pitch.nominal / 2.0 + pad_y / 2.0;
+        let mut half_x = body.max_x;
+        if y - SILK_LINE_WIDTH / 2.0 - pad_top < SILK_PAD_CLEARANCE {
+            half_x = half_x.min(center - pad_x / 2.0 - SILK_PAD_CLEARANCE);
+        }
+        if half_x > 0.0 {
+            for y in [-y, y] {
+                elements.push(GraphicElement {
+                    element_type: GraphicType::Line { start: (-half_x, y), end: (half_x, y) },
+                    layer: LayerType::SilkScreen,
+                    side: Side::Front,
+                    stroke: Stroke { width: SILK_LINE_WIDTH, stroke_type: StrokeType::Solid },
+                    uuid: fresh_uuid(),
+                    role: Some(ElementRole::SilkOutline(Some(if y < 0.0 { Edge::Top } else { Edge::Bottom }))),
+                });
+            }
+        }
+        elements.extend(pin1_marker(self.pin1, &body, &pads, "1"));
+        elements
+    }
+
+    fn model_3d(&self) -> Option<Model3D> {
+        Some(Model3D {
+            path: format!("${{KICAD9_3DMODEL_DIR}}/{}.3dshapes/{}.wrl", self.library_name(), self.footprint_name()),
+            offset: (0.0, 0.0, 0.0),
+            scale: (1.0, 1.0, 1.0),
+            rotation: (0.0, 0.0, 0.0),
+        })
+    }
+
+    /// Courtyard around the pads as well as the body
+    fn generate_courtyard(&self) -> Courtyard {
+        Courtyard::new(self.extent(), self.courtyard_margin())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_interface::PadNumber;
+
+    /// Number, position and size of a pad, without its UUID
+    type Pad = (PadNumber, (f32, f32), (f32, f32));
+
+    fn pads(footprint: &impl BoardComposableObject) -> Vec<Pad> {
+        footprint.pad_descriptors().into_iter().map(|pad| (pad.number, pad.position, pad.size)).collect()
+    }
+
+    #[test]
+    fn from_jedec_equals_the_soic_typed_by_hand() {
+        let generated = Soic::from_jedec("MS-012-AA", 8).unwrap();
+        let manual = Soic::new(8, 1.27, 4.9, 3.9).with_leads(6.0, 0.835, 0.41).with_height(1.75);
+        assert_eq!(pads(&generated), pads(&manual));
+        assert_eq!(generated.footprint_name(), manual.footprint_name());
+        // Only the description names where the dimensions came from
+        assert!(generated.description().unwrap().starts_with("SOIC, 8 Pin (MS-012-AA), 3.9x4.9 mm body"));
+        assert_eq!(generated.bounding_box(), manual.bounding_box());
+        assert_eq!(generated.generate_courtyard().bounds, manual.generate_courtyard().bounds);
+    }
+
+    #[test]
+    fn names_follow_kicad() {
+        assert_eq!(Soic::from_jedec("MS-012-AA", 8).unwrap().footprint_name(), "SOIC-8_3.9x4.9mm_P1.27mm");
+        assert_eq!(Soic::from_jedec("MS-013-AE", 28).unwrap().footprint_name(), "SOIC-28W_7.5x17.9mm_P1.27mm");
+    }
+
+    #[test]
+    fn only_soic_outlines_build_a_soic() {
+        assert!(matches!(
+            Soic::from_jedec("TO-236-AB", 3),
+            Err(OutlineError::WrongFamily { family: OutlineFamily::Sot, expected: OutlineFamily::Soic, .. })
+        ));
+        assert!(matches!(Soic::from_jedec("MS-012-AA", 14), Err(OutlineError::PinCount { .. })));
+        assert!(matches!(Soic::from_jedec("MS-012-AZ", 8), Err(OutlineError::Unknown { .. })));
+    }
+
+    #[test]
+    fn toleranced_leads_land_near_kicads_library() {
+        // KiCad's SOIC-8_3.9x4.9mm_P1.27mm, built from the MS-012 ranges the same way
+        let kicad = (1.95, 0.6, 2.475);
+        let error = |soic: &Soic| {
+            let ((length, width), center) = soic.land_pattern();
+            (length - kicad.0).abs() + (width - kicad.1).abs() + (center - kicad.2).abs()
+        };
+        let nominal = Soic::from_jedec("MS-012-AA", 8).unwrap();
+        let toleranced = nominal.clone().with_leads(
+            Toleranced::min_max(5.8, 6.2),
+            Toleranced::min_max(0.4, 1.27),
+            Toleranced::min_max(0.31, 0.51),
+        );
+        assert!(error(&toleranced) < error(&nominal) / 5.0, "{} vs {}", error(&toleranced), error(&nominal));
+        assert!(error(&toleranced) < 0.05);
+
+        let description = toleranced.description().unwrap();
+        assert!(
+            description.contains("lead span 5.8-6.2 mm, lead length 0.4-1.27 mm, lead width 0.31-0.51 mm, \
+                                  0.1 mm fabrication and 0.05 mm placement tolerance, IPC_7351 nominal"),
+            "{}",
+            description
+        );
+        assert!(!nominal.description().unwrap().contains("tolerance"));
+    }
+
+    #[test]
+    fn outlines_from_toml_build_like_builtin_ones() {
+        let library = OutlineLibrary::builtin()
+            .with_toml(
+                "[[outline]]\ncode = \"SOIC-8-VENDOR\"\nfamily = \"soic\"\npins = [8]\nbody_length = 4.9\nbody_width = 3.9\n\
+                 height = 1.7\npitch = 1.27\nlead_span = 6.0\nlead_length = 0.8\nlead_width = 0.42\n",
+            )
+            .unwrap();
+        let custom = Soic::from_outline(library.get("SOIC-8-VENDOR").unwrap(), 8).unwrap();
+        assert_eq!(custom.terminal_count(), 8);
+        assert!(custom.pad_descriptors().iter().all(|pad| pad.size.1 > 0.42), "pads are wider than the leads");
+    }
+}
@@ -0,0 +1,276 @@
+//! Solder bridge jumpers
+//!
+//! Two or three pads a small gap apart, closed with a blob of solder or opened by cutting
+//! a copper link. Outer pads are half-moons, round on the outside and flat towards the gap,
+//! as in KiCad's Jumper library; the middle pad of a three way jumper is a rectangle. One
+//! solder mask opening spans all pads so solder can bridge the gap, which leaves no mask
+//! web between them, so the footprints carry KiCad's `allow_soldermask_bridges` attribute
+//! and stay out of the BOM and placement files.
+//!
+//! Bridged variants ship closed: the outer pad's custom shape reaches across the gap into
+//! its neighbour. Names follow KiCad's "SolderJumper-2_P1.3mm_Open_RoundedPad1.0x1.5mm".
+
+use crate::board_interface::{
+    BoardComposableObject, FontSettings, FpText, FpTextType, GraphicElement, GraphicType, Model3D, PadDescriptor,
+    PadShape, PadType, Rectangle, Stroke, StrokeType,
+};
+use crate::functional_types::FunctionalType;
+use crate::geometry::{push_arc, Point};
+use crate::ids::fresh_uuid;
+use crate::layer_type::{LayerType, Side};
+use crate::pad_groups::PadGrouping;
+
+const SILK_LINE_WIDTH: f32 = 0.12;
+/// Gap between silkscreen and pad copper
+const SILK_PAD_CLEARANCE: f32 = 0.2;
+/// Largest deviation of the tessellated round pad ends from a true arc
+const ARC_TOLERANCE: f32 = 0.002;
+/// How far the closing link reaches into the neighbouring pad
+const LINK_OVERLAP: f32 = 0.2;
+/// Width of the closing link as a fraction of the pad height
+const LINK_WIDTH_RATIO: f32 = 0.4;
+
+/// Which pads of a jumper come joined by a copper link
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JumperBridge {
+    Open,
+    /// Pads 1 and 2, the only link a two way jumper has
+    Bridged12,
+    /// Pads 2 and 3 of a three way jumper
+    Bridged23,
+}
+
+/// Solder jumper with two or three pads; see the module documentation
+#[derive(Debug, Clone)]
+pub struct SolderJumper {
+    ways: usize,
+    bridge: JumperBridge,
+    /// Center to center pad distance
+    pub pitch: f32,
+    /// Width along the row by height of each pad; the height should be at least the width
+    pub pad_size: (f32, f32),
+}
+
+impl SolderJumper {
+    /// Two pads, optionally joined by a copper link
+    pub fn two_way(bridged: bool) -> Self {
+        let bridge = if bridged { JumperBridge::Bridged12 } else { JumperBridge::Open };
+        Self { ways: 2, bridge, pitch: 1.3, pad_size: (1.0, 1.5) }
+    }
+
+    /// Three pads, the middle one shared, e.g. to select one of two options
+    pub fn three_way(bridge: JumperBridge) -> Self {
+        Self { ways: 3, bridge, pitch: 1.3, pad_size: (1.0, 1.5) }
+    }
+
+    fn state_name(&self) -> &'static str {
+        match (self.bridge, self.ways) {
+            (JumperBridge::Open, _) => "Open",
+            (JumperBridge::Bridged12, 2) => "Bridged",
+            (JumperBridge::Bridged12, _) => "Bridged12",
+            (JumperBridge::Bridged23, _) => "Bridged23",
+        }
+    }
+
+    fn gap(&self) -> f32 {
+        self.pitch - self.pad_size.0
+    }
+
+    /// Total length of the pad row
+    fn span(&self) -> f32 {
+        (self.ways - 1) as f32 * self.pitch + self.pad_size.0
+    }
+
+    /// Silkscreen outline around the pads, on the line center
+    fn silk_bounds(&self) -> Rectangle {
+        let margin = SILK_PAD_CLEARANCE + SILK_LINE_WIDTH / 2.0;
+        let (half_x, half_y) = (self.span() / 2.0 + margin, self.pad_size.1 / 2.0 + margin);
+        Rectangle { min_x: -half_x, min_y: -half_y, max_x: half_x, max_y: half_y }
+    }
+
+    /// Half-moon outline with the flat side towards +X, plus the closing link when `linked`
+    fn half_moon(&self, linked: bool) -> Vec<(f32, f32)> {
+        let (width, height) = self.pad_size;
+        let radius = width / 2.0;
+        let straight = (height / 2.0 - radius).max(0.0);
+        let mut points = vec![Point::new(radius, -height / 2.0)];
+        if linked {
+            let (link_end, link_half) = (radius + self.gap() + LINK_OVERLAP, height * LINK_WIDTH_RATIO / 2.0);
+            points.extend([
+                Point::new(radius, -link_half),
+                Point::new(link_end, -link_half),
+                Point::new(link_end, link_half),
+                Point::new(radius, link_half),
+            ]);
+        }
+        points.extend([Point::new(radius, height / 2.0), Point::new(0.0, straight + radius)]);
+        push_arc(&mut points, Point::new(0.0, straight), radius, 90.0, 90.0, ARC_TOLERANCE);
+        points.push(Point::new(-radius, -straight));
+        push_arc(&mut points, Point::new(0.0, -straight), radius, 180.0, 90.0, ARC_TOLERANCE);
+        points.dedup_by(|b, a| a.distance_to(b) < 1e-6);
+        points.into_iter().map(|p| (round(p.x), round(p.y))).collect()
+    }
+
+    fn pad(number: &str, x: f32, shape: PadShape, size: (f32, f32), layers: &[&str]) -> PadDescriptor {
+        PadDescriptor::new(number, PadType::SMD, shape, (round(x), 0.0), size, layers.iter().copied())
+    }
+}
+
+/// Snap tessellated coordinates to 0.1 µm so the output has no float noise; adding zero
+/// turns -0 into 0
+fn round(value: f32) -> f32 {
+    (value * 1e4).round() / 1e4 + 0.0
+}
+
+fn mirrored(points: Vec<(f32, f32)>) -> Vec<(f32, f32)> {
+    points.into_iter().map(|(x, y)| (round(-x), y)).collect()
+}
+
+impl BoardComposableObject for SolderJumper {
+    fn is_smt(&self) -> bool {
+        true
+    }
+
+    fn is_electrical(&self) -> bool {
+        true
+    }
+
+    fn terminal_count(&self) -> usize {
+        self.ways
+    }
+
+    /// Jumpers are copper on the board, nothing to buy or place
+    fn is_assembled(&self) -> bool {
+        false
+    }
+
+    fn allows_soldermask_bridges(&self) -> bool {
+        true
+    }
+
+    /// Bridged variants tie the nets of the linked pads together
+    fn pad_grouping(&self) -> PadGrouping {
+        match self.bridge {
+            JumperBridge::Open => PadGrouping::new(),
+            JumperBridge::Bridged12 => PadGrouping::new().with_net_tie(&["1", "2"]),
+            JumperBridge::Bridged23 => PadGrouping::new().with_net_tie(&["2", "3"]),
+        }
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        FunctionalType::Jumper(format!("SolderJumper_{}_{}", self.ways, self.state_name()))
+    }
+
+    fn footprint_name(&self) -> String {
+        format!(
+            "SolderJumper-{}_P{}mm_{}_RoundedPad{:.1}x{:.1}mm",
+            self.ways,
+            self.pitch,
+            self.state_name(),
+            self.pad_size.0,
+            self.pad_size.1
+        )
+    }
+
+    fn library_name(&self) -> String {
+        "Jumper".to_string()
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        let bounds = self.silk_bounds();
+        let half_line = SILK_LINE_WIDTH / 2.0;
+        Rectangle {
+            min_x: bounds.min_x - half_line,
+            min_y: bounds.min_y - half_line,
+            max_x: bounds.max_x + half_line,
+            max_y: bounds.max_y + half_line,
+        }
+    }
+
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        let (width, height) = self.pad_size;
+        // The anchor is the rectangle between the round ends, inside the half-moon
+        let anchor = (width, (height - width).max(0.0));
+        let first_x = -((self.ways - 1) as f32) * self.pitch / 2.0;
+        let last_x = -first_x;
+        let copper = ["F.Cu", "F.Mask"];
+
+        let first = PadShape::Custom(self.half_moon(self.bridge == JumperBridge::Bridged12));
+        let last = PadShape::Custom(mirrored(self.half_moon(self.bridge == JumperBridge::Bridged23)));
+        let mut pads = vec![Self::pad("1", first_x, first, anchor, &copper)];
+        if self.ways == 3 {
+            pads.push(Self::pad("2", 0.0, PadShape::Rect, self.pad_size, &copper));
+        }
+        pads.push(Self::pad(&self.ways.to_string(), last_x, last, anchor, &copper));
+        // One mask opening over the whole row, so there is no web for solder to stop at
+        pads.push(Self::pad("", 0.0, PadShape::Rect, (round(self.span()), height), &["F.Mask"]));
+        pads
+    }
+
+    fn description(&self) -> Option<String> {
+        let bridge = match (self.bridge, self.ways) {
+            (JumperBridge::Open, _) => "open".to_string(),
+            (JumperBridge::Bridged12, 2) => "bridged with 1 copper strip".to_string(),
+            (JumperBridge::Bridged12, _) => "pads 1-2 bridged with 1 copper strip".to_string(),
+            (JumperBridge::Bridged23, _) => "pads 2-3 bridged with 1 copper strip".to_string(),
+        };
+        Some(format!(
+            "SMD Solder {}-pad Jumper, {}x{}mm rounded Pads, {}mm gap, {}",
+            self.ways,
+            self.pad_size.0,
+            self.pad_size.1,
+            round(self.gap()),
+            bridge
+        ))
+    }
+
+    fn tags(&self) -> Option<String> {
+        let state = if self.bridge == JumperBridge::Open { "open" } else { "bridged" };
+        Some(format!("net tie solder jumper {}", state))
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        let text_y = self.bounding_box().max_y + 1.0;
+        let text = |text_type, text: String, position, layer: &str| FpText {
+            text_type,
+            text,
+            position,
+            rotation: None,
+            layer: layer.into(),
+            uuid: fresh_uuid(),
+            font: FontSettings { size: (1.0, 1.0), thickness: 0.15 },
+            hidden: false,
+            justify: None,
+            role: None,
+        };
+        vec![
+            text(FpTextType::Reference, "REF**".to_string(), (0.0, -text_y), "F.SilkS"),
+            text(FpTextType::Value, self.footprint_name(), (0.0, text_y), "F.Fab"),
+        ]
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        let bounds = self.silk_bounds();
+        let corners = [
+            (bounds.min_x, bounds.min_y),
+            (bounds.max_x, bounds.min_y),
+            (bounds.max_x, bounds.max_y),
+            (bounds.min_x, bounds.max_y),
+        ];
+        (0..4)
+            .map(|i| GraphicElement {
+                element_type: GraphicType::Line { start: corners[i], end: corners[(i + 1) % 4] },
+                layer: LayerType::SilkScreen,
+                side: Side::Front,
+                stroke: Stroke { width: SILK_LINE_WIDTH, stroke_type: StrokeType::Solid },
+                uuid: fresh_uuid(),
+                role: None,
+            })
+            .collect()
+    }
+
+    /// Bare copper, there is no part to model
+    fn model_3d(&self) -> Option<Model3D> {
+        None
+    }
+}
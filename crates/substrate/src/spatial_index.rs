@@ -0,0 +1,618 @@
+//! Spatial index over board geometry
+//!
+//! `SpatialIndex` is a uniform grid of axis aligned bounding boxes keyed by any id. Items
+//! spanning several cells are stored in each of them; queries report every item once.
+//! A grid suits boards well: parts are of similar size and spread fairly evenly, and an
+//! item moves by removing and reinserting it without touching the rest of the index.
+//!
+//! The board keeps an index of component extents and one of pads, used by
+//! `components_near`, `pads_in_rect` and `nearest_pad`. It is brought up to date lazily
+//! on the first query after a change, and only the placements that actually changed are
+//! recomputed.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::rc::Rc;
+
+use crate::board::{Board, PlacedComponent};
+use crate::board_interface::{PadNumber, Rectangle};
+use crate::geometry::Point;
+use crate::layer_type::Side;
+
+/// Grid cell size of the board indexes in millimeters, a few small parts per cell
+pub const BOARD_INDEX_CELL_SIZE: f32 = 2.5;
+
+/// Arc tolerance of the pad outlines whose bounds are indexed
+const PAD_BOUNDS_TOLERANCE: f32 = 0.01;
+
+/// Distance from `point` to the nearest point of `rect`, zero inside it
+pub fn rectangle_distance(point: Point, rect: &Rectangle) -> f32 {
+    let dx = (rect.min_x - point.x).max(point.x - rect.max_x).max(0.0);
+    let dy = (rect.min_y - point.y).max(point.y - rect.max_y).max(0.0);
+    dx.hypot(dy)
+}
+
+fn rectangles_overlap(a: &Rectangle, b: &Rectangle) -> bool {
+    a.min_x <= b.max_x && b.min_x <= a.max_x && a.min_y <= b.max_y && b.min_y <= a.max_y
+}
+
+/// Smallest rectangle holding all `points`, `None` when there are none
+pub fn bounds_of(points: impl IntoIterator<Item = Point>) -> Option<Rectangle> {
+    points.into_iter().fold(None, |bounds, p| {
+        Some(match bounds {
+            None => Rectangle { min_x: p.x, min_y: p.y, max_x: p.x, max_y: p.y },
+            Some(r) => Rectangle { min_x: r.min_x.min(p.x), min_y: r.min_y.min(p.y), max_x: r.max_x.max(p.x), max_y: r.max_y.max(p.y) },
+        })
+    })
+}
+
+/// Distance along a ray at which it enters `rect`, `None` if it misses within `max_distance`.
+/// `direction` must be a unit vector; a ray starting inside enters at 0.
+fn ray_entry(origin: Point, direction: Point, rect: &Rectangle, max_distance: f32) -> Option<f32> {
+    let (mut enter, mut exit) = (0.0f32, max_distance);
+    for (o, d, lo, hi) in [(origin.x, direction.x, rect.min_x, rect.max_x), (origin.y, direction.y, rect.min_y, rect.max_y)] {
+        if d.abs() < f32::EPSILON {
+            if o < lo || o > hi {
+                return None;
+            }
+        } else {
+            let (a, b) = ((lo - o) / d, (hi - o) / d);
+            enter = enter.max(a.min(b));
+            exit = exit.min(a.max(b));
+            if enter > exit {
+                return None;
+            }
+        }
+    }
+    Some(enter)
+}
+
+/// Cells on the square ring `ring` cells away from (cx, cy)
+fn ring_cells(cx: i32, cy: i32, ring: i32) -> impl Iterator<Item = (i32, i32)> {
+    let rows = (cx - ring..=cx + ring).flat_map(move |x| {
+        let bottom = (ring > 0).then_some((x, cy + ring));
+        std::iter::once((x, cy - ring)).chain(bottom)
+    });
+    let columns = (cy - ring + 1..cy + ring).flat_map(move |y| [(cx - ring, y), (cx + ring, y)]);
+    rows.chain(columns)
+}
+
+/// Uniform grid of bounding boxes keyed by `K`
+#[derive(Debug, Clone)]
+pub struct SpatialIndex<K> {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<K>>,
+    items: HashMap<K, Rectangle>,
+    /// Cells that have ever held an item (min x, min y, max x, max y); bounds the nearest
+    /// item search and only ever grows
+    extent: Option<(i32, i32, i32, i32)>,
+}
+
+impl<K: Clone + Eq + Hash> SpatialIndex<K> {
+    pub fn new(cell_size: f32) -> Self {
+        Self { cell_size, cells: HashMap::new(), items: HashMap::new(), extent: None }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn bounds(&self, key: &K) -> Option<&Rectangle> {
+        self.items.get(key)
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.items.clear();
+        self.extent = None;
+    }
+
+    fn cell_of(&self, point: Point) -> (i32, i32) {
+        ((point.x / self.cell_size).floor() as i32, (point.y / self.cell_size).floor() as i32)
+    }
+
+    /// Cells covered by `rect` as (min x, min y, max x, max y)
+    fn cell_range(&self, rect: &Rectangle) -> (i32, i32, i32, i32) {
+        let (min_x, min_y) = self.cell_of(Point::new(rect.min_x, rect.min_y));
+        let (max_x, max_y) = self.cell_of(Point::new(rect.max_x, rect.max_y));
+        (min_x, min_y, max_x, max_y)
+    }
+
+    /// Add an item, replacing the bounds of an existing item with the same key
+    pub fn insert(&mut self, key: K, bounds: Rectangle) {
+        self.remove(&key);
+        let (min_x, min_y, max_x, max_y) = self.cell_range(&bounds);
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                self.cells.entry((x, y)).or_default().push(key.clone());
+            }
+        }
+        self.extent = Some(match self.extent {
+            None => (min_x, min_y, max_x, max_y),
+            Some((a, b, c, d)) => (a.min(min_x), b.min(min_y), c.max(max_x), d.max(max_y)),
+        });
+        self.items.insert(key, bounds);
+    }
+
+    /// Remove an item, returning its bounds
+    pub fn remove(&mut self, key: &K) -> Option<Rectangle> {
+        let bounds = self.items.remove(key)?;
+        let (min_x, min_y, max_x, max_y) = self.cell_range(&bounds);
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                if let Some(cell) = self.cells.get_mut(&(x, y)) {
+                    cell.retain(|k| k != key);
+                    if cell.is_empty() {
+                        self.cells.remove(&(x, y));
+                    }
+                }
+            }
+        }
+        Some(bounds)
+    }
+
+    /// Items whose bounds overlap `rect`, touching edges included
+    pub fn query_rect(&self, rect: &Rectangle) -> Vec<K> {
+        let (min_x, min_y, max_x, max_y) = self.cell_range(rect);
+        let mut found = Vec::new();
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                for key in self.cells.get(&(x, y)).into_iter().flatten() {
+                    let bounds = &self.items[key];
+                    // Report an item only from the first cell it shares with the query
+                    let (item_x, item_y, _, _) = self.cell_range(bounds);
+                    if (item_x.max(min_x), item_y.max(min_y)) == (x, y) && rectangles_overlap(bounds, rect) {
+                        found.push(key.clone());
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    /// Items whose bounds contain `point`
+    pub fn query_point(&self, point: Point) -> Vec<K> {
+        self.query_rect(&Rectangle { min_x: point.x, min_y: point.y, max_x: point.x, max_y: point.y })
+    }
+
+    /// Items whose bounds come within `radius` of `center`
+    pub fn query_radius(&self, center: Point, radius: f32) -> Vec<K> {
+        let rect = Rectangle { min_x: center.x - radius, min_y: center.y - radius, max_x: center.x + radius, max_y: center.y + radius };
+        let mut found = self.query_rect(&rect);
+        found.retain(|key| rectangle_distance(center, &self.items[key]) <= radius);
+        found
+    }
+
+    /// Items whose bounds a ray from `origin` along `direction` enters within
+    /// `max_distance`, nearest first, with the distance at which it enters them
+    pub fn query_ray(&self, origin: Point, direction: Point, max_distance: f32) -> Vec<(K, f32)> {
+        let length = direction.x.hypot(direction.y);
+        if self.is_empty() || length == 0.0 || !max_distance.is_finite() {
+            return Vec::new();
+        }
+        let direction = Point::new(direction.x / length, direction.y / length);
+
+        // Walk the cells the ray passes through (Amanatides and Woo)
+        let mut cell = self.cell_of(origin);
+        let axis = |o: f32, d: f32, c: i32| -> (i32, f32, f32) {
+            if d.abs() < f32::EPSILON {
+                return (0, f32::INFINITY, f32::INFINITY);
+            }
+            let step = if d > 0.0 { 1 } else { -1 };
+            let boundary = (c + i32::from(d > 0.0)) as f32 * self.cell_size;
+            (step, (boundary - o) / d, self.cell_size / d.abs())
+        };
+        let (step_x, mut next_x, delta_x) = axis(origin.x, direction.x, cell.0);
+        let (step_y, mut next_y, delta_y) = axis(origin.y, direction.y, cell.1);
+
+        let mut seen = HashSet::new();
+        let mut hits = Vec::new();
+        loop {
+            for key in self.cells.get(&cell).into_iter().flatten() {
+                if seen.insert(key.clone())
+                    && let Some(distance) = ray_entry(origin, direction, &self.items[key], max_distance)
+                {
+                    hits.push((key.clone(), distance));
+                }
+            }
+            if next_x.min(next_y) > max_distance {
+                break;
+            }
+            if next_x < next_y {
+                cell.0 += step_x;
+                next_x += delta_x;
+            } else {
+                cell.1 += step_y;
+                next_y += delta_y;
+            }
+        }
+        hits.sort_by(|a, b| a.1.total_cmp(&b.1));
+        hits
+    }
+
+    /// The item nearest to `point` by `distance`, which must never be less than the
+    /// distance to the item's bounds (e.g. the distance to a point inside them)
+    pub fn nearest_by(&self, point: Point, distance: impl Fn(&K, &Rectangle) -> f32) -> Option<(K, f32)> {
+        let (min_x, min_y, max_x, max_y) = self.extent?;
+        let (cx, cy) = self.cell_of(point);
+        let last_ring = (cx - min_x).abs().max((max_x - cx).abs()).max((cy - min_y).abs()).max((max_y - cy).abs());
+
+        let mut best: Option<(K, f32)> = None;
+        for ring in 0..=last_ring {
+            // Items not yet visited lie in this ring or beyond, more than ring - 1 cells away
+            if best.as_ref().is_some_and(|(_, d)| *d <= (ring - 1) as f32 * self.cell_size) {
+                break;
+            }
+            for cell in ring_cells(cx, cy, ring) {
+                for key in self.cells.get(&cell).into_iter().flatten() {
+                    let bounds = &self.items[key];
+                    let bound = best.as_ref().map_or(f32::INFINITY, |(_, d)| *d);
+                    if rectangle_distance(point, bounds) >= bound {
+                        continue;
+                    }
+                    let d = distance(key, bounds);
+                    if d < bound {
+                        best = Some((key.clone(), d));
+                    }
+                }
+            }
+        }
+        best
+    }
+}
+
+/// A pad in board coordinates
+#[derive(Debug, Clone)]
+pub struct BoardPad {
+    pub reference: String,
+    pub number: PadNumber,
+    /// Position of the pad in the footprint's `pad_descriptors`
+    pub index: usize,
+    pub center: Point,
+    pub bounds: Rectangle,
+    /// Side the footprint is mounted on
+    pub side: Side,
+}
+
+/// What an indexed placement was computed from, to tell whether it changed
+#[derive(Debug, Clone, PartialEq)]
+struct PlacementSnapshot {
+    /// Address of the shared component, so swapping the footprint counts as a change
+    component: usize,
+    placement: ((f64, f64), f32),
+    side: Side,
+    /// Position in the board's component list
+    index: usize,
+    pad_count: usize,
+}
+
+/// The board's component and pad indexes. Components are keyed by reference; references
+/// are expected to be unique.
+#[derive(Debug, Clone)]
+pub(crate) struct BoardSpatial {
+    /// Set by every mutable access to placements; queries refresh first
+    pub(crate) dirty: bool,
+    snapshots: HashMap<String, PlacementSnapshot>,
+    components: SpatialIndex<String>,
+    pads: SpatialIndex<(String, usize)>,
+    pad_data: HashMap<(String, usize), BoardPad>,
+}
+
+impl Default for BoardSpatial {
+    fn default() -> Self {
+        Self {
+            dirty: true,
+            snapshots: HashMap::new(),
+            components: SpatialIndex::new(BOARD_INDEX_CELL_SIZE),
+            pads: SpatialIndex::new(BOARD_INDEX_CELL_SIZE),
+            pad_data: HashMap::new(),
+        }
+    }
+}
+
+impl BoardSpatial {
+    fn remove_component(&mut self, reference: &str, snapshot: &PlacementSnapshot) {
+        self.components.remove(&reference.to_string());
+        for index in 0..snapshot.pad_count {
+            let key = (reference.to_string(), index);
+            self.pads.remove(&key);
+            self.pad_data.remove(&key);
+        }
+    }
+
+    /// Pads and footprint extent (body and pads) of one placement
+    fn add_component(&mut self, board: &Board, placed: &PlacedComponent) -> usize {
+        let to_board = |p: Point| board.board_point(placed, p);
+        let body = placed.component.bounding_box();
+        let corners = [(body.min_x, body.min_y), (body.max_x, body.min_y), (body.max_x, body.max_y), (body.min_x, body.max_y)];
+        let mut extent = bounds_of(corners.into_iter().map(|c| to_board(Point::from(c))));
+
+        let pads = placed.component.pad_descriptors();
+        for (index, pad) in pads.iter().enumerate() {
+            let center = to_board(Point::from(pad.position));
+            let bounds = bounds_of(pad.outline_polygon(PAD_BOUNDS_TOLERANCE).into_iter().map(to_board))
+                .unwrap_or(Rectangle { min_x: center.x, min_y: center.y, max_x: center.x, max_y: center.y });
+            extent = bounds_of(
+                extent
+                    .iter()
+                    .chain([&bounds])
+                    .flat_map(|r| [Point::new(r.min_x, r.min_y), Point::new(r.max_x, r.max_y)]),
+            );
+            let key = (placed.reference.clone(), index);
+            self.pads.insert(key.clone(), bounds.clone());
+            let pad = BoardPad { reference: placed.reference.clone(), number: pad.number.clone(), index, center, bounds, side: placed.side };
+            self.pad_data.insert(key, pad);
+        }
+        if let Some(extent) = extent {
+            self.components.insert(placed.reference.clone(), extent);
+        }
+        pads.len()
+    }
+
+    /// Bring the indexes in line with the board, recomputing only changed placements
+    fn refresh(&mut self, board: &Board) {
+        if !self.dirty {
+            return;
+        }
+        let mut present = HashSet::new();
+        for (index, placed) in board.components().iter().enumerate() {
+            present.insert(placed.reference.as_str());
+            let component = Rc::as_ptr(&placed.component) as *const () as usize;
+            let placement = board.exported_placement(placed);
+            match self.snapshots.get_mut(&placed.reference) {
+                Some(snapshot) if snapshot.component == component && snapshot.placement == placement && snapshot.side == placed.side => {
+                    snapshot.index = index;
+                }
+                previous => {
+                    if let Some(previous) = previous.cloned() {
+                        self.remove_component(&placed.reference, &previous);
+                    }
+                    let pad_count = self.add_component(board, placed);
+                    let snapshot = PlacementSnapshot { component, placement, side: placed.side, index, pad_count };
+                    self.snapshots.insert(placed.reference.clone(), snapshot);
+                }
+            }
+        }
+        let removed: Vec<String> = self.snapshots.keys().filter(|r| !present.contains(r.as_str())).cloned().collect();
+        for reference in removed {
+            if let Some(snapshot) = self.snapshots.remove(&reference) {
+                self.remove_component(&reference, &snapshot);
+            }
+        }
+        self.dirty = false;
+    }
+}
+
+impl Board {
+    /// Components whose footprint (body and pads) comes within `radius` of `point`, in
+    /// board order
+    pub fn components_near(&self, point: Point, radius: f32) -> Vec<&PlacedComponent> {
+        let mut spatial = self.spatial.borrow_mut();
+        spatial.refresh(self);
+        let mut indices: Vec<usize> =
+            spatial.components.query_radius(point, radius).iter().map(|reference| spatial.snapshots[reference].index).collect();
+        indices.sort_unstable();
+        indices.into_iter().map(|index| &self.components()[index]).collect()
+    }
+
+    /// Pads whose outline bounds overlap `rect`, in board and pad order
+    pub fn pads_in_rect(&self, rect: &Rectangle) -> Vec<BoardPad> {
+        let mut spatial = self.spatial.borrow_mut();
+        spatial.refresh(self);
+        let mut keys = spatial.pads.query_rect(rect);
+        keys.sort_by_key(|(reference, index)| (spatial.snapshots[reference].index, *index));
+        keys.iter().map(|key| spatial.pad_data[key].clone()).collect()
+    }
+
+    /// The pad whose center is nearest to `point`
+    pub fn nearest_pad(&self, point: Point) -> Option<BoardPad> {
+        let mut spatial = self.spatial.borrow_mut();
+        spatial.refresh(self);
+        let (key, _) = spatial.pads.nearest_by(point, |key, _| spatial.pad_data[key].center.distance_to(&point))?;
+        Some(spatial.pad_data[&key].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer_type::Side;
+    use crate::presets;
+
+    const COLUMNS: usize = 20;
+
+    /// Small deterministic generator so failures reproduce
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> f32 {
+            self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (self.0 >> 40) as f32 / (1u64 << 24) as f32
+        }
+    }
+
+    fn rect(min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> Rectangle {
+        Rectangle { min_x, min_y, max_x, max_y }
+    }
+
+    /// 400 0603 resistors on a 3 mm grid, jittered and turned in steps of 90 degrees
+    fn jittered_board(rng: &mut Lcg) -> Board {
+        let mut board = Board::new("spatial_index").with_rectangular_outline(COLUMNS as f32 * 3.0, COLUMNS as f32 * 3.0);
+        for i in 0..COLUMNS * COLUMNS {
+            let (x, y) = ((i % COLUMNS) as f32 * 3.0 + 1.5, (i / COLUMNS) as f32 * 3.0 + 1.5);
+            let rotation = (rng.next() * 4.0).floor() * 90.0;
+            board.place(&format!("R{}", i + 1), presets::resistor_0603("10k"), (x + rng.next() - 0.5, y + rng.next() - 0.5), rotation).unwrap();
+        }
+        board
+    }
+
+    /// Every pad of the board with its center and outline bounds, by brute force
+    fn all_pads(board: &Board) -> Vec<(String, usize, Point, Rectangle)> {
+        let mut pads = Vec::new();
+        for placed in board.components() {
+            for (index, pad) in placed.component.pad_descriptors().iter().enumerate() {
+                let outline = pad.outline_polygon(PAD_BOUNDS_TOLERANCE).into_iter().map(|p| board.board_point(placed, p));
+                let center = board.board_point(placed, Point::from(pad.position));
+                pads.push((placed.reference.clone(), index, center, bounds_of(outline).unwrap()));
+            }
+        }
+        pads
+    }
+
+    fn brute_components_near(board: &Board, point: Point, radius: f32) -> Vec<String> {
+        let pads = all_pads(board);
+        board
+            .components()
+            .iter()
+            .filter(|placed| {
+                let body = placed.component.bounding_box();
+                let corners = [(body.min_x, body.min_y), (body.max_x, body.max_y), (body.min_x, body.max_y), (body.max_x, body.min_y)];
+                let mut points: Vec<Point> = corners.into_iter().map(|c| board.board_point(placed, Point::from(c))).collect();
+                for (_, _, _, bounds) in pads.iter().filter(|(reference, ..)| *reference == placed.reference) {
+                    points.extend([Point::new(bounds.min_x, bounds.min_y), Point::new(bounds.max_x, bounds.max_y)]);
+                }
+                rectangle_distance(point, &bounds_of(points).unwrap()) <= radius
+            })
+            .map(|placed| placed.reference.clone())
+            .collect()
+    }
+
+    /// Board queries at random points must give the brute force answers
+    fn assert_matches_brute_force(board: &Board, rng: &mut Lcg) {
+        let size = COLUMNS as f32 * 3.0;
+        let pads = all_pads(board);
+        for _ in 0..50 {
+            let point = Point::new(rng.next() * size, rng.next() * size);
+
+            let found: Vec<String> = board.components_near(point, 2.0).iter().map(|placed| placed.reference.clone()).collect();
+            assert_eq!(found, brute_components_near(board, point, 2.0), "components_near at {:?}", point);
+
+            let query = rect(point.x, point.y, point.x + 4.0, point.y + 2.5);
+            let expected: Vec<(String, usize)> = pads
+                .iter()
+                .filter(|(.., bounds)| rectangles_overlap(bounds, &query))
+                .map(|(reference, index, ..)| (reference.clone(), *index))
+                .collect();
+            let found: Vec<(String, usize)> = board.pads_in_rect(&query).into_iter().map(|pad| (pad.reference, pad.index)).collect();
+            assert_eq!(found, expected, "pads_in_rect {:?}", query);
+
+            // Ties may pick a different pad, the distance must match
+            let nearest = pads.iter().map(|(_, _, center, _)| center.distance_to(&point)).fold(f32::INFINITY, f32::min);
+            assert_eq!(board.nearest_pad(point).unwrap().center.distance_to(&point), nearest, "nearest_pad to {:?}", point);
+        }
+    }
+
+    #[test]
+    fn items_spanning_cells_are_reported_once() {
+        let mut index = SpatialIndex::new(1.0);
+        index.insert("wide", rect(0.2, 0.2, 3.8, 0.8));
+        index.insert("small", rect(5.1, 5.1, 5.4, 5.4));
+        assert_eq!(index.query_rect(&rect(-1.0, -1.0, 10.0, 10.0)).len(), 2);
+        assert_eq!(index.query_rect(&rect(1.0, 0.0, 3.0, 1.0)), vec!["wide"]);
+        assert_eq!(index.query_point(Point::new(5.2, 5.3)), vec!["small"]);
+        assert!(index.query_point(Point::new(4.5, 4.5)).is_empty());
+    }
+
+    #[test]
+    fn touching_edges_overlap() {
+        let mut index = SpatialIndex::new(1.0);
+        index.insert(1, rect(0.0, 0.0, 1.0, 1.0));
+        assert_eq!(index.query_rect(&rect(1.0, 1.0, 2.0, 2.0)), vec![1]);
+        assert!(index.query_rect(&rect(1.01, 0.0, 2.0, 1.0)).is_empty());
+    }
+
+    #[test]
+    fn reinserting_replaces_and_removing_forgets() {
+        let mut index = SpatialIndex::new(1.0);
+        index.insert("a", rect(0.0, 0.0, 0.5, 0.5));
+        index.insert("a", rect(8.0, 8.0, 8.5, 8.5));
+        assert_eq!(index.len(), 1);
+        assert!(index.query_point(Point::new(0.2, 0.2)).is_empty());
+        assert_eq!(index.query_point(Point::new(8.2, 8.2)), vec!["a"]);
+        assert_eq!(index.remove(&"a"), Some(rect(8.0, 8.0, 8.5, 8.5)));
+        assert!(index.is_empty() && index.query_rect(&rect(-10.0, -10.0, 10.0, 10.0)).is_empty());
+        assert_eq!(index.remove(&"a"), None);
+    }
+
+    #[test]
+    fn radius_queries_measure_to_the_bounds() {
+        let mut index = SpatialIndex::new(1.0);
+        index.insert("corner", rect(3.0, 4.0, 5.0, 5.0));
+        // 3-4-5 triangle to the nearest corner
+        assert!(index.query_radius(Point::new(0.0, 0.0), 4.99).is_empty());
+        assert_eq!(index.query_radius(Point::new(0.0, 0.0), 5.0), vec!["corner"]);
+    }
+
+    #[test]
+    fn rays_report_hits_nearest_first() {
+        let mut index = SpatialIndex::new(1.0);
+        index.insert("far", rect(7.0, -0.5, 8.0, 0.5));
+        index.insert("near", rect(2.0, -0.5, 3.0, 0.5));
+        index.insert("off axis", rect(4.0, 2.0, 5.0, 3.0));
+        let hits = index.query_ray(Point::new(0.0, 0.0), Point::new(2.0, 0.0), 20.0);
+        assert_eq!(hits, vec![("near", 2.0), ("far", 7.0)]);
+        assert_eq!(index.query_ray(Point::new(0.0, 0.0), Point::new(1.0, 0.0), 5.0), vec![("near", 2.0)]);
+        assert_eq!(index.query_ray(Point::new(2.5, 0.0), Point::new(-1.0, 0.0), 5.0), vec![("near", 0.0)]);
+        let diagonal = index.query_ray(Point::new(0.0, -2.0), Point::new(1.0, 1.0), 20.0);
+        assert_eq!(diagonal.iter().map(|(key, _)| *key).collect::<Vec<_>>(), vec!["near", "off axis"]);
+    }
+
+    #[test]
+    fn nearest_searches_past_empty_rings() {
+        let mut index = SpatialIndex::new(1.0);
+        assert!(index.nearest_by(Point::new(0.0, 0.0), |_, bounds| rectangle_distance(Point::new(0.0, 0.0), bounds)).is_none());
+        index.insert("close", rect(6.0, 0.0, 6.5, 0.5));
+        index.insert("closer diagonally", rect(4.0, 4.0, 4.5, 4.5));
+        index.insert("closest", rect(0.0, -5.5, 0.5, -5.0));
+        let origin = Point::new(0.0, 0.0);
+        let (key, distance) = index.nearest_by(origin, |_, bounds| rectangle_distance(origin, bounds)).unwrap();
+        assert_eq!((key, distance), ("closest", 5.0));
+    }
+
+    #[test]
+    fn board_queries_match_brute_force() {
+        let mut rng = Lcg(7);
+        let board = jittered_board(&mut rng);
+        assert_matches_brute_force(&board, &mut rng);
+    }
+
+    #[test]
+    fn board_queries_follow_moves_flips_and_removals() {
+        let mut rng = Lcg(11);
+        let mut board = jittered_board(&mut rng);
+        assert_matches_brute_force(&board, &mut rng);
+        for i in (0..COLUMNS * COLUMNS).step_by(7) {
+            let placed = board.component_mut(&format!("R{}", i + 1)).unwrap();
+            placed.position.0 += 5.0;
+            placed.rotation += 45.0;
+            placed.side = Side::Back;
+        }
+        board.remove("R2");
+        assert_matches_brute_force(&board, &mut rng);
+        assert!(board.pads_in_rect(&rect(-100.0, -100.0, 100.0, 100.0)).iter().all(|pad| pad.reference != "R2"));
+        let flipped = board.pads_in_rect(&rect(-100.0, -100.0, 100.0, 100.0)).into_iter().find(|pad| pad.reference == "R1").unwrap();
+        assert_eq!(flipped.side, Side::Back);
+    }
+
+    #[test]
+    fn moving_one_part_leaves_the_others_indexed() {
+        let mut rng = Lcg(3);
+        let mut board = jittered_board(&mut rng);
+        board.nearest_pad(Point::new(0.0, 0.0));
+        let before = board.spatial.borrow().components.bounds(&"R5".to_string()).cloned();
+        board.component_mut("R6").unwrap().position.1 += 1.0;
+        board.nearest_pad(Point::new(0.0, 0.0));
+        let spatial = board.spatial.borrow();
+        assert!(!spatial.dirty);
+        assert_eq!(spatial.components.bounds(&"R5".to_string()).cloned(), before);
+        assert_eq!(spatial.components.len(), COLUMNS * COLUMNS);
+    }
+}
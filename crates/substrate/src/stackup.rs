@@ -0,0 +1,336 @@
+//! Board stack-up description
+//!
+//! Describes the physical layer sequence of a board from top to bottom: copper foils
+//! separated by dielectric (core and prepreg) layers. Thicknesses are in millimeters and
+//! dielectric properties are given at the frequency of interest.
+//!
+//! `Stackup::builder` builds a stackup from material names (see `materials`), taking
+//! thickness and permittivity from the material library at a design frequency.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::materials::{MaterialError, MaterialKind, MaterialLibrary, DEFAULT_DESIGN_FREQUENCY_GHZ};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum StackupLayerKind {
+    Copper,
+    Dielectric { epsilon_r: f32, loss_tangent: f32 },
+}
+
+/// Library material a dielectric layer was built from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayerMaterial {
+    pub name: String,
+    pub kind: MaterialKind,
+    /// Prepreg plies pressed together, 1 for cores
+    pub plies: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StackupLayer {
+    /// KiCad layer name for copper ("F.Cu", "In1.Cu"), free text for dielectrics
+    pub name: String,
+    pub kind: StackupLayerKind,
+    pub thickness: f32,
+    /// `None` for copper and for dielectrics given by value
+    pub material: Option<LayerMaterial>,
+}
+
+impl StackupLayer {
+    pub fn copper(name: &str, thickness: f32) -> Self {
+        Self { name: name.to_string(), kind: StackupLayerKind::Copper, thickness, material: None }
+    }
+
+    pub fn dielectric(name: &str, thickness: f32, epsilon_r: f32, loss_tangent: f32) -> Self {
+        Self { name: name.to_string(), kind: StackupLayerKind::Dielectric { epsilon_r, loss_tangent }, thickness, material: None }
+    }
+
+    pub fn with_material(mut self, material: LayerMaterial) -> Self {
+        self.material = Some(material);
+        self
+    }
+
+    pub fn is_copper(&self) -> bool {
+        matches!(self.kind, StackupLayerKind::Copper)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StackupError {
+    UnknownLayer(String),
+    /// No copper layer beneath (or above, for the bottom side) to act as a reference plane
+    NoReferencePlane(String),
+}
+
+impl fmt::Display for StackupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StackupError::UnknownLayer(name) => write!(f, "layer \"{}\" is not in the stackup", name),
+            StackupError::NoReferencePlane(name) => write!(f, "layer \"{}\" has no adjacent reference plane", name),
+        }
+    }
+}
+
+impl std::error::Error for StackupError {}
+
+/// Dielectric separating a copper layer from its nearest reference plane
+#[derive(Debug, Clone, PartialEq)]
+pub struct DielectricGap {
+    pub reference_layer: String,
+    /// Total dielectric height between the two copper layers in millimeters
+    pub height: f32,
+    /// Thickness weighted relative permittivity of the dielectrics in between
+    pub epsilon_r: f32,
+}
+
+/// Layers ordered from the top (F.Cu side) to the bottom
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stackup {
+    pub layers: Vec<StackupLayer>,
+}
+
+impl Stackup {
+    pub fn new(layers: Vec<StackupLayer>) -> Self {
+        Self { layers }
+    }
+
+    /// Plain double sided FR-4 board with 35um (1oz) copper
+    pub fn two_layer(board_thickness: f32) -> Self {
+        Self::new(vec![
+            StackupLayer::copper("F.Cu", 0.035),
+            StackupLayer::dielectric("Core", board_thickness - 0.07, 4.5, 0.02),
+            StackupLayer::copper("B.Cu", 0.035),
+        ])
+    }
+
+    /// Common 1.6mm four layer build: 7628 prepreg on both sides of a 1.065mm core
+    pub fn four_layer_default() -> Self {
+        Self::new(vec![
+            StackupLayer::copper("F.Cu", 0.035),
+            StackupLayer::dielectric("Prepreg 1", 0.2104, 4.4, 0.02),
+            StackupLayer::copper("In1.Cu", 0.0152),
+            StackupLayer::dielectric("Core", 1.065, 4.6, 0.02),
+            StackupLayer::copper("In2.Cu", 0.0152),
+            StackupLayer::dielectric("Prepreg 2", 0.2104, 4.4, 0.02),
+            StackupLayer::copper("B.Cu", 0.035),
+        ])
+    }
+
+    /// Build a stackup from library materials, see `StackupBuilder`
+    pub fn builder() -> StackupBuilder {
+        StackupBuilder::new(MaterialLibrary::builtin())
+    }
+
+    pub fn total_thickness(&self) -> f32 {
+        self.layers.iter().map(|layer| layer.thickness).sum()
+    }
+
+    pub fn copper_layers(&self) -> impl Iterator<Item = &StackupLayer> {
+        self.layers.iter().filter(|layer| layer.is_copper())
+    }
+
+    pub fn layer(&self, name: &str) -> Option<&StackupLayer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    fn index_of(&self, name: &str) -> Result<usize, StackupError> {
+        self.layers
+            .iter()
+            .position(|layer| layer.name == name)
+            .ok_or_else(|| StackupError::UnknownLayer(name.to_string()))
+    }
+
+    /// Dielectric between `copper_layer` and the nearest copper layer further into the
+    /// board, i.e. the plane a pad or trace on that layer references
+    pub fn reference_gap(&self, copper_layer: &str) -> Result<DielectricGap, StackupError> {
+        let index = self.index_of(copper_layer)?;
+        // Layers on the bottom half reference upwards
+        let inward: Vec<&StackupLayer> = if index * 2 < self.layers.len() {
+            self.layers[index + 1..].iter().collect()
+        } else {
+            self.layers[..index].iter().rev().collect()
+        };
+
+        let mut height = 0.0;
+        let mut weighted_er = 0.0;
+        for layer in inward {
+            match layer.kind {
+                StackupLayerKind::Copper => {
+                    return Ok(DielectricGap {
+                        reference_layer: layer.name.clone(),
+                        height,
+                        epsilon_r: if height > 0.0 { weighted_er / height } else { 1.0 },
+                    });
+                }
+                StackupLayerKind::Dielectric { epsilon_r, .. } => {
+                    height += layer.thickness;
+                    weighted_er += epsilon_r * layer.thickness;
+                }
+            }
+        }
+        Err(StackupError::NoReferencePlane(copper_layer.to_string()))
+    }
+
+    /// Vertical distance between two copper layers, from the middle of one foil to the
+    /// middle of the other: the length of via barrel a signal runs through between them
+    pub fn layer_distance(&self, a: &str, b: &str) -> Result<f32, StackupError> {
+        let (a, b) = (self.index_of(a)?, self.index_of(b)?);
+        let (top, bottom) = (a.min(b), a.max(b));
+        if top == bottom {
+            return Ok(0.0);
+        }
+        let between: f32 = self.layers[top + 1..bottom].iter().map(|layer| layer.thickness).sum();
+        Ok(between + (self.layers[top].thickness + self.layers[bottom].thickness) / 2.0)
+    }
+
+    /// Thickness weighted relative permittivity of the dielectrics between two copper
+    /// layers, 1.0 when there are none
+    pub fn epsilon_r_between(&self, a: &str, b: &str) -> Result<f32, StackupError> {
+        let (a, b) = (self.index_of(a)?, self.index_of(b)?);
+        let (mut height, mut weighted_er) = (0.0, 0.0);
+        for layer in &self.layers[a.min(b)..=a.max(b)] {
+            if let StackupLayerKind::Dielectric { epsilon_r, .. } = layer.kind {
+                height += layer.thickness;
+                weighted_er += epsilon_r * layer.thickness;
+            }
+        }
+        Ok(if height > 0.0 { weighted_er / height } else { 1.0 })
+    }
+
+    /// Thickness weighted loss tangent of the dielectrics between two copper layers, 0.0
+    /// when there are none
+    pub fn loss_tangent_between(&self, a: &str, b: &str) -> Result<f32, StackupError> {
+        let (a, b) = (self.index_of(a)?, self.index_of(b)?);
+        let (mut height, mut weighted_tan) = (0.0, 0.0);
+        for layer in &self.layers[a.min(b)..=a.max(b)] {
+            if let StackupLayerKind::Dielectric { loss_tangent, .. } = layer.kind {
+                height += layer.thickness;
+                weighted_tan += loss_tangent * layer.thickness;
+            }
+        }
+        Ok(if height > 0.0 { weighted_tan / height } else { 0.0 })
+    }
+
+    /// Effective relative permittivity seen by a trace `width` wide on `copper_layer`. Outer
+    /// layers are microstrip, with part of the field in air (Hammerstad and Jensen:
+    /// `(er + 1)/2 + (er - 1)/2 / sqrt(1 + 12h/w)`); inner layers are stripline, entirely in
+    /// the dielectrics between the neighbouring copper layers.
+    pub fn effective_epsilon_r(&self, copper_layer: &str, width: f32) -> Result<f32, StackupError> {
+        let index = self.index_of(copper_layer)?;
+        let copper: Vec<usize> = (0..self.layers.len()).filter(|&i| self.layers[i].is_copper()).collect();
+        let position = copper.iter().position(|&i| i == index).ok_or_else(|| StackupError::UnknownLayer(copper_layer.to_string()))?;
+        if position == 0 || position == copper.len() - 1 {
+            let gap = self.reference_gap(copper_layer)?;
+            let er = gap.epsilon_r;
+            return Ok((er + 1.0) / 2.0 + (er - 1.0) / 2.0 / (1.0 + 12.0 * gap.height / width.max(f32::EPSILON)).sqrt());
+        }
+        let (above, below) = (&self.layers[copper[position - 1]].name, &self.layers[copper[position + 1]].name);
+        self.epsilon_r_between(above, below)
+    }
+}
+
+impl Default for Stackup {
+    fn default() -> Self {
+        Self::four_layer_default()
+    }
+}
+
+/// Stackup from the top down, dielectrics by material name. Materials are looked up in
+/// `build`, with the library and frequency set anywhere in the chain, and errors such as
+/// unknown materials surface there:
+///
+/// ```text
+/// Stackup::builder()
+///     .copper("F.Cu", 0.035)
+///     .prepreg("2116", 2)
+///     .copper("In1.Cu", 0.0152)
+///     .core("FR-4")
+///     ...
+///     .build()?
+/// ```
+/// Copper layer or dielectric of a `StackupBuilder`, resolved against the library in `build`
+#[derive(Debug, Clone)]
+enum PendingLayer {
+    Copper(StackupLayer),
+    Dielectric { name: String, kind: MaterialKind, plies: u32, thickness: Option<f32> },
+}
+
+#[derive(Debug, Clone)]
+pub struct StackupBuilder {
+    library: MaterialLibrary,
+    frequency_ghz: f32,
+    layers: Vec<PendingLayer>,
+}
+
+impl StackupBuilder {
+    pub fn new(library: MaterialLibrary) -> Self {
+        Self { library, frequency_ghz: DEFAULT_DESIGN_FREQUENCY_GHZ, layers: Vec::new() }
+    }
+
+    /// Use `library` instead of the built-in materials, e.g. with custom TOML materials
+    pub fn with_library(mut self, library: MaterialLibrary) -> Self {
+        self.library = library;
+        self
+    }
+
+    /// Frequency permittivity is evaluated at, 1 GHz by default
+    pub fn at_frequency(mut self, ghz: f32) -> Self {
+        self.frequency_ghz = ghz;
+        self
+    }
+
+    pub fn copper(mut self, name: &str, thickness: f32) -> Self {
+        self.layers.push(PendingLayer::Copper(StackupLayer::copper(name, thickness)));
+        self
+    }
+
+    /// `plies` plies of prepreg glass style `name`
+    pub fn prepreg(self, name: &str, plies: u32) -> Self {
+        self.dielectric(name, MaterialKind::Prepreg, plies, None)
+    }
+
+    /// Core laminate `name` at its nominal thickness
+    pub fn core(self, name: &str) -> Self {
+        self.dielectric(name, MaterialKind::Core, 1, None)
+    }
+
+    /// Core laminate `name` in another of the thicknesses it comes in
+    pub fn core_with_thickness(self, name: &str, thickness: f32) -> Self {
+        self.dielectric(name, MaterialKind::Core, 1, Some(thickness))
+    }
+
+    fn dielectric(mut self, name: &str, kind: MaterialKind, plies: u32, thickness: Option<f32>) -> Self {
+        self.layers.push(PendingLayer::Dielectric { name: name.to_string(), kind, plies, thickness });
+        self
+    }
+
+    fn resolve(&self, name: &str, kind: MaterialKind, plies: u32, thickness: Option<f32>) -> Result<StackupLayer, MaterialError> {
+        if plies == 0 {
+            return Err(MaterialError::Invalid(format!("prepreg {} needs at least one ply", name)));
+        }
+        let material = self.library.get_kind(name, kind)?;
+        let thickness = thickness.unwrap_or(material.thickness * plies as f32);
+        let label = match kind {
+            MaterialKind::Prepreg if plies > 1 => format!("Prepreg {} x{}", name, plies),
+            MaterialKind::Prepreg => format!("Prepreg {}", name),
+            MaterialKind::Core => format!("Core {}", name),
+        };
+        Ok(StackupLayer::dielectric(&label, thickness, material.epsilon_r_at(self.frequency_ghz), material.loss_tangent)
+            .with_material(LayerMaterial { name: name.to_string(), kind, plies }))
+    }
+
+    /// The stackup, or the first error from top to bottom
+    pub fn build(self) -> Result<Stackup, MaterialError> {
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| match layer {
+                PendingLayer::Copper(layer) => Ok(layer.clone()),
+                PendingLayer::Dielectric { name, kind, plies, thickness } => self.resolve(name, *kind, *plies, *thickness),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Stackup::new(layers))
+    }
+}
@@ -0,0 +1,235 @@
+//! Stencil alignment footprints
+//!
+//! Stencil printers align the stencil to the board with fiducials and targets cut into the
+//! stencil itself. These footprints exist only on the paste and silkscreen layers: the
+//! pads are paste apertures without copper, so they never reach the copper gerbers, the
+//! BOM or the placement list.
+
+use crate::board_interface::{
+    BoardComposableObject, FontSettings, FpText, FpTextType, GraphicElement, GraphicType, Model3D, PadDescriptor,
+    PadFabProperty, PadNumber, PadShape, PadType, Rectangle, Stroke, StrokeType, TentingSettings, TentingType,
+};
+use crate::functional_types::FunctionalType;
+use crate::ids::fresh_uuid;
+use crate::layer_type::{LayerType, PadLayers, Side};
+
+/// Common stencil fiducial diameter in millimeters
+pub const STENCIL_FIDUCIAL_DIAMETER: f32 = 1.0;
+
+/// Silkscreen line width of the marker rings and crosshairs
+const MARKER_LINE_WIDTH: f32 = 0.12;
+
+fn marker_line(start: (f32, f32), end: (f32, f32), layer: LayerType, width: f32) -> GraphicElement {
+    GraphicElement {
+        element_type: GraphicType::Line { start, end },
+        layer,
+        side: Side::Front,
+        stroke: Stroke { width, stroke_type: StrokeType::Solid },
+        uuid: fresh_uuid(),
+        role: None,
+    }
+}
+
+fn marker_ring(radius: f32) -> GraphicElement {
+    GraphicElement {
+        element_type: GraphicType::Circle { center: (0.0, 0.0), radius },
+        layer: LayerType::SilkScreen,
+        side: Side::Front,
+        stroke: Stroke { width: MARKER_LINE_WIDTH, stroke_type: StrokeType::Solid },
+        uuid: fresh_uuid(),
+        role: None,
+    }
+}
+
+fn reference_text(position: (f32, f32)) -> FpText {
+    FpText {
+        text_type: FpTextType::Reference,
+        text: "REF**".to_string(),
+        position,
+        rotation: None,
+        layer: "F.Fab".into(),
+        uuid: fresh_uuid(),
+        font: FontSettings { size: (1.0, 1.0), thickness: 0.15 },
+        hidden: false,
+        justify: None,
+        role: None,
+    }
+}
+
+/// Two round paste apertures `spacing` apart on X, each with a silkscreen ring
+#[derive(Debug, Clone)]
+pub struct StencilFiducialPair {
+    pub diameter: f32,
+    pub spacing: f32,
+}
+
+impl StencilFiducialPair {
+    pub fn new(diameter: f32, spacing: f32) -> Self {
+        Self { diameter, spacing }
+    }
+
+    fn centers(&self) -> [(f32, f32); 2] {
+        [(-self.spacing / 2.0, 0.0), (self.spacing / 2.0, 0.0)]
+    }
+}
+
+impl BoardComposableObject for StencilFiducialPair {
+    fn is_smt(&self) -> bool {
+        true
+    }
+
+    fn is_electrical(&self) -> bool {
+        false
+    }
+
+    fn terminal_count(&self) -> usize {
+        0
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        FunctionalType::Fiducial("Stencil".to_string())
+    }
+
+    fn footprint_name(&self) -> String {
+        format!("Fiducial_Stencil_Pair_D{}mm_P{}mm", self.diameter, self.spacing)
+    }
+
+    fn library_name(&self) -> String {
+        "Fiducial".to_string()
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        let radius = self.diameter;
+        Rectangle { min_x: -self.spacing / 2.0 - radius, min_y: -radius, max_x: self.spacing / 2.0 + radius, max_y: radius }
+    }
+
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        self.centers()
+            .into_iter()
+            .map(|position| PadDescriptor {
+                // KiCad fiducials have no pad number
+                number: PadNumber::default(),
+                pin_function: None,
+                pad_type: PadType::SMD,
+                shape: PadShape::Circle,
+                position,
+                rotation: None,
+                size: (self.diameter, self.diameter),
+                drill_size: None,
+                drill_slot: None,
+                layers: PadLayers::paste_front(),
+                roundrect_ratio: None,
+                tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+                padstack: None,
+                fab_property: Some(PadFabProperty::GlobalFiducial),
+                solder_mask_margin: None,
+                routing_hint: None,
+                uuid: fresh_uuid(),
+            })
+            .collect()
+    }
+
+    fn description(&self) -> Option<String> {
+        Some(format!("Stencil fiducial pair, paste only, {}mm diameter, {}mm apart", self.diameter, self.spacing))
+    }
+
+    fn tags(&self) -> Option<String> {
+        Some("fiducial stencil paste".to_string())
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        vec![reference_text((0.0, -self.diameter - 1.0))]
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        self.centers()
+            .into_iter()
+            .map(|center| {
+                let mut ring = marker_ring(self.diameter);
+                ring.element_type = GraphicType::Circle { center, radius: self.diameter };
+                ring
+            })
+            .collect()
+    }
+
+    fn model_3d(&self) -> Option<Model3D> {
+        None
+    }
+}
+
+/// Crosshair alignment target: the cross is cut into the stencil (paste) and repeated on
+/// the silkscreen with a ring around it
+#[derive(Debug, Clone)]
+pub struct StencilTarget {
+    /// Length of each crosshair arm from end to end
+    pub size: f32,
+    pub line_width: f32,
+}
+
+impl StencilTarget {
+    pub fn new(size: f32, line_width: f32) -> Self {
+        Self { size, line_width }
+    }
+}
+
+impl BoardComposableObject for StencilTarget {
+    fn is_smt(&self) -> bool {
+        true
+    }
+
+    fn is_electrical(&self) -> bool {
+        false
+    }
+
+    fn terminal_count(&self) -> usize {
+        0
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        FunctionalType::Fiducial("Target".to_string())
+    }
+
+    fn footprint_name(&self) -> String {
+        format!("Fiducial_Stencil_Target_{}mm", self.size)
+    }
+
+    fn library_name(&self) -> String {
+        "Fiducial".to_string()
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        let half = self.size / 2.0;
+        Rectangle { min_x: -half, min_y: -half, max_x: half, max_y: half }
+    }
+
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        Vec::new()
+    }
+
+    fn description(&self) -> Option<String> {
+        Some(format!("Stencil crosshair alignment target, {}mm", self.size))
+    }
+
+    fn tags(&self) -> Option<String> {
+        Some("fiducial stencil target crosshair".to_string())
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        vec![reference_text((0.0, -self.size / 2.0 - 1.0))]
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        let half = self.size / 2.0;
+        let mut elements = Vec::new();
+        for (layer, width) in [(LayerType::Paste, self.line_width), (LayerType::SilkScreen, MARKER_LINE_WIDTH)] {
+            elements.push(marker_line((-half, 0.0), (half, 0.0), layer, width));
+            elements.push(marker_line((0.0, -half), (0.0, half), layer, width));
+        }
+        elements.push(marker_ring(half * 0.75));
+        elements
+    }
+
+    fn model_3d(&self) -> Option<Model3D> {
+        None
+    }
+}
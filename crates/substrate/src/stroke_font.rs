@@ -0,0 +1,277 @@
+//! Stroke font for footprint and board text
+//!
+//! Text on silkscreen and fab layers is plotted with a single line font, every glyph a
+//! few polylines drawn with the text's stroke thickness, as KiCad plots its own stroke
+//! font. Turning an `FpText` into those polylines gives the exporters geometry to write
+//! and the checks an extent to test, rather than an estimate from the character count.
+//!
+//! Glyphs are laid out on a grid 12 units to the cap height: capitals and digits 12 units
+//! high and mostly 8 wide, lowercase 8 high with descenders 4 below the baseline. Printable
+//! ASCII is covered plus µ, Ω and °; any other character is drawn as '?'. The font
+//! follows KiCad's stroke font metrics rather than its glyph data:
+//!
+//! - the text height (`FontSettings::size.1`) is the cap height, measured between stroke
+//!   centers, so a 1 mm "R" inks 1 mm plus one stroke thickness
+//! - the text width (`FontSettings::size.0`) scales the glyphs horizontally, a grid unit
+//!   being 1/14 of it, and glyphs are `GLYPH_GAP` units apart
+//! - lines of a multi line text are `INTERLINE` text heights apart
+//!
+//! Justification anchors the block of lines on the text position: horizontally each line
+//! by its own width, vertically the first line's cap height (top), the middle of the block
+//! (center) or the last line's baseline (bottom). Mirrored text runs leftwards from the
+//! anchor, then the text is turned counter-clockwise by its rotation about the anchor.
+
+use crate::board_interface::{FpText, HorizontalJustify, Rectangle, VerticalJustify};
+use crate::geometry::Point;
+
+/// Grid units from the baseline to the top of a capital
+const CAP_HEIGHT: f32 = 12.0;
+/// Grid units across a text width; a capital is 8 of them
+const EM_WIDTH: f32 = 14.0;
+/// Grid units between the right of one glyph and the left of the next
+const GLYPH_GAP: f32 = 3.0;
+/// Baseline to baseline distance of multi line text, in text heights
+pub const INTERLINE: f32 = 1.6;
+
+/// Width in grid units and strokes of a character: polylines separated by '|', each
+/// a list of "x,y" points with y up from the baseline. A single point is a dot.
+fn glyph(c: char) -> (f32, &'static str) {
+    match c {
+        ' ' => (4.0, ""),
+        '!' => (2.0, "1,12 1,3|1,0 1,0"),
+        '"' => (3.0, "0,12 0,9|3,12 3,9"),
+        '#' => (8.0, "2,0 3,12|5,0 6,12|0,4 8,4|0,8 8,8"),
+        '$' => (8.0, "8,10 6,11 2,11 0,9 0,8 2,6 6,6 8,4 8,2 6,1 2,1 0,2|4,12 4,0"),
+        '%' => (8.0, "8,12 0,0|1,12 0,11 1,10 2,11 1,12|7,2 6,1 7,0 8,1 7,2"),
+        '&' => (8.0, "8,0 2,8 2,10 3,12 5,12 6,10 6,9 0,4 0,2 2,0 4,0 8,4"),
+        '\'' => (0.0, "0,12 0,9"),
+        '(' => (3.0, "3,13 1,10 0,6 1,2 3,-1"),
+        ')' => (3.0, "0,13 2,10 3,6 2,2 0,-1"),
+        '*' => (6.0, "3,12 3,6|0,11 6,7|6,11 0,7"),
+        '+' => (8.0, "4,10 4,2|0,6 8,6"),
+        ',' => (1.0, "1,1 1,0 0,-2"),
+        '-' => (6.0, "0,6 6,6"),
+        '.' => (0.0, "0,0 0,0"),
+        '/' => (6.0, "6,13 0,-1"),
+        '0' => (8.0, "3,12 1,11 0,8 0,4 1,1 3,0 5,0 7,1 8,4 8,8 7,11 5,12 3,12"),
+        '1' => (8.0, "1,10 4,12 4,0|1,0 7,0"),
+        '2' => (8.0, "0,10 1,11 3,12 5,12 7,11 8,9 8,8 7,6 0,0 8,0"),
+        '3' => (8.0, "0,12 8,12 4,7 6,7 7,6 8,4 8,2 7,1 5,0 3,0 1,1 0,2"),
+        '4' => (8.0, "6,0 6,12 0,4 8,4"),
+        '5' => (8.0, "7,12 1,12 0,7 2,8 5,8 7,7 8,5 8,3 7,1 5,0 2,0 0,1"),
+        '6' => (8.0, "7,11 5,12 3,12 1,11 0,8 0,3 1,1 3,0 5,0 7,1 8,3 8,4 7,6 5,7 3,7 1,6 0,4"),
+        '7' => (8.0, "0,12 8,12 3,0"),
+        '8' => (8.0, "4,7 6,8 7,10 6,11 4,12 2,11 1,10 2,8 4,7 1,6 0,4 0,2 1,1 3,0 5,0 7,1 8,2 8,4 7,6 4,7"),
+        '9' => (8.0, "8,8 7,6 5,5 3,5 1,6 0,8 0,9 1,11 3,12 5,12 7,11 8,9 8,4 7,1 5,0 3,0 1,1"),
+        ':' => (0.0, "0,8 0,8|0,0 0,0"),
+        ';' => (1.0, "1,8 1,8|1,1 1,0 0,-2"),
+        '<' => (8.0, "8,11 0,6 8,1"),
+        '=' => (8.0, "0,8 8,8|0,4 8,4"),
+        '>' => (8.0, "0,11 8,6 0,1"),
+        '?' => (8.0, "0,9 1,11 3,12 5,12 7,11 8,9 7,7 4,5 4,3|4,0 4,0"),
+        '@' => (10.0, "7,4 6,3 4,3 3,5 3,7 4,8 6,8 7,7 7,3 9,3 10,5 10,8 9,10 7,12 3,12 1,10 0,8 0,4 1,2 3,0 8,0"),
+        'A' => (8.0, "0,0 4,12 8,0|1,3 7,3"),
+        'B' => (8.0, "0,0 0,12 5,12 7,11 8,9 7,7 5,6 0,6|5,6 7,5 8,3 7,1 5,0 0,0"),
+        'C' => (8.0, "8,10 7,11 5,12 3,12 1,11 0,9 0,3 1,1 3,0 5,0 7,1 8,2"),
+        'D' => (8.0, "0,0 0,12 4,12 6,11 7,10 8,7 8,5 7,2 6,1 4,0 0,0"),
+        'E' => (7.0, "7,12 0,12 0,0 7,0|0,6 5,6"),
+        'F' => (7.0, "7,12 0,12 0,0|0,6 5,6"),
+        'G' => (8.0, "8,10 7,11 5,12 3,12 1,11 0,9 0,3 1,1 3,0 5,0 7,1 8,3 8,6 5,6"),
+        'H' => (8.0, "0,0 0,12|8,0 8,12|0,6 8,6"),
+        'I' => (0.0, "0,0 0,12"),
+        'J' => (6.0, "6,12 6,3 5,1 3,0 2,0 0,1 0,3"),
+        'K' => (8.0, "0,0 0,12|8,12 0,4|3,7 8,0"),
+        'L' => (7.0, "0,12 0,0 7,0"),
+        'M' => (10.0, "0,0 0,12 5,3 10,12 10,0"),
+        'N' => (8.0, "0,0 0,12 8,0 8,12"),
+        'O' => (8.0, "3,12 1,11 0,9 0,3 1,1 3,0 5,0 7,1 8,3 8,9 7,11 5,12 3,12"),
+        'P' => (8.0, "0,0 0,12 5,12 7,11 8,9 8,8 7,6 5,5 0,5"),
+        'Q' => (8.0, "3,12 1,11 0,9 0,3 1,1 3,0 5,0 7,1 8,3 8,9 7,11 5,12 3,12|5,3 8,0"),
+        'R' => (8.0, "0,0 0,12 5,12 7,11 8,9 8,8 7,6 5,5 0,5|4,5 8,0"),
+        'S' => (8.0, "8,10 7,11 5,12 3,12 1,11 0,9 1,7 3,6 5,6 7,5 8,3 7,1 5,0 3,0 1,1 0,2"),
+        'T' => (8.0, "0,12 8,12|4,12 4,0"),
+        'U' => (8.0, "0,12 0,3 1,1 3,0 5,0 7,1 8,3 8,12"),
+        'V' => (8.0, "0,12 4,0 8,12"),
+        'W' => (10.0, "0,12 2,0 5,9 8,0 10,12"),
+        'X' => (8.0, "0,12 8,0|8,12 0,0"),
+        'Y' => (8.0, "0,12 4,6 8,12|4,6 4,0"),
+        'Z' => (8.0, "0,12 8,12 0,0 8,0"),
+        '[' => (3.0, "3,13 0,13 0,-1 3,-1"),
+        '\\' => (6.0, "0,13 6,-1"),
+        ']' => (3.0, "0,13 3,13 3,-1 0,-1"),
+        '^' => (6.0, "0,9 3,12 6,9"),
+        '_' => (8.0, "0,-2 8,-2"),
+        '`' => (2.0, "0,12 2,10"),
+        'a' => (7.0, "7,8 7,0|7,6 5,8 2,8 0,6 0,2 2,0 5,0 7,2"),
+        'b' => (7.0, "0,12 0,0|0,6 2,8 5,8 7,6 7,2 5,0 2,0 0,2"),
+        'c' => (7.0, "7,6 5,8 2,8 0,6 0,2 2,0 5,0 7,2"),
+        'd' => (7.0, "7,12 7,0|7,6 5,8 2,8 0,6 0,2 2,0 5,0 7,2"),
+        'e' => (7.0, "0,4 7,4 7,6 5,8 2,8 0,6 0,2 2,0 5,0 7,1"),
+        'f' => (5.0, "5,12 3,12 2,11 2,0|0,8 5,8"),
+        'g' => (7.0, "7,8 7,-2 5,-4 2,-4 0,-3|7,6 5,8 2,8 0,6 0,2 2,0 5,0 7,2"),
+        'h' => (7.0, "0,12 0,0|0,6 2,8 5,8 7,6 7,0"),
+        'i' => (0.0, "0,8 0,0|0,11 0,11"),
+        'j' => (3.0, "3,8 3,-2 2,-4 0,-4|3,11 3,11"),
+        'k' => (7.0, "0,12 0,0|6,8 0,3|2,5 7,0"),
+        'l' => (2.0, "0,12 0,1 1,0 2,0"),
+        'm' => (12.0, "0,8 0,0|0,6 2,8 4,8 6,6 6,0|6,6 8,8 10,8 12,6 12,0"),
+        'n' => (7.0, "0,8 0,0|0,6 2,8 5,8 7,6 7,0"),
+        'o' => (7.0, "2,8 0,6 0,2 2,0 5,0 7,2 7,6 5,8 2,8"),
+        'p' => (7.0, "0,8 0,-4|0,6 2,8 5,8 7,6 7,2 5,0 2,0 0,2"),
+        'q' => (7.0, "7,8 7,-4|7,6 5,8 2,8 0,6 0,2 2,0 5,0 7,2"),
+        'r' => (5.0, "0,8 0,0|0,5 2,7 3,8 5,8"),
+        's' => (7.0, "7,7 5,8 2,8 0,7 0,5 2,4 5,4 7,3 7,1 5,0 2,0 0,1"),
+        't' => (5.0, "2,12 2,1 3,0 5,0|0,8 5,8"),
+        'u' => (7.0, "0,8 0,2 2,0 5,0 7,2|7,8 7,0"),
+        'v' => (8.0, "0,8 4,0 8,8"),
+        'w' => (10.0, "0,8 2,0 5,6 8,0 10,8"),
+        'x' => (7.0, "0,8 7,0|7,8 0,0"),
+        'y' => (8.0, "0,8 4,0|8,8 2,-4 0,-4"),
+        'z' => (7.0, "0,8 7,8 0,0 7,0"),
+        '{' => (4.0, "4,13 2,12 2,7 0,6 2,5 2,0 4,-1"),
+        '|' => (0.0, "0,13 0,-1"),
+        '}' => (4.0, "0,13 2,12 2,7 4,6 2,5 2,0 0,-1"),
+        '~' => (8.0, "0,5 2,7 6,5 8,7"),
+        'µ' => (7.0, "0,8 0,-4|0,2 2,0 5,0 7,2|7,8 7,0"),
+        'Ω' => (8.0, "0,0 3,0 3,2 1,4 0,7 1,10 3,12 5,12 7,10 8,7 7,4 5,2 5,0 8,0"),
+        '°' => (2.0, "1,12 0,11 1,10 2,11 1,12"),
+        _ => glyph('?'),
+    }
+}
+
+/// Polylines of a glyph's strokes in grid units, y up
+fn glyph_strokes(strokes: &str) -> Vec<Vec<Point>> {
+    strokes
+        .split('|')
+        .filter(|stroke| !stroke.is_empty())
+        .map(|stroke| {
+            stroke
+                .split_whitespace()
+                .filter_map(|point| {
+                    let (x, y) = point.split_once(',')?;
+                    Some(Point::new(x.parse().ok()?, y.parse().ok()?))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Width of one line of text from the left of its first glyph to the right of its last,
+/// between stroke centers, at text width `size_x` (mm)
+pub fn line_width(line: &str, size_x: f32) -> f32 {
+    let units: f32 = line.chars().map(|c| glyph(c).0 + GLYPH_GAP).sum();
+    (units - GLYPH_GAP).max(0.0) * size_x / EM_WIDTH
+}
+
+impl FpText {
+    /// Polylines of the text in footprint coordinates, to be drawn with round ends at the
+    /// font thickness; see the module documentation
+    pub fn strokes(&self) -> Vec<Vec<Point>> {
+        let justify = self.justify.unwrap_or_default();
+        let (size_x, size_y) = self.font.size;
+        let (scale_x, scale_y) = (size_x / EM_WIDTH, size_y / CAP_HEIGHT);
+        let lines: Vec<&str> = self.text.split('\n').collect();
+        let pitch = INTERLINE * size_y;
+        let last_baseline = (lines.len() - 1) as f32 * pitch;
+        // Baseline of the first line below the anchor
+        let first_baseline = match justify.vertical {
+            VerticalJustify::Top => size_y,
+            VerticalJustify::Center => (size_y - last_baseline) / 2.0,
+            VerticalJustify::Bottom => -last_baseline,
+        };
+        let anchor = Point::from(self.position);
+        let angle = self.rotation.unwrap_or(0.0);
+
+        let mut strokes = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            let baseline = first_baseline + index as f32 * pitch;
+            let width = line_width(line, size_x);
+            let mut pen = match justify.horizontal {
+                HorizontalJustify::Left => 0.0,
+                HorizontalJustify::Center => -width / 2.0,
+                HorizontalJustify::Right => -width,
+            };
+            for c in line.chars() {
+                let (advance, data) = glyph(c);
+                for stroke in glyph_strokes(data) {
+                    let placed = stroke.iter().map(|point| {
+                        let x = pen + point.x * scale_x;
+                        let local = Point::new(if justify.mirror { -x } else { x }, baseline - point.y * scale_y);
+                        let turned = local.rotated(angle);
+                        Point::new(anchor.x + turned.x, anchor.y + turned.y)
+                    });
+                    strokes.push(placed.collect());
+                }
+                pen += (advance + GLYPH_GAP) * scale_x;
+            }
+        }
+        strokes
+    }
+
+    /// Area the text inks: its strokes grown by half the thickness, `None` for text
+    /// without any
+    pub fn stroke_bounds(&self) -> Option<Rectangle> {
+        let strokes = self.strokes();
+        let points: Vec<Point> = strokes.into_iter().flatten().collect();
+        let bounds = Rectangle::enclosing(&points)?;
+        let half = self.font.thickness / 2.0;
+        Some(Rectangle { min_x: bounds.min_x - half, min_y: bounds.min_y - half, max_x: bounds.max_x + half, max_y: bounds.max_y + half })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_interface::{FontSettings, FpTextType, Justify};
+
+    fn reference(text: &str) -> FpText {
+        FpText::new(FpTextType::Reference, text, (10.0, 5.0), "F.SilkS")
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn extents_follow_the_cap_height_and_glyph_widths() {
+        let text = reference("REF**");
+        let bounds = text.stroke_bounds().unwrap();
+        // R, E, F and two asterisks: 8 + 7 + 7 + 6 + 6 units and four gaps of 3
+        let width = 46.0 / 14.0;
+        assert!(close(bounds.width(), width + 0.15), "{:?}", bounds);
+        assert!(close(bounds.height(), 1.0 + 0.15), "{:?}", bounds);
+        assert!(close((bounds.min_x + bounds.max_x) / 2.0, 10.0) && close((bounds.min_y + bounds.max_y) / 2.0, 5.0), "{:?}", bounds);
+        assert!(close(line_width("REF**", 1.0), width));
+
+        // Descenders reach below the baseline, wider text scales only across
+        let low = reference("gy").stroke_bounds().unwrap();
+        assert!(low.height() > 1.0 && close(low.height(), 1.0 + 0.15));
+        let wide = FpText { font: FontSettings { size: (2.0, 1.0), thickness: 0.15 }, ..reference("REF**") };
+        assert!(close(wide.stroke_bounds().unwrap().width(), 2.0 * width + 0.15));
+        assert!(reference(" ").stroke_bounds().is_none());
+    }
+
+    #[test]
+    fn justification_mirroring_and_rotation_move_the_block() {
+        let width = line_width("R8", 1.0);
+        let text = FpText { justify: Some(Justify::new(HorizontalJustify::Left, VerticalJustify::Bottom)), ..reference("R8") };
+        let bounds = text.stroke_bounds().unwrap();
+        assert!(close(bounds.min_x, 10.0 - 0.075) && close(bounds.max_y, 5.0 + 0.075), "{:?}", bounds);
+
+        let mirrored = FpText { justify: Some(Justify::new(HorizontalJustify::Left, VerticalJustify::Top).with_mirror(true)), ..reference("R8") };
+        let bounds = mirrored.stroke_bounds().unwrap();
+        assert!(close(bounds.max_x, 10.0 + 0.075) && close(bounds.min_x, 10.0 - width - 0.075), "{:?}", bounds);
+        assert!(close(bounds.min_y, 5.0 - 0.075), "{:?}", bounds);
+
+        // A quarter turn counter-clockwise stands the text on its end, reading upwards
+        let turned = FpText { rotation: Some(90.0), ..reference("R8") };
+        let bounds = turned.stroke_bounds().unwrap();
+        assert!(close(bounds.height(), width + 0.15) && close(bounds.width(), 1.15), "{:?}", bounds);
+        let first = turned.strokes()[0][0];
+        assert!(first.y > 5.0, "R starts at the bottom: {:?}", first);
+
+        let two_lines = reference("R1\nR2").stroke_bounds().unwrap();
+        assert!(close(two_lines.height(), 1.0 + INTERLINE + 0.15), "{:?}", two_lines);
+        assert!(close((two_lines.min_y + two_lines.max_y) / 2.0, 5.0), "{:?}", two_lines);
+    }
+}
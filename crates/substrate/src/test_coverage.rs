@@ -0,0 +1,493 @@
+//! Test point coverage of the nets
+//!
+//! Design for test review asks which nets a bed of nails or a flying probe can reach.
+//! `Board::test_coverage_report` looks through every net of the board for copper a probe
+//! can land on, and on which side of the board it is:
+//!
+//! - pads with the test point fab property, on the sides they have copper
+//! - through hole pads, on both sides
+//! - vias of at least `min_via_diameter` left open by the solder mask on an outer layer
+//!   they reach (see `Via::is_exposed`); tented vias don't count
+//!
+//! Other SMD pads don't count: probing a lead can lift it, and the balls under a BGA
+//! can't be reached at all. A net with none of these is uncovered. For uncovered nets the
+//! report suggests where a test point could go: the midpoints of the net's longest
+//! tracks on an outer layer, at least `probe_clearance` from every pad and via, inside the
+//! outline.
+//!
+//! The report prints as a table, and `to_csv` writes one row per net. The exporters'
+//! `json_export::test_coverage_report_to_json` gives the JSON form.
+
+use std::fmt;
+
+use crate::board::{Board, PlacedComponent};
+use crate::board_interface::{PadDescriptor, PadFabProperty, PadType, Rectangle};
+use crate::geometry::{point_in_polygon, Point};
+use crate::layer_type::{PadSide, Side};
+
+/// Smallest via a probe lands on reliably (mm)
+pub const DEFAULT_MIN_PROBE_VIA: f32 = 0.5;
+
+/// Room a suggested test point needs around it, to the edge of any pad or via (mm)
+pub const DEFAULT_PROBE_CLEARANCE: f32 = 1.0;
+
+/// Suggestions per uncovered net
+pub const DEFAULT_MAX_SUGGESTIONS: usize = 3;
+
+/// Settings of `Board::test_coverage`, see the module documentation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TestCoverageOptions {
+    pub min_via_diameter: f32,
+    pub probe_clearance: f32,
+    /// Test point locations suggested per uncovered net, none when 0
+    pub max_suggestions: usize,
+}
+
+impl Default for TestCoverageOptions {
+    fn default() -> Self {
+        Self {
+            min_via_diameter: DEFAULT_MIN_PROBE_VIA,
+            probe_clearance: DEFAULT_PROBE_CLEARANCE,
+            max_suggestions: DEFAULT_MAX_SUGGESTIONS,
+        }
+    }
+}
+
+impl TestCoverageOptions {
+    pub fn with_min_via_diameter(mut self, diameter: f32) -> Self {
+        self.min_via_diameter = diameter;
+        self
+    }
+
+    pub fn with_probe_clearance(mut self, clearance: f32) -> Self {
+        self.probe_clearance = clearance;
+        self
+    }
+
+    pub fn with_max_suggestions(mut self, count: usize) -> Self {
+        self.max_suggestions = count;
+        self
+    }
+}
+
+/// What a probe lands on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TestAccessKind {
+    TestPoint,
+    ThroughHolePad,
+    Via,
+}
+
+impl fmt::Display for TestAccessKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TestAccessKind::TestPoint => "test point",
+            TestAccessKind::ThroughHolePad => "through hole pad",
+            TestAccessKind::Via => "via",
+        })
+    }
+}
+
+/// One place a net can be probed
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestAccess {
+    pub kind: TestAccessKind,
+    /// "TP1.1" for pads, "via 4" for the fifth via of the board
+    pub element: String,
+    pub location: Point,
+    /// Board sides it can be probed from
+    pub sides: PadSide,
+}
+
+/// A place to add a test point to an uncovered net, on one of its tracks
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestPointSuggestion {
+    pub location: Point,
+    pub side: Side,
+    /// Index of the track in `Board::tracks`
+    pub track: usize,
+}
+
+/// Probe access of one net
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetCoverage {
+    pub net: String,
+    pub accesses: Vec<TestAccess>,
+    /// Longest tracks first, empty for covered nets
+    pub suggestions: Vec<TestPointSuggestion>,
+}
+
+impl NetCoverage {
+    pub fn is_covered(&self) -> bool {
+        !self.accesses.is_empty()
+    }
+
+    /// Whether a probe reaches the net from the front
+    pub fn has_front(&self) -> bool {
+        self.accesses.iter().any(|access| access.sides.has_front())
+    }
+
+    pub fn has_back(&self) -> bool {
+        self.accesses.iter().any(|access| access.sides.has_back())
+    }
+
+    /// Sides the net can be probed from, `None` when uncovered
+    pub fn sides(&self) -> Option<PadSide> {
+        match (self.has_front(), self.has_back()) {
+            (true, true) => Some(PadSide::Both),
+            (true, false) => Some(PadSide::Front),
+            (false, true) => Some(PadSide::Back),
+            (false, false) => None,
+        }
+    }
+}
+
+/// Probe access of every net of a board, in net name order
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestCoverageReport {
+    pub options: TestCoverageOptions,
+    pub nets: Vec<NetCoverage>,
+}
+
+impl TestCoverageReport {
+    pub fn covered(&self) -> impl Iterator<Item = &NetCoverage> {
+        self.nets.iter().filter(|net| net.is_covered())
+    }
+
+    pub fn uncovered(&self) -> impl Iterator<Item = &NetCoverage> {
+        self.nets.iter().filter(|net| !net.is_covered())
+    }
+
+    /// Share of nets with any access, in percent; 100 for a board without nets
+    pub fn coverage(&self) -> f32 {
+        self.percent(NetCoverage::is_covered)
+    }
+
+    /// Share of nets a single sided fixture reaches from the front, in percent
+    pub fn front_coverage(&self) -> f32 {
+        self.percent(NetCoverage::has_front)
+    }
+
+    pub fn back_coverage(&self) -> f32 {
+        self.percent(NetCoverage::has_back)
+    }
+
+    fn percent(&self, counts: impl Fn(&NetCoverage) -> bool) -> f32 {
+        if self.nets.is_empty() {
+            return 100.0;
+        }
+        100.0 * self.nets.iter().filter(|net| counts(net)).count() as f32 / self.nets.len() as f32
+    }
+
+    /// One row per net: net,covered,front,back,accesses,suggestions. Accesses and
+    /// suggestions are separated by spaces, suggestions as "x;y;side" in board
+    /// millimeters.
+    pub fn to_csv(&self) -> String {
+        let mut text = String::from("net,covered,front,back,accesses,suggestions\n");
+        for net in &self.nets {
+            let accesses: Vec<&str> = net.accesses.iter().map(|access| access.element.as_str()).collect();
+            let suggestions: Vec<String> = net
+                .suggestions
+                .iter()
+                .map(|suggestion| format!("{};{};{}", suggestion.location.x, suggestion.location.y, side_name(suggestion.side)))
+                .collect();
+            text += &format!(
+                "{},{},{},{},{},{}\n",
+                csv_field(&net.net),
+                net.is_covered(),
+                net.has_front(),
+                net.has_back(),
+                csv_field(&accesses.join(" ")),
+                suggestions.join(" ")
+            );
+        }
+        text
+    }
+}
+
+impl fmt::Display for TestCoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.nets.iter().map(|net| net.net.len()).chain(["Net".len()]).max().unwrap_or_default();
+        writeln!(f, "{:<width$}  {:<5}  Access", "Net", "Sides")?;
+        for net in &self.nets {
+            let sides = match net.sides() {
+                Some(PadSide::Both) => "both",
+                Some(PadSide::Front) => "front",
+                Some(PadSide::Back) => "back",
+                None => "-",
+            };
+            let access = if net.is_covered() {
+                net.accesses.iter().map(|access| format!("{} {}", access.kind, access.element)).collect::<Vec<_>>().join(", ")
+            } else {
+                let places: Vec<String> = net
+                    .suggestions
+                    .iter()
+                    .map(|suggestion| format!("({:.2}, {:.2}) {}", suggestion.location.x, suggestion.location.y, side_name(suggestion.side)))
+                    .collect();
+                if places.is_empty() { "uncovered".to_string() } else { format!("uncovered, try {}", places.join(", ")) }
+            };
+            writeln!(f, "{:<width$}  {:<5}  {}", net.net, sides, access)?;
+        }
+        write!(
+            f,
+            "{} of {} nets covered ({:.1}%), {:.1}% from the front, {:.1}% from the back",
+            self.covered().count(),
+            self.nets.len(),
+            self.coverage(),
+            self.front_coverage(),
+            self.back_coverage()
+        )
+    }
+}
+
+fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::Back => "back",
+        _ => "front",
+    }
+}
+
+/// Quote a CSV field when it contains a separator or quote
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Board sides of a pad of a part mounted on `mounted`, which mirrors its layers
+fn board_sides(pad: &PadDescriptor, mounted: Side) -> Option<PadSide> {
+    let sides = pad.side()?;
+    Some(match (sides, mounted) {
+        (PadSide::Front, Side::Back) => PadSide::Back,
+        (PadSide::Back, Side::Back) => PadSide::Front,
+        (sides, _) => sides,
+    })
+}
+
+/// The kind of access a pad gives, if it gives any
+fn pad_access(pad: &PadDescriptor) -> Option<TestAccessKind> {
+    if !pad.has_copper() {
+        return None;
+    }
+    match (pad.fab_property, &pad.pad_type) {
+        (Some(PadFabProperty::TestPoint), _) => Some(TestAccessKind::TestPoint),
+        (_, PadType::ThroughHole) => Some(TestAccessKind::ThroughHolePad),
+        _ => None,
+    }
+}
+
+/// Distance from `point` to the edge of `bounds`, 0 inside
+fn distance_to_bounds(point: Point, bounds: &Rectangle) -> f32 {
+    let dx = (bounds.min_x - point.x).max(point.x - bounds.max_x).max(0.0);
+    let dy = (bounds.min_y - point.y).max(point.y - bounds.max_y).max(0.0);
+    dx.hypot(dy)
+}
+
+impl Board {
+    /// Probe access of every net with the default options, see the module documentation
+    pub fn test_coverage_report(&self) -> TestCoverageReport {
+        self.test_coverage(&TestCoverageOptions::default())
+    }
+
+    /// Probe access of every net, see the module documentation
+    pub fn test_coverage(&self, options: &TestCoverageOptions) -> TestCoverageReport {
+        let mut nets: Vec<NetCoverage> = self
+            .nets()
+            .into_iter()
+            .map(|net| NetCoverage { net: net.to_string(), accesses: Vec::new(), suggestions: Vec::new() })
+            .collect();
+        let index = |nets: &[NetCoverage], net: &str| nets.binary_search_by(|coverage| coverage.net.as_str().cmp(net)).ok();
+
+        for placed in self.components() {
+            for pad in placed.component.pad_descriptors() {
+                let Some(kind) = pad_access(&pad) else { continue };
+                let Some(sides) = board_sides(&pad, placed.side) else { continue };
+                let Some(slot) = self.pad_net(&placed.reference, &pad.number).and_then(|net| index(&nets, net)) else { continue };
+                nets[slot].accesses.push(TestAccess {
+                    kind,
+                    element: format!("{}.{}", placed.reference, pad.number),
+                    location: pad_center(self, placed, &pad),
+                    sides,
+                });
+            }
+        }
+        for (number, via) in self.vias().iter().enumerate() {
+            if via.diameter < options.min_via_diameter {
+                continue;
+            }
+            let sides = match (via.is_exposed(Side::Front), via.is_exposed(Side::Back)) {
+                (true, true) => PadSide::Both,
+                (true, false) => PadSide::Front,
+                (false, true) => PadSide::Back,
+                (false, false) => continue,
+            };
+            let Some(slot) = index(&nets, &via.net) else { continue };
+            nets[slot].accesses.push(TestAccess { kind: TestAccessKind::Via, element: format!("via {}", number), location: via.position, sides });
+        }
+
+        if options.max_suggestions > 0 {
+            for net in nets.iter_mut().filter(|net| !net.is_covered()) {
+                net.suggestions = self.test_point_suggestions(&net.net, options);
+            }
+        }
+        TestCoverageReport { options: *options, nets }
+    }
+
+    /// Midpoints of `net`'s outer layer tracks with room for a probe, longest track first
+    fn test_point_suggestions(&self, net: &str, options: &TestCoverageOptions) -> Vec<TestPointSuggestion> {
+        let mut tracks: Vec<(usize, f32)> = self
+            .tracks()
+            .iter()
+            .enumerate()
+            .filter(|(_, track)| track.net == net && matches!(track.layer.side, Side::Front | Side::Back))
+            .map(|(index, track)| (index, track.start.distance_to(&track.end)))
+            .collect();
+        tracks.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let clearance = options.probe_clearance;
+        let mut suggestions = Vec::new();
+        for (index, _) in tracks {
+            let track = &self.tracks()[index];
+            let location = Point::new((track.start.x + track.end.x) / 2.0, (track.start.y + track.end.y) / 2.0);
+            let around = Rectangle {
+                min_x: location.x - clearance,
+                min_y: location.y - clearance,
+                max_x: location.x + clearance,
+                max_y: location.y + clearance,
+            };
+            let near_pad = self.pads_in_rect(&around).iter().any(|pad| distance_to_bounds(location, &pad.bounds) < clearance);
+            let near_via = self.vias().iter().any(|via| via.position.distance_to(&location) - via.diameter / 2.0 < clearance);
+            let near_suggestion = suggestions.iter().any(|other: &TestPointSuggestion| other.location.distance_to(&location) < clearance);
+            let outside = self.outline().is_some_and(|outline| !point_in_polygon(location, outline));
+            if near_pad || near_via || near_suggestion || outside {
+                continue;
+            }
+            suggestions.push(TestPointSuggestion { location, side: track.layer.side, track: index });
+            if suggestions.len() == options.max_suggestions {
+                break;
+            }
+        }
+        suggestions
+    }
+}
+
+fn pad_center(board: &Board, placed: &PlacedComponent, pad: &PadDescriptor) -> Point {
+    board.board_point(placed, Point::new(pad.position.0, pad.position.1))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::board_interface::{PadShape, TentingSettings, TentingType};
+    use crate::footprint::Footprint;
+    use crate::layer_type::{BoardLayer, LayerType};
+    use crate::presets;
+    use crate::routing::{Track, Via, ViaKind};
+
+    fn open() -> TentingSettings {
+        TentingSettings { front: TentingType::None, back: TentingType::None }
+    }
+
+    /// A part made of `pads`, borrowing the rest from an 0402
+    fn part(pads: Vec<PadDescriptor>) -> Footprint {
+        Footprint { pads, ..Footprint::capture(&presets::resistor_0402("10k")) }
+    }
+
+    /// A 6x6 grid of 0.4 mm balls at 0.8 mm pitch
+    fn bga() -> Footprint {
+        let balls = (0..36)
+            .map(|index| {
+                let position = ((index % 6) as f32 * 0.8 - 2.0, (index / 6) as f32 * 0.8 - 2.0);
+                PadDescriptor::new((index + 1).to_string(), PadType::SMD, PadShape::Circle, position, (0.4, 0.4), ["F.Cu", "F.Paste", "F.Mask"])
+            })
+            .collect();
+        Footprint { bounding_box: Rectangle { min_x: -2.8, min_y: -2.8, max_x: 2.8, max_y: 2.8 }, ..part(balls) }
+    }
+
+    fn coverage<'a>(report: &'a TestCoverageReport, net: &str) -> &'a NetCoverage {
+        report.nets.iter().find(|coverage| coverage.net == net).unwrap()
+    }
+
+    #[test]
+    fn tented_vias_give_no_access() {
+        let mut board = Board::new("vias").with_rectangular_outline(30.0, 20.0);
+        board.add_via(Via::new("TENTED", Point::new(5.0, 5.0), 0.6, 0.3));
+        board.add_via(Via::new("OPEN", Point::new(10.0, 5.0), 0.6, 0.3).with_tenting(open()));
+        board.add_via(Via::new("SMALL", Point::new(15.0, 5.0), 0.4, 0.2).with_tenting(open()));
+        let front_only = TentingSettings { front: TentingType::None, back: TentingType::Full };
+        board.add_via(Via::new("FRONT", Point::new(20.0, 5.0), 0.6, 0.3).with_tenting(front_only));
+        let blind = Via::new("BLIND", Point::new(25.0, 5.0), 0.6, 0.3)
+            .with_span(ViaKind::Blind, BoardLayer::front(LayerType::Copper), BoardLayer::new(LayerType::Copper, Side::Inner(1)))
+            .with_tenting(open());
+        board.add_via(blind);
+
+        let report = board.test_coverage_report();
+        assert!(!coverage(&report, "TENTED").is_covered());
+        assert!(!coverage(&report, "SMALL").is_covered());
+        let open = coverage(&report, "OPEN");
+        assert_eq!(open.sides(), Some(PadSide::Both));
+        assert_eq!((open.accesses[0].kind, open.accesses[0].element.as_str()), (TestAccessKind::Via, "via 1"));
+        assert_eq!(coverage(&report, "FRONT").sides(), Some(PadSide::Front));
+        // Open on both sides, but only reaching the front
+        assert_eq!(coverage(&report, "BLIND").sides(), Some(PadSide::Front));
+        assert_eq!((report.coverage(), report.front_coverage(), report.back_coverage()), (60.0, 60.0, 20.0));
+
+        let csv = report.to_csv();
+        assert!(csv.starts_with("net,covered,front,back,accesses,suggestions\n"), "{}", csv);
+        assert!(csv.contains("\nOPEN,true,true,true,via 1,\n"), "{}", csv);
+        assert!(csv.contains("\nTENTED,false,false,false,,\n"), "{}", csv);
+    }
+
+    #[test]
+    fn smd_pads_under_a_bga_leave_the_net_uncovered() {
+        let mut board = Board::new("bga").with_rectangular_outline(30.0, 20.0);
+        board.place("U1", bga(), (10.0, 10.0), 0.0).unwrap();
+        board.place("R1", presets::resistor_0402("10k"), (20.0, 10.0), 0.0).unwrap();
+        board.connect_pad("U1", "8", "DQ0");
+        board.connect_pad("R1", "1", "DQ0");
+        // A tented via in the fanout doesn't help either
+        board.add_via(Via::new("DQ0", Point::new(8.8, 8.8), 0.6, 0.3));
+        board.connect_pad("U1", "1", "GND");
+        let tp = PadDescriptor::new("1", PadType::SMD, PadShape::Circle, (0.0, 0.0), (1.0, 1.0), ["F.Cu", "F.Mask"])
+            .with_fab_property(PadFabProperty::TestPoint);
+        // On the back, where the footprint's F.Cu pad ends up on B.Cu
+        board.place_shared("TP1", Rc::new(part(vec![tp])), (25.0, 5.0), 0.0, Side::Back).unwrap();
+        board.connect_pad("TP1", "1", "GND");
+        let header = PadDescriptor::new("1", PadType::ThroughHole, PadShape::Circle, (0.0, 0.0), (1.7, 1.7), ["*.Cu", "*.Mask"]).with_drill(1.0);
+        board.place("J1", part(vec![header]), (3.0, 3.0), 0.0).unwrap();
+        board.connect_pad("J1", "1", "VCC");
+        board.connect_pad("U1", "2", "VCC");
+
+        let report = board.test_coverage(&TestCoverageOptions::default().with_max_suggestions(0));
+        assert!(!coverage(&report, "DQ0").is_covered(), "{}", report);
+        assert_eq!(report.uncovered().count(), 1);
+        let gnd = coverage(&report, "GND");
+        assert_eq!((gnd.accesses.len(), gnd.accesses[0].kind, gnd.sides()), (1, TestAccessKind::TestPoint, Some(PadSide::Back)));
+        assert_eq!(gnd.accesses[0].location, Point::new(25.0, 5.0));
+        let vcc = coverage(&report, "VCC");
+        assert_eq!((vcc.accesses[0].kind, vcc.sides()), (TestAccessKind::ThroughHolePad, Some(PadSide::Both)));
+        assert!(report.to_string().ends_with("2 of 3 nets covered (66.7%), 33.3% from the front, 66.7% from the back"), "{}", report);
+    }
+
+    #[test]
+    fn suggestions_sit_on_long_tracks_clear_of_pads() {
+        let mut board = Board::new("suggest").with_rectangular_outline(40.0, 20.0);
+        board.place("R1", presets::resistor_0402("10k"), (5.0, 10.0), 0.0).unwrap();
+        board.connect_pad("R1", "2", "SIG");
+        let front = BoardLayer::front(LayerType::Copper);
+        // The short stub's midpoint is by the resistor, the long run has room
+        board.add_track(Track::new("SIG", front, Point::new(5.5, 10.0), Point::new(6.5, 10.0), 0.2));
+        board.add_track(Track::new("SIG", front, Point::new(6.5, 10.0), Point::new(30.0, 10.0), 0.2));
+        board.add_track(Track::new("SIG", BoardLayer::new(LayerType::Copper, Side::Inner(1)), Point::new(0.0, 15.0), Point::new(39.0, 15.0), 0.2));
+
+        let report = board.test_coverage_report();
+        let signal = coverage(&report, "SIG");
+        assert!(!signal.is_covered());
+        assert_eq!(signal.suggestions, vec![TestPointSuggestion { location: Point::new(18.25, 10.0), side: Side::Front, track: 1 }]);
+        assert!(report.to_string().contains("uncovered, try (18.25, 10.00) front"), "{}", report);
+    }
+}
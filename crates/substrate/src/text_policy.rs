@@ -0,0 +1,333 @@
+//! Reference designator text policies
+//!
+//! Assembly houses want reference text sized by component class, e.g. 0.5 mm on 0201 and
+//! 0402 parts and 1.0 mm elsewhere, instead of whatever each generator picks. A
+//! `TextPolicy` has a default style and rules matching a functional type and/or a largest
+//! body size; the first matching rule's settings override the default. Rules that set only
+//! a size scale the default thickness with it.
+//!
+//! `BoardComposableObject::with_text_policy` applies a policy to any footprint. Hidden
+//! references are still written, so KiCad can show them on demand. Policies load from TOML:
+//!
+//! ```toml
+//! [default]
+//! size = 1.0
+//! thickness = 0.15
+//!
+//! [[rule]]
+//! max_body = 1.0
+//! size = 0.5
+//!
+//! [[rule]]
+//! functional_type = "connector"
+//! visible = false
+//! ```
+
+use std::fmt;
+
+use serde::Deserialize;
+
+use crate::anchor::Anchor;
+use crate::assembly_process::ProcessSet;
+use crate::board_interface::{BoardComposableObject, FpText, FpTextType, GraphicElement, Model3D, PadDescriptor, Rectangle};
+use crate::courtyard::{ActuationEnvelope, Courtyard};
+use crate::describe::DescriptionContext;
+use crate::functional_types::FunctionalType;
+use crate::pad_groups::PadGrouping;
+use crate::rule_area::RuleArea;
+
+/// Font size, stroke thickness and visibility of a text, in millimeters
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TextStyle {
+    pub size: f32,
+    pub thickness: f32,
+    #[serde(default = "visible_by_default")]
+    pub visible: bool,
+}
+
+fn visible_by_default() -> bool {
+    true
+}
+
+impl TextStyle {
+    pub fn new(size: f32, thickness: f32) -> Self {
+        Self { size, thickness, visible: true }
+    }
+
+    pub fn hidden(mut self) -> Self {
+        self.visible = false;
+        self
+    }
+}
+
+impl Default for TextStyle {
+    /// KiCad's default 1.0 mm text with 0.15 mm strokes
+    fn default() -> Self {
+        Self::new(1.0, 0.15)
+    }
+}
+
+/// Settings for the footprints a rule matches; unset fields keep the default style
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TextRule {
+    /// Functional type kind, e.g. "Resistor", matched case insensitively
+    pub functional_type: Option<String>,
+    /// Largest body side (mm) of matching footprints
+    pub max_body: Option<f32>,
+    pub size: Option<f32>,
+    pub thickness: Option<f32>,
+    pub visible: Option<bool>,
+}
+
+impl TextRule {
+    /// A rule matching every footprint
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn for_type(mut self, kind: &str) -> Self {
+        self.functional_type = Some(kind.to_string());
+        self
+    }
+
+    pub fn with_max_body(mut self, max_body: f32) -> Self {
+        self.max_body = Some(max_body);
+        self
+    }
+
+    pub fn with_size(mut self, size: f32) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    pub fn with_thickness(mut self, thickness: f32) -> Self {
+        self.thickness = Some(thickness);
+        self
+    }
+
+    pub fn with_visible(mut self, visible: bool) -> Self {
+        self.visible = Some(visible);
+        self
+    }
+
+    pub fn matches(&self, functional_type: &FunctionalType, body: &Rectangle) -> bool {
+        self.functional_type.as_ref().is_none_or(|kind| kind.eq_ignore_ascii_case(functional_type.kind_name()))
+            && self.max_body.is_none_or(|max_body| body.width().max(body.height()) <= max_body + f32::EPSILON)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextPolicyError {
+    Parse(String),
+    /// A size or thickness that isn't positive
+    Invalid(String),
+}
+
+impl fmt::Display for TextPolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextPolicyError::Parse(message) => write!(f, "invalid text policy: {}", message),
+            TextPolicyError::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for TextPolicyError {}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyFile {
+    #[serde(default)]
+    default: TextStyle,
+    #[serde(default)]
+    rule: Vec<TextRule>,
+}
+
+/// Reference text style by component class, see the module documentation
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextPolicy {
+    pub default: TextStyle,
+    /// Tried in order, the first match applies
+    pub rules: Vec<TextRule>,
+}
+
+impl TextPolicy {
+    pub fn new(default: TextStyle) -> Self {
+        Self { default, rules: Vec::new() }
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, TextPolicyError> {
+        let file: PolicyFile = toml::from_str(text).map_err(|e| TextPolicyError::Parse(e.to_string().trim_end().to_string()))?;
+        let policy = Self { default: file.default, rules: file.rule };
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    pub fn with_rule(mut self, rule: TextRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Sizes and thicknesses must be positive
+    pub fn validate(&self) -> Result<(), TextPolicyError> {
+        let values = [("default size", Some(self.default.size)), ("default thickness", Some(self.default.thickness))]
+            .into_iter()
+            .chain(self.rules.iter().flat_map(|rule| [("rule size", rule.size), ("rule thickness", rule.thickness)]));
+        for (name, value) in values {
+            if let Some(value) = value.filter(|value| !(value.is_finite() && *value > 0.0)) {
+                return Err(TextPolicyError::Invalid(format!("{} must be positive, got {}", name, value)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reference style of a footprint of `functional_type` with body `body`
+    pub fn style_for(&self, functional_type: &FunctionalType, body: &Rectangle) -> TextStyle {
+        let Some(rule) = self.rules.iter().find(|rule| rule.matches(functional_type, body)) else {
+            return self.default;
+        };
+        let size = rule.size.unwrap_or(self.default.size);
+        TextStyle {
+            size,
+            thickness: rule.thickness.unwrap_or(self.default.thickness * size / self.default.size),
+            visible: rule.visible.unwrap_or(self.default.visible),
+        }
+    }
+
+    /// Restyle the reference texts among `texts`
+    pub fn apply(&self, texts: &mut [FpText], functional_type: &FunctionalType, body: &Rectangle) {
+        let style = self.style_for(functional_type, body);
+        for text in texts.iter_mut().filter(|text| matches!(text.text_type, FpTextType::Reference)) {
+            text.font.size = (style.size, style.size);
+            text.font.thickness = style.thickness;
+            text.hidden = !style.visible;
+        }
+    }
+}
+
+/// A component with its reference text restyled by a `TextPolicy`
+#[derive(Debug, Clone)]
+pub struct TextStyled<T> {
+    inner: T,
+    policy: TextPolicy,
+}
+
+impl<T: BoardComposableObject> TextStyled<T> {
+    pub fn new(inner: T, policy: &TextPolicy) -> Self {
+        Self { inner, policy: policy.clone() }
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn policy(&self) -> &TextPolicy {
+        &self.policy
+    }
+}
+
+impl<T: BoardComposableObject> BoardComposableObject for TextStyled<T> {
+    fn is_smt(&self) -> bool {
+        self.inner.is_smt()
+    }
+
+    fn is_electrical(&self) -> bool {
+        self.inner.is_electrical()
+    }
+
+    fn is_passive(&self) -> bool {
+        self.inner.is_passive()
+    }
+
+    fn is_assembled(&self) -> bool {
+        self.inner.is_assembled()
+    }
+
+    fn allows_soldermask_bridges(&self) -> bool {
+        self.inner.allows_soldermask_bridges()
+    }
+
+    fn pad_grouping(&self) -> PadGrouping {
+        self.inner.pad_grouping()
+    }
+
+    fn terminal_count(&self) -> usize {
+        self.inner.terminal_count()
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        self.inner.functional_type()
+    }
+
+    fn footprint_name(&self) -> String {
+        self.inner.footprint_name()
+    }
+
+    fn library_name(&self) -> String {
+        self.inner.library_name()
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        self.inner.bounding_box()
+    }
+
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        self.inner.pad_descriptors()
+    }
+
+    fn description_context(&self) -> Option<DescriptionContext> {
+        self.inner.description_context()
+    }
+
+    fn description(&self) -> Option<String> {
+        self.inner.description()
+    }
+
+    fn tags(&self) -> Option<String> {
+        self.inner.tags()
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        let mut texts = self.inner.fp_text_elements();
+        self.policy.apply(&mut texts, &self.inner.functional_type(), &self.inner.bounding_box());
+        texts
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        self.inner.graphic_elements()
+    }
+
+    fn model_3d(&self) -> Option<Model3D> {
+        self.inner.model_3d()
+    }
+
+    fn rule_areas(&self) -> Vec<RuleArea> {
+        self.inner.rule_areas()
+    }
+
+    fn actuation_envelope(&self) -> Option<ActuationEnvelope> {
+        self.inner.actuation_envelope()
+    }
+
+    fn process(&self) -> ProcessSet {
+        self.inner.process()
+    }
+
+    fn courtyard_margin(&self) -> f32 {
+        self.inner.courtyard_margin()
+    }
+
+    fn generate_courtyard(&self) -> Courtyard {
+        self.inner.generate_courtyard()
+    }
+
+    fn generate_courtyards(&self) -> Vec<Courtyard> {
+        self.inner.generate_courtyards()
+    }
+
+    fn anchor(&self) -> Anchor {
+        self.inner.anchor()
+    }
+}
@@ -0,0 +1,404 @@
+//! Thermal balance of two terminal parts
+//!
+//! A chip whose pads sink heat unevenly during reflow wets one end first, and the surface
+//! tension of that joint can stand the part up on it (tombstoning). Most of a pad's heat
+//! sinking is the copper joined to it: a pad tied straight into a pour heats far slower
+//! than one reached by a thin track.
+//!
+//! `Board::thermal_balance` measures, for every two pad SMD part, the copper connected to
+//! each pad within `radius` of its center on the part's own layer. It draws the pad's net
+//! on that layer (see `Board::copper_outlines`) into a raster of `resolution` millimeter
+//! pixels and flood fills it from the pad, so copper of the same net that is only joined
+//! further out, or through another layer, doesn't count. Parts whose pads differ by more
+//! than `max_ratio` are reported; a thermal relief on the heavier pad's connection
+//! evens them out. Zones count with their whole outline, as in `copper_density`.
+//!
+//! The imbalances are informational findings, rule "thermal_imbalance", reported against
+//! the part's reference designator alongside the other board checks.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::analysis::{ElementRef, Finding, Severity, Violation};
+use crate::board::{Board, PlacedComponent};
+use crate::board_interface::{PadType, Rectangle};
+use crate::copper_density::sample_copper;
+use crate::geometry::{point_in_polygon, Point};
+use crate::layer_type::{BoardLayer, LayerType, Side};
+use crate::thieving::THIEVING_ARC_TOLERANCE;
+
+/// Reach of the copper counted around each pad (mm)
+pub const DEFAULT_THERMAL_RADIUS: f32 = 2.0;
+
+/// Largest ratio of the heavier pad's copper to the lighter's before a part is reported
+pub const DEFAULT_THERMAL_RATIO: f32 = 3.0;
+
+/// Raster pixel size (mm)
+pub const DEFAULT_THERMAL_RESOLUTION: f32 = 0.025;
+
+/// Settings of `Board::thermal_balance`, see the module documentation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThermalBalanceOptions {
+    pub radius: f32,
+    pub max_ratio: f32,
+    pub resolution: f32,
+}
+
+impl Default for ThermalBalanceOptions {
+    fn default() -> Self {
+        Self { radius: DEFAULT_THERMAL_RADIUS, max_ratio: DEFAULT_THERMAL_RATIO, resolution: DEFAULT_THERMAL_RESOLUTION }
+    }
+}
+
+impl ThermalBalanceOptions {
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    pub fn with_max_ratio(mut self, max_ratio: f32) -> Self {
+        self.max_ratio = max_ratio;
+        self
+    }
+
+    pub fn with_resolution(mut self, resolution: f32) -> Self {
+        self.resolution = resolution;
+        self
+    }
+}
+
+/// Copper connected to one pad
+#[derive(Debug, Clone, PartialEq)]
+pub struct PadCopper {
+    pub number: String,
+    pub net: Option<String>,
+    pub center: Point,
+    /// Connected copper within the radius, the pad included (mm²)
+    pub area: f32,
+}
+
+/// A two pad part and the copper on each of its pads
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThermalBalanceEntry {
+    pub reference: String,
+    pub footprint: String,
+    pub layer: BoardLayer,
+    pub pads: [PadCopper; 2],
+}
+
+impl ThermalBalanceEntry {
+    /// Pads ordered heavier first
+    pub fn by_area(&self) -> (&PadCopper, &PadCopper) {
+        let [a, b] = &self.pads;
+        if a.area >= b.area { (a, b) } else { (b, a) }
+    }
+
+    /// Heavier pad's copper over the lighter's, at least 1
+    pub fn ratio(&self) -> f32 {
+        let (heavy, light) = self.by_area();
+        if light.area > 0.0 { heavy.area / light.area } else { f32::INFINITY }
+    }
+}
+
+/// A part whose pads sink heat unevenly
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThermalImbalance {
+    pub reference: String,
+    pub heavy_pad: String,
+    pub heavy_area: f32,
+    pub light_pad: String,
+    pub light_area: f32,
+    pub radius: f32,
+    /// Center of the heavier pad
+    pub location: Point,
+}
+
+impl ThermalImbalance {
+    pub fn ratio(&self) -> f32 {
+        self.heavy_area / self.light_area
+    }
+}
+
+impl fmt::Display for ThermalImbalance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} pad {} joins {:.2} mm² of copper within {} mm, pad {} only {:.2} mm² ({:.1}x); \
+             consider a thermal relief on pad {}",
+            self.reference,
+            self.heavy_pad,
+            self.heavy_area,
+            self.radius,
+            self.light_pad,
+            self.light_area,
+            self.ratio(),
+            self.heavy_pad
+        )
+    }
+}
+
+impl Violation for ThermalImbalance {
+    fn rule(&self) -> &'static str {
+        "thermal_imbalance"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    fn footprint(&self) -> String {
+        self.reference.clone()
+    }
+
+    fn element(&self) -> Option<String> {
+        Some(self.heavy_pad.clone())
+    }
+
+    fn location(&self) -> Option<Point> {
+        Some(self.location)
+    }
+
+    fn elements(&self) -> Vec<ElementRef> {
+        vec![
+            ElementRef::BoardPad(format!("{}.{}", self.reference, self.heavy_pad)),
+            ElementRef::BoardPad(format!("{}.{}", self.reference, self.light_pad)),
+            ElementRef::Component(self.reference.clone()),
+        ]
+    }
+}
+
+/// Every two pad SMD part of a board and those out of balance
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThermalBalanceReport {
+    pub options: ThermalBalanceOptions,
+    pub entries: Vec<ThermalBalanceEntry>,
+}
+
+impl ThermalBalanceReport {
+    /// Parts whose ratio exceeds `options.max_ratio`
+    pub fn imbalances(&self) -> Vec<ThermalImbalance> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.ratio() > self.options.max_ratio)
+            .map(|entry| {
+                let (heavy, light) = entry.by_area();
+                ThermalImbalance {
+                    reference: entry.reference.clone(),
+                    heavy_pad: heavy.number.clone(),
+                    heavy_area: heavy.area,
+                    light_pad: light.number.clone(),
+                    light_area: light.area,
+                    radius: self.options.radius,
+                    location: heavy.center,
+                }
+            })
+            .collect()
+    }
+
+    pub fn findings(&self) -> Vec<Finding> {
+        self.imbalances().iter().map(Violation::to_finding).collect()
+    }
+}
+
+impl fmt::Display for ThermalBalanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.entries.iter().map(|entry| entry.reference.len()).chain(["Ref".len()]).max().unwrap_or_default();
+        writeln!(f, "{:<width$}  {:<5}  {:>4}  {:>9}  {:>4}  {:>9}  {:>6}", "Ref", "Layer", "Pad", "Area mm²", "Pad", "Area mm²", "Ratio")?;
+        for entry in &self.entries {
+            let [a, b] = &entry.pads;
+            let flag = if entry.ratio() > self.options.max_ratio { "  !" } else { "" };
+            writeln!(
+                f,
+                "{:<width$}  {:<5}  {:>4}  {:>9.3}  {:>4}  {:>9.3}  {:>6.2}{}",
+                entry.reference,
+                entry.layer.to_kicad_string(),
+                a.number,
+                a.area,
+                b.number,
+                b.area,
+                entry.ratio(),
+                flag
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Area of `copper` connected to `pad` within `radius` of `center`, flood filled over a
+/// raster of `resolution` pixels
+fn connected_area(copper: &[Vec<Point>], pad: &[Point], center: Point, radius: f32, resolution: f32) -> f32 {
+    let size = ((2.0 * radius / resolution).ceil() as usize).max(1);
+    let origin = Point::new(center.x - radius, center.y - radius);
+    let covered = sample_copper(copper, origin, resolution, size, size);
+    let sample = |column: usize, row: usize| {
+        Point::new(origin.x + (column as f32 + 0.5) * resolution, origin.y + (row as f32 + 0.5) * resolution)
+    };
+    let reachable = |index: usize| covered[index] && sample(index % size, index / size).distance_to(&center) <= radius;
+
+    let mut filled = vec![false; size * size];
+    let mut stack: Vec<usize> = (0..size * size).filter(|&index| reachable(index) && point_in_polygon(sample(index % size, index / size), pad)).collect();
+    for &index in &stack {
+        filled[index] = true;
+    }
+    let mut count = 0;
+    while let Some(index) = stack.pop() {
+        count += 1;
+        let (column, row) = (index % size, index / size);
+        let neighbours = [
+            (column > 0).then(|| index - 1),
+            (column + 1 < size).then_some(index + 1),
+            (row > 0).then(|| index - size),
+            (row + 1 < size).then_some(index + size),
+        ];
+        for next in neighbours.into_iter().flatten() {
+            if !filled[next] && reachable(next) {
+                filled[next] = true;
+                stack.push(next);
+            }
+        }
+    }
+    count as f32 * resolution * resolution
+}
+
+/// Numbers of the two SMD copper pads of a two terminal part, `None` for any other part
+fn two_smd_pads(placed: &PlacedComponent) -> Option<[String; 2]> {
+    let pads: Vec<_> = placed
+        .component
+        .pad_descriptors()
+        .into_iter()
+        .filter(|pad| !pad.number.is_empty() && pad.has_copper() && !matches!(pad.pad_type, PadType::NPTH))
+        .collect();
+    match pads.as_slice() {
+        [a, b] if a.number != b.number && [a, b].iter().all(|pad| matches!(pad.pad_type, PadType::SMD)) => {
+            Some([a.number.to_string(), b.number.to_string()])
+        }
+        _ => None,
+    }
+}
+
+impl Board {
+    /// Copper connected to each pad of every two pad SMD part, see the module
+    /// documentation
+    pub fn thermal_balance(&self, options: &ThermalBalanceOptions) -> ThermalBalanceReport {
+        let mut pad_outlines: HashMap<BoardLayer, HashMap<String, Vec<Point>>> = HashMap::new();
+        let mut net_copper: HashMap<(BoardLayer, String), Vec<Vec<Point>>> = HashMap::new();
+        let mut entries = Vec::new();
+        for placed in self.components() {
+            let Some(numbers) = two_smd_pads(placed) else { continue };
+            let layer = match placed.side {
+                Side::Back => BoardLayer::back(LayerType::Copper),
+                _ => BoardLayer::front(LayerType::Copper),
+            };
+            let outlines = pad_outlines
+                .entry(layer)
+                .or_insert_with(|| self.pad_outlines(layer, THIEVING_ARC_TOLERANCE).into_iter().collect());
+            let mut pads = Vec::with_capacity(2);
+            for number in numbers {
+                let Some(outline) = outlines.get(&format!("{}.{}", placed.reference, number)) else { break };
+                let Some(bounds) = Rectangle::enclosing(outline) else { break };
+                let center = Point::new((bounds.min_x + bounds.max_x) / 2.0, (bounds.min_y + bounds.max_y) / 2.0);
+                let net = self.pad_net(&placed.reference, &number).map(str::to_string);
+                // A pad without a net joins nothing but itself
+                let area = match &net {
+                    Some(net) => {
+                        let copper = net_copper.entry((layer, net.clone())).or_insert_with(|| self.net_copper_outlines(layer, net));
+                        connected_area(copper, outline, center, options.radius, options.resolution)
+                    }
+                    None => connected_area(std::slice::from_ref(outline), outline, center, options.radius, options.resolution),
+                };
+                pads.push(PadCopper { number, net, center, area });
+            }
+            let Ok(pads) = <[PadCopper; 2]>::try_from(pads) else { continue };
+            entries.push(ThermalBalanceEntry {
+                reference: placed.reference.clone(),
+                footprint: placed.component.footprint_name(),
+                layer,
+                pads,
+            });
+        }
+        ThermalBalanceReport { options: *options, entries }
+    }
+
+    /// The thermal imbalances of `thermal_balance` as informational findings
+    pub fn thermal_balance_findings(&self, options: &ThermalBalanceOptions) -> Vec<Finding> {
+        self.thermal_balance(options).findings()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::presets;
+    use crate::routing::Track;
+    use crate::zone::Zone;
+
+    fn front() -> BoardLayer {
+        BoardLayer::front(LayerType::Copper)
+    }
+
+    fn pad_center(board: &Board, reference: &str, number: &str) -> Point {
+        let everything = Rectangle { min_x: -100.0, min_y: -100.0, max_x: 100.0, max_y: 100.0 };
+        board.pads_in_rect(&everything).into_iter().find(|pad| pad.reference == reference && pad.number.as_str() == number).unwrap().center
+    }
+
+    /// An 0402 at (10, 10) with a 0.2 mm track leaving each pad sideways
+    fn traced_0402() -> Board {
+        let mut board = Board::new("thermal").with_rectangular_outline(20.0, 20.0);
+        board.place("R1", presets::resistor_0402("10k"), (10.0, 10.0), 0.0).unwrap();
+        board.connect_pad("R1", "1", "GND");
+        board.connect_pad("R1", "2", "SIG");
+        let (pad1, pad2) = (pad_center(&board, "R1", "1"), pad_center(&board, "R1", "2"));
+        board.add_track(Track::new("GND", front(), pad1, Point::new(pad1.x - 5.0, pad1.y), 0.2));
+        board.add_track(Track::new("SIG", front(), pad2, Point::new(pad2.x + 5.0, pad2.y), 0.2));
+        board
+    }
+
+    #[test]
+    fn pad_in_a_pour_is_flagged() {
+        let mut board = traced_0402();
+        let pad1 = pad_center(&board, "R1", "1");
+        // A GND pour reaching up to pad 1, leaving pad 2 well clear
+        let outline = vec![Point::new(2.0, 2.0), Point::new(pad1.x, 2.0), Point::new(pad1.x, 18.0), Point::new(2.0, 18.0)];
+        board.add_zone(Zone::new("GND", &[front()], outline));
+
+        let report = board.thermal_balance(&ThermalBalanceOptions::default());
+        assert_eq!(report.entries.len(), 1);
+        let (heavy, light) = report.entries[0].by_area();
+        assert_eq!((heavy.number.as_str(), light.number.as_str()), ("1", "2"));
+        // Half the 2 mm circle is pour, the other pad gets a pad and a 0.2 mm track stub
+        assert!(heavy.area > 6.0, "{}", report);
+        assert!(light.area < 1.0, "{}", report);
+
+        let findings = board.thermal_balance_findings(&ThermalBalanceOptions::default());
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, "thermal_imbalance");
+        assert_eq!(findings[0].severity, Severity::Info);
+        assert_eq!(findings[0].footprint, "R1");
+        assert_eq!(findings[0].element.as_deref(), Some("1"));
+        assert!(findings[0].elements.contains(&ElementRef::BoardPad("R1.2".to_string())));
+    }
+
+    #[test]
+    fn symmetric_connections_pass() {
+        let board = traced_0402();
+        let report = board.thermal_balance(&ThermalBalanceOptions::default());
+        assert_eq!(report.entries.len(), 1);
+        let [a, b] = &report.entries[0].pads;
+        assert!((a.area - b.area).abs() < 0.05, "{}", report);
+        assert!(report.imbalances().is_empty());
+    }
+
+    #[test]
+    fn copper_of_other_nets_and_beyond_the_radius_is_left_out() {
+        let mut board = traced_0402();
+        let pad1 = pad_center(&board, "R1", "1");
+        // Another net's pour next to pad 1 and a GND pour on its track out of reach
+        board.add_zone(Zone::new("VCC", &[front()], vec![Point::new(6.0, 6.0), Point::new(9.0, 6.0), Point::new(9.0, 9.6), Point::new(6.0, 9.6)]));
+        let end = Point::new(pad1.x - 5.0, pad1.y);
+        board.add_zone(Zone::new("GND", &[front()], vec![Point::new(0.5, 8.0), Point::new(end.x + 0.1, 8.0), Point::new(end.x + 0.1, 12.0), Point::new(0.5, 12.0)]));
+        assert!(board.thermal_balance(&ThermalBalanceOptions::default()).imbalances().is_empty());
+        // Widening the reach takes in the GND pour along the track
+        let wide = ThermalBalanceOptions::default().with_radius(8.0).with_resolution(0.05);
+        assert_eq!(board.thermal_balance(&wide).imbalances().len(), 1);
+    }
+}
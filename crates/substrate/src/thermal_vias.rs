@@ -0,0 +1,157 @@
+//! Thermal vias for exposed pads
+//!
+//! Heat from an exposed pad or power tab reaches the inner and back copper through a grid
+//! of small plated holes inside the pad. `layout_thermal_vias` fits as many vias at
+//! `pitch` as the pad holds with every via's copper at least `edge_clearance` inside the
+//! pad edge, and centers the grid on the pad. The drill and the ring around it are checked
+//! against a `FabProfile` first.
+//!
+//! The vias are through hole pads carrying the pad's number, so they join its net and
+//! count as the same terminal. They have copper only: the mask stays closed over them on
+//! the back, and on the front the pad's own opening exposes them. A pad whose routing
+//! hint forbids vias under it (see `routing_hint`) gets none.
+
+use std::fmt;
+
+use crate::board_interface::{PadDescriptor, PadShape, PadType, TentingSettings, TentingType};
+use crate::fab_profile::FabProfile;
+use crate::geometry::Point;
+use crate::ids::fresh_uuid;
+use crate::layer_type::PadLayers;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThermalViaOptions {
+    /// Finished hole diameter
+    pub drill: f32,
+    /// Copper diameter of each via
+    pub diameter: f32,
+    /// Center to center distance along both axes
+    pub pitch: f32,
+    /// Smallest distance from a via's copper to the pad edge
+    pub edge_clearance: f32,
+}
+
+impl Default for ThermalViaOptions {
+    fn default() -> Self {
+        Self { drill: 0.3, diameter: 0.6, pitch: 1.2, edge_clearance: 0.2 }
+    }
+}
+
+impl ThermalViaOptions {
+    pub fn new(drill: f32, diameter: f32) -> Self {
+        Self { drill, diameter, ..Self::default() }
+    }
+
+    pub fn with_pitch(mut self, pitch: f32) -> Self {
+        self.pitch = pitch;
+        self
+    }
+
+    pub fn with_edge_clearance(mut self, edge_clearance: f32) -> Self {
+        self.edge_clearance = edge_clearance;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ThermalViaError {
+    /// The drill is below what the profile can make
+    DrillTooSmall { drill: f32, min_drill: f32 },
+    /// The copper around the drill is below the profile's annular ring
+    AnnularRing { ring: f32, minimum: f32 },
+    /// The pitch would let neighbouring vias touch
+    PitchTooSmall { pitch: f32, diameter: f32 },
+    /// Not even one via fits inside the pad with its edge clearance
+    PadTooSmall { size: (f32, f32), diameter: f32, edge_clearance: f32 },
+    /// The pad's routing hint forbids vias in it, see `routing_hint`
+    ViaUnderPadForbidden(String),
+}
+
+impl fmt::Display for ThermalViaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThermalViaError::DrillTooSmall { drill, min_drill } => {
+                write!(f, "{} mm thermal via drill is below the {} mm minimum", drill, min_drill)
+            }
+            ThermalViaError::AnnularRing { ring, minimum } => {
+                write!(f, "thermal via annular ring {:.3} mm is below the {} mm minimum", ring, minimum)
+            }
+            ThermalViaError::PitchTooSmall { pitch, diameter } => {
+                write!(f, "{} mm thermal via pitch is not more than the {} mm via diameter", pitch, diameter)
+            }
+            ThermalViaError::PadTooSmall { size, diameter, edge_clearance } => write!(
+                f,
+                "{} x {} mm pad can't hold a {} mm via {} mm from its edge",
+                size.0, size.1, diameter, edge_clearance
+            ),
+            ThermalViaError::ViaUnderPadForbidden(number) => write!(f, "pad {} takes no vias", number),
+        }
+    }
+}
+
+impl std::error::Error for ThermalViaError {}
+
+/// Via count along a pad side of `size`, see the module documentation
+fn via_count(size: f32, options: &ThermalViaOptions) -> usize {
+    let usable = size - 2.0 * options.edge_clearance - options.diameter;
+    if usable < -f32::EPSILON { 0 } else { (usable / options.pitch + 1e-4).floor() as usize + 1 }
+}
+
+/// Lay out thermal vias inside `pad`, see the module documentation
+pub fn layout_thermal_vias(
+    pad: &PadDescriptor,
+    options: &ThermalViaOptions,
+    profile: &FabProfile,
+) -> Result<Vec<PadDescriptor>, ThermalViaError> {
+    if pad.routing_hint.as_ref().is_some_and(|hint| hint.no_via_under_pad) {
+        return Err(ThermalViaError::ViaUnderPadForbidden(pad.number.to_string()));
+    }
+    if options.drill < profile.min_drill {
+        return Err(ThermalViaError::DrillTooSmall { drill: options.drill, min_drill: profile.min_drill });
+    }
+    let ring = (options.diameter - options.drill) / 2.0;
+    if ring < profile.min_annular_ring {
+        return Err(ThermalViaError::AnnularRing { ring, minimum: profile.min_annular_ring });
+    }
+    if options.pitch <= options.diameter {
+        return Err(ThermalViaError::PitchTooSmall { pitch: options.pitch, diameter: options.diameter });
+    }
+    let (columns, rows) = (via_count(pad.size.0, options), via_count(pad.size.1, options));
+    if columns == 0 || rows == 0 {
+        return Err(ThermalViaError::PadTooSmall {
+            size: pad.size,
+            diameter: options.diameter,
+            edge_clearance: options.edge_clearance,
+        });
+    }
+
+    let rotation = pad.rotation.unwrap_or(0.0);
+    let start = ((columns - 1) as f32 * options.pitch / -2.0, (rows - 1) as f32 * options.pitch / -2.0);
+    let vias = (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (row, column)))
+        .map(|(row, column)| {
+            let center = (start.0 + column as f32 * options.pitch, start.1 + row as f32 * options.pitch);
+            let position = Point::from(center).rotated(rotation).offset(pad.position.0, pad.position.1);
+            PadDescriptor {
+                number: pad.number.clone(),
+                pin_function: pad.pin_function.clone(),
+                pad_type: PadType::ThroughHole,
+                shape: PadShape::Circle,
+                position: (position.x, position.y),
+                rotation: None,
+                size: (options.diameter, options.diameter),
+                drill_size: Some(options.drill),
+                drill_slot: None,
+                layers: PadLayers::tht_tented(),
+                roundrect_ratio: None,
+                tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+                padstack: None,
+                fab_property: None,
+                solder_mask_margin: None,
+                routing_hint: None,
+                uuid: fresh_uuid(),
+            }
+        })
+        .collect();
+    Ok(vias)
+}
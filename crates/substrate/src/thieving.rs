@@ -0,0 +1,389 @@
+//! Copper thieving
+//!
+//! Large empty areas on the outer layers plate unevenly and let the board warp, so fabs ask
+//! for copper balancing. Thieving fills such areas with small copper shapes that belong to
+//! no net, each kept `clearance` away from pads, other copper and the board edge.
+//!
+//! Coverage is measured by sampling the copper inside a region on a fine raster, which
+//! copes with overlapping shapes (hatch crossings, stacked pads) without polygon boolean
+//! operations.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::board::Board;
+use crate::board_interface::Rectangle;
+use crate::geometry::{point_in_polygon, polygon_distance, polygon_edge_distance, push_arc, Point};
+use crate::layer_type::{BoardLayer, LayerType};
+
+/// Arc tolerance when tessellating dots and pads for thieving (10 microns)
+pub const THIEVING_ARC_TOLERANCE: f32 = 0.01;
+
+/// Sample spacing of the coverage raster in millimeters
+pub const COVERAGE_RESOLUTION: f32 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThievingPattern {
+    /// Round dots of diameter `size` on a square lattice
+    Dots,
+    /// Square pads of side `size` on a square lattice
+    Grid,
+    /// Horizontal and vertical lines `size` wide, `pitch` apart, broken around obstacles
+    Hatch,
+}
+
+impl ThievingPattern {
+    /// Fraction of an unobstructed area the pattern covers
+    pub fn coverage(&self, size: f32, pitch: f32) -> f32 {
+        let cell = pitch * pitch;
+        match self {
+            ThievingPattern::Dots => std::f32::consts::PI * size * size / 4.0 / cell,
+            ThievingPattern::Grid => size * size / cell,
+            ThievingPattern::Hatch => (2.0 * size * pitch - size * size) / cell,
+        }
+    }
+
+    /// Pitch at which the pattern covers `target` (0 to 1) of an unobstructed area. Keepouts
+    /// around existing copper take their share, so the achieved coverage of a populated
+    /// region is somewhat lower.
+    pub fn pitch_for_coverage(&self, size: f32, target: f32) -> f32 {
+        let target = target.clamp(f32::EPSILON, 1.0);
+        match self {
+            ThievingPattern::Dots => size * (std::f32::consts::PI / 4.0 / target).sqrt(),
+            ThievingPattern::Grid => size / target.sqrt(),
+            ThievingPattern::Hatch => size * (1.0 + (1.0 - target).sqrt()) / target,
+        }
+    }
+}
+
+/// A single piece of thieving copper in board coordinates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ThievingShape {
+    Dot { center: Point, diameter: f32 },
+    /// Closed polygon, last point not repeated
+    Polygon(Vec<Point>),
+}
+
+impl ThievingShape {
+    pub fn outline(&self, arc_tolerance: f32) -> Vec<Point> {
+        match self {
+            ThievingShape::Dot { center, diameter } => {
+                let mut outline = Vec::new();
+                push_arc(&mut outline, *center, diameter / 2.0, 0.0, 360.0, arc_tolerance);
+                outline
+            }
+            ThievingShape::Polygon(points) => points.clone(),
+        }
+    }
+}
+
+/// Thieving shape on a copper layer, not connected to any net (net 0 in KiCad)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThievingCopper {
+    pub layer: BoardLayer,
+    pub shape: ThievingShape,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CopperCoverage {
+    /// Copper inside the region in square millimeters
+    pub copper_area: f32,
+    pub region_area: f32,
+}
+
+impl CopperCoverage {
+    /// Covered fraction of the region, 0 to 1
+    pub fn fraction(&self) -> f32 {
+        if self.region_area > 0.0 { self.copper_area / self.region_area } else { 0.0 }
+    }
+}
+
+impl fmt::Display for CopperCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.1}% ({:.2} of {:.2} mm²)", self.fraction() * 100.0, self.copper_area, self.region_area)
+    }
+}
+
+/// Outcome of `Board::generate_thieving`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThievingReport {
+    pub layer: BoardLayer,
+    pub shapes_added: usize,
+    pub before: CopperCoverage,
+    pub after: CopperCoverage,
+}
+
+impl fmt::Display for ThievingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}: {} thieving shapes added", self.layer.to_kicad_string(), self.shapes_added)?;
+        writeln!(f, "Coverage before: {}", self.before)?;
+        writeln!(f, "Coverage after: {}", self.after)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ThievingError {
+    /// Thieving only goes on copper layers
+    NotCopper(BoardLayer),
+    /// The region needs at least three points
+    InvalidRegion,
+    /// Size, pitch or clearance out of range
+    InvalidPattern(String),
+}
+
+impl fmt::Display for ThievingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThievingError::NotCopper(layer) => write!(f, "{} is not a copper layer", layer.to_kicad_string()),
+            ThievingError::InvalidRegion => write!(f, "thieving region must be a polygon with at least three points"),
+            ThievingError::InvalidPattern(message) => write!(f, "invalid thieving pattern: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ThievingError {}
+
+/// Thieving copper closer to other copper or the board edge than the required clearance
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClearanceViolation {
+    pub layer: BoardLayer,
+    /// A point on the offending thieving shape
+    pub location: Point,
+    /// What it is too close to, e.g. "pad R1.2" or "board edge"
+    pub object: String,
+    pub distance: f32,
+}
+
+impl fmt::Display for ClearanceViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: thieving at ({:.3}, {:.3}) is {:.3} mm from {}",
+            self.layer.to_kicad_string(),
+            self.location.x,
+            self.location.y,
+            self.distance,
+            self.object
+        )
+    }
+}
+
+/// A polygon together with its bounds, for cheap rejection before exact distance checks
+struct Obstacle {
+    name: String,
+    outline: Vec<Point>,
+    bounds: Rectangle,
+}
+
+impl Obstacle {
+    fn new(name: String, outline: Vec<Point>) -> Option<Self> {
+        let bounds = Rectangle::enclosing(&outline)?;
+        Some(Self { name, outline, bounds })
+    }
+
+    /// Edge to edge distance, or infinity when the bounds are already more than `limit` apart
+    fn distance(&self, outline: &[Point], bounds: &Rectangle, limit: f32) -> f32 {
+        let dx = (self.bounds.min_x - bounds.max_x).max(bounds.min_x - self.bounds.max_x);
+        let dy = (self.bounds.min_y - bounds.max_y).max(bounds.min_y - self.bounds.max_y);
+        if dx > limit || dy > limit {
+            return f32::INFINITY;
+        }
+        polygon_distance(&self.outline, outline)
+    }
+}
+
+/// Distance from a shape to the boundary of `container`, zero when any part lies outside
+fn inset_distance(outline: &[Point], container: &[Point]) -> f32 {
+    if outline.iter().any(|p| !point_in_polygon(*p, container)) {
+        return 0.0;
+    }
+    polygon_edge_distance(outline, container)
+}
+
+/// Every shape of `pattern` on a lattice anchored at the origin and covering `bounds`
+fn pattern_shapes(pattern: ThievingPattern, bounds: &Rectangle, size: f32, pitch: f32) -> Vec<ThievingShape> {
+    let columns = ((bounds.min_x / pitch).floor() as i32)..=((bounds.max_x / pitch).ceil() as i32);
+    let rows = ((bounds.min_y / pitch).floor() as i32)..=((bounds.max_y / pitch).ceil() as i32);
+    let half = size / 2.0;
+    let rectangle = |min_x: f32, min_y: f32, max_x: f32, max_y: f32| {
+        ThievingShape::Polygon(vec![
+            Point::new(min_x, min_y),
+            Point::new(max_x, min_y),
+            Point::new(max_x, max_y),
+            Point::new(min_x, max_y),
+        ])
+    };
+
+    let mut shapes = Vec::new();
+    for row in rows {
+        for column in columns.clone() {
+            let (x, y) = (column as f32 * pitch, row as f32 * pitch);
+            match pattern {
+                ThievingPattern::Dots => shapes.push(ThievingShape::Dot { center: Point::new(x, y), diameter: size }),
+                ThievingPattern::Grid => shapes.push(rectangle(x - half, y - half, x + half, y + half)),
+                ThievingPattern::Hatch => {
+                    shapes.push(rectangle(x, y - half, x + pitch, y + half));
+                    shapes.push(rectangle(x - half, y, x + half, y + pitch));
+                }
+            }
+        }
+    }
+    shapes
+}
+
+/// Sample the copper inside `region` on a raster of `resolution` millimeters
+pub fn copper_coverage(region: &[Point], copper: &[Vec<Point>], resolution: f32) -> CopperCoverage {
+    let Some(bounds) = Rectangle::enclosing(region) else {
+        return CopperCoverage { copper_area: 0.0, region_area: 0.0 };
+    };
+    let columns = (bounds.width() / resolution).ceil() as usize;
+    let rows = (bounds.height() / resolution).ceil() as usize;
+    let sample = |column: usize, row: usize| {
+        Point::new(bounds.min_x + (column as f32 + 0.5) * resolution, bounds.min_y + (row as f32 + 0.5) * resolution)
+    };
+
+    let mut in_region = vec![false; columns * rows];
+    for row in 0..rows {
+        for column in 0..columns {
+            in_region[row * columns + column] = point_in_polygon(sample(column, row), region);
+        }
+    }
+
+    let mut covered = vec![false; columns * rows];
+    for outline in copper {
+        let Some(shape_bounds) = Rectangle::enclosing(outline) else { continue };
+        let first_column = ((shape_bounds.min_x - bounds.min_x) / resolution).floor().max(0.0) as usize;
+        let last_column = (((shape_bounds.max_x - bounds.min_x) / resolution).ceil().max(0.0) as usize).min(columns);
+        let first_row = ((shape_bounds.min_y - bounds.min_y) / resolution).floor().max(0.0) as usize;
+        let last_row = (((shape_bounds.max_y - bounds.min_y) / resolution).ceil().max(0.0) as usize).min(rows);
+        for row in first_row..last_row {
+            for column in first_column..last_column {
+                let index = row * columns + column;
+                if in_region[index] && !covered[index] && point_in_polygon(sample(column, row), outline) {
+                    covered[index] = true;
+                }
+            }
+        }
+    }
+
+    let cell_area = resolution * resolution;
+    CopperCoverage {
+        copper_area: covered.iter().filter(|c| **c).count() as f32 * cell_area,
+        region_area: in_region.iter().filter(|c| **c).count() as f32 * cell_area,
+    }
+}
+
+impl Board {
+    /// Thieving copper added so far, on every layer
+    pub fn thieving(&self) -> &[ThievingCopper] {
+        &self.thieving
+    }
+
+    /// Remove the thieving on `layer`, e.g. before regenerating it with another pattern
+    pub fn clear_thieving(&mut self, layer: BoardLayer) {
+        self.thieving.retain(|copper| copper.layer != layer);
+    }
+
+    /// Fill `region` on a copper `layer` with non-connected copper in `pattern`, keeping
+    /// `clearance` from pads, existing copper, the region boundary and the board outline.
+    /// Shapes that would violate the clearance are dropped rather than clipped.
+    pub fn generate_thieving(
+        &mut self,
+        layer: BoardLayer,
+        region: &[Point],
+        pattern: ThievingPattern,
+        size: f32,
+        pitch: f32,
+        clearance: f32,
+    ) -> Result<ThievingReport, ThievingError> {
+        if layer.layer != LayerType::Copper {
+            return Err(ThievingError::NotCopper(layer));
+        }
+        let region_bounds = match Rectangle::enclosing(region) {
+            Some(bounds) if region.len() >= 3 => bounds,
+            _ => return Err(ThievingError::InvalidRegion),
+        };
+        if size <= 0.0 || clearance < 0.0 {
+            return Err(ThievingError::InvalidPattern("size must be positive and clearance non-negative".to_string()));
+        }
+        if pitch < size {
+            return Err(ThievingError::InvalidPattern(format!("pitch {} is smaller than size {}", pitch, size)));
+        }
+
+        let before = self.copper_coverage(layer, region);
+        let obstacles = self.copper_obstacles(layer);
+        let mut added = Vec::new();
+        for shape in pattern_shapes(pattern, &region_bounds, size, pitch) {
+            let outline = shape.outline(THIEVING_ARC_TOLERANCE);
+            let Some(bounds) = Rectangle::enclosing(&outline) else { continue };
+            if inset_distance(&outline, region) < clearance {
+                continue;
+            }
+            if self.outline().is_some_and(|edge| inset_distance(&outline, edge) < clearance) {
+                continue;
+            }
+            if obstacles.iter().any(|obstacle| obstacle.distance(&outline, &bounds, clearance) < clearance) {
+                continue;
+            }
+            added.push(ThievingCopper { layer, shape });
+        }
+
+        let shapes_added = added.len();
+        self.thieving.extend(added);
+        let after = self.copper_coverage(layer, region);
+        Ok(ThievingReport { layer, shapes_added, before, after })
+    }
+
+    /// Pad and thieving copper on `layer` inside `region`
+    pub fn copper_coverage(&self, layer: BoardLayer, region: &[Point]) -> CopperCoverage {
+        let copper: Vec<Vec<Point>> = self
+            .pad_outlines(layer, THIEVING_ARC_TOLERANCE)
+            .into_iter()
+            .map(|(_, outline)| outline)
+            .chain(self.thieving_outlines(layer))
+            .collect();
+        copper_coverage(region, &copper, COVERAGE_RESOLUTION)
+    }
+
+    /// Thieving copper closer than `clearance` to a pad or the board outline
+    pub fn thieving_clearance_violations(&self, clearance: f32) -> Vec<ClearanceViolation> {
+        let mut violations = Vec::new();
+        for copper in &self.thieving {
+            let outline = copper.shape.outline(THIEVING_ARC_TOLERANCE);
+            let Some(bounds) = Rectangle::enclosing(&outline) else { continue };
+            let mut report = |object: String, distance: f32| {
+                violations.push(ClearanceViolation { layer: copper.layer, location: outline[0], object, distance });
+            };
+            if let Some(edge) = self.outline() {
+                let distance = inset_distance(&outline, edge);
+                if distance < clearance {
+                    report("board edge".to_string(), distance);
+                }
+            }
+            for (name, pad) in self.pad_outlines(copper.layer, THIEVING_ARC_TOLERANCE) {
+                let Some(obstacle) = Obstacle::new(name, pad) else { continue };
+                let distance = obstacle.distance(&outline, &bounds, clearance);
+                if distance < clearance {
+                    report(format!("pad {}", obstacle.name), distance);
+                }
+            }
+        }
+        violations
+    }
+
+    fn thieving_outlines(&self, layer: BoardLayer) -> impl Iterator<Item = Vec<Point>> + '_ {
+        self.thieving
+            .iter()
+            .filter(move |copper| copper.layer == layer)
+            .map(|copper| copper.shape.outline(THIEVING_ARC_TOLERANCE))
+    }
+
+    fn copper_obstacles(&self, layer: BoardLayer) -> Vec<Obstacle> {
+        self.pad_outlines(layer, THIEVING_ARC_TOLERANCE)
+            .into_iter()
+            .chain(self.thieving_outlines(layer).map(|outline| ("thieving".to_string(), outline)))
+            .filter_map(|(name, outline)| Obstacle::new(name, outline))
+            .collect()
+    }
+}
@@ -0,0 +1,206 @@
+//! Toleranced dimensions and IPC-7351 land pattern math
+//!
+//! Datasheets give lead dimensions as a nominal value with a tolerance, or as a minimum
+//! and a maximum. `Toleranced` holds either; a plain `f32` converts to an exact value, so
+//! generator builders take `impl Into<Toleranced>` and keep accepting numbers.
+//!
+//! `ipc_land` builds a land pattern from the envelope of the part, as IPC-7351 does: the
+//! outer pad span `Zmax` from the shortest lead span, the inner gap `Gmin` from the
+//! longest heel to heel distance and the pad width `Xmax` from the narrowest lead. Each
+//! is widened by the root sum square of the part's own tolerance and the board's
+//! fabrication and placement tolerances (`ManufacturingTolerances`). The heel to heel
+//! tolerance is itself the root sum square of the span's and both feet's.
+//!
+//! When every dimension is exact there is no envelope to work from, so the manufacturing
+//! tolerances are left out as well and the result is the nominal construction: span plus
+//! toe fillets, heel to heel less heel fillets, lead plus side fillets.
+
+use std::fmt;
+
+use crate::chip::ChipFillets;
+
+/// Fabrication tolerance assumed for the board (mm)
+pub const DEFAULT_FABRICATION_TOLERANCE: f32 = 0.1;
+/// Placement accuracy assumed for the assembly machine (mm)
+pub const DEFAULT_PLACEMENT_TOLERANCE: f32 = 0.05;
+
+/// A dimension with its tolerance (mm). `plus` and `minus` are both positive: the
+/// dimension lies between `nominal - minus` and `nominal + plus`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Toleranced {
+    pub nominal: f32,
+    pub plus: f32,
+    pub minus: f32,
+}
+
+impl Toleranced {
+    pub fn new(nominal: f32, plus: f32, minus: f32) -> Self {
+        Self { nominal, plus: plus.abs(), minus: minus.abs() }
+    }
+
+    /// No tolerance at all
+    pub fn exact(nominal: f32) -> Self {
+        Self { nominal, plus: 0.0, minus: 0.0 }
+    }
+
+    /// `nominal ± tolerance`
+    pub fn symmetric(nominal: f32, tolerance: f32) -> Self {
+        Self::new(nominal, tolerance, tolerance)
+    }
+
+    /// From a datasheet's minimum and maximum, nominal in the middle
+    pub fn min_max(min: f32, max: f32) -> Self {
+        let (min, max) = (min.min(max), min.max(max));
+        let half = (max - min) / 2.0;
+        Self { nominal: min + half, plus: half, minus: half }
+    }
+
+    pub fn min(&self) -> f32 {
+        self.nominal - self.minus
+    }
+
+    pub fn max(&self) -> f32 {
+        self.nominal + self.plus
+    }
+
+    /// Width of the range, maximum less minimum
+    pub fn tolerance(&self) -> f32 {
+        self.plus + self.minus
+    }
+
+    pub fn is_exact(&self) -> bool {
+        self.plus == 0.0 && self.minus == 0.0
+    }
+}
+
+impl From<f32> for Toleranced {
+    fn from(nominal: f32) -> Self {
+        Self::exact(nominal)
+    }
+}
+
+impl fmt::Display for Toleranced {
+    /// "6", "6±0.2" or "6 +0.1/-0.2"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_exact() {
+            write!(f, "{}", self.nominal)
+        } else if self.plus == self.minus {
+            write!(f, "{}±{}", self.nominal, self.plus)
+        } else {
+            write!(f, "{} +{}/-{}", self.nominal, self.plus, self.minus)
+        }
+    }
+}
+
+/// Board fabrication and placement tolerances the land pattern allows for (mm)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ManufacturingTolerances {
+    pub fabrication: f32,
+    pub placement: f32,
+}
+
+impl Default for ManufacturingTolerances {
+    fn default() -> Self {
+        Self { fabrication: DEFAULT_FABRICATION_TOLERANCE, placement: DEFAULT_PLACEMENT_TOLERANCE }
+    }
+}
+
+/// Outer span, inner gap and width of the pads for two opposite rows of leads (mm)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IpcLand {
+    /// Outer edge to outer edge across the rows, IPC's Z
+    pub outer: f32,
+    /// Inner edge to inner edge, IPC's G
+    pub inner: f32,
+    /// Pad size along the row, IPC's X
+    pub width: f32,
+}
+
+impl IpcLand {
+    /// Pad size across the row
+    pub fn pad_length(&self) -> f32 {
+        (self.outer - self.inner) / 2.0
+    }
+
+    /// Distance of each row of pad centers from the middle
+    pub fn row_offset(&self) -> f32 {
+        (self.outer + self.inner) / 4.0
+    }
+}
+
+/// Land pattern for leads spanning `span` toe to toe, with feet `length` long and
+/// `width` wide, see the module documentation
+pub fn ipc_land(
+    span: Toleranced,
+    length: Toleranced,
+    width: Toleranced,
+    fillets: ChipFillets,
+    tolerances: ManufacturingTolerances,
+) -> IpcLand {
+    let exact = span.is_exact() && length.is_exact() && width.is_exact();
+    let (fabrication, placement) = if exact { (0.0, 0.0) } else { (tolerances.fabrication, tolerances.placement) };
+    let rss = |part: f32| (part * part + fabrication * fabrication + placement * placement).sqrt();
+
+    let outer = span.min() + 2.0 * fillets.toe + rss(span.tolerance());
+    let heel_tolerance = (span.tolerance().powi(2) + 2.0 * length.tolerance().powi(2)).sqrt();
+    let heel_nominal = ((span.min() - 2.0 * length.max()) + (span.max() - 2.0 * length.min())) / 2.0;
+    let inner = (heel_nominal + heel_tolerance / 2.0 - 2.0 * fillets.heel - rss(heel_tolerance)).max(0.0);
+    let width = width.min() + 2.0 * fillets.side + rss(width.tolerance());
+    IpcLand { outer, inner, width }
+}
+
+/// The ranges and board tolerances a land assumed, for footprint descriptions: "lead span
+/// 5.8-6.2 mm, ..., 0.1 mm fabrication and 0.05 mm placement tolerance". `None` when
+/// every dimension is exact.
+pub(crate) fn describe_tolerances(dimensions: &[(&str, Toleranced)], tolerances: ManufacturingTolerances) -> Option<String> {
+    if dimensions.iter().all(|(_, value)| value.is_exact()) {
+        return None;
+    }
+    let mm = |v: f32| (v * 1000.0).round() / 1000.0;
+    let ranges: Vec<String> =
+        dimensions.iter().map(|(name, value)| format!("{} {}-{} mm", name, mm(value.min()), mm(value.max()))).collect();
+    Some(format!(
+        "{}, {} mm fabrication and {} mm placement tolerance",
+        ranges.join(", "),
+        tolerances.fabrication,
+        tolerances.placement
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILLETS: ChipFillets = ChipFillets { toe: 0.35, heel: 0.35, side: 0.03 };
+
+    #[test]
+    fn plain_numbers_are_exact() {
+        let value: Toleranced = 1.27.into();
+        assert!(value.is_exact());
+        assert_eq!((value.min(), value.max()), (1.27, 1.27));
+        let range = Toleranced::min_max(6.2, 5.8);
+        assert!((range.nominal - 6.0).abs() < 1e-6 && (range.min() - 5.8).abs() < 1e-6 && (range.max() - 6.2).abs() < 1e-6);
+        assert_eq!(Toleranced::new(1.0, 0.1, -0.2).to_string(), "1 +0.1/-0.2");
+    }
+
+    #[test]
+    fn exact_leads_give_the_nominal_construction() {
+        let land = ipc_land(6.0.into(), 0.835.into(), 0.41.into(), FILLETS, ManufacturingTolerances::default());
+        assert_eq!(land.outer, 6.0 + 2.0 * 0.35);
+        assert_eq!(land.inner, 6.0 - 2.0 * 0.835 - 2.0 * 0.35);
+        assert_eq!(land.width, 0.41 + 2.0 * 0.03);
+    }
+
+    #[test]
+    fn tolerances_widen_the_land() {
+        let span = Toleranced::min_max(5.8, 6.2);
+        let (length, width) = (Toleranced::min_max(0.4, 1.27), Toleranced::min_max(0.31, 0.51));
+        let land = ipc_land(span, length, width, FILLETS, ManufacturingTolerances::default());
+        assert!((land.outer - 6.915).abs() < 1e-3, "{:?}", land);
+        assert!((land.inner - 2.978).abs() < 1e-3, "{:?}", land);
+        assert!((land.width - 0.599).abs() < 1e-3, "{:?}", land);
+        // Tighter parts need less margin
+        let tight = ipc_land(Toleranced::min_max(5.9, 6.1), length, width, FILLETS, ManufacturingTolerances::default());
+        assert!(tight.outer < land.outer);
+    }
+}
@@ -0,0 +1,82 @@
+//! Copper zones
+//!
+//! A zone is a copper pour on one net: an outline on one or more copper layers that KiCad
+//! fills around the other copper, keeping `clearance` to it. The board only keeps the
+//! outline and the fill settings; filling is left to KiCad (press B after opening).
+//!
+//! Flex boards pour hatched ground to stay bendable. KiCad hatches zones as a grid of two
+//! line sets at right angles, so `FillStyle::Hatch` and `FillStyle::CrossHatch` are both
+//! written as that grid, with the gap between lines being the pitch less the line width.
+
+use serde::{Deserialize, Serialize};
+
+use crate::board::Board;
+use crate::geometry::Point;
+use crate::hatch::FillStyle;
+use crate::layer_type::BoardLayer;
+
+/// KiCad's default zone clearance in millimeters
+pub const DEFAULT_ZONE_CLEARANCE: f32 = 0.5;
+
+/// KiCad's default minimum fill width in millimeters
+pub const DEFAULT_ZONE_MIN_THICKNESS: f32 = 0.25;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Zone {
+    pub net: String,
+    pub layers: Vec<BoardLayer>,
+    /// Closed outline in board coordinates, last point not repeated
+    pub outline: Vec<Point>,
+    pub fill: FillStyle,
+    pub clearance: f32,
+    /// Narrowest copper the fill may leave
+    pub min_thickness: f32,
+}
+
+impl Zone {
+    /// A solid zone with KiCad's default clearance and minimum width
+    pub fn new(net: &str, layers: &[BoardLayer], outline: Vec<Point>) -> Self {
+        Self {
+            net: net.to_string(),
+            layers: layers.to_vec(),
+            outline,
+            fill: FillStyle::Solid,
+            clearance: DEFAULT_ZONE_CLEARANCE,
+            min_thickness: DEFAULT_ZONE_MIN_THICKNESS,
+        }
+    }
+
+    pub fn with_fill(mut self, fill: FillStyle) -> Self {
+        self.fill = fill;
+        self
+    }
+
+    pub fn with_clearance(mut self, clearance: f32) -> Self {
+        self.clearance = clearance;
+        self
+    }
+
+    pub fn with_min_thickness(mut self, min_thickness: f32) -> Self {
+        self.min_thickness = min_thickness;
+        self
+    }
+
+    /// Center lines of the hatch as KiCad draws it, a grid for either hatch style; empty
+    /// for solid zones
+    pub fn hatch_lines(&self) -> Vec<(Point, Point)> {
+        match self.fill.hatch() {
+            Some((pitch, width, angle)) => FillStyle::CrossHatch { pitch, width, angle }.hatch_lines(&self.outline),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl Board {
+    pub fn add_zone(&mut self, zone: Zone) {
+        self.zones.push(zone);
+    }
+
+    pub fn zones(&self) -> &[Zone] {
+        &self.zones
+    }
+}
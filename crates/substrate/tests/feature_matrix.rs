@@ -0,0 +1,97 @@
+//! Builds of copper-substrate under each combination of its features
+//!
+//! The default build has `render-traits` (egui and the preview) and `uuid-gen` (random
+//! uuids). Every other combination is checked with `cargo check`, and a scratch crate
+//! that depends on copper-substrate with default features off is built and run against
+//! the core types, as a trybuild pass case would be. Builds go to their own target
+//! directory under the workspace's, so they don't wait on the lock of the build running
+//! this test, and are offline: they need no crate the workspace doesn't already have.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Feature lists passed with `--no-default-features`; the default set is this test's own
+/// build
+const COMBINATIONS: &[&str] = &["", "uuid-gen", "render-traits"];
+
+/// The core types with default features off; uuids come out counted but distinct
+const CORE_USE: &str = r#"
+use copper_substrate::analysis::footprint_drc::FootprintDrc;
+use copper_substrate::prelude::*;
+use uuid::Uuid;
+
+fn main() {
+    let dip = Dip::new(8).unwrap();
+    let pads = dip.pad_descriptors();
+    assert_eq!(pads.len(), 8);
+    assert_ne!(pads[0].uuid, pads[1].uuid);
+    let chip = ChipFootprint::standard(FunctionalType::Resistor("10k".to_string()), "0603").unwrap();
+    assert_eq!(chip.footprint_name(), "R_0603_1608Metric");
+    assert!(FootprintDrc::default().check(&chip).is_empty());
+    let uuid = Uuid::parse_str("6f1c2d3e-0000-4000-8000-000000000001").unwrap();
+    let text = FpText::new(FpTextType::User, "x", (0.0, 0.0), "F.Fab").with_uuid(uuid);
+    assert_eq!(text.uuid, uuid);
+    let qfn = Qfn::from_jedec("MO-220-4x4-24", 24).unwrap();
+    assert_eq!(qfn.pad_descriptors().iter().filter(|pad| pad.has_copper()).count(), 25);
+}
+"#;
+
+fn cargo() -> Command {
+    let mut command = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()));
+    command.env("CARGO_TARGET_DIR", workspace().join("target/feature-matrix"));
+    command
+}
+
+fn workspace() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../..").canonicalize().unwrap()
+}
+
+fn run(mut command: Command, what: &str) {
+    let output = command.output().unwrap();
+    assert!(output.status.success(), "{} failed:\n{}", what, String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn every_feature_combination_builds() {
+    for features in COMBINATIONS {
+        let mut command = cargo();
+        command.current_dir(workspace()).args(["check", "--offline", "-p", "copper-substrate", "--no-default-features"]);
+        if !features.is_empty() {
+            command.args(["--features", features]);
+        }
+        run(command, &format!("cargo check with features [{}]", features));
+    }
+}
+
+#[test]
+fn core_types_work_without_default_features() {
+    let scratch = std::env::temp_dir().join(format!("copper-feature-matrix-{}", std::process::id()));
+    fs::create_dir_all(scratch.join("src")).unwrap();
+    let manifest = format!(
+        "[package]\nname = \"substrate-core\"\nversion = \"0.1.0\"\nedition = \"2024\"\n\n[dependencies]\n\
+         copper-substrate = {{ path = {:?}, default-features = false }}\nuuid = \"1.11\"\n\n[workspace]\n",
+        Path::new(env!("CARGO_MANIFEST_DIR")).canonicalize().unwrap()
+    );
+    fs::write(scratch.join("Cargo.toml"), manifest).unwrap();
+    fs::write(scratch.join("src/main.rs"), CORE_USE).unwrap();
+    // The workspace's lock file, so the scratch crate resolves to crates already fetched
+    let lock = workspace().join("Cargo.lock");
+    if lock.exists() {
+        fs::copy(lock, scratch.join("Cargo.lock")).unwrap();
+    }
+
+    let mut command = cargo();
+    command.current_dir(&scratch).args(["run", "--offline", "--quiet"]);
+    run(command, "the scratch crate");
+
+    // Nothing built without default features may pull in egui or uuid's generator
+    let mut tree = cargo();
+    tree.current_dir(&scratch).args(["tree", "--offline", "--edges", "normal,features", "--prefix", "none"]);
+    let output = tree.output().unwrap();
+    let tree = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(!tree.contains("egui"), "{}", tree);
+    assert!(!tree.contains("getrandom") && !tree.contains("\"v4\""), "{}", tree);
+    fs::remove_dir_all(&scratch).unwrap();
+}
@@ -0,0 +1,153 @@
+//! Antenna module with a copper keepout
+//!
+//! `cargo run -p copper-exporters --example antenna_keepout` builds a small 2.4 GHz module
+//! whose printed antenna needs bare board underneath: the footprint carries a rule area
+//! over the antenna end that keeps tracks, vias and copper pour off every copper layer.
+//! The library footprint must contain the keepout zone and read back with it, and on a
+//! board with the module turned 90 degrees a track and a via under the antenna must be
+//! reported while a track beside it must not.
+
+use copper_exporters::kicad_footprint_parse::{KiCadFootprint, ParseOptions};
+use copper_exporters::kicad_pcb_export::to_kicad_footprint;
+use copper_substrate::board::Board;
+use copper_substrate::prelude::*;
+use copper_substrate::routing::RoutingViolation;
+use uuid::Uuid;
+
+/// Module body, with the antenna in the part above `ANTENNA_EDGE`
+const WIDTH: f32 = 12.0;
+const LENGTH: f32 = 16.0;
+const ANTENNA_EDGE: f32 = -3.0;
+
+struct AntennaModule;
+
+impl BoardComposableObject for AntennaModule {
+    fn is_smt(&self) -> bool {
+        true
+    }
+
+    fn is_electrical(&self) -> bool {
+        true
+    }
+
+    fn terminal_count(&self) -> usize {
+        6
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        FunctionalType::IntegratedCircuit("RF_Module".to_string())
+    }
+
+    fn footprint_name(&self) -> String {
+        "RF_Module_12x16mm_PCBAntenna".to_string()
+    }
+
+    fn library_name(&self) -> String {
+        "RF_Module".to_string()
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        Rectangle { min_x: -WIDTH / 2.0, min_y: -LENGTH / 2.0, max_x: WIDTH / 2.0, max_y: LENGTH / 2.0 }
+    }
+
+    /// Castellated pads along the bottom end, away from the antenna
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        (0..6)
+            .map(|pin| PadDescriptor {
+                number: (pin + 1).to_string().into(),
+                pin_function: None,
+                pad_type: PadType::SMD,
+                shape: PadShape::Rect,
+                position: (-3.75 + pin as f32 * 1.5, LENGTH / 2.0 - 0.5),
+                rotation: None,
+                size: (0.9, 1.5),
+                drill_size: None,
+                drill_slot: None,
+                layers: vec!["F.Cu".into(), "F.Paste".into(), "F.Mask".into()],
+                roundrect_ratio: None,
+                tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+                padstack: None,
+                fab_property: None,
+                solder_mask_margin: None,
+                routing_hint: None,
+                uuid: Uuid::new_v4(),
+            })
+            .collect()
+    }
+
+    fn description(&self) -> Option<String> {
+        Some("2.4 GHz module with printed antenna, keep copper away from the antenna end".to_string())
+    }
+
+    fn tags(&self) -> Option<String> {
+        Some("RF module antenna keepout".to_string())
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        vec![FpText {
+            text_type: FpTextType::Reference,
+            text: "REF**".to_string(),
+            position: (0.0, -LENGTH / 2.0 - 1.0),
+            rotation: None,
+            layer: "F.SilkS".into(),
+            uuid: Uuid::new_v4(),
+            font: FontSettings { size: (1.0, 1.0), thickness: 0.15 },
+            hidden: false,
+            justify: None,
+            role: None,
+        }]
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        Vec::new()
+    }
+
+    fn model_3d(&self) -> Option<Model3D> {
+        None
+    }
+
+    fn rule_areas(&self) -> Vec<RuleArea> {
+        let antenna = Rectangle { min_x: -WIDTH / 2.0, min_y: -LENGTH / 2.0, max_x: WIDTH / 2.0, max_y: ANTENNA_EDGE };
+        vec![RuleArea::keepout_rect("antenna", &antenna, &["*.Cu"])]
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let footprint = to_kicad_footprint(&AntennaModule);
+    let zone = footprint.lines().skip_while(|line| !line.contains("(zone")).take(24).collect::<Vec<_>>().join("\n");
+    println!("{}", zone);
+    let keepout = "\t\t(keepout\n\t\t\t(tracks not_allowed)\n\t\t\t(vias not_allowed)\n\t\t\t(pads allowed)\n\t\t\t(copperpour not_allowed)\n\t\t\t(footprints allowed)\n\t\t)";
+    if !zone.contains("\t\t(layers \"*.Cu\")") || !zone.contains(keepout) || !zone.contains("(name \"antenna\")") {
+        return Err("the footprint must carry an all-copper keepout zone".into());
+    }
+    let parsed = KiCadFootprint::parse(&footprint, ParseOptions::strict())?;
+    if parsed.rule_areas() != AntennaModule.rule_areas() {
+        return Err("the keepout must read back unchanged".into());
+    }
+
+    // Turned 90 degrees the antenna end points to -x, covering x 12..17 around (20, 20)
+    let mut board = Board::new("antenna").with_rectangular_outline(40.0, 40.0);
+    board.place("U1", AntennaModule, (20.0, 20.0), 90.0)?;
+    board.add_track(Track::new("SIG", BoardLayer::back(LayerType::Copper), Point::new(14.0, 10.0), Point::new(14.0, 30.0), 0.2));
+    board.add_track(Track::new("SIG", BoardLayer::front(LayerType::Copper), Point::new(10.0, 10.0), Point::new(10.0, 30.0), 0.2));
+    board.add_via(Via::new("GND", Point::new(15.0, 22.0), 0.6, 0.3));
+    let keepouts: Vec<RoutingViolation> = board
+        .routing_violations()
+        .into_iter()
+        .filter(|violation| matches!(violation, RoutingViolation::Keepout { .. }))
+        .collect();
+    for violation in &keepouts {
+        println!("{}", violation);
+    }
+    let items: Vec<String> = keepouts
+        .iter()
+        .filter_map(|violation| match violation {
+            RoutingViolation::Keepout { item, .. } => Some(item.to_string()),
+            _ => None,
+        })
+        .collect();
+    if items != ["track 0", "via 0"] {
+        return Err(format!("expected the back track and the via under the antenna, got {:?}", items).into());
+    }
+    Ok(())
+}
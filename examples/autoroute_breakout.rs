@@ -0,0 +1,55 @@
+//! Autorouting a 50 net breakout
+//!
+//! `cargo run -p copper-exporters --example autoroute_breakout` places three columns of
+//! 25 0603 resistors on a 50 x 60 mm two layer board and connects each column to the next
+//! with rows swapped end for end in groups of five, so the nets of a group all cross. `Board::autoroute` routes them on a 0.2 mm grid, the report
+//! and the time it took are printed, and the board must route completely and pass DRC
+//! before it is saved to the temp directory.
+
+use std::time::Instant;
+
+use copper_exporters::kicad_board_export::to_kicad_pcb;
+use copper_substrate::analysis::Severity;
+use copper_substrate::board::Board;
+use copper_substrate::presets;
+use copper_substrate::prelude::*;
+
+const ROWS: usize = 25;
+/// Rows are swapped end for end within groups of this many
+const GROUP: usize = 5;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut board = Board::new("breakout").with_rectangular_outline(50.0, 60.0);
+    let mut netlist = Netlist::new();
+    for row in 0..ROWS {
+        let y = 6.0 + 2.0 * row as f32;
+        for (column, x) in [5.0, 22.0, 42.0].into_iter().enumerate() {
+            board.place(&format!("R{}{:02}", column + 1, row + 1), presets::resistor_0603("10k"), (x, y), 0.0)?;
+        }
+        let facing = row / GROUP * GROUP + GROUP - row % GROUP;
+        netlist = netlist
+            .with_net(&format!("A{}", row + 1), &[&format!("R1{:02}.2", row + 1), &format!("R2{:02}.1", facing)])
+            .with_net(&format!("B{}", row + 1), &[&format!("R2{:02}.2", row + 1), &format!("R3{:02}.1", facing)]);
+    }
+    board.apply_netlist(&netlist)?;
+
+    let started = Instant::now();
+    let report = board.autoroute(&AutorouteOptions::default().with_grid(0.2));
+    println!("{} in {:.2} s", report, started.elapsed().as_secs_f32());
+    if !report.is_complete() {
+        return Err(format!("autorouting left {} unrouted", report.failed.join(", ")).into());
+    }
+
+    let errors: Vec<_> = board.drc().into_iter().filter(|finding| finding.severity == Severity::Error).collect();
+    for finding in &errors {
+        println!("{}", finding);
+    }
+    if !errors.is_empty() {
+        return Err(format!("DRC found {} errors", errors.len()).into());
+    }
+
+    let path = std::env::temp_dir().join("autoroute_breakout.kicad_pcb");
+    std::fs::write(&path, to_kicad_pcb(&board))?;
+    println!("saved {}", path.display());
+    Ok(())
+}
@@ -0,0 +1,181 @@
+//! Dog-bone fanout of a BGA
+//!
+//! `cargo run -p copper-substrate --example bga_fanout` fans out BGA test parts. A 64-ball
+//! 0.8 mm pitch BGA with 0.45/0.2 mm vias must fan out completely: the two outer rings
+//! left to the surface, every inner ball joined to a via half a pitch away diagonally and
+//! pointing away from the center, and no routing violations. The same part on the back of
+//! a board, turned 90 degrees, must fan out as cleanly onto B.Cu, and an odd array must
+//! keep its center row and column apart. At 0.5 mm pitch the vias can't fit: the balls
+//! must be reported and the board left without violations. The balls take no via in the
+//! pad, so 0.8 mm vias, which would reach into the balls beside them, must be refused.
+
+use std::rc::Rc;
+
+use copper_substrate::board::Board;
+use copper_substrate::prelude::*;
+use uuid::Uuid;
+
+/// JEDEC row letters, skipping those easily mistaken for digits
+const ROWS: &str = "ABCDEFGHJKLMNPRTUVWY";
+
+#[derive(Clone, Copy)]
+struct Bga {
+    pitch: f32,
+    count: usize,
+    ball: f32,
+}
+
+impl Bga {
+    fn extent(&self) -> f32 {
+        (self.count as f32 + 1.0) * self.pitch / 2.0
+    }
+}
+
+impl BoardComposableObject for Bga {
+    fn is_smt(&self) -> bool {
+        true
+    }
+
+    fn is_electrical(&self) -> bool {
+        true
+    }
+
+    fn terminal_count(&self) -> usize {
+        self.count * self.count
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        FunctionalType::IntegratedCircuit("BGA".to_string())
+    }
+
+    fn footprint_name(&self) -> String {
+        format!("BGA-{}_{}x{}_P{}mm", self.count * self.count, self.count, self.count, self.pitch)
+    }
+
+    fn library_name(&self) -> String {
+        "Package_BGA".to_string()
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        let extent = self.extent();
+        Rectangle { min_x: -extent, min_y: -extent, max_x: extent, max_y: extent }
+    }
+
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        let first = -(self.count as f32 - 1.0) * self.pitch / 2.0;
+        let mut pads = Vec::new();
+        for (row, letter) in ROWS.chars().take(self.count).enumerate() {
+            for column in 0..self.count {
+                pads.push(PadDescriptor {
+                    number: format!("{}{}", letter, column + 1).into(),
+                    pin_function: None,
+                    pad_type: PadType::SMD,
+                    shape: PadShape::Circle,
+                    position: (first + column as f32 * self.pitch, first + row as f32 * self.pitch),
+                    rotation: None,
+                    size: (self.ball, self.ball),
+                    drill_size: None,
+                    drill_slot: None,
+                    layers: vec!["F.Cu".into(), "F.Paste".into(), "F.Mask".into()],
+                    roundrect_ratio: None,
+                    tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+                    padstack: None,
+                    fab_property: None,
+                    solder_mask_margin: None,
+                    // Balls take no via in the pad, only dog-bones beside them
+                    routing_hint: Some(RoutingHint::new().with_no_via_under_pad()),
+                    uuid: Uuid::new_v4(),
+                });
+            }
+        }
+        pads
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        Vec::new()
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        Vec::new()
+    }
+
+    fn model_3d(&self) -> Option<Model3D> {
+        None
+    }
+}
+
+/// A board for BGA escapes: 0.1 mm tracks and clearance, 0.45/0.2 mm vias
+fn test_board(name: &str) -> Board {
+    let mut board = Board::new(name).with_rectangular_outline(30.0, 30.0);
+    board.set_default_net_class(NetClass::new("BGA", 0.1, 0.1, 0.45, 0.2));
+    board
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let bga = Bga { pitch: 0.8, count: 8, ball: 0.4 };
+    let mut board = test_board("fanout");
+    board.place("U1", bga, (15.0, 15.0), 0.0)?;
+    let report = board.fanout_bga("U1", &FanoutOptions::default())?;
+    println!(
+        "0.8 mm: {} fanned, {} on the surface ({} rings), {} failed",
+        report.fanned.len(),
+        report.surface.len(),
+        report.surface_rings,
+        report.failed.len()
+    );
+    assert!(report.is_complete(), "{:#?}", report.failed);
+    assert_eq!((report.surface_rings, report.surface.len(), report.fanned.len()), (2, 48, 16));
+    assert_eq!((board.tracks().len(), board.vias().len()), (16, 16));
+    let violations = board.routing_violations();
+    assert!(violations.is_empty(), "{:#?}", violations);
+    let center = Point::new(15.0, 15.0);
+    for ball in &report.fanned {
+        let (track, via) = (&board.tracks()[ball.track], &board.vias()[ball.via]);
+        assert_eq!(track.end, via.position);
+        assert_eq!(track.layer, BoardLayer::front(LayerType::Copper));
+        assert!((track.start.distance_to(&via.position) - 0.8 * std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-4);
+        assert!(via.position.distance_to(&center) > track.start.distance_to(&center), "{} points inwards", ball.number);
+        assert_eq!(board.pad_net("U1", &ball.number), Some(via.net.as_str()));
+        assert_eq!(ball.escape_layer, BoardLayer::back(LayerType::Copper));
+    }
+
+    // Mounted on the back and turned, the tracks go on B.Cu
+    let mut back = test_board("fanout-back");
+    back.place_shared("U2", Rc::new(bga), (15.0, 15.0), 90.0, Side::Back)?;
+    back.connect_pad("U2", "D4", "GND");
+    let report = back.fanout_bga("U2", &FanoutOptions::default())?;
+    assert!(report.is_complete() && report.fanned.len() == 16, "{:#?}", report.failed);
+    assert!(back.tracks().iter().all(|track| track.layer == BoardLayer::back(LayerType::Copper)));
+    assert!(back.vias().iter().any(|via| via.net == "GND"));
+    assert!(back.routing_violations().is_empty(), "{:#?}", back.routing_violations());
+
+    // An odd array: the center row and column alternate sides ring by ring
+    let mut odd = test_board("fanout-odd");
+    odd.place("U3", Bga { pitch: 0.8, count: 9, ball: 0.4 }, (15.0, 15.0), 0.0)?;
+    let report = odd.fanout_bga("U3", &FanoutOptions::default().with_surface_rings(1))?;
+    println!("9x9: {} fanned, {} failed", report.fanned.len(), report.failed.len());
+    assert!(report.is_complete() && report.fanned.len() == 49, "{:#?}", report.failed);
+    assert!(odd.routing_violations().is_empty(), "{:#?}", odd.routing_violations());
+
+    // 0.45 mm vias don't fit between 0.25 mm balls at 0.5 mm pitch
+    let mut fine = test_board("fanout-fine");
+    fine.place("U4", Bga { pitch: 0.5, count: 8, ball: 0.25 }, (15.0, 15.0), 0.0)?;
+    let report = fine.fanout_bga("U4", &FanoutOptions::default())?;
+    println!("0.5 mm: {} fanned, {} failed", report.fanned.len(), report.failed.len());
+    assert!(!report.is_complete());
+    assert_eq!(report.fanned.len() + report.failed.len() + report.surface.len(), 64);
+    println!("  {}: {}", report.failed[0].number, report.failed[0].violation);
+    assert!(fine.routing_violations().is_empty(), "{:#?}", fine.routing_violations());
+    assert_eq!(fine.vias().len(), report.fanned.len());
+
+    // Vias as wide as the balls would reach into the flagged balls diagonal to them
+    let mut wide = test_board("fanout-wide");
+    wide.place("U5", bga, (15.0, 15.0), 0.0)?;
+    let report = wide.fanout_bga("U5", &FanoutOptions::default().with_via(0.8, 0.4))?;
+    println!("0.8 mm vias: {} refused", report.refused.len());
+    assert_eq!((report.refused.len(), report.fanned.len()), (16, 0));
+    assert!(wide.vias().is_empty());
+
+    assert!(matches!(board.fanout_bga("U9", &FanoutOptions::default()), Err(FanoutError::UnknownComponent(_))));
+    Ok(())
+}
@@ -0,0 +1,108 @@
+//! Copper artwork of a KiCad board
+//!
+//! `cargo run -p copper-exporters --example board_artwork [board.kicad_pcb]` reads the
+//! copper of a board, examples/fixtures/small_board.kicad_pcb by default, draws F.Cu and
+//! B.Cu as the 3D viewer's artwork textures do and prints them coarsely as text. On the
+//! sample board every track, the arc, the rotated resistor's pads, the vias and the back
+//! pour must show as copper where the file puts them, with bare board between. The
+//! fingerprint must stay the same until the copper changes.
+
+use std::path::PathBuf;
+
+use copper_exporters::kicad_board_import::read_board_file;
+use copper_substrate::prelude::*;
+
+/// Resolution of the checked artwork, that of the viewer's default texture
+const PIXELS_PER_MM: f32 = 20.0;
+
+/// One character per `block` by `block` pixels, '#' when any of them is copper
+fn print_artwork(artwork: &CopperArtwork, block: usize) {
+    println!("{} {} x {} px, {:.1}% copper", artwork.layer.to_kicad_string(), artwork.width, artwork.height, artwork.coverage() * 100.0);
+    for y in (0..artwork.height).step_by(block) {
+        let row: String = (0..artwork.width)
+            .step_by(block)
+            .map(|x| {
+                let copper = (y..y + block).any(|y| (x..x + block).any(|x| artwork.is_copper(x, y)));
+                if copper { '#' } else { '.' }
+            })
+            .collect();
+        println!("  {}", row);
+    }
+}
+
+fn copper_at(artwork: &CopperArtwork, x: f32, y: f32) -> bool {
+    artwork.pixel_at(Point::new(x, y)).is_some_and(|(column, row)| artwork.is_copper(column, row))
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let sample = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../examples/fixtures/small_board.kicad_pcb");
+    let path = std::env::args().nth(1).map(PathBuf::from).unwrap_or(sample.clone());
+    let mut board = read_board_file(&path)?;
+    println!(
+        "{}: {} footprints, {} tracks, {} vias, {} zones",
+        board.name,
+        board.components().len(),
+        board.tracks().len(),
+        board.vias().len(),
+        board.zones().len()
+    );
+
+    let (front, back) = (BoardLayer::front(LayerType::Copper), BoardLayer::back(LayerType::Copper));
+    // Half millimeter characters, fine enough for the tracks to show
+    for layer in [front, back] {
+        print_artwork(&board.copper_artwork(layer, 8.0), 4);
+    }
+    if path != sample {
+        return Ok(());
+    }
+
+    let top = board.copper_artwork(front, PIXELS_PER_MM);
+    let bottom = board.copper_artwork(back, PIXELS_PER_MM);
+    if (top.width, top.height) != (600, 400) {
+        return Err(format!("30 x 20 mm at {} px/mm must be 600 x 400 px, got {} x {}", PIXELS_PER_MM, top.width, top.height).into());
+    }
+    let copper = [
+        (front, 12.0, 10.0, "R1 to the arc"),
+        (front, 17.12, 10.88, "middle of the arc"),
+        (front, 20.0, 13.0, "arc to R2"),
+        (front, 3.0, 7.5, "IN down to its via"),
+        (front, 24.5, 7.0, "OUT stub"),
+        (front, 7.0875, 10.0, "R1 pad 1"),
+        (front, 22.65, 10.9125, "R2 pad 1, long side along X once rotated"),
+        (front, 27.0, 5.0, "via on F.Cu"),
+        (back, 15.0, 5.0, "IN on B.Cu"),
+        (back, 15.0, 16.0, "back pour"),
+    ];
+    let bare = [
+        (front, 12.0, 12.0, "between the tracks"),
+        (front, 15.0, 13.0, "center of the arc"),
+        (front, 22.45, 11.5, "beside R2 pad 1"),
+        (front, 15.0, 16.0, "under the back pour"),
+        (back, 15.0, 12.0, "between the B.Cu track and the pour"),
+        (back, 0.5, 16.0, "outside the pour"),
+    ];
+    for (layer, x, y, what) in copper {
+        let artwork = if layer == front { &top } else { &bottom };
+        if !copper_at(artwork, x, y) {
+            return Err(format!("{} at ({}, {}) must be copper on {}", what, x, y, layer.to_kicad_string()).into());
+        }
+    }
+    for (layer, x, y, what) in bare {
+        let artwork = if layer == front { &top } else { &bottom };
+        if copper_at(artwork, x, y) {
+            return Err(format!("{} at ({}, {}) must be bare on {}", what, x, y, layer.to_kicad_string()).into());
+        }
+    }
+
+    // Cached artwork stays valid until the copper changes
+    let fingerprint = board.copper_fingerprint(front);
+    if board.copper_fingerprint(front) != fingerprint || top.fingerprint != fingerprint {
+        return Err("an unchanged board must keep its fingerprint".into());
+    }
+    board.add_track(Track::new("OUT", front, Point::new(27.0, 7.0), Point::new(27.0, 12.0), 0.4));
+    if board.copper_fingerprint(front) == fingerprint || board.copper_fingerprint(back) != bottom.fingerprint {
+        return Err("a new F.Cu track must change the F.Cu fingerprint only".into());
+    }
+    println!("checked {} copper and {} bare points", copper.len(), bare.len());
+    Ok(())
+}
@@ -0,0 +1,76 @@
+//! A board in under 100 lines, from footprints to fabrication files
+//!
+//! `cargo run -p copper-exporters --example board_in_100_lines` generates an SOIC-8, a
+//! 0603 resistor and a 0402 capacitor from their parameters, places them on a 40 x 30 mm
+//! board and connects them with a netlist: a pull-up from pin 8 into an RC filter. VCC and
+//! the filter node are autorouted, GND is poured on both sides and stitched with a
+//! via. The board must pass DRC before its board file, Gerbers, drill files, BOM and
+//! placement list are written to the temp directory. The board file is then read back and
+//! checked against the design. `tests/board_end_to_end.rs` builds the same board.
+
+use copper_exporters::fab_outputs::write_fab_outputs;
+use copper_exporters::kicad_board_import::read_board_file;
+use copper_exporters::ExportOptions;
+use copper_substrate::analysis::Severity;
+use copper_substrate::board::{Board, FabOrigin};
+use copper_substrate::prelude::*;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Footprints from their parameters
+    let soic = Soic::from_jedec("MS-012-AA", 8)?;
+    let resistor = ChipFootprint::standard(FunctionalType::Resistor("10k".into()), "0603").ok_or("no 0603 size")?;
+    let capacitor = ChipFootprint::standard(FunctionalType::Capacitor("100n".into()), "0402").ok_or("no 0402 size")?;
+
+    // Placement and nets
+    let mut board = Board::new("rc_filter").with_rectangular_outline(40.0, 30.0);
+    board.place("U1", soic, (15.0, 15.0), 0.0)?;
+    board.place("R1", resistor, (25.0, 10.0), 0.0)?;
+    board.place("C1", capacitor, (32.0, 10.0), 0.0)?;
+    let netlist = Netlist::new().with_net("VCC", &["U1.8", "R1.1"]).with_net("FILT", &["R1.2", "C1.1"]).with_net("GND", &["U1.4", "C1.2"]);
+    board.apply_netlist(&netlist)?;
+
+    // Two routed nets, ground poured on both sides
+    let report = board.autoroute(&AutorouteOptions::default().with_nets(&["VCC", "FILT"]));
+    println!("{}", report);
+    if !report.is_complete() {
+        return Err(format!("autorouting left {} unrouted", report.failed.join(", ")).into());
+    }
+    let top = BoardLayer::front(LayerType::Copper);
+    let outline = board.outline().ok_or("the board has no outline")?.to_vec();
+    for layer in [top, BoardLayer::back(LayerType::Copper)] {
+        board.add_zone(Zone::new("GND", &[layer], outline.clone()));
+    }
+    board.add_via(Via::new("GND", Point::new(5.0, 25.0), 0.6, 0.3));
+
+    // DRC before anything is written
+    let findings = board.drc();
+    for finding in &findings {
+        println!("{}", finding);
+    }
+    let errors = findings.iter().filter(|finding| finding.severity == Severity::Error).count();
+    if errors > 0 {
+        return Err(format!("DRC found {} errors", errors).into());
+    }
+
+    // The fab package
+    let dir = std::env::temp_dir().join("copper_board_in_100_lines");
+    for path in write_fab_outputs(&board, &dir, FabOrigin::Absolute, &ExportOptions::default())? {
+        println!("saved {}", path.display());
+    }
+
+    // The board file reads back as designed
+    let read = read_board_file(&dir.join("rc_filter.kicad_pcb"))?;
+    for (original, placed) in board.components().iter().zip(read.components()) {
+        if placed.reference != original.reference || placed.position != original.position || placed.rotation != original.rotation {
+            return Err(format!("{} came back as {} at {:?}", original.reference, placed.reference, placed.position).into());
+        }
+    }
+    if read.components().len() != board.components().len() || read.nets() != board.nets() {
+        return Err("the board file lost components or nets".into());
+    }
+    if !read.unconnected_pads().is_empty() {
+        return Err(format!("unconnected after reading back: {:?}", read.unconnected_pads()).into());
+    }
+    println!("{} components on nets {:?} read back unchanged", read.components().len(), read.nets());
+    Ok(())
+}
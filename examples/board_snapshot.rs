@@ -0,0 +1,116 @@
+//! Board snapshot benchmark
+//!
+//! Builds a board of 5000 two-pad chips (10k pads, every pad on a net) with 5000 tracks
+//! and 1000 vias, then times snapshot and restore, which together must stay under a
+//! millisecond. Run with `cargo run --release -p copper-substrate --example
+//! board_snapshot`. What snapshots keep, restore and diff is checked by the unit tests of
+//! `snapshot`.
+
+use std::time::{Duration, Instant};
+
+use copper_substrate::board::Board;
+use copper_substrate::prelude::*;
+use uuid::Uuid;
+
+const COMPONENTS: usize = 5000;
+const ROUNDS: u32 = 1000;
+
+struct Chip0603;
+
+impl BoardComposableObject for Chip0603 {
+    fn is_smt(&self) -> bool {
+        true
+    }
+    fn is_electrical(&self) -> bool {
+        true
+    }
+    fn terminal_count(&self) -> usize {
+        2
+    }
+    fn functional_type(&self) -> FunctionalType {
+        FunctionalType::Resistor("10k".to_string())
+    }
+    fn footprint_name(&self) -> String {
+        "R_0603_1608Metric".to_string()
+    }
+    fn library_name(&self) -> String {
+        "Resistor_SMD".to_string()
+    }
+    fn bounding_box(&self) -> Rectangle {
+        Rectangle { min_x: -0.8, min_y: -0.4, max_x: 0.8, max_y: 0.4 }
+    }
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        ["1", "2"]
+            .iter()
+            .zip([-0.825, 0.825])
+            .map(|(number, x)| PadDescriptor {
+                number: (*number).into(),
+                pin_function: None,
+                pad_type: PadType::SMD,
+                shape: PadShape::RoundRect,
+                position: (x, 0.0),
+                rotation: None,
+                size: (0.8, 0.95),
+                drill_size: None,
+                drill_slot: None,
+                layers: PadLayers::smd_front(),
+                roundrect_ratio: Some(0.25),
+                tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+                padstack: None,
+                fab_property: None,
+                solder_mask_margin: None,
+                routing_hint: None,
+                uuid: Uuid::new_v4(),
+            })
+            .collect()
+    }
+    fn description(&self) -> Option<String> {
+        None
+    }
+    fn tags(&self) -> Option<String> {
+        None
+    }
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        Vec::new()
+    }
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        Vec::new()
+    }
+    fn model_3d(&self) -> Option<Model3D> {
+        None
+    }
+}
+
+fn main() {
+    let columns = (COMPONENTS as f32).sqrt().ceil() as usize;
+    let size = columns as f32 * 3.0;
+    let mut board = Board::new("snapshot").with_rectangular_outline(size, size);
+    for i in 0..COMPONENTS {
+        let (x, y) = ((i % columns) as f32 * 3.0 + 1.5, (i / columns) as f32 * 3.0 + 1.5);
+        let reference = format!("R{}", i + 1);
+        board.place(&reference, Chip0603, (x, y), 0.0).unwrap();
+        board.connect_pad(&reference, "1", &format!("N{}", i));
+        board.connect_pad(&reference, "2", &format!("N{}", i + 1));
+        board.add_track(Track::new(&format!("N{}", i + 1), BoardLayer::front(LayerType::Copper), Point::new(x + 0.825, y), Point::new(x + 2.175, y), 0.2));
+        if i % 5 == 0 {
+            board.add_via(Via::new(&format!("N{}", i), Point::new(x, y + 1.0), 0.6, 0.3));
+        }
+    }
+    board.nearest_pad(Point::new(0.0, 0.0));
+
+    // Snapshot and restore, with an edit in between so the restore has work to do
+    let (mut snapshotting, mut restoring) = (Duration::ZERO, Duration::ZERO);
+    for round in 0..ROUNDS {
+        let start = Instant::now();
+        let snapshot = board.snapshot();
+        snapshotting += start.elapsed();
+        board.component_mut("R1").unwrap().position.0 += 1.0;
+        board.add_track(Track::new("N0", BoardLayer::back(LayerType::Copper), Point::new(0.0, 0.0), Point::new(round as f32, 1.0), 0.2));
+        let start = Instant::now();
+        board.restore(&snapshot);
+        restoring += start.elapsed();
+    }
+    let (snapshot_each, restore_each) = (snapshotting / ROUNDS, restoring / ROUNDS);
+    println!("{} pads, {} tracks: snapshot {:?}, restore {:?}", 2 * COMPONENTS, board.tracks().len(), snapshot_each, restore_each);
+    assert!(snapshot_each + restore_each < Duration::from_millis(1), "snapshot and restore must take under a millisecond");
+}
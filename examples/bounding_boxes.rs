@@ -0,0 +1,113 @@
+//! Declared bounding boxes against the drawn body
+//!
+//! `cargo run -p copper-exporters --example bounding_boxes` checks that every generator
+//! declares the bounding box its Fab layer draws, or that its pads span when it draws no
+//! body, also after rebasing and scaling; without a body only the pads must fit. Read
+//! back from its .kicad_mod, each must compute the same box. A hand written part that
+//! declares less than it draws must be reported as an undersized box.
+
+use copper_exporters::kicad_footprint_parse::{KiCadFootprint, ParseOptions};
+use copper_exporters::kicad_pcb_export::to_kicad_footprint;
+use copper_substrate::analysis::{Severity, Violation};
+use copper_substrate::prelude::*;
+
+/// A chip whose declared box stops short of its Fab outline by `shortfall`
+struct ShortBox {
+    chip: ChipFootprint,
+    shortfall: f32,
+}
+
+impl BoardComposableObject for ShortBox {
+    fn is_smt(&self) -> bool {
+        self.chip.is_smt()
+    }
+    fn is_electrical(&self) -> bool {
+        self.chip.is_electrical()
+    }
+    fn terminal_count(&self) -> usize {
+        self.chip.terminal_count()
+    }
+    fn functional_type(&self) -> FunctionalType {
+        self.chip.functional_type()
+    }
+    fn footprint_name(&self) -> String {
+        self.chip.footprint_name()
+    }
+    fn library_name(&self) -> String {
+        self.chip.library_name()
+    }
+    fn bounding_box(&self) -> Rectangle {
+        let body = self.chip.bounding_box();
+        Rectangle { max_x: body.max_x - self.shortfall, ..body }
+    }
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        self.chip.pad_descriptors()
+    }
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        self.chip.fp_text_elements()
+    }
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        self.chip.graphic_elements()
+    }
+    fn model_3d(&self) -> Option<Model3D> {
+        self.chip.model_3d()
+    }
+}
+
+fn check(component: &dyn BoardComposableObject) -> Result<(), String> {
+    match component.bounding_box_mismatch(BOUNDING_BOX_TOLERANCE) {
+        Some(mismatch) => Err(mismatch.to_string()),
+        None => Ok(()),
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let profile = FabProfile::standard();
+    let chip = ChipFootprint::standard(FunctionalType::Capacitor("100nF".to_string()), "0805").ok_or("0805 is a standard chip")?;
+    let dip = Dip::new(14).ok_or("14 pins make a DIP")?;
+    let generated: Vec<Box<dyn BoardComposableObject>> = vec![
+        Box::new(chip.clone()),
+        Box::new(Soic::from_jedec("MS-012-AA", 8)?),
+        Box::new(Crystal::standard("3225").ok_or("3225 is a standard crystal")?),
+        Box::new(Crystal::hc49u()),
+        Box::new(Dpak::standard("TO-263", 5).ok_or("TO-263-5 is a standard variant")?),
+        Box::new(dip.clone()),
+        Box::new(dip.clone().with_socket()),
+        Box::new(EdgeMountSma::new(1.6)),
+        Box::new(SolderJumper::two_way(false)),
+        Box::new(SolderJumper::three_way(JumperBridge::Open)),
+        Box::new(StencilFiducialPair::new(1.0, 10.0)),
+        Box::new(StencilTarget::new(2.0, 0.2)),
+        Box::new(dip.clone().rebase_to(Anchor::Centroid)?),
+        Box::new(chip.clone().scaled(1.5, 1.2, &profile)?),
+    ];
+    for component in &generated {
+        check(component.as_ref())?;
+        let parsed = KiCadFootprint::parse(&to_kicad_footprint(component.as_ref()), ParseOptions::strict())?;
+        let (computed, read_back) = (component.computed_bounding_box(), parsed.bounding_box());
+        let agrees = [
+            (computed.min_x, read_back.min_x),
+            (computed.min_y, read_back.min_y),
+            (computed.max_x, read_back.max_x),
+            (computed.max_y, read_back.max_y),
+        ]
+        .iter()
+        .all(|(a, b)| (a - b).abs() <= BOUNDING_BOX_TOLERANCE);
+        if !agrees {
+            return Err(format!("{} computes {:?} but reads back as {:?}", component.footprint_name(), computed, read_back).into());
+        }
+    }
+
+    let short = ShortBox { chip, shortfall: 0.3 };
+    let mismatch = short.bounding_box_mismatch(BOUNDING_BOX_TOLERANCE).ok_or("a box short of the body must be reported")?;
+    let finding = mismatch.to_finding();
+    if !mismatch.is_undersized() || finding.rule != "bounding_box_mismatch" || finding.severity != Severity::Warning {
+        return Err(format!("expected an undersized box warning, got {}", finding).into());
+    }
+    if (mismatch.deviation - 0.3).abs() > 1e-4 || short.bounding_box_mismatch(0.5).is_some() {
+        return Err(format!("the box is 0.3 mm short, got {:.3}", mismatch.deviation).into());
+    }
+    println!("{}", finding);
+    println!("checked {} generated footprints", generated.len());
+    Ok(())
+}
@@ -0,0 +1,114 @@
+//! Reusing a routed layout block twice on one board
+//!
+//! `cargo run -p copper-exporters --example buck_block` lays out the passives of a small
+//! buck converter (input and output capacitors, inductor, feedback resistor), routes them,
+//! saves the layout as a block file and reads it back. The block then goes onto one board
+//! twice: once as is, once rotated by 90 degrees, with its supply nets renamed per copy.
+//! Every reference and local net must be unique, the copies must keep the block's
+//! geometry, and the exported board must carry one group per copy.
+
+use copper_exporters::block_file::{block_from_str, block_to_string};
+use copper_exporters::kicad_board_export::to_kicad_pcb;
+use copper_substrate::board::Board;
+use copper_substrate::presets;
+use copper_substrate::prelude::*;
+
+const WIDTH: f32 = 0.5;
+
+/// Board position of pad `number` of `reference`
+fn pad(board: &Board, reference: &str, number: &str) -> Point {
+    let placed = board.component(reference).expect("placed above");
+    let pad = placed.component.pad_descriptors().into_iter().find(|pad| pad.number == number).expect("two pad chip");
+    board.board_point(placed, pad.position.into())
+}
+
+fn route(board: &mut Board, net: &str, from: (&str, &str), to: (&str, &str)) {
+    let (start, end) = (pad(board, from.0, from.1), pad(board, to.0, to.1));
+    board.connect_pad(from.0, from.1, net);
+    board.connect_pad(to.0, to.1, net);
+    board.add_track(Track::new(net, BoardLayer::front(LayerType::Copper), start, end, WIDTH));
+}
+
+/// The block on its own at the origin, to measure against
+fn block_board(block: &Block) -> Board {
+    let mut board = Board::new(&block.name);
+    for placed in block.components() {
+        board
+            .place_shared(&placed.reference, placed.component.clone(), placed.position, placed.rotation, placed.side)
+            .expect("unrestricted policy");
+    }
+    board
+}
+
+fn buck_layout() -> Result<Block, Box<dyn std::error::Error>> {
+    let mut layout = Board::new("buck");
+    layout.place("C1", presets::capacitor_1206("10u"), (-4.0, 0.0), 90.0)?;
+    layout.place("L1", presets::inductor_1210("4u7"), (0.0, 0.0), 0.0)?;
+    layout.place("C2", presets::capacitor_1206("22u"), (4.0, 0.0), 90.0)?;
+    layout.place("R1", presets::resistor_0603("100k"), (4.0, 4.0), 0.0)?;
+    route(&mut layout, "VIN", ("C1", "1"), ("L1", "1"));
+    route(&mut layout, "SW", ("L1", "2"), ("C2", "1"));
+    route(&mut layout, "SW", ("C2", "1"), ("R1", "1"));
+    layout.connect_pad("C1", "2", "GND");
+    layout.connect_pad("C2", "2", "GND");
+    layout.connect_pad("R1", "2", "VOUT");
+    layout.add_via(Via::new("GND", pad(&layout, "C1", "2").offset(0.0, 1.5), 0.6, 0.3));
+    Ok(Block::from_board("buck", &layout, Point::new(0.0, 0.0)))
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let block = block_from_str(&block_to_string(&buck_layout()?))?;
+    println!("block {}: {} components, {} tracks, {} vias", block.name, block.components().len(), block.tracks().len(), block.vias().len());
+
+    let mut board = Board::new("dual_buck").with_rectangular_outline(80.0, 40.0);
+    let copies = [
+        BlockPlacement::new("BUCK_A", Point::new(20.0, 20.0), 0.0).with_net("VIN", "VIN_A").with_net("VOUT", "3V3"),
+        BlockPlacement::new("BUCK_B", Point::new(60.0, 20.0), 90.0).with_net("VIN", "VIN_B").with_net("VOUT", "5V"),
+    ];
+    for copy in &copies {
+        let copy = copy.clone().with_net("GND", "GND");
+        let group = board.instantiate_block(&block, &copy)?;
+        println!("{}: {}", group.name, group.references.join(" "));
+    }
+
+    let mut references: Vec<&str> = board.components().iter().map(|placed| placed.reference.as_str()).collect();
+    references.sort_unstable();
+    references.dedup();
+    if references.len() != 2 * block.components().len() || board.component("L101").is_none() || board.component("L201").is_none() {
+        return Err("copies must be renumbered U1 -> U101, U201".into());
+    }
+    if board.pad_net("L101", "2") != Some("BUCK_A/SW") || board.pad_net("L201", "2") != Some("BUCK_B/SW") {
+        return Err("local nets must stay local to their copy".into());
+    }
+    if board.pad_net("C101", "1") != Some("VIN_A") || board.pad_net("C201", "1") != Some("VIN_B") || board.pad_net("C202", "2") != Some("GND") {
+        return Err("mapped nets must be renamed".into());
+    }
+
+    // Distances between pads and track lengths don't change with the copy's position or rotation
+    let local = block_board(&block);
+    for (copy, offset) in [("BUCK_A", 100), ("BUCK_B", 200)] {
+        for (a, b) in [(("C1", "1"), ("R1", "2")), (("L1", "1"), ("C2", "2"))] {
+            let expected = pad(&local, a.0, a.1).distance_to(&pad(&local, b.0, b.1));
+            let renamed = |reference: &str| format!("{}{}", &reference[..1], offset + reference[1..].parse::<u32>().unwrap());
+            let actual = pad(&board, &renamed(a.0), a.1).distance_to(&pad(&board, &renamed(b.0), b.1));
+            if (actual - expected).abs() > 1e-3 {
+                return Err(format!("{}: {}.{} to {}.{} is {} mm, {} mm in the block", copy, a.0, a.1, b.0, b.1, actual, expected).into());
+            }
+        }
+        let group = board.group(copy).expect("instantiated above");
+        for (index, track) in group.tracks.iter().zip(block.tracks()) {
+            let placed = &board.tracks()[*index];
+            if (placed.start.distance_to(&placed.end) - track.start.distance_to(&track.end)).abs() > 1e-3 {
+                return Err(format!("{}: track {} changed length", copy, index).into());
+            }
+        }
+    }
+
+    let pcb = to_kicad_pcb(&board);
+    if pcb.matches("\t(group ").count() != copies.len() {
+        return Err("the board file must have a group per copy".into());
+    }
+    std::fs::write("dual_buck.kicad_pcb", pcb)?;
+    println!("saved dual_buck.kicad_pcb");
+    Ok(())
+}
@@ -0,0 +1,68 @@
+//! Canonical element order
+//!
+//! `cargo run -p copper-exporters --example canonical_order` prints the pad order the
+//! KiCad exporter writes for a SOIC-16 given its pads back to front, with and without
+//! `ExportOptions::canonical_order`, and how `natural_cmp` compares a few pad numbers
+//! ("2" before "10", "A2" before "A10", row "Y" before "AA"). The checks live in the unit
+//! tests of `element_order`.
+
+use copper_exporters::element_order::natural_cmp;
+use copper_exporters::exporter::ExportOptions;
+use copper_exporters::kicad_pcb_export::to_kicad_footprint_with_options;
+use copper_substrate::prelude::*;
+
+/// A SOIC with its pads handed over back to front
+struct Reversed(Soic);
+
+impl BoardComposableObject for Reversed {
+    fn is_smt(&self) -> bool {
+        true
+    }
+    fn is_electrical(&self) -> bool {
+        true
+    }
+    fn terminal_count(&self) -> usize {
+        self.0.terminal_count()
+    }
+    fn functional_type(&self) -> FunctionalType {
+        self.0.functional_type()
+    }
+    fn footprint_name(&self) -> String {
+        self.0.footprint_name()
+    }
+    fn library_name(&self) -> String {
+        self.0.library_name()
+    }
+    fn bounding_box(&self) -> Rectangle {
+        self.0.bounding_box()
+    }
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        self.0.pad_descriptors().into_iter().rev().collect()
+    }
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        self.0.fp_text_elements()
+    }
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        self.0.graphic_elements()
+    }
+    fn model_3d(&self) -> Option<Model3D> {
+        None
+    }
+}
+
+fn pad_numbers(text: &str) -> Vec<&str> {
+    text.lines().filter_map(|line| line.trim().strip_prefix("(pad \"")?.split('"').next()).collect()
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let footprint = Reversed(Soic::from_jedec("MS-012-AC", 16)?);
+    let canonical = to_kicad_footprint_with_options(&footprint, &ExportOptions::default());
+    println!("canonical order: {}", pad_numbers(&canonical).join(" "));
+    let literal = ExportOptions { canonical_order: false, ..ExportOptions::default() };
+    let as_given = to_kicad_footprint_with_options(&footprint, &literal);
+    println!("as given:        {}", pad_numbers(&as_given).join(" "));
+    for (a, b) in [("2", "10"), ("A2", "A10"), ("Y3", "AA1"), ("16", "A1"), ("A1", "EP"), ("01", "1")] {
+        println!("{} vs {}: {:?}", a, b, natural_cmp(a, b));
+    }
+    Ok(())
+}
@@ -1,3 +1,4 @@
+use copper_substrate::analysis::Violation;
 use copper_substrate::prelude::*;
 use uuid::Uuid;
 
@@ -44,34 +45,48 @@ impl BoardComposableObject for SMTCapacitor0805 {
     fn pad_descriptors(&self) -> Vec<PadDescriptor> {
         vec![
             PadDescriptor {
-                number: "1".to_string(),
+                number: "1".into(),
+                pin_function: None,
                 pad_type: PadType::SMD,
                 shape: PadShape::RoundRect,
                 position: (-0.48, 0.0),
+                rotation: None,
                 size: (0.56, 0.62),
                 drill_size: None,
-                layers: vec!["F.Cu".to_string(), "F.Paste".to_string(), "F.Mask".to_string()],
+                drill_slot: None,
+                layers: PadLayers::smd_front(),
                 roundrect_ratio: Some(0.25),
                 tenting: TentingSettings {
                     front: TentingType::None,
                     back: TentingType::None,
                 },
-                uuid: Uuid::new_v4().to_string(),
+                padstack: None,
+                fab_property: None,
+                solder_mask_margin: None,
+                routing_hint: None,
+                uuid: Uuid::new_v4(),
             },
             PadDescriptor {
-                number: "2".to_string(),
+                number: "2".into(),
+                pin_function: None,
                 pad_type: PadType::SMD,
                 shape: PadShape::RoundRect,
                 position: (0.48, 0.0),
+                rotation: None,
                 size: (0.56, 0.62),
                 drill_size: None,
-                layers: vec!["F.Cu".to_string(), "F.Paste".to_string(), "F.Mask".to_string()],
+                drill_slot: None,
+                layers: PadLayers::smd_front(),
                 roundrect_ratio: Some(0.25),
                 tenting: TentingSettings {
                     front: TentingType::None,
                     back: TentingType::None,
                 },
-                uuid: Uuid::new_v4().to_string(),
+                padstack: None,
+                fab_property: None,
+                solder_mask_margin: None,
+                routing_hint: None,
+                uuid: Uuid::new_v4(),
             },
         ]
     }
@@ -91,36 +106,45 @@ impl BoardComposableObject for SMTCapacitor0805 {
                 text: "REF**".to_string(),
                 position: (0.0, -1.16),
                 rotation: None,
-                layer: "F.SilkS".to_string(),
-                uuid: Uuid::new_v4().to_string(),
+                layer: "F.SilkS".into(),
+                uuid: Uuid::new_v4(),
                 font: FontSettings {
                     size: (1.0, 1.0),
                     thickness: 0.15,
                 },
+                hidden: false,
+                justify: None,
+                role: None,
             },
             FpText {
                 text_type: FpTextType::Value,
                 text: "C_0402_1005Metric".to_string(),
                 position: (0.0, 1.16),
                 rotation: None,
-                layer: "F.Fab".to_string(),
-                uuid: Uuid::new_v4().to_string(),
+                layer: "F.Fab".into(),
+                uuid: Uuid::new_v4(),
                 font: FontSettings {
                     size: (1.0, 1.0),
                     thickness: 0.15,
                 },
+                hidden: false,
+                justify: None,
+                role: None,
             },
             FpText {
                 text_type: FpTextType::User,
                 text: "${REFERENCE}".to_string(),
                 position: (0.0, 0.0),
                 rotation: None,
-                layer: "F.Fab".to_string(),
-                uuid: Uuid::new_v4().to_string(),
+                layer: "F.Fab".into(),
+                uuid: Uuid::new_v4(),
                 font: FontSettings {
                     size: (0.25, 0.25),
                     thickness: 0.04,
                 },
+                hidden: false,
+                justify: None,
+                role: None,
             },
         ]
     }
@@ -135,8 +159,10 @@ impl BoardComposableObject for SMTCapacitor0805 {
                     end: (0.107836, -0.36),
                 },
                 layer: LayerType::SilkScreen,
+                side: Side::Front,
                 stroke: Stroke { width: 0.12, stroke_type: StrokeType::Solid },
-                uuid: Uuid::new_v4().to_string(),
+                uuid: Uuid::new_v4(),
+                role: None,
             },
             GraphicElement {
                 element_type: GraphicType::Line {
@@ -144,8 +170,10 @@ impl BoardComposableObject for SMTCapacitor0805 {
                     end: (0.107836, 0.36),
                 },
                 layer: LayerType::SilkScreen,
+                side: Side::Front,
                 stroke: Stroke { width: 0.12, stroke_type: StrokeType::Solid },
-                uuid: Uuid::new_v4().to_string(),
+                uuid: Uuid::new_v4(),
+                role: None,
             },
             // Fab layer outline
             GraphicElement {
@@ -154,8 +182,10 @@ impl BoardComposableObject for SMTCapacitor0805 {
                     end: (0.5, -0.25),
                 },
                 layer: LayerType::Fabrication,
+                side: Side::Front,
                 stroke: Stroke { width: 0.1, stroke_type: StrokeType::Solid },
-                uuid: Uuid::new_v4().to_string(),
+                uuid: Uuid::new_v4(),
+                role: None,
             },
             GraphicElement {
                 element_type: GraphicType::Line {
@@ -163,8 +193,10 @@ impl BoardComposableObject for SMTCapacitor0805 {
                     end: (-0.5, -0.25),
                 },
                 layer: LayerType::Fabrication,
+                side: Side::Front,
                 stroke: Stroke { width: 0.1, stroke_type: StrokeType::Solid },
-                uuid: Uuid::new_v4().to_string(),
+                uuid: Uuid::new_v4(),
+                role: None,
             },
             GraphicElement {
                 element_type: GraphicType::Line {
@@ -172,8 +204,10 @@ impl BoardComposableObject for SMTCapacitor0805 {
                     end: (0.5, 0.25),
                 },
                 layer: LayerType::Fabrication,
+                side: Side::Front,
                 stroke: Stroke { width: 0.1, stroke_type: StrokeType::Solid },
-                uuid: Uuid::new_v4().to_string(),
+                uuid: Uuid::new_v4(),
+                role: None,
             },
             GraphicElement {
                 element_type: GraphicType::Line {
@@ -181,8 +215,10 @@ impl BoardComposableObject for SMTCapacitor0805 {
                     end: (-0.5, 0.25),
                 },
                 layer: LayerType::Fabrication,
+                side: Side::Front,
                 stroke: Stroke { width: 0.1, stroke_type: StrokeType::Solid },
-                uuid: Uuid::new_v4().to_string(),
+                uuid: Uuid::new_v4(),
+                role: None,
             },
         ]
     }
@@ -209,8 +245,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         value: "100nF".to_string() 
     };
     
+    // Compare the declared body with the one drawn on the fab layer
+    match cap.bounding_box_mismatch(BOUNDING_BOX_TOLERANCE) {
+        Some(mismatch) => println!("{}", mismatch.to_finding()),
+        None => println!("Declared bounding box matches the fab outline"),
+    }
+
     // Generate the footprint
-    let footprint_content = copper_exporters::to_kicad_footprint(&cap);
+    let footprint_content = copper_exporters::try_to_kicad_footprint(&cap)?;
     
     // Write to file
     std::fs::write("C_0402_1005Metric.kicad_mod", footprint_content)?;
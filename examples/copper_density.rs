@@ -0,0 +1,70 @@
+//! Copper density grid
+//!
+//! `cargo run -p copper-substrate --example copper_density` measures a 50 x 50 mm board
+//! whose F.Cu has a ground pour over the left half and a few tracks on the right: the
+//! board the 3D viewer's heat map demo shows. Left cells must be fully covered and right
+//! cells nearly bare, and each half must match the coverage sampled over the same region
+//! with `thieving::copper_coverage`. A cell the pour's edge cuts must be partly covered,
+//! and a hatched pour must cover what its grid of lines does.
+
+use copper_substrate::board::Board;
+use copper_substrate::prelude::*;
+use copper_substrate::thieving::{copper_coverage, COVERAGE_RESOLUTION};
+
+fn rectangle(min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> Vec<Point> {
+    vec![Point::new(min_x, min_y), Point::new(max_x, min_y), Point::new(max_x, max_y), Point::new(min_x, max_y)]
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let top = BoardLayer::front(LayerType::Copper);
+    let mut board = Board::new("half_pour").with_rectangular_outline(50.0, 50.0);
+    board.add_zone(Zone::new("GND", &[top], rectangle(0.0, 0.0, 25.0, 50.0)));
+    for index in 0..5 {
+        let y = 5.0 + index as f32 * 10.0;
+        board.add_track(Track::new("SIG", top, Point::new(30.0, y), Point::new(45.0, y), 0.25));
+    }
+
+    let grid = board.copper_density_grid(top, 5.0);
+    for row in 0..grid.rows {
+        let line: Vec<String> = (0..grid.columns).map(|column| format!("{:3.0}", grid.get(column, row).unwrap_or(f32::NAN) * 100.0)).collect();
+        println!("{}", line.join(" "));
+    }
+    let half = |columns: std::ops::Range<usize>| {
+        let grid = &grid;
+        let values: Vec<f32> = (0..grid.rows).flat_map(|row| columns.clone().filter_map(move |column| grid.get(column, row))).collect();
+        values.iter().sum::<f32>() / values.len() as f32
+    };
+    let (left, right) = (half(0..5), half(5..10));
+    let copper = board.copper_outlines(top);
+    let sampled_left = copper_coverage(&rectangle(0.0, 0.0, 25.0, 50.0), &copper, COVERAGE_RESOLUTION).fraction();
+    let sampled_right = copper_coverage(&rectangle(25.0, 0.0, 50.0, 50.0), &copper, COVERAGE_RESOLUTION).fraction();
+    println!("left {:.3} (sampled {:.3}), right {:.3} (sampled {:.3}), average {:.3}", left, sampled_left, right, sampled_right, grid.average());
+    if (0..grid.rows).any(|row| (0..5).any(|column| grid.get(column, row) != Some(1.0))) {
+        return Err("every cell under the pour must be fully covered".into());
+    }
+    if (0..grid.rows).any(|row| (5..10).any(|column| grid.get(column, row).is_none_or(|coverage| coverage > 0.1))) {
+        return Err("cells on the right carry at most a track".into());
+    }
+    if (left - sampled_left).abs() > 0.01 || (right - sampled_right).abs() > 0.01 {
+        return Err("each half must match the sampled coverage".into());
+    }
+
+    // The pour's edge at x = 25 runs through the 24..27 mm column of a 3 mm grid
+    let fine = board.copper_density_grid(top, 3.0);
+    let edge = fine.get(8, 0).ok_or("edge cell missing")?;
+    println!("3 mm cells: {} x {}, edge cell {:.3}", fine.columns, fine.rows, edge);
+    if (edge - 1.0 / 3.0).abs() > 0.05 {
+        return Err("the pour edge cell must be about a third covered".into());
+    }
+
+    // A hatched pour: 0.25 mm lines at 1 mm pitch both ways cover 1 - 0.75² of the area
+    let mut hatched = Board::new("hatched").with_rectangular_outline(20.0, 20.0);
+    let fill = FillStyle::Hatch { pitch: 1.0, width: 0.25, angle: 0.0 };
+    hatched.add_zone(Zone::new("GND", &[top], rectangle(0.0, 0.0, 20.0, 20.0)).with_fill(fill));
+    let average = hatched.copper_density_grid(top, 5.0).average();
+    println!("hatched pour {:.3}", average);
+    if (average - (1.0 - 0.75 * 0.75)).abs() > 0.03 {
+        return Err("a hatched pour covers what its line grid does".into());
+    }
+    Ok(())
+}
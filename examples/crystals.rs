@@ -0,0 +1,145 @@
+//! Crystal and oscillator footprints
+//!
+//! `cargo run -p copper-exporters --example crystals` checks the 3225 crystal against
+//! KiCad's Crystal_SMD_3225-4Pin_3.2x2.5mm: name, pad numbering, pad positions and sizes,
+//! silkscreen and courtyard, with pins 2 and 4 tied to ground. Every standard package,
+//! the HC-49/U with and without its case ground pad and an oscillator are then generated,
+//! and each must keep its silkscreen off the copper, fit its pads inside the courtyard and
+//! read back from its .kicad_mod as the same part.
+
+use copper_exporters::kicad_footprint_parse::{KiCadFootprint, ParseOptions};
+use copper_exporters::kicad_pcb_export::to_kicad_footprint;
+use copper_substrate::crystal::STANDARD_CRYSTAL_SIZES;
+use copper_substrate::prelude::*;
+
+/// Pad number, position and size
+type PadSpec = (&'static str, (f32, f32), (f32, f32));
+
+/// Pads of KiCad's Crystal_SMD_3225-4Pin_3.2x2.5mm
+const KICAD_3225_PADS: [PadSpec; 4] = [
+    ("1", (-1.1, 0.85), (1.4, 1.2)),
+    ("2", (1.1, 0.85), (1.4, 1.2)),
+    ("3", (1.1, -0.85), (1.4, 1.2)),
+    ("4", (-1.1, -0.85), (1.4, 1.2)),
+];
+
+/// Its silkscreen outline and courtyard
+const KICAD_3225_SILK: Rectangle = Rectangle { min_x: -2.0, min_y: -1.65, max_x: 2.0, max_y: 1.65 };
+const KICAD_3225_COURTYARD: Rectangle = Rectangle { min_x: -2.1, min_y: -1.7, max_x: 2.1, max_y: 1.7 };
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-4
+}
+
+fn same_rectangle(a: &Rectangle, b: &Rectangle) -> bool {
+    close(a.min_x, b.min_x) && close(a.min_y, b.min_y) && close(a.max_x, b.max_x) && close(a.max_y, b.max_y)
+}
+
+fn check_3225() -> Result<(), String> {
+    let crystal = Crystal::standard("3225").ok_or("3225 is a standard size")?.with_functional_type(FunctionalType::Crystal("16MHz".into()));
+    if crystal.footprint_name() != "Crystal_SMD_3225-4Pin_3.2x2.5mm" {
+        return Err(format!("3225 must follow KiCad's naming, got {}", crystal.footprint_name()));
+    }
+    let pads = crystal.pad_descriptors();
+    if pads.len() != KICAD_3225_PADS.len() {
+        return Err(format!("3225 has 4 pads, got {}", pads.len()));
+    }
+    for (pad, (number, position, size)) in pads.iter().zip(KICAD_3225_PADS) {
+        let matches = pad.number == number
+            && close(pad.position.0, position.0)
+            && close(pad.position.1, position.1)
+            && close(pad.size.0, size.0)
+            && close(pad.size.1, size.1)
+            && matches!(pad.shape, PadShape::Rect);
+        if !matches {
+            return Err(format!("pad {} at {:?} size {:?}, KiCad has {} at {:?} size {:?}", pad.number, pad.position, pad.size, number, position, size));
+        }
+        let ground = number == "2" || number == "4";
+        if (pad.pin_function.as_deref() == Some("GND")) != ground {
+            return Err(format!("pad {} is {}a ground pin", number, if ground { "" } else { "not " }));
+        }
+    }
+    let silk: Vec<Rectangle> = crystal
+        .graphic_elements()
+        .into_iter()
+        .filter(|element| element.layer == LayerType::SilkScreen)
+        .filter_map(|element| match element.element_type {
+            GraphicType::Rectangle { bounds } => Some(bounds),
+            _ => None,
+        })
+        .collect();
+    if silk.len() != 1 || !same_rectangle(&silk[0], &KICAD_3225_SILK) {
+        return Err(format!("silkscreen {:?}, KiCad's runs around {:?}", silk, KICAD_3225_SILK));
+    }
+    let courtyard = crystal.generate_courtyard().bounds;
+    if !same_rectangle(&courtyard, &KICAD_3225_COURTYARD) {
+        return Err(format!("courtyard {:?}, KiCad's is {:?}", courtyard, KICAD_3225_COURTYARD));
+    }
+    println!("{}: {}", crystal.footprint_name(), crystal.description().unwrap_or_default());
+    Ok(())
+}
+
+/// Silkscreen clear of copper, pads inside the courtyard, and the same part read back
+fn check(footprint: &Crystal) -> Result<(), Box<dyn std::error::Error>> {
+    let name = footprint.footprint_name();
+    let pads: Vec<Rectangle> = footprint.pad_descriptors().iter().map(|pad| pad.outline_aabb()).collect();
+    let courtyard = footprint.generate_courtyard().bounds;
+    for pad in &pads {
+        let inside = pad.min_x >= courtyard.min_x && pad.max_x <= courtyard.max_x && pad.min_y >= courtyard.min_y && pad.max_y <= courtyard.max_y;
+        if !inside {
+            return Err(format!("{}: pad {:?} sticks out of the courtyard {:?}", name, pad, courtyard).into());
+        }
+    }
+    for element in footprint.graphic_elements().iter().filter(|element| element.layer == LayerType::SilkScreen) {
+        let points: Vec<(f32, f32)> = match &element.element_type {
+            GraphicType::Rectangle { bounds } => vec![(bounds.min_x, bounds.min_y), (bounds.max_x, bounds.min_y), (bounds.max_x, bounds.max_y), (bounds.min_x, bounds.max_y)],
+            GraphicType::Polygon { points, .. } => points.clone(),
+            _ => Vec::new(),
+        };
+        for index in 0..points.len() {
+            let (start, end) = (points[index], points[(index + 1) % points.len()]);
+            // Sample each edge; no point may come within half a line width of a pad
+            for step in 0..=20 {
+                let t = step as f32 / 20.0;
+                let (x, y) = (start.0 + (end.0 - start.0) * t, start.1 + (end.1 - start.1) * t);
+                let margin = element.stroke.width / 2.0;
+                if pads.iter().any(|pad| x > pad.min_x - margin && x < pad.max_x + margin && y > pad.min_y - margin && y < pad.max_y + margin) {
+                    return Err(format!("{}: silkscreen at ({:.2}, {:.2}) touches a pad", name, x, y).into());
+                }
+            }
+        }
+    }
+    let parsed = KiCadFootprint::parse(&to_kicad_footprint(footprint), ParseOptions::strict())?;
+    if parsed.footprint_name() != name || parsed.terminal_count() != footprint.terminal_count() || parsed.functional_type().kind_name() != footprint.functional_type().kind_name() {
+        return Err(format!("{} must read back as the same {}", name, footprint.functional_type().kind_name()).into());
+    }
+    let grounds: Vec<PadNumber> = footprint.pad_descriptors().into_iter().filter(|pad| pad.pin_function.is_some()).map(|pad| pad.number).collect();
+    println!("  {:<36} {} pads, ground {:?}, courtyard {:.1} x {:.1} mm", name, footprint.terminal_count(), grounds, courtyard.max_x - courtyard.min_x, courtyard.max_y - courtyard.min_y);
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    check_3225()?;
+
+    let mut footprints: Vec<Crystal> = STANDARD_CRYSTAL_SIZES.iter().filter_map(|(code, ..)| Crystal::standard(code)).collect();
+    footprints.push(Crystal::smd(5.0, 3.2, 2));
+    footprints.push(Crystal::hc49u());
+    footprints.push(Crystal::hc49u().with_ground_pad());
+    footprints.push(Crystal::standard("3225").ok_or("3225 is a standard size")?.with_functional_type(FunctionalType::Oscillator("25MHz".into())));
+    for footprint in &footprints {
+        check(footprint)?;
+    }
+
+    let hc49 = Crystal::hc49u().with_ground_pad();
+    println!("{}: {}", hc49.footprint_name(), hc49.description().unwrap_or_default());
+    let pin1 = hc49.pad_descriptors().into_iter().find(|pad| pad.number == "1").map(|pad| pad.position);
+    if pin1 != Some((0.0, 0.0)) || hc49.is_smt() {
+        return Err("the HC-49 is through hole with pin 1 at the origin".into());
+    }
+    let oscillator = footprints.last().ok_or("an oscillator was generated")?;
+    if oscillator.ground_pins() != ["2"] || oscillator.library_name() != "Oscillator" {
+        return Err("oscillators ground pin 2 only and live in the Oscillator library".into());
+    }
+    println!("checked {} crystal and oscillator footprints", footprints.len());
+    Ok(())
+}
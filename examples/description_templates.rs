@@ -0,0 +1,72 @@
+//! Templated descriptions
+//!
+//! `cargo run -p copper-substrate --example description_templates` generates 0603 and
+//! 0805 resistors with KiCad's attribution and body size source, and their descriptions
+//! must match the KiCad library strings word for word. A German template must be used
+//! once registered, a language without templates must fall back to English, and malformed
+//! templates must be rejected.
+
+use copper_substrate::describe::{register_template, set_attribution, set_language};
+use copper_substrate::prelude::*;
+use copper_substrate::presets;
+
+const SOURCE: &str = "IPC-SM-782 page 72, https://www.pcb-3d.com/wordpress/wp-content/uploads/ipc-sm-782a_amendment_1_and_2.pdf";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    set_attribution("kicad-footprint-generator");
+    for (resistor, kicad) in [
+        (
+            presets::resistor_0603("10k"),
+            "Resistor SMD 0603 (1608 Metric), square (rectangular) end terminal, IPC_7351 nominal, \
+             (Body size source: IPC-SM-782 page 72, https://www.pcb-3d.com/wordpress/wp-content/uploads/ipc-sm-782a_amendment_1_and_2.pdf), \
+             generated with kicad-footprint-generator",
+        ),
+        (
+            presets::resistor_0805("10k"),
+            "Resistor SMD 0805 (2012 Metric), square (rectangular) end terminal, IPC_7351 nominal, \
+             (Body size source: IPC-SM-782 page 72, https://www.pcb-3d.com/wordpress/wp-content/uploads/ipc-sm-782a_amendment_1_and_2.pdf), \
+             generated with kicad-footprint-generator",
+        ),
+    ] {
+        let resistor = resistor.with_body_size_source(SOURCE);
+        let description = resistor.description().unwrap_or_default();
+        println!("{}: {}", resistor.footprint_name(), description);
+        println!("  tags: {}", resistor.tags().unwrap_or_default());
+        if description != kicad {
+            return Err(format!("{} doesn't match the KiCad library description", resistor.footprint_name()).into());
+        }
+    }
+
+    // Without a source or at custom fillets the optional parts drop out
+    let custom = presets::resistor_0603("10k").with_fillets(ChipFillets { toe: 0.5, heel: 0.1, side: 0.1 });
+    let description = custom.description().unwrap_or_default();
+    println!("custom fillets: {}", description);
+    if description.contains("IPC_7351") || description.contains("source") {
+        return Err("custom fillets have no density and no source".into());
+    }
+    let soic = Soic::from_jedec("MS-012-AA", 8)?;
+    println!("{}: {} / {}", soic.footprint_name(), soic.description().unwrap_or_default(), soic.tags().unwrap_or_default());
+
+    // Other languages
+    register_template("chip", "de", "{kind} SMD {body_size_imperial} ({body_size_metric} metrisch), erstellt mit {generator}")?;
+    set_language("de");
+    let german = presets::resistor_0603("10k").description().unwrap_or_default();
+    println!("de: {}", german);
+    if !german.ends_with("(1608 metrisch), erstellt mit kicad-footprint-generator") {
+        return Err("the German template must be used".into());
+    }
+    set_language("fr");
+    let fallback = presets::resistor_0603("10k").description().unwrap_or_default();
+    if !fallback.starts_with("Resistor SMD 0603 (1608 Metric)") {
+        return Err("languages without a template fall back to English".into());
+    }
+    set_language("en");
+
+    for template in ["{kind", "[, {density}", "[[, {density}]", "a [b [c]]", "}"] {
+        match register_template("chip", "en", template) {
+            Err(error) => println!("\"{}\": {}", template, error),
+            Ok(()) => return Err(format!("\"{}\" must be rejected", template).into()),
+        }
+    }
+    Ok(())
+}
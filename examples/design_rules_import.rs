@@ -0,0 +1,71 @@
+//! Design rules imported from a KiCad project
+//!
+//! `cargo run -p copper-exporters --example design_rules_import` reads
+//! examples/fixtures/rules_project, whose net classes are Default and Power (GND by name,
+//! `+*V*` by pattern) and whose custom rules raise the Power to Default clearance to
+//! 0.5 mm and the Default track width to 0.25 mm. It prints the classes, the rules it
+//! could not apply and the violations of a board routed against them. The checks live in
+//! the unit tests of `kicad_rules_import`.
+
+use std::path::Path;
+
+use copper_exporters::kicad_rules_import::{read_project_rules, KiCadDesignRules};
+use copper_substrate::board::Board;
+use copper_substrate::prelude::*;
+use copper_substrate::routing::RoutingViolation;
+
+/// Rule and nets of every violation, in order
+fn summary(board: &Board) -> Vec<String> {
+    board
+        .routing_violations()
+        .iter()
+        .map(|violation| match violation {
+            RoutingViolation::Clearance { net_a, net_b, required, .. } => {
+                format!("clearance {} {} {:.2}", net_a.as_deref().unwrap_or("-"), net_b.as_deref().unwrap_or("-"), required)
+            }
+            RoutingViolation::TrackWidth { net, required, .. } => format!("track_width {} {:.2}", net, required),
+            RoutingViolation::ViaDiameter { net, required, .. } => format!("via_diameter {} {:.2}", net, required),
+            RoutingViolation::ViaDrill { net, required, .. } => format!("via_drill {} {:.2}", net, required),
+            other => format!("{:?}", other),
+        })
+        .collect()
+}
+
+fn routed_board(rules: &KiCadDesignRules) -> Board {
+    let copper = BoardLayer::front(LayerType::Copper);
+    let mut board = Board::new("rules").with_rectangular_outline(30.0, 12.0);
+    rules.apply_to(&mut board);
+    // 0.425 mm between GND and SIG: enough for the classes, not for the custom rule
+    board.add_track(Track::new("GND", copper, Point::new(2.0, 2.0), Point::new(12.0, 2.0), 0.5));
+    board.add_track(Track::new("SIG", copper, Point::new(2.0, 2.8), Point::new(12.0, 2.8), 0.25));
+    board.add_track(Track::new("SIG2", copper, Point::new(2.0, 3.3), Point::new(12.0, 3.3), 0.25));
+    board.add_track(Track::new("+3V3", copper, Point::new(2.0, 6.0), Point::new(12.0, 6.0), 0.4));
+    board.add_track(Track::new("CLK", copper, Point::new(2.0, 9.0), Point::new(12.0, 9.0), 0.2));
+    board.add_via(Via::new("GND", Point::new(20.0, 2.0), 0.6, 0.3));
+    board
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let project = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples/fixtures/rules_project/rules_project.kicad_pro");
+    let rules = read_project_rules(&project)?;
+    println!("default: {:?}", rules.default_class);
+    for class in &rules.classes {
+        println!("class: {:?}", class);
+    }
+    for rule in &rules.unsupported {
+        println!("{}", rule);
+    }
+
+    println!("with custom rules:");
+    for violation in summary(&routed_board(&rules)) {
+        println!("  {}", violation);
+    }
+
+    let text = std::fs::read_to_string(&project)?;
+    let board = routed_board(&KiCadDesignRules::from_project(&text)?);
+    println!("project only: {} violations", summary(&board).len());
+
+    let fab = rules.fab_profile(FabProfile::standard());
+    println!("fab profile: min drill {} mm, min annular ring {} mm", fab.min_drill, fab.min_annular_ring);
+    Ok(())
+}
@@ -0,0 +1,184 @@
+//! DIP footprints
+//!
+//! `cargo run -p copper-exporters --example dip` checks DIP-14_W7.62mm against KiCad's
+//! Package_DIP footprint: pad numbering, positions, shapes, sizes and drills, the chamfered
+//! fab outline, the notched silkscreen outline and the courtyard. The socket and long pad
+//! variants are checked for their silkscreen.
+//!
+//! Then DIP-8 to DIP-40, narrow and wide, plain, socketed and with long pads must keep
+//! their silkscreen off the copper and their pads inside the courtyard. They must export
+//! as through hole parts with every pad drilled, and read back from their .kicad_mod as
+//! the same part.
+//!
+//! Last, DIP-8 with pin-in-paste apertures overflowing the pads prints the solder volume
+//! of each joint. The volume math is checked in the unit tests of `pin_in_paste`.
+
+use copper_exporters::kicad_footprint_parse::{KiCadFootprint, ParseOptions};
+use copper_exporters::kicad_pcb_export::to_kicad_footprint;
+use copper_substrate::dip::DIP_DRILL;
+use copper_substrate::prelude::*;
+
+/// Corners of KiCad's fab outline, the pin 1 chamfer first
+const KICAD_FAB: [(f32, f32); 5] = [(0.635, -0.27), (1.635, -1.27), (6.985, -1.27), (6.985, 16.51), (0.635, 16.51)];
+/// Ends of KiCad's silkscreen lines, and the middle of its notch arc
+const KICAD_SILK: [(f32, f32); 6] = [(1.16, -1.33), (2.81, -1.33), (4.81, -1.33), (6.46, -1.33), (6.46, 16.57), (1.16, 16.57)];
+const KICAD_NOTCH_MIDDLE: (f32, f32) = (3.81, -0.33);
+/// KiCad's courtyard; its generator rounds the left edge out a grid step past -1.05, the
+/// pads less the 0.25 mm margin, so that edge may differ by one step
+const KICAD_COURTYARD: Rectangle = Rectangle { min_x: -1.1, min_y: -1.55, max_x: 8.7, max_y: 16.8 };
+const COURTYARD_STEP: f32 = 0.05;
+/// Socket silkscreen of DIP-14_W7.62mm_Socket, and the silkscreen inset of the long pads
+const KICAD_SOCKET_SILK: Rectangle = Rectangle { min_x: -1.33, min_y: -1.39, max_x: 8.95, max_y: 16.63 };
+const KICAD_LONG_PAD_SILK_X: f32 = 1.56;
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-3
+}
+
+fn polygon(elements: &[GraphicElement], layer: LayerType) -> Vec<(f32, f32)> {
+    elements
+        .iter()
+        .filter(|element| element.layer == layer)
+        .find_map(|element| match &element.element_type {
+            GraphicType::Polygon { points, .. } => Some(points.clone()),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+fn has_point(points: &[(f32, f32)], point: (f32, f32)) -> bool {
+    points.iter().any(|&(x, y)| close(x, point.0) && close(y, point.1))
+}
+
+fn check_dip14() -> Result<(), String> {
+    let dip = Dip::new(14).ok_or("14 pins make a DIP")?;
+    if dip.footprint_name() != "DIP-14_W7.62mm" {
+        return Err(format!("DIP-14 must follow KiCad's naming, got {}", dip.footprint_name()));
+    }
+    for pad in dip.pad_descriptors() {
+        let number: usize = pad.number.parse().map_err(|_| format!("pad {} must be numbered", pad.number))?;
+        let expected = if number <= 7 { (0.0, (number - 1) as f32 * 2.54) } else { (7.62, (14 - number) as f32 * 2.54) };
+        let shape_matches = if number == 1 { matches!(pad.shape, PadShape::Rect) } else { matches!(pad.shape, PadShape::Oval) };
+        let matches = close(pad.position.0, expected.0)
+            && close(pad.position.1, expected.1)
+            && shape_matches
+            && close(pad.size.0, 1.6)
+            && close(pad.size.1, 1.6)
+            && pad.drill_size == Some(0.8);
+        if !matches {
+            return Err(format!("pad {} at {:?} size {:?} drill {:?}, KiCad has it at {:?}", number, pad.position, pad.size, pad.drill_size, expected));
+        }
+    }
+    let elements = dip.graphic_elements();
+    let fab = polygon(&elements, LayerType::Fabrication);
+    if let Some(missing) = KICAD_FAB.iter().find(|&&corner| !has_point(&fab, corner)) {
+        return Err(format!("fab outline lacks KiCad's corner {:?}", missing));
+    }
+    let silk = polygon(&elements, LayerType::SilkScreen);
+    if let Some(missing) = KICAD_SILK.iter().chain([&KICAD_NOTCH_MIDDLE]).find(|&&point| !has_point(&silk, point)) {
+        return Err(format!("silkscreen lacks KiCad's point {:?}", missing));
+    }
+    let courtyard = dip.generate_courtyard().bounds;
+    let courtyard_matches = (courtyard.min_x - KICAD_COURTYARD.min_x).abs() <= COURTYARD_STEP + 1e-3
+        && close(courtyard.min_y, KICAD_COURTYARD.min_y)
+        && close(courtyard.max_x, KICAD_COURTYARD.max_x)
+        && close(courtyard.max_y, KICAD_COURTYARD.max_y);
+    if !courtyard_matches {
+        return Err(format!("courtyard {:?}, KiCad's is {:?}", courtyard, KICAD_COURTYARD));
+    }
+
+    let socket = dip.clone().with_socket();
+    let socket_silk = socket.graphic_elements().into_iter().find_map(|element| match element.element_type {
+        GraphicType::Rectangle { bounds } if element.layer == LayerType::SilkScreen => Some(bounds),
+        _ => None,
+    });
+    let socket_matches = socket_silk.as_ref().is_some_and(|bounds| {
+        close(bounds.min_x, KICAD_SOCKET_SILK.min_x)
+            && close(bounds.min_y, KICAD_SOCKET_SILK.min_y)
+            && close(bounds.max_x, KICAD_SOCKET_SILK.max_x)
+            && close(bounds.max_y, KICAD_SOCKET_SILK.max_y)
+    });
+    if socket.footprint_name() != "DIP-14_W7.62mm_Socket" || !socket_matches {
+        return Err(format!("{} silkscreen {:?}, KiCad's is {:?}", socket.footprint_name(), socket_silk, KICAD_SOCKET_SILK));
+    }
+    let long = dip.clone().with_long_pads();
+    let long_silk = polygon(&long.graphic_elements(), LayerType::SilkScreen);
+    if long.footprint_name() != "DIP-14_W7.62mm_LongPads" || !has_point(&long_silk, (KICAD_LONG_PAD_SILK_X, -1.33)) {
+        return Err(format!("{} silkscreen must start at x = {}", long.footprint_name(), KICAD_LONG_PAD_SILK_X));
+    }
+    println!("{}: {}", dip.footprint_name(), dip.description().unwrap_or_default());
+    Ok(())
+}
+
+/// Silkscreen clear of copper, pads inside the courtyard, and a drilled through hole part
+/// read back
+fn check(dip: &Dip) -> Result<(), Box<dyn std::error::Error>> {
+    let name = dip.footprint_name();
+    let pads: Vec<Rectangle> = dip.pad_descriptors().iter().map(|pad| pad.outline_aabb()).collect();
+    let courtyard = dip.generate_courtyard().bounds;
+    for pad in &pads {
+        let inside = pad.min_x >= courtyard.min_x && pad.max_x <= courtyard.max_x && pad.min_y >= courtyard.min_y && pad.max_y <= courtyard.max_y;
+        if !inside {
+            return Err(format!("{}: pad {:?} sticks out of the courtyard {:?}", name, pad, courtyard).into());
+        }
+    }
+    for element in dip.graphic_elements().iter().filter(|element| element.layer == LayerType::SilkScreen) {
+        let points = match &element.element_type {
+            GraphicType::Rectangle { bounds } => vec![(bounds.min_x, bounds.min_y), (bounds.max_x, bounds.min_y), (bounds.max_x, bounds.max_y), (bounds.min_x, bounds.max_y)],
+            GraphicType::Polygon { points, .. } => points.clone(),
+            _ => Vec::new(),
+        };
+        let margin = element.stroke.width / 2.0;
+        for index in 0..points.len() {
+            let (start, end) = (points[index], points[(index + 1) % points.len()]);
+            for step in 0..=100 {
+                let t = step as f32 / 100.0;
+                let (x, y) = (start.0 + (end.0 - start.0) * t, start.1 + (end.1 - start.1) * t);
+                if pads.iter().any(|pad| x > pad.min_x - margin && x < pad.max_x + margin && y > pad.min_y - margin && y < pad.max_y + margin) {
+                    return Err(format!("{}: silkscreen at ({:.2}, {:.2}) touches a pad", name, x, y).into());
+                }
+            }
+        }
+    }
+    let text = to_kicad_footprint(dip);
+    let drilled = text.matches(&format!("(drill {})", DIP_DRILL)).count();
+    if !text.contains("(attr through_hole)") || drilled != dip.pins {
+        return Err(format!("{} must be a through hole part with {} drilled pads, {} found", name, dip.pins, drilled).into());
+    }
+    let parsed = KiCadFootprint::parse(&text, ParseOptions::strict())?;
+    if parsed.footprint_name() != name || parsed.terminal_count() != dip.pins || parsed.is_smt() {
+        return Err(format!("{} must read back as the same through hole part", name).into());
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    check_dip14()?;
+
+    let mut checked = 0;
+    for (pins, row_spacing) in (8..=40).step_by(2).map(|pins| (pins, 7.62)).chain((24..=40).step_by(4).map(|pins| (pins, 15.24))) {
+        let dip = Dip::new(pins).ok_or("even pin counts make DIPs")?.with_row_spacing(row_spacing);
+        for variant in [dip.clone(), dip.clone().with_socket(), dip.clone().with_long_pads(), dip.with_socket().with_long_pads()] {
+            check(&variant)?;
+            checked += 1;
+        }
+    }
+    let wide = Dip::new(40).ok_or("40 pins make a DIP")?.with_row_spacing(15.24).with_socket();
+    let courtyard = wide.generate_courtyard().bounds;
+    println!("{}: {}", wide.footprint_name(), wide.description().unwrap_or_default());
+    println!("  courtyard {:.2} x {:.2} mm", courtyard.max_x - courtyard.min_x, courtyard.max_y - courtyard.min_y);
+    if Dip::new(7).is_some() || Dip::new(2).is_some() {
+        return Err("DIPs have an even number of pins, at least 4".into());
+    }
+    println!("checked {} DIP footprints", checked);
+
+    let pin_in_paste = PinInPaste::new(PinSection::Rect { width: 0.5, thickness: 0.25 })
+        .with_stencil_thickness(0.15)
+        .with_overflow(Overflow { max_extension: 1.6, ..Overflow::default() });
+    let dip = Dip::new(8).ok_or("8 pins make a DIP")?.with_pin_in_paste(pin_in_paste)?;
+    println!("{} pin-in-paste:", dip.footprint_name());
+    for aperture in dip.pin_in_paste_report() {
+        println!("  {}", aperture);
+    }
+    Ok(())
+}
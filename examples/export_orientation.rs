@@ -0,0 +1,201 @@
+//! One orientation across export formats
+//!
+//! `cargo run -p copper-exporters --example export_orientation` turns an SOIC-8 to a range
+//! of angles and exports it as KiCad, SVG and DXF. In each format pin 1 must land in the
+//! quadrant of the screen it is turned into, and the reference text must read at the same
+//! angle. Y down or up and clockwise or counter-clockwise are undone by reading each file
+//! the way its viewer renders it.
+
+use copper_exporters::dxf_export::to_dxf;
+use copper_exporters::format::{y_up, Angle};
+use copper_exporters::kicad_footprint_parse::{KiCadFootprint, ParseOptions};
+use copper_exporters::kicad_pcb_export::to_kicad_footprint;
+use copper_exporters::layer_map::LayerMap;
+use copper_exporters::svg_export::to_svg;
+use copper_substrate::prelude::*;
+
+const TURNS: [f32; 6] = [0.0, 30.0, 90.0, 180.0, 225.0, 270.0];
+
+/// `inner` turned counter-clockwise on screen by `degrees` about the origin
+struct Turned<T> {
+    inner: T,
+    degrees: f32,
+}
+
+impl<T: BoardComposableObject> Turned<T> {
+    fn turn(&self, point: (f32, f32)) -> (f32, f32) {
+        let turned = Point::from(point).rotated(self.degrees);
+        (turned.x, turned.y)
+    }
+
+    fn angle(&self, local: Option<f32>) -> Option<f32> {
+        Some((Angle::from_option(local) + Angle::ccw(self.degrees)).normalized().ccw_degrees())
+    }
+}
+
+impl<T: BoardComposableObject> BoardComposableObject for Turned<T> {
+    fn is_smt(&self) -> bool {
+        self.inner.is_smt()
+    }
+    fn is_electrical(&self) -> bool {
+        self.inner.is_electrical()
+    }
+    fn terminal_count(&self) -> usize {
+        self.inner.terminal_count()
+    }
+    fn functional_type(&self) -> FunctionalType {
+        self.inner.functional_type()
+    }
+    fn footprint_name(&self) -> String {
+        self.inner.footprint_name()
+    }
+    fn library_name(&self) -> String {
+        self.inner.library_name()
+    }
+    fn bounding_box(&self) -> Rectangle {
+        let body = self.inner.bounding_box();
+        let corners: Vec<Point> = [(body.min_x, body.min_y), (body.max_x, body.min_y), (body.max_x, body.max_y), (body.min_x, body.max_y)]
+            .into_iter()
+            .map(|corner| Point::from(self.turn(corner)))
+            .collect();
+        Rectangle::enclosing(&corners).unwrap_or(body)
+    }
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        self.inner
+            .pad_descriptors()
+            .into_iter()
+            .map(|mut pad| {
+                pad.position = self.turn(pad.position);
+                pad.rotation = self.angle(pad.rotation);
+                pad
+            })
+            .collect()
+    }
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        self.inner
+            .fp_text_elements()
+            .into_iter()
+            .map(|mut text| {
+                text.position = self.turn(text.position);
+                text.rotation = self.angle(text.rotation);
+                text
+            })
+            .collect()
+    }
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        self.inner
+            .graphic_elements()
+            .into_iter()
+            .map(|mut element| {
+                element.element_type = match element.element_type {
+                    GraphicType::Line { start, end } => GraphicType::Line { start: self.turn(start), end: self.turn(end) },
+                    GraphicType::Rectangle { bounds } => GraphicType::Polygon {
+                        points: [(bounds.min_x, bounds.min_y), (bounds.max_x, bounds.min_y), (bounds.max_x, bounds.max_y), (bounds.min_x, bounds.max_y)]
+                            .into_iter()
+                            .map(|corner| self.turn(corner))
+                            .collect(),
+                        fill: None,
+                    },
+                    GraphicType::Circle { center, radius } => GraphicType::Circle { center: self.turn(center), radius },
+                    GraphicType::Polygon { points, fill } => {
+                        GraphicType::Polygon { points: points.into_iter().map(|point| self.turn(point)).collect(), fill }
+                    }
+                    other => other,
+                };
+                element
+            })
+            .collect()
+    }
+    fn model_3d(&self) -> Option<Model3D> {
+        self.inner.model_3d()
+    }
+}
+
+/// Screen quadrant of a Y down point: (right, below)
+fn quadrant(x: f32, y: f32) -> (bool, bool) {
+    (x > 0.0, y > 0.0)
+}
+
+fn angles_match(a: Angle, b: Angle) -> bool {
+    let difference = Angle::ccw(a.ccw_degrees() - b.ccw_degrees()).normalized().ccw_degrees();
+    !(0.01..=359.99).contains(&difference)
+}
+
+fn centroid(points: &[(f32, f32)]) -> (f32, f32) {
+    let count = points.len().max(1) as f32;
+    (points.iter().map(|point| point.0).sum::<f32>() / count, points.iter().map(|point| point.1).sum::<f32>() / count)
+}
+
+/// Value of attribute `name` in an SVG tag
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let start = tag.find(&format!(" {}=\"", name))? + name.len() + 3;
+    tag[start..].split('"').next()
+}
+
+/// Pin 1 centre and reference text angle as an SVG viewer shows them: Y down, `rotate()`
+/// clockwise
+fn read_svg(svg: &str) -> Option<((f32, f32), Angle)> {
+    let pad = svg.lines().find(|line| line.contains("data-pad=\"1\""))?;
+    let points: Vec<(f32, f32)> = attribute(pad, "points")?
+        .split(' ')
+        .filter_map(|pair| pair.split_once(','))
+        .filter_map(|(x, y)| Some((x.parse().ok()?, y.parse().ok()?)))
+        .collect();
+    let text = svg.lines().find(|line| line.contains(">REF**<"))?;
+    let angle = match attribute(text, "transform").and_then(|transform| transform.strip_prefix("rotate(")) {
+        Some(rotate) => Angle::cw(rotate.split(' ').next()?.parse().ok()?),
+        None => Angle::ZERO,
+    };
+    Some((centroid(&points), angle))
+}
+
+/// Pin 1 centre and reference text angle as a DXF viewer shows them, Y up and angles
+/// counter-clockwise, turned back to screen Y down. Pads carry no numbers in DXF; pin 1
+/// is the first outline on the copper layer.
+fn read_dxf(dxf: &str) -> Option<((f32, f32), Angle)> {
+    let lines: Vec<&str> = dxf.lines().collect();
+    let pairs: Vec<(&str, &str)> = lines.chunks(2).filter_map(|pair| Some((pair[0], *pair.get(1)?))).collect();
+    let start = pairs.iter().position(|&pair| pair == ("8", "TOP_COPPER"))?;
+    let end = start + pairs[start..].iter().position(|&pair| pair == ("0", "SEQEND"))?;
+    let mut points = Vec::new();
+    let mut x = None;
+    for &(code, value) in &pairs[start..end] {
+        match code {
+            "10" => x = value.parse::<f32>().ok(),
+            "20" => points.push((x?, y_up(value.parse().ok()?))),
+            _ => {}
+        }
+    }
+    let text = pairs.iter().position(|&pair| pair == ("1", "REF**"))?;
+    let angle = pairs[text..].iter().find(|(code, _)| *code == "50")?.1.parse().ok()?;
+    Some((centroid(&points), Angle::ccw(angle)))
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let soic = Soic::from_jedec("MS-012-AA", 8)?;
+    let pin1 = soic.pad_descriptors().into_iter().find(|pad| pad.number == "1").ok_or("SOIC-8 has a pin 1")?;
+    for degrees in TURNS {
+        let turned = Turned { inner: soic.clone(), degrees };
+        let expected = Point::from(pin1.position).rotated(degrees);
+        let expected_quadrant = quadrant(expected.x, expected.y);
+        let expected_angle = Angle::ccw(degrees);
+
+        let kicad = KiCadFootprint::parse(&to_kicad_footprint(&turned), ParseOptions::strict())?;
+        let pad = kicad.pad_descriptors().into_iter().find(|pad| pad.number == "1").ok_or("KiCad output has pin 1")?;
+        let reference = kicad.fp_text_elements().into_iter().find(|text| matches!(text.text_type, FpTextType::Reference)).ok_or("KiCad output has a reference")?;
+        let svg = read_svg(&to_svg(&turned, 0.01)).ok_or("SVG output has pin 1 and the reference")?;
+        let dxf = read_dxf(&to_dxf(&turned, &LayerMap::dxf_default())?).ok_or("DXF output has pin 1 and the reference")?;
+
+        let outputs = [("KiCad", pad.position, Angle::from_option(reference.rotation)), ("SVG", svg.0, svg.1), ("DXF", dxf.0, dxf.1)];
+        for (format, (x, y), angle) in outputs {
+            if quadrant(x, y) != expected_quadrant {
+                return Err(format!("turned {}°, {} shows pin 1 at ({:.3}, {:.3}), expected near ({:.3}, {:.3})", degrees, format, x, y, expected.x, expected.y).into());
+            }
+            if !angles_match(angle, expected_angle) {
+                return Err(format!("turned {}°, {} shows the reference at {}°", degrees, format, angle.ccw_degrees()).into());
+            }
+        }
+        println!("{:>5}°: pin 1 near ({:.2}, {:.2}) and reference at {}° in KiCad, SVG and DXF", degrees, expected.x, expected.y, expected_angle);
+    }
+    Ok(())
+}
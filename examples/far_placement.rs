@@ -0,0 +1,60 @@
+//! Parts far from the origin
+//!
+//! `cargo run -p copper-exporters --example far_placement` places a resistor at
+//! x = 10,000.0005 mm, which f32 can't hold: its nearest values there are 10,000 and
+//! 10,000.001. Placements are f64, so the exported footprint must sit exactly where it
+//! was put, with no precision warning. A track out there is still f32 and its two X coordinates must be flagged,
+//! as must a NaN coordinate. Footprint-local values must not pick up binary noise on the
+//! way to f64 (12.7 stays 12.7), and a block copied out there must keep the exact offsets.
+
+use copper_exporters::kicad_board_export::{precision_warnings, to_kicad_pcb};
+use copper_exporters::exporter::ExportWarning;
+use copper_substrate::block::{Block, BlockPlacement};
+use copper_substrate::board::Board;
+use copper_substrate::prelude::*;
+use copper_substrate::presets;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut board = Board::new("far").with_rectangular_outline(100.0, 100.0);
+    board.place("R1", presets::resistor_0603("10k"), (10000.0005_f64, 2500.0125_f64), 0.0)?;
+    board.place("R2", presets::resistor_0603("1k"), (12.7_f32, 5.1_f32), 90.0)?;
+
+    let warnings = precision_warnings(&board);
+    let pcb = to_kicad_pcb(&board);
+    for line in pcb.lines().map(str::trim).filter(|line| line.starts_with("(at 10000") || line.starts_with("(at 12.7")) {
+        println!("{}", line);
+    }
+    if !pcb.contains("(at 10000.0005 2500.0125)") {
+        return Err("R1 must be exported at exactly 10000.0005, 2500.0125".into());
+    }
+    if !pcb.contains("(at 12.7 5.1 90)") {
+        return Err("f32 positions must widen without binary noise".into());
+    }
+    if !warnings.is_empty() {
+        return Err(format!("a far placement must not warn: {:?}", warnings).into());
+    }
+
+    // A block copied far out keeps its offsets exactly
+    let mut block = Block::new("pair");
+    block.add_component("R1", std::rc::Rc::new(presets::resistor_0603("100")), (0.0005_f64, 0.0_f64), 0.0, Side::Front);
+    board.instantiate_block(&block, &BlockPlacement::new("PAIR", Point::new(10000.0, 0.0), 0.0))?;
+    let copied = board.components().iter().find(|placed| placed.reference == "R101").ok_or("block copy missing")?;
+    println!("block copy at {:?}", copied.position);
+    if copied.position != (10000.0005, 0.0) {
+        return Err("the block copy must land at exactly 10000.0005".into());
+    }
+
+    // Board items still in f32 are flagged
+    board.add_track(Track::new("N1", BoardLayer::front(LayerType::Copper), Point::new(10000.0, 2500.0), Point::new(10001.0, 2500.0), 0.2));
+    board.add_via(Via::new("N1", Point::new(f32::NAN, 0.0), 0.6, 0.3));
+    let warnings = precision_warnings(&board);
+    for warning in &warnings {
+        println!("warning: {}", warning);
+    }
+    let range = warnings.iter().filter(|warning| matches!(warning, ExportWarning::CoordinateRange { .. })).count();
+    let lost = warnings.iter().filter(|warning| matches!(warning, ExportWarning::PrecisionLoss { .. })).count();
+    if range != 2 || lost != 1 {
+        return Err(format!("expected 2 range and 1 precision warnings, got {} and {}", range, lost).into());
+    }
+    Ok(())
+}
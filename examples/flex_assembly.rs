@@ -0,0 +1,52 @@
+//! Flex-rigid assembly of two boards
+//!
+//! `cargo run -p copper-exporters --example flex_assembly` builds a main board and a
+//! display board joined by a 15 mm flex tail between two 2x4 headers. The flex folds the
+//! display back over the main board. The supply is VBUS on the main board and PWR_IN on
+//! the display; the assembly maps both onto VBUS. Each board goes to its own .kicad_pcb
+//! and the layout to handheld.assembly.json, in a directory under the system temp
+//! directory. VBUS must be declared under that name in both board files.
+
+use copper_exporters::assembly_export::{declared_nets, export_assembly};
+use copper_exporters::exporter::ExportOptions;
+use copper_substrate::board::Board;
+use copper_substrate::prelude::*;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let top = BoardLayer::front(LayerType::Copper);
+    let header = || Dip::new(8).ok_or("8 pins make a 2x4 header");
+
+    let mut main = Board::new("main").with_rectangular_outline(50.0, 30.0);
+    main.place("J1", header()?, (40.0, 11.0), 0.0)?;
+    main.connect_pad("J1", "1", "VBUS");
+    main.add_track(Track::new("VBUS", top, Point::new(10.0, 11.0), Point::new(40.0, 11.0), 0.5));
+
+    let mut display = Board::new("display").with_rectangular_outline(50.0, 30.0);
+    display.place("J2", header()?, (40.0, 11.0), 0.0)?;
+    display.connect_pad("J2", "1", "PWR_IN");
+    display.add_track(Track::new("PWR_IN", top, Point::new(20.0, 11.0), Point::new(40.0, 11.0), 0.5));
+
+    let mut assembly = Assembly::new("handheld");
+    assembly.add_board(main, BoardPlacement::default())?;
+    // Folded back over the main board, 6 mm above it
+    assembly.add_board(display, BoardPlacement::new(0.0, 30.0, 6.0).with_fold(180.0))?;
+    assembly.connect(InterconnectKind::Flex { length: 15.0 }, ConnectorEnd::new("main", "J1"), ConnectorEnd::new("display", "J2"))?;
+    assembly.map_net("VBUS", "main", "VBUS")?;
+    assembly.map_net("VBUS", "display", "PWR_IN")?;
+    let issues = assembly.check();
+    assert!(issues.is_empty(), "{:?}", issues);
+
+    let export = export_assembly(&assembly, &ExportOptions::default())?;
+    for (board, file, text) in &export.boards {
+        let nets = declared_nets(text)?;
+        println!("{} ({}): nets {:?}", board, file, nets);
+        assert!(nets.contains("VBUS") && !nets.contains("PWR_IN"));
+    }
+    let mismatches = export.net_mismatches(&assembly)?;
+    assert!(mismatches.is_empty(), "{:?}", mismatches);
+
+    let dir = std::env::temp_dir().join("copper_flex_assembly");
+    let manifest = export.write_to(&dir)?;
+    println!("wrote the boards and {}", manifest.display());
+    Ok(())
+}
@@ -0,0 +1,407 @@
+//! Interactive footprint preview
+//!
+//! Pick a generator, drag the sliders and the footprint is regenerated and redrawn every
+//! frame. Drag to pan, scroll to zoom, hover a pad for its number and size.
+//! Run with `cargo run -p copper-exporters --example footprint_preview`.
+//!
+//! With `-- --watch <dir>` the preview follows the TOML footprint definitions in `<dir>`
+//! instead: every saved change regenerates the footprint, redraws it and rewrites its
+//! `.kicad_mod` next to the definitions. Errors are listed in the side panel while the
+//! last good footprint stays on screen.
+//!
+//! `-- --footprint <file.kicad_mod>` shows a KiCad footprint and `-- --board <file.kicad_pcb>`
+//! a board's copper. The violations panel on the right runs footprint or board DRC and
+//! lists the findings by rule; clicking one centers the view on it and outlines the pads,
+//! graphics, tracks or vias involved. examples/fixtures/broken_footprint.kicad_mod has one
+//! violation of each footprint rule to try it on.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use copper_exporters::hot_reload::{DefinitionWatcher, ReloadOutcome, ReloadPipeline, DEFAULT_DEBOUNCE};
+use copper_exporters::kicad_board_import::read_board_file;
+use copper_exporters::kicad_footprint_parse::{KiCadFootprint, ParseOptions};
+use copper_substrate::analysis::{Finding, FootprintDrc, Violation};
+use copper_substrate::board::Board;
+use copper_substrate::prelude::*;
+use copper_substrate::render::{BoardRenderer, FootprintRenderer, Highlight, ViewTransform, ViolationPanel};
+use eframe::egui;
+
+/// How often the watcher is polled while the window is idle
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Lines kept in the reload log
+const LOG_LINES: usize = 200;
+/// Millimeters shown around a board finding navigated to
+const BOARD_FOCUS_SPAN: f32 = 8.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Generator {
+    Resistor,
+    Led,
+    StencilFiducials,
+    StencilTarget,
+    EdgeMountSma,
+}
+
+impl Generator {
+    const ALL: [Generator; 5] =
+        [Generator::Resistor, Generator::Led, Generator::StencilFiducials, Generator::StencilTarget, Generator::EdgeMountSma];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Generator::Resistor => "Chip resistor",
+            Generator::Led => "Chip LED",
+            Generator::StencilFiducials => "Stencil fiducial pair",
+            Generator::StencilTarget => "Stencil target",
+            Generator::EdgeMountSma => "Edge mount SMA",
+        }
+    }
+}
+
+struct WatchState {
+    dir: PathBuf,
+    watcher: DefinitionWatcher,
+    pipeline: ReloadPipeline,
+    selected: Option<PathBuf>,
+    log: Vec<String>,
+}
+
+impl WatchState {
+    fn new(dir: &Path) -> Result<Self, String> {
+        let watcher = DefinitionWatcher::new(dir, DEFAULT_DEBOUNCE).map_err(|e| e.to_string())?;
+        let mut state =
+            Self { dir: dir.to_path_buf(), watcher, pipeline: ReloadPipeline::new(dir), selected: None, log: Vec::new() };
+        for (path, outcome) in state.pipeline.load_dir(dir).map_err(|e| e.to_string())? {
+            state.record(&path, &outcome);
+        }
+        state.selected = state.pipeline.definitions().next().map(|(path, _)| path.to_path_buf());
+        Ok(state)
+    }
+
+    /// Reload settled changes; true when the selection changed
+    fn poll(&mut self) -> bool {
+        let (paths, errors) = self.watcher.poll();
+        for error in errors {
+            self.push_log(format!("watch error: {}", error));
+        }
+        for path in paths {
+            let outcome = self.pipeline.reload(&path);
+            self.record(&path, &outcome);
+        }
+        if self.selected.as_ref().is_some_and(|path| self.pipeline.get(path).is_none()) || self.selected.is_none() {
+            let next = self.pipeline.definitions().next().map(|(path, _)| path.to_path_buf());
+            let changed = next != self.selected;
+            self.selected = next;
+            return changed;
+        }
+        false
+    }
+
+    fn record(&mut self, path: &Path, outcome: &ReloadOutcome) {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let line = match outcome {
+            ReloadOutcome::Updated(file) => format!("{}: wrote {}", name, file.display()),
+            ReloadOutcome::Failed(error) => format!("{}: {}", name, error),
+            ReloadOutcome::Removed => format!("{}: removed", name),
+        };
+        self.push_log(line);
+    }
+
+    fn push_log(&mut self, line: String) {
+        self.log.push(line);
+        if self.log.len() > LOG_LINES {
+            self.log.remove(0);
+        }
+    }
+
+    fn component(&self) -> Option<&dyn BoardComposableObject> {
+        self.pipeline.get(self.selected.as_ref()?)?.footprint.as_deref()
+    }
+
+    /// The definition list with errors; true when the selection changed
+    fn definitions_panel(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        ui.heading("Definitions");
+        ui.label(self.dir.display().to_string());
+        ui.separator();
+        for (path, definition) in self.pipeline.definitions() {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let selected = self.selected.as_deref() == Some(path);
+            if ui.selectable_label(selected, name).clicked() && !selected {
+                self.selected = Some(path.to_path_buf());
+                changed = true;
+            }
+            if let Some(error) = &definition.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+                if definition.footprint.is_some() {
+                    ui.weak("showing the last good footprint");
+                }
+            }
+        }
+        if self.watcher.has_pending() {
+            ui.weak("reloading...");
+        }
+        changed
+    }
+}
+
+/// Routing and placement findings of a board
+fn board_findings(board: &Board) -> Vec<Finding> {
+    let routing = board.routing_violations();
+    let placement = board.audit_placements();
+    routing.iter().map(Violation::to_finding).chain(placement.iter().map(Violation::to_finding)).collect()
+}
+
+struct PreviewApp {
+    watch: Option<WatchState>,
+    loaded: Option<KiCadFootprint>,
+    board: Option<Board>,
+    board_renderer: BoardRenderer,
+    drc: FootprintDrc,
+    violations: ViolationPanel,
+    generator: Generator,
+    body_length: f32,
+    body_width: f32,
+    terminal_length: f32,
+    fillets: ChipFillets,
+    fiducial_diameter: f32,
+    fiducial_spacing: f32,
+    target_size: f32,
+    board_thickness: f32,
+    renderer: FootprintRenderer,
+    fit_pending: bool,
+}
+
+impl Default for PreviewApp {
+    fn default() -> Self {
+        Self {
+            watch: None,
+            loaded: None,
+            board: None,
+            board_renderer: BoardRenderer::new(ViewTransform::default()),
+            drc: FootprintDrc::default(),
+            violations: ViolationPanel::default(),
+            generator: Generator::Resistor,
+            body_length: 1.6,
+            body_width: 0.8,
+            terminal_length: 0.3,
+            fillets: ChipFillets::default(),
+            fiducial_diameter: 1.0,
+            fiducial_spacing: 10.0,
+            target_size: 3.0,
+            board_thickness: 1.6,
+            renderer: FootprintRenderer::new(ViewTransform::default()),
+            fit_pending: true,
+        }
+    }
+}
+
+impl PreviewApp {
+    fn component(&self) -> Box<dyn BoardComposableObject> {
+        match self.generator {
+            Generator::Resistor | Generator::Led => {
+                let functional_type = if self.generator == Generator::Led {
+                    FunctionalType::LED("red".to_string())
+                } else {
+                    FunctionalType::Resistor("10k".to_string())
+                };
+                let chip = ChipFootprint::new(functional_type, self.body_length, self.body_width, self.terminal_length);
+                Box::new(chip.with_fillets(self.fillets))
+            }
+            Generator::StencilFiducials => Box::new(StencilFiducialPair::new(self.fiducial_diameter, self.fiducial_spacing)),
+            Generator::StencilTarget => Box::new(StencilTarget::new(self.target_size, 0.2)),
+            Generator::EdgeMountSma => Box::new(EdgeMountSma::new(self.board_thickness)),
+        }
+    }
+
+    fn parameters(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        egui::ComboBox::from_label("Generator").selected_text(self.generator.label()).show_ui(ui, |ui| {
+            for generator in Generator::ALL {
+                changed |= ui.selectable_value(&mut self.generator, generator, generator.label()).changed();
+            }
+        });
+        ui.separator();
+        let slider = |ui: &mut egui::Ui, value: &mut f32, range, label| {
+            ui.add(egui::Slider::new(value, range).text(label).suffix(" mm")).changed()
+        };
+        match self.generator {
+            Generator::Resistor | Generator::Led => {
+                changed |= slider(ui, &mut self.body_length, 0.4..=7.0, "Body length");
+                changed |= slider(ui, &mut self.body_width, 0.2..=4.0, "Body width");
+                let max_terminal = self.body_length / 2.0;
+                changed |= slider(ui, &mut self.terminal_length, 0.05..=max_terminal, "Terminal");
+                changed |= slider(ui, &mut self.fillets.toe, 0.0..=0.8, "Toe fillet");
+                changed |= slider(ui, &mut self.fillets.heel, -0.2..=0.4, "Heel fillet");
+                changed |= slider(ui, &mut self.fillets.side, -0.1..=0.3, "Side fillet");
+            }
+            Generator::StencilFiducials => {
+                changed |= slider(ui, &mut self.fiducial_diameter, 0.5..=3.0, "Diameter");
+                changed |= slider(ui, &mut self.fiducial_spacing, 2.0..=50.0, "Spacing");
+            }
+            Generator::StencilTarget => {
+                changed |= slider(ui, &mut self.target_size, 1.0..=10.0, "Size");
+            }
+            Generator::EdgeMountSma => {
+                changed |= slider(ui, &mut self.board_thickness, 0.4..=3.2, "Board thickness");
+            }
+        }
+        ui.separator();
+        ui.checkbox(&mut self.renderer.hide_courtyard, "Hide courtyard");
+        ui.checkbox(&mut self.renderer.hide_text, "Hide text");
+        if ui.button("Zoom to fit").clicked() {
+            self.fit_pending = true;
+        }
+        changed
+    }
+}
+
+impl PreviewApp {
+    fn update_board(&mut self, ctx: &egui::Context) {
+        let Some(board) = &self.board else {
+            return;
+        };
+        egui::SidePanel::right("violations").show(ctx, |ui| {
+            ui.heading("Violations");
+            if ui.button("Run board DRC").clicked() {
+                self.violations.set_findings(board_findings(board));
+                self.board_renderer.highlight = Highlight::default();
+            }
+            ui.separator();
+            if let Some(highlight) = self.violations.ui(ui, &mut self.board_renderer.view) {
+                self.board_renderer.highlight = highlight;
+            }
+        });
+        egui::TopBottomPanel::bottom("status").show(ctx, |ui| {
+            let (components, tracks, vias) = (board.components().len(), board.tracks().len(), board.vias().len());
+            ui.label(format!("{}: {} components, {} tracks, {} vias", board.name, components, tracks, vias));
+        });
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let (response, painter) = ui.allocate_painter(ui.available_size(), egui::Sense::drag());
+            self.board_renderer.view.handle_input(&response);
+            painter.rect_filled(response.rect, 0.0, egui::Color32::from_rgb(0x00, 0x10, 0x23));
+            if self.fit_pending {
+                self.board_renderer.view.fit(&BoardRenderer::bounds(board), 40.0);
+                self.fit_pending = false;
+            }
+            self.board_renderer.render(board, &painter);
+        });
+    }
+}
+
+impl eframe::App for PreviewApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if self.board.is_some() {
+            self.update_board(ctx);
+            return;
+        }
+        let generated;
+        let mut changed = false;
+        let component = match (self.watch.as_mut(), self.loaded.as_ref()) {
+            (Some(watch), _) => {
+                changed |= watch.poll();
+                egui::SidePanel::left("definitions").show(ctx, |ui| {
+                    changed |= watch.definitions_panel(ui);
+                });
+                egui::TopBottomPanel::bottom("log").resizable(true).show(ctx, |ui| {
+                    egui::ScrollArea::vertical().stick_to_bottom(true).show(ui, |ui| {
+                        for line in &watch.log {
+                            ui.monospace(line);
+                        }
+                    });
+                });
+                ctx.request_repaint_after(WATCH_POLL_INTERVAL);
+                watch.component()
+            }
+            (None, Some(loaded)) => Some(loaded as &dyn BoardComposableObject),
+            (None, None) => {
+                egui::SidePanel::left("parameters").show(ctx, |ui| {
+                    changed |= self.parameters(ui);
+                });
+                generated = self.component();
+                Some(generated.as_ref())
+            }
+        };
+        if changed {
+            self.fit_pending = true;
+            self.violations.set_findings(Vec::new());
+            self.renderer.highlight = Highlight::default();
+        }
+        egui::SidePanel::right("violations").show(ctx, |ui| {
+            ui.heading("Violations");
+            if let Some(component) = component
+                && ui.button("Run footprint DRC").clicked()
+            {
+                self.violations.set_findings(self.drc.findings(component));
+                self.renderer.highlight = Highlight::default();
+            }
+            ui.separator();
+            if let Some(highlight) = self.violations.ui(ui, &mut self.renderer.view) {
+                self.renderer.highlight = highlight;
+            }
+        });
+        if let Some(component) = component {
+            egui::TopBottomPanel::bottom("status").show(ctx, |ui| {
+                ui.label(format!("{}:{}", component.library_name(), component.footprint_name()));
+            });
+        }
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let (response, mut painter) = ui.allocate_painter(ui.available_size(), egui::Sense::drag());
+            self.renderer.view.handle_input(&response);
+            painter.rect_filled(response.rect, 0.0, egui::Color32::from_rgb(0x00, 0x10, 0x23));
+            let Some(component) = component else {
+                return;
+            };
+            if self.fit_pending {
+                self.renderer.view.fit(&FootprintRenderer::bounds(component), 40.0);
+                self.fit_pending = false;
+            }
+            self.renderer.render(component, &mut painter);
+        });
+    }
+}
+
+fn main() -> eframe::Result {
+    let mut app = PreviewApp::default();
+    let args: Vec<String> = std::env::args().collect();
+    let path_after = |flag: &str| args.iter().position(|arg| arg == flag).and_then(|index| args.get(index + 1)).map(PathBuf::from);
+    if let Some(path) = path_after("--footprint") {
+        match KiCadFootprint::read(&path, ParseOptions::default()) {
+            Ok(footprint) => {
+                app.violations.set_findings(app.drc.findings(&footprint));
+                app.loaded = Some(footprint);
+            }
+            Err(error) => {
+                eprintln!("cannot read {}: {}", path.display(), error);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(path) = path_after("--board") {
+        match read_board_file(&path) {
+            Ok(board) => {
+                app.violations = ViolationPanel::new(board_findings(&board)).with_focus_span(BOARD_FOCUS_SPAN);
+                app.board = Some(board);
+            }
+            Err(error) => {
+                eprintln!("cannot read {}: {}", path.display(), error);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(index) = args.iter().position(|arg| arg == "--watch") {
+        let dir = args.get(index + 1).map(PathBuf::from).unwrap_or_else(|| PathBuf::from("."));
+        match WatchState::new(&dir) {
+            Ok(watch) => app.watch = Some(watch),
+            Err(error) => {
+                eprintln!("cannot watch {}: {}", dir.display(), error);
+                std::process::exit(1);
+            }
+        }
+    }
+    eframe::run_native(
+        "Footprint preview",
+        eframe::NativeOptions::default(),
+        Box::new(|_creation_context| Ok(Box::new(app))),
+    )
+}
@@ -0,0 +1,100 @@
+//! Gerber X2 attributes on copper and paste layers
+//!
+//! `cargo run -p copper-exporters --example gerber_attributes` writes the copper and paste
+//! layers of a board with one via, one SMD pad and one global fiducial and prints their
+//! file, aperture and object attribute lines. The unit tests of `gerber_export` check those
+//! lines and read every layer back, in X2 and in plain RS-274X.
+
+use copper_exporters::gerber_export::{to_copper_gerber, GerberDialect};
+use copper_exporters::stencil_export::to_paste_gerber;
+use copper_substrate::board::{Board, FabOrigin};
+use copper_substrate::prelude::*;
+use uuid::Uuid;
+
+#[derive(BoardComponent)]
+#[footprint(name = "TestPoint_Pad_1.0x0.6mm", library = "TestPoint", kind = "Connector", smt)]
+#[pad(number = "1", position = (0.0, 0.0), size = (1.0, 0.6), shape = "rect")]
+struct SmdPad(#[value] &'static str);
+
+/// 1 mm copper dot with no number, marked as a board fiducial
+struct Fiducial;
+
+impl BoardComposableObject for Fiducial {
+    fn is_smt(&self) -> bool {
+        true
+    }
+    fn is_electrical(&self) -> bool {
+        false
+    }
+    fn terminal_count(&self) -> usize {
+        0
+    }
+    fn functional_type(&self) -> FunctionalType {
+        FunctionalType::Fiducial("Board".to_string())
+    }
+    fn footprint_name(&self) -> String {
+        "Fiducial_1mm_Mask2mm".to_string()
+    }
+    fn library_name(&self) -> String {
+        "Fiducial".to_string()
+    }
+    fn bounding_box(&self) -> Rectangle {
+        Rectangle { min_x: -0.5, min_y: -0.5, max_x: 0.5, max_y: 0.5 }
+    }
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        vec![PadDescriptor {
+            number: PadNumber::default(),
+            pin_function: None,
+            pad_type: PadType::SMD,
+            shape: PadShape::Circle,
+            position: (0.0, 0.0),
+            rotation: None,
+            size: (1.0, 1.0),
+            drill_size: None,
+            drill_slot: None,
+            layers: vec!["F.Cu".into(), "F.Mask".into()],
+            roundrect_ratio: None,
+            tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+            padstack: None,
+            fab_property: Some(PadFabProperty::GlobalFiducial),
+            solder_mask_margin: None,
+            routing_hint: None,
+            uuid: Uuid::new_v4(),
+        }]
+    }
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        vec![]
+    }
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        vec![]
+    }
+    fn model_3d(&self) -> Option<Model3D> {
+        None
+    }
+}
+
+/// Attribute lines only, the part X2 adds
+fn attributes(text: &str) -> Vec<&str> {
+    text.lines().filter(|line| line.starts_with("%T")).collect()
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut board = Board::new("x2").with_rectangular_outline(20.0, 10.0);
+    board.place("TP1", SmdPad("SIG"), (5.0, 5.0), 0.0)?;
+    board.place("FID1", Fiducial, (15.0, 5.0), 0.0)?;
+    board.add_via(Via::new("GND", Point::new(10.0, 5.0), 0.6, 0.3));
+
+    for layer in [BoardLayer::front(LayerType::Copper), BoardLayer::back(LayerType::Copper)] {
+        println!("{} attributes:", layer.to_kicad_string());
+        for line in attributes(&to_copper_gerber(&board, layer, 0.01, FabOrigin::Absolute, GerberDialect::X2)) {
+            println!("  {}", line);
+        }
+    }
+    println!("F.Paste attributes:");
+    for line in attributes(&to_paste_gerber(&board, Side::Front, 0.01, FabOrigin::Absolute, GerberDialect::X2)) {
+        println!("  {}", line);
+    }
+    let plain = to_copper_gerber(&board, BoardLayer::front(LayerType::Copper), 0.01, FabOrigin::Absolute, GerberDialect::Rs274x);
+    println!("F.Cu as plain RS-274X: {} attribute lines", attributes(&plain).len());
+    Ok(())
+}
@@ -0,0 +1,54 @@
+//! Hatched fills
+//!
+//! `cargo run -p copper-exporters --example hatch_fill` cross-hatches a 10 x 10 mm square at
+//! 1 mm pitch, hatches an L-shaped polygon at 45 degrees and prints the lines, then prints
+//! the KiCad zone of a flex board's hatched ground pour and a hatched fab rectangle as
+//! footprint lines. The checks live in the unit tests of `hatch` and the KiCad exporters.
+
+use copper_exporters::kicad_board_export::to_kicad_pcb;
+use copper_exporters::kicad_pcb_export::write_graphic_element;
+use copper_substrate::board::Board;
+use copper_substrate::prelude::*;
+
+fn main() {
+    let square = vec![Point::new(0.0, 0.0), Point::new(10.0, 0.0), Point::new(10.0, 10.0), Point::new(0.0, 10.0)];
+    let lines = FillStyle::CrossHatch { pitch: 1.0, width: 0.2, angle: 0.0 }.hatch_lines(&square);
+    println!("10 x 10 square, 1 mm cross-hatch: {} lines", lines.len());
+
+    // Concave shape: lines crossing the notch split in two
+    let ell = vec![
+        Point::new(0.0, 0.0),
+        Point::new(8.0, 0.0),
+        Point::new(8.0, 3.0),
+        Point::new(3.0, 3.0),
+        Point::new(3.0, 8.0),
+        Point::new(0.0, 8.0),
+    ];
+    let lines = FillStyle::Hatch { pitch: 0.5, width: 0.1, angle: 45.0 }.hatch_lines(&ell);
+    println!("L shape, 0.5 mm hatch at 45 degrees: {} lines", lines.len());
+    for (start, end) in &lines {
+        println!("  ({:.3}, {:.3}) - ({:.3}, {:.3})", start.x, start.y, end.x, end.y);
+    }
+
+    // Hatched ground on a flex board
+    let mut board = Board::new("flex").with_rectangular_outline(20.0, 10.0);
+    let outline = vec![Point::new(1.0, 1.0), Point::new(19.0, 1.0), Point::new(19.0, 9.0), Point::new(1.0, 9.0)];
+    let ground = FillStyle::CrossHatch { pitch: 1.5, width: 0.3, angle: 45.0 };
+    board.add_zone(Zone::new("GND", &[BoardLayer::back(LayerType::Copper)], outline).with_fill(ground));
+    let pcb = to_kicad_pcb(&board);
+    println!("{}", pcb.lines().skip_while(|line| !line.contains("(zone")).take(24).collect::<Vec<_>>().join("\n"));
+
+    // Hatched fab rectangle, written as lines
+    let bounds = Rectangle { min_x: -2.0, min_y: -1.0, max_x: 2.0, max_y: 1.0 };
+    let element = GraphicElement {
+        element_type: GraphicType::filled_rectangle(&bounds, FillStyle::Hatch { pitch: 0.5, width: 0.1, angle: 0.0 }),
+        layer: LayerType::Fabrication,
+        side: Side::Front,
+        stroke: Stroke { width: 0.1, stroke_type: StrokeType::Solid },
+        uuid: uuid::Uuid::nil(),
+        role: None,
+    };
+    let mut footprint = String::new();
+    write_graphic_element(&mut footprint, &element);
+    print!("{}", footprint);
+}
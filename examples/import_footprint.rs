@@ -0,0 +1,44 @@
+//! Normalize vendor downloaded footprints to the KiCad Library Conventions
+//!
+//! `cargo run -p copper-exporters --example import_footprint [file.kicad_mod ...]`
+//! imports the given files, or the vendor footprints in examples/fixtures, prints every
+//! change made and writes the results to the current directory. Importing a result again
+//! must change nothing.
+
+use std::path::{Path, PathBuf};
+
+use copper_exporters::footprint_import::{import_footprint, import_footprint_file, NormalizeOptions};
+use copper_substrate::prelude::*;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut paths: Vec<PathBuf> = std::env::args().skip(1).map(PathBuf::from).collect();
+    if paths.is_empty() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples/fixtures");
+        paths = std::fs::read_dir(fixtures)?.map(|entry| entry.map(|entry| entry.path())).collect::<Result<_, _>>()?;
+        paths.retain(|path| path.extension().is_some_and(|extension| extension == "kicad_mod"));
+        paths.sort();
+    }
+
+    let options = NormalizeOptions::default();
+    for path in paths {
+        let imported = import_footprint_file(&path, &options)?;
+        let name = imported.footprint.footprint_name();
+        println!("{} ({} changes)", name, imported.changes.len());
+        for change in &imported.changes {
+            println!("  {}", change);
+        }
+        for token in imported.footprint.unknown_tokens() {
+            println!("  kept unmodelled token {}", token);
+        }
+
+        let normalized = imported.footprint.to_kicad_string();
+        let again = import_footprint(&normalized, &options)?;
+        if !again.changes.is_empty() {
+            return Err(format!("{} still needs {} changes after normalizing", name, again.changes.len()).into());
+        }
+        let output = format!("{}.kicad_mod", name);
+        std::fs::write(&output, normalized)?;
+        println!("  saved to {}", output);
+    }
+    Ok(())
+}
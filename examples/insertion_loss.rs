@@ -0,0 +1,45 @@
+//! Insertion loss of routed nets
+//!
+//! `cargo run -p copper-substrate --example insertion_loss` prints the closed form loss
+//! of a 5 mil, 50 ohm line next to Bogatin's rules of thumb, then the insertion loss
+//! report of a four layer board at 1 GHz and 10 GHz: an inch of track on F.Cu
+//! (microstrip), an inch on In1.Cu (stripline) and a net routed on both. The checks live
+//! in the unit tests of `insertion_loss`.
+
+use copper_substrate::board::Board;
+use copper_substrate::insertion_loss::{conductor_loss_db_per_meter, dielectric_loss_db_per_meter};
+use copper_substrate::prelude::*;
+
+const METERS_PER_INCH: f64 = 0.0254;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // 5 mil wide 1 oz copper, 50 ohm
+    let (w, t) = (5.0 * 25.4e-6, 35e-6);
+    for ghz in [1.0, 10.0] {
+        let conductor = conductor_loss_db_per_meter(w, t, 50.0, ghz * 1e9) * METERS_PER_INCH;
+        let dielectric = dielectric_loss_db_per_meter(4.0, 4.0, 0.02, ghz * 1e9) * METERS_PER_INCH;
+        println!(
+            "{:>4} GHz: conductor {:.3} dB/inch (rule of thumb {:.3}), dielectric {:.3} dB/inch (rule of thumb {:.3})",
+            ghz,
+            conductor,
+            36.0 / (5.0 * 50.0) * f64::sqrt(ghz),
+            dielectric,
+            2.3 * ghz * 0.02 * 2.0
+        );
+    }
+
+    let mut board = Board::new("loss").with_rectangular_outline(60.0, 40.0).with_stackup(Stackup::four_layer_default());
+    let (front, inner) = (BoardLayer::front(LayerType::Copper), BoardLayer::inner_copper(1));
+    board.add_track(Track::new("CLK", front, Point::new(5.0, 5.0), Point::new(30.4, 5.0), 0.3));
+    board.add_track(Track::new("DDR", inner, Point::new(5.0, 10.0), Point::new(30.4, 10.0), 0.15));
+    board.add_track(Track::new("MIX", front, Point::new(5.0, 20.0), Point::new(15.0, 20.0), 0.3));
+    board.add_track(Track::new("MIX", inner, Point::new(15.0, 20.0), Point::new(25.0, 20.0), 0.15));
+    for ghz in [1.0, 10.0] {
+        let report = board.insertion_loss_report(ghz)?;
+        print!("{}", report);
+        for net in &report.nets {
+            println!("{}: {}", net.net, net.assumptions.join("; "));
+        }
+    }
+    Ok(())
+}
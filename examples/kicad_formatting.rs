@@ -0,0 +1,21 @@
+//! KiCad file layout
+//!
+//! `cargo run -p copper-exporters --example kicad_formatting` prints an 0805 resistor
+//! exported for KiCad 8 in the compact and the canonical style, the latter laid out the
+//! way pcbnew saves files. The checks live in the unit tests of `kicad_format`.
+
+use copper_exporters::kicad_format::OutputStyle;
+use copper_exporters::kicad_pcb_export::{try_to_kicad_footprint_with_options, KiCadVersion};
+use copper_exporters::ExportOptions;
+use copper_substrate::presets;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let resistor = presets::resistor_0805("10k");
+    let compact = ExportOptions { kicad_version: KiCadVersion::V8, ..ExportOptions::default() };
+    let canonical = ExportOptions { output_style: OutputStyle::KiCadCanonical, ..compact.clone() };
+    for (name, options) in [("compact", &compact), ("canonical", &canonical)] {
+        let text = try_to_kicad_footprint_with_options(&resistor, options)?;
+        println!("--- {} ({} lines)\n{}", name, text.lines().count(), text);
+    }
+    Ok(())
+}
@@ -0,0 +1,95 @@
+//! Library release changelog
+//!
+//! `cargo run -p copper-exporters --example library_changelog` releases a small library
+//! as 1.0.0 and prints the changelog of 1.1.0 against it: one footprint added, one
+//! removed, one renamed and one modified. The checks live in the unit tests of
+//! `library_manifest`.
+
+use std::fs;
+
+use copper_exporters::library_manifest::{library_changelog, LibraryManifest, LibraryVersion, ManifestError, MANIFEST_FILE};
+use copper_exporters::FootprintLibrary;
+use copper_substrate::presets;
+use copper_substrate::prelude::*;
+
+/// A chip footprint published under another name, its value text following the name
+struct Renamed(ChipFootprint, &'static str);
+
+impl BoardComposableObject for Renamed {
+    fn is_smt(&self) -> bool {
+        true
+    }
+    fn is_electrical(&self) -> bool {
+        true
+    }
+    fn is_passive(&self) -> bool {
+        self.0.is_passive()
+    }
+    fn terminal_count(&self) -> usize {
+        2
+    }
+    fn functional_type(&self) -> FunctionalType {
+        self.0.functional_type()
+    }
+    fn footprint_name(&self) -> String {
+        self.1.to_string()
+    }
+    fn library_name(&self) -> String {
+        self.0.library_name()
+    }
+    fn bounding_box(&self) -> Rectangle {
+        self.0.bounding_box()
+    }
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        self.0.pad_descriptors()
+    }
+    fn description_context(&self) -> Option<DescriptionContext> {
+        self.0.description_context()
+    }
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        let mut texts = self.0.fp_text_elements();
+        for text in texts.iter_mut().filter(|text| text.text == self.0.footprint_name()) {
+            text.text = self.1.to_string();
+        }
+        texts
+    }
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        self.0.graphic_elements()
+    }
+    fn model_3d(&self) -> Option<Model3D> {
+        self.0.model_3d()
+    }
+    fn courtyard_margin(&self) -> f32 {
+        self.0.courtyard_margin()
+    }
+    fn generate_courtyard(&self) -> Courtyard {
+        self.0.generate_courtyard()
+    }
+}
+
+fn release_1_0() -> Result<FootprintLibrary, ManifestError> {
+    let mut library = FootprintLibrary::new("Passives").with_version(LibraryVersion::new("1.0.0")?.with_timestamp(1_700_000_000));
+    library
+        .add(presets::resistor_0603("10k"))
+        .add(presets::resistor_0805("10k"))
+        .add(presets::capacitor_0402("100n"))
+        .add(presets::capacitor_1206("10u"));
+    Ok(library)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let work = std::env::temp_dir().join("copper_library_changelog");
+    let _ = fs::remove_dir_all(&work);
+    let pretty = release_1_0()?.write_to(&work)?;
+    let written = LibraryManifest::load(&pretty.join(MANIFEST_FILE))?;
+    println!("wrote {} ({} footprints)", pretty.join(MANIFEST_FILE).display(), written.footprints.len());
+
+    let profile = FabProfile { min_silk_line_width: 0.2, ..FabProfile::standard() };
+    let mut new = FootprintLibrary::new("Passives").with_version(LibraryVersion::new("1.1.0")?);
+    new.add(presets::resistor_0603("10k"))
+        .add(presets::resistor_0805("10k").with_silk_minimums(&profile))
+        .add(Renamed(presets::capacitor_0402("100n"), "C_0402_1005Metric_Small"))
+        .add(presets::capacitor_0603("1u"));
+    print!("{}", library_changelog(&written, &new)?.to_markdown());
+    Ok(())
+}
@@ -0,0 +1,147 @@
+//! microSD socket with a card insertion envelope
+//!
+//! `cargo run -p copper-exporters --example microsd_envelope` builds a push-push microSD
+//! socket whose card sticks out of its open end when ejected. The footprint carries that
+//! space as an actuation envelope: 6 mm past the socket, plus the 2 mm of board in front
+//! of it where a finger pushes the card. Placed 2 mm in from the top edge, the envelope
+//! overhangs the board and the board still passes DRC. A resistor placed in front of the
+//! socket must then be reported. The board file and assembly drawing are written to the
+//! temp directory, with the envelope dashed in both.
+
+use copper_exporters::assembly_drawing::to_assembly_svg;
+use copper_exporters::kicad_board_export::to_kicad_pcb;
+use copper_substrate::analysis::Severity;
+use copper_substrate::board::Board;
+use copper_substrate::presets;
+use copper_substrate::prelude::*;
+use uuid::Uuid;
+
+/// Socket body, open end towards -y
+const WIDTH: f32 = 12.0;
+const LENGTH: f32 = 11.5;
+/// Card width, and how far it sticks out of the body when ejected
+const CARD_WIDTH: f32 = 11.0;
+const CARD_OUT: f32 = 6.0;
+/// Room in front of the socket for a finger
+const FINGER: f32 = 2.0;
+
+struct MicroSdSocket;
+
+impl MicroSdSocket {
+    fn pad(number: usize, position: (f32, f32), size: (f32, f32)) -> PadDescriptor {
+        PadDescriptor {
+            number: number.to_string().into(),
+            pin_function: None,
+            pad_type: PadType::SMD,
+            shape: PadShape::Rect,
+            position,
+            rotation: None,
+            size,
+            drill_size: None,
+            drill_slot: None,
+            layers: vec!["F.Cu".into(), "F.Paste".into(), "F.Mask".into()],
+            roundrect_ratio: None,
+            tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+            padstack: None,
+            fab_property: None,
+            solder_mask_margin: None,
+            routing_hint: None,
+            uuid: Uuid::new_v4(),
+        }
+    }
+}
+
+impl BoardComposableObject for MicroSdSocket {
+    fn is_smt(&self) -> bool {
+        true
+    }
+
+    fn is_electrical(&self) -> bool {
+        true
+    }
+
+    fn terminal_count(&self) -> usize {
+        10
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        FunctionalType::Connector("microSD".to_string())
+    }
+
+    fn footprint_name(&self) -> String {
+        "microSD_PushPush_12x11.5mm".to_string()
+    }
+
+    fn library_name(&self) -> String {
+        "Connector_Card".to_string()
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        Rectangle { min_x: -WIDTH / 2.0, min_y: -LENGTH / 2.0, max_x: WIDTH / 2.0, max_y: LENGTH / 2.0 }
+    }
+
+    /// Eight contacts at the closed end, a shell tab on either side
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        let mut pads: Vec<PadDescriptor> = (0..8).map(|pin| Self::pad(pin + 1, (-3.85 + pin as f32 * 1.1, LENGTH / 2.0 - 0.4), (0.7, 1.4))).collect();
+        pads.push(Self::pad(9, (-WIDTH / 2.0 + 0.6, -1.0), (1.2, 2.0)));
+        pads.push(Self::pad(10, (WIDTH / 2.0 - 0.6, -1.0), (1.2, 2.0)));
+        pads
+    }
+
+    fn description(&self) -> Option<String> {
+        Some("microSD push-push socket, the card sticks out 6 mm when ejected".to_string())
+    }
+
+    fn tags(&self) -> Option<String> {
+        Some("microSD card socket".to_string())
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        vec![FpText::new(FpTextType::Reference, "REF**", (0.0, LENGTH / 2.0 + 1.0), "F.SilkS")]
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        Vec::new()
+    }
+
+    fn model_3d(&self) -> Option<Model3D> {
+        None
+    }
+
+    fn actuation_envelope(&self) -> Option<ActuationEnvelope> {
+        let card = Rectangle { min_x: -CARD_WIDTH / 2.0, min_y: -LENGTH / 2.0 - FINGER - CARD_OUT, max_x: CARD_WIDTH / 2.0, max_y: -LENGTH / 2.0 };
+        Some(ActuationEnvelope::rect("card insertion", &card))
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // The open end 2 mm in from the top edge, so the ejected card hangs 4 mm over it
+    let mut board = Board::new("microsd").with_rectangular_outline(40.0, 30.0);
+    board.place("J1", MicroSdSocket, (20.0, FINGER + LENGTH / 2.0), 0.0)?;
+    board.place("R1", presets::resistor_0603("10k"), (32.0, 6.0), 0.0)?;
+    let errors: Vec<_> = board.drc().into_iter().filter(|finding| finding.severity == Severity::Error).collect();
+    if !errors.is_empty() {
+        return Err(format!("an envelope over the board edge must pass DRC: {:?}", errors).into());
+    }
+
+    let directory = std::env::temp_dir();
+    let pcb = to_kicad_pcb(&board);
+    let svg = to_assembly_svg(&board);
+    if !pcb.contains("(type dash)") || !pcb.contains("(layer \"Dwgs.User\")") || !svg.contains("class=\"envelope\"") {
+        return Err("the envelope must be drawn dashed in the board file and the assembly drawing".into());
+    }
+    std::fs::write(directory.join("microsd.kicad_pcb"), pcb)?;
+    std::fs::write(directory.join("microsd_assembly.svg"), svg)?;
+    println!("saved microsd.kicad_pcb and microsd_assembly.svg in {}", directory.display());
+
+    // A resistor right in front of the socket is in the card's way
+    board.place("R2", presets::resistor_0603("10k"), (20.0, 1.0), 0.0)?;
+    let intrusions = board.envelope_intrusions();
+    for intrusion in &intrusions {
+        println!("{}", intrusion);
+    }
+    if intrusions.len() != 1 || intrusions[0].intruder != "R2" {
+        return Err(format!("R2 must be reported inside the card envelope: {:?}", intrusions).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,103 @@
+//! Non-plated holes and slots
+//!
+//! `cargo run -p copper-exporters --example npth_slots` checks NPTH holes for mounting and
+//! alignment. A 1.2 x 2.4 mm alignment slot must export without copper layers as an oval
+//! drill and read back as the same slot, and pass the footprint DRC. On a board with an M3
+//! hole, the slot turned 90 degrees, a DIP and a via, the NPTH drill file must hold
+//! exactly the two holes, the slot routed across its length, and the PTH file only the
+//! DIP pins and the via. A track passing 0.05 mm from the hole must not trip the copper
+//! checks. The holes must stay out of the copper and paste Gerbers and the placement list,
+//! and be no terminal. A KiCad-style hole listed on `*.Cu` has no copper either, and a
+//! plated slot measures its annular ring along each side.
+
+use copper_exporters::cpl_export::cpl_entries;
+use copper_exporters::drill_export::{drill_holes, drill_tools, to_excellon, Plating};
+use copper_exporters::gerber_export::{to_copper_gerber, GerberDialect};
+use copper_exporters::kicad_footprint_parse::{KiCadFootprint, ParseOptions};
+use copper_exporters::kicad_pcb_export::try_to_kicad_footprint;
+use copper_exporters::stencil_export::to_paste_gerber;
+use copper_exporters::svg_export::to_svg;
+use copper_substrate::analysis::FootprintDrc;
+use copper_substrate::anchor::CentroidSource;
+use copper_substrate::board::{Board, FabOrigin};
+use copper_substrate::prelude::*;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let slot = MountingHole::slot(1.2, 2.4);
+    let pad = &slot.pad_descriptors()[0];
+    assert!(pad.is_npth() && !pad.has_copper());
+    assert_eq!(slot.footprint_name(), "MountingHole_Slot_1.2x2.4mm");
+    let text = try_to_kicad_footprint(&slot)?;
+    assert!(text.contains("(drill oval 1.2 2.4)"), "{}", text);
+    assert!(text.contains("(layers \"*.Mask\")"), "{}", text);
+    let parsed = KiCadFootprint::parse(&text, ParseOptions::strict())?;
+    let read = &parsed.pad_descriptors()[0];
+    assert_eq!((read.drill_size, read.drill_slot), (Some(1.2), Some((1.2, 2.4))));
+    assert!(read.is_npth() && !read.has_copper() && read.layers.iter().all(|layer| !layer.ends_with(".Cu")));
+    let violations = FootprintDrc::new(FabProfile::standard()).check(&slot);
+    assert!(violations.is_empty(), "{:#?}", violations);
+    assert_eq!(slot.pad_descriptors()[0].slot_ends(), Some((Point::new(0.0, -0.6), Point::new(0.0, 0.6))));
+
+    let svg = to_svg(&slot, 0.01);
+    assert!(svg.contains("class=\"drill npth\"") && !svg.contains("class=\"pad\""), "{}", svg);
+
+    let mut board = Board::new("holes").with_rectangular_outline(40.0, 30.0);
+    let m3 = MountingHole::for_screw("M3").ok_or("M3 is a standard screw")?;
+    assert_eq!(m3.footprint_name(), "MountingHole_3.2mm_M3");
+    board.place("H1", m3, (5.0, 5.0), 0.0)?;
+    board.place("H2", slot, (35.0, 5.0), 90.0)?;
+    board.place("U1", Dip::new(8).ok_or("DIP-8 exists")?, (20.0, 18.0), 0.0)?;
+    board.add_via(Via::new("GND", Point::new(30.0, 25.0), 0.6, 0.3));
+    // Passes 0.05 mm from the M3 hole, which would be far too close to copper
+    board.add_track(Track::new("GND", BoardLayer::front(LayerType::Copper), Point::new(6.75, 0.5), Point::new(6.75, 25.0), 0.2));
+    let violations = board.routing_violations();
+    assert!(violations.is_empty(), "{:#?}", violations);
+
+    let npth = to_excellon(&board, Plating::NonPlated, FabOrigin::Absolute);
+    println!("{}", npth);
+    assert!(npth.contains("TF.FileFunction,NonPlated,1,2,NPTH"));
+    assert!(npth.contains("T1C1.200") && npth.contains("T2C3.200"), "{}", npth);
+    assert!(npth.contains("; T1 0 holes, 1 slots") && npth.contains("; T2 1 holes, 0 slots"));
+    // Turned 90 degrees, the 2.4 mm slot lies along X
+    assert!(npth.contains("G00X34.4Y-5\nM15\nG01X35.6Y-5\nM16\nG05\n"), "{}", npth);
+    assert!(npth.contains("\nX5Y-5\n"));
+    let holes = drill_holes(&board, Plating::NonPlated);
+    assert_eq!(holes.iter().map(|hole| hole.source.as_str()).collect::<Vec<_>>(), ["H1.", "H2."]);
+
+    let pth = to_excellon(&board, Plating::Plated, FabOrigin::Absolute);
+    let plated = drill_holes(&board, Plating::Plated);
+    assert_eq!(plated.len(), 9);
+    assert!(plated.iter().all(|hole| hole.source.starts_with("U1.") || hole.source == "via"));
+    let tools = drill_tools(&plated);
+    assert_eq!(tools.iter().map(|tool| tool.holes).sum::<usize>(), 9);
+    assert!(!pth.contains("M15") && pth.contains("TF.FileFunction,Plated,1,2,PTH"));
+
+    let copper = to_copper_gerber(&board, BoardLayer::front(LayerType::Copper), 0.01, FabOrigin::Absolute, GerberDialect::X2);
+    assert!(!copper.contains("%TO.C,H1*%") && !copper.contains("%TO.C,H2*%"));
+    assert!(copper.contains("%TO.C,U1*%"));
+    let paste = to_paste_gerber(&board, Side::Front, 0.01, FabOrigin::Absolute, GerberDialect::X2);
+    assert!(!paste.contains("%TO.C,H"));
+    let placed: Vec<String> = cpl_entries(&board, CentroidSource::BodyBounds).into_iter().map(|entry| entry.reference).collect();
+    assert_eq!(placed, ["U1"]);
+    assert!(board.pad_outlines(BoardLayer::front(LayerType::Copper), 0.01).iter().all(|(name, _)| name.starts_with("U1.")));
+    assert_eq!(MountingHole::new(2.0).terminal_count(), 0);
+
+    // KiCad writes its holes on every copper layer, with no copper around them
+    let kicad = "(footprint \"MountingHole_2.2mm_M2\" (layer \"F.Cu\")\n\t(pad \"\" np_thru_hole circle (at 0 0) (size 2.2 2.2) \
+                 (drill 2.2) (layers \"*.Cu\" \"*.Mask\"))\n)\n";
+    let hole = KiCadFootprint::parse(kicad, ParseOptions::default())?;
+    assert!(!hole.pad_descriptors()[0].has_copper());
+
+    // A plated slot keeps 0.4 mm of copper on its narrow sides, 0.3 mm at its ends
+    let plated_slot = PadDescriptor {
+        pad_type: PadType::ThroughHole,
+        shape: PadShape::Oval,
+        size: (2.0, 3.0),
+        drill_size: Some(1.2),
+        drill_slot: Some((1.2, 2.4)),
+        layers: PadLayers::tht(),
+        ..pad.clone()
+    };
+    assert!((plated_slot.annular_ring(PadLayerClass::Front).unwrap_or_default() - 0.3).abs() < 1e-5);
+    Ok(())
+}
@@ -0,0 +1,37 @@
+//! Occupancy grid of a rotated chip
+//!
+//! `cargo run -p copper-exporters --example occupancy_grid` places an 0805 resistor rotated
+//! by 30 degrees on a small board, rasterizes the front side at 0.25 mm and 0.1 mm and
+//! saves both grids as PNG and JSON in the system temp directory. The cell counts are
+//! checked by the unit tests of `occupancy`.
+
+use copper_exporters::occupancy_export::{occupancy_to_json, occupancy_to_png};
+use copper_substrate::board::Board;
+use copper_substrate::geometry::polygon_area;
+use copper_substrate::presets;
+use copper_substrate::prelude::*;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut board = Board::new("occupancy").with_rectangular_outline(20.0, 20.0);
+    board.place("R1", presets::resistor_0805("10k"), (10.03, 10.07), 30.0)?;
+    let (_, courtyard) = board.courtyard_polygons(Side::Front).remove(0);
+    let directory = std::env::temp_dir();
+
+    for resolution in [0.25, 0.1] {
+        let grid = board.occupancy_grid(resolution, Side::Front);
+        let occupied = grid.count(Occupancy::Occupied);
+        println!(
+            "{} mm: {} of {} cells occupied, {:.3} mm² courtyard in {:.3} mm² of cells",
+            resolution,
+            occupied,
+            grid.columns * grid.rows,
+            polygon_area(&courtyard),
+            occupied as f32 * resolution * resolution
+        );
+        let (png, json) = (directory.join(format!("occupancy_{}mm.png", resolution)), directory.join(format!("occupancy_{}mm.json", resolution)));
+        std::fs::write(&png, occupancy_to_png(&grid)?)?;
+        std::fs::write(&json, serde_json::to_string(&occupancy_to_json(&grid))?)?;
+        println!("saved {} and {}", png.display(), json.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,51 @@
+//! Package outline library
+//!
+//! `cargo run -p copper-substrate --example package_outlines` lists the built-in outlines,
+//! builds SOICs from their JEDEC codes, prints what a few mistyped codes report, and loads
+//! a fab's own outline from TOML. The datasheet cross-checks and the comparison with
+//! hand-typed SOICs live in the unit tests of `package_outlines` and `soic`.
+
+use copper_substrate::prelude::*;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let library = OutlineLibrary::builtin();
+    for code in library.codes() {
+        let outline = library.get(code)?;
+        println!(
+            "{:<14} {:?} {:?} pins, body {} x {} x {}, pitch {}, span {}",
+            code, outline.family, outline.pins, outline.body_length, outline.body_width, outline.height, outline.pitch, outline.lead_span
+        );
+    }
+
+    for (code, pins) in [("MS-012-AA", 8), ("MS-013-AE", 28)] {
+        let soic = Soic::from_jedec(code, pins)?;
+        println!("{}: {}", soic.footprint_name(), soic.description().unwrap_or_default());
+    }
+
+    // Mistakes
+    for (code, pins) in [("MS-012-AZ", 8), ("ms-012-aa", 8), ("MS-012-AA", 14), ("TO-236-AB", 3), ("XYZ", 8)] {
+        if let Err(error) = Soic::from_jedec(code, pins) {
+            println!("{} / {} pins: {}", code, pins, error);
+        }
+    }
+
+    // A fab's own outline from TOML
+    let library = library.with_toml(
+        r#"
+        [[outline]]
+        code = "SOIC-8-EP-VENDOR"
+        family = "soic"
+        pins = [8]
+        body_length = 4.9
+        body_width = 3.9
+        height = 1.7
+        pitch = 1.27
+        lead_span = 6.0
+        lead_length = 0.8
+        lead_width = 0.42
+        "#,
+    )?;
+    let custom = Soic::from_outline(library.get("SOIC-8-EP-VENDOR")?, 8)?;
+    println!("custom: {}", custom.description().unwrap_or_default());
+    Ok(())
+}
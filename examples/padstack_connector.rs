@@ -0,0 +1,147 @@
+//! Through-hole connector with a reduced inner annulus
+//!
+//! `cargo run -p copper-exporters --example padstack_connector` builds a 1x4 pin header
+//! whose pads are 1.7 mm on the outer layers and 1.3 mm on inner layers, checks every
+//! layer's annular ring against the standard fab profile, and writes the footprint for
+//! KiCad 9 and KiCad 7. The KiCad 9 file must carry the padstack and read back with it;
+//! the KiCad 7 file must fall back to the outer pads with one warning per pad.
+
+use copper_exporters::kicad_footprint_parse::{KiCadFootprint, ParseOptions};
+use copper_exporters::kicad_pcb_export::{to_kicad_footprint_for, version_warnings, KiCadVersion};
+use copper_substrate::prelude::*;
+use uuid::Uuid;
+
+const PITCH: f32 = 2.54;
+const DRILL: f32 = 1.0;
+
+struct PinHeader {
+    pins: usize,
+    inner_pad: f32,
+}
+
+impl BoardComposableObject for PinHeader {
+    fn is_smt(&self) -> bool {
+        false
+    }
+
+    fn is_electrical(&self) -> bool {
+        true
+    }
+
+    fn terminal_count(&self) -> usize {
+        self.pins
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        FunctionalType::Connector(format!("Conn_01x{:02}", self.pins))
+    }
+
+    fn footprint_name(&self) -> String {
+        format!("PinHeader_1x{:02}_P2.54mm_Vertical_ReducedInnerAnnulus", self.pins)
+    }
+
+    fn library_name(&self) -> String {
+        "Connector_PinHeader_2.54mm".to_string()
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        let length = (self.pins - 1) as f32 * PITCH;
+        Rectangle { min_x: -1.27, min_y: -1.27, max_x: 1.27, max_y: length + 1.27 }
+    }
+
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        (0..self.pins)
+            .map(|pin| {
+                PadDescriptor {
+                    number: (pin + 1).to_string().into(),
+                    pin_function: None,
+                    pad_type: PadType::ThroughHole,
+                    shape: if pin == 0 { PadShape::Rect } else { PadShape::Oval },
+                    position: (0.0, pin as f32 * PITCH),
+                    rotation: None,
+                    size: (1.7, 1.7),
+                    drill_size: Some(DRILL),
+                    drill_slot: None,
+                    layers: vec!["*.Cu".into(), "*.Mask".into()],
+                    roundrect_ratio: None,
+                    tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+                    padstack: None,
+                    fab_property: None,
+                    solder_mask_margin: None,
+                    routing_hint: None,
+                    uuid: Uuid::new_v4(),
+                }
+                .with_padstack(PadStack::inner(PadLayerGeometry::new(PadShape::Circle, (self.inner_pad, self.inner_pad))))
+            })
+            .collect()
+    }
+
+    fn description(&self) -> Option<String> {
+        Some(format!("Through hole straight pin header, 1x{:02}, 2.54mm pitch, reduced inner layer pads", self.pins))
+    }
+
+    fn tags(&self) -> Option<String> {
+        Some("Through hole pin header THT 1x04 2.54mm single row padstack".to_string())
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        vec![FpText {
+            text_type: FpTextType::Reference,
+            text: "REF**".to_string(),
+            position: (0.0, -2.33),
+            rotation: None,
+            layer: "F.SilkS".into(),
+            uuid: Uuid::new_v4(),
+            font: FontSettings { size: (1.0, 1.0), thickness: 0.15 },
+            hidden: false,
+            justify: None,
+            role: None,
+        }]
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        Vec::new()
+    }
+
+    fn model_3d(&self) -> Option<Model3D> {
+        None
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let header = PinHeader { pins: 4, inner_pad: 1.3 };
+    let profile = FabProfile::standard();
+    for pad in header.pad_descriptors() {
+        pad.check_annular_rings(&profile)?;
+    }
+
+    // An inner pad this small leaves 0.05 mm of copper, below the standard profile
+    let too_small = PinHeader { pins: 4, inner_pad: 1.1 };
+    match too_small.pad_descriptors()[0].check_annular_rings(&profile) {
+        Err(e) => println!("rejected: {}", e),
+        Ok(()) => return Err("a 1.1 mm inner pad on a 1.0 mm drill must be rejected".into()),
+    }
+
+    let v9 = to_kicad_footprint_for(&header, KiCadVersion::V9);
+    let parsed = KiCadFootprint::parse(&v9, ParseOptions { strict: true })?;
+    let inner = parsed.pad_descriptors()[0].geometry_on(PadLayerClass::Inner);
+    if !v9.contains("(padstack") || inner.size != (1.3, 1.3) {
+        return Err("the KiCad 9 footprint lost its padstack".into());
+    }
+    println!("KiCad 9: inner pads {} x {} mm", inner.size.0, inner.size.1);
+
+    let v7 = to_kicad_footprint_for(&header, KiCadVersion::V7);
+    let warnings = version_warnings(&header, KiCadVersion::V7);
+    if v7.contains("(padstack") || warnings.len() != header.pins {
+        return Err("the KiCad 7 footprint must collapse every padstack with a warning".into());
+    }
+    for warning in &warnings {
+        println!("KiCad 7: {}", warning);
+    }
+
+    let name = header.footprint_name();
+    std::fs::write(format!("{}.kicad_mod", name), v9)?;
+    std::fs::write(format!("{}_KiCad7.kicad_mod", name), v7)?;
+    println!("saved {}.kicad_mod and {}_KiCad7.kicad_mod", name, name);
+    Ok(())
+}
@@ -0,0 +1,63 @@
+//! Parameter sweep over a footprint definition
+//!
+//! `cargo run -p copper-exporters --example parameter_sweep [sweep.toml [out_dir]]` builds
+//! the footprint family of a sweep file, by default the QFN-24 pad length sweep in
+//! examples/fixtures, prints the metrics of each variant and writes the `.pretty` library
+//! and the CSV to `out_dir`, or a directory under the system temp directory. The checks
+//! live in the unit tests of `sweep`.
+//!
+//! `--uuid-namespace <uuid or domain>` writes deterministic UUIDs derived in that
+//! organization namespace, and `--library-version <version>` mixes the version in, see
+//! `uuids`.
+
+use std::path::{Path, PathBuf};
+
+use copper_exporters::sweep::Sweep;
+use copper_exporters::uuids::{UuidMode, UuidNamespace};
+use copper_exporters::ExportOptions;
+use uuid::Uuid;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut positional = Vec::new();
+    let (mut organization, mut version) = (None, None);
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--uuid-namespace" => organization = Some(args.next().ok_or("--uuid-namespace needs a UUID or a domain")?),
+            "--library-version" => version = Some(args.next().ok_or("--library-version needs a version")?),
+            _ => positional.push(arg),
+        }
+    }
+    let mut positional = positional.into_iter();
+    let path = positional
+        .next()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples/fixtures/qfn_pad_length_sweep.toml"));
+    let out_dir = positional.next().map(PathBuf::from).unwrap_or_else(|| std::env::temp_dir().join("copper-parameter-sweep"));
+
+    let sweep = Sweep::read(&path)?;
+    println!("{}: {} variants of {}", path.display(), sweep.values.len(), sweep.parameter);
+    let mut result = sweep.run()?;
+    print!("{}", result.to_csv());
+
+    if organization.is_some() || version.is_some() {
+        let mut namespace = match organization {
+            Some(organization) => match Uuid::parse_str(&organization) {
+                Ok(uuid) => UuidNamespace::for_organization(uuid),
+                Err(_) => UuidNamespace::for_domain(&organization),
+            },
+            None => UuidNamespace::new(),
+        };
+        if let Some(version) = version {
+            namespace = namespace.with_version(&version);
+        }
+        println!("UUIDs derived in {}", namespace.organization());
+        let options = ExportOptions { uuids: UuidMode::Deterministic, uuid_namespace: namespace, ..ExportOptions::default() };
+        result.library = result.library.with_export_options(options);
+    }
+
+    std::fs::create_dir_all(&out_dir)?;
+    let (pretty, csv) = result.write_to(&out_dir)?;
+    println!("wrote {} and {}", pretty.display(), csv.display());
+    Ok(())
+}
@@ -0,0 +1,146 @@
+//! Symbol pins against footprint pads
+//!
+//! `cargo run -p copper-exporters --example pin_mapping` prints the pin mapping of an
+//! SOIC-8 against a matching symbol, a QFN-16 whose exposed pad 17 has no pin, with and
+//! without the heatsink mark, and symbols that miss a pin or name one differently than the
+//! footprint, then the pin mapping section of a library report. The checks live in the
+//! unit tests of `pin_mapping`, the .kicad_mod parser and `footprint_library`.
+
+use std::collections::HashMap;
+
+use copper_exporters::footprint_library::FootprintLibrary;
+use copper_substrate::analysis::PinMappingReport;
+use copper_substrate::fixed_geometry::{FixedGeometry, FixedPadShape, PadSpec};
+use copper_substrate::prelude::*;
+
+/// Box symbol with pins numbered as given, optionally named
+#[derive(Clone)]
+struct Symbol(Vec<(&'static str, Option<&'static str>)>);
+
+impl Symbol {
+    fn numbered(count: usize) -> Self {
+        let numbers: Vec<&'static str> = (1..=count).map(|n| &*Box::leak(n.to_string().into_boxed_str())).collect();
+        Symbol(numbers.into_iter().map(|number| (number, None)).collect())
+    }
+}
+
+impl ElectricalComponent for Symbol {
+    fn pins(&self) -> Vec<Pin> {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(index, (number, name))| Pin {
+                id: index as PinId,
+                number: number.to_string(),
+                name: name.map(str::to_string),
+                position: (0.0, index as f32 * 2.54),
+                electrical_type: ElectricalType::Passive,
+            })
+            .collect()
+    }
+    fn net_connections(&self) -> HashMap<PinId, NetId> {
+        HashMap::new()
+    }
+}
+
+/// 3x3 mm QFN-16 with a 0.5 mm pitch and exposed pad 17, marked as a heatsink or not
+#[derive(Clone)]
+struct Qfn16 {
+    geometry: FixedGeometry,
+    exposed_pad: Option<PadFabProperty>,
+}
+
+impl Qfn16 {
+    fn new(exposed_pad: Option<PadFabProperty>) -> Self {
+        let pad = |number: usize, position, size| PadSpec {
+            number: number.to_string().into(),
+            position,
+            size,
+            shape: FixedPadShape::RoundRect,
+            drill: None,
+            roundrect_ratio: None,
+            layers: None,
+        };
+        let mut pads = Vec::new();
+        for i in 0..4 {
+            let offset = -0.75 + 0.5 * i as f32;
+            pads.push(pad(1 + i, (-1.45, offset), (0.8, 0.25)));
+            pads.push(pad(5 + i, (offset, 1.45), (0.25, 0.8)));
+            pads.push(pad(9 + i, (1.45, -offset), (0.8, 0.25)));
+            pads.push(pad(13 + i, (-offset, -1.45), (0.25, 0.8)));
+        }
+        pads.push(PadSpec { shape: FixedPadShape::Rect, ..pad(17, (0.0, 0.0), (1.7, 1.7)) });
+        Qfn16 { geometry: FixedGeometry { pads, ..FixedGeometry::default() }, exposed_pad }
+    }
+}
+
+impl BoardComposableObject for Qfn16 {
+    fn is_smt(&self) -> bool {
+        true
+    }
+    fn is_electrical(&self) -> bool {
+        true
+    }
+    fn terminal_count(&self) -> usize {
+        self.geometry.terminal_count()
+    }
+    fn functional_type(&self) -> FunctionalType {
+        FunctionalType::IntegratedCircuit("QFN-16".to_string())
+    }
+    fn footprint_name(&self) -> String {
+        "QFN-16-1EP_3x3mm_P0.5mm_EP1.7x1.7mm".to_string()
+    }
+    fn library_name(&self) -> String {
+        "Package_DFN_QFN".to_string()
+    }
+    fn bounding_box(&self) -> Rectangle {
+        self.computed_bounding_box()
+    }
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        let mut pads = self.geometry.pad_descriptors();
+        for pad in pads.iter_mut().filter(|pad| pad.number == "17") {
+            pad.fab_property = self.exposed_pad;
+        }
+        pads
+    }
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        vec![]
+    }
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        vec![]
+    }
+    fn model_3d(&self) -> Option<Model3D> {
+        None
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let soic = Soic::from_jedec("MS-012-AA", 8)?;
+    println!("SOIC-8 with an 8 pin symbol: {}", PinMappingReport::check(&Symbol::numbered(8), &soic).summary().trim_end());
+
+    // The exposed pad carries no pin; allowed only once it is marked as a heatsink
+    let qfn = Qfn16::new(Some(PadFabProperty::Heatsink));
+    println!("QFN-16 with a heatsink pad: {}", PinMappingReport::check(&Symbol::numbered(16), &qfn).summary().trim_end());
+    let unmarked = PinMappingReport::check(&Symbol::numbered(16), &Qfn16::new(None));
+    println!("QFN-16 with an unmarked exposed pad: {}", unmarked.summary().trim_end());
+
+    // Pin 4 left off the symbol and a ninth pin the SOIC doesn't have
+    let mut shifted = Symbol::numbered(9);
+    shifted.0.remove(3);
+    print!("SOIC-8 with pin 4 moved to 9:\n{}", PinMappingReport::check(&shifted, &soic));
+
+    // Names are compared with pad pin functions, ignoring case, and only warned about
+    let mut pads = soic.pad_descriptors();
+    pads[0].pin_function = Some("GND".to_string());
+    pads[1].pin_function = Some("vcc".to_string());
+    let mut named = Symbol::numbered(8);
+    named.0[0].1 = Some("VDD");
+    named.0[1].1 = Some("VCC");
+    print!("SOIC-8 with named pins:\n{}", PinMappingReport::for_pins(&named.pins(), &pads, "SOIC-8"));
+
+    let mut library = FootprintLibrary::new("Pin_Mapping");
+    library.add_with_symbol(soic.clone(), Symbol::numbered(8)).add_with_symbol(qfn, Symbol::numbered(16)).add(soic);
+    let report = library.report();
+    println!("{}", &report[report.find("## Pin mapping").unwrap_or_default()..].trim_end());
+    Ok(())
+}
@@ -0,0 +1,173 @@
+//! TO-252 and TO-263 power package footprints
+//!
+//! `cargo run -p copper-exporters --example power_packages` checks TO-252-2 against
+//! KiCad's Package_TO_SOT_SMD:TO-252-2: name, pad numbering, positions, sizes and corner
+//! ratios, and courtyard. Its tab must print through paste windows rather than in full.
+//!
+//! Then every variant is generated with both tab numberings and with thermal vias. Each
+//! must keep its silkscreen off the copper and its pads inside the courtyard. The spacing
+//! report must only find gaps between different pins, so a tab sharing a lead's number
+//! and its vias count as one pin. Each must also read back from its .kicad_mod with every
+//! pad of the tab's number, and with pads of one number not taken for jumpers.
+
+use copper_exporters::kicad_footprint_parse::{KiCadFootprint, ParseOptions};
+use copper_exporters::kicad_pcb_export::to_kicad_footprint;
+use copper_substrate::analysis::{SpacingOptions, SpacingReport};
+use copper_substrate::dpak::DPAK_VARIANTS;
+use copper_substrate::prelude::*;
+use copper_substrate::thermal_vias::{ThermalViaError, ThermalViaOptions};
+
+/// Pad number, position, size and roundrect ratio
+type PadSpec = (&'static str, (f32, f32), (f32, f32), f32);
+
+/// Copper pads of KiCad's TO-252-2; its paste windows aren't compared
+const KICAD_TO252_2_PADS: [PadSpec; 3] = [
+    ("1", (-4.2, -2.28), (2.2, 1.2), 0.2083),
+    ("3", (-4.2, 2.28), (2.2, 1.2), 0.2083),
+    ("2", (2.1, 0.0), (6.4, 5.8), 0.0431),
+];
+const KICAD_TO252_2_COURTYARD: Rectangle = Rectangle { min_x: -5.55, min_y: -3.5, max_x: 5.55, max_y: 3.5 };
+
+/// Narrowest copper gap between different pins the variants may have
+const MIN_COPPER_GAP: f32 = 0.4;
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-3
+}
+
+fn check_to252_2() -> Result<(), String> {
+    let dpak = Dpak::standard("TO-252", 2).ok_or("TO-252-2 is a standard variant")?;
+    if dpak.footprint_name() != "TO-252-2" {
+        return Err(format!("TO-252-2 must follow KiCad's naming, got {}", dpak.footprint_name()));
+    }
+    let pads = dpak.pad_descriptors();
+    let copper: Vec<&PadDescriptor> = pads.iter().filter(|pad| pad.has_layer("F.Cu")).collect();
+    if copper.len() != KICAD_TO252_2_PADS.len() {
+        return Err(format!("TO-252-2 has 3 copper pads, got {}", copper.len()));
+    }
+    for (pad, (number, position, size, ratio)) in copper.iter().zip(KICAD_TO252_2_PADS) {
+        let matches = pad.number == number
+            && close(pad.position.0, position.0)
+            && close(pad.position.1, position.1)
+            && close(pad.size.0, size.0)
+            && close(pad.size.1, size.1)
+            && pad.roundrect_ratio.is_some_and(|own| close(own, ratio));
+        if !matches {
+            return Err(format!(
+                "pad {} at {:?} size {:?} ratio {:?}, KiCad has {} at {:?} size {:?} ratio {}",
+                pad.number, pad.position, pad.size, pad.roundrect_ratio, number, position, size, ratio
+            ));
+        }
+    }
+    let courtyard = dpak.generate_courtyard().bounds;
+    let courtyard_matches = close(courtyard.min_x, KICAD_TO252_2_COURTYARD.min_x)
+        && close(courtyard.min_y, KICAD_TO252_2_COURTYARD.min_y)
+        && close(courtyard.max_x, KICAD_TO252_2_COURTYARD.max_x)
+        && close(courtyard.max_y, KICAD_TO252_2_COURTYARD.max_y);
+    if !courtyard_matches {
+        return Err(format!("courtyard {:?}, KiCad's is {:?}", courtyard, KICAD_TO252_2_COURTYARD));
+    }
+    let windows = pads.iter().filter(|pad| pad.number.is_empty() && pad.has_layer("F.Paste")).count();
+    if copper[2].has_layer("F.Paste") || windows < 2 {
+        return Err(format!("the tab must print through windows, not in full; {} windows", windows));
+    }
+    println!("{}: {}", dpak.footprint_name(), dpak.description().unwrap_or_default());
+    Ok(())
+}
+
+/// Silkscreen clear of copper, pads inside the courtyard, spacing only between different
+/// pins, and the same pads read back
+fn check(dpak: &Dpak) -> Result<(), Box<dyn std::error::Error>> {
+    let name = dpak.footprint_name();
+    let pads = dpak.pad_descriptors();
+    let copper: Vec<Rectangle> = pads.iter().filter(|pad| pad.has_layer("F.Cu")).map(|pad| pad.outline_aabb()).collect();
+    let courtyard = dpak.generate_courtyard().bounds;
+    for pad in &copper {
+        let inside = pad.min_x >= courtyard.min_x && pad.max_x <= courtyard.max_x && pad.min_y >= courtyard.min_y && pad.max_y <= courtyard.max_y;
+        if !inside {
+            return Err(format!("{}: pad {:?} sticks out of the courtyard {:?}", name, pad, courtyard).into());
+        }
+    }
+    for element in dpak.graphic_elements().iter().filter(|element| element.layer == LayerType::SilkScreen) {
+        let GraphicType::Line { start, end } = element.element_type else {
+            return Err(format!("{}: unexpected silkscreen {:?}", name, element.element_type).into());
+        };
+        let margin = element.stroke.width / 2.0;
+        for step in 0..=50 {
+            let t = step as f32 / 50.0;
+            let (x, y) = (start.0 + (end.0 - start.0) * t, start.1 + (end.1 - start.1) * t);
+            if copper.iter().any(|pad| x > pad.min_x - margin && x < pad.max_x + margin && y > pad.min_y - margin && y < pad.max_y + margin) {
+                return Err(format!("{}: silkscreen at ({:.2}, {:.2}) touches a pad", name, x, y).into());
+            }
+        }
+    }
+
+    let spacing = SpacingReport::for_component(dpak, SpacingOptions::default());
+    let gap = spacing.copper.ok_or("there are different pins to space")?;
+    if gap.pad_a == gap.pad_b || gap.gap < MIN_COPPER_GAP {
+        return Err(format!("{}: {:.3} mm between pads {} and {}", name, gap.gap, gap.pad_a, gap.pad_b).into());
+    }
+
+    let tab = dpak.tab_pin();
+    let text = to_kicad_footprint(dpak);
+    if !text.contains("(duplicate_pad_numbers_are_jumpers no)") {
+        return Err(format!("{}: pads sharing the tab's number must not be jumpers", name).into());
+    }
+    let parsed = KiCadFootprint::parse(&text, ParseOptions::strict())?;
+    let tab_pads = |pads: &[PadDescriptor]| pads.iter().filter(|pad| pad.number == tab).count();
+    if parsed.footprint_name() != name
+        || parsed.terminal_count() != dpak.terminal_count()
+        || tab_pads(&parsed.pad_descriptors()) != tab_pads(&pads)
+    {
+        return Err(format!("{} must read back with all {} pads numbered {}", name, tab_pads(&pads), tab).into());
+    }
+    println!(
+        "  {:<32} {} pins, tab {} on {} pads, copper gap {:.2} mm ({}/{}), courtyard {:.2} x {:.2} mm",
+        name,
+        dpak.terminal_count(),
+        tab,
+        tab_pads(&pads),
+        gap.gap,
+        gap.pad_a,
+        gap.pad_b,
+        courtyard.max_x - courtyard.min_x,
+        courtyard.max_y - courtyard.min_y
+    );
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    check_to252_2()?;
+
+    let mut checked = 0;
+    for variant in DPAK_VARIANTS {
+        let dpak = Dpak::standard(variant.package, variant.leads).ok_or("every variant is standard")?;
+        for numbering in [TabNumber::Center, TabNumber::Next] {
+            let dpak = dpak.clone().with_tab_number(numbering);
+            check(&dpak)?;
+            check(&dpak.with_thermal_vias(ThermalViaOptions::default(), FabProfile::standard())?)?;
+            checked += 2;
+            if variant.leads == 2 {
+                break;
+            }
+        }
+    }
+
+    let mosfet = Dpak::standard("TO-252", 3).ok_or("TO-252-3 is a standard variant")?;
+    if mosfet.footprint_name() != "TO-252-3_TabPin2" || mosfet.terminal_count() != 3 {
+        return Err("by default the tab is the middle lead's pin, the drain".into());
+    }
+    let separate = mosfet.clone().with_tab_number(TabNumber::Next);
+    if separate.footprint_name() != "TO-252-3_TabPin4" || separate.terminal_count() != 4 {
+        return Err("a separately numbered tab is pin 4".into());
+    }
+    let regulator = Dpak::standard("TO-263", 5).ok_or("TO-263-5 is a standard variant")?;
+    println!("{}: {}", regulator.footprint_name(), regulator.description().unwrap_or_default());
+
+    match mosfet.with_thermal_vias(ThermalViaOptions::new(0.2, 0.45), FabProfile::standard()) {
+        Err(ThermalViaError::DrillTooSmall { .. }) => {}
+        other => return Err(format!("0.2 mm vias are too small for a standard fab, got {:?}", other.map(|dpak| dpak.footprint_name())).into()),
+    }
+    println!("checked {} power package footprints", checked);
+    Ok(())
+}
@@ -44,34 +44,48 @@ impl BoardComposableObject for SMTResistor0805 {
     fn pad_descriptors(&self) -> Vec<PadDescriptor> {
         vec![
             PadDescriptor {
-                number: "1".to_string(),
+                number: "1".into(),
+                pin_function: None,
                 pad_type: PadType::SMD,
                 shape: PadShape::RoundRect,
                 position: (-0.95, 0.0),
+                rotation: None,
                 size: (1.0, 1.45),
                 drill_size: None,
-                layers: vec!["F.Cu".to_string(), "F.Mask".to_string(), "F.Paste".to_string()],
+                drill_slot: None,
+                layers: PadLayers::smd_front(),
                 roundrect_ratio: Some(0.25),
                 tenting: TentingSettings {
                     front: TentingType::None,
                     back: TentingType::None,
                 },
-                uuid: Uuid::new_v4().to_string(),
+                padstack: None,
+                fab_property: None,
+                solder_mask_margin: None,
+                routing_hint: None,
+                uuid: Uuid::new_v4(),
             },
             PadDescriptor {
-                number: "2".to_string(),
+                number: "2".into(),
+                pin_function: None,
                 pad_type: PadType::SMD,
                 shape: PadShape::RoundRect,
                 position: (0.95, 0.0),
+                rotation: None,
                 size: (1.0, 1.45),
                 drill_size: None,
-                layers: vec!["F.Cu".to_string(), "F.Mask".to_string(), "F.Paste".to_string()],
+                drill_slot: None,
+                layers: PadLayers::smd_front(),
                 roundrect_ratio: Some(0.25),
                 tenting: TentingSettings {
                     front: TentingType::None,
                     back: TentingType::None,
                 },
-                uuid: Uuid::new_v4().to_string(),
+                padstack: None,
+                fab_property: None,
+                solder_mask_margin: None,
+                routing_hint: None,
+                uuid: Uuid::new_v4(),
             },
         ]
     }
@@ -91,36 +105,45 @@ impl BoardComposableObject for SMTResistor0805 {
                 text: "REF**".to_string(),
                 position: (0.0, -1.16),
                 rotation: None,
-                layer: "F.SilkS".to_string(),
-                uuid: Uuid::new_v4().to_string(),
+                layer: "F.SilkS".into(),
+                uuid: Uuid::new_v4(),
                 font: FontSettings {
                     size: (1.0, 1.0),
                     thickness: 0.15,
                 },
+                hidden: false,
+                justify: None,
+                role: None,
             },
             FpText {
                 text_type: FpTextType::Value,
                 text: "R_0805_2012Metric".to_string(),
                 position: (0.0, 1.16),
                 rotation: None,
-                layer: "F.Fab".to_string(),
-                uuid: Uuid::new_v4().to_string(),
+                layer: "F.Fab".into(),
+                uuid: Uuid::new_v4(),
                 font: FontSettings {
                     size: (1.0, 1.0),
                     thickness: 0.15,
                 },
+                hidden: false,
+                justify: None,
+                role: None,
             },
             FpText {
                 text_type: FpTextType::User,
                 text: "${REFERENCE}".to_string(),
                 position: (0.0, 0.0),
                 rotation: None,
-                layer: "F.Fab".to_string(),
-                uuid: Uuid::new_v4().to_string(),
+                layer: "F.Fab".into(),
+                uuid: Uuid::new_v4(),
                 font: FontSettings {
                     size: (0.25, 0.25),
                     thickness: 0.04,
                 },
+                hidden: false,
+                justify: None,
+                role: None,
             },
         ]
     }
@@ -150,12 +173,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
     
     // Generate the footprint
-    let footprint_content = copper_exporters::to_kicad_footprint(&resistor);
+    let footprint_content = copper_exporters::try_to_kicad_footprint(&resistor)?;
     
     // Write to file
     std::fs::write("R_0805_2012Metric.kicad_mod", footprint_content)?;
     
     println!("Footprint saved to R_0805_2012Metric.kicad_mod");
+
+    // Matching schematic symbol, pre-linked to Resistor_SMD:R_0805_2012Metric
+    let symbol_content = copper_exporters::kicad_sym_export::to_kicad_symbol(&resistor);
+    std::fs::write("R_0805_2012Metric.kicad_sym", symbol_content)?;
+
+    println!("Symbol saved to R_0805_2012Metric.kicad_sym");
     
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,49 @@
+//! Shipping 3D models with a generated library
+//!
+//! `cargo run -p copper-exporters --example ship_models` writes a two footprint library
+//! whose models come from a local directory instead of KiCad's model library. The source
+//! directory has the resistor's model as both STEP and WRL and nothing for the capacitor,
+//! so the STEP file is copied into `Demo.3dshapes/`, the resistor's model path is rewritten
+//! to `${KIPRJMOD}` with its offset, scale and rotation untouched, and the capacitor is
+//! reported missing with its original path left in place.
+
+use std::fs;
+
+use copper_exporters::library_models::LibraryModels;
+use copper_exporters::FootprintLibrary;
+use copper_substrate::presets;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let work = std::env::temp_dir().join("copper_ship_models");
+    let _ = fs::remove_dir_all(&work);
+    let source = work.join("models").join("Resistor_SMD.3dshapes");
+    fs::create_dir_all(&source)?;
+    fs::write(source.join("R_0603_1608Metric.step"), "ISO-10303-21;\nEND-ISO-10303-21;\n")?;
+    fs::write(source.join("R_0603_1608Metric.wrl"), "#VRML V2.0 utf8\n")?;
+
+    let mut library = FootprintLibrary::new("Demo").with_models(LibraryModels::from_dir(&work.join("models")));
+    library.add(presets::resistor_0603("10k")).add(presets::capacitor_1206("1u"));
+    let report = library.model_report();
+    print!("{}", report);
+
+    let project = work.join("project");
+    let pretty = library.write_to(&project)?;
+    let shipped = project.join("Demo.3dshapes");
+    let copied: Vec<_> = fs::read_dir(&shipped)?.map(|entry| entry.map(|e| e.file_name())).collect::<Result<_, _>>()?;
+    println!("{}: {:?}", shipped.display(), copied);
+    if copied != ["R_0603_1608Metric.step"] || report.missing.len() != 1 {
+        return Err("only the resistor's STEP model should be shipped".into());
+    }
+
+    let resistor = fs::read_to_string(pretty.join("R_0603_1608Metric.kicad_mod"))?;
+    let capacitor = fs::read_to_string(pretty.join("C_1206_3216Metric.kicad_mod"))?;
+    if !resistor.contains("(model \"${KIPRJMOD}/Demo.3dshapes/R_0603_1608Metric.step\"\n\t\t(offset") {
+        return Err("the resistor's model path must point at the shipped file".into());
+    }
+    if !capacitor.contains("(model \"${KICAD9_3DMODEL_DIR}/Capacitor_SMD.3dshapes/C_1206_3216Metric.wrl\"") {
+        return Err("a missing model keeps its original path".into());
+    }
+    println!("{}", resistor.lines().skip_while(|line| !line.contains("(model")).take(4).collect::<Vec<_>>().join("\n"));
+    fs::remove_dir_all(&work)?;
+    Ok(())
+}
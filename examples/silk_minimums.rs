@@ -0,0 +1,156 @@
+//! Silkscreen minimums of a fab profile
+//!
+//! `cargo run -p copper-exporters --example silk_minimums` checks a test point drawn with
+//! a 0.1 mm silkscreen outline and 0.4 mm reference text against a fab printing 0.15 mm
+//! lines and 0.8 mm text. The footprint DRC must report exactly those two, and the same
+//! for the part placed on a board. Raising the silkscreen to the minimums must list both
+//! changes and pass the check again. It must leave every position, the Fab outline and
+//! the hidden value text as they were, and the export must carry the new sizes.
+
+use copper_exporters::kicad_pcb_export::try_to_kicad_footprint;
+use copper_substrate::analysis::{FootprintDrc, Violation};
+use copper_substrate::board::Board;
+use copper_substrate::prelude::*;
+use uuid::Uuid;
+
+struct TestPoint;
+
+fn stroke(layer: LayerType, width: f32, bounds: Rectangle) -> GraphicElement {
+    GraphicElement {
+        element_type: GraphicType::Rectangle { bounds },
+        layer,
+        side: Side::Front,
+        stroke: Stroke { width, stroke_type: StrokeType::Solid },
+        uuid: Uuid::new_v4(),
+        role: None,
+    }
+}
+
+fn text(text_type: FpTextType, text: &str, layer: &str, hidden: bool) -> FpText {
+    FpText {
+        text_type,
+        text: text.to_string(),
+        position: (0.0, -1.5),
+        rotation: None,
+        layer: layer.into(),
+        uuid: Uuid::new_v4(),
+        font: FontSettings { size: (0.4, 0.4), thickness: 0.06 },
+        hidden,
+        justify: None,
+        role: None,
+    }
+}
+
+impl BoardComposableObject for TestPoint {
+    fn is_smt(&self) -> bool {
+        true
+    }
+
+    fn is_electrical(&self) -> bool {
+        true
+    }
+
+    fn terminal_count(&self) -> usize {
+        1
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        FunctionalType::Connector("TestPoint".to_string())
+    }
+
+    fn footprint_name(&self) -> String {
+        "TestPoint_Pad_1.5x1.5mm".to_string()
+    }
+
+    fn library_name(&self) -> String {
+        "TestPoint".to_string()
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        Rectangle { min_x: -0.75, min_y: -0.75, max_x: 0.75, max_y: 0.75 }
+    }
+
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        vec![PadDescriptor {
+            number: "1".into(),
+            pin_function: None,
+            pad_type: PadType::SMD,
+            shape: PadShape::Rect,
+            position: (0.0, 0.0),
+            rotation: None,
+            size: (1.5, 1.5),
+            drill_size: None,
+            drill_slot: None,
+            layers: vec!["F.Cu".into(), "F.Mask".into()],
+            roundrect_ratio: None,
+            tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+            padstack: None,
+            fab_property: None,
+            solder_mask_margin: None,
+            routing_hint: None,
+            uuid: Uuid::new_v4(),
+        }]
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        vec![text(FpTextType::Reference, "REF**", "F.SilkS", false), text(FpTextType::Value, "TP", "F.SilkS", true)]
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        vec![
+            stroke(LayerType::SilkScreen, 0.1, Rectangle { min_x: -1.0, min_y: -1.0, max_x: 1.0, max_y: 1.0 }),
+            stroke(LayerType::Fabrication, 0.05, Rectangle { min_x: -0.75, min_y: -0.75, max_x: 0.75, max_y: 0.75 }),
+        ]
+    }
+
+    fn model_3d(&self) -> Option<Model3D> {
+        None
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let profile = FabProfile {
+        min_silk_line_width: 0.15,
+        min_silk_text_height: 0.8,
+        min_silk_text_thickness: 0.12,
+        ..FabProfile::standard()
+    };
+    let drc = FootprintDrc::new(profile.clone());
+    let violations = drc.check(&TestPoint);
+    for violation in &violations {
+        println!("{}", violation.to_finding());
+    }
+    let rules: Vec<&str> = violations.iter().map(|violation| violation.rule()).collect();
+    assert_eq!(rules, ["silk_line_width", "silk_text_size"]);
+
+    let mut board = Board::new("silk").with_rectangular_outline(10.0, 10.0);
+    board.place("TP1", TestPoint, (5.0, 5.0), 0.0)?;
+    let findings = board.silk_size_findings(&profile);
+    assert_eq!(findings.len(), 2);
+    assert!(findings.iter().all(|finding| finding.footprint == "TP1"));
+    assert!(findings.iter().any(|finding| finding.location == Some(Point::new(5.0, 3.5))), "{:#?}", findings);
+
+    let fixed = TestPoint.with_silk_minimums(&profile);
+    let changes = fixed.changes();
+    for change in &changes {
+        println!("fixed: {}", change);
+    }
+    assert!(matches!(changes[..], [SilkChange::LineWidth { graphic: 0, .. }, SilkChange::Text { text: 0, .. }]));
+    assert!(drc.check(&fixed).is_empty(), "{:#?}", drc.check(&fixed));
+
+    // Only widths and sizes change
+    let (before, after) = (TestPoint.graphic_elements(), fixed.graphic_elements());
+    assert_eq!(after[0].element_type, before[0].element_type);
+    assert_eq!(after[0].stroke.width, 0.15);
+    assert_eq!((after[1].element_type.clone(), after[1].stroke.width), (before[1].element_type.clone(), 0.05));
+    let (before, after) = (TestPoint.fp_text_elements(), fixed.fp_text_elements());
+    assert_eq!(after[0].position, before[0].position);
+    assert_eq!((after[0].font.size, after[0].font.thickness), ((0.8, 0.8), 0.12));
+    assert_eq!((after[1].font.size, after[1].font.thickness), (before[1].font.size, before[1].font.thickness));
+
+    let exported = try_to_kicad_footprint(&fixed)?;
+    assert!(exported.contains("(width 0.15)") && exported.contains("(size 0.8 0.8)"), "{}", exported);
+    // A profile already met changes nothing
+    assert!(fixed.with_silk_minimums(&profile).changes().is_empty());
+    Ok(())
+}
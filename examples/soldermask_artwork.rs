@@ -0,0 +1,124 @@
+//! Solder mask artwork over copper
+//!
+//! `cargo run -p copper-substrate --example soldermask_artwork` builds the 3D viewer's demo
+//! board, a 50 mm square with a ground pour on the left half and five tracks on the right,
+//! with the 0805 resistor the viewer places on it, and draws F.Cu and F.Mask as the viewer's
+//! textures do. It prints the area around the resistor, '@' for copper showing through the
+//! mask, '#' for copper under it and '.' for bare board under it.
+//!
+//! From above the resistor must show exactly two exposed copper pads: the mask open in two
+//! separate patches, each over copper, at the pad centers, and closed over the pour and the
+//! tracks. The back, without any pads, has no openings.
+
+use copper_substrate::board::Board;
+use copper_substrate::prelude::*;
+use copper_substrate::presets;
+
+/// Resolution of the viewer's default textures
+const PIXELS_PER_MM: f32 = 20.0;
+
+/// Where the viewer places the resistor, between the last two tracks
+const RESISTOR_AT: (f64, f64) = (37.5, 40.0);
+
+fn demo_board() -> Result<Board, Box<dyn std::error::Error>> {
+    let top = BoardLayer::front(LayerType::Copper);
+    let mut board = Board::new("half_pour").with_rectangular_outline(50.0, 50.0);
+    let pour = vec![Point::new(0.0, 0.0), Point::new(25.0, 0.0), Point::new(25.0, 50.0), Point::new(0.0, 50.0)];
+    board.add_zone(Zone::new("GND", &[top], pour));
+    for index in 0..5 {
+        let y = 5.0 + index as f32 * 10.0;
+        board.add_track(Track::new("SIG", top, Point::new(30.0, y), Point::new(45.0, y), 0.25));
+    }
+    board.place("R1", presets::resistor_0805("10k"), RESISTOR_AT, 0.0)?;
+    Ok(board)
+}
+
+/// Separate patches of set pixels, pixels touching along a side counted together
+fn patches(artwork: &CopperArtwork) -> usize {
+    let mut seen = vec![false; artwork.width * artwork.height];
+    let mut count = 0;
+    for start in 0..seen.len() {
+        if seen[start] || !artwork.pixels()[start] {
+            continue;
+        }
+        count += 1;
+        seen[start] = true;
+        let mut stack = vec![start];
+        while let Some(index) = stack.pop() {
+            let (x, y) = (index % artwork.width, index / artwork.width);
+            let neighbours = [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)];
+            for (x, y) in neighbours {
+                if artwork.is_copper(x, y) && !seen[y * artwork.width + x] {
+                    seen[y * artwork.width + x] = true;
+                    stack.push(y * artwork.width + x);
+                }
+            }
+        }
+    }
+    count
+}
+
+fn set_at(artwork: &CopperArtwork, x: f32, y: f32) -> bool {
+    artwork.pixel_at(Point::new(x, y)).is_some_and(|(column, row)| artwork.is_copper(column, row))
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut board = demo_board()?;
+    let copper = board.copper_artwork(BoardLayer::front(LayerType::Copper), PIXELS_PER_MM);
+    let mask = board.mask_artwork(Side::Front, PIXELS_PER_MM);
+
+    // A quarter millimeter per character around the resistor and the tracks beside it
+    let (x, y) = (RESISTOR_AT.0 as f32, RESISTOR_AT.1 as f32);
+    for row in 0..44 {
+        let line: String = (0..32)
+            .map(|column| {
+                let (px, py) = (x - 4.0 + column as f32 * 0.25 + 0.125, y - 5.5 + row as f32 * 0.25 + 0.125);
+                match (set_at(&copper, px, py), set_at(&mask, px, py)) {
+                    (true, true) => '@',
+                    (true, false) => '#',
+                    _ => '.',
+                }
+            })
+            .collect();
+        println!("  {}", line);
+    }
+
+    if (mask.width, mask.height, mask.origin) != (copper.width, copper.height, copper.origin) {
+        return Err("the mask artwork must line up with the copper artwork".into());
+    }
+    let openings = patches(&mask);
+    if openings != 2 {
+        return Err(format!("the 0805 must open the mask in 2 places, found {}", openings).into());
+    }
+    let over_bare = mask.pixels().iter().zip(copper.pixels()).filter(|&(&open, &copper)| open && !copper).count();
+    if over_bare > 0 {
+        return Err(format!("{} open mask pixels over bare board", over_bare).into());
+    }
+    let pads = board.pad_outlines(BoardLayer::front(LayerType::Copper), 0.01);
+    for (name, outline) in &pads {
+        let center = Rectangle::enclosing(outline).map(|bounds| Point::new((bounds.min_x + bounds.max_x) / 2.0, (bounds.min_y + bounds.max_y) / 2.0));
+        if !center.is_some_and(|center| set_at(&mask, center.x, center.y)) {
+            return Err(format!("pad {} must show through the mask", name).into());
+        }
+    }
+    for (what, px, py) in [("the pour", 10.0, 10.0), ("a track", 40.0, 35.0), ("a track", 32.0, 45.0)] {
+        if !set_at(&copper, px, py) || set_at(&mask, px, py) {
+            return Err(format!("{} at ({}, {}) must be copper under the mask", what, px, py).into());
+        }
+    }
+    if board.mask_artwork(Side::Back, PIXELS_PER_MM).coverage() > 0.0 {
+        return Err("the back has no pads, so no openings".into());
+    }
+
+    // Mask textures are cached like copper ones, until the openings change
+    let fingerprint = board.mask_fingerprint(Side::Front);
+    if mask.fingerprint != fingerprint {
+        return Err("mask artwork must carry the mask fingerprint".into());
+    }
+    board.place("R2", presets::resistor_0805("10k"), (37.5, 30.0), 0.0)?;
+    if board.mask_fingerprint(Side::Front) == fingerprint {
+        return Err("a new part must change the mask fingerprint".into());
+    }
+    println!("{} pads exposed through {:.2}% open mask", pads.len(), mask.coverage() * 100.0);
+    Ok(())
+}
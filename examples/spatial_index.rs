@@ -0,0 +1,196 @@
+//! Spatial index benchmark
+//!
+//! Places 5000 two-pad chips on a synthetic board and times the board's indexed queries
+//! against brute force scans of every component. Moves a few parts afterwards to time the
+//! incremental refresh. Run with `cargo run --release -p copper-substrate --example
+//! spatial_index`. That both give the same answers is checked by the unit tests of
+//! `spatial_index`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use copper_substrate::board::Board;
+use copper_substrate::prelude::*;
+use copper_substrate::spatial_index::{bounds_of, rectangle_distance};
+use uuid::Uuid;
+
+const COMPONENTS: usize = 5000;
+const QUERIES: usize = 200;
+
+struct Chip0603;
+
+impl BoardComposableObject for Chip0603 {
+    fn is_smt(&self) -> bool {
+        true
+    }
+    fn is_electrical(&self) -> bool {
+        true
+    }
+    fn terminal_count(&self) -> usize {
+        2
+    }
+    fn functional_type(&self) -> FunctionalType {
+        FunctionalType::Resistor("10k".to_string())
+    }
+    fn footprint_name(&self) -> String {
+        "R_0603_1608Metric".to_string()
+    }
+    fn library_name(&self) -> String {
+        "Resistor_SMD".to_string()
+    }
+    fn bounding_box(&self) -> Rectangle {
+        Rectangle { min_x: -0.8, min_y: -0.4, max_x: 0.8, max_y: 0.4 }
+    }
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        ["1", "2"]
+            .iter()
+            .zip([-0.825, 0.825])
+            .map(|(number, x)| PadDescriptor {
+                number: (*number).into(),
+                pin_function: None,
+                pad_type: PadType::SMD,
+                shape: PadShape::RoundRect,
+                position: (x, 0.0),
+                rotation: None,
+                size: (0.8, 0.95),
+                drill_size: None,
+                drill_slot: None,
+                layers: PadLayers::smd_front(),
+                roundrect_ratio: Some(0.25),
+                tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+                padstack: None,
+                fab_property: None,
+                solder_mask_margin: None,
+                routing_hint: None,
+                uuid: Uuid::new_v4(),
+            })
+            .collect()
+    }
+    fn description(&self) -> Option<String> {
+        None
+    }
+    fn tags(&self) -> Option<String> {
+        None
+    }
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        Vec::new()
+    }
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        Vec::new()
+    }
+    fn model_3d(&self) -> Option<Model3D> {
+        None
+    }
+}
+
+/// Small deterministic generator so runs are comparable
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> f32 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+fn pad_bounds(board: &Board) -> Vec<(String, usize, Point, Rectangle)> {
+    let mut pads = Vec::new();
+    for placed in board.components() {
+        for (index, pad) in placed.component.pad_descriptors().iter().enumerate() {
+            let outline = pad.outline_polygon(0.01).into_iter().map(|p| board.board_point(placed, p));
+            let center = board.board_point(placed, Point::from(pad.position));
+            pads.push((placed.reference.clone(), index, center, bounds_of(outline).unwrap()));
+        }
+    }
+    pads
+}
+
+fn brute_components_near(board: &Board, point: Point, radius: f32) -> Vec<String> {
+    board
+        .components()
+        .iter()
+        .filter(|placed| {
+            let body = placed.component.bounding_box();
+            let corners = [(body.min_x, body.min_y), (body.max_x, body.max_y), (body.min_x, body.max_y), (body.max_x, body.min_y)];
+            let mut points: Vec<Point> = corners.into_iter().map(|c| board.board_point(placed, Point::from(c))).collect();
+            for pad in placed.component.pad_descriptors() {
+                points.extend(pad.outline_polygon(0.01).into_iter().map(|p| board.board_point(placed, p)));
+            }
+            rectangle_distance(point, &bounds_of(points).unwrap()) <= radius
+        })
+        .map(|placed| placed.reference.clone())
+        .collect()
+}
+
+fn overlaps(a: &Rectangle, b: &Rectangle) -> bool {
+    a.min_x <= b.max_x && b.min_x <= a.max_x && a.min_y <= b.max_y && b.min_y <= a.max_y
+}
+
+fn main() {
+    let mut rng = Lcg(7);
+    let columns = (COMPONENTS as f32).sqrt().ceil() as usize;
+    let size = columns as f32 * 3.0;
+    let mut board = Board::new("spatial_index").with_rectangular_outline(size, size);
+    for i in 0..COMPONENTS {
+        let (x, y) = ((i % columns) as f32 * 3.0 + 1.5, (i / columns) as f32 * 3.0 + 1.5);
+        let rotation = (rng.next() * 4.0).floor() * 90.0;
+        board.place(&format!("R{}", i + 1), Chip0603, (x + rng.next() - 0.5, y + rng.next() - 0.5), rotation).unwrap();
+    }
+    let points: Vec<Point> = (0..QUERIES).map(|_| Point::new(rng.next() * size, rng.next() * size)).collect();
+
+    let start = Instant::now();
+    board.nearest_pad(Point::new(0.0, 0.0));
+    println!("{} components, index built in {:?}", COMPONENTS, start.elapsed());
+
+    let run = |board: &Board| {
+        let (mut brute, mut indexed) = (Duration::ZERO, Duration::ZERO);
+
+        for point in &points {
+            let start = Instant::now();
+            black_box(brute_components_near(board, *point, 2.0));
+            brute += start.elapsed();
+            let start = Instant::now();
+            black_box(board.components_near(*point, 2.0));
+            indexed += start.elapsed();
+        }
+        println!("components_near: brute force {:?}, indexed {:?}", brute, indexed);
+
+        let (mut brute, mut indexed) = (Duration::ZERO, Duration::ZERO);
+        for point in &points {
+            let rect = Rectangle { min_x: point.x, min_y: point.y, max_x: point.x + 4.0, max_y: point.y + 2.5 };
+            let start = Instant::now();
+            black_box(pad_bounds(board).into_iter().filter(|(_, _, _, bounds)| overlaps(bounds, &rect)).count());
+            brute += start.elapsed();
+            let start = Instant::now();
+            black_box(board.pads_in_rect(&rect));
+            indexed += start.elapsed();
+        }
+        println!("pads_in_rect:    brute force {:?}, indexed {:?}", brute, indexed);
+
+        let (mut brute, mut indexed) = (Duration::ZERO, Duration::ZERO);
+        for point in &points {
+            let start = Instant::now();
+            black_box(pad_bounds(board).into_iter().map(|(_, _, center, _)| center.distance_to(point)).fold(f32::INFINITY, f32::min));
+            brute += start.elapsed();
+            let start = Instant::now();
+            black_box(board.nearest_pad(*point));
+            indexed += start.elapsed();
+        }
+        println!("nearest_pad:     brute force {:?}, indexed {:?}", brute, indexed);
+    };
+
+    run(&board);
+
+    // Move, rotate, flip and remove a few parts; only those are recomputed
+    for i in (0..COMPONENTS).step_by(97) {
+        let placed = board.component_mut(&format!("R{}", i + 1)).unwrap();
+        placed.position.0 += 5.0;
+        placed.rotation += 45.0;
+        placed.side = Side::Back;
+    }
+    board.remove("R2");
+    let start = Instant::now();
+    board.nearest_pad(Point::new(0.0, 0.0));
+    println!("index refreshed after edits in {:?}", start.elapsed());
+    run(&board);
+}
@@ -0,0 +1,88 @@
+//! Building a stackup from named materials
+//!
+//! `cargo run -p copper-exporters --example stackup_materials` builds the common 1.6 mm four
+//! layer board from one ply of 7628 prepreg on each side of an FR-4 core, checks the
+//! dielectric heights and permittivities the stackup reports against the material table,
+//! shows that misspelt or misused material names fail cleanly, adds a custom laminate from
+//! TOML, and writes the board with its stackup for KiCad.
+
+use copper_exporters::kicad_board_export::to_kicad_pcb;
+use copper_substrate::board::Board;
+use copper_substrate::prelude::*;
+
+const CUSTOM_MATERIALS: &str = r#"
+[[material]]
+name = "IT-180A"
+kind = "core"
+thickness = 0.2
+epsilon_r_1ghz = 4.3
+epsilon_r_10ghz = 4.1
+loss_tangent = 0.015
+resin_content = 52
+"#;
+
+fn four_layer(prepreg: &str, core: &str) -> StackupBuilder {
+    Stackup::builder()
+        .copper("F.Cu", 0.035)
+        .prepreg(prepreg, 1)
+        .copper("In1.Cu", 0.0152)
+        .core(core)
+        .copper("In2.Cu", 0.0152)
+        .prepreg(prepreg, 1)
+        .copper("B.Cu", 0.035)
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-4
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let library = MaterialLibrary::builtin();
+    let (prepreg, core) = (library.get("7628")?, library.get("FR-4")?);
+
+    let stackup = four_layer("7628", "FR-4").build()?;
+    let outer = stackup.reference_gap("F.Cu")?;
+    let inner = stackup.reference_gap("In1.Cu")?;
+    println!(
+        "F.Cu to {}: {:.4} mm, er {:.2}; In1.Cu to {}: {:.4} mm, er {:.2}; board {:.3} mm",
+        outer.reference_layer, outer.height, outer.epsilon_r, inner.reference_layer, inner.height, inner.epsilon_r, stackup.total_thickness()
+    );
+    if !close(outer.height, prepreg.thickness) || !close(inner.height, core.thickness) {
+        return Err("dielectric heights must come from the 7628 ply and the FR-4 core".into());
+    }
+    if !close(outer.epsilon_r, prepreg.epsilon_r_1ghz) || !close(stackup.total_thickness(), 2.0 * 0.035 + 2.0 * 0.0152 + 2.0 * prepreg.thickness + core.thickness) {
+        return Err("permittivity and board thickness must follow the materials".into());
+    }
+
+    // Two plies double the height; at 10 GHz the second permittivity point applies
+    let doubled = Stackup::builder().copper("F.Cu", 0.035).prepreg("2116", 2).copper("In1.Cu", 0.0152).at_frequency(10.0).build()?;
+    let gap = doubled.reference_gap("F.Cu")?;
+    let style = library.get("2116")?;
+    println!("2116 x2 at 10 GHz: {:.4} mm, er {:.2}", gap.height, gap.epsilon_r);
+    if !close(gap.height, 2.0 * style.thickness) || !close(gap.epsilon_r, style.epsilon_r_10ghz) {
+        return Err("2116 x2 must be two plies thick with its 10 GHz permittivity".into());
+    }
+
+    for (builder, expected) in [
+        (four_layer("7629", "FR-4"), MaterialError::Unknown("7629".to_string())),
+        (four_layer("7628", "2116"), MaterialError::WrongKind { name: "2116".to_string(), expected: MaterialKind::Core }),
+        (Stackup::builder().prepreg("106", 0), MaterialError::Invalid("prepreg 106 needs at least one ply".to_string())),
+    ] {
+        match builder.build() {
+            Err(error) if error == expected => println!("rejected: {}", error),
+            other => return Err(format!("expected {:?}, got {:?}", expected, other).into()),
+        }
+    }
+
+    let custom = four_layer("2116", "IT-180A").with_library(MaterialLibrary::builtin().with_toml(CUSTOM_MATERIALS)?).build()?;
+    println!("custom core: {:.4} mm between In1.Cu and In2.Cu", custom.reference_gap("In1.Cu")?.height);
+
+    let board = Board::new("materials").with_rectangular_outline(50.0, 40.0).with_stackup(stackup);
+    let pcb = to_kicad_pcb(&board);
+    if !pcb.contains("(type \"prepreg\") (thickness 0.2104) (material \"7628\")") || !pcb.contains("(material \"FR-4\")") {
+        return Err("the KiCad stackup must name the materials".into());
+    }
+    std::fs::write("materials.kicad_pcb", pcb)?;
+    println!("saved materials.kicad_pcb");
+    Ok(())
+}
@@ -0,0 +1,36 @@
+//! Generated box models with through hole leads
+//!
+//! `cargo run -p copper-exporters --example tht_model` writes a DIP-8 library whose model
+//! is generated, with leads through the board and a raised body, plus the same part with
+//! kinked leads as a part lying on the board would have. Both go to a directory under the
+//! system temp directory, for a look in KiCad's 3D viewer or any VRML viewer. The numeric
+//! checks live in the unit tests of `wrl_model`.
+
+use std::fs;
+
+use copper_exporters::library_models::LibraryModels;
+use copper_exporters::wrl_model::{LeadForm, WrlModel};
+use copper_exporters::FootprintLibrary;
+use copper_substrate::prelude::*;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let work = std::env::temp_dir().join("copper_tht_model");
+    let _ = fs::remove_dir_all(&work);
+    fs::create_dir_all(&work)?;
+
+    let dip = Dip::new(8).ok_or("8 pins make a DIP")?;
+    let model = WrlModel::new(3.3).with_standoff(0.5);
+    let geometry = model.geometry(&dip);
+    for lead in &geometry.leads {
+        println!("lead at ({:.2}, {:.2}) from z {:.2} to {:.2} mm", lead.start[0], lead.start[1], lead.start[2], lead.end[2]);
+    }
+
+    let models = LibraryModels::default().with_extensions(&["wrl"]).with_generated_model(&dip, &model);
+    let mut library = FootprintLibrary::new("Generated").with_models(models);
+    library.add(Dip::new(8).ok_or("8 pins make a DIP")?);
+    let pretty = library.write_to(&work)?;
+    let kinked = work.join("DIP-8_kinked.wrl");
+    fs::write(&kinked, model.with_lead_form(LeadForm::Kinked).to_wrl(&dip))?;
+    println!("wrote {} with its model in Generated.3dshapes, and {}", pretty.display(), kinked.display());
+    Ok(())
+}
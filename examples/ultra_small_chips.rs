@@ -0,0 +1,120 @@
+//! 0201 and 01005 chips
+//!
+//! `cargo run -p copper-exporters --example ultra_small_chips` checks the ultra-small chip
+//! rules. R_0201_0603Metric must have the land pattern of KiCad's library footprint
+//! (0.46 x 0.4 mm pads 0.69 mm apart, center to center) and no silkscreen like it, with a
+//! 0.2 mm courtyard margin, a hidden reference and paste apertures inside the pads. 01005
+//! gets a 0.15 mm margin. A text policy must still be able to show the reference, the
+//! footprint DRC must accept the missing silkscreen on both but not on an 0402 stripped of
+//! its silkscreen, and both must export.
+
+use copper_exporters::kicad_footprint_parse::{KiCadFootprint, ParseOptions};
+use copper_exporters::kicad_pcb_export::try_to_kicad_footprint;
+use copper_substrate::analysis::FootprintDrc;
+use copper_substrate::presets;
+use copper_substrate::prelude::*;
+
+/// A chip drawn without its silkscreen
+struct NoSilk(ChipFootprint);
+
+impl BoardComposableObject for NoSilk {
+    fn is_smt(&self) -> bool {
+        true
+    }
+    fn is_electrical(&self) -> bool {
+        true
+    }
+    fn terminal_count(&self) -> usize {
+        2
+    }
+    fn functional_type(&self) -> FunctionalType {
+        self.0.functional_type()
+    }
+    fn footprint_name(&self) -> String {
+        self.0.footprint_name()
+    }
+    fn library_name(&self) -> String {
+        self.0.library_name()
+    }
+    fn bounding_box(&self) -> Rectangle {
+        self.0.bounding_box()
+    }
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        self.0.pad_descriptors()
+    }
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        self.0.fp_text_elements()
+    }
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        self.0.graphic_elements().into_iter().filter(|element| element.layer != LayerType::SilkScreen).collect()
+    }
+    fn model_3d(&self) -> Option<Model3D> {
+        self.0.model_3d()
+    }
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-4
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let r0201 = presets::resistor_0201("10k");
+    assert_eq!(r0201.footprint_name(), "R_0201_0603Metric");
+    assert!(r0201.is_ultra_small());
+
+    let pads = r0201.pad_descriptors();
+    let copper: Vec<&PadDescriptor> = pads.iter().filter(|pad| pad.has_copper()).collect();
+    let paste: Vec<&PadDescriptor> = pads.iter().filter(|pad| !pad.has_copper()).collect();
+    assert_eq!(copper.len(), 2);
+    for (pad, x) in copper.iter().zip([-0.345, 0.345]) {
+        assert!(close(pad.position.0, x) && close(pad.size.0, 0.46) && close(pad.size.1, 0.4), "{:?}", pad);
+        assert!(!pad.has_layer("F.Paste") && pad.has_layer("F.Mask"));
+    }
+    assert_eq!(paste.len(), 2);
+    for (aperture, pad) in paste.iter().zip(&copper) {
+        assert!(aperture.number.is_empty() && aperture.has_layer("F.Paste"));
+        assert_eq!(aperture.position, pad.position);
+        assert!(aperture.size.0 < pad.size.0 && aperture.size.1 < pad.size.1);
+    }
+    assert!(!r0201.graphic_elements().iter().any(|element| element.layer == LayerType::SilkScreen));
+    assert_eq!(r0201.courtyard_margin(), 0.2);
+    let reference = r0201.fp_text_elements().into_iter().find(|text| matches!(text.text_type, FpTextType::Reference));
+    let reference = reference.ok_or("no reference text")?;
+    assert!(reference.hidden && reference.font.size.0 < 0.5);
+    let margin = r0201.courtyard_margin();
+    println!("{}: pads {:?} at ±{}, courtyard margin {}", r0201.footprint_name(), copper[0].size, copper[1].position.0, margin);
+
+    let r01005 = presets::resistor_01005("10k");
+    assert_eq!(r01005.footprint_name(), "R_01005_0402Metric");
+    assert_eq!(r01005.courtyard_margin(), 0.15);
+    assert!(!r01005.graphic_elements().iter().any(|element| element.layer == LayerType::SilkScreen));
+    let (size, center) = r01005.land_pattern();
+    assert!(center - size.0 / 2.0 > 0.05, "01005 pads overlap: {:?} at ±{}", size, center);
+    // Polarized ultra-small parts keep their fab marker but lose the silkscreen one
+    let led = ChipFootprint::standard(FunctionalType::LED("red".to_string()), "0201").ok_or("0201 is standard")?;
+    assert!(!led.graphic_elements().iter().any(|element| element.layer == LayerType::SilkScreen));
+
+    // A policy showing references on small parts overrides the hidden default
+    let policy = TextPolicy::new(TextStyle::default()).with_rule(TextRule::new().with_max_body(1.0).with_size(0.5).with_visible(true));
+    let styled = r0201.clone().with_text_policy(&policy);
+    let shown = styled.fp_text_elements().into_iter().find(|text| matches!(text.text_type, FpTextType::Reference));
+    assert!(shown.is_some_and(|text| !text.hidden && close(text.font.size.0, 0.5)));
+
+    // The missing silkscreen is only accepted below the size threshold
+    let drc = FootprintDrc::default();
+    for chip in [&r0201, &r01005] {
+        let findings = drc.findings(chip);
+        assert!(findings.iter().all(|finding| finding.rule != "silk_missing"), "{:#?}", findings);
+    }
+    let stripped = NoSilk(presets::resistor_0402("10k"));
+    let rules: Vec<String> = drc.findings(&stripped).into_iter().map(|finding| finding.rule).collect();
+    assert!(rules.iter().any(|rule| rule == "silk_missing"), "{:?}", rules);
+
+    for chip in [&r0201, &r01005] {
+        let text = try_to_kicad_footprint(chip)?;
+        let read = KiCadFootprint::parse(&text, ParseOptions::default())?;
+        assert_eq!(read.pad_descriptors().len(), 4);
+        println!("{} exports {} pads", chip.footprint_name(), read.pad_descriptors().len());
+    }
+    Ok(())
+}
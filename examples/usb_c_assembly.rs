@@ -0,0 +1,142 @@
+//! USB-C receptacle with connector marks in the assembly drawing
+//!
+//! `cargo run -p copper-exporters --example usb_c_assembly` builds a 16 pin USB 2.0 Type-C
+//! receptacle whose fab layer carries a mating face arrow and an insertion arrow (see
+//! `connector_marks`), places it on the top edge of a small board with its shell 1 mm
+//! past the edge and writes the board's assembly drawing to the temp directory. The
+//! mating face arrow must point off the board edge.
+
+use copper_exporters::assembly_drawing::to_assembly_svg;
+use copper_substrate::board::Board;
+use copper_substrate::prelude::*;
+use uuid::Uuid;
+
+/// Shell size, mating face towards -Y
+const BODY: Rectangle = Rectangle { min_x: -4.47, min_y: -3.65, max_x: 4.47, max_y: 3.65 };
+/// Signal pad columns and names: the outer pairs are wider, ground and VBUS
+const SIGNALS: &[(f32, &str, f32)] = &[
+    (-3.2, "A1", 0.6),
+    (-2.4, "A4", 0.6),
+    (-1.75, "B8", 0.3),
+    (-1.25, "A5", 0.3),
+    (-0.75, "B7", 0.3),
+    (-0.25, "A6", 0.3),
+    (0.25, "A7", 0.3),
+    (0.75, "B6", 0.3),
+    (1.25, "A8", 0.3),
+    (1.75, "B5", 0.3),
+    (2.4, "B4", 0.6),
+    (3.2, "B1", 0.6),
+];
+
+struct UsbCReceptacle {
+    marks: ConnectorMarks,
+}
+
+fn pad(number: &str, pad_type: PadType, position: (f32, f32), size: (f32, f32), drill: Option<f32>) -> PadDescriptor {
+    let layers = match pad_type {
+        PadType::SMD => vec!["F.Cu".into(), "F.Paste".into(), "F.Mask".into()],
+        _ => vec!["*.Cu".into(), "*.Mask".into()],
+    };
+    PadDescriptor {
+        number: number.into(),
+        pin_function: None,
+        pad_type,
+        shape: if drill.is_some() { PadShape::Oval } else { PadShape::Rect },
+        position,
+        rotation: None,
+        size,
+        drill_size: drill,
+        drill_slot: None,
+        layers,
+        roundrect_ratio: None,
+        tenting: TentingSettings { front: TentingType::None, back: TentingType::None },
+        padstack: None,
+        fab_property: None,
+        solder_mask_margin: None,
+        routing_hint: None,
+        uuid: Uuid::new_v4(),
+    }
+}
+
+impl BoardComposableObject for UsbCReceptacle {
+    fn is_smt(&self) -> bool {
+        true
+    }
+
+    fn is_electrical(&self) -> bool {
+        true
+    }
+
+    fn terminal_count(&self) -> usize {
+        SIGNALS.len() + 1
+    }
+
+    fn functional_type(&self) -> FunctionalType {
+        FunctionalType::Connector("USB_C_Receptacle_USB2.0".to_string())
+    }
+
+    fn footprint_name(&self) -> String {
+        "USB_C_Receptacle_16P_TopMnt_Horizontal".to_string()
+    }
+
+    fn library_name(&self) -> String {
+        "Connector_USB".to_string()
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        BODY
+    }
+
+    fn pad_descriptors(&self) -> Vec<PadDescriptor> {
+        let mut pads: Vec<PadDescriptor> =
+            SIGNALS.iter().map(|&(x, name, width)| pad(name, PadType::SMD, (x, -2.6), (width, 1.1), None)).collect();
+        for (x, y) in [(-4.32, -2.0), (4.32, -2.0), (-4.32, 2.2), (4.32, 2.2)] {
+            pads.push(pad("S1", PadType::ThroughHole, (x, y), (1.0, 1.8), Some(0.6)));
+        }
+        pads
+    }
+
+    fn fp_text_elements(&self) -> Vec<FpText> {
+        let mut texts = vec![
+            FpText::new(FpTextType::Reference, "REF**", (0.0, 5.0), "F.SilkS"),
+            FpText::new(FpTextType::Value, self.footprint_name(), (0.0, 6.5), "F.Fab"),
+        ];
+        texts.extend(self.marks.fp_text_elements(&BODY));
+        texts
+    }
+
+    fn graphic_elements(&self) -> Vec<GraphicElement> {
+        let mut elements = vec![GraphicElement::new(GraphicType::Rectangle { bounds: BODY }, LayerType::Fabrication, 0.1)
+            .with_role(ElementRole::FabOutline(None))];
+        elements.extend(self.marks.graphic_elements(&BODY));
+        elements
+    }
+
+    fn model_3d(&self) -> Option<Model3D> {
+        None
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let usb = UsbCReceptacle { marks: ConnectorMarks::new().with_mating_face(Edge::Top).with_insertion_arrow() };
+    let mut board = Board::new("usb_c_demo").with_rectangular_outline(30.0, 20.0);
+    // The shell sticks out 1 mm past the top edge
+    board.place("J1", usb, (15.0, BODY.max_y - 1.0), 0.0)?;
+
+    let marks = board.connector_marks();
+    let tip = marks
+        .iter()
+        .find(|mark| mark.role == ElementRole::MatingFace && mark.filled)
+        .map(|mark| mark.points[0])
+        .ok_or("the receptacle drew no mating face arrow")?;
+    if tip.y >= 0.0 {
+        return Err(format!("the mating face arrow ends at {:?}, on the board", tip).into());
+    }
+    println!("mating face arrow tip at ({}, {}), {} mm past the board edge", tip.x, tip.y, -tip.y);
+
+    let path = std::env::temp_dir().join("usb_c_demo_assembly.svg");
+    std::fs::write(&path, to_assembly_svg(&board))?;
+    println!("saved {}", path.display());
+    Ok(())
+}
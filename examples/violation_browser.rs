@@ -0,0 +1,115 @@
+//! Navigating DRC findings
+//!
+//! `cargo run -p copper-exporters --example violation_browser` checks what the preview's
+//! violations panel works from, without opening a window. The deliberately broken
+//! footprint in examples/fixtures must give exactly five findings, one per footprint rule,
+//! each with a location and the pads or graphics involved. Selecting each one must center
+//! the view on it with the highlighted pads on screen. A board with a thin track and a
+//! via too close to it must navigate the same way in board coordinates, and a frame of
+//! both renderers and the panel must draw in a headless egui context.
+
+use std::path::Path;
+
+use copper_exporters::kicad_footprint_parse::{KiCadFootprint, ParseOptions};
+use copper_substrate::analysis::{ElementRef, FootprintDrc, Severity, Violation};
+use copper_substrate::board::Board;
+use copper_substrate::prelude::*;
+use copper_substrate::render::{BoardRenderer, FootprintRenderer, Highlight, ViewTransform, ViolationPanel};
+use eframe::egui;
+
+/// Viewport the navigation is checked in
+fn view() -> ViewTransform {
+    ViewTransform { viewport: egui::Rect::from_min_size(egui::pos2(200.0, 0.0), egui::vec2(800.0, 600.0)), ..ViewTransform::default() }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples/fixtures/broken_footprint.kicad_mod");
+    let footprint = KiCadFootprint::read(&path, ParseOptions::default())?;
+    let findings = FootprintDrc::default().findings(&footprint);
+    let rules: Vec<&str> = findings.iter().map(|finding| finding.rule.as_str()).collect();
+    assert_eq!(rules, ["pad_size", "drill_size", "annular_ring", "pad_clearance", "silk_over_pad"], "{:#?}", findings);
+
+    let pads = footprint.pad_descriptors();
+    let expected_pads: [&[&str]; 5] = [&["1"], &["2"], &["3"], &["4", "5"], &["5"]];
+    let mut panel = ViolationPanel::new(findings.clone());
+    // Errors come before the silkscreen warning
+    let groups: Vec<String> = panel.groups().into_iter().map(|(rule, _)| rule).collect();
+    assert_eq!(groups.last().map(String::as_str), Some("silk_over_pad"));
+
+    for (index, finding) in findings.iter().enumerate() {
+        let mut view = view();
+        let highlight = panel.select(index, &mut view);
+        assert_eq!(panel.selected(), Some(finding));
+        assert_eq!(highlight, Highlight::of(finding));
+
+        let location = finding.location.ok_or("finding without a location")?;
+        let on_screen = view.to_screen((location.x, location.y));
+        assert!((on_screen - view.viewport.center()).length() < 0.01, "{} not centered", finding.rule);
+
+        let numbers: Vec<String> = highlight
+            .elements
+            .iter()
+            .filter_map(|element| match element {
+                ElementRef::Pad(pad) => Some(pads[*pad].number.to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(numbers, expected_pads[index], "{}", finding.rule);
+        for element in &highlight.elements {
+            if let ElementRef::Pad(pad) = element {
+                let center = view.to_screen(pads[*pad].position);
+                assert!(view.viewport.contains(center), "pad {} off screen for {}", pads[*pad].number, finding.rule);
+            }
+        }
+        let (icon, _) = copper_substrate::render::severity_icon(finding.severity);
+        println!("{} {} at ({:.2}, {:.2}): {}", icon, finding.rule, location.x, location.y, finding.message);
+    }
+    let silk = &findings[4];
+    assert_eq!(silk.severity, Severity::Warning);
+    let graphic = silk.elements.iter().find_map(|element| match element {
+        ElementRef::Graphic(index) => Some(*index),
+        _ => None,
+    });
+    let graphic = graphic.ok_or("silkscreen finding without its graphic")?;
+    assert_eq!(footprint.graphic_elements()[graphic].layer, LayerType::SilkScreen);
+
+    // A board: a 0.1 mm track and a via 0.1 mm from another net's track
+    let copper = BoardLayer::front(LayerType::Copper);
+    let mut board = Board::new("browse").with_rectangular_outline(40.0, 30.0);
+    board.add_track(Track::new("SIG", copper, Point::new(5.0, 5.0), Point::new(15.0, 5.0), 0.1));
+    board.add_track(Track::new("GND", copper, Point::new(20.0, 20.0), Point::new(30.0, 20.0), 0.25));
+    board.add_via(Via::new("VCC", Point::new(25.0, 20.525), 0.6, 0.3));
+    let board_findings: Vec<_> = board.routing_violations().iter().map(Violation::to_finding).collect();
+    let mut board_panel = ViolationPanel::new(board_findings.clone()).with_focus_span(8.0);
+    assert_eq!(board_findings.len(), 2, "{:#?}", board_findings);
+    for (index, finding) in board_findings.iter().enumerate() {
+        let mut view = view();
+        let highlight = board_panel.select(index, &mut view);
+        let location = finding.location.ok_or("board finding without a location")?;
+        assert!((view.to_screen((location.x, location.y)) - view.viewport.center()).length() < 0.01);
+        assert_eq!(view.zoom, 600.0 / 8.0);
+        println!("board {} at ({:.2}, {:.2}) highlights {:?}", finding.rule, location.x, location.y, highlight.elements);
+    }
+    assert_eq!(board_findings[0].elements, [ElementRef::Track(0)]);
+    assert_eq!(board_findings[1].elements, [ElementRef::Track(1), ElementRef::Via(0)]);
+
+    // One headless frame of everything the preview draws
+    let mut renderer = FootprintRenderer::new(view());
+    renderer.highlight = panel.select(3, &mut renderer.view);
+    let mut board_renderer = BoardRenderer::new(view());
+    board_renderer.highlight = board_panel.select(1, &mut board_renderer.view);
+    let ctx = egui::Context::default();
+    let output = ctx.run(egui::RawInput::default(), |ctx| {
+        egui::SidePanel::right("violations").show(ctx, |ui| {
+            panel.ui(ui, &mut renderer.view);
+        });
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let (_, mut painter) = ui.allocate_painter(egui::vec2(400.0, 300.0), egui::Sense::drag());
+            renderer.render(&footprint, &mut painter);
+            board_renderer.render(&board, &painter);
+        });
+    });
+    assert!(!output.shapes.is_empty());
+    println!("headless frame: {} shapes", output.shapes.len());
+    Ok(())
+}
@@ -0,0 +1,26 @@
+//! Arbitrary bytes into the KiCad footprint parser, which may reject them but must not
+//! panic. Whatever it accepts is read, written and parsed again.
+//!
+//! `cargo +nightly fuzz run parse_footprint` from this directory.
+
+#![no_main]
+
+use copper_exporters::kicad_footprint_parse::{KiCadFootprint, ParseOptions};
+use copper_substrate::prelude::*;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    for strict in [false, true] {
+        let Ok(parsed) = KiCadFootprint::parse(text, ParseOptions { strict }) else {
+            continue;
+        };
+        parsed.pad_descriptors();
+        parsed.fp_text_elements();
+        parsed.graphic_elements();
+        let written = parsed.to_kicad_string();
+        KiCadFootprint::parse(&written, ParseOptions::default()).expect("a written footprint parses");
+    }
+});